@@ -0,0 +1,307 @@
+//! Chunked document helpers
+//!
+//! Long documents are stored as one `documents` row per chunk. Chunk rows
+//! carry `parent_doc_path`, `chunk_index` and `chunk_total` in their
+//! metadata; the first chunk keeps the parent's path and later chunks use
+//! `{parent}#chunk-{index}`.
+
+use crate::models::{Document, ScoredDocument};
+use serde_json::{json, Value};
+
+/// Metadata key holding the path of the document a chunk was cut from
+pub const PARENT_DOC_PATH_KEY: &str = "parent_doc_path";
+
+/// Metadata key holding the zero-based position of a chunk
+pub const CHUNK_INDEX_KEY: &str = "chunk_index";
+
+/// Metadata key holding the number of chunks of the parent document
+pub const CHUNK_TOTAL_KEY: &str = "chunk_total";
+
+/// Storage path for chunk `index` of `parent`
+#[must_use]
+pub fn chunk_doc_path(parent: &str, index: usize) -> String {
+    if index == 0 {
+        parent.to_string()
+    } else {
+        format!("{parent}#chunk-{index}")
+    }
+}
+
+/// Record chunk position in a document metadata object
+///
+/// Non-object metadata is left untouched.
+pub fn annotate_chunk_metadata(metadata: &mut Value, parent: &str, index: usize, total: usize) {
+    if let Some(map) = metadata.as_object_mut() {
+        map.insert(PARENT_DOC_PATH_KEY.to_string(), json!(parent));
+        map.insert(CHUNK_INDEX_KEY.to_string(), json!(index));
+        map.insert(CHUNK_TOTAL_KEY.to_string(), json!(total));
+    }
+}
+
+/// Search results that wrap a [`Document`]
+pub trait ChunkedResult {
+    /// The wrapped document
+    fn document(&self) -> &Document;
+    /// Mutable access to the wrapped document
+    fn document_mut(&mut self) -> &mut Document;
+}
+
+impl ChunkedResult for Document {
+    fn document(&self) -> &Document {
+        self
+    }
+
+    fn document_mut(&mut self) -> &mut Document {
+        self
+    }
+}
+
+impl ChunkedResult for ScoredDocument {
+    fn document(&self) -> &Document {
+        &self.document
+    }
+
+    fn document_mut(&mut self) -> &mut Document {
+        &mut self.document
+    }
+}
+
+fn chunk_position(doc: &Document) -> Option<(String, u64)> {
+    let parent = doc.metadata.get(PARENT_DOC_PATH_KEY)?.as_str()?;
+    let index = doc.metadata.get(CHUNK_INDEX_KEY)?.as_u64()?;
+    Some((parent.to_string(), index))
+}
+
+struct ChunkGroup<T> {
+    /// Best-ranked result of the group, which carries the merged content
+    head: T,
+    key: (String, String, String),
+    chunks: Vec<(u64, String)>,
+}
+
+impl<T> ChunkGroup<T> {
+    fn range(&self) -> (u64, u64) {
+        let lo = self.chunks.iter().map(|(i, _)| *i).min().unwrap_or(0);
+        let hi = self.chunks.iter().map(|(i, _)| *i).max().unwrap_or(0);
+        (lo, hi)
+    }
+
+    fn touches(&self, key: &(String, String, String), lo: u64, hi: u64) -> bool {
+        if self.chunks.is_empty() {
+            return false;
+        }
+        let (glo, ghi) = self.range();
+        self.key == *key && lo <= ghi.saturating_add(1) && glo <= hi.saturating_add(1)
+    }
+}
+
+/// Merge search results that are adjacent chunks of the same parent document
+///
+/// Each run of consecutive chunks becomes one result at the rank of its best
+/// chunk, with the overlap between chunks removed. Unchunked results and
+/// non-adjacent chunks are returned unchanged.
+#[must_use]
+pub fn group_adjacent_chunks<T: ChunkedResult>(results: Vec<T>) -> Vec<T> {
+    let mut groups: Vec<ChunkGroup<T>> = Vec::new();
+    let mut order: Vec<Result<T, usize>> = Vec::new();
+
+    for result in results {
+        let doc = result.document();
+        let Some((parent, index)) = chunk_position(doc) else {
+            order.push(Ok(result));
+            continue;
+        };
+        let key = (doc.doc_type.clone(), doc.source_name.clone(), parent);
+        let content = doc.content.clone();
+
+        if let Some(group) = groups.iter_mut().find(|g| g.touches(&key, index, index)) {
+            if !group.chunks.iter().any(|(i, _)| *i == index) {
+                group.chunks.push((index, content));
+            }
+        } else {
+            order.push(Err(groups.len()));
+            groups.push(ChunkGroup {
+                head: result,
+                key,
+                chunks: vec![(index, content)],
+            });
+            continue;
+        }
+
+        // A new chunk may bridge two groups, e.g. hits for chunks 1, 3 then 2
+        merge_bridged_groups(&mut groups, &mut order);
+    }
+
+    let mut groups: Vec<Option<ChunkGroup<T>>> = groups.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Ok(result) => Some(result),
+            Err(slot) => groups[slot].take().map(finish_group),
+        })
+        .collect()
+}
+
+fn merge_bridged_groups<T>(groups: &mut [ChunkGroup<T>], order: &mut Vec<Result<T, usize>>) {
+    loop {
+        let mut merge = None;
+        'outer: for a in 0..groups.len() {
+            if groups[a].chunks.is_empty() {
+                continue;
+            }
+            let (lo, hi) = groups[a].range();
+            for b in (a + 1)..groups.len() {
+                if groups[b].touches(&groups[a].key, lo, hi) {
+                    merge = Some((a, b));
+                    break 'outer;
+                }
+            }
+        }
+        let Some((a, b)) = merge else {
+            return;
+        };
+        // Groups are created in rank order, so `a` holds the better head
+        let moved = std::mem::take(&mut groups[b].chunks);
+        for chunk in moved {
+            if !groups[a].chunks.iter().any(|(i, _)| *i == chunk.0) {
+                groups[a].chunks.push(chunk);
+            }
+        }
+        order.retain(|entry| !matches!(entry, Err(slot) if *slot == b));
+    }
+}
+
+fn finish_group<T: ChunkedResult>(mut group: ChunkGroup<T>) -> T {
+    if group.chunks.len() < 2 {
+        return group.head;
+    }
+    group.chunks.sort_by_key(|(i, _)| *i);
+    let (lo, hi) = group.range();
+
+    let mut content = String::new();
+    for (_, chunk) in &group.chunks {
+        content = join_overlapping(&content, chunk);
+    }
+
+    let doc = group.head.document_mut();
+    doc.doc_path = group.key.2.clone();
+    doc.content = content;
+    if let Some(map) = doc.metadata.as_object_mut() {
+        map.insert(CHUNK_INDEX_KEY.to_string(), json!(lo));
+        map.insert("chunk_range".to_string(), json!([lo, hi]));
+    }
+    group.head
+}
+
+/// Append `next` to `prev`, dropping the prefix of `next` that repeats the end of `prev`
+fn join_overlapping(prev: &str, next: &str) -> String {
+    if prev.is_empty() {
+        return next.to_string();
+    }
+
+    let probe_len = next.char_indices().nth(8).map_or(next.len(), |(i, _)| i);
+    let probe = &next[..probe_len];
+    if !probe.is_empty() {
+        // Earliest match gives the longest overlap
+        for (pos, _) in prev.match_indices(probe) {
+            if let Some(rest) = next.strip_prefix(&prev[pos..]) {
+                return format!("{prev}{rest}");
+            }
+        }
+    }
+
+    format!("{prev}\n\n{next}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn chunk(parent: &str, index: usize, total: usize, content: &str) -> Document {
+        let mut metadata = json!({"crate_name": "demo"});
+        annotate_chunk_metadata(&mut metadata, parent, index, total);
+        Document {
+            id: Uuid::new_v4(),
+            doc_type: "rust".to_string(),
+            source_name: "demo".to_string(),
+            doc_path: chunk_doc_path(parent, index),
+            content: content.to_string(),
+            metadata,
+            embedding: None,
+            token_count: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    fn plain(path: &str) -> Document {
+        let mut doc = chunk(path, 0, 1, "plain");
+        doc.metadata = json!({});
+        doc
+    }
+
+    #[test]
+    fn test_chunk_doc_path() {
+        assert_eq!(chunk_doc_path("a/b.html", 0), "a/b.html");
+        assert_eq!(chunk_doc_path("a/b.html", 2), "a/b.html#chunk-2");
+    }
+
+    #[test]
+    fn test_adjacent_chunks_merged_without_overlap() {
+        let results = vec![
+            chunk("p", 1, 3, "shared tail of one. Second part of the page."),
+            plain("other"),
+            chunk("p", 0, 3, "First part of the page. shared tail of one."),
+        ];
+        let grouped = group_adjacent_chunks(results);
+
+        assert_eq!(grouped.len(), 2);
+        assert_eq!(grouped[0].doc_path, "p");
+        assert_eq!(
+            grouped[0].content,
+            "First part of the page. shared tail of one. Second part of the page."
+        );
+        assert_eq!(grouped[0].metadata["chunk_range"], json!([0, 1]));
+        assert_eq!(grouped[1].doc_path, "other");
+    }
+
+    #[test]
+    fn test_non_adjacent_chunks_kept_apart() {
+        let results = vec![chunk("p", 0, 5, "zero"), chunk("p", 3, 5, "three")];
+        let grouped = group_adjacent_chunks(results);
+        assert_eq!(grouped.len(), 2);
+        assert_eq!(grouped[1].doc_path, "p#chunk-3");
+    }
+
+    #[test]
+    fn test_bridging_chunk_merges_groups() {
+        let results = vec![
+            chunk("p", 1, 4, "one"),
+            chunk("p", 3, 4, "three"),
+            chunk("p", 2, 4, "two"),
+        ];
+        let grouped = group_adjacent_chunks(results);
+        assert_eq!(grouped.len(), 1);
+        assert_eq!(grouped[0].content, "one\n\ntwo\n\nthree");
+        assert_eq!(grouped[0].metadata["chunk_range"], json!([1, 3]));
+    }
+
+    #[test]
+    fn test_scored_results_keep_best_score() {
+        let results = vec![
+            ScoredDocument {
+                document: chunk("p", 2, 3, "two"),
+                score: 0.9,
+            },
+            ScoredDocument {
+                document: chunk("p", 1, 3, "one"),
+                score: 0.4,
+            },
+        ];
+        let grouped = group_adjacent_chunks(results);
+        assert_eq!(grouped.len(), 1);
+        assert!((grouped[0].score - 0.9).abs() < f64::EPSILON);
+        assert_eq!(grouped[0].document.content, "one\n\ntwo");
+    }
+}
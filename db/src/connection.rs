@@ -7,7 +7,8 @@ use crate::pool_config::PoolConfig;
 use crate::retry::{RetryConfig, RetryExecutor};
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
-use sqlx::{postgres::PgPoolOptions, PgPool};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{Executor, PgPool};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -78,6 +79,12 @@ pub struct PoolMetricsSnapshot {
     pub query_failures: u64,
     pub success_rate_percent: f64,
     pub last_health_check_ago_seconds: u64,
+    /// Connections currently open, idle or in use
+    pub pool_size: u32,
+    /// Open connections waiting in the pool
+    pub idle_connections: u32,
+    /// Open connections checked out by queries
+    pub in_use_connections: u32,
 }
 
 impl DatabasePool {
@@ -157,21 +164,15 @@ impl DatabasePool {
         let retry_config = RetryConfig::from_env();
         let retry_executor = RetryExecutor::with_config(retry_config);
 
-        // Build connection string with application name
-        let connection_url = if config.database_url.contains("application_name=") {
-            config.database_url.clone()
-        } else {
-            format!(
-                "{}{}application_name={}",
-                config.database_url,
-                if config.database_url.contains('?') {
-                    "&"
-                } else {
-                    "?"
-                },
-                config.application_name
-            )
-        };
+        // Name the connections in pg_stat_activity unless the URL already does
+        let mut connect_options: PgConnectOptions = config
+            .database_url
+            .parse()
+            .map_err(|e| anyhow!("Invalid database URL: {e}"))?;
+        if !config.database_url.contains("application_name=") {
+            connect_options = connect_options.application_name(&config.application_name);
+        }
+        let statement_timeout_ms = config.statement_timeout_ms;
 
         let pool = retry_executor
             .execute_with_classification(|| async {
@@ -189,7 +190,17 @@ impl DatabasePool {
                     pool_options = pool_options.idle_timeout(idle_timeout);
                 }
 
-                pool_options.connect(&connection_url).await
+                if let Some(timeout_ms) = statement_timeout_ms {
+                    pool_options = pool_options.after_connect(move |conn, _meta| {
+                        Box::pin(async move {
+                            conn.execute(format!("SET statement_timeout = {timeout_ms}").as_str())
+                                .await
+                                .map(|_| ())
+                        })
+                    });
+                }
+
+                pool_options.connect_with(connect_options.clone()).await
             })
             .await?;
 
@@ -334,11 +345,11 @@ impl DatabasePool {
 
     /// Get current pool size (active, idle)
     fn get_pool_size(&self) -> (u32, u32) {
-        // Note: sqlx doesn't expose pool metrics directly
-        // This is a simplified implementation
-        let active = self.pool.size();
-        let idle = if active > 0 { active - 1 } else { 0 }; // Rough estimate
-        (active, idle)
+        let size = self.pool.size();
+        let idle = u32::try_from(self.pool.num_idle())
+            .unwrap_or(size)
+            .min(size);
+        (size - idle, idle)
     }
 
     /// Get snapshot of current metrics
@@ -349,6 +360,7 @@ impl DatabasePool {
         let total_queries = self.metrics.total_queries.load(Ordering::Relaxed);
         let query_failures = self.metrics.query_failures.load(Ordering::Relaxed);
         let last_health_check = self.metrics.last_health_check.load(Ordering::Relaxed);
+        let (active, idle) = self.get_pool_size();
 
         #[allow(clippy::cast_precision_loss)]
         let success_rate = if total_queries > 0 {
@@ -376,6 +388,9 @@ impl DatabasePool {
             query_failures,
             success_rate_percent: success_rate,
             last_health_check_ago_seconds: last_check_ago,
+            pool_size: active + idle,
+            idle_connections: idle,
+            in_use_connections: active,
         }
     }
 
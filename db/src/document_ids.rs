@@ -0,0 +1,105 @@
+//! Deterministic document ids
+//!
+//! A document's id is a UUIDv5 of its identity: doc type, source name, path
+//! and the crate or package version from its metadata. Re-ingesting the same
+//! page yields the same id in every environment, and two versions of a page
+//! never share one. Deployments that rely on random ids can keep them with
+//! `DOCUMENT_ID_MODE=random`; existing random ids are rewritten by
+//! [`crate::DocumentQueries::rewrite_document_ids`].
+
+use serde_json::Value;
+use std::sync::LazyLock;
+use uuid::Uuid;
+
+/// Namespace of the UUIDv5 document ids
+pub const DOCUMENT_ID_NAMESPACE: Uuid = Uuid::from_u128(0x3c1f_5b9e_8d2a_4e67_9a41_0f6d_2b8c_7e15);
+
+/// Whether new documents get random ids (`DOCUMENT_ID_MODE=random`)
+static RANDOM_IDS: LazyLock<bool> = LazyLock::new(|| {
+    std::env::var("DOCUMENT_ID_MODE").is_ok_and(|mode| mode.trim().eq_ignore_ascii_case("random"))
+});
+
+/// Id of the document identified by `doc_type`, `source_name`, `doc_path` and `version`
+///
+/// The parts are joined with NUL bytes, so no two identities share a name.
+#[must_use]
+pub fn stable_document_id(
+    doc_type: &str,
+    source_name: &str,
+    doc_path: &str,
+    version: Option<&str>,
+) -> Uuid {
+    let name = [doc_type, source_name, doc_path, version.unwrap_or("")].join("\0");
+    Uuid::new_v5(&DOCUMENT_ID_NAMESPACE, name.as_bytes())
+}
+
+/// Crate or package version recorded in a document's metadata
+#[must_use]
+pub fn document_version(metadata: &Value) -> Option<&str> {
+    ["crate_version", "package_version"]
+        .into_iter()
+        .find_map(|key| metadata.get(key).and_then(Value::as_str))
+}
+
+/// Id for a new document: stable unless `DOCUMENT_ID_MODE=random`
+#[must_use]
+pub fn new_document_id(
+    doc_type: &str,
+    source_name: &str,
+    doc_path: &str,
+    metadata: &Value,
+) -> Uuid {
+    if *RANDOM_IDS {
+        Uuid::new_v4()
+    } else {
+        stable_document_id(doc_type, source_name, doc_path, document_version(metadata))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_stable_ids_are_deterministic() {
+        let id = stable_document_id("rust", "tokio", "tokio/fn.spawn.html", Some("1.38.0"));
+        assert_eq!(
+            id,
+            stable_document_id("rust", "tokio", "tokio/fn.spawn.html", Some("1.38.0"))
+        );
+        assert_eq!(id.get_version_num(), 5);
+    }
+
+    #[test]
+    fn test_every_identity_part_changes_the_id() {
+        let base = stable_document_id("rust", "tokio", "a", Some("1.0.0"));
+        for other in [
+            stable_document_id("npm", "tokio", "a", Some("1.0.0")),
+            stable_document_id("rust", "serde", "a", Some("1.0.0")),
+            stable_document_id("rust", "tokio", "b", Some("1.0.0")),
+            stable_document_id("rust", "tokio", "a", Some("1.0.1")),
+            stable_document_id("rust", "tokio", "a", None),
+        ] {
+            assert_ne!(base, other);
+        }
+        // Parts cannot run into each other
+        assert_ne!(
+            stable_document_id("rust", "ab", "c", None),
+            stable_document_id("rust", "a", "bc", None)
+        );
+    }
+
+    #[test]
+    fn test_version_read_from_metadata() {
+        assert_eq!(
+            document_version(&json!({"crate_version": "1.2.3"})),
+            Some("1.2.3")
+        );
+        assert_eq!(
+            document_version(&json!({"package_version": "4.0.0"})),
+            Some("4.0.0")
+        );
+        assert_eq!(document_version(&json!({})), None);
+    }
+}
@@ -0,0 +1,252 @@
+//! Lifecycle operations shared by the crate and ingest job tables
+//!
+//! `crate_jobs` and `ingest_jobs` carry different payloads but move through
+//! the same states. [`JobStore`] names what the two have in common, so job
+//! processing code (heartbeats, stale-job recovery, retention) is written
+//! once: [`CrateStorage`] implements it over [`crate::CrateStore`] and
+//! [`IngestJobStore`] over [`IngestJobQueries`]. Table-specific operations,
+//! such as crate job progress, events and retries, stay on those types.
+
+use crate::connection::DatabasePool;
+use crate::models::{CrateJob, IngestJob, JobStatus};
+use crate::queries::IngestJobQueries;
+use crate::store::CrateStorage;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Fields every job row has
+pub trait JobRecord: Send + Sync {
+    fn id(&self) -> Uuid;
+    fn status(&self) -> &JobStatus;
+    fn updated_at(&self) -> DateTime<Utc>;
+}
+
+impl JobRecord for CrateJob {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn status(&self) -> &JobStatus {
+        &self.status
+    }
+
+    fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+}
+
+impl JobRecord for IngestJob {
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn status(&self) -> &JobStatus {
+        &self.status
+    }
+
+    fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+}
+
+/// A job table: creation, lookup, status transitions, heartbeats, stale
+/// jobs and retention
+#[async_trait]
+pub trait JobStore: Send + Sync {
+    /// Row type of the table
+    type Job: JobRecord;
+    /// What a caller supplies to create a job
+    type NewJob: Send + Sync;
+
+    /// Short name for log messages, e.g. `crate`
+    fn kind(&self) -> &'static str;
+
+    /// Create a queued job
+    ///
+    /// The flag is `false` when an existing job was returned instead, as an
+    /// idempotency key allows for crate jobs.
+    async fn create(&self, new_job: &Self::NewJob) -> Result<(Self::Job, bool)>;
+
+    async fn find(&self, job_id: Uuid) -> Result<Option<Self::Job>>;
+
+    /// Move a job to `status`, setting `finished_at` for terminal states
+    async fn transition(
+        &self,
+        job_id: Uuid,
+        status: JobStatus,
+        error: Option<&str>,
+    ) -> Result<Self::Job>;
+
+    /// Bump a running job's `updated_at`; `false` once it is no longer running
+    async fn heartbeat(&self, job_id: Uuid) -> Result<bool>;
+
+    /// Running jobs without an update since `cutoff`, oldest first
+    async fn find_stale(&self, cutoff: DateTime<Utc>) -> Result<Vec<Self::Job>>;
+
+    /// Fail running jobs without an update since `cutoff`, appending `reason`
+    /// to their error; returns how many were failed
+    async fn recover_stale(&self, cutoff: DateTime<Utc>, reason: &str) -> Result<u64>;
+
+    /// Delete finished jobs past the 30-day retention window
+    async fn cleanup(&self) -> Result<i32>;
+}
+
+/// A crate job to create through [`JobStore::create`]
+#[derive(Debug, Clone)]
+pub struct NewCrateJob {
+    pub crate_name: String,
+    /// `add_crate` or `remove_crate`
+    pub operation: String,
+    pub options: Value,
+    pub idempotency_key: Option<String>,
+}
+
+#[async_trait]
+impl JobStore for CrateStorage {
+    type Job = CrateJob;
+    type NewJob = NewCrateJob;
+
+    fn kind(&self) -> &'static str {
+        "crate"
+    }
+
+    async fn create(&self, new_job: &NewCrateJob) -> Result<(CrateJob, bool)> {
+        self.store()
+            .create_job(
+                &new_job.crate_name,
+                &new_job.operation,
+                &new_job.options,
+                new_job.idempotency_key.as_deref(),
+            )
+            .await
+    }
+
+    async fn find(&self, job_id: Uuid) -> Result<Option<CrateJob>> {
+        self.store().find_job_by_id(job_id).await
+    }
+
+    async fn transition(
+        &self,
+        job_id: Uuid,
+        status: JobStatus,
+        error: Option<&str>,
+    ) -> Result<CrateJob> {
+        // Crate job updates also set progress; keep what the job reported
+        let job = self
+            .store()
+            .find_job_by_id(job_id)
+            .await?
+            .ok_or_else(|| anyhow!("Job {job_id} not found"))?;
+        self.store()
+            .update_job_status(job_id, status, job.progress, error)
+            .await
+    }
+
+    async fn heartbeat(&self, job_id: Uuid) -> Result<bool> {
+        self.store().heartbeat_job(job_id).await
+    }
+
+    async fn find_stale(&self, cutoff: DateTime<Utc>) -> Result<Vec<CrateJob>> {
+        self.store().find_stale_jobs(cutoff).await
+    }
+
+    async fn recover_stale(&self, cutoff: DateTime<Utc>, reason: &str) -> Result<u64> {
+        self.store().fail_stale_jobs(cutoff, reason).await
+    }
+
+    async fn cleanup(&self) -> Result<i32> {
+        self.store().cleanup_old_jobs().await
+    }
+}
+
+/// An ingest job to create through [`JobStore::create`]
+#[derive(Debug, Clone)]
+pub struct NewIngestJob {
+    /// Repository URL or local checkout path
+    pub url: String,
+    pub doc_type: String,
+}
+
+/// The `ingest_jobs` table
+#[derive(Clone)]
+pub struct IngestJobStore {
+    db_pool: DatabasePool,
+}
+
+impl IngestJobStore {
+    #[must_use]
+    pub const fn new(db_pool: DatabasePool) -> Self {
+        Self { db_pool }
+    }
+
+    /// Set a job's status, replacing `output` and `error` when given
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database update fails.
+    pub async fn update_job_status(
+        &self,
+        job_id: Uuid,
+        status: JobStatus,
+        output: Option<&str>,
+        error: Option<&str>,
+    ) -> Result<IngestJob> {
+        IngestJobQueries::update_job_status(self.db_pool.pool(), job_id, status, output, error)
+            .await
+    }
+}
+
+impl From<DatabasePool> for IngestJobStore {
+    fn from(db_pool: DatabasePool) -> Self {
+        Self::new(db_pool)
+    }
+}
+
+#[async_trait]
+impl JobStore for IngestJobStore {
+    type Job = IngestJob;
+    type NewJob = NewIngestJob;
+
+    fn kind(&self) -> &'static str {
+        "ingest"
+    }
+
+    async fn create(&self, new_job: &NewIngestJob) -> Result<(IngestJob, bool)> {
+        let job =
+            IngestJobQueries::create_job(self.db_pool.pool(), &new_job.url, &new_job.doc_type)
+                .await?;
+        Ok((job, true))
+    }
+
+    async fn find(&self, job_id: Uuid) -> Result<Option<IngestJob>> {
+        IngestJobQueries::find_job_by_id(self.db_pool.pool(), job_id).await
+    }
+
+    async fn transition(
+        &self,
+        job_id: Uuid,
+        status: JobStatus,
+        error: Option<&str>,
+    ) -> Result<IngestJob> {
+        self.update_job_status(job_id, status, None, error).await
+    }
+
+    async fn heartbeat(&self, job_id: Uuid) -> Result<bool> {
+        IngestJobQueries::heartbeat(self.db_pool.pool(), job_id).await
+    }
+
+    async fn find_stale(&self, cutoff: DateTime<Utc>) -> Result<Vec<IngestJob>> {
+        IngestJobQueries::find_stale_jobs(self.db_pool.pool(), cutoff).await
+    }
+
+    async fn recover_stale(&self, cutoff: DateTime<Utc>, reason: &str) -> Result<u64> {
+        IngestJobQueries::fail_stale_jobs(self.db_pool.pool(), cutoff, reason).await
+    }
+
+    async fn cleanup(&self) -> Result<i32> {
+        IngestJobQueries::cleanup_old_jobs(self.db_pool.pool()).await
+    }
+}
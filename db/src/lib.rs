@@ -9,27 +9,47 @@
 //! - Schema integrity validation
 //! - Connection pool metrics and alerting
 
+pub mod chunks;
 pub mod connection;
+pub mod document_ids;
+pub mod job_store;
+pub mod memory;
 pub mod metadata;
 pub mod migration_system;
 pub mod models;
 pub mod pool_config;
 pub mod queries;
 pub mod retry;
+pub mod store;
+pub mod taxonomy;
 
 pub use connection::{DatabasePool, HealthCheckResult, PoolMetricsSnapshot, PoolStatus};
-pub use metadata::{create_enhanced_metadata, merge_enhanced_metadata};
+pub use document_ids::{new_document_id, stable_document_id};
+pub use job_store::{IngestJobStore, JobRecord, JobStore, NewCrateJob, NewIngestJob};
+pub use memory::MemoryCrateStore;
+pub use metadata::{
+    add_alternate_url, alternate_urls, annotate_content_hash, content_hash,
+    create_enhanced_metadata, merge_enhanced_metadata, normalized_content_hash, ContentDeduper,
+    ALTERNATE_URLS_KEY, CONTENT_HASH_KEY,
+};
 pub use migration_system::{
-    DatabaseMigrationManager, MigrationHistory, MigrationInfo, MigrationStatus,
+    DatabaseMigrationManager, MigrateOnStart, MigrationHistory, MigrationInfo, MigrationStatus,
     MigrationStatusSummary, SchemaValidationReport,
 };
 pub use models::*;
 pub use pool_config::{PoolConfig, PoolConfigBuilder};
 pub use queries::{
-    CrateJobQueries, CrateQueries, DocumentQueries, IngestJobQueries, QueryPerformanceMetrics,
-    QueryPerformanceMonitor,
+    ContentChanges, CrateJobQueries, CrateQueries, DocumentQueries, DocumentSourceQueries,
+    EmbeddingCacheQueries, FeedbackQueries, FetchCacheQueries, FtsSettings, IdempotencyConflict,
+    IngestJobQueries, PackageQueries, QueryPerformanceMetrics, QueryPerformanceMonitor,
+    ToolAuditQueries, ToolSettingQueries, WebhookQueries,
+};
+pub use retry::{
+    execute_with_retry, retry_counts, DatabaseError, ErrorClass, RetryConfig, RetryCounts,
+    RetryExecutor,
 };
-pub use retry::{DatabaseError, RetryConfig, RetryExecutor};
+pub use store::{CrateStorage, CrateStore};
+pub use taxonomy::{Taxonomy, TaxonomyRule};
 
 /// Re-export commonly used types
 pub use sqlx::{PgPool, Row};
@@ -0,0 +1,866 @@
+//! In-memory [`CrateStore`] for tests without PostgreSQL
+//!
+//! Documents, jobs and job events live in maps guarded by `RwLock`s and are
+//! filtered the way the SQL in [`crate::queries`] filters rows: documents
+//! belong to a crate through their `crate_name` metadata, compared by
+//! [`crate_name_key`] (or source name for counts and deletes), and `metadata.status = "inactive"` marks soft-deleted
+//! ones. Dependencies are only read from document metadata, since there is
+//! no `document_sources` table here. Nothing runs the queued jobs.
+
+use crate::models::{
+    crate_name_key, embedding_coverage_pct, CrateCursor, CrateDependent, CrateInfo, CrateJob,
+    CrateJobEvent, CrateSortField, CrateStatistics, CrateStatusFilter, Document, JobStatus,
+    PaginatedResponse, PaginationParams, SortOrder,
+};
+use crate::queries::{CrateQueries, IdempotencyConflict, IDEMPOTENCY_KEY_TTL_HOURS};
+use crate::store::CrateStore;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use uuid::Uuid;
+
+/// Attempts a new job gets, matching the `crate_jobs.max_attempts` default
+const DEFAULT_MAX_ATTEMPTS: i32 = 3;
+
+/// Crate documents and jobs held in process memory
+#[derive(Debug, Default)]
+pub struct MemoryCrateStore {
+    documents: RwLock<HashMap<Uuid, Document>>,
+    jobs: RwLock<HashMap<Uuid, CrateJob>>,
+    events: RwLock<Vec<CrateJobEvent>>,
+}
+
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn meta_str<'a>(document: &'a Document, key: &str) -> Option<&'a str> {
+    document.metadata.get(key).and_then(Value::as_str)
+}
+
+fn is_rust(document: &Document) -> bool {
+    document.doc_type == "rust"
+}
+
+fn is_inactive(document: &Document) -> bool {
+    meta_str(document, "status") == Some("inactive")
+}
+
+fn in_status(document: &Document, status: CrateStatusFilter) -> bool {
+    match status {
+        CrateStatusFilter::Active => !is_inactive(document),
+        CrateStatusFilter::Inactive => is_inactive(document),
+        CrateStatusFilter::All => true,
+    }
+}
+
+/// Whether a document belongs to `crate_name` for counts, deletes and status changes
+fn belongs_to(document: &Document, crate_name: &str) -> bool {
+    is_rust(document) && (named(document, crate_name) || document.source_name == crate_name)
+}
+
+/// Whether a document's `crate_name` is `crate_name` up to case and `-`/`_`
+fn named(document: &Document, crate_name: &str) -> bool {
+    meta_str(document, "crate_name")
+        .is_some_and(|name| crate_name_key(name) == crate_name_key(crate_name))
+}
+
+/// Whether a document is of crate version `version`, or of any version without one
+fn in_version(document: &Document, version: Option<&str>) -> bool {
+    version.is_none_or(|v| meta_str(document, "crate_version") == Some(v))
+}
+
+fn set_status(document: &mut Document, status: &str) {
+    if !document.metadata.is_object() {
+        document.metadata = json!({});
+    }
+    document.metadata["status"] = json!(status);
+    document.updated_at = Some(Utc::now());
+}
+
+/// Merge the keys of `extra` into `details`, like jsonb `||`
+fn merge_details(details: &mut Option<Value>, extra: &Value) {
+    let target = details.get_or_insert_with(|| json!({}));
+    if !target.is_object() {
+        *target = json!({});
+    }
+    if let (Some(target), Some(extra)) = (target.as_object_mut(), extra.as_object()) {
+        for (key, value) in extra {
+            target.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+/// Order of two crates in a listing sorted by `sort_by`, ascending
+fn compare_crates(sort_by: CrateSortField, a: &CrateInfo, b: &CrateInfo) -> Ordering {
+    let key = match sort_by {
+        CrateSortField::Name => a.name.cmp(&b.name),
+        CrateSortField::LastUpdated => a.last_updated.cmp(&b.last_updated),
+        CrateSortField::TotalDocs => a.total_docs.cmp(&b.total_docs),
+        CrateSortField::TotalTokens => a.total_tokens.cmp(&b.total_tokens),
+    };
+    key.then_with(|| a.name.cmp(&b.name))
+        .then_with(|| a.version.cmp(&b.version))
+}
+
+/// Order of a crate relative to a cursor position, ascending
+fn compare_to_cursor(info: &CrateInfo, cursor: &CrateCursor) -> Ordering {
+    let key = match cursor.sort_by {
+        CrateSortField::Name => info.name.as_str().cmp(cursor.key.as_str()),
+        CrateSortField::LastUpdated => DateTime::parse_from_rfc3339(&cursor.key)
+            .map_or(Ordering::Greater, |key| {
+                info.last_updated.cmp(&key.with_timezone(&Utc))
+            }),
+        CrateSortField::TotalDocs => cursor.key.parse::<i64>().map_or(Ordering::Greater, |key| {
+            i64::from(info.total_docs).cmp(&key)
+        }),
+        CrateSortField::TotalTokens => cursor
+            .key
+            .parse::<i64>()
+            .map_or(Ordering::Greater, |key| info.total_tokens.cmp(&key)),
+    };
+    key.then_with(|| info.name.cmp(&cursor.name))
+        .then_with(|| info.version.cmp(&cursor.version))
+}
+
+/// Running totals for one crate (or crate version)
+#[derive(Default)]
+struct CrateTotals {
+    /// Each version with the time its newest document was stored
+    versions: BTreeMap<String, Option<DateTime<Utc>>>,
+    docs: i64,
+    tokens: i64,
+    embedded: i64,
+    last_updated: Option<DateTime<Utc>>,
+    /// `crate_name` of the most recently stored document
+    name: Option<String>,
+}
+
+impl CrateTotals {
+    fn add(&mut self, document: &Document) {
+        let stored = self
+            .versions
+            .entry(
+                meta_str(document, "crate_version")
+                    .unwrap_or("latest")
+                    .to_string(),
+            )
+            .or_default();
+        *stored = (*stored).max(document.created_at);
+        self.docs += 1;
+        self.tokens += i64::from(document.token_count.unwrap_or(0));
+        self.embedded += i64::from(document.embedding.is_some());
+        if self.name.is_none() || document.created_at >= self.last_updated {
+            self.name = meta_str(document, "crate_name").map(String::from);
+        }
+        self.last_updated = self.last_updated.max(document.created_at);
+    }
+
+    /// Versions most recently stored first, like `CrateQueries::find_crate_versions`
+    fn versions_newest_first(&self) -> Vec<&str> {
+        let mut versions: Vec<(&String, &Option<DateTime<Utc>>)> = self.versions.iter().collect();
+        versions.sort_by(|a, b| b.1.cmp(a.1).then_with(|| b.0.cmp(a.0)));
+        versions.into_iter().map(|(v, _)| v.as_str()).collect()
+    }
+
+    fn into_info(self, name: String) -> CrateInfo {
+        CrateInfo {
+            version: self.versions_newest_first().join(", "),
+            name: self.name.unwrap_or(name),
+            description: None,
+            documentation_url: None,
+            total_docs: i32::try_from(self.docs).unwrap_or(i32::MAX),
+            total_tokens: self.tokens,
+            embedded_docs: self.embedded,
+            embedding_coverage_pct: embedding_coverage_pct(self.embedded, self.docs),
+            last_updated: self.last_updated.unwrap_or_default(),
+        }
+    }
+}
+
+impl MemoryCrateStore {
+    /// Create an empty store
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Store a document, stamping `created_at` and `updated_at` if unset
+    pub fn insert_document(&self, mut document: Document) {
+        let now = Utc::now();
+        document.created_at.get_or_insert(now);
+        document.updated_at.get_or_insert(now);
+        write(&self.documents).insert(document.id, document);
+    }
+
+    /// Run `f` on a job, failing like a missing row would
+    fn update_job<F>(&self, job_id: Uuid, f: F) -> Result<CrateJob>
+    where
+        F: FnOnce(&mut CrateJob),
+    {
+        let mut jobs = write(&self.jobs);
+        let job = jobs
+            .get_mut(&job_id)
+            .ok_or_else(|| anyhow!("Job {job_id} not found"))?;
+        f(job);
+        job.updated_at = Utc::now();
+        Ok(job.clone())
+    }
+
+    fn push_event(&self, mut event: CrateJobEvent) {
+        let mut events = write(&self.events);
+        event.id = i64::try_from(events.len()).unwrap_or(i64::MAX) + 1;
+        events.push(event);
+    }
+
+    /// Jobs matching `filter`, sorted by `order` and cut to `limit`
+    fn jobs_where<F, O>(&self, filter: F, order: O, limit: Option<i64>) -> Vec<CrateJob>
+    where
+        F: Fn(&CrateJob) -> bool,
+        O: Fn(&CrateJob, &CrateJob) -> Ordering,
+    {
+        let mut jobs: Vec<CrateJob> = read(&self.jobs)
+            .values()
+            .filter(|job| filter(job))
+            .cloned()
+            .collect();
+        jobs.sort_by(order);
+        if let Some(limit) = limit {
+            jobs.truncate(usize::try_from(limit).unwrap_or(0));
+        }
+        jobs
+    }
+}
+
+#[async_trait]
+impl CrateStore for MemoryCrateStore {
+    async fn find_crate_by_name(&self, crate_name: &str) -> Result<Option<CrateInfo>> {
+        let mut totals = CrateTotals::default();
+        for document in read(&self.documents).values() {
+            if is_rust(document) && named(document, crate_name) {
+                totals.add(document);
+            }
+        }
+        Ok((totals.docs > 0).then(|| totals.into_info(crate_name.to_string())))
+    }
+
+    async fn find_crate_versions(&self, crate_name: &str) -> Result<Vec<CrateInfo>> {
+        let mut by_version: BTreeMap<String, CrateTotals> = BTreeMap::new();
+        for document in read(&self.documents).values() {
+            if is_rust(document) && named(document, crate_name) {
+                let version = meta_str(document, "crate_version").unwrap_or("latest");
+                by_version
+                    .entry(version.to_string())
+                    .or_default()
+                    .add(document);
+            }
+        }
+        let mut versions: Vec<CrateInfo> = by_version
+            .into_values()
+            .map(|totals| totals.into_info(crate_name.to_string()))
+            .collect();
+        versions.sort_by(|a, b| {
+            b.last_updated
+                .cmp(&a.last_updated)
+                .then_with(|| b.version.cmp(&a.version))
+        });
+        Ok(versions)
+    }
+
+    async fn list_crates_with_status(
+        &self,
+        pagination: &PaginationParams,
+        name_pattern: Option<&str>,
+        status: CrateStatusFilter,
+    ) -> Result<PaginatedResponse<CrateInfo>> {
+        let pattern = name_pattern.map(crate_name_key);
+        let mut by_version: BTreeMap<(String, String), CrateTotals> = BTreeMap::new();
+        for document in read(&self.documents).values() {
+            let Some(name) = meta_str(document, "crate_name") else {
+                continue;
+            };
+            if !is_rust(document)
+                || !in_status(document, status)
+                || pattern
+                    .as_deref()
+                    .is_some_and(|p| !crate_name_key(name).contains(p))
+            {
+                continue;
+            }
+            let version = meta_str(document, "crate_version").unwrap_or("latest");
+            by_version
+                .entry((name.to_string(), version.to_string()))
+                .or_default()
+                .add(document);
+        }
+
+        let total_items = by_version.len();
+        let mut items: Vec<CrateInfo> = by_version
+            .into_iter()
+            .map(|((name, _), totals)| totals.into_info(name))
+            .collect();
+
+        let descending = pagination.sort_order == SortOrder::Desc;
+        items.sort_by(|a, b| {
+            let order = compare_crates(pagination.sort_by, a, b);
+            if descending {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+        if let Some(cursor) = &pagination.cursor {
+            let after = if descending {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            };
+            items.retain(|info| compare_to_cursor(info, cursor) == after);
+        }
+
+        let limit = usize::try_from(pagination.limit).unwrap_or(usize::MAX);
+        let mut items: Vec<CrateInfo> = items
+            .into_iter()
+            .skip(usize::try_from(pagination.offset).unwrap_or(0))
+            .take(limit.saturating_add(1))
+            .collect();
+        let has_more = items.len() > limit;
+        items.truncate(limit);
+
+        Ok(CrateQueries::crate_page(
+            items,
+            pagination,
+            has_more,
+            i64::try_from(total_items).unwrap_or(i64::MAX),
+        ))
+    }
+
+    async fn get_crate_statistics(&self) -> Result<CrateStatistics> {
+        let mut crates: HashMap<&str, CrateTotals> = HashMap::new();
+        let documents = read(&self.documents);
+        for document in documents.values().filter(|d| is_rust(d)) {
+            let Some(name) = meta_str(document, "crate_name") else {
+                continue;
+            };
+            let totals = crates.entry(name).or_default();
+            if !is_inactive(document) {
+                totals.add(document);
+            }
+        }
+
+        let total_crates = i64::try_from(crates.len()).unwrap_or(i64::MAX);
+        let active: Vec<&CrateTotals> = crates.values().filter(|t| t.docs > 0).collect();
+        let active_crates = i64::try_from(active.len()).unwrap_or(i64::MAX);
+        let total_versions = active.iter().map(|t| t.versions.len()).sum::<usize>();
+        let total_docs: i64 = active.iter().map(|t| t.docs).sum();
+        let docs_with_embeddings: i64 = active.iter().map(|t| t.embedded).sum();
+        let average_docs_per_crate = if active_crates > 0 {
+            #[allow(clippy::cast_precision_loss)] // Acceptable precision loss for statistics
+            {
+                total_docs as f64 / active_crates as f64
+            }
+        } else {
+            0.0
+        };
+
+        Ok(CrateStatistics {
+            total_crates,
+            active_crates,
+            total_versions: i64::try_from(total_versions).unwrap_or(i64::MAX),
+            total_docs_managed: total_docs,
+            total_tokens_managed: active.iter().map(|t| t.tokens).sum(),
+            docs_with_embeddings,
+            embedding_coverage_pct: embedding_coverage_pct(docs_with_embeddings, total_docs),
+            average_docs_per_crate,
+            last_update: active.iter().filter_map(|t| t.last_updated).max(),
+        })
+    }
+
+    async fn find_dependents(&self, crate_name: &str) -> Result<Vec<CrateDependent>> {
+        // Highest version per (dependent, req, kind), like DISTINCT ON in SQL
+        let mut found: BTreeMap<(String, String, String), Option<String>> = BTreeMap::new();
+        for document in read(&self.documents).values() {
+            let Some(name) = meta_str(document, "crate_name") else {
+                continue;
+            };
+            if !is_rust(document) || is_inactive(document) || name == crate_name {
+                continue;
+            }
+            let Some(dependencies) = document
+                .metadata
+                .get("dependencies")
+                .and_then(Value::as_array)
+            else {
+                continue;
+            };
+            for dependency in dependencies {
+                if dependency.get("name").and_then(Value::as_str) != Some(crate_name) {
+                    continue;
+                }
+                let Some(req) = dependency.get("req").and_then(Value::as_str) else {
+                    continue;
+                };
+                let kind = dependency
+                    .get("kind")
+                    .and_then(Value::as_str)
+                    .unwrap_or("normal");
+                let version = meta_str(document, "crate_version").map(String::from);
+                let entry = found
+                    .entry((name.to_string(), req.to_string(), kind.to_string()))
+                    .or_insert(None);
+                *entry = (*entry).clone().max(version);
+            }
+        }
+
+        Ok(found
+            .into_iter()
+            .map(|((crate_name, req, kind), crate_version)| CrateDependent {
+                crate_name,
+                crate_version,
+                req,
+                kind,
+            })
+            .collect())
+    }
+
+    async fn find_crates_without_dependencies(&self, crate_name: &str) -> Result<Vec<String>> {
+        let mut lacks_metadata: BTreeMap<&str, bool> = BTreeMap::new();
+        let documents = read(&self.documents);
+        for document in documents.values() {
+            let Some(name) = meta_str(document, "crate_name") else {
+                continue;
+            };
+            if !is_rust(document) || is_inactive(document) || name == crate_name {
+                continue;
+            }
+            let lacks = document.metadata.get("dependencies").is_none();
+            *lacks_metadata.entry(name).or_insert(true) &= lacks;
+        }
+
+        Ok(lacks_metadata
+            .into_iter()
+            .filter(|(_, lacks)| *lacks)
+            .map(|(name, _)| name.to_string())
+            .collect())
+    }
+
+    async fn count_crate_documents(
+        &self,
+        crate_name: &str,
+        version: Option<&str>,
+        status: CrateStatusFilter,
+    ) -> Result<i64> {
+        let count = read(&self.documents)
+            .values()
+            .filter(|d| belongs_to(d, crate_name) && in_version(d, version) && in_status(d, status))
+            .count();
+        Ok(i64::try_from(count).unwrap_or(i64::MAX))
+    }
+
+    async fn count_crate_embeddings(&self, crate_name: &str, version: Option<&str>) -> Result<i64> {
+        let count = read(&self.documents)
+            .values()
+            .filter(|d| {
+                belongs_to(d, crate_name) && in_version(d, version) && d.embedding.is_some()
+            })
+            .count();
+        Ok(i64::try_from(count).unwrap_or(i64::MAX))
+    }
+
+    async fn count_rust_documents(&self) -> Result<i64> {
+        let count = read(&self.documents)
+            .values()
+            .filter(|d| is_rust(d))
+            .count();
+        Ok(i64::try_from(count).unwrap_or(i64::MAX))
+    }
+
+    async fn count_embeddings_by_model(&self) -> Result<Vec<(Option<String>, i64)>> {
+        let mut counts: HashMap<Option<String>, i64> = HashMap::new();
+        for document in read(&self.documents).values() {
+            if document.embedding.is_some() {
+                *counts
+                    .entry(meta_str(document, "embedding_model").map(String::from))
+                    .or_default() += 1;
+            }
+        }
+        let mut counts: Vec<_> = counts.into_iter().collect();
+        counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        Ok(counts)
+    }
+
+    async fn find_crate_version_documents(
+        &self,
+        crate_name: &str,
+        version: &str,
+        after_path: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Document>> {
+        let documents = read(&self.documents);
+        let mut page: Vec<Document> = documents
+            .values()
+            .filter(|d| {
+                belongs_to(d, crate_name)
+                    && in_version(d, Some(version))
+                    && in_status(d, CrateStatusFilter::Active)
+                    && after_path.is_none_or(|after| d.doc_path.as_str() > after)
+            })
+            .cloned()
+            .collect();
+        page.sort_by(|a, b| a.doc_path.cmp(&b.doc_path));
+        page.truncate(usize::try_from(limit).unwrap_or(0));
+        Ok(page)
+    }
+
+    async fn delete_crate_documents(&self, crate_name: &str, version: Option<&str>) -> Result<u64> {
+        let mut documents = write(&self.documents);
+        let before = documents.len();
+        documents.retain(|_, d| !(belongs_to(d, crate_name) && in_version(d, version)));
+        Ok((before - documents.len()) as u64)
+    }
+
+    async fn delete_crate_documents_batch(
+        &self,
+        crate_name: &str,
+        version: Option<&str>,
+        limit: i64,
+    ) -> Result<u64> {
+        let mut documents = write(&self.documents);
+        let batch: Vec<Uuid> = documents
+            .values()
+            .filter(|d| belongs_to(d, crate_name) && in_version(d, version))
+            .map(|d| d.id)
+            .take(usize::try_from(limit).unwrap_or(0))
+            .collect();
+        for id in &batch {
+            documents.remove(id);
+        }
+        Ok(batch.len() as u64)
+    }
+
+    async fn mark_crate_inactive(&self, crate_name: &str, version: Option<&str>) -> Result<u64> {
+        let mut marked = 0;
+        for document in write(&self.documents).values_mut() {
+            if belongs_to(document, crate_name) && in_version(document, version) {
+                set_status(document, "inactive");
+                marked += 1;
+            }
+        }
+        Ok(marked)
+    }
+
+    async fn restore_crate(&self, crate_name: &str) -> Result<u64> {
+        let mut restored = 0;
+        for document in write(&self.documents).values_mut() {
+            if belongs_to(document, crate_name) && is_inactive(document) {
+                set_status(document, "active");
+                restored += 1;
+            }
+        }
+        Ok(restored)
+    }
+
+    async fn create_job(
+        &self,
+        crate_name: &str,
+        operation: &str,
+        options: &Value,
+        idempotency_key: Option<&str>,
+    ) -> Result<(CrateJob, bool)> {
+        let now = Utc::now();
+        let mut jobs = write(&self.jobs);
+        if let Some(key) = idempotency_key {
+            let expired = now - chrono::Duration::hours(i64::from(IDEMPOTENCY_KEY_TTL_HOURS));
+            for job in jobs.values_mut() {
+                if job.idempotency_key.as_deref() == Some(key) && job.created_at < expired {
+                    job.idempotency_key = None;
+                }
+            }
+            if let Some(existing) = jobs
+                .values()
+                .find(|job| job.idempotency_key.as_deref() == Some(key))
+            {
+                IdempotencyConflict::check(existing, key, crate_name, operation)?;
+                return Ok((existing.clone(), false));
+            }
+        }
+
+        let job = CrateJob {
+            id: Uuid::new_v4(),
+            crate_name: crate_name.to_string(),
+            operation: operation.to_string(),
+            status: JobStatus::Queued,
+            progress: None,
+            error: None,
+            started_at: now,
+            finished_at: None,
+            created_at: now,
+            updated_at: now,
+            options: Some(options.clone()),
+            attempts: 0,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            next_run_at: None,
+            details: None,
+            idempotency_key: idempotency_key.map(String::from),
+        };
+        jobs.insert(job.id, job.clone());
+        Ok((job, true))
+    }
+
+    async fn find_job_by_id(&self, job_id: Uuid) -> Result<Option<CrateJob>> {
+        Ok(read(&self.jobs).get(&job_id).cloned())
+    }
+
+    async fn find_job_by_idempotency_key(&self, idempotency_key: &str) -> Result<Option<CrateJob>> {
+        let cutoff = Utc::now() - chrono::Duration::hours(i64::from(IDEMPOTENCY_KEY_TTL_HOURS));
+        Ok(read(&self.jobs)
+            .values()
+            .find(|job| {
+                job.idempotency_key.as_deref() == Some(idempotency_key) && job.created_at >= cutoff
+            })
+            .cloned())
+    }
+
+    async fn update_job_status(
+        &self,
+        job_id: Uuid,
+        status: JobStatus,
+        progress: Option<i32>,
+        error: Option<&str>,
+    ) -> Result<CrateJob> {
+        let now = Utc::now();
+        let mut previous = None;
+        let job = self.update_job(job_id, |job| {
+            previous = Some((job.status.clone(), job.progress));
+            if status == JobStatus::Running && job.status != JobStatus::Running {
+                job.started_at = now;
+            }
+            job.finished_at = matches!(
+                status,
+                JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled
+            )
+            .then_some(now);
+            job.status = status.clone();
+            job.progress = progress;
+            job.error = error.map(String::from);
+        })?;
+
+        if let Some((old_status, old_progress)) = previous {
+            let status_changed = old_status != status;
+            if status_changed || (progress.is_some() && progress != old_progress) || error.is_some()
+            {
+                self.push_event(CrateJobEvent {
+                    id: 0,
+                    job_id,
+                    created_at: now,
+                    event_type: if status_changed {
+                        "status_changed"
+                    } else {
+                        "progress"
+                    }
+                    .to_string(),
+                    old_status: Some(old_status),
+                    new_status: Some(status),
+                    progress,
+                    detail: error.map(|error| json!({ "error": error })),
+                });
+            }
+        }
+        Ok(job)
+    }
+
+    async fn record_job_event(&self, job_id: Uuid, event_type: &str, detail: &Value) -> Result<()> {
+        let Some(job) = read(&self.jobs).get(&job_id).cloned() else {
+            return Ok(());
+        };
+        self.push_event(CrateJobEvent {
+            id: 0,
+            job_id,
+            created_at: Utc::now(),
+            event_type: event_type.to_string(),
+            old_status: None,
+            new_status: Some(job.status),
+            progress: job.progress,
+            detail: Some(detail.clone()),
+        });
+        Ok(())
+    }
+
+    async fn find_job_events(&self, job_id: Uuid, limit: i64) -> Result<Vec<CrateJobEvent>> {
+        Ok(read(&self.events)
+            .iter()
+            .rev()
+            .filter(|event| event.job_id == job_id)
+            .take(usize::try_from(limit).unwrap_or(0))
+            .cloned()
+            .collect())
+    }
+
+    async fn merge_job_details(&self, job_id: Uuid, details: &Value) -> Result<()> {
+        if let Some(job) = write(&self.jobs).get_mut(&job_id) {
+            merge_details(&mut job.details, details);
+        }
+        Ok(())
+    }
+
+    async fn schedule_retry(
+        &self,
+        job_id: Uuid,
+        next_run_at: DateTime<Utc>,
+        error: &str,
+        details: &Value,
+    ) -> Result<CrateJob> {
+        self.update_job(job_id, |job| {
+            job.status = JobStatus::Queued;
+            job.progress = Some(0);
+            job.error = Some(error.to_string());
+            job.next_run_at = Some(next_run_at);
+            merge_details(&mut job.details, details);
+        })
+    }
+
+    async fn dead_letter_job(
+        &self,
+        job_id: Uuid,
+        error: &str,
+        details: &Value,
+    ) -> Result<CrateJob> {
+        self.update_job(job_id, |job| {
+            job.status = JobStatus::Failed;
+            job.error = Some(error.to_string());
+            job.next_run_at = None;
+            job.attempts = job.attempts.max(job.max_attempts);
+            merge_details(&mut job.details, details);
+            merge_details(&mut job.details, &json!({ "dead_letter": true }));
+            job.finished_at = Some(Utc::now());
+        })
+    }
+
+    async fn requeue_job(&self, job_id: Uuid) -> Result<Option<CrateJob>> {
+        let requeueable = read(&self.jobs)
+            .get(&job_id)
+            .is_some_and(|job| matches!(job.status, JobStatus::Failed | JobStatus::Cancelled));
+        if !requeueable {
+            return Ok(None);
+        }
+        self.update_job(job_id, |job| {
+            job.status = JobStatus::Queued;
+            job.attempts = 0;
+            job.progress = None;
+            job.error = None;
+            job.next_run_at = None;
+            job.finished_at = None;
+            if let Some(details) = job.details.as_mut().and_then(Value::as_object_mut) {
+                details.remove("dead_letter");
+            }
+        })
+        .map(Some)
+    }
+
+    async fn find_active_jobs(&self) -> Result<Vec<CrateJob>> {
+        Ok(self.jobs_where(
+            |job| matches!(job.status, JobStatus::Queued | JobStatus::Running),
+            |a, b| a.created_at.cmp(&b.created_at),
+            None,
+        ))
+    }
+
+    async fn find_dead_letter_jobs(&self, limit: i64) -> Result<Vec<CrateJob>> {
+        Ok(self.jobs_where(
+            |job| job.status == JobStatus::Failed && job.is_dead_lettered(),
+            // Newest first, unfinished last
+            |a, b| match (a.finished_at, b.finished_at) {
+                (Some(a), Some(b)) => b.cmp(&a),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            },
+            Some(limit),
+        ))
+    }
+
+    async fn find_recent_jobs(&self, limit: i64) -> Result<Vec<CrateJob>> {
+        Ok(self.jobs_where(
+            |_| true,
+            |a, b| b.started_at.cmp(&a.started_at),
+            Some(limit),
+        ))
+    }
+
+    async fn find_latest_job_for_crate(&self, crate_name: &str) -> Result<Option<CrateJob>> {
+        Ok(self
+            .jobs_where(
+                |job| job.crate_name == crate_name,
+                |a, b| b.started_at.cmp(&a.started_at),
+                Some(1),
+            )
+            .pop())
+    }
+
+    async fn find_jobs_with_costs(&self, since: DateTime<Utc>) -> Result<Vec<CrateJob>> {
+        Ok(self.jobs_where(
+            |job| {
+                job.started_at >= since
+                    && job
+                        .details
+                        .as_ref()
+                        .is_some_and(|details| details.get("job_costs").is_some())
+            },
+            |a, b| a.started_at.cmp(&b.started_at),
+            None,
+        ))
+    }
+
+    async fn heartbeat_job(&self, job_id: Uuid) -> Result<bool> {
+        Ok(write(&self.jobs)
+            .get_mut(&job_id)
+            .filter(|job| job.status == JobStatus::Running)
+            .map(|job| job.updated_at = Utc::now())
+            .is_some())
+    }
+
+    async fn find_stale_jobs(&self, cutoff: DateTime<Utc>) -> Result<Vec<CrateJob>> {
+        Ok(self.jobs_where(
+            |job| job.status == JobStatus::Running && job.updated_at < cutoff,
+            |a, b| a.updated_at.cmp(&b.updated_at),
+            None,
+        ))
+    }
+
+    async fn fail_stale_jobs(&self, cutoff: DateTime<Utc>, reason: &str) -> Result<u64> {
+        let now = Utc::now();
+        let mut failed = 0;
+        for job in write(&self.jobs).values_mut() {
+            if job.status != JobStatus::Running || job.updated_at >= cutoff {
+                continue;
+            }
+            job.status = JobStatus::Failed;
+            job.finished_at = Some(now);
+            job.error = Some(match job.error.take().filter(|e| !e.is_empty()) {
+                Some(error) => format!("{error}\n{reason}"),
+                None => reason.to_string(),
+            });
+            failed += 1;
+        }
+        Ok(failed)
+    }
+
+    async fn cleanup_old_jobs(&self) -> Result<i32> {
+        let cutoff = Utc::now() - chrono::Duration::days(30);
+        let mut jobs = write(&self.jobs);
+        let before = jobs.len();
+        jobs.retain(|_, job| {
+            !(matches!(
+                job.status,
+                JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled
+            ) && job.finished_at.is_some_and(|at| at < cutoff))
+        });
+        Ok(i32::try_from(before - jobs.len()).unwrap_or(i32::MAX))
+    }
+}
@@ -5,10 +5,129 @@
 //! This ensures consistent metadata across all ingestion paths (loader CLI, MCP tools, etc.).
 //!
 //! The metadata extraction is driven by configuration from tools.json, making it flexible
-//! and extensible for new document types without code changes.
+//! and extensible for new document types without code changes. Topics and categories
+//! come from each doc type's [`Taxonomy`].
 
-use serde_json::Value;
+use crate::taxonomy::Taxonomy;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use tracing::warn;
+
+/// Metadata key holding the SHA-256 of a document's content
+pub const CONTENT_HASH_KEY: &str = "content_hash";
+
+/// Hex-encoded SHA-256 of `content`
+#[must_use]
+pub fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// Record the content hash in a document metadata object
+///
+/// Non-object metadata is left untouched.
+pub fn annotate_content_hash(metadata: &mut Value, content: &str) {
+    if let Some(map) = metadata.as_object_mut() {
+        map.insert(CONTENT_HASH_KEY.to_string(), json!(content_hash(content)));
+    }
+}
+
+/// Metadata key listing other URLs that served the same content as a document
+pub const ALTERNATE_URLS_KEY: &str = "alternate_urls";
+
+/// Hex-encoded SHA-256 of `content` after normalizing insignificant whitespace
+///
+/// Line endings become `\n`, trailing whitespace is trimmed from every line,
+/// runs of blank lines collapse to one and the result is trimmed. Any other
+/// difference, however small, gives a different hash.
+#[must_use]
+pub fn normalized_content_hash(content: &str) -> String {
+    let mut normalized = String::with_capacity(content.len());
+    let mut blank_run = false;
+    for line in content.lines().map(str::trim_end) {
+        if line.is_empty() {
+            blank_run = true;
+            continue;
+        }
+        if !normalized.is_empty() {
+            normalized.push_str(if blank_run { "\n\n" } else { "\n" });
+        }
+        blank_run = false;
+        normalized.push_str(line);
+    }
+    content_hash(&normalized)
+}
+
+/// Alternate URLs recorded in a document metadata object
+#[must_use]
+pub fn alternate_urls(metadata: &Value) -> Vec<&str> {
+    metadata
+        .get(ALTERNATE_URLS_KEY)
+        .and_then(Value::as_array)
+        .map(|urls| urls.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+/// Add `url` to the alternate URLs of a document metadata object
+///
+/// URLs already listed are not repeated; non-object metadata is left untouched.
+pub fn add_alternate_url(metadata: &mut Value, url: &str) {
+    let Some(map) = metadata.as_object_mut() else {
+        return;
+    };
+    let urls = map.entry(ALTERNATE_URLS_KEY).or_insert_with(|| json!([]));
+    if !urls.is_array() {
+        *urls = json!([]);
+    }
+    if let Some(urls) = urls.as_array_mut() {
+        if !urls.iter().any(|u| u == url) {
+            urls.push(json!(url));
+        }
+    }
+}
+
+/// Detects pages that repeat the content of an earlier page in one ingestion run
+///
+/// The first URL seen with a given normalized content hash is canonical;
+/// later URLs with the same hash are duplicates of it. Pages whose content is
+/// empty after normalization are never treated as duplicates.
+#[derive(Debug, Default)]
+pub struct ContentDeduper {
+    canonical: HashMap<String, String>,
+    skipped: usize,
+}
+
+impl ContentDeduper {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Note a page, returning the canonical URL if it duplicates an earlier one
+    pub fn check(&mut self, url: &str, content: &str) -> Option<String> {
+        if content.trim().is_empty() {
+            return None;
+        }
+        let hash = normalized_content_hash(content);
+        match self.canonical.get(&hash) {
+            Some(canonical) if canonical != url => {
+                self.skipped += 1;
+                Some(canonical.clone())
+            }
+            Some(_) => None,
+            None => {
+                self.canonical.insert(hash, url.to_string());
+                None
+            }
+        }
+    }
+
+    /// Number of duplicates found so far
+    #[must_use]
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+}
 
 /// Metadata hints loaded from tools.json configuration
 #[derive(Debug, Clone)]
@@ -18,13 +137,16 @@ pub struct MetadataHints {
     pub supported_categories: Vec<String>,
     pub supported_topics: Vec<String>,
     pub supports_api_version: bool,
-    pub topic_keywords: HashMap<String, Vec<String>>,
-    pub category_keywords: HashMap<String, Vec<String>>,
+    /// Rules assigning topics and categories
+    pub taxonomy: Taxonomy,
 }
 
 impl MetadataHints {
     /// Load metadata hints from tools.json configuration
     ///
+    /// A doc type whose taxonomy is invalid is logged and left out, so its
+    /// documents get the generic metadata.
+    ///
     /// # Errors
     ///
     /// Returns an error if tools.json cannot be found or parsed
@@ -41,6 +163,13 @@ impl MetadataHints {
                 if let (Some(doc_type), Some(hints)) =
                     (tool["docType"].as_str(), tool["metadataHints"].as_object())
                 {
+                    let taxonomy = match Taxonomy::from_hints(&tool["metadataHints"]) {
+                        Ok(taxonomy) => taxonomy,
+                        Err(e) => {
+                            warn!("Ignoring metadata hints of doc type {}: {}", doc_type, e);
+                            continue;
+                        }
+                    };
                     let metadata_hints = Self {
                         supported_formats: hints
                             .get("supported_formats")
@@ -90,10 +219,7 @@ impl MetadataHints {
                             .get("supports_api_version")
                             .and_then(Value::as_bool)
                             .unwrap_or(false),
-                        topic_keywords: Self::parse_keyword_mappings(hints.get("topic_keywords")),
-                        category_keywords: Self::parse_keyword_mappings(
-                            hints.get("category_keywords"),
-                        ),
+                        taxonomy,
                     };
                     hints_map.insert(doc_type.to_string(), metadata_hints);
                 }
@@ -102,25 +228,6 @@ impl MetadataHints {
 
         Ok(hints_map)
     }
-
-    /// Parse keyword mappings from JSON configuration
-    fn parse_keyword_mappings(keywords_value: Option<&Value>) -> HashMap<String, Vec<String>> {
-        let mut mappings = HashMap::new();
-
-        if let Some(keywords_obj) = keywords_value.and_then(|v| v.as_object()) {
-            for (key, value) in keywords_obj {
-                if let Some(keywords_array) = value.as_array() {
-                    let keywords: Vec<String> = keywords_array
-                        .iter()
-                        .filter_map(|v| v.as_str().map(String::from))
-                        .collect();
-                    mappings.insert(key.clone(), keywords);
-                }
-            }
-        }
-
-        mappings
-    }
 }
 
 /// Create enhanced metadata by analyzing document content and structure
@@ -180,17 +287,11 @@ fn create_metadata_from_hints(
         metadata.insert("complexity".to_string(), Value::String(complexity));
     }
 
-    // Determine topic
-    if !hints.supported_topics.is_empty() {
-        let topic = determine_best_topic(content, doc_path, &hints.supported_topics, hints);
-        metadata.insert("topic".to_string(), Value::String(topic));
+    if let Some(topic) = hints.taxonomy.topic(content, doc_path) {
+        metadata.insert("topic".to_string(), Value::String(topic.to_string()));
     }
-
-    // Determine category
-    if !hints.supported_categories.is_empty() {
-        let category =
-            determine_best_category(content, doc_path, &hints.supported_categories, hints);
-        metadata.insert("category".to_string(), Value::String(category));
+    if let Some(category) = hints.taxonomy.category(content, doc_path) {
+        metadata.insert("category".to_string(), Value::String(category.to_string()));
     }
 
     // Add API version if supported (would need additional logic to extract from content)
@@ -260,80 +361,6 @@ fn determine_best_format(doc_path: &str, content: &str, supported_formats: &[Str
     }
 }
 
-/// Determine best topic from supported options based on content analysis
-fn determine_best_topic(
-    content: &str,
-    doc_path: &str,
-    supported_topics: &[String],
-    hints: &MetadataHints,
-) -> String {
-    let content_lower = content.to_lowercase();
-    let path_lower = doc_path.to_lowercase();
-
-    // Score each supported topic based on keyword matches
-    let mut topic_scores: Vec<(String, i32)> = supported_topics
-        .iter()
-        .map(|topic| {
-            let score =
-                calculate_topic_score(&content_lower, &path_lower, topic, &hints.topic_keywords);
-            (topic.clone(), score)
-        })
-        .collect();
-
-    // Sort by score (highest first)
-    topic_scores.sort_by(|a, b| b.1.cmp(&a.1));
-
-    // Return the highest scoring topic, or first as default
-    topic_scores.first().map_or_else(
-        || {
-            supported_topics
-                .first()
-                .unwrap_or(&"general".to_string())
-                .clone()
-        },
-        |(topic, _)| topic.clone(),
-    )
-}
-
-/// Determine best category from supported options based on content analysis  
-fn determine_best_category(
-    content: &str,
-    doc_path: &str,
-    supported_categories: &[String],
-    hints: &MetadataHints,
-) -> String {
-    let content_lower = content.to_lowercase();
-    let path_lower = doc_path.to_lowercase();
-
-    // Score each supported category based on keyword matches
-    let mut category_scores: Vec<(String, i32)> = supported_categories
-        .iter()
-        .map(|category| {
-            let score = calculate_category_score(
-                &content_lower,
-                &path_lower,
-                category,
-                &hints.category_keywords,
-            );
-            (category.clone(), score)
-        })
-        .collect();
-
-    // Sort by score (highest first)
-    category_scores.sort_by(|a, b| b.1.cmp(&a.1));
-
-    // Return the highest scoring category, or first as default
-    category_scores.first().map_or_else(
-        || {
-            supported_categories
-                .first()
-                .unwrap_or(&"general".to_string())
-                .clone()
-        },
-        |(category, _)| category.clone(),
-    )
-}
-
 /// Determine best complexity from supported options
 fn determine_best_complexity(content: &str, doc_path: &str, supported_levels: &[String]) -> String {
     let detected_complexity = determine_complexity_by_content(content, doc_path);
@@ -354,61 +381,6 @@ fn determine_best_complexity(content: &str, doc_path: &str, supported_levels: &[
     }
 }
 
-/// Calculate topic relevance score based on keyword matches
-fn calculate_topic_score(
-    content_lower: &str,
-    path_lower: &str,
-    topic: &str,
-    topic_keywords: &HashMap<String, Vec<String>>,
-) -> i32 {
-    let mut score = 0;
-
-    // Get keywords from configuration, or use topic name as fallback
-    let keywords = topic_keywords.get(topic).map_or_else(
-        || vec![topic],
-        |v| v.iter().map(String::as_str).collect::<Vec<_>>(),
-    );
-
-    // Count keyword matches in content and path
-    for keyword in keywords {
-        if content_lower.contains(keyword) {
-            score += 2; // Content matches are worth more
-        }
-        if path_lower.contains(keyword) {
-            score += 1; // Path matches are worth less
-        }
-    }
-
-    score
-}
-
-/// Calculate category relevance score based on keyword matches
-fn calculate_category_score(
-    content_lower: &str,
-    path_lower: &str,
-    category: &str,
-    category_keywords: &HashMap<String, Vec<String>>,
-) -> i32 {
-    let mut score = 0;
-
-    // Get keywords from configuration, or use category name as fallback
-    let keywords = category_keywords.get(category).map_or_else(
-        || vec![category],
-        |v| v.iter().map(String::as_str).collect::<Vec<_>>(),
-    );
-
-    for keyword in keywords {
-        if content_lower.contains(keyword) {
-            score += 2;
-        }
-        if path_lower.contains(keyword) {
-            score += 1;
-        }
-    }
-
-    score
-}
-
 /// Extract API version from content (basic implementation)
 fn extract_api_version(content: &str) -> Option<String> {
     let content_lower = content.to_lowercase();
@@ -551,4 +523,283 @@ async fn main() {
         assert_eq!(metadata["format"], "markdown");
         assert_eq!(metadata["complexity"], "beginner");
     }
+
+    /// Topic and category scoring before taxonomies, kept as the reference
+    /// the taxonomy engine must reproduce
+    mod legacy {
+        use serde_json::Value;
+        use std::collections::HashMap;
+
+        fn keyword_map(hints: &Value, key: &str) -> HashMap<String, Vec<String>> {
+            hints
+                .get(key)
+                .and_then(Value::as_object)
+                .map(|map| {
+                    map.iter()
+                        .map(|(name, list)| {
+                            let keywords = list
+                                .as_array()
+                                .into_iter()
+                                .flatten()
+                                .filter_map(|v| v.as_str().map(String::from))
+                                .collect();
+                            (name.clone(), keywords)
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+
+        fn score(
+            content_lower: &str,
+            path_lower: &str,
+            name: &str,
+            keywords: &HashMap<String, Vec<String>>,
+        ) -> i32 {
+            let keywords = keywords.get(name).map_or_else(
+                || vec![name],
+                |v| v.iter().map(String::as_str).collect::<Vec<_>>(),
+            );
+            let mut score = 0;
+            for keyword in keywords {
+                if content_lower.contains(keyword) {
+                    score += 2;
+                }
+                if path_lower.contains(keyword) {
+                    score += 1;
+                }
+            }
+            score
+        }
+
+        pub fn best(
+            hints: &Value,
+            names_key: &str,
+            keywords_key: &str,
+            content: &str,
+            doc_path: &str,
+        ) -> Option<String> {
+            let names: Vec<String> = hints
+                .get(names_key)
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect();
+            if names.is_empty() {
+                return None;
+            }
+            let keywords = keyword_map(hints, keywords_key);
+            let content_lower = content.to_lowercase();
+            let path_lower = doc_path.to_lowercase();
+            let mut scores: Vec<(String, i32)> = names
+                .iter()
+                .map(|name| {
+                    let s = score(&content_lower, &path_lower, name, &keywords);
+                    (name.clone(), s)
+                })
+                .collect();
+            scores.sort_by_key(|b| std::cmp::Reverse(b.1));
+            scores.first().map(|(name, _)| name.clone())
+        }
+    }
+
+    /// Sample documents covering every configured doc type's vocabulary
+    const GOLDEN_CORPUS: &[(&str, &str)] = &[
+        (
+            "docs/api/v6/swap.md",
+            "# Swap API\n\nCall the swap endpoint to trade tokens on the market.",
+        ),
+        (
+            "docs/liquidity/pools.md",
+            "Provide liquidity to a pool and stake LP tokens.",
+        ),
+        (
+            "guides/integration/wallet.md",
+            "Integrate the wallet adapter into your dApp. Getting started.",
+        ),
+        (
+            "docs/price.md",
+            "The price API returns token prices over HTTP requests.",
+        ),
+        (
+            "consensus/tower-bft.md",
+            "Validators vote on forks; consensus relies on Tower BFT and networking via gossip.",
+        ),
+        (
+            "zk/proofs.md",
+            "Zero-knowledge cryptography for confidential transfers in the core runtime.",
+        ),
+        (
+            "talos/install.md",
+            "Install Talos on bare metal, then apply the machine config and set up the cluster.",
+        ),
+        (
+            "talos/troubleshooting.md",
+            "Troubleshooting networking and security issues in Kubernetes.",
+        ),
+        (
+            "cilium/policy.md",
+            "Network policy with eBPF, service mesh and monitoring with Hubble observability.",
+        ),
+        (
+            "ebpf/kprobes.md",
+            "BPF programs attach to kernel hooks for tracing and performance analysis.",
+        ),
+        (
+            "rust/ownership.md",
+            "Ownership, borrowing and error handling idioms; concurrency patterns and testing.",
+        ),
+        (
+            "meteora/farming.md",
+            "Yield farming strategies for DeFi liquidity pools on Solana.",
+        ),
+        (
+            "raydium/amm.md",
+            "The AMM supports swap and farming in liquidity pools; see the API for trading.",
+        ),
+        (
+            "birdeye/openapi.json",
+            "{\"endpoints\": [\"/defi/price\"], \"parameters\": {}, \"responses\": {}}",
+        ),
+        (
+            "ai/cli.md",
+            "Installation, authentication and usage of the CLI for code generation; API examples.",
+        ),
+        (
+            "cursor/extensions.md",
+            "Configure extensions in the Cursor IDE for AI assistance.",
+        ),
+        ("README", "Nothing in particular."),
+        ("", ""),
+    ];
+
+    #[test]
+    fn test_taxonomy_matches_legacy_scoring() {
+        let tools_content = std::fs::read_to_string("../tools.json")
+            .or_else(|_| std::fs::read_to_string("tools.json"))
+            .expect("tools.json");
+        let tools_config: Value = serde_json::from_str(&tools_content).unwrap();
+        let hints_map = MetadataHints::load_from_tools_config().unwrap();
+
+        let mut compared = 0;
+        for tool in tools_config["tools"].as_array().unwrap() {
+            let (Some(doc_type), Some(raw_hints)) =
+                (tool["docType"].as_str(), tool.get("metadataHints"))
+            else {
+                continue;
+            };
+            // Doc types listed more than once keep their last hints
+            let last = tools_config["tools"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .rev()
+                .find(|t| t["docType"] == doc_type && t.get("metadataHints").is_some())
+                .unwrap();
+            if !std::ptr::eq(last, tool) {
+                continue;
+            }
+            let hints = &hints_map[doc_type];
+            for (path, content) in GOLDEN_CORPUS {
+                let metadata = create_enhanced_metadata(doc_type, "golden", content, path);
+                let expected_topic = legacy::best(
+                    raw_hints,
+                    "supported_topics",
+                    "topic_keywords",
+                    content,
+                    path,
+                );
+                let expected_category = legacy::best(
+                    raw_hints,
+                    "supported_categories",
+                    "category_keywords",
+                    content,
+                    path,
+                );
+                assert_eq!(
+                    metadata.get("topic").and_then(Value::as_str),
+                    expected_topic.as_deref(),
+                    "topic of {path} for {doc_type}"
+                );
+                assert_eq!(
+                    metadata.get("category").and_then(Value::as_str),
+                    expected_category.as_deref(),
+                    "category of {path} for {doc_type}"
+                );
+                if !hints.supported_complexity_levels.is_empty() {
+                    assert_eq!(
+                        metadata["complexity"],
+                        determine_best_complexity(
+                            content,
+                            path,
+                            &hints.supported_complexity_levels
+                        ),
+                        "complexity of {path} for {doc_type}"
+                    );
+                }
+                compared += 1;
+            }
+        }
+        assert!(
+            compared > GOLDEN_CORPUS.len() * 10,
+            "{compared} comparisons"
+        );
+    }
+
+    #[test]
+    fn test_exact_duplicates_are_detected() {
+        let page = "# Struct Client\n\nAn HTTP client.\n";
+        let mut deduper = ContentDeduper::new();
+        assert_eq!(
+            deduper.check("https://docs.rs/a/struct.Client.html", page),
+            None
+        );
+        assert_eq!(
+            deduper.check("https://docs.rs/a/client/struct.Client.html", page),
+            Some("https://docs.rs/a/struct.Client.html".to_string())
+        );
+        // Seeing the canonical page again is not a duplicate
+        assert_eq!(
+            deduper.check("https://docs.rs/a/struct.Client.html", page),
+            None
+        );
+        assert_eq!(deduper.skipped(), 1);
+    }
+
+    #[test]
+    fn test_whitespace_only_differences_are_duplicates() {
+        let a = "# Client\n\nAn HTTP client.\n";
+        let b = "# Client  \r\n\r\n\r\nAn HTTP client.\r\n\r\n";
+        assert_eq!(normalized_content_hash(a), normalized_content_hash(b));
+        assert_ne!(content_hash(a), content_hash(b));
+    }
+
+    #[test]
+    fn test_near_duplicates_are_not_merged() {
+        let mut deduper = ContentDeduper::new();
+        assert_eq!(deduper.check("a.html", "# Client\n\nAn HTTP client."), None);
+        assert_eq!(
+            deduper.check("b.html", "# Client\n\nAn HTTPS client."),
+            None
+        );
+        assert_eq!(deduper.check("c.html", "# Client\nAn HTTP client."), None);
+        assert_eq!(
+            deduper.check("d.html", "#  Client\n\nAn HTTP client."),
+            None
+        );
+        assert_eq!(deduper.check("e.html", ""), None);
+        assert_eq!(deduper.check("f.html", "  \n"), None);
+        assert_eq!(deduper.skipped(), 0);
+    }
+
+    #[test]
+    fn test_alternate_urls_are_listed_once() {
+        let mut metadata = json!({"source_url": "a.html"});
+        add_alternate_url(&mut metadata, "b.html");
+        add_alternate_url(&mut metadata, "c.html");
+        add_alternate_url(&mut metadata, "b.html");
+        assert_eq!(alternate_urls(&metadata), ["b.html", "c.html"]);
+        assert!(alternate_urls(&json!({})).is_empty());
+    }
 }
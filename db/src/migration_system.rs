@@ -7,10 +7,62 @@ use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{PgPool, Postgres, Row};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+/// Advisory lock key held while migrations are applied, so only one
+/// replica (or migration job) runs them at a time
+const MIGRATION_LOCK_KEY: i64 = 0x646f_635f_6d69_6772;
+
+/// What the server does with pending migrations at startup (`MIGRATE_ON_START`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MigrateOnStart {
+    /// Apply pending migrations under the advisory lock (`true`, the default)
+    #[default]
+    Apply,
+    /// Leave migrations to another process and stay unready while any are pending (`check`)
+    Check,
+    /// Neither apply migrations nor hold readiness for them (`false`)
+    Off,
+}
+
+impl MigrateOnStart {
+    /// Mode from `MIGRATE_ON_START`, [`MigrateOnStart::Apply`] when unset
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for values other than `true`, `false` and `check`.
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("MIGRATE_ON_START") {
+            Ok(value) if !value.trim().is_empty() => value.parse(),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Whether readiness should wait until no migration is pending
+    #[must_use]
+    pub fn gates_readiness(self) -> bool {
+        !matches!(self, Self::Off)
+    }
+}
+
+impl FromStr for MigrateOnStart {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" => Ok(Self::Apply),
+            "check" => Ok(Self::Check),
+            "false" | "0" | "no" => Ok(Self::Off),
+            other => Err(anyhow!(
+                "Invalid MIGRATE_ON_START value '{other}', expected true, false or check"
+            )),
+        }
+    }
+}
+
 /// Migration metadata and version information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MigrationInfo {
@@ -85,6 +137,18 @@ impl DatabaseMigrationManager {
     async fn initialize_migration_tables(&self) -> Result<()> {
         info!("Initializing migration metadata tables...");
 
+        // Replicas starting on a fresh database would otherwise race on the DDL below
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SELECT pg_advisory_xact_lock($1)")
+            .bind(MIGRATION_LOCK_KEY)
+            .execute(&mut *tx)
+            .await?;
+
+        // uuid_generate_v4() backs the history table's ID default
+        sqlx::query(r#"CREATE EXTENSION IF NOT EXISTS "uuid-ossp""#)
+            .execute(&mut *tx)
+            .await?;
+
         // Create migration status enum
         sqlx::query(
             r"
@@ -95,7 +159,7 @@ impl DatabaseMigrationManager {
             END $$;
         ",
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
 
         // Create migration history table (idempotent)
@@ -115,7 +179,7 @@ impl DatabaseMigrationManager {
             )
         ",
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
 
         // Self-heal existing tables that may be missing new columns from earlier versions
@@ -153,28 +217,29 @@ impl DatabaseMigrationManager {
             END $$;
             ",
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
 
         // Create indexes for performance
         sqlx::query(
             "CREATE INDEX IF NOT EXISTS idx_migration_history_migration_id ON migration_history(migration_id)",
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
 
         sqlx::query(
             "CREATE INDEX IF NOT EXISTS idx_migration_history_version ON migration_history(version)",
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
 
         sqlx::query(
             "CREATE INDEX IF NOT EXISTS idx_migration_history_status ON migration_history(status)",
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
 
+        tx.commit().await?;
         info!("Migration metadata tables initialized successfully");
         Ok(())
     }
@@ -195,30 +260,42 @@ impl DatabaseMigrationManager {
     /// Returns an error if dependency resolution fails or database queries fail.
     pub async fn get_pending_migrations(&self) -> Result<Vec<MigrationInfo>> {
         let applied_migrations = self.get_applied_migrations().await?;
-        let applied_ids: std::collections::HashSet<String> = applied_migrations
+        let applied_ids: HashSet<String> = applied_migrations
             .into_iter()
             .map(|m| m.migration_id)
             .collect();
 
-        let mut pending: Vec<MigrationInfo> = self
+        let mut remaining: Vec<MigrationInfo> = self
             .migrations
             .values()
             .filter(|m| !applied_ids.contains(&m.id))
             .cloned()
             .collect();
-
-        // Sort by dependencies and version
-        pending.sort_by(|a, b| {
-            // First sort by dependencies
-            if a.dependencies.contains(&b.id) {
-                std::cmp::Ordering::Greater
-            } else if b.dependencies.contains(&a.id) {
-                std::cmp::Ordering::Less
-            } else {
-                // Then by version
-                a.version.cmp(&b.version)
-            }
-        });
+        // IDs start with a sequence number, so this is registration order
+        remaining.sort_by(|a, b| a.id.cmp(&b.id));
+
+        // Take the first migration whose pending dependencies are all placed
+        let mut pending: Vec<MigrationInfo> = Vec::with_capacity(remaining.len());
+        while !remaining.is_empty() {
+            let next = remaining
+                .iter()
+                .position(|m| {
+                    m.dependencies
+                        .iter()
+                        .all(|dep| !remaining.iter().any(|other| &other.id == dep))
+                })
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Circular migration dependencies among: {}",
+                        remaining
+                            .iter()
+                            .map(|m| m.id.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })?;
+            pending.push(remaining.remove(next));
+        }
 
         Ok(pending)
     }
@@ -280,8 +357,8 @@ impl DatabaseMigrationManager {
             match self.apply_single_migration(&migration).await {
                 Ok(history) => {
                     info!(
-                        "Successfully applied migration: {} in {}ms",
-                        migration.id, history.execution_time_ms
+                        "Successfully applied migration: {} ({}) in {}ms",
+                        migration.id, migration.version, history.execution_time_ms
                     );
                     applied.push(history);
                 }
@@ -302,6 +379,68 @@ impl DatabaseMigrationManager {
         Ok(applied)
     }
 
+    /// Apply pending migrations while holding the migration advisory lock
+    ///
+    /// Replicas starting together wait for each other; the later ones find
+    /// nothing left to apply.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock cannot be taken or a migration fails.
+    pub async fn apply_migrations_locked(&self) -> Result<Vec<MigrationHistory>> {
+        let mut conn = self.pool.acquire().await?;
+        info!("Waiting for the migration lock...");
+        sqlx::query("SELECT pg_advisory_lock($1)")
+            .bind(MIGRATION_LOCK_KEY)
+            .execute(&mut *conn)
+            .await?;
+
+        let result = self.apply_migrations().await;
+
+        if let Err(e) = sqlx::query("SELECT pg_advisory_unlock($1)")
+            .bind(MIGRATION_LOCK_KEY)
+            .execute(&mut *conn)
+            .await
+        {
+            // The lock goes with the connection, so don't return it to the pool
+            warn!("Failed to release the migration lock: {}", e);
+            conn.detach();
+        }
+        result
+    }
+
+    /// Handle pending migrations at startup according to `mode`
+    ///
+    /// Returns the migrations applied, which is always empty unless `mode`
+    /// is [`MigrateOnStart::Apply`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a migration fails or the pending list cannot be read.
+    pub async fn run_on_start(&self, mode: MigrateOnStart) -> Result<Vec<MigrationHistory>> {
+        match mode {
+            MigrateOnStart::Apply => self.apply_migrations_locked().await,
+            MigrateOnStart::Check => {
+                let pending = self.get_pending_migrations().await?;
+                if pending.is_empty() {
+                    info!("MIGRATE_ON_START=check: database schema is up to date");
+                } else {
+                    let ids: Vec<&str> = pending.iter().map(|m| m.id.as_str()).collect();
+                    warn!(
+                        "MIGRATE_ON_START=check: not ready until {} pending migrations are applied: {}",
+                        pending.len(),
+                        ids.join(", ")
+                    );
+                }
+                Ok(Vec::new())
+            }
+            MigrateOnStart::Off => {
+                info!("MIGRATE_ON_START=false: skipping migrations");
+                Ok(Vec::new())
+            }
+        }
+    }
+
     /// Apply a single migration with transaction safety
     async fn apply_single_migration(&self, migration: &MigrationInfo) -> Result<MigrationHistory> {
         let start_time = std::time::Instant::now();
@@ -312,11 +451,8 @@ impl DatabaseMigrationManager {
         // Record migration start
         let history_id = self.record_migration_start(&mut tx, migration).await?;
 
-        // Execute migration SQL
-        match sqlx::query::<Postgres>(&migration.up_sql)
-            .execute(&mut *tx)
-            .await
-        {
+        // Execute migration SQL; unprepared, so a migration may hold several statements
+        match sqlx::raw_sql(&migration.up_sql).execute(&mut *tx).await {
             Ok(_) => {
                 let execution_time =
                     i64::try_from(start_time.elapsed().as_millis()).unwrap_or(i64::MAX);
@@ -601,6 +737,12 @@ impl DatabaseMigrationManager {
             completed: completed_count,
             failed: failed_count,
             pending: pending.len(),
+            pending_migrations: pending.into_iter().map(|m| m.id).collect(),
+            schema_version: applied
+                .iter()
+                .filter(|m| matches!(m.status, MigrationStatus::Completed))
+                .map(|m| m.migration_id.clone())
+                .max(),
             last_applied: applied.last().map(|m| m.applied_at),
         })
     }
@@ -623,5 +765,9 @@ pub struct MigrationStatusSummary {
     pub completed: usize,
     pub failed: usize,
     pub pending: usize,
+    /// IDs of the pending migrations, in the order they would be applied
+    pub pending_migrations: Vec<String>,
+    /// Newest completed migration ID; IDs start with a sequence number
+    pub schema_version: Option<String>,
     pub last_applied: Option<DateTime<Utc>>,
 }
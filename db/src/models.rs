@@ -61,6 +61,36 @@ pub struct Document {
     pub updated_at: Option<DateTime<Utc>>,
 }
 
+/// Search hit paired with its relevance rank
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoredDocument {
+    pub document: Document,
+    /// Raw full-text rank (higher is more relevant)
+    pub score: f64,
+}
+
+/// Lightweight document descriptor used for MCP resource listings
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DocumentResource {
+    pub id: Uuid,
+    pub doc_type: String,
+    pub source_name: String,
+    pub doc_path: String,
+    pub token_count: Option<i32>,
+    /// Content length in characters
+    pub content_length: i32,
+}
+
+/// Reference to one chunk of a chunked document
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ChunkReference {
+    pub id: Uuid,
+    pub doc_path: String,
+    /// Zero-based position within the parent document
+    pub chunk_index: i32,
+    pub token_count: Option<i32>,
+}
+
 /// Document source configuration
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct DocumentSource {
@@ -73,6 +103,31 @@ pub struct DocumentSource {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Document source with the number of documents stored for it
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DocumentSourceSummary {
+    pub doc_type: String,
+    pub source_name: String,
+    pub config: serde_json::Value,
+    pub enabled: bool,
+    pub document_count: i64,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of deleting, or previewing the deletion of, a document source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentSourceDeletion {
+    pub doc_type: String,
+    pub source_name: String,
+    /// Documents stored for the source
+    pub document_count: i64,
+    /// Queued or running jobs that may still write to the source
+    pub active_jobs: i64,
+    /// Whether the source and its documents were removed
+    pub deleted: bool,
+}
+
 /// Tool configuration from JSON
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolConfig {
@@ -85,6 +140,23 @@ pub struct ToolConfig {
     /// Optional metadata hints for supported filters and content types
     #[serde(rename = "metadataHints", default)]
     pub metadata_hints: Option<ToolMetadataHints>,
+    /// Metadata filters a query tool accepts; every filter when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filters: Option<Vec<String>>,
+    /// Results a query tool returns when a call gives no `limit`
+    #[serde(
+        rename = "defaultLimit",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub default_limit: Option<i64>,
+    /// Seconds a call may run before it is abandoned; see `mcp::tool_timeouts`
+    #[serde(
+        rename = "timeoutSecs",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timeout_secs: Option<u64>,
 }
 
 /// Metadata hints for tool configuration
@@ -105,6 +177,9 @@ pub struct ToolMetadataHints {
     /// Whether API version filtering is supported
     #[serde(default)]
     pub supports_api_version: bool,
+    /// Topic and category rules; see [`crate::taxonomy`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taxonomy: Option<crate::taxonomy::Taxonomy>,
 }
 
 /// Tools configuration container
@@ -137,6 +212,184 @@ pub struct CrateJob {
     pub finished_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Request options the job was enqueued with
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<serde_json::Value>,
+    /// Number of times the job has been claimed to run
+    #[sqlx(default)]
+    #[serde(default)]
+    pub attempts: i32,
+    /// Attempts allowed before the job is dead-lettered
+    #[sqlx(default)]
+    #[serde(default)]
+    pub max_attempts: i32,
+    /// Earliest time a rescheduled job may run again
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_run_at: Option<DateTime<Utc>>,
+    /// Failure details such as `dead_letter` and `last_error_kind`
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+    /// Client-supplied key that maps retried requests to this job
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+}
+
+impl CrateJob {
+    /// Whether the job failed permanently or ran out of attempts
+    #[must_use]
+    pub fn is_dead_lettered(&self) -> bool {
+        self.details
+            .as_ref()
+            .and_then(|d| d.get("dead_letter"))
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false)
+    }
+}
+
+/// Entry in a crate job's audit trail
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct CrateJobEvent {
+    pub id: i64,
+    pub job_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    /// `status_changed`, `progress`, or an informational type such as `batch_committed`
+    pub event_type: String,
+    pub old_status: Option<JobStatus>,
+    pub new_status: Option<JobStatus>,
+    pub progress: Option<i32>,
+    pub detail: Option<serde_json::Value>,
+}
+
+/// Recorded tool invocation in the tool audit log
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ToolAuditEntry {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    pub session_id: Option<String>,
+    /// Bearer token label of the authenticated client
+    pub client_label: Option<String>,
+    pub user_agent: Option<String>,
+    pub tool_name: String,
+    /// Call arguments with sensitive values redacted
+    pub arguments: serde_json::Value,
+    pub success: bool,
+    pub duration_ms: i64,
+    pub error: Option<String>,
+}
+
+/// Tool invocation to append to the tool audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewToolAuditEntry {
+    pub created_at: DateTime<Utc>,
+    pub session_id: Option<String>,
+    pub client_label: Option<String>,
+    pub user_agent: Option<String>,
+    pub tool_name: String,
+    pub arguments: serde_json::Value,
+    pub success: bool,
+    pub duration_ms: i64,
+    pub error: Option<String>,
+}
+
+/// Filters for listing tool audit log entries; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct ToolAuditFilter {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub tool_name: Option<String>,
+    pub session_id: Option<String>,
+}
+
+/// Webhook subscription notified of job and crate events
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub url: String,
+    /// HMAC-SHA256 key for the `X-Signature` header
+    #[serde(skip_serializing)]
+    pub secret: String,
+    /// Events delivered to the webhook; empty means every event
+    pub events: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Attempted delivery of one event to one webhook
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    /// Subscription delivered to; `None` for the `WEBHOOK_URL` webhook
+    pub webhook_id: Option<Uuid>,
+    pub url: String,
+    pub event: String,
+    pub payload: serde_json::Value,
+    /// `pending`, `delivered` or `failed`
+    pub status: String,
+    pub attempts: i32,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// Rating of one search result by the agent that used it
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct SearchFeedback {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    pub session_id: Option<String>,
+    pub query: String,
+    /// `useful`, `partially` or `not_useful`
+    pub rating: String,
+    pub document_id: Option<Uuid>,
+    pub doc_path: Option<String>,
+    pub doc_type: Option<String>,
+    /// Crate or package of the rated document
+    pub crate_name: Option<String>,
+    /// Whether the id or path named a stored document when the rating was recorded
+    pub document_found: bool,
+}
+
+/// Rating to record in the search feedback log
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NewSearchFeedback {
+    pub session_id: Option<String>,
+    pub query: String,
+    pub rating: String,
+    /// Looked up first; `doc_path` is used when it is unset
+    pub document_id: Option<Uuid>,
+    pub doc_path: Option<String>,
+}
+
+/// Ratings of the feedback entries sharing a doc type, crate or query term
+#[derive(Debug, Clone, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+pub struct FeedbackCounts {
+    pub key: String,
+    pub useful: i64,
+    pub partially: i64,
+    pub not_useful: i64,
+}
+
+impl FeedbackCounts {
+    /// Number of ratings
+    #[must_use]
+    pub const fn total(&self) -> i64 {
+        self.useful + self.partially + self.not_useful
+    }
+
+    /// Share of useful ratings, counting partial ones as half, between 0 and 1
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn score(&self) -> f64 {
+        let total = self.total();
+        if total == 0 {
+            return 0.0;
+        }
+        (self.useful as f64 + 0.5 * self.partially as f64) / total as f64
+    }
 }
 
 /// Intelligent ingest job record for tracking asynchronous ingestion
@@ -154,12 +407,188 @@ pub struct IngestJob {
     pub updated_at: chrono::DateTime<Utc>,
 }
 
+/// Minutes a running job may go without an update before it counts as stuck
+pub const STUCK_JOB_MINUTES: i64 = 60;
+
+impl IngestJob {
+    /// Whether the job is running but has not been updated for
+    /// [`STUCK_JOB_MINUTES`] at `now`
+    #[must_use]
+    pub fn is_stuck(&self, now: DateTime<Utc>) -> bool {
+        self.status == JobStatus::Running
+            && now - self.updated_at > chrono::Duration::minutes(STUCK_JOB_MINUTES)
+    }
+}
+
+/// Column crate listings can be sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrateSortField {
+    #[default]
+    Name,
+    LastUpdated,
+    TotalDocs,
+    TotalTokens,
+}
+
+impl CrateSortField {
+    /// Parse a `sort_by` argument; anything outside the whitelist is `None`
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "name" => Some(Self::Name),
+            "last_updated" => Some(Self::LastUpdated),
+            "total_docs" => Some(Self::TotalDocs),
+            "total_tokens" => Some(Self::TotalTokens),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::LastUpdated => "last_updated",
+            Self::TotalDocs => "total_docs",
+            Self::TotalTokens => "total_tokens",
+        }
+    }
+
+    /// Column of the per-crate `crate_stats` aggregate holding the sort key
+    #[must_use]
+    pub const fn sql_column(self) -> &'static str {
+        match self {
+            Self::Name => "crate_stats.crate_name",
+            Self::LastUpdated => "crate_stats.last_updated",
+            Self::TotalDocs => "crate_stats.total_docs",
+            Self::TotalTokens => "crate_stats.total_tokens",
+        }
+    }
+
+    /// SQL type a cursor's text key is cast to before comparing
+    #[must_use]
+    pub const fn sql_type(self) -> &'static str {
+        match self {
+            Self::Name => "text",
+            Self::LastUpdated => "timestamptz",
+            Self::TotalDocs | Self::TotalTokens => "numeric",
+        }
+    }
+
+    /// Text form of a crate's sort key, as stored in a cursor
+    #[must_use]
+    pub fn key_of(self, info: &CrateInfo) -> String {
+        match self {
+            Self::Name => info.name.clone(),
+            Self::LastUpdated => info
+                .last_updated
+                .to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+            Self::TotalDocs => info.total_docs.to_string(),
+            Self::TotalTokens => info.total_tokens.to_string(),
+        }
+    }
+}
+
+/// Sort direction for listings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    /// Parse a `sort_order` argument
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "asc" => Some(Self::Asc),
+            "desc" => Some(Self::Desc),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Asc => "asc",
+            Self::Desc => "desc",
+        }
+    }
+
+    #[must_use]
+    pub const fn sql_keyword(self) -> &'static str {
+        match self {
+            Self::Asc => "ASC",
+            Self::Desc => "DESC",
+        }
+    }
+}
+
+/// Keyset position after the last crate of a page
+///
+/// Carries the sort it was issued for, so a cursor cannot be replayed
+/// against a different ordering. Encoded as an opaque hex string.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateCursor {
+    pub sort_by: CrateSortField,
+    pub sort_order: SortOrder,
+    /// Sort key of the last crate, in [`CrateSortField::key_of`] form
+    pub key: String,
+    pub name: String,
+    pub version: String,
+}
+
+impl CrateCursor {
+    /// Cursor positioned after `info` in the given sort
+    #[must_use]
+    pub fn after(info: &CrateInfo, sort_by: CrateSortField, sort_order: SortOrder) -> Self {
+        Self {
+            sort_by,
+            sort_order,
+            key: sort_by.key_of(info),
+            name: info.name.clone(),
+            version: info.version.clone(),
+        }
+    }
+
+    /// Opaque string form handed to clients
+    #[must_use]
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        json.iter()
+            .fold(String::with_capacity(json.len() * 2), |mut out, byte| {
+                out.push_str(&format!("{byte:02x}"));
+                out
+            })
+    }
+
+    /// Decode a cursor from [`Self::encode`]; `None` if it is malformed
+    #[must_use]
+    pub fn decode(cursor: &str) -> Option<Self> {
+        if !cursor.len().is_multiple_of(2) {
+            return None;
+        }
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(cursor.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<u8>>>()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
 /// Pagination parameters for listing operations
+///
+/// With a `cursor`, listings continue after it and ignore `page`/`offset`.
 #[derive(Debug, Clone)]
 pub struct PaginationParams {
     pub page: i32,
     pub limit: i32,
     pub offset: i32,
+    pub sort_by: CrateSortField,
+    pub sort_order: SortOrder,
+    pub cursor: Option<CrateCursor>,
 }
 
 impl PaginationParams {
@@ -174,8 +603,30 @@ impl PaginationParams {
             page,
             limit,
             offset,
+            sort_by: CrateSortField::default(),
+            sort_order: SortOrder::default(),
+            cursor: None,
         }
     }
+
+    /// Sort by `sort_by` in `sort_order` instead of by name ascending
+    #[must_use]
+    pub const fn with_sort(mut self, sort_by: CrateSortField, sort_order: SortOrder) -> Self {
+        self.sort_by = sort_by;
+        self.sort_order = sort_order;
+        self
+    }
+
+    /// Continue after `cursor`, in the sort it was issued for
+    #[must_use]
+    pub fn with_cursor(mut self, cursor: CrateCursor) -> Self {
+        self.sort_by = cursor.sort_by;
+        self.sort_order = cursor.sort_order;
+        self.page = 1;
+        self.offset = 0;
+        self.cursor = Some(cursor);
+        self
+    }
 }
 
 /// Paginated response container
@@ -187,6 +638,9 @@ pub struct PaginatedResponse<T> {
     pub total_items: i64,
     pub has_previous: bool,
     pub has_next: bool,
+    /// Cursor for the page after this one, when there is one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 impl<T> PaginatedResponse<T> {
@@ -212,6 +666,7 @@ impl<T> PaginatedResponse<T> {
             total_items,
             has_previous,
             has_next,
+            next_cursor: None,
         }
     }
 }
@@ -225,16 +680,139 @@ pub struct CrateInfo {
     pub documentation_url: Option<String>,
     pub total_docs: i32,
     pub total_tokens: i64,
+    /// Number of documents that have an embedding stored
+    #[serde(default)]
+    pub embedded_docs: i64,
+    /// Percentage of documents with an embedding; semantic search only
+    /// finds those
+    #[serde(default)]
+    pub embedding_coverage_pct: f64,
     pub last_updated: DateTime<Utc>,
 }
 
+impl CrateInfo {
+    /// Whether coverage is below [`LOW_EMBEDDING_COVERAGE_PCT`], so the
+    /// crate needs `backfill_embeddings`
+    #[must_use]
+    pub fn has_low_embedding_coverage(&self) -> bool {
+        self.total_docs > 0 && self.embedding_coverage_pct < LOW_EMBEDDING_COVERAGE_PCT
+    }
+}
+
+/// Key under which crate names are compared: trimmed, lowercased, with `-` as `_`
+///
+/// crates.io treats `serde-json` and `serde_json` as one crate id, so a crate
+/// may be requested, or have been stored, under either spelling. The SQL in
+/// [`crate::queries`] folds `crate_name` metadata the same way, through
+/// [`CRATE_NAME_KEY_SQL`].
+#[must_use]
+pub fn crate_name_key(name: &str) -> String {
+    name.trim().to_lowercase().replace('-', "_")
+}
+
+/// SQL form of [`crate_name_key`] over `documents.metadata`
+///
+/// Migration `029_crate_name_key_index` indexes this exact expression, so
+/// queries must use it verbatim to avoid scanning `documents`.
+pub const CRATE_NAME_KEY_SQL: &str = "replace(lower(metadata->>'crate_name'), '-', '_')";
+
+/// SQL predicate selecting the documents of the crate named by placeholder `param`
+///
+/// Matches the source name exactly or the crate name by [`CRATE_NAME_KEY_SQL`].
+#[must_use]
+pub fn crate_name_match_sql(param: &str) -> String {
+    format!("(source_name = {param} OR {CRATE_NAME_KEY_SQL} = replace(lower({param}), '-', '_'))")
+}
+
+/// `LIKE` pattern for crate names whose [`crate_name_key`] contains `pattern`
+///
+/// `\`, `%` and `_` in the pattern match literally; compare with `ESCAPE '\'`.
+#[must_use]
+pub fn crate_name_like_pattern(pattern: &str) -> String {
+    let escaped = crate_name_key(pattern)
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+/// Embedding coverage below which crate listings flag a crate
+pub const LOW_EMBEDDING_COVERAGE_PCT: f64 = 90.0;
+
+/// Percentage of `total` documents that are embedded (0.0 without documents)
+#[must_use]
+pub fn embedding_coverage_pct(embedded: i64, total: i64) -> f64 {
+    if total > 0 {
+        #[allow(clippy::cast_precision_loss)]
+        {
+            (embedded as f64 / total as f64) * 100.0
+        }
+    } else {
+        0.0
+    }
+}
+
+/// An ingested crate that declares a dependency on another crate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct CrateDependent {
+    pub crate_name: String,
+    /// `None` when the dependency was only recorded on the document source
+    pub crate_version: Option<String>,
+    /// Version requirement on the depended-on crate
+    pub req: String,
+    /// `normal`, `dev` or `build`
+    pub kind: String,
+}
+
+/// Soft-delete status filter for crate listings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrateStatusFilter {
+    /// Only crates whose documents are not marked inactive
+    #[default]
+    Active,
+    /// Only soft-deleted crates
+    Inactive,
+    /// Both active and inactive crates
+    All,
+}
+
+impl CrateStatusFilter {
+    /// SQL predicate on `documents.metadata` selecting rows for this filter
+    #[must_use]
+    pub const fn sql_predicate(self) -> &'static str {
+        match self {
+            Self::Active => "COALESCE(metadata->>'status','active') <> 'inactive'",
+            Self::Inactive => "metadata->>'status' = 'inactive'",
+            Self::All => "TRUE",
+        }
+    }
+}
+
 /// Crate statistics for system monitoring
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CrateStatistics {
     pub total_crates: i64,
     pub active_crates: i64,
+    /// Active (crate, version) pairs; a crate kept in two versions counts twice
+    #[serde(default)]
+    pub total_versions: i64,
     pub total_docs_managed: i64,
     pub total_tokens_managed: i64,
+    /// Active documents that have an embedding stored
+    #[serde(default)]
+    pub docs_with_embeddings: i64,
+    /// Percentage of active documents with an embedding
+    #[serde(default)]
+    pub embedding_coverage_pct: f64,
     pub average_docs_per_crate: f64,
     pub last_update: Option<DateTime<Utc>>,
 }
+
+/// Validators and links remembered for a crawled docs.rs page
+#[derive(Debug, Clone, PartialEq, Eq, FromRow, Serialize, Deserialize)]
+pub struct FetchCacheEntry {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub links: Vec<String>,
+}
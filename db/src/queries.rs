@@ -4,10 +4,14 @@ use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::OnceLock;
 use std::time::{Duration, Instant};
-use tracing::{info, warn};
+use tracing::{info, info_span, warn, Instrument};
 
-use crate::models::{DocType, Document};
+use crate::models::{ChunkReference, DocType, Document, DocumentResource, ScoredDocument};
+use crate::retry::execute_with_retry;
 
 /// Metadata filters for document search
 #[derive(Debug, Clone, Default)]
@@ -17,6 +21,164 @@ pub struct MetadataFilters {
     pub category: Option<String>,
     pub topic: Option<String>,
     pub api_version: Option<String>,
+    pub crate_name: Option<String>,
+    pub crate_version: Option<String>,
+    pub package_name: Option<String>,
+    pub package_version: Option<String>,
+    pub item_type: Option<String>,
+    /// Only documents created at or after this time
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only documents created before this time
+    pub created_before: Option<DateTime<Utc>>,
+    /// Only documents updated at or after this time
+    pub updated_after: Option<DateTime<Utc>>,
+    /// Multiply ranks by `1 + recency_boost * 0.5^(age / 30 days)`, so newer
+    /// documents win near-ties
+    pub recency_boost: Option<f64>,
+    /// Alternative phrasings of the query, OR'd into the full-text match of
+    /// [`DocumentQueries::doc_type_search_scored`]; the `doc_path` pattern and
+    /// any embedding still use the query alone
+    pub expansions: Vec<String>,
+}
+
+/// Age at which a document's recency boost has halved
+const RECENCY_HALF_LIFE_DAYS: f64 = 30.0;
+
+/// Full-text search settings of a doc type
+///
+/// Read from the `fts_language`, `fts_title_weight` and `fts_content_weight`
+/// keys of its `document_sources.config`; the first source by name that
+/// sets a key wins. Path segments and `metadata->>'title'` are ranked at
+/// weight A with `title_weight`, content at weight B with `content_weight`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FtsSettings {
+    /// Text search configuration for documents and queries
+    pub language: String,
+    /// Rank weight of title matches, between 0.0 and 1.0
+    pub title_weight: f32,
+    /// Rank weight of content matches, between 0.0 and 1.0
+    pub content_weight: f32,
+}
+
+impl Default for FtsSettings {
+    fn default() -> Self {
+        Self {
+            language: "english".to_string(),
+            title_weight: 1.0,
+            content_weight: 0.4,
+        }
+    }
+}
+
+impl FtsSettings {
+    /// Settings from source configs ordered by source name
+    ///
+    /// The language is taken as given; [`DocumentSourceQueries::fts_settings`]
+    /// checks it against the server's configurations. Weights are clamped to
+    /// the range `ts_rank_cd` accepts.
+    #[must_use]
+    pub fn from_configs<'a>(configs: impl IntoIterator<Item = &'a serde_json::Value>) -> Self {
+        let mut settings = Self::default();
+        let (mut language, mut title, mut content) = (None, None, None);
+        for config in configs {
+            language = language.or_else(|| config.get("fts_language")?.as_str());
+            title = title.or_else(|| config.get("fts_title_weight")?.as_f64());
+            content = content.or_else(|| config.get("fts_content_weight")?.as_f64());
+        }
+        if let Some(language) = language {
+            settings.language = language.trim().to_ascii_lowercase();
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        if let Some(weight) = title {
+            settings.title_weight = weight.clamp(0.0, 1.0) as f32;
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        if let Some(weight) = content {
+            settings.content_weight = weight.clamp(0.0, 1.0) as f32;
+        }
+        settings
+    }
+
+    /// Path segments and title, the same expression in every query
+    fn title_vector(&self) -> String {
+        format!(
+            "to_tsvector('{}', regexp_replace(coalesce(doc_path,''), '[/._:#-]+', ' ', 'g') \
+             || ' ' || coalesce(metadata->>'title',''))",
+            self.language
+        )
+    }
+
+    /// Content vector; matches the `idx_documents_fts` index for `english`
+    fn content_vector(&self) -> String {
+        format!("to_tsvector('{}', coalesce(content,''))", self.language)
+    }
+
+    /// Statements creating the GIN indexes [`Self::match_sql`] uses for this
+    /// language; `english` is covered by migrations 011 and 030
+    fn index_statements(&self) -> [String; 2] {
+        [
+            format!(
+                "CREATE INDEX IF NOT EXISTS idx_documents_fts_{0} ON documents USING GIN ({1})",
+                self.language,
+                self.content_vector()
+            ),
+            format!(
+                "CREATE INDEX IF NOT EXISTS idx_documents_fts_title_{0} ON documents USING GIN ({1})",
+                self.language,
+                self.title_vector()
+            ),
+        ]
+    }
+
+    fn tsquery(&self, param: usize) -> String {
+        format!("websearch_to_tsquery('{}', ${param})", self.language)
+    }
+
+    /// The query bound at `$param` OR'd with the `count` phrasings of the
+    /// `text[]` bound at `$expansions`
+    fn expanded_tsquery(&self, param: usize, expansions: usize, count: usize) -> String {
+        let mut query = self.tsquery(param);
+        for i in 1..=count {
+            let _ = write!(
+                query,
+                " || websearch_to_tsquery('{}', (${expansions}::text[])[{i}])",
+                self.language
+            );
+        }
+        format!("({query})")
+    }
+
+    /// Predicate matching the tsquery expression `query` in title or content
+    fn match_sql(&self, query: &str) -> String {
+        format!(
+            "({} @@ {query} OR {} @@ {query})",
+            self.title_vector(),
+            self.content_vector()
+        )
+    }
+
+    /// Weighted `ts_rank_cd` of the tsquery expression `query`
+    fn rank_sql(&self, query: &str) -> String {
+        format!(
+            "ts_rank_cd('{{0, 0, {}, {}}}'::float4[], setweight({}, 'A') || setweight({}, 'B'), {})",
+            self.content_weight,
+            self.title_weight,
+            self.title_vector(),
+            self.content_vector(),
+            query
+        )
+    }
+}
+
+/// Documents split by comparison with their stored content hashes
+#[derive(Debug, Default)]
+pub struct ContentChanges {
+    /// Documents with no stored row
+    pub new: Vec<Document>,
+    /// Documents whose stored content differs
+    pub changed: Vec<Document>,
+    /// Number of documents whose stored content is identical
+    pub unchanged: usize,
 }
 
 /// Trait for types that can report how many rows they represent
@@ -48,6 +210,38 @@ impl RowCountable for String {
     }
 }
 
+/// Search predicate hiding documents whose `document_sources` row is disabled
+///
+/// Documents without a source row stay searchable.
+const ENABLED_SOURCE_FILTER: &str = "NOT EXISTS (SELECT 1 FROM document_sources ds \
+     WHERE ds.doc_type = documents.doc_type AND ds.source_name = documents.source_name \
+     AND ds.enabled = false)";
+
+/// Search settings of `doc_type`, or the defaults if they cannot be read
+async fn search_fts_settings(pool: &PgPool, doc_type: &str) -> FtsSettings {
+    DocumentSourceQueries::fts_settings(pool, doc_type)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Failed to read search settings for '{}': {}", doc_type, e);
+            FtsSettings::default()
+        })
+}
+
+/// Default number of rows written per statement by `batch_insert_documents`
+pub const DEFAULT_INSERT_CHUNK_SIZE: usize = 500;
+
+/// Rows per statement from `DOCUMENT_INSERT_CHUNK_SIZE`, read from the environment once
+fn insert_chunk_size() -> usize {
+    static CHUNK_SIZE: OnceLock<usize> = OnceLock::new();
+    *CHUNK_SIZE.get_or_init(|| {
+        std::env::var("DOCUMENT_INSERT_CHUNK_SIZE")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(DEFAULT_INSERT_CHUNK_SIZE)
+    })
+}
+
 /// Document query operations
 pub struct DocumentQueries;
 
@@ -62,23 +256,45 @@ impl DocumentQueries {
         doc_type: &str,
         source_name: &str,
     ) -> Result<()> {
-        sqlx::query(
-            r#"
-            INSERT INTO document_sources (doc_type, source_name, config, enabled)
-            VALUES ($1, $2, '{"auto_created": true}', true)
-            ON CONFLICT DO NOTHING
-            "#,
-        )
-        .bind(doc_type)
-        .bind(source_name)
-        .execute(pool)
+        execute_with_retry("ensure_document_source", || {
+            sqlx::query(
+                r#"
+                INSERT INTO document_sources (doc_type, source_name, config, enabled)
+                VALUES ($1, $2, '{"auto_created": true}', true)
+                ON CONFLICT DO NOTHING
+                "#,
+            )
+            .bind(doc_type)
+            .bind(source_name)
+            .execute(pool)
+        })
         .await?;
 
         Ok(())
     }
 
+    /// List distinct doc types that have at least one enabled document source
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn list_enabled_source_doc_types(pool: &PgPool) -> Result<Vec<String>> {
+        let doc_types = execute_with_retry("list_enabled_source_doc_types", || {
+            sqlx::query_scalar::<_, String>(
+                "SELECT DISTINCT doc_type FROM document_sources WHERE enabled ORDER BY doc_type",
+            )
+            .fetch_all(pool)
+        })
+        .await?;
+
+        Ok(doc_types)
+    }
+
     /// Insert a single document
     ///
+    /// Upserts on `(doc_type, source_name, doc_path)`; the stored embedding is
+    /// cleared only when the content changed.
+    ///
     /// # Errors
     ///
     /// Returns an error if the database insertion fails.
@@ -87,46 +303,52 @@ impl DocumentQueries {
         pool: &PgPool,
         document: &crate::models::Document,
     ) -> Result<crate::models::Document> {
-        let row = sqlx::query(
-            r"
-            INSERT INTO documents (
-                id,
-                doc_type,
-                source_name,
-                doc_path,
-                content,
-                metadata,
-                token_count,
-                created_at,
-                updated_at
+        let row = execute_with_retry("insert_document", || {
+            sqlx::query(
+                r"
+                INSERT INTO documents (
+                    id,
+                    doc_type,
+                    source_name,
+                    doc_path,
+                    content,
+                    metadata,
+                    token_count,
+                    created_at,
+                    updated_at
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)
+                ON CONFLICT (doc_type, source_name, doc_path) DO UPDATE SET
+                    content = EXCLUDED.content,
+                    metadata = EXCLUDED.metadata,
+                    token_count = EXCLUDED.token_count,
+                    updated_at = EXCLUDED.updated_at,
+                    embedding = CASE
+                        WHEN documents.content IS DISTINCT FROM EXCLUDED.content THEN NULL
+                        ELSE documents.embedding
+                    END
+                RETURNING
+                    id,
+                    doc_type,
+                    source_name,
+                    doc_path,
+                    content,
+                    metadata,
+                    token_count,
+                    created_at,
+                    updated_at
+                ",
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)
-            ON CONFLICT (id) DO UPDATE SET
-                content = EXCLUDED.content,
-                metadata = EXCLUDED.metadata,
-                token_count = EXCLUDED.token_count,
-                updated_at = EXCLUDED.updated_at
-            RETURNING
-                id,
-                doc_type,
-                source_name,
-                doc_path,
-                content,
-                metadata,
-                token_count,
-                created_at,
-                updated_at
-            ",
-        )
-        .bind(document.id)
-        .bind(&document.doc_type)
-        .bind(&document.source_name)
-        .bind(&document.doc_path)
-        .bind(&document.content)
-        .bind(&document.metadata)
-        .bind(document.token_count)
-        .bind(document.created_at.unwrap_or_else(chrono::Utc::now))
-        .fetch_one(pool)
+            .bind(document.id)
+            .bind(&document.doc_type)
+            .bind(&document.source_name)
+            .bind(&document.doc_path)
+            .bind(&document.content)
+            .bind(&document.metadata)
+            .bind(document.token_count)
+            .bind(document.created_at.unwrap_or_else(chrono::Utc::now))
+            .fetch_one(pool)
+        })
         .await?;
 
         let doc = crate::models::Document {
@@ -147,33 +369,84 @@ impl DocumentQueries {
 
     /// Batch insert multiple documents with transaction support
     ///
+    /// Documents are written in chunks of `DOCUMENT_INSERT_CHUNK_SIZE` rows
+    /// (default 500, read once per process), each chunk as a single multi-row
+    /// `INSERT ... UNNEST`.
+    ///
     /// # Errors
     ///
     /// Returns an error if the database batch insertion fails.
-    #[allow(clippy::too_many_lines)]
     pub async fn batch_insert_documents(
         pool: &PgPool,
         documents: &[crate::models::Document],
+    ) -> Result<Vec<crate::models::Document>> {
+        Self::batch_insert_documents_chunked(pool, documents, insert_chunk_size()).await
+    }
+
+    /// Batch insert multiple documents using an explicit chunk size
+    ///
+    /// All chunks are written inside one transaction. Each chunk binds one
+    /// array per column, so the statement always uses eight parameters
+    /// regardless of how many rows it carries. Documents sharing a
+    /// `(doc_type, source_name, doc_path)` key within the input are collapsed
+    /// to the last occurrence, matching what sequential upserts would have
+    /// produced. Conflicting rows keep their existing `id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database batch insertion fails.
+    pub async fn batch_insert_documents_chunked(
+        pool: &PgPool,
+        documents: &[crate::models::Document],
+        chunk_size: usize,
     ) -> Result<Vec<crate::models::Document>> {
         if documents.is_empty() {
             return Ok(Vec::new());
         }
 
-        // Ensure document sources exist for all documents
-        let mut sources_to_create = std::collections::HashSet::new();
-        for doc in documents {
-            sources_to_create.insert((doc.doc_type.clone(), doc.source_name.clone()));
-        }
+        Self::ensure_sources_of(pool, documents).await?;
+        let unique_docs = Self::last_of_each_key(documents);
 
-        for (doc_type, source_name) in sources_to_create {
-            Self::ensure_document_source(pool, doc_type.as_str(), &source_name).await?;
-        }
+        // A dropped connection rolls the transaction back, so replay all of it
+        execute_with_retry("batch_insert_documents", || {
+            Self::upsert_chunks(pool, &unique_docs, chunk_size)
+        })
+        .await
+    }
 
+    /// Upsert `unique_docs` in chunks within one transaction
+    async fn upsert_chunks(
+        pool: &PgPool,
+        unique_docs: &[&Document],
+        chunk_size: usize,
+    ) -> Result<Vec<Document>> {
+        let doc_key = Self::upsert_key;
         let mut transaction = pool.begin().await?;
-        let mut inserted_docs = Vec::new();
+        let mut inserted_docs = Vec::with_capacity(unique_docs.len());
+        let now = Utc::now();
 
-        for doc in documents {
-            let row = sqlx::query(
+        for chunk in unique_docs.chunks(chunk_size.max(1)) {
+            let mut ids = Vec::with_capacity(chunk.len());
+            let mut doc_types = Vec::with_capacity(chunk.len());
+            let mut source_names = Vec::with_capacity(chunk.len());
+            let mut doc_paths = Vec::with_capacity(chunk.len());
+            let mut contents = Vec::with_capacity(chunk.len());
+            let mut metadatas = Vec::with_capacity(chunk.len());
+            let mut token_counts: Vec<Option<i32>> = Vec::with_capacity(chunk.len());
+            let mut created_ats = Vec::with_capacity(chunk.len());
+
+            for doc in chunk {
+                ids.push(doc.id);
+                doc_types.push(doc.doc_type.as_str());
+                source_names.push(doc.source_name.as_str());
+                doc_paths.push(doc.doc_path.as_str());
+                contents.push(doc.content.as_str());
+                metadatas.push(&doc.metadata);
+                token_counts.push(doc.token_count);
+                created_ats.push(doc.created_at.unwrap_or(now));
+            }
+
+            let rows = sqlx::query(
                 r"
                 INSERT INTO documents (
                     id,
@@ -186,13 +459,26 @@ impl DocumentQueries {
                     created_at,
                     updated_at
                 )
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8)
-                ON CONFLICT (id) DO UPDATE SET
+                SELECT id, doc_type, source_name, doc_path, content, metadata, token_count, created_at, created_at
+                FROM UNNEST(
+                    $1::uuid[],
+                    $2::text[],
+                    $3::text[],
+                    $4::text[],
+                    $5::text[],
+                    $6::jsonb[],
+                    $7::int4[],
+                    $8::timestamptz[]
+                ) AS t(id, doc_type, source_name, doc_path, content, metadata, token_count, created_at)
+                ON CONFLICT (doc_type, source_name, doc_path) DO UPDATE SET
                     content = EXCLUDED.content,
                     metadata = EXCLUDED.metadata,
                     token_count = EXCLUDED.token_count,
-                    updated_at = EXCLUDED.updated_at
-
+                    updated_at = EXCLUDED.updated_at,
+                    embedding = CASE
+                        WHEN documents.content IS DISTINCT FROM EXCLUDED.content THEN NULL
+                        ELSE documents.embedding
+                    END
                 RETURNING
                     id,
                     doc_type,
@@ -205,46 +491,269 @@ impl DocumentQueries {
                     updated_at
                 ",
             )
-            .bind(doc.id)
-            .bind(&doc.doc_type)
-            .bind(&doc.source_name)
-            .bind(&doc.doc_path)
-            .bind(&doc.content)
-            .bind(&doc.metadata)
-            .bind(doc.token_count)
-            .bind(doc.created_at.unwrap_or_else(chrono::Utc::now))
-            .fetch_one(&mut *transaction)
+            .bind(&ids)
+            .bind(&doc_types)
+            .bind(&source_names)
+            .bind(&doc_paths)
+            .bind(&contents)
+            .bind(&metadatas)
+            .bind(&token_counts)
+            .bind(&created_ats)
+            .fetch_all(&mut *transaction)
             .await?;
 
-            let inserted_doc = crate::models::Document {
+            // RETURNING order is not guaranteed, so restore input order by key
+            let mut by_key: std::collections::HashMap<_, crate::models::Document> = rows
+                .into_iter()
+                .map(|row| {
+                    let doc = crate::models::Document {
+                        id: row.get("id"),
+                        doc_type: row.get("doc_type"),
+                        source_name: row.get("source_name"),
+                        doc_path: row.get("doc_path"),
+                        content: row.get("content"),
+                        metadata: row.get("metadata"),
+                        embedding: None, // Skip embedding for now
+                        token_count: row.get("token_count"),
+                        created_at: row.get("created_at"),
+                        updated_at: row.get("updated_at"),
+                    };
+                    (doc_key(&doc), doc)
+                })
+                .collect();
+
+            inserted_docs.extend(chunk.iter().filter_map(|doc| by_key.remove(&doc_key(doc))));
+        }
+
+        transaction.commit().await?;
+        Ok(inserted_docs)
+    }
+
+    /// Ensure document sources exist for all documents
+    async fn ensure_sources_of(pool: &PgPool, documents: &[Document]) -> Result<()> {
+        let mut sources_to_create = std::collections::HashSet::new();
+        for doc in documents {
+            sources_to_create.insert((doc.doc_type.as_str(), doc.source_name.as_str()));
+        }
+
+        for (doc_type, source_name) in sources_to_create {
+            Self::ensure_document_source(pool, doc_type, source_name).await?;
+        }
+        Ok(())
+    }
+
+    /// Key the batch upserts conflict on
+    fn upsert_key(doc: &Document) -> (String, String, String) {
+        (
+            doc.doc_type.clone(),
+            doc.source_name.clone(),
+            doc.doc_path.clone(),
+        )
+    }
+
+    /// The last version of every upsert key, in input order
+    ///
+    /// A single statement cannot upsert the same row twice.
+    fn last_of_each_key(documents: &[Document]) -> Vec<&Document> {
+        let mut last_index = HashMap::with_capacity(documents.len());
+        for (idx, doc) in documents.iter().enumerate() {
+            last_index.insert(Self::upsert_key(doc), idx);
+        }
+        documents
+            .iter()
+            .enumerate()
+            .filter(|(idx, doc)| last_index.get(&Self::upsert_key(doc)) == Some(idx))
+            .map(|(_, doc)| doc)
+            .collect()
+    }
+
+    /// Upsert exported documents exactly as they were exported
+    ///
+    /// Unlike [`Self::batch_insert_documents`], rows keep the document's `id`,
+    /// `created_at` and `updated_at`, and its embedding when it has one. A
+    /// document without an embedding keeps the stored embedding if its
+    /// content is unchanged. Returns the number of rows written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database upsert fails, for example because an
+    /// embedding does not match the embedding column's dimensions.
+    pub async fn restore_documents(pool: &PgPool, documents: &[Document]) -> Result<u64> {
+        if documents.is_empty() {
+            return Ok(0);
+        }
+
+        Self::ensure_sources_of(pool, documents).await?;
+        let unique_docs = Self::last_of_each_key(documents);
+        let now = Utc::now();
+
+        let mut ids = Vec::with_capacity(unique_docs.len());
+        let mut doc_types = Vec::with_capacity(unique_docs.len());
+        let mut source_names = Vec::with_capacity(unique_docs.len());
+        let mut doc_paths = Vec::with_capacity(unique_docs.len());
+        let mut contents = Vec::with_capacity(unique_docs.len());
+        let mut metadatas = Vec::with_capacity(unique_docs.len());
+        let mut token_counts: Vec<Option<i32>> = Vec::with_capacity(unique_docs.len());
+        let mut created_ats = Vec::with_capacity(unique_docs.len());
+        let mut updated_ats = Vec::with_capacity(unique_docs.len());
+        let mut embeddings: Vec<Option<pgvector::Vector>> = Vec::with_capacity(unique_docs.len());
+
+        for doc in unique_docs {
+            ids.push(doc.id);
+            doc_types.push(doc.doc_type.as_str());
+            source_names.push(doc.source_name.as_str());
+            doc_paths.push(doc.doc_path.as_str());
+            contents.push(doc.content.as_str());
+            metadatas.push(&doc.metadata);
+            token_counts.push(doc.token_count);
+            created_ats.push(doc.created_at.unwrap_or(now));
+            updated_ats.push(doc.updated_at.or(doc.created_at).unwrap_or(now));
+            embeddings.push(doc.embedding.clone());
+        }
+
+        // Batches without embeddings leave the column out, so they also load
+        // into databases without the vector extension
+        let with_embeddings = embeddings.iter().any(Option::is_some);
+        let (embedding_column, embedding_array, embedding_update) = if with_embeddings {
+            (
+                ", embedding",
+                ", $10::vector[]",
+                "WHEN EXCLUDED.embedding IS NOT NULL THEN EXCLUDED.embedding",
+            )
+        } else {
+            ("", "", "")
+        };
+        let sql = format!(
+            r"
+            INSERT INTO documents (
+                id, doc_type, source_name, doc_path, content, metadata,
+                token_count, created_at, updated_at{embedding_column}
+            )
+            SELECT * FROM UNNEST(
+                $1::uuid[],
+                $2::text[],
+                $3::text[],
+                $4::text[],
+                $5::text[],
+                $6::jsonb[],
+                $7::int4[],
+                $8::timestamptz[],
+                $9::timestamptz[]{embedding_array}
+            )
+            ON CONFLICT (doc_type, source_name, doc_path) DO UPDATE SET
+                id = EXCLUDED.id,
+                content = EXCLUDED.content,
+                metadata = EXCLUDED.metadata,
+                token_count = EXCLUDED.token_count,
+                created_at = EXCLUDED.created_at,
+                updated_at = EXCLUDED.updated_at,
+                embedding = CASE
+                    {embedding_update}
+                    WHEN documents.content IS DISTINCT FROM EXCLUDED.content THEN NULL
+                    ELSE documents.embedding
+                END
+            "
+        );
+
+        let mut query = sqlx::query(&sql)
+            .bind(&ids)
+            .bind(&doc_types)
+            .bind(&source_names)
+            .bind(&doc_paths)
+            .bind(&contents)
+            .bind(&metadatas)
+            .bind(&token_counts)
+            .bind(&created_ats)
+            .bind(&updated_ats);
+        if with_embeddings {
+            query = query.bind(&embeddings);
+        }
+        let result = query.execute(pool).await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// One page of documents with their embeddings, for export
+    ///
+    /// Pages through documents by ID after `cursor`, optionally only those of
+    /// `doc_type` and `source_name`. Returns up to `limit` documents and the
+    /// cursor for the next page, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn export_page(
+        pool: &PgPool,
+        doc_type: Option<&str>,
+        source_name: Option<&str>,
+        cursor: Option<uuid::Uuid>,
+        limit: i64,
+    ) -> Result<(Vec<Document>, Option<uuid::Uuid>)> {
+        let limit = limit.max(1);
+        let rows = sqlx::query(
+            r"
+            SELECT id, doc_type, source_name, doc_path, content, metadata,
+                   embedding, token_count, created_at, updated_at
+            FROM documents
+            WHERE ($1::text IS NULL OR doc_type = $1)
+              AND ($2::text IS NULL OR source_name = $2)
+              AND ($3::uuid IS NULL OR id > $3)
+            ORDER BY id
+            LIMIT $4
+            ",
+        )
+        .bind(doc_type)
+        .bind(source_name)
+        .bind(cursor)
+        .bind(limit + 1)
+        .fetch_all(pool)
+        .await?;
+
+        let mut docs: Vec<Document> = rows
+            .into_iter()
+            .map(|row| Document {
                 id: row.get("id"),
                 doc_type: row.get("doc_type"),
                 source_name: row.get("source_name"),
                 doc_path: row.get("doc_path"),
                 content: row.get("content"),
                 metadata: row.get("metadata"),
-                embedding: None, // Skip embedding for now
+                embedding: row.get("embedding"),
                 token_count: row.get("token_count"),
                 created_at: row.get("created_at"),
                 updated_at: row.get("updated_at"),
-            };
+            })
+            .collect();
 
-            inserted_docs.push(inserted_doc);
+        let has_more = docs.len() > usize::try_from(limit).unwrap_or(usize::MAX);
+        if has_more {
+            docs.pop();
         }
+        let next_cursor = if has_more {
+            docs.last().map(|d| d.id)
+        } else {
+            None
+        };
 
-        transaction.commit().await?;
-        Ok(inserted_docs)
+        Ok((docs, next_cursor))
     }
 
-    /// Delete documents by source name
+    /// Delete the documents of one source
+    ///
+    /// Accepts a pool or an open transaction.
     ///
     /// # Errors
     ///
     /// Returns an error if the database deletion fails.
-    pub async fn delete_by_source(pool: &PgPool, source_name: &str) -> Result<i64> {
-        let result = sqlx::query("DELETE FROM documents WHERE source_name = $1")
+    pub async fn delete_by_source<'e>(
+        executor: impl sqlx::PgExecutor<'e>,
+        doc_type: &str,
+        source_name: &str,
+    ) -> Result<i64> {
+        let result = sqlx::query("DELETE FROM documents WHERE doc_type = $1 AND source_name = $2")
+            .bind(doc_type)
             .bind(source_name)
-            .execute(pool)
+            .execute(executor)
             .await?;
 
         Ok(result.rows_affected().try_into().unwrap_or(i64::MAX))
@@ -268,27 +777,29 @@ impl DocumentQueries {
     /// Returns an error if the database query fails or the result rows cannot
     /// be deserialized into `Document` values.
     pub async fn find_by_type_str(pool: &PgPool, doc_type: &str) -> Result<Vec<Document>> {
-        let rows = sqlx::query(
-            r"
-            SELECT 
-                id,
-                doc_type,
-                source_name,
-                doc_path,
-                content,
-                metadata,
-                token_count,
-                created_at,
-                updated_at
-            FROM documents
-            WHERE doc_type = $1
-            ORDER BY created_at DESC
-            ",
-        )
-        .bind(doc_type)
-        .fetch_all(pool)
-        .await?;
-
+        let rows = execute_with_retry("find_by_type_str", || {
+            sqlx::query(
+                r"
+                SELECT 
+                    id,
+                    doc_type,
+                    source_name,
+                    doc_path,
+                    content,
+                    metadata,
+                    token_count,
+                    created_at,
+                    updated_at
+                FROM documents
+                WHERE doc_type = $1
+                ORDER BY created_at DESC
+                ",
+            )
+            .bind(doc_type)
+            .fetch_all(pool)
+        })
+        .await?;
+
         let docs = rows
             .into_iter()
             .map(|row| {
@@ -317,25 +828,27 @@ impl DocumentQueries {
     /// Returns an error if the database query fails or the results cannot be
     /// mapped into `Document` values.
     pub async fn find_by_source(pool: &PgPool, source_name: &str) -> Result<Vec<Document>> {
-        let rows = sqlx::query(
-            r"
-            SELECT 
-                id,
-                doc_type,
-                source_name,
-                doc_path,
-                content,
-                metadata,
-                token_count,
-                created_at,
-                updated_at
-            FROM documents 
-            WHERE source_name = $1
-            ORDER BY created_at DESC
-            ",
-        )
-        .bind(source_name)
-        .fetch_all(pool)
+        let rows = execute_with_retry("find_by_source", || {
+            sqlx::query(
+                r"
+                SELECT 
+                    id,
+                    doc_type,
+                    source_name,
+                    doc_path,
+                    content,
+                    metadata,
+                    token_count,
+                    created_at,
+                    updated_at
+                FROM documents 
+                WHERE source_name = $1
+                ORDER BY created_at DESC
+                ",
+            )
+            .bind(source_name)
+            .fetch_all(pool)
+        })
         .await?;
 
         let docs = rows
@@ -359,189 +872,528 @@ impl DocumentQueries {
         Ok(docs)
     }
 
-    /// Perform vector similarity search
+    /// List document descriptors in stable order using keyset pagination
+    ///
+    /// Soft-deleted crates and disabled sources are left out, as in search.
+    /// Returns up to `limit` descriptors after `cursor` (the ID of the last
+    /// descriptor of the previous page) and the cursor for the next page, if any.
     ///
     /// # Errors
     ///
     /// Returns an error if the database query fails.
-    pub async fn vector_search(
+    pub async fn list_resources(
         pool: &PgPool,
-        _embedding: &[f32],
+        cursor: Option<uuid::Uuid>,
         limit: i64,
-    ) -> Result<Vec<Document>> {
-        // For now, return a basic text search as fallback
-        let rows = sqlx::query(
+    ) -> Result<(Vec<DocumentResource>, Option<uuid::Uuid>)> {
+        let limit = limit.max(1);
+        let sql = format!(
             r"
-            SELECT 
-                id,
-                doc_type,
-                source_name,
-                doc_path,
-                content,
-                metadata,
-                token_count,
-                created_at,
-                updated_at
-            FROM documents 
-            WHERE content IS NOT NULL
-            ORDER BY created_at DESC
-            LIMIT $1
+            SELECT id, doc_type, source_name, doc_path, token_count,
+                   char_length(content) AS content_length
+            FROM documents
+            WHERE ($1::uuid IS NULL OR id > $1)
+              AND {}
+              AND {ENABLED_SOURCE_FILTER}
+            ORDER BY id
+            LIMIT $2
             ",
-        )
-        .bind(limit)
-        .fetch_all(pool)
+            crate::models::CrateStatusFilter::Active.sql_predicate()
+        );
+        let mut resources = execute_with_retry("list_resources", || {
+            sqlx::query_as::<_, DocumentResource>(&sql)
+                .bind(cursor)
+                .bind(limit + 1)
+                .fetch_all(pool)
+        })
         .await?;
 
-        let docs = rows
-            .into_iter()
-            .map(|row| {
-                Document {
-                    id: row.get("id"),
-                    doc_type: row.get("doc_type"),
-                    source_name: row.get("source_name"),
-                    doc_path: row.get("doc_path"),
-                    content: row.get("content"),
-                    metadata: row.get("metadata"),
-                    embedding: None, // Skip embedding for now
-                    token_count: row.get("token_count"),
-                    created_at: row.get("created_at"),
-                    updated_at: row.get("updated_at"),
-                }
-            })
-            .collect();
+        let has_more = resources.len() > usize::try_from(limit).unwrap_or(usize::MAX);
+        if has_more {
+            resources.pop();
+        }
+        let next_cursor = if has_more {
+            resources.last().map(|r| r.id)
+        } else {
+            None
+        };
 
-        Ok(docs)
+        Ok((resources, next_cursor))
     }
 
-    /// Perform vector similarity search for Rust documents only
+    /// Find a single document by its ID
+    ///
+    /// Documents of soft-deleted crates and disabled sources are not found.
     ///
     /// # Errors
     ///
     /// Returns an error if the database query fails.
-    pub async fn rust_vector_search(
-        pool: &PgPool,
-        query: &str,
-        _embedding: &[f32],
-        limit: i64,
-    ) -> Result<Vec<Document>> {
-        // Perform full-text search on Rust documents with relevance ranking
-        // Try full-text search first, fallback to tokenized ILIKE if FTS not available
-        let fts_sql = r"
-            SELECT
-                id,
-                doc_type,
-                source_name,
-                doc_path,
-                content,
-                metadata,
-                token_count,
-                created_at,
-                updated_at,
-                ts_rank_cd(
-                    to_tsvector('english', coalesce(content,'')),
-                    websearch_to_tsquery('english', $1)
-                ) AS rank
+    pub async fn find_by_id(pool: &PgPool, id: uuid::Uuid) -> Result<Option<Document>> {
+        let sql = format!(
+            r"
+            SELECT id, doc_type, source_name, doc_path, content, metadata,
+                   token_count, created_at, updated_at
             FROM documents
-            WHERE doc_type = 'rust'
-              AND (
-                    to_tsvector('english', coalesce(content,'')) @@ websearch_to_tsquery('english', $1)
-                 OR doc_path ILIKE $2
-                 OR content ILIKE $2
-              )
-            ORDER BY
-              rank DESC,
-              created_at DESC
-            LIMIT $3
-        ";
-
-        let fts_attempt = sqlx::query(fts_sql)
-            .bind(query)
-            .bind(format!("%{query}%"))
-            .bind(limit)
-            .fetch_all(pool)
-            .await;
-
-        let rows = if let Ok(rows) = fts_attempt {
-            rows
-        } else {
-            // Fallback: tokenized ILIKE requiring all significant tokens
-            let tokens: Vec<String> = query
-                .split_whitespace()
-                .map(|t| t.trim_matches(|c: char| !c.is_alphanumeric()))
-                .filter(|t| t.len() >= 3)
-                .map(|t| format!("%{t}%"))
-                .collect();
-
-            let mut where_parts = vec!["doc_type = $1".to_string()];
-            let mut binds: Vec<String> = Vec::new();
-            let mut bind_index = 2;
-            for _tok in &tokens {
-                where_parts.push(format!(
-                    "(content ILIKE ${bind_index} OR doc_path ILIKE ${bind_index})"
-                ));
-                bind_index += 1;
-            }
-            // Re-add binds in order matching the placeholders
-            binds.extend(tokens.clone());
+            WHERE id = $1
+              AND {}
+              AND {ENABLED_SOURCE_FILTER}
+            ",
+            crate::models::CrateStatusFilter::Active.sql_predicate()
+        );
+        let row = execute_with_retry("find_by_id", || {
+            sqlx::query(&sql).bind(id).fetch_optional(pool)
+        })
+        .await?;
 
-            // If no tokens, fall back to simple ILIKE of full query
-            if tokens.is_empty() {
-                where_parts.push("(content ILIKE $2 OR doc_path ILIKE $2)".to_string());
-                binds.push(format!("%{query}%"));
-                bind_index = 3;
-            }
+        Ok(row.map(|row| Document {
+            id: row.get("id"),
+            doc_type: row.get("doc_type"),
+            source_name: row.get("source_name"),
+            doc_path: row.get("doc_path"),
+            content: row.get("content"),
+            metadata: row.get("metadata"),
+            embedding: None,
+            token_count: row.get("token_count"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        }))
+    }
 
-            let sql = format!(
-                    "SELECT id, doc_type, source_name, doc_path, content, metadata, token_count, created_at, updated_at \
-                     FROM documents WHERE {} ORDER BY created_at DESC LIMIT ${}",
-                    where_parts.join(" AND "),
-                    bind_index
-                );
+    /// Find a single document by its natural key
+    ///
+    /// Documents of soft-deleted crates and disabled sources are not found.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_by_path(
+        pool: &PgPool,
+        doc_type: &str,
+        source_name: &str,
+        doc_path: &str,
+    ) -> Result<Option<Document>> {
+        let sql = format!(
+            r"
+            SELECT id, doc_type, source_name, doc_path, content, metadata,
+                   token_count, created_at, updated_at
+            FROM documents
+            WHERE doc_type = $1 AND source_name = $2 AND doc_path = $3
+              AND {}
+              AND {ENABLED_SOURCE_FILTER}
+            ",
+            crate::models::CrateStatusFilter::Active.sql_predicate()
+        );
+        let row = execute_with_retry("find_by_path", || {
+            sqlx::query(&sql)
+                .bind(doc_type)
+                .bind(source_name)
+                .bind(doc_path)
+                .fetch_optional(pool)
+        })
+        .await?;
 
-            let mut q = sqlx::query(&sql).bind("rust");
-            for b in &binds {
-                q = q.bind(b);
-            }
-            q = q.bind(limit);
-            q.fetch_all(pool).await?
-        };
+        Ok(row.map(|row| Document {
+            id: row.get("id"),
+            doc_type: row.get("doc_type"),
+            source_name: row.get("source_name"),
+            doc_path: row.get("doc_path"),
+            content: row.get("content"),
+            metadata: row.get("metadata"),
+            embedding: None,
+            token_count: row.get("token_count"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        }))
+    }
 
-        let docs = rows
-            .into_iter()
-            .map(|row| {
-                Document {
-                    id: row.get("id"),
-                    doc_type: row.get("doc_type"),
-                    source_name: row.get("source_name"),
-                    doc_path: row.get("doc_path"),
-                    content: row.get("content"),
-                    metadata: row.get("metadata"),
-                    embedding: None, // Skip embedding for now
-                    token_count: row.get("token_count"),
-                    created_at: row.get("created_at"),
-                    updated_at: row.get("updated_at"),
-                }
-            })
-            .collect();
+    /// All chunks cut from `parent_doc_path`, in chunk order
+    ///
+    /// See [`crate::chunks`] for the chunk metadata layout. Like
+    /// [`Self::find_by_path`], chunks of soft-deleted crates and disabled
+    /// sources are left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_chunks(
+        pool: &PgPool,
+        doc_type: &str,
+        source_name: &str,
+        parent_doc_path: &str,
+    ) -> Result<Vec<ChunkReference>> {
+        let sql = format!(
+            r"
+            SELECT id, doc_path, (metadata->>'chunk_index')::int AS chunk_index, token_count
+            FROM documents
+            WHERE doc_type = $1 AND source_name = $2
+              AND metadata->>'parent_doc_path' = $3
+              AND metadata->>'chunk_index' ~ '^[0-9]+$'
+              AND {}
+              AND {ENABLED_SOURCE_FILTER}
+            ORDER BY chunk_index, doc_path
+            ",
+            crate::models::CrateStatusFilter::Active.sql_predicate()
+        );
+        let chunks = execute_with_retry("find_chunks", || {
+            sqlx::query_as::<_, ChunkReference>(&sql)
+                .bind(doc_type)
+                .bind(source_name)
+                .bind(parent_doc_path)
+                .fetch_all(pool)
+        })
+        .await?;
 
-        Ok(docs)
+        Ok(chunks)
     }
 
-    /// Perform vector similarity search for documents of a specific type
+    /// Stored content hashes for `doc_paths` of one source, keyed by path
+    ///
+    /// Rows written before `content_hash` was recorded are hashed in SQL, so
+    /// they compare equal when their content is unchanged.
     ///
     /// # Errors
     ///
     /// Returns an error if the database query fails.
-    pub async fn doc_type_vector_search(
+    pub async fn stored_content_hashes(
         pool: &PgPool,
         doc_type: &str,
-        query: &str,
-        _embedding: &[f32],
-        limit: i64,
-    ) -> Result<Vec<Document>> {
-        // Attempt full-text search first (uses built-in FTS, no extension required)
-        // Fallback to tokenized ILIKE if FTS functions are unavailable
-        let fts_sql = r"
+        source_name: &str,
+        doc_paths: &[String],
+    ) -> Result<HashMap<String, String>> {
+        if doc_paths.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let rows: Vec<(String, String)> = execute_with_retry("stored_content_hashes", || {
+            sqlx::query_as(
+                r"
+                SELECT doc_path,
+                       COALESCE(metadata->>'content_hash',
+                                encode(sha256(convert_to(content, 'UTF8')), 'hex'))
+                FROM documents
+                WHERE doc_type = $1 AND source_name = $2 AND doc_path = ANY($3)
+                ",
+            )
+            .bind(doc_type)
+            .bind(source_name)
+            .bind(doc_paths)
+            .fetch_all(pool)
+        })
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Split `documents` into new, changed and unchanged by content hash
+    ///
+    /// Each returned document has `content_hash` recorded in its metadata.
+    /// Stored hashes are fetched with one query per `(doc_type, source_name)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn partition_by_content_hash(
+        pool: &PgPool,
+        documents: Vec<Document>,
+    ) -> Result<ContentChanges> {
+        let mut groups: HashMap<(String, String), Vec<Document>> = HashMap::new();
+        for mut doc in documents {
+            crate::metadata::annotate_content_hash(&mut doc.metadata, &doc.content);
+            groups
+                .entry((doc.doc_type.clone(), doc.source_name.clone()))
+                .or_default()
+                .push(doc);
+        }
+
+        let mut changes = ContentChanges::default();
+        for ((doc_type, source_name), docs) in groups {
+            let paths: Vec<String> = docs.iter().map(|d| d.doc_path.clone()).collect();
+            let stored = Self::stored_content_hashes(pool, &doc_type, &source_name, &paths).await?;
+
+            for doc in docs {
+                match stored.get(&doc.doc_path) {
+                    None => changes.new.push(doc),
+                    Some(hash) if *hash == crate::metadata::content_hash(&doc.content) => {
+                        changes.unchanged += 1;
+                    }
+                    Some(_) => changes.changed.push(doc),
+                }
+            }
+        }
+
+        Ok(changes)
+    }
+
+    /// Declared dimension of the `documents.embedding` column
+    ///
+    /// Returns `None` when the column is missing, is not a pgvector type, or
+    /// is declared without a dimension.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn embedding_column_dimensions(pool: &PgPool) -> Result<Option<i32>> {
+        let column_type: Option<String> = sqlx::query_scalar(
+            r"
+            SELECT format_type(a.atttypid, a.atttypmod)
+            FROM pg_attribute a
+            WHERE a.attrelid = to_regclass('documents')
+              AND a.attname = 'embedding'
+              AND NOT a.attisdropped
+            ",
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(column_type.as_deref().and_then(|t| {
+            t.strip_prefix("vector(")
+                .and_then(|rest| rest.strip_suffix(')'))
+                .and_then(|n| n.parse().ok())
+        }))
+    }
+
+    /// Count the documents stored for `doc_type`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn count_by_type(pool: &PgPool, doc_type: &str) -> Result<i64> {
+        let count = execute_with_retry("count_by_type", || {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM documents WHERE doc_type = $1")
+                .bind(doc_type)
+                .fetch_one(pool)
+        })
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Count stored embeddings grouped by the model recorded in metadata
+    ///
+    /// Documents embedded before the model was recorded are grouped under `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn count_embeddings_by_model(pool: &PgPool) -> Result<Vec<(Option<String>, i64)>> {
+        let rows = sqlx::query(
+            r"
+            SELECT metadata->>'embedding_model' AS model, COUNT(*) AS count
+            FROM documents
+            WHERE embedding IS NOT NULL
+            GROUP BY 1
+            ORDER BY 2 DESC
+            ",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.get("model"), row.get("count")))
+            .collect())
+    }
+
+    /// Recreate the `documents.embedding` column as `vector(dimensions)`
+    ///
+    /// Existing embeddings cannot be converted between dimensions, so they are
+    /// cleared along with their recorded model; the documents themselves are
+    /// kept and can be re-embedded with `backfill_embeddings`. Returns the
+    /// number of embeddings that were cleared.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `dimensions` is not positive or the schema change fails.
+    pub async fn recreate_embedding_column(pool: &PgPool, dimensions: i32) -> Result<u64> {
+        if dimensions <= 0 {
+            return Err(anyhow::anyhow!(
+                "Embedding dimensions must be positive, got {dimensions}"
+            ));
+        }
+
+        let mut tx = pool.begin().await?;
+
+        let cleared = sqlx::query(
+            r"
+            UPDATE documents
+            SET embedding = NULL,
+                metadata = metadata - 'embedding_model' - 'embedding_dimensions'
+            WHERE embedding IS NOT NULL
+            ",
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        // The type modifier cannot be bound as a parameter
+        sqlx::query(&format!(
+            "ALTER TABLE documents ALTER COLUMN embedding TYPE vector({dimensions}) USING NULL"
+        ))
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        info!(
+            "Recreated documents.embedding as vector({}), cleared {} embeddings",
+            dimensions, cleared
+        );
+        Ok(cleared)
+    }
+
+    /// Rewrite document ids to their stable UUIDv5 form
+    ///
+    /// Ids are computed as [`crate::document_ids::stable_document_id`] does
+    /// for new documents, and `search_feedback.document_id` references follow
+    /// in the same transaction. With `dry_run` nothing is written. Returns the
+    /// number of documents examined and the number whose id differs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the documents cannot be read or updated.
+    pub async fn rewrite_document_ids(pool: &PgPool, dry_run: bool) -> Result<(u64, u64)> {
+        const BATCH_SIZE: usize = 5_000;
+
+        // The version `document_version` reads for new documents
+        let rows = sqlx::query_as::<_, (uuid::Uuid, String, String, String, Option<String>)>(
+            r"
+            SELECT id, doc_type, source_name, doc_path,
+                   COALESCE(metadata->>'crate_version', metadata->>'package_version')
+            FROM documents
+            ",
+        )
+        .fetch_all(pool)
+        .await?;
+        let examined = rows.len() as u64;
+
+        let (old_ids, new_ids): (Vec<uuid::Uuid>, Vec<uuid::Uuid>) = rows
+            .iter()
+            .map(|(id, doc_type, source_name, doc_path, version)| {
+                let stable = crate::document_ids::stable_document_id(
+                    doc_type,
+                    source_name,
+                    doc_path,
+                    version.as_deref(),
+                );
+                (*id, stable)
+            })
+            .filter(|(old, new)| old != new)
+            .unzip();
+        let rewritten = old_ids.len() as u64;
+        if dry_run || old_ids.is_empty() {
+            return Ok((examined, rewritten));
+        }
+
+        let feedback_exists: bool =
+            sqlx::query_scalar("SELECT to_regclass('search_feedback') IS NOT NULL")
+                .fetch_one(pool)
+                .await?;
+        let mut tx = pool.begin().await?;
+        for (old, new) in old_ids.chunks(BATCH_SIZE).zip(new_ids.chunks(BATCH_SIZE)) {
+            sqlx::query(
+                r"
+                UPDATE documents d SET id = m.new_id
+                FROM unnest($1::uuid[], $2::uuid[]) AS m(old_id, new_id)
+                WHERE d.id = m.old_id
+                ",
+            )
+            .bind(old)
+            .bind(new)
+            .execute(&mut *tx)
+            .await?;
+            if feedback_exists {
+                sqlx::query(
+                    r"
+                    UPDATE search_feedback f SET document_id = m.new_id
+                    FROM unnest($1::uuid[], $2::uuid[]) AS m(old_id, new_id)
+                    WHERE f.document_id = m.old_id
+                    ",
+                )
+                .bind(old)
+                .bind(new)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        tx.commit().await?;
+
+        info!(
+            "Rewrote {} of {} document ids to stable ids",
+            rewritten, examined
+        );
+        Ok((examined, rewritten))
+    }
+
+    /// Perform vector similarity search
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn vector_search(
+        pool: &PgPool,
+        _embedding: &[f32],
+        limit: i64,
+    ) -> Result<Vec<Document>> {
+        // For now, return a basic text search as fallback
+        let rows = sqlx::query(
+            r"
+            SELECT 
+                id,
+                doc_type,
+                source_name,
+                doc_path,
+                content,
+                metadata,
+                token_count,
+                created_at,
+                updated_at
+            FROM documents 
+            WHERE content IS NOT NULL
+            ORDER BY created_at DESC
+            LIMIT $1
+            ",
+        )
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        let docs = rows
+            .into_iter()
+            .map(|row| {
+                Document {
+                    id: row.get("id"),
+                    doc_type: row.get("doc_type"),
+                    source_name: row.get("source_name"),
+                    doc_path: row.get("doc_path"),
+                    content: row.get("content"),
+                    metadata: row.get("metadata"),
+                    embedding: None, // Skip embedding for now
+                    token_count: row.get("token_count"),
+                    created_at: row.get("created_at"),
+                    updated_at: row.get("updated_at"),
+                }
+            })
+            .collect();
+
+        Ok(docs)
+    }
+
+    /// Perform vector similarity search for Rust documents only
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn rust_vector_search(
+        pool: &PgPool,
+        query: &str,
+        _embedding: &[f32],
+        limit: i64,
+    ) -> Result<Vec<Document>> {
+        // Perform full-text search on Rust documents with relevance ranking
+        // Try full-text search first, fallback to tokenized ILIKE if FTS not available
+        let fts = search_fts_settings(pool, "rust").await;
+        let fts_sql = format!(
+            r"
             SELECT
                 id,
                 doc_type,
@@ -552,29 +1404,34 @@ impl DocumentQueries {
                 token_count,
                 created_at,
                 updated_at,
-                ts_rank_cd(
-                    to_tsvector('english', coalesce(content,'')),
-                    websearch_to_tsquery('english', $2)
-                ) AS rank
+                {rank} AS rank
             FROM documents
-            WHERE doc_type = $1
+            WHERE doc_type = 'rust'
+              AND {active}
+              AND {ENABLED_SOURCE_FILTER}
               AND (
-                    to_tsvector('english', coalesce(content,'')) @@ websearch_to_tsquery('english', $2)
-                 OR doc_path ILIKE $3
+                    {matches}
+                 OR doc_path ILIKE $2
+                 OR content ILIKE $2
               )
-            ORDER BY 
+            ORDER BY
               rank DESC,
               created_at DESC
-            LIMIT $4
-        ";
+            LIMIT $3
+        ",
+            rank = fts.rank_sql(&fts.tsquery(1)),
+            matches = fts.match_sql(&fts.tsquery(1)),
+            active = crate::models::CrateStatusFilter::Active.sql_predicate(),
+        );
 
-        let fts_attempt = sqlx::query(fts_sql)
-            .bind(doc_type)
-            .bind(query)
-            .bind(format!("%{query}%"))
-            .bind(limit)
-            .fetch_all(pool)
-            .await;
+        let fts_attempt = execute_with_retry("rust_vector_search", || {
+            sqlx::query(&fts_sql)
+                .bind(query)
+                .bind(format!("%{query}%"))
+                .bind(limit)
+                .fetch_all(pool)
+        })
+        .await;
 
         let rows = if let Ok(rows) = fts_attempt {
             rows
@@ -587,7 +1444,13 @@ impl DocumentQueries {
                 .map(|t| format!("%{t}%"))
                 .collect();
 
-            let mut where_parts = vec!["doc_type = $1".to_string()];
+            let mut where_parts = vec![
+                "doc_type = $1".to_string(),
+                crate::models::CrateStatusFilter::Active
+                    .sql_predicate()
+                    .to_string(),
+                ENABLED_SOURCE_FILTER.to_string(),
+            ];
             let mut binds: Vec<String> = Vec::new();
             let mut bind_index = 2;
             for _tok in &tokens {
@@ -595,10 +1458,10 @@ impl DocumentQueries {
                     "(content ILIKE ${bind_index} OR doc_path ILIKE ${bind_index})"
                 ));
                 bind_index += 1;
-                // push corresponding bind after the loop using same index order
             }
-            // Re-add binds in order matching the placeholders constructed above
+            // Re-add binds in order matching the placeholders
             binds.extend(tokens.clone());
+
             // If no tokens, fall back to simple ILIKE of full query
             if tokens.is_empty() {
                 where_parts.push("(content ILIKE $2 OR doc_path ILIKE $2)".to_string());
@@ -612,7 +1475,8 @@ impl DocumentQueries {
                     where_parts.join(" AND "),
                     bind_index
                 );
-            let mut q = sqlx::query(&sql).bind(doc_type);
+
+            let mut q = sqlx::query(&sql).bind("rust");
             for b in &binds {
                 q = q.bind(b);
             }
@@ -620,12 +1484,6 @@ impl DocumentQueries {
             q.fetch_all(pool).await?
         };
 
-        info!(
-            "doc_type_vector_search: Found {} documents for doc_type '{}'",
-            rows.len(),
-            doc_type
-        );
-
         let docs = rows
             .into_iter()
             .map(|row| {
@@ -647,76 +1505,379 @@ impl DocumentQueries {
         Ok(docs)
     }
 
-    /// Perform vector similarity search with metadata filtering
+    /// Perform vector similarity search for documents of a specific type
     ///
     /// # Errors
     ///
     /// Returns an error if the database query fails.
-    #[allow(clippy::too_many_lines)]
-    pub async fn doc_type_vector_search_with_filters(
+    pub async fn doc_type_vector_search(
         pool: &PgPool,
         doc_type: &str,
         query: &str,
         _embedding: &[f32],
         limit: i64,
-        filters: &MetadataFilters,
     ) -> Result<Vec<Document>> {
-        // Try FTS variant with ranking and metadata filters
-        let mut where_parts = vec!["doc_type = $1".to_string()];
-        // FTS predicate and doc_path fallback
-        where_parts.push("(to_tsvector('english', coalesce(content,'')) @@ websearch_to_tsquery('english', $2) OR doc_path ILIKE $3)".to_string());
-        let mut bind_index = 4;
-        if filters.format.is_some() {
-            where_parts.push(format!("(metadata->>'format' = ${bind_index})"));
-            bind_index += 1;
-        }
-        if filters.complexity.is_some() {
-            where_parts.push(format!("(metadata->>'complexity' = ${bind_index})"));
-            bind_index += 1;
-        }
-        if filters.category.is_some() {
-            where_parts.push(format!("(metadata->>'category' = ${bind_index})"));
-            bind_index += 1;
-        }
-        if filters.topic.is_some() {
-            where_parts.push(format!("(metadata->>'topic' = ${bind_index})"));
-            bind_index += 1;
-        }
-        if filters.api_version.is_some() {
-            where_parts.push(format!("(metadata->>'api_version' = ${bind_index})"));
-            bind_index += 1;
-        }
-
+        // Attempt full-text search first (uses built-in FTS, no extension required)
+        // Fallback to tokenized ILIKE if FTS functions are unavailable
+        let fts = search_fts_settings(pool, doc_type).await;
         let fts_sql = format!(
-            "SELECT id, doc_type, source_name, doc_path, content, metadata, token_count, created_at, updated_at, \
-             ts_rank_cd(to_tsvector('english', coalesce(content,'')), websearch_to_tsquery('english', $2)) AS rank \
-             FROM documents WHERE {} ORDER BY rank DESC, created_at DESC LIMIT ${}",
-            where_parts.join(" AND "),
-            bind_index
-        );
-
-        let mut q = sqlx::query(&fts_sql)
-            .bind(doc_type)
-            .bind(query)
-            .bind(format!("%{query}%"));
-        if let Some(v) = &filters.format {
-            q = q.bind(v);
+            r"
+            SELECT
+                id,
+                doc_type,
+                source_name,
+                doc_path,
+                content,
+                metadata,
+                token_count,
+                created_at,
+                updated_at,
+                {rank} AS rank
+            FROM documents
+            WHERE doc_type = $1
+              AND {active}
+              AND {ENABLED_SOURCE_FILTER}
+              AND (
+                    {matches}
+                 OR doc_path ILIKE $3
+              )
+            ORDER BY 
+              rank DESC,
+              created_at DESC
+            LIMIT $4
+        ",
+            rank = fts.rank_sql(&fts.tsquery(2)),
+            matches = fts.match_sql(&fts.tsquery(2)),
+            active = crate::models::CrateStatusFilter::Active.sql_predicate(),
+        );
+
+        let fts_attempt = execute_with_retry("doc_type_vector_search", || {
+            sqlx::query(&fts_sql)
+                .bind(doc_type)
+                .bind(query)
+                .bind(format!("%{query}%"))
+                .bind(limit)
+                .fetch_all(pool)
+        })
+        .await;
+
+        let rows = if let Ok(rows) = fts_attempt {
+            rows
+        } else {
+            // Fallback: tokenized ILIKE requiring all significant tokens
+            let tokens: Vec<String> = query
+                .split_whitespace()
+                .map(|t| t.trim_matches(|c: char| !c.is_alphanumeric()))
+                .filter(|t| t.len() >= 3)
+                .map(|t| format!("%{t}%"))
+                .collect();
+
+            let mut where_parts = vec![
+                "doc_type = $1".to_string(),
+                crate::models::CrateStatusFilter::Active
+                    .sql_predicate()
+                    .to_string(),
+                ENABLED_SOURCE_FILTER.to_string(),
+            ];
+            let mut binds: Vec<String> = Vec::new();
+            let mut bind_index = 2;
+            for _tok in &tokens {
+                where_parts.push(format!(
+                    "(content ILIKE ${bind_index} OR doc_path ILIKE ${bind_index})"
+                ));
+                bind_index += 1;
+                // push corresponding bind after the loop using same index order
+            }
+            // Re-add binds in order matching the placeholders constructed above
+            binds.extend(tokens.clone());
+            // If no tokens, fall back to simple ILIKE of full query
+            if tokens.is_empty() {
+                where_parts.push("(content ILIKE $2 OR doc_path ILIKE $2)".to_string());
+                binds.push(format!("%{query}%"));
+                bind_index = 3;
+            }
+
+            let sql = format!(
+                    "SELECT id, doc_type, source_name, doc_path, content, metadata, token_count, created_at, updated_at \
+                     FROM documents WHERE {} ORDER BY created_at DESC LIMIT ${}",
+                    where_parts.join(" AND "),
+                    bind_index
+                );
+            let mut q = sqlx::query(&sql).bind(doc_type);
+            for b in &binds {
+                q = q.bind(b);
+            }
+            q = q.bind(limit);
+            q.fetch_all(pool).await?
+        };
+
+        info!(
+            "doc_type_vector_search: Found {} documents for doc_type '{}'",
+            rows.len(),
+            doc_type
+        );
+
+        let docs = rows
+            .into_iter()
+            .map(|row| {
+                Document {
+                    id: row.get("id"),
+                    doc_type: row.get("doc_type"),
+                    source_name: row.get("source_name"),
+                    doc_path: row.get("doc_path"),
+                    content: row.get("content"),
+                    metadata: row.get("metadata"),
+                    embedding: None, // Skip embedding for now
+                    token_count: row.get("token_count"),
+                    created_at: row.get("created_at"),
+                    updated_at: row.get("updated_at"),
+                }
+            })
+            .collect();
+
+        Ok(docs)
+    }
+
+    /// Perform vector similarity search with metadata filtering
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn doc_type_vector_search_with_filters(
+        pool: &PgPool,
+        doc_type: &str,
+        query: &str,
+        embedding: &[f32],
+        limit: i64,
+        filters: &MetadataFilters,
+    ) -> Result<Vec<Document>> {
+        let results =
+            Self::doc_type_search_scored(pool, doc_type, query, embedding, limit, filters).await?;
+        Ok(results.into_iter().map(|r| r.document).collect())
+    }
+
+    /// Words of each of `contents` that full-text search matches against `query`
+    ///
+    /// Read from `ts_headline` in the language of `fts` with every match
+    /// marked, so stemmed forms count (`spawned` for `spawn`). Each list is ASCII-lowercased and holds each
+    /// word once, in order of first appearance; it is empty when nothing
+    /// matches or the query has only stop words.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn headline_terms(
+        pool: &PgPool,
+        fts: &FtsSettings,
+        query: &str,
+        contents: &[&str],
+    ) -> Result<Vec<Vec<String>>> {
+        const START: char = '\u{2}';
+        const STOP: char = '\u{3}';
+        let sql = format!(
+            r"
+            SELECT ts_headline(
+                '{language}', t.content, {tsquery},
+                'HighlightAll=true, StartSel=' || chr(2) || ', StopSel=' || chr(3)
+            )
+            FROM unnest($2::text[]) WITH ORDINALITY AS t(content, ord)
+            ORDER BY t.ord
+            ",
+            language = fts.language,
+            tsquery = fts.tsquery(1),
+        );
+        let headlines: Vec<String> = execute_with_retry("headline_terms", || {
+            sqlx::query_scalar(&sql)
+                .bind(query)
+                .bind(contents)
+                .fetch_all(pool)
+        })
+        .await?;
+
+        Ok(headlines
+            .iter()
+            .map(|headline| {
+                let mut words: Vec<String> = Vec::new();
+                for marked in headline.split(START).skip(1) {
+                    let Some((word, _)) = marked.split_once(STOP) else {
+                        continue;
+                    };
+                    let word = word.to_ascii_lowercase();
+                    if !word.is_empty() && !words.contains(&word) {
+                        words.push(word);
+                    }
+                }
+                words
+            })
+            .collect())
+    }
+
+    /// Search documents of a type with metadata filtering, returning the rank of each match
+    ///
+    /// Scores are the full-text `ts_rank_cd` rank; they are 0.0 when full-text
+    /// search is unavailable and the tokenized ILIKE fallback is used.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    #[allow(clippy::too_many_lines)]
+    pub async fn doc_type_search_scored(
+        pool: &PgPool,
+        doc_type: &str,
+        query: &str,
+        _embedding: &[f32],
+        limit: i64,
+        filters: &MetadataFilters,
+    ) -> Result<Vec<ScoredDocument>> {
+        // Try FTS variant with ranking and metadata filters
+        let mut where_parts = vec![
+            "doc_type = $1".to_string(),
+            crate::models::CrateStatusFilter::Active
+                .sql_predicate()
+                .to_string(),
+            ENABLED_SOURCE_FILTER.to_string(),
+        ];
+        // FTS predicate and doc_path fallback
+        let fts = search_fts_settings(pool, doc_type).await;
+        let mut bind_index = 4;
+        let tsquery = if filters.expansions.is_empty() {
+            fts.tsquery(2)
+        } else {
+            bind_index += 1;
+            fts.expanded_tsquery(2, 4, filters.expansions.len())
+        };
+        where_parts.push(format!(
+            "({} OR doc_path ILIKE $3)",
+            fts.match_sql(&tsquery)
+        ));
+        if filters.format.is_some() {
+            where_parts.push(format!("(metadata->>'format' = ${bind_index})"));
+            bind_index += 1;
+        }
+        if filters.complexity.is_some() {
+            where_parts.push(format!("(metadata->>'complexity' = ${bind_index})"));
+            bind_index += 1;
+        }
+        if filters.category.is_some() {
+            where_parts.push(format!("(metadata->>'category' = ${bind_index})"));
+            bind_index += 1;
+        }
+        if filters.topic.is_some() {
+            where_parts.push(format!("(metadata->>'topic' = ${bind_index})"));
+            bind_index += 1;
         }
-        if let Some(v) = &filters.complexity {
-            q = q.bind(v);
+        if filters.api_version.is_some() {
+            where_parts.push(format!("(metadata->>'api_version' = ${bind_index})"));
+            bind_index += 1;
+        }
+        if filters.crate_name.is_some() {
+            where_parts.push(format!("(metadata->>'crate_name' = ${bind_index})"));
+            bind_index += 1;
+        }
+        if filters.crate_version.is_some() {
+            where_parts.push(format!("(metadata->>'crate_version' = ${bind_index})"));
+            bind_index += 1;
+        }
+        if filters.package_name.is_some() {
+            where_parts.push(format!("(metadata->>'package_name' = ${bind_index})"));
+            bind_index += 1;
+        }
+        if filters.package_version.is_some() {
+            where_parts.push(format!("(metadata->>'package_version' = ${bind_index})"));
+            bind_index += 1;
+        }
+        if filters.item_type.is_some() {
+            where_parts.push(format!("(metadata->>'item_type' = ${bind_index})"));
+            bind_index += 1;
         }
-        if let Some(v) = &filters.category {
-            q = q.bind(v);
+        if filters.created_after.is_some() {
+            where_parts.push(format!("(created_at >= ${bind_index})"));
+            bind_index += 1;
         }
-        if let Some(v) = &filters.topic {
-            q = q.bind(v);
+        if filters.created_before.is_some() {
+            where_parts.push(format!("(created_at < ${bind_index})"));
+            bind_index += 1;
         }
-        if let Some(v) = &filters.api_version {
-            q = q.bind(v);
+        if filters.updated_after.is_some() {
+            where_parts.push(format!("(updated_at >= ${bind_index})"));
+            bind_index += 1;
         }
-        q = q.bind(limit);
+        let boost = if filters.recency_boost.is_some() {
+            let sql = format!(
+                " * (1 + ${bind_index}::float8 * power(0.5, \
+                 EXTRACT(EPOCH FROM (NOW() - COALESCE(updated_at, created_at)))::float8 \
+                 / 86400.0 / {RECENCY_HALF_LIFE_DAYS:.1}))"
+            );
+            bind_index += 1;
+            sql
+        } else {
+            String::new()
+        };
+
+        let fts_sql = format!(
+            "SELECT id, doc_type, source_name, doc_path, content, metadata, token_count, created_at, updated_at, \
+             {}::float8{} AS rank \
+             FROM documents WHERE {} ORDER BY rank DESC, created_at DESC LIMIT ${}",
+            fts.rank_sql(&tsquery),
+            boost,
+            where_parts.join(" AND "),
+            bind_index
+        );
+
+        let fts_query = || {
+            let mut q = sqlx::query(&fts_sql)
+                .bind(doc_type)
+                .bind(query)
+                .bind(format!("%{query}%"));
+            if !filters.expansions.is_empty() {
+                q = q.bind(&filters.expansions);
+            }
+            if let Some(v) = &filters.format {
+                q = q.bind(v);
+            }
+            if let Some(v) = &filters.complexity {
+                q = q.bind(v);
+            }
+            if let Some(v) = &filters.category {
+                q = q.bind(v);
+            }
+            if let Some(v) = &filters.topic {
+                q = q.bind(v);
+            }
+            if let Some(v) = &filters.api_version {
+                q = q.bind(v);
+            }
+            if let Some(v) = &filters.crate_name {
+                q = q.bind(v);
+            }
+            if let Some(v) = &filters.crate_version {
+                q = q.bind(v);
+            }
+            if let Some(v) = &filters.package_name {
+                q = q.bind(v);
+            }
+            if let Some(v) = &filters.package_version {
+                q = q.bind(v);
+            }
+            if let Some(v) = &filters.item_type {
+                q = q.bind(v);
+            }
+            if let Some(v) = filters.created_after {
+                q = q.bind(v);
+            }
+            if let Some(v) = filters.created_before {
+                q = q.bind(v);
+            }
+            if let Some(v) = filters.updated_after {
+                q = q.bind(v);
+            }
+            if let Some(v) = filters.recency_boost {
+                q = q.bind(v);
+            }
+            q.bind(limit).fetch_all(pool)
+        };
 
-        let rows = match q.fetch_all(pool).await {
+        let rows = match execute_with_retry("doc_type_search_scored", fts_query).await {
             Ok(rows) => rows,
             Err(e) => {
                 // Log the error before falling back
@@ -732,7 +1893,13 @@ impl DocumentQueries {
                     .map(|t| format!("%{t}%"))
                     .collect();
 
-                let mut parts = vec!["doc_type = $1".to_string()];
+                let mut parts = vec![
+                    "doc_type = $1".to_string(),
+                    crate::models::CrateStatusFilter::Active
+                        .sql_predicate()
+                        .to_string(),
+                    ENABLED_SOURCE_FILTER.to_string(),
+                ];
                 let mut idx = 2;
                 for _t in &tokens {
                     parts.push(format!("(content ILIKE ${idx} OR doc_path ILIKE ${idx})"));
@@ -762,9 +1929,41 @@ impl DocumentQueries {
                     parts.push(format!("(metadata->>'api_version' = ${idx})"));
                     idx += 1;
                 }
+                if filters.crate_name.is_some() {
+                    parts.push(format!("(metadata->>'crate_name' = ${idx})"));
+                    idx += 1;
+                }
+                if filters.crate_version.is_some() {
+                    parts.push(format!("(metadata->>'crate_version' = ${idx})"));
+                    idx += 1;
+                }
+                if filters.package_name.is_some() {
+                    parts.push(format!("(metadata->>'package_name' = ${idx})"));
+                    idx += 1;
+                }
+                if filters.package_version.is_some() {
+                    parts.push(format!("(metadata->>'package_version' = ${idx})"));
+                    idx += 1;
+                }
+                if filters.item_type.is_some() {
+                    parts.push(format!("(metadata->>'item_type' = ${idx})"));
+                    idx += 1;
+                }
+                if filters.created_after.is_some() {
+                    parts.push(format!("(created_at >= ${idx})"));
+                    idx += 1;
+                }
+                if filters.created_before.is_some() {
+                    parts.push(format!("(created_at < ${idx})"));
+                    idx += 1;
+                }
+                if filters.updated_after.is_some() {
+                    parts.push(format!("(updated_at >= ${idx})"));
+                    idx += 1;
+                }
                 let sql = format!(
-                    "SELECT id, doc_type, source_name, doc_path, content, metadata, token_count, created_at, updated_at \
-                     FROM documents WHERE {} ORDER BY created_at DESC LIMIT ${}",
+                    "SELECT id, doc_type, source_name, doc_path, content, metadata, token_count, created_at, updated_at, \
+                     0::float8 AS rank FROM documents WHERE {} ORDER BY created_at DESC LIMIT ${}",
                     parts.join(" AND "),
                     idx
                 );
@@ -791,6 +1990,30 @@ impl DocumentQueries {
                 if let Some(v) = &filters.api_version {
                     q2 = q2.bind(v);
                 }
+                if let Some(v) = &filters.crate_name {
+                    q2 = q2.bind(v);
+                }
+                if let Some(v) = &filters.crate_version {
+                    q2 = q2.bind(v);
+                }
+                if let Some(v) = &filters.package_name {
+                    q2 = q2.bind(v);
+                }
+                if let Some(v) = &filters.package_version {
+                    q2 = q2.bind(v);
+                }
+                if let Some(v) = &filters.item_type {
+                    q2 = q2.bind(v);
+                }
+                if let Some(v) = filters.created_after {
+                    q2 = q2.bind(v);
+                }
+                if let Some(v) = filters.created_before {
+                    q2 = q2.bind(v);
+                }
+                if let Some(v) = filters.updated_after {
+                    q2 = q2.bind(v);
+                }
                 q2 = q2.bind(limit);
                 match q2.fetch_all(pool).await {
                     Ok(rows) => rows,
@@ -805,10 +2028,11 @@ impl DocumentQueries {
             }
         };
 
-        let docs = rows
+        let results = rows
             .into_iter()
-            .map(|row| {
-                Document {
+            .map(|row| ScoredDocument {
+                score: row.get("rank"),
+                document: Document {
                     id: row.get("id"),
                     doc_type: row.get("doc_type"),
                     source_name: row.get("source_name"),
@@ -819,27 +2043,303 @@ impl DocumentQueries {
                     token_count: row.get("token_count"),
                     created_at: row.get("created_at"),
                     updated_at: row.get("updated_at"),
-                }
+                },
             })
             .collect();
 
-        Ok(docs)
+        Ok(results)
     }
 }
 
-/// Query performance metrics
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct QueryPerformanceMetrics {
-    pub query_name: String,
-    pub execution_time_ms: u64,
-    pub rows_returned: usize,
-    pub timestamp: chrono::DateTime<chrono::Utc>,
-}
-
-/// Performance monitoring for database queries
-pub struct QueryPerformanceMonitor;
+/// Document source query operations
+pub struct DocumentSourceQueries;
 
-impl QueryPerformanceMonitor {
+impl DocumentSourceQueries {
+    /// Full-text search settings of `doc_type`
+    ///
+    /// A language that is not one of the server's text search configurations
+    /// falls back to `simple`, with a warning the first time it is seen.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn fts_settings(pool: &PgPool, doc_type: &str) -> Result<FtsSettings> {
+        let configs: Vec<serde_json::Value> = execute_with_retry("fts_settings", || {
+            sqlx::query_scalar(
+                "SELECT COALESCE(config, '{}'::jsonb) FROM document_sources \
+                 WHERE doc_type = $1 ORDER BY source_name",
+            )
+            .bind(doc_type)
+            .fetch_all(pool)
+        })
+        .await?;
+
+        let mut settings = FtsSettings::from_configs(&configs);
+        if settings.language == FtsSettings::default().language {
+            return Ok(settings);
+        }
+        // Names are spliced into the SQL, so only plain identifiers qualify
+        let plain = settings
+            .language
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        let known = plain
+            && execute_with_retry("fts_language_exists", || {
+                sqlx::query_scalar::<_, bool>(
+                    "SELECT EXISTS (SELECT 1 FROM pg_ts_config WHERE cfgname = $1)",
+                )
+                .bind(&settings.language)
+                .fetch_one(pool)
+            })
+            .await?;
+        if !known {
+            static WARNED: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+            if let Ok(mut warned) = WARNED.lock() {
+                if !warned.contains(&settings.language) {
+                    warn!(
+                        "Unsupported fts_language '{}' for doc type '{}', using 'simple'",
+                        settings.language, doc_type
+                    );
+                    warned.push(settings.language.clone());
+                }
+            }
+            settings.language = "simple".to_string();
+        }
+        Ok(settings)
+    }
+
+    /// Create the full-text indexes of every configured language besides `english`
+    ///
+    /// Searches match title and content vectors computed in the doc type's
+    /// language; without an index per language they scan every document.
+    /// Returns the languages whose indexes were created or already existed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query or an index build fails.
+    pub async fn ensure_fts_indexes(pool: &PgPool) -> Result<Vec<String>> {
+        let doc_types: Vec<String> = execute_with_retry("fts_doc_types", || {
+            sqlx::query_scalar(
+                "SELECT DISTINCT doc_type FROM document_sources WHERE config ? 'fts_language'",
+            )
+            .fetch_all(pool)
+        })
+        .await?;
+
+        let default = FtsSettings::default();
+        let mut languages: Vec<String> = Vec::new();
+        for doc_type in doc_types {
+            let settings = Self::fts_settings(pool, &doc_type).await?;
+            if settings.language == default.language || languages.contains(&settings.language) {
+                continue;
+            }
+            for statement in settings.index_statements() {
+                sqlx::query(&statement).execute(pool).await?;
+            }
+            languages.push(settings.language);
+        }
+        Ok(languages)
+    }
+
+    /// Sources with their document counts, ordered by doc type and name
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn list_sources(
+        pool: &PgPool,
+        doc_type: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<crate::models::DocumentSourceSummary>> {
+        let sources = sqlx::query_as::<_, crate::models::DocumentSourceSummary>(
+            r"
+            SELECT
+                s.doc_type,
+                s.source_name,
+                COALESCE(s.config, '{}'::jsonb) AS config,
+                COALESCE(s.enabled, true) AS enabled,
+                (
+                    SELECT COUNT(*) FROM documents d
+                    WHERE d.doc_type = s.doc_type AND d.source_name = s.source_name
+                ) AS document_count,
+                s.created_at,
+                s.updated_at
+            FROM document_sources s
+            WHERE ($1::text IS NULL OR s.doc_type = $1)
+            ORDER BY s.doc_type, s.source_name
+            LIMIT $2 OFFSET $3
+            ",
+        )
+        .bind(doc_type)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(sources)
+    }
+
+    /// Find one source with its document count
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_source(
+        pool: &PgPool,
+        doc_type: &str,
+        source_name: &str,
+    ) -> Result<Option<crate::models::DocumentSourceSummary>> {
+        let source = sqlx::query_as::<_, crate::models::DocumentSourceSummary>(
+            r"
+            SELECT
+                s.doc_type,
+                s.source_name,
+                COALESCE(s.config, '{}'::jsonb) AS config,
+                COALESCE(s.enabled, true) AS enabled,
+                (
+                    SELECT COUNT(*) FROM documents d
+                    WHERE d.doc_type = s.doc_type AND d.source_name = s.source_name
+                ) AS document_count,
+                s.created_at,
+                s.updated_at
+            FROM document_sources s
+            WHERE s.doc_type = $1 AND s.source_name = $2
+            ",
+        )
+        .bind(doc_type)
+        .bind(source_name)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(source)
+    }
+
+    /// Enable or disable a source, returning whether it exists
+    ///
+    /// Documents of a disabled source are excluded from search.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database update fails.
+    pub async fn set_enabled(
+        pool: &PgPool,
+        doc_type: &str,
+        source_name: &str,
+        enabled: bool,
+    ) -> Result<bool> {
+        let result = sqlx::query(
+            r"
+            UPDATE document_sources
+            SET enabled = $3, updated_at = CURRENT_TIMESTAMP
+            WHERE doc_type = $1 AND source_name = $2
+            ",
+        )
+        .bind(doc_type)
+        .bind(source_name)
+        .bind(enabled)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete a source and its documents in one transaction
+    ///
+    /// Nothing is removed when `dry_run` is set or when queued or running
+    /// ingest or crate jobs may still write to the source; the returned
+    /// summary says which. Returns `None` if the source does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a database operation fails.
+    pub async fn delete_source(
+        pool: &PgPool,
+        doc_type: &str,
+        source_name: &str,
+        dry_run: bool,
+    ) -> Result<Option<crate::models::DocumentSourceDeletion>> {
+        let mut tx = pool.begin().await?;
+
+        let exists = sqlx::query_scalar::<_, i32>(
+            "SELECT 1 FROM document_sources WHERE doc_type = $1 AND source_name = $2 FOR UPDATE",
+        )
+        .bind(doc_type)
+        .bind(source_name)
+        .fetch_optional(&mut *tx)
+        .await?
+        .is_some();
+        if !exists {
+            return Ok(None);
+        }
+
+        let document_count = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM documents WHERE doc_type = $1 AND source_name = $2",
+        )
+        .bind(doc_type)
+        .bind(source_name)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // Ingest jobs only record their doc type; crate and npm package jobs
+        // write to the source named after the crate or package
+        let active_jobs = sqlx::query_scalar::<_, i64>(
+            r"
+            SELECT
+                (SELECT COUNT(*) FROM ingest_jobs
+                 WHERE doc_type = $1 AND status IN ('queued', 'running'))
+              + (SELECT COUNT(*) FROM crate_jobs
+                 WHERE crate_name = $2 AND status IN ('queued', 'running')
+                   AND (($1 = 'rust' AND operation IN ('add_crate', 'remove_crate'))
+                     OR ($1 = 'npm' AND operation IN ('add_npm_package', 'remove_npm_package'))
+                     OR ($1 = 'python' AND operation IN ('add_python_package', 'remove_python_package'))))
+            ",
+        )
+        .bind(doc_type)
+        .bind(source_name)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let deleted = !dry_run && active_jobs == 0;
+        if deleted {
+            DocumentQueries::delete_by_source(&mut *tx, doc_type, source_name).await?;
+            sqlx::query("DELETE FROM document_sources WHERE doc_type = $1 AND source_name = $2")
+                .bind(doc_type)
+                .bind(source_name)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            info!(
+                "Deleted document source {}/{} with {} documents",
+                doc_type, source_name, document_count
+            );
+        } else {
+            tx.rollback().await?;
+        }
+
+        Ok(Some(crate::models::DocumentSourceDeletion {
+            doc_type: doc_type.to_string(),
+            source_name: source_name.to_string(),
+            document_count,
+            active_jobs,
+            deleted,
+        }))
+    }
+}
+
+/// Query performance metrics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryPerformanceMetrics {
+    pub query_name: String,
+    pub execution_time_ms: u64,
+    pub rows_returned: usize,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+/// Performance monitoring for database queries
+pub struct QueryPerformanceMonitor;
+
+impl QueryPerformanceMonitor {
     /// Execute a query with performance monitoring
     ///
     /// # Errors
@@ -853,13 +2353,15 @@ impl QueryPerformanceMonitor {
         F: std::future::Future<Output = Result<T>>,
         T: RowCountable,
     {
+        let span = info_span!("db.query", query = query_name, rows = tracing::field::Empty);
         let start = Instant::now();
-        let result = operation.await;
+        let result = operation.instrument(span.clone()).await;
         let execution_time = start.elapsed();
 
         match result {
             Ok(value) => {
                 let row_count = value.row_count();
+                span.record("rows", row_count);
                 let metrics = QueryPerformanceMetrics {
                     query_name: query_name.to_string(),
                     execution_time_ms: u64::try_from(execution_time.as_millis())
@@ -1027,6 +2529,111 @@ impl QueryPerformanceMonitor {
     }
 }
 
+/// Error note on crate jobs requeued because the server shut down mid-run
+pub const INTERRUPTED_JOB_NOTE: &str = "Interrupted by server shutdown; queued for resume";
+
+/// Postgres channel notified with the job ID whenever a crate job is queued
+pub const CRATE_JOBS_CHANNEL: &str = "crate_jobs_changed";
+
+/// Postgres channel notified with the doc type whenever statements change
+/// `documents` or `document_sources` rows (triggers from migration
+/// `028_query_cache_notify`), whichever process runs them
+pub const QUERY_CACHE_CHANNEL: &str = "query_cache_invalidate";
+
+/// Hours an idempotency key keeps mapping to the job it created
+pub const IDEMPOTENCY_KEY_TTL_HOURS: i32 = 24;
+
+/// An idempotency key was reused for a different crate or operation
+#[derive(Debug, thiserror::Error)]
+#[error("idempotency_key '{key}' already names {operation} job {job_id} for '{crate_name}'; use a new key for a different request")]
+pub struct IdempotencyConflict {
+    pub key: String,
+    pub job_id: uuid::Uuid,
+    pub crate_name: String,
+    pub operation: String,
+}
+
+impl IdempotencyConflict {
+    /// Error unless `job`, found under `key`, was created for `crate_name` and `operation`
+    ///
+    /// Names are compared by [`crate::models::crate_name_key`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the conflict when the job belongs to another request.
+    pub fn check(
+        job: &crate::models::CrateJob,
+        key: &str,
+        crate_name: &str,
+        operation: &str,
+    ) -> std::result::Result<(), Self> {
+        let same_crate = crate::models::crate_name_key(&job.crate_name)
+            == crate::models::crate_name_key(crate_name);
+        if same_crate && job.operation == operation {
+            return Ok(());
+        }
+        Err(Self {
+            key: key.to_string(),
+            job_id: job.id,
+            crate_name: job.crate_name.clone(),
+            operation: job.operation.clone(),
+        })
+    }
+}
+
+/// Bump `updated_at` of a running job in `table`, returning whether it is still running
+async fn touch_running_job(pool: &PgPool, table: &str, job_id: uuid::Uuid) -> Result<bool> {
+    let sql = format!("UPDATE {table} SET updated_at = NOW() WHERE id = $1 AND status = 'running'");
+    let result = execute_with_retry("job_heartbeat", || {
+        sqlx::query(&sql).bind(job_id).execute(pool)
+    })
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Running jobs in `table` last updated before `cutoff`, oldest first
+async fn find_stale_jobs<T>(pool: &PgPool, table: &str, cutoff: DateTime<Utc>) -> Result<Vec<T>>
+where
+    T: for<'r> sqlx::FromRow<'r, sqlx::postgres::PgRow> + Send + Unpin,
+{
+    let sql = format!(
+        "SELECT * FROM {table} WHERE status = 'running' AND updated_at < $1 ORDER BY updated_at"
+    );
+    let rows = execute_with_retry("find_stale_jobs", || {
+        sqlx::query_as::<_, T>(&sql).bind(cutoff).fetch_all(pool)
+    })
+    .await?;
+
+    Ok(rows)
+}
+
+/// Fail running jobs in `table` last updated before `cutoff`, appending
+/// `reason` to their error
+async fn fail_stale_jobs(
+    pool: &PgPool,
+    table: &str,
+    cutoff: DateTime<Utc>,
+    reason: &str,
+) -> Result<u64> {
+    let sql = format!(
+        r"
+        UPDATE {table}
+        SET status = 'failed',
+            finished_at = CURRENT_TIMESTAMP,
+            error = COALESCE(error, '') || CASE WHEN error IS NULL OR error = '' THEN '' ELSE E'\n' END || $2
+        WHERE status = 'running'
+          AND updated_at < $1
+        "
+    );
+    let result = execute_with_retry("fail_stale_jobs", || {
+        sqlx::query(&sql).bind(cutoff).bind(reason).execute(pool)
+    })
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
 /// Crate job query operations
 pub struct CrateJobQueries;
 
@@ -1040,173 +2647,403 @@ impl CrateJobQueries {
         pool: &PgPool,
         crate_name: &str,
         operation: &str,
+    ) -> Result<crate::models::CrateJob> {
+        Self::create_job_with_options(pool, crate_name, operation, None).await
+    }
+
+    /// Create a new crate job, storing the request options needed to run it
+    ///
+    /// Listeners on [`CRATE_JOBS_CHANNEL`] are notified once the insert commits.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database insertion fails.
+    pub async fn create_job_with_options(
+        pool: &PgPool,
+        crate_name: &str,
+        operation: &str,
+        options: Option<&serde_json::Value>,
     ) -> Result<crate::models::CrateJob> {
         let job_id = uuid::Uuid::new_v4();
         let now = chrono::Utc::now();
+        let mut tx = pool.begin().await?;
 
-        let row = sqlx::query_as::<_, crate::models::CrateJob>(
+        // Only reference the options column when there is something to store
+        let row = if let Some(options) = options {
+            sqlx::query_as::<_, crate::models::CrateJob>(
+                r"
+                INSERT INTO crate_jobs (id, crate_name, operation, status, options, started_at, created_at, updated_at)
+                VALUES ($1, $2, $3, 'queued', $4, $5, $5, $5)
+                RETURNING *
+                "
+            )
+            .bind(job_id)
+            .bind(crate_name)
+            .bind(operation)
+            .bind(options)
+            .bind(now)
+            .fetch_one(&mut *tx)
+            .await?
+        } else {
+            sqlx::query_as::<_, crate::models::CrateJob>(
+                r"
+                INSERT INTO crate_jobs (id, crate_name, operation, status, started_at, created_at, updated_at)
+                VALUES ($1, $2, $3, 'queued', $4, $4, $4)
+                RETURNING *
+                "
+            )
+            .bind(job_id)
+            .bind(crate_name)
+            .bind(operation)
+            .bind(now)
+            .fetch_one(&mut *tx)
+            .await?
+        };
+
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(CRATE_JOBS_CHANNEL)
+            .bind(job_id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(row)
+    }
+
+    /// Create a job unless `idempotency_key` already names a recent one
+    ///
+    /// Returns the job and whether it was created. A key naming a job of
+    /// another crate or operation is an [`IdempotencyConflict`]. Keys older than
+    /// [`IDEMPOTENCY_KEY_TTL_HOURS`] are released first, so a reused key
+    /// starts a new job. Concurrent calls with the same key are serialised by
+    /// the unique index: exactly one creates the job and the rest get it back.
+    /// Listeners on [`CRATE_JOBS_CHANNEL`] are only notified for a new job.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database insertion fails.
+    pub async fn create_job_idempotent(
+        pool: &PgPool,
+        crate_name: &str,
+        operation: &str,
+        options: &serde_json::Value,
+        idempotency_key: &str,
+    ) -> Result<(crate::models::CrateJob, bool)> {
+        let job_id = uuid::Uuid::new_v4();
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            r"
+            UPDATE crate_jobs SET idempotency_key = NULL
+            WHERE idempotency_key = $1 AND created_at < NOW() - make_interval(hours => $2)
+            ",
+        )
+        .bind(idempotency_key)
+        .bind(IDEMPOTENCY_KEY_TTL_HOURS)
+        .execute(&mut *tx)
+        .await?;
+
+        let created = sqlx::query_as::<_, crate::models::CrateJob>(
             r"
-            INSERT INTO crate_jobs (id, crate_name, operation, status, started_at, created_at, updated_at)
-            VALUES ($1, $2, $3, 'queued', $4, $4, $4)
+            INSERT INTO crate_jobs (id, crate_name, operation, status, options, idempotency_key, started_at, created_at, updated_at)
+            VALUES ($1, $2, $3, 'queued', $4, $5, NOW(), NOW(), NOW())
+            ON CONFLICT (idempotency_key) DO NOTHING
             RETURNING *
-            "
+            ",
         )
         .bind(job_id)
         .bind(crate_name)
         .bind(operation)
-        .bind(now)
-        .fetch_one(pool)
+        .bind(options)
+        .bind(idempotency_key)
+        .fetch_optional(&mut *tx)
         .await?;
 
-        Ok(row)
+        let Some(job) = created else {
+            // The conflicting insert has committed, so this statement sees it
+            let existing = sqlx::query_as::<_, crate::models::CrateJob>(
+                "SELECT * FROM crate_jobs WHERE idempotency_key = $1",
+            )
+            .bind(idempotency_key)
+            .fetch_one(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            IdempotencyConflict::check(&existing, idempotency_key, crate_name, operation)?;
+            return Ok((existing, false));
+        };
+
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(CRATE_JOBS_CHANNEL)
+            .bind(job.id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok((job, true))
     }
 
-    /// Find job by ID
+    /// Find the job created with `idempotency_key` within [`IDEMPOTENCY_KEY_TTL_HOURS`]
     ///
     /// # Errors
     ///
     /// Returns an error if the database query fails.
-    pub async fn find_job_by_id(
+    pub async fn find_job_by_idempotency_key(
         pool: &PgPool,
-        job_id: uuid::Uuid,
+        idempotency_key: &str,
     ) -> Result<Option<crate::models::CrateJob>> {
-        let row =
-            sqlx::query_as::<_, crate::models::CrateJob>("SELECT * FROM crate_jobs WHERE id = $1")
-                .bind(job_id)
-                .fetch_optional(pool)
-                .await?;
+        let row = execute_with_retry("find_job_by_idempotency_key", || {
+            sqlx::query_as::<_, crate::models::CrateJob>(
+                r"
+                SELECT * FROM crate_jobs
+                WHERE idempotency_key = $1 AND created_at >= NOW() - make_interval(hours => $2)
+                ",
+            )
+            .bind(idempotency_key)
+            .bind(IDEMPOTENCY_KEY_TTL_HOURS)
+            .fetch_optional(pool)
+        })
+        .await?;
 
         Ok(row)
     }
 
-    /// Update job status
+    /// Claim up to `limit` queued `add_crate` and `remove_crate` jobs, oldest first, and mark them running
+    ///
+    /// Jobs rescheduled for later are skipped until `next_run_at`, and each
+    /// claim counts as an attempt and is recorded as a `status_changed` event.
+    /// Uses `SKIP LOCKED` so concurrent dispatchers never claim the same job.
     ///
     /// # Errors
     ///
     /// Returns an error if the database update fails.
-    pub async fn update_job_status(
+    pub async fn claim_queued_jobs(
+        pool: &PgPool,
+        limit: i64,
+    ) -> Result<Vec<crate::models::CrateJob>> {
+        let jobs = sqlx::query_as::<_, crate::models::CrateJob>(
+            r"
+            WITH claimed AS (
+                UPDATE crate_jobs
+                SET status = 'running', error = NULL, attempts = attempts + 1,
+                    next_run_at = NULL,
+                    started_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+                WHERE id IN (
+                    SELECT id FROM crate_jobs
+                    WHERE status = 'queued'
+                      AND operation IN (
+                          'add_crate', 'remove_crate', 'add_npm_package', 'remove_npm_package',
+                          'add_python_package', 'remove_python_package'
+                      )
+                      AND (next_run_at IS NULL OR next_run_at <= CURRENT_TIMESTAMP)
+                    ORDER BY created_at
+                    FOR UPDATE SKIP LOCKED
+                    LIMIT $1
+                )
+                RETURNING *
+            ),
+            events AS (
+                INSERT INTO crate_job_events (job_id, event_type, old_status, new_status, progress, detail)
+                SELECT id, 'status_changed', 'queued', 'running', progress,
+                       jsonb_build_object('attempt', attempts)
+                FROM claimed
+            )
+            SELECT * FROM claimed
+            ",
+        )
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(jobs)
+    }
+
+    /// Put a failed job back in the queue to run again at `next_run_at`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database update fails.
+    pub async fn schedule_retry(
         pool: &PgPool,
         job_id: uuid::Uuid,
-        status: crate::models::JobStatus,
-        progress: Option<i32>,
-        error: Option<&str>,
+        next_run_at: chrono::DateTime<chrono::Utc>,
+        error: &str,
+        details: &serde_json::Value,
     ) -> Result<crate::models::CrateJob> {
-        let now = chrono::Utc::now();
-        let finished_at = if matches!(
-            status,
-            crate::models::JobStatus::Completed
-                | crate::models::JobStatus::Failed
-                | crate::models::JobStatus::Cancelled
-        ) {
-            Some(now)
-        } else {
-            None
-        };
+        let row = execute_with_retry("schedule_retry", || {
+            sqlx::query_as::<_, crate::models::CrateJob>(
+                r"
+                UPDATE crate_jobs
+                SET status = 'queued', progress = 0, error = $3, next_run_at = $2,
+                    details = COALESCE(details, '{}'::jsonb) || $4,
+                    updated_at = CURRENT_TIMESTAMP
+                WHERE id = $1
+                RETURNING *
+                ",
+            )
+            .bind(job_id)
+            .bind(next_run_at)
+            .bind(error)
+            .bind(details)
+            .fetch_one(pool)
+        })
+        .await?;
+
+        Ok(row)
+    }
 
+    /// Fail a job for good and tag it `dead_letter: true`
+    ///
+    /// Attempts are marked exhausted so the dispatcher never picks it up again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database update fails.
+    pub async fn dead_letter_job(
+        pool: &PgPool,
+        job_id: uuid::Uuid,
+        error: &str,
+        details: &serde_json::Value,
+    ) -> Result<crate::models::CrateJob> {
         let row = sqlx::query_as::<_, crate::models::CrateJob>(
-            r"
+            r#"
             UPDATE crate_jobs
-            SET status = $2,
-                progress = $3,
-                error = $4,
-                finished_at = $5,
-                -- Set started_at when transitioning into running for the first time
-                started_at = CASE
-                    WHEN $2 = 'running'::job_status AND status <> 'running'::job_status THEN $6
-                    ELSE started_at
-                END,
-                updated_at = $6
+            SET status = 'failed', error = $2, next_run_at = NULL,
+                attempts = GREATEST(attempts, max_attempts),
+                details = COALESCE(details, '{}'::jsonb) || $3 || '{"dead_letter": true}'::jsonb,
+                finished_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
             WHERE id = $1
             RETURNING *
-            ",
+            "#,
         )
         .bind(job_id)
-        .bind(status)
-        .bind(progress)
         .bind(error)
-        .bind(finished_at)
-        .bind(now)
+        .bind(details)
         .fetch_one(pool)
         .await?;
 
         Ok(row)
     }
 
-    /// Find active jobs (queued or running)
+    /// List dead-lettered jobs, most recently failed first
     ///
     /// # Errors
     ///
     /// Returns an error if the database query fails.
-    pub async fn find_active_jobs(pool: &PgPool) -> Result<Vec<crate::models::CrateJob>> {
+    pub async fn find_dead_letter_jobs(
+        pool: &PgPool,
+        limit: i64,
+    ) -> Result<Vec<crate::models::CrateJob>> {
         let rows = sqlx::query_as::<_, crate::models::CrateJob>(
             r"
-            SELECT * FROM crate_jobs 
-            WHERE status IN ('queued', 'running')
-            ORDER BY created_at ASC
+            SELECT * FROM crate_jobs
+            WHERE status = 'failed' AND (details->>'dead_letter')::boolean
+            ORDER BY finished_at DESC NULLS LAST
+            LIMIT $1
             ",
         )
+        .bind(limit)
         .fetch_all(pool)
         .await?;
 
         Ok(rows)
     }
 
-    /// Clean up old completed jobs
+    /// Reset attempts on a failed or cancelled job and queue it again
+    ///
+    /// Returns `None` if the job does not exist or is still queued or running.
+    /// Listeners on [`CRATE_JOBS_CHANNEL`] are notified once the update commits.
     ///
     /// # Errors
     ///
-    /// Returns an error if the cleanup operation fails.
-    pub async fn cleanup_old_jobs(pool: &PgPool) -> Result<i32> {
+    /// Returns an error if the database update fails.
+    pub async fn requeue_job(
+        pool: &PgPool,
+        job_id: uuid::Uuid,
+    ) -> Result<Option<crate::models::CrateJob>> {
+        let mut tx = pool.begin().await?;
+        let row = sqlx::query_as::<_, crate::models::CrateJob>(
+            r"
+            UPDATE crate_jobs
+            SET status = 'queued', attempts = 0, progress = NULL, error = NULL,
+                next_run_at = NULL, finished_at = NULL,
+                details = COALESCE(details, '{}'::jsonb) - 'dead_letter',
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND status IN ('failed', 'cancelled')
+            RETURNING *
+            ",
+        )
+        .bind(job_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if row.is_some() {
+            sqlx::query("SELECT pg_notify($1, $2)")
+                .bind(CRATE_JOBS_CHANNEL)
+                .bind(job_id.to_string())
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        Ok(row)
+    }
+
+    /// Move running jobs back to `queued` after a shutdown interrupted them
+    ///
+    /// Only jobs still in `running` state are touched; they are tagged with
+    /// [`INTERRUPTED_JOB_NOTE`] so startup recovery can resume them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database update fails.
+    pub async fn requeue_interrupted_jobs(pool: &PgPool, job_ids: &[uuid::Uuid]) -> Result<u64> {
+        if job_ids.is_empty() {
+            return Ok(0);
+        }
+
         let result = sqlx::query(
             r"
-            DELETE FROM crate_jobs 
-            WHERE status IN ('completed', 'failed', 'cancelled') 
-            AND finished_at < CURRENT_TIMESTAMP - INTERVAL '30 days'
+            UPDATE crate_jobs
+            SET status = 'queued', error = $2, updated_at = CURRENT_TIMESTAMP
+            WHERE id = ANY($1) AND status = 'running'
             ",
         )
+        .bind(job_ids)
+        .bind(INTERRUPTED_JOB_NOTE)
         .execute(pool)
         .await?;
 
-        Ok(i32::try_from(result.rows_affected()).unwrap_or(i32::MAX))
+        Ok(result.rows_affected())
     }
-}
-
-/// Ingest job query operations
-pub struct IngestJobQueries;
 
-impl IngestJobQueries {
-    /// Create a new ingest job
+    /// Claim jobs requeued by a shutdown and mark them running again
+    ///
+    /// Uses `SKIP LOCKED` so concurrent replicas never claim the same job.
     ///
     /// # Errors
     ///
-    /// Returns an error if the database insertion fails.
-    pub async fn create_job(
-        pool: &PgPool,
-        url: &str,
-        doc_type: &str,
-    ) -> Result<crate::models::IngestJob> {
-        let job_id = uuid::Uuid::new_v4();
-        let now = chrono::Utc::now();
-
-        let row = sqlx::query_as::<_, crate::models::IngestJob>(
+    /// Returns an error if the database update fails.
+    pub async fn claim_interrupted_jobs(pool: &PgPool) -> Result<Vec<crate::models::CrateJob>> {
+        let jobs = sqlx::query_as::<_, crate::models::CrateJob>(
             r"
-            INSERT INTO ingest_jobs (
-                id, url, doc_type, status, started_at, created_at, updated_at
-            ) VALUES ($1, $2, $3, 'queued', $4, $4, $4)
+            UPDATE crate_jobs
+            SET status = 'running', error = NULL, updated_at = CURRENT_TIMESTAMP
+            WHERE id IN (
+                SELECT id FROM crate_jobs
+                WHERE status = 'queued' AND operation = 'add_crate' AND error = $1
+                FOR UPDATE SKIP LOCKED
+            )
             RETURNING *
             ",
         )
-        .bind(job_id)
-        .bind(url)
-        .bind(doc_type)
-        .bind(now)
-        .fetch_one(pool)
+        .bind(INTERRUPTED_JOB_NOTE)
+        .fetch_all(pool)
         .await?;
 
-        Ok(row)
+        Ok(jobs)
     }
 
-    /// Find ingest job by ID
+    /// Find job by ID
     ///
     /// # Errors
     ///
@@ -1214,18 +3051,22 @@ impl IngestJobQueries {
     pub async fn find_job_by_id(
         pool: &PgPool,
         job_id: uuid::Uuid,
-    ) -> Result<Option<crate::models::IngestJob>> {
-        let row = sqlx::query_as::<_, crate::models::IngestJob>(
-            "SELECT * FROM ingest_jobs WHERE id = $1",
-        )
-        .bind(job_id)
-        .fetch_optional(pool)
+    ) -> Result<Option<crate::models::CrateJob>> {
+        let row = execute_with_retry("find_job_by_id", || {
+            sqlx::query_as::<_, crate::models::CrateJob>("SELECT * FROM crate_jobs WHERE id = $1")
+                .bind(job_id)
+                .fetch_optional(pool)
+        })
         .await?;
 
         Ok(row)
     }
 
-    /// Update ingest job status
+    /// Update job status
+    ///
+    /// A status change, a new progress value or an error is recorded in
+    /// `crate_job_events` in the same transaction; heartbeats that change
+    /// neither leave no event.
     ///
     /// # Errors
     ///
@@ -1234,9 +3075,9 @@ impl IngestJobQueries {
         pool: &PgPool,
         job_id: uuid::Uuid,
         status: crate::models::JobStatus,
-        output: Option<&str>,
+        progress: Option<i32>,
         error: Option<&str>,
-    ) -> Result<crate::models::IngestJob> {
+    ) -> Result<crate::models::CrateJob> {
         let now = chrono::Utc::now();
         let finished_at = if matches!(
             status,
@@ -1249,173 +3090,1956 @@ impl IngestJobQueries {
             None
         };
 
-        let row = sqlx::query_as::<_, crate::models::IngestJob>(
+        // Replay the whole transaction: a lost commit leaves the status already
+        // set, so the replay records no second event
+        let status = &status;
+        execute_with_retry("update_job_status", || async move {
+            let mut tx = pool.begin().await?;
+            let previous = sqlx::query_as::<_, (crate::models::JobStatus, Option<i32>)>(
+                "SELECT status, progress FROM crate_jobs WHERE id = $1 FOR UPDATE",
+            )
+            .bind(job_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            let row = sqlx::query_as::<_, crate::models::CrateJob>(
+                r"
+                UPDATE crate_jobs
+                SET status = $2,
+                    progress = $3,
+                    error = $4,
+                    finished_at = $5,
+                    -- Set started_at when transitioning into running for the first time
+                    started_at = CASE
+                        WHEN $2 = 'running'::job_status AND status <> 'running'::job_status THEN $6
+                        ELSE started_at
+                    END,
+                    updated_at = $6
+                WHERE id = $1
+                RETURNING *
+                ",
+            )
+            .bind(job_id)
+            .bind(status)
+            .bind(progress)
+            .bind(error)
+            .bind(finished_at)
+            .bind(now)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            if let Some((old_status, old_progress)) = previous {
+                let status_changed = old_status != *status;
+                if status_changed || (progress.is_some() && progress != old_progress) || error.is_some()
+                {
+                    sqlx::query(
+                        r"
+                        INSERT INTO crate_job_events (job_id, created_at, event_type, old_status, new_status, progress, detail)
+                        VALUES ($1, $2, $3, $4, $5, $6, $7)
+                        ",
+                    )
+                    .bind(job_id)
+                    .bind(now)
+                    .bind(if status_changed { "status_changed" } else { "progress" })
+                    .bind(old_status)
+                    .bind(status)
+                    .bind(progress)
+                    .bind(error.map(|error| serde_json::json!({ "error": error })))
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+            tx.commit().await?;
+
+            Ok(row)
+        })
+        .await
+    }
+
+    /// Append an informational event to a job's audit trail
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database insertion fails.
+    pub async fn record_job_event(
+        pool: &PgPool,
+        job_id: uuid::Uuid,
+        event_type: &str,
+        detail: &serde_json::Value,
+    ) -> Result<()> {
+        sqlx::query(
             r"
-            UPDATE ingest_jobs
-            SET status = $2,
-                output = COALESCE($3, output),
-                error = COALESCE($4, error),
-                finished_at = COALESCE($5, finished_at),
-                -- Set started_at when transitioning into running for the first time
-                started_at = CASE
-                    WHEN $2 = 'running'::job_status AND status <> 'running'::job_status THEN $6
-                    ELSE started_at
-                END,
-                updated_at = $6
-            WHERE id = $1
-            RETURNING *
+            INSERT INTO crate_job_events (job_id, event_type, new_status, progress, detail)
+            SELECT id, $2, status, progress, $3 FROM crate_jobs WHERE id = $1
             ",
         )
         .bind(job_id)
-        .bind(status)
-        .bind(output)
-        .bind(error)
-        .bind(finished_at)
-        .bind(now)
-        .fetch_one(pool)
+        .bind(event_type)
+        .bind(detail)
+        .execute(pool)
         .await?;
 
-        Ok(row)
+        Ok(())
     }
 
-    /// Clean up old ingest jobs (older than 30 days and completed/failed/cancelled)
+    /// Most recent `limit` events of a job, newest first
     ///
     /// # Errors
     ///
-    /// Returns an error if the cleanup operation fails.
-    pub async fn cleanup_old_jobs(pool: &PgPool) -> Result<i32> {
+    /// Returns an error if the database query fails.
+    pub async fn find_job_events(
+        pool: &PgPool,
+        job_id: uuid::Uuid,
+        limit: i64,
+    ) -> Result<Vec<crate::models::CrateJobEvent>> {
+        let rows = sqlx::query_as::<_, crate::models::CrateJobEvent>(
+            "SELECT * FROM crate_job_events WHERE job_id = $1 ORDER BY id DESC LIMIT $2",
+        )
+        .bind(job_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Delete job events older than the 30 days [`Self::cleanup_old_jobs`] keeps jobs
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database delete fails.
+    pub async fn prune_job_events(pool: &PgPool) -> Result<u64> {
         let result = sqlx::query(
-            r"
-            DELETE FROM ingest_jobs
-            WHERE status IN ('completed', 'failed', 'cancelled')
-              AND finished_at < CURRENT_TIMESTAMP - INTERVAL '30 days'
-            ",
+            "DELETE FROM crate_job_events WHERE created_at < CURRENT_TIMESTAMP - INTERVAL '30 days'",
         )
         .execute(pool)
         .await?;
 
-        Ok(i32::try_from(result.rows_affected()).unwrap_or(i32::MAX))
+        Ok(result.rows_affected())
     }
-}
 
-/// Crate-related query operations (using documents table only)
-pub struct CrateQueries;
+    /// Find active jobs (queued or running)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_active_jobs(pool: &PgPool) -> Result<Vec<crate::models::CrateJob>> {
+        let rows = execute_with_retry("find_active_jobs", || {
+            sqlx::query_as::<_, crate::models::CrateJob>(
+                r"
+                SELECT * FROM crate_jobs 
+                WHERE status IN ('queued', 'running')
+                ORDER BY created_at ASC
+                ",
+            )
+            .fetch_all(pool)
+        })
+        .await?;
 
-impl CrateQueries {
-    /// Get list of crates from document metadata with pagination
+        Ok(rows)
+    }
+
+    /// Most recently started `limit` jobs of any status, newest first
     ///
     /// # Errors
     ///
     /// Returns an error if the database query fails.
-    #[allow(clippy::too_many_lines)]
-    pub async fn list_crates(
+    pub async fn find_recent_jobs(
         pool: &PgPool,
-        pagination: &crate::models::PaginationParams,
-        name_pattern: Option<&str>,
-    ) -> Result<crate::models::PaginatedResponse<crate::models::CrateInfo>> {
-        // Build the base query for crate information from documents
-        let mut query_parts = vec![r"
-            WITH crate_stats AS (
-                SELECT 
-                    metadata->>'crate_name' as crate_name,
-                    metadata->>'crate_version' as crate_version,
-                    COUNT(*) as total_docs,
-                    COALESCE(SUM(token_count), 0) as total_tokens,
-                    MAX(created_at) as last_updated
-                FROM documents 
-                WHERE doc_type = 'rust' 
-                AND metadata->>'crate_name' IS NOT NULL
-            "
-        .to_string()];
+        limit: i64,
+    ) -> Result<Vec<crate::models::CrateJob>> {
+        let rows = execute_with_retry("find_recent_jobs", || {
+            sqlx::query_as::<_, crate::models::CrateJob>(
+                "SELECT * FROM crate_jobs ORDER BY started_at DESC LIMIT $1",
+            )
+            .bind(limit)
+            .fetch_all(pool)
+        })
+        .await?;
 
-        // Add name pattern filter if provided
-        if name_pattern.is_some() {
-            query_parts.push("AND metadata->>'crate_name' ILIKE $3".to_string());
-        }
+        Ok(rows)
+    }
 
-        query_parts.push(
-            r"
-                GROUP BY metadata->>'crate_name', metadata->>'crate_version'
+    /// Jobs started since `since` that recorded `job_costs` in their details, oldest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_jobs_with_costs(
+        pool: &PgPool,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<crate::models::CrateJob>> {
+        let rows = execute_with_retry("find_jobs_with_costs", || {
+            sqlx::query_as::<_, crate::models::CrateJob>(
+                "SELECT * FROM crate_jobs WHERE started_at >= $1 AND details ? 'job_costs' ORDER BY started_at",
             )
-            SELECT 
-                COALESCE(crate_name, 'unknown') as name,
-                COALESCE(crate_version, 'latest') as version,
-                '' as description,
+            .bind(since)
+            .fetch_all(pool)
+        })
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Most recently started `limit` jobs, optionally only those in `status`
+    /// or for `crate_name`, newest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn list_jobs(
+        pool: &PgPool,
+        status: Option<&crate::models::JobStatus>,
+        crate_name: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<crate::models::CrateJob>> {
+        let rows = execute_with_retry("list_jobs", || {
+            sqlx::query_as::<_, crate::models::CrateJob>(
+                r"
+                SELECT * FROM crate_jobs
+                WHERE ($1::job_status IS NULL OR status = $1)
+                  AND ($2::text IS NULL OR crate_name = $2)
+                ORDER BY started_at DESC
+                LIMIT $3
+                ",
+            )
+            .bind(status)
+            .bind(crate_name)
+            .bind(limit)
+            .fetch_all(pool)
+        })
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Find the most recently started job for a crate
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_latest_job_for_crate(
+        pool: &PgPool,
+        crate_name: &str,
+    ) -> Result<Option<crate::models::CrateJob>> {
+        let row = execute_with_retry("find_latest_job_for_crate", || {
+            sqlx::query_as::<_, crate::models::CrateJob>(
+                "SELECT * FROM crate_jobs WHERE crate_name = $1 ORDER BY started_at DESC LIMIT 1",
+            )
+            .bind(crate_name)
+            .fetch_optional(pool)
+        })
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Save the crawl checkpoint for a job
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database update fails.
+    pub async fn save_crawl_state(
+        pool: &PgPool,
+        job_id: uuid::Uuid,
+        state: &serde_json::Value,
+    ) -> Result<()> {
+        execute_with_retry("save_crawl_state", || {
+            sqlx::query(
+                "UPDATE crate_jobs SET crate_job_state = $2, updated_at = CURRENT_TIMESTAMP WHERE id = $1",
+            )
+            .bind(job_id)
+            .bind(state)
+            .execute(pool)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    /// Load the crawl checkpoint saved for a job, if any
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn load_crawl_state(
+        pool: &PgPool,
+        job_id: uuid::Uuid,
+    ) -> Result<Option<serde_json::Value>> {
+        let state = execute_with_retry("load_crawl_state", || {
+            sqlx::query_scalar::<_, Option<serde_json::Value>>(
+                "SELECT crate_job_state FROM crate_jobs WHERE id = $1",
+            )
+            .bind(job_id)
+            .fetch_optional(pool)
+        })
+        .await?;
+
+        Ok(state.flatten())
+    }
+
+    /// Clear the crawl checkpoint for a job once it no longer applies
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database update fails.
+    pub async fn clear_crawl_state(pool: &PgPool, job_id: uuid::Uuid) -> Result<()> {
+        sqlx::query("UPDATE crate_jobs SET crate_job_state = NULL WHERE id = $1")
+            .bind(job_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Merge `details` into a job's details without changing its status
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database update fails.
+    pub async fn merge_job_details(
+        pool: &PgPool,
+        job_id: uuid::Uuid,
+        details: &serde_json::Value,
+    ) -> Result<()> {
+        execute_with_retry("merge_job_details", || {
+            sqlx::query(
+                "UPDATE crate_jobs SET details = COALESCE(details, '{}'::jsonb) || $2 WHERE id = $1",
+            )
+            .bind(job_id)
+            .bind(details)
+            .execute(pool)
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    /// Keep a running job's `updated_at` fresh
+    ///
+    /// Returns `false` once the job is no longer running.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database update fails.
+    pub async fn heartbeat(pool: &PgPool, job_id: uuid::Uuid) -> Result<bool> {
+        touch_running_job(pool, "crate_jobs", job_id).await
+    }
+
+    /// Running jobs without an update since `cutoff`, oldest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_stale_jobs(
+        pool: &PgPool,
+        cutoff: DateTime<Utc>,
+    ) -> Result<Vec<crate::models::CrateJob>> {
+        find_stale_jobs(pool, "crate_jobs", cutoff).await
+    }
+
+    /// Mark running jobs without an update since `cutoff` failed, appending
+    /// `reason` to their error
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database update fails.
+    pub async fn fail_stale_jobs(
+        pool: &PgPool,
+        cutoff: DateTime<Utc>,
+        reason: &str,
+    ) -> Result<u64> {
+        fail_stale_jobs(pool, "crate_jobs", cutoff, reason).await
+    }
+
+    /// Clean up old completed jobs
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cleanup operation fails.
+    pub async fn cleanup_old_jobs(pool: &PgPool) -> Result<i32> {
+        let result = sqlx::query(
+            r"
+            DELETE FROM crate_jobs 
+            WHERE status IN ('completed', 'failed', 'cancelled') 
+            AND finished_at < CURRENT_TIMESTAMP - INTERVAL '30 days'
+            ",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(i32::try_from(result.rows_affected()).unwrap_or(i32::MAX))
+    }
+}
+
+/// Embedding cache query operations
+///
+/// Entries are keyed by the SHA-256 of the embedded text together with the
+/// model and dimension, so switching models never returns stale vectors.
+pub struct EmbeddingCacheQueries;
+
+impl EmbeddingCacheQueries {
+    /// Look up cached embeddings for a set of content hashes
+    ///
+    /// Hits are marked as used so that eviction keeps them. Returns the
+    /// embeddings keyed by content hash; missing hashes are absent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn lookup(
+        pool: &PgPool,
+        hashes: &[String],
+        model: &str,
+        dimensions: i32,
+    ) -> Result<HashMap<String, Vec<f32>>> {
+        if hashes.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let rows = execute_with_retry("embedding_cache_lookup", || {
+            sqlx::query_as::<_, (String, Vec<f32>)>(
+                r"
+                UPDATE embedding_cache
+                SET last_used_at = NOW()
+                WHERE content_sha256 = ANY($1) AND model = $2 AND dimensions = $3
+                RETURNING content_sha256, embedding
+                ",
+            )
+            .bind(hashes)
+            .bind(model)
+            .bind(dimensions)
+            .fetch_all(pool)
+        })
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Store embeddings for a set of content hashes in a single statement
+    ///
+    /// Existing entries are kept and only marked as used.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn store(
+        pool: &PgPool,
+        entries: &[(String, Vec<f32>)],
+        model: &str,
+        dimensions: i32,
+    ) -> Result<u64> {
+        if entries.is_empty() {
+            return Ok(0);
+        }
+
+        let result = execute_with_retry("embedding_cache_store", || async move {
+            let mut builder = sqlx::QueryBuilder::<sqlx::Postgres>::new(
+                "INSERT INTO embedding_cache (content_sha256, model, dimensions, embedding) ",
+            );
+            builder.push_values(entries, |mut row, (hash, embedding)| {
+                row.push_bind(hash)
+                    .push_bind(model)
+                    .push_bind(dimensions)
+                    .push_bind(embedding);
+            });
+            builder.push(
+                " ON CONFLICT (content_sha256, model, dimensions) DO UPDATE SET last_used_at = NOW()",
+            );
+            builder.build().execute(pool).await
+        })
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Evict entries that have not been used for `max_age_days`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn evict_unused(pool: &PgPool, max_age_days: i32) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM embedding_cache WHERE last_used_at < NOW() - make_interval(days => $1)",
+        )
+        .bind(max_age_days)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// Fetch cache query operations
+///
+/// Entries are keyed by URL and crate version so a re-crawl can send
+/// conditional requests for the pages it stored last time.
+pub struct FetchCacheQueries;
+
+impl FetchCacheQueries {
+    /// Look up the cached entry for a page of `source_name`
+    ///
+    /// Entries whose page has no stored document are ignored, so a `304`
+    /// never stands in for a document that was removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn lookup(
+        pool: &PgPool,
+        source_name: &str,
+        crate_version: &str,
+        url: &str,
+    ) -> Result<Option<crate::models::FetchCacheEntry>> {
+        let entry = execute_with_retry("fetch_cache_lookup", || {
+            sqlx::query_as::<_, crate::models::FetchCacheEntry>(
+                r"
+                SELECT fc.url, fc.etag, fc.last_modified, fc.links
+                FROM fetch_cache fc
+                WHERE fc.url = $1 AND fc.crate_version = $2
+                  AND EXISTS (
+                      SELECT 1 FROM documents d
+                      WHERE d.doc_type = 'rust' AND d.source_name = $3
+                        AND d.metadata->>'source_url' = fc.url
+                  )
+                ",
+            )
+            .bind(url)
+            .bind(crate_version)
+            .bind(source_name)
+            .fetch_optional(pool)
+        })
+        .await?;
+
+        Ok(entry)
+    }
+
+    /// Insert or replace entries for `crate_version` in a single statement
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn store(
+        pool: &PgPool,
+        crate_version: &str,
+        entries: &[crate::models::FetchCacheEntry],
+    ) -> Result<u64> {
+        if entries.is_empty() {
+            return Ok(0);
+        }
+
+        let result = execute_with_retry("fetch_cache_store", || async move {
+            let mut builder = sqlx::QueryBuilder::<sqlx::Postgres>::new(
+                "INSERT INTO fetch_cache (url, crate_version, etag, last_modified, links) ",
+            );
+            builder.push_values(entries, |mut row, entry| {
+                row.push_bind(&entry.url)
+                    .push_bind(crate_version)
+                    .push_bind(&entry.etag)
+                    .push_bind(&entry.last_modified)
+                    .push_bind(&entry.links);
+            });
+            builder.push(
+                " ON CONFLICT (url, crate_version) DO UPDATE SET etag = EXCLUDED.etag, last_modified = EXCLUDED.last_modified, links = EXCLUDED.links, fetched_at = NOW()",
+            );
+            builder.build().execute(pool).await
+        })
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// Tool audit log query operations
+pub struct ToolAuditQueries;
+
+impl ToolAuditQueries {
+    /// Append a batch of audit entries in a single statement
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database insertion fails.
+    pub async fn insert_batch(
+        pool: &PgPool,
+        entries: &[crate::models::NewToolAuditEntry],
+    ) -> Result<u64> {
+        if entries.is_empty() {
+            return Ok(0);
+        }
+
+        let mut builder = sqlx::QueryBuilder::<sqlx::Postgres>::new(
+            "INSERT INTO tool_audit_log (created_at, session_id, client_label, user_agent, tool_name, arguments, success, duration_ms, error) ",
+        );
+        builder.push_values(entries, |mut row, entry| {
+            row.push_bind(entry.created_at)
+                .push_bind(&entry.session_id)
+                .push_bind(&entry.client_label)
+                .push_bind(&entry.user_agent)
+                .push_bind(&entry.tool_name)
+                .push_bind(&entry.arguments)
+                .push_bind(entry.success)
+                .push_bind(entry.duration_ms)
+                .push_bind(&entry.error);
+        });
+
+        let result = builder.build().execute(pool).await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Entries matching `filter`, newest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_entries(
+        pool: &PgPool,
+        filter: &crate::models::ToolAuditFilter,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<crate::models::ToolAuditEntry>> {
+        let mut builder =
+            sqlx::QueryBuilder::<sqlx::Postgres>::new("SELECT * FROM tool_audit_log WHERE TRUE");
+        if let Some(since) = filter.since {
+            builder.push(" AND created_at >= ").push_bind(since);
+        }
+        if let Some(until) = filter.until {
+            builder.push(" AND created_at < ").push_bind(until);
+        }
+        if let Some(tool_name) = &filter.tool_name {
+            builder.push(" AND tool_name = ").push_bind(tool_name);
+        }
+        if let Some(session_id) = &filter.session_id {
+            builder.push(" AND session_id = ").push_bind(session_id);
+        }
+        builder
+            .push(" ORDER BY created_at DESC, id DESC LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+
+        let rows = builder
+            .build_query_as::<crate::models::ToolAuditEntry>()
+            .fetch_all(pool)
+            .await?;
+        Ok(rows)
+    }
+
+    /// Delete entries older than `retention_days`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn prune(pool: &PgPool, retention_days: i32) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM tool_audit_log WHERE created_at < NOW() - make_interval(days => $1)",
+        )
+        .bind(retention_days)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// Runtime tool enable/disable overrides
+pub struct ToolSettingQueries;
+
+impl ToolSettingQueries {
+    /// Every stored override as `(tool_name, enabled)`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn list(pool: &PgPool) -> Result<Vec<(String, bool)>> {
+        let rows = sqlx::query_as::<_, (String, bool)>(
+            "SELECT tool_name, enabled FROM tool_settings ORDER BY tool_name",
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Store whether `tool_name` is enabled, recording who changed it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn set_enabled(
+        pool: &PgPool,
+        tool_name: &str,
+        enabled: bool,
+        updated_by: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r"
+            INSERT INTO tool_settings (tool_name, enabled, updated_by, updated_at)
+            VALUES ($1, $2, $3, NOW())
+            ON CONFLICT (tool_name) DO UPDATE
+            SET enabled = EXCLUDED.enabled,
+                updated_by = EXCLUDED.updated_by,
+                updated_at = NOW()
+            ",
+        )
+        .bind(tool_name)
+        .bind(enabled)
+        .bind(updated_by)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Webhook subscriptions and their delivery log
+pub struct WebhookQueries;
+
+impl WebhookQueries {
+    /// Subscribe `url` to `events`, signing deliveries with `secret`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database insertion fails.
+    pub async fn create(
+        pool: &PgPool,
+        url: &str,
+        secret: &str,
+        events: &[String],
+    ) -> Result<crate::models::Webhook> {
+        let row = sqlx::query_as::<_, crate::models::Webhook>(
+            r"
+            INSERT INTO webhooks (id, url, secret, events)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            ",
+        )
+        .bind(uuid::Uuid::new_v4())
+        .bind(url)
+        .bind(secret)
+        .bind(events)
+        .fetch_one(pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// Every subscription, oldest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn list(pool: &PgPool) -> Result<Vec<crate::models::Webhook>> {
+        let rows = sqlx::query_as::<_, crate::models::Webhook>(
+            "SELECT * FROM webhooks ORDER BY created_at, id",
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Delete a subscription; its deliveries stay in the log
+    ///
+    /// Returns whether the subscription existed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn delete(pool: &PgPool, id: uuid::Uuid) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM webhooks WHERE id = $1")
+            .bind(id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Log a pending delivery of `event` to `url`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database insertion fails.
+    pub async fn create_delivery(
+        pool: &PgPool,
+        webhook_id: Option<uuid::Uuid>,
+        url: &str,
+        event: &str,
+        payload: &serde_json::Value,
+    ) -> Result<crate::models::WebhookDelivery> {
+        let row = sqlx::query_as::<_, crate::models::WebhookDelivery>(
+            r"
+            INSERT INTO webhook_deliveries (id, webhook_id, url, event, payload, status)
+            VALUES ($1, $2, $3, $4, $5, 'pending')
+            RETURNING *
+            ",
+        )
+        .bind(uuid::Uuid::new_v4())
+        .bind(webhook_id)
+        .bind(url)
+        .bind(event)
+        .bind(payload)
+        .fetch_one(pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// Record how a delivery ended
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database update fails.
+    pub async fn finish_delivery(
+        pool: &PgPool,
+        id: uuid::Uuid,
+        delivered: bool,
+        attempts: i32,
+        last_status_code: Option<i32>,
+        last_error: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r"
+            UPDATE webhook_deliveries
+            SET status = CASE WHEN $2 THEN 'delivered' ELSE 'failed' END,
+                attempts = $3, last_status_code = $4, last_error = $5,
+                delivered_at = CASE WHEN $2 THEN CURRENT_TIMESTAMP END
+            WHERE id = $1
+            ",
+        )
+        .bind(id)
+        .bind(delivered)
+        .bind(attempts)
+        .bind(last_status_code)
+        .bind(last_error)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Deliveries newest first, optionally of one webhook or with one status
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn list_deliveries(
+        pool: &PgPool,
+        webhook_id: Option<uuid::Uuid>,
+        status: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<crate::models::WebhookDelivery>> {
+        let rows = sqlx::query_as::<_, crate::models::WebhookDelivery>(
+            r"
+            SELECT * FROM webhook_deliveries
+            WHERE ($1::uuid IS NULL OR webhook_id = $1)
+              AND ($2::text IS NULL OR status = $2)
+            ORDER BY created_at DESC, id
+            LIMIT $3
+            ",
+        )
+        .bind(webhook_id)
+        .bind(status)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Delete deliveries older than `retention_days`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn prune_deliveries(pool: &PgPool, retention_days: i32) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM webhook_deliveries WHERE created_at < NOW() - make_interval(days => $1)",
+        )
+        .bind(retention_days)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// Ingest job query operations
+pub struct IngestJobQueries;
+
+impl IngestJobQueries {
+    /// Create a new ingest job
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database insertion fails.
+    pub async fn create_job(
+        pool: &PgPool,
+        url: &str,
+        doc_type: &str,
+    ) -> Result<crate::models::IngestJob> {
+        let job_id = uuid::Uuid::new_v4();
+        let now = chrono::Utc::now();
+
+        let row = sqlx::query_as::<_, crate::models::IngestJob>(
+            r"
+            INSERT INTO ingest_jobs (
+                id, url, doc_type, status, started_at, created_at, updated_at
+            ) VALUES ($1, $2, $3, 'queued', $4, $4, $4)
+            RETURNING *
+            ",
+        )
+        .bind(job_id)
+        .bind(url)
+        .bind(doc_type)
+        .bind(now)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Find ingest job by ID
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_job_by_id(
+        pool: &PgPool,
+        job_id: uuid::Uuid,
+    ) -> Result<Option<crate::models::IngestJob>> {
+        let row = sqlx::query_as::<_, crate::models::IngestJob>(
+            "SELECT * FROM ingest_jobs WHERE id = $1",
+        )
+        .bind(job_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Update ingest job status
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database update fails.
+    pub async fn update_job_status(
+        pool: &PgPool,
+        job_id: uuid::Uuid,
+        status: crate::models::JobStatus,
+        output: Option<&str>,
+        error: Option<&str>,
+    ) -> Result<crate::models::IngestJob> {
+        let now = chrono::Utc::now();
+        let finished_at = if matches!(
+            status,
+            crate::models::JobStatus::Completed
+                | crate::models::JobStatus::Failed
+                | crate::models::JobStatus::Cancelled
+        ) {
+            Some(now)
+        } else {
+            None
+        };
+
+        let row = sqlx::query_as::<_, crate::models::IngestJob>(
+            r"
+            UPDATE ingest_jobs
+            SET status = $2,
+                output = COALESCE($3, output),
+                error = COALESCE($4, error),
+                finished_at = COALESCE($5, finished_at),
+                -- Set started_at when transitioning into running for the first time
+                started_at = CASE
+                    WHEN $2 = 'running'::job_status AND status <> 'running'::job_status THEN $6
+                    ELSE started_at
+                END,
+                updated_at = $6
+            WHERE id = $1
+            RETURNING *
+            ",
+        )
+        .bind(job_id)
+        .bind(status)
+        .bind(output)
+        .bind(error)
+        .bind(finished_at)
+        .bind(now)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Queued and running ingest jobs, oldest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_active_jobs(pool: &PgPool) -> Result<Vec<crate::models::IngestJob>> {
+        let rows = execute_with_retry("find_active_ingest_jobs", || {
+            sqlx::query_as::<_, crate::models::IngestJob>(
+                "SELECT * FROM ingest_jobs WHERE status IN ('queued', 'running') ORDER BY created_at",
+            )
+            .fetch_all(pool)
+        })
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Completed, failed and cancelled ingest jobs, most recently finished first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn list_finished_jobs(
+        pool: &PgPool,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<crate::models::IngestJob>> {
+        let rows = execute_with_retry("list_finished_ingest_jobs", || {
+            sqlx::query_as::<_, crate::models::IngestJob>(
+                r"
+                SELECT * FROM ingest_jobs
+                WHERE status IN ('completed', 'failed', 'cancelled')
+                ORDER BY COALESCE(finished_at, updated_at) DESC, id
+                LIMIT $1 OFFSET $2
+                ",
+            )
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(pool)
+        })
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Count running ingest jobs without an update for
+    /// [`crate::models::STUCK_JOB_MINUTES`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn count_stuck_jobs(pool: &PgPool) -> Result<i64> {
+        let count = execute_with_retry("count_stuck_ingest_jobs", || {
+            sqlx::query_scalar::<_, i64>(
+                r"
+                SELECT COUNT(*) FROM ingest_jobs
+                WHERE status = 'running'
+                  AND updated_at < NOW() - make_interval(mins => $1::int)
+                ",
+            )
+            .bind(i32::try_from(crate::models::STUCK_JOB_MINUTES).unwrap_or(i32::MAX))
+            .fetch_one(pool)
+        })
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Keep a running job's `updated_at` fresh
+    ///
+    /// Returns `false` once the job is no longer running.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database update fails.
+    pub async fn heartbeat(pool: &PgPool, job_id: uuid::Uuid) -> Result<bool> {
+        touch_running_job(pool, "ingest_jobs", job_id).await
+    }
+
+    /// Running jobs without an update since `cutoff`, oldest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_stale_jobs(
+        pool: &PgPool,
+        cutoff: DateTime<Utc>,
+    ) -> Result<Vec<crate::models::IngestJob>> {
+        find_stale_jobs(pool, "ingest_jobs", cutoff).await
+    }
+
+    /// Mark running jobs without an update since `cutoff` failed, appending
+    /// `reason` to their error
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database update fails.
+    pub async fn fail_stale_jobs(
+        pool: &PgPool,
+        cutoff: DateTime<Utc>,
+        reason: &str,
+    ) -> Result<u64> {
+        fail_stale_jobs(pool, "ingest_jobs", cutoff, reason).await
+    }
+
+    /// Clean up old ingest jobs (older than 30 days and completed/failed/cancelled)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cleanup operation fails.
+    pub async fn cleanup_old_jobs(pool: &PgPool) -> Result<i32> {
+        let result = sqlx::query(
+            r"
+            DELETE FROM ingest_jobs
+            WHERE status IN ('completed', 'failed', 'cancelled')
+              AND finished_at < CURRENT_TIMESTAMP - INTERVAL '30 days'
+            ",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(i32::try_from(result.rows_affected()).unwrap_or(i32::MAX))
+    }
+}
+
+/// Crate-related query operations (using documents table only)
+pub struct CrateQueries;
+
+impl CrateQueries {
+    /// Get list of active crates from document metadata with pagination
+    ///
+    /// Soft-deleted (inactive) crates are excluded; use
+    /// [`Self::list_crates_with_status`] to include them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn list_crates(
+        pool: &PgPool,
+        pagination: &crate::models::PaginationParams,
+        name_pattern: Option<&str>,
+    ) -> Result<crate::models::PaginatedResponse<crate::models::CrateInfo>> {
+        Self::list_crates_with_status(
+            pool,
+            pagination,
+            name_pattern,
+            crate::models::CrateStatusFilter::Active,
+        )
+        .await
+    }
+
+    /// Get list of crates filtered by soft-delete status with pagination
+    ///
+    /// Rows are ordered by the pagination's sort column, then name and
+    /// version. With a cursor the page starts after the cursor's row by
+    /// comparing on those keys, so deep pages cost no more than the first;
+    /// `next_cursor` is set whenever another row follows the page.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    #[allow(clippy::too_many_lines)]
+    pub async fn list_crates_with_status(
+        pool: &PgPool,
+        pagination: &crate::models::PaginationParams,
+        name_pattern: Option<&str>,
+        status: crate::models::CrateStatusFilter,
+    ) -> Result<crate::models::PaginatedResponse<crate::models::CrateInfo>> {
+        // Build the base query for crate information from documents
+        let mut query_parts = vec![
+            r"
+            WITH crate_stats AS (
+                SELECT 
+                    metadata->>'crate_name' as crate_name,
+                    metadata->>'crate_version' as crate_version,
+                    COUNT(*) as total_docs,
+                    COALESCE(SUM(token_count), 0) as total_tokens,
+                    COUNT(embedding) as embedded_docs,
+                    MAX(created_at) as last_updated
+                FROM documents 
+                WHERE doc_type = 'rust' 
+                AND metadata->>'crate_name' IS NOT NULL
+            "
+            .to_string(),
+            format!("AND {}", status.sql_predicate()),
+        ];
+
+        // Add name pattern filter if provided
+        if name_pattern.is_some() {
+            query_parts.push(format!(
+                r"AND {} LIKE $3 ESCAPE '\'",
+                crate::models::CRATE_NAME_KEY_SQL
+            ));
+        }
+
+        query_parts.push(
+            r"
+                GROUP BY metadata->>'crate_name', metadata->>'crate_version'
+            )
+            SELECT 
+                COALESCE(crate_name, 'unknown') as name,
+                COALESCE(crate_version, 'latest') as version,
+                '' as description,
                 '' as documentation_url,
                 total_docs::int as total_docs,
                 total_tokens,
+                embedded_docs,
                 last_updated
             FROM crate_stats
-            WHERE crate_name IS NOT NULL
-            ORDER BY crate_name
-            LIMIT $1 OFFSET $2
-        "
-            .to_string(),
+            WHERE crate_name IS NOT NULL
+        "
+            .to_string(),
+        );
+
+        // Sort column and direction come from whitelisted enums, never from input
+        let sort_column = pagination.sort_by.sql_column();
+        let direction = pagination.sort_order.sql_keyword();
+        if let Some(cursor) = &pagination.cursor {
+            let first = if name_pattern.is_some() { 4 } else { 3 };
+            let comparison = match pagination.sort_order {
+                crate::models::SortOrder::Asc => ">",
+                crate::models::SortOrder::Desc => "<",
+            };
+            query_parts.push(format!(
+                "AND ({sort_column}, crate_stats.crate_name, COALESCE(crate_stats.crate_version, 'latest')) {comparison} (${first}::{}, ${}, ${})",
+                cursor.sort_by.sql_type(),
+                first + 1,
+                first + 2
+            ));
+        }
+        query_parts.push(format!(
+            "ORDER BY {sort_column} {direction}, crate_stats.crate_name {direction}, COALESCE(crate_stats.crate_version, 'latest') {direction}"
+        ));
+        // One extra row tells whether a next page exists
+        query_parts.push("LIMIT $1 OFFSET $2".to_string());
+
+        let query_str = query_parts.join(" ");
+
+        // Execute main query
+        let mut query = sqlx::query(&query_str)
+            .bind(pagination.limit + 1)
+            .bind(pagination.offset);
+
+        if let Some(pattern) = name_pattern {
+            query = query.bind(crate::models::crate_name_like_pattern(pattern));
+        }
+        if let Some(cursor) = &pagination.cursor {
+            query = query
+                .bind(&cursor.key)
+                .bind(&cursor.name)
+                .bind(&cursor.version);
+        }
+
+        let mut rows = query.fetch_all(pool).await?;
+        let has_more = rows.len() > usize::try_from(pagination.limit).unwrap_or(usize::MAX);
+        rows.truncate(usize::try_from(pagination.limit).unwrap_or(usize::MAX));
+
+        // Get total count: one item per (crate, version), like the rows
+        let mut count_query_parts = vec![
+            r"
+            SELECT COUNT(DISTINCT (metadata->>'crate_name', COALESCE(metadata->>'crate_version', 'latest')))
+            FROM documents 
+            WHERE doc_type = 'rust' 
+            AND metadata->>'crate_name' IS NOT NULL
+            "
+            .to_string(),
+            format!("AND {}", status.sql_predicate()),
+        ];
+
+        if name_pattern.is_some() {
+            count_query_parts.push(format!(
+                r"AND {} LIKE $1 ESCAPE '\'",
+                crate::models::CRATE_NAME_KEY_SQL
+            ));
+        }
+
+        let count_query_str = count_query_parts.join(" ");
+        let mut count_query = sqlx::query_scalar::<_, i64>(&count_query_str);
+
+        if let Some(pattern) = name_pattern {
+            count_query = count_query.bind(crate::models::crate_name_like_pattern(pattern));
+        }
+
+        let total_items = count_query.fetch_one(pool).await?;
+
+        // Convert rows to CrateInfo
+        let items: Vec<crate::models::CrateInfo> = rows
+            .into_iter()
+            .map(|row| {
+                let name: String = row.get("name");
+                let version: String = row.get("version");
+                let description: String = row.get("description");
+                let documentation_url: String = row.get("documentation_url");
+                let total_docs: i32 = row.get("total_docs");
+                let total_tokens: i64 = row.get("total_tokens");
+                let embedded_docs: i64 = row.get("embedded_docs");
+                let last_updated: DateTime<Utc> = row.get("last_updated");
+
+                crate::models::CrateInfo {
+                    name,
+                    version,
+                    description: if description.is_empty() {
+                        None
+                    } else {
+                        Some(description)
+                    },
+                    documentation_url: if documentation_url.is_empty() {
+                        None
+                    } else {
+                        Some(documentation_url)
+                    },
+                    total_docs,
+                    total_tokens,
+                    embedded_docs,
+                    embedding_coverage_pct: crate::models::embedding_coverage_pct(
+                        embedded_docs,
+                        i64::from(total_docs),
+                    ),
+                    last_updated,
+                }
+            })
+            .collect();
+
+        Ok(Self::crate_page(items, pagination, has_more, total_items))
+    }
+
+    /// Page of a crate listing, with `next_cursor` set when `has_more`
+    pub(crate) fn crate_page(
+        items: Vec<crate::models::CrateInfo>,
+        pagination: &crate::models::PaginationParams,
+        has_more: bool,
+        total_items: i64,
+    ) -> crate::models::PaginatedResponse<crate::models::CrateInfo> {
+        let next_cursor = items.last().filter(|_| has_more).map(|last| {
+            crate::models::CrateCursor::after(last, pagination.sort_by, pagination.sort_order)
+                .encode()
+        });
+        let mut response = crate::models::PaginatedResponse::new(items, pagination, total_items);
+        if pagination.cursor.is_some() {
+            response.has_previous = true;
+            response.has_next = next_cursor.is_some();
+        }
+        response.next_cursor = next_cursor;
+        response
+    }
+
+    /// Active crates whose recorded dependencies include `crate_name`
+    ///
+    /// Only structured references count: the `dependencies` array in document
+    /// metadata and in the crate's `document_sources.config`. Sources whose
+    /// crate has no active documents left are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_dependents(
+        pool: &PgPool,
+        crate_name: &str,
+    ) -> Result<Vec<crate::models::CrateDependent>> {
+        let active = crate::models::CrateStatusFilter::Active.sql_predicate();
+        let query = format!(
+            r"
+            SELECT DISTINCT ON (crate_name, req, kind) crate_name, crate_version, req, kind
+            FROM (
+                SELECT d.metadata->>'crate_name' AS crate_name,
+                       d.metadata->>'crate_version' AS crate_version,
+                       dep->>'req' AS req,
+                       COALESCE(dep->>'kind', 'normal') AS kind,
+                       0 AS origin
+                FROM documents d
+                CROSS JOIN LATERAL jsonb_array_elements(d.metadata->'dependencies') dep
+                WHERE d.doc_type = 'rust'
+                AND jsonb_typeof(d.metadata->'dependencies') = 'array'
+                AND d.metadata->>'crate_name' IS DISTINCT FROM $1
+                AND dep->>'name' = $1
+                AND {active}
+                UNION ALL
+                SELECT s.source_name, NULL, dep->>'req', COALESCE(dep->>'kind', 'normal'), 1
+                FROM document_sources s
+                CROSS JOIN LATERAL jsonb_array_elements(s.config->'dependencies') dep
+                WHERE s.doc_type = 'rust'
+                AND jsonb_typeof(s.config->'dependencies') = 'array'
+                AND s.source_name <> $1
+                AND dep->>'name' = $1
+                AND EXISTS (
+                    SELECT 1 FROM documents
+                    WHERE doc_type = 'rust' AND source_name = s.source_name AND {active}
+                )
+            ) refs
+            WHERE crate_name IS NOT NULL AND req IS NOT NULL
+            ORDER BY crate_name, req, kind, origin, crate_version DESC
+            "
+        );
+        let dependents = sqlx::query_as::<_, crate::models::CrateDependent>(&query)
+            .bind(crate_name)
+            .fetch_all(pool)
+            .await?;
+
+        Ok(dependents)
+    }
+
+    /// Active crates other than `crate_name` ingested without dependency metadata
+    ///
+    /// Their dependencies are unknown, so they may depend on `crate_name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_crates_without_dependencies(
+        pool: &PgPool,
+        crate_name: &str,
+    ) -> Result<Vec<String>> {
+        let active = crate::models::CrateStatusFilter::Active.sql_predicate();
+        let query = format!(
+            r"
+            SELECT c.crate_name
+            FROM (
+                SELECT metadata->>'crate_name' AS crate_name
+                FROM documents
+                WHERE doc_type = 'rust'
+                AND metadata->>'crate_name' IS NOT NULL
+                AND metadata->>'crate_name' <> $1
+                AND {active}
+                GROUP BY metadata->>'crate_name'
+                HAVING bool_and(NOT (metadata ? 'dependencies'))
+            ) c
+            WHERE NOT EXISTS (
+                SELECT 1 FROM document_sources s
+                WHERE s.doc_type = 'rust'
+                AND s.source_name = c.crate_name
+                AND s.config ? 'dependencies'
+            )
+            ORDER BY c.crate_name
+            "
+        );
+        let names = sqlx::query_scalar::<_, String>(&query)
+            .bind(crate_name)
+            .fetch_all(pool)
+            .await?;
+
+        Ok(names)
+    }
+
+    /// Names of crates whose source sets `config.auto_update` to `false`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_auto_update_opt_outs(pool: &PgPool) -> Result<Vec<String>> {
+        let names = execute_with_retry("find_auto_update_opt_outs", || {
+            sqlx::query_scalar::<_, String>(
+                r"
+                SELECT source_name FROM document_sources
+                WHERE doc_type = 'rust' AND config->>'auto_update' = 'false'
+                ",
+            )
+            .fetch_all(pool)
+        })
+        .await?;
+
+        Ok(names)
+    }
+
+    /// Get crate statistics
+    ///
+    /// `total_crates` counts every crate including soft-deleted ones; document,
+    /// token, and average figures cover active documents only. Crates are
+    /// counted by name, while `total_versions` counts each active
+    /// (name, version) pair.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_crate_statistics(pool: &PgPool) -> Result<crate::models::CrateStatistics> {
+        let active = crate::models::CrateStatusFilter::Active.sql_predicate();
+        let stats_sql = format!(
+            r"
+            WITH crate_stats AS (
+                SELECT 
+                    metadata->>'crate_name' as crate_name,
+                    COUNT(*) FILTER (
+                        WHERE {active}
+                    ) as docs_count,
+                    MAX(created_at) FILTER (
+                        WHERE {active}
+                    ) as last_updated,
+                    COUNT(DISTINCT COALESCE(metadata->>'crate_version', 'latest')) FILTER (
+                        WHERE {active}
+                    ) as versions_count,
+                    COUNT(embedding) FILTER (
+                        WHERE {active}
+                    ) as embedded_count
+                FROM documents 
+                WHERE doc_type = 'rust' 
+                AND metadata->>'crate_name' IS NOT NULL
+                GROUP BY metadata->>'crate_name'
+            )
+            SELECT 
+                COUNT(*)::bigint as total_crates,
+                COUNT(*) FILTER (WHERE docs_count > 0)::bigint as active_crates,
+                COALESCE(SUM(versions_count), 0)::bigint as total_versions,
+                COALESCE(SUM(docs_count), 0)::bigint as total_docs,
+                COALESCE(SUM(embedded_count), 0)::bigint as docs_with_embeddings,
+                MAX(last_updated) as last_update
+            FROM crate_stats
+            "
         );
+        let row = sqlx::query(&stats_sql).fetch_one(pool).await?;
 
-        let query_str = query_parts.join(" ");
+        let total_crates: i64 = row.get("total_crates");
+        let active_crates: i64 = row.get("active_crates");
+        let total_versions: i64 = row.get("total_versions");
+        let total_docs: i64 = row.get("total_docs");
+        let docs_with_embeddings: i64 = row.get("docs_with_embeddings");
+        let last_update: Option<DateTime<Utc>> = row.get("last_update");
 
-        // Execute main query
-        let mut query = sqlx::query(&query_str)
-            .bind(pagination.limit)
-            .bind(pagination.offset);
+        // Get total tokens separately with proper type handling
+        let tokens_sql = format!(
+            r"
+            SELECT COALESCE(SUM(CAST(token_count AS BIGINT)), 0)::bigint
+            FROM documents 
+            WHERE doc_type = 'rust' 
+            AND metadata->>'crate_name' IS NOT NULL
+            AND {active}
+            "
+        );
+        let total_tokens = sqlx::query_scalar::<_, i64>(&tokens_sql)
+            .fetch_one(pool)
+            .await?;
 
-        if let Some(pattern) = name_pattern {
-            query = query.bind(format!("%{pattern}%"));
-        }
+        let average_docs_per_crate = if active_crates > 0 {
+            #[allow(clippy::cast_precision_loss)] // Acceptable precision loss for statistics
+            {
+                total_docs as f64 / active_crates as f64
+            }
+        } else {
+            0.0
+        };
+
+        Ok(crate::models::CrateStatistics {
+            total_crates,
+            active_crates,
+            total_versions,
+            total_docs_managed: total_docs,
+            total_tokens_managed: total_tokens,
+            docs_with_embeddings,
+            embedding_coverage_pct: crate::models::embedding_coverage_pct(
+                docs_with_embeddings,
+                total_docs,
+            ),
+            average_docs_per_crate,
+            last_update,
+        })
+    }
+
+    /// Check if crate exists by name
+    ///
+    /// Aggregates across all stored versions; `version` lists each distinct
+    /// version separated by commas, most recently ingested first. Names match
+    /// by [`crate::models::crate_name_key`], and `name` is the spelling of the
+    /// most recently stored document.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_crate_by_name(
+        pool: &PgPool,
+        crate_name: &str,
+    ) -> Result<Option<crate::models::CrateInfo>> {
+        let versions = Self::find_crate_versions(pool, crate_name).await?;
+        let Some(latest) = versions.first() else {
+            return Ok(None);
+        };
 
-        let rows = query.fetch_all(pool).await?;
+        let total_docs: i64 = versions.iter().map(|v| i64::from(v.total_docs)).sum();
+        let embedded_docs: i64 = versions.iter().map(|v| v.embedded_docs).sum();
+        Ok(Some(crate::models::CrateInfo {
+            name: latest.name.clone(),
+            version: versions
+                .iter()
+                .map(|v| v.version.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            description: None,
+            documentation_url: None,
+            total_docs: i32::try_from(total_docs).unwrap_or(i32::MAX),
+            total_tokens: versions.iter().map(|v| v.total_tokens).sum(),
+            embedded_docs,
+            embedding_coverage_pct: crate::models::embedding_coverage_pct(
+                embedded_docs,
+                total_docs,
+            ),
+            last_updated: latest.last_updated,
+        }))
+    }
 
-        // Get total count
-        let mut count_query_parts = vec![r"
-            SELECT COUNT(DISTINCT metadata->>'crate_name') 
+    /// Each stored version of a crate, most recently ingested first
+    ///
+    /// Versions ingested at the same moment are ordered by version, highest
+    /// first, so the order never depends on the scan. Documents without a
+    /// `crate_version` are reported as version `latest`. Names match by
+    /// [`crate::models::crate_name_key`], so `serde-json` finds `serde_json`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_crate_versions(
+        pool: &PgPool,
+        crate_name: &str,
+    ) -> Result<Vec<crate::models::CrateInfo>> {
+        let key = crate::models::CRATE_NAME_KEY_SQL;
+        let query = format!(
+            r"
+            SELECT 
+                (array_agg(metadata->>'crate_name' ORDER BY created_at DESC))[1] as name,
+                COALESCE(metadata->>'crate_version', 'latest') as version,
+                COUNT(*) as total_docs,
+                COALESCE(SUM(CAST(token_count AS BIGINT)), 0)::bigint as total_tokens,
+                COUNT(embedding) as embedded_docs,
+                MAX(created_at) as last_updated
             FROM documents 
             WHERE doc_type = 'rust' 
-            AND metadata->>'crate_name' IS NOT NULL
-            "
-        .to_string()];
+            AND {key} = replace(lower($1), '-', '_')
+            GROUP BY {key}, COALESCE(metadata->>'crate_version', 'latest')
+            ORDER BY last_updated DESC, version DESC
+            "
+        );
+        let rows = execute_with_retry("find_crate_versions", || {
+            sqlx::query(&query).bind(crate_name).fetch_all(pool)
+        })
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let total_docs: i64 = row.get("total_docs");
+                let embedded_docs: i64 = row.get("embedded_docs");
+                crate::models::CrateInfo {
+                    name: row.get("name"),
+                    version: row.get("version"),
+                    description: None,
+                    documentation_url: None,
+                    total_docs: i32::try_from(total_docs).unwrap_or(i32::MAX),
+                    total_tokens: row.get("total_tokens"),
+                    embedded_docs,
+                    embedding_coverage_pct: crate::models::embedding_coverage_pct(
+                        embedded_docs,
+                        total_docs,
+                    ),
+                    last_updated: row.get("last_updated"),
+                }
+            })
+            .collect())
+    }
+
+    /// Delete up to `limit` of a crate's documents in one transaction
+    ///
+    /// Returns the number of documents deleted; callers repeat until it is 0 so
+    /// no single statement holds row locks on the whole crate. With `version`,
+    /// only documents of that crate version are deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database delete fails.
+    pub async fn delete_crate_documents_batch(
+        pool: &PgPool,
+        crate_name: &str,
+        version: Option<&str>,
+        limit: i64,
+    ) -> Result<u64> {
+        let query = format!(
+            r"
+            DELETE FROM documents WHERE id IN (
+                SELECT id FROM documents
+                WHERE doc_type = 'rust' AND {}
+                AND ($3::text IS NULL OR metadata->>'crate_version' = $3)
+                LIMIT $2
+            )
+            ",
+            crate::models::crate_name_match_sql("$1")
+        );
+        let result = sqlx::query(&query)
+            .bind(crate_name)
+            .bind(limit)
+            .bind(version)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Active documents of one crate version ordered by `doc_path`, the
+    /// `limit` after `after_path` (keyset pagination)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn find_crate_version_documents(
+        pool: &PgPool,
+        crate_name: &str,
+        version: &str,
+        after_path: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Document>> {
+        let query = format!(
+            r"
+            SELECT id, doc_type, source_name, doc_path, content, metadata,
+                   token_count, created_at, updated_at
+            FROM documents
+            WHERE doc_type = 'rust' AND {}
+              AND metadata->>'crate_version' = $2
+              AND ($3::text IS NULL OR doc_path > $3)
+              AND {}
+            ORDER BY doc_path
+            LIMIT $4
+            ",
+            crate::models::crate_name_match_sql("$1"),
+            crate::models::CrateStatusFilter::Active.sql_predicate()
+        );
+        let rows = sqlx::query(&query)
+            .bind(crate_name)
+            .bind(version)
+            .bind(after_path)
+            .bind(limit)
+            .fetch_all(pool)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| Document {
+                id: row.get("id"),
+                doc_type: row.get("doc_type"),
+                source_name: row.get("source_name"),
+                doc_path: row.get("doc_path"),
+                content: row.get("content"),
+                metadata: row.get("metadata"),
+                embedding: None,
+                token_count: row.get("token_count"),
+                created_at: row.get("created_at"),
+                updated_at: row.get("updated_at"),
+            })
+            .collect())
+    }
+
+    /// Count a crate's documents in the given soft-delete status
+    ///
+    /// Documents match on their `crate_name` metadata or their source name,
+    /// and on their `crate_version` metadata when `version` is given.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn count_crate_documents(
+        pool: &PgPool,
+        crate_name: &str,
+        version: Option<&str>,
+        status: crate::models::CrateStatusFilter,
+    ) -> Result<i64> {
+        let query = format!(
+            "SELECT COUNT(*) FROM documents WHERE doc_type = 'rust' AND {} AND ($2::text IS NULL OR metadata->>'crate_version' = $2) AND {}",
+            crate::models::crate_name_match_sql("$1"),
+            status.sql_predicate()
+        );
+        let count = execute_with_retry("count_crate_documents", || {
+            sqlx::query_scalar::<_, i64>(&query)
+                .bind(crate_name)
+                .bind(version)
+                .fetch_one(pool)
+        })
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Count a crate's documents, of one version if `version` is given, that
+    /// have an embedding stored
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn count_crate_embeddings(
+        pool: &PgPool,
+        crate_name: &str,
+        version: Option<&str>,
+    ) -> Result<i64> {
+        let query = format!(
+            "SELECT COUNT(*) FROM documents WHERE doc_type = 'rust' AND {} AND ($2::text IS NULL OR metadata->>'crate_version' = $2) AND embedding IS NOT NULL",
+            crate::models::crate_name_match_sql("$1")
+        );
+        let count = execute_with_retry("count_crate_embeddings", || {
+            sqlx::query_scalar::<_, i64>(&query)
+                .bind(crate_name)
+                .bind(version)
+                .fetch_one(pool)
+        })
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Count all Rust documents, active or not
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn count_rust_documents(pool: &PgPool) -> Result<i64> {
+        let count = execute_with_retry("count_rust_documents", || {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM documents WHERE doc_type = 'rust'")
+                .fetch_one(pool)
+        })
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Delete all of a crate's documents, or those of one `version`, in one
+    /// statement
+    ///
+    /// Returns the number of documents deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database delete fails.
+    pub async fn delete_crate_documents(
+        pool: &PgPool,
+        crate_name: &str,
+        version: Option<&str>,
+    ) -> Result<u64> {
+        let query = format!(
+            "DELETE FROM documents WHERE doc_type = 'rust' AND {} AND ($2::text IS NULL OR metadata->>'crate_version' = $2)",
+            crate::models::crate_name_match_sql("$1")
+        );
+        let result = sqlx::query(&query)
+            .bind(crate_name)
+            .bind(version)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Soft-delete a crate by marking all of its documents, or those of one
+    /// `version`, inactive
+    ///
+    /// Returns the number of documents marked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database update fails.
+    pub async fn mark_crate_inactive(
+        pool: &PgPool,
+        crate_name: &str,
+        version: Option<&str>,
+    ) -> Result<u64> {
+        let query = format!(
+            r#"
+            UPDATE documents 
+            SET metadata = jsonb_set(metadata, '{{status}}', '"inactive"', true),
+                updated_at = CURRENT_TIMESTAMP
+            WHERE doc_type = 'rust' 
+            AND {}
+            AND ($2::text IS NULL OR metadata->>'crate_version' = $2)
+            "#,
+            crate::models::crate_name_match_sql("$1")
+        );
+        let result = sqlx::query(&query)
+            .bind(crate_name)
+            .bind(version)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Reactivate a soft-deleted crate's inactive documents
+    ///
+    /// Returns the number of documents reactivated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database update fails.
+    pub async fn restore_crate(pool: &PgPool, crate_name: &str) -> Result<u64> {
+        let query = format!(
+            r#"
+            UPDATE documents 
+            SET metadata = jsonb_set(metadata, '{{status}}', '"active"', true),
+                updated_at = CURRENT_TIMESTAMP
+            WHERE doc_type = 'rust' 
+            AND {}
+            AND {}
+            "#,
+            crate::models::crate_name_match_sql("$1"),
+            crate::models::CrateStatusFilter::Inactive.sql_predicate()
+        );
+        let result = sqlx::query(&query).bind(crate_name).execute(pool).await?;
 
-        if name_pattern.is_some() {
-            count_query_parts.push("AND metadata->>'crate_name' ILIKE $1".to_string());
-        }
+        Ok(result.rows_affected())
+    }
+}
 
-        let count_query_str = count_query_parts.join(" ");
-        let mut count_query = sqlx::query_scalar::<_, i64>(&count_query_str);
+/// Package query operations for package ecosystems such as npm and Python
+///
+/// Each package is one source of its `doc_type`; documents record
+/// `package_name` and `package_version` in their metadata.
+pub struct PackageQueries;
 
-        if let Some(pattern) = name_pattern {
-            count_query = count_query.bind(format!("%{pattern}%"));
-        }
+impl PackageQueries {
+    /// Ingested packages of `doc_type`, one item per (package, version), with pagination
+    ///
+    /// Rows are ordered by name, then version. `name_pattern` matches a
+    /// substring of the package name, case-insensitively.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn list_packages(
+        pool: &PgPool,
+        doc_type: &str,
+        pagination: &crate::models::PaginationParams,
+        name_pattern: Option<&str>,
+    ) -> Result<crate::models::PaginatedResponse<crate::models::CrateInfo>> {
+        let pattern = name_pattern.map(|p| format!("%{}%", p.to_lowercase()));
+        let rows = sqlx::query(
+            r"
+            SELECT
+                metadata->>'package_name' AS name,
+                COALESCE(metadata->>'package_version', 'latest') AS version,
+                MAX(metadata->>'package_description') AS description,
+                MAX(metadata->>'homepage') AS documentation_url,
+                COUNT(*)::int AS total_docs,
+                COALESCE(SUM(token_count), 0) AS total_tokens,
+                COUNT(embedding) AS embedded_docs,
+                MAX(created_at) AS last_updated
+            FROM documents
+            WHERE doc_type = $4
+            AND metadata->>'package_name' IS NOT NULL
+            AND ($3::text IS NULL OR lower(metadata->>'package_name') LIKE $3)
+            GROUP BY metadata->>'package_name', COALESCE(metadata->>'package_version', 'latest')
+            ORDER BY name, version
+            LIMIT $1 OFFSET $2
+            ",
+        )
+        .bind(pagination.limit)
+        .bind(pagination.offset)
+        .bind(&pattern)
+        .bind(doc_type)
+        .fetch_all(pool)
+        .await?;
 
-        let total_items = count_query.fetch_one(pool).await?;
+        let total_items = sqlx::query_scalar::<_, i64>(
+            r"
+            SELECT COUNT(DISTINCT (metadata->>'package_name', COALESCE(metadata->>'package_version', 'latest')))
+            FROM documents
+            WHERE doc_type = $2
+            AND metadata->>'package_name' IS NOT NULL
+            AND ($1::text IS NULL OR lower(metadata->>'package_name') LIKE $1)
+            ",
+        )
+        .bind(&pattern)
+        .bind(doc_type)
+        .fetch_one(pool)
+        .await?;
 
-        // Convert rows to CrateInfo
         let items = rows
             .into_iter()
             .map(|row| {
-                let name: String = row.get("name");
-                let version: String = row.get("version");
-                let description: String = row.get("description");
-                let documentation_url: String = row.get("documentation_url");
                 let total_docs: i32 = row.get("total_docs");
-                let total_tokens: i64 = row.get("total_tokens");
-                let last_updated: DateTime<Utc> = row.get("last_updated");
-
+                let embedded_docs: i64 = row.get("embedded_docs");
                 crate::models::CrateInfo {
-                    name,
-                    version,
-                    description: if description.is_empty() {
-                        None
-                    } else {
-                        Some(description)
-                    },
-                    documentation_url: if documentation_url.is_empty() {
-                        None
-                    } else {
-                        Some(documentation_url)
-                    },
+                    name: row.get("name"),
+                    version: row.get("version"),
+                    description: row.get("description"),
+                    documentation_url: row.get("documentation_url"),
                     total_docs,
-                    total_tokens,
-                    last_updated,
+                    total_tokens: row.get("total_tokens"),
+                    embedded_docs,
+                    embedding_coverage_pct: crate::models::embedding_coverage_pct(
+                        embedded_docs,
+                        i64::from(total_docs),
+                    ),
+                    last_updated: row.get("last_updated"),
                 }
             })
             .collect();
@@ -1427,118 +5051,328 @@ impl CrateQueries {
         ))
     }
 
-    /// Get crate statistics
+    /// Stored versions of a package, newest ingestion first
     ///
     /// # Errors
     ///
     /// Returns an error if the database query fails.
-    pub async fn get_crate_statistics(pool: &PgPool) -> Result<crate::models::CrateStatistics> {
-        let row = sqlx::query(
+    pub async fn find_package_versions(
+        pool: &PgPool,
+        doc_type: &str,
+        package_name: &str,
+    ) -> Result<Vec<crate::models::CrateInfo>> {
+        let rows = sqlx::query(
             r"
-            WITH crate_stats AS (
-                SELECT 
-                    metadata->>'crate_name' as crate_name,
-                    COUNT(*) as docs_count,
-                    COALESCE(SUM(CAST(token_count AS BIGINT)), 0) as tokens_count,
-                    MAX(created_at) as last_updated
-                FROM documents 
-                WHERE doc_type = 'rust' 
-                AND metadata->>'crate_name' IS NOT NULL
-                GROUP BY metadata->>'crate_name'
+            SELECT
+                COALESCE(metadata->>'package_version', 'latest') AS version,
+                MAX(metadata->>'package_description') AS description,
+                MAX(metadata->>'homepage') AS documentation_url,
+                COUNT(*)::int AS total_docs,
+                COALESCE(SUM(token_count), 0) AS total_tokens,
+                COUNT(embedding) AS embedded_docs,
+                MAX(created_at) AS last_updated
+            FROM documents
+            WHERE doc_type = $2 AND source_name = $1
+            GROUP BY COALESCE(metadata->>'package_version', 'latest')
+            ORDER BY last_updated DESC
+            ",
+        )
+        .bind(package_name)
+        .bind(doc_type)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let total_docs: i32 = row.get("total_docs");
+                let embedded_docs: i64 = row.get("embedded_docs");
+                crate::models::CrateInfo {
+                    name: package_name.to_string(),
+                    version: row.get("version"),
+                    description: row.get("description"),
+                    documentation_url: row.get("documentation_url"),
+                    total_docs,
+                    total_tokens: row.get("total_tokens"),
+                    embedded_docs,
+                    embedding_coverage_pct: crate::models::embedding_coverage_pct(
+                        embedded_docs,
+                        i64::from(total_docs),
+                    ),
+                    last_updated: row.get("last_updated"),
+                }
+            })
+            .collect())
+    }
+
+    /// Count a package's documents, of one version if `version` is given
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn count_package_documents(
+        pool: &PgPool,
+        doc_type: &str,
+        package_name: &str,
+        version: Option<&str>,
+    ) -> Result<i64> {
+        let count = execute_with_retry("count_package_documents", || {
+            sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM documents WHERE doc_type = $3 AND source_name = $1 AND ($2::text IS NULL OR metadata->>'package_version' = $2)",
+            )
+            .bind(package_name)
+            .bind(version)
+            .bind(doc_type)
+            .fetch_one(pool)
+        })
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Count a package's documents, of one version if `version` is given,
+    /// that have an embedding stored
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn count_package_embeddings(
+        pool: &PgPool,
+        doc_type: &str,
+        package_name: &str,
+        version: Option<&str>,
+    ) -> Result<i64> {
+        let count = execute_with_retry("count_package_embeddings", || {
+            sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM documents WHERE doc_type = $3 AND source_name = $1 AND ($2::text IS NULL OR metadata->>'package_version' = $2) AND embedding IS NOT NULL",
+            )
+            .bind(package_name)
+            .bind(version)
+            .bind(doc_type)
+            .fetch_one(pool)
+        })
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Delete a package's documents, of one version if `version` is given
+    ///
+    /// Returns the number of documents deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database delete fails.
+    pub async fn delete_package_documents(
+        pool: &PgPool,
+        doc_type: &str,
+        package_name: &str,
+        version: Option<&str>,
+    ) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM documents WHERE doc_type = $3 AND source_name = $1 AND ($2::text IS NULL OR metadata->>'package_version' = $2)",
+        )
+        .bind(package_name)
+        .bind(version)
+        .bind(doc_type)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Delete up to `limit` of a package's documents, of one version if
+    /// `version` is given
+    ///
+    /// Returns the number of documents deleted; 0 once none are left.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database delete fails.
+    pub async fn delete_package_documents_batch(
+        pool: &PgPool,
+        doc_type: &str,
+        package_name: &str,
+        version: Option<&str>,
+        limit: i64,
+    ) -> Result<u64> {
+        let result = sqlx::query(
+            r"
+            DELETE FROM documents WHERE id IN (
+                SELECT id FROM documents
+                WHERE doc_type = $4 AND source_name = $1
+                AND ($3::text IS NULL OR metadata->>'package_version' = $3)
+                LIMIT $2
             )
-            SELECT 
-                COUNT(*)::bigint as total_crates,
-                COUNT(*)::bigint as active_crates,
-                COALESCE(SUM(docs_count), 0)::bigint as total_docs,
-                MAX(last_updated) as last_update
-            FROM crate_stats
             ",
         )
-        .fetch_one(pool)
+        .bind(package_name)
+        .bind(limit)
+        .bind(version)
+        .bind(doc_type)
+        .execute(pool)
         .await?;
 
-        let total_crates: i64 = row.get("total_crates");
-        let active_crates: i64 = row.get("active_crates");
-        let total_docs: i64 = row.get("total_docs");
-        let last_update: Option<DateTime<Utc>> = row.get("last_update");
+        Ok(result.rows_affected())
+    }
+}
 
-        // Get total tokens separately with proper type handling
-        let total_tokens = sqlx::query_scalar::<_, i64>(
+/// Relevance feedback on search results and its aggregates
+pub struct FeedbackQueries;
+
+impl FeedbackQueries {
+    /// Record a rating, resolving the rated document in the same statement
+    ///
+    /// The document is looked up by id, or by path when no id is given. An
+    /// unknown document is still recorded, with the given id and path and
+    /// `document_found` false.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database insertion fails.
+    pub async fn record(
+        pool: &PgPool,
+        feedback: &crate::models::NewSearchFeedback,
+    ) -> Result<crate::models::SearchFeedback> {
+        let row = sqlx::query_as::<_, crate::models::SearchFeedback>(
             r"
-            SELECT COALESCE(SUM(CAST(token_count AS BIGINT)), 0)::bigint
-            FROM documents 
-            WHERE doc_type = 'rust' 
-            AND metadata->>'crate_name' IS NOT NULL
+            WITH doc AS (
+                SELECT id, doc_path, doc_type,
+                       COALESCE(metadata->>'crate_name', metadata->>'package_name') AS crate_name
+                FROM documents
+                WHERE CASE WHEN $4::uuid IS NULL THEN doc_path = $5 ELSE id = $4 END
+                ORDER BY updated_at DESC NULLS LAST
+                LIMIT 1
+            )
+            INSERT INTO search_feedback
+                (session_id, query, rating, document_id, doc_path, doc_type, crate_name, document_found)
+            SELECT $1, $2, $3, COALESCE(doc.id, $4), COALESCE(doc.doc_path, $5),
+                   doc.doc_type, doc.crate_name, doc.id IS NOT NULL
+            FROM (SELECT 1) AS one LEFT JOIN doc ON TRUE
+            RETURNING *
             ",
         )
+        .bind(&feedback.session_id)
+        .bind(&feedback.query)
+        .bind(&feedback.rating)
+        .bind(feedback.document_id)
+        .bind(&feedback.doc_path)
         .fetch_one(pool)
         .await?;
+        Ok(row)
+    }
 
-        let average_docs_per_crate = if total_crates > 0 {
-            #[allow(clippy::cast_precision_loss)] // Acceptable precision loss for statistics
-            {
-                total_docs as f64 / total_crates as f64
-            }
-        } else {
-            0.0
-        };
+    /// Ratings since `since` grouped by doc type, busiest first
+    ///
+    /// Entries whose document was not found have no doc type and are left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn counts_by_doc_type(
+        pool: &PgPool,
+        since: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<crate::models::FeedbackCounts>> {
+        Self::counts(pool, "doc_type", "search_feedback", since, limit).await
+    }
 
-        Ok(crate::models::CrateStatistics {
-            total_crates,
-            active_crates,
-            total_docs_managed: total_docs,
-            total_tokens_managed: total_tokens,
-            average_docs_per_crate,
-            last_update,
-        })
+    /// Ratings since `since` grouped by crate or package, busiest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn counts_by_crate(
+        pool: &PgPool,
+        since: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<crate::models::FeedbackCounts>> {
+        Self::counts(pool, "crate_name", "search_feedback", since, limit).await
     }
 
-    /// Check if crate exists by name
+    /// Ratings since `since` grouped by query term, busiest first
+    ///
+    /// Terms are the English full-text lexemes of each query, so stop words
+    /// are dropped and `spawned` counts as `spawn`; a term is counted once
+    /// per entry.
     ///
     /// # Errors
     ///
     /// Returns an error if the database query fails.
-    pub async fn find_crate_by_name(
+    pub async fn counts_by_term(
         pool: &PgPool,
-        crate_name: &str,
-    ) -> Result<Option<crate::models::CrateInfo>> {
-        let row = sqlx::query(
+        since: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<crate::models::FeedbackCounts>> {
+        Self::counts(
+            pool,
+            "term",
+            "search_feedback, unnest(tsvector_to_array(to_tsvector('english', query))) AS term",
+            since,
+            limit,
+        )
+        .await
+    }
+
+    async fn counts(
+        pool: &PgPool,
+        key: &str,
+        from: &str,
+        since: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<crate::models::FeedbackCounts>> {
+        let sql = format!(
             r"
-            SELECT 
-                COALESCE(metadata->>'crate_name', 'unknown') as name,
-                COALESCE(metadata->>'crate_version', 'latest') as version,
-                COUNT(*) as total_docs,
-                COALESCE(SUM(CAST(token_count AS BIGINT)), 0)::bigint as total_tokens,
-                MAX(created_at) as last_updated
-            FROM documents 
-            WHERE doc_type = 'rust' 
-            AND metadata->>'crate_name' = $1
-            GROUP BY metadata->>'crate_name', metadata->>'crate_version'
-            LIMIT 1
+            SELECT {key} AS key,
+                   COUNT(*) FILTER (WHERE rating = 'useful') AS useful,
+                   COUNT(*) FILTER (WHERE rating = 'partially') AS partially,
+                   COUNT(*) FILTER (WHERE rating = 'not_useful') AS not_useful
+            FROM {from}
+            WHERE created_at >= $1 AND {key} IS NOT NULL
+            GROUP BY {key}
+            ORDER BY COUNT(*) DESC, {key}
+            LIMIT $2
+            "
+        );
+        let rows = sqlx::query_as::<_, crate::models::FeedbackCounts>(&sql)
+            .bind(since)
+            .bind(limit)
+            .fetch_all(pool)
+            .await?;
+        Ok(rows)
+    }
+
+    /// Number of entries since `since`, and how many of them named an unknown document
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn totals(pool: &PgPool, since: DateTime<Utc>) -> Result<(i64, i64)> {
+        let row = sqlx::query_as::<_, (i64, i64)>(
+            r"
+            SELECT COUNT(*), COUNT(*) FILTER (WHERE NOT document_found)
+            FROM search_feedback
+            WHERE created_at >= $1
             ",
         )
-        .bind(crate_name)
-        .fetch_optional(pool)
+        .bind(since)
+        .fetch_one(pool)
         .await?;
+        Ok(row)
+    }
 
-        if let Some(row) = row {
-            let name: String = row.get("name");
-            let version: String = row.get("version");
-            let total_docs: i64 = row.get("total_docs");
-            let total_tokens: i64 = row.get("total_tokens");
-            let last_updated: DateTime<Utc> = row.get("last_updated");
-
-            Ok(Some(crate::models::CrateInfo {
-                name,
-                version,
-                description: None,
-                documentation_url: None,
-                total_docs: i32::try_from(total_docs).unwrap_or(i32::MAX),
-                total_tokens,
-                last_updated,
-            }))
-        } else {
-            Ok(None)
-        }
+    /// Delete entries older than `retention_days`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn prune(pool: &PgPool, retention_days: i32) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM search_feedback WHERE created_at < NOW() - make_interval(days => $1)",
+        )
+        .bind(retention_days)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected())
     }
 }
@@ -1,13 +1,17 @@
 //! Database connection retry logic with exponential backoff
 //!
 //! This module provides robust retry mechanisms for handling temporary database
-//! unavailability, network issues, and connection failures during startup.
+//! unavailability, network issues, and connection failures during startup,
+//! and [`execute_with_retry`] for queries that are safe to run again.
 
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
 use std::time::Duration;
 use tokio::time::sleep;
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
 
 /// Retry configuration for database connections
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -77,6 +81,32 @@ impl RetryConfig {
         config
     }
 
+    /// Retry configuration for queries wrapped in [`execute_with_retry`]
+    ///
+    /// Shorter than the startup defaults, since a request is waiting:
+    /// `DB_QUERY_RETRY_MAX_ATTEMPTS` retries (default 3, 0 disables), starting
+    /// at `DB_QUERY_RETRY_INITIAL_DELAY_MS` (default 100) and doubling up to
+    /// two seconds, with jitter.
+    #[must_use]
+    pub fn queries_from_env() -> Self {
+        let env_u64 = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+        Self {
+            max_retries: env_u64("DB_QUERY_RETRY_MAX_ATTEMPTS")
+                .and_then(|v| usize::try_from(v).ok())
+                .unwrap_or(3),
+            initial_delay: Duration::from_millis(
+                env_u64("DB_QUERY_RETRY_INITIAL_DELAY_MS").unwrap_or(100),
+            ),
+            max_delay: Duration::from_secs(2),
+            multiplier: 2.0,
+            jitter: true,
+        }
+    }
+
     /// Validate retry configuration
     ///
     /// # Errors
@@ -186,6 +216,180 @@ impl DatabaseError {
     }
 }
 
+/// How [`execute_with_retry`] treats a failed query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// The connection dropped or the server is restarting
+    Connection,
+    /// No connection could be had in time, from the pool or the server
+    PoolTimeout,
+    /// Serialization failure or deadlock; the transaction can be replayed
+    Serialization,
+    /// Constraint violations, syntax errors, missing rows and anything else
+    Permanent,
+}
+
+impl ErrorClass {
+    /// Classify a sqlx error
+    #[must_use]
+    pub fn of(error: &sqlx::Error) -> Self {
+        match error {
+            sqlx::Error::Io(_) | sqlx::Error::Tls(_) | sqlx::Error::WorkerCrashed => {
+                Self::Connection
+            }
+            sqlx::Error::PoolTimedOut => Self::PoolTimeout,
+            sqlx::Error::Database(db_error) => match db_error.code().as_deref() {
+                // connection_exception class, admin/crash shutdown, cannot_connect_now
+                Some(code) if code.starts_with("08") => Self::Connection,
+                Some("57P01" | "57P02" | "57P03") => Self::Connection,
+                Some("53300") => Self::PoolTimeout,
+                Some("40001" | "40P01") => Self::Serialization,
+                _ => Self::Permanent,
+            },
+            _ => Self::Permanent,
+        }
+    }
+
+    /// Whether running the query again may succeed
+    #[must_use]
+    pub fn is_retryable(self) -> bool {
+        !matches!(self, Self::Permanent)
+    }
+
+    /// Label used in logs and retry counts
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Connection => "connection",
+            Self::PoolTimeout => "pool_timeout",
+            Self::Serialization => "serialization",
+            Self::Permanent => "permanent",
+        }
+    }
+}
+
+/// Errors [`execute_with_retry`] can look into for a sqlx error
+pub trait SqlxErrorSource {
+    /// The underlying sqlx error, if any
+    fn sqlx_error(&self) -> Option<&sqlx::Error>;
+}
+
+impl SqlxErrorSource for sqlx::Error {
+    fn sqlx_error(&self) -> Option<&sqlx::Error> {
+        Some(self)
+    }
+}
+
+impl SqlxErrorSource for anyhow::Error {
+    fn sqlx_error(&self) -> Option<&sqlx::Error> {
+        self.downcast_ref()
+    }
+}
+
+/// Retries made by [`execute_with_retry`] since start, by error class
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryCounts {
+    pub connection: u64,
+    pub pool_timeout: u64,
+    pub serialization: u64,
+    /// Operations that still failed after their last retry
+    pub exhausted: u64,
+}
+
+static RETRIES_CONNECTION: AtomicU64 = AtomicU64::new(0);
+static RETRIES_POOL_TIMEOUT: AtomicU64 = AtomicU64::new(0);
+static RETRIES_SERIALIZATION: AtomicU64 = AtomicU64::new(0);
+static RETRIES_EXHAUSTED: AtomicU64 = AtomicU64::new(0);
+
+/// Current [`RetryCounts`]
+#[must_use]
+pub fn retry_counts() -> RetryCounts {
+    RetryCounts {
+        connection: RETRIES_CONNECTION.load(Ordering::Relaxed),
+        pool_timeout: RETRIES_POOL_TIMEOUT.load(Ordering::Relaxed),
+        serialization: RETRIES_SERIALIZATION.load(Ordering::Relaxed),
+        exhausted: RETRIES_EXHAUSTED.load(Ordering::Relaxed),
+    }
+}
+
+fn count_retry(class: ErrorClass) {
+    let counter = match class {
+        ErrorClass::Connection => &RETRIES_CONNECTION,
+        ErrorClass::PoolTimeout => &RETRIES_POOL_TIMEOUT,
+        ErrorClass::Serialization => &RETRIES_SERIALIZATION,
+        ErrorClass::Permanent => return,
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Query retry configuration, read from the environment once
+fn query_retry_config() -> &'static RetryConfig {
+    static CONFIG: OnceLock<RetryConfig> = OnceLock::new();
+    CONFIG.get_or_init(RetryConfig::queries_from_env)
+}
+
+/// Run `operation`, running it again after connection drops, pool
+/// timeouts and serialization failures
+///
+/// Only wrap operations that are safe to repeat: reads, upserts and
+/// updates that set absolute values. A transaction must be begun and
+/// committed inside `operation`, so a retry replays all of it. Backoff
+/// follows [`RetryConfig::queries_from_env`].
+///
+/// # Errors
+///
+/// Returns the first permanent error, or the last error once retries run out.
+pub async fn execute_with_retry<F, Fut, T, E>(label: &str, mut operation: F) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: SqlxErrorSource + std::fmt::Display,
+{
+    let config = query_retry_config();
+    let mut attempt = 0;
+    loop {
+        let error = match operation().await {
+            Ok(value) => {
+                if attempt > 0 {
+                    debug!("{} succeeded after {} retries", label, attempt);
+                }
+                return Ok(value);
+            }
+            Err(error) => error,
+        };
+
+        let class = error
+            .sqlx_error()
+            .map_or(ErrorClass::Permanent, ErrorClass::of);
+        if !class.is_retryable() {
+            return Err(error);
+        }
+        if attempt >= config.max_retries {
+            RETRIES_EXHAUSTED.fetch_add(1, Ordering::Relaxed);
+            warn!(
+                "{} failed after {} retries ({}): {}",
+                label,
+                attempt,
+                class.as_str(),
+                error
+            );
+            return Err(error);
+        }
+
+        count_retry(class);
+        let delay = config.calculate_delay(attempt);
+        warn!(
+            "{} hit a {} error, retrying in {:?}: {}",
+            label,
+            class.as_str(),
+            delay,
+            error
+        );
+        sleep(delay).await;
+        attempt += 1;
+    }
+}
+
 /// Retry executor for database operations
 pub struct RetryExecutor {
     config: RetryConfig,
@@ -299,10 +503,7 @@ impl RetryExecutor {
                     // Check if this error should be retried
                     if !db_error.is_retryable() {
                         error!("Non-retryable database error: {}", db_error.to_string());
-                        return Err(anyhow!(
-                            "Non-retryable database error: {}",
-                            db_error.to_string()
-                        ));
+                        return Err(anyhow!("Non-retryable database error: {}", db_error));
                     }
 
                     if attempt == self.config.max_retries {
@@ -409,6 +610,107 @@ mod tests {
         assert!(!DatabaseError::Other("test".to_string()).is_retryable());
     }
 
+    /// Database error carrying only a SQLSTATE code
+    #[derive(Debug)]
+    struct CodedError(&'static str);
+
+    impl std::fmt::Display for CodedError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "SQLSTATE {}", self.0)
+        }
+    }
+
+    impl std::error::Error for CodedError {}
+
+    impl sqlx::error::DatabaseError for CodedError {
+        fn message(&self) -> &str {
+            self.0
+        }
+
+        fn code(&self) -> Option<std::borrow::Cow<'_, str>> {
+            Some(self.0.into())
+        }
+
+        fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+            self
+        }
+
+        fn kind(&self) -> sqlx::error::ErrorKind {
+            sqlx::error::ErrorKind::Other
+        }
+    }
+
+    fn coded(code: &'static str) -> sqlx::Error {
+        sqlx::Error::Database(Box::new(CodedError(code)))
+    }
+
+    #[test]
+    fn test_error_class() {
+        let reset = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+        assert_eq!(
+            ErrorClass::of(&sqlx::Error::Io(reset)),
+            ErrorClass::Connection
+        );
+        assert_eq!(ErrorClass::of(&coded("08006")), ErrorClass::Connection);
+        assert_eq!(ErrorClass::of(&coded("57P01")), ErrorClass::Connection);
+        assert_eq!(
+            ErrorClass::of(&sqlx::Error::PoolTimedOut),
+            ErrorClass::PoolTimeout
+        );
+        assert_eq!(ErrorClass::of(&coded("53300")), ErrorClass::PoolTimeout);
+        assert_eq!(ErrorClass::of(&coded("40001")), ErrorClass::Serialization);
+        assert_eq!(ErrorClass::of(&coded("40P01")), ErrorClass::Serialization);
+
+        // Constraint violations, syntax errors and missing rows are permanent
+        for permanent in [coded("23505"), coded("23503"), coded("42601")] {
+            assert_eq!(ErrorClass::of(&permanent), ErrorClass::Permanent);
+        }
+        assert_eq!(
+            ErrorClass::of(&sqlx::Error::RowNotFound),
+            ErrorClass::Permanent
+        );
+        assert!(!ErrorClass::Permanent.is_retryable());
+        assert!(ErrorClass::Serialization.is_retryable());
+
+        // Errors wrapped in anyhow are classified by their sqlx source
+        let wrapped = anyhow::Error::from(coded("40001"));
+        assert_eq!(
+            wrapped.sqlx_error().map(ErrorClass::of),
+            Some(ErrorClass::Serialization)
+        );
+        assert!(anyhow!("not a database error").sqlx_error().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_retry_stops_at_permanent_errors() {
+        let mut calls = 0;
+        let result: Result<(), sqlx::Error> = execute_with_retry("test", || {
+            calls += 1;
+            let error = if calls == 1 {
+                coded("40001")
+            } else {
+                coded("23505")
+            };
+            async move { Err(error) }
+        })
+        .await;
+
+        // One retry for the serialization failure, none for the violation
+        assert_eq!(calls, 2);
+        assert_eq!(
+            result.unwrap_err().sqlx_error().map(ErrorClass::of),
+            Some(ErrorClass::Permanent)
+        );
+    }
+
     #[tokio::test]
     async fn test_retry_executor_success() {
         let executor = RetryExecutor::new();
@@ -0,0 +1,445 @@
+//! Storage behind the crate management tools
+//!
+//! The crate tools only touch a handful of query surfaces: crate listings
+//! and statistics, per-crate document counts and deletes, and the crate job
+//! table. [`CrateStore`] names those operations so the tools can run against
+//! PostgreSQL ([`DatabasePool`] implements it by delegating to
+//! [`CrateQueries`] and [`CrateJobQueries`]) or against the
+//! [`MemoryCrateStore`] used by tests without a database.
+//!
+//! Tools take a [`CrateStorage`], which converts from a `DatabasePool`, so
+//! existing call sites keep passing the pool.
+
+use crate::connection::DatabasePool;
+use crate::memory::MemoryCrateStore;
+use crate::models::{
+    CrateDependent, CrateInfo, CrateJob, CrateJobEvent, CrateStatistics, CrateStatusFilter,
+    Document, JobStatus, PaginatedResponse, PaginationParams,
+};
+use crate::queries::{CrateJobQueries, CrateQueries, DocumentQueries};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Crate and crate-job operations used by the crate management tools
+///
+/// Method semantics follow the [`CrateQueries`] and [`CrateJobQueries`]
+/// functions of the same name.
+#[async_trait]
+pub trait CrateStore: Send + Sync {
+    /// Aggregate of a crate's documents across versions, if it has any
+    async fn find_crate_by_name(&self, crate_name: &str) -> Result<Option<CrateInfo>>;
+
+    /// Each stored version of a crate, most recently ingested first
+    async fn find_crate_versions(&self, crate_name: &str) -> Result<Vec<CrateInfo>>;
+
+    /// One page of crates in the given soft-delete status
+    async fn list_crates_with_status(
+        &self,
+        pagination: &PaginationParams,
+        name_pattern: Option<&str>,
+        status: CrateStatusFilter,
+    ) -> Result<PaginatedResponse<CrateInfo>>;
+
+    /// System-wide crate statistics
+    async fn get_crate_statistics(&self) -> Result<CrateStatistics>;
+
+    /// Active crates whose recorded dependencies include `crate_name`
+    async fn find_dependents(&self, crate_name: &str) -> Result<Vec<CrateDependent>>;
+
+    /// Active crates other than `crate_name` without dependency metadata
+    async fn find_crates_without_dependencies(&self, crate_name: &str) -> Result<Vec<String>>;
+
+    /// Count a crate's documents, of one version if given, in the given soft-delete status
+    async fn count_crate_documents(
+        &self,
+        crate_name: &str,
+        version: Option<&str>,
+        status: CrateStatusFilter,
+    ) -> Result<i64>;
+
+    /// Count a crate's documents, of one version if given, that have an embedding
+    async fn count_crate_embeddings(&self, crate_name: &str, version: Option<&str>) -> Result<i64>;
+
+    /// Count all Rust documents
+    async fn count_rust_documents(&self) -> Result<i64>;
+
+    /// Embedded documents per recorded embedding model
+    async fn count_embeddings_by_model(&self) -> Result<Vec<(Option<String>, i64)>>;
+
+    /// Active documents of one crate version ordered by `doc_path`, the
+    /// `limit` after `after_path`
+    async fn find_crate_version_documents(
+        &self,
+        crate_name: &str,
+        version: &str,
+        after_path: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Document>>;
+
+    /// Delete all of a crate's documents, or one version's, returning how many were deleted
+    async fn delete_crate_documents(&self, crate_name: &str, version: Option<&str>) -> Result<u64>;
+
+    /// Delete up to `limit` of a crate's documents, or of one version's
+    async fn delete_crate_documents_batch(
+        &self,
+        crate_name: &str,
+        version: Option<&str>,
+        limit: i64,
+    ) -> Result<u64>;
+
+    /// Mark a crate's documents, or one version's, inactive, returning how many were marked
+    async fn mark_crate_inactive(&self, crate_name: &str, version: Option<&str>) -> Result<u64>;
+
+    /// Reactivate a crate's inactive documents, returning how many changed
+    async fn restore_crate(&self, crate_name: &str) -> Result<u64>;
+
+    /// Create a queued job, or return the one `idempotency_key` already names
+    ///
+    /// The flag is `false` when an existing job was returned. A key naming a
+    /// job of another crate or operation is an [`crate::IdempotencyConflict`].
+    async fn create_job(
+        &self,
+        crate_name: &str,
+        operation: &str,
+        options: &Value,
+        idempotency_key: Option<&str>,
+    ) -> Result<(CrateJob, bool)>;
+
+    async fn find_job_by_id(&self, job_id: Uuid) -> Result<Option<CrateJob>>;
+
+    async fn find_job_by_idempotency_key(&self, idempotency_key: &str) -> Result<Option<CrateJob>>;
+
+    /// Set a job's status, progress and error, recording an event on change
+    async fn update_job_status(
+        &self,
+        job_id: Uuid,
+        status: JobStatus,
+        progress: Option<i32>,
+        error: Option<&str>,
+    ) -> Result<CrateJob>;
+
+    async fn record_job_event(&self, job_id: Uuid, event_type: &str, detail: &Value) -> Result<()>;
+
+    /// Most recent `limit` events of a job, newest first
+    async fn find_job_events(&self, job_id: Uuid, limit: i64) -> Result<Vec<CrateJobEvent>>;
+
+    async fn merge_job_details(&self, job_id: Uuid, details: &Value) -> Result<()>;
+
+    async fn schedule_retry(
+        &self,
+        job_id: Uuid,
+        next_run_at: DateTime<Utc>,
+        error: &str,
+        details: &Value,
+    ) -> Result<CrateJob>;
+
+    async fn dead_letter_job(&self, job_id: Uuid, error: &str, details: &Value)
+        -> Result<CrateJob>;
+
+    /// Queue a failed or cancelled job again; `None` if there is no such job
+    async fn requeue_job(&self, job_id: Uuid) -> Result<Option<CrateJob>>;
+
+    /// Queued and running jobs, oldest first
+    async fn find_active_jobs(&self) -> Result<Vec<CrateJob>>;
+
+    async fn find_dead_letter_jobs(&self, limit: i64) -> Result<Vec<CrateJob>>;
+
+    /// Most recently started `limit` jobs, newest first
+    async fn find_recent_jobs(&self, limit: i64) -> Result<Vec<CrateJob>>;
+
+    async fn find_latest_job_for_crate(&self, crate_name: &str) -> Result<Option<CrateJob>>;
+
+    /// Jobs started since `since` that recorded `job_costs`, oldest first
+    async fn find_jobs_with_costs(&self, since: DateTime<Utc>) -> Result<Vec<CrateJob>>;
+
+    /// Bump a running job's `updated_at`; `false` once it is no longer running
+    async fn heartbeat_job(&self, job_id: Uuid) -> Result<bool>;
+
+    /// Running jobs without an update since `cutoff`, oldest first
+    async fn find_stale_jobs(&self, cutoff: DateTime<Utc>) -> Result<Vec<CrateJob>>;
+
+    /// Fail running jobs without an update since `cutoff`, appending `reason` to their error
+    async fn fail_stale_jobs(&self, cutoff: DateTime<Utc>, reason: &str) -> Result<u64>;
+
+    /// Delete finished jobs past the 30-day retention window
+    async fn cleanup_old_jobs(&self) -> Result<i32>;
+}
+
+#[async_trait]
+impl CrateStore for DatabasePool {
+    async fn find_crate_by_name(&self, crate_name: &str) -> Result<Option<CrateInfo>> {
+        CrateQueries::find_crate_by_name(self.pool(), crate_name).await
+    }
+
+    async fn find_crate_versions(&self, crate_name: &str) -> Result<Vec<CrateInfo>> {
+        CrateQueries::find_crate_versions(self.pool(), crate_name).await
+    }
+
+    async fn list_crates_with_status(
+        &self,
+        pagination: &PaginationParams,
+        name_pattern: Option<&str>,
+        status: CrateStatusFilter,
+    ) -> Result<PaginatedResponse<CrateInfo>> {
+        CrateQueries::list_crates_with_status(self.pool(), pagination, name_pattern, status).await
+    }
+
+    async fn get_crate_statistics(&self) -> Result<CrateStatistics> {
+        CrateQueries::get_crate_statistics(self.pool()).await
+    }
+
+    async fn find_dependents(&self, crate_name: &str) -> Result<Vec<CrateDependent>> {
+        CrateQueries::find_dependents(self.pool(), crate_name).await
+    }
+
+    async fn find_crates_without_dependencies(&self, crate_name: &str) -> Result<Vec<String>> {
+        CrateQueries::find_crates_without_dependencies(self.pool(), crate_name).await
+    }
+
+    async fn count_crate_documents(
+        &self,
+        crate_name: &str,
+        version: Option<&str>,
+        status: CrateStatusFilter,
+    ) -> Result<i64> {
+        CrateQueries::count_crate_documents(self.pool(), crate_name, version, status).await
+    }
+
+    async fn count_crate_embeddings(&self, crate_name: &str, version: Option<&str>) -> Result<i64> {
+        CrateQueries::count_crate_embeddings(self.pool(), crate_name, version).await
+    }
+
+    async fn count_rust_documents(&self) -> Result<i64> {
+        CrateQueries::count_rust_documents(self.pool()).await
+    }
+
+    async fn count_embeddings_by_model(&self) -> Result<Vec<(Option<String>, i64)>> {
+        DocumentQueries::count_embeddings_by_model(self.pool()).await
+    }
+
+    async fn find_crate_version_documents(
+        &self,
+        crate_name: &str,
+        version: &str,
+        after_path: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Document>> {
+        CrateQueries::find_crate_version_documents(
+            self.pool(),
+            crate_name,
+            version,
+            after_path,
+            limit,
+        )
+        .await
+    }
+
+    async fn delete_crate_documents(&self, crate_name: &str, version: Option<&str>) -> Result<u64> {
+        CrateQueries::delete_crate_documents(self.pool(), crate_name, version).await
+    }
+
+    async fn delete_crate_documents_batch(
+        &self,
+        crate_name: &str,
+        version: Option<&str>,
+        limit: i64,
+    ) -> Result<u64> {
+        CrateQueries::delete_crate_documents_batch(self.pool(), crate_name, version, limit).await
+    }
+
+    async fn mark_crate_inactive(&self, crate_name: &str, version: Option<&str>) -> Result<u64> {
+        CrateQueries::mark_crate_inactive(self.pool(), crate_name, version).await
+    }
+
+    async fn restore_crate(&self, crate_name: &str) -> Result<u64> {
+        CrateQueries::restore_crate(self.pool(), crate_name).await
+    }
+
+    async fn create_job(
+        &self,
+        crate_name: &str,
+        operation: &str,
+        options: &Value,
+        idempotency_key: Option<&str>,
+    ) -> Result<(CrateJob, bool)> {
+        match idempotency_key {
+            Some(key) => {
+                CrateJobQueries::create_job_idempotent(
+                    self.pool(),
+                    crate_name,
+                    operation,
+                    options,
+                    key,
+                )
+                .await
+            }
+            None => Ok((
+                CrateJobQueries::create_job_with_options(
+                    self.pool(),
+                    crate_name,
+                    operation,
+                    Some(options),
+                )
+                .await?,
+                true,
+            )),
+        }
+    }
+
+    async fn find_job_by_id(&self, job_id: Uuid) -> Result<Option<CrateJob>> {
+        CrateJobQueries::find_job_by_id(self.pool(), job_id).await
+    }
+
+    async fn find_job_by_idempotency_key(&self, idempotency_key: &str) -> Result<Option<CrateJob>> {
+        CrateJobQueries::find_job_by_idempotency_key(self.pool(), idempotency_key).await
+    }
+
+    async fn update_job_status(
+        &self,
+        job_id: Uuid,
+        status: JobStatus,
+        progress: Option<i32>,
+        error: Option<&str>,
+    ) -> Result<CrateJob> {
+        CrateJobQueries::update_job_status(self.pool(), job_id, status, progress, error).await
+    }
+
+    async fn record_job_event(&self, job_id: Uuid, event_type: &str, detail: &Value) -> Result<()> {
+        CrateJobQueries::record_job_event(self.pool(), job_id, event_type, detail).await
+    }
+
+    async fn find_job_events(&self, job_id: Uuid, limit: i64) -> Result<Vec<CrateJobEvent>> {
+        CrateJobQueries::find_job_events(self.pool(), job_id, limit).await
+    }
+
+    async fn merge_job_details(&self, job_id: Uuid, details: &Value) -> Result<()> {
+        CrateJobQueries::merge_job_details(self.pool(), job_id, details).await
+    }
+
+    async fn schedule_retry(
+        &self,
+        job_id: Uuid,
+        next_run_at: DateTime<Utc>,
+        error: &str,
+        details: &Value,
+    ) -> Result<CrateJob> {
+        CrateJobQueries::schedule_retry(self.pool(), job_id, next_run_at, error, details).await
+    }
+
+    async fn dead_letter_job(
+        &self,
+        job_id: Uuid,
+        error: &str,
+        details: &Value,
+    ) -> Result<CrateJob> {
+        CrateJobQueries::dead_letter_job(self.pool(), job_id, error, details).await
+    }
+
+    async fn requeue_job(&self, job_id: Uuid) -> Result<Option<CrateJob>> {
+        CrateJobQueries::requeue_job(self.pool(), job_id).await
+    }
+
+    async fn find_active_jobs(&self) -> Result<Vec<CrateJob>> {
+        CrateJobQueries::find_active_jobs(self.pool()).await
+    }
+
+    async fn find_dead_letter_jobs(&self, limit: i64) -> Result<Vec<CrateJob>> {
+        CrateJobQueries::find_dead_letter_jobs(self.pool(), limit).await
+    }
+
+    async fn find_recent_jobs(&self, limit: i64) -> Result<Vec<CrateJob>> {
+        CrateJobQueries::find_recent_jobs(self.pool(), limit).await
+    }
+
+    async fn find_latest_job_for_crate(&self, crate_name: &str) -> Result<Option<CrateJob>> {
+        CrateJobQueries::find_latest_job_for_crate(self.pool(), crate_name).await
+    }
+
+    async fn find_jobs_with_costs(&self, since: DateTime<Utc>) -> Result<Vec<CrateJob>> {
+        CrateJobQueries::find_jobs_with_costs(self.pool(), since).await
+    }
+
+    async fn heartbeat_job(&self, job_id: Uuid) -> Result<bool> {
+        CrateJobQueries::heartbeat(self.pool(), job_id).await
+    }
+
+    async fn find_stale_jobs(&self, cutoff: DateTime<Utc>) -> Result<Vec<CrateJob>> {
+        CrateJobQueries::find_stale_jobs(self.pool(), cutoff).await
+    }
+
+    async fn fail_stale_jobs(&self, cutoff: DateTime<Utc>, reason: &str) -> Result<u64> {
+        CrateJobQueries::fail_stale_jobs(self.pool(), cutoff, reason).await
+    }
+
+    async fn cleanup_old_jobs(&self) -> Result<i32> {
+        CrateJobQueries::cleanup_old_jobs(self.pool()).await
+    }
+}
+
+/// Storage a crate tool runs against
+#[derive(Clone)]
+pub enum CrateStorage {
+    /// The PostgreSQL database
+    Postgres(DatabasePool),
+    /// Process-local maps, for tests without a database
+    Memory(Arc<MemoryCrateStore>),
+}
+
+impl CrateStorage {
+    /// Fresh, empty in-memory storage
+    #[must_use]
+    pub fn in_memory() -> Self {
+        Self::Memory(Arc::new(MemoryCrateStore::new()))
+    }
+
+    /// Whether `TEST_DATABASE_URL=mock` asks tests to use in-memory storage
+    #[must_use]
+    pub fn mock_requested() -> bool {
+        std::env::var("TEST_DATABASE_URL").is_ok_and(|v| v.trim().eq_ignore_ascii_case("mock"))
+    }
+
+    /// The crate operations of this storage
+    #[must_use]
+    pub fn store(&self) -> &dyn CrateStore {
+        match self {
+            Self::Postgres(pool) => pool,
+            Self::Memory(store) => store.as_ref(),
+        }
+    }
+
+    /// The database pool, if this storage is PostgreSQL
+    #[must_use]
+    pub const fn as_pool(&self) -> Option<&DatabasePool> {
+        match self {
+            Self::Postgres(pool) => Some(pool),
+            Self::Memory(_) => None,
+        }
+    }
+
+    /// The database pool, for operations only PostgreSQL supports
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for in-memory storage.
+    pub fn pool(&self) -> Result<&DatabasePool> {
+        self.as_pool().ok_or_else(|| {
+            anyhow!("This operation needs PostgreSQL; the in-memory store does not support it")
+        })
+    }
+}
+
+impl From<DatabasePool> for CrateStorage {
+    fn from(pool: DatabasePool) -> Self {
+        Self::Postgres(pool)
+    }
+}
+
+impl From<Arc<MemoryCrateStore>> for CrateStorage {
+    fn from(store: Arc<MemoryCrateStore>) -> Self {
+        Self::Memory(store)
+    }
+}
@@ -0,0 +1,339 @@
+//! Topic and category taxonomy of a doc type
+//!
+//! A [`Taxonomy`] lists the topics and categories a doc type's documents are
+//! classified into, each as a [`TaxonomyRule`] of keywords, path patterns and
+//! a priority. It comes from a tool's `metadataHints` in `tools.json`, either
+//! as an explicit `taxonomy` object:
+//!
+//! ```json
+//! "taxonomy": {
+//!   "topics": [
+//!     {"name": "trading", "keywords": ["swap", "trade"], "path_patterns": ["*/swap/*"]},
+//!     {"name": "apis", "keywords": ["api", "endpoint"], "priority": 1}
+//!   ],
+//!   "categories": [{"name": "swap-api", "keywords": ["swap"]}]
+//! }
+//! ```
+//!
+//! or from the older `supported_topics`/`topic_keywords` and
+//! `supported_categories`/`category_keywords` hints, where a name without
+//! keywords is its own keyword.
+//!
+//! A rule scores 2 for each keyword found in the content, 1 for each keyword
+//! found in the path and 3 for each path pattern the path matches, all
+//! case-insensitively. The highest score wins; ties go to the higher
+//! priority, then to the rule listed first, which also wins when nothing
+//! matches.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+/// Score of a keyword found in the content
+const CONTENT_KEYWORD_SCORE: i32 = 2;
+
+/// Score of a keyword found in the document path
+const PATH_KEYWORD_SCORE: i32 = 1;
+
+/// Score of a path pattern matching the document path
+const PATH_PATTERN_SCORE: i32 = 3;
+
+/// Topics and categories of one doc type
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Taxonomy {
+    #[serde(default)]
+    pub topics: Vec<TaxonomyRule>,
+    #[serde(default)]
+    pub categories: Vec<TaxonomyRule>,
+}
+
+/// How to recognise one topic or category
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TaxonomyRule {
+    pub name: String,
+    /// Words looked for in the content and the path
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Globs over the whole document path; `*` matches any run of characters
+    #[serde(default)]
+    pub path_patterns: Vec<String>,
+    /// Breaks ties between equally scored rules; higher wins
+    #[serde(default)]
+    pub priority: i32,
+}
+
+impl Taxonomy {
+    /// Taxonomy described by a tool's `metadataHints`
+    ///
+    /// Uses the `taxonomy` object when present and the keyword hints
+    /// otherwise. The result is validated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the offending field if the taxonomy has
+    /// unknown fields, duplicate or empty names, or a rule that can never
+    /// match.
+    pub fn from_hints(hints: &Value) -> Result<Self> {
+        let taxonomy = match hints.get("taxonomy") {
+            Some(value) => serde_json::from_value(value.clone())
+                .map_err(|e| anyhow!("Invalid taxonomy: {e}"))?,
+            None => Self::from_keyword_hints(hints)?,
+        };
+        taxonomy.validate()?;
+        Ok(taxonomy)
+    }
+
+    /// Taxonomy of the `supported_*` and `*_keywords` hints
+    fn from_keyword_hints(hints: &Value) -> Result<Self> {
+        Ok(Self {
+            topics: keyword_rules(hints, "supported_topics", "topic_keywords")?,
+            categories: keyword_rules(hints, "supported_categories", "category_keywords")?,
+        })
+    }
+
+    /// Check that every rule has a unique name and can match something
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first offending rule.
+    pub fn validate(&self) -> Result<()> {
+        for (kind, rules) in [("topics", &self.topics), ("categories", &self.categories)] {
+            let mut names = HashSet::new();
+            for (index, rule) in rules.iter().enumerate() {
+                let at = format!("taxonomy.{kind}[{index}]");
+                if rule.name.trim().is_empty() {
+                    return Err(anyhow!("{at}: name cannot be empty"));
+                }
+                let at = format!("{at} ('{}')", rule.name);
+                if !names.insert(rule.name.as_str()) {
+                    return Err(anyhow!("{at}: duplicate name"));
+                }
+                if rule.keywords.is_empty() && rule.path_patterns.is_empty() {
+                    return Err(anyhow!("{at}: needs at least one keyword or path pattern"));
+                }
+                if rule.keywords.iter().any(|k| k.trim().is_empty()) {
+                    return Err(anyhow!("{at}: keywords cannot be empty strings"));
+                }
+                if rule.path_patterns.iter().any(|p| p.trim().is_empty()) {
+                    return Err(anyhow!("{at}: path patterns cannot be empty strings"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Best topic for a document, if the taxonomy has topics
+    #[must_use]
+    pub fn topic(&self, content: &str, doc_path: &str) -> Option<&str> {
+        best_rule(&self.topics, content, doc_path)
+    }
+
+    /// Best category for a document, if the taxonomy has categories
+    #[must_use]
+    pub fn category(&self, content: &str, doc_path: &str) -> Option<&str> {
+        best_rule(&self.categories, content, doc_path)
+    }
+}
+
+impl TaxonomyRule {
+    /// Score of a document whose content and path are lowercased
+    fn score(&self, content_lower: &str, path_lower: &str) -> i32 {
+        let keywords: i32 = self
+            .keywords
+            .iter()
+            .map(|keyword| {
+                let keyword = keyword.to_lowercase();
+                let mut score = 0;
+                if content_lower.contains(&keyword) {
+                    score += CONTENT_KEYWORD_SCORE;
+                }
+                if path_lower.contains(&keyword) {
+                    score += PATH_KEYWORD_SCORE;
+                }
+                score
+            })
+            .sum();
+        let patterns = self
+            .path_patterns
+            .iter()
+            .filter(|pattern| glob_matches(&pattern.to_lowercase(), path_lower))
+            .count();
+        keywords + PATH_PATTERN_SCORE * i32::try_from(patterns).unwrap_or(i32::MAX / 4)
+    }
+}
+
+/// Rules of the names listed under `names_key`, with keywords from `keywords_key`
+fn keyword_rules(hints: &Value, names_key: &str, keywords_key: &str) -> Result<Vec<TaxonomyRule>> {
+    let names = string_list(hints.get(names_key), names_key)?;
+    let mut keywords: HashMap<String, Vec<String>> = HashMap::new();
+    if let Some(mapping) = hints.get(keywords_key) {
+        let mapping = mapping
+            .as_object()
+            .ok_or_else(|| anyhow!("{keywords_key} must be an object of keyword lists"))?;
+        for (name, list) in mapping {
+            keywords.insert(
+                name.clone(),
+                string_list(Some(list), &format!("{keywords_key}.{name}"))?,
+            );
+        }
+    }
+
+    Ok(names
+        .into_iter()
+        .map(|name| TaxonomyRule {
+            keywords: keywords.remove(&name).unwrap_or_else(|| vec![name.clone()]),
+            name,
+            path_patterns: Vec::new(),
+            priority: 0,
+        })
+        .collect())
+}
+
+/// Strings of a JSON array; a missing value is empty
+fn string_list(value: Option<&Value>, key: &str) -> Result<Vec<String>> {
+    let Some(value) = value else {
+        return Ok(Vec::new());
+    };
+    value
+        .as_array()
+        .ok_or_else(|| anyhow!("{key} must be an array of strings"))?
+        .iter()
+        .map(|item| {
+            item.as_str()
+                .map(String::from)
+                .ok_or_else(|| anyhow!("{key} must be an array of strings"))
+        })
+        .collect()
+}
+
+/// Highest scoring rule; ties go to priority, then to the earlier rule
+fn best_rule<'a>(rules: &'a [TaxonomyRule], content: &str, doc_path: &str) -> Option<&'a str> {
+    let content_lower = content.to_lowercase();
+    let path_lower = doc_path.to_lowercase();
+    let mut best: Option<(&TaxonomyRule, i32)> = None;
+    for rule in rules {
+        let score = rule.score(&content_lower, &path_lower);
+        if best.is_none_or(|(top, top_score)| (score, rule.priority) > (top_score, top.priority)) {
+            best = Some((rule, score));
+        }
+    }
+    best.map(|(rule, _)| rule.name.as_str())
+}
+
+/// Whether `text` matches `pattern` in full, `*` matching any run of characters
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*`: the prefix must be the whole text
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{glob_matches, Taxonomy};
+    use serde_json::json;
+
+    #[test]
+    fn test_keyword_hints_become_rules() {
+        let taxonomy = Taxonomy::from_hints(&json!({
+            "supported_topics": ["trading", "apis"],
+            "topic_keywords": {"trading": ["swap", "trade"]},
+            "supported_categories": ["swap-api"]
+        }))
+        .unwrap();
+        assert_eq!(taxonomy.topics[0].keywords, ["swap", "trade"]);
+        // A name without keywords is its own keyword
+        assert_eq!(taxonomy.topics[1].keywords, ["apis"]);
+        assert_eq!(taxonomy.categories[0].keywords, ["swap-api"]);
+
+        assert_eq!(
+            taxonomy.topic("How to swap tokens", "docs/guide.md"),
+            Some("trading")
+        );
+        // Nothing matches: the first topic
+        assert_eq!(taxonomy.topic("Nothing here", "docs/x.md"), Some("trading"));
+        assert_eq!(Taxonomy::default().topic("swap", "x"), None);
+    }
+
+    #[test]
+    fn test_path_patterns_and_priorities_decide() {
+        let taxonomy = Taxonomy::from_hints(&json!({
+            "taxonomy": {
+                "topics": [
+                    {"name": "guides", "keywords": ["guide"]},
+                    {"name": "reference", "keywords": ["reference"], "priority": 1},
+                    {"name": "networking", "path_patterns": ["*/network/*"]}
+                ]
+            }
+        }))
+        .unwrap();
+        // Equal scores: the higher priority wins over the earlier rule
+        assert_eq!(
+            taxonomy.topic("A guide and a reference", "docs/a.md"),
+            Some("reference")
+        );
+        // A matching path pattern outscores a content keyword
+        assert_eq!(
+            taxonomy.topic("A guide", "docs/Network/setup.md"),
+            Some("networking")
+        );
+    }
+
+    #[test]
+    fn test_invalid_taxonomies_are_rejected() {
+        let error =
+            |hints: serde_json::Value| Taxonomy::from_hints(&hints).unwrap_err().to_string();
+
+        assert!(
+            error(json!({"taxonomy": {"topics": [{"name": "a", "keyword": ["x"]}]}}))
+                .contains("unknown field `keyword`")
+        );
+        assert!(
+            error(json!({"taxonomy": {"topics": [{"name": "a", "keywords": []}]}}))
+                .contains("taxonomy.topics[0] ('a'): needs at least one keyword or path pattern")
+        );
+        assert!(error(json!({"taxonomy": {"categories": [
+            {"name": "a", "keywords": ["x"]},
+            {"name": "a", "keywords": ["y"]}
+        ]}}))
+        .contains("taxonomy.categories[1] ('a'): duplicate name"));
+        assert!(
+            error(json!({"taxonomy": {"topics": [{"name": " ", "keywords": ["x"]}]}}))
+                .contains("taxonomy.topics[0]: name cannot be empty")
+        );
+        assert!(error(json!({
+            "supported_topics": ["a"],
+            "topic_keywords": {"a": []}
+        }))
+        .contains("needs at least one keyword"));
+        assert!(
+            error(json!({"supported_topics": "a"})).contains("supported_topics must be an array")
+        );
+    }
+
+    #[test]
+    fn test_globs_match_whole_paths() {
+        assert!(glob_matches("*/swap/*", "docs/swap/index.md"));
+        assert!(glob_matches("docs/*.md", "docs/a/b.md"));
+        assert!(glob_matches("readme.md", "readme.md"));
+        assert!(!glob_matches("readme.md", "docs/readme.md"));
+        assert!(!glob_matches("docs/*.md", "docs/a.rs"));
+        assert!(!glob_matches("*ab*ba", "aba"));
+    }
+}
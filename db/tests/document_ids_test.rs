@@ -0,0 +1,127 @@
+//! Stable document ids: re-ingestion upserts in place and existing random
+//! ids can be rewritten
+//!
+//! Tests skip when no database is configured.
+
+mod common;
+
+use chrono::Utc;
+use common::create_test_pool;
+use db::models::Document;
+use db::{new_document_id, stable_document_id, DatabasePool, DocumentQueries};
+use serde_json::json;
+use uuid::Uuid;
+
+fn document(source_name: &str, content: &str) -> Document {
+    let doc_path = format!("https://docs.rs/{source_name}/1.0.0/{source_name}/fn.run.html");
+    let metadata = json!({"crate_name": source_name, "crate_version": "1.0.0"});
+    Document {
+        id: new_document_id("rust", source_name, &doc_path, &metadata),
+        doc_type: "rust".to_string(),
+        source_name: source_name.to_string(),
+        doc_path,
+        content: content.to_string(),
+        metadata,
+        embedding: None,
+        token_count: None,
+        created_at: Some(Utc::now()),
+        updated_at: Some(Utc::now()),
+    }
+}
+
+async fn rows_of(pool: &DatabasePool, source_name: &str) -> Vec<(Uuid, String)> {
+    sqlx::query_as("SELECT id, content FROM documents WHERE source_name = $1")
+        .bind(source_name)
+        .fetch_all(pool.pool())
+        .await
+        .unwrap()
+}
+
+async fn cleanup(pool: &DatabasePool, source_name: &str) {
+    let _ = sqlx::query("DELETE FROM documents WHERE source_name = $1")
+        .bind(source_name)
+        .execute(pool.pool())
+        .await;
+}
+
+#[tokio::test]
+async fn test_reingestion_updates_the_same_document() {
+    let Some(pool) = create_test_pool().await else {
+        println!("⚠️ Skipping database-dependent test - no database available");
+        return;
+    };
+    let source_name = format!("ids-{}", Uuid::new_v4().simple());
+
+    let first = document(&source_name, "First run");
+    let second = document(&source_name, "Second run");
+    assert_eq!(first.id, second.id);
+
+    DocumentQueries::batch_insert_documents(pool.pool(), std::slice::from_ref(&first))
+        .await
+        .unwrap();
+    DocumentQueries::insert_document(pool.pool(), &second)
+        .await
+        .unwrap();
+    let rows = rows_of(&pool, &source_name).await;
+    cleanup(&pool, &source_name).await;
+
+    assert_eq!(rows, [(first.id, "Second run".to_string())]);
+    assert_eq!(
+        first.id,
+        stable_document_id("rust", &source_name, &first.doc_path, Some("1.0.0"))
+    );
+}
+
+#[tokio::test]
+async fn test_random_ids_are_rewritten_with_their_references() {
+    let Some(pool) = create_test_pool().await else {
+        println!("⚠️ Skipping database-dependent test - no database available");
+        return;
+    };
+    let source_name = format!("ids-{}", Uuid::new_v4().simple());
+    let mut doc = document(&source_name, "Ingested with a random id");
+    let stable_id = doc.id;
+    doc.id = Uuid::new_v4();
+    DocumentQueries::batch_insert_documents(pool.pool(), std::slice::from_ref(&doc))
+        .await
+        .unwrap();
+    let feedback = sqlx::query(
+        "INSERT INTO search_feedback (query, rating, document_id, document_found) VALUES ($1, 'useful', $2, TRUE)",
+    )
+    .bind(&source_name)
+    .bind(doc.id)
+    .execute(pool.pool())
+    .await
+    .is_ok();
+
+    let dry_run = DocumentQueries::rewrite_document_ids(pool.pool(), true).await;
+    let after_dry_run = rows_of(&pool, &source_name).await;
+    let rewrite = DocumentQueries::rewrite_document_ids(pool.pool(), false).await;
+    let after_rewrite = rows_of(&pool, &source_name).await;
+    let rerun = DocumentQueries::rewrite_document_ids(pool.pool(), true).await;
+    let referenced: Option<Uuid> = if feedback {
+        sqlx::query_scalar("SELECT document_id FROM search_feedback WHERE query = $1")
+            .bind(&source_name)
+            .fetch_one(pool.pool())
+            .await
+            .ok()
+    } else {
+        None
+    };
+    let _ = sqlx::query("DELETE FROM search_feedback WHERE query = $1")
+        .bind(&source_name)
+        .execute(pool.pool())
+        .await;
+    cleanup(&pool, &source_name).await;
+
+    let (examined, changed) = dry_run.unwrap();
+    assert!(changed >= 1 && examined >= changed);
+    assert_eq!(after_dry_run[0].0, doc.id, "dry run must not write");
+
+    rewrite.unwrap();
+    assert_eq!(after_rewrite[0].0, stable_id);
+    assert_eq!(rerun.unwrap().1, 0, "rewriting is idempotent");
+    if feedback {
+        assert_eq!(referenced, Some(stable_id));
+    }
+}
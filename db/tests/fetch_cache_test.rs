@@ -0,0 +1,105 @@
+//! Fetch cache tests
+//!
+//! Cached validators are only handed out while the page's document is
+//! stored. Tests skip when no database is configured.
+
+mod common;
+
+use db::models::FetchCacheEntry;
+use db::{DatabasePool, FetchCacheQueries};
+use serde_json::json;
+use uuid::Uuid;
+
+/// Test database pool, or `None` without one or without the `fetch_cache` table
+async fn create_test_pool() -> Option<DatabasePool> {
+    let pool = common::create_test_pool().await?;
+    let has_table: Option<String> =
+        sqlx::query_scalar("SELECT to_regclass('public.fetch_cache')::text")
+            .fetch_one(pool.pool())
+            .await
+            .ok()?;
+    has_table.map(|_| pool)
+}
+
+#[tokio::test]
+async fn test_lookup_requires_stored_document() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database with fetch_cache configured");
+        return;
+    };
+
+    let source_name = format!("fetch-cache-test-{}", Uuid::new_v4());
+    let url = format!("https://docs.rs/{source_name}/1.0.0/{source_name}/index.html");
+    let entry = FetchCacheEntry {
+        url: url.clone(),
+        etag: Some("\"v1\"".to_string()),
+        last_modified: None,
+        links: vec![format!("{url}#links")],
+    };
+    FetchCacheQueries::store(pool.pool(), "1.0.0", std::slice::from_ref(&entry))
+        .await
+        .unwrap();
+
+    // No document for the page yet, so a 304 could not be honoured
+    let missing = FetchCacheQueries::lookup(pool.pool(), &source_name, "1.0.0", &url)
+        .await
+        .unwrap();
+    assert!(missing.is_none());
+
+    sqlx::query(
+        "INSERT INTO document_sources (doc_type, source_name, config, enabled)
+         VALUES ('rust', $1, '{}', true)",
+    )
+    .bind(&source_name)
+    .execute(pool.pool())
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO documents (id, doc_type, source_name, doc_path, content, metadata)
+         VALUES ($1, 'rust', $2, $3, 'content', $4)",
+    )
+    .bind(Uuid::new_v4())
+    .bind(&source_name)
+    .bind(&url)
+    .bind(json!({"source_url": url}))
+    .execute(pool.pool())
+    .await
+    .unwrap();
+
+    let found = FetchCacheQueries::lookup(pool.pool(), &source_name, "1.0.0", &url)
+        .await
+        .unwrap();
+    assert_eq!(found, Some(entry.clone()));
+
+    // Entries are per crate version
+    let other_version = FetchCacheQueries::lookup(pool.pool(), &source_name, "2.0.0", &url)
+        .await
+        .unwrap();
+    assert!(other_version.is_none());
+
+    // Storing again replaces the validators
+    let updated = FetchCacheEntry {
+        etag: Some("\"v2\"".to_string()),
+        ..entry
+    };
+    FetchCacheQueries::store(pool.pool(), "1.0.0", std::slice::from_ref(&updated))
+        .await
+        .unwrap();
+    let found = FetchCacheQueries::lookup(pool.pool(), &source_name, "1.0.0", &url)
+        .await
+        .unwrap();
+    assert_eq!(found.and_then(|e| e.etag).as_deref(), Some("\"v2\""));
+
+    for table in ["documents", "document_sources"] {
+        let _ = sqlx::query(&format!(
+            "DELETE FROM {table} WHERE doc_type = 'rust' AND source_name = $1"
+        ))
+        .bind(&source_name)
+        .execute(pool.pool())
+        .await;
+    }
+    let _ = sqlx::query("DELETE FROM fetch_cache WHERE url = $1")
+        .bind(&url)
+        .execute(pool.pool())
+        .await;
+}
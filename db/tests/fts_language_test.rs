@@ -0,0 +1,185 @@
+//! Full-text search language and weighting per doc type
+//!
+//! Each test seeds its own doc types with `fts_language` set in the source
+//! config. Tests skip when no database is configured.
+
+mod common;
+
+use common::create_test_pool;
+use db::queries::MetadataFilters;
+use db::{DatabasePool, DocumentQueries, DocumentSourceQueries, FtsSettings};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// Create a doc type with one source using `config` and the given documents
+async fn seed(pool: &DatabasePool, config: Value, docs: &[(&str, &str)]) -> String {
+    let doc_type = format!("fts-{}", Uuid::new_v4().simple());
+    sqlx::query(
+        "INSERT INTO document_sources (doc_type, source_name, config, enabled) VALUES ($1, 'main', $2, true)",
+    )
+    .bind(&doc_type)
+    .bind(config)
+    .execute(pool.pool())
+    .await
+    .expect("seed source");
+
+    for (path, content) in docs {
+        sqlx::query(
+            "INSERT INTO documents (id, doc_type, source_name, doc_path, content, metadata)
+             VALUES ($1, $2, 'main', $3, $4, '{}')",
+        )
+        .bind(Uuid::new_v4())
+        .bind(&doc_type)
+        .bind(path)
+        .bind(content)
+        .execute(pool.pool())
+        .await
+        .expect("seed document");
+    }
+    doc_type
+}
+
+async fn cleanup(pool: &DatabasePool, doc_type: &str) {
+    for table in ["documents", "document_sources"] {
+        let _ = sqlx::query(&format!("DELETE FROM {table} WHERE doc_type = $1"))
+            .bind(doc_type)
+            .execute(pool.pool())
+            .await;
+    }
+}
+
+/// Paths of the documents a search returns, best first
+async fn search(pool: &DatabasePool, doc_type: &str, query: &str) -> Vec<String> {
+    DocumentQueries::doc_type_vector_search(pool.pool(), doc_type, query, &[], 10)
+        .await
+        .expect("search")
+        .into_iter()
+        .map(|doc| doc.doc_path)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_stemming_follows_the_configured_language() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database configured");
+        return;
+    };
+
+    let docs = [
+        ("es/guia.md", "Las bibliotecas cargan documentos"),
+        ("en/guide.md", "The workers are running tasks"),
+    ];
+    let spanish = seed(&pool, json!({"fts_language": "spanish"}), &docs).await;
+    let english = seed(&pool, json!({}), &docs).await;
+
+    // "cargar" and "cargan" share a Spanish stem only
+    assert_eq!(search(&pool, &spanish, "cargar").await, ["es/guia.md"]);
+    assert!(search(&pool, &english, "cargar").await.is_empty());
+    // The filtered search uses the same settings
+    let scored = DocumentQueries::doc_type_search_scored(
+        pool.pool(),
+        &spanish,
+        "cargar",
+        &[],
+        10,
+        &MetadataFilters::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(scored.len(), 1);
+    assert!(scored[0].score > 0.0);
+    // Highlighting stems the same way as the match
+    let fts = DocumentSourceQueries::fts_settings(pool.pool(), &spanish)
+        .await
+        .unwrap();
+    let terms = DocumentQueries::headline_terms(pool.pool(), &fts, "cargar", &[docs[0].1])
+        .await
+        .unwrap();
+    assert_eq!(terms, [vec!["cargan".to_string()]]);
+    // The language gets its own title and content indexes
+    let languages = DocumentSourceQueries::ensure_fts_indexes(pool.pool())
+        .await
+        .unwrap();
+    assert!(languages.contains(&"spanish".to_string()), "{languages:?}");
+    let indexed: i64 = sqlx::query_scalar(
+        "SELECT count(*) FROM pg_indexes WHERE indexname IN ('idx_documents_fts_spanish', 'idx_documents_fts_title_spanish')",
+    )
+    .fetch_one(pool.pool())
+    .await
+    .unwrap();
+    assert_eq!(indexed, 2);
+    // English stays the default
+    assert_eq!(search(&pool, &english, "runs").await, ["en/guide.md"]);
+
+    cleanup(&pool, &spanish).await;
+    cleanup(&pool, &english).await;
+}
+
+#[tokio::test]
+async fn test_unsupported_language_falls_back_to_simple() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database configured");
+        return;
+    };
+
+    let doc_type = seed(
+        &pool,
+        json!({"fts_language": "klingon"}),
+        &[("en/guide.md", "The workers are running tasks")],
+    )
+    .await;
+
+    let settings = DocumentSourceQueries::fts_settings(pool.pool(), &doc_type)
+        .await
+        .unwrap();
+    assert_eq!(settings.language, "simple");
+    // No stemming, exact words only
+    assert!(search(&pool, &doc_type, "runs").await.is_empty());
+    assert_eq!(search(&pool, &doc_type, "running").await, ["en/guide.md"]);
+
+    cleanup(&pool, &doc_type).await;
+}
+
+#[tokio::test]
+async fn test_path_matches_outrank_content_matches() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database configured");
+        return;
+    };
+
+    let docs = [
+        ("guides/other.md", "The scheduler runs tasks"),
+        ("guides/scheduler.md", "Notes on queued tasks"),
+    ];
+    let weighted = seed(&pool, json!({}), &docs).await;
+    assert_eq!(
+        search(&pool, &weighted, "scheduler").await,
+        ["guides/scheduler.md", "guides/other.md"]
+    );
+
+    // Weights come from the config too
+    let inverted = seed(
+        &pool,
+        json!({"fts_title_weight": 0.1, "fts_content_weight": 1.0}),
+        &docs,
+    )
+    .await;
+    let settings = DocumentSourceQueries::fts_settings(pool.pool(), &inverted)
+        .await
+        .unwrap();
+    assert_eq!(
+        settings,
+        FtsSettings {
+            language: "english".to_string(),
+            title_weight: 0.1,
+            content_weight: 1.0,
+        }
+    );
+    assert_eq!(
+        search(&pool, &inverted, "scheduler").await,
+        ["guides/other.md", "guides/scheduler.md"]
+    );
+
+    cleanup(&pool, &weighted).await;
+    cleanup(&pool, &inverted).await;
+}
@@ -0,0 +1,431 @@
+//! Crate job dispatch tests
+//!
+//! Creating a job must notify `crate_jobs_changed`, claiming must skip jobs
+//! another dispatcher holds, retried calls with an idempotency key must reuse
+//! one job, and status changes must leave an event trail. Tests skip when no
+//! database is configured.
+
+mod common;
+
+use db::models::JobStatus;
+use db::queries::CRATE_JOBS_CHANNEL;
+use db::{CrateJobQueries, DatabasePool, IdempotencyConflict};
+use serde_json::json;
+use sqlx::postgres::PgListener;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Claims take every queued job, so tests that claim must not overlap
+static CLAIM_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Test database pool, or `None` without one or without `crate_jobs.options`
+async fn create_test_pool() -> Option<DatabasePool> {
+    let pool = common::create_test_pool().await?;
+    let has_options: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM information_schema.columns
+                        WHERE table_name = 'crate_jobs' AND column_name = 'options')",
+    )
+    .fetch_one(pool.pool())
+    .await
+    .ok()?;
+    has_options.then_some(pool)
+}
+
+async fn cleanup(pool: &DatabasePool, crate_name: &str) {
+    let _ = sqlx::query("DELETE FROM crate_jobs WHERE crate_name = $1")
+        .bind(crate_name)
+        .execute(pool.pool())
+        .await;
+}
+
+#[tokio::test]
+async fn test_create_job_notifies_with_options() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database with crate_jobs.options configured");
+        return;
+    };
+
+    let mut listener = PgListener::connect_with(pool.pool()).await.unwrap();
+    listener.listen(CRATE_JOBS_CHANNEL).await.unwrap();
+
+    let crate_name = format!("dispatch-test-{}", Uuid::new_v4());
+    let options = json!({"version": "1.0.0", "force_update": true});
+    let job = CrateJobQueries::create_job_with_options(
+        pool.pool(),
+        &crate_name,
+        "add_crate",
+        Some(&options),
+    )
+    .await
+    .unwrap();
+    assert_eq!(job.status, JobStatus::Queued);
+    assert_eq!(job.options, Some(options));
+
+    // Other jobs may be created concurrently; wait for ours
+    let received = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let notification = listener.recv().await.unwrap();
+            if notification.payload() == job.id.to_string() {
+                break;
+            }
+        }
+    })
+    .await;
+    assert!(received.is_ok(), "no notification for job {}", job.id);
+
+    cleanup(&pool, &crate_name).await;
+}
+
+#[tokio::test]
+async fn test_claim_skips_locked_jobs() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database with crate_jobs.options configured");
+        return;
+    };
+    let _guard = CLAIM_LOCK.lock().await;
+
+    let crate_name = format!("dispatch-test-{}", Uuid::new_v4());
+    let held = CrateJobQueries::create_job(pool.pool(), &crate_name, "add_crate")
+        .await
+        .unwrap();
+
+    // Another dispatcher holding the row lock hides it from this claim
+    let mut tx = pool.pool().begin().await.unwrap();
+    sqlx::query("SELECT id FROM crate_jobs WHERE id = $1 FOR UPDATE")
+        .bind(held.id)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    let claimed = CrateJobQueries::claim_queued_jobs(pool.pool(), 1000)
+        .await
+        .unwrap();
+    assert!(claimed.iter().all(|job| job.id != held.id));
+    tx.rollback().await.unwrap();
+
+    let claimed = CrateJobQueries::claim_queued_jobs(pool.pool(), 1000)
+        .await
+        .unwrap();
+    let job = claimed.iter().find(|job| job.id == held.id).unwrap();
+    assert_eq!(job.status, JobStatus::Running);
+
+    // A claimed job is never handed out twice
+    let again = CrateJobQueries::claim_queued_jobs(pool.pool(), 1000)
+        .await
+        .unwrap();
+    assert!(again.iter().all(|job| job.id != held.id));
+
+    cleanup(&pool, &crate_name).await;
+}
+
+#[tokio::test]
+async fn test_retry_and_dead_letter_lifecycle() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database with crate_jobs.options configured");
+        return;
+    };
+    let _guard = CLAIM_LOCK.lock().await;
+
+    let crate_name = format!("dispatch-test-{}", Uuid::new_v4());
+    let job = CrateJobQueries::create_job(pool.pool(), &crate_name, "add_crate")
+        .await
+        .unwrap();
+    let claimed = CrateJobQueries::claim_queued_jobs(pool.pool(), 1000)
+        .await
+        .unwrap();
+    let claimed = claimed.iter().find(|j| j.id == job.id).unwrap();
+    assert_eq!(claimed.attempts, 1);
+
+    // A rescheduled job is not claimable before next_run_at
+    let details = json!({"last_error_kind": "retryable"});
+    let next_run_at = chrono::Utc::now() + chrono::Duration::hours(1);
+    let retried = CrateJobQueries::schedule_retry(
+        pool.pool(),
+        job.id,
+        next_run_at,
+        "HTTP status: 503",
+        &details,
+    )
+    .await
+    .unwrap();
+    assert_eq!(retried.status, JobStatus::Queued);
+    let claimed = CrateJobQueries::claim_queued_jobs(pool.pool(), 1000)
+        .await
+        .unwrap();
+    assert!(claimed.iter().all(|j| j.id != job.id));
+
+    let dead = CrateJobQueries::dead_letter_job(pool.pool(), job.id, "HTTP status: 404", &details)
+        .await
+        .unwrap();
+    assert_eq!(dead.status, JobStatus::Failed);
+    assert!(dead.is_dead_lettered());
+    assert_eq!(dead.attempts, dead.max_attempts);
+    let dead_letters = CrateJobQueries::find_dead_letter_jobs(pool.pool(), 1000)
+        .await
+        .unwrap();
+    assert!(dead_letters.iter().any(|j| j.id == job.id));
+
+    let requeued = CrateJobQueries::requeue_job(pool.pool(), job.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(requeued.status, JobStatus::Queued);
+    assert_eq!(requeued.attempts, 0);
+    assert!(!requeued.is_dead_lettered());
+    // Only failed or cancelled jobs can be requeued
+    assert!(CrateJobQueries::requeue_job(pool.pool(), job.id)
+        .await
+        .unwrap()
+        .is_none());
+
+    cleanup(&pool, &crate_name).await;
+}
+
+/// Whether the test database has the `crate_jobs.idempotency_key` column
+async fn has_idempotency_key(pool: &DatabasePool) -> bool {
+    sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM information_schema.columns
+                        WHERE table_name = 'crate_jobs' AND column_name = 'idempotency_key')",
+    )
+    .fetch_one(pool.pool())
+    .await
+    .unwrap_or(false)
+}
+
+#[tokio::test]
+async fn test_concurrent_calls_with_same_idempotency_key_create_one_job() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database with crate_jobs.options configured");
+        return;
+    };
+    if !has_idempotency_key(&pool).await {
+        println!("Skipping test - crate_jobs.idempotency_key not migrated");
+        return;
+    }
+
+    let crate_name = format!("dispatch-test-{}", Uuid::new_v4());
+    let key = format!("retry-{}", Uuid::new_v4());
+    let options = json!({"version": "1.0.0"});
+
+    let mut calls = tokio::task::JoinSet::new();
+    for _ in 0..8 {
+        let pool = pool.clone();
+        let (crate_name, key, options) = (crate_name.clone(), key.clone(), options.clone());
+        calls.spawn(async move {
+            CrateJobQueries::create_job_idempotent(
+                pool.pool(),
+                &crate_name,
+                "add_crate",
+                &options,
+                &key,
+            )
+            .await
+            .unwrap()
+        });
+    }
+    let results = calls.join_all().await;
+
+    assert_eq!(results.iter().filter(|(_, created)| *created).count(), 1);
+    let job_id = results[0].0.id;
+    assert!(results.iter().all(|(job, _)| job.id == job_id));
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM crate_jobs WHERE crate_name = $1")
+        .bind(&crate_name)
+        .fetch_one(pool.pool())
+        .await
+        .unwrap();
+    assert_eq!(stored, 1);
+
+    let found = CrateJobQueries::find_job_by_idempotency_key(pool.pool(), &key)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.id, job_id);
+
+    cleanup(&pool, &crate_name).await;
+}
+
+#[tokio::test]
+async fn test_expired_idempotency_key_creates_new_job() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database with crate_jobs.options configured");
+        return;
+    };
+    if !has_idempotency_key(&pool).await {
+        println!("Skipping test - crate_jobs.idempotency_key not migrated");
+        return;
+    }
+
+    let crate_name = format!("dispatch-test-{}", Uuid::new_v4());
+    let key = format!("retry-{}", Uuid::new_v4());
+    let options = json!({});
+    let (first, created) = CrateJobQueries::create_job_idempotent(
+        pool.pool(),
+        &crate_name,
+        "add_crate",
+        &options,
+        &key,
+    )
+    .await
+    .unwrap();
+    assert!(created);
+
+    sqlx::query("UPDATE crate_jobs SET created_at = NOW() - INTERVAL '25 hours' WHERE id = $1")
+        .bind(first.id)
+        .execute(pool.pool())
+        .await
+        .unwrap();
+    assert!(
+        CrateJobQueries::find_job_by_idempotency_key(pool.pool(), &key)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    let (second, created) = CrateJobQueries::create_job_idempotent(
+        pool.pool(),
+        &crate_name,
+        "add_crate",
+        &options,
+        &key,
+    )
+    .await
+    .unwrap();
+    assert!(created);
+    assert_ne!(second.id, first.id);
+
+    cleanup(&pool, &crate_name).await;
+}
+
+#[tokio::test]
+async fn test_idempotency_key_reused_for_another_request_conflicts() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database with crate_jobs.options configured");
+        return;
+    };
+    if !has_idempotency_key(&pool).await {
+        println!("Skipping test - crate_jobs.idempotency_key not migrated");
+        return;
+    }
+
+    let crate_name = format!("dispatch-test-{}", Uuid::new_v4());
+    let key = format!("retry-{}", Uuid::new_v4());
+    let options = json!({});
+    let (first, created) = CrateJobQueries::create_job_idempotent(
+        pool.pool(),
+        &crate_name,
+        "add_crate",
+        &options,
+        &key,
+    )
+    .await
+    .unwrap();
+    assert!(created);
+
+    // The same crate under its underscore spelling is the same request
+    let (replayed, created) = CrateJobQueries::create_job_idempotent(
+        pool.pool(),
+        &crate_name.replace('-', "_"),
+        "add_crate",
+        &options,
+        &key,
+    )
+    .await
+    .unwrap();
+    assert!(!created);
+    assert_eq!(replayed.id, first.id);
+
+    for (other_crate, operation) in [
+        (format!("{crate_name}-other"), "add_crate"),
+        (crate_name.clone(), "remove_crate"),
+    ] {
+        let err = CrateJobQueries::create_job_idempotent(
+            pool.pool(),
+            &other_crate,
+            operation,
+            &options,
+            &key,
+        )
+        .await
+        .unwrap_err();
+        let conflict = err
+            .downcast_ref::<IdempotencyConflict>()
+            .expect("a reused key is a conflict");
+        assert_eq!(conflict.job_id, first.id);
+    }
+
+    cleanup(&pool, &crate_name).await;
+}
+
+#[tokio::test]
+async fn test_status_updates_and_events_form_audit_trail() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database with crate_jobs.options configured");
+        return;
+    };
+    let has_events: bool =
+        sqlx::query_scalar("SELECT to_regclass('public.crate_job_events') IS NOT NULL")
+            .fetch_one(pool.pool())
+            .await
+            .unwrap_or(false);
+    if !has_events {
+        println!("Skipping test - crate_job_events not migrated");
+        return;
+    }
+
+    let crate_name = format!("dispatch-test-{}", Uuid::new_v4());
+    let job = CrateJobQueries::create_job(pool.pool(), &crate_name, "add_crate")
+        .await
+        .unwrap();
+    let db = pool.pool();
+    CrateJobQueries::update_job_status(db, job.id, JobStatus::Running, Some(0), None)
+        .await
+        .unwrap();
+    // A heartbeat changes nothing and leaves no event
+    CrateJobQueries::update_job_status(db, job.id, JobStatus::Running, None, None)
+        .await
+        .unwrap();
+    CrateJobQueries::update_job_status(db, job.id, JobStatus::Running, Some(40), None)
+        .await
+        .unwrap();
+    CrateJobQueries::record_job_event(db, job.id, "batch_committed", &json!({"pages": 50}))
+        .await
+        .unwrap();
+    CrateJobQueries::update_job_status(db, job.id, JobStatus::Failed, Some(40), Some("boom"))
+        .await
+        .unwrap();
+
+    let events = CrateJobQueries::find_job_events(db, job.id, 20)
+        .await
+        .unwrap();
+    let types: Vec<&str> = events.iter().rev().map(|e| e.event_type.as_str()).collect();
+    assert_eq!(
+        types,
+        [
+            "status_changed",
+            "progress",
+            "batch_committed",
+            "status_changed"
+        ]
+    );
+    let failed = &events[0];
+    assert_eq!(failed.old_status, Some(JobStatus::Running));
+    assert_eq!(failed.new_status, Some(JobStatus::Failed));
+    assert_eq!(failed.detail, Some(json!({"error": "boom"})));
+    assert_eq!(events[1].progress, Some(40));
+    assert_eq!(events[1].new_status, Some(JobStatus::Running));
+
+    let newest = CrateJobQueries::find_job_events(db, job.id, 1)
+        .await
+        .unwrap();
+    assert_eq!(newest.len(), 1);
+    assert_eq!(newest[0].id, failed.id);
+
+    // Events go with their job
+    cleanup(&pool, &crate_name).await;
+    let left: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM crate_job_events WHERE job_id = $1")
+        .bind(job.id)
+        .fetch_one(db)
+        .await
+        .unwrap();
+    assert_eq!(left, 0);
+}
@@ -0,0 +1,189 @@
+//! Shared [`JobStore`] behaviour of the crate and ingest job tables
+//!
+//! The same checks run against in-memory crate storage, `crate_jobs` and
+//! `ingest_jobs`. Database tests skip when no database is configured.
+
+mod common;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use common::create_test_pool;
+use db::models::JobStatus;
+use db::{
+    CrateStorage, DatabasePool, IngestJobStore, JobRecord, JobStore, NewCrateJob, NewIngestJob,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+fn new_crate_job() -> NewCrateJob {
+    NewCrateJob {
+        crate_name: format!("jobstore-{}", Uuid::new_v4().simple()),
+        operation: "add_crate".to_string(),
+        options: json!({}),
+        idempotency_key: None,
+    }
+}
+
+fn new_ingest_job() -> NewIngestJob {
+    NewIngestJob {
+        url: "https://github.com/example/widgets".to_string(),
+        doc_type: format!("jobstore-{}", Uuid::new_v4().simple()),
+    }
+}
+
+/// Create a job and take it through running, heartbeats and completion
+async fn check_lifecycle<S: JobStore>(store: &S, new_job: &S::NewJob) -> Uuid {
+    let (job, created) = store.create(new_job).await.unwrap();
+    assert!(created);
+    assert_eq!(job.status(), &JobStatus::Queued);
+    let id = job.id();
+    assert_eq!(store.find(id).await.unwrap().unwrap().id(), id);
+    assert!(store.find(Uuid::new_v4()).await.unwrap().is_none());
+
+    // Heartbeats only touch running jobs
+    assert!(!store.heartbeat(id).await.unwrap());
+    let running = store
+        .transition(id, JobStatus::Running, None)
+        .await
+        .unwrap();
+    assert_eq!(running.status(), &JobStatus::Running);
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    assert!(store.heartbeat(id).await.unwrap());
+    let beaten = store.find(id).await.unwrap().unwrap();
+    assert!(beaten.updated_at() > running.updated_at());
+    assert_eq!(beaten.status(), &JobStatus::Running);
+
+    // Fresh running jobs are not stale
+    let stale = store
+        .find_stale(beaten.updated_at() - Duration::seconds(1))
+        .await
+        .unwrap();
+    assert!(stale.iter().all(|job| job.id() != id));
+
+    let done = store
+        .transition(id, JobStatus::Completed, None)
+        .await
+        .unwrap();
+    assert_eq!(done.status(), &JobStatus::Completed);
+    assert!(!store.heartbeat(id).await.unwrap());
+    store.cleanup().await.unwrap();
+    id
+}
+
+/// Recover a running job whose last update is before `cutoff`
+async fn check_stale_recovery<S: JobStore>(store: &S, id: Uuid, cutoff: DateTime<Utc>) {
+    let stale = store.find_stale(cutoff).await.unwrap();
+    assert!(stale.iter().any(|job| job.id() == id));
+
+    let recovered = store
+        .recover_stale(cutoff, "Recovery: stale in test")
+        .await
+        .unwrap();
+    assert!(recovered >= 1);
+    let job = store.find(id).await.unwrap().unwrap();
+    assert_eq!(job.status(), &JobStatus::Failed);
+    assert!(store.find_stale(cutoff).await.unwrap().is_empty());
+}
+
+/// Move a job's last update to 2000, well before any other running job
+async fn backdate(pool: &DatabasePool, table: &str, id: Uuid) -> DateTime<Utc> {
+    let at = Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap();
+    let mut tx = pool.pool().begin().await.unwrap();
+    // Skip the trigger that stamps `updated_at` on every update
+    sqlx::query("SET LOCAL session_replication_role = replica")
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    sqlx::query(&format!("UPDATE {table} SET updated_at = $2 WHERE id = $1"))
+        .bind(id)
+        .bind(at)
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+    at + Duration::seconds(1)
+}
+
+async fn delete_job(pool: &DatabasePool, table: &str, id: Uuid) {
+    let _ = sqlx::query(&format!("DELETE FROM {table} WHERE id = $1"))
+        .bind(id)
+        .execute(pool.pool())
+        .await;
+}
+
+#[tokio::test]
+async fn test_in_memory_crate_jobs() {
+    let store = CrateStorage::in_memory();
+    check_lifecycle(&store, &new_crate_job()).await;
+
+    let (job, _) = store.create(&new_crate_job()).await.unwrap();
+    store
+        .transition(job.id, JobStatus::Running, None)
+        .await
+        .unwrap();
+    check_stale_recovery(&store, job.id, Utc::now() + Duration::seconds(1)).await;
+    let failed = store.find(job.id).await.unwrap().unwrap();
+    assert_eq!(failed.error.as_deref(), Some("Recovery: stale in test"));
+}
+
+#[tokio::test]
+async fn test_postgres_crate_jobs() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database configured");
+        return;
+    };
+    let store = CrateStorage::from(pool.clone());
+    let finished = check_lifecycle(&store, &new_crate_job()).await;
+
+    let (job, _) = store.create(&new_crate_job()).await.unwrap();
+    store
+        .store()
+        .update_job_status(job.id, JobStatus::Running, Some(40), None)
+        .await
+        .unwrap();
+    // Crate transitions keep the progress the job reported
+    let running = store
+        .transition(job.id, JobStatus::Running, None)
+        .await
+        .unwrap();
+    assert_eq!(running.progress, Some(40));
+    let cutoff = backdate(&pool, "crate_jobs", job.id).await;
+    check_stale_recovery(&store, job.id, cutoff).await;
+    let failed = store.find(job.id).await.unwrap().unwrap();
+    assert_eq!(failed.progress, Some(40));
+    assert!(failed.finished_at.is_some());
+
+    delete_job(&pool, "crate_jobs", finished).await;
+    delete_job(&pool, "crate_jobs", job.id).await;
+}
+
+#[tokio::test]
+async fn test_postgres_ingest_jobs() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database configured");
+        return;
+    };
+    let store = IngestJobStore::new(pool.clone());
+    let finished = check_lifecycle(&store, &new_ingest_job()).await;
+
+    let (job, _) = store.create(&new_ingest_job()).await.unwrap();
+    store
+        .update_job_status(
+            job.id,
+            JobStatus::Running,
+            Some("Step 1/2: git clone"),
+            Some("earlier warning"),
+        )
+        .await
+        .unwrap();
+    let cutoff = backdate(&pool, "ingest_jobs", job.id).await;
+    check_stale_recovery(&store, job.id, cutoff).await;
+    let failed = store.find(job.id).await.unwrap().unwrap();
+    assert_eq!(failed.output.as_deref(), Some("Step 1/2: git clone"));
+    assert_eq!(
+        failed.error.as_deref(),
+        Some("earlier warning\nRecovery: stale in test")
+    );
+
+    delete_job(&pool, "ingest_jobs", finished).await;
+    delete_job(&pool, "ingest_jobs", job.id).await;
+}
@@ -0,0 +1,54 @@
+//! Pool settings from `DB_*` variables reach the server's connections
+//!
+//! Tests skip when no database is configured.
+
+use db::{DatabasePool, PoolConfig};
+use std::collections::HashMap;
+use std::env;
+use uuid::Uuid;
+
+/// Test database URL, or `None` when tests should be skipped
+fn test_database_url() -> Option<String> {
+    let database_url = env::var("TEST_DATABASE_URL")
+        .or_else(|_| env::var("DATABASE_URL"))
+        .ok()?;
+
+    if database_url.trim().is_empty() || database_url.trim().eq_ignore_ascii_case("mock") {
+        return None;
+    }
+    Some(database_url)
+}
+
+#[tokio::test]
+async fn test_connections_carry_application_name_and_statement_timeout() {
+    let Some(database_url) = test_database_url() else {
+        println!("⚠️ Skipping database-dependent test - no database available");
+        return;
+    };
+    let application_name = format!("pool-test-{}", Uuid::new_v4().simple());
+    let vars = HashMap::from([
+        ("DATABASE_URL", database_url),
+        ("DB_MAX_CONNECTIONS", "2".to_string()),
+        ("DB_MIN_CONNECTIONS", "1".to_string()),
+        ("DB_STATEMENT_TIMEOUT_MS", "1500".to_string()),
+        ("DB_APP_NAME", application_name.clone()),
+    ]);
+    let config = PoolConfig::from_vars(|name| vars.get(name).cloned()).unwrap();
+    let Ok(pool) = DatabasePool::with_config(config).await else {
+        println!("⚠️ Skipping database-dependent test - database unreachable");
+        return;
+    };
+
+    let (listed, statement_timeout): (Option<String>, String) = sqlx::query_as(
+        "SELECT application_name, current_setting('statement_timeout')
+         FROM pg_stat_activity
+         WHERE pid = pg_backend_pid()",
+    )
+    .fetch_one(pool.pool())
+    .await
+    .unwrap();
+    pool.pool().close().await;
+
+    assert_eq!(listed.as_deref(), Some(application_name.as_str()));
+    assert_eq!(statement_timeout, "1500ms");
+}
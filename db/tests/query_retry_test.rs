@@ -0,0 +1,86 @@
+//! Retry of queries whose connection dies underneath them
+//!
+//! The pool's only connection is terminated from a second connection, so
+//! the next query fails on it and is replayed on a fresh one. Tests skip
+//! when no database is configured.
+
+use db::{execute_with_retry, retry_counts, CrateQueries};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use std::env;
+
+/// Database URL for the tests, or `None` when tests should be skipped
+fn database_url() -> Option<String> {
+    let url = env::var("TEST_DATABASE_URL")
+        .or_else(|_| env::var("DATABASE_URL"))
+        .ok()?;
+    if url.trim().is_empty() || url.trim().eq_ignore_ascii_case("mock") {
+        return None;
+    }
+    Some(url)
+}
+
+/// Single-connection pool that hands out connections without checking them
+async fn single_connection_pool(url: &str) -> PgPool {
+    PgPoolOptions::new()
+        .max_connections(1)
+        .test_before_acquire(false)
+        .connect(url)
+        .await
+        .expect("connect")
+}
+
+/// Terminate the backend behind the pool's idle connection
+async fn kill_pooled_connection(pool: &PgPool, url: &str) {
+    let pid: i32 = sqlx::query_scalar("SELECT pg_backend_pid()")
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    let killer = PgPool::connect(url).await.unwrap();
+    let terminated: bool = sqlx::query_scalar("SELECT pg_terminate_backend($1)")
+        .bind(pid)
+        .fetch_one(&killer)
+        .await
+        .unwrap();
+    assert!(terminated);
+    killer.close().await;
+}
+
+#[tokio::test]
+async fn test_query_survives_a_terminated_connection() {
+    let Some(url) = database_url() else {
+        println!("Skipping test - no database configured");
+        return;
+    };
+    let pool = single_connection_pool(&url).await;
+    kill_pooled_connection(&pool, &url).await;
+
+    let before = retry_counts().connection;
+    let count = CrateQueries::count_rust_documents(&pool)
+        .await
+        .expect("query should be retried on a new connection");
+    assert!(count >= 0);
+    assert!(retry_counts().connection > before);
+
+    pool.close().await;
+}
+
+#[tokio::test]
+async fn test_permanent_errors_are_not_retried() {
+    let Some(url) = database_url() else {
+        println!("Skipping test - no database configured");
+        return;
+    };
+    let pool = single_connection_pool(&url).await;
+
+    let mut attempts = 0;
+    let result = execute_with_retry("syntax_error", || {
+        attempts += 1;
+        sqlx::query("SELEC 1").execute(&pool)
+    })
+    .await;
+    assert!(result.is_err());
+    assert_eq!(attempts, 1);
+
+    pool.close().await;
+}
@@ -0,0 +1,156 @@
+//! Date-range filters and recency boosting in filtered search
+//!
+//! Documents are seeded with fixed `created_at`/`updated_at` times relative
+//! to now. Tests skip when no database is configured.
+
+mod common;
+
+use chrono::{DateTime, Duration, Utc};
+use common::create_test_pool;
+use db::queries::MetadataFilters;
+use db::{DatabasePool, DocumentQueries};
+use uuid::Uuid;
+
+/// Seed a doc type with `(path, content, age in days)` documents
+async fn seed(pool: &DatabasePool, docs: &[(&str, &str, i64)]) -> String {
+    let doc_type = format!("recency-{}", Uuid::new_v4().simple());
+    DocumentQueries::ensure_document_source(pool.pool(), &doc_type, "main")
+        .await
+        .expect("seed source");
+    for (path, content, age_days) in docs {
+        let at = Utc::now() - Duration::days(*age_days);
+        sqlx::query(
+            "INSERT INTO documents (id, doc_type, source_name, doc_path, content, metadata, created_at, updated_at)
+             VALUES ($1, $2, 'main', $3, $4, '{}', $5, $5)",
+        )
+        .bind(Uuid::new_v4())
+        .bind(&doc_type)
+        .bind(path)
+        .bind(content)
+        .bind(at)
+        .execute(pool.pool())
+        .await
+        .expect("seed document");
+    }
+    doc_type
+}
+
+async fn cleanup(pool: &DatabasePool, doc_type: &str) {
+    for table in ["documents", "document_sources"] {
+        let _ = sqlx::query(&format!("DELETE FROM {table} WHERE doc_type = $1"))
+            .bind(doc_type)
+            .execute(pool.pool())
+            .await;
+    }
+}
+
+/// Paths of the documents a filtered search returns, best first
+async fn search(
+    pool: &DatabasePool,
+    doc_type: &str,
+    query: &str,
+    filters: &MetadataFilters,
+) -> Vec<String> {
+    DocumentQueries::doc_type_vector_search_with_filters(
+        pool.pool(),
+        doc_type,
+        query,
+        &[],
+        10,
+        filters,
+    )
+    .await
+    .expect("search")
+    .into_iter()
+    .map(|doc| doc.doc_path)
+    .collect()
+}
+
+fn days_ago(days: i64) -> Option<DateTime<Utc>> {
+    Some(Utc::now() - Duration::days(days))
+}
+
+#[tokio::test]
+async fn test_date_filters_exclude_documents_outside_the_range() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database configured");
+        return;
+    };
+    let doc_type = seed(
+        &pool,
+        &[
+            ("old.md", "Validator release notes", 400),
+            ("recent.md", "Validator release notes", 20),
+            ("new.md", "Validator release notes", 1),
+        ],
+    )
+    .await;
+
+    let sorted = |mut paths: Vec<String>| {
+        paths.sort();
+        paths
+    };
+    let after = MetadataFilters {
+        created_after: days_ago(30),
+        ..MetadataFilters::default()
+    };
+    assert_eq!(
+        sorted(search(&pool, &doc_type, "validator", &after).await),
+        ["new.md", "recent.md"]
+    );
+
+    let window = MetadataFilters {
+        created_after: days_ago(30),
+        created_before: days_ago(7),
+        ..MetadataFilters::default()
+    };
+    assert_eq!(
+        search(&pool, &doc_type, "validator", &window).await,
+        ["recent.md"]
+    );
+
+    let updated = MetadataFilters {
+        updated_after: days_ago(7),
+        ..MetadataFilters::default()
+    };
+    assert_eq!(
+        search(&pool, &doc_type, "validator", &updated).await,
+        ["new.md"]
+    );
+
+    cleanup(&pool, &doc_type).await;
+}
+
+#[tokio::test]
+async fn test_recency_boost_lifts_newer_documents() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database configured");
+        return;
+    };
+    // The old page mentions the term twice, so it ranks first unboosted
+    let doc_type = seed(
+        &pool,
+        &[
+            ("old.md", "Staking rewards and staking limits", 365),
+            ("new.md", "Staking rewards", 0),
+        ],
+    )
+    .await;
+
+    let unboosted = MetadataFilters::default();
+    assert_eq!(
+        search(&pool, &doc_type, "staking", &unboosted).await,
+        ["old.md", "new.md"]
+    );
+
+    let boosted = MetadataFilters {
+        recency_boost: Some(2.0),
+        ..MetadataFilters::default()
+    };
+    assert_eq!(
+        search(&pool, &doc_type, "staking", &boosted).await,
+        ["new.md", "old.md"]
+    );
+
+    cleanup(&pool, &doc_type).await;
+}
@@ -1,15 +1,52 @@
-use crate::claude::ClaudeRunner;
+use crate::claude::{prompt_runner_from_env, ClaudeRunner, LlmUseCase, PromptRunner};
+use crate::error::AnalysisError;
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tracing::info;
 
+/// Extensions counted when surveying a local checkout
+const DOC_EXTENSIONS: &[&str] = &[
+    "md", "mdx", "rst", "adoc", "html", "txt", "yaml", "yml", "json", "toml",
+];
+
+/// Directories never worth descending into when surveying a local checkout
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", "vendor", "dist", "build"];
+
+/// Deepest directory level visited by the local survey
+const SURVEY_MAX_DEPTH: usize = 6;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RepositoryAnalysis {
     pub repo_info: RepoInfo,
     pub strategy: IngestionStrategy,
     pub cli_commands: Vec<String>,
     pub reasoning: String,
+    #[serde(default)]
+    pub assessment: DocumentationAssessment,
+}
+
+/// What the analysis found about the repository's documentation
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DocumentationAssessment {
+    pub primary_format: Option<String>,
+    pub key_directories: Vec<String>,
+    pub estimated_file_count: Option<u64>,
+    /// Documentation files per extension, known only for local checkouts
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub file_counts: BTreeMap<String, u64>,
+}
+
+/// Repository to analyze
+#[derive(Debug, Clone)]
+pub enum RepositorySource {
+    /// Remote repository, cloned by the plan's `git clone` step
+    Remote(String),
+    /// Existing checkout on this host, read in place
+    Local(PathBuf),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -45,15 +82,30 @@ pub struct IngestionStrategy {
 }
 
 pub struct IntelligentRepositoryAnalyzer {
-    runner: ClaudeRunner,
+    runner: Arc<dyn PromptRunner>,
 }
 
 impl IntelligentRepositoryAnalyzer {
     #[must_use]
     pub fn new() -> Self {
-        Self {
-            runner: ClaudeRunner::new(),
-        }
+        Self::with_runner(Arc::new(ClaudeRunner::new()))
+    }
+
+    /// Create an analyzer using the runner selected by
+    /// `DISCOVERY_LLM_PROVIDER` or `LLM_PROVIDER`
+    ///
+    /// # Errors
+    /// Returns an error if the configured runner cannot be built.
+    pub fn from_env() -> Result<Self> {
+        Ok(Self::with_runner(prompt_runner_from_env(
+            LlmUseCase::Discovery,
+        )?))
+    }
+
+    /// Create an analyzer that sends its prompts to `runner`
+    #[must_use]
+    pub fn with_runner(runner: Arc<dyn PromptRunner>) -> Self {
+        Self { runner }
     }
 
     /// Analyze a GitHub repository and produce an ingest plan.
@@ -61,11 +113,69 @@ impl IntelligentRepositoryAnalyzer {
     /// # Errors
     /// Returns an error if the Claude runner fails or the response cannot be parsed.
     pub async fn analyze_repository(&mut self, github_url: &str) -> Result<RepositoryAnalysis> {
-        info!("Analyzing repository (discovery): {}", github_url);
-        let repo_info = Self::get_repository_info(github_url)?;
-        let prompt = Self::create_analysis_prompt(github_url, &repo_info);
+        self.analyze(&RepositorySource::Remote(github_url.to_string()), None)
+            .await
+    }
+
+    /// Analyze a repository and produce an ingest plan, without executing it.
+    ///
+    /// `hint` is passed to the model as operator guidance, e.g. which part of
+    /// the repository holds the documentation.
+    ///
+    /// # Errors
+    /// Returns an error if the source is unusable, the Claude runner fails or
+    /// the response cannot be parsed. These are [`AnalysisError`]s.
+    pub async fn analyze(
+        &self,
+        source: &RepositorySource,
+        hint: Option<&str>,
+    ) -> Result<RepositoryAnalysis> {
+        let (repo_info, survey) = match source {
+            RepositorySource::Remote(url) => {
+                info!("Analyzing repository (discovery): {}", url);
+                (Self::get_repository_info(url)?, None)
+            }
+            RepositorySource::Local(path) => {
+                info!("Analyzing local checkout (discovery): {}", path.display());
+                let path = path.clone();
+                let survey = tokio::task::spawn_blocking(move || survey_local_tree(&path))
+                    .await
+                    .map_err(|e| anyhow!("local survey task failed: {e}"))??;
+                (Self::get_local_info(source)?, Some(survey))
+            }
+        };
+        let prompt = Self::create_analysis_prompt(source, &repo_info, survey.as_ref(), hint);
         let raw = self.runner.run(&prompt).await?;
-        Self::parse_claude_analysis(&raw, &repo_info)
+        let mut analysis = Self::parse_claude_analysis(&raw, &repo_info)
+            .map_err(|e| AnalysisError::InvalidResponse(e.to_string()))?;
+        if let Some(survey) = survey {
+            // Counted on disk, so better than the model's estimate
+            analysis.assessment.estimated_file_count = Some(survey.total());
+            analysis.assessment.file_counts = survey.file_counts;
+            if analysis.assessment.key_directories.is_empty() {
+                analysis.assessment.key_directories = survey.doc_directories;
+            }
+        }
+        Ok(analysis)
+    }
+
+    fn get_local_info(source: &RepositorySource) -> Result<RepoInfo> {
+        let RepositorySource::Local(path) = source else {
+            return Err(anyhow!("not a local source"));
+        };
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or_else(|| {
+                AnalysisError::InvalidSource(format!("{} has no name", path.display()))
+            })?;
+        Ok(RepoInfo {
+            url: path.to_string_lossy().to_string(),
+            name,
+            primary_language: None,
+            documentation_type: DocumentationType::Unknown,
+            estimated_size: String::new(),
+        })
     }
 
     fn get_repository_info(github_url: &str) -> Result<RepoInfo> {
@@ -83,7 +193,9 @@ impl IntelligentRepositoryAnalyzer {
         let url = url_str.trim_end_matches('/');
         let parts: Vec<&str> = url.split('/').collect();
         if parts.len() < 2 {
-            return Err(anyhow!("Invalid GitHub URL format"));
+            return Err(
+                AnalysisError::InvalidSource("Invalid GitHub URL format".to_string()).into(),
+            );
         }
         Ok((
             parts[parts.len() - 2].to_string(),
@@ -91,16 +203,46 @@ impl IntelligentRepositoryAnalyzer {
         ))
     }
 
-    fn create_analysis_prompt(github_url: &str, repo_info: &RepoInfo) -> String {
+    fn create_analysis_prompt(
+        source: &RepositorySource,
+        repo_info: &RepoInfo,
+        survey: Option<&LocalSurvey>,
+        hint: Option<&str>,
+    ) -> String {
         let mut structure = String::new();
         let _ = writeln!(structure, "Key documentation files found:");
+        if let Some(survey) = survey {
+            for (dir, count) in &survey.directory_counts {
+                let _ = writeln!(structure, "- {dir}/ ({count} files)");
+            }
+            for (ext, count) in &survey.file_counts {
+                let _ = writeln!(structure, "- .{ext}: {count} files");
+            }
+        }
+        let (location, source_note) = match source {
+            RepositorySource::Remote(url) => (url.clone(), String::new()),
+            RepositorySource::Local(path) => (
+                path.display().to_string(),
+                format!(
+                    "\nLOCAL CHECKOUT: the repository is already on disk at {}. Do NOT include a git clone step; use this path in place of UNIQUE_REPO_DIR.\n",
+                    path.display()
+                ),
+            ),
+        };
+        let hint_note = hint
+            .map(str::trim)
+            .filter(|h| !h.is_empty())
+            .map(|h| {
+                format!("\nOPERATOR HINT (follow it unless the structure contradicts it): {h}\n")
+            })
+            .unwrap_or_default();
         format!(
             r#"
 TASK: Analyze the GitHub repository and create a comprehensive documentation ingestion strategy.
 
 REPOSITORY TO ANALYZE: {}
 REPOSITORY NAME: {}
-
+{}{}
 ACTUAL REPOSITORY STRUCTURE:
 {}
 
@@ -156,27 +298,33 @@ IMPORTANT CONSTRAINTS FOR cli_commands:
    - PATH (required): The directory or file to process
    - --extensions: Comma-separated list of file extensions (e.g., md,html,yaml)
    - --recursive: Flag to traverse directories recursively
+   - --exclude: Comma-separated globs of paths to skip (default: node_modules,target,vendor); names like "examples" match any directory, patterns with "/" match paths relative to PATH
+   - --max-file-size: Skip files larger than this many KB
+   - --min-content-chars: Skip files with less text than this
+   - --prioritize with --max-files N: Keep only the N most documentation-like files (READMEs and docs/ first)
    - -o or --output: Output directory path
    
 2. DO NOT use any of these invalid flags with 'loader cli':
    - --include-dirs (NOT VALID)
-   - --exclude-dirs (NOT VALID)
    - --include-paths (NOT VALID)
-   - --exclude-paths (NOT VALID)
    - Any other flags not listed above
 
 3. To process specific directories, use the PATH argument directly:
    - CORRECT: "loader cli UNIQUE_REPO_DIR/docs --extensions md,mdx,rst,html,json,yaml,yml,toml,txt --recursive -o UNIQUE_DOCS_OUT"
    - WRONG: "loader cli UNIQUE_REPO_DIR --include-dirs docs --extensions md --recursive -o UNIQUE_DOCS_OUT"
 
-4. If unsure of the exact path, include multiple 'loader cli' commands, each targeting a different common docs directory (e.g., docs/, website/content/docs, docs/source).
+4. If the documentation is published on a website rather than kept in the repository, crawl it with 'loader web':
+   - CORRECT: "loader web https://docs.example.com/docs/ --sitemap /sitemap.xml --allow-prefix /docs/ --max-pages 200 -o UNIQUE_DOCS_OUT"
+   - Without --sitemap, links are followed up to --max-depth (default 3). Pages stay on the URL's origin and honor robots.txt.
+
+5. If unsure of the exact path, include multiple 'loader cli' commands, each targeting a different common docs directory (e.g., docs/, website/content/docs, docs/source).
    - CORRECT: "loader cli UNIQUE_REPO_DIR/website/content/docs --extensions md,mdx,rst,html,json,yaml,yml,toml,txt --recursive -o UNIQUE_DOCS_OUT"
    - CORRECT: "loader cli UNIQUE_REPO_DIR/docs/source --extensions md,mdx,rst,html,json,yaml,yml,toml,txt --recursive -o UNIQUE_DOCS_OUT"
    - CORRECT (OpenAPI): "loader cli UNIQUE_REPO_DIR/openapi --extensions yaml,yml --recursive -o UNIQUE_DOCS_OUT"
 
 RESPOND ONLY WITH THE JSON. DO NOT include any other text before or after the JSON.
 "#,
-            github_url, repo_info.name, structure
+            location, repo_info.name, source_note, hint_note, structure
         )
     }
 
@@ -312,12 +460,35 @@ RESPOND ONLY WITH THE JSON. DO NOT include any other text before or after the JS
             .and_then(|x| x.as_str())
             .unwrap_or("No reasoning provided")
             .to_string();
+        let assessment = v
+            .get("documentation_assessment")
+            .map(|a| DocumentationAssessment {
+                primary_format: a
+                    .get("primary_format")
+                    .and_then(serde_json::Value::as_str)
+                    .map(ToString::to_string),
+                key_directories: a
+                    .get("key_directories")
+                    .and_then(serde_json::Value::as_array)
+                    .map(|d| {
+                        d.iter()
+                            .filter_map(|e| e.as_str().map(ToString::to_string))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                estimated_file_count: a
+                    .get("estimated_file_count")
+                    .and_then(serde_json::Value::as_u64),
+                file_counts: BTreeMap::new(),
+            })
+            .unwrap_or_default();
 
         Ok(RepositoryAnalysis {
             repo_info: repo_info.clone(),
             strategy,
             cli_commands,
             reasoning,
+            assessment,
         })
     }
 }
@@ -327,3 +498,88 @@ impl Default for IntelligentRepositoryAnalyzer {
         Self::new()
     }
 }
+
+/// Documentation files found in a local checkout
+#[derive(Debug, Default)]
+struct LocalSurvey {
+    /// Files per extension
+    file_counts: BTreeMap<String, u64>,
+    /// Files per top-level directory ("." for the repository root)
+    directory_counts: BTreeMap<String, u64>,
+    /// Top-level directories holding the most documentation files
+    doc_directories: Vec<String>,
+}
+
+impl LocalSurvey {
+    fn total(&self) -> u64 {
+        self.file_counts.values().sum()
+    }
+}
+
+/// Count documentation files under `root`, skipping hidden and build directories
+fn survey_local_tree(root: &Path) -> Result<LocalSurvey> {
+    if !root.is_dir() {
+        return Err(
+            AnalysisError::InvalidSource(format!("{} is not a directory", root.display())).into(),
+        );
+    }
+
+    let mut survey = LocalSurvey::default();
+    let mut pending = vec![(root.to_path_buf(), 0usize)];
+    while let Some((dir, depth)) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                if depth < SURVEY_MAX_DEPTH
+                    && !name.starts_with('.')
+                    && !SKIPPED_DIRS.contains(&name.as_str())
+                {
+                    pending.push((entry.path(), depth + 1));
+                }
+                continue;
+            }
+            let Some(ext) = Path::new(&name)
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+            else {
+                continue;
+            };
+            if !DOC_EXTENSIONS.contains(&ext.as_str()) {
+                continue;
+            }
+            *survey.file_counts.entry(ext).or_default() += 1;
+            let top = entry
+                .path()
+                .strip_prefix(root)
+                .ok()
+                .and_then(|rel| {
+                    let mut parts = rel.components();
+                    let first = parts.next()?;
+                    parts
+                        .next()
+                        .map(|_| first.as_os_str().to_string_lossy().to_string())
+                })
+                .unwrap_or_else(|| ".".to_string());
+            *survey.directory_counts.entry(top).or_default() += 1;
+        }
+    }
+
+    let mut ranked: Vec<(&String, &u64)> = survey
+        .directory_counts
+        .iter()
+        .filter(|(dir, _)| dir.as_str() != ".")
+        .collect();
+    ranked.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    survey.doc_directories = ranked
+        .into_iter()
+        .take(5)
+        .map(|(dir, _)| format!("{dir}/"))
+        .collect();
+    Ok(survey)
+}
@@ -0,0 +1,413 @@
+//! Direct Anthropic Messages API runner
+//!
+//! [`AnthropicRunner`] sends prompts to `POST /v1/messages` instead of
+//! starting the Claude CLI. Its output is a single `result` event shaped like
+//! the CLI's stream-json output, so [`crate::result_text`] and
+//! [`crate::result_usage`] read both runners alike. Rate-limited (429) and
+//! overloaded (529) responses are retried after the server's `retry-after`.
+
+use crate::claude::PromptRunner;
+use crate::error::AnalysisError;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Value of the `anthropic-version` header
+pub const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// API host used unless `ANTHROPIC_BASE_URL` is set
+const DEFAULT_BASE_URL: &str = "https://api.anthropic.com";
+
+/// Default cap on the tokens of one answer
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Default retries of a rate-limited or overloaded request
+const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// Longest wait before a retry, whatever `retry-after` asks for
+const MAX_RETRY_WAIT: Duration = Duration::from_secs(60);
+
+/// Settings of an [`AnthropicRunner`]
+#[derive(Clone)]
+pub struct AnthropicConfig {
+    pub api_key: String,
+    /// Scheme and host, without the `/v1/messages` path
+    pub base_url: String,
+    pub model: String,
+    pub max_tokens: u32,
+    /// Retries of a 429 or 529 response before giving up
+    pub max_retries: u32,
+    /// Limit on one request, retries excluded
+    pub timeout: Duration,
+    /// Read the answer as server-sent events instead of one JSON body
+    pub stream: bool,
+}
+
+impl AnthropicConfig {
+    /// Configuration with defaults for everything but the key
+    #[must_use]
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            model: "claude-3-5-sonnet-20241022".to_string(),
+            max_tokens: DEFAULT_MAX_TOKENS,
+            max_retries: DEFAULT_MAX_RETRIES,
+            timeout: Duration::from_secs(120),
+            stream: false,
+        }
+    }
+
+    /// Read `ANTHROPIC_API_KEY` and the optional `ANTHROPIC_BASE_URL`,
+    /// `ANTHROPIC_MODEL` (falling back to `CLAUDE_MODEL`),
+    /// `ANTHROPIC_MAX_TOKENS`, `ANTHROPIC_MAX_RETRIES`,
+    /// `ANTHROPIC_TIMEOUT_SECS` and `ANTHROPIC_STREAM`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `ANTHROPIC_API_KEY` is not set.
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        let api_key = var("ANTHROPIC_API_KEY").ok_or_else(|| {
+            anyhow!("ANTHROPIC_API_KEY must be set for the anthropic LLM provider")
+        })?;
+        let mut config = Self::new(api_key);
+        if let Some(base_url) = var("ANTHROPIC_BASE_URL") {
+            config.base_url = base_url.trim_end_matches('/').to_string();
+        }
+        if let Some(model) = var("ANTHROPIC_MODEL").or_else(|| var("CLAUDE_MODEL")) {
+            config.model = model;
+        }
+        if let Some(max_tokens) = var("ANTHROPIC_MAX_TOKENS").and_then(|v| v.parse().ok()) {
+            config.max_tokens = max_tokens;
+        }
+        if let Some(max_retries) = var("ANTHROPIC_MAX_RETRIES").and_then(|v| v.parse().ok()) {
+            config.max_retries = max_retries;
+        }
+        if let Some(secs) = var("ANTHROPIC_TIMEOUT_SECS").and_then(|v| v.parse().ok()) {
+            config.timeout = Duration::from_secs(secs);
+        }
+        config.stream =
+            var("ANTHROPIC_STREAM").is_some_and(|v| v == "1" || v.eq_ignore_ascii_case("true"));
+        Ok(config)
+    }
+}
+
+/// Prompt runner calling the Anthropic Messages API
+pub struct AnthropicRunner {
+    client: reqwest::Client,
+    config: AnthropicConfig,
+}
+
+/// Answer text, stop reason and usage of one message
+#[derive(Debug, Default)]
+struct Reply {
+    text: String,
+    stop_reason: Option<String>,
+    usage: Value,
+}
+
+impl AnthropicRunner {
+    /// Create a runner with `config`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be built.
+    pub fn new(config: AnthropicConfig) -> Result<Self> {
+        let client = reqwest::Client::builder().timeout(config.timeout).build()?;
+        Ok(Self { client, config })
+    }
+
+    /// Create a runner configured by [`AnthropicConfig::from_env`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `ANTHROPIC_API_KEY` is not set.
+    pub fn from_env() -> Result<Self> {
+        Self::new(AnthropicConfig::from_env()?)
+    }
+
+    /// Post the prompt, retrying 429 and 529 responses
+    async fn send(&self, prompt: &str) -> Result<reqwest::Response> {
+        let body = json!({
+            "model": self.config.model,
+            "max_tokens": self.config.max_tokens,
+            "stream": self.config.stream,
+            "messages": [{"role": "user", "content": prompt}],
+        });
+        let url = format!("{}/v1/messages", self.config.base_url);
+
+        let mut attempt = 0;
+        loop {
+            let response = self
+                .client
+                .post(&url)
+                .header("x-api-key", &self.config.api_key)
+                .header("anthropic-version", ANTHROPIC_VERSION)
+                .json(&body)
+                .send()
+                .await
+                .map_err(|e| self.request_error(&e))?;
+
+            let status = response.status().as_u16();
+            match status {
+                200..=299 => return Ok(response),
+                429 | 529 if attempt < self.config.max_retries => {
+                    let wait = retry_after(response.headers())
+                        .unwrap_or_else(|| Duration::from_secs(1 << attempt.min(5)))
+                        .min(MAX_RETRY_WAIT);
+                    warn!(
+                        "Anthropic API answered {} (attempt {}), retrying in {:?}",
+                        status,
+                        attempt + 1,
+                        wait
+                    );
+                    attempt += 1;
+                    tokio::time::sleep(wait).await;
+                }
+                429 | 529 => {
+                    return Err(AnalysisError::RateLimited {
+                        status,
+                        attempts: attempt + 1,
+                    }
+                    .into())
+                }
+                401 => {
+                    return Err(AnalysisError::Unauthorized {
+                        message: error_message(response).await,
+                    }
+                    .into())
+                }
+                _ => {
+                    return Err(AnalysisError::ApiFailed {
+                        status,
+                        message: error_message(response).await,
+                    }
+                    .into())
+                }
+            }
+        }
+    }
+
+    fn request_error(&self, error: &reqwest::Error) -> anyhow::Error {
+        if error.is_timeout() {
+            AnalysisError::Timeout {
+                secs: self.config.timeout.as_secs(),
+            }
+            .into()
+        } else {
+            anyhow!("Anthropic API request failed: {error}")
+        }
+    }
+
+    /// Collect a streamed message from its server-sent events
+    async fn read_stream(&self, mut response: reqwest::Response) -> Result<Reply> {
+        let mut reply = Reply::default();
+        let mut buffer = String::new();
+        while let Some(chunk) = response.chunk().await.map_err(|e| self.request_error(&e))? {
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+            while let Some(end) = buffer.find("\n\n") {
+                let event: String = buffer.drain(..end + 2).collect();
+                apply_event(&mut reply, &event)?;
+            }
+        }
+        if !buffer.trim().is_empty() {
+            apply_event(&mut reply, &buffer)?;
+        }
+        Ok(reply)
+    }
+}
+
+/// Seconds in a `retry-after` header
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let secs: f64 = headers
+        .get("retry-after")?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    (secs.is_finite() && secs >= 0.0).then(|| Duration::from_secs_f64(secs))
+}
+
+/// Message of an API error body, or the body itself
+async fn error_message(response: reqwest::Response) -> String {
+    let body = response.text().await.unwrap_or_default();
+    serde_json::from_str::<Value>(&body)
+        .ok()
+        .and_then(|v| v["error"]["message"].as_str().map(str::to_string))
+        .unwrap_or_else(|| body.chars().take(1000).collect())
+}
+
+/// Text blocks, stop reason and usage of a whole message
+fn parse_message(message: &Value) -> Reply {
+    Reply {
+        text: message["content"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|block| block["type"] == "text")
+            .filter_map(|block| block["text"].as_str())
+            .collect(),
+        stop_reason: message["stop_reason"].as_str().map(str::to_string),
+        usage: message["usage"].clone(),
+    }
+}
+
+/// Fold one server-sent event into `reply`
+fn apply_event(reply: &mut Reply, event: &str) -> Result<()> {
+    let data: String = event
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(str::trim_start)
+        .collect();
+    let Ok(data) = serde_json::from_str::<Value>(&data) else {
+        return Ok(());
+    };
+    match data["type"].as_str() {
+        Some("message_start") => reply.usage = data["message"]["usage"].clone(),
+        Some("content_block_delta") if data["delta"]["type"] == "text_delta" => {
+            reply
+                .text
+                .push_str(data["delta"]["text"].as_str().unwrap_or_default());
+        }
+        Some("message_delta") => {
+            if let Some(stop_reason) = data["delta"]["stop_reason"].as_str() {
+                reply.stop_reason = Some(stop_reason.to_string());
+            }
+            if let (Some(usage), Some(output)) = (
+                reply.usage.as_object_mut(),
+                data["usage"]["output_tokens"].as_u64(),
+            ) {
+                usage.insert("output_tokens".to_string(), output.into());
+            }
+        }
+        Some("error") => {
+            return Err(AnalysisError::InvalidResponse(format!(
+                "stream error {}: {}",
+                data["error"]["type"].as_str().unwrap_or("unknown"),
+                data["error"]["message"].as_str().unwrap_or_default()
+            ))
+            .into())
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// The reply as a `result` event, or the error its stop reason stands for
+fn finish(reply: Reply) -> Result<String> {
+    match reply.stop_reason.as_deref() {
+        None | Some("end_turn" | "stop_sequence") => {}
+        Some("refusal") => return Err(AnalysisError::Refused.into()),
+        Some(other) => {
+            return Err(AnalysisError::Incomplete {
+                stop_reason: other.to_string(),
+            }
+            .into())
+        }
+    }
+    if reply.text.trim().is_empty() {
+        return Err(AnalysisError::EmptyResponse.into());
+    }
+    Ok(json!({
+        "type": "result",
+        "result": reply.text,
+        "stop_reason": reply.stop_reason,
+        "usage": reply.usage,
+    })
+    .to_string())
+}
+
+#[async_trait]
+impl PromptRunner for AnthropicRunner {
+    fn model(&self) -> Option<&str> {
+        Some(&self.config.model)
+    }
+
+    async fn run(&self, prompt: &str) -> Result<String> {
+        let response = self.send(prompt).await?;
+        let reply = if self.config.stream {
+            self.read_stream(response).await?
+        } else {
+            let message: Value = response
+                .json()
+                .await
+                .map_err(|e| AnalysisError::InvalidResponse(e.to_string()))?;
+            parse_message(&message)
+        };
+        debug!(len = reply.text.len(), "Anthropic reply received");
+        finish(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_events_build_the_reply() {
+        let mut reply = Reply::default();
+        for event in [
+            "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":12,\"output_tokens\":1}}}\n\n",
+            "event: ping\ndata: {\"type\":\"ping\"}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hel\"}}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"lo\"}}\n\n",
+            "event: message_delta\ndata: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},\"usage\":{\"output_tokens\":5}}\n\n",
+        ] {
+            apply_event(&mut reply, event).unwrap();
+        }
+        let output = finish(reply).unwrap();
+        assert_eq!(crate::result_text(&output), "Hello");
+        assert_eq!(
+            crate::result_usage(&output),
+            Some(crate::Usage {
+                input_tokens: 12,
+                output_tokens: 5
+            })
+        );
+    }
+
+    #[test]
+    fn test_stop_reasons_map_to_errors() {
+        let reply = |stop_reason: &str| Reply {
+            text: "partial".to_string(),
+            stop_reason: Some(stop_reason.to_string()),
+            usage: Value::Null,
+        };
+        let kind = |stop_reason: &str| {
+            finish(reply(stop_reason))
+                .unwrap_err()
+                .downcast::<AnalysisError>()
+                .unwrap()
+                .kind()
+        };
+        assert_eq!(kind("refusal"), "refused");
+        assert_eq!(kind("max_tokens"), "incomplete");
+        assert!(finish(reply("stop_sequence")).is_ok());
+
+        let mut stream_error = Reply::default();
+        assert!(apply_event(
+            &mut stream_error,
+            "event: error\ndata: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_retry_after_header() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert("retry-after", "1.5".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_millis(1500)));
+        headers.insert("retry-after", "soon".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+    }
+}
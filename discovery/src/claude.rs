@@ -1,5 +1,8 @@
+use crate::error::AnalysisError;
 use anyhow::{anyhow, Result};
+use async_trait::async_trait;
 use std::process::Stdio;
+use std::sync::Arc;
 use std::{
     fs,
     path::{Path, PathBuf},
@@ -29,6 +32,78 @@ use tokio::process::Command;
 use tokio::time::{timeout, Duration};
 use tracing::debug;
 
+/// Runs an analysis prompt and returns the raw model output
+///
+/// [`ClaudeRunner`] is the production implementation; tests substitute a
+/// runner returning a canned response.
+#[async_trait]
+pub trait PromptRunner: Send + Sync {
+    /// Execute a single user prompt and return its output
+    ///
+    /// # Errors
+    /// CLI failures are reported as [`AnalysisError`] inside the `anyhow::Error`.
+    async fn run(&self, prompt: &str) -> Result<String>;
+
+    /// Model the prompts run on, for cost accounting; `None` when unknown
+    fn model(&self) -> Option<&str> {
+        None
+    }
+
+    /// Check the model answers by running a one-word prompt
+    ///
+    /// # Errors
+    /// Returns the error of the probe run.
+    async fn health_check(&self) -> Result<()> {
+        self.run("Reply with the single word: ok").await.map(|_| ())
+    }
+}
+
+/// Tokens one model run consumed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Usage {
+    /// Prompt tokens, including tokens read from or written to the prompt cache
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// Token usage reported by the `result` event of a stream-json run
+#[must_use]
+pub fn result_usage(output: &str) -> Option<Usage> {
+    let event = output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|event| event.get("type").and_then(|t| t.as_str()) == Some("result"))?;
+    let usage = event.get("usage")?;
+    let count = |key: &str| {
+        usage
+            .get(key)
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or(0)
+    };
+    Some(Usage {
+        input_tokens: count("input_tokens")
+            + count("cache_creation_input_tokens")
+            + count("cache_read_input_tokens"),
+        output_tokens: count("output_tokens"),
+    })
+}
+
+/// Final text of a stream-json run, or the whole output if it has no `result` event
+#[must_use]
+pub fn result_text(output: &str) -> String {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|event| event.get("type").and_then(|t| t.as_str()) == Some("result"))
+        .find_map(|event| {
+            event
+                .get("result")
+                .and_then(|r| r.as_str())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| output.trim().to_string())
+}
+
 /// Minimal Claude Code runner for stream-json prompts
 pub struct ClaudeRunner {
     pub binary_path: String,
@@ -47,9 +122,95 @@ impl ClaudeRunner {
         }
     }
 
+    /// Whether the CLI binary exists, as a path or on `PATH`
+    #[must_use]
+    pub fn is_installed(&self) -> bool {
+        let binary = Path::new(&self.binary_path);
+        if binary.components().count() > 1 {
+            return binary.is_file();
+        }
+        std::env::var_os("PATH").is_some_and(|paths| {
+            std::env::split_paths(&paths).any(|dir| dir.join(binary).is_file())
+        })
+    }
+}
+
+impl Default for ClaudeRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What a prompt runner is used for; each use can pick its own provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlmUseCase {
+    /// `answer_question`
+    Answer,
+    /// Reranking of query results
+    Rerank,
+    /// Repository analysis for ingest plans
+    Discovery,
+}
+
+impl LlmUseCase {
+    /// Variable that overrides `LLM_PROVIDER` for this use
+    #[must_use]
+    pub const fn provider_var(self) -> &'static str {
+        match self {
+            Self::Answer => "ANSWER_LLM_PROVIDER",
+            Self::Rerank => "RERANK_LLM_PROVIDER",
+            Self::Discovery => "DISCOVERY_LLM_PROVIDER",
+        }
+    }
+
+    /// Provider configured for this use: its own variable if set, else
+    /// `LLM_PROVIDER`, lowercased and trimmed (empty for the default)
+    #[must_use]
+    pub fn provider(self) -> String {
+        std::env::var(self.provider_var())
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .or_else(|| std::env::var("LLM_PROVIDER").ok())
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase()
+    }
+
+    /// Whether this use runs through the Claude CLI
+    #[must_use]
+    pub fn uses_cli(self) -> bool {
+        matches!(self.provider().as_str(), "" | "claude-cli" | "cli")
+    }
+}
+
+/// Runner for `use_case`, selected by its provider variable or else
+/// `LLM_PROVIDER`: the Claude CLI (`claude-cli`, the default) or the
+/// Anthropic Messages API (`anthropic`)
+///
+/// # Errors
+///
+/// Returns an error for an unknown provider, or if the API runner is
+/// selected without `ANTHROPIC_API_KEY`.
+pub fn prompt_runner_from_env(use_case: LlmUseCase) -> Result<Arc<dyn PromptRunner>> {
+    match use_case.provider().as_str() {
+        "" | "claude-cli" | "cli" => Ok(Arc::new(ClaudeRunner::new())),
+        "anthropic" => Ok(Arc::new(crate::AnthropicRunner::from_env()?)),
+        other => Err(anyhow!(
+            "Unknown LLM provider '{other}' for {} (or LLM_PROVIDER), expected claude-cli or anthropic",
+            use_case.provider_var()
+        )),
+    }
+}
+
+#[async_trait]
+impl PromptRunner for ClaudeRunner {
+    fn model(&self) -> Option<&str> {
+        Some(&self.model_name)
+    }
+
     /// Execute a single user prompt and return stdout
     #[allow(clippy::too_many_lines)]
-    pub async fn run(&self, prompt: &str) -> Result<String> {
+    async fn run(&self, prompt: &str) -> Result<String> {
         let timeout_secs: u64 = std::env::var("CLAUDE_TIMEOUT_SECS")
             .ok()
             .and_then(|s| s.parse().ok())
@@ -114,19 +275,18 @@ impl ClaudeRunner {
             .env("DISABLE_TELEMETRY", "1")
             .env("DISABLE_ERROR_REPORTING", "1")
             .env("DISABLE_AUTOUPDATER", "1")
-            .env("CLAUDE_CONFIG_DIR", selected_config_dir.as_os_str());
+            .env("CLAUDE_CONFIG_DIR", selected_config_dir.as_os_str())
+            // Callers may abandon a run (timeout, cancellation); don't leave the CLI behind
+            .kill_on_drop(true);
 
         let mut child = cmd
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
-            .map_err(|e| {
-                anyhow!(
-                    "Failed to start Claude binary '{}': {}",
-                    self.binary_path,
-                    e
-                )
+            .map_err(|e| AnalysisError::CliUnavailable {
+                binary: self.binary_path.clone(),
+                message: e.to_string(),
             })?;
 
         let mut stdin = child.stdin.take().ok_or_else(|| anyhow!("no stdin"))?;
@@ -139,21 +299,21 @@ impl ClaudeRunner {
             Ok(Ok(output)) => {
                 if !output.status.success() {
                     let stderr = String::from_utf8_lossy(&output.stderr);
-                    return Err(anyhow!(
-                        "Claude binary exited with status {}: {}",
-                        output.status,
-                        stderr.chars().take(1000).collect::<String>()
-                    ));
+                    return Err(AnalysisError::CliFailed {
+                        status: output.status.to_string(),
+                        stderr: stderr.chars().take(1000).collect(),
+                    }
+                    .into());
                 }
                 let stdout = String::from_utf8_lossy(&output.stdout).to_string();
                 debug!(len = stdout.len(), "Claude stdout received");
                 if stdout.trim().is_empty() {
-                    return Err(anyhow!("Claude returned empty response"));
+                    return Err(AnalysisError::EmptyResponse.into());
                 }
                 Ok(stdout.trim().to_string())
             }
             Ok(Err(e)) => Err(anyhow!("Failed to read Claude output: {e}")),
-            Err(_) => Err(anyhow!("Claude timed out after {timeout_secs}s")),
+            Err(_) => Err(AnalysisError::Timeout { secs: timeout_secs }.into()),
         }
     }
 }
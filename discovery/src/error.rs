@@ -0,0 +1,59 @@
+use thiserror::Error;
+
+/// Failure of a repository analysis, classified for callers that report it
+#[derive(Debug, Error)]
+pub enum AnalysisError {
+    /// The Claude binary could not be started
+    #[error("Failed to start Claude binary '{binary}': {message}")]
+    CliUnavailable { binary: String, message: String },
+    /// The Claude binary exited unsuccessfully
+    #[error("Claude binary exited with status {status}: {stderr}")]
+    CliFailed { status: String, stderr: String },
+    /// The analysis did not finish in time
+    #[error("Claude timed out after {secs}s")]
+    Timeout { secs: u64 },
+    /// The Claude binary produced no output
+    #[error("Claude returned empty response")]
+    EmptyResponse,
+    /// The output did not contain a usable analysis
+    #[error("Failed to parse Claude response: {0}")]
+    InvalidResponse(String),
+    /// The repository to analyze was not usable
+    #[error("Invalid repository source: {0}")]
+    InvalidSource(String),
+    /// The Anthropic API rejected the API key
+    #[error("Anthropic API rejected the API key: {message}")]
+    Unauthorized { message: String },
+    /// The Anthropic API stayed rate-limited or overloaded through every retry
+    #[error("Anthropic API answered {status} after {attempts} attempts")]
+    RateLimited { status: u16, attempts: u32 },
+    /// The Anthropic API answered with another error status
+    #[error("Anthropic API error ({status}): {message}")]
+    ApiFailed { status: u16, message: String },
+    /// The model declined to answer
+    #[error("Claude refused to answer")]
+    Refused,
+    /// The answer stopped early, e.g. at `max_tokens`
+    #[error("Claude stopped early: {stop_reason}")]
+    Incomplete { stop_reason: String },
+}
+
+impl AnalysisError {
+    /// Stable machine-readable name of the failure
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::CliUnavailable { .. } => "cli_unavailable",
+            Self::CliFailed { .. } => "cli_failed",
+            Self::Timeout { .. } => "timeout",
+            Self::EmptyResponse => "empty_response",
+            Self::InvalidResponse(_) => "invalid_response",
+            Self::InvalidSource(_) => "invalid_source",
+            Self::Unauthorized { .. } => "unauthorized",
+            Self::RateLimited { .. } => "rate_limited",
+            Self::ApiFailed { .. } => "api_failed",
+            Self::Refused => "refused",
+            Self::Incomplete { .. } => "incomplete",
+        }
+    }
+}
@@ -1,6 +1,17 @@
 //! Discovery crate: Claude Code–powered repository analysis producing ingest plans.
 
 mod analyzer;
+mod anthropic;
 mod claude;
+mod error;
 
-pub use analyzer::{IngestionStrategy, IntelligentRepositoryAnalyzer, RepositoryAnalysis};
+pub use analyzer::{
+    DocumentationAssessment, IngestionStrategy, IntelligentRepositoryAnalyzer, RepositoryAnalysis,
+    RepositorySource,
+};
+pub use anthropic::{AnthropicConfig, AnthropicRunner, ANTHROPIC_VERSION};
+pub use claude::{
+    prompt_runner_from_env, result_text, result_usage, ClaudeRunner, LlmUseCase, PromptRunner,
+    Usage,
+};
+pub use error::AnalysisError;
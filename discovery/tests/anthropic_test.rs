@@ -0,0 +1,197 @@
+//! `AnthropicRunner` against a local server standing in for the Messages API
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use discovery::{
+    result_text, result_usage, AnalysisError, AnthropicConfig, AnthropicRunner, PromptRunner,
+    Usage, ANTHROPIC_VERSION,
+};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+/// Canned answer of the mock server
+#[derive(Clone)]
+enum Answer {
+    Message(Value),
+    Status(u16, Option<&'static str>),
+    Events(&'static str),
+}
+
+#[derive(Clone, Default)]
+struct Mock {
+    answers: Arc<Mutex<VecDeque<Answer>>>,
+    requests: Arc<Mutex<Vec<(HeaderMap, Value)>>>,
+}
+
+async fn messages(
+    State(mock): State<Mock>,
+    headers: HeaderMap,
+    Json(body): Json<Value>,
+) -> Response {
+    mock.requests.lock().unwrap().push((headers, body));
+    let answer = mock.answers.lock().unwrap().pop_front();
+    match answer.expect("unexpected request") {
+        Answer::Message(message) => Json(message).into_response(),
+        Answer::Status(status, retry_after) => {
+            let status = StatusCode::from_u16(status).unwrap();
+            let error = Json(json!({
+                "type": "error",
+                "error": {"type": "rate_limit_error", "message": format!("status {status}")},
+            }));
+            match retry_after {
+                Some(secs) => (status, [("retry-after", secs)], error).into_response(),
+                None => (status, error).into_response(),
+            }
+        }
+        Answer::Events(events) => ([("content-type", "text/event-stream")], events).into_response(),
+    }
+}
+
+/// Start the mock server; returns a runner config pointed at it
+async fn start(answers: Vec<Answer>) -> (AnthropicConfig, Mock) {
+    let mock = Mock {
+        answers: Arc::new(Mutex::new(answers.into())),
+        ..Mock::default()
+    };
+    let app = Router::new()
+        .route("/v1/messages", post(messages))
+        .with_state(mock.clone());
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let mut config = AnthropicConfig::new("test-key");
+    config.base_url = format!("http://{}", listener.local_addr().unwrap());
+    config.model = "claude-test".to_string();
+    config.timeout = Duration::from_secs(5);
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    (config, mock)
+}
+
+/// Runner with the default settings against the mock server
+async fn mock_runner(answers: Vec<Answer>) -> (AnthropicRunner, Mock) {
+    let (config, mock) = start(answers).await;
+    (AnthropicRunner::new(config).unwrap(), mock)
+}
+
+fn message(text: &str, stop_reason: &str) -> Value {
+    json!({
+        "id": "msg_1",
+        "type": "message",
+        "role": "assistant",
+        "model": "claude-test",
+        "content": [{"type": "text", "text": text}],
+        "stop_reason": stop_reason,
+        "usage": {"input_tokens": 20, "cache_read_input_tokens": 5, "output_tokens": 3},
+    })
+}
+
+fn error_kind(error: &anyhow::Error) -> &'static str {
+    error.downcast_ref::<AnalysisError>().unwrap().kind()
+}
+
+#[tokio::test]
+async fn test_message_is_returned_as_result_event() {
+    let (runner, mock) = mock_runner(vec![Answer::Message(message("ok", "end_turn"))]).await;
+
+    let output = runner.run("Say ok").await.unwrap();
+    assert_eq!(result_text(&output), "ok");
+    assert_eq!(
+        result_usage(&output),
+        Some(Usage {
+            input_tokens: 25,
+            output_tokens: 3
+        })
+    );
+
+    let requests = mock.requests.lock().unwrap();
+    let (headers, body) = &requests[0];
+    assert_eq!(headers["x-api-key"], "test-key");
+    assert_eq!(headers["anthropic-version"], ANTHROPIC_VERSION);
+    assert_eq!(body["model"], "claude-test");
+    assert_eq!(body["messages"][0]["content"], "Say ok");
+    assert_eq!(body["stream"], false);
+}
+
+#[tokio::test]
+async fn test_rate_limited_request_is_retried_after_retry_after() {
+    let (runner, mock) = mock_runner(vec![
+        Answer::Status(429, Some("1")),
+        Answer::Message(message("after retry", "end_turn")),
+    ])
+    .await;
+
+    let started = std::time::Instant::now();
+    let output = runner.run("Say something").await.unwrap();
+    assert_eq!(result_text(&output), "after retry");
+    assert_eq!(mock.requests.lock().unwrap().len(), 2);
+    assert!(
+        started.elapsed() >= Duration::from_secs(1),
+        "retry-after honored"
+    );
+}
+
+#[tokio::test]
+async fn test_errors_are_typed() {
+    let (runner, _) = mock_runner(vec![Answer::Status(401, None)]).await;
+    assert_eq!(
+        error_kind(&runner.run("x").await.unwrap_err()),
+        "unauthorized"
+    );
+
+    // 529 is retried like 429, until the retries run out
+    let (mut config, mock) = start(vec![
+        Answer::Status(529, Some("0")),
+        Answer::Status(529, Some("0")),
+    ])
+    .await;
+    config.max_retries = 1;
+    let runner = AnthropicRunner::new(config).unwrap();
+    assert_eq!(
+        error_kind(&runner.run("x").await.unwrap_err()),
+        "rate_limited"
+    );
+    assert_eq!(mock.requests.lock().unwrap().len(), 2);
+
+    let (runner, _) = mock_runner(vec![Answer::Message(message("", "refusal"))]).await;
+    assert_eq!(error_kind(&runner.run("x").await.unwrap_err()), "refused");
+
+    let (runner, _) = mock_runner(vec![Answer::Message(message("cut", "max_tokens"))]).await;
+    assert_eq!(
+        error_kind(&runner.run("x").await.unwrap_err()),
+        "incomplete"
+    );
+}
+
+#[tokio::test]
+async fn test_streamed_message() {
+    let (mut config, mock) = start(vec![Answer::Events(concat!(
+        "event: message_start\n",
+        "data: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":9,\"output_tokens\":1}}}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"streamed \"}}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"answer\"}}\n\n",
+        "event: message_delta\n",
+        "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},\"usage\":{\"output_tokens\":4}}\n\n",
+        "event: message_stop\n",
+        "data: {\"type\":\"message_stop\"}\n\n",
+    ))])
+    .await;
+    config.stream = true;
+    let runner = AnthropicRunner::new(config).unwrap();
+
+    let output = runner.run("Stream it").await.unwrap();
+    assert_eq!(result_text(&output), "streamed answer");
+    assert_eq!(
+        result_usage(&output),
+        Some(Usage {
+            input_tokens: 9,
+            output_tokens: 4
+        })
+    );
+    assert_eq!(mock.requests.lock().unwrap()[0].1["stream"], true);
+}
@@ -0,0 +1,32 @@
+//! Provider selection per use from `LLM_PROVIDER` and its overrides
+
+use discovery::{prompt_runner_from_env, LlmUseCase};
+
+#[test]
+fn test_use_case_overrides_fall_back_to_llm_provider() {
+    std::env::remove_var("ANTHROPIC_API_KEY");
+    std::env::set_var("LLM_PROVIDER", "Anthropic");
+    std::env::set_var("ANSWER_LLM_PROVIDER", "claude-cli");
+    std::env::set_var("RERANK_LLM_PROVIDER", " ");
+    std::env::set_var("DISCOVERY_LLM_PROVIDER", "gpt");
+
+    assert_eq!(LlmUseCase::Answer.provider(), "claude-cli");
+    assert!(LlmUseCase::Answer.uses_cli());
+    assert!(prompt_runner_from_env(LlmUseCase::Answer).is_ok());
+
+    // A blank override falls back to LLM_PROVIDER, which needs a key
+    assert_eq!(LlmUseCase::Rerank.provider(), "anthropic");
+    let err = prompt_runner_from_env(LlmUseCase::Rerank)
+        .err()
+        .expect("anthropic without a key");
+    assert!(err.to_string().contains("ANTHROPIC_API_KEY"));
+
+    let err = prompt_runner_from_env(LlmUseCase::Discovery)
+        .err()
+        .expect("unknown provider");
+    assert!(err.to_string().contains("DISCOVERY_LLM_PROVIDER"));
+
+    std::env::remove_var("LLM_PROVIDER");
+    std::env::remove_var("RERANK_LLM_PROVIDER");
+    assert!(LlmUseCase::Rerank.uses_cli());
+}
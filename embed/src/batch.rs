@@ -4,6 +4,7 @@
 //! in batches through the `OpenAI` Batch API, enabling significant cost savings.
 
 use crate::client::{EmbeddingClient, RateLimiter};
+use crate::config::EmbeddingConfig;
 use crate::models::{
     BatchResponse, BatchStatus, CostInfo, JsonlBatchLine, JsonlRequestBody, JsonlResponseLine,
 };
@@ -36,20 +37,15 @@ pub struct EmbeddingBatchRequest {
 }
 
 impl EmbeddingBatchRequest {
-    /// Create a new batch request with text-embedding-3-large defaults
+    /// Create a new batch request using the configured embedding model
     #[must_use]
     pub fn new(id: String, text: String) -> Self {
-        let model = std::env::var("OPENAI_EMBEDDING_MODEL")
-            .unwrap_or_else(|_| "text-embedding-3-large".to_string());
-        let dimensions = std::env::var("OPENAI_EMBEDDING_DIMS")
-            .ok()
-            .and_then(|v| v.parse::<u32>().ok())
-            .unwrap_or(3072);
+        let config = EmbeddingConfig::from_env_or_default();
         Self {
             id,
             text,
-            model,
-            dimensions: Some(dimensions),
+            dimensions: config.request_dimensions(),
+            model: config.model,
             metadata: HashMap::new(),
         }
     }
@@ -57,8 +53,7 @@ impl EmbeddingBatchRequest {
     /// Create a new batch request with optimized dimensions
     #[must_use]
     pub fn new_optimized(id: String, text: String) -> Self {
-        let model = std::env::var("OPENAI_EMBEDDING_MODEL")
-            .unwrap_or_else(|_| "text-embedding-3-large".to_string());
+        let model = EmbeddingConfig::from_env_or_default().model;
         let dimensions = std::env::var("OPENAI_EMBEDDING_DIMS_OPTIMIZED")
             .ok()
             .and_then(|v| v.parse::<u32>().ok())
@@ -0,0 +1,335 @@
+//! Splitting long documents into overlapping chunks before embedding
+//!
+//! Sizes are measured in characters, matching the loader CLI's
+//! `--chunk-size` / `--chunk-overlap` options. Splits prefer heading and
+//! paragraph boundaries, then sentence ends, then whitespace, and only cut
+//! inside a word when a single word is longer than a chunk.
+
+/// Default chunk size in characters
+pub const DEFAULT_CHUNK_SIZE: usize = 2000;
+
+/// Default overlap between consecutive chunks in characters
+pub const DEFAULT_CHUNK_OVERLAP: usize = 200;
+
+/// Chunk size and overlap, in characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkConfig {
+    /// Maximum characters per chunk
+    pub chunk_size: usize,
+    /// Characters repeated from the end of the previous chunk
+    pub overlap: usize,
+}
+
+impl ChunkConfig {
+    /// Create a chunk configuration
+    ///
+    /// The size is clamped to at least 1 and the overlap to less than half
+    /// the size so that every chunk makes progress.
+    #[must_use]
+    pub fn new(chunk_size: usize, overlap: usize) -> Self {
+        let chunk_size = chunk_size.max(1);
+        Self {
+            chunk_size,
+            overlap: overlap.min(chunk_size / 2),
+        }
+    }
+
+    /// Read `EMBEDDING_CHUNK_SIZE` / `EMBEDDING_CHUNK_OVERLAP`, defaulting to 2000/200
+    #[must_use]
+    pub fn from_env() -> Self {
+        let read = |key: &str, default: usize| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .unwrap_or(default)
+        };
+        Self::new(
+            read("EMBEDDING_CHUNK_SIZE", DEFAULT_CHUNK_SIZE),
+            read("EMBEDDING_CHUNK_OVERLAP", DEFAULT_CHUNK_OVERLAP),
+        )
+    }
+}
+
+impl Default for ChunkConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_CHUNK_SIZE, DEFAULT_CHUNK_OVERLAP)
+    }
+}
+
+/// Split `text` into overlapping chunks of at most `config.chunk_size` characters
+///
+/// Text that already fits is returned as a single chunk, unchanged. Empty
+/// text yields no chunks.
+#[must_use]
+pub fn chunk_text(text: &str, config: &ChunkConfig) -> Vec<String> {
+    if text.trim().is_empty() {
+        return Vec::new();
+    }
+    if text.chars().count() <= config.chunk_size {
+        return vec![text.to_string()];
+    }
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    loop {
+        let rest = &text[start..];
+        if rest.chars().count() <= config.chunk_size {
+            let chunk = rest.trim();
+            if !chunk.is_empty() {
+                chunks.push(chunk.to_string());
+            }
+            break;
+        }
+
+        let window_end = start + byte_offset(rest, config.chunk_size);
+        let split = find_split(text, start, window_end, config.chunk_size);
+        let chunk = text[start..split].trim();
+        if !chunk.is_empty() {
+            chunks.push(chunk.to_string());
+        }
+
+        start = overlap_start(text, start, split, config.overlap);
+    }
+
+    chunks
+}
+
+/// Split `text` into chunks that start at `boundaries` where possible
+///
+/// `boundaries` are character offsets of section starts, such as the
+/// headings of a markdown outline. Consecutive sections are packed into
+/// chunks of at most `config.chunk_size` characters; a section that does not
+/// fit on its own is split with [`chunk_text`]. Text that already fits is
+/// returned as a single chunk, unchanged.
+#[must_use]
+pub fn chunk_text_at_boundaries(
+    text: &str,
+    boundaries: &[usize],
+    config: &ChunkConfig,
+) -> Vec<String> {
+    if text.chars().count() <= config.chunk_size || boundaries.is_empty() {
+        return chunk_text(text, config);
+    }
+
+    let mut splits: Vec<usize> = boundaries
+        .iter()
+        .map(|&offset| byte_offset(text, offset))
+        .filter(|&split| split > 0 && split < text.len())
+        .collect();
+    splits.sort_unstable();
+    splits.dedup();
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut start = 0;
+    for end in splits.into_iter().chain([text.len()]) {
+        let section = text[start..end].trim();
+        start = end;
+        if section.is_empty() {
+            continue;
+        }
+        let length = section.chars().count();
+        if !current.is_empty() && current.chars().count() + 1 + length > config.chunk_size {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if length > config.chunk_size {
+            chunks.extend(chunk_text(section, config));
+            continue;
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(section);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Byte offset of the `chars`-th character of `s` (or its length)
+fn byte_offset(s: &str, chars: usize) -> usize {
+    s.char_indices().nth(chars).map_or(s.len(), |(i, _)| i)
+}
+
+/// Pick the best split point in `text[start..window_end]`
+///
+/// Only the second half of the window is considered so chunks stay close to
+/// the configured size.
+fn find_split(text: &str, start: usize, window_end: usize, chunk_size: usize) -> usize {
+    let window = &text[start..window_end];
+    let min = byte_offset(window, chunk_size / 2);
+    let candidates = &window[min..];
+
+    // Heading: split right before a markdown heading line
+    if let Some(pos) = candidates.rfind("\n#") {
+        return start + min + pos + 1;
+    }
+    // Paragraph break
+    if let Some(pos) = candidates.rfind("\n\n") {
+        return start + min + pos + 2;
+    }
+    // Sentence end followed by whitespace
+    let sentence_end = candidates
+        .char_indices()
+        .filter(|&(i, c)| {
+            matches!(c, '.' | '!' | '?')
+                && candidates[i + c.len_utf8()..]
+                    .chars()
+                    .next()
+                    .is_some_and(char::is_whitespace)
+        })
+        .map(|(i, c)| i + c.len_utf8())
+        .next_back();
+    if let Some(pos) = sentence_end {
+        return start + min + pos;
+    }
+    // Any whitespace
+    if let Some((pos, c)) = candidates.char_indices().rfind(|(_, c)| c.is_whitespace()) {
+        return start + min + pos + c.len_utf8();
+    }
+    window_end
+}
+
+/// Start of the next chunk: up to `overlap` characters before `split`,
+/// moved forward to the start of a word
+fn overlap_start(text: &str, start: usize, split: usize, overlap: usize) -> usize {
+    if overlap == 0 {
+        return split;
+    }
+
+    let before = &text[start..split];
+    let total = before.chars().count();
+    let mut candidate = start + byte_offset(before, total.saturating_sub(overlap));
+
+    // Avoid starting mid-word
+    if candidate > start && !text[..candidate].ends_with(char::is_whitespace) {
+        match text[candidate..split].find(char::is_whitespace) {
+            Some(ws) => candidate += ws,
+            None => return split,
+        }
+    }
+    let trimmed = text[candidate..split].trim_start();
+    let candidate = split - trimmed.len();
+
+    // Always make progress
+    if candidate <= start {
+        split
+    } else {
+        candidate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sentences(count: usize) -> String {
+        (0..count)
+            .map(|i| format!("Sentence number {i} describes the item in some detail."))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn test_short_document_single_chunk() {
+        let config = ChunkConfig::new(100, 10);
+        let text = "A short doc comment.";
+        assert_eq!(chunk_text(text, &config), vec![text.to_string()]);
+        assert!(chunk_text("   ", &config).is_empty());
+    }
+
+    #[test]
+    fn test_exactly_at_boundary() {
+        let config = ChunkConfig::new(100, 10);
+        let text = "x".repeat(100);
+        assert_eq!(chunk_text(&text, &config), vec![text.clone()]);
+
+        let text = format!("{} {}", "a".repeat(60), "b".repeat(40));
+        let chunks = chunk_text(&text, &config);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0], "a".repeat(60));
+        assert!(chunks.iter().all(|c| c.chars().count() <= 100));
+    }
+
+    #[test]
+    fn test_large_document() {
+        let config = ChunkConfig::new(500, 50);
+        let text = sentences(200);
+        let chunks = chunk_text(&text, &config);
+
+        assert!(chunks.len() > 10);
+        for chunk in &chunks {
+            assert!(chunk.chars().count() <= 500);
+            // Sentence boundaries are preferred over mid-sentence cuts
+            assert!(chunk.ends_with('.'), "chunk ends mid-sentence: {chunk:?}");
+        }
+        // Consecutive chunks overlap
+        for pair in chunks.windows(2) {
+            let head: String = pair[1].chars().take(20).collect();
+            assert!(pair[0].contains(&head));
+        }
+        // Nothing is lost
+        assert!(chunks
+            .last()
+            .unwrap()
+            .ends_with("Sentence number 199 describes the item in some detail."));
+        assert!(chunks[0].starts_with("Sentence number 0 "));
+    }
+
+    #[test]
+    fn test_prefers_headings_and_paragraphs() {
+        let config = ChunkConfig::new(200, 0);
+        let text = format!(
+            "# Intro\n\n{}\n\n# Usage\n\n{}",
+            "intro words ".repeat(10),
+            "usage words ".repeat(10)
+        );
+        let chunks = chunk_text(&text, &config);
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].starts_with("# Intro"));
+        assert!(chunks[1].starts_with("# Usage"));
+    }
+
+    #[test]
+    fn test_splits_at_boundaries() {
+        let config = ChunkConfig::new(60, 0);
+        let text = format!(
+            "Intro {} Usage {} Errors {}",
+            "a".repeat(20),
+            "b".repeat(20),
+            "c ".repeat(50)
+        );
+        let usage = text.find("Usage").unwrap();
+        let errors = text.find("Errors").unwrap();
+        let chunks = chunk_text_at_boundaries(&text, &[usage, errors], &config);
+        assert_eq!(
+            chunks[0],
+            format!("Intro {} Usage {}", "a".repeat(20), "b".repeat(20))
+        );
+        assert!(chunks[1].starts_with("Errors c"));
+        assert!(chunks.iter().all(|c| c.chars().count() <= 60));
+
+        let short = "Intro text. Usage text.";
+        assert_eq!(chunk_text_at_boundaries(short, &[12], &config), [short]);
+    }
+
+    #[test]
+    fn test_long_word_hard_split() {
+        let config = ChunkConfig::new(50, 5);
+        let text = "z".repeat(175);
+        let chunks = chunk_text(&text, &config);
+        assert!(chunks.iter().all(|c| c.chars().count() <= 50));
+        assert_eq!(chunks.concat().len(), 175);
+    }
+
+    #[test]
+    fn test_multibyte_text() {
+        let config = ChunkConfig::new(30, 5);
+        let text = "détails über die Straße und das Ökosystem. ".repeat(10);
+        let chunks = chunk_text(&text, &config);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.chars().count() <= 30));
+    }
+}
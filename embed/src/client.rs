@@ -1,5 +1,6 @@
 //! `OpenAI` embedding client
 
+use crate::config::{EmbeddingConfig, EmbeddingProvider};
 use crate::models::{
     BatchRequest, BatchResponse, EmbeddingRequest, EmbeddingResponse, FileUploadResponse,
     JsonlResponseLine,
@@ -10,7 +11,7 @@ use reqwest::{multipart, Client};
 use serde_json::json;
 use std::{env, sync::Arc, time::Duration};
 use tokio::{sync::Mutex, time::Instant};
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, info_span, warn, Instrument};
 
 // === Retry Logic Configuration ===
 
@@ -308,12 +309,10 @@ impl RateLimiter {
         Ok(())
     }
 
-    /// Estimate token count from text length
+    /// Estimate token count for rate limiting (min 1)
     #[must_use]
     pub fn estimate_tokens(text: &str) -> u32 {
-        // 1 token ≈ 4 chars; ceil division, min 1
-        let len = text.len();
-        let est = len.div_ceil(4);
+        let est = crate::tokens::token_count(text);
         u32::try_from(est.max(1)).unwrap_or(u32::MAX)
     }
 }
@@ -347,6 +346,57 @@ pub trait EmbeddingClient {
 
     /// Cancel a batch
     async fn cancel_batch(&self, batch_id: &str) -> Result<BatchResponse>;
+
+    /// Model and dimension used by [`EmbeddingClient::embed`]
+    fn embedding_config(&self) -> EmbeddingConfig {
+        EmbeddingConfig::from_env_or_default()
+    }
+
+    /// Embed several texts in one call, returning embeddings in input order
+    ///
+    /// The default embeds the texts one at a time; clients with batched
+    /// inference override it and return `true` from
+    /// [`EmbeddingClient::prefers_batches`].
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for text in texts {
+            embeddings.push(self.embed(text).await?);
+        }
+        Ok(embeddings)
+    }
+
+    /// Whether one [`EmbeddingClient::embed_batch`] call beats concurrent [`EmbeddingClient::embed`] calls
+    fn prefers_batches(&self) -> bool {
+        false
+    }
+
+    /// Check the provider answers by embedding a single word
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the probe embedding.
+    async fn health_check(&self) -> Result<()> {
+        self.embed("ping").await.map(|_| ())
+    }
+}
+
+/// Create the embedding client selected by `EMBEDDING_PROVIDER`
+///
+/// # Errors
+///
+/// Returns an error if the provider or model configuration is invalid, the
+/// local provider was requested without the `local` feature, or the client
+/// fails to initialize.
+pub fn embedding_client_from_env() -> Result<Arc<dyn EmbeddingClient + Send + Sync>> {
+    match EmbeddingProvider::from_env()? {
+        EmbeddingProvider::OpenAi => Ok(Arc::new(OpenAIEmbeddingClient::new()?)),
+        #[cfg(feature = "local")]
+        EmbeddingProvider::Local => Ok(Arc::new(crate::local::LocalEmbeddingClient::new()?)),
+        #[cfg(not(feature = "local"))]
+        EmbeddingProvider::Local => Err(anyhow!(
+            "EMBEDDING_PROVIDER=local requires building with the embed crate's `local` feature"
+        )),
+    }
 }
 
 /// `OpenAI` embedding client implementation
@@ -354,7 +404,7 @@ pub struct OpenAIEmbeddingClient {
     client: Client,
     api_key: String,
     base_url: String,
-    default_model: String,
+    config: EmbeddingConfig,
     rate_limiter: RateLimiter,
     retry_policy: RetryPolicy,
     circuit_breaker: Arc<Mutex<CircuitBreaker>>,
@@ -366,13 +416,12 @@ impl OpenAIEmbeddingClient {
     /// # Errors
     ///
     /// Returns an error if required environment variables or HTTP client
-    /// initialization fails.
+    /// initialization fails, or the embedding model configuration is invalid.
     pub fn new() -> Result<Self> {
         let api_key = env::var("OPENAI_API_KEY").unwrap_or_else(|_| "dummy-key".to_string()); // Allow dummy key for testing
         let base_url =
             env::var("OPENAI_BASE_URL").unwrap_or_else(|_| "https://api.openai.com/v1".to_string());
-        let default_model = env::var("OPENAI_EMBEDDING_MODEL")
-            .unwrap_or_else(|_| "text-embedding-3-large".to_string());
+        let config = EmbeddingConfig::from_env()?;
 
         let client = Client::new();
 
@@ -380,7 +429,7 @@ impl OpenAIEmbeddingClient {
             client,
             api_key,
             base_url,
-            default_model,
+            config,
             rate_limiter: RateLimiter::new(),
             retry_policy: RetryPolicy::new(),
             circuit_breaker: Arc::new(Mutex::new(CircuitBreaker::new(5, Duration::from_secs(300)))), // 5 failures, 5-minute timeout
@@ -475,10 +524,12 @@ impl EmbeddingClient for OpenAIEmbeddingClient {
     async fn embed(&self, text: &str) -> Result<Vec<f32>> {
         let request = EmbeddingRequest {
             input: text.to_string(),
-            model: self.default_model.clone(),
+            model: self.config.model.clone(),
+            dimensions: self.config.request_dimensions(),
         };
 
-        let response = self.generate_embedding(request).await?;
+        let span = info_span!("embedding", model = %request.model, input_chars = text.len());
+        let response = self.generate_embedding(request).instrument(span).await?;
         Ok(response.embedding)
     }
 
@@ -495,11 +546,14 @@ impl EmbeddingClient for OpenAIEmbeddingClient {
             .wait_for_capacity(estimated_tokens)
             .await?;
 
-        let payload = json!({
+        let mut payload = json!({
             "input": request.input,
             "model": request.model,
             "encoding_format": "float"
         });
+        if let Some(dimensions) = request.dimensions {
+            payload["dimensions"] = json!(dimensions);
+        }
 
         let response = self
             .client
@@ -510,13 +564,15 @@ impl EmbeddingClient for OpenAIEmbeddingClient {
             .send()
             .await?;
 
-        if !response.status().is_success() {
+        let status = response.status();
+        if !status.is_success() {
             let error_text = response
                 .text()
                 .await
                 .unwrap_or_else(|_| "Unknown error".to_string());
-            error!("OpenAI API error: {}", error_text);
-            return Err(anyhow!("OpenAI API error: {}", error_text));
+            error!("OpenAI API error ({}): {}", status, error_text);
+            // The status code lets RetryPolicy::is_retryable_error spot 429s and 5xx
+            return Err(anyhow!("OpenAI API error ({}): {}", status, error_text));
         }
 
         let api_response: serde_json::Value = response.json().await?;
@@ -721,4 +777,8 @@ impl EmbeddingClient for OpenAIEmbeddingClient {
         info!("Successfully cancelled batch: {}", batch_response.id);
         Ok(batch_response)
     }
+
+    fn embedding_config(&self) -> EmbeddingConfig {
+        self.config.clone()
+    }
 }
@@ -0,0 +1,318 @@
+//! Embedding model configuration
+//!
+//! The model and output dimension are read from the environment once and
+//! shared by the embedding client, batch requests and the schema check that
+//! runs at server startup. `EMBEDDING_MODEL` / `EMBEDDING_DIMENSIONS` take
+//! precedence over the older `OPENAI_EMBEDDING_MODEL` / `OPENAI_EMBEDDING_DIMS`.
+//! `EMBEDDING_PROVIDER` selects between the `OpenAI` API and an in-process
+//! model; the default model and dimension follow the provider.
+
+use anyhow::{anyhow, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Model used when none is configured
+pub const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-large";
+
+/// Model used by the local provider when none is configured
+pub const DEFAULT_LOCAL_EMBEDDING_MODEL: &str = "bge-small-en-v1.5";
+
+/// Metadata key recording which model produced a document's embedding
+pub const METADATA_MODEL_KEY: &str = "embedding_model";
+
+/// Metadata key recording the dimension of a document's embedding
+pub const METADATA_DIMENSIONS_KEY: &str = "embedding_dimensions";
+
+/// Cleared when the configured dimension does not match the database column
+static VECTOR_WRITES_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Where embeddings are computed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddingProvider {
+    /// `OpenAI` embeddings API
+    OpenAi,
+    /// In-process ONNX model (requires the `local` feature)
+    Local,
+}
+
+impl EmbeddingProvider {
+    /// Read `EMBEDDING_PROVIDER` (`openai` or `local`, default `openai`)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for any other value.
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("EMBEDDING_PROVIDER") {
+            Ok(raw) => Self::parse(&raw),
+            Err(_) => Ok(Self::OpenAi),
+        }
+    }
+
+    /// Parse a provider name
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is not `openai` or `local`.
+    pub fn parse(raw: &str) -> Result<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "" | "openai" => Ok(Self::OpenAi),
+            "local" => Ok(Self::Local),
+            other => Err(anyhow!(
+                "EMBEDDING_PROVIDER must be 'openai' or 'local', got '{other}'"
+            )),
+        }
+    }
+
+    /// Model used when `EMBEDDING_MODEL` is not set
+    #[must_use]
+    pub const fn default_model(self) -> &'static str {
+        match self {
+            Self::OpenAi => DEFAULT_EMBEDDING_MODEL,
+            Self::Local => DEFAULT_LOCAL_EMBEDDING_MODEL,
+        }
+    }
+}
+
+/// Embedding model and output dimension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddingConfig {
+    /// `OpenAI` or local model name
+    pub model: String,
+    /// Length of the vectors produced by the model
+    pub dimensions: u32,
+}
+
+impl EmbeddingConfig {
+    /// Create a configuration, validating the dimension against the model
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `dimensions` is zero, exceeds the model's native
+    /// size, or the model does not support shortened embeddings.
+    pub fn new(model: impl Into<String>, dimensions: u32) -> Result<Self> {
+        let config = Self {
+            model: model.into(),
+            dimensions,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Read the configuration from the environment
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `EMBEDDING_PROVIDER` is unknown, or
+    /// `EMBEDDING_DIMENSIONS` is not a positive integer or is not valid for
+    /// the configured model.
+    pub fn from_env() -> Result<Self> {
+        let provider = EmbeddingProvider::from_env()?;
+        let model = std::env::var("EMBEDDING_MODEL")
+            .or_else(|_| std::env::var("OPENAI_EMBEDDING_MODEL"))
+            .ok()
+            .filter(|m| !m.trim().is_empty())
+            .unwrap_or_else(|| provider.default_model().to_string());
+
+        let dimensions = match std::env::var("EMBEDDING_DIMENSIONS")
+            .or_else(|_| std::env::var("OPENAI_EMBEDDING_DIMS"))
+        {
+            Ok(raw) => raw.trim().parse::<u32>().map_err(|_| {
+                anyhow!("EMBEDDING_DIMENSIONS must be a positive integer, got '{raw}'")
+            })?,
+            Err(_) => native_dimensions(&model).unwrap_or(3072),
+        };
+
+        Self::new(model, dimensions)
+    }
+
+    /// Read the configuration from the environment, falling back to defaults
+    ///
+    /// Invalid settings are logged and replaced by the default model.
+    #[must_use]
+    pub fn from_env_or_default() -> Self {
+        Self::from_env().unwrap_or_else(|e| {
+            tracing::warn!("Invalid embedding configuration, using defaults: {}", e);
+            Self::default()
+        })
+    }
+
+    /// Check the dimension against what the model can produce
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the first invalid setting.
+    pub fn validate(&self) -> Result<()> {
+        if self.dimensions == 0 {
+            return Err(anyhow!("Embedding dimensions must be greater than zero"));
+        }
+        if let Some(native) = native_dimensions(&self.model) {
+            if self.dimensions > native {
+                return Err(anyhow!(
+                    "Model {} produces at most {} dimensions, {} requested",
+                    self.model,
+                    native,
+                    self.dimensions
+                ));
+            }
+            if self.dimensions != native && !supports_dimensions_param(&self.model) {
+                return Err(anyhow!(
+                    "Model {} does not support the dimensions parameter (fixed at {})",
+                    self.model,
+                    native
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Value for the `dimensions` request parameter, if it should be sent
+    ///
+    /// Only the `text-embedding-3` family accepts the parameter; it is
+    /// omitted for other models.
+    #[must_use]
+    pub fn request_dimensions(&self) -> Option<u32> {
+        supports_dimensions_param(&self.model).then_some(self.dimensions)
+    }
+
+    /// Metadata fields recorded on each embedded document
+    #[must_use]
+    pub fn metadata_fields(&self) -> [(&'static str, serde_json::Value); 2] {
+        [
+            (METADATA_MODEL_KEY, serde_json::json!(self.model)),
+            (METADATA_DIMENSIONS_KEY, serde_json::json!(self.dimensions)),
+        ]
+    }
+
+    /// Add the model and dimension to a document metadata object
+    ///
+    /// Non-object metadata is left untouched.
+    pub fn annotate_metadata(&self, metadata: &mut serde_json::Value) {
+        if let Some(map) = metadata.as_object_mut() {
+            for (key, value) in self.metadata_fields() {
+                map.insert(key.to_string(), value);
+            }
+        }
+    }
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            model: DEFAULT_EMBEDDING_MODEL.to_string(),
+            dimensions: 3072,
+        }
+    }
+}
+
+/// Native output dimension of known `OpenAI` and local embedding models
+#[must_use]
+pub fn native_dimensions(model: &str) -> Option<u32> {
+    match model {
+        "text-embedding-3-large" => Some(3072),
+        "text-embedding-3-small" | "text-embedding-ada-002" => Some(1536),
+        "bge-small-en-v1.5" | "all-minilm-l6-v2" => Some(384),
+        _ => None,
+    }
+}
+
+/// List price in USD per million input tokens of known `OpenAI` embedding models
+#[must_use]
+pub fn price_per_million_tokens(model: &str) -> Option<f64> {
+    match model {
+        "text-embedding-3-large" => Some(0.13),
+        "text-embedding-3-small" => Some(0.02),
+        "text-embedding-ada-002" => Some(0.10),
+        _ => None,
+    }
+}
+
+/// Whether the model accepts the `dimensions` request parameter
+#[must_use]
+pub fn supports_dimensions_param(model: &str) -> bool {
+    model.starts_with("text-embedding-3")
+}
+
+/// Stop storing embeddings for the rest of the process lifetime
+///
+/// Used when the configured dimension does not fit the database column and
+/// the operator has chosen to keep serving instead of refusing to start.
+pub fn disable_vector_writes() {
+    VECTOR_WRITES_ENABLED.store(false, Ordering::SeqCst);
+}
+
+/// Whether embeddings may be written to the database
+#[must_use]
+pub fn vector_writes_enabled() -> bool {
+    VECTOR_WRITES_ENABLED.load(Ordering::SeqCst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_native_dimensions_default() {
+        let config = EmbeddingConfig::new("text-embedding-3-small", 1536).unwrap();
+        assert_eq!(config.request_dimensions(), Some(1536));
+        assert_eq!(native_dimensions("text-embedding-3-large"), Some(3072));
+        assert_eq!(native_dimensions("custom-model"), None);
+        assert_eq!(
+            price_per_million_tokens("text-embedding-3-small"),
+            Some(0.02)
+        );
+        assert_eq!(price_per_million_tokens("custom-model"), None);
+    }
+
+    #[test]
+    fn test_shortened_dimensions() {
+        let config = EmbeddingConfig::new("text-embedding-3-large", 1024).unwrap();
+        assert_eq!(config.request_dimensions(), Some(1024));
+    }
+
+    #[test]
+    fn test_invalid_dimensions() {
+        assert!(EmbeddingConfig::new("text-embedding-3-small", 3072).is_err());
+        assert!(EmbeddingConfig::new("text-embedding-3-large", 0).is_err());
+        assert!(EmbeddingConfig::new("text-embedding-ada-002", 512).is_err());
+    }
+
+    #[test]
+    fn test_ada_omits_dimensions_param() {
+        let config = EmbeddingConfig::new("text-embedding-ada-002", 1536).unwrap();
+        assert_eq!(config.request_dimensions(), None);
+    }
+
+    #[test]
+    fn test_unknown_model_accepted() {
+        let config = EmbeddingConfig::new("local-model", 768).unwrap();
+        assert_eq!(config.request_dimensions(), None);
+    }
+
+    #[test]
+    fn test_local_provider_defaults() {
+        assert_eq!(
+            EmbeddingProvider::parse(" Local ").unwrap(),
+            EmbeddingProvider::Local
+        );
+        assert_eq!(
+            EmbeddingProvider::parse("").unwrap(),
+            EmbeddingProvider::OpenAi
+        );
+        assert!(EmbeddingProvider::parse("cohere").is_err());
+
+        let model = EmbeddingProvider::Local.default_model();
+        assert_eq!(native_dimensions(model), Some(384));
+        let config = EmbeddingConfig::new(model, 384).unwrap();
+        assert_eq!(config.request_dimensions(), None);
+        assert!(EmbeddingConfig::new("all-minilm-l6-v2", 256).is_err());
+    }
+
+    #[test]
+    fn test_annotate_metadata() {
+        let config = EmbeddingConfig::new("text-embedding-3-small", 512).unwrap();
+        let mut metadata = serde_json::json!({"crate_name": "serde"});
+        config.annotate_metadata(&mut metadata);
+        assert_eq!(metadata[METADATA_MODEL_KEY], "text-embedding-3-small");
+        assert_eq!(metadata[METADATA_DIMENSIONS_KEY], 512);
+        assert_eq!(metadata["crate_name"], "serde");
+    }
+}
@@ -0,0 +1,278 @@
+//! Model prices and cost accounting
+//!
+//! Prices are USD per 1,000 tokens, keyed by model name. The defaults are
+//! the providers' list prices; `MODEL_PRICES` overrides or extends them with
+//! a JSON object, given inline or as the path of a file:
+//!
+//! ```json
+//! {"text-embedding-3-large": 0.00013,
+//!  "claude-3-5-sonnet": {"input_per_1k": 0.003, "output_per_1k": 0.015}}
+//! ```
+//!
+//! A bare number is the input price. A model without an exact entry takes
+//! the longest entry its name starts with, so dated model names such as
+//! `claude-3-5-sonnet-20241022` match their family. Unknown models, like the
+//! in-process embedding models, cost nothing.
+
+use crate::config::price_per_million_tokens;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+/// Environment variable holding price overrides
+pub const MODEL_PRICES_ENV: &str = "MODEL_PRICES";
+
+/// Price of one model in USD per 1,000 tokens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_1k: f64,
+    /// Generated tokens; embedding models have none
+    #[serde(default)]
+    pub output_per_1k: f64,
+}
+
+impl ModelPrice {
+    /// Cost in USD of the given token counts
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn cost(&self, input_tokens: u64, output_tokens: u64) -> f64 {
+        (input_tokens as f64 * self.input_per_1k + output_tokens as f64 * self.output_per_1k)
+            / 1_000.0
+    }
+}
+
+/// Per-model prices with list-price defaults
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceTable {
+    prices: BTreeMap<String, ModelPrice>,
+}
+
+impl Default for PriceTable {
+    fn default() -> Self {
+        let mut prices = BTreeMap::new();
+        for model in [
+            "text-embedding-3-large",
+            "text-embedding-3-small",
+            "text-embedding-ada-002",
+        ] {
+            if let Some(per_million) = price_per_million_tokens(model) {
+                prices.insert(
+                    model.to_string(),
+                    ModelPrice {
+                        input_per_1k: per_million / 1_000.0,
+                        output_per_1k: 0.0,
+                    },
+                );
+            }
+        }
+        for (model, input_per_1k, output_per_1k) in [
+            ("claude-3-haiku", 0.000_25, 0.001_25),
+            ("claude-3-5-haiku", 0.000_8, 0.004),
+            ("claude-3-5-sonnet", 0.003, 0.015),
+            ("claude-3-7-sonnet", 0.003, 0.015),
+            ("claude-sonnet-4", 0.003, 0.015),
+            ("claude-3-opus", 0.015, 0.075),
+            ("claude-opus-4", 0.015, 0.075),
+        ] {
+            prices.insert(
+                model.to_string(),
+                ModelPrice {
+                    input_per_1k,
+                    output_per_1k,
+                },
+            );
+        }
+        Self { prices }
+    }
+}
+
+impl PriceTable {
+    /// Default prices with the `MODEL_PRICES` overrides applied
+    ///
+    /// Overrides that cannot be read are logged and ignored.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let table = Self::default();
+        let Ok(value) = std::env::var(MODEL_PRICES_ENV) else {
+            return table;
+        };
+        let json = if value.trim_start().starts_with('{') {
+            Ok(value)
+        } else {
+            std::fs::read_to_string(value.trim()).map_err(|e| anyhow!("Read {}: {}", value, e))
+        };
+        match json.and_then(|json| table.clone().with_overrides(&json)) {
+            Ok(table) => table,
+            Err(e) => {
+                tracing::warn!("Ignoring {}: {}", MODEL_PRICES_ENV, e);
+                table
+            }
+        }
+    }
+
+    /// This table with the prices of a JSON override object applied
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `json` is not an object of prices.
+    pub fn with_overrides(mut self, json: &str) -> Result<Self> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Entry {
+            Input(f64),
+            Price(ModelPrice),
+        }
+        let overrides: BTreeMap<String, Entry> =
+            serde_json::from_str(json).map_err(|e| anyhow!("Invalid model prices: {}", e))?;
+        for (model, entry) in overrides {
+            let price = match entry {
+                Entry::Input(input_per_1k) => ModelPrice {
+                    input_per_1k,
+                    output_per_1k: 0.0,
+                },
+                Entry::Price(price) => price,
+            };
+            self.prices.insert(model, price);
+        }
+        Ok(self)
+    }
+
+    /// Price of `model`, or of the longest entry its name starts with
+    #[must_use]
+    pub fn price(&self, model: &str) -> Option<ModelPrice> {
+        self.prices.get(model).copied().or_else(|| {
+            self.prices
+                .iter()
+                .filter(|(name, _)| model.starts_with(name.as_str()))
+                .max_by_key(|(name, _)| name.len())
+                .map(|(_, price)| *price)
+        })
+    }
+
+    /// Cost in USD of the given token counts; zero for unknown models
+    #[must_use]
+    pub fn cost(&self, model: &str, input_tokens: u64, output_tokens: u64) -> f64 {
+        self.price(model)
+            .map_or(0.0, |price| price.cost(input_tokens, output_tokens))
+    }
+}
+
+static PRICES: LazyLock<PriceTable> = LazyLock::new(PriceTable::from_env);
+
+/// Process-wide price table, read from the environment on first use
+#[must_use]
+pub fn prices() -> &'static PriceTable {
+    &PRICES
+}
+
+/// API usage and its cost for one model
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelUsage {
+    /// Requests sent to the model
+    pub calls: u64,
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+}
+
+/// API usage of one job, per model, as recorded in its `job_costs` details
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct JobCosts {
+    pub models: BTreeMap<String, ModelUsage>,
+    pub total_cost_usd: f64,
+}
+
+impl JobCosts {
+    /// Account `calls` requests to `model`, returning their cost
+    pub fn record(
+        &mut self,
+        model: &str,
+        calls: u64,
+        input_tokens: u64,
+        output_tokens: u64,
+        prices: &PriceTable,
+    ) -> f64 {
+        let cost = prices.cost(model, input_tokens, output_tokens);
+        let usage = self.models.entry(model.to_string()).or_default();
+        usage.calls += calls;
+        usage.input_tokens += input_tokens;
+        usage.output_tokens += output_tokens;
+        usage.cost_usd += cost;
+        self.total_cost_usd += cost;
+        cost
+    }
+
+    /// Add another account to this one, such as an earlier attempt of the same job
+    pub fn merge(&mut self, other: &Self) {
+        for (model, usage) in &other.models {
+            let total = self.models.entry(model.clone()).or_default();
+            total.calls += usage.calls;
+            total.input_tokens += usage.input_tokens;
+            total.output_tokens += usage.output_tokens;
+            total.cost_usd += usage.cost_usd;
+        }
+        self.total_cost_usd += other.total_cost_usd;
+    }
+
+    /// Whether no request was accounted
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.models.values().all(|usage| usage.calls == 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_price_lookup_and_overrides() {
+        let table = PriceTable::default();
+        let large = table.price("text-embedding-3-large").unwrap();
+        assert!((large.input_per_1k - 0.000_13).abs() < 1e-12);
+        // Dated names take their family's price
+        assert_eq!(
+            table.price("claude-3-5-sonnet-20241022"),
+            table.price("claude-3-5-sonnet")
+        );
+        assert_eq!(table.price("bge-small-en-v1.5"), None);
+        assert!(table.cost("bge-small-en-v1.5", 1_000, 0).abs() < f64::EPSILON);
+
+        let table = table
+            .with_overrides(
+                r#"{"text-embedding-3-large": 0.001, "in-house": {"input_per_1k": 0.01, "output_per_1k": 0.02}}"#,
+            )
+            .unwrap();
+        assert!((table.cost("text-embedding-3-large", 2_000, 0) - 0.002).abs() < 1e-12);
+        assert!((table.cost("in-house", 1_000, 500) - 0.02).abs() < 1e-12);
+        assert!(PriceTable::default().with_overrides("[1]").is_err());
+    }
+
+    #[test]
+    fn test_job_costs_accumulate() {
+        let table = PriceTable::default()
+            .with_overrides(
+                r#"{"embedder": 0.1, "writer": {"input_per_1k": 1.0, "output_per_1k": 2.0}}"#,
+            )
+            .unwrap();
+        let mut costs = JobCosts::default();
+        assert!(costs.is_empty());
+        costs.record("embedder", 3, 1_500, 0, &table);
+        costs.record("embedder", 1, 500, 0, &table);
+        costs.record("writer", 1, 1_000, 1_000, &table);
+
+        let mut retried = costs.clone();
+        retried.merge(&costs);
+        let embedder = &retried.models["embedder"];
+        assert_eq!(embedder.calls, 8);
+        assert_eq!(embedder.input_tokens, 4_000);
+        assert!((embedder.cost_usd - 0.4).abs() < 1e-12);
+        assert!((retried.total_cost_usd - 6.4).abs() < 1e-12);
+
+        let json = serde_json::to_value(&costs).unwrap();
+        assert_eq!(json["models"]["writer"]["output_tokens"], 1_000);
+        assert_eq!(serde_json::from_value::<JobCosts>(json).unwrap(), costs);
+    }
+}
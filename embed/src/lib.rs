@@ -1,18 +1,31 @@
 //! Embedding generation and processing
 //!
 //! This crate handles `OpenAI` API integration for generating embeddings,
-//! batch processing for cost optimization, and vector operations.
+//! batch processing for cost optimization, and vector operations. With the
+//! `local` feature, embeddings can instead come from an in-process model.
 
 pub mod batch;
+pub mod chunking;
 pub mod client;
+pub mod config;
+pub mod cost;
+#[cfg(feature = "local")]
+pub mod local;
 pub mod models;
+pub mod pipeline;
+pub mod tokens;
 
 #[cfg(test)]
 mod integration_tests;
 
 pub use batch::BatchProcessor;
-pub use client::{EmbeddingClient, OpenAIEmbeddingClient};
+pub use chunking::{chunk_text, chunk_text_at_boundaries, ChunkConfig};
+pub use client::{embedding_client_from_env, EmbeddingClient, OpenAIEmbeddingClient};
+pub use config::{vector_writes_enabled, EmbeddingConfig, EmbeddingProvider};
+pub use cost::{prices, JobCosts, ModelPrice, ModelUsage, PriceTable};
 pub use models::*;
+pub use pipeline::{EmbeddingPipeline, EmbeddingStats, PipelineConfig};
+pub use tokens::token_count;
 
 /// Re-export pgvector types
 pub use pgvector::Vector;
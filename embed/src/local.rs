@@ -0,0 +1,215 @@
+//! In-process embedding models
+//!
+//! With `EMBEDDING_PROVIDER=local` embeddings come from an ONNX model run by
+//! fastembed instead of the `OpenAI` API, for deployments that cannot reach
+//! it. The model is loaded once per process and inference runs on the
+//! blocking thread pool so it never stalls the async runtime. Model files are
+//! downloaded into `LOCAL_EMBEDDING_CACHE_DIR` on first use; air-gapped hosts
+//! pre-populate that directory. ONNX Runtime itself is loaded from
+//! `ORT_DYLIB_PATH`.
+
+use crate::client::EmbeddingClient;
+use crate::config::EmbeddingConfig;
+use crate::models::{
+    BatchResponse, EmbeddingRequest, EmbeddingResponse, FileUploadResponse, JsonlResponseLine,
+};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use fastembed::{EmbeddingModel, TextEmbedding, TextInitOptions};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+/// Texts per inference batch when `LOCAL_EMBEDDING_BATCH_SIZE` is not set
+pub const DEFAULT_LOCAL_BATCH_SIZE: usize = 32;
+
+/// Model directory used when `LOCAL_EMBEDDING_CACHE_DIR` is not set
+pub const DEFAULT_LOCAL_CACHE_DIR: &str = ".fastembed_cache";
+
+/// The process-wide model, loaded by the first client
+static MODEL: Mutex<Option<SharedModel>> = Mutex::new(None);
+
+type SharedModel = Arc<Mutex<TextEmbedding>>;
+
+/// fastembed model for a configured model name
+fn fastembed_model(name: &str) -> Result<EmbeddingModel> {
+    match name {
+        "bge-small-en-v1.5" => Ok(EmbeddingModel::BGESmallENV15),
+        "all-minilm-l6-v2" => Ok(EmbeddingModel::AllMiniLML6V2),
+        other => Err(anyhow!(
+            "Unsupported local embedding model '{other}'; use bge-small-en-v1.5 or all-minilm-l6-v2"
+        )),
+    }
+}
+
+/// Load the configured model, or reuse the one already loaded
+fn shared_model(config: &EmbeddingConfig) -> Result<SharedModel> {
+    let mut loaded = MODEL
+        .lock()
+        .map_err(|_| anyhow!("local embedding model lock poisoned"))?;
+    if let Some(model) = loaded.as_ref() {
+        return Ok(model.clone());
+    }
+
+    let cache_dir = std::env::var("LOCAL_EMBEDDING_CACHE_DIR")
+        .ok()
+        .filter(|dir| !dir.trim().is_empty())
+        .map_or_else(|| PathBuf::from(DEFAULT_LOCAL_CACHE_DIR), PathBuf::from);
+    info!(
+        "Loading local embedding model {} from {}",
+        config.model,
+        cache_dir.display()
+    );
+    let options = TextInitOptions::new(fastembed_model(&config.model)?)
+        .with_cache_dir(cache_dir)
+        .with_show_download_progress(false);
+    let model = Arc::new(Mutex::new(TextEmbedding::try_new(options)?));
+    *loaded = Some(model.clone());
+    Ok(model)
+}
+
+/// Embedding client running a local ONNX model
+pub struct LocalEmbeddingClient {
+    model: SharedModel,
+    config: EmbeddingConfig,
+    batch_size: usize,
+}
+
+impl LocalEmbeddingClient {
+    /// Create a client for the configured local model, loading it on first use
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the model configuration is invalid or the model
+    /// cannot be loaded or downloaded.
+    pub fn new() -> Result<Self> {
+        Self::with_config(EmbeddingConfig::from_env()?)
+    }
+
+    /// Create a client for an explicit model configuration
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the model is not a supported local model or
+    /// cannot be loaded or downloaded.
+    pub fn with_config(config: EmbeddingConfig) -> Result<Self> {
+        config.validate()?;
+        let batch_size = std::env::var("LOCAL_EMBEDDING_BATCH_SIZE")
+            .ok()
+            .and_then(|v| v.trim().parse::<usize>().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or(DEFAULT_LOCAL_BATCH_SIZE);
+
+        Ok(Self {
+            model: shared_model(&config)?,
+            config,
+            batch_size,
+        })
+    }
+
+    /// Run the model over `texts` on the blocking thread pool
+    async fn infer(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        let model = self.model.clone();
+        let batch_size = self.batch_size;
+        tokio::task::spawn_blocking(move || {
+            let mut model = model
+                .lock()
+                .map_err(|_| anyhow!("local embedding model lock poisoned"))?;
+            model.embed(texts, Some(batch_size))
+        })
+        .await?
+    }
+}
+
+#[async_trait]
+impl EmbeddingClient for LocalEmbeddingClient {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.infer(vec![text.to_string()])
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("local embedding model returned no embedding"))
+    }
+
+    async fn generate_embedding(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse> {
+        Ok(EmbeddingResponse {
+            embedding: self.embed(&request.input).await?,
+        })
+    }
+
+    async fn upload_batch_file(
+        &self,
+        _content: &str,
+        _filename: &str,
+    ) -> Result<FileUploadResponse> {
+        Err(anyhow!("The local embedding provider has no batch API"))
+    }
+
+    async fn create_batch(&self, _input_file_id: &str) -> Result<BatchResponse> {
+        Err(anyhow!("The local embedding provider has no batch API"))
+    }
+
+    async fn get_batch(&self, _batch_id: &str) -> Result<BatchResponse> {
+        Err(anyhow!("The local embedding provider has no batch API"))
+    }
+
+    async fn download_batch_results(&self, _file_id: &str) -> Result<Vec<JsonlResponseLine>> {
+        Err(anyhow!("The local embedding provider has no batch API"))
+    }
+
+    async fn cancel_batch(&self, _batch_id: &str) -> Result<BatchResponse> {
+        Err(anyhow!("The local embedding provider has no batch API"))
+    }
+
+    fn embedding_config(&self) -> EmbeddingConfig {
+        self.config.clone()
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.infer(texts.to_vec()).await
+    }
+
+    fn prefers_batches(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cosine(a: &[f32], b: &[f32]) -> f32 {
+        let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+        let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        dot / (norm(a) * norm(b))
+    }
+
+    #[tokio::test]
+    #[ignore = "downloads the model and needs ONNX Runtime at ORT_DYLIB_PATH"]
+    async fn test_similar_sentences_are_closer() -> Result<()> {
+        let model = crate::config::DEFAULT_LOCAL_EMBEDDING_MODEL;
+        let client = LocalEmbeddingClient::with_config(EmbeddingConfig::new(model, 384)?)?;
+        let texts = [
+            "How do I spawn an async task with tokio?",
+            "Starting a background task on the tokio runtime",
+            "Serialize a struct to JSON with serde",
+            "The recipe needs two cups of flour and an egg",
+        ]
+        .map(String::from);
+
+        let embeddings = client.embed_batch(&texts).await?;
+        let dimensions = client.embedding_config().dimensions as usize;
+        assert!(embeddings.iter().all(|e| e.len() == dimensions));
+
+        // The two tokio sentences are closer to each other than to either unrelated one
+        let similar = cosine(&embeddings[0], &embeddings[1]);
+        for unrelated in &embeddings[2..] {
+            assert!(similar > cosine(&embeddings[0], unrelated));
+            assert!(similar > cosine(&embeddings[1], unrelated));
+        }
+
+        // Single and batched inference agree
+        let single = client.embed(&texts[0]).await?;
+        assert!(cosine(&single, &embeddings[0]) > 0.999);
+        Ok(())
+    }
+}
@@ -12,6 +12,9 @@ const BATCH_DISCOUNT_FACTOR: f64 = 0.5;
 pub struct EmbeddingRequest {
     pub input: String,
     pub model: String,
+    /// Requested output dimension (`text-embedding-3` models only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<u32>,
 }
 
 /// Embedding response (simplified for internal use)
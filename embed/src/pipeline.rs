@@ -0,0 +1,381 @@
+//! Bounded-concurrency embedding pipeline
+//!
+//! Ingestion paths hand a batch of contents to [`EmbeddingPipeline::embed_all`]
+//! instead of calling [`EmbeddingClient::embed`] one document at a time. At
+//! most `concurrency` requests are in flight, and rate-limit (429) and server
+//! (5xx) errors are retried with exponential backoff before an item is
+//! reported as failed, so a burst of 429s slows the batch down instead of
+//! dropping embeddings. Clients with batched inference (the local provider)
+//! get whole slices of texts per call instead.
+
+use crate::client::{EmbeddingClient, RetryPolicy};
+use anyhow::Result;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use tracing::warn;
+
+/// Concurrent requests used when `EMBEDDING_CONCURRENCY` is not set
+pub const DEFAULT_EMBEDDING_CONCURRENCY: usize = 4;
+
+/// Texts handed to one [`EmbeddingClient::embed_batch`] call
+const TEXTS_PER_BATCH_CALL: usize = 256;
+
+/// Metadata key recording why a document has no embedding
+pub const METADATA_EMBEDDING_ERROR_KEY: &str = "embedding_error";
+
+/// Pipeline settings
+#[derive(Debug, Clone)]
+pub struct PipelineConfig {
+    /// Maximum embedding requests in flight at once
+    pub concurrency: usize,
+    /// Backoff applied to retryable errors
+    pub retry_policy: RetryPolicy,
+}
+
+impl PipelineConfig {
+    /// Read the concurrency from `EMBEDDING_CONCURRENCY` (default 4)
+    #[must_use]
+    pub fn from_env() -> Self {
+        let concurrency = std::env::var("EMBEDDING_CONCURRENCY")
+            .ok()
+            .and_then(|v| v.trim().parse::<usize>().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or(DEFAULT_EMBEDDING_CONCURRENCY);
+        Self {
+            concurrency,
+            retry_policy: RetryPolicy::new(),
+        }
+    }
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_EMBEDDING_CONCURRENCY,
+            retry_policy: RetryPolicy::new(),
+        }
+    }
+}
+
+/// Counts of documents embedded, failed and skipped during one job
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddingStats {
+    /// Documents that received an embedding
+    pub embedded: usize,
+    /// Documents whose embedding failed after retries
+    pub failed: usize,
+    /// Documents that did not need an embedding
+    pub skipped: usize,
+}
+
+impl EmbeddingStats {
+    /// Count one pipeline result
+    pub fn record<T>(&mut self, result: &Result<T>) {
+        if result.is_ok() {
+            self.embedded += 1;
+        } else {
+            self.failed += 1;
+        }
+    }
+}
+
+impl fmt::Display for EmbeddingStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} embedded, {} failed, {} skipped",
+            self.embedded, self.failed, self.skipped
+        )
+    }
+}
+
+/// Embeds batches of texts with bounded concurrency and retries
+#[derive(Clone)]
+pub struct EmbeddingPipeline {
+    client: Arc<dyn EmbeddingClient + Send + Sync>,
+    config: PipelineConfig,
+}
+
+impl EmbeddingPipeline {
+    /// Create a pipeline configured from the environment
+    #[must_use]
+    pub fn new(client: Arc<dyn EmbeddingClient + Send + Sync>) -> Self {
+        Self::with_config(client, PipelineConfig::from_env())
+    }
+
+    /// Create a pipeline with explicit settings
+    #[must_use]
+    pub fn with_config(
+        client: Arc<dyn EmbeddingClient + Send + Sync>,
+        config: PipelineConfig,
+    ) -> Self {
+        Self { client, config }
+    }
+
+    /// Maximum embedding requests in flight at once
+    #[must_use]
+    pub fn concurrency(&self) -> usize {
+        self.config.concurrency.max(1)
+    }
+
+    /// Embed every text, returning results in the same order as `texts`
+    ///
+    /// A failed item never fails the batch; its error is returned in place
+    /// of the embedding.
+    pub async fn embed_all(&self, texts: &[&str]) -> Vec<Result<Vec<f32>>> {
+        // Owned texts keep the stream's futures `Send` for any caller lifetime
+        let texts: Vec<String> = texts.iter().map(ToString::to_string).collect();
+        if self.client.prefers_batches() {
+            return self.embed_in_batches(&texts).await;
+        }
+        stream::iter(texts)
+            .map(|text| async move { self.embed_with_retry(&text).await })
+            .buffered(self.concurrency())
+            .collect()
+            .await
+    }
+
+    /// Embed slices of texts with one call each
+    ///
+    /// A slice whose call fails is retried item by item, so one bad text
+    /// only fails itself.
+    async fn embed_in_batches(&self, texts: &[String]) -> Vec<Result<Vec<f32>>> {
+        let mut results = Vec::with_capacity(texts.len());
+        for slice in texts.chunks(TEXTS_PER_BATCH_CALL) {
+            match self.client.embed_batch(slice).await {
+                Ok(embeddings) if embeddings.len() == slice.len() => {
+                    results.extend(embeddings.into_iter().map(Ok));
+                }
+                outcome => {
+                    if let Err(e) = outcome {
+                        warn!(
+                            "Batched embedding failed, embedding items one by one: {}",
+                            e
+                        );
+                    }
+                    for text in slice {
+                        results.push(self.embed_with_retry(text).await);
+                    }
+                }
+            }
+        }
+        results
+    }
+
+    /// Embed one text, retrying rate-limit and server errors with backoff
+    async fn embed_with_retry(&self, text: &str) -> Result<Vec<f32>> {
+        let policy = &self.config.retry_policy;
+        let mut attempt = 0;
+        loop {
+            let error = match self.client.embed(text).await {
+                Ok(embedding) => return Ok(embedding),
+                Err(e) => e,
+            };
+            if attempt >= policy.max_retries || !RetryPolicy::is_retryable_error(&error) {
+                return Err(error);
+            }
+
+            attempt += 1;
+            let delay = policy.calculate_delay(attempt);
+            warn!(
+                "Embedding request failed (attempt {}/{}), retrying after {:?}: {}",
+                attempt,
+                policy.max_retries + 1,
+                delay,
+                error
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        BatchResponse, EmbeddingRequest, EmbeddingResponse, FileUploadResponse, JsonlResponseLine,
+    };
+    use anyhow::anyhow;
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// Fails the first attempt of every third item with a 429, and every
+    /// attempt of items containing "invalid" with a 400
+    ///
+    /// With `batched` set it prefers batches, and a batch containing an
+    /// invalid item fails as a whole.
+    #[derive(Default)]
+    struct FlakyClient {
+        attempts: Mutex<HashMap<String, usize>>,
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+        batched: bool,
+        batch_calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl EmbeddingClient for FlakyClient {
+        async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            let attempt = {
+                let mut attempts = self.attempts.lock().unwrap();
+                let count = attempts.entry(text.to_string()).or_default();
+                *count += 1;
+                *count
+            };
+            let index: usize = text.trim_start_matches("item-").parse().unwrap_or(0);
+            if text.contains("invalid") {
+                return Err(anyhow!("OpenAI API error (400 Bad Request): invalid input"));
+            }
+            if index % 3 == 2 && attempt == 1 {
+                return Err(anyhow!(
+                    "OpenAI API error (429 Too Many Requests): slow down"
+                ));
+            }
+            #[allow(clippy::cast_precision_loss)]
+            Ok(vec![index as f32; 3])
+        }
+
+        async fn generate_embedding(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse> {
+            Ok(EmbeddingResponse {
+                embedding: self.embed(&request.input).await?,
+            })
+        }
+
+        async fn upload_batch_file(&self, _: &str, _: &str) -> Result<FileUploadResponse> {
+            Err(anyhow!("not supported"))
+        }
+
+        async fn create_batch(&self, _: &str) -> Result<BatchResponse> {
+            Err(anyhow!("not supported"))
+        }
+
+        async fn get_batch(&self, _: &str) -> Result<BatchResponse> {
+            Err(anyhow!("not supported"))
+        }
+
+        async fn download_batch_results(&self, _: &str) -> Result<Vec<JsonlResponseLine>> {
+            Err(anyhow!("not supported"))
+        }
+
+        async fn cancel_batch(&self, _: &str) -> Result<BatchResponse> {
+            Err(anyhow!("not supported"))
+        }
+
+        async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            self.batch_calls.fetch_add(1, Ordering::SeqCst);
+            if texts.iter().any(|t| t.contains("invalid")) {
+                return Err(anyhow!("OpenAI API error (400 Bad Request): invalid input"));
+            }
+            #[allow(clippy::cast_precision_loss)]
+            Ok(texts.iter().map(|t| vec![t.len() as f32; 3]).collect())
+        }
+
+        fn prefers_batches(&self) -> bool {
+            self.batched
+        }
+    }
+
+    fn pipeline(client: Arc<FlakyClient>, concurrency: usize) -> EmbeddingPipeline {
+        let retry_policy = RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            max_retries: 3,
+            backoff_multiplier: 2.0,
+            jitter_factor: 0.0,
+        };
+        EmbeddingPipeline::with_config(
+            client,
+            PipelineConfig {
+                concurrency,
+                retry_policy,
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_items_are_retried_in_order() {
+        let client = Arc::new(FlakyClient::default());
+        let texts: Vec<String> = (0..12).map(|i| format!("item-{i}")).collect();
+        let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+
+        let results = pipeline(client.clone(), 4).embed_all(&texts).await;
+
+        let mut stats = EmbeddingStats::default();
+        for (i, result) in results.iter().enumerate() {
+            #[allow(clippy::cast_precision_loss)]
+            let expected = vec![i as f32; 3];
+            assert_eq!(result.as_ref().unwrap(), &expected);
+            stats.record(result);
+        }
+        assert_eq!(stats.embedded, 12);
+        assert_eq!(stats.failed, 0);
+
+        let attempts = client.attempts.lock().unwrap();
+        assert_eq!(attempts["item-2"], 2);
+        assert_eq!(attempts["item-3"], 1);
+        assert_eq!(attempts.values().sum::<usize>(), 16);
+        let max_in_flight = client.max_in_flight.load(Ordering::SeqCst);
+        assert!(max_in_flight <= 4, "{max_in_flight} requests in flight");
+        assert!(max_in_flight > 1);
+    }
+
+    #[tokio::test]
+    async fn test_non_retryable_errors_fail_the_item_only() {
+        let client = Arc::new(FlakyClient::default());
+        let results = pipeline(client.clone(), 2)
+            .embed_all(&["item-0", "invalid", "item-1"])
+            .await;
+
+        assert!(results[0].is_ok());
+        assert!(results[1]
+            .as_ref()
+            .unwrap_err()
+            .to_string()
+            .contains("400 Bad Request"));
+        assert!(results[2].is_ok());
+        assert_eq!(client.attempts.lock().unwrap()["invalid"], 1);
+
+        let mut stats = EmbeddingStats {
+            skipped: 4,
+            ..EmbeddingStats::default()
+        };
+        results.iter().for_each(|result| stats.record(result));
+        assert_eq!(stats.to_string(), "2 embedded, 1 failed, 4 skipped");
+    }
+
+    #[tokio::test]
+    async fn test_batching_clients_get_whole_slices() {
+        let client = Arc::new(FlakyClient {
+            batched: true,
+            ..FlakyClient::default()
+        });
+        let texts: Vec<String> = (0..300).map(|i| format!("text-{i}")).collect();
+        let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+
+        let results = pipeline(client.clone(), 4).embed_all(&texts).await;
+        assert_eq!(results.len(), 300);
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(results[0].as_ref().unwrap(), &vec![6.0; 3]);
+        // Two calls of up to 256 texts, and no single-text requests
+        assert_eq!(client.batch_calls.load(Ordering::SeqCst), 2);
+        assert!(client.attempts.lock().unwrap().is_empty());
+
+        // A failed slice falls back to per-item calls, so only the bad text fails
+        let results = pipeline(client.clone(), 4)
+            .embed_all(&["item-0", "invalid", "item-1"])
+            .await;
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert!(results[2].is_ok());
+    }
+}
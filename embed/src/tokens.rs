@@ -0,0 +1,63 @@
+//! Token counting for document content
+//!
+//! Uses the `cl100k_base` BPE vocabulary shared by the `OpenAI` embedding
+//! models. Builds without the `tiktoken` feature fall back to a
+//! characters-per-token heuristic.
+
+/// Count tokens in `text`.
+///
+/// The encoder is initialized once on first use; if it cannot be loaded the
+/// heuristic is used instead.
+#[must_use]
+pub fn token_count(text: &str) -> usize {
+    #[cfg(feature = "tiktoken")]
+    {
+        use std::sync::OnceLock;
+        use tiktoken_rs::CoreBPE;
+
+        static ENCODER: OnceLock<Option<CoreBPE>> = OnceLock::new();
+
+        let encoder = ENCODER.get_or_init(|| match tiktoken_rs::cl100k_base() {
+            Ok(bpe) => Some(bpe),
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to load cl100k_base tokenizer, using heuristic: {}",
+                    e
+                );
+                None
+            }
+        });
+
+        if let Some(bpe) = encoder {
+            return bpe.encode_ordinary(text).len();
+        }
+    }
+
+    heuristic_token_count(text)
+}
+
+/// Approximate token count assuming roughly four bytes per token.
+#[must_use]
+pub const fn heuristic_token_count(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_text_has_no_tokens() {
+        assert_eq!(token_count(""), 0);
+        assert_eq!(heuristic_token_count(""), 0);
+    }
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn test_counts_cl100k_tokens() {
+        assert_eq!(token_count("hello world"), 2);
+        // Code is denser than the 4-bytes-per-token heuristic assumes
+        let code = "fn main() { let x: Vec<u8> = vec![1, 2, 3]; println!(\"{x:?}\"); }";
+        assert!(token_count(code) > heuristic_token_count(code));
+    }
+}
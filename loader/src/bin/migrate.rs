@@ -4,7 +4,7 @@
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use db::models::DocType;
-use embed::{EmbeddingClient, OpenAIEmbeddingClient};
+use embed::{embedding_client_from_env, EmbeddingClient};
 use loader::migration::{MigrationConfig, MigrationPipeline, MigrationType, ValidationLevel};
 use sqlx::PgPool;
 use std::collections::HashMap;
@@ -296,7 +296,7 @@ async fn main() -> Result<()> {
 
     // Initialize embedding client
     let embedding_client: Arc<dyn EmbeddingClient + Send + Sync> =
-        Arc::new(OpenAIEmbeddingClient::new().context("Failed to create embedding client")?);
+        embedding_client_from_env().context("Failed to create embedding client")?;
 
     match args.command {
         MigrateCommand::Full {
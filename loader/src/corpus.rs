@@ -0,0 +1,358 @@
+//! Corpus dumps for backup and environment promotion
+//!
+//! A dump is newline-delimited JSON with one [`DumpRecord`] per document,
+//! gzip-compressed when the file name ends in `.gz`. Records carry what is
+//! needed to restore a document exactly: its ID, timestamps, metadata,
+//! token count and embedding. Export pages through the table and import
+//! upserts in batches, so neither holds more than one batch in memory.
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
+use db::models::Document;
+use db::queries::DocumentQueries;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use tracing::info;
+use uuid::Uuid;
+
+/// Documents per export page or import batch when not configured
+pub const DEFAULT_DUMP_BATCH_SIZE: usize = 500;
+
+/// How embeddings are written to a dump
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum EmbeddingEncoding {
+    /// Base64 of the little-endian `f32` values
+    #[default]
+    Base64,
+    /// A JSON list of numbers
+    List,
+}
+
+/// One document in a dump
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DumpRecord {
+    pub id: Uuid,
+    pub doc_type: String,
+    pub source_name: String,
+    pub doc_path: String,
+    pub content: String,
+    pub metadata: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_count: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Embedding as a list of numbers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
+    /// Embedding as base64 of little-endian `f32` values
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_base64: Option<String>,
+}
+
+impl DumpRecord {
+    /// Record for a stored document, embedding encoded as requested
+    #[must_use]
+    pub fn from_document(document: Document, encoding: EmbeddingEncoding) -> Self {
+        let embedding = document.embedding.as_ref().map(pgvector::Vector::to_vec);
+        let (embedding, embedding_base64) = match encoding {
+            EmbeddingEncoding::List => (embedding, None),
+            EmbeddingEncoding::Base64 => (None, embedding.as_deref().map(encode_embedding)),
+        };
+
+        Self {
+            id: document.id,
+            doc_type: document.doc_type,
+            source_name: document.source_name,
+            doc_path: document.doc_path,
+            content: document.content,
+            metadata: document.metadata,
+            token_count: document.token_count,
+            created_at: document.created_at,
+            updated_at: document.updated_at,
+            embedding,
+            embedding_base64,
+        }
+    }
+
+    /// The document to restore, without its embedding when `skip_embedding`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the base64 embedding is malformed.
+    pub fn into_document(self, skip_embedding: bool) -> Result<Document> {
+        let embedding = match (skip_embedding, self.embedding, self.embedding_base64) {
+            (true, _, _) | (false, None, None) => None,
+            (false, Some(values), _) => Some(values),
+            (false, None, Some(encoded)) => Some(decode_embedding(&encoded)?),
+        };
+
+        Ok(Document {
+            id: self.id,
+            doc_type: self.doc_type,
+            source_name: self.source_name,
+            doc_path: self.doc_path,
+            content: self.content,
+            metadata: self.metadata,
+            embedding: embedding.map(pgvector::Vector::from),
+            token_count: self.token_count,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        })
+    }
+}
+
+/// Base64 of the little-endian bytes of `values`
+#[must_use]
+pub fn encode_embedding(values: &[f32]) -> String {
+    let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+/// Inverse of [`encode_embedding`]
+///
+/// # Errors
+///
+/// Returns an error if `encoded` is not base64 or not a whole number of
+/// `f32` values.
+pub fn decode_embedding(encoded: &str) -> Result<Vec<f32>> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .context("embedding is not valid base64")?;
+    if bytes.len() % 4 != 0 {
+        bail!("embedding is {} bytes, not a multiple of 4", bytes.len());
+    }
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect())
+}
+
+/// Which documents to export and how
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    pub doc_type: Option<String>,
+    pub source_name: Option<String>,
+    pub batch_size: usize,
+    pub encoding: EmbeddingEncoding,
+}
+
+/// How to import a dump
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    pub batch_size: usize,
+    /// Leave embeddings out, keeping stored ones for unchanged content
+    pub skip_embeddings: bool,
+    /// Source renames applied on the way in, keyed by the exported name
+    pub remap_source: HashMap<String, String>,
+}
+
+/// Counts reported by [`import_corpus`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Records read from the dump
+    pub records: usize,
+    /// Rows inserted or updated
+    pub written: u64,
+}
+
+/// Dump file writer, compressing when the path ends in `.gz`
+enum DumpWriter {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl DumpWriter {
+    fn create(path: &Path) -> Result<Self> {
+        let file = BufWriter::new(
+            File::create(path).with_context(|| format!("cannot create {}", path.display()))?,
+        );
+        Ok(if is_gzip(path) {
+            Self::Gzip(GzEncoder::new(file, Compression::default()))
+        } else {
+            Self::Plain(file)
+        })
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match self {
+            Self::Plain(w) => w,
+            Self::Gzip(w) => w,
+        }
+    }
+
+    /// Flush everything, writing the gzip trailer
+    fn finish(self) -> Result<()> {
+        match self {
+            Self::Plain(mut w) => w.flush()?,
+            Self::Gzip(w) => w.finish()?.flush()?,
+        }
+        Ok(())
+    }
+}
+
+fn is_gzip(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
+/// Write the selected documents to `path`, returning how many were written
+///
+/// # Errors
+///
+/// Returns an error if a page cannot be read or the file cannot be written.
+pub async fn export_corpus(pool: &PgPool, path: &Path, options: &ExportOptions) -> Result<usize> {
+    let batch_size = i64::try_from(options.batch_size.max(1)).unwrap_or(i64::MAX);
+    let mut writer = DumpWriter::create(path)?;
+    let mut cursor = None;
+    let mut exported = 0;
+
+    loop {
+        let (documents, next_cursor) = DocumentQueries::export_page(
+            pool,
+            options.doc_type.as_deref(),
+            options.source_name.as_deref(),
+            cursor,
+            batch_size,
+        )
+        .await?;
+
+        for document in documents {
+            let record = DumpRecord::from_document(document, options.encoding);
+            serde_json::to_writer(writer.writer(), &record)?;
+            writer.writer().write_all(b"\n")?;
+            exported += 1;
+        }
+        info!("Exported {} documents", exported);
+
+        match next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+
+    writer.finish()?;
+    Ok(exported)
+}
+
+/// Upsert every record of the dump at `path`
+///
+/// # Errors
+///
+/// Returns an error naming the line if a record cannot be parsed, or if a
+/// batch cannot be written.
+pub async fn import_corpus(
+    pool: &PgPool,
+    path: &Path,
+    options: &ImportOptions,
+) -> Result<ImportSummary> {
+    let file = File::open(path).with_context(|| format!("cannot open {}", path.display()))?;
+    let reader: Box<dyn BufRead> = if is_gzip(path) {
+        Box::new(BufReader::new(MultiGzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+
+    let batch_size = options.batch_size.max(1);
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut summary = ImportSummary::default();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut record: DumpRecord = serde_json::from_str(&line)
+            .map_err(|e| anyhow!("{}:{}: invalid record: {e}", path.display(), index + 1))?;
+        if let Some(renamed) = options.remap_source.get(&record.source_name) {
+            record.source_name.clone_from(renamed);
+        }
+        batch.push(
+            record
+                .into_document(options.skip_embeddings)
+                .with_context(|| format!("{}:{}", path.display(), index + 1))?,
+        );
+        summary.records += 1;
+
+        if batch.len() >= batch_size {
+            summary.written += DocumentQueries::restore_documents(pool, &batch).await?;
+            batch.clear();
+            info!("Imported {} documents", summary.records);
+        }
+    }
+    summary.written += DocumentQueries::restore_documents(pool, &batch).await?;
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn document(embedding: Option<Vec<f32>>) -> Document {
+        Document {
+            id: Uuid::new_v4(),
+            doc_type: "rust".to_string(),
+            source_name: "tokio".to_string(),
+            doc_path: "tokio/runtime".to_string(),
+            content: "The tokio runtime, with \"quotes\" and ünïcode.\n".to_string(),
+            metadata: json!({"crate_name": "tokio"}),
+            embedding: embedding.map(pgvector::Vector::from),
+            token_count: Some(12),
+            created_at: Some(Utc::now()),
+            updated_at: Some(Utc::now()),
+        }
+    }
+
+    #[test]
+    fn test_embedding_base64_round_trip_is_exact() {
+        let values = vec![0.0, -1.5, f32::MIN_POSITIVE, 0.123_456_79, f32::MAX];
+        assert_eq!(
+            decode_embedding(&encode_embedding(&values)).unwrap(),
+            values
+        );
+        assert!(decode_embedding("AAA=").is_err());
+        assert!(decode_embedding("not base64!").is_err());
+    }
+
+    #[test]
+    fn test_record_round_trip_in_both_encodings() {
+        let values = vec![0.25, -0.333_333_34, 1e-8];
+        for encoding in [EmbeddingEncoding::Base64, EmbeddingEncoding::List] {
+            let original = document(Some(values.clone()));
+            let line =
+                serde_json::to_string(&DumpRecord::from_document(original.clone(), encoding))
+                    .unwrap();
+            let record: DumpRecord = serde_json::from_str(&line).unwrap();
+            assert_eq!(
+                record.embedding_base64.is_some(),
+                encoding == EmbeddingEncoding::Base64
+            );
+
+            let restored = record.clone().into_document(false).unwrap();
+            assert_eq!(restored.id, original.id);
+            assert_eq!(restored.content, original.content);
+            assert_eq!(restored.created_at, original.created_at);
+            assert_eq!(restored.embedding.unwrap().to_vec(), values);
+            assert!(record.into_document(true).unwrap().embedding.is_none());
+        }
+    }
+
+    #[test]
+    fn test_records_without_embedding_omit_the_fields() {
+        let record = DumpRecord::from_document(document(None), EmbeddingEncoding::Base64);
+        let line = serde_json::to_value(&record).unwrap();
+        assert!(line.get("embedding").is_none());
+        assert!(line.get("embedding_base64").is_none());
+    }
+}
@@ -0,0 +1,182 @@
+//! Documentation extracted from source code
+//!
+//! A raw source file makes a noisy document: the code drowns out the prose.
+//! The extractors pull each documented item out of a file on its own, Rust
+//! `///`/`//!` comments, Python docstrings and TypeScript/JavaScript JSDoc
+//! blocks, together with the signature of the item they describe. They work
+//! line by line instead of parsing the language, so unusual code may be
+//! missed, but any input is accepted.
+
+use std::path::Path;
+
+pub mod python;
+pub mod rust;
+pub mod typescript;
+
+/// Most lines of a multi-line declaration kept as its signature
+pub(crate) const MAX_SIGNATURE_LINES: usize = 12;
+
+/// Which documents source files produce
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum CodeMode {
+    /// The whole file as one document
+    #[default]
+    Full,
+    /// One document per documented item
+    DocsOnly,
+    /// The whole file and its documented items
+    Both,
+}
+
+impl CodeMode {
+    /// Whether the whole file is emitted as a document
+    #[must_use]
+    pub const fn includes_file(self) -> bool {
+        !matches!(self, Self::DocsOnly)
+    }
+
+    /// Whether documented items are emitted as documents
+    #[must_use]
+    pub const fn includes_items(self) -> bool {
+        !matches!(self, Self::Full)
+    }
+}
+
+/// Documented item of a source file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeItem {
+    /// Symbol kind, such as `function`, `struct`, `method` or `module`
+    pub kind: &'static str,
+    /// Symbol name, qualified by its enclosing type (`Type::method` in Rust,
+    /// `Class.method` otherwise)
+    pub name: String,
+    /// Declaration without its body; empty for module docs
+    pub signature: String,
+    /// Documentation text without comment markers
+    pub docs: String,
+    /// 1-based line of the declaration, or of the docs for module docs
+    pub line: usize,
+}
+
+impl CodeItem {
+    /// Document text: the signature as a code block followed by the docs
+    #[must_use]
+    pub fn content(&self, language: &str) -> String {
+        if self.signature.is_empty() {
+            return self.docs.clone();
+        }
+        format!("```{language}\n{}\n```\n\n{}", self.signature, self.docs)
+    }
+}
+
+/// Language of a source file the extractors understand
+#[must_use]
+pub fn language(path: &str) -> Option<&'static str> {
+    let extension = Path::new(path)
+        .extension()?
+        .to_string_lossy()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "rs" => Some("rust"),
+        "py" | "pyi" => Some("python"),
+        "ts" | "tsx" | "mts" | "cts" => Some("typescript"),
+        "js" | "jsx" | "mjs" | "cjs" => Some("javascript"),
+        _ => None,
+    }
+}
+
+/// Documented items of a source file, or `None` for other languages
+#[must_use]
+pub fn extract(content: &str, path: &str) -> Option<Vec<CodeItem>> {
+    let module = Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let items = match language(path)? {
+        "rust" => rust::extract(content, &module),
+        "python" => python::extract(content, &module),
+        _ => typescript::extract(content, &module),
+    };
+    Some(items)
+}
+
+/// Leading whitespace of a line, in characters
+pub(crate) fn indent(line: &str) -> usize {
+    line.chars().take_while(|c| c.is_whitespace()).count()
+}
+
+/// Identifier at the start of `text`, which may also contain `extra`
+pub(crate) fn identifier<'a>(text: &'a str, extra: &[char]) -> Option<&'a str> {
+    let end = text
+        .char_indices()
+        .find(|&(_, c)| !(c.is_alphanumeric() || c == '_' || extra.contains(&c)))
+        .map_or(text.len(), |(i, _)| i);
+    let name = &text[..end];
+    (!name.is_empty() && !name.starts_with(|c: char| c.is_ascii_digit())).then_some(name)
+}
+
+/// Lines of a declaration from `lines[start]` on, up to the first line
+/// `ends` accepts, with the first line's indentation removed
+///
+/// Returns the signature and the number of lines it spans.
+pub(crate) fn declaration(
+    lines: &[&str],
+    start: usize,
+    mut ends: impl FnMut(&str) -> bool,
+) -> (String, usize) {
+    let prefix: String = lines[start]
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .collect();
+    let mut signature: Vec<&str> = Vec::new();
+    for line in lines.iter().skip(start).take(MAX_SIGNATURE_LINES) {
+        let line = line.trim_end();
+        signature.push(
+            line.strip_prefix(prefix.as_str())
+                .unwrap_or(line.trim_start()),
+        );
+        if ends(line) {
+            break;
+        }
+    }
+    let count = signature.len();
+    (signature.join("\n"), count)
+}
+
+/// Remove the body opener and separators trailing a declaration
+pub(crate) fn trim_body(signature: &str) -> String {
+    let signature = match signature.find('{') {
+        Some(pos) if pos > 0 => &signature[..pos],
+        _ => signature,
+    };
+    signature
+        .trim_end()
+        .trim_end_matches([';', ','])
+        .trim_end()
+        .to_string()
+}
+
+/// Comment text lines without their common indentation
+pub(crate) fn dedent(lines: &[String]) -> Vec<String> {
+    let common = lines
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| indent(l))
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|l| {
+            l.chars()
+                .skip(common)
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+/// Comment text lines without common indentation and surrounding blank lines
+pub(crate) fn clean_docs(lines: &[String]) -> String {
+    dedent(lines).join("\n").trim_matches('\n').to_string()
+}
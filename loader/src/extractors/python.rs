@@ -0,0 +1,141 @@
+//! Docstrings in Python source
+//!
+//! The module docstring is the file's first statement; class and function
+//! docstrings are the first statement of their body. Methods are named
+//! `Class.method` after the classes enclosing them by indentation.
+
+use super::{declaration, dedent, identifier, indent, CodeItem};
+
+/// Documented items of a Python file
+#[must_use]
+pub fn extract(content: &str, module: &str) -> Vec<CodeItem> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut items = Vec::new();
+
+    // The module docstring precedes any code, after comments and blank lines
+    if let Some(first) = lines
+        .iter()
+        .position(|l| !l.trim().is_empty() && !l.trim().starts_with('#'))
+    {
+        if let Some((docs, _)) = docstring(&lines, first) {
+            items.push(CodeItem {
+                kind: "module",
+                name: module.to_string(),
+                signature: String::new(),
+                docs,
+                line: first + 1,
+            });
+        }
+    }
+
+    // (indentation, name) of the classes enclosing the current line
+    let mut classes: Vec<(usize, String)> = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('@') {
+            i += 1;
+            continue;
+        }
+        let depth = indent(line);
+        classes.retain(|(class_depth, _)| *class_depth < depth);
+
+        let declared = trimmed.strip_prefix("async ").unwrap_or(trimmed);
+        let (keyword, rest) = if let Some(rest) = declared.strip_prefix("class ") {
+            ("class", rest)
+        } else if let Some(rest) = declared.strip_prefix("def ") {
+            ("def", rest)
+        } else {
+            i += 1;
+            continue;
+        };
+        let Some(name) = identifier(rest.trim_start(), &[]) else {
+            i += 1;
+            continue;
+        };
+
+        // The declaration ends once its brackets are balanced
+        let (mut opened, mut closed) = (0, 0);
+        let (signature, count) = declaration(&lines, i, |l| {
+            opened += l.matches(['(', '[']).count();
+            closed += l.matches([')', ']']).count();
+            closed >= opened
+        });
+        let signature = signature.trim_end().trim_end_matches(':').to_string();
+
+        let kind = match keyword {
+            "class" => "class",
+            _ if classes.is_empty() => "function",
+            _ => "method",
+        };
+        let qualified = classes
+            .iter()
+            .map(|(_, class)| class.as_str())
+            .chain([name])
+            .collect::<Vec<_>>()
+            .join(".");
+
+        if keyword == "class" {
+            classes.push((depth, name.to_string()));
+        }
+        let line_number = i + 1;
+        i += count;
+
+        let body = (i..lines.len()).find(|&j| !lines[j].trim().is_empty());
+        if let Some((docs, end)) = body.and_then(|j| docstring(&lines, j)) {
+            items.push(CodeItem {
+                kind,
+                name: qualified,
+                signature,
+                docs,
+                line: line_number,
+            });
+            // Docstring lines may be indented less than the body
+            i = end + 1;
+        }
+    }
+    items
+}
+
+/// Docstring starting at `lines[start]` and the index of its last line
+///
+/// An unterminated triple-quoted string runs to the end of the file.
+fn docstring(lines: &[&str], start: usize) -> Option<(String, usize)> {
+    let trimmed = lines[start].trim_start();
+    let prefix = trimmed
+        .chars()
+        .take_while(|c| matches!(c, 'r' | 'R' | 'u' | 'U'))
+        .count();
+    if prefix > 1 {
+        return None;
+    }
+    let literal = &trimmed[prefix..];
+    let quote = ["\"\"\"", "'''", "\"", "'"]
+        .into_iter()
+        .find(|q| literal.starts_with(q))?;
+    let first = &literal[quote.len()..];
+
+    if quote.len() == 1 {
+        let (text, _) = first.split_once(quote)?;
+        return Some((text.trim().to_string(), start));
+    }
+    if let Some((text, _)) = first.split_once(quote) {
+        return Some((text.trim().to_string(), start));
+    }
+
+    let mut text = vec![first.trim().to_string()];
+    let mut end = lines.len() - 1;
+    for (j, line) in lines.iter().enumerate().skip(start + 1) {
+        if let Some((last, _)) = line.split_once(quote) {
+            text.push(last.to_string());
+            end = j;
+            break;
+        }
+        text.push((*line).to_string());
+    }
+    // The first line is not indented like the rest
+    let mut docs = vec![text.remove(0)];
+    docs.extend(dedent(&text));
+    Some((docs.join("\n").trim_matches('\n').to_string(), end))
+}
@@ -0,0 +1,223 @@
+//! `///` and `//!` doc comments in Rust source
+//!
+//! Each `///` block is paired with the item that follows it, skipping
+//! attributes. Items indented under a top-level `impl`, `struct`, `enum`
+//! or `trait` are named `Type::item`. `//!` comments anywhere in the file
+//! make up the module docs.
+
+use super::{clean_docs, declaration, identifier, indent, trim_body, CodeItem};
+
+/// Words that may precede an item keyword
+const QUALIFIERS: &[&str] = &["async", "unsafe", "default", "extern"];
+
+/// Documented items of a Rust file
+#[must_use]
+pub fn extract(content: &str, module: &str) -> Vec<CodeItem> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut items = Vec::new();
+    let mut module_docs: Vec<String> = Vec::new();
+    let mut module_line = 0;
+    let mut docs: Vec<String> = Vec::new();
+    // Type whose members are indented below the current top-level item
+    let mut container: Option<String> = None;
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+
+        if let Some(doc) = trimmed.strip_prefix("//!") {
+            if module_docs.is_empty() {
+                module_line = i + 1;
+            }
+            module_docs.push(doc.strip_prefix(' ').unwrap_or(doc).to_string());
+            i += 1;
+            continue;
+        }
+        if let Some(doc) = trimmed.strip_prefix("///").filter(|d| !d.starts_with('/')) {
+            docs.push(doc.strip_prefix(' ').unwrap_or(doc).to_string());
+            i += 1;
+            continue;
+        }
+        if trimmed.is_empty() || trimmed.starts_with("//") {
+            i += 1;
+            continue;
+        }
+        if trimmed.starts_with("#[") || trimmed.starts_with("#![") {
+            // Attributes may span lines; skip to the closing bracket
+            let (_, count) = declaration(&lines, i, |l| l.trim_end().ends_with(']'));
+            i += count;
+            continue;
+        }
+        if indent(line) == 0 && trimmed.starts_with('}') {
+            container = None;
+            docs.clear();
+            i += 1;
+            continue;
+        }
+        // Undocumented nested lines only matter for their doc comments
+        if docs.is_empty() && indent(line) > 0 {
+            i += 1;
+            continue;
+        }
+
+        // The declaration ends at its body, or at `;`/`,` outside parentheses
+        let (mut opened, mut closed) = (0, 0);
+        let (signature, count) = declaration(&lines, i, |l| {
+            opened += l.matches('(').count();
+            closed += l.matches(')').count();
+            l.contains('{') || (closed >= opened && (l.ends_with(';') || l.ends_with(',')))
+        });
+        let signature = trim_body(&signature);
+        let member = indent(line) > 0 && container.is_some();
+        let item = parse(&signature, member);
+
+        if !docs.is_empty() {
+            if let Some((kind, name)) = &item {
+                let name = match (&container, member) {
+                    (Some(parent), true) => format!("{parent}::{name}"),
+                    _ => name.clone(),
+                };
+                items.push(CodeItem {
+                    kind,
+                    name,
+                    signature: signature.clone(),
+                    docs: clean_docs(&docs),
+                    line: i + 1,
+                });
+            }
+        }
+        docs.clear();
+
+        if indent(line) == 0 {
+            container = item.and_then(|(kind, name)| {
+                let opens_block = lines[i..i + count].iter().any(|l| l.contains('{'))
+                    && !lines[i + count - 1].trim_end().ends_with('}');
+                match kind {
+                    "impl" => Some(impl_target(&name)),
+                    "struct" | "enum" | "trait" | "union" if opens_block => Some(name),
+                    _ => None,
+                }
+            });
+        }
+        i += count;
+    }
+
+    if !module_docs.is_empty() {
+        items.insert(
+            0,
+            CodeItem {
+                kind: "module",
+                name: module.to_string(),
+                signature: String::new(),
+                docs: clean_docs(&module_docs),
+                line: module_line,
+            },
+        );
+    }
+    items
+}
+
+/// Symbol kind and name of a declaration
+///
+/// Members of a type without an item keyword are fields (`name: Type`) or
+/// enum variants.
+fn parse(signature: &str, member: bool) -> Option<(&'static str, String)> {
+    let mut rest = signature.trim_start();
+    loop {
+        if let Some(after) = rest.strip_prefix("pub") {
+            if let Some(scope) = after.strip_prefix('(') {
+                rest = scope.split_once(')').map_or("", |(_, r)| r).trim_start();
+                continue;
+            }
+            if after.starts_with(char::is_whitespace) {
+                rest = after.trim_start();
+                continue;
+            }
+        }
+        let word = rest.split_whitespace().next().unwrap_or_default();
+        if QUALIFIERS.contains(&word) {
+            rest = rest[word.len()..].trim_start();
+            // extern "C"
+            if rest.starts_with('"') {
+                rest = rest[1..]
+                    .split_once('"')
+                    .map_or("", |(_, r)| r)
+                    .trim_start();
+            }
+            continue;
+        }
+        if word == "const" {
+            let next = rest[word.len()..].trim_start();
+            if ["fn", "unsafe", "async", "extern"]
+                .iter()
+                .any(|q| next.split_whitespace().next() == Some(q))
+            {
+                rest = next;
+                continue;
+            }
+        }
+        break;
+    }
+
+    let word = rest.split_whitespace().next().unwrap_or_default();
+    let after = rest[word.len()..].trim_start();
+    let kind = match word {
+        "fn" if member => "method",
+        "fn" => "function",
+        "struct" => "struct",
+        "enum" => "enum",
+        "trait" => "trait",
+        "union" => "union",
+        "type" => "type",
+        "const" => "constant",
+        "static" => "static",
+        "mod" => "module",
+        "macro_rules!" => "macro",
+        "impl" => {
+            return Some(("impl", skip_generics(after).trim().to_string()));
+        }
+        _ if member => {
+            let name = identifier(rest, &[])?;
+            let kind = if rest[name.len()..].trim_start().starts_with(':') {
+                "field"
+            } else {
+                "variant"
+            };
+            return Some((kind, name.to_string()));
+        }
+        _ => return None,
+    };
+    let after = after.strip_prefix("mut ").unwrap_or(after);
+    let name = identifier(after, &[])?;
+    Some((kind, name.to_string()))
+}
+
+/// `text` without a leading `<...>` generic parameter list
+fn skip_generics(text: &str) -> &str {
+    if !text.starts_with('<') {
+        return text;
+    }
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return &text[i + 1..];
+                }
+            }
+            _ => {}
+        }
+    }
+    ""
+}
+
+/// Type an `impl` block is for: `Foo` for `Display for Foo<T>`
+fn impl_target(header: &str) -> String {
+    let target = header.rsplit_once(" for ").map_or(header, |(_, t)| t);
+    let target = target.split_whitespace().next().unwrap_or_default();
+    let path = target.split('<').next().unwrap_or_default();
+    path.rsplit("::").next().unwrap_or_default().to_string()
+}
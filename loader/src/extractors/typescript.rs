@@ -0,0 +1,181 @@
+//! JSDoc blocks in TypeScript and JavaScript source
+//!
+//! Each `/** ... */` block is paired with the declaration that follows it,
+//! skipping decorators. Members indented under a top-level class or
+//! interface are named `Class.member`. A block followed by no declaration
+//! before any other code, such as a `@fileoverview` header, is the module
+//! docs.
+
+use super::{clean_docs, declaration, identifier, indent, trim_body, CodeItem};
+
+/// Words that may precede a declaration keyword or member name
+const MODIFIERS: &[&str] = &[
+    "export",
+    "default",
+    "declare",
+    "abstract",
+    "async",
+    "public",
+    "private",
+    "protected",
+    "static",
+    "readonly",
+    "override",
+    "get",
+    "set",
+];
+
+/// Documented items of a TypeScript or JavaScript file
+#[must_use]
+pub fn extract(content: &str, module: &str) -> Vec<CodeItem> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut items = Vec::new();
+    // Class or interface whose members are indented below it
+    let mut container: Option<String> = None;
+    let mut seen_code = false;
+
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+
+        if trimmed.starts_with("/**") && !trimmed.starts_with("/**/") {
+            let (docs, end) = jsdoc(&lines, i);
+            let doc_line = i + 1;
+            i = end + 1;
+            while i < lines.len() && {
+                let next = lines[i].trim();
+                next.is_empty() || next.starts_with('@')
+            } {
+                i += 1;
+            }
+
+            let declared = lines.get(i).and_then(|&line| {
+                let (signature, _) = declaration(&lines, i, |l| {
+                    l.contains('{') || l.ends_with(';') || l.ends_with(',')
+                });
+                let signature = trim_body(&signature);
+                let member = indent(line) > 0 && container.is_some();
+                parse(&signature, member).map(|item| (item, signature, member))
+            });
+            match declared {
+                Some(((kind, name), signature, member)) => {
+                    let name = match (&container, member) {
+                        (Some(parent), true) => format!("{parent}.{name}"),
+                        _ => name,
+                    };
+                    items.push(CodeItem {
+                        kind,
+                        name,
+                        signature,
+                        docs,
+                        line: i + 1,
+                    });
+                }
+                None if !seen_code && !docs.is_empty() => items.push(CodeItem {
+                    kind: "module",
+                    name: module.to_string(),
+                    signature: String::new(),
+                    docs,
+                    line: doc_line,
+                }),
+                None => {}
+            }
+            // The declaration line itself is handled below
+            continue;
+        }
+
+        if !trimmed.is_empty() && !trimmed.starts_with("//") {
+            seen_code = true;
+        }
+        if indent(line) == 0 && !trimmed.is_empty() {
+            if trimmed.starts_with('}') {
+                container = None;
+            } else if let Some(("class" | "interface", name)) = parse(trimmed, false) {
+                container = (!trimmed.ends_with('}')).then_some(name);
+            }
+        }
+        i += 1;
+    }
+    items
+}
+
+/// Text of the JSDoc block starting at `lines[start]` and the index of its
+/// last line
+///
+/// An unterminated block runs to the end of the file.
+fn jsdoc(lines: &[&str], start: usize) -> (String, usize) {
+    let mut text: Vec<String> = Vec::new();
+    let mut end = lines.len() - 1;
+    for (j, line) in lines.iter().enumerate().skip(start) {
+        let mut line = line.trim();
+        if j == start {
+            line = &line[3..];
+        }
+        let closed = line.find("*/");
+        if let Some(pos) = closed {
+            line = &line[..pos];
+        }
+        let line = line.strip_prefix('*').unwrap_or(line);
+        let line = line.strip_prefix(' ').unwrap_or(line);
+        if !(j == start && line.trim().is_empty()) {
+            text.push(line.to_string());
+        }
+        if closed.is_some() {
+            end = j;
+            break;
+        }
+    }
+    (clean_docs(&text), end)
+}
+
+/// Symbol kind and name of a declaration
+fn parse(signature: &str, member: bool) -> Option<(&'static str, String)> {
+    let mut rest = signature.trim_start();
+    loop {
+        let word = rest.split_whitespace().next().unwrap_or_default();
+        let after = rest[word.len()..].trim_start();
+        // `get(` or `static = 1` are member names, not modifiers
+        if MODIFIERS.contains(&word) && identifier(after, &['$', '*', '#']).is_some() {
+            rest = after;
+        } else {
+            break;
+        }
+    }
+
+    let word = rest.split_whitespace().next().unwrap_or_default();
+    let after = rest[word.len()..].trim_start();
+    let kind = match word {
+        "function" | "function*" => "function",
+        "class" => "class",
+        "interface" => "interface",
+        "type" => "type",
+        "enum" => "enum",
+        "namespace" | "module" => "namespace",
+        "const" | "let" | "var" => {
+            let name = identifier(after, &['$'])?;
+            let value = after[name.len()..].split_once('=').map_or("", |(_, v)| v);
+            let kind = if value.contains("=>") || value.trim_start().starts_with("function") {
+                "function"
+            } else if word == "const" {
+                "constant"
+            } else {
+                "variable"
+            };
+            return Some((kind, name.to_string()));
+        }
+        _ if member => {
+            let name = identifier(rest.trim_start_matches(['*', '#']), &['$'])?;
+            let after = rest.trim_start_matches(['*', '#'])[name.len()..].trim_start();
+            let kind = if after.starts_with('(') || after.starts_with('<') {
+                "method"
+            } else {
+                "property"
+            };
+            return Some((kind, name.to_string()));
+        }
+        _ => return None,
+    };
+    let name = identifier(after.trim_start_matches('*').trim_start(), &['$'])?;
+    Some((kind, name.to_string()))
+}
@@ -1,11 +1,16 @@
 //! Document loading and parsing
 //!
 //! This crate provides document loading functionality for various documentation
-//! types including Rust crates, Jupiter documentation, and API documentation.
+//! types including Rust crates, Jupyter notebooks, and API documentation.
 
+pub mod corpus;
+pub mod extractors;
 pub mod loaders;
 pub mod migration;
 pub mod parsers;
+pub mod repo;
+pub mod scan;
+pub mod web;
 
 pub use loaders::*;
 pub use migration::*;
@@ -11,4 +11,7 @@ pub struct DocPage {
     pub item_type: String, // "markdown", "html", "code", etc.
     pub module_path: String,
     pub extracted_at: DateTime<Utc>,
+    /// Parser metadata, stored as the document metadata when loaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
 }
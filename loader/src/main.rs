@@ -4,9 +4,12 @@
 //! Supported flows:
 //! - Analyzer-driven ingest runs in the server; loader provides the execution primitives used by plans
 //! - "local" (directly parse files from a local path and emit JSON documents)
+//! - "rustdoc" (parse a crate's local `cargo doc` output and emit JSON documents)
 //! - "database" (load previously emitted JSON docs into the DB)
+//! - "export"/"import" (dump the stored corpus to NDJSON and restore it)
 
 use clap::{Parser, Subcommand};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use tracing::{info, warn, Level};
 use tracing_subscriber::fmt;
@@ -17,38 +20,48 @@ use loader::parsers::{DocumentFormat, UniversalParser};
 use db::models::Document;
 use db::queries::DocumentQueries;
 use db::DatabasePool;
-use uuid::Uuid;
-
-/// Helper function to scan a directory for files with specific extensions
-fn scan_dir(
-    dir: &std::path::Path,
-    extensions: &[&str],
-    recursive: bool,
-    files: &mut Vec<std::path::PathBuf>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    for entry in std::fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
-
-        if path.is_dir() {
-            if recursive && !path.ends_with(".git") {
-                // Skip directory symlinks to avoid recursion loops
-                if let Ok(meta) = std::fs::symlink_metadata(&path) {
-                    if meta.file_type().is_symlink() {
-                        continue;
-                    }
-                }
-                scan_dir(&path, extensions, recursive, files)?;
-            }
-        } else if let Some(ext) = path.extension() {
-            if let Some(ext_str) = ext.to_str() {
-                if extensions.contains(&ext_str) {
-                    files.push(path);
-                }
-            }
+
+/// Which scanned files `cli` parses
+#[derive(clap::Args)]
+struct ScanFilters {
+    /// Glob patterns of paths to skip (comma-separated); patterns without
+    /// `/` match any file or directory name
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_value = "node_modules,target,vendor"
+    )]
+    exclude: Vec<String>,
+
+    /// Skip files larger than this many KB
+    #[arg(long)]
+    max_file_size: Option<u64>,
+
+    /// Skip text files with fewer non-whitespace characters than this
+    #[arg(long, default_value = "0")]
+    min_content_chars: usize,
+
+    /// Rank files by documentation value (READMEs and docs/ first, shallow
+    /// paths, commented source) before applying --max-files
+    #[arg(long)]
+    prioritize: bool,
+
+    /// Parse at most this many files
+    #[arg(long)]
+    max_files: Option<usize>,
+}
+
+impl ScanFilters {
+    fn scan_options(&self, extensions: &str, recursive: bool) -> loader::scan::ScanOptions {
+        loader::scan::ScanOptions {
+            exclude: self.exclude.clone(),
+            max_file_size: self.max_file_size.map(|kb| kb.saturating_mul(1024)),
+            min_content_chars: self.min_content_chars,
+            prioritize: self.prioritize,
+            max_files: self.max_files,
+            ..loader::scan::ScanOptions::new(extensions, recursive)
         }
     }
-    Ok(())
 }
 
 /// AI-enabled Document Ingestion CLI
@@ -89,13 +102,109 @@ enum Commands {
         path: PathBuf,
 
         /// File extensions to include (comma-separated)
-        #[arg(long, default_value = "md,rs,py,js,ts,json,yaml,yml,toml,txt")]
+        #[arg(
+            long,
+            default_value = "md,rs,py,js,ts,json,yaml,yml,toml,txt,ipynb,pdf"
+        )]
         extensions: String,
 
         /// Recursive directory traversal
         #[arg(long)]
         recursive: bool,
 
+        #[command(flatten)]
+        filters: ScanFilters,
+
+        /// Documents produced from source files: the whole file, one per
+        /// documented item, or both
+        #[arg(long, value_enum, default_value = "full")]
+        code_mode: loader::extractors::CodeMode,
+
+        /// Output directory for processed documents
+        #[arg(short, long, default_value = "./output")]
+        output: PathBuf,
+    },
+
+    /// Shallow-clone a git repository and parse it like `cli`
+    Repo {
+        /// Repository URL (HTTPS, SSH or file://)
+        #[arg(long)]
+        repo_url: String,
+
+        /// Branch or tag to check out (defaults to the remote HEAD)
+        #[arg(long)]
+        branch: Option<String>,
+
+        /// Subdirectory of the repository to scan
+        #[arg(long)]
+        subdir: Option<PathBuf>,
+
+        /// File extensions to include (comma-separated)
+        #[arg(
+            long,
+            default_value = "md,rs,py,js,ts,json,yaml,yml,toml,txt,ipynb,pdf"
+        )]
+        extensions: String,
+
+        /// Abort if the scan finds more files than this
+        #[arg(long, default_value = "5000")]
+        max_files: usize,
+
+        /// Documents produced from source files: the whole file, one per
+        /// documented item, or both
+        #[arg(long, value_enum, default_value = "full")]
+        code_mode: loader::extractors::CodeMode,
+
+        /// Output directory for processed documents
+        #[arg(short, long, default_value = "./output")]
+        output: PathBuf,
+    },
+
+    /// Crawl a documentation website into documents
+    Web {
+        /// Page to start from; pages must share its origin
+        base_url: String,
+
+        /// Sitemap to read the page list from (path relative to the base URL
+        /// or absolute URL); without one links are followed
+        #[arg(long)]
+        sitemap: Option<String>,
+
+        /// Path prefixes pages must start with (comma-separated; defaults to
+        /// the base URL's directory)
+        #[arg(long, value_delimiter = ',')]
+        allow_prefix: Vec<String>,
+
+        /// Stop after this many pages
+        #[arg(long, default_value = "100")]
+        max_pages: usize,
+
+        /// Link depth followed from the base URL when there is no sitemap
+        #[arg(long, default_value = "3")]
+        max_depth: usize,
+
+        /// Minimum milliseconds between requests
+        #[arg(long, default_value = "1000")]
+        interval_ms: u64,
+
+        /// Output directory for processed documents
+        #[arg(short, long, default_value = "./output")]
+        output: PathBuf,
+    },
+
+    /// Parse a crate's local rustdoc output (`cargo doc`) into `rust` documents
+    Rustdoc {
+        /// Crate directory holding Cargo.toml (name and version come from it)
+        path: PathBuf,
+
+        /// Rustdoc output directory (defaults to target/doc of the crate or its workspace)
+        #[arg(long)]
+        doc_dir: Option<PathBuf>,
+
+        /// Run `cargo doc --no-deps` in the crate directory first
+        #[arg(long)]
+        build: bool,
+
         /// Output directory for processed documents
         #[arg(short, long, default_value = "./output")]
         output: PathBuf,
@@ -122,10 +231,70 @@ enum Commands {
         /// Skip confirmation prompt
         #[arg(long)]
         yes: bool,
+
+        /// Skip documents whose content hash matches the stored row
+        #[arg(long)]
+        incremental: bool,
+
+        /// Store documents with identical content once, listing the others as alternate URLs
+        #[arg(long)]
+        dedupe: bool,
+    },
+
+    /// Export documents as newline-delimited JSON (gzip when the file ends in .gz)
+    Export {
+        /// Dump file to write
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Only documents of this type
+        #[arg(long)]
+        doc_type: Option<String>,
+
+        /// Only documents of this source
+        #[arg(long)]
+        source_name: Option<String>,
+
+        /// Documents read per page
+        #[arg(long, default_value_t = loader::corpus::DEFAULT_DUMP_BATCH_SIZE)]
+        batch_size: usize,
+
+        /// How embeddings are written
+        #[arg(long, value_enum, default_value = "base64")]
+        embedding_format: loader::corpus::EmbeddingEncoding,
+    },
+
+    /// Upsert documents from an export, keeping their IDs and timestamps
+    Import {
+        /// Dump file to read
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Documents written per batch
+        #[arg(long, default_value_t = loader::corpus::DEFAULT_DUMP_BATCH_SIZE)]
+        batch_size: usize,
+
+        /// Leave embeddings out (re-embed or backfill afterwards)
+        #[arg(long)]
+        skip_embeddings: bool,
+
+        /// Rename a source on the way in (format: old=new, repeatable)
+        #[arg(long, value_parser = parse_source_remap)]
+        remap_source: Vec<(String, String)>,
     },
     // Intelligent ingest moved to server via discovery crate
 }
 
+/// Parse an `old=new` source rename
+fn parse_source_remap(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((old, new)) if !old.trim().is_empty() && !new.trim().is_empty() => {
+            Ok((old.trim().to_string(), new.trim().to_string()))
+        }
+        _ => Err(format!("expected old=new, got '{s}'")),
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
@@ -149,9 +318,58 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             path,
             extensions,
             recursive,
+            filters,
+            code_mode,
             output,
         } => {
-            handle_cli_command(path.as_path(), &extensions, recursive, output.as_path()).await?;
+            let options = filters.scan_options(&extensions, recursive);
+            handle_cli_command(path.as_path(), &options, code_mode, output.as_path()).await?;
+        }
+        Commands::Repo {
+            repo_url,
+            branch,
+            subdir,
+            extensions,
+            max_files,
+            code_mode,
+            output,
+        } => {
+            handle_repo_command(
+                &repo_url,
+                branch.as_deref(),
+                subdir.as_deref(),
+                &extensions,
+                max_files,
+                code_mode,
+                output.as_path(),
+            )
+            .await?;
+        }
+        Commands::Web {
+            base_url,
+            sitemap,
+            allow_prefix,
+            max_pages,
+            max_depth,
+            interval_ms,
+            output,
+        } => {
+            let options = loader::web::WebOptions {
+                sitemap,
+                allow_prefixes: allow_prefix,
+                max_pages,
+                max_depth,
+                ..loader::web::WebOptions::new(url::Url::parse(&base_url)?)
+            };
+            handle_web_command(&options, interval_ms, output.as_path()).await?;
+        }
+        Commands::Rustdoc {
+            path,
+            doc_dir,
+            build,
+            output,
+        } => {
+            handle_rustdoc_command(&path, doc_dir.as_deref(), build, output.as_path()).await?;
         }
         Commands::Database {
             input_dir,
@@ -159,15 +377,57 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             source_name,
             batch_size,
             yes,
+            incremental,
+            dedupe,
         } => {
             handle_database_command(
                 input_dir.as_path(),
                 &doc_type,
                 &source_name,
                 batch_size,
+                &embed::ChunkConfig::new(cli.chunk_size, cli.chunk_overlap),
                 yes,
+                incremental,
+                dedupe,
             )
             .await?;
+        }
+        Commands::Export {
+            output,
+            doc_type,
+            source_name,
+            batch_size,
+            embedding_format,
+        } => {
+            let pool = DatabasePool::from_env().await?;
+            let options = loader::corpus::ExportOptions {
+                doc_type,
+                source_name,
+                batch_size,
+                encoding: embedding_format,
+            };
+            let exported = loader::corpus::export_corpus(pool.pool(), &output, &options).await?;
+            info!("📦 Exported {} documents to {}", exported, output.display());
+        }
+        Commands::Import {
+            input,
+            batch_size,
+            skip_embeddings,
+            remap_source,
+        } => {
+            let pool = DatabasePool::from_env().await?;
+            let options = loader::corpus::ImportOptions {
+                batch_size,
+                skip_embeddings,
+                remap_source: remap_source.into_iter().collect(),
+            };
+            let summary = loader::corpus::import_corpus(pool.pool(), &input, &options).await?;
+            info!(
+                "📥 Imported {} records from {} ({} rows written)",
+                summary.records,
+                input.display(),
+                summary.written
+            );
         } // Intelligent ingest now handled by server (discovery)
     }
 
@@ -179,41 +439,144 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
 async fn handle_cli_command(
     path: &std::path::Path,
-    extensions: &str,
-    recursive: bool,
+    options: &loader::scan::ScanOptions,
+    code_mode: loader::extractors::CodeMode,
     output: &std::path::Path,
 ) -> Result<(), Box<dyn std::error::Error>> {
     info!("🔍 Scanning local repository: {}", path.display());
 
     // Scan the local filesystem for documentation files
-    let doc_files = scan_local_repository(path, extensions, recursive)?;
-    info!("Found {} candidate files", doc_files.len());
+    let (doc_files, summary) = loader::scan::scan_files(path, options)?;
+    info!("Found {} candidate files ({})", doc_files.len(), summary);
 
     if doc_files.is_empty() {
         info!(
             "No documentation files found with extensions: {}",
-            extensions
+            options.extensions.join(",")
         );
         return Ok(());
     }
 
     // Process files directly (no LLM prioritization needed here)
-    process_local_files(&doc_files, output).await?;
+    process_local_files(&doc_files, output, None, code_mode).await?;
 
     Ok(())
 }
 
-/// Scan local repository for documentation files
-fn scan_local_repository(
-    path: &std::path::Path,
+/// Clone a repository, parse its files and remove the checkout
+async fn handle_repo_command(
+    repo_url: &str,
+    branch: Option<&str>,
+    subdir: Option<&std::path::Path>,
     extensions: &str,
-    recursive: bool,
-) -> Result<Vec<std::path::PathBuf>, Box<dyn std::error::Error>> {
-    let extensions: Vec<&str> = extensions.split(',').map(str::trim).collect();
-    let mut doc_files = Vec::new();
+    max_files: usize,
+    code_mode: loader::extractors::CodeMode,
+    output: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let url = repo_url.to_string();
+    let branch_name = branch.map(str::to_string);
+    let checkout = tokio::task::spawn_blocking(move || {
+        loader::repo::clone_repository(&url, branch_name.as_deref())
+    })
+    .await??;
+
+    let scan_root = match subdir {
+        Some(subdir) => checkout.path().join(subdir),
+        None => checkout.path().to_path_buf(),
+    };
+    if !scan_root.starts_with(checkout.path()) || !scan_root.is_dir() {
+        return Err(format!(
+            "{} is not a directory in the repository",
+            scan_root.display()
+        )
+        .into());
+    }
+
+    let (doc_files, summary) = loader::scan::scan_files(
+        &scan_root,
+        &loader::scan::ScanOptions::new(extensions, true),
+    )?;
+    info!("Found {} candidate files ({})", doc_files.len(), summary);
+    if doc_files.len() > max_files {
+        return Err(format!(
+            "Repository has {} matching files, more than --max-files {}; narrow --extensions or --subdir",
+            doc_files.len(),
+            max_files
+        )
+        .into());
+    }
+
+    process_local_files(&doc_files, output, Some(&checkout), code_mode).await?;
+    Ok(())
+}
+
+/// Crawl a website and save its pages as `DocPage` JSON
+async fn handle_web_command(
+    options: &loader::web::WebOptions,
+    interval_ms: u64,
+    output: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    info!("🌐 Crawling {}", options.base_url);
+    let client =
+        rust_crates::RateLimiter::with_limits(std::time::Duration::from_millis(interval_ms), 1);
+    let (documents, summary) = loader::web::crawl_site(&client, options).await?;
+    info!("Crawled {}", summary);
+
+    process_and_save_documents(documents, output).await?;
+    Ok(())
+}
+
+/// Parse a crate's rustdoc output into documents carrying its crate metadata
+///
+/// Load them with `database --doc-type rust --source-name <crate>`.
+async fn handle_rustdoc_command(
+    path: &std::path::Path,
+    doc_dir: Option<&std::path::Path>,
+    build: bool,
+    output: &std::path::Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let local_crate = rust_crates::LocalCrate::from_manifest(path)?;
+    let doc_dir = if build {
+        local_crate.build_docs().await?
+    } else {
+        local_crate.find_doc_dir(doc_dir)?
+    };
+    info!(
+        "🦀 Reading rustdoc output of {} {} in {}",
+        local_crate.name(),
+        local_crate.version(),
+        doc_dir.display()
+    );
+
+    let pages = local_crate.load_docs(&doc_dir).await?;
+    let documents = pages
+        .into_iter()
+        .map(|page| {
+            let mut metadata = serde_json::json!({
+                "crate_name": local_crate.name(),
+                "crate_version": local_crate.version(),
+                "item_type": page.item_type,
+                "module_path": page.module_path,
+                "format": "markdown",
+                "source_url": page.url,
+                "origin": rust_crates::LOCAL_ORIGIN,
+            });
+            if let Some(parent_url) = &page.parent_url {
+                metadata["example_of"] = serde_json::json!(parent_url);
+            }
+            loader::loaders::DocPage {
+                url: page.url,
+                content: page.content,
+                item_type: page.item_type,
+                module_path: page.module_path,
+                extracted_at: page.extracted_at,
+                metadata: Some(metadata),
+            }
+        })
+        .collect();
 
-    scan_dir(path, &extensions, recursive, &mut doc_files)?;
-    Ok(doc_files)
+    process_and_save_documents(documents, output).await?;
+    Ok(())
 }
 
 /// Use Claude to analyze and prioritize local documentation files
@@ -221,6 +584,8 @@ fn scan_local_repository(
 async fn process_local_files(
     files: &[std::path::PathBuf],
     output: &std::path::Path,
+    checkout: Option<&loader::repo::RepoCheckout>,
+    code_mode: loader::extractors::CodeMode,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let parser = UniversalParser::default();
 
@@ -233,37 +598,138 @@ async fn process_local_files(
             file_path.display()
         );
 
-        let content = tokio::fs::read_to_string(file_path).await?;
+        // Read bytes so binary formats such as PDF can be parsed
+        let bytes = tokio::fs::read(file_path).await?;
         let path_str = file_path.to_string_lossy();
-        let parsed = parser.parse(&content, &path_str).await?;
-
-        let item_type = match parsed.format {
-            DocumentFormat::Markdown => "markdown",
-            DocumentFormat::Html => "html",
-            DocumentFormat::Json => "json_config",
-            DocumentFormat::Yaml => "yaml_config",
-            DocumentFormat::Toml => "toml_config",
-            DocumentFormat::Pdf => "pdf",
-            DocumentFormat::ApiSpec => "api_spec",
-            DocumentFormat::Code => "code",
-            DocumentFormat::PlainText => "plain_text",
-            DocumentFormat::Unknown => "unknown",
-        };
+        // Repository documents are addressed relative to the checkout root
+        let doc_path = checkout
+            .and_then(|c| file_path.strip_prefix(c.path()).ok())
+            .map_or_else(|| path_str.to_string(), |p| p.to_string_lossy().to_string());
+        let url = checkout.map_or_else(|| format!("file://{path_str}"), |c| c.file_url(&doc_path));
+
+        // Documented items of source files become documents of their own
+        if let Some(language) = loader::extractors::language(&path_str) {
+            if code_mode.includes_items() {
+                let items = std::str::from_utf8(&bytes)
+                    .ok()
+                    .and_then(|text| loader::extractors::extract(text, &path_str))
+                    .unwrap_or_default();
+                info!("Extracted {} documented items", items.len());
+                documents.extend(
+                    items
+                        .iter()
+                        .map(|item| code_item_page(item, language, &url, &doc_path, checkout)),
+                );
+            }
+            if !code_mode.includes_file() {
+                continue;
+            }
+        }
 
-        let doc_page = loader::loaders::DocPage {
-            url: format!("file://{path_str}"),
-            content: parsed.text_content,
-            item_type: item_type.to_string(),
-            module_path: path_str.to_string(),
-            extracted_at: chrono::Utc::now(),
+        // API specs expand into an overview plus one document per endpoint,
+        // PDFs into one document per page range
+        let parsed_documents = match parser.parse_bytes(&bytes, &path_str).await {
+            Ok(parsed) => parsed,
+            Err(e) if loader::parsers::pdf::is_pdf(&bytes, &path_str) => {
+                warn!("Skipping PDF {}: {}", path_str, e);
+                continue;
+            }
+            Err(e) => return Err(e.into()),
         };
-        documents.push(doc_page);
+        for parsed in parsed_documents {
+            let item_type = match parsed.format {
+                DocumentFormat::Markdown => "markdown",
+                DocumentFormat::Html => "html",
+                DocumentFormat::Json => "json_config",
+                DocumentFormat::Yaml => "yaml_config",
+                DocumentFormat::Toml => "toml_config",
+                DocumentFormat::Pdf => "pdf",
+                DocumentFormat::ApiSpec => "api_spec",
+                DocumentFormat::Notebook => "notebook",
+                DocumentFormat::Code => "code",
+                DocumentFormat::PlainText => "plain_text",
+                DocumentFormat::Unknown => "unknown",
+            };
+            let page_range = (
+                parsed.metadata.get(loader::parsers::pdf::PAGE_START_KEY),
+                parsed.metadata.get(loader::parsers::pdf::PAGE_END_KEY),
+            );
+            let module_path = match (
+                parsed.metadata.get(loader::parsers::openapi::ENDPOINT_KEY),
+                page_range,
+            ) {
+                (Some(endpoint), _) => format!("{doc_path}#{endpoint}"),
+                (None, (Some(start), Some(end))) if start == end => {
+                    format!("{doc_path}#page-{start}")
+                }
+                (None, (Some(start), Some(end))) => format!("{doc_path}#pages-{start}-{end}"),
+                _ => doc_path.clone(),
+            };
+            let mut metadata = match parsed.format {
+                DocumentFormat::ApiSpec | DocumentFormat::Pdf => Some(
+                    parsed
+                        .metadata
+                        .iter()
+                        .map(|(k, v)| (k.clone(), serde_json::Value::String(v.clone())))
+                        .collect::<serde_json::Map<_, _>>(),
+                ),
+                // Frontmatter fields and the heading outline
+                DocumentFormat::Markdown => Some(loader::parsers::markdown::document_metadata(
+                    &parsed.metadata,
+                ))
+                .filter(|m| !m.is_empty()),
+                _ => None,
+            };
+            if let Some(checkout) = checkout {
+                metadata
+                    .get_or_insert_with(serde_json::Map::new)
+                    .extend(checkout.metadata());
+            }
+
+            documents.push(loader::loaders::DocPage {
+                url: url.clone(),
+                content: parsed.text_content,
+                item_type: item_type.to_string(),
+                module_path,
+                extracted_at: chrono::Utc::now(),
+                metadata: metadata.map(serde_json::Value::Object),
+            });
+        }
     }
 
     process_and_save_documents(documents, output).await?;
     Ok(())
 }
 
+/// Document for one documented item of a source file
+///
+/// The module path is the file path plus the symbol name, and the URL points
+/// at the item's line.
+fn code_item_page(
+    item: &loader::extractors::CodeItem,
+    language: &str,
+    file_url: &str,
+    doc_path: &str,
+    checkout: Option<&loader::repo::RepoCheckout>,
+) -> loader::loaders::DocPage {
+    let mut metadata = serde_json::Map::from_iter([
+        ("language".to_string(), serde_json::json!(language)),
+        ("symbol".to_string(), serde_json::json!(item.name)),
+        ("line".to_string(), serde_json::json!(item.line)),
+    ]);
+    if let Some(checkout) = checkout {
+        metadata.extend(checkout.metadata());
+    }
+    loader::loaders::DocPage {
+        url: format!("{file_url}#L{}", item.line),
+        content: item.content(language),
+        item_type: item.kind.to_string(),
+        module_path: format!("{doc_path}#{}", item.name),
+        extracted_at: chrono::Utc::now(),
+        metadata: Some(serde_json::Value::Object(metadata)),
+    }
+}
+
 // Interactive mode removed for now; analyzer-driven or direct subcommands are preferred.
 
 async fn process_and_save_documents(
@@ -308,18 +774,28 @@ fn sanitize_filename(name: &str) -> String {
         .to_lowercase()
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_database_command(
     input_dir: &std::path::Path,
     doc_type: &str,
     source_name: &str,
     batch_size: usize,
+    chunk_config: &embed::ChunkConfig,
     skip_confirmation: bool,
+    incremental: bool,
+    dedupe: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     info!("🗄️ Loading documents from database");
     info!("  📂 Input directory: {:?}", input_dir);
     info!("  📄 Document type: {}", doc_type);
     info!("  🏷️ Source name: {}", source_name);
     info!("  📦 Batch size: {}", batch_size);
+    if incremental {
+        info!("  ♻️ Incremental: skipping unchanged documents");
+    }
+    if dedupe {
+        info!("  🧬 Dedupe: storing identical documents once");
+    }
 
     // Check if input directory exists
     if !input_dir.exists() {
@@ -331,9 +807,13 @@ async fn handle_database_command(
     info!("✅ Connected to database");
 
     // Collect all JSON files from the directory (recursively)
-    let mut json_files = Vec::new();
-    // Reuse scan_dir helper to recursively gather .json files
-    scan_dir(input_dir, &["json"], true, &mut json_files)?;
+    let (json_files, _) = loader::scan::scan_files(
+        input_dir,
+        &loader::scan::ScanOptions {
+            exclude: Vec::new(),
+            ..loader::scan::ScanOptions::new("json", true)
+        },
+    )?;
 
     if json_files.is_empty() {
         return Err(format!("No JSON files found in {}", input_dir.display()).into());
@@ -342,16 +822,34 @@ async fn handle_database_command(
     info!("📄 Found {} JSON files to process", json_files.len());
 
     // Load and parse JSON files
-    let mut documents = Vec::new();
+    let mut parsed_docs = Vec::new();
     for file_path in &json_files {
         info!("📖 Loading: {}", file_path.display());
 
         let content = tokio::fs::read_to_string(file_path).await?;
         let parsed_doc: serde_json::Value = serde_json::from_str(&content)?;
+        parsed_docs.push(create_document_from_json(
+            &parsed_doc,
+            doc_type,
+            source_name,
+        ));
+    }
+
+    let mut duplicate_count = 0;
+    if dedupe {
+        (parsed_docs, duplicate_count) = dedupe_documents(parsed_docs);
+        info!("🧬 Skipped {} duplicate documents", duplicate_count);
+    }
 
-        // Convert to Document struct
-        let doc = create_document_from_json(&parsed_doc, doc_type, source_name);
-        documents.push(doc);
+    // One row per chunk for long documents
+    let mut documents = Vec::new();
+    for doc in parsed_docs {
+        documents.extend(split_document_into_chunks(doc, chunk_config));
+    }
+
+    // Record the hash on every row so later incremental loads can skip it
+    for doc in &mut documents {
+        db::annotate_content_hash(&mut doc.metadata, &doc.content);
     }
 
     info!("✅ Loaded {} documents from JSON files", documents.len());
@@ -380,6 +878,8 @@ async fn handle_database_command(
 
     // Insert documents in batches
     let mut inserted_count = 0;
+    let mut updated_count = 0;
+    let mut skipped_count = 0;
     let mut failed_count = 0;
 
     for (i, batch) in documents.chunks(batch_size).enumerate() {
@@ -390,13 +890,46 @@ async fn handle_database_command(
             batch.len()
         );
 
-        match DocumentQueries::batch_insert_documents(pool.pool(), batch).await {
-            Ok(inserted_docs) => {
-                inserted_count += inserted_docs.len();
-                info!("  ✅ Inserted {} documents in batch", inserted_docs.len());
+        if !incremental {
+            match DocumentQueries::batch_insert_documents(pool.pool(), batch).await {
+                Ok(inserted_docs) => {
+                    inserted_count += inserted_docs.len();
+                    info!("  ✅ Inserted {} documents in batch", inserted_docs.len());
+                }
+                Err(e) => {
+                    failed_count += batch.len();
+                    warn!("  ❌ Failed to insert batch: {}", e);
+                }
+            }
+            continue;
+        }
+
+        // One hash lookup per batch decides what actually needs writing
+        let changes =
+            match DocumentQueries::partition_by_content_hash(pool.pool(), batch.to_vec()).await {
+                Ok(changes) => changes,
+                Err(e) => {
+                    failed_count += batch.len();
+                    warn!("  ❌ Failed to compare content hashes: {}", e);
+                    continue;
+                }
+            };
+        skipped_count += changes.unchanged;
+        let (new_count, changed_count) = (changes.new.len(), changes.changed.len());
+        let mut to_write = changes.new;
+        to_write.extend(changes.changed);
+
+        match DocumentQueries::batch_insert_documents(pool.pool(), &to_write).await {
+            Ok(_) => {
+                inserted_count += new_count;
+                updated_count += changed_count;
+                info!(
+                    "  ✅ Inserted {}, updated {}, skipped {} unchanged",
+                    new_count, changed_count, changes.unchanged
+                );
             }
             Err(e) => {
-                failed_count += batch.len();
+                failed_count += to_write.len();
                 warn!("  ❌ Failed to insert batch: {}", e);
             }
         }
@@ -406,6 +939,13 @@ async fn handle_database_command(
     println!();
     println!("📊 DATABASE INSERTION COMPLETE:");
     println!("  ✅ Documents inserted: {inserted_count}");
+    if incremental {
+        println!("  🔄 Documents updated: {updated_count}");
+        println!("  ⏭️ Documents skipped (unchanged): {skipped_count}");
+    }
+    if dedupe {
+        println!("  🧬 Documents skipped (duplicate): {duplicate_count}");
+    }
     if failed_count > 0 {
         println!("  ❌ Documents failed: {failed_count}");
     }
@@ -421,13 +961,47 @@ async fn handle_database_command(
     Ok(())
 }
 
+/// Drop documents whose content repeats an earlier document
+///
+/// Each dropped document's URL (or path) is added to the `alternate_urls` of
+/// the first document with the same content. Returns the kept documents and
+/// the number dropped.
+fn dedupe_documents(documents: Vec<Document>) -> (Vec<Document>, usize) {
+    let location = |doc: &Document| {
+        ["source_url", "url"]
+            .iter()
+            .find_map(|key| doc.metadata.get(*key).and_then(serde_json::Value::as_str))
+            .unwrap_or(&doc.doc_path)
+            .to_string()
+    };
+
+    let mut deduper = db::ContentDeduper::new();
+    let mut kept: Vec<Document> = Vec::with_capacity(documents.len());
+    let mut index_by_location = HashMap::new();
+    for doc in documents {
+        let url = location(&doc);
+        match deduper.check(&url, &doc.content) {
+            Some(canonical) => {
+                if let Some(&index) = index_by_location.get(&canonical) {
+                    let canonical_doc: &mut Document = &mut kept[index];
+                    db::add_alternate_url(&mut canonical_doc.metadata, &url);
+                }
+            }
+            None => {
+                index_by_location.entry(url).or_insert(kept.len());
+                kept.push(doc);
+            }
+        }
+    }
+    (kept, deduper.skipped())
+}
+
 fn create_document_from_json(
     json_doc: &serde_json::Value,
     doc_type: &str,
     source_name: &str,
 ) -> Document {
     // Extract fields from JSON, with defaults for missing fields
-    let id = Uuid::new_v4(); // Generate new ID for database
     let doc_type = doc_type.to_string();
     let source_name = source_name.to_string();
 
@@ -449,21 +1023,31 @@ fn create_document_from_json(
         .to_string();
 
     // Extract metadata (use the entire JSON as metadata, or create enhanced metadata)
-    let metadata = if let Some(meta) = json_doc.get("metadata") {
-        meta.clone()
-    } else {
-        // Create enhanced metadata by analyzing content using shared logic
-        db::create_enhanced_metadata(&doc_type, &source_name, &content, &doc_path)
+    // Create enhanced metadata by analyzing content using shared logic; keys
+    // emitted by the parser (API spec fields, page ranges, repo provenance)
+    // take precedence
+    let metadata = match json_doc.get("metadata") {
+        Some(serde_json::Value::Object(emitted)) => {
+            let mut metadata =
+                db::create_enhanced_metadata(&doc_type, &source_name, &content, &doc_path);
+            if let Some(map) = metadata.as_object_mut() {
+                map.extend(emitted.clone());
+            }
+            metadata
+        }
+        Some(meta) if !meta.is_null() => meta.clone(),
+        _ => db::create_enhanced_metadata(&doc_type, &source_name, &content, &doc_path),
     };
 
-    // Extract token count if available
+    // Use the token count from the JSON if available, otherwise compute it
     let token_count = json_doc
         .get("token_count")
         .and_then(serde_json::Value::as_i64)
-        .and_then(|v| i32::try_from(v).ok());
+        .and_then(|v| i32::try_from(v).ok())
+        .or_else(|| i32::try_from(embed::token_count(&content)).ok());
 
     Document {
-        id,
+        id: db::new_document_id(&doc_type, &source_name, &doc_path, &metadata),
         doc_type,
         source_name,
         doc_path,
@@ -476,4 +1060,43 @@ fn create_document_from_json(
     }
 }
 
+/// Split a document into chunk documents sharing the parent's metadata
+///
+/// Every row records `parent_doc_path`, `chunk_index` and `chunk_total`;
+/// documents that fit in one chunk keep their path and content.
+fn split_document_into_chunks(doc: Document, config: &embed::ChunkConfig) -> Vec<Document> {
+    // Markdown documents are split at their headings where possible
+    let headings = loader::parsers::markdown::outline_offsets(&doc.metadata);
+    let chunks = embed::chunk_text_at_boundaries(&doc.content, &headings, config);
+    if chunks.len() <= 1 {
+        let mut doc = doc;
+        let parent = doc.doc_path.clone();
+        db::chunks::annotate_chunk_metadata(&mut doc.metadata, &parent, 0, 1);
+        return vec![doc];
+    }
+
+    let total = chunks.len();
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(index, content)| {
+            let mut metadata = doc.metadata.clone();
+            db::chunks::annotate_chunk_metadata(&mut metadata, &doc.doc_path, index, total);
+            let doc_path = db::chunks::chunk_doc_path(&doc.doc_path, index);
+            Document {
+                id: db::new_document_id(&doc.doc_type, &doc.source_name, &doc_path, &metadata),
+                doc_type: doc.doc_type.clone(),
+                source_name: doc.source_name.clone(),
+                doc_path,
+                token_count: i32::try_from(embed::token_count(&content)).ok(),
+                content,
+                metadata,
+                embedding: None,
+                created_at: doc.created_at,
+                updated_at: doc.updated_at,
+            }
+        })
+        .collect()
+}
+
 // Intelligent command removed; discovery is handled by server
@@ -485,16 +485,23 @@ impl MigrationPipeline {
         // Convert to pgvector format
         let embedding = pgvector::Vector::from(embedding_vector);
 
+        let token_count = i32::try_from(embed::token_count(&doc.content)).ok();
+        let mut metadata = serde_json::json!({});
+        self.embedding_client
+            .embedding_config()
+            .annotate_metadata(&mut metadata);
+
         // Create document record
+        let doc_type = doc.doc_type.to_string().to_lowercase();
         let document = Document {
-            id: Uuid::new_v4(),
-            doc_type: doc.doc_type.to_string().to_lowercase(),
+            id: db::new_document_id(&doc_type, "migration", &doc.path, &metadata),
+            doc_type,
             source_name: "migration".to_string(),
             doc_path: doc.path,
             content: doc.content,
-            metadata: serde_json::json!({}),
+            metadata,
             embedding: Some(embedding),
-            token_count: None,
+            token_count,
             created_at: Some(Utc::now()),
             updated_at: Some(Utc::now()),
         };
@@ -0,0 +1,286 @@
+//! Markdown frontmatter and heading outline
+//!
+//! A document may open with a YAML block between `---` lines. Its fields
+//! are kept under the `frontmatter` metadata key, and `title`, `tags` and
+//! `category` are also promoted to top-level keys. YAML that does not parse
+//! is kept as `frontmatter_raw` instead of failing the file. The `outline`
+//! lists the H1–H3 headings with their character offsets in the extracted
+//! text; chunking prefers to split there.
+//!
+//! Parser metadata holds strings, so structured values are stored as JSON
+//! text and decoded by [`document_metadata`].
+
+use pulldown_cmark::{Event, HeadingLevel, Parser, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Metadata key for the parsed frontmatter fields
+pub const FRONTMATTER_KEY: &str = "frontmatter";
+
+/// Metadata key for frontmatter that is not valid YAML
+pub const FRONTMATTER_RAW_KEY: &str = "frontmatter_raw";
+
+/// Metadata key for the heading outline
+pub const OUTLINE_KEY: &str = "outline";
+
+/// Frontmatter fields also stored as top-level metadata
+pub const PROMOTED_KEYS: [&str; 3] = ["title", "tags", "category"];
+
+/// Deepest heading level included in the outline
+const MAX_OUTLINE_LEVEL: usize = 3;
+
+/// Heading in a document outline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutlineEntry {
+    /// Heading level, 1 to 3
+    pub level: usize,
+    /// Heading text without markup
+    pub title: String,
+    /// Character offset of the heading in the extracted text
+    pub offset: usize,
+}
+
+/// Split leading frontmatter from `content`, returning the YAML and the body
+///
+/// The document must open with a `---` line immediately followed by a
+/// `key:` line and closed by a `---` or `...` line. Anything else, such as a
+/// horizontal rule followed by a blank line, is left as markdown.
+#[must_use]
+pub fn split_frontmatter(content: &str) -> Option<(&str, &str)> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let rest = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))?;
+    if !starts_with_key(rest) {
+        return None;
+    }
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Whether `text` starts with a YAML mapping key such as `title:`
+fn starts_with_key(text: &str) -> bool {
+    let line = text.lines().next().unwrap_or_default();
+    line.split_once(':').is_some_and(|(key, value)| {
+        !key.is_empty()
+            && !key.starts_with(char::is_whitespace)
+            && (value.is_empty() || value.starts_with(char::is_whitespace))
+    })
+}
+
+/// Metadata entries for a frontmatter block
+///
+/// A YAML mapping yields `frontmatter` plus the promoted keys it defines;
+/// anything else is kept verbatim under `frontmatter_raw`.
+#[must_use]
+pub fn frontmatter_metadata(yaml: &str) -> HashMap<String, String> {
+    let fields = serde_yaml::from_str::<serde_yaml::Value>(yaml)
+        .ok()
+        .and_then(|value| serde_json::to_value(value).ok());
+    let Some(Value::Object(fields)) = fields else {
+        return HashMap::from([(FRONTMATTER_RAW_KEY.to_string(), yaml.to_string())]);
+    };
+
+    let mut metadata = HashMap::from([(
+        FRONTMATTER_KEY.to_string(),
+        Value::Object(fields.clone()).to_string(),
+    )]);
+    if let Some(title) = fields.get("title").and_then(scalar_string) {
+        metadata.insert("title".to_string(), title);
+    }
+    if let Some(category) = fields.get("category").and_then(scalar_string) {
+        metadata.insert("category".to_string(), category);
+    }
+    let tags: Vec<String> = match fields.get("tags") {
+        Some(Value::Array(tags)) => tags.iter().filter_map(scalar_string).collect(),
+        Some(Value::String(tags)) => tags
+            .split(',')
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(ToString::to_string)
+            .collect(),
+        _ => Vec::new(),
+    };
+    if !tags.is_empty() {
+        metadata.insert("tags".to_string(), Value::from(tags).to_string());
+    }
+    metadata
+}
+
+/// String form of a YAML scalar
+fn scalar_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// H1–H3 headings of `markdown`, located in `text`, its extracted text
+///
+/// Offsets count characters. A heading whose text cannot be found is placed
+/// at the previous heading's offset so the outline stays in order.
+#[must_use]
+pub fn outline(markdown: &str, text: &str) -> Vec<OutlineEntry> {
+    let mut headings: Vec<(usize, Vec<String>)> = Vec::new();
+    let mut current: Option<(usize, Vec<String>)> = None;
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                current = Some((heading_level(level), Vec::new()));
+            }
+            Event::End(TagEnd::Heading(_)) => headings.extend(current.take()),
+            Event::Text(fragment) | Event::Code(fragment) => {
+                if let Some((_, fragments)) = current.as_mut() {
+                    fragments.push(fragment.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut entries = Vec::new();
+    let mut cursor = 0;
+    for (level, fragments) in headings {
+        let title = fragments
+            .concat()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if level > MAX_OUTLINE_LEVEL || title.is_empty() {
+            continue;
+        }
+        let first = fragments
+            .iter()
+            .map(|f| f.trim())
+            .find(|f| !f.is_empty())
+            .unwrap_or_default();
+        if let Some(pos) = text[cursor..].find(first) {
+            cursor += pos;
+        }
+        entries.push(OutlineEntry {
+            level,
+            title,
+            offset: text[..cursor].chars().count(),
+        });
+    }
+    entries
+}
+
+const fn heading_level(level: HeadingLevel) -> usize {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+/// Frontmatter and outline entries of parser metadata as document metadata,
+/// with JSON-encoded values decoded
+#[must_use]
+pub fn document_metadata(metadata: &HashMap<String, String>) -> Map<String, Value> {
+    let mut out = Map::new();
+    for key in [FRONTMATTER_KEY, FRONTMATTER_RAW_KEY, OUTLINE_KEY]
+        .into_iter()
+        .chain(PROMOTED_KEYS)
+    {
+        let Some(value) = metadata.get(key) else {
+            continue;
+        };
+        let value = match key {
+            FRONTMATTER_KEY | OUTLINE_KEY | "tags" => {
+                serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.clone()))
+            }
+            _ => Value::String(value.clone()),
+        };
+        out.insert(key.to_string(), value);
+    }
+    out
+}
+
+/// Character offsets of the outline headings in document metadata
+#[must_use]
+pub fn outline_offsets(metadata: &Value) -> Vec<usize> {
+    metadata
+        .get(OUTLINE_KEY)
+        .and_then(Value::as_array)
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|e| e.get("offset").and_then(Value::as_u64))
+                .filter_map(|offset| usize::try_from(offset).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frontmatter_with_lists_and_nested_maps() {
+        let content = "---\ntitle: Getting Started\ntags:\n  - setup\n  - install\ncategory: guide\nauthor:\n  name: Ada\n  links: [a, b]\n---\n# Intro\n\nBody text.\n";
+        let (yaml, body) = split_frontmatter(content).unwrap();
+        assert_eq!(body, "# Intro\n\nBody text.\n");
+
+        let metadata = document_metadata(&frontmatter_metadata(yaml));
+        assert_eq!(metadata["title"], "Getting Started");
+        assert_eq!(metadata["category"], "guide");
+        assert_eq!(metadata["tags"], serde_json::json!(["setup", "install"]));
+        assert_eq!(metadata["frontmatter"]["author"]["name"], "Ada");
+        assert_eq!(
+            metadata["frontmatter"]["author"]["links"],
+            serde_json::json!(["a", "b"])
+        );
+        assert!(!metadata.contains_key(FRONTMATTER_RAW_KEY));
+    }
+
+    #[test]
+    fn test_leading_horizontal_rule_is_not_frontmatter() {
+        assert_eq!(
+            split_frontmatter("---\n\n# Title\n\nText\n\n---\n\nMore\n"),
+            None
+        );
+        assert_eq!(split_frontmatter("---\nJust a rule\n---\n"), None);
+        assert_eq!(split_frontmatter("---\ntitle: never closed\n"), None);
+    }
+
+    #[test]
+    fn test_malformed_yaml_is_kept_raw() {
+        let (yaml, _) = split_frontmatter("---\ntitle: [unclosed\n---\nBody\n").unwrap();
+        let metadata = frontmatter_metadata(yaml);
+        assert_eq!(metadata[FRONTMATTER_RAW_KEY], "title: [unclosed\n");
+        assert!(!metadata.contains_key(FRONTMATTER_KEY));
+        assert!(!metadata.contains_key("title"));
+    }
+
+    #[test]
+    fn test_outline_offsets() {
+        let markdown =
+            "# Guide\n\nIntro.\n\n## Install `cli`\n\nSteps.\n\n#### Deep\n\n### Usage\n";
+        let text = "Guide Intro. Install  cli Steps. Deep Usage";
+        let entries = outline(markdown, text);
+        let summary: Vec<(usize, &str, usize)> = entries
+            .iter()
+            .map(|e| (e.level, e.title.as_str(), e.offset))
+            .collect();
+        assert_eq!(
+            summary,
+            [(1, "Guide", 0), (2, "Install cli", 13), (3, "Usage", 38)]
+        );
+    }
+}
@@ -14,6 +14,11 @@ use std::fmt::Write as _;
 use std::path::Path;
 use tracing::{debug, info, warn};
 
+pub mod markdown;
+pub mod notebook;
+pub mod openapi;
+pub mod pdf;
+
 /// Supported document formats
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DocumentFormat {
@@ -26,6 +31,7 @@ pub enum DocumentFormat {
     PlainText,
     Code,
     ApiSpec,
+    Notebook,
     Unknown,
 }
 
@@ -43,6 +49,7 @@ impl DocumentFormat {
                 "yaml" | "yml" => Self::Yaml,
                 "toml" => Self::Toml,
                 "pdf" => Self::Pdf,
+                "ipynb" => Self::Notebook,
                 "txt" => Self::PlainText,
                 "rs" | "py" | "js" | "ts" | "go" | "java" | "cpp" | "c" | "h" => Self::Code,
                 _ => Self::Unknown,
@@ -55,11 +62,20 @@ impl DocumentFormat {
     /// Detect format from content
     #[must_use]
     pub fn from_content(content: &str) -> Self {
-        // Check for JSON
-        if serde_json::from_str::<Value>(content).is_ok() {
-            // Check if it's an API spec
-            if content.contains("swagger") || content.contains("openapi") {
-                return Self::ApiSpec;
+        // Check for PDF magic bytes
+        if content.starts_with("%PDF") {
+            return Self::Pdf;
+        }
+
+        // Check for OpenAPI / Swagger specs in JSON or YAML
+        if openapi::is_openapi(content) {
+            return Self::ApiSpec;
+        }
+
+        // Check for JSON (Jupyter notebooks are JSON with an `nbformat` field)
+        if let Ok(value) = serde_json::from_str::<Value>(content) {
+            if value.get("nbformat").is_some() {
+                return Self::Notebook;
             }
             return Self::Json;
         }
@@ -214,6 +230,7 @@ impl UniversalParser {
             DocumentFormat::Toml => self.parse_toml(content, path).await?,
             DocumentFormat::Pdf => self.parse_pdf(content, path).await?,
             DocumentFormat::ApiSpec => self.parse_api_spec(content, path).await?,
+            DocumentFormat::Notebook => self.parse_notebook(content, path).await?,
             DocumentFormat::Code => self.parse_code(content, path).await?,
             DocumentFormat::PlainText => self.parse_plain_text(content, path).await?,
             DocumentFormat::Unknown => self.parse_unknown(content, path).await?,
@@ -222,10 +239,68 @@ impl UniversalParser {
         Ok(parsed)
     }
 
+    /// Parse content into one or more documents
+    ///
+    /// API specs are split into an overview plus one document per endpoint;
+    /// every other format yields the single document returned by [`Self::parse`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content cannot be parsed as the detected format.
+    pub async fn parse_documents(&self, content: &str, path: &str) -> Result<Vec<ParsedContent>> {
+        if Self::detect_format(content, path) == DocumentFormat::ApiSpec {
+            info!("Parsing API specification: {}", path);
+            return openapi::parse_openapi(content, path);
+        }
+        Ok(vec![self.parse(content, path).await?])
+    }
+
+    /// Parse raw file bytes into one or more documents
+    ///
+    /// PDFs (by extension or `%PDF` magic bytes) are split into page-range
+    /// documents carrying `page_start`/`page_end` metadata; everything else
+    /// must be UTF-8 text and goes through [`Self::parse_documents`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a PDF has no extractable text, a non-PDF file is
+    /// not valid UTF-8, or the content cannot be parsed as its format.
+    pub async fn parse_bytes(&self, bytes: &[u8], path: &str) -> Result<Vec<ParsedContent>> {
+        if pdf::is_pdf(bytes, path) {
+            let parsed = self.parse_pdf_bytes(bytes, path).await?;
+            let documents = self
+                .chunk_content(&parsed, path)
+                .into_iter()
+                .map(|chunk| {
+                    let mut metadata = parsed.metadata.clone();
+                    metadata.extend(chunk.metadata);
+                    let estimated_tokens = Self::estimate_tokens(&chunk.content);
+                    ParsedContent {
+                        format: DocumentFormat::Pdf,
+                        text_content: chunk.content,
+                        structured_content: None,
+                        metadata,
+                        estimated_tokens: Some(estimated_tokens),
+                    }
+                })
+                .collect();
+            return Ok(documents);
+        }
+
+        let content = std::str::from_utf8(bytes)
+            .map_err(|e| anyhow!("{} is not a UTF-8 text file: {}", path, e))?;
+        self.parse_documents(content, path).await
+    }
+
     /// Detect document format from content and path
     fn detect_format(content: &str, path: &str) -> DocumentFormat {
         // First try extension-based detection
         let ext_format = DocumentFormat::from_extension(path);
+        if matches!(ext_format, DocumentFormat::Json | DocumentFormat::Yaml)
+            && openapi::is_openapi(content)
+        {
+            return DocumentFormat::ApiSpec;
+        }
         if !matches!(ext_format, DocumentFormat::Unknown) {
             return ext_format;
         }
@@ -239,6 +314,12 @@ impl UniversalParser {
     async fn parse_markdown(&self, content: &str, path: &str) -> Result<ParsedContent> {
         debug!("Parsing markdown content from: {}", path);
 
+        // Frontmatter becomes metadata rather than text
+        let (frontmatter, content) = match markdown::split_frontmatter(content) {
+            Some((yaml, body)) => (Some(markdown::frontmatter_metadata(yaml)), body),
+            None => (None, content),
+        };
+
         // Convert markdown to HTML first
         let html_content = Self::markdown_to_html(content);
 
@@ -248,7 +329,7 @@ impl UniversalParser {
         // Parse structure
         let structured = Self::parse_markdown_structure(content);
 
-        let metadata = HashMap::from([
+        let mut metadata = HashMap::from([
             ("format".to_string(), "markdown".to_string()),
             (
                 "has_code_blocks".to_string(),
@@ -259,6 +340,14 @@ impl UniversalParser {
                 structured.sections.len().to_string(),
             ),
         ]);
+        metadata.extend(frontmatter.unwrap_or_default());
+        let outline = markdown::outline(content, &text_content);
+        if !outline.is_empty() {
+            metadata.insert(
+                markdown::OUTLINE_KEY.to_string(),
+                serde_json::to_string(&outline)?,
+            );
+        }
 
         let estimated_tokens = Self::estimate_tokens(&text_content);
 
@@ -398,44 +487,45 @@ impl UniversalParser {
         })
     }
 
-    /// Parse PDF content (placeholder for now)
-    #[allow(clippy::unused_async)]
+    /// Parse PDF content
+    ///
+    /// Only works for PDFs that happen to be valid UTF-8; use
+    /// [`Self::parse_bytes`] for files read from disk.
     async fn parse_pdf(&self, content: &str, path: &str) -> Result<ParsedContent> {
-        warn!("PDF parsing not fully implemented yet for: {}", path);
+        self.parse_pdf_bytes(content.as_bytes(), path).await
+    }
 
-        let estimated_tokens = Self::estimate_tokens(content);
+    /// Extract PDF text per page on a blocking thread
+    #[allow(clippy::unused_self)]
+    async fn parse_pdf_bytes(&self, bytes: &[u8], path: &str) -> Result<ParsedContent> {
+        debug!("Extracting PDF text from: {}", path);
 
-        Ok(ParsedContent {
-            format: DocumentFormat::Pdf,
-            text_content: content.to_string(),
-            structured_content: None,
-            metadata: HashMap::from([
-                ("format".to_string(), "pdf".to_string()),
-                ("parsing_status".to_string(), "partial".to_string()),
-            ]),
-            estimated_tokens: Some(estimated_tokens),
-        })
+        let bytes = bytes.to_vec();
+        let owned_path = path.to_string();
+        tokio::task::spawn_blocking(move || pdf::parse_pdf(&bytes, &owned_path))
+            .await
+            .map_err(|e| anyhow!("PDF extraction for {} failed: {}", path, e))?
     }
 
-    /// Parse API specification (`OpenAPI`, GraphQL, etc.)
+    /// Parse an `OpenAPI` / Swagger specification into its overview document
+    ///
+    /// Use [`Self::parse_documents`] to also get one document per endpoint.
     #[allow(clippy::unused_async)]
     async fn parse_api_spec(&self, content: &str, path: &str) -> Result<ParsedContent> {
         debug!("Parsing API specification from: {}", path);
 
-        let json_value: Value = serde_json::from_str(content)
-            .map_err(|e| anyhow!("Failed to parse API spec JSON: {}", e))?;
+        openapi::parse_openapi(content, path)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("API spec {} produced no documents", path))
+    }
 
-        let text_content = Self::api_spec_to_text(&json_value);
-        let metadata = Self::extract_api_spec_metadata(&json_value);
-        let estimated_tokens = Self::estimate_tokens(&text_content);
+    /// Parse a Jupyter notebook
+    #[allow(clippy::unused_async)]
+    async fn parse_notebook(&self, content: &str, path: &str) -> Result<ParsedContent> {
+        debug!("Parsing Jupyter notebook from: {}", path);
 
-        Ok(ParsedContent {
-            format: DocumentFormat::ApiSpec,
-            text_content,
-            structured_content: None,
-            metadata,
-            estimated_tokens: Some(estimated_tokens),
-        })
+        notebook::parse_notebook(content, path, &notebook::NotebookOptions::from_env())
     }
 
     /// Parse code files
@@ -495,11 +585,9 @@ impl UniversalParser {
         })
     }
 
-    /// Estimate token count (rough approximation: 1 token ≈ 4 characters)
+    /// Estimate token count using the shared BPE tokenizer (min 1)
     fn estimate_tokens(text: &str) -> i32 {
-        // Approximation: 1 token ≈ 4 chars, round up, min 1
-        let len = text.len();
-        let estimated = len.div_ceil(4); // ceil(len/4)
+        let estimated = embed::token_count(text);
         i32::try_from(estimated.max(1)).unwrap_or(i32::MAX)
     }
 
@@ -698,82 +786,6 @@ impl UniversalParser {
         Self::json_to_text(value) // TOML and JSON have similar structure
     }
 
-    /// Convert API spec to readable text
-    fn api_spec_to_text(value: &Value) -> String {
-        let mut result = String::new();
-
-        // Extract basic info
-        if let Some(title) = value
-            .get("info")
-            .and_then(|i| i.get("title"))
-            .and_then(|t| t.as_str())
-        {
-            let _ = writeln!(result, "API Title: {title}");
-        }
-
-        if let Some(description) = value
-            .get("info")
-            .and_then(|i| i.get("description"))
-            .and_then(|d| d.as_str())
-        {
-            let _ = writeln!(result, "Description: {description}");
-        }
-
-        if let Some(version) = value
-            .get("info")
-            .and_then(|i| i.get("version"))
-            .and_then(|v| v.as_str())
-        {
-            let _ = writeln!(result, "Version: {version}");
-        }
-
-        // Extract paths
-        if let Some(paths) = value.get("paths").and_then(|p| p.as_object()) {
-            result.push_str("\nAPI Endpoints:\n");
-            for (path, methods) in paths {
-                let _ = writeln!(result, "\nPath: {path}");
-                if let Some(methods_obj) = methods.as_object() {
-                    for (method, details) in methods_obj {
-                        if let Some(summary) = details.get("summary").and_then(|s| s.as_str()) {
-                            let _ = writeln!(result, "  {}: {summary}", method.to_uppercase());
-                        }
-                    }
-                }
-            }
-        }
-
-        result
-    }
-
-    /// Extract API spec metadata
-    fn extract_api_spec_metadata(value: &Value) -> HashMap<String, String> {
-        let mut metadata = HashMap::new();
-
-        metadata.insert("format".to_string(), "api_spec".to_string());
-
-        if let Some(spec_version) = value
-            .get("openapi")
-            .or_else(|| value.get("swagger"))
-            .and_then(|v| v.as_str())
-        {
-            metadata.insert("spec_version".to_string(), spec_version.to_string());
-        }
-
-        if let Some(title) = value
-            .get("info")
-            .and_then(|i| i.get("title"))
-            .and_then(|t| t.as_str())
-        {
-            metadata.insert("title".to_string(), title.to_string());
-        }
-
-        if let Some(paths) = value.get("paths").and_then(|p| p.as_object()) {
-            metadata.insert("endpoint_count".to_string(), paths.len().to_string());
-        }
-
-        metadata
-    }
-
     /// Detect programming language from file path
     fn detect_code_language(path: &str) -> String {
         let path_obj = Path::new(path);
@@ -820,6 +832,13 @@ impl UniversalParser {
     pub fn chunk_content(&self, parsed: &ParsedContent, source_path: &str) -> Vec<ContentChunk> {
         let mut chunks = Vec::new();
 
+        // PDF sections are pages; keep page numbers with each chunk
+        if let (DocumentFormat::Pdf, Some(structured)) =
+            (&parsed.format, &parsed.structured_content)
+        {
+            return self.chunk_pdf_pages(structured, source_path);
+        }
+
         // If we have structured content, use it for intelligent chunking
         if let Some(structured) = &parsed.structured_content {
             for section in &structured.sections {
@@ -864,6 +883,74 @@ impl UniversalParser {
         chunks
     }
 
+    /// Pack consecutive PDF pages into chunks of at most `max_chunk_size`,
+    /// recording the page range of each chunk
+    fn chunk_pdf_pages(
+        &self,
+        structured: &StructuredDocument,
+        source_path: &str,
+    ) -> Vec<ContentChunk> {
+        fn push_chunk(
+            chunks: &mut Vec<ContentChunk>,
+            content: String,
+            (start, end): (usize, usize),
+            source_path: &str,
+        ) {
+            chunks.push(ContentChunk {
+                content,
+                chunk_type: "page".to_string(),
+                source_path: source_path.to_string(),
+                position: chunks.len(),
+                metadata: HashMap::from([
+                    (pdf::PAGE_START_KEY.to_string(), start.to_string()),
+                    (pdf::PAGE_END_KEY.to_string(), end.to_string()),
+                ]),
+            });
+        }
+
+        let mut chunks = Vec::new();
+        let mut current = String::new();
+        let mut pages: Option<(usize, usize)> = None;
+
+        for section in &structured.sections {
+            let Some(page) = pdf::section_page(section) else {
+                continue;
+            };
+
+            if !current.is_empty()
+                && current.len() + 2 + section.content.len() > self.max_chunk_size
+            {
+                if let Some(range) = pages.take() {
+                    push_chunk(
+                        &mut chunks,
+                        std::mem::take(&mut current),
+                        range,
+                        source_path,
+                    );
+                }
+            }
+
+            // Oversized pages are split on their own
+            if section.content.len() > self.max_chunk_size {
+                for piece in self.chunk_text(&section.content, source_path) {
+                    push_chunk(&mut chunks, piece.content, (page, page), source_path);
+                }
+                continue;
+            }
+
+            if !current.is_empty() {
+                current.push_str("\n\n");
+            }
+            current.push_str(&section.content);
+            pages = Some((pages.map_or(page, |(start, _)| start), page));
+        }
+
+        if let Some(range) = pages {
+            push_chunk(&mut chunks, current, range, source_path);
+        }
+        chunks
+    }
+
     /// Simple text chunking for unstructured content
     fn chunk_text(&self, text: &str, source_path: &str) -> Vec<ContentChunk> {
         let mut chunks = Vec::new();
@@ -0,0 +1,342 @@
+//! Jupyter notebook (`.ipynb`) parser
+//!
+//! Markdown cells become prose and code cells are fenced with the kernel
+//! language. Each markdown cell starts a section that also holds the code
+//! cells following it, so chunking by section never separates code from the
+//! text explaining it. Both nbformat v4 (`cells`) and v3 (`worksheets`) are
+//! supported.
+
+use super::{DocumentFormat, DocumentSection, ParsedContent, StructuredDocument, UniversalParser};
+use anyhow::{anyhow, bail, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+/// Default cap on output characters kept per code cell
+pub const DEFAULT_MAX_OUTPUT_CHARS: usize = 1000;
+
+/// Controls how cell outputs are included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotebookOptions {
+    /// Include `text/plain` and stream outputs after each code cell
+    pub include_outputs: bool,
+    /// Maximum output characters kept per cell
+    pub max_output_chars: usize,
+}
+
+impl NotebookOptions {
+    /// Read `NOTEBOOK_INCLUDE_OUTPUTS` / `NOTEBOOK_MAX_OUTPUT_CHARS`, defaulting to `true`/1000
+    #[must_use]
+    pub fn from_env() -> Self {
+        let include_outputs = std::env::var("NOTEBOOK_INCLUDE_OUTPUTS")
+            .ok()
+            .and_then(|v| v.parse::<bool>().ok())
+            .unwrap_or(true);
+        let max_output_chars = std::env::var("NOTEBOOK_MAX_OUTPUT_CHARS")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_MAX_OUTPUT_CHARS);
+        Self {
+            include_outputs,
+            max_output_chars,
+        }
+    }
+}
+
+impl Default for NotebookOptions {
+    fn default() -> Self {
+        Self {
+            include_outputs: true,
+            max_output_chars: DEFAULT_MAX_OUTPUT_CHARS,
+        }
+    }
+}
+
+/// A notebook cell normalised across nbformat versions
+struct Cell {
+    kind: CellKind,
+    source: String,
+    outputs: Vec<String>,
+}
+
+enum CellKind {
+    Markdown,
+    /// nbformat v3 heading cell
+    Heading(usize),
+    Code,
+    Raw,
+}
+
+/// Parse an nbformat v3 or v4 notebook
+///
+/// # Errors
+///
+/// Returns an error if the content is not JSON or has no cell list.
+pub fn parse_notebook(
+    content: &str,
+    path: &str,
+    options: &NotebookOptions,
+) -> Result<ParsedContent> {
+    let notebook: Value = serde_json::from_str(content)
+        .map_err(|e| anyhow!("Failed to parse notebook {path}: {e}"))?;
+    let nbformat = notebook.get("nbformat").and_then(Value::as_u64);
+    let language = kernel_language(&notebook);
+    let cells = read_cells(&notebook)
+        .ok_or_else(|| anyhow!("{path} is not a Jupyter notebook: no `cells` or `worksheets`"))?;
+    if cells.is_empty() && nbformat.is_none() {
+        bail!("{path} is not a Jupyter notebook: missing `nbformat`");
+    }
+
+    let mut text = String::new();
+    let mut sections: Vec<DocumentSection> = Vec::new();
+    let mut code_cells = 0;
+    let mut markdown_cells = 0;
+
+    for cell in &cells {
+        let rendered = match cell.kind {
+            CellKind::Markdown | CellKind::Raw => cell.source.trim().to_string(),
+            CellKind::Heading(level) => format!("{} {}", "#".repeat(level), cell.source.trim()),
+            CellKind::Code => render_code_cell(cell, &language, options),
+        };
+        if rendered.is_empty() {
+            continue;
+        }
+
+        match cell.kind {
+            CellKind::Markdown | CellKind::Heading(_) => {
+                markdown_cells += 1;
+                let (level, title) = section_heading(&rendered);
+                sections.push(DocumentSection {
+                    level,
+                    title,
+                    content: String::new(),
+                    subsections: Vec::new(),
+                });
+            }
+            CellKind::Code => code_cells += 1,
+            CellKind::Raw => {}
+        }
+        if sections.is_empty() {
+            // Code before the first markdown cell
+            sections.push(DocumentSection {
+                level: 1,
+                title: "Code".to_string(),
+                content: String::new(),
+                subsections: Vec::new(),
+            });
+        }
+        if let Some(section) = sections.last_mut() {
+            if !section.content.is_empty() {
+                section.content.push_str("\n\n");
+            }
+            section.content.push_str(&rendered);
+        }
+
+        // Blank lines between cells give text chunkers a paragraph boundary
+        if !text.is_empty() {
+            text.push_str("\n\n");
+        }
+        text.push_str(&rendered);
+    }
+
+    let title = sections
+        .iter()
+        .find(|s| s.title != "Code")
+        .map(|s| s.title.clone());
+    let mut metadata = HashMap::from([
+        ("format".to_string(), "notebook".to_string()),
+        ("language".to_string(), language),
+        ("cell_count".to_string(), cells.len().to_string()),
+        ("code_cells".to_string(), code_cells.to_string()),
+        ("markdown_cells".to_string(), markdown_cells.to_string()),
+    ]);
+    if let Some(nbformat) = nbformat {
+        metadata.insert("nbformat".to_string(), nbformat.to_string());
+    }
+    if let Some(title) = &title {
+        metadata.insert("title".to_string(), title.clone());
+    }
+    let estimated_tokens = UniversalParser::estimate_tokens(&text);
+
+    Ok(ParsedContent {
+        format: DocumentFormat::Notebook,
+        text_content: text,
+        structured_content: Some(StructuredDocument {
+            title,
+            toc: Vec::new(),
+            sections,
+            code_blocks: Vec::new(),
+            links: Vec::new(),
+        }),
+        metadata,
+        estimated_tokens: Some(estimated_tokens),
+    })
+}
+
+/// Kernel language from `kernelspec`, `language_info` or the v3 `language` field
+fn kernel_language(notebook: &Value) -> String {
+    let metadata = notebook.get("metadata");
+    ["/kernelspec/language", "/language_info/name", "/language"]
+        .iter()
+        .find_map(|pointer| metadata.and_then(|m| m.pointer(pointer)))
+        .or_else(|| notebook.pointer("/worksheets/0/cells/0/language"))
+        .and_then(Value::as_str)
+        .unwrap_or("python")
+        .to_lowercase()
+}
+
+/// Cells from `cells` (v4) or all `worksheets[].cells` (v3)
+fn read_cells(notebook: &Value) -> Option<Vec<Cell>> {
+    if let Some(cells) = notebook.get("cells").and_then(Value::as_array) {
+        return Some(cells.iter().filter_map(read_cell).collect());
+    }
+    let worksheets = notebook.get("worksheets")?.as_array()?;
+    Some(
+        worksheets
+            .iter()
+            .filter_map(|w| w.get("cells").and_then(Value::as_array))
+            .flatten()
+            .filter_map(read_cell)
+            .collect(),
+    )
+}
+
+fn read_cell(cell: &Value) -> Option<Cell> {
+    let kind = match cell.get("cell_type")?.as_str()? {
+        "markdown" => CellKind::Markdown,
+        "heading" => {
+            let level = cell.get("level").and_then(Value::as_u64).unwrap_or(1);
+            CellKind::Heading(usize::try_from(level).unwrap_or(1).clamp(1, 6))
+        }
+        "code" => CellKind::Code,
+        "raw" => CellKind::Raw,
+        _ => return None,
+    };
+    // v3 code cells keep their source under `input`
+    let source = cell
+        .get("source")
+        .or_else(|| cell.get("input"))
+        .map(multiline_text)
+        .unwrap_or_default();
+    let outputs = cell
+        .get("outputs")
+        .and_then(Value::as_array)
+        .map(|outputs| outputs.iter().filter_map(output_text).collect())
+        .unwrap_or_default();
+    Some(Cell {
+        kind,
+        source,
+        outputs,
+    })
+}
+
+/// nbformat "multiline strings" are either a string or a list of lines
+fn multiline_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Plain-text rendering of one output; rich-only outputs (images, HTML) are skipped
+fn output_text(output: &Value) -> Option<String> {
+    let text = match output.get("output_type")?.as_str()? {
+        "stream" => output.get("text").map(multiline_text),
+        "execute_result" | "display_data" => {
+            output.pointer("/data/text~1plain").map(multiline_text)
+        }
+        // v3
+        "pyout" => output.get("text").map(multiline_text),
+        "error" | "pyerr" => {
+            let name = output
+                .get("ename")
+                .and_then(Value::as_str)
+                .unwrap_or("Error");
+            let value = output.get("evalue").and_then(Value::as_str).unwrap_or("");
+            Some(format!("{name}: {value}"))
+        }
+        _ => None,
+    }?;
+    let text = text.trim_end().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+fn render_code_cell(cell: &Cell, language: &str, options: &NotebookOptions) -> String {
+    let source = cell.source.trim_end();
+    if source.trim().is_empty() {
+        return String::new();
+    }
+    let mut rendered = format!("```{language}\n{source}\n```");
+
+    if options.include_outputs && !cell.outputs.is_empty() {
+        let output = cell.outputs.join("\n");
+        let (output, truncated) = truncate_chars(&output, options.max_output_chars);
+        if !output.is_empty() {
+            let _ = write!(rendered, "\n\nOutput:\n```text\n{output}");
+            if truncated {
+                rendered.push_str("\n... [output truncated]");
+            }
+            rendered.push_str("\n```");
+        }
+    }
+    rendered
+}
+
+fn truncate_chars(text: &str, max: usize) -> (&str, bool) {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => (&text[..end], true),
+        None => (text, false),
+    }
+}
+
+/// Section level and title from the first line of a markdown cell
+fn section_heading(markdown: &str) -> (usize, String) {
+    let first = markdown.lines().next().unwrap_or("").trim();
+    let level = first.chars().take_while(|&c| c == '#').count();
+    let title = first[level..].trim();
+    let title: String = if title.is_empty() {
+        first.chars().take(80).collect()
+    } else {
+        title.to_string()
+    };
+    (level.max(1), title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_truncation() {
+        let cell = Cell {
+            kind: CellKind::Code,
+            source: "for i in range(10000): print(i)".to_string(),
+            outputs: vec!["0123456789".repeat(50)],
+        };
+        let options = NotebookOptions {
+            include_outputs: true,
+            max_output_chars: 20,
+        };
+        let rendered = render_code_cell(&cell, "python", &options);
+        assert!(rendered.contains("01234567890123456789\n... [output truncated]"));
+
+        let options = NotebookOptions {
+            include_outputs: false,
+            ..options
+        };
+        assert!(!render_code_cell(&cell, "python", &options).contains("Output:"));
+    }
+
+    #[test]
+    fn test_missing_source_and_rich_outputs() {
+        let cell = read_cell(&serde_json::json!({
+            "cell_type": "code",
+            "outputs": [{"output_type": "display_data", "data": {"image/png": "iVBOR"}}]
+        }))
+        .unwrap();
+        assert!(cell.source.is_empty());
+        assert!(cell.outputs.is_empty());
+        assert!(render_code_cell(&cell, "python", &NotebookOptions::default()).is_empty());
+    }
+}
@@ -0,0 +1,919 @@
+//! `OpenAPI` 3.x and Swagger 2.0 specification parser
+//!
+//! A spec is split into one overview document (info, servers, security
+//! schemes, tags and an endpoint index) followed by one document per
+//! path + method. Request and response schemas are flattened into indented
+//! field lists with local `$ref`s resolved; circular references are labelled
+//! instead of expanded.
+
+use super::{DocumentFormat, ParsedContent, UniversalParser};
+use anyhow::{anyhow, bail, Result};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+
+/// HTTP methods that may appear as keys of a path item
+const HTTP_METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// Maximum number of nested `$ref`s expanded while flattening one schema
+const MAX_REF_DEPTH: usize = 8;
+
+/// Metadata key holding `METHOD /path` for endpoint documents
+pub const ENDPOINT_KEY: &str = "endpoint";
+
+/// Parse `content` as JSON or YAML and return it if it is an `OpenAPI`/Swagger spec
+#[must_use]
+pub fn sniff(content: &str) -> Option<Value> {
+    let value = parse_structured(content).ok()?;
+    spec_version(&value).is_some().then_some(value)
+}
+
+/// Whether `content` is a JSON or YAML `OpenAPI`/Swagger spec
+#[must_use]
+pub fn is_openapi(content: &str) -> bool {
+    sniff(content).is_some()
+}
+
+/// Split an `OpenAPI` 3.x or Swagger 2.0 spec into documents
+///
+/// The first document is the API overview; each following document
+/// describes one operation.
+///
+/// # Errors
+///
+/// Returns an error if the content is neither JSON nor YAML, lacks an
+/// `openapi`/`swagger` version or `info` object, declares an unsupported
+/// version, or has `paths` / operations that are not objects.
+pub fn parse_openapi(content: &str, path: &str) -> Result<Vec<ParsedContent>> {
+    let spec = parse_structured(content)
+        .map_err(|e| anyhow!("Failed to parse API spec {path} as JSON or YAML: {e}"))?;
+    let spec_version = spec_version(&spec).ok_or_else(|| {
+        anyhow!("{path} is not an OpenAPI document: missing `openapi` or `swagger` version field")
+    })?;
+    if !(spec_version.starts_with("3.") || spec_version.starts_with("2.")) {
+        bail!("{path}: unsupported API spec version {spec_version} (expected OpenAPI 3.x or Swagger 2.0)");
+    }
+    let info = spec
+        .get("info")
+        .and_then(Value::as_object)
+        .ok_or_else(|| anyhow!("{path}: API spec is missing the required `info` object"))?;
+    let paths = match spec.get("paths") {
+        None | Some(Value::Null) => None,
+        Some(Value::Object(paths)) => Some(paths),
+        Some(_) => bail!("{path}: `paths` must be an object"),
+    };
+
+    let api = ApiInfo {
+        title: str_field(info, "title")
+            .unwrap_or("Untitled API")
+            .to_string(),
+        version: str_field(info, "version").map(str::to_string),
+        spec_version: spec_version.to_string(),
+    };
+    let resolver = Resolver { root: &spec };
+
+    let mut endpoints = Vec::new();
+    for (route, item) in paths.into_iter().flatten() {
+        let item = resolver
+            .deref(item)
+            .and_then(Value::as_object)
+            .ok_or_else(|| anyhow!("{path}: path item {route} must be an object"))?;
+        for method in HTTP_METHODS {
+            let Some(operation) = item.get(method) else {
+                continue;
+            };
+            let operation = operation.as_object().ok_or_else(|| {
+                anyhow!(
+                    "{path}: operation {} {route} must be an object",
+                    method.to_uppercase()
+                )
+            })?;
+            endpoints.push(resolver.endpoint_document(&api, route, method, item, operation));
+        }
+    }
+
+    let mut documents = vec![overview_document(&spec, info, &api, &endpoints)];
+    documents.extend(endpoints);
+    Ok(documents)
+}
+
+/// Parse JSON, falling back to YAML
+fn parse_structured(content: &str) -> Result<Value> {
+    match serde_json::from_str::<Value>(content) {
+        Ok(value) => Ok(value),
+        Err(json_err) => {
+            let yaml: serde_yaml::Value = serde_yaml::from_str(content)
+                .map_err(|yaml_err| anyhow!("JSON: {json_err}; YAML: {yaml_err}"))?;
+            Ok(yaml_to_json(yaml))
+        }
+    }
+}
+
+/// Convert YAML to JSON, stringifying non-string mapping keys such as
+/// response codes (`200:`)
+fn yaml_to_json(value: serde_yaml::Value) -> Value {
+    match value {
+        serde_yaml::Value::Null => Value::Null,
+        serde_yaml::Value::Bool(b) => Value::Bool(b),
+        serde_yaml::Value::Number(n) => serde_json::to_value(n).unwrap_or(Value::Null),
+        serde_yaml::Value::String(s) => Value::String(s),
+        serde_yaml::Value::Sequence(items) => {
+            Value::Array(items.into_iter().map(yaml_to_json).collect())
+        }
+        serde_yaml::Value::Mapping(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| {
+                    let key = match yaml_to_json(k) {
+                        Value::String(s) => s,
+                        other => other.to_string(),
+                    };
+                    (key, yaml_to_json(v))
+                })
+                .collect(),
+        ),
+        serde_yaml::Value::Tagged(tagged) => yaml_to_json(tagged.value),
+    }
+}
+
+/// The `openapi` or `swagger` version string of a spec
+fn spec_version(value: &Value) -> Option<&str> {
+    value
+        .get("openapi")
+        .or_else(|| value.get("swagger"))
+        .and_then(Value::as_str)
+}
+
+fn str_field<'a>(map: &'a Map<String, Value>, key: &str) -> Option<&'a str> {
+    map.get(key)
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty())
+}
+
+struct ApiInfo {
+    title: String,
+    version: Option<String>,
+    spec_version: String,
+}
+
+impl ApiInfo {
+    fn base_metadata(&self) -> HashMap<String, String> {
+        let mut metadata = HashMap::from([
+            ("format".to_string(), "api_spec".to_string()),
+            ("spec_version".to_string(), self.spec_version.clone()),
+            ("title".to_string(), self.title.clone()),
+        ]);
+        if let Some(version) = &self.version {
+            metadata.insert("api_version".to_string(), version.clone());
+        }
+        metadata
+    }
+
+    fn label(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{} {version}", self.title),
+            None => self.title.clone(),
+        }
+    }
+}
+
+fn api_document(text_content: String, metadata: HashMap<String, String>) -> ParsedContent {
+    let estimated_tokens = UniversalParser::estimate_tokens(&text_content);
+    ParsedContent {
+        format: DocumentFormat::ApiSpec,
+        text_content,
+        structured_content: None,
+        metadata,
+        estimated_tokens: Some(estimated_tokens),
+    }
+}
+
+fn overview_document(
+    spec: &Value,
+    info: &Map<String, Value>,
+    api: &ApiInfo,
+    endpoints: &[ParsedContent],
+) -> ParsedContent {
+    let mut text = String::new();
+    let _ = writeln!(text, "API: {}", api.title);
+    if let Some(version) = &api.version {
+        let _ = writeln!(text, "Version: {version}");
+    }
+    let _ = writeln!(text, "Specification: {}", api.spec_version);
+    if let Some(description) = str_field(info, "description") {
+        let _ = writeln!(text, "\n{description}");
+    }
+
+    let servers = server_urls(spec);
+    if !servers.is_empty() {
+        text.push_str("\nServers:\n");
+        for server in &servers {
+            let _ = writeln!(text, "- {server}");
+        }
+    }
+
+    let schemes = spec
+        .pointer("/components/securitySchemes")
+        .or_else(|| spec.get("securityDefinitions"))
+        .and_then(Value::as_object);
+    if let Some(schemes) = schemes.filter(|s| !s.is_empty()) {
+        text.push_str("\nSecurity schemes:\n");
+        for (name, scheme) in schemes {
+            let _ = writeln!(text, "- {name}: {}", security_scheme_summary(scheme));
+        }
+    }
+
+    if let Some(tags) = spec.get("tags").and_then(Value::as_array) {
+        let tags: Vec<_> = tags.iter().filter_map(Value::as_object).collect();
+        if !tags.is_empty() {
+            text.push_str("\nTags:\n");
+            for tag in tags {
+                let name = str_field(tag, "name").unwrap_or("unnamed");
+                match str_field(tag, "description") {
+                    Some(description) => {
+                        let _ = writeln!(text, "- {name}: {description}");
+                    }
+                    None => {
+                        let _ = writeln!(text, "- {name}");
+                    }
+                }
+            }
+        }
+    }
+
+    if !endpoints.is_empty() {
+        text.push_str("\nEndpoints:\n");
+        for endpoint in endpoints {
+            let name = endpoint
+                .metadata
+                .get(ENDPOINT_KEY)
+                .map_or("", String::as_str);
+            match endpoint.metadata.get("summary") {
+                Some(summary) => {
+                    let _ = writeln!(text, "- {name}: {summary}");
+                }
+                None => {
+                    let _ = writeln!(text, "- {name}");
+                }
+            }
+        }
+    }
+
+    let mut metadata = api.base_metadata();
+    metadata.insert("topic".to_string(), "overview".to_string());
+    metadata.insert("endpoint_count".to_string(), endpoints.len().to_string());
+    api_document(text, metadata)
+}
+
+/// Server URLs from `servers` (3.x) or `schemes` + `host` + `basePath` (2.0)
+fn server_urls(spec: &Value) -> Vec<String> {
+    if let Some(servers) = spec.get("servers").and_then(Value::as_array) {
+        return servers
+            .iter()
+            .filter_map(|s| {
+                let url = s.get("url")?.as_str()?;
+                Some(match s.get("description").and_then(Value::as_str) {
+                    Some(description) => format!("{url} ({description})"),
+                    None => url.to_string(),
+                })
+            })
+            .collect();
+    }
+
+    let Some(host) = spec.get("host").and_then(Value::as_str) else {
+        return Vec::new();
+    };
+    let base_path = spec.get("basePath").and_then(Value::as_str).unwrap_or("");
+    let schemes: Vec<&str> = spec
+        .get("schemes")
+        .and_then(Value::as_array)
+        .map(|s| s.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    if schemes.is_empty() {
+        return vec![format!("{host}{base_path}")];
+    }
+    schemes
+        .iter()
+        .map(|scheme| format!("{scheme}://{host}{base_path}"))
+        .collect()
+}
+
+fn security_scheme_summary(scheme: &Value) -> String {
+    let field = |key: &str| scheme.get(key).and_then(Value::as_str);
+    let mut parts = vec![field("type").unwrap_or("unknown").to_string()];
+    if let Some(s) = field("scheme") {
+        parts.push(format!("scheme {s}"));
+    }
+    if let Some(f) = field("bearerFormat") {
+        parts.push(format!("format {f}"));
+    }
+    if let (Some(name), Some(location)) = (field("name"), field("in")) {
+        parts.push(format!("{location} parameter `{name}`"));
+    }
+    if let Some(flow) = field("flow") {
+        parts.push(format!("flow {flow}"));
+    } else if let Some(flows) = scheme.get("flows").and_then(Value::as_object) {
+        let names: Vec<&str> = flows.keys().map(String::as_str).collect();
+        parts.push(format!("flows {}", names.join(", ")));
+    }
+    let mut summary = parts.join(", ");
+    if let Some(description) = field("description") {
+        let _ = write!(summary, " - {description}");
+    }
+    summary
+}
+
+/// Resolves local `$ref` pointers against the spec root
+struct Resolver<'a> {
+    root: &'a Value,
+}
+
+impl<'a> Resolver<'a> {
+    fn lookup(&self, reference: &str) -> Option<&'a Value> {
+        self.root.pointer(reference.strip_prefix('#')?)
+    }
+
+    /// Follow a chain of `$ref`s; `None` if unresolvable or circular
+    fn deref(&self, value: &'a Value) -> Option<&'a Value> {
+        let mut current = value;
+        let mut seen = HashSet::new();
+        while let Some(reference) = current.get("$ref").and_then(Value::as_str) {
+            if !seen.insert(reference) {
+                return None;
+            }
+            current = self.lookup(reference)?;
+        }
+        Some(current)
+    }
+
+    fn endpoint_document(
+        &self,
+        api: &ApiInfo,
+        route: &str,
+        method: &str,
+        item: &'a Map<String, Value>,
+        operation: &'a Map<String, Value>,
+    ) -> ParsedContent {
+        let endpoint = format!("{} {route}", method.to_uppercase());
+        let tags: Vec<&str> = operation
+            .get("tags")
+            .and_then(Value::as_array)
+            .map(|t| t.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+
+        let mut text = String::new();
+        let _ = writeln!(text, "{endpoint}");
+        let _ = writeln!(text, "API: {}", api.label());
+        if let Some(id) = str_field(operation, "operationId") {
+            let _ = writeln!(text, "Operation: {id}");
+        }
+        let summary = str_field(operation, "summary").or_else(|| str_field(item, "summary"));
+        if let Some(summary) = summary {
+            let _ = writeln!(text, "Summary: {summary}");
+        }
+        if !tags.is_empty() {
+            let _ = writeln!(text, "Tags: {}", tags.join(", "));
+        }
+        if operation.get("deprecated").and_then(Value::as_bool) == Some(true) {
+            text.push_str("Deprecated: yes\n");
+        }
+        let description =
+            str_field(operation, "description").or_else(|| str_field(item, "description"));
+        if let Some(description) = description {
+            let _ = writeln!(text, "\n{description}");
+        }
+
+        let parameters = self.parameters(item, operation);
+        let (body_params, parameters): (Vec<_>, Vec<_>) = parameters
+            .into_iter()
+            .partition(|p| p.get("in").and_then(Value::as_str) == Some("body"));
+
+        if !parameters.is_empty() {
+            text.push_str("\nParameters:\n");
+            for parameter in parameters {
+                self.write_parameter(parameter, &mut text);
+            }
+        }
+
+        if let Some(body) = operation.get("requestBody") {
+            self.write_request_body(body, &mut text);
+        } else if let Some(body) = body_params.first() {
+            // Swagger 2.0 body parameter
+            let mut flags = Vec::new();
+            if body.get("required").and_then(Value::as_bool) == Some(true) {
+                flags.push("required".to_string());
+            }
+            self.write_body_schema(
+                "Request body",
+                &flags,
+                body.get("description").and_then(Value::as_str),
+                body.get("schema"),
+                &mut text,
+            );
+        }
+
+        if let Some(responses) = operation.get("responses").and_then(Value::as_object) {
+            if !responses.is_empty() {
+                text.push_str("\nResponses:\n");
+                for (status, response) in responses {
+                    self.write_response(status, response, &mut text);
+                }
+            }
+        }
+
+        let mut metadata = api.base_metadata();
+        metadata.insert(ENDPOINT_KEY.to_string(), endpoint);
+        metadata.insert("method".to_string(), method.to_uppercase());
+        metadata.insert("path".to_string(), route.to_string());
+        metadata.insert("topic".to_string(), route_topic(route));
+        if let Some(tag) = tags.first() {
+            metadata.insert("category".to_string(), (*tag).to_string());
+            metadata.insert("tags".to_string(), tags.join(","));
+        }
+        if let Some(id) = str_field(operation, "operationId") {
+            metadata.insert("operation_id".to_string(), id.to_string());
+        }
+        if let Some(summary) = summary {
+            metadata.insert("summary".to_string(), summary.to_string());
+        }
+        api_document(text, metadata)
+    }
+
+    /// Path-level parameters overridden by operation-level ones (by name + location)
+    fn parameters(
+        &self,
+        item: &'a Map<String, Value>,
+        operation: &'a Map<String, Value>,
+    ) -> Vec<&'a Value> {
+        let mut merged: Vec<&'a Value> = Vec::new();
+        let lists = [item.get("parameters"), operation.get("parameters")];
+        for parameter in lists
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_array)
+            .flatten()
+        {
+            let Some(parameter) = self.deref(parameter) else {
+                continue;
+            };
+            let key = |p: &Value| {
+                (
+                    p.get("name").and_then(Value::as_str).map(str::to_string),
+                    p.get("in").and_then(Value::as_str).map(str::to_string),
+                )
+            };
+            let parameter_key = key(parameter);
+            merged.retain(|p| key(p) != parameter_key);
+            merged.push(parameter);
+        }
+        merged
+    }
+
+    fn write_parameter(&self, parameter: &'a Value, out: &mut String) {
+        let name = parameter
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or("unnamed");
+        let mut flags = Vec::new();
+        if let Some(location) = parameter.get("in").and_then(Value::as_str) {
+            flags.push(location.to_string());
+        }
+        // 3.x puts the type under `schema`, 2.0 on the parameter itself
+        let schema = parameter.get("schema").unwrap_or(parameter);
+        flags.push(self.label(schema, &[]));
+        if parameter.get("required").and_then(Value::as_bool) == Some(true) {
+            flags.push("required".to_string());
+        }
+        let _ = write!(out, "- {name} ({})", flags.join(", "));
+        if let Some(description) = parameter.get("description").and_then(Value::as_str) {
+            let _ = write!(out, ": {description}");
+        }
+        out.push('\n');
+        self.write_schema(schema, 2, &mut Vec::new(), out);
+    }
+
+    fn write_request_body(&self, body: &'a Value, out: &mut String) {
+        let Some(body) = self.deref(body) else {
+            out.push_str("\nRequest body: unresolved reference\n");
+            return;
+        };
+        let mut flags = Vec::new();
+        if body.get("required").and_then(Value::as_bool) == Some(true) {
+            flags.push("required".to_string());
+        }
+        let description = body.get("description").and_then(Value::as_str);
+        match body.get("content").and_then(Value::as_object) {
+            Some(content) if !content.is_empty() => {
+                for (media_type, media) in content {
+                    let mut flags = flags.clone();
+                    flags.insert(0, media_type.clone());
+                    self.write_body_schema(
+                        "Request body",
+                        &flags,
+                        description,
+                        media.get("schema"),
+                        out,
+                    );
+                }
+            }
+            _ => self.write_body_schema("Request body", &flags, description, None, out),
+        }
+    }
+
+    fn write_body_schema(
+        &self,
+        heading: &str,
+        flags: &[String],
+        description: Option<&str>,
+        schema: Option<&'a Value>,
+        out: &mut String,
+    ) {
+        let _ = write!(out, "\n{heading}");
+        if !flags.is_empty() {
+            let _ = write!(out, " ({})", flags.join(", "));
+        }
+        out.push_str(":\n");
+        if let Some(description) = description {
+            let _ = writeln!(out, "{description}");
+        }
+        if let Some(schema) = schema {
+            let _ = writeln!(out, "Schema: {}", self.label(schema, &[]));
+            self.write_schema(schema, 0, &mut Vec::new(), out);
+        }
+    }
+
+    fn write_response(&self, status: &str, response: &'a Value, out: &mut String) {
+        let Some(response) = self.deref(response) else {
+            let _ = writeln!(out, "- {status}: unresolved reference");
+            return;
+        };
+        let description = response
+            .get("description")
+            .and_then(Value::as_str)
+            .unwrap_or("");
+
+        // 3.x: content map; 2.0: schema directly on the response
+        let mut schemas: Vec<(Option<&str>, &'a Value)> = Vec::new();
+        if let Some(content) = response.get("content").and_then(Value::as_object) {
+            for (media_type, media) in content {
+                if let Some(schema) = media.get("schema") {
+                    schemas.push((Some(media_type.as_str()), schema));
+                }
+            }
+        } else if let Some(schema) = response.get("schema") {
+            schemas.push((None, schema));
+        }
+
+        let _ = writeln!(out, "- {status}: {description}");
+        for (media_type, schema) in schemas {
+            let label = self.label(schema, &[]);
+            match media_type {
+                Some(media_type) => {
+                    let _ = writeln!(out, "  {media_type}: {label}");
+                }
+                None => {
+                    let _ = writeln!(out, "  Schema: {label}");
+                }
+            }
+            self.write_schema(schema, 4, &mut Vec::new(), out);
+        }
+    }
+
+    /// Short type description, e.g. `string, date-time` or `array of Pet`
+    fn label(&self, schema: &'a Value, stack: &[&'a str]) -> String {
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let name = reference.rsplit('/').next().unwrap_or(reference);
+            if stack.contains(&reference) {
+                return format!("{name}, circular reference");
+            }
+            if self.lookup(reference).is_none() {
+                return format!("{name}, unresolved reference");
+            }
+            return name.to_string();
+        }
+
+        let mut label = match schema.get("type") {
+            Some(Value::String(t)) if t == "array" => match schema.get("items") {
+                Some(items) => format!("array of {}", self.label(items, stack)),
+                None => "array".to_string(),
+            },
+            Some(Value::String(t)) => t.clone(),
+            Some(Value::Array(types)) => types
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join(" | "),
+            _ if schema.get("properties").is_some() => "object".to_string(),
+            _ if schema.get("allOf").is_some() => "allOf".to_string(),
+            _ if schema.get("oneOf").is_some() => "oneOf".to_string(),
+            _ if schema.get("anyOf").is_some() => "anyOf".to_string(),
+            _ => "any".to_string(),
+        };
+        if let Some(format) = schema.get("format").and_then(Value::as_str) {
+            let _ = write!(label, ", {format}");
+        }
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            let values: Vec<String> = values
+                .iter()
+                .map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string))
+                .collect();
+            let _ = write!(label, ", one of: {}", values.join(" | "));
+        }
+        label
+    }
+
+    /// Write the fields of `schema` as an indented list
+    ///
+    /// `stack` holds the `$ref`s currently being expanded so that circular
+    /// references stop after one level.
+    fn write_schema(
+        &self,
+        schema: &'a Value,
+        indent: usize,
+        stack: &mut Vec<&'a str>,
+        out: &mut String,
+    ) {
+        let mut pushed = false;
+        let schema = match schema.get("$ref").and_then(Value::as_str) {
+            Some(reference) => {
+                if stack.contains(&reference) || stack.len() >= MAX_REF_DEPTH {
+                    return;
+                }
+                let Some(target) = self.lookup(reference) else {
+                    return;
+                };
+                stack.push(reference);
+                pushed = true;
+                target
+            }
+            None => schema,
+        };
+        let pad = " ".repeat(indent);
+
+        if let Some(parts) = schema.get("allOf").and_then(Value::as_array) {
+            for part in parts {
+                self.write_schema(part, indent, stack, out);
+            }
+        }
+        for key in ["oneOf", "anyOf"] {
+            if let Some(variants) = schema.get(key).and_then(Value::as_array) {
+                let _ = writeln!(out, "{pad}{key}:");
+                for variant in variants {
+                    let _ = writeln!(out, "{pad}  - {}", self.label(variant, stack));
+                    self.write_schema(variant, indent + 4, stack, out);
+                }
+            }
+        }
+
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            let required: HashSet<&str> = schema
+                .get("required")
+                .and_then(Value::as_array)
+                .map(|r| r.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            for (name, property) in properties {
+                let mut label = self.label(property, stack);
+                if required.contains(name.as_str()) {
+                    label.push_str(", required");
+                }
+                let _ = write!(out, "{pad}- {name} ({label})");
+                if let Some(description) = property.get("description").and_then(Value::as_str) {
+                    let _ = write!(out, ": {description}");
+                }
+                out.push('\n');
+                self.write_schema(property, indent + 2, stack, out);
+            }
+        }
+
+        if let Some(items) = schema.get("items") {
+            self.write_schema(items, indent, stack, out);
+        }
+        if let Some(extra) = schema.get("additionalProperties").filter(|v| v.is_object()) {
+            let _ = writeln!(out, "{pad}- <any key> ({})", self.label(extra, stack));
+            self.write_schema(extra, indent + 2, stack, out);
+        }
+
+        if pushed {
+            stack.pop();
+        }
+    }
+}
+
+/// First literal path segment, used as the document topic (`/pets/{id}` -> `pets`)
+fn route_topic(route: &str) -> String {
+    route
+        .split('/')
+        .find(|s| !s.is_empty() && !s.starts_with('{'))
+        .unwrap_or("root")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PETSTORE_YAML: &str = r##"
+openapi: 3.0.3
+info:
+  title: Petstore
+  version: 1.2.0
+  description: A sample pet store.
+servers:
+  - url: https://petstore.example.com/v1
+components:
+  securitySchemes:
+    apiKey:
+      type: apiKey
+      in: header
+      name: X-API-Key
+  schemas:
+    Pet:
+      type: object
+      required: [id, name]
+      properties:
+        id:
+          type: integer
+          format: int64
+        name:
+          type: string
+        parent:
+          $ref: '#/components/schemas/Pet'
+        owner:
+          $ref: '#/components/schemas/Owner'
+    Owner:
+      type: object
+      properties:
+        pets:
+          type: array
+          items:
+            $ref: '#/components/schemas/Pet'
+  parameters:
+    PetId:
+      name: petId
+      in: path
+      required: true
+      schema:
+        type: string
+paths:
+  /pets:
+    get:
+      tags: [pets]
+      summary: List pets
+      operationId: listPets
+      parameters:
+        - name: limit
+          in: query
+          schema:
+            type: integer
+      responses:
+        200:
+          description: A list of pets
+          content:
+            application/json:
+              schema:
+                type: array
+                items:
+                  $ref: '#/components/schemas/Pet'
+    post:
+      tags: [pets, admin]
+      summary: Create a pet
+      requestBody:
+        required: true
+        content:
+          application/json:
+            schema:
+              $ref: '#/components/schemas/Pet'
+      responses:
+        '201':
+          description: Created
+  /pets/{petId}:
+    parameters:
+      - $ref: '#/components/parameters/PetId'
+    get:
+      tags: [pets]
+      summary: Show a pet
+      responses:
+        default:
+          description: unexpected error
+"##;
+
+    #[test]
+    fn test_openapi_yaml_one_document_per_operation() {
+        assert!(is_openapi(PETSTORE_YAML));
+        let docs = parse_openapi(PETSTORE_YAML, "petstore.yaml").unwrap();
+        assert_eq!(docs.len(), 4);
+
+        let overview = &docs[0];
+        assert_eq!(overview.metadata["topic"], "overview");
+        assert_eq!(overview.metadata["api_version"], "1.2.0");
+        assert!(overview
+            .text_content
+            .contains("https://petstore.example.com/v1"));
+        assert!(overview
+            .text_content
+            .contains("apiKey: apiKey, header parameter `X-API-Key`"));
+        assert!(overview.text_content.contains("- GET /pets: List pets"));
+
+        let list = &docs[1];
+        assert_eq!(list.metadata[ENDPOINT_KEY], "GET /pets");
+        assert_eq!(list.metadata["category"], "pets");
+        assert_eq!(list.metadata["topic"], "pets");
+        assert_eq!(list.metadata["api_version"], "1.2.0");
+        assert_eq!(list.metadata["operation_id"], "listPets");
+        assert!(list.text_content.contains("- limit (query, integer)"));
+        assert!(list.text_content.contains("- 200: A list of pets"));
+        assert!(list.text_content.contains("application/json: array of Pet"));
+        assert!(list
+            .text_content
+            .contains("- id (integer, int64, required)"));
+
+        let create = &docs[2];
+        assert_eq!(create.metadata["tags"], "pets,admin");
+        assert!(create
+            .text_content
+            .contains("Request body (application/json, required):"));
+
+        // Path-level parameter reference is resolved into the operation
+        let show = &docs[3];
+        assert_eq!(show.metadata["path"], "/pets/{petId}");
+        assert!(show
+            .text_content
+            .contains("- petId (path, string, required)"));
+    }
+
+    #[test]
+    fn test_circular_refs_terminate() {
+        let docs = parse_openapi(PETSTORE_YAML, "petstore.yaml").unwrap();
+        let list = &docs[1].text_content;
+        assert!(list.contains("- parent (Pet, circular reference)"));
+        // Pet -> Owner -> pets: array of Pet stops at the cycle
+        assert!(list.contains("- pets (array of Pet, circular reference)"));
+    }
+
+    #[test]
+    fn test_swagger_2_json() {
+        let spec = serde_json::json!({
+            "swagger": "2.0",
+            "info": {"title": "Legacy", "version": "v1"},
+            "host": "api.example.com",
+            "basePath": "/v1",
+            "schemes": ["https"],
+            "securityDefinitions": {"basic": {"type": "basic"}},
+            "definitions": {
+                "User": {"type": "object", "properties": {"email": {"type": "string"}}}
+            },
+            "paths": {
+                "/users": {
+                    "post": {
+                        "tags": ["users"],
+                        "parameters": [
+                            {"name": "body", "in": "body", "required": true,
+                             "schema": {"$ref": "#/definitions/User"}},
+                            {"name": "dry_run", "in": "query", "type": "boolean"}
+                        ],
+                        "responses": {
+                            "200": {"description": "OK", "schema": {"$ref": "#/definitions/User"}}
+                        }
+                    }
+                }
+            }
+        })
+        .to_string();
+
+        assert_eq!(DocumentFormat::from_content(&spec), DocumentFormat::ApiSpec);
+        let docs = parse_openapi(&spec, "legacy.json").unwrap();
+        assert_eq!(docs.len(), 2);
+        assert!(docs[0].text_content.contains("https://api.example.com/v1"));
+
+        let create = &docs[1];
+        assert_eq!(create.metadata["api_version"], "v1");
+        assert_eq!(create.metadata["category"], "users");
+        assert!(create.text_content.contains("- dry_run (query, boolean)"));
+        assert!(create.text_content.contains("Request body (required):"));
+        assert!(create.text_content.contains("Schema: User"));
+        assert!(create.text_content.contains("- email (string)"));
+        assert!(!create.text_content.contains("- body ("));
+    }
+
+    #[test]
+    fn test_malformed_specs() {
+        let err = parse_openapi("{not json: [", "bad.json").unwrap_err();
+        assert!(err.to_string().contains("as JSON or YAML"));
+
+        let err = parse_openapi(r#"{"name": "x"}"#, "plain.json").unwrap_err();
+        assert!(err.to_string().contains("missing `openapi` or `swagger`"));
+
+        let err = parse_openapi(r#"{"openapi": "3.0.0"}"#, "noinfo.json").unwrap_err();
+        assert!(err.to_string().contains("`info`"));
+
+        let err = parse_openapi(
+            r#"{"openapi": "3.0.0", "info": {"title": "t"}, "paths": {"/a": {"get": 1}}}"#,
+            "badop.json",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("GET /a must be an object"));
+
+        assert!(!is_openapi("key: value\nother: 1\n"));
+    }
+}
@@ -0,0 +1,159 @@
+//! PDF text extraction
+//!
+//! Text is extracted page by page with `pdf-extract`; images and other
+//! non-text content are ignored. Encrypted PDFs and PDFs without a text
+//! layer (scans) are rejected so they do not end up as empty documents.
+
+use super::{DocumentFormat, DocumentSection, ParsedContent, StructuredDocument, UniversalParser};
+use anyhow::{anyhow, bail, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::warn;
+
+/// Metadata key for the first page covered by a chunk (1-based)
+pub const PAGE_START_KEY: &str = "page_start";
+
+/// Metadata key for the last page covered by a chunk (1-based)
+pub const PAGE_END_KEY: &str = "page_end";
+
+/// Whether a file is a PDF, by `.pdf` extension or `%PDF` magic bytes
+#[must_use]
+pub fn is_pdf(bytes: &[u8], path: &str) -> bool {
+    bytes.starts_with(b"%PDF")
+        || Path::new(path)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+}
+
+/// Extract the text of each page
+///
+/// # Errors
+///
+/// Returns an error if the bytes are not a readable PDF, the PDF is
+/// encrypted, or no page has extractable text.
+pub fn extract_pages(bytes: &[u8], path: &str) -> Result<Vec<String>> {
+    if !bytes.starts_with(b"%PDF") {
+        bail!("{path} is not a PDF file (missing %PDF header)");
+    }
+    let mut document = pdf_extract::Document::load_mem(bytes)
+        .map_err(|e| anyhow!("Failed to read PDF {path}: {e}"))?;
+    if document.is_encrypted() && document.decrypt("").is_err() {
+        bail!("{path}: no extractable text (PDF is encrypted)");
+    }
+    let page_count = document.get_pages().len();
+
+    let pages = pdf_extract::extract_text_from_mem_by_pages(bytes)
+        .map_err(|e| anyhow!("Failed to extract text from PDF {path}: {e}"))?;
+    if pages.len() < page_count {
+        warn!(
+            "Extracted text from {} of {} pages of {}",
+            pages.len(),
+            page_count,
+            path
+        );
+    }
+
+    let pages: Vec<String> = pages.iter().map(|p| normalize_page(p)).collect();
+    if pages.iter().all(String::is_empty) {
+        bail!("{path}: no extractable text (scanned or image-only PDF)");
+    }
+    Ok(pages)
+}
+
+/// Trim each line and collapse runs of blank lines
+fn normalize_page(text: &str) -> String {
+    let mut result = String::new();
+    let mut blank = false;
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            blank = !result.is_empty();
+            continue;
+        }
+        if blank {
+            result.push('\n');
+            blank = false;
+        }
+        if !result.is_empty() {
+            result.push('\n');
+        }
+        result.push_str(line);
+    }
+    result
+}
+
+/// Parse a PDF into one section per page
+///
+/// # Errors
+///
+/// Returns an error under the same conditions as [`extract_pages`].
+pub fn parse_pdf(bytes: &[u8], path: &str) -> Result<ParsedContent> {
+    let pages = extract_pages(bytes, path)?;
+
+    let sections: Vec<DocumentSection> = pages
+        .iter()
+        .enumerate()
+        .filter(|(_, text)| !text.is_empty())
+        .map(|(i, text)| DocumentSection {
+            level: 1,
+            title: format!("Page {}", i + 1),
+            content: text.clone(),
+            subsections: Vec::new(),
+        })
+        .collect();
+    let text_content = sections
+        .iter()
+        .map(|s| s.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let metadata = HashMap::from([
+        ("format".to_string(), "pdf".to_string()),
+        ("page_count".to_string(), pages.len().to_string()),
+    ]);
+    let estimated_tokens = UniversalParser::estimate_tokens(&text_content);
+
+    Ok(ParsedContent {
+        format: DocumentFormat::Pdf,
+        text_content,
+        structured_content: Some(StructuredDocument {
+            title: None,
+            toc: Vec::new(),
+            sections,
+            code_blocks: Vec::new(),
+            links: Vec::new(),
+        }),
+        metadata,
+        estimated_tokens: Some(estimated_tokens),
+    })
+}
+
+/// Page number of a section titled `Page N`
+pub(super) fn section_page(section: &DocumentSection) -> Option<usize> {
+    section.title.strip_prefix("Page ")?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_pdf() {
+        assert!(is_pdf(b"%PDF-1.7\n", "download"));
+        assert!(is_pdf(b"", "manual.PDF"));
+        assert!(!is_pdf(b"# Title", "README.md"));
+    }
+
+    #[test]
+    fn test_normalize_page() {
+        assert_eq!(
+            normalize_page("\n\n  Title  \n\n\n\nBody line\n  more \n\n"),
+            "Title\n\nBody line\nmore"
+        );
+    }
+
+    #[test]
+    fn test_not_a_pdf() {
+        let err = extract_pages(b"plain text", "notes.pdf").unwrap_err();
+        assert!(err.to_string().contains("missing %PDF header"));
+    }
+}
@@ -0,0 +1,301 @@
+//! Shallow git checkouts for repository ingestion
+//!
+//! Repositories are cloned with the `git` binary (depth 1, no submodules)
+//! into a temporary directory that is removed when the [`RepoCheckout`] is
+//! dropped. Private HTTPS repositories authenticate with a token from
+//! `LOADER_GIT_TOKEN` (falling back to `GITHUB_TOKEN`), passed to git through
+//! environment config so it never appears in process arguments. The token is
+//! only sent to `github.com`, or to the host named by `LOADER_GIT_TOKEN_HOST`.
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine as _;
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::{info, warn};
+
+/// Token used for private repositories
+fn git_token() -> Option<String> {
+    ["LOADER_GIT_TOKEN", "GITHUB_TOKEN"]
+        .iter()
+        .find_map(|key| std::env::var(key).ok())
+        .filter(|t| !t.trim().is_empty())
+}
+
+/// Host the token is sent to: `LOADER_GIT_TOKEN_HOST`, else `github.com`
+fn git_token_host() -> String {
+    std::env::var("LOADER_GIT_TOKEN_HOST")
+        .ok()
+        .map(|h| h.trim().to_ascii_lowercase())
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "github.com".to_string())
+}
+
+/// Whether `url` is an HTTPS URL on `token_host`, so it may carry the token
+fn url_accepts_token(url: &str, token_host: &str) -> bool {
+    url::Url::parse(url).is_ok_and(|parsed| {
+        parsed.scheme() == "https"
+            && parsed
+                .host_str()
+                .is_some_and(|host| host.eq_ignore_ascii_case(token_host))
+    })
+}
+
+/// `git clone` of `url` into `dir`, authenticated with `token` if given
+fn clone_command(url: &str, branch: Option<&str>, dir: &Path, token: Option<&str>) -> Command {
+    let mut command = Command::new("git");
+    command
+        .args(["clone", "--depth", "1", "--single-branch", "--no-tags"])
+        .env("GIT_TERMINAL_PROMPT", "0");
+    if let Some(branch) = branch {
+        command.args(["--branch", branch]);
+    }
+    // `--` keeps a URL such as `--upload-pack=...` from being read as an option
+    command.arg("--").arg(url).arg(dir);
+
+    if let Some(token) = token {
+        let credentials =
+            base64::engine::general_purpose::STANDARD.encode(format!("x-access-token:{token}"));
+        command
+            .env("GIT_CONFIG_COUNT", "1")
+            .env("GIT_CONFIG_KEY_0", "http.extraHeader")
+            .env(
+                "GIT_CONFIG_VALUE_0",
+                format!("Authorization: Basic {credentials}"),
+            );
+    }
+    command
+}
+
+/// A cloned repository in a temporary directory
+#[derive(Debug)]
+pub struct RepoCheckout {
+    /// Repository URL as given
+    pub url: String,
+    /// Checked-out branch or tag
+    pub branch: String,
+    /// Full commit SHA of the checkout
+    pub commit_sha: String,
+    dir: PathBuf,
+}
+
+impl RepoCheckout {
+    /// Root directory of the working tree
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Provenance recorded on every document from this checkout
+    #[must_use]
+    pub fn metadata(&self) -> Map<String, Value> {
+        let mut metadata = Map::new();
+        metadata.insert("repo_url".to_string(), json!(self.url));
+        metadata.insert("commit_sha".to_string(), json!(self.commit_sha));
+        metadata.insert("branch".to_string(), json!(self.branch));
+        metadata
+    }
+
+    /// Browsable URL for a file in the checkout, for GitHub repositories
+    #[must_use]
+    pub fn file_url(&self, relative_path: &str) -> String {
+        let base = self.url.trim_end_matches('/').trim_end_matches(".git");
+        if base.starts_with("https://github.com/") {
+            format!("{base}/blob/{}/{relative_path}", self.commit_sha)
+        } else {
+            format!("{base}#{relative_path}")
+        }
+    }
+}
+
+impl Drop for RepoCheckout {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.dir) {
+            warn!("Failed to remove checkout {}: {}", self.dir.display(), e);
+        }
+    }
+}
+
+/// Shallow-clone `url` at `branch` (the remote default when `None`)
+///
+/// # Errors
+///
+/// Returns an error if git is unavailable, the clone fails (bad URL, missing
+/// branch, authentication), or the commit cannot be resolved.
+pub fn clone_repository(url: &str, branch: Option<&str>) -> Result<RepoCheckout> {
+    let dir = std::env::temp_dir().join(format!("loader-repo-{}", uuid::Uuid::new_v4()));
+    info!("📥 Cloning {} into {}", url, dir.display());
+
+    let token = git_token().filter(|_| url_accepts_token(url, &git_token_host()));
+    let output = clone_command(url, branch, &dir, token.as_deref())
+        .output()
+        .context("Failed to run git; is it installed?")?;
+    // Construct before checking status so a partial clone is cleaned up
+    let mut checkout = RepoCheckout {
+        url: url.to_string(),
+        branch: branch.unwrap_or_default().to_string(),
+        commit_sha: String::new(),
+        dir,
+    };
+    if !output.status.success() {
+        let mut stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if let Some(token) = &token {
+            stderr = stderr.replace(token.as_str(), "***");
+        }
+        bail!("git clone of {url} failed: {stderr}");
+    }
+
+    checkout.commit_sha = git_output(checkout.path(), &["rev-parse", "HEAD"])?;
+    if checkout.branch.is_empty() {
+        checkout.branch = git_output(checkout.path(), &["rev-parse", "--abbrev-ref", "HEAD"])?;
+    }
+    info!(
+        "✓ Cloned {} at {} ({})",
+        url, checkout.commit_sha, checkout.branch
+    );
+    Ok(checkout)
+}
+
+fn git_output(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?} failed");
+    }
+
+    #[test]
+    fn test_clone_local_repository() {
+        let source = std::env::temp_dir().join(format!("loader-src-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&source).unwrap();
+        git(&source, &["init", "-q", "-b", "main"]);
+        std::fs::write(source.join("README.md"), "# Demo\n").unwrap();
+        git(&source, &["add", "."]);
+        git(
+            &source,
+            &[
+                "-c",
+                "user.name=test",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "-q",
+                "-m",
+                "init",
+            ],
+        );
+
+        let url = format!("file://{}", source.display());
+        let checkout = clone_repository(&url, None).unwrap();
+        let dir = checkout.path().to_path_buf();
+        assert!(dir.join("README.md").exists());
+        assert_eq!(checkout.branch, "main");
+        assert_eq!(checkout.commit_sha.len(), 40);
+        assert_eq!(checkout.metadata()["repo_url"], json!(url));
+
+        drop(checkout);
+        assert!(!dir.exists());
+
+        let err = clone_repository(&url, Some("no-such-branch")).unwrap_err();
+        assert!(err.to_string().contains("git clone of"));
+        std::fs::remove_dir_all(&source).unwrap();
+    }
+
+    /// Value `command` sets for the environment variable `key`
+    fn command_env(command: &Command, key: &str) -> Option<String> {
+        command
+            .get_envs()
+            .find(|(k, _)| *k == key)
+            .and_then(|(_, v)| v.map(|v| v.to_string_lossy().into_owned()))
+    }
+
+    #[test]
+    fn test_token_only_sent_to_token_host() {
+        assert!(url_accepts_token(
+            "https://github.com/acme/widgets.git",
+            "github.com"
+        ));
+        assert!(url_accepts_token(
+            "https://GitHub.com/acme/widgets",
+            "github.com"
+        ));
+        assert!(url_accepts_token(
+            "https://git.example.com/acme/widgets.git",
+            "git.example.com"
+        ));
+        assert!(!url_accepts_token(
+            "https://evil.example.com/acme/widgets.git",
+            "github.com"
+        ));
+        assert!(!url_accepts_token(
+            "https://github.com.evil.example.com/acme/widgets.git",
+            "github.com"
+        ));
+        assert!(!url_accepts_token(
+            "https://user@evil.example.com/github.com/widgets",
+            "github.com"
+        ));
+        assert!(!url_accepts_token(
+            "http://github.com/acme/widgets.git",
+            "github.com"
+        ));
+        assert!(!url_accepts_token("file:///tmp/github.com", "github.com"));
+    }
+
+    #[test]
+    fn test_clone_of_other_host_has_no_auth_header() {
+        let dir = std::env::temp_dir().join("loader-repo-test-auth-header");
+        let url = "https://git.example.com/acme/widgets.git";
+
+        let token = Some("secret-token").filter(|_| url_accepts_token(url, "github.com"));
+        let command = clone_command(url, None, &dir, token);
+        assert_eq!(command_env(&command, "GIT_CONFIG_KEY_0"), None);
+        assert_eq!(command_env(&command, "GIT_CONFIG_VALUE_0"), None);
+
+        let token = Some("secret-token").filter(|_| url_accepts_token(url, "git.example.com"));
+        let command = clone_command(url, None, &dir, token);
+        assert_eq!(
+            command_env(&command, "GIT_CONFIG_KEY_0").as_deref(),
+            Some("http.extraHeader")
+        );
+        assert!(command_env(&command, "GIT_CONFIG_VALUE_0")
+            .is_some_and(|v| v.starts_with("Authorization: Basic ")));
+    }
+
+    #[test]
+    fn test_file_url() {
+        let checkout = RepoCheckout {
+            url: "https://github.com/acme/widgets.git".to_string(),
+            branch: "main".to_string(),
+            commit_sha: "abc123".to_string(),
+            dir: std::env::temp_dir().join("loader-repo-test-file-url"),
+        };
+        assert_eq!(
+            checkout.file_url("docs/intro.md"),
+            "https://github.com/acme/widgets/blob/abc123/docs/intro.md"
+        );
+    }
+}
@@ -0,0 +1,467 @@
+//! Selecting the files a local or repository ingest parses
+//!
+//! A scan walks a directory for files with the requested extensions and
+//! drops what would only bloat the corpus: paths matching an exclude glob
+//! (dependency and build directories by default), symlinks, files over a
+//! size limit and files with too little text. With `prioritize` the
+//! remaining files are ranked by how likely they are to be documentation and
+//! only the top `max_files` are kept. [`ScanSummary`] counts what each rule
+//! removed.
+
+use anyhow::Result;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+/// Exclude patterns applied unless the caller passes its own
+pub const DEFAULT_EXCLUDES: &[&str] = &["node_modules", "target", "vendor"];
+
+/// Extensions whose files are scored by their share of comment lines
+const SOURCE_EXTENSIONS: &[&str] = &["rs", "py", "js", "ts", "go", "c", "h", "cpp", "java"];
+
+/// Directory names whose files rank ahead of the rest
+const DOC_DIRECTORIES: &[&str] = &["docs", "doc", "documentation", "guides"];
+
+/// Which files a scan keeps
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// Extensions to include, without the dot
+    pub extensions: Vec<String>,
+    /// Descend into subdirectories
+    pub recursive: bool,
+    /// Glob patterns of paths to skip; patterns without `/` match any single
+    /// file or directory name, others the path relative to the scan root
+    pub exclude: Vec<String>,
+    /// Skip files larger than this many bytes
+    pub max_file_size: Option<u64>,
+    /// Skip text files with fewer non-whitespace characters than this
+    pub min_content_chars: usize,
+    /// Rank files by documentation value before applying `max_files`
+    pub prioritize: bool,
+    /// Keep at most this many files (the highest ranked with `prioritize`)
+    pub max_files: Option<usize>,
+}
+
+impl ScanOptions {
+    /// Options for `extensions` (comma-separated) with the default excludes
+    #[must_use]
+    pub fn new(extensions: &str, recursive: bool) -> Self {
+        Self {
+            extensions: extensions
+                .split(',')
+                .map(str::trim)
+                .filter(|e| !e.is_empty())
+                .map(ToString::to_string)
+                .collect(),
+            recursive,
+            exclude: DEFAULT_EXCLUDES.iter().map(ToString::to_string).collect(),
+            max_file_size: None,
+            min_content_chars: 0,
+            prioritize: false,
+            max_files: None,
+        }
+    }
+}
+
+/// Files a scan kept and how many each rule removed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanSummary {
+    /// Files or directories matching an exclude pattern (a directory counts once)
+    pub excluded_by_pattern: usize,
+    /// Symlinked files and directories
+    pub symlinks: usize,
+    /// Files over `max_file_size`
+    pub too_large: usize,
+    /// Files under `min_content_chars`
+    pub too_short: usize,
+    /// Files dropped by `max_files`
+    pub over_limit: usize,
+}
+
+impl fmt::Display for ScanSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "excluded {} by pattern, {} symlinks, {} too large, {} too short, {} over the file limit",
+            self.excluded_by_pattern, self.symlinks, self.too_large, self.too_short, self.over_limit
+        )
+    }
+}
+
+/// Whether `text` matches the glob `pattern`
+///
+/// `*` matches within one path segment, `**` across segments and `?` one
+/// character.
+#[must_use]
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    fn matches(pattern: &[char], text: &[char]) -> bool {
+        match pattern.split_first() {
+            None => text.is_empty(),
+            Some(('*', rest)) if rest.first() == Some(&'*') => {
+                let rest = rest[1..].strip_prefix(&['/']).unwrap_or(&rest[1..]);
+                (0..=text.len()).any(|i| matches(rest, &text[i..]))
+            }
+            Some(('*', rest)) => (0..=text.len())
+                .take_while(|&i| i == 0 || text[i - 1] != '/')
+                .any(|i| matches(rest, &text[i..])),
+            Some(('?', rest)) => {
+                text.first().is_some_and(|&c| c != '/') && matches(rest, &text[1..])
+            }
+            Some((c, rest)) => text.first() == Some(c) && matches(rest, &text[1..]),
+        }
+    }
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    matches(&pattern, &text)
+}
+
+/// Whether `relative` (to the scan root) matches one of `patterns`
+fn is_excluded(relative: &Path, patterns: &[String]) -> bool {
+    let name = relative
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default();
+    let relative = relative.to_string_lossy().replace('\\', "/");
+    patterns.iter().filter(|p| !p.is_empty()).any(|p| {
+        if p.contains('/') {
+            glob_match(p.trim_start_matches("./"), &relative)
+        } else {
+            glob_match(p, &name)
+        }
+    })
+}
+
+/// Documentation value of a file: READMEs and docs directories first,
+/// shallow paths before deep ones, commented source before bare code
+fn priority(relative: &Path, content: Option<&str>) -> i64 {
+    let name = relative
+        .file_name()
+        .map(|n| n.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let extension = relative
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let directories: Vec<String> = relative
+        .parent()
+        .into_iter()
+        .flat_map(Path::components)
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_string_lossy().to_ascii_lowercase()),
+            _ => None,
+        })
+        .collect();
+
+    let mut score = 0;
+    if name.starts_with("readme") {
+        score += 100;
+    }
+    if directories
+        .iter()
+        .any(|d| DOC_DIRECTORIES.contains(&d.as_str()))
+    {
+        score += 50;
+    }
+    score -= 5 * i64::try_from(directories.len()).unwrap_or(i64::MAX / 5);
+
+    if SOURCE_EXTENSIONS.contains(&extension.as_str()) {
+        if let Some(content) = content {
+            let (mut comments, mut lines) = (0_i64, 0_i64);
+            for line in content.lines().map(str::trim).filter(|l| !l.is_empty()) {
+                lines += 1;
+                if ["//", "#", "/*", "*", "\"\"\""]
+                    .iter()
+                    .any(|marker| line.starts_with(marker))
+                {
+                    comments += 1;
+                }
+            }
+            // Up to 40 points for a file that is all comments
+            if lines > 0 {
+                score += comments * 40 / lines;
+            }
+        }
+    } else {
+        // Markdown, text and other non-code formats are documentation themselves
+        score += 20;
+    }
+    score
+}
+
+/// Walk `dir`, collecting files that pass every per-file rule
+fn walk(
+    root: &Path,
+    dir: &Path,
+    options: &ScanOptions,
+    files: &mut Vec<(PathBuf, i64)>,
+    summary: &mut ScanSummary,
+) -> Result<()> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    entries.sort();
+
+    for path in entries {
+        let relative = path.strip_prefix(root).unwrap_or(&path);
+        // Symlinks would ingest the same file twice or loop back into the tree
+        let metadata = std::fs::symlink_metadata(&path)?;
+        if metadata.file_type().is_symlink() {
+            summary.symlinks += 1;
+            continue;
+        }
+
+        if metadata.is_dir() {
+            // Submodule checkouts have a `.git` file pointing at the parent repository
+            if path.ends_with(".git") || path.join(".git").is_file() || !options.recursive {
+                continue;
+            }
+            if is_excluded(relative, &options.exclude) {
+                summary.excluded_by_pattern += 1;
+                continue;
+            }
+            walk(root, &path, options, files, summary)?;
+            continue;
+        }
+
+        let Some(extension) = path.extension().and_then(|e| e.to_str()) else {
+            continue;
+        };
+        if !options.extensions.iter().any(|e| e == extension) {
+            continue;
+        }
+        if is_excluded(relative, &options.exclude) {
+            summary.excluded_by_pattern += 1;
+            continue;
+        }
+        if options
+            .max_file_size
+            .is_some_and(|limit| metadata.len() > limit)
+        {
+            summary.too_large += 1;
+            continue;
+        }
+
+        // Binary formats such as PDF do not read as text and skip the content rules
+        let content = if options.min_content_chars > 0 || options.prioritize {
+            std::fs::read_to_string(&path).ok()
+        } else {
+            None
+        };
+        if let Some(text) = &content {
+            let chars = text.chars().filter(|c| !c.is_whitespace()).count();
+            if chars < options.min_content_chars {
+                summary.too_short += 1;
+                continue;
+            }
+        }
+
+        let score = if options.prioritize {
+            priority(relative, content.as_deref())
+        } else {
+            0
+        };
+        files.push((path, score));
+    }
+    Ok(())
+}
+
+/// Files under `root` selected by `options`, with the count each rule removed
+///
+/// Files come in path order, or by descending priority with `prioritize`.
+///
+/// # Errors
+///
+/// Returns an error if a directory cannot be read.
+pub fn scan_files(root: &Path, options: &ScanOptions) -> Result<(Vec<PathBuf>, ScanSummary)> {
+    let mut summary = ScanSummary::default();
+    let mut files = Vec::new();
+    if root.is_file() {
+        files.push((root.to_path_buf(), 0));
+    } else {
+        walk(root, root, options, &mut files, &mut summary)?;
+    }
+
+    if options.prioritize {
+        files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    }
+    if let Some(max_files) = options.max_files {
+        summary.over_limit = files.len().saturating_sub(max_files);
+        files.truncate(max_files);
+    }
+    Ok((files.into_iter().map(|(path, _)| path).collect(), summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{glob_match, scan_files, ScanOptions, ScanSummary};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// Synthetic repository: docs, sources, vendored code, a lockfile and a symlink
+    fn fixture() -> PathBuf {
+        let root = std::env::temp_dir().join(format!("loader-scan-{}", uuid::Uuid::new_v4()));
+        let files: &[(&str, &str)] = &[
+            (
+                "README.md",
+                "# Project\n\nWhat this project does and how to use it.",
+            ),
+            (
+                "docs/guide.md",
+                "# Guide\n\nA long walkthrough of every feature.",
+            ),
+            (
+                "docs/deep/nested/notes.md",
+                "# Notes\n\nDesign notes kept deep in the tree.",
+            ),
+            (
+                "src/lib.rs",
+                "/// Documented entry point\n/// More docs\npub fn run() {}\n",
+            ),
+            (
+                "src/bare.rs",
+                "pub fn a() {}\npub fn b() {}\npub fn c() {}\n",
+            ),
+            ("stub.md", "# x"),
+            ("big.txt", &"a large generated file ".repeat(200)),
+            (
+                "node_modules/pkg/README.md",
+                "# Vendored package readme with text",
+            ),
+            (
+                "vendor/lib/README.md",
+                "# Vendored library readme with text",
+            ),
+            (
+                "Cargo.lock",
+                "# lockfile contents that nobody wants to search",
+            ),
+            ("build/out.min.js", "function generated(){return 1}"),
+        ];
+        for (path, content) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root.join("docs/guide.md"), root.join("guide-link.md")).unwrap();
+        root
+    }
+
+    fn relative(root: &Path, files: &[PathBuf]) -> Vec<String> {
+        files
+            .iter()
+            .map(|f| {
+                f.strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect()
+    }
+
+    fn options() -> ScanOptions {
+        ScanOptions::new("md,rs,txt,lock,js", true)
+    }
+
+    #[test]
+    fn test_default_excludes_and_symlinks_are_skipped() {
+        let root = fixture();
+        let (files, summary) = scan_files(&root, &options()).unwrap();
+        let files = relative(&root, &files);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            files,
+            [
+                "Cargo.lock",
+                "README.md",
+                "big.txt",
+                "build/out.min.js",
+                "docs/deep/nested/notes.md",
+                "docs/guide.md",
+                "src/bare.rs",
+                "src/lib.rs",
+                "stub.md"
+            ]
+        );
+        assert_eq!(summary.excluded_by_pattern, 2);
+        #[cfg(unix)]
+        assert_eq!(summary.symlinks, 1);
+    }
+
+    #[test]
+    fn test_exclude_patterns_match_names_and_paths() {
+        let root = fixture();
+        let mut options = options();
+        options.exclude = vec!["*.lock".into(), "build/**".into(), "*.txt".into()];
+        let (files, summary) = scan_files(&root, &options).unwrap();
+        let files = relative(&root, &files);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(!files
+            .iter()
+            .any(|f| f.ends_with(".lock") || f.ends_with(".txt")));
+        assert!(!files.iter().any(|f| f.starts_with("build/")));
+        // Without the default excludes, vendored readmes come back
+        assert!(files.contains(&"node_modules/pkg/README.md".to_string()));
+        assert_eq!(summary.excluded_by_pattern, 3);
+    }
+
+    #[test]
+    fn test_size_and_content_limits_are_counted() {
+        let root = fixture();
+        let mut options = options();
+        options.max_file_size = Some(1024);
+        options.min_content_chars = 10;
+        let (files, summary) = scan_files(&root, &options).unwrap();
+        let files = relative(&root, &files);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(!files.contains(&"big.txt".to_string()));
+        assert!(!files.contains(&"stub.md".to_string()));
+        assert_eq!(summary.too_large, 1);
+        assert_eq!(summary.too_short, 1);
+    }
+
+    #[test]
+    fn test_prioritize_keeps_the_most_documented_files() {
+        let root = fixture();
+        let mut options = options();
+        options.exclude.push("*.lock".into());
+        options.exclude.push("build".into());
+        options.prioritize = true;
+        options.max_files = Some(4);
+        let (files, summary) = scan_files(&root, &options).unwrap();
+        let files = relative(&root, &files);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            files,
+            [
+                "README.md",
+                "docs/guide.md",
+                "docs/deep/nested/notes.md",
+                "src/lib.rs"
+            ]
+        );
+        assert_eq!(summary.over_limit, 3);
+        assert_eq!(
+            summary,
+            ScanSummary {
+                excluded_by_pattern: 4,
+                symlinks: summary.symlinks,
+                over_limit: 3,
+                ..ScanSummary::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_globs() {
+        assert!(glob_match("*.lock", "Cargo.lock"));
+        assert!(!glob_match("*.lock", "Cargo.toml"));
+        assert!(glob_match("build/**", "build/js/out.min.js"));
+        assert!(glob_match("**/generated/*.rs", "src/generated/api.rs"));
+        assert!(!glob_match("src/*.rs", "src/generated/api.rs"));
+        assert!(glob_match("READ?E.md", "README.md"));
+    }
+}
@@ -0,0 +1,597 @@
+//! Documentation websites crawled into `DocPage`s
+//!
+//! Sites such as Talos or Solana publish their docs as plain web pages. A
+//! crawl starts from a base URL and either reads the pages listed in a
+//! sitemap or follows links breadth-first up to a depth limit. It stays on
+//! the base URL's origin, under the allowed path prefixes and within
+//! `robots.txt`, and fetches through the same rate-limited client as crate
+//! crawls. Each page's main content (the largest `article`/`main` element,
+//! without navigation, headers and footers) becomes one document; sites with
+//! their own layout, such as Sphinx, name their content elements instead.
+
+use crate::loaders::DocPage;
+use anyhow::{anyhow, Result};
+use rust_crates::PageFetcher;
+use scraper::{ElementRef, Html, Node, Selector};
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use tracing::{info, warn};
+use url::Url;
+
+/// Product token matched against `User-agent` lines in robots.txt
+pub const ROBOTS_AGENT: &str = "doc-server-rust-loader";
+
+/// Item type of crawled pages
+pub const WEB_PAGE_ITEM_TYPE: &str = "web_page";
+
+/// Elements holding the main content of most documentation sites
+pub const MAIN_CONTENT_SELECTOR: &str = "article, main, [role=main]";
+
+/// Content elements of Sphinx pages (readthedocs), most specific first
+pub const SPHINX_CONTENT_SELECTORS: &[&str] = &["div[role=main]", ".document"];
+
+/// Elements whose text is never part of the main content
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "nav", "header", "footer", "aside", "script", "style", "noscript", "form", "template", "svg",
+    "button",
+];
+
+/// Elements that start a new line in extracted text
+const BLOCK_ELEMENTS: &[&str] = &[
+    "p",
+    "div",
+    "section",
+    "article",
+    "main",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "li",
+    "ul",
+    "ol",
+    "pre",
+    "table",
+    "tr",
+    "blockquote",
+    "dt",
+    "dd",
+    "br",
+    "hr",
+];
+
+/// Linked files that are not pages
+const NON_PAGE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "svg", "webp", "ico", "pdf", "zip", "gz", "tar", "css", "js",
+    "json", "xml", "woff", "woff2", "ttf", "mp4", "webm",
+];
+
+/// Sitemap index entries read before giving up on nested sitemaps
+const MAX_SITEMAPS: usize = 20;
+
+/// What a crawl fetches
+#[derive(Debug, Clone)]
+pub struct WebOptions {
+    /// Page the crawl starts from; also fixes the allowed origin
+    pub base_url: Url,
+    /// Sitemap listing the pages to fetch, relative to `base_url`; without one
+    /// links are followed
+    pub sitemap: Option<String>,
+    /// Path prefixes pages must start with; empty allows the base URL's
+    /// directory
+    pub allow_prefixes: Vec<String>,
+    /// Stop after this many pages
+    pub max_pages: usize,
+    /// Links followed from the base page when there is no sitemap
+    pub max_depth: usize,
+    /// CSS selectors of the main content, tried in order; the first that
+    /// matches with text wins
+    pub content_selectors: Vec<String>,
+}
+
+impl WebOptions {
+    /// Options for crawling `base_url` with default limits (100 pages, depth 3)
+    #[must_use]
+    pub fn new(base_url: Url) -> Self {
+        Self {
+            base_url,
+            sitemap: None,
+            allow_prefixes: Vec::new(),
+            max_pages: 100,
+            max_depth: 3,
+            content_selectors: vec![MAIN_CONTENT_SELECTOR.to_string()],
+        }
+    }
+
+    /// Path prefixes in effect
+    fn prefixes(&self) -> Vec<String> {
+        if !self.allow_prefixes.is_empty() {
+            return self.allow_prefixes.clone();
+        }
+        let path = self.base_url.path();
+        vec![path[..=path.rfind('/').unwrap_or(0)].to_string()]
+    }
+
+    /// Whether `url` is on the base origin and under an allowed prefix
+    fn in_scope(&self, url: &Url, prefixes: &[String]) -> bool {
+        url.origin() == self.base_url.origin()
+            && prefixes.iter().any(|p| url.path().starts_with(p.as_str()))
+    }
+}
+
+/// Pages a crawl skipped and why
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WebSummary {
+    /// Pages fetched and turned into documents
+    pub pages: usize,
+    /// Links or sitemap entries outside the origin or allowed prefixes
+    pub out_of_scope: usize,
+    /// URLs robots.txt disallows
+    pub disallowed: usize,
+    /// Pages that failed to fetch
+    pub failed: usize,
+    /// Pages without any main content
+    pub empty: usize,
+}
+
+impl fmt::Display for WebSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} pages, {} out of scope, {} disallowed by robots.txt, {} failed, {} empty",
+            self.pages, self.out_of_scope, self.disallowed, self.failed, self.empty
+        )
+    }
+}
+
+/// `(allow, pattern)` pairs of a robots.txt group
+type Rules = Vec<(bool, String)>;
+
+/// Allow and disallow rules of a robots.txt that apply to this crawler
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Robots {
+    rules: Rules,
+}
+
+impl Robots {
+    /// Rules of the group naming [`ROBOTS_AGENT`], or else the `*` group
+    #[must_use]
+    pub fn parse(text: &str) -> Self {
+        // (agents, rules) of each group in file order
+        let mut groups: Vec<(Vec<String>, Rules)> = Vec::new();
+        let mut in_agents = false;
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((field, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match field.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if !in_agents {
+                        groups.push((Vec::new(), Vec::new()));
+                    }
+                    in_agents = true;
+                    if let Some((agents, _)) = groups.last_mut() {
+                        agents.push(value.to_ascii_lowercase());
+                    }
+                }
+                field @ ("allow" | "disallow") => {
+                    in_agents = false;
+                    if let Some((_, rules)) = groups.last_mut() {
+                        // An empty Disallow allows everything
+                        if !value.is_empty() {
+                            rules.push((field == "allow", value.to_string()));
+                        }
+                    }
+                }
+                _ => in_agents = false,
+            }
+        }
+
+        let named = groups.iter().find(|(agents, _)| {
+            agents
+                .iter()
+                .any(|a| a != "*" && ROBOTS_AGENT.contains(a.as_str()))
+        });
+        let group = named.or_else(|| {
+            groups
+                .iter()
+                .find(|(agents, _)| agents.iter().any(|a| a == "*"))
+        });
+        Self {
+            rules: group.map(|(_, rules)| rules.clone()).unwrap_or_default(),
+        }
+    }
+
+    /// Whether `path` (with its query) may be fetched
+    ///
+    /// The longest matching rule wins and `Allow` wins ties; `*` matches any
+    /// characters and a trailing `$` anchors the end.
+    #[must_use]
+    pub fn allows(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|(_, pattern)| robots_match(pattern, path))
+            .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+            .is_none_or(|(allow, _)| *allow)
+    }
+}
+
+/// Whether a robots.txt path pattern matches `path`
+fn robots_match(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        if anchored && i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+/// Page URLs listed in a sitemap, and nested sitemaps of a sitemap index
+#[must_use]
+pub fn parse_sitemap(xml: &str) -> (Vec<String>, Vec<String>) {
+    let mut pages = Vec::new();
+    let mut sitemaps = Vec::new();
+    let mut in_sitemap = false;
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let Some(end) = rest.find('>') else { break };
+        let tag = rest[..end].trim();
+        rest = &rest[end + 1..];
+        // Namespaced tags such as `<sm:loc>` count too
+        let name = tag.rsplit(':').next().unwrap_or(tag);
+        match name {
+            "sitemap" => in_sitemap = true,
+            "/sitemap" => in_sitemap = false,
+            "loc" => {
+                let Some(close) = rest.find("</") else { break };
+                let url = decode_entities(rest[..close].trim());
+                if in_sitemap {
+                    sitemaps.push(url);
+                } else {
+                    pages.push(url);
+                }
+            }
+            _ => {}
+        }
+    }
+    (pages, sitemaps)
+}
+
+/// Replace the XML entities that appear in URLs
+fn decode_entities(text: &str) -> String {
+    text.replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+}
+
+/// Main content of an HTML page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageContent {
+    /// `<title>`, or the first `<h1>`
+    pub title: Option<String>,
+    /// Text of the main content element
+    pub text: String,
+    /// Absolute URLs of every link on the page
+    pub links: Vec<Url>,
+}
+
+/// Extract the title, main content and links of a page
+///
+/// The main content is the `article`, `main` or `[role=main]` element with
+/// the most text, falling back to `body`; navigation, headers, footers,
+/// sidebars and scripts inside it are dropped.
+#[must_use]
+pub fn extract_page(html: &str, page_url: &Url) -> PageContent {
+    extract_page_with(html, page_url, &[MAIN_CONTENT_SELECTOR])
+}
+
+/// Extract a page whose main content is the largest element matching the
+/// first of `selectors` that finds any text, falling back to `body`
+#[must_use]
+pub fn extract_page_with<S: AsRef<str>>(
+    html: &str,
+    page_url: &Url,
+    selectors: &[S],
+) -> PageContent {
+    let document = Html::parse_document(html);
+    let select = |selector: &str| Selector::parse(selector).ok();
+
+    let title = select("title")
+        .and_then(|s| document.select(&s).next())
+        .or_else(|| select("h1").and_then(|s| document.select(&s).next()))
+        .map(|e| normalize_whitespace(&e.text().collect::<String>()))
+        .filter(|t| !t.is_empty());
+
+    let text = selectors
+        .iter()
+        .filter_map(|selector| select(selector.as_ref()))
+        .map(|s| {
+            document
+                .select(&s)
+                .map(|e| main_text(e))
+                .max_by_key(String::len)
+                .unwrap_or_default()
+        })
+        .find(|t| !t.is_empty())
+        .or_else(|| {
+            select("body")
+                .and_then(|s| document.select(&s).next())
+                .map(main_text)
+        })
+        .unwrap_or_default();
+
+    let links = select("a[href]")
+        .map(|s| {
+            document
+                .select(&s)
+                .filter_map(|a| a.value().attr("href"))
+                .filter_map(|href| page_url.join(href).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    PageContent { title, text, links }
+}
+
+/// Text of `element` without skipped elements, one line per block
+fn main_text(element: ElementRef<'_>) -> String {
+    fn walk(element: ElementRef<'_>, out: &mut String) {
+        for child in element.children() {
+            if let Node::Text(text) = child.value() {
+                out.push_str(text);
+            } else if let Some(child) = ElementRef::wrap(child) {
+                let name = child.value().name();
+                if SKIPPED_ELEMENTS.contains(&name) {
+                    continue;
+                }
+                let block = BLOCK_ELEMENTS.contains(&name);
+                if block {
+                    out.push('\n');
+                }
+                walk(child, out);
+                if block {
+                    out.push('\n');
+                }
+            }
+        }
+    }
+    let mut raw = String::new();
+    walk(element, &mut raw);
+    raw.lines()
+        .map(normalize_whitespace)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `url` without its fragment, if it looks like a page
+fn page_url(mut url: Url) -> Option<Url> {
+    url.set_fragment(None);
+    let extension = url
+        .path()
+        .rsplit('/')
+        .next()
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, ext)| ext.to_ascii_lowercase());
+    let is_file = extension.is_some_and(|ext| NON_PAGE_EXTENSIONS.contains(&ext.as_str()));
+    (matches!(url.scheme(), "http" | "https") && !is_file).then_some(url)
+}
+
+/// Crawl a site into documents
+///
+/// # Errors
+///
+/// Returns an error if the sitemap cannot be fetched; individual pages that
+/// fail are counted in the summary and skipped.
+pub async fn crawl_site(
+    fetcher: &dyn PageFetcher,
+    options: &WebOptions,
+) -> Result<(Vec<DocPage>, WebSummary)> {
+    let base = &options.base_url;
+    let prefixes = options.prefixes();
+    let robots = match base.join("/robots.txt") {
+        Ok(url) => match fetcher.fetch_text(url.as_str()).await {
+            Ok(text) => Robots::parse(&text),
+            Err(e) => {
+                info!("No robots.txt at {}: {}", url, e);
+                Robots::default()
+            }
+        },
+        Err(_) => Robots::default(),
+    };
+
+    let mut summary = WebSummary::default();
+    let mut seen: HashSet<Url> = HashSet::new();
+    // (url, depth); sitemap pages are not followed further
+    let mut queue: VecDeque<(Url, usize)> = VecDeque::new();
+    let follow_links = options.sitemap.is_none();
+
+    let mut admit =
+        |url: Url, depth: usize, queue: &mut VecDeque<(Url, usize)>, summary: &mut WebSummary| {
+            let Some(url) = page_url(url) else {
+                return;
+            };
+            if !seen.insert(url.clone()) {
+                return;
+            }
+            if !options.in_scope(&url, &prefixes) {
+                summary.out_of_scope += 1;
+            } else if !robots.allows(&robots_path(&url)) {
+                summary.disallowed += 1;
+            } else {
+                queue.push_back((url, depth));
+            }
+        };
+
+    match &options.sitemap {
+        Some(sitemap) => {
+            let mut sitemaps = vec![base
+                .join(sitemap)
+                .map_err(|e| anyhow!("Invalid sitemap path {sitemap}: {e}"))?];
+            let mut read = 0;
+            while let Some(sitemap_url) = sitemaps.pop() {
+                if read == MAX_SITEMAPS {
+                    warn!("Stopping after {} sitemaps", MAX_SITEMAPS);
+                    break;
+                }
+                read += 1;
+                let xml = fetcher.fetch_text(sitemap_url.as_str()).await?;
+                let (pages, nested) = parse_sitemap(&xml);
+                for page in pages {
+                    match sitemap_url.join(&page) {
+                        Ok(url) => admit(url, 0, &mut queue, &mut summary),
+                        Err(_) => summary.out_of_scope += 1,
+                    }
+                }
+                sitemaps.extend(
+                    nested
+                        .iter()
+                        .filter_map(|s| sitemap_url.join(s).ok())
+                        .filter(|s| s.origin() == base.origin()),
+                );
+            }
+        }
+        None => admit(base.clone(), 0, &mut queue, &mut summary),
+    }
+
+    let mut documents = Vec::new();
+    while let Some((url, depth)) = queue.pop_front() {
+        if documents.len() + summary.empty >= options.max_pages {
+            break;
+        }
+        let html = match fetcher.fetch_text(url.as_str()).await {
+            Ok(html) => html,
+            Err(e) => {
+                warn!("Failed to fetch {}: {}", url, e);
+                summary.failed += 1;
+                continue;
+            }
+        };
+        let page = extract_page_with(&html, &url, &options.content_selectors);
+        if follow_links && depth < options.max_depth {
+            for link in page.links {
+                admit(link, depth + 1, &mut queue, &mut summary);
+            }
+        }
+        if page.text.is_empty() {
+            summary.empty += 1;
+            continue;
+        }
+
+        let mut metadata = serde_json::Map::new();
+        if let Some(title) = &page.title {
+            metadata.insert("title".to_string(), serde_json::json!(title));
+        }
+        documents.push(DocPage {
+            url: url.to_string(),
+            content: page.text,
+            item_type: WEB_PAGE_ITEM_TYPE.to_string(),
+            module_path: robots_path(&url),
+            extracted_at: chrono::Utc::now(),
+            metadata: Some(serde_json::Value::Object(metadata)),
+        });
+    }
+    summary.pages = documents.len();
+    Ok((documents, summary))
+}
+
+/// Path and query of `url`, as robots.txt rules see it
+fn robots_path(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_robots_groups_and_precedence() {
+        let robots = Robots::parse(
+            "User-agent: *\nDisallow: /\n\nUser-agent: Doc-Server\nDisallow: /docs/private/\nAllow: /docs/private/public.html\nDisallow: /*.php$\n",
+        );
+        assert!(robots.allows("/docs/intro.html"));
+        assert!(!robots.allows("/docs/private/secret.html"));
+        assert!(robots.allows("/docs/private/public.html"));
+        assert!(!robots.allows("/index.php"));
+        assert!(robots.allows("/index.php?x=1"));
+
+        let fallback = Robots::parse("User-agent: *\nDisallow: /admin\n");
+        assert!(!fallback.allows("/admin/users"));
+        assert!(fallback.allows("/docs"));
+        assert!(Robots::parse("").allows("/anything"));
+    }
+
+    #[test]
+    fn test_sitemap_and_index() {
+        let (pages, nested) = parse_sitemap(
+            r#"<?xml version="1.0"?><urlset><url><loc> https://a.test/docs/x?a=1&amp;b=2 </loc></url><url><loc>/docs/y</loc></url></urlset>"#,
+        );
+        assert_eq!(pages, ["https://a.test/docs/x?a=1&b=2", "/docs/y"]);
+        assert!(nested.is_empty());
+
+        let (pages, nested) = parse_sitemap(
+            "<sitemapindex><sitemap><loc>https://a.test/s1.xml</loc></sitemap></sitemapindex>",
+        );
+        assert!(pages.is_empty());
+        assert_eq!(nested, ["https://a.test/s1.xml"]);
+    }
+
+    #[test]
+    fn test_main_content_skips_navigation() {
+        let url = Url::parse("https://a.test/docs/").unwrap();
+        let page = extract_page(
+            "<html><head><title> Intro </title></head><body><nav>Home Docs</nav>\
+             <main><h1>Intro</h1><p>Install   the CLI.</p><footer>© 2024</footer></main>\
+             <article><p>Short</p></article><a href=\"guide.html#top\">Guide</a></body></html>",
+            &url,
+        );
+        assert_eq!(page.title.as_deref(), Some("Intro"));
+        assert_eq!(page.text, "Intro\nInstall the CLI.");
+        assert_eq!(page.links[0].as_str(), "https://a.test/docs/guide.html#top");
+    }
+
+    #[test]
+    fn test_selectors_tried_in_order() {
+        let url = Url::parse("https://a.test/docs/").unwrap();
+        let html = "<body><div class=\"document\"><div class=\"sphinxsidebar\">Table of contents and more</div>\
+             <div class=\"body\" role=\"main\"><h1>API</h1><p>Call it.</p></div></div></body>";
+        let page = extract_page_with(html, &url, SPHINX_CONTENT_SELECTORS);
+        assert_eq!(page.text, "API\nCall it.");
+
+        // Without a match the next selector is used
+        let page = extract_page_with(
+            "<body><div class=\"document\"><p>Only text</p></div><p>Footer</p></body>",
+            &url,
+            SPHINX_CONTENT_SELECTORS,
+        );
+        assert_eq!(page.text, "Only text");
+    }
+}
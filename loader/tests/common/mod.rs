@@ -0,0 +1,19 @@
+//! Helpers shared by the integration tests
+
+use db::DatabasePool;
+
+/// Connect to the test database, or `None` when tests should be skipped
+///
+/// Uses `TEST_DATABASE_URL`, then `DATABASE_URL`; an empty URL or `mock`
+/// means no database.
+pub async fn create_test_pool() -> Option<DatabasePool> {
+    let database_url = std::env::var("TEST_DATABASE_URL")
+        .or_else(|_| std::env::var("DATABASE_URL"))
+        .ok()?;
+
+    if database_url.trim().is_empty() || database_url.trim().eq_ignore_ascii_case("mock") {
+        return None;
+    }
+
+    DatabasePool::new(&database_url).await.ok()
+}
@@ -0,0 +1,176 @@
+//! Corpus export/import round trip against a seeded database
+//!
+//! Skips when no database is configured.
+
+mod common;
+
+use common::create_test_pool;
+use db::queries::DocumentQueries;
+use db::DatabasePool;
+use loader::corpus::{
+    export_corpus, import_corpus, EmbeddingEncoding, ExportOptions, ImportOptions,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use std::env;
+use uuid::Uuid;
+
+/// Every stored document of `doc_type`, embeddings included, ordered by ID
+async fn snapshot(pool: &DatabasePool, doc_type: &str) -> Vec<db::models::Document> {
+    DocumentQueries::export_page(pool.pool(), Some(doc_type), None, None, 1000)
+        .await
+        .expect("read documents")
+        .0
+}
+
+async fn cleanup(pool: &DatabasePool, doc_type: &str) {
+    for table in ["documents", "document_sources"] {
+        let _ = sqlx::query(&format!("DELETE FROM {table} WHERE doc_type = $1"))
+            .bind(doc_type)
+            .execute(pool.pool())
+            .await;
+    }
+}
+
+#[tokio::test]
+async fn test_export_import_round_trip() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping corpus round trip: no database configured");
+        return;
+    };
+    let doc_type = format!("corpus-test-{}", Uuid::new_v4());
+    // Embedding fidelity needs the vector extension; without it the rest
+    // of the round trip is still checked
+    let vector_available = sqlx::query("SELECT '[1,2,3]'::vector(3)")
+        .execute(pool.pool())
+        .await
+        .is_ok();
+    let dimensions = DocumentQueries::embedding_column_dimensions(pool.pool())
+        .await
+        .expect("embedding dimensions")
+        .unwrap_or(8);
+    let dimensions = usize::try_from(dimensions).unwrap();
+
+    // Seed five documents with distinct timestamps
+    DocumentQueries::ensure_document_source(pool.pool(), &doc_type, "widgets")
+        .await
+        .expect("seed source");
+    for i in 0..5_i32 {
+        sqlx::query(
+            r"
+            INSERT INTO documents (id, doc_type, source_name, doc_path, content, metadata,
+                                   token_count, created_at, updated_at)
+            VALUES ($1, $2, 'widgets', $3, $4, $5, $6,
+                    now() - make_interval(days => $6), now() - make_interval(hours => $6))
+            ",
+        )
+        .bind(Uuid::new_v4())
+        .bind(&doc_type)
+        .bind(format!("guide/part-{i}.md"))
+        .bind(format!(
+            "Part {i}: ünïcode, \"quotes\", tabs\tand\nnewlines \u{1F980}"
+        ))
+        .bind(json!({"part": i, "tags": ["a", "b"], "nested": {"ok": true}}))
+        .bind(i + 1)
+        .execute(pool.pool())
+        .await
+        .expect("seed document");
+    }
+
+    // Embed three of them
+    if vector_available {
+        for i in 0..3_i32 {
+            let embedding = pgvector::Vector::from(
+                (0..dimensions)
+                    .map(|d| (i as f32 + 1.0) / (d as f32 + 3.0) - 0.123_456_7)
+                    .collect::<Vec<f32>>(),
+            );
+            sqlx::query(
+                "UPDATE documents SET embedding = $1 WHERE doc_type = $2 AND doc_path = $3",
+            )
+            .bind(embedding)
+            .bind(&doc_type)
+            .bind(format!("guide/part-{i}.md"))
+            .execute(pool.pool())
+            .await
+            .expect("seed embedding");
+        }
+    } else {
+        println!("vector extension not installed: embedding fidelity not checked");
+    }
+    let before = snapshot(&pool, &doc_type).await;
+    assert_eq!(before.len(), 5);
+
+    let dump = env::temp_dir().join(format!("{doc_type}.jsonl.gz"));
+    let exported = export_corpus(
+        pool.pool(),
+        &dump,
+        &ExportOptions {
+            doc_type: Some(doc_type.clone()),
+            source_name: None,
+            batch_size: 2,
+            encoding: EmbeddingEncoding::Base64,
+        },
+    )
+    .await
+    .expect("export");
+    assert_eq!(exported, 5);
+
+    // Restore into an empty table
+    cleanup(&pool, &doc_type).await;
+    let summary = import_corpus(
+        pool.pool(),
+        &dump,
+        &ImportOptions {
+            batch_size: 2,
+            ..ImportOptions::default()
+        },
+    )
+    .await
+    .expect("import");
+    assert_eq!(summary.records, 5);
+    assert_eq!(summary.written, 5);
+
+    let after = snapshot(&pool, &doc_type).await;
+    assert_eq!(after.len(), before.len());
+    for (original, restored) in before.iter().zip(&after) {
+        assert_eq!(restored.id, original.id);
+        assert_eq!(restored.source_name, original.source_name);
+        assert_eq!(restored.doc_path, original.doc_path);
+        assert_eq!(restored.content.as_bytes(), original.content.as_bytes());
+        assert_eq!(restored.metadata, original.metadata);
+        assert_eq!(restored.token_count, original.token_count);
+        assert_eq!(restored.created_at, original.created_at);
+        assert_eq!(restored.updated_at, original.updated_at);
+        assert_eq!(
+            restored.embedding.as_ref().map(pgvector::Vector::to_vec),
+            original.embedding.as_ref().map(pgvector::Vector::to_vec)
+        );
+    }
+    let embedded = if vector_available { 3 } else { 0 };
+    assert_eq!(
+        after.iter().filter(|d| d.embedding.is_some()).count(),
+        embedded
+    );
+
+    // Renamed and without embeddings
+    cleanup(&pool, &doc_type).await;
+    import_corpus(
+        pool.pool(),
+        &dump,
+        &ImportOptions {
+            batch_size: 10,
+            skip_embeddings: true,
+            remap_source: HashMap::from([("widgets".to_string(), "gadgets".to_string())]),
+        },
+    )
+    .await
+    .expect("remapped import");
+    let remapped = snapshot(&pool, &doc_type).await;
+    assert_eq!(remapped.len(), 5);
+    assert!(remapped.iter().all(|d| d.source_name == "gadgets"));
+    assert!(remapped.iter().all(|d| d.embedding.is_none()));
+
+    cleanup(&pool, &doc_type).await;
+    let _ = std::fs::remove_file(&dump);
+}
@@ -0,0 +1,143 @@
+//! Doc comment extraction against fixture source files
+
+use loader::extractors::{extract, language, CodeItem};
+
+const RUST_SOURCE: &str = include_str!("fixtures/extract_sample.rs");
+const PYTHON_SOURCE: &str = include_str!("fixtures/extract_sample.py");
+const TYPESCRIPT_SOURCE: &str = include_str!("fixtures/extract_sample.ts");
+
+fn find<'a>(items: &'a [CodeItem], name: &str) -> &'a CodeItem {
+    items
+        .iter()
+        .find(|item| item.name == name)
+        .unwrap_or_else(|| panic!("no item named {name} in {items:#?}"))
+}
+
+fn kinds(items: &[CodeItem]) -> Vec<(&str, &str)> {
+    items
+        .iter()
+        .map(|item| (item.kind, item.name.as_str()))
+        .collect()
+}
+
+#[test]
+fn test_rust_doc_comments() {
+    let items = extract(RUST_SOURCE, "src/pool.rs").unwrap();
+    assert_eq!(
+        kinds(&items),
+        [
+            ("module", "pool"),
+            ("constant", "DEFAULT_SIZE"),
+            ("struct", "PoolConfig"),
+            ("field", "PoolConfig::max_size"),
+            ("enum", "PoolError"),
+            ("variant", "PoolError::Exhausted"),
+            ("variant", "PoolError::Refused"),
+            ("method", "PoolConfig::new"),
+            ("function", "acquire"),
+        ]
+    );
+
+    let module = find(&items, "pool");
+    assert_eq!(module.line, 1);
+    assert!(module.docs.ends_with("«with» unicode ✓."));
+
+    let new = find(&items, "PoolConfig::new");
+    assert_eq!(
+        new.signature,
+        "pub const fn new(\n    max_size: usize,\n    timeout: Duration,\n) -> Self"
+    );
+    assert!(new.docs.contains("\n\n# Panics\n\n"));
+    assert_eq!(new.line, 35);
+
+    let acquire = find(&items, "acquire");
+    assert!(acquire.signature.ends_with("where\n    T: Default"));
+    assert_eq!(
+        acquire.content("rust"),
+        format!(
+            "```rust\n{}\n```\n\nAcquire a connection, waiting up to the configured timeout",
+            acquire.signature
+        )
+    );
+}
+
+#[test]
+fn test_python_docstrings() {
+    let items = extract(PYTHON_SOURCE, "billing/invoice.py").unwrap();
+    assert_eq!(
+        kinds(&items),
+        [
+            ("module", "invoice"),
+            ("function", "parse_total"),
+            ("class", "Invoice"),
+            ("method", "Invoice.paid"),
+            ("method", "Invoice.send"),
+            ("function", "broken"),
+        ]
+    );
+
+    assert_eq!(
+        find(&items, "invoice").docs,
+        "Utilities for parsing invoices.\n\nHandles PDFs and CSV exports — even «unicode» ✓."
+    );
+    assert_eq!(
+        find(&items, "Invoice").docs,
+        "A parsed invoice.\n\nAttributes:\n    number: Invoice number"
+    );
+    let send = find(&items, "Invoice.send");
+    assert!(send.signature.starts_with("async def send(\n    self,"));
+    assert!(send.signature.ends_with(") -> None"));
+    assert_eq!(send.line, 30);
+    assert_eq!(
+        find(&items, "Invoice.paid").signature,
+        "def paid(self) -> bool"
+    );
+    // An unterminated docstring runs to the end of the file
+    assert!(find(&items, "broken").docs.ends_with("end of the file 🦀"));
+}
+
+#[test]
+fn test_typescript_jsdoc() {
+    let items = extract(TYPESCRIPT_SOURCE, "src/client.ts").unwrap();
+    assert_eq!(
+        kinds(&items),
+        [
+            ("module", "client"),
+            ("interface", "RetryPolicy"),
+            ("property", "RetryPolicy.attempts"),
+            ("class", "Client"),
+            ("property", "Client.baseUrl"),
+            ("method", "Client.get"),
+            ("function", "toQuery"),
+            ("constant", "DEFAULT_TIMEOUT"),
+            ("enum", "Method"),
+        ]
+    );
+
+    let get = find(&items, "Client.get");
+    assert_eq!(get.signature, "async get<T>(path: string): Promise<T>");
+    assert_eq!(
+        get.docs,
+        "Send a GET request.\n@param path Path relative to the base URL"
+    );
+    assert_eq!(get.line, 29);
+    assert_eq!(find(&items, "Client").signature, "export class Client");
+    assert_eq!(language("src/client.js"), Some("javascript"));
+    assert_eq!(language("README.md"), None);
+}
+
+#[test]
+fn test_truncated_sources_do_not_panic() {
+    for (source, path) in [
+        (RUST_SOURCE, "pool.rs"),
+        (PYTHON_SOURCE, "invoice.py"),
+        (TYPESCRIPT_SOURCE, "client.ts"),
+    ] {
+        for (end, _) in source.char_indices() {
+            let _ = extract(&source[..end], path);
+        }
+    }
+    let _ = extract("/// 🦀\n///\u{200b}\nfn\n", "odd.rs");
+    let _ = extract("def (:\n    '''\u{feff}", "odd.py");
+    let _ = extract("/***/\n/** */ export const", "odd.ts");
+}
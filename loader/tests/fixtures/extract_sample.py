@@ -0,0 +1,47 @@
+#!/usr/bin/env python3
+"""Utilities for parsing invoices.
+
+Handles PDFs and CSV exports — even «unicode» ✓.
+"""
+
+import csv
+
+
+def parse_total(line: str) -> float:
+    """Return the total amount on an invoice line."""
+    return float(line.split(",")[-1])
+
+
+class Invoice:
+    """A parsed invoice.
+
+    Attributes:
+        number: Invoice number
+    """
+
+    def __init__(self, number):
+        self.number = number
+
+    @property
+    def paid(self) -> bool:
+        '''Whether the invoice has been settled'''
+        return False
+
+    async def send(
+        self,
+        recipient: str,
+        cc: list[str] | None = None,
+    ) -> None:
+        """Email the invoice.
+
+    Oddly indented continuation line.
+        """
+
+
+def undocumented():
+    return 1
+
+
+def broken():
+    """This docstring never ends
+    and runs to the end of the file 🦀
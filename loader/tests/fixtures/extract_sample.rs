@@ -0,0 +1,54 @@
+//! Connection pooling for the billing service.
+//!
+//! Pools hand out connections — «with» unicode ✓.
+
+use std::time::Duration;
+
+/// Default number of pooled connections
+pub const DEFAULT_SIZE: usize = 8;
+
+/// Settings for a [`Pool`]
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// Largest number of open connections
+    pub max_size: usize,
+    timeout: Duration,
+}
+
+/// Why acquiring a connection failed
+pub enum PoolError {
+    /// Every connection is in use
+    Exhausted,
+    /// The database refused the connection
+    Refused(String),
+}
+
+// Not a doc comment
+pub struct Undocumented;
+
+impl PoolConfig {
+    /// Create a configuration with `max_size` connections
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_size` is zero.
+    pub const fn new(
+        max_size: usize,
+        timeout: Duration,
+    ) -> Self {
+        assert!(max_size > 0);
+        Self { max_size, timeout }
+    }
+
+    fn private_helper(&self) {}
+}
+
+/// Acquire a connection, waiting up to the configured timeout
+pub async fn acquire<T: Send>(config: &PoolConfig) -> Result<T, PoolError>
+where
+    T: Default,
+{
+    todo!()
+}
+
+/// Unterminated /* comment and a stray 🦀
@@ -0,0 +1,47 @@
+/**
+ * @fileoverview HTTP client helpers — «unicode» ✓.
+ */
+
+import { fetch } from "./fetch";
+
+/**
+ * Retry policy for failed requests.
+ */
+export interface RetryPolicy {
+  /** Maximum number of attempts */
+  attempts: number;
+  backoffMs?: number;
+}
+
+/**
+ * A small HTTP client.
+ * @example new Client("https://api.example.com")
+ */
+export class Client {
+  /** Base URL for every request */
+  private readonly baseUrl: string;
+
+  /**
+   * Send a GET request.
+   * @param path Path relative to the base URL
+   */
+  @traced()
+  async get<T>(path: string): Promise<T> {
+    return fetch(this.baseUrl + path);
+  }
+}
+
+/** Build a query string from `params`. */
+export const toQuery = (params: Record<string, string>): string =>
+  new URLSearchParams(params).toString();
+
+/** Default timeout in milliseconds */
+export const DEFAULT_TIMEOUT = 30_000;
+
+/** Supported HTTP methods */
+export enum Method {
+  Get = "GET",
+}
+
+/**
+ * Unterminated comment 🦀
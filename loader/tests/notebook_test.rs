@@ -0,0 +1,85 @@
+//! Jupyter notebook parsing against fixture notebooks
+
+use loader::parsers::notebook::{parse_notebook, NotebookOptions};
+use loader::{DocumentFormat, UniversalParser};
+
+const V4_NOTEBOOK: &str = include_str!("fixtures/linear_regression.ipynb");
+const V3_NOTEBOOK: &str = include_str!("fixtures/legacy_v3.ipynb");
+
+#[tokio::test]
+async fn test_v4_notebook_prose_and_code() {
+    let parser = UniversalParser::default();
+    let parsed = parser
+        .parse(V4_NOTEBOOK, "notebooks/linear_regression.ipynb")
+        .await
+        .unwrap();
+
+    assert_eq!(parsed.format, DocumentFormat::Notebook);
+    assert_eq!(parsed.metadata["language"], "python");
+    assert_eq!(parsed.metadata["nbformat"], "4");
+    assert_eq!(parsed.metadata["title"], "Linear regression");
+    assert_eq!(parsed.metadata["code_cells"], "3");
+
+    let text = &parsed.text_content;
+    assert!(text.starts_with("# Linear regression\n\nFit a line"));
+    assert!(text.contains("```python\nimport numpy as np\nrng = np.random.default_rng(0)\n```"));
+    // text/plain output kept, image data dropped
+    assert!(text.contains("Output:\n```text\narray([2.01, 0.99])\n```"));
+    assert!(!text.contains("iVBOR"));
+}
+
+#[tokio::test]
+async fn test_sections_keep_code_with_markdown() {
+    let parser = UniversalParser::default();
+    let parsed = parser
+        .parse(V4_NOTEBOOK, "linear_regression.ipynb")
+        .await
+        .unwrap();
+    let chunks = parser.chunk_content(&parsed, "linear_regression.ipynb");
+
+    assert_eq!(chunks.len(), 2);
+    assert!(chunks[0].content.starts_with("Linear regression"));
+    assert!(chunks[0].content.contains("import numpy as np"));
+    assert!(chunks[1].content.starts_with("Generate data"));
+    assert!(chunks[1].content.contains("x = np.linspace(0, 1, 50)"));
+    assert!(chunks[1].content.contains("np.polyfit(x, y, 1)"));
+}
+
+#[test]
+fn test_outputs_capped_per_cell() {
+    let options = NotebookOptions {
+        include_outputs: true,
+        max_output_chars: 40,
+    };
+    let parsed = parse_notebook(V4_NOTEBOOK, "linear_regression.ipynb", &options).unwrap();
+    assert!(parsed.text_content.contains("epoch 1 loss 3.87"));
+    assert!(!parsed.text_content.contains("epoch 9 loss"));
+    assert!(parsed.text_content.contains("... [output truncated]"));
+
+    let options = NotebookOptions {
+        include_outputs: false,
+        ..options
+    };
+    let parsed = parse_notebook(V4_NOTEBOOK, "linear_regression.ipynb", &options).unwrap();
+    assert!(!parsed.text_content.contains("Output:"));
+}
+
+#[test]
+fn test_v3_worksheets() {
+    let parsed =
+        parse_notebook(V3_NOTEBOOK, "legacy_v3.ipynb", &NotebookOptions::default()).unwrap();
+
+    assert_eq!(parsed.metadata["nbformat"], "3");
+    assert_eq!(parsed.metadata["title"], "Legacy notebook");
+    assert_eq!(
+        parsed.text_content,
+        "# Legacy notebook\n\nComputes a sum.\n\n```python\nsum(range(10))\n```\n\nOutput:\n```text\n45\n```"
+    );
+}
+
+#[test]
+fn test_not_a_notebook() {
+    let err =
+        parse_notebook(r#"{"name": "x"}"#, "x.ipynb", &NotebookOptions::default()).unwrap_err();
+    assert!(err.to_string().contains("not a Jupyter notebook"));
+}
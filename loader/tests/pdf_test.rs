@@ -0,0 +1,62 @@
+//! PDF text extraction against fixture PDFs
+
+use loader::parsers::pdf::{extract_pages, PAGE_END_KEY, PAGE_START_KEY};
+use loader::{DocumentFormat, UniversalParser};
+
+const TWO_PAGES: &[u8] = include_bytes!("fixtures/two_pages.pdf");
+const SCANNED: &[u8] = include_bytes!("fixtures/scanned.pdf");
+
+#[test]
+fn test_extract_text_per_page() {
+    let pages = extract_pages(TWO_PAGES, "two_pages.pdf").unwrap();
+    assert_eq!(pages.len(), 2);
+    assert!(pages[0].contains("Getting Started"));
+    assert!(pages[0].contains("cargo install toolkit"));
+    assert!(pages[1].contains("Configuration"));
+    assert!(pages[1].contains("TOOLKIT_HOME"));
+}
+
+#[tokio::test]
+async fn test_page_metadata_per_chunk() {
+    // Small chunks so each page becomes its own document
+    let parser = UniversalParser::new(80, 0);
+    let docs = parser
+        .parse_bytes(TWO_PAGES, "docs/manual.pdf")
+        .await
+        .unwrap();
+
+    assert_eq!(docs.len(), 2);
+    assert!(docs.iter().all(|d| d.format == DocumentFormat::Pdf));
+    assert_eq!(docs[0].metadata[PAGE_START_KEY], "1");
+    assert_eq!(docs[0].metadata[PAGE_END_KEY], "1");
+    assert_eq!(docs[1].metadata[PAGE_START_KEY], "2");
+    assert_eq!(docs[1].metadata["page_count"], "2");
+    assert!(docs[1].text_content.contains("TOOLKIT_HOME"));
+
+    // Default size packs both pages into one chunk
+    let docs = UniversalParser::default()
+        .parse_bytes(TWO_PAGES, "docs/manual.pdf")
+        .await
+        .unwrap();
+    assert_eq!(docs.len(), 1);
+    assert_eq!(docs[0].metadata[PAGE_START_KEY], "1");
+    assert_eq!(docs[0].metadata[PAGE_END_KEY], "2");
+}
+
+#[tokio::test]
+async fn test_detected_by_magic_bytes() {
+    let docs = UniversalParser::default()
+        .parse_bytes(TWO_PAGES, "download")
+        .await
+        .unwrap();
+    assert!(docs[0].text_content.contains("Getting Started"));
+}
+
+#[tokio::test]
+async fn test_image_only_pdf_has_no_text() {
+    let err = UniversalParser::default()
+        .parse_bytes(SCANNED, "scanned.pdf")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("no extractable text"), "{err}");
+}
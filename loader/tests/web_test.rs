@@ -0,0 +1,161 @@
+//! Website crawling against fixture pages served by a local HTTP server
+
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use loader::web::{crawl_site, WebOptions, WEB_PAGE_ITEM_TYPE};
+use rust_crates::RateLimiter;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use url::Url;
+
+/// Serve `tests/fixtures/site`, recording every requested path
+async fn serve_fixture_site() -> (Url, Arc<Mutex<Vec<String>>>) {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/site");
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let log = Arc::clone(&requests);
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                return;
+            };
+            let (root, log) = (root.clone(), Arc::clone(&log));
+            tokio::spawn(async move {
+                let service = service_fn(move |request: Request<Incoming>| {
+                    let path = request.uri().path().to_string();
+                    log.lock().unwrap().push(path.clone());
+                    let file = if path.ends_with('/') {
+                        format!("{path}index.html")
+                    } else {
+                        path
+                    };
+                    let body = std::fs::read(root.join(file.trim_start_matches('/')));
+                    async move {
+                        let response = match body {
+                            Ok(body) => Response::new(Full::new(Bytes::from(body))),
+                            Err(_) => Response::builder()
+                                .status(StatusCode::NOT_FOUND)
+                                .body(Full::new(Bytes::new()))
+                                .unwrap(),
+                        };
+                        Ok::<_, std::convert::Infallible>(response)
+                    }
+                });
+                let _ = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await;
+            });
+        }
+    });
+
+    let base = Url::parse(&format!("http://{address}/docs/")).unwrap();
+    (base, requests)
+}
+
+fn unlimited() -> RateLimiter {
+    RateLimiter::with_limits(Duration::ZERO, 1)
+}
+
+fn paths(documents: &[loader::DocPage]) -> Vec<String> {
+    let mut paths: Vec<String> = documents.iter().map(|d| d.module_path.clone()).collect();
+    paths.sort();
+    paths
+}
+
+#[tokio::test]
+async fn test_follows_links_within_scope() {
+    let (base, requests) = serve_fixture_site().await;
+    let options = WebOptions::new(base.clone());
+    let (documents, summary) = crawl_site(&unlimited(), &options).await.unwrap();
+
+    assert_eq!(
+        paths(&documents),
+        [
+            "/docs/",
+            "/docs/guide/deep/one.html",
+            "/docs/guide/setup.html",
+            "/docs/intro.html",
+        ]
+    );
+    // Blog, other origin; the private page is disallowed by robots.txt
+    assert_eq!(summary.out_of_scope, 2);
+    assert_eq!(summary.disallowed, 1);
+    assert_eq!(summary.pages, 4);
+    let requested = requests.lock().unwrap().clone();
+    assert!(requested.contains(&"/robots.txt".to_string()));
+    assert!(!requested
+        .iter()
+        .any(|p| p.contains("private") || p.ends_with(".png")));
+
+    let index = documents
+        .iter()
+        .find(|d| d.module_path == "/docs/")
+        .unwrap();
+    assert_eq!(index.url, base.as_str());
+    assert_eq!(index.item_type, WEB_PAGE_ITEM_TYPE);
+    assert_eq!(index.metadata.as_ref().unwrap()["title"], "Example Docs");
+    assert!(index.content.starts_with("Welcome\nExample is a tool"));
+    assert!(!index.content.contains("Copyright"));
+    assert!(!index.content.contains("Blog"));
+
+    let intro = documents
+        .iter()
+        .find(|d| d.module_path == "/docs/intro.html")
+        .unwrap();
+    assert!(intro.content.contains("applied with example apply."));
+    assert!(!intro.content.contains("On this page"));
+}
+
+#[tokio::test]
+async fn test_depth_and_page_limits() {
+    let (base, _) = serve_fixture_site().await;
+    let options = WebOptions {
+        max_depth: 1,
+        ..WebOptions::new(base.clone())
+    };
+    let (documents, _) = crawl_site(&unlimited(), &options).await.unwrap();
+    assert_eq!(
+        paths(&documents),
+        ["/docs/", "/docs/guide/setup.html", "/docs/intro.html"]
+    );
+
+    let options = WebOptions {
+        max_pages: 2,
+        ..WebOptions::new(base)
+    };
+    let (documents, _) = crawl_site(&unlimited(), &options).await.unwrap();
+    assert_eq!(documents.len(), 2);
+}
+
+#[tokio::test]
+async fn test_sitemap_pages_and_prefix_allowlist() {
+    let (base, requests) = serve_fixture_site().await;
+    let options = WebOptions {
+        sitemap: Some("/sitemap.xml".to_string()),
+        allow_prefixes: vec!["/docs/guide/".to_string()],
+        ..WebOptions::new(base)
+    };
+    let (documents, summary) = crawl_site(&unlimited(), &options).await.unwrap();
+
+    // Sitemap pages are not followed, so deep/one.html is not reached
+    assert_eq!(paths(&documents), ["/docs/guide/setup.html"]);
+    assert_eq!(summary.out_of_scope, 3);
+    assert_eq!(
+        documents[0].metadata.as_ref().unwrap()["title"],
+        "Setup | Example Docs"
+    );
+    assert!(documents[0].content.starts_with("Install\ncurl -sL"));
+    assert!(!requests
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|p| p.contains("private")));
+}
@@ -0,0 +1,436 @@
+//! `doc-admin` command line for job and crate management
+//!
+//! On-call engineers run it from a pod shell to inspect and unstick crate
+//! jobs without crafting JSON-RPC requests. Commands go through the same
+//! queries and tools as the MCP crate tools, so both paths behave the same.
+//! Output is a plain table by default, or JSON with `--json`.
+
+use crate::crate_tools::{RemoveRustCrateTool, RetryRustJobTool};
+use crate::tools::Tool;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand, ValueEnum};
+use db::models::{CrateJob, CrateStatusFilter, JobStatus, PaginationParams};
+use db::queries::{CrateJobQueries, CrateQueries, DocumentSourceQueries};
+use db::DatabasePool;
+use serde_json::{json, Value};
+use std::fmt::Write as _;
+use uuid::Uuid;
+
+/// Job and crate administration for the Doc Server
+#[derive(Debug, Parser)]
+#[command(name = "doc-admin")]
+#[command(about = "Job and crate administration for the Doc Server")]
+#[command(version = "0.1.0")]
+pub struct AdminCli {
+    #[command(subcommand)]
+    pub command: AdminCommand,
+
+    /// Print JSON instead of a table
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Database URL
+    #[arg(long, env = "DATABASE_URL", hide_env_values = true)]
+    pub database_url: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AdminCommand {
+    /// Inspect, retry and cancel crate jobs
+    Jobs {
+        #[command(subcommand)]
+        command: JobsCommand,
+    },
+    /// List and remove ingested crates
+    Crates {
+        #[command(subcommand)]
+        command: CratesCommand,
+    },
+    /// List document sources
+    Sources {
+        #[command(subcommand)]
+        command: SourcesCommand,
+    },
+    /// Crate, document and job totals
+    Stats,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum JobsCommand {
+    /// List the most recent jobs
+    List {
+        /// Only jobs in this status
+        #[arg(long, value_enum)]
+        status: Option<JobStatusArg>,
+
+        /// Only jobs for this crate
+        #[arg(long = "crate")]
+        crate_name: Option<String>,
+
+        /// Maximum number of jobs to list
+        #[arg(long, default_value = "50")]
+        limit: i64,
+    },
+    /// Requeue a failed or cancelled job, like the `retry_rust_job` tool
+    Retry {
+        /// Job ID
+        job_id: Uuid,
+    },
+    /// Cancel a queued or running job
+    Cancel {
+        /// Job ID
+        job_id: Uuid,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CratesCommand {
+    /// List ingested crates, like the `list_rust_crates` tool
+    List {
+        /// Only crates whose name contains this text
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Soft-delete status to list
+        #[arg(long, value_enum, default_value = "active")]
+        status: CrateStatusArg,
+
+        /// Page number (starts at 1)
+        #[arg(long, default_value = "1")]
+        page: i32,
+
+        /// Crates per page (at most 100)
+        #[arg(long, default_value = "100")]
+        limit: i32,
+    },
+    /// Remove a crate, like the `remove_rust_crate` tool
+    Remove {
+        /// Crate name
+        name: String,
+
+        /// Mark the crate inactive instead of deleting its documents
+        #[arg(long)]
+        soft: bool,
+
+        /// Remove even if other ingested crates depend on it
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SourcesCommand {
+    /// List document sources with their document counts
+    List {
+        /// Only sources of this doc type
+        #[arg(long)]
+        doc_type: Option<String>,
+
+        /// Maximum number of sources to list
+        #[arg(long, default_value = "100")]
+        limit: i64,
+    },
+}
+
+/// Job status accepted by `jobs list --status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum JobStatusArg {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl From<JobStatusArg> for JobStatus {
+    fn from(status: JobStatusArg) -> Self {
+        match status {
+            JobStatusArg::Queued => Self::Queued,
+            JobStatusArg::Running => Self::Running,
+            JobStatusArg::Completed => Self::Completed,
+            JobStatusArg::Failed => Self::Failed,
+            JobStatusArg::Cancelled => Self::Cancelled,
+        }
+    }
+}
+
+/// Crate status accepted by `crates list --status`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CrateStatusArg {
+    Active,
+    Inactive,
+    All,
+}
+
+impl From<CrateStatusArg> for CrateStatusFilter {
+    fn from(status: CrateStatusArg) -> Self {
+        match status {
+            CrateStatusArg::Active => Self::Active,
+            CrateStatusArg::Inactive => Self::Inactive,
+            CrateStatusArg::All => Self::All,
+        }
+    }
+}
+
+/// Run a parsed command and return what to print
+///
+/// # Errors
+///
+/// Returns an error if a query fails or the command is rejected, such as
+/// cancelling a job that already finished.
+pub async fn run(cli: &AdminCli, db_pool: &DatabasePool) -> Result<String> {
+    let pool = db_pool.pool();
+    match &cli.command {
+        AdminCommand::Jobs { command } => match command {
+            JobsCommand::List {
+                status,
+                crate_name,
+                limit,
+            } => {
+                let status = status.map(JobStatus::from);
+                let jobs = CrateJobQueries::list_jobs(
+                    pool,
+                    status.as_ref(),
+                    crate_name.as_deref(),
+                    *limit,
+                )
+                .await?;
+                Ok(if cli.json {
+                    json!(jobs).to_string()
+                } else {
+                    jobs_table(&jobs)
+                })
+            }
+            JobsCommand::Retry { job_id } => {
+                let tool = RetryRustJobTool::new(db_pool.clone());
+                let output = tool.execute(json!({"job_id": job_id.to_string()})).await?;
+                Ok(tool_output(cli.json, output))
+            }
+            JobsCommand::Cancel { job_id } => {
+                let job = cancel_job(db_pool, *job_id).await?;
+                Ok(if cli.json {
+                    json!(job).to_string()
+                } else {
+                    format!("Cancelled job {} for crate '{}'", job.id, job.crate_name)
+                })
+            }
+        },
+        AdminCommand::Crates { command } => match command {
+            CratesCommand::List {
+                name,
+                status,
+                page,
+                limit,
+            } => {
+                let pagination = PaginationParams::new(Some(*page), Some(*limit));
+                let crates = CrateQueries::list_crates_with_status(
+                    pool,
+                    &pagination,
+                    name.as_deref(),
+                    (*status).into(),
+                )
+                .await?;
+                if cli.json {
+                    return Ok(json!(crates).to_string());
+                }
+                let rows = crates
+                    .items
+                    .iter()
+                    .map(|info| {
+                        vec![
+                            info.name.clone(),
+                            info.version.clone(),
+                            info.total_docs.to_string(),
+                            info.total_tokens.to_string(),
+                            format!(
+                                "{:.1}%{}",
+                                info.embedding_coverage_pct,
+                                if info.has_low_embedding_coverage() {
+                                    " (low)"
+                                } else {
+                                    ""
+                                }
+                            ),
+                            timestamp(info.last_updated),
+                        ]
+                    })
+                    .collect::<Vec<_>>();
+                let mut output = table(
+                    &["NAME", "VERSION", "DOCS", "TOKENS", "EMBEDDED", "UPDATED"],
+                    &rows,
+                );
+                let _ = write!(
+                    output,
+                    "\nPage {} of {} ({} crates)",
+                    crates.page, crates.total_pages, crates.total_items
+                );
+                Ok(output)
+            }
+            CratesCommand::Remove { name, soft, force } => {
+                let tool = RemoveRustCrateTool::new(db_pool.clone());
+                let output = tool
+                    .execute(json!({"name": name, "soft_delete": soft, "force": force}))
+                    .await?;
+                Ok(tool_output(cli.json, output))
+            }
+        },
+        AdminCommand::Sources {
+            command: SourcesCommand::List { doc_type, limit },
+        } => {
+            let sources =
+                DocumentSourceQueries::list_sources(pool, doc_type.as_deref(), *limit, 0).await?;
+            if cli.json {
+                return Ok(json!(sources).to_string());
+            }
+            let rows = sources
+                .iter()
+                .map(|source| {
+                    vec![
+                        source.doc_type.clone(),
+                        source.source_name.clone(),
+                        if source.enabled { "yes" } else { "no" }.to_string(),
+                        source.document_count.to_string(),
+                        source.updated_at.map_or_else(|| "-".to_string(), timestamp),
+                    ]
+                })
+                .collect::<Vec<_>>();
+            Ok(table(
+                &["DOC TYPE", "SOURCE", "ENABLED", "DOCS", "UPDATED"],
+                &rows,
+            ))
+        }
+        AdminCommand::Stats => {
+            let stats = CrateQueries::get_crate_statistics(pool).await?;
+            let active_jobs = CrateJobQueries::find_active_jobs(pool).await?.len();
+            if cli.json {
+                return Ok(json!({
+                    "crates": stats,
+                    "active_jobs": active_jobs,
+                })
+                .to_string());
+            }
+            let rows = [
+                ("Total crates", stats.total_crates.to_string()),
+                ("Active crates", stats.active_crates.to_string()),
+                ("Crate versions", stats.total_versions.to_string()),
+                ("Documents", stats.total_docs_managed.to_string()),
+                ("Tokens", stats.total_tokens_managed.to_string()),
+                (
+                    "Average docs per crate",
+                    format!("{:.1}", stats.average_docs_per_crate),
+                ),
+                (
+                    "Last update",
+                    stats.last_update.map_or_else(|| "-".to_string(), timestamp),
+                ),
+                ("Active jobs", active_jobs.to_string()),
+            ]
+            .into_iter()
+            .map(|(label, value)| vec![label.to_string(), value])
+            .collect::<Vec<_>>();
+            Ok(table(&["METRIC", "VALUE"], &rows))
+        }
+    }
+}
+
+/// Mark a queued or running job cancelled
+///
+/// A running job's worker is not interrupted; cancelling it is for jobs whose
+/// worker is gone and that would otherwise show as running forever.
+async fn cancel_job(db_pool: &DatabasePool, job_id: Uuid) -> Result<CrateJob> {
+    let pool = db_pool.pool();
+    let job = CrateJobQueries::find_job_by_id(pool, job_id)
+        .await?
+        .ok_or_else(|| anyhow!("Job {job_id} not found"))?;
+    if !matches!(job.status, JobStatus::Queued | JobStatus::Running) {
+        return Err(anyhow!(
+            "Job {job_id} is {:?}; only queued or running jobs can be cancelled",
+            job.status
+        ));
+    }
+    CrateJobQueries::update_job_status(
+        pool,
+        job_id,
+        JobStatus::Cancelled,
+        job.progress,
+        Some("Cancelled by operator"),
+    )
+    .await
+}
+
+/// A tool's response as printed by the CLI
+///
+/// Tools answer with either JSON or a message; `--json` wraps messages so the
+/// output always parses.
+fn tool_output(as_json: bool, output: String) -> String {
+    if !as_json || serde_json::from_str::<Value>(&output).is_ok() {
+        output
+    } else {
+        json!({ "message": output }).to_string()
+    }
+}
+
+fn jobs_table(jobs: &[CrateJob]) -> String {
+    let rows = jobs
+        .iter()
+        .map(|job| {
+            vec![
+                job.id.to_string(),
+                job.crate_name.clone(),
+                job.operation.clone(),
+                format!("{:?}", job.status).to_lowercase(),
+                job.progress
+                    .map_or_else(|| "-".to_string(), |p| format!("{p}%")),
+                format!("{}/{}", job.attempts, job.max_attempts),
+                timestamp(job.started_at),
+                job.error.clone().unwrap_or_default(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    table(
+        &[
+            "ID",
+            "CRATE",
+            "OPERATION",
+            "STATUS",
+            "PROGRESS",
+            "ATTEMPTS",
+            "STARTED",
+            "ERROR",
+        ],
+        &rows,
+    )
+}
+
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Left-aligned columns padded to their widest cell
+fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    if rows.is_empty() {
+        return "(none)".to_string();
+    }
+
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut output = String::new();
+    let header_cells = headers.iter().map(|h| (*h).to_string()).collect::<Vec<_>>();
+    for row in std::iter::once(&header_cells).chain(rows) {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        let _ = writeln!(output, "{}", line.trim_end());
+    }
+    output.pop();
+    output
+}
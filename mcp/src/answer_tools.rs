@@ -0,0 +1,464 @@
+//! Answer synthesis over search results for MCP
+//!
+//! `answer_question` runs the same scored search as the query tools, hands
+//! the best excerpts to Claude with the question and returns the answer with
+//! the sources it cited. When Claude cannot be reached the ranked excerpts
+//! are returned instead, so simple clients always get something usable.
+
+use crate::embedding_cache::account_embeddings;
+use crate::metrics::account_llm_run;
+use crate::tools::{ExecutionContext, Tool};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use db::chunks::group_adjacent_chunks;
+use db::queries::{DocumentQueries, MetadataFilters};
+use db::{DatabasePool, ScoredDocument};
+use discovery::{prompt_runner_from_env, result_text, LlmUseCase, PromptRunner};
+use embed::{embedding_client_from_env, EmbeddingClient};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info_span, warn, Instrument};
+
+/// Documents searched when the call gives no `limit`
+const DEFAULT_TOP_K: i64 = 5;
+
+/// Largest accepted `limit`
+const MAX_TOP_K: i64 = 20;
+
+/// Excerpts are dropped rather than cut below this many tokens
+const MIN_EXCERPT_TOKENS: usize = 50;
+
+/// Answers kept in the cache at once; the oldest is dropped beyond this
+const MAX_CACHED_ANSWERS: usize = 256;
+
+/// Limits for `answer_question`
+#[derive(Debug, Clone)]
+pub struct AnswerConfig {
+    /// Prompt size limit, question and excerpts included
+    pub max_input_tokens: usize,
+    /// How long an answer is reused for the same question
+    pub cache_ttl: Duration,
+    /// Limit on a single Claude run
+    pub timeout: Duration,
+}
+
+impl AnswerConfig {
+    /// Read `ANSWER_MAX_INPUT_TOKENS`, `ANSWER_CACHE_TTL_SECS` and
+    /// `ANSWER_TIMEOUT_SECS`, falling back to the defaults
+    #[must_use]
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let env_u64 = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+        Self {
+            max_input_tokens: env_u64("ANSWER_MAX_INPUT_TOKENS")
+                .filter(|&n| n > 0)
+                .and_then(|n| usize::try_from(n).ok())
+                .unwrap_or(defaults.max_input_tokens),
+            cache_ttl: env_u64("ANSWER_CACHE_TTL_SECS")
+                .map_or(defaults.cache_ttl, Duration::from_secs),
+            timeout: env_u64("ANSWER_TIMEOUT_SECS")
+                .filter(|&n| n > 0)
+                .map_or(defaults.timeout, Duration::from_secs),
+        }
+    }
+}
+
+impl Default for AnswerConfig {
+    fn default() -> Self {
+        Self {
+            max_input_tokens: 8_000,
+            cache_ttl: Duration::from_secs(5 * 60),
+            timeout: Duration::from_secs(120),
+        }
+    }
+}
+
+/// One search hit as shown to the model, numbered from 1
+struct Excerpt {
+    index: usize,
+    id: uuid::Uuid,
+    source_name: String,
+    doc_path: String,
+    module_path: Option<String>,
+    text: String,
+}
+
+impl Excerpt {
+    fn source_json(&self) -> Value {
+        json!({
+            "index": self.index,
+            "id": self.id,
+            "source_name": self.source_name,
+            "doc_path": self.doc_path,
+            "module_path": self.module_path,
+        })
+    }
+}
+
+/// First part of `text` that fits in `max_tokens`, with its token count
+fn trim_to_tokens(text: &str, max_tokens: usize) -> (String, usize) {
+    let tokens = embed::token_count(text);
+    if tokens <= max_tokens {
+        return (text.to_string(), tokens);
+    }
+
+    // Start from the proportional length and shrink until it fits
+    let mut chars = text.chars().count() * max_tokens / tokens.max(1);
+    loop {
+        let cut: String = text.chars().take(chars).collect();
+        let tokens = embed::token_count(&cut);
+        if tokens <= max_tokens || chars == 0 {
+            return (cut, tokens);
+        }
+        chars = chars * 9 / 10;
+    }
+}
+
+/// Prompt asking for an answer from the numbered `excerpts`
+fn build_prompt(question: &str, excerpts: &[Excerpt]) -> String {
+    let mut prompt = format!(
+        "Answer the question using only the numbered documentation excerpts below. \
+         Cite every excerpt you rely on by its number in square brackets. \
+         If the excerpts do not contain the answer, say so instead of guessing.\n\n\
+         Question: {question}\n\nSources:\n"
+    );
+    for excerpt in excerpts {
+        let location = excerpt.module_path.as_deref().map_or_else(
+            || excerpt.doc_path.clone(),
+            |module_path| format!("{} ({module_path})", excerpt.doc_path),
+        );
+        let _ = write!(
+            &mut prompt,
+            "\n[{}] {location}\n{}\n",
+            excerpt.index, excerpt.text
+        );
+    }
+    prompt
+}
+
+/// Numbers cited as `[n]` in `answer`
+fn cited_indices(answer: &str) -> BTreeSet<usize> {
+    answer
+        .split('[')
+        .skip(1)
+        .filter_map(|rest| rest.split_once(']'))
+        .filter_map(|(inside, _)| inside.trim().parse().ok())
+        .collect()
+}
+
+/// Doc type, question and result count of a cached answer
+type AnswerKey = (String, String, i64);
+
+/// Recently produced answers
+struct AnswerCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<AnswerKey, (Instant, Value)>>,
+}
+
+impl AnswerCache {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get(&self, key: &AnswerKey) -> Option<Value> {
+        let entries = self.entries.lock().ok()?;
+        entries
+            .get(key)
+            .filter(|(stored, _)| stored.elapsed() < self.ttl)
+            .map(|(_, answer)| answer.clone())
+    }
+
+    fn insert(&self, key: AnswerKey, answer: Value) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+        entries.retain(|_, (stored, _)| stored.elapsed() < self.ttl);
+        if entries.len() >= MAX_CACHED_ANSWERS {
+            if let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, (stored, _))| *stored)
+                .map(|(key, _)| key.clone())
+            {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(key, (Instant::now(), answer));
+    }
+}
+
+/// Answer a question from the indexed documentation
+pub struct AnswerQuestionTool {
+    db_pool: DatabasePool,
+    embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
+    runner: Arc<dyn PromptRunner>,
+    config: AnswerConfig,
+    cache: AnswerCache,
+}
+
+impl AnswerQuestionTool {
+    /// Create an answer tool using the configured embedding provider and the
+    /// runner selected by `ANSWER_LLM_PROVIDER` or `LLM_PROVIDER`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the embedding client or the prompt runner fails to
+    /// initialize.
+    pub fn new(db_pool: DatabasePool) -> Result<Self> {
+        Ok(Self::with_clients(
+            db_pool,
+            embedding_client_from_env()?,
+            prompt_runner_from_env(LlmUseCase::Answer)?,
+            AnswerConfig::from_env(),
+        ))
+    }
+
+    /// Create an answer tool with explicit clients and limits
+    #[must_use]
+    pub fn with_clients(
+        db_pool: DatabasePool,
+        embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
+        runner: Arc<dyn PromptRunner>,
+        config: AnswerConfig,
+    ) -> Self {
+        Self {
+            cache: AnswerCache::new(config.cache_ttl),
+            db_pool,
+            embedding_client,
+            runner,
+            config,
+        }
+    }
+
+    /// Number and trim search hits so the prompt stays within the token budget
+    ///
+    /// The budget left after the question is shared between the remaining
+    /// hits, so short excerpts leave room for later ones.
+    fn excerpts(&self, question: &str, results: &[ScoredDocument]) -> Vec<Excerpt> {
+        let overhead = embed::token_count(&build_prompt(question, &[]));
+        let mut available = self.config.max_input_tokens.saturating_sub(overhead);
+        let mut excerpts = Vec::new();
+
+        for (position, result) in results.iter().enumerate() {
+            let share = available / (results.len() - position);
+            if share < MIN_EXCERPT_TOKENS {
+                break;
+            }
+            let doc = &result.document;
+            let (text, tokens) = trim_to_tokens(&doc.content, share);
+            // Header line and spacing of the excerpt in the prompt
+            available = available.saturating_sub(tokens + 16);
+            excerpts.push(Excerpt {
+                index: excerpts.len() + 1,
+                id: doc.id,
+                source_name: doc.source_name.clone(),
+                doc_path: doc.doc_path.clone(),
+                module_path: doc
+                    .metadata
+                    .get("module_path")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                text,
+            });
+        }
+        excerpts
+    }
+}
+
+#[async_trait]
+impl Tool for AnswerQuestionTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "answer_question",
+            "description": "Answer a question from the indexed documentation. Searches the doc type, asks Claude to answer from the top excerpts only, and returns the answer as JSON with the sources it cited (doc_path and module_path). If Claude is unavailable the top search results are returned instead, with a note.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "question": {
+                        "type": "string",
+                        "description": "The question to answer"
+                    },
+                    "doc_type": {
+                        "type": "string",
+                        "description": "Documentation type to search (default: rust)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Number of documents to consider (default: 5, max: 20)",
+                        "minimum": 1,
+                        "maximum": MAX_TOP_K
+                    }
+                },
+                "required": ["question"]
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        self.execute_with_context(arguments, &ExecutionContext::default())
+            .await
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ExecutionContext,
+    ) -> Result<String> {
+        let question = arguments
+            .get("question")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .ok_or_else(|| anyhow!("Missing required parameter: question"))?;
+        let doc_type = arguments
+            .get("doc_type")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .unwrap_or("rust");
+        let limit = arguments
+            .get("limit")
+            .and_then(Value::as_i64)
+            .unwrap_or(DEFAULT_TOP_K)
+            .clamp(1, MAX_TOP_K);
+
+        let cache_key = (doc_type.to_string(), question.to_string(), limit);
+        if let Some(mut cached) = self.cache.get(&cache_key) {
+            cached["cached"] = json!(true);
+            return Ok(serde_json::to_string_pretty(&cached)?);
+        }
+
+        let query_embedding = context.run(self.embedding_client.embed(question)).await?;
+        account_embeddings(
+            &self.embedding_client.embedding_config().model,
+            1,
+            embed::token_count(question) as u64,
+        );
+        let results = context
+            .run(DocumentQueries::doc_type_search_scored(
+                self.db_pool.pool(),
+                doc_type,
+                question,
+                &query_embedding,
+                limit,
+                &MetadataFilters::default(),
+            ))
+            .await?;
+        let results = group_adjacent_chunks(results);
+
+        let excerpts = self.excerpts(question, &results);
+        if excerpts.is_empty() {
+            let response = json!({
+                "question": question,
+                "doc_type": doc_type,
+                "answer": null,
+                "sources": [],
+                "note": format!("No {doc_type} documentation matched the question"),
+            });
+            return Ok(serde_json::to_string_pretty(&response)?);
+        }
+
+        let prompt = build_prompt(question, &excerpts);
+        let input_tokens = embed::token_count(&prompt);
+        let outcome = context
+            .run(async {
+                let span = info_span!("llm", purpose = "answer", input_tokens);
+                tokio::time::timeout(
+                    self.config.timeout,
+                    self.runner.run(&prompt).instrument(span),
+                )
+                .await
+                .map_err(|_| {
+                    anyhow!(
+                        "Claude did not answer within {} seconds",
+                        self.config.timeout.as_secs()
+                    )
+                })?
+            })
+            .await;
+
+        let output = match outcome {
+            Ok(output) => output,
+            Err(e) if e.is::<crate::tools::RequestCancelled>() => return Err(e),
+            Err(e) => {
+                // Degrade to the ranked excerpts; not cached so a later call retries Claude
+                warn!("answer_question falling back to search results: {}", e);
+                let results: Vec<Value> = excerpts
+                    .iter()
+                    .map(|excerpt| {
+                        let mut entry = excerpt.source_json();
+                        entry["excerpt"] = json!(excerpt.text);
+                        entry
+                    })
+                    .collect();
+                let response = json!({
+                    "question": question,
+                    "doc_type": doc_type,
+                    "answer": null,
+                    "degraded": true,
+                    "note": format!("The answer model is unavailable ({e}); returning the top search results instead"),
+                    "results": results,
+                });
+                return Ok(serde_json::to_string_pretty(&response)?);
+            }
+        };
+
+        let (usage, cost_usd) = account_llm_run(self.runner.as_ref(), &prompt, &output);
+        let answer = result_text(&output);
+        let cited = cited_indices(&answer);
+        let sources: Vec<Value> = excerpts
+            .iter()
+            .filter(|excerpt| cited.contains(&excerpt.index))
+            .map(Excerpt::source_json)
+            .collect();
+        let response = json!({
+            "question": question,
+            "doc_type": doc_type,
+            "answer": answer,
+            "sources": sources,
+            "excerpts_considered": excerpts.len(),
+            "input_tokens": input_tokens,
+            "usage": usage,
+            "cost_usd": cost_usd,
+            "cached": false,
+        });
+        self.cache.insert(cache_key, response.clone());
+
+        Ok(serde_json::to_string_pretty(&response)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cited_indices() {
+        let cited = cited_indices("Use spawn [2], see also [ 4 ] and [x] or [].");
+        assert_eq!(cited.into_iter().collect::<Vec<_>>(), vec![2, 4]);
+    }
+
+    #[test]
+    fn test_trim_to_tokens_fits_budget() {
+        let text = "tokio runtime ".repeat(200);
+        let (trimmed, tokens) = trim_to_tokens(&text, 60);
+        assert!(tokens <= 60);
+        assert!(text.starts_with(&trimmed));
+        assert!(!trimmed.is_empty());
+
+        let (whole, _) = trim_to_tokens("short", 60);
+        assert_eq!(whole, "short");
+    }
+}
@@ -0,0 +1,267 @@
+//! Tool invocation audit log
+//!
+//! Every `tools/call` is recorded in the `tool_audit_log` table with the
+//! caller, the arguments (sensitive values redacted), the outcome and the
+//! duration. Entries are queued on a bounded channel and written by a
+//! background task, so auditing never delays or fails a tool call; when the
+//! queue is full the entry is dropped and counted in the metrics.
+
+use crate::metrics::metrics;
+use chrono::Utc;
+use db::{NewToolAuditEntry, ToolAuditQueries};
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+/// Replacement for redacted argument values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Key fragments that mark an argument as sensitive
+const SENSITIVE_KEY_FRAGMENTS: &[&str] = &[
+    "token",
+    "secret",
+    "password",
+    "passwd",
+    "api_key",
+    "apikey",
+    "authorization",
+    "credential",
+    "private_key",
+    "cookie",
+];
+
+/// Characters of error text kept per entry
+const MAX_ERROR_CHARS: usize = 1000;
+
+/// Entries written per insert statement
+const WRITE_BATCH_SIZE: usize = 100;
+
+/// Audit log settings
+#[derive(Debug, Clone)]
+pub struct AuditConfig {
+    /// Additional argument names to redact, lowercased
+    pub redact_keys: Vec<String>,
+    /// Days entries are kept before the retention sweep deletes them
+    pub retention_days: i32,
+    /// Entries that may wait for the writer before new ones are dropped
+    pub queue_capacity: usize,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            redact_keys: Vec::new(),
+            retention_days: 90,
+            queue_capacity: 1000,
+        }
+    }
+}
+
+impl AuditConfig {
+    /// Load settings from the environment
+    ///
+    /// - `MCP_AUDIT_REDACT_KEYS`: comma-separated argument names to redact in
+    ///   addition to token- and secret-like names
+    /// - `MCP_AUDIT_RETENTION_DAYS` (default 90)
+    /// - `MCP_AUDIT_QUEUE_CAPACITY` (default 1000)
+    #[must_use]
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            redact_keys: std::env::var("MCP_AUDIT_REDACT_KEYS")
+                .map(|v| {
+                    v.split(',')
+                        .map(|key| key.trim().to_ascii_lowercase().replace('-', "_"))
+                        .filter(|key| !key.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            retention_days: std::env::var("MCP_AUDIT_RETENTION_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&n| n > 0)
+                .unwrap_or(defaults.retention_days),
+            queue_capacity: std::env::var("MCP_AUDIT_QUEUE_CAPACITY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&n| n > 0)
+                .unwrap_or(defaults.queue_capacity),
+        }
+    }
+}
+
+/// Who made a tool call
+#[derive(Debug, Clone, Default)]
+pub struct ToolCaller {
+    pub session_id: Option<String>,
+    /// Bearer token label of the authenticated client
+    pub client_label: Option<String>,
+    pub user_agent: Option<String>,
+    /// Whether the caller presented the admin token
+    pub admin: bool,
+}
+
+impl ToolCaller {
+    /// Caller identified only by its session
+    #[must_use]
+    pub fn from_session(session_id: Option<&str>) -> Self {
+        Self {
+            session_id: session_id.map(ToString::to_string),
+            ..Self::default()
+        }
+    }
+}
+
+/// Handle for queueing audit entries to the background writer
+#[derive(Clone)]
+pub struct AuditLogger {
+    sender: mpsc::Sender<NewToolAuditEntry>,
+    redact_keys: Arc<[String]>,
+}
+
+impl AuditLogger {
+    /// Spawn the writer task for `pool` and return a handle to it
+    #[must_use]
+    pub fn start(pool: PgPool, config: &AuditConfig) -> Self {
+        let (sender, mut receiver) = mpsc::channel::<NewToolAuditEntry>(config.queue_capacity);
+        tokio::spawn(async move {
+            let mut batch = Vec::with_capacity(WRITE_BATCH_SIZE);
+            while receiver.recv_many(&mut batch, WRITE_BATCH_SIZE).await > 0 {
+                if let Err(e) = ToolAuditQueries::insert_batch(&pool, &batch).await {
+                    warn!("Failed to write {} audit log entries: {}", batch.len(), e);
+                }
+                batch.clear();
+            }
+        });
+
+        Self {
+            sender,
+            redact_keys: config.redact_keys.clone().into(),
+        }
+    }
+
+    /// Queue an entry for a finished tool call
+    ///
+    /// Never blocks; the entry is dropped and counted when the queue is full.
+    pub fn record(
+        &self,
+        caller: &ToolCaller,
+        tool_name: &str,
+        arguments: &Value,
+        elapsed: Duration,
+        error: Option<&str>,
+    ) {
+        let entry = NewToolAuditEntry {
+            created_at: Utc::now(),
+            session_id: caller.session_id.clone(),
+            client_label: caller.client_label.clone(),
+            user_agent: caller.user_agent.clone(),
+            tool_name: tool_name.to_string(),
+            arguments: redact_arguments(arguments, &self.redact_keys),
+            success: error.is_none(),
+            duration_ms: i64::try_from(elapsed.as_millis()).unwrap_or(i64::MAX),
+            error: error.map(truncate_error),
+        };
+        if self.sender.try_send(entry).is_err() {
+            metrics().increment_audit_entries_dropped();
+            debug!("Audit log queue full; dropped entry for {}", tool_name);
+        }
+    }
+}
+
+/// Whether an argument named `key` holds a value that must not be stored
+fn is_sensitive_key(key: &str, redact_keys: &[String]) -> bool {
+    let key = key.to_ascii_lowercase().replace('-', "_");
+    SENSITIVE_KEY_FRAGMENTS
+        .iter()
+        .any(|fragment| key.contains(fragment))
+        || redact_keys.contains(&key)
+}
+
+/// Copy of `arguments` with the values of sensitive keys replaced, at any depth
+#[must_use]
+pub fn redact_arguments(arguments: &Value, redact_keys: &[String]) -> Value {
+    match arguments {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let value = if is_sensitive_key(key, redact_keys) {
+                        Value::String(REDACTED.to_string())
+                    } else {
+                        redact_arguments(value, redact_keys)
+                    };
+                    (key.clone(), value)
+                })
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| redact_arguments(item, redact_keys))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn truncate_error(error: &str) -> String {
+    match error.char_indices().nth(MAX_ERROR_CHARS) {
+        Some((end, _)) => format!("{}…", &error[..end]),
+        None => error.to_string(),
+    }
+}
+
+/// Delete audit entries older than `retention_days`
+///
+/// Run by the `audit_log_retention` maintenance task.
+///
+/// # Errors
+///
+/// Returns an error if the entries cannot be deleted.
+pub async fn prune_audit_log(pool: &PgPool, retention_days: i32) -> anyhow::Result<String> {
+    let pruned = ToolAuditQueries::prune(pool, retention_days).await?;
+    Ok(format!(
+        "pruned {pruned} entries older than {retention_days} days"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_redact_arguments() {
+        let arguments = json!({
+            "crate_name": "serde",
+            "token": "abc",
+            "GitHub-Token": "def",
+            "idempotency_key": "job-1",
+            "nested": { "client_secret": "ghi", "ticket": "T-1" },
+            "headers": [{ "Authorization": "Bearer x" }]
+        });
+
+        let redacted = redact_arguments(&arguments, &["ticket".to_string()]);
+        assert_eq!(
+            redacted,
+            json!({
+                "crate_name": "serde",
+                "token": REDACTED,
+                "GitHub-Token": REDACTED,
+                "idempotency_key": "job-1",
+                "nested": { "client_secret": REDACTED, "ticket": REDACTED },
+                "headers": [{ "Authorization": REDACTED }]
+            })
+        );
+    }
+
+    #[test]
+    fn test_truncate_error() {
+        assert_eq!(truncate_error("short"), "short");
+        let long = "é".repeat(MAX_ERROR_CHARS + 5);
+        assert_eq!(truncate_error(&long).chars().count(), MAX_ERROR_CHARS + 1);
+    }
+}
@@ -0,0 +1,473 @@
+//! Scheduled updates of ingested crates
+//!
+//! Opt in with `CRATE_AUTO_UPDATE_INTERVAL_SECS`. After a random start delay
+//! the scheduler started by [`start_scheduler`] periodically compares every
+//! active crate with its newest release on crates.io and enqueues a forced
+//! update job for each outdated one. The job dispatcher runs those jobs under
+//! the usual concurrency limit.
+//!
+//! A crate is left alone while it already has a queued or running job, or
+//! when its source sets `config.auto_update` to `false`. No run starts while
+//! more than `CRATE_AUTO_UPDATE_MAX_BACKLOG` jobs are active, and a run stops
+//! queueing once it reaches that backlog.
+
+use crate::job_queue::{CrateJobOptions, CrateJobProcessor};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use db::{
+    models::PaginationParams,
+    queries::{CrateJobQueries, CrateQueries},
+    DatabasePool,
+};
+use rand::Rng;
+use rust_crates::{is_newer_version, RustLoader};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Crates listed per page while scanning for updates
+const LIST_PAGE_SIZE: i32 = 100;
+
+/// Scheduler settings read from the environment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoUpdateConfig {
+    /// Time between runs
+    pub interval: Duration,
+    /// Upper bound of the random delay before the first run
+    pub max_start_jitter: Duration,
+    /// Active jobs above which a run is skipped
+    pub max_backlog: usize,
+}
+
+impl AutoUpdateConfig {
+    /// Read the scheduler settings, or `None` when auto-update is disabled
+    ///
+    /// `CRATE_AUTO_UPDATE_INTERVAL_SECS` enables the scheduler;
+    /// `CRATE_AUTO_UPDATE_JITTER_SECS` (default 300) and
+    /// `CRATE_AUTO_UPDATE_MAX_BACKLOG` (default 10) tune it.
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        let interval = std::env::var("CRATE_AUTO_UPDATE_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|&secs| secs > 0)
+            .map(Duration::from_secs)?;
+        let max_start_jitter = std::env::var("CRATE_AUTO_UPDATE_JITTER_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .map_or(Duration::from_secs(300), Duration::from_secs);
+        let max_backlog = std::env::var("CRATE_AUTO_UPDATE_MAX_BACKLOG")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(10);
+        Some(Self {
+            interval,
+            max_start_jitter,
+            max_backlog,
+        })
+    }
+
+    /// Random delay before the first run, never longer than one interval
+    fn start_delay(&self) -> Duration {
+        let bound = self.max_start_jitter.min(self.interval);
+        if bound.is_zero() {
+            return Duration::ZERO;
+        }
+        let millis = u64::try_from(bound.as_millis()).unwrap_or(u64::MAX);
+        Duration::from_millis(rand::rng().random_range(0..=millis))
+    }
+}
+
+/// Source of the newest published version of a crate
+#[async_trait]
+pub trait VersionSource: Send + Sync {
+    /// Newest version of `crate_name`
+    async fn newest_version(&self, crate_name: &str) -> Result<String>;
+}
+
+/// crates.io lookups through the rate-limited crawl client
+pub struct CratesIoVersions {
+    loader: tokio::sync::Mutex<RustLoader>,
+}
+
+impl CratesIoVersions {
+    /// Create a lookup with its own rate-limited client
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            loader: tokio::sync::Mutex::new(RustLoader::new()),
+        }
+    }
+}
+
+impl Default for CratesIoVersions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl VersionSource for CratesIoVersions {
+    async fn newest_version(&self, crate_name: &str) -> Result<String> {
+        let metadata = self
+            .loader
+            .lock()
+            .await
+            .load_crate_metadata(crate_name)
+            .await?;
+        Ok(metadata.newest_version)
+    }
+}
+
+/// Source of the current time, replaceable in tests
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The system clock
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// What a run decided for one crate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum UpdateDecision {
+    /// An update job to `version` was enqueued
+    Queued {
+        version: String,
+        job_id: Uuid,
+    },
+    UpToDate,
+    /// The source sets `config.auto_update = false`
+    OptedOut,
+    /// A queued or running job already covers the crate
+    JobActive,
+    /// Left for a later run because the backlog is full
+    Deferred {
+        version: String,
+    },
+    /// The newest version could not be determined or the job not created
+    Failed {
+        error: String,
+    },
+}
+
+/// A crate considered by a run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateDecision {
+    pub crate_name: String,
+    pub current_version: String,
+    #[serde(flatten)]
+    pub decision: UpdateDecision,
+}
+
+/// Outcome of one scheduler run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoUpdateRun {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// Why the run did not check any crate
+    pub skipped: Option<String>,
+    pub decisions: Vec<CrateDecision>,
+}
+
+impl AutoUpdateRun {
+    /// Number of update jobs the run enqueued
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.decisions
+            .iter()
+            .filter(|d| matches!(d.decision, UpdateDecision::Queued { .. }))
+            .count()
+    }
+
+    /// One-line status, e.g. `auto-update: last ran 5m ago, 3 crates queued`
+    #[must_use]
+    pub fn summary(&self, now: DateTime<Utc>) -> String {
+        let ago = format_age(now.signed_duration_since(self.finished_at));
+        if let Some(reason) = &self.skipped {
+            return format!("auto-update: last ran {ago}, skipped ({reason})");
+        }
+        let queued = self.queued();
+        let mut summary = format!(
+            "auto-update: last ran {ago}, {queued} {} queued",
+            if queued == 1 { "crate" } else { "crates" }
+        );
+        let failed = self
+            .decisions
+            .iter()
+            .filter(|d| matches!(d.decision, UpdateDecision::Failed { .. }))
+            .count();
+        if failed > 0 {
+            summary.push_str(&format!(", {failed} failed"));
+        }
+        summary
+    }
+}
+
+fn format_age(age: chrono::Duration) -> String {
+    let minutes = age.num_minutes().max(0);
+    if minutes < 1 {
+        "just now".to_string()
+    } else if minutes < 60 {
+        format!("{minutes}m ago")
+    } else if minutes < 60 * 24 {
+        format!("{}h ago", minutes / 60)
+    } else {
+        format!("{}d ago", minutes / (60 * 24))
+    }
+}
+
+static LAST_RUN: OnceLock<Mutex<Option<AutoUpdateRun>>> = OnceLock::new();
+
+fn last_run_slot() -> &'static Mutex<Option<AutoUpdateRun>> {
+    LAST_RUN.get_or_init(|| Mutex::new(None))
+}
+
+/// The most recent run in this process, if any
+#[must_use]
+pub fn last_run() -> Option<AutoUpdateRun> {
+    last_run_slot()
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .clone()
+}
+
+fn record_run(run: &AutoUpdateRun) {
+    *last_run_slot()
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(run.clone());
+}
+
+/// Check every active crate once and enqueue updates for outdated ones
+///
+/// The run is recorded for [`last_run`] whether or not it checked anything.
+///
+/// # Errors
+///
+/// Returns an error if active jobs, crates or opt-outs cannot be read.
+pub async fn run_once(
+    db_pool: &DatabasePool,
+    versions: &dyn VersionSource,
+    clock: &dyn Clock,
+    config: &AutoUpdateConfig,
+) -> Result<AutoUpdateRun> {
+    let started_at = clock.now();
+    let active_jobs = CrateJobQueries::find_active_jobs(db_pool.pool()).await?;
+    if active_jobs.len() > config.max_backlog {
+        let run = AutoUpdateRun {
+            started_at,
+            finished_at: clock.now(),
+            skipped: Some(format!(
+                "backlog of {} jobs exceeds {}",
+                active_jobs.len(),
+                config.max_backlog
+            )),
+            decisions: Vec::new(),
+        };
+        record_run(&run);
+        return Ok(run);
+    }
+
+    let mut backlog = active_jobs.len();
+    let active: HashSet<String> = active_jobs.into_iter().map(|job| job.crate_name).collect();
+    let opted_out: HashSet<String> = CrateQueries::find_auto_update_opt_outs(db_pool.pool())
+        .await?
+        .into_iter()
+        .collect();
+    let processor = CrateJobProcessor::new(db_pool.clone());
+
+    let mut decisions = Vec::new();
+    for (crate_name, current_version) in list_current_versions(db_pool).await? {
+        let decision = if opted_out.contains(&crate_name) {
+            UpdateDecision::OptedOut
+        } else if active.contains(&crate_name) {
+            UpdateDecision::JobActive
+        } else {
+            match versions.newest_version(&crate_name).await {
+                Ok(newest) if !is_newer_version(&current_version, &newest) => {
+                    UpdateDecision::UpToDate
+                }
+                Ok(newest) if backlog >= config.max_backlog => {
+                    UpdateDecision::Deferred { version: newest }
+                }
+                Ok(newest) => {
+                    let options = CrateJobOptions {
+                        version: Some(newest.clone()),
+                        force_update: true,
+                        ..CrateJobOptions::default()
+                    };
+                    match processor
+                        .enqueue_add_crate_job(&crate_name, &options, None)
+                        .await
+                    {
+                        Ok(enqueued) => {
+                            let job_id = enqueued.job.id;
+                            backlog += 1;
+                            info!(
+                                "Auto-update queued {} {} -> {} (job {})",
+                                crate_name, current_version, newest, job_id
+                            );
+                            UpdateDecision::Queued {
+                                version: newest,
+                                job_id,
+                            }
+                        }
+                        Err(e) => UpdateDecision::Failed {
+                            error: e.to_string(),
+                        },
+                    }
+                }
+                Err(e) => UpdateDecision::Failed {
+                    error: e.to_string(),
+                },
+            }
+        };
+        decisions.push(CrateDecision {
+            crate_name,
+            current_version,
+            decision,
+        });
+    }
+
+    let run = AutoUpdateRun {
+        started_at,
+        finished_at: clock.now(),
+        skipped: None,
+        decisions,
+    };
+    record_run(&run);
+    Ok(run)
+}
+
+/// Active crates with the highest version stored for each, ordered by name
+///
+/// `list_crates` lists a crate once per stored version, so pages are read
+/// until one comes back empty.
+async fn list_current_versions(db_pool: &DatabasePool) -> Result<BTreeMap<String, String>> {
+    let mut crates: BTreeMap<String, String> = BTreeMap::new();
+    for page in 1.. {
+        let pagination = PaginationParams::new(Some(page), Some(LIST_PAGE_SIZE));
+        let listed = CrateQueries::list_crates(db_pool.pool(), &pagination, None).await?;
+        if listed.items.is_empty() {
+            break;
+        }
+        for info in listed.items {
+            match crates.get_mut(&info.name) {
+                Some(version) if is_newer_version(version, &info.version) => {
+                    *version = info.version;
+                }
+                Some(_) => {}
+                None => {
+                    crates.insert(info.name, info.version);
+                }
+            }
+        }
+    }
+    Ok(crates)
+}
+
+/// Start the auto-update scheduler when `CRATE_AUTO_UPDATE_INTERVAL_SECS` is set
+pub fn start_scheduler(db_pool: DatabasePool) {
+    let Some(config) = AutoUpdateConfig::from_env() else {
+        return;
+    };
+    tokio::spawn(async move {
+        let delay = config.start_delay();
+        info!(
+            "Crate auto-update every {}s, first run in {}s",
+            config.interval.as_secs(),
+            delay.as_secs()
+        );
+        tokio::time::sleep(delay).await;
+
+        let versions = CratesIoVersions::new();
+        let mut interval = tokio::time::interval(config.interval);
+        loop {
+            interval.tick().await;
+            match run_once(&db_pool, &versions, &SystemClock, &config).await {
+                Ok(run) => info!("{}", run.summary(Utc::now())),
+                Err(e) => warn!("Crate auto-update run failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn run(skipped: Option<&str>, decisions: Vec<UpdateDecision>) -> AutoUpdateRun {
+        let at = Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        AutoUpdateRun {
+            started_at: at,
+            finished_at: at,
+            skipped: skipped.map(ToString::to_string),
+            decisions: decisions
+                .into_iter()
+                .map(|decision| CrateDecision {
+                    crate_name: "demo".to_string(),
+                    current_version: "1.0.0".to_string(),
+                    decision,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_summary_reports_queued_and_failed_crates() {
+        let queued = UpdateDecision::Queued {
+            version: "1.1.0".to_string(),
+            job_id: Uuid::nil(),
+        };
+        let run = run(
+            None,
+            vec![
+                queued.clone(),
+                queued.clone(),
+                queued,
+                UpdateDecision::UpToDate,
+                UpdateDecision::Failed {
+                    error: "HTTP status: 503".to_string(),
+                },
+            ],
+        );
+        let later = run.finished_at + chrono::Duration::minutes(5);
+        assert_eq!(
+            run.summary(later),
+            "auto-update: last ran 5m ago, 3 crates queued, 1 failed"
+        );
+    }
+
+    #[test]
+    fn test_summary_reports_skipped_runs() {
+        let run = run(Some("backlog of 12 jobs exceeds 10"), Vec::new());
+        let later = run.finished_at + chrono::Duration::hours(3);
+        assert_eq!(
+            run.summary(later),
+            "auto-update: last ran 3h ago, skipped (backlog of 12 jobs exceeds 10)"
+        );
+    }
+
+    #[test]
+    fn test_start_delay_stays_within_one_interval() {
+        let config = AutoUpdateConfig {
+            interval: Duration::from_secs(60),
+            max_start_jitter: Duration::from_secs(300),
+            max_backlog: 10,
+        };
+        for _ in 0..100 {
+            assert!(config.start_delay() <= config.interval);
+        }
+    }
+}
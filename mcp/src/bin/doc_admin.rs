@@ -0,0 +1,33 @@
+//! Job and crate administration CLI (`doc-admin`)
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use db::DatabasePool;
+use mcp::admin::{run, AdminCli};
+use tracing::Level;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenvy::dotenv().ok();
+    tracing_subscriber::fmt()
+        .with_max_level(Level::WARN)
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .init();
+
+    let cli = AdminCli::parse();
+
+    let database_url = cli
+        .database_url
+        .clone()
+        .or_else(|| std::env::var("DATABASE_URL").ok())
+        .ok_or_else(|| {
+            anyhow::anyhow!("DATABASE_URL is required (pass --database-url or set DATABASE_URL)")
+        })?;
+    let db_pool = DatabasePool::connect(&database_url)
+        .await
+        .context("Failed to connect to database")?;
+
+    println!("{}", run(&cli, &db_pool).await?);
+    Ok(())
+}
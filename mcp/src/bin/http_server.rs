@@ -3,11 +3,14 @@
 //! This binary provides the main HTTP endpoint for MCP communication with Streamable HTTP transport support.
 
 use anyhow::Result;
-use db::{DatabaseMigrationManager, DatabasePool, MigrationInfo, QueryPerformanceMonitor};
+use db::{
+    DatabaseMigrationManager, DatabasePool, DocumentQueries, DocumentSourceQueries, MigrateOnStart,
+    MigrationInfo, QueryPerformanceMonitor,
+};
 use dotenvy::dotenv;
+use mcp::config::ConfigLoader;
 use mcp::McpServer;
 use std::env;
-use tokio::signal;
 use tracing::{error, info, warn};
 //use tracing_subscriber;
 
@@ -26,6 +29,19 @@ async fn main() -> Result<()> {
                 // Run migrations only and exit (for K8s migration jobs)
                 return run_migrations_only().await;
             }
+            "--validate-config" => {
+                // Check the tools configuration and exit without starting the server
+                return run_validate_config(args.get(2).map(String::as_str));
+            }
+            "--recreate-embedding-column" => {
+                // Switch documents.embedding to the configured dimension and exit
+                return run_recreate_embedding_column(args.get(2).map(String::as_str)).await;
+            }
+            "--rewrite-document-ids" => {
+                // Give existing documents their stable ids and exit
+                return run_rewrite_document_ids(args.get(2).is_some_and(|a| a == "--dry-run"))
+                    .await;
+            }
             _ => {
                 // Continue with normal startup
             }
@@ -35,26 +51,22 @@ async fn main() -> Result<()> {
     // Load environment variables
     dotenv().ok();
 
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            env::var("RUST_LOG").unwrap_or_else(|_| "info,doc_server=debug".to_string()),
-        )
-        .init();
+    // Initialize tracing, with OTLP export when configured
+    let _telemetry = mcp::telemetry::init_tracing("info,doc_server=debug");
 
     info!("Starting Doc Server HTTP server...");
 
     // Get configuration from environment
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
     // Prefer PORT, then MCP_PORT, default to 3001 to align with container defaults
     let port = env::var("PORT")
         .or_else(|_| env::var("MCP_PORT"))
         .unwrap_or_else(|_| "3001".to_string())
         .parse::<u16>()
         .expect("PORT/MCP_PORT must be a valid number");
+    let migrate_on_start = MigrateOnStart::from_env()?;
 
-    // Initialize database
-    let db_pool = DatabasePool::new(&database_url).await?;
+    // Initialize database; pool settings come from DATABASE_URL and DB_*
+    let db_pool = DatabasePool::from_env().await?;
 
     // Initialize and run database migrations
     let mut migration_manager = DatabaseMigrationManager::new(db_pool.pool().clone()).await?;
@@ -74,13 +86,13 @@ async fn main() -> Result<()> {
         }
     }
 
-    // Apply pending migrations
-    match migration_manager.apply_migrations().await {
+    // Apply or check pending migrations according to MIGRATE_ON_START
+    match migration_manager.run_on_start(migrate_on_start).await {
         Ok(applied) => {
-            if applied.is_empty() {
-                info!("Database schema is up to date");
-            } else {
+            if !applied.is_empty() {
                 info!("Successfully applied {} database migrations", applied.len());
+            } else if migrate_on_start == MigrateOnStart::Apply {
+                info!("Database schema is up to date");
             }
         }
         Err(e) => {
@@ -89,6 +101,17 @@ async fn main() -> Result<()> {
         }
     }
 
+    // Full-text indexes for doc types configured with another language
+    if migrate_on_start == MigrateOnStart::Apply {
+        match DocumentSourceQueries::ensure_fts_indexes(db_pool.pool()).await {
+            Ok(languages) if !languages.is_empty() => {
+                info!("Full-text indexes ready for: {}", languages.join(", "));
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to create full-text indexes: {}", e),
+        }
+    }
+
     // Run performance benchmarks to ensure queries meet <2s requirement
     info!("Running database performance benchmarks...");
     match QueryPerformanceMonitor::benchmark_queries(db_pool.pool()).await {
@@ -119,8 +142,10 @@ async fn main() -> Result<()> {
         }
     }
 
-    // Initialize MCP server
-    let mcp_server = McpServer::new(db_pool).await?;
+    // Initialize MCP server; readiness tracks migrations registered above
+    let mcp_server = McpServer::new(db_pool)
+        .await?
+        .with_migration_manager(migration_manager, migrate_on_start);
 
     // Start HTTP server with graceful shutdown
     // Allow host override via MCP_HOST; default to all interfaces
@@ -137,54 +162,21 @@ async fn main() -> Result<()> {
 async fn run_server_with_graceful_shutdown(mcp_server: McpServer, addr: &str) -> Result<()> {
     use tokio::net::TcpListener;
 
-    // Create router and bind listener
-    let app = mcp_server.create_router();
     let listener = TcpListener::bind(addr).await?;
     info!(
         "Server listening on {} with graceful shutdown enabled",
         addr
     );
 
-    // Run server with graceful shutdown
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal())
+    // Drains SSE streams, in-flight tool calls and crate jobs on SIGTERM/SIGINT
+    mcp_server
+        .serve_with_shutdown(listener, mcp::server::shutdown_signal())
         .await?;
 
     info!("Server shutdown complete");
     Ok(())
 }
 
-/// Wait for shutdown signal (SIGTERM or SIGINT)
-async fn shutdown_signal() {
-    let ctrl_c = async {
-        signal::ctrl_c()
-            .await
-            .expect("Failed to install Ctrl+C handler");
-    };
-
-    #[cfg(unix)]
-    let terminate = async {
-        signal::unix::signal(signal::unix::SignalKind::terminate())
-            .expect("Failed to install signal handler")
-            .recv()
-            .await;
-    };
-
-    #[cfg(not(unix))]
-    let terminate = std::future::pending::<()>();
-
-    tokio::select! {
-        () = ctrl_c => {
-            warn!("Received SIGINT (Ctrl+C), initiating graceful shutdown...");
-        },
-        () = terminate => {
-            warn!("Received SIGTERM, initiating graceful shutdown...");
-        }
-    }
-
-    info!("Shutdown signal received, starting graceful shutdown (timeout: 30s)");
-}
-
 /// Register core database migrations
 #[allow(clippy::too_many_lines)]
 fn register_core_migrations(migration_manager: &mut DatabaseMigrationManager) {
@@ -206,7 +198,7 @@ fn register_core_migrations(migration_manager: &mut DatabaseMigrationManager) {
             BEGIN
                 CREATE EXTENSION IF NOT EXISTS vector;
             EXCEPTION
-                WHEN insufficient_privilege THEN
+                WHEN insufficient_privilege OR feature_not_supported OR undefined_file THEN
                     RAISE NOTICE 'Vector extension not available, skipping';
             END;
 
@@ -253,24 +245,16 @@ fn register_core_migrations(migration_manager: &mut DatabaseMigrationManager) {
     let documents_sql = r"
         DO $$
         BEGIN
-            -- Drop existing enum if it exists and recreate as TEXT
-            DO $$
-            BEGIN
-                -- Check if doc_type column exists as enum and convert to TEXT
-                IF EXISTS (
-                    SELECT 1 FROM information_schema.columns
-                    WHERE table_name = 'documents'
-                    AND column_name = 'doc_type'
-                    AND data_type = 'USER-DEFINED'
-                ) THEN
-                    ALTER TABLE documents ALTER COLUMN doc_type TYPE TEXT;
-                    RAISE NOTICE 'Converted existing doc_type enum column to TEXT';
-                END IF;
-            EXCEPTION
-                WHEN undefined_table THEN
-                    -- Table doesn't exist yet, that's fine
-                    NULL;
-            END $$;
+            -- Convert an existing doc_type enum column to TEXT
+            IF EXISTS (
+                SELECT 1 FROM information_schema.columns
+                WHERE table_name = 'documents'
+                AND column_name = 'doc_type'
+                AND data_type = 'USER-DEFINED'
+            ) THEN
+                ALTER TABLE documents ALTER COLUMN doc_type TYPE TEXT;
+                RAISE NOTICE 'Converted existing doc_type enum column to TEXT';
+            END IF;
 
             CREATE TABLE IF NOT EXISTS documents (
                 id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
@@ -287,13 +271,20 @@ fn register_core_migrations(migration_manager: &mut DatabaseMigrationManager) {
                 UNIQUE(doc_type, source_name, doc_path)
             );
 
-            -- Try to convert embedding column to vector type if extension is available
-            BEGIN
-                ALTER TABLE documents ALTER COLUMN embedding TYPE vector(3072);
-            EXCEPTION
-                WHEN undefined_object THEN
-                    RAISE NOTICE 'Vector extension not available, keeping TEXT type for embeddings';
-            END;
+            -- Try to convert a new TEXT embedding column to vector type if extension is available
+            IF EXISTS (
+                SELECT 1 FROM information_schema.columns
+                WHERE table_name = 'documents'
+                AND column_name = 'embedding'
+                AND data_type = 'text'
+            ) THEN
+                BEGIN
+                    ALTER TABLE documents ALTER COLUMN embedding TYPE vector(3072);
+                EXCEPTION
+                    WHEN undefined_object THEN
+                        RAISE NOTICE 'Vector extension not available, keeping TEXT type for embeddings';
+                END;
+            END IF;
         END $$;
     ";
     migration_manager.register_migration(MigrationInfo {
@@ -727,6 +718,590 @@ fn register_core_migrations(migration_manager: &mut DatabaseMigrationManager) {
         ],
         checksum: calculate_checksum(force_text_sql),
     });
+
+    // Migration 13: Deduplicate documents and enforce a unique natural key
+    let unique_doc_path_sql = r"
+        DO $$
+        BEGIN
+            -- Keep only the newest row for each (doc_type, source_name, doc_path)
+            DELETE FROM documents d
+            USING (
+                SELECT id,
+                       ROW_NUMBER() OVER (
+                           PARTITION BY doc_type, source_name, doc_path
+                           ORDER BY updated_at DESC NULLS LAST, created_at DESC NULLS LAST, id
+                       ) AS rn
+                FROM documents
+            ) ranked
+            WHERE d.id = ranked.id AND ranked.rn > 1;
+
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_documents_unique_path
+            ON documents(doc_type, source_name, doc_path);
+        END $$;
+    ";
+    migration_manager.register_migration(MigrationInfo {
+        id: "013_documents_unique_path".to_string(),
+        version: "1.3.0".to_string(),
+        description:
+            "Deduplicate documents and add unique index on (doc_type, source_name, doc_path)"
+                .to_string(),
+        up_sql: unique_doc_path_sql.to_string(),
+        down_sql: Some("DROP INDEX IF EXISTS idx_documents_unique_path;".to_string()),
+        dependencies: vec!["003_documents_table".to_string()],
+        checksum: calculate_checksum(unique_doc_path_sql),
+    });
+
+    // Migration 14: Allow embedding backfill jobs in crate_jobs
+    let crate_job_operations_sql = r"
+        DO $$
+        DECLARE
+            con record;
+        BEGIN
+            IF to_regclass('public.crate_jobs') IS NULL THEN
+                RETURN;
+            END IF;
+
+            FOR con IN
+                SELECT conname, pg_get_constraintdef(oid) AS def
+                FROM pg_constraint
+                WHERE conrelid = 'public.crate_jobs'::regclass AND contype = 'c'
+            LOOP
+                IF position('operation' in con.def) > 0 THEN
+                    EXECUTE format('ALTER TABLE crate_jobs DROP CONSTRAINT %I', con.conname);
+                END IF;
+            END LOOP;
+
+            ALTER TABLE crate_jobs ADD CONSTRAINT crate_jobs_operation_check
+                CHECK (operation IN ('add_crate', 'remove_crate', 'backfill_embeddings'));
+        END $$;
+    ";
+    migration_manager.register_migration(MigrationInfo {
+        id: "014_crate_job_operations".to_string(),
+        version: "1.3.0".to_string(),
+        description: "Extend crate_jobs operation check with backfill_embeddings".to_string(),
+        up_sql: crate_job_operations_sql.to_string(),
+        down_sql: Some("-- irreversible migration; no-op".to_string()),
+        dependencies: vec!["009_job_status_enum".to_string()],
+        checksum: calculate_checksum(crate_job_operations_sql),
+    });
+
+    // Migration 15: Crawl checkpoints for resumable crate ingestion
+    let crate_job_state_sql = r"
+        DO $$
+        BEGIN
+            IF to_regclass('public.crate_jobs') IS NOT NULL THEN
+                ALTER TABLE crate_jobs ADD COLUMN IF NOT EXISTS crate_job_state JSONB;
+            END IF;
+        END $$;
+    ";
+    migration_manager.register_migration(MigrationInfo {
+        id: "015_crate_job_state".to_string(),
+        version: "1.3.0".to_string(),
+        description: "Add crate_job_state column for crawl checkpoints".to_string(),
+        up_sql: crate_job_state_sql.to_string(),
+        down_sql: Some("ALTER TABLE crate_jobs DROP COLUMN IF EXISTS crate_job_state;".to_string()),
+        dependencies: vec!["009_job_status_enum".to_string()],
+        checksum: calculate_checksum(crate_job_state_sql),
+    });
+
+    // Migration 16: Embedding cache keyed by content hash
+    let embedding_cache_sql = r"
+        CREATE TABLE IF NOT EXISTS embedding_cache (
+            content_sha256 TEXT NOT NULL,
+            model TEXT NOT NULL,
+            dimensions INTEGER NOT NULL,
+            embedding REAL[] NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            last_used_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (content_sha256, model, dimensions)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_embedding_cache_last_used ON embedding_cache(last_used_at);
+    ";
+    migration_manager.register_migration(MigrationInfo {
+        id: "016_embedding_cache".to_string(),
+        version: "1.4.0".to_string(),
+        description: "Create embedding_cache table to reuse embeddings for identical content"
+            .to_string(),
+        up_sql: embedding_cache_sql.to_string(),
+        down_sql: Some("DROP TABLE IF EXISTS embedding_cache;".to_string()),
+        dependencies: vec!["003_documents_table".to_string()],
+        checksum: calculate_checksum(embedding_cache_sql),
+    });
+
+    // Migration 17: Request options so any process can run a queued crate job
+    let crate_job_options_sql = r"
+        DO $$
+        BEGIN
+            IF to_regclass('public.crate_jobs') IS NOT NULL THEN
+                ALTER TABLE crate_jobs ADD COLUMN IF NOT EXISTS options JSONB;
+            END IF;
+        END $$;
+    ";
+    migration_manager.register_migration(MigrationInfo {
+        id: "017_crate_job_options".to_string(),
+        version: "1.4.0".to_string(),
+        description: "Add options column so queued crate jobs can be dispatched by any process"
+            .to_string(),
+        up_sql: crate_job_options_sql.to_string(),
+        down_sql: Some("ALTER TABLE crate_jobs DROP COLUMN IF EXISTS options;".to_string()),
+        dependencies: vec!["009_job_status_enum".to_string()],
+        checksum: calculate_checksum(crate_job_options_sql),
+    });
+
+    // Migration 18: Retry bookkeeping and dead-letter details for crate jobs
+    let crate_job_retries_sql = r"
+        DO $$
+        BEGIN
+            IF to_regclass('public.crate_jobs') IS NOT NULL THEN
+                ALTER TABLE crate_jobs ADD COLUMN IF NOT EXISTS attempts INTEGER NOT NULL DEFAULT 0;
+                ALTER TABLE crate_jobs ADD COLUMN IF NOT EXISTS max_attempts INTEGER NOT NULL DEFAULT 3;
+                ALTER TABLE crate_jobs ADD COLUMN IF NOT EXISTS next_run_at TIMESTAMPTZ;
+                ALTER TABLE crate_jobs ADD COLUMN IF NOT EXISTS details JSONB;
+            END IF;
+        END $$;
+    ";
+    migration_manager.register_migration(MigrationInfo {
+        id: "018_crate_job_retries".to_string(),
+        version: "1.4.0".to_string(),
+        description: "Add attempts, max_attempts, next_run_at and details to crate_jobs"
+            .to_string(),
+        up_sql: crate_job_retries_sql.to_string(),
+        down_sql: Some(
+            "ALTER TABLE crate_jobs DROP COLUMN IF EXISTS attempts, DROP COLUMN IF EXISTS max_attempts, DROP COLUMN IF EXISTS next_run_at, DROP COLUMN IF EXISTS details;"
+                .to_string(),
+        ),
+        dependencies: vec!["017_crate_job_options".to_string()],
+        checksum: calculate_checksum(crate_job_retries_sql),
+    });
+
+    // Migration 19: Conditional request validators for crawled docs.rs pages
+    let fetch_cache_sql = r"
+        CREATE TABLE IF NOT EXISTS fetch_cache (
+            url TEXT NOT NULL,
+            crate_version TEXT NOT NULL,
+            etag TEXT,
+            last_modified TEXT,
+            links TEXT[] NOT NULL DEFAULT '{}',
+            fetched_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (url, crate_version)
+        );
+    ";
+    migration_manager.register_migration(MigrationInfo {
+        id: "019_fetch_cache".to_string(),
+        version: "1.4.0".to_string(),
+        description: "Create fetch_cache table for conditional docs.rs requests".to_string(),
+        up_sql: fetch_cache_sql.to_string(),
+        down_sql: Some("DROP TABLE IF EXISTS fetch_cache;".to_string()),
+        dependencies: vec!["003_documents_table".to_string()],
+        checksum: calculate_checksum(fetch_cache_sql),
+    });
+
+    // Migration 20: Idempotency keys so client retries reuse the job they created
+    let crate_job_idempotency_sql = r"
+        DO $$
+        BEGIN
+            IF to_regclass('public.crate_jobs') IS NOT NULL THEN
+                ALTER TABLE crate_jobs ADD COLUMN IF NOT EXISTS idempotency_key TEXT;
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_crate_jobs_idempotency_key
+                    ON crate_jobs(idempotency_key);
+            END IF;
+        END $$;
+    ";
+    migration_manager.register_migration(MigrationInfo {
+        id: "020_crate_job_idempotency_key".to_string(),
+        version: "1.4.0".to_string(),
+        description: "Add unique idempotency_key column to crate_jobs".to_string(),
+        up_sql: crate_job_idempotency_sql.to_string(),
+        down_sql: Some(
+            "DROP INDEX IF EXISTS idx_crate_jobs_idempotency_key; ALTER TABLE crate_jobs DROP COLUMN IF EXISTS idempotency_key;"
+                .to_string(),
+        ),
+        dependencies: vec!["017_crate_job_options".to_string()],
+        checksum: calculate_checksum(crate_job_idempotency_sql),
+    });
+
+    // Migration 21: Audit trail of crate job status changes and progress
+    let crate_job_events_sql = r"
+        DO $$
+        BEGIN
+            IF to_regclass('public.crate_jobs') IS NOT NULL THEN
+                CREATE TABLE IF NOT EXISTS crate_job_events (
+                    id BIGSERIAL PRIMARY KEY,
+                    job_id UUID NOT NULL REFERENCES crate_jobs(id) ON DELETE CASCADE,
+                    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                    event_type TEXT NOT NULL,
+                    old_status job_status,
+                    new_status job_status,
+                    progress INTEGER,
+                    detail JSONB
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_crate_job_events_job ON crate_job_events(job_id, id DESC);
+                CREATE INDEX IF NOT EXISTS idx_crate_job_events_created_at ON crate_job_events(created_at);
+            END IF;
+        END $$;
+    ";
+    migration_manager.register_migration(MigrationInfo {
+        id: "021_crate_job_events".to_string(),
+        version: "1.4.0".to_string(),
+        description: "Create crate_job_events audit trail table".to_string(),
+        up_sql: crate_job_events_sql.to_string(),
+        down_sql: Some("DROP TABLE IF EXISTS crate_job_events;".to_string()),
+        dependencies: vec!["009_job_status_enum".to_string()],
+        checksum: calculate_checksum(crate_job_events_sql),
+    });
+
+    // Migration 22: Audit log of tool invocations
+    let tool_audit_log_sql = r"
+        CREATE TABLE IF NOT EXISTS tool_audit_log (
+            id BIGSERIAL PRIMARY KEY,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            session_id TEXT,
+            client_label TEXT,
+            user_agent TEXT,
+            tool_name TEXT NOT NULL,
+            arguments JSONB NOT NULL DEFAULT '{}',
+            success BOOLEAN NOT NULL,
+            duration_ms BIGINT NOT NULL,
+            error TEXT
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_tool_audit_log_created_at ON tool_audit_log(created_at);
+        CREATE INDEX IF NOT EXISTS idx_tool_audit_log_tool ON tool_audit_log(tool_name, created_at DESC);
+        CREATE INDEX IF NOT EXISTS idx_tool_audit_log_session ON tool_audit_log(session_id, created_at DESC);
+    ";
+    migration_manager.register_migration(MigrationInfo {
+        id: "022_tool_audit_log".to_string(),
+        version: "1.4.0".to_string(),
+        description: "Create tool_audit_log table for tool invocations".to_string(),
+        up_sql: tool_audit_log_sql.to_string(),
+        down_sql: Some("DROP TABLE IF EXISTS tool_audit_log;".to_string()),
+        dependencies: vec![],
+        checksum: calculate_checksum(tool_audit_log_sql),
+    });
+
+    // Migration 23: Tools switched on or off at runtime
+    let tool_settings_sql = r"
+        CREATE TABLE IF NOT EXISTS tool_settings (
+            tool_name TEXT PRIMARY KEY,
+            enabled BOOLEAN NOT NULL,
+            updated_by TEXT,
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+        );
+    ";
+    migration_manager.register_migration(MigrationInfo {
+        id: "023_tool_settings".to_string(),
+        version: "1.4.0".to_string(),
+        description: "Create tool_settings table for runtime tool enable/disable".to_string(),
+        up_sql: tool_settings_sql.to_string(),
+        down_sql: Some("DROP TABLE IF EXISTS tool_settings;".to_string()),
+        dependencies: vec![],
+        checksum: calculate_checksum(tool_settings_sql),
+    });
+
+    // Migration 24: npm package jobs share crate_jobs
+    let npm_package_jobs_sql = r"
+        DO $$
+        BEGIN
+            IF to_regclass('public.crate_jobs') IS NULL THEN
+                RETURN;
+            END IF;
+
+            ALTER TABLE crate_jobs DROP CONSTRAINT IF EXISTS crate_jobs_operation_check;
+            ALTER TABLE crate_jobs ADD CONSTRAINT crate_jobs_operation_check
+                CHECK (operation IN (
+                    'add_crate', 'remove_crate', 'backfill_embeddings',
+                    'add_npm_package', 'remove_npm_package'
+                ));
+        END $$;
+    ";
+    migration_manager.register_migration(MigrationInfo {
+        id: "024_npm_package_jobs".to_string(),
+        version: "1.4.0".to_string(),
+        description: "Extend crate_jobs operation check with npm package operations".to_string(),
+        up_sql: npm_package_jobs_sql.to_string(),
+        down_sql: Some("-- irreversible migration; no-op".to_string()),
+        dependencies: vec!["014_crate_job_operations".to_string()],
+        checksum: calculate_checksum(npm_package_jobs_sql),
+    });
+
+    // Migration 25: Python package jobs share crate_jobs
+    let python_package_jobs_sql = r"
+        DO $$
+        BEGIN
+            IF to_regclass('public.crate_jobs') IS NULL THEN
+                RETURN;
+            END IF;
+
+            ALTER TABLE crate_jobs DROP CONSTRAINT IF EXISTS crate_jobs_operation_check;
+            ALTER TABLE crate_jobs ADD CONSTRAINT crate_jobs_operation_check
+                CHECK (operation IN (
+                    'add_crate', 'remove_crate', 'backfill_embeddings',
+                    'add_npm_package', 'remove_npm_package',
+                    'add_python_package', 'remove_python_package'
+                ));
+        END $$;
+    ";
+    migration_manager.register_migration(MigrationInfo {
+        id: "025_python_package_jobs".to_string(),
+        version: "1.4.0".to_string(),
+        description: "Extend crate_jobs operation check with Python package operations".to_string(),
+        up_sql: python_package_jobs_sql.to_string(),
+        down_sql: Some("-- irreversible migration; no-op".to_string()),
+        dependencies: vec!["024_npm_package_jobs".to_string()],
+        checksum: calculate_checksum(python_package_jobs_sql),
+    });
+
+    // Migration 26: Webhook subscriptions and their delivery log
+    let webhooks_sql = r"
+        CREATE TABLE IF NOT EXISTS webhooks (
+            id UUID PRIMARY KEY,
+            url TEXT NOT NULL,
+            secret TEXT NOT NULL,
+            events TEXT[] NOT NULL DEFAULT '{}',
+            created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            id UUID PRIMARY KEY,
+            webhook_id UUID,
+            url TEXT NOT NULL,
+            event TEXT NOT NULL,
+            payload JSONB NOT NULL,
+            status TEXT NOT NULL CHECK (status IN ('pending', 'delivered', 'failed')),
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_status_code INTEGER,
+            last_error TEXT,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            delivered_at TIMESTAMPTZ
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_created_at ON webhook_deliveries(created_at DESC);
+        CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook ON webhook_deliveries(webhook_id, created_at DESC);
+    ";
+    migration_manager.register_migration(MigrationInfo {
+        id: "026_webhooks".to_string(),
+        version: "1.4.0".to_string(),
+        description: "Create webhooks and webhook_deliveries tables".to_string(),
+        up_sql: webhooks_sql.to_string(),
+        down_sql: Some(
+            "DROP TABLE IF EXISTS webhook_deliveries; DROP TABLE IF EXISTS webhooks;".to_string(),
+        ),
+        dependencies: vec![],
+        checksum: calculate_checksum(webhooks_sql),
+    });
+
+    // Migration 27: Relevance feedback on search results
+    let search_feedback_sql = r"
+        CREATE TABLE IF NOT EXISTS search_feedback (
+            id BIGSERIAL PRIMARY KEY,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            session_id TEXT,
+            query TEXT NOT NULL,
+            rating TEXT NOT NULL CHECK (rating IN ('useful', 'partially', 'not_useful')),
+            document_id UUID,
+            doc_path TEXT,
+            doc_type TEXT,
+            crate_name TEXT,
+            document_found BOOLEAN NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_search_feedback_created_at ON search_feedback(created_at DESC);
+        CREATE INDEX IF NOT EXISTS idx_search_feedback_doc_type ON search_feedback(doc_type, created_at DESC);
+    ";
+    migration_manager.register_migration(MigrationInfo {
+        id: "027_search_feedback".to_string(),
+        version: "1.4.0".to_string(),
+        description: "Create search_feedback table".to_string(),
+        up_sql: search_feedback_sql.to_string(),
+        down_sql: Some("DROP TABLE IF EXISTS search_feedback;".to_string()),
+        dependencies: vec![],
+        checksum: calculate_checksum(search_feedback_sql),
+    });
+
+    // Migration 28: Notify servers of document changes so they drop cached query results.
+    // Transition tables need one trigger per event.
+    let query_cache_notify_sql = r"
+        CREATE OR REPLACE FUNCTION notify_query_cache_invalidate() RETURNS trigger AS $$
+        BEGIN
+            PERFORM pg_notify('query_cache_invalidate', doc_type)
+            FROM (SELECT DISTINCT doc_type::text AS doc_type FROM changed_rows) AS changed;
+            RETURN NULL;
+        END;
+        $$ LANGUAGE plpgsql;
+
+        DROP TRIGGER IF EXISTS documents_notify_insert ON documents;
+        CREATE TRIGGER documents_notify_insert AFTER INSERT ON documents
+            REFERENCING NEW TABLE AS changed_rows
+            FOR EACH STATEMENT EXECUTE FUNCTION notify_query_cache_invalidate();
+        DROP TRIGGER IF EXISTS documents_notify_update ON documents;
+        CREATE TRIGGER documents_notify_update AFTER UPDATE ON documents
+            REFERENCING NEW TABLE AS changed_rows
+            FOR EACH STATEMENT EXECUTE FUNCTION notify_query_cache_invalidate();
+        DROP TRIGGER IF EXISTS documents_notify_delete ON documents;
+        CREATE TRIGGER documents_notify_delete AFTER DELETE ON documents
+            REFERENCING OLD TABLE AS changed_rows
+            FOR EACH STATEMENT EXECUTE FUNCTION notify_query_cache_invalidate();
+        DROP TRIGGER IF EXISTS document_sources_notify_update ON document_sources;
+        CREATE TRIGGER document_sources_notify_update AFTER UPDATE ON document_sources
+            REFERENCING NEW TABLE AS changed_rows
+            FOR EACH STATEMENT EXECUTE FUNCTION notify_query_cache_invalidate();
+        DROP TRIGGER IF EXISTS document_sources_notify_delete ON document_sources;
+        CREATE TRIGGER document_sources_notify_delete AFTER DELETE ON document_sources
+            REFERENCING OLD TABLE AS changed_rows
+            FOR EACH STATEMENT EXECUTE FUNCTION notify_query_cache_invalidate();
+    ";
+    migration_manager.register_migration(MigrationInfo {
+        id: "028_query_cache_notify".to_string(),
+        version: "1.4.0".to_string(),
+        description: "Notify query_cache_invalidate on document and source changes".to_string(),
+        up_sql: query_cache_notify_sql.to_string(),
+        down_sql: Some(
+            r"
+            DROP TRIGGER IF EXISTS documents_notify_insert ON documents;
+            DROP TRIGGER IF EXISTS documents_notify_update ON documents;
+            DROP TRIGGER IF EXISTS documents_notify_delete ON documents;
+            DROP TRIGGER IF EXISTS document_sources_notify_update ON document_sources;
+            DROP TRIGGER IF EXISTS document_sources_notify_delete ON document_sources;
+            DROP FUNCTION IF EXISTS notify_query_cache_invalidate();
+            "
+            .to_string(),
+        ),
+        dependencies: vec![],
+        checksum: calculate_checksum(query_cache_notify_sql),
+    });
+
+    // Migration 29: Index crate name lookups. The expression must stay identical
+    // to `db::CRATE_NAME_KEY_SQL` for the planner to use it; together with
+    // idx_documents_source_name it serves `db::crate_name_match_sql`.
+    let crate_name_key_index_sql = r"
+        CREATE INDEX IF NOT EXISTS idx_documents_crate_name_key
+            ON documents ((replace(lower(metadata->>'crate_name'), '-', '_')));
+    ";
+    migration_manager.register_migration(MigrationInfo {
+        id: "029_crate_name_key_index".to_string(),
+        version: "1.4.0".to_string(),
+        description: "Index documents by folded crate name".to_string(),
+        up_sql: crate_name_key_index_sql.to_string(),
+        down_sql: Some("DROP INDEX IF EXISTS idx_documents_crate_name_key;".to_string()),
+        dependencies: vec![],
+        checksum: calculate_checksum(crate_name_key_index_sql),
+    });
+
+    // Migration 30: Index the path and title vector searches match besides the
+    // content. The expression must stay identical to `FtsSettings::title_vector`
+    // for `english`; other languages are indexed by
+    // `DocumentSourceQueries::ensure_fts_indexes` at startup.
+    let fts_title_index_sql = r"
+        CREATE INDEX IF NOT EXISTS idx_documents_fts_title
+            ON documents USING GIN (to_tsvector('english', regexp_replace(coalesce(doc_path,''), '[/._:#-]+', ' ', 'g') || ' ' || coalesce(metadata->>'title','')));
+    ";
+    migration_manager.register_migration(MigrationInfo {
+        id: "030_fts_title_index".to_string(),
+        version: "1.4.0".to_string(),
+        description: "Index the path and title full-text vector".to_string(),
+        up_sql: fts_title_index_sql.to_string(),
+        down_sql: Some("DROP INDEX IF EXISTS idx_documents_fts_title;".to_string()),
+        dependencies: vec![],
+        checksum: calculate_checksum(fts_title_index_sql),
+    });
+}
+
+/// Validate the tools configuration and print the tools it registers
+///
+/// Reads the file given on the command line, or the configuration the
+/// server would load (`TOOLS_CONFIG_PATH`, `TOOLS_CONFIG`, `/app/tools.json`).
+fn run_validate_config(path: Option<&str>) -> Result<()> {
+    dotenvy::dotenv().ok();
+
+    let config = match path {
+        Some(path) => ConfigLoader::load_from_file(path)?,
+        None => ConfigLoader::load_default()?,
+    };
+
+    let enabled = ConfigLoader::filter_enabled_tools(&config);
+    println!(
+        "Tools configuration is valid: {} tools ({} enabled)",
+        config.tools.len(),
+        enabled.len()
+    );
+    for tool in &enabled {
+        println!("  {} -> {}", tool.name, tool.doc_type);
+    }
+    Ok(())
+}
+
+/// Recreate the embedding column for a new embedding dimension
+///
+/// Uses the dimension given on the command line, or the configured
+/// `EMBEDDING_DIMENSIONS`. Existing embeddings are cleared and must be
+/// regenerated with `backfill_embeddings`.
+async fn run_recreate_embedding_column(dimensions: Option<&str>) -> Result<()> {
+    dotenvy::dotenv().ok();
+
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            std::env::var("RUST_LOG").unwrap_or_else(|_| "info,doc_server=debug".to_string()),
+        )
+        .init();
+
+    let dimensions = match dimensions {
+        Some(raw) => raw
+            .parse::<i32>()
+            .map_err(|_| anyhow::anyhow!("Invalid dimension '{raw}'"))?,
+        None => i32::try_from(embed::EmbeddingConfig::from_env()?.dimensions)?,
+    };
+
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let db_pool = DatabasePool::new(&database_url).await?;
+
+    let current = DocumentQueries::embedding_column_dimensions(db_pool.pool()).await?;
+    if current == Some(dimensions) {
+        info!("documents.embedding is already vector({})", dimensions);
+        return Ok(());
+    }
+
+    warn!(
+        "Recreating documents.embedding as vector({}) (was {:?}); existing embeddings will be cleared",
+        dimensions, current
+    );
+    let cleared = DocumentQueries::recreate_embedding_column(db_pool.pool(), dimensions).await?;
+    info!(
+        "Embedding column updated; {} documents need re-embedding via backfill_embeddings",
+        cleared
+    );
+    Ok(())
+}
+
+/// Rewrite existing document ids to their stable UUIDv5 form
+///
+/// For deployments that ingested documents with random ids; references in
+/// `search_feedback` are updated along with them. `--dry-run` only counts
+/// the ids that would change.
+async fn run_rewrite_document_ids(dry_run: bool) -> Result<()> {
+    dotenvy::dotenv().ok();
+
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            std::env::var("RUST_LOG").unwrap_or_else(|_| "info,doc_server=debug".to_string()),
+        )
+        .init();
+
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let db_pool = DatabasePool::new(&database_url).await?;
+
+    let (examined, rewritten) =
+        DocumentQueries::rewrite_document_ids(db_pool.pool(), dry_run).await?;
+    if dry_run {
+        info!(
+            "{} of {} documents would get a new id (dry run, nothing written)",
+            rewritten, examined
+        );
+    } else {
+        info!("Rewrote {} of {} document ids", rewritten, examined);
+    }
+    Ok(())
 }
 
 /// Run database migrations only (for K8s migration jobs)
@@ -768,9 +1343,9 @@ async fn run_migrations_only() -> Result<()> {
         }
     }
 
-    // Apply pending migrations
+    // Apply pending migrations, waiting for any replica already migrating
     info!("Applying pending database migrations...");
-    match migration_manager.apply_migrations().await {
+    match migration_manager.apply_migrations_locked().await {
         Ok(applied) => {
             if applied.is_empty() {
                 info!("Database schema is already up to date");
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use db::{models::JobStatus, DatabasePool};
+use mcp::package_tools::{PackageEcosystem, NPM_PACKAGES, PYTHON_PACKAGES};
 use mcp::queue::{redis_url_from_env, RedisJobMessage};
 use serde::Deserialize;
 use serde_json::Value;
@@ -48,7 +49,9 @@ async fn main() -> Result<()> {
 
     // Job types to process
     let job_types: Vec<String> = std::env::var("WORKER_JOB_TYPES")
-        .unwrap_or_else(|_| "ingest,crate_add".to_string())
+        .unwrap_or_else(|_| {
+            "ingest,crate_add,crate_remove,npm_add,npm_remove,python_add,python_remove".to_string()
+        })
         .split(',')
         .map(|s| s.trim().to_string())
         .filter(|s| !s.is_empty())
@@ -115,6 +118,36 @@ async fn main() -> Result<()> {
                     warn!("Crate add job failed: {e}");
                 }
             }
+            "crate_remove" => {
+                if let Err(e) = handle_crate_remove(&db_pool, &msg.payload, msg.job_id).await {
+                    warn!("Crate remove job failed: {e}");
+                }
+            }
+            "npm_add" => {
+                if let Err(e) = handle_npm_add(&db_pool, &msg.payload, msg.job_id).await {
+                    warn!("npm package add job failed: {e}");
+                }
+            }
+            "npm_remove" => {
+                if let Err(e) =
+                    handle_package_remove(&NPM_PACKAGES, &db_pool, &msg.payload, msg.job_id).await
+                {
+                    warn!("npm package remove job failed: {e}");
+                }
+            }
+            "python_add" => {
+                if let Err(e) = handle_python_add(&db_pool, &msg.payload, msg.job_id).await {
+                    warn!("Python package add job failed: {e}");
+                }
+            }
+            "python_remove" => {
+                if let Err(e) =
+                    handle_package_remove(&PYTHON_PACKAGES, &db_pool, &msg.payload, msg.job_id)
+                        .await
+                {
+                    warn!("Python package remove job failed: {e}");
+                }
+            }
             other => {
                 warn!("Unknown job type '{other}', ignoring");
             }
@@ -126,70 +159,69 @@ async fn main() -> Result<()> {
 struct IngestPayload {
     url: String,
     doc_type: String,
+    /// Plan from `analyze_repository`; discovery is skipped when present
+    #[serde(default)]
+    plan: Option<discovery::RepositoryAnalysis>,
 }
 
 async fn handle_ingest(db_pool: &DatabasePool, payload: &Value, job_id: uuid::Uuid) -> Result<()> {
     use discovery::IntelligentRepositoryAnalyzer;
-    use mcp::ingest::execute_cli_plan;
+    use mcp::ingest::run_plan_for_job;
+    use mcp::job_queue::IngestJobProcessor;
 
     let p: IngestPayload = serde_json::from_value(payload.clone())?;
+    let processor = IngestJobProcessor::new(db_pool.clone());
 
-    let _ = db::queries::IngestJobQueries::update_job_status(
-        db_pool.pool(),
-        job_id,
-        JobStatus::Running,
-        None,
-        None,
-    )
-    .await?;
-
-    let mut analyzer = IntelligentRepositoryAnalyzer::new();
-    match analyzer.analyze_repository(&p.url).await {
-        Ok(analysis) => match execute_cli_plan(&analysis, &p.doc_type, &p.url).await {
-            Ok(output) => {
-                let _ = db::queries::IngestJobQueries::update_job_status(
-                    db_pool.pool(),
-                    job_id,
-                    JobStatus::Completed,
-                    Some(&output),
-                    None,
-                )
-                .await?;
-            }
-            Err(e) => {
-                let _ = db::queries::IngestJobQueries::update_job_status(
-                    db_pool.pool(),
-                    job_id,
-                    JobStatus::Failed,
-                    None,
-                    Some(&e.to_string()),
-                )
-                .await?;
+    let _ = processor
+        .transition(job_id, JobStatus::Running, None)
+        .await?;
+
+    processor
+        .run_with_heartbeat(job_id, async {
+            if let Some(plan) = &p.plan {
+                run_plan_for_job(db_pool, job_id, plan, &p.doc_type, &p.url).await;
+                return Ok(());
             }
-        },
-        Err(e) => {
-            let _ = db::queries::IngestJobQueries::update_job_status(
-                db_pool.pool(),
-                job_id,
-                JobStatus::Failed,
-                None,
-                Some(&e.to_string()),
-            )
-            .await?;
-        }
-    }
 
-    Ok(())
+            let analysis = match IntelligentRepositoryAnalyzer::from_env() {
+                Ok(mut analyzer) => analyzer.analyze_repository(&p.url).await,
+                Err(e) => Err(e),
+            };
+            match analysis {
+                Ok(analysis) => {
+                    run_plan_for_job(db_pool, job_id, &analysis, &p.doc_type, &p.url).await;
+                }
+                Err(e) => {
+                    let _ = processor
+                        .transition(job_id, JobStatus::Failed, Some(&e.to_string()))
+                        .await?;
+                }
+            }
+            Ok(())
+        })
+        .await
 }
 
 #[derive(Deserialize)]
 struct CrateAddPayload {
     crate_name: String,
     version: Option<String>,
+    #[serde(default)]
+    version_req: Option<String>,
     features: Option<Vec<String>>,
     include_dev_deps: bool,
     force_update: bool,
+    #[serde(default)]
+    no_cache: bool,
     atomic_rollback: bool,
+    #[serde(default)]
+    local: Option<rust_crates::LocalDocsSource>,
+    #[serde(default)]
+    alias: Option<String>,
+    #[serde(default)]
+    metadata: Option<rust_crates::CrateMetadata>,
+    #[serde(default)]
+    from_archive: bool,
 }
 
 async fn handle_crate_add(
@@ -198,15 +230,13 @@ async fn handle_crate_add(
     job_id: uuid::Uuid,
 ) -> Result<()> {
     use embed::client::EmbeddingClient;
-    use embed::OpenAIEmbeddingClient;
     use mcp::crate_tools::AddRustCrateTool;
     use rust_crates::RustLoader;
     use std::sync::Arc as StdArc;
 
     let p: CrateAddPayload = serde_json::from_value(payload.clone())?;
 
-    let client: StdArc<dyn EmbeddingClient + Send + Sync> =
-        StdArc::new(OpenAIEmbeddingClient::new()?);
+    let client: StdArc<dyn EmbeddingClient + Send + Sync> = embed::embedding_client_from_env()?;
     let tool = AddRustCrateTool::new(db_pool.clone(), client.clone());
 
     // Construct a minimal call path by invoking the internal ingestion function
@@ -219,18 +249,189 @@ async fn handle_crate_add(
         .await?;
 
     // We call the same internal processing method via a public facade exposed by the tool
-    tool.process_in_worker(
-        &processor,
-        &mut loader,
-        &client,
-        db_pool,
-        job_id,
-        &p.crate_name,
-        p.version.as_deref(),
-        p.features.as_ref(),
-        p.include_dev_deps,
-        p.force_update,
-        p.atomic_rollback,
-    )
-    .await
+    processor
+        .run_with_heartbeat(
+            job_id,
+            tool.process_in_worker(
+                &processor,
+                &mut loader,
+                &client,
+                db_pool,
+                job_id,
+                &p.crate_name,
+                p.version.as_deref(),
+                p.version_req.as_deref(),
+                p.features.as_ref(),
+                p.include_dev_deps,
+                p.force_update,
+                p.no_cache,
+                p.atomic_rollback,
+                p.local.as_ref(),
+                p.from_archive,
+                p.alias.as_deref(),
+                p.metadata.as_ref(),
+            ),
+        )
+        .await
+}
+
+#[derive(Deserialize)]
+struct CrateRemovePayload {
+    crate_name: String,
+    #[serde(default = "default_verify_cleanup")]
+    verify_cleanup: bool,
+    #[serde(default)]
+    version: Option<String>,
+}
+
+const fn default_verify_cleanup() -> bool {
+    true
+}
+
+async fn handle_crate_remove(
+    db_pool: &DatabasePool,
+    payload: &Value,
+    job_id: uuid::Uuid,
+) -> Result<()> {
+    use mcp::crate_tools::RemoveRustCrateTool;
+    use mcp::job_queue::{CrateJobProcessor, RemoveJobOptions};
+
+    let p: CrateRemovePayload = serde_json::from_value(payload.clone())?;
+    let processor = CrateJobProcessor::new(db_pool.clone());
+    let options = RemoveJobOptions {
+        verify_cleanup: p.verify_cleanup,
+        version: p.version,
+    };
+
+    let result = processor
+        .run_with_heartbeat(
+            job_id,
+            RemoveRustCrateTool::process_removal(&processor, job_id, &p.crate_name, &options),
+        )
+        .await;
+    if let Err(e) = result {
+        processor.record_failure(job_id, &e).await?;
+        return Err(e);
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct PackageAddPayload {
+    package_name: String,
+    #[serde(flatten)]
+    options: mcp::job_queue::PackageJobOptions,
+}
+
+async fn handle_npm_add(db_pool: &DatabasePool, payload: &Value, job_id: uuid::Uuid) -> Result<()> {
+    use embed::client::EmbeddingClient;
+    use mcp::job_queue::CrateJobProcessor;
+    use mcp::npm_tools::AddNpmPackageTool;
+    use npm_packages::NpmLoader;
+    use std::sync::Arc as StdArc;
+
+    let p: PackageAddPayload = serde_json::from_value(payload.clone())?;
+    let client: StdArc<dyn EmbeddingClient + Send + Sync> = embed::embedding_client_from_env()?;
+    let processor = CrateJobProcessor::new(db_pool.clone());
+
+    let result = processor
+        .run_with_heartbeat(
+            job_id,
+            AddNpmPackageTool::process_ingestion(
+                &processor,
+                &NpmLoader::new(),
+                &client,
+                db_pool,
+                job_id,
+                &p.package_name,
+                &p.options,
+            ),
+        )
+        .await;
+    if let Err(e) = result {
+        processor.record_failure(job_id, &e).await?;
+        return Err(e);
+    }
+    Ok(())
+}
+
+async fn handle_python_add(
+    db_pool: &DatabasePool,
+    payload: &Value,
+    job_id: uuid::Uuid,
+) -> Result<()> {
+    use embed::client::EmbeddingClient;
+    use mcp::job_queue::CrateJobProcessor;
+    use mcp::python_tools::AddPythonPackageTool;
+    use python_packages::PyPiLoader;
+    use std::sync::Arc as StdArc;
+
+    let p: PackageAddPayload = serde_json::from_value(payload.clone())?;
+    let client: StdArc<dyn EmbeddingClient + Send + Sync> = embed::embedding_client_from_env()?;
+    let processor = CrateJobProcessor::new(db_pool.clone());
+
+    let result = processor
+        .run_with_heartbeat(
+            job_id,
+            AddPythonPackageTool::process_ingestion(
+                &processor,
+                &PyPiLoader::new(),
+                &client,
+                db_pool,
+                job_id,
+                &p.package_name,
+                &p.options,
+            ),
+        )
+        .await;
+    if let Err(e) = result {
+        processor.record_failure(job_id, &e).await?;
+        return Err(e);
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct PackageRemovePayload {
+    package_name: String,
+    #[serde(default = "default_verify_cleanup")]
+    verify_cleanup: bool,
+    #[serde(default)]
+    version: Option<String>,
+}
+
+async fn handle_package_remove(
+    ecosystem: &PackageEcosystem,
+    db_pool: &DatabasePool,
+    payload: &Value,
+    job_id: uuid::Uuid,
+) -> Result<()> {
+    use mcp::job_queue::{CrateJobProcessor, RemoveJobOptions};
+    use mcp::package_tools::process_package_removal;
+
+    let p: PackageRemovePayload = serde_json::from_value(payload.clone())?;
+    let processor = CrateJobProcessor::new(db_pool.clone());
+    let options = RemoveJobOptions {
+        verify_cleanup: p.verify_cleanup,
+        version: p.version,
+    };
+
+    let result = processor
+        .run_with_heartbeat(
+            job_id,
+            process_package_removal(
+                ecosystem,
+                &processor,
+                db_pool,
+                job_id,
+                &p.package_name,
+                &options,
+            ),
+        )
+        .await;
+    if let Err(e) = result {
+        processor.record_failure(job_id, &e).await?;
+        return Err(e);
+    }
+    Ok(())
 }
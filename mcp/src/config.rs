@@ -1,51 +1,106 @@
 //! Configuration loading and validation
+//!
+//! The tools configuration is JSON, or TOML when the file name ends in
+//! `.toml`. Every entry is validated before any tool is registered, and
+//! errors name the configuration source and the entry index.
 
 use anyhow::{anyhow, Result};
 use db::models::{ToolConfig, ToolsConfig};
+use std::collections::HashMap;
 use std::path::Path;
 use tracing::{debug, info};
 
+/// Metadata filter keys a query tool can enable
+pub const FILTER_KEYS: [&str; 5] = ["format", "complexity", "category", "topic", "api_version"];
+
+/// Largest `limit` a query tool call may ask for
+pub const MAX_QUERY_LIMIT: i64 = 20;
+
+/// Largest `timeoutSecs` accepted for a tool
+pub const MAX_TIMEOUT_SECS: u64 = 3_600;
+
+/// Crate management tools that may be listed in the configuration
+const CRATE_MANAGEMENT_TOOLS: [&str; 16] = [
+    "add_rust_crate",
+    "add_local_crate",
+    "reprocess_rust_crate",
+    "remove_rust_crate",
+    "restore_rust_crate",
+    "list_rust_crates",
+    "check_rust_status",
+    "diff_rust_crate_versions",
+    "backfill_embeddings",
+    "retry_rust_job",
+    "add_npm_package",
+    "remove_npm_package",
+    "list_npm_packages",
+    "add_python_package",
+    "remove_python_package",
+    "list_python_packages",
+];
+
 /// Configuration loader for dynamic tools
 pub struct ConfigLoader;
 
 impl ConfigLoader {
-    /// Load tools configuration from JSON file
+    /// Load tools configuration from a JSON or TOML file
     ///
     /// # Errors
     ///
-    /// Returns an error if the file cannot be read, is not valid JSON,
-    /// or doesn't match the expected schema.
+    /// Returns an error if the file cannot be read, cannot be parsed,
+    /// or contains an invalid entry.
     pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<ToolsConfig> {
-        debug!("Loading configuration from: {:?}", path.as_ref());
-
-        let content = std::fs::read_to_string(&path)
-            .map_err(|e| anyhow!("Failed to read config file {:?}: {}", path.as_ref(), e))?;
+        let path = path.as_ref();
+        debug!("Loading configuration from: {:?}", path);
 
-        let config: ToolsConfig = serde_json::from_str(&content)
-            .map_err(|e| anyhow!("Failed to parse config JSON: {}", e))?;
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read config file {:?}: {}", path, e))?;
+        let is_toml = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
 
-        Self::validate_config(&config)?;
+        let config = Self::parse(&content, &path.display().to_string(), is_toml)?;
 
         info!(
             "Loaded configuration with {} tools from {:?}",
             config.tools.len(),
-            path.as_ref()
+            path
         );
 
         Ok(config)
     }
 
-    /// Load tools configuration from filesystem or embedded fallback
+    /// Parse and validate configuration `content` read from `source`
     ///
     /// # Errors
     ///
-    /// Returns an error if the config is invalid.
-    pub fn load_default() -> Result<ToolsConfig> {
+    /// Returns an error naming `source` if the content cannot be parsed or
+    /// contains an invalid entry.
+    pub fn parse(content: &str, source: &str, is_toml: bool) -> Result<ToolsConfig> {
+        let config: ToolsConfig = if is_toml {
+            toml::from_str(content).map_err(|e| anyhow!("{source}: invalid TOML: {e}"))?
+        } else {
+            serde_json::from_str(content).map_err(|e| anyhow!("{source}: invalid JSON: {e}"))?
+        };
+
+        Self::validate_config_from(&config, source)?;
+        Ok(config)
+    }
+
+    /// Load the tools configuration, or `None` when none is provided
+    ///
+    /// Sources are checked in order: the file at `TOOLS_CONFIG_PATH`, JSON
+    /// in `TOOLS_CONFIG`, then `/app/tools.json`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a provided configuration is invalid.
+    pub fn load_configured() -> Result<Option<ToolsConfig>> {
         // Highest priority: explicit file path via TOOLS_CONFIG_PATH
         if let Ok(path) = std::env::var("TOOLS_CONFIG_PATH") {
             if !path.trim().is_empty() {
                 debug!("Loading configuration from TOOLS_CONFIG_PATH: {}", path);
-                return Self::load_from_file(path);
+                return Self::load_from_file(path).map(Some);
             }
         }
 
@@ -53,55 +108,38 @@ impl ConfigLoader {
         if let Ok(config_content) = std::env::var("TOOLS_CONFIG") {
             debug!("Loading configuration from environment variable TOOLS_CONFIG");
 
-            let config: ToolsConfig = serde_json::from_str(&config_content)
-                .map_err(|e| anyhow!("Failed to parse environment config: {}", e))?;
-
-            Self::validate_config(&config)?;
+            let config = Self::parse(&config_content, "TOOLS_CONFIG", false)?;
 
             info!(
                 "Loaded environment configuration with {} tools",
                 config.tools.len()
             );
 
-            return Ok(config);
+            return Ok(Some(config));
         }
 
         // Finally: load from filesystem (expected in production)
-        match std::fs::read_to_string("/app/tools.json") {
-            Ok(config_content) => {
-                debug!("Loading configuration from filesystem (/app/tools.json)");
-                info!(
-                    "Successfully read {} bytes from filesystem",
-                    config_content.len()
-                );
-
-                let config: ToolsConfig = serde_json::from_str(&config_content)
-                    .map_err(|e| anyhow!("Failed to parse filesystem config: {}", e))?;
-
-                Self::validate_config(&config)?;
+        if Path::new("/app/tools.json").exists() {
+            return Self::load_from_file("/app/tools.json").map(Some);
+        }
 
-                info!(
-                    "Loaded filesystem configuration with {} tools",
-                    config.tools.len()
-                );
+        debug!("No tools configuration found");
+        Ok(None)
+    }
 
-                Ok(config)
-            }
-            Err(e) => {
-                // No fallback to embedded config - configuration is now required
-                debug!(
-                    "Failed to load tools configuration from /app/tools.json: {}",
-                    e
-                );
-                Err(anyhow!(
-                    "No tools configuration found. Configuration must be provided via:\n\
-                    1. TOOLS_CONFIG environment variable, or\n\
-                    2. /app/tools.json file (mounted from ConfigMap)\n\
-                    Error: {}",
-                    e
-                ))
-            }
-        }
+    /// Load tools configuration from the environment or filesystem
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no configuration is provided or it is invalid.
+    pub fn load_default() -> Result<ToolsConfig> {
+        Self::load_configured()?.ok_or_else(|| {
+            anyhow!(
+                "No tools configuration found. Configuration must be provided via:\n\
+                1. TOOLS_CONFIG environment variable, or\n\
+                2. /app/tools.json file (mounted from ConfigMap)"
+            )
+        })
     }
 
     /// Validate the tools configuration
@@ -110,52 +148,109 @@ impl ConfigLoader {
     ///
     /// Returns an error if the configuration is invalid.
     pub fn validate_config(config: &ToolsConfig) -> Result<()> {
+        Self::validate_config_from(config, "tools configuration")
+    }
+
+    /// Validate the tools configuration read from `source`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for the first invalid entry, prefixed with `source`
+    /// and the entry's index in `tools`.
+    pub fn validate_config_from(config: &ToolsConfig, source: &str) -> Result<()> {
         if config.tools.is_empty() {
-            return Err(anyhow!("Configuration must contain at least one tool"));
+            return Err(anyhow!(
+                "{source}: Configuration must contain at least one tool"
+            ));
         }
 
-        let mut tool_names = std::collections::HashSet::new();
+        let mut first_index: HashMap<&str, usize> = HashMap::new();
 
-        for tool in &config.tools {
-            // Check for empty fields
-            if tool.name.is_empty() {
-                return Err(anyhow!("Tool name cannot be empty"));
-            }
-            if tool.doc_type.is_empty() {
-                return Err(anyhow!("Tool docType cannot be empty"));
-            }
-            if tool.title.is_empty() {
-                return Err(anyhow!("Tool title cannot be empty"));
-            }
-            if tool.description.is_empty() {
-                return Err(anyhow!("Tool description cannot be empty"));
-            }
+        for (index, tool) in config.tools.iter().enumerate() {
+            Self::validate_tool(tool)
+                .map_err(|e| anyhow!("{source}: tools[{index}] ('{}'): {e}", tool.name))?;
 
             // Check for unique tool names
-            if !tool_names.insert(&tool.name) {
-                return Err(anyhow!("Duplicate tool name: {}", tool.name));
+            if let Some(first) = first_index.insert(&tool.name, index) {
+                return Err(anyhow!(
+                    "{source}: tools[{index}]: Duplicate tool name '{}' (first defined at tools[{first}])",
+                    tool.name
+                ));
             }
 
-            // Validate tool name format - allow both query tools and management tools
-            let is_query_tool = tool.name.ends_with("_query");
-            let is_crate_management_tool = matches!(
-                tool.name.as_str(),
-                "add_rust_crate" | "remove_rust_crate" | "list_rust_crates" | "check_rust_status"
-            );
+            debug!("Validated tool: {} -> {}", tool.name, tool.doc_type);
+        }
+
+        Ok(())
+    }
+
+    /// Validate a single configuration entry
+    fn validate_tool(tool: &ToolConfig) -> Result<()> {
+        // Check for empty fields
+        if tool.name.is_empty() {
+            return Err(anyhow!("Tool name cannot be empty"));
+        }
+        if tool.doc_type.is_empty() {
+            return Err(anyhow!("Tool docType cannot be empty"));
+        }
+        if tool.title.is_empty() {
+            return Err(anyhow!("Tool title cannot be empty"));
+        }
+        if tool.description.is_empty() {
+            return Err(anyhow!("Tool description cannot be empty"));
+        }
 
-            if !is_query_tool && !is_crate_management_tool {
+        // Validate tool name format - allow both query tools and management tools
+        let is_query_tool = tool.name.ends_with("_query");
+        if !is_query_tool && !CRATE_MANAGEMENT_TOOLS.contains(&tool.name.as_str()) {
+            return Err(anyhow!(
+                "Tool name '{}' must either end with '_query' or be a valid crate management tool ({})",
+                tool.name,
+                CRATE_MANAGEMENT_TOOLS.join(", ")
+            ));
+        }
+
+        if let Some(filters) = &tool.filters {
+            if !is_query_tool {
+                return Err(anyhow!("filters are only supported on query tools"));
+            }
+            if let Some(unknown) = filters.iter().find(|f| !FILTER_KEYS.contains(&f.as_str())) {
                 return Err(anyhow!(
-                    "Tool name '{}' must either end with '_query' or be a valid crate management tool (add_rust_crate, remove_rust_crate, list_rust_crates, check_rust_status)", 
-                    tool.name
+                    "Unknown filter key '{unknown}' (expected one of {})",
+                    FILTER_KEYS.join(", ")
                 ));
             }
+        }
 
-            // Validate doc_type - all doc types from the configuration are considered valid
-            // No need to validate against a hardcoded list since we extract them dynamically
+        if let Some(limit) = tool.default_limit {
+            if !is_query_tool {
+                return Err(anyhow!("defaultLimit is only supported on query tools"));
+            }
+            if !(1..=MAX_QUERY_LIMIT).contains(&limit) {
+                return Err(anyhow!(
+                    "defaultLimit must be between 1 and {MAX_QUERY_LIMIT}, got {limit}"
+                ));
+            }
+        }
 
-            debug!("Validated tool: {} -> {}", tool.name, tool.doc_type);
+        if let Some(secs) = tool.timeout_secs {
+            if !(1..=MAX_TIMEOUT_SECS).contains(&secs) {
+                return Err(anyhow!(
+                    "timeoutSecs must be between 1 and {MAX_TIMEOUT_SECS}, got {secs}"
+                ));
+            }
+        }
+
+        if let Some(taxonomy) = tool
+            .metadata_hints
+            .as_ref()
+            .and_then(|h| h.taxonomy.as_ref())
+        {
+            taxonomy.validate()?;
         }
 
+        // Validate doc_type - all doc types from the configuration are considered valid
+        // No need to validate against a hardcoded list since we extract them dynamically
         Ok(())
     }
 
@@ -236,6 +331,9 @@ mod tests {
             description: "Test tool".to_string(),
             enabled: true,
             metadata_hints: None,
+            filters: None,
+            default_limit: None,
+            timeout_secs: None,
         };
         let tool2 = tool1.clone();
         let config = ToolsConfig {
@@ -259,6 +357,9 @@ mod tests {
             description: "Test tool".to_string(),
             enabled: true,
             metadata_hints: None,
+            filters: None,
+            default_limit: None,
+            timeout_secs: None,
         };
         let config = ToolsConfig { tools: vec![tool] };
 
@@ -279,6 +380,9 @@ mod tests {
             description: "Test tool".to_string(),
             enabled: true,
             metadata_hints: None,
+            filters: None,
+            default_limit: None,
+            timeout_secs: None,
         };
         let config = ToolsConfig { tools: vec![tool] };
 
@@ -299,6 +403,9 @@ mod tests {
             description: "Enabled tool".to_string(),
             enabled: true,
             metadata_hints: None,
+            filters: None,
+            default_limit: None,
+            timeout_secs: None,
         };
         let tool2 = ToolConfig {
             name: "disabled_query".to_string(),
@@ -307,6 +414,9 @@ mod tests {
             description: "Disabled tool".to_string(),
             enabled: false,
             metadata_hints: None,
+            filters: None,
+            default_limit: None,
+            timeout_secs: None,
         };
         let config = ToolsConfig {
             tools: vec![tool1, tool2],
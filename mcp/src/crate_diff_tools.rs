@@ -0,0 +1,433 @@
+//! Documentation diff between two ingested versions of a Rust crate
+//!
+//! Pages are matched across versions by their `doc_path` with the version
+//! segment removed, so `https://docs.rs/serde/1.0.0/serde/de/index.html` and
+//! `https://docs.rs/serde/1.0.200/serde/de/index.html` are the same item.
+//! Both versions are read in `doc_path` order one batch at a time and only a
+//! content hash per chunk is kept; a second pass reads the content of changed
+//! pages to build their diff excerpts.
+
+use crate::tools::{continuations, response_soft_cap, ExecutionContext, Tool};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use db::chunks::{CHUNK_INDEX_KEY, PARENT_DOC_PATH_KEY};
+use db::{CrateStorage, CrateStore, Document};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write as _;
+
+/// Documents read per query while walking a version
+const DIFF_BATCH_SIZE: i64 = 500;
+
+/// Changed pages that get a diff excerpt; later ones are only listed
+const MAX_DIFF_EXCERPTS: usize = 100;
+
+/// Diff lines kept per excerpt before it is cut off
+const MAX_EXCERPT_LINES: usize = 40;
+
+/// Unchanged lines shown around a change
+const EXCERPT_CONTEXT_LINES: usize = 2;
+
+/// What is kept of one page of a version
+#[derive(Debug, Default)]
+struct PageSummary {
+    module_path: String,
+    item_type: String,
+    /// Content hash per chunk index
+    chunk_hashes: BTreeMap<usize, String>,
+}
+
+impl PageSummary {
+    fn item_json(&self, item: &str) -> Value {
+        json!({
+            "item": item,
+            "module_path": self.module_path,
+            "item_type": self.item_type,
+        })
+    }
+}
+
+/// Path of the page a document row belongs to (its own path unless it is a later chunk)
+fn page_path(document: &Document) -> &str {
+    document
+        .metadata
+        .get(PARENT_DOC_PATH_KEY)
+        .and_then(Value::as_str)
+        .unwrap_or(&document.doc_path)
+}
+
+fn chunk_index(document: &Document) -> usize {
+    document
+        .metadata
+        .get(CHUNK_INDEX_KEY)
+        .and_then(Value::as_u64)
+        .and_then(|i| usize::try_from(i).ok())
+        .unwrap_or(0)
+}
+
+/// Version-independent key of a page: its path without the `/{version}/` segment
+fn item_key(page_path: &str, version: &str) -> String {
+    page_path.replacen(&format!("/{version}/"), "/", 1)
+}
+
+/// Walk every active document of `version` in `doc_path` order, a batch at a time
+async fn for_each_document(
+    store: &dyn CrateStore,
+    crate_name: &str,
+    version: &str,
+    mut visit: impl FnMut(Document),
+) -> Result<()> {
+    let mut after: Option<String> = None;
+    loop {
+        let batch = store
+            .find_crate_version_documents(crate_name, version, after.as_deref(), DIFF_BATCH_SIZE)
+            .await?;
+        let full = i64::try_from(batch.len()).unwrap_or(i64::MAX) >= DIFF_BATCH_SIZE;
+        after = batch.last().map(|d| d.doc_path.clone());
+        batch.into_iter().for_each(&mut visit);
+        if !full {
+            return Ok(());
+        }
+    }
+}
+
+/// Content hashes of every page of `version`, keyed by item
+async fn summarize_version(
+    store: &dyn CrateStore,
+    crate_name: &str,
+    version: &str,
+) -> Result<BTreeMap<String, PageSummary>> {
+    let mut pages: BTreeMap<String, PageSummary> = BTreeMap::new();
+    for_each_document(store, crate_name, version, |document| {
+        let page = pages
+            .entry(item_key(page_path(&document), version))
+            .or_default();
+        let index = chunk_index(&document);
+        if index == 0 {
+            let meta = |key: &str| {
+                document
+                    .metadata
+                    .get(key)
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            page.module_path = meta("module_path");
+            page.item_type = meta("item_type");
+        }
+        page.chunk_hashes
+            .insert(index, db::content_hash(&document.content));
+    })
+    .await?;
+    Ok(pages)
+}
+
+/// Full content of the pages of `version` whose item is in `items`
+async fn page_contents(
+    store: &dyn CrateStore,
+    crate_name: &str,
+    version: &str,
+    items: &HashSet<&str>,
+) -> Result<HashMap<String, String>> {
+    let mut chunks: HashMap<String, BTreeMap<usize, String>> = HashMap::new();
+    for_each_document(store, crate_name, version, |document| {
+        let key = item_key(page_path(&document), version);
+        if items.contains(key.as_str()) {
+            let index = chunk_index(&document);
+            chunks
+                .entry(key)
+                .or_default()
+                .insert(index, document.content);
+        }
+    })
+    .await?;
+    Ok(chunks
+        .into_iter()
+        .map(|(key, parts)| (key, parts.into_values().collect::<Vec<_>>().join("\n")))
+        .collect())
+}
+
+/// Single-hunk unified diff of `old` and `new` around the lines that differ,
+/// cut off after [`MAX_EXCERPT_LINES`] lines
+fn unified_excerpt(old: &str, new: &str, from_label: &str, to_label: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let prefix = old_lines
+        .iter()
+        .zip(&new_lines)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let start = prefix.saturating_sub(EXCERPT_CONTEXT_LINES);
+    let trailing = suffix.min(EXCERPT_CONTEXT_LINES);
+    let old_end = old_lines.len() - suffix + trailing;
+    let new_end = new_lines.len() - suffix + trailing;
+    let range = |end: usize| {
+        let count = end - start;
+        let first = if count == 0 { start } else { start + 1 };
+        format!("{first},{count}")
+    };
+
+    let mut body: Vec<String> = Vec::new();
+    body.extend(old_lines[start..prefix].iter().map(|l| format!(" {l}")));
+    body.extend(
+        old_lines[prefix..old_lines.len() - suffix]
+            .iter()
+            .map(|l| format!("-{l}")),
+    );
+    body.extend(
+        new_lines[prefix..new_lines.len() - suffix]
+            .iter()
+            .map(|l| format!("+{l}")),
+    );
+    body.extend(
+        old_lines[old_lines.len() - suffix..old_end]
+            .iter()
+            .map(|l| format!(" {l}")),
+    );
+
+    let mut excerpt = format!(
+        "--- {from_label}\n+++ {to_label}\n@@ -{} +{} @@\n",
+        range(old_end),
+        range(new_end)
+    );
+    for line in body.iter().take(MAX_EXCERPT_LINES) {
+        excerpt.push_str(line);
+        excerpt.push('\n');
+    }
+    if body.len() > MAX_EXCERPT_LINES {
+        let _ = writeln!(excerpt, "... {} more lines", body.len() - MAX_EXCERPT_LINES);
+    }
+    excerpt
+}
+
+/// Compare the documentation of two ingested versions of a crate
+pub struct DiffRustCrateVersionsTool {
+    storage: CrateStorage,
+}
+
+impl DiffRustCrateVersionsTool {
+    /// Create a new crate version diff tool
+    pub fn new(storage: impl Into<CrateStorage>) -> Self {
+        Self {
+            storage: storage.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for DiffRustCrateVersionsTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "diff_rust_crate_versions",
+            "description": "Compare the documentation of two ingested versions of a Rust crate. Returns JSON with the items added, removed and changed between the versions, their counts, and a short unified diff excerpt of each changed item.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "The name of the Rust crate"
+                    },
+                    "from_version": {
+                        "type": "string",
+                        "description": "The older ingested version"
+                    },
+                    "to_version": {
+                        "type": "string",
+                        "description": "The newer ingested version"
+                    },
+                    "summary_only": {
+                        "type": "boolean",
+                        "description": "List the changed items without diff excerpts (default: false)",
+                        "default": false
+                    }
+                },
+                "required": ["name", "from_version", "to_version"],
+                "additionalProperties": false
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        self.execute_with_context(arguments, &ExecutionContext::default())
+            .await
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ExecutionContext,
+    ) -> Result<String> {
+        let str_arg = |key: &str| {
+            arguments
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| anyhow!("Missing required parameter: {key}"))
+        };
+        let crate_name = str_arg("name")?;
+        let from_version = str_arg("from_version")?;
+        let to_version = str_arg("to_version")?;
+        let summary_only = arguments
+            .get("summary_only")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        if from_version == to_version {
+            return Err(anyhow!("from_version and to_version are the same"));
+        }
+
+        let store = self.storage.store();
+        let stored: Vec<String> = store
+            .find_crate_versions(crate_name)
+            .await?
+            .into_iter()
+            .map(|info| info.version)
+            .collect();
+        for version in [from_version, to_version] {
+            if !stored.iter().any(|v| v == version) {
+                return Err(anyhow!(
+                    "Crate '{crate_name}' has no ingested version '{version}'. Stored versions: {}",
+                    if stored.is_empty() {
+                        "none".to_string()
+                    } else {
+                        stored.join(", ")
+                    }
+                ));
+            }
+        }
+
+        let old_pages = summarize_version(store, crate_name, from_version).await?;
+        let new_pages = summarize_version(store, crate_name, to_version).await?;
+
+        let removed: Vec<Value> = old_pages
+            .iter()
+            .filter(|(item, _)| !new_pages.contains_key(*item))
+            .map(|(item, page)| page.item_json(item))
+            .collect();
+        let added: Vec<Value> = new_pages
+            .iter()
+            .filter(|(item, _)| !old_pages.contains_key(*item))
+            .map(|(item, page)| page.item_json(item))
+            .collect();
+        let changed: Vec<(&String, &PageSummary)> = new_pages
+            .iter()
+            .filter(|(item, page)| {
+                old_pages
+                    .get(*item)
+                    .is_some_and(|old| old.chunk_hashes != page.chunk_hashes)
+            })
+            .collect();
+        let unchanged = new_pages.len() - added.len() - changed.len();
+
+        let (mut old_contents, mut new_contents) = (HashMap::new(), HashMap::new());
+        if !summary_only && !changed.is_empty() {
+            let excerpted: HashSet<&str> = changed
+                .iter()
+                .take(MAX_DIFF_EXCERPTS)
+                .map(|(item, _)| item.as_str())
+                .collect();
+            old_contents = page_contents(store, crate_name, from_version, &excerpted).await?;
+            new_contents = page_contents(store, crate_name, to_version, &excerpted).await?;
+        }
+        let from_label = format!("{crate_name}@{from_version}");
+        let to_label = format!("{crate_name}@{to_version}");
+        let changed_items: Vec<Value> = changed
+            .iter()
+            .map(|(item, page)| {
+                let mut entry = page.item_json(item);
+                if let (Some(old), Some(new)) = (old_contents.get(*item), new_contents.get(*item)) {
+                    entry["diff"] = json!(unified_excerpt(old, new, &from_label, &to_label));
+                }
+                entry
+            })
+            .collect();
+
+        tracing::info!(
+            "Diffed {} {} -> {}: {} added, {} removed, {} changed",
+            crate_name,
+            from_version,
+            to_version,
+            added.len(),
+            removed.len(),
+            changed_items.len()
+        );
+
+        let mut envelope = Map::new();
+        envelope.insert("crate".to_string(), json!(crate_name));
+        envelope.insert("from_version".to_string(), json!(from_version));
+        envelope.insert("to_version".to_string(), json!(to_version));
+        envelope.insert(
+            "counts".to_string(),
+            json!({
+                "added": added.len(),
+                "removed": removed.len(),
+                "changed": changed_items.len(),
+                "unchanged": unchanged,
+            }),
+        );
+        if !summary_only && changed_items.len() > MAX_DIFF_EXCERPTS {
+            envelope.insert(
+                "note".to_string(),
+                json!(format!(
+                    "Diff excerpts cover the first {MAX_DIFF_EXCERPTS} changed items"
+                )),
+            );
+        }
+        envelope.insert("added".to_string(), Value::Array(added));
+        envelope.insert("removed".to_string(), Value::Array(removed));
+        let response = continuations().paginate_items(
+            context.session_id(),
+            envelope,
+            "changed",
+            changed_items,
+            response_soft_cap(),
+        );
+        Ok(serde_json::to_string_pretty(&response)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{item_key, unified_excerpt, MAX_EXCERPT_LINES};
+
+    #[test]
+    fn test_item_keys_drop_the_version_segment() {
+        assert_eq!(
+            item_key("https://docs.rs/serde/1.0.0/serde/de/index.html", "1.0.0"),
+            "https://docs.rs/serde/serde/de/index.html"
+        );
+        assert_eq!(
+            item_key(
+                "https://docs.rs/serde/1.0.200/serde/de/index.html",
+                "1.0.200"
+            ),
+            item_key("https://docs.rs/serde/1.0.0/serde/de/index.html", "1.0.0")
+        );
+    }
+
+    #[test]
+    fn test_excerpt_shows_the_changed_lines_with_context() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh";
+        let new = "a\nb\nc\nD\ne\nf\ng\nh";
+        assert_eq!(
+            unified_excerpt(old, new, "x@1", "x@2"),
+            "--- x@1\n+++ x@2\n@@ -2,5 +2,5 @@\n b\n c\n-d\n+D\n e\n f\n"
+        );
+    }
+
+    #[test]
+    fn test_excerpt_is_capped() {
+        let new: String = (0..100).map(|i| format!("line {i}\n")).collect();
+        let excerpt = unified_excerpt("", &new, "x@1", "x@2");
+        assert_eq!(excerpt.lines().count(), 3 + MAX_EXCERPT_LINES + 1);
+        assert!(excerpt.ends_with("... 60 more lines\n"));
+        assert!(excerpt.contains("@@ -0,0 +1,100 @@"));
+    }
+}
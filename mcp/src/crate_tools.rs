@@ -6,48 +6,95 @@
 #![allow(clippy::cast_possible_truncation)]
 #![allow(clippy::too_many_lines)]
 
-use crate::job_queue::CrateJobProcessor;
+use crate::embedding_cache::{content_hash, CachedEmbeddingClient};
+use crate::job_queue::{CrateJobOptions, CrateJobProcessor, PackageJobOptions, RemoveJobOptions};
+use crate::package_tools::PackageEcosystem;
+use crate::provider_health::{provider_checks, ProviderStatus};
+use crate::query_cache::query_cache;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use db::{
-    models::{CrateJob, JobStatus, PaginationParams},
-    queries::{CrateJobQueries, CrateQueries},
-    DatabasePool,
+    models::{
+        CrateCursor, CrateDependent, CrateJob, CrateSortField, CrateStatusFilter, FetchCacheEntry,
+        JobStatus, PaginationParams, SortOrder,
+    },
+    queries::{
+        CrateJobQueries, DocumentQueries, FetchCacheQueries, IdempotencyConflict, IngestJobQueries,
+    },
+    CrateStorage, CrateStore, DatabasePool,
+};
+use embed::client::{EmbeddingClient, RetryPolicy};
+use embed::JobCosts;
+use rust_crates::{
+    BuiltFeatures, CacheValidators, CachedPage, CrateDependency, CrateLookup, CrateMetadata,
+    CrawlProgress, CrawlSink, CrawlState, DocPage, FetchCache, LocalCrate, LocalDocsSource,
+    RateLimiter, RawArchive, RustLoader, LOCAL_ORIGIN,
 };
-use embed::client::EmbeddingClient;
-use rust_crates::RustLoader;
 use serde_json::{json, Value};
 use sqlx;
-use std::{fmt::Write as _, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write as _,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 // use tokio::task; // Commented out for MVP - not using background tasks
-use std::sync::OnceLock;
-use tokio::sync::{oneshot, Semaphore};
+use std::sync::{LazyLock, Mutex, OnceLock};
+use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore};
 use uuid::Uuid;
 
-use crate::tools::Tool;
+use crate::tools::{response_soft_cap, ExecutionContext, RequestCancelled, Tool, ToolError};
+
+/// How long `add_rust_crate` waits for crates.io to confirm a crate exists
+pub const CRATES_IO_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Add Rust crate tool - enqueues background job and returns 202 + job ID
 pub struct AddRustCrateTool {
     job_processor: CrateJobProcessor,
-    #[allow(dead_code)] // Will be used when background processing is fully implemented
-    rust_loader: RustLoader,
+    /// Checks requested crates on crates.io before a job is queued
+    crates_io: RustLoader,
+    crates_io_timeout: Duration,
     embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
-    db_pool: DatabasePool,
+    storage: CrateStorage,
 }
 
 impl AddRustCrateTool {
     /// Create a new add crate tool
     pub fn new(
-        db_pool: DatabasePool,
+        storage: impl Into<CrateStorage>,
         embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
     ) -> Self {
+        let storage = storage.into();
         Self {
-            job_processor: CrateJobProcessor::new(db_pool.clone()),
-            rust_loader: RustLoader::new(),
+            job_processor: CrateJobProcessor::new(storage.clone()),
+            // crates.io asks API clients for at most one request per second
+            crates_io: RustLoader::with_fetcher(Box::new(RateLimiter::with_limits(
+                Duration::from_secs(1),
+                2,
+            ))),
+            crates_io_timeout: CRATES_IO_CHECK_TIMEOUT,
             embedding_client,
-            db_pool,
+            storage,
         }
     }
+
+    /// Check crates through `loader`, waiting at most `timeout` for an answer
+    #[must_use]
+    pub fn with_crates_io(mut self, loader: RustLoader, timeout: Duration) -> Self {
+        self.crates_io = loader;
+        self.crates_io_timeout = timeout;
+        self
+    }
+}
+
+/// A requested crate as `add_rust_crate` queues it
+struct ResolvedCrate {
+    /// The stored spelling, else the crates.io id
+    name: String,
+    /// crates.io metadata fetched while checking the crate exists
+    metadata: Option<CrateMetadata>,
+    /// crates.io could not be asked; the job finds out whether the crate exists
+    unverified: bool,
 }
 
 #[async_trait]
@@ -55,7 +102,7 @@ impl Tool for AddRustCrateTool {
     fn definition(&self) -> Value {
         json!({
             "name": "add_rust_crate",
-            "description": "Add a new Rust crate to the documentation system with automatic docs.rs ingestion, version management, and feature selection. Supports atomic operations with rollback capability. Returns immediately with a job ID for tracking progress.",
+            "description": "Add a new Rust crate to the documentation system with automatic docs.rs ingestion, version management, and feature selection. Supports atomic operations with rollback capability. Returns immediately with a job ID for tracking progress. Errors carry a code in error.data.code: already_exists (-32005) when the crate, or the pinned version, is already stored and force_update is not set (details.current_version); invalid_input (-32602) for an empty name, a crate crates.io does not know (details.suggestions lists up to five close names) or a version/channel that does not fit the crate. If crates.io cannot be reached within 5 seconds the job is queued anyway and the response says verified: false.",
             "inputSchema": {
                 "type": "object",
                 "properties": {
@@ -65,7 +112,12 @@ impl Tool for AddRustCrateTool {
                     },
                     "version": {
                         "type": "string",
-                        "description": "Specific version to fetch (optional, defaults to latest stable version)"
+                        "description": "Version or semver range to fetch, e.g. '1.0.3', '^1.0' or '1.x' (optional, defaults to latest stable version). Ranges resolve to the highest matching non-yanked release. A requested version is kept next to the crate's other ingested versions; force_update then replaces only that version."
+                    },
+                    "channel": {
+                        "type": "string",
+                        "enum": ["stable", "beta", "nightly"],
+                        "description": "Rust release channel to fetch standard library crates (std, core, alloc, proc_macro, test) from doc.rust-lang.org (optional, defaults to stable). Only valid for those crates, which take no 'version'."
                     },
                     "features": {
                         "type": "array",
@@ -83,9 +135,22 @@ impl Tool for AddRustCrateTool {
                     "atomic_rollback": {
                         "type": "boolean",
                         "description": "Enable atomic operations with rollback on failure (optional, defaults to true)"
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "Only estimate the ingestion (pages, crawl time, tokens, embedding cost) from the crate's docs.rs index pages; nothing is queued or stored (optional, defaults to false)"
+                    },
+                    "no_cache": {
+                        "type": "boolean",
+                        "description": "Fetch every docs.rs page in full instead of skipping pages unchanged since the last crawl (optional, defaults to false; force_update also fetches everything)"
+                    },
+                    "idempotency_key": {
+                        "type": "string",
+                        "description": "Client-chosen key for safe retries: a repeated call with the same key within 24 hours returns the job the first call created, with its current status, instead of queueing another (optional)"
                     }
                 },
-                "required": ["name"]
+                "required": ["name"],
+                "additionalProperties": false
             }
         })
     }
@@ -97,6 +162,7 @@ impl Tool for AddRustCrateTool {
             .ok_or_else(|| anyhow!("Missing required 'name' parameter"))?;
 
         let version = arguments.get("version").and_then(Value::as_str);
+        let channel = arguments.get("channel").and_then(Value::as_str);
         let features = arguments
             .get("features")
             .and_then(|f| f.as_array())
@@ -117,21 +183,101 @@ impl Tool for AddRustCrateTool {
             .get("atomic_rollback")
             .and_then(Value::as_bool)
             .unwrap_or(true);
+        let no_cache = arguments
+            .get("no_cache")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let dry_run = arguments
+            .get("dry_run")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let idempotency_key = idempotency_key_argument(&arguments);
 
         // Validate crate name
-        if crate_name.is_empty() {
-            return Err(anyhow!("Crate name cannot be empty"));
+        if crate_name.trim().is_empty() {
+            return Err(ToolError::invalid_input("Crate name cannot be empty").into());
         }
 
-        // Check if crate already exists by looking at documents
-        if let Some(existing_crate) =
-            CrateQueries::find_crate_by_name(self.db_pool.pool(), crate_name).await?
-        {
+        // A retried request gets the job it already created
+        if let Some(key) = idempotency_key.filter(|_| !dry_run) {
+            if let Some(job) = self
+                .storage
+                .store()
+                .find_job_by_idempotency_key(key)
+                .await?
+            {
+                return replay_job(&job, key, crate_name, "add_crate");
+            }
+        }
+
+        if channel.is_some() && !rust_crates::is_std_crate(&crate_name.trim().to_lowercase()) {
+            return Err(ToolError::invalid_input(format!(
+                "'channel' only applies to standard library crates ({})",
+                rust_crates::STD_CRATES.join(", ")
+            ))
+            .into());
+        }
+
+        // Existence checks, the job and its documents all use the canonical name
+        let requested_name = crate_name;
+        let resolved = self.resolve_crate(requested_name).await?;
+        let crate_name = resolved.name.as_str();
+        let alias = (crate_name != requested_name).then_some(requested_name);
+        if let Some(alias) = alias {
+            tracing::info!("Normalized crate name '{}' to '{}'", alias, crate_name);
+        }
+
+        // Resolve ranges now so a request nothing satisfies fails here, not in the job.
+        // Standard library crates have no crates.io versions; their docs are
+        // crawled per release channel, which the job carries as its version.
+        let resolved_version = if rust_crates::is_std_crate(crate_name) {
+            if version.is_some() {
+                return Err(ToolError::invalid_input(format!(
+                    "'{crate_name}' is a standard library crate; use 'channel' (stable, beta or nightly) instead of 'version'"
+                ))
+                .into());
+            }
+            let channel = channel.unwrap_or(rust_crates::DEFAULT_RUST_CHANNEL);
+            if !rust_crates::is_rust_channel(channel) {
+                return Err(ToolError::invalid_input(format!(
+                    "Unknown channel '{}': expected one of {}",
+                    channel,
+                    rust_crates::RUST_CHANNELS.join(", ")
+                ))
+                .into());
+            }
+            Some(channel.to_string())
+        } else {
+            match version {
+                Some(requested) => {
+                    Some(Self::resolve_requested_version(crate_name, requested).await?)
+                }
+                None => None,
+            }
+        };
+
+        if dry_run {
+            return self
+                .estimate_ingestion(crate_name, resolved_version.as_deref(), force_update)
+                .await;
+        }
+
+        // Check if crate already exists by looking at documents. A pinned
+        // version is added next to the crate's other versions, so only the
+        // same version counts as existing.
+        let existing = match version.and(resolved_version.as_deref()) {
+            Some(pinned) => self
+                .storage
+                .store()
+                .find_crate_versions(crate_name)
+                .await?
+                .into_iter()
+                .find(|info| info.version == pinned),
+            None => self.storage.store().find_crate_by_name(crate_name).await?,
+        };
+        if let Some(existing_crate) = existing {
             if !force_update {
-                return Ok(format!(
-                    "Crate '{}' already exists in the system (version: {}). Use force_update=true to update it, or remove_rust_crate first if you want to completely replace it.",
-                    crate_name, existing_crate.version
-                ));
+                return Err(already_exists(crate_name, &existing_crate.version, alias).into());
             }
             tracing::info!(
                 "Force updating existing crate '{}' (current version: {})",
@@ -140,249 +286,857 @@ impl Tool for AddRustCrateTool {
             );
         }
 
-        // Enqueue the background job
-        let job_id = self.job_processor.enqueue_add_crate_job(crate_name).await?;
-
-        // Start async processing via Redis or local background
-        if crate::queue::use_redis_queue() {
-            let msg = crate::queue::RedisJobMessage::new(
-                job_id,
-                "crate_add",
-                3,
-                json!({
-                    "crate_name": crate_name,
-                    "version": version,
-                    "features": features,
-                    "include_dev_deps": include_dev_deps,
-                    "force_update": force_update,
-                    "atomic_rollback": atomic_rollback
-                }),
-            );
-            crate::queue::enqueue_job(&msg).await?;
-        } else {
-            let job_processor = self.job_processor.clone();
-            let embedding_client = self.embedding_client.clone();
-            let db_pool = self.db_pool.clone();
-            let crate_name_owned = crate_name.to_string();
-            let version_owned = version.map(String::from);
-
-            tokio::spawn(async move {
-                // Global concurrency cap for crate ingestion jobs
-                let _permit = get_crate_job_semaphore().acquire_owned().await.ok();
-                tracing::info!("Background task started for crate: {}", crate_name_owned);
-                let mut rust_loader = RustLoader::new();
-
-                // First, update job status to running
-                if let Err(e) = job_processor
-                    .update_job_status(job_id, JobStatus::Running, Some(0), None)
-                    .await
-                {
-                    tracing::error!("Failed to update job status to running: {}", e);
-                }
-
-                // Heartbeat task to keep updated_at fresh while job runs
-                let (hb_tx, mut hb_rx) = oneshot::channel::<()>();
-                let hb_processor = job_processor.clone();
-                let hb_job_id = job_id;
-                let hb_handle = tokio::spawn(async move {
-                    let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
-                    loop {
-                        tokio::select! {
-                            _ = interval.tick() => {
-                                let _ = hb_processor.update_job_status(hb_job_id, JobStatus::Running, None, None).await;
-                            }
-                            _ = &mut hb_rx => {
-                                break;
-                            }
-                        }
-                    }
-                });
-
-                if let Err(e) = Self::process_crate_ingestion(
-                    &job_processor,
-                    &mut rust_loader,
-                    &embedding_client,
-                    &db_pool,
-                    job_id,
-                    &crate_name_owned,
-                    version_owned.as_deref(),
-                    features.as_ref(),
-                    include_dev_deps,
-                    force_update,
-                    atomic_rollback,
-                )
-                .await
-                {
-                    tracing::error!(
-                        "Background crate ingestion failed for {}: {}",
-                        crate_name_owned,
-                        e
-                    );
-                    // Update job status to failed
-                    if let Err(update_err) = job_processor
-                        .update_job_status(job_id, JobStatus::Failed, Some(0), Some(&e.to_string()))
-                        .await
-                    {
-                        tracing::error!("Failed to update job status to failed: {}", update_err);
-                    }
-                } else {
-                    tracing::info!(
-                        "Background crate ingestion completed successfully for: {}",
-                        crate_name_owned
-                    );
-                }
+        let options = CrateJobOptions {
+            version: resolved_version.clone(),
+            version_req: version.map(String::from),
+            features: features.clone(),
+            include_dev_deps,
+            force_update,
+            no_cache,
+            atomic_rollback,
+            local: None,
+            alias: alias.map(String::from),
+            metadata: resolved.metadata.clone(),
+            unverified: resolved.unverified,
+            from_archive: false,
+        };
 
-                // Stop heartbeat
-                let _ = hb_tx.send(());
-                let _ = hb_handle.await;
-            });
+        // Enqueue the background job
+        let enqueued = self
+            .job_processor
+            .enqueue_add_crate_job(crate_name, &options, idempotency_key)
+            .await?;
+        // A concurrent call with the same key won; its job is already queued
+        if !enqueued.created {
+            return Ok(replayed_job_response(&enqueued.job));
         }
+        let job_id = enqueued.job.id;
+
+        start_add_job(
+            &self.storage,
+            &self.embedding_client,
+            job_id,
+            crate_name,
+            &options,
+        )
+        .await?;
 
         // Return 202 Accepted with job ID immediately
+        let normalized = alias
+            .map(|alias| format!(" (normalized from '{alias}')"))
+            .unwrap_or_default();
         Ok(json!({
             "status": "accepted",
             "job_id": job_id.to_string(),
-            "message": format!("Crate '{}' ingestion job queued successfully. Use check_rust_status with job_id to track progress.", crate_name)
+            "crate_name": crate_name,
+            "normalized_from": alias,
+            "verified": !resolved.unverified,
+            "version": resolved_version,
+            "version_req": version,
+            "message": format!("Crate '{crate_name}'{normalized} ingestion job queued successfully. Use check_rust_status with job_id to track progress.")
         }).to_string())
     }
 }
 
-impl AddRustCrateTool {
-    /// Public wrapper for worker usage to process crate ingestion
-    ///
-    /// # Errors
-    /// Returns an error if ingestion fails or database updates cannot be performed.
-    #[allow(clippy::too_many_arguments)]
-    pub async fn process_in_worker(
-        &self,
-        job_processor: &CrateJobProcessor,
-        rust_loader: &mut RustLoader,
-        embedding_client: &Arc<dyn EmbeddingClient + Send + Sync>,
-        db_pool: &DatabasePool,
-        job_id: Uuid,
-        crate_name: &str,
-        version: Option<&str>,
-        features: Option<&Vec<String>>,
-        include_dev_deps: bool,
-        force_update: bool,
-        atomic_rollback: bool,
-    ) -> Result<()> {
-        Self::process_crate_ingestion(
-            job_processor,
-            rust_loader,
+/// Add a crate from a local `cargo doc` build - enqueues a background job like
+/// `add_rust_crate`, so `check_rust_status` and `remove_rust_crate` apply
+pub struct AddLocalCrateTool {
+    job_processor: CrateJobProcessor,
+    embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
+    storage: CrateStorage,
+}
+
+impl AddLocalCrateTool {
+    /// Create a new add local crate tool
+    pub fn new(
+        storage: impl Into<CrateStorage>,
+        embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
+    ) -> Self {
+        let storage = storage.into();
+        Self {
+            job_processor: CrateJobProcessor::new(storage.clone()),
             embedding_client,
-            db_pool,
-            job_id,
-            crate_name,
-            version,
-            features,
-            include_dev_deps,
-            force_update,
-            atomic_rollback,
-        )
-        .await
+            storage,
+        }
     }
+}
 
-    /// Process crate ingestion in background with enhanced options
-    #[allow(clippy::too_many_arguments)]
-    async fn process_crate_ingestion(
-        job_processor: &CrateJobProcessor,
-        rust_loader: &mut RustLoader,
-        embedding_client: &Arc<dyn EmbeddingClient + Send + Sync>,
-        db_pool: &DatabasePool,
-        job_id: Uuid,
-        crate_name: &str,
-        version: Option<&str>,
-        features: Option<&Vec<String>>,
-        _include_dev_deps: bool,
-        force_update: bool,
-        atomic_rollback: bool,
-    ) -> Result<()> {
-        tracing::info!(
-            "process_crate_ingestion started for crate: {} (job_id: {})",
-            crate_name,
-            job_id
-        );
+#[async_trait]
+impl Tool for AddLocalCrateTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "add_local_crate",
+            "description": "Add a private or unpublished Rust crate from its locally built rustdoc output (cargo doc). Name and version come from the crate's Cargo.toml. Returns immediately with a job ID for tracking progress with check_rust_status; remove it with remove_rust_crate. Errors carry a code in error.data.code: already_exists (-32005) when the crate is already stored and force_update is not set (details.current_version).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Directory of the crate, holding its Cargo.toml, on the server's filesystem"
+                    },
+                    "doc_dir": {
+                        "type": "string",
+                        "description": "Rustdoc output directory, e.g. '/work/app/target/doc' (optional, defaults to target/doc of the crate or its workspace)"
+                    },
+                    "build": {
+                        "type": "boolean",
+                        "description": "Run 'cargo doc --no-deps' in the crate directory before reading the output (optional, defaults to false)"
+                    },
+                    "force_update": {
+                        "type": "boolean",
+                        "description": "Force update if crate already exists (optional, defaults to false)"
+                    },
+                    "idempotency_key": {
+                        "type": "string",
+                        "description": "Client-chosen key for safe retries: a repeated call with the same key within 24 hours returns the job the first call created, with its current status, instead of queueing another (optional)"
+                    }
+                },
+                "required": ["path"],
+                "additionalProperties": false
+            }
+        })
+    }
 
-        // Check if vector extension is available by trying a simple vector operation
-        let vector_extension_available = match sqlx::query("SELECT '[1,2,3]'::vector(3)")
-            .execute(db_pool.pool())
-            .await
-        {
-            Ok(_) => {
-                tracing::debug!("Vector extension is available");
-                true
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        let path = arguments
+            .get("path")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+            .ok_or_else(|| anyhow!("Missing required 'path' parameter"))?;
+        let doc_dir = arguments
+            .get("doc_dir")
+            .and_then(Value::as_str)
+            .map(std::path::PathBuf::from);
+        let build = arguments
+            .get("build")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let force_update = arguments
+            .get("force_update")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let idempotency_key = idempotency_key_argument(&arguments);
+
+        // The job runs on this host, so the paths are checked here rather than in the job
+        let path = std::path::Path::new(path)
+            .canonicalize()
+            .map_err(|e| anyhow!("Cannot read crate directory '{}': {}", path, e))?;
+        let local_crate = LocalCrate::from_manifest(&path)?;
+        if !build {
+            local_crate.find_doc_dir(doc_dir.as_deref())?;
+        }
+        let crate_name = local_crate.name();
+
+        if let Some(key) = idempotency_key {
+            if let Some(job) = self
+                .storage
+                .store()
+                .find_job_by_idempotency_key(key)
+                .await?
+            {
+                return replay_job(&job, key, crate_name, "add_crate");
             }
-            Err(e) => {
-                if e.to_string().contains("vector")
-                    || e.to_string().contains("extension")
-                    || e.to_string().contains("type")
-                {
-                    tracing::warn!(
-                        "Vector extension not available in database, skipping embeddings: {}",
-                        e
-                    );
-                    false
-                } else {
-                    return Err(anyhow!("Database connection test failed: {}", e));
-                }
+        }
+
+        if let Some(existing_crate) = self.storage.store().find_crate_by_name(crate_name).await? {
+            if !force_update {
+                return Err(already_exists(crate_name, &existing_crate.version, None).into());
             }
-        };
+        }
 
-        // Update job status to running
-        job_processor
-            .update_job_status(job_id, JobStatus::Running, Some(0), None)
+        let options = CrateJobOptions {
+            version: Some(local_crate.version().to_string()),
+            force_update,
+            local: Some(LocalDocsSource {
+                path,
+                doc_dir,
+                build,
+            }),
+            ..CrateJobOptions::default()
+        };
+        let enqueued = self
+            .job_processor
+            .enqueue_add_crate_job(crate_name, &options, idempotency_key)
             .await?;
+        if !enqueued.created {
+            return Ok(replayed_job_response(&enqueued.job));
+        }
+        let job_id = enqueued.job.id;
+        start_add_job(
+            &self.storage,
+            &self.embedding_client,
+            job_id,
+            crate_name,
+            &options,
+        )
+        .await?;
 
-        // If force_update is true and atomic_rollback is enabled, first store existing state
-        let rollback_data = if force_update && atomic_rollback {
-            tracing::info!(
-                "Storing rollback data for atomic operation on crate: {}",
-                crate_name
-            );
-            Self::backup_existing_crate_data(db_pool, crate_name).await?
-        } else {
-            None
-        };
+        Ok(json!({
+            "status": "accepted",
+            "job_id": job_id.to_string(),
+            "version": local_crate.version(),
+            "origin": LOCAL_ORIGIN,
+            "message": format!("Local crate '{}' ingestion job queued successfully. Use check_rust_status with job_id to track progress.", crate_name)
+        })
+        .to_string())
+    }
+}
 
-        // Load crate documentation
-        tracing::info!(
-            "Starting ingestion for crate: {} with enhanced options",
-            crate_name
-        );
-        let (crate_info, doc_pages) = rust_loader
-            .load_crate_docs(crate_name, version)
-            .await
-            .map_err(|e| {
-                // Note: rollback will be handled in error processing below if needed
-                if rollback_data.is_some() {
-                    tracing::warn!(
-                        "Load failed, will attempt rollback for crate: {}",
-                        crate_name
-                    );
-                }
-                anyhow!("Failed to load crate documentation: {}", e)
-            })?;
+/// Re-parse a crate version from its archived raw pages - enqueues an
+/// `add_crate` job that reads the archive instead of crawling docs.rs
+pub struct ReprocessRustCrateTool {
+    job_processor: CrateJobProcessor,
+    embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
+    storage: CrateStorage,
+    archive: Option<Arc<RawArchive>>,
+}
 
-        // Update progress
-        job_processor
-            .update_job_status(job_id, JobStatus::Running, Some(25), None)
-            .await?;
+impl ReprocessRustCrateTool {
+    /// Create a new reprocess tool reading the archive at `RAW_ARCHIVE_URL`
+    pub fn new(
+        storage: impl Into<CrateStorage>,
+        embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
+    ) -> Self {
+        let storage = storage.into();
+        Self {
+            job_processor: CrateJobProcessor::new(storage.clone()),
+            embedding_client,
+            storage,
+            archive: RawArchive::from_env().map(Arc::new),
+        }
+    }
 
-        // If force_update, remove existing documents first
-        if force_update {
-            tracing::info!(
-                "Removing existing documents for force update of crate: {}",
-                crate_name
-            );
-            let mut tx = db_pool.pool().begin().await?;
-            sqlx::query("DELETE FROM documents WHERE doc_type = 'rust' AND (metadata->>'crate_name' = $1 OR source_name = $1)")
-                .bind(crate_name)
-                .execute(&mut *tx)
-                .await?;
-            tx.commit().await?;
+    /// Check archived pages in `archive` instead of the configured one
+    #[must_use]
+    pub fn with_archive(mut self, archive: Arc<RawArchive>) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+}
+
+#[async_trait]
+impl Tool for ReprocessRustCrateTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "reprocess_rust_crate",
+            "description": "Re-run parsing, chunking and embedding for a stored Rust crate from the raw docs.rs pages archived when it was crawled (RAW_ARCHIVE_URL), without fetching docs.rs again. Documents of the version are replaced; unchanged chunks keep their embeddings. Returns immediately with a job ID for tracking progress with check_rust_status. Errors carry a code in error.data.code: not_found (-32004) when the crate is not stored or the archive holds no pages for the version.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Name of a stored crate"
+                    },
+                    "version": {
+                        "type": "string",
+                        "description": "Archived version to reprocess (optional, defaults to the latest stored version; the release channel for standard library crates)"
+                    },
+                    "idempotency_key": {
+                        "type": "string",
+                        "description": "Client-chosen key for safe retries: a repeated call with the same key within 24 hours returns the job the first call created, with its current status, instead of queueing another (optional)"
+                    }
+                },
+                "required": ["name"],
+                "additionalProperties": false
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        let crate_name = arguments
+            .get("name")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .ok_or_else(|| anyhow!("Missing required 'name' parameter"))?;
+        if crate_name.is_empty() {
+            return Err(ToolError::invalid_input("Crate name cannot be empty").into());
+        }
+        let version = arguments
+            .get("version")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|version| !version.is_empty());
+        let idempotency_key = idempotency_key_argument(&arguments);
+
+        let Some(archive) = &self.archive else {
+            return Err(anyhow!(
+                "No raw page archive is configured; set RAW_ARCHIVE_URL and crawl the crate again to archive its pages"
+            ));
+        };
+
+        if let Some(key) = idempotency_key {
+            if let Some(job) = self
+                .storage
+                .store()
+                .find_job_by_idempotency_key(key)
+                .await?
+            {
+                return replay_job(&job, key, crate_name, "add_crate");
+            }
+        }
+
+        let Some(stored) = self.storage.store().find_crate_by_name(crate_name).await? else {
+            return Err(crate_not_found(crate_name).into());
+        };
+        // Standard library pages are archived under the channel they were crawled from
+        let archived_version = match version {
+            Some(version) => version.to_string(),
+            None if rust_crates::is_std_crate(crate_name) => {
+                rust_crates::DEFAULT_RUST_CHANNEL.to_string()
+            }
+            None => stored.version.clone(),
+        };
+        if !archive.has_pages(crate_name, &archived_version).await? {
+            return Err(ToolError::NotFound {
+                message: format!(
+                    "No archived pages for crate '{crate_name}' version {archived_version}. Pages are archived by crawls that run while RAW_ARCHIVE_URL is set."
+                ),
+                details: json!({
+                    "crate_name": crate_name,
+                    "version": archived_version,
+                }),
+            }
+            .into());
+        }
+
+        let options = CrateJobOptions {
+            version: Some(archived_version.clone()),
+            version_req: version.map(String::from),
+            force_update: true,
+            from_archive: true,
+            ..CrateJobOptions::default()
+        };
+        let enqueued = self
+            .job_processor
+            .enqueue_add_crate_job(crate_name, &options, idempotency_key)
+            .await?;
+        if !enqueued.created {
+            return Ok(replayed_job_response(&enqueued.job));
+        }
+        let job_id = enqueued.job.id;
+        start_add_job(
+            &self.storage,
+            &self.embedding_client,
+            job_id,
+            crate_name,
+            &options,
+        )
+        .await?;
+
+        Ok(json!({
+            "status": "accepted",
+            "job_id": job_id.to_string(),
+            "crate_name": crate_name,
+            "version": archived_version,
+            "message": format!("Crate '{crate_name}' {archived_version} reprocessing job queued from the raw page archive. Use check_rust_status with job_id to track progress.")
+        })
+        .to_string())
+    }
+}
+
+/// Non-empty `idempotency_key` argument of a job-creating tool
+pub(crate) fn idempotency_key_argument(arguments: &Value) -> Option<&str> {
+    arguments
+        .get("idempotency_key")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+}
+
+/// Error for adding a crate that is already stored without `force_update`
+///
+/// `normalized_from` is the requested spelling when it was not the stored one.
+fn already_exists(
+    crate_name: &str,
+    current_version: &str,
+    normalized_from: Option<&str>,
+) -> ToolError {
+    let normalized = normalized_from
+        .map(|alias| format!(" (normalized from '{alias}')"))
+        .unwrap_or_default();
+    ToolError::AlreadyExists {
+        message: format!(
+            "Crate '{crate_name}'{normalized} already exists in the system (version: {current_version}). Use force_update=true to update it, or remove_rust_crate first if you want to completely replace it."
+        ),
+        details: json!({
+            "crate_name": crate_name,
+            "normalized_from": normalized_from,
+            "current_version": current_version,
+        }),
+    }
+}
+
+/// Error for an idempotency key that already names another crate's or operation's job
+pub(crate) fn idempotency_conflict(conflict: &IdempotencyConflict) -> ToolError {
+    ToolError::Conflict {
+        message: conflict.to_string(),
+        details: json!({
+            "idempotency_key": conflict.key,
+            "job_id": conflict.job_id,
+            "crate_name": conflict.crate_name,
+            "operation": conflict.operation,
+        }),
+    }
+}
+
+/// Response to a retried call whose idempotency key `key` matched `job`
+///
+/// # Errors
+///
+/// Returns [`ToolError::Conflict`] when `job` was created for another crate
+/// or operation than `crate_name` and `operation`.
+pub(crate) fn replay_job(
+    job: &CrateJob,
+    key: &str,
+    crate_name: &str,
+    operation: &str,
+) -> Result<String> {
+    IdempotencyConflict::check(job, key, crate_name, operation)
+        .map_err(|conflict| idempotency_conflict(&conflict))?;
+    Ok(replayed_job_response(job))
+}
+
+/// Response to a retried call whose idempotency key matched an existing job
+pub(crate) fn replayed_job_response(job: &CrateJob) -> String {
+    json!({
+        "status": "accepted",
+        "job_id": job.id.to_string(),
+        "job_status": job.status,
+        "progress": job.progress,
+        "idempotent_replay": true,
+        "message": format!("A request with this idempotency_key already created {} job {} for crate '{}'. Use check_rust_status with job_id to track progress.", job.operation, job.id, job.crate_name)
+    })
+    .to_string()
+}
+
+/// Start a queued `add_crate` job via Redis or the local dispatcher
+async fn start_add_job(
+    storage: &CrateStorage,
+    embedding_client: &Arc<dyn EmbeddingClient + Send + Sync>,
+    job_id: Uuid,
+    crate_name: &str,
+    options: &CrateJobOptions,
+) -> Result<()> {
+    if crate::queue::use_redis_queue() {
+        let mut payload = serde_json::to_value(options)?;
+        payload["crate_name"] = json!(crate_name);
+        let msg = crate::queue::RedisJobMessage::new(job_id, "crate_add", 3, payload);
+        crate::queue::enqueue_job(&msg).await?;
+    } else if let Some(db_pool) = storage.as_pool() {
+        if let Err(e) = crate::job_queue::dispatch_queued_jobs(db_pool, embedding_client).await {
+            // The job stays queued for the dispatcher's next pass
+            tracing::warn!("Failed to dispatch crate job {}: {}", job_id, e);
+        }
+    }
+    Ok(())
+}
+
+impl AddRustCrateTool {
+    /// Canonical name of a requested crate, checked against crates.io
+    ///
+    /// crates.io ids ignore case and treat `-` and `_` alike. A crate already
+    /// stored under another spelling keeps it. Any other crate must exist on
+    /// crates.io, which also supplies its id; a crate crates.io does not know
+    /// is rejected with the closest existing names. When crates.io fails or
+    /// does not answer within the timeout, the lowercased request is queued
+    /// unverified.
+    async fn resolve_crate(&self, requested: &str) -> Result<ResolvedCrate> {
+        let name = requested.trim().to_lowercase();
+        let unchecked = |name: String, unverified| ResolvedCrate {
+            name,
+            metadata: None,
+            unverified,
+        };
+        if rust_crates::is_std_crate(&name) {
+            return Ok(unchecked(name, false));
+        }
+        if let Some(stored) = self.storage.store().find_crate_by_name(&name).await? {
+            return Ok(unchecked(stored.name, false));
+        }
+        match self
+            .crates_io
+            .clone()
+            .lookup_crate(&name, self.crates_io_timeout)
+            .await
+        {
+            CrateLookup::Found(metadata) => Ok(ResolvedCrate {
+                name: metadata.name.clone(),
+                metadata: Some(metadata),
+                unverified: false,
+            }),
+            CrateLookup::NotFound { suggestions } => {
+                let hint = if suggestions.is_empty() {
+                    String::new()
+                } else {
+                    format!(" Did you mean: {}?", suggestions.join(", "))
+                };
+                Err(ToolError::InvalidInput {
+                    message: format!(
+                        "Crate '{}' does not exist on crates.io.{hint}",
+                        requested.trim()
+                    ),
+                    details: json!({
+                        "crate_name": requested.trim(),
+                        "suggestions": suggestions,
+                    }),
+                }
+                .into())
+            }
+            CrateLookup::Unavailable(reason) => {
+                tracing::warn!(
+                    "Could not check crate '{}' on crates.io, queueing it unverified: {}",
+                    name,
+                    reason
+                );
+                Ok(unchecked(name, true))
+            }
+        }
+    }
+
+    /// Estimate an ingestion from the crate's docs.rs index pages without queueing it
+    ///
+    /// Only crates.io and docs.rs are read; no job or document is written.
+    async fn estimate_ingestion(
+        &self,
+        crate_name: &str,
+        version: Option<&str>,
+        force_update: bool,
+    ) -> Result<String> {
+        let mut loader = RustLoader::new();
+        let crate_info = loader.load_crate_metadata(crate_name).await?;
+        let version = version.unwrap_or(&crate_info.newest_version).to_string();
+        let estimate = loader.estimate_crawl(crate_name, &version).await?;
+
+        let embedding = embed::EmbeddingConfig::from_env_or_default();
+        let cost_usd = embed::prices()
+            .price(&embedding.model)
+            .map(|price| price.cost(estimate.estimated_tokens as u64, 0));
+
+        let existing = self.storage.store().find_crate_by_name(crate_name).await?;
+        let note = match &existing {
+            Some(_) if force_update => {
+                "The existing documents listed under 'existing' would be replaced."
+            }
+            Some(_) => {
+                "Crate already exists; add it with force_update=true to replace the documents listed under 'existing'."
+            }
+            None => "Crate is not ingested yet.",
+        };
+
+        Ok(json!({
+            "status": "dry_run",
+            "crate": crate_name,
+            "version": version,
+            "description": crate_info.description,
+            "estimate": {
+                "pages": estimate.estimated_pages,
+                "page_limit": estimate.page_limit,
+                "discovered_links": estimate.discovered_links,
+                "all_items_page": estimate.all_items_page,
+                "crawl_duration_secs": estimate.estimated_crawl_secs,
+                "tokens": estimate.estimated_tokens,
+                "embedding_model": embedding.model,
+                "embedding_cost_usd": cost_usd,
+            },
+            "existing": existing.map(|info| json!({
+                "version": info.version,
+                "documents": info.total_docs,
+                "tokens": info.total_tokens,
+                "last_updated": info.last_updated,
+            })),
+            "note": note,
+        })
+        .to_string())
+    }
+
+    /// Resolve a requested version or semver range against crates.io
+    ///
+    /// If crates.io cannot be reached the request is passed through unchanged
+    /// and the job resolves it against docs.rs, retrying like any other fetch.
+    async fn resolve_requested_version(crate_name: &str, requested: &str) -> Result<String> {
+        let mut loader = RustLoader::new();
+        let versions = match loader.fetch_versions(crate_name).await {
+            Ok(versions) => versions,
+            Err(e) => {
+                tracing::warn!(
+                    "Could not list versions of {} to resolve '{}': {}",
+                    crate_name,
+                    requested,
+                    e
+                );
+                return Ok(requested.to_string());
+            }
+        };
+        rust_crates::resolve_version(crate_name, requested, &versions)
+    }
+
+    /// Run crate ingestion for an existing job on a background task
+    ///
+    /// Waits for a concurrency permit unless the caller already reserved one.
+    pub(crate) fn spawn_ingestion(
+        job_processor: CrateJobProcessor,
+        embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
+        db_pool: DatabasePool,
+        job_id: Uuid,
+        crate_name_owned: String,
+        permit: Option<OwnedSemaphorePermit>,
+        options: CrateJobOptions,
+    ) {
+        tokio::spawn(async move {
+            // Global concurrency cap for crate ingestion jobs
+            let _permit = match permit {
+                Some(permit) => Some(permit),
+                None => get_crate_job_semaphore().acquire_owned().await.ok(),
+            };
+            let _running = RunningJobGuard::register(job_id);
+            tracing::info!("Background task started for crate: {}", crate_name_owned);
+            let mut rust_loader = RustLoader::new();
+
+            // First, update job status to running
+            if let Err(e) = job_processor
+                .update_job_status(job_id, JobStatus::Running, Some(0), None)
+                .await
+            {
+                tracing::error!("Failed to update job status to running: {}", e);
+            }
+
+            let result = job_processor
+                .run_with_heartbeat(
+                    job_id,
+                    Self::process_crate_ingestion(
+                        &job_processor,
+                        &mut rust_loader,
+                        &embedding_client,
+                        &db_pool,
+                        job_id,
+                        &crate_name_owned,
+                        options.version.as_deref(),
+                        options.version_req.as_deref(),
+                        options.features.as_ref(),
+                        options.include_dev_deps,
+                        options.force_update,
+                        options.no_cache,
+                        options.atomic_rollback,
+                        options.local.as_ref(),
+                        options.from_archive,
+                        options.alias.as_deref(),
+                        options.metadata.as_ref(),
+                    ),
+                )
+                .await;
+            // Even a failed run may have stored some pages
+            query_cache().invalidate_doc_type("rust");
+            if let Err(e) = result {
+                tracing::error!(
+                    "Background crate ingestion failed for {}: {}",
+                    crate_name_owned,
+                    e
+                );
+                // Reschedule with backoff, or dead-letter once attempts run out
+                if let Err(update_err) = job_processor.record_failure(job_id, &e).await {
+                    tracing::error!("Failed to record job failure: {}", update_err);
+                }
+            } else {
+                tracing::info!(
+                    "Background crate ingestion completed successfully for: {}",
+                    crate_name_owned
+                );
+            }
+        });
+    }
+
+    /// Public wrapper for worker usage to process crate ingestion
+    ///
+    /// # Errors
+    /// Returns an error if ingestion fails or database updates cannot be performed.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn process_in_worker(
+        &self,
+        job_processor: &CrateJobProcessor,
+        rust_loader: &mut RustLoader,
+        embedding_client: &Arc<dyn EmbeddingClient + Send + Sync>,
+        db_pool: &DatabasePool,
+        job_id: Uuid,
+        crate_name: &str,
+        version: Option<&str>,
+        version_req: Option<&str>,
+        features: Option<&Vec<String>>,
+        include_dev_deps: bool,
+        force_update: bool,
+        no_cache: bool,
+        atomic_rollback: bool,
+        local: Option<&LocalDocsSource>,
+        from_archive: bool,
+        alias: Option<&str>,
+        crate_metadata: Option<&CrateMetadata>,
+    ) -> Result<()> {
+        let result = Self::process_crate_ingestion(
+            job_processor,
+            rust_loader,
+            embedding_client,
+            db_pool,
+            job_id,
+            crate_name,
+            version,
+            version_req,
+            features,
+            include_dev_deps,
+            force_update,
+            no_cache,
+            atomic_rollback,
+            local,
+            from_archive,
+            alias,
+            crate_metadata,
+        )
+        .await;
+        query_cache().invalidate_doc_type("rust");
+        result
+    }
+
+    /// Process crate ingestion in background with enhanced options
+    #[allow(clippy::too_many_arguments)]
+    async fn process_crate_ingestion(
+        job_processor: &CrateJobProcessor,
+        rust_loader: &mut RustLoader,
+        embedding_client: &Arc<dyn EmbeddingClient + Send + Sync>,
+        db_pool: &DatabasePool,
+        job_id: Uuid,
+        crate_name: &str,
+        version: Option<&str>,
+        version_req: Option<&str>,
+        features: Option<&Vec<String>>,
+        _include_dev_deps: bool,
+        force_update: bool,
+        no_cache: bool,
+        atomic_rollback: bool,
+        local: Option<&LocalDocsSource>,
+        from_archive: bool,
+        alias: Option<&str>,
+        crate_metadata: Option<&CrateMetadata>,
+    ) -> Result<()> {
+        tracing::info!(
+            "process_crate_ingestion started for crate: {} (job_id: {})",
+            crate_name,
+            job_id
+        );
+
+        // Crawled pages go to the raw archive when one is configured
+        let archive = RawArchive::from_env().map(Arc::new);
+        if from_archive && archive.is_none() {
+            return Err(anyhow!(
+                "Cannot reprocess crate '{}': no raw page archive is configured (RAW_ARCHIVE_URL)",
+                crate_name
+            ));
         }
 
+        let vector_extension_available = vector_writes_available(db_pool, crate_name).await?;
+
+        // Update job status to running
+        job_processor
+            .update_job_status(job_id, JobStatus::Running, Some(0), None)
+            .await?;
+
+        // If force_update is true and atomic_rollback is enabled, first store existing state
+        let rollback_data = if force_update && atomic_rollback {
+            tracing::info!(
+                "Storing rollback data for atomic operation on crate: {}",
+                crate_name
+            );
+            Self::backup_existing_crate_data(db_pool, crate_name).await?
+        } else {
+            None
+        };
+
+        // Load crate documentation
+        tracing::info!(
+            "Starting ingestion for crate: {} with enhanced options",
+            crate_name
+        );
+        // A local crate is described by its manifest instead of crates.io,
+        // and metadata fetched when the job was queued is not fetched again
+        let local_crate = local
+            .map(|source| LocalCrate::from_manifest(&source.path))
+            .transpose()?;
+        // Reprocessing keeps the metadata stored with the crate
+        let stored_metadata = if from_archive && crate_metadata.is_none() {
+            Self::stored_crate_metadata(db_pool, crate_name).await?
+        } else {
+            None
+        };
+        let crate_info = match (&local_crate, crate_metadata.or(stored_metadata.as_ref())) {
+            (Some(local_crate), _) => local_crate.metadata.clone(),
+            (None, Some(metadata)) => metadata.clone(),
+            (None, None) => rust_loader
+                .load_crate_metadata(crate_name)
+                .await
+                .map_err(|e| {
+                    // Note: rollback will be handled in error processing below if needed
+                    if rollback_data.is_some() {
+                        tracing::warn!(
+                            "Load failed, will attempt rollback for crate: {}",
+                            crate_name
+                        );
+                    }
+                    anyhow!("Failed to load crate documentation: {}", e)
+                })?,
+        };
+        // Standard library docs are crawled per channel but recorded under
+        // the Rust release the channel was built from
+        let docs_version = version.unwrap_or(&crate_info.newest_version).to_string();
+        let target_version = if rust_crates::is_std_crate(crate_name) {
+            rust_loader
+                .fetch_rust_release(crate_name, &docs_version)
+                .await
+                .unwrap_or_else(|| docs_version.clone())
+        } else {
+            docs_version.clone()
+        };
+        // Recorded for remove_rust_crate's dependency check; absent means unknown.
+        // Reprocessing keeps what the crawl recorded.
+        let dependencies = if local.is_some() || from_archive {
+            None
+        } else {
+            match rust_loader
+                .fetch_dependencies(crate_name, &target_version)
+                .await
+            {
+                Ok(dependencies) => Some(dependencies),
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to fetch dependencies of {} {}: {}",
+                        crate_name,
+                        target_version,
+                        e
+                    );
+                    None
+                }
+            }
+        };
+        // The features docs.rs enabled decide which feature-gated items the docs show
+        let built_features =
+            if local.is_some() || from_archive || rust_crates::is_std_crate(crate_name) {
+                None
+            } else {
+                match rust_loader
+                    .fetch_built_features(crate_name, &target_version)
+                    .await
+                {
+                    Ok(built) => Some(built),
+                    Err(e) => {
+                        tracing::warn!(
+                            "Failed to read the docs.rs build features of {} {}: {}",
+                            crate_name,
+                            target_version,
+                            e
+                        );
+                        None
+                    }
+                }
+            };
+        let warnings = feature_warnings(features.map(Vec::as_slice), built_features.as_ref());
+
         // Ensure document source exists
         tracing::info!("Ensuring document source exists for crate: {}", crate_name);
         let mut tx = db_pool.pool().begin().await?;
@@ -424,148 +1178,276 @@ impl AddRustCrateTool {
                 }
             }
         }
+        // Refresh the crates.io metadata of a source that already existed
+        let mut source_config = json!({"crate_info": crate_info});
+        if let Some(dependencies) = &dependencies {
+            source_config["dependencies"] = json!(dependencies);
+        }
+        if let Some(built) = &built_features {
+            source_config["built_features"] = json!(built);
+        }
+        if rust_crates::is_std_crate(crate_name) {
+            source_config["rust_channel"] = json!(docs_version);
+        }
+        // Local crates have no crates.io release for auto-update to compare against
+        if let Some(source) = local {
+            source_config["origin"] = json!(LOCAL_ORIGIN);
+            source_config["local_path"] = json!(source.path);
+            source_config["auto_update"] = json!(false);
+        }
+        sqlx::query(
+            "UPDATE document_sources SET config = config || $2 WHERE doc_type = 'rust' AND source_name = $1",
+        )
+        .bind(crate_name)
+        .bind(source_config)
+        .execute(&mut *tx)
+        .await?;
         tx.commit().await?;
 
-        // No separate crate record - we use document metadata instead
-        tracing::info!(
-            "Processing {} documentation pages for crate {} with enhanced metadata",
-            doc_pages.len(),
-            crate_name
-        );
-
         // Update progress
         job_processor
-            .update_job_status(job_id, JobStatus::Running, Some(50), None)
+            .update_job_status(job_id, JobStatus::Running, Some(25), None)
             .await?;
 
+        // Outlives the crawl so a failed attempt still reports what it spent
+        let cached_client =
+            CachedEmbeddingClient::new(embedding_client.clone(), db_pool.pool().clone());
+
         // Wrap document processing in error handling for rollback
         let processing_result = async {
-            let mut total_docs = 0;
-            let mut total_tokens = 0i64;
-            let batch_size = 10;
-
-            // Process documents in batches
-            for (batch_idx, chunk) in doc_pages.chunks(batch_size).enumerate() {
-            let mut tx = db_pool.pool().begin().await?;
-
-            for doc_page in chunk {
-                // Create document record with enhanced metadata
-                let document_id = uuid::Uuid::new_v4();
+            // Resume from the checkpoint of an earlier attempt of this job, if any
+            let resume = CrateJobQueries::load_crawl_state(db_pool.pool(), job_id)
+                .await?
+                .and_then(|state| serde_json::from_value::<CrawlState>(state).ok());
+            let stored_urls: HashSet<String> = if resume.is_some() {
+                sqlx::query_scalar::<_, String>(
+                    "SELECT metadata->>'source_url' FROM documents WHERE doc_type = 'rust' AND metadata->>'ingestion_job_id' = $1 AND metadata ? 'source_url'",
+                )
+                .bind(job_id.to_string())
+                .fetch_all(db_pool.pool())
+                .await?
+                .into_iter()
+                .collect()
+            } else {
+                HashSet::new()
+            };
+            if let Some(state) = &resume {
+                tracing::info!(
+                    "Resuming crawl for crate {} from checkpoint: {} pages processed, {} already stored",
+                    crate_name,
+                    state.processed,
+                    stored_urls.len()
+                );
+            }
 
-                // Start with intelligent content-based metadata
-                let mut metadata = db::create_enhanced_metadata(
-                    "rust",
-                    &crate_info.name,
-                    &doc_page.content,
-                    &doc_page.module_path
-                );
-
-                // Merge in crate-specific metadata
-                if let Some(metadata_obj) = metadata.as_object_mut() {
-                    metadata_obj.insert("crate_name".to_string(), json!(crate_info.name));
-                    metadata_obj.insert("crate_version".to_string(), json!(crate_info.newest_version));
-                    metadata_obj.insert("item_type".to_string(), json!(doc_page.item_type));
-                    metadata_obj.insert("module_path".to_string(), json!(doc_page.module_path));
-                    metadata_obj.insert("extracted_at".to_string(), json!(doc_page.extracted_at));
-                    metadata_obj.insert("source_url".to_string(), json!(doc_page.url));
-                    metadata_obj.insert("force_updated".to_string(), json!(force_update));
-                    metadata_obj.insert("atomic_rollback_enabled".to_string(), json!(atomic_rollback));
-                    metadata_obj.insert("ingestion_job_id".to_string(), json!(job_id.to_string()));
-
-                    // Add feature information if specified
-                    if let Some(feature_list) = features {
-                        metadata_obj.insert("selected_features".to_string(), json!(&feature_list));
+            tracing::info!(
+                "Crawling documentation for crate {} with enhanced metadata",
+                crate_name
+            );
+            let max_pages = RustLoader::max_pages();
+            let mut sink = IngestionSink {
+                job_processor,
+                embedding_client: &cached_client,
+                db_pool,
+                crate_info: &crate_info,
+                crate_version: &target_version,
+                dependencies: dependencies.as_deref(),
+                version_req,
+                job_id,
+                features,
+                built_features: built_features.as_ref(),
+                force_update,
+                atomic_rollback,
+                vector_extension_available,
+                chunk_config: embed::ChunkConfig::from_env(),
+                total_docs: 0,
+                total_tokens: 0,
+                unchanged_docs: 0,
+                unchanged_pages: 0,
+                embedding_stats: embed::EmbeddingStats::default(),
+                deduper: db::ContentDeduper::new(),
+                origin: local.map(|_| LOCAL_ORIGIN),
+                crate_alias: alias.filter(|alias| *alias != crate_info.name),
+            };
+            // Crawl progress drives the job progress while the crawl runs
+            let (progress_tx, progress_rx) = watch::channel(CrawlProgress::default());
+            let reporter = report_crawl_progress(job_processor, job_id, max_pages, progress_rx);
+            let crawl = async {
+                let mut report = move |snapshot| {
+                    let _ = progress_tx.send(snapshot);
+                };
+                if let (Some(source), Some(local_crate)) = (local, &local_crate) {
+                    // A directory walk is cheap to repeat, so local jobs never resume
+                    let doc_dir = if source.build {
+                        local_crate.build_docs().await?
+                    } else {
+                        local_crate.find_doc_dir(source.doc_dir.as_deref())?
+                    };
+                    local_crate
+                        .crawl(
+                            &doc_dir,
+                            max_pages,
+                            crawl_checkpoint_pages(),
+                            &mut sink,
+                            &mut report,
+                        )
+                        .await?;
+                } else if let Some(archive) = archive.as_ref().filter(|_| from_archive) {
+                    // Reading the archive is cheap to repeat, so reprocessing never resumes
+                    archive
+                        .crawl(
+                            crate_name,
+                            &docs_version,
+                            max_pages,
+                            crawl_checkpoint_pages(),
+                            &mut sink,
+                            &mut report,
+                        )
+                        .await?;
+                } else {
+                    if let Some(archive) = &archive {
+                        rust_loader.set_raw_archive(archive.clone());
                     }
-                }
-
-                // Calculate token count (approximation)
-                let token_count = doc_page.content.len() / 4; // Rough approximation
-                #[allow(clippy::cast_possible_wrap)]
-                let token_count_i32 = token_count as i32;
-
-                // Insert document
-                sqlx::query(
-                    r"
-                    INSERT INTO documents (id, doc_type, source_name, doc_path, content, metadata, token_count, created_at, updated_at)
-                    VALUES ($1, 'rust', $2, $3, $4, $5, $6, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
-                    "
-                )
-                .bind(document_id)
-                .bind(&crate_info.name)
-                .bind(&doc_page.url)
-                .bind(&doc_page.content)
-                .bind(&metadata)
-                .bind(token_count_i32)
-                .execute(&mut *tx)
-                .await?;
-
-                // Generate and store embedding (skip if vector extension not available)
-                if !doc_page.content.is_empty() && vector_extension_available {
-                    match embedding_client.embed(&doc_page.content).await {
-                        Ok(embedding) => {
-                            let vector = pgvector::Vector::from(embedding);
-                            if let Err(e) = sqlx::query("UPDATE documents SET embedding = $1 WHERE id = $2")
-                                .bind(&vector)
-                                .bind(document_id)
-                                .execute(&mut *tx)
-                                .await {
-                                tracing::warn!("Failed to store embedding for document {}: {}", document_id, e);
-                            } else {
-                                tracing::debug!("Stored embedding for document {}", document_id);
-                            }
-                        }
-                        Err(e) => {
-                            tracing::warn!(
-                                "Failed to generate embedding for document {}: {}",
-                                document_id,
-                                e
-                            );
+                    // Unchanged pages are kept without refetching unless the caller wants everything
+                    rust_loader.set_fetch_cache(
+                        Arc::new(DbFetchCache {
+                            pool: db_pool.pool().clone(),
+                            source_name: crate_name.to_string(),
+                            crate_version: target_version.clone(),
+                        }),
+                        !(force_update || no_cache),
+                    );
+                    rust_loader
+                        .crawl_docs_rs(
+                            crate_name,
+                            &docs_version,
+                            max_pages,
+                            resume,
+                            &stored_urls,
+                            crawl_checkpoint_pages(),
+                            &mut sink,
+                            &mut report,
+                        )
+                        .await?;
+                    // Stored through the sink so force updates replace it like any other page
+                    if let Some(readme) = rust_loader.fetch_readme(crate_name, &target_version).await
+                    {
+                        sink.store_pages(&[readme]).await?;
+                    }
+                    if let Some(archive) = &archive {
+                        if let Err(e) = archive.prune(crate_name).await {
+                            tracing::warn!("Failed to prune the raw archive of {}: {}", crate_name, e);
                         }
                     }
                 }
-
-                total_docs += 1;
-                #[allow(clippy::cast_possible_wrap)]
-                let token_count_i64 = token_count as i64;
-                total_tokens += token_count_i64;
+                Ok::<_, anyhow::Error>(())
+            };
+            let (crawled, crawl_progress) = tokio::join!(crawl, reporter);
+            crawled?;
+            let (total_docs, total_tokens, embedding_stats) =
+                (sink.total_docs, sink.total_tokens, sink.embedding_stats);
+            let duplicates_skipped = sink.deduper.skipped();
+            if sink.unchanged_pages > 0 {
+                tracing::info!(
+                    "Kept {} pages docs.rs reported unchanged for crate: {}",
+                    sink.unchanged_pages,
+                    crate_name
+                );
+            }
+            if sink.unchanged_docs > 0 {
+                tracing::info!(
+                    "Skipped {} unchanged documents for crate: {}",
+                    sink.unchanged_docs,
+                    crate_name
+                );
             }
 
-            // Commit batch
-            tx.commit().await?;
-
-            // Update progress
-            let total_batches = doc_pages.len().div_ceil(batch_size);
-            let progress = 50 + ((batch_idx + 1) * 40 / total_batches);
-            #[allow(clippy::cast_possible_wrap)]
-            let progress_i32 = progress as i32;
-            job_processor
-                .update_job_status(job_id, JobStatus::Running, Some(progress_i32), None)
+            // Pages that disappeared from the crawl were not touched by this job.
+            // A pinned version only replaces its own documents, and no update
+            // removes other versions that were pinned when they were added.
+            if force_update {
+                let query = format!(
+                    r"
+                    DELETE FROM documents
+                    WHERE doc_type = 'rust' AND {}
+                    AND metadata->>'ingestion_job_id' IS DISTINCT FROM $2
+                    AND ($3::text IS NULL OR metadata->>'crate_version' = $3)
+                    AND NOT (metadata ? 'version_req' AND metadata->>'crate_version' IS DISTINCT FROM $4)
+                    ",
+                    db::crate_name_match_sql("$1")
+                );
+                let removed = sqlx::query(&query)
+                .bind(crate_name)
+                .bind(job_id.to_string())
+                .bind(version_req.map(|_| target_version.as_str()))
+                .bind(&target_version)
+                .execute(db_pool.pool())
                 .await?;
-
-            tracing::info!(
-                "Processed batch {} of {} for crate {}",
-                batch_idx + 1,
-                total_batches,
-                crate_name
-            );
+                tracing::info!(
+                    "Removed {} stale documents for force update of crate: {}",
+                    removed.rows_affected(),
+                    crate_name
+                );
             }
 
-            Ok((total_docs, total_tokens))
+            CrateJobQueries::clear_crawl_state(db_pool.pool(), job_id).await?;
+
+            Ok((total_docs, total_tokens, embedding_stats, duplicates_skipped, crawl_progress))
         }.await;
 
+        let costs = cached_client.costs();
+        if !costs.is_empty() {
+            match job_processor.add_job_costs(job_id, &costs).await {
+                Ok(total) => tracing::info!(
+                    "Job {} for crate {} spent ${:.6} on embeddings (${:.6} across attempts)",
+                    job_id,
+                    crate_name,
+                    costs.total_cost_usd,
+                    total.total_cost_usd
+                ),
+                Err(e) => tracing::warn!("Failed to record costs of job {}: {}", job_id, e),
+            }
+        }
+
         // Handle processing result with potential rollback
         match processing_result {
-            Ok((total_docs, total_tokens)) => {
+            Ok((total_docs, total_tokens, embedding_stats, duplicates_skipped, crawl_progress)) => {
+                // Shown by check_rust_status next to the final status
+                let mut details = json!({
+                    "embeddings": embedding_stats,
+                    "duplicates_skipped": duplicates_skipped,
+                    "crawl": {
+                        "pages_fetched": crawl_progress.pages_fetched,
+                        "pages_skipped": crawl_progress.pages_skipped,
+                        "duration_secs": crawl_progress.elapsed.as_secs(),
+                    },
+                });
+                if let Some(built) = &built_features {
+                    details["built_features"] = json!(built);
+                }
+                if !warnings.is_empty() {
+                    for warning in &warnings {
+                        tracing::warn!("Crate {}: {}", crate_name, warning);
+                    }
+                    details["warnings"] = json!(warnings);
+                }
+                CrateJobQueries::merge_job_details(db_pool.pool(), job_id, &details).await?;
+
                 // Mark job as completed
                 job_processor
                     .update_job_status(job_id, JobStatus::Completed, Some(100), None)
                     .await?;
 
                 tracing::info!(
-                    "Successfully completed enhanced ingestion for crate {}: {} documents, {} tokens",
+                    "Successfully completed enhanced ingestion for crate {}: {} documents, {} tokens, {} pages fetched and {} skipped in {:.1}s, {} duplicate pages skipped, embeddings: {}",
                     crate_name,
                     total_docs,
-                    total_tokens
+                    total_tokens,
+                    crawl_progress.pages_fetched,
+                    crawl_progress.pages_skipped,
+                    crawl_progress.elapsed.as_secs_f64(),
+                    duplicates_skipped,
+                    embedding_stats
                 );
             }
             Err(processing_error) => {
@@ -573,6 +1455,12 @@ impl AddRustCrateTool {
                 if atomic_rollback {
                     match Self::rollback_failed_ingestion(db_pool, crate_name, job_id).await {
                         Ok(()) => {
+                            // The checkpointed pages were rolled back, so a retry must start over
+                            if let Err(e) =
+                                CrateJobQueries::clear_crawl_state(db_pool.pool(), job_id).await
+                            {
+                                tracing::warn!("Failed to clear crawl checkpoint: {}", e);
+                            }
                             tracing::warn!("Successfully rolled back failed ingestion for crate '{}' due to processing error: {}", crate_name, processing_error);
                             return Err(anyhow!(
                                 "Processing failed but rollback succeeded: {}",
@@ -597,17 +1485,34 @@ impl AddRustCrateTool {
         Ok(())
     }
 
+    /// crates.io metadata recorded when the crate was last ingested
+    async fn stored_crate_metadata(
+        db_pool: &DatabasePool,
+        crate_name: &str,
+    ) -> Result<Option<CrateMetadata>> {
+        let stored: Option<Value> = sqlx::query_scalar(
+            "SELECT config->'crate_info' FROM document_sources WHERE doc_type = 'rust' AND source_name = $1",
+        )
+        .bind(crate_name)
+        .fetch_optional(db_pool.pool())
+        .await?
+        .flatten();
+        Ok(stored.and_then(|info| serde_json::from_value(info).ok()))
+    }
+
     /// Backup existing crate data for rollback capability (simplified to just count)
     async fn backup_existing_crate_data(
         db_pool: &DatabasePool,
         crate_name: &str,
     ) -> Result<Option<i64>> {
-        let count = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM documents WHERE doc_type = 'rust' AND (metadata->>'crate_name' = $1 OR source_name = $1)"
-        )
-        .bind(crate_name)
-        .fetch_one(db_pool.pool())
-        .await?;
+        let query = format!(
+            "SELECT COUNT(*) FROM documents WHERE doc_type = 'rust' AND {}",
+            db::crate_name_match_sql("$1")
+        );
+        let count = sqlx::query_scalar::<_, i64>(&query)
+            .bind(crate_name)
+            .fetch_one(db_pool.pool())
+            .await?;
 
         if count == 0 {
             Ok(None)
@@ -652,6 +1557,47 @@ impl AddRustCrateTool {
     }
 }
 
+/// Whether an ingestion of `source` can store embeddings
+///
+/// False when the database lacks the vector extension or embedding writes
+/// are disabled by a dimension mismatch; documents are then stored without
+/// embeddings.
+///
+/// # Errors
+///
+/// Returns an error if the database cannot be reached.
+pub(crate) async fn vector_writes_available(db_pool: &DatabasePool, source: &str) -> Result<bool> {
+    // Check if vector extension is available by trying a simple vector operation
+    match sqlx::query("SELECT '[1,2,3]'::vector(3)")
+        .execute(db_pool.pool())
+        .await
+    {
+        Ok(_) => tracing::debug!("Vector extension is available"),
+        Err(e) => {
+            if e.to_string().contains("vector")
+                || e.to_string().contains("extension")
+                || e.to_string().contains("type")
+            {
+                tracing::warn!(
+                    "Vector extension not available in database, skipping embeddings: {}",
+                    e
+                );
+                return Ok(false);
+            }
+            return Err(anyhow!("Database connection test failed: {}", e));
+        }
+    }
+
+    if !embed::vector_writes_enabled() {
+        tracing::warn!(
+            "Embedding writes disabled by dimension mismatch, ingesting {} without embeddings",
+            source
+        );
+        return Ok(false);
+    }
+    Ok(true)
+}
+
 // Global semaphore for crate ingestion concurrency
 fn crate_job_max_concurrency() -> usize {
     std::env::var("CRATE_JOB_MAX_CONCURRENCY")
@@ -661,345 +1607,1831 @@ fn crate_job_max_concurrency() -> usize {
         .unwrap_or(2)
 }
 
-static CRATE_JOB_SEMAPHORE: OnceLock<Arc<Semaphore>> = OnceLock::new();
+static CRATE_JOB_SEMAPHORE: OnceLock<Arc<Semaphore>> = OnceLock::new();
+
+pub(crate) fn get_crate_job_semaphore() -> Arc<Semaphore> {
+    CRATE_JOB_SEMAPHORE
+        .get_or_init(|| Arc::new(Semaphore::new(crate_job_max_concurrency())))
+        .clone()
+}
+
+/// Number of crate ingestion jobs currently holding a concurrency permit
+pub fn active_crate_jobs() -> usize {
+    crate_job_max_concurrency().saturating_sub(get_crate_job_semaphore().available_permits())
+}
+
+/// IDs of crate jobs running on this process
+static RUNNING_CRATE_JOBS: LazyLock<Mutex<HashSet<Uuid>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// IDs of crate ingestion jobs currently running on this process
+pub fn running_crate_job_ids() -> Vec<Uuid> {
+    RUNNING_CRATE_JOBS
+        .lock()
+        .map(|jobs| jobs.iter().copied().collect())
+        .unwrap_or_default()
+}
+
+/// Tracks a job in [`RUNNING_CRATE_JOBS`] for as long as it runs
+pub(crate) struct RunningJobGuard(Uuid);
+
+impl RunningJobGuard {
+    pub(crate) fn register(job_id: Uuid) -> Self {
+        if let Ok(mut jobs) = RUNNING_CRATE_JOBS.lock() {
+            jobs.insert(job_id);
+        }
+        Self(job_id)
+    }
+}
+
+impl Drop for RunningJobGuard {
+    fn drop(&mut self) {
+        if let Ok(mut jobs) = RUNNING_CRATE_JOBS.lock() {
+            jobs.remove(&self.0);
+        }
+    }
+}
+
+/// Resume crate jobs that a previous shutdown requeued
+///
+/// Resumed jobs keep their stored request options but always force an update,
+/// since part of the crate is already stored; the checkpointed crawl state
+/// lets them skip finished pages.
+///
+/// # Errors
+///
+/// Returns an error if the interrupted jobs cannot be claimed.
+pub async fn resume_interrupted_crate_jobs(
+    db_pool: &DatabasePool,
+    embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
+) -> Result<usize> {
+    let jobs = CrateJobQueries::claim_interrupted_jobs(db_pool.pool()).await?;
+    for job in &jobs {
+        tracing::info!(
+            "Resuming interrupted ingestion job {} for crate {}",
+            job.id,
+            job.crate_name
+        );
+        AddRustCrateTool::spawn_ingestion(
+            CrateJobProcessor::new(db_pool.clone()),
+            embedding_client.clone(),
+            db_pool.clone(),
+            job.id,
+            job.crate_name.clone(),
+            None,
+            CrateJobOptions {
+                force_update: true,
+                ..CrateJobOptions::from_job(job)
+            },
+        );
+    }
+    Ok(jobs.len())
+}
+
+/// Minimum time between two job progress writes driven by a crawl
+const CRAWL_PROGRESS_WRITE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Write the crawl progress arriving on `updates` to the job, mapped onto the
+/// 25-95% band of an ingestion. Writes only move forward and happen at most
+/// once per [`CRAWL_PROGRESS_WRITE_INTERVAL`]; a failed write is logged and
+/// never stops the crawl. Returns the last snapshot once the crawl is done.
+async fn report_crawl_progress(
+    job_processor: &CrateJobProcessor,
+    job_id: Uuid,
+    max_pages: usize,
+    mut updates: watch::Receiver<CrawlProgress>,
+) -> CrawlProgress {
+    let mut written = 25;
+    let mut last_write: Option<Instant> = None;
+    while updates.changed().await.is_ok() {
+        let snapshot = *updates.borrow_and_update();
+        let progress = crawl_job_progress(&snapshot, max_pages);
+        if progress <= written
+            || last_write.is_some_and(|at| at.elapsed() < CRAWL_PROGRESS_WRITE_INTERVAL)
+        {
+            continue;
+        }
+        if let Err(e) = job_processor
+            .update_job_status(job_id, JobStatus::Running, Some(progress), None)
+            .await
+        {
+            tracing::warn!("Failed to record crawl progress of job {}: {}", job_id, e);
+        }
+        written = progress;
+        last_write = Some(Instant::now());
+    }
+    let last = *updates.borrow();
+    last
+}
+
+/// Job progress of an ingestion during its crawl: 25% when the crawl starts,
+/// 95% once every page is handled
+fn crawl_job_progress(snapshot: &CrawlProgress, max_pages: usize) -> i32 {
+    #[allow(clippy::cast_possible_truncation)]
+    let progress = 25 + (snapshot.fraction(max_pages) * 70.0).floor() as i32;
+    progress
+}
+
+/// Number of crawled pages between ingestion checkpoints (`CRATE_CRAWL_CHECKPOINT_PAGES`, default 50)
+fn crawl_checkpoint_pages() -> usize {
+    std::env::var("CRATE_CRAWL_CHECKPOINT_PAGES")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(50)
+}
+
+/// Metadata fields recording which model produced a document's embedding
+fn embedding_metadata(client: &(dyn EmbeddingClient + Send + Sync)) -> Value {
+    let mut metadata = json!({});
+    client.embedding_config().annotate_metadata(&mut metadata);
+    metadata
+}
+
+/// Warnings for an ingestion whose docs may not cover what was requested
+///
+/// docs.rs documents one build of a release, so items gated behind a
+/// requested feature that build did not enable are missing from the crawl.
+/// A failed latest build is reported whether or not features were requested.
+#[must_use]
+pub fn feature_warnings(
+    requested: Option<&[String]>,
+    built: Option<&BuiltFeatures>,
+) -> Vec<String> {
+    let requested = requested.unwrap_or_default();
+    let mut warnings = Vec::new();
+    if built.and_then(|built| built.build_succeeded) == Some(false) {
+        warnings.push(
+            "The latest docs.rs build of this release failed; documentation may be incomplete"
+                .to_string(),
+        );
+    }
+    match built {
+        Some(built) => {
+            let missing = built.missing(requested);
+            if !missing.is_empty() {
+                let enabled = if built.features.is_empty() {
+                    "no features".to_string()
+                } else {
+                    built.features.join(", ")
+                };
+                warnings.push(format!(
+                    "docs.rs built the documentation without requested features: {} (built with {}); items gated behind them are missing",
+                    missing.join(", "),
+                    enabled
+                ));
+            }
+        }
+        None if !requested.is_empty() => warnings.push(format!(
+            "Could not determine the features docs.rs built with; requested features may be missing: {}",
+            requested.join(", ")
+        )),
+        None => {}
+    }
+    warnings
+}
+
+/// Stores crawled pages for an ingestion job and checkpoints the crawl after each batch
+struct IngestionSink<'a> {
+    job_processor: &'a CrateJobProcessor,
+    embedding_client: &'a CachedEmbeddingClient,
+    db_pool: &'a DatabasePool,
+    crate_info: &'a CrateMetadata,
+    crate_version: &'a str,
+    dependencies: Option<&'a [CrateDependency]>,
+    version_req: Option<&'a str>,
+    job_id: Uuid,
+    features: Option<&'a Vec<String>>,
+    /// Features the docs.rs build enabled, when known
+    built_features: Option<&'a BuiltFeatures>,
+    force_update: bool,
+    atomic_rollback: bool,
+    vector_extension_available: bool,
+    chunk_config: embed::ChunkConfig,
+    total_docs: usize,
+    total_tokens: i64,
+    unchanged_docs: usize,
+    unchanged_pages: usize,
+    embedding_stats: embed::EmbeddingStats,
+    /// Pages already seen in this run, by normalized content hash
+    deduper: db::ContentDeduper,
+    /// `origin` metadata of every page, for crates not from docs.rs
+    origin: Option<&'static str>,
+    /// Spelling the crate was requested under, when not its canonical name
+    crate_alias: Option<&'a str>,
+}
+
+impl IngestionSink<'_> {
+    /// Upsert pages, split into chunks, in transactions of ten pages
+    ///
+    /// Chunks whose content hash matches the stored row are not rewritten;
+    /// only their job metadata is refreshed so force-update cleanup keeps them.
+    /// A page repeating the content of an earlier page in this run (docs.rs
+    /// serves re-exports under several paths) is not stored; its URL is added
+    /// to the earlier page's `alternate_urls` instead.
+    async fn store_pages(&mut self, doc_pages: &[DocPage]) -> Result<()> {
+        let batch_size = 10;
+
+        for batch in doc_pages.chunks(batch_size) {
+            // Canonical page URL and the URL of its duplicate
+            let mut alternates: Vec<(String, &str)> = Vec::new();
+            let mut unique: Vec<&DocPage> = Vec::with_capacity(batch.len());
+            for doc_page in batch {
+                match self.deduper.check(&doc_page.url, &doc_page.content) {
+                    Some(canonical) => alternates.push((canonical, &doc_page.url)),
+                    None => unique.push(doc_page),
+                }
+            }
+
+            // Long pages are stored as one row per chunk; empty pages keep a single row
+            let pages: Vec<(&DocPage, Vec<String>)> = unique
+                .into_iter()
+                .map(|doc_page| {
+                    let mut chunks = embed::chunk_text(&doc_page.content, &self.chunk_config);
+                    if chunks.is_empty() {
+                        chunks.push(doc_page.content.clone());
+                    }
+                    (doc_page, chunks)
+                })
+                .collect();
+
+            // One hash lookup for every chunk in the batch
+            let chunk_paths: Vec<String> = pages
+                .iter()
+                .flat_map(|(doc_page, chunks)| {
+                    (0..chunks.len()).map(|i| db::chunks::chunk_doc_path(&doc_page.url, i))
+                })
+                .collect();
+            let stored_hashes = DocumentQueries::stored_content_hashes(
+                self.db_pool.pool(),
+                "rust",
+                &self.crate_info.name,
+                &chunk_paths,
+            )
+            .await?;
+
+            let mut embeddings = self.embed_changed_chunks(&pages, &stored_hashes).await;
+            let embedding_config = self.embedding_client.embedding_config();
+
+            let mut tx = self.db_pool.pool().begin().await?;
+            let mut unchanged_paths: Vec<String> = Vec::new();
+
+            for (doc_page, chunks) in pages {
+                // Start with intelligent content-based metadata
+                let mut metadata = db::create_enhanced_metadata(
+                    "rust",
+                    &self.crate_info.name,
+                    &doc_page.content,
+                    &doc_page.module_path,
+                );
+
+                // Merge in crate-specific metadata
+                if let Some(metadata_obj) = metadata.as_object_mut() {
+                    metadata_obj.insert("crate_name".to_string(), json!(self.crate_info.name));
+                    if let Some(alias) = self.crate_alias {
+                        metadata_obj.insert("crate_alias".to_string(), json!(alias));
+                    }
+                    metadata_obj.insert("crate_version".to_string(), json!(self.crate_version));
+                    if let Some(version_req) = self.version_req {
+                        metadata_obj.insert("version_req".to_string(), json!(version_req));
+                    }
+                    metadata_obj.insert("item_type".to_string(), json!(doc_page.item_type));
+                    // Crawled doc blocks are converted to Markdown, whatever code they quote
+                    metadata_obj.insert("format".to_string(), json!("markdown"));
+                    metadata_obj.insert("module_path".to_string(), json!(doc_page.module_path));
+                    metadata_obj
+                        .insert("repository".to_string(), json!(self.crate_info.repository));
+                    metadata_obj.insert("keywords".to_string(), json!(self.crate_info.keywords));
+                    metadata_obj
+                        .insert("categories".to_string(), json!(self.crate_info.categories));
+                    if let Some(dependencies) = self.dependencies {
+                        metadata_obj.insert("dependencies".to_string(), json!(dependencies));
+                    }
+                    metadata_obj.insert("extracted_at".to_string(), json!(doc_page.extracted_at));
+                    metadata_obj.insert("source_url".to_string(), json!(doc_page.url));
+                    if let Some(origin) = self.origin {
+                        metadata_obj.insert("origin".to_string(), json!(origin));
+                    }
+                    // Examples link back to the page they were extracted from
+                    if let Some(parent_url) = &doc_page.parent_url {
+                        metadata_obj.insert("example_of".to_string(), json!(parent_url));
+                    }
+                    metadata_obj.insert("force_updated".to_string(), json!(self.force_update));
+                    metadata_obj.insert(
+                        "atomic_rollback_enabled".to_string(),
+                        json!(self.atomic_rollback),
+                    );
+                    metadata_obj.insert(
+                        "ingestion_job_id".to_string(),
+                        json!(self.job_id.to_string()),
+                    );
+
+                    // Add feature information if specified
+                    if let Some(feature_list) = self.features {
+                        metadata_obj.insert("selected_features".to_string(), json!(&feature_list));
+                    }
+                    if let Some(built) = self.built_features {
+                        metadata_obj.insert("built_features".to_string(), json!(built.features));
+                    }
+                }
+
+                let chunk_total = chunks.len();
+
+                for (chunk_index, chunk) in chunks.into_iter().enumerate() {
+                    let doc_path = db::chunks::chunk_doc_path(&doc_page.url, chunk_index);
+
+                    // Calculate token count with the shared BPE tokenizer
+                    let token_count = embed::token_count(&chunk);
+                    let token_count_i32 = i32::try_from(token_count).unwrap_or(i32::MAX);
+                    self.total_docs += 1;
+                    self.total_tokens += i64::from(token_count_i32);
+
+                    if stored_hashes.get(&doc_path) == Some(&db::content_hash(&chunk)) {
+                        self.unchanged_docs += 1;
+                        self.embedding_stats.skipped += 1;
+                        unchanged_paths.push(doc_path);
+                        continue;
+                    }
+
+                    let mut chunk_metadata = metadata.clone();
+                    db::chunks::annotate_chunk_metadata(
+                        &mut chunk_metadata,
+                        &doc_page.url,
+                        chunk_index,
+                        chunk_total,
+                    );
+                    db::annotate_content_hash(&mut chunk_metadata, &chunk);
+
+                    let embedding = match embeddings.remove(&doc_path) {
+                        Some(Ok(embedding)) => {
+                            embedding_config.annotate_metadata(&mut chunk_metadata);
+                            Some(pgvector::Vector::from(embedding))
+                        }
+                        Some(Err(e)) => {
+                            // Left for the backfill tool to retry
+                            chunk_metadata[embed::pipeline::METADATA_EMBEDDING_ERROR_KEY] =
+                                json!(e.to_string());
+                            None
+                        }
+                        None => {
+                            self.embedding_stats.skipped += 1;
+                            None
+                        }
+                    };
+
+                    // Upsert document; an existing embedding is kept only if the content is unchanged
+                    sqlx::query(
+                        r"
+                        INSERT INTO documents (id, doc_type, source_name, doc_path, content, metadata, token_count, embedding, created_at, updated_at)
+                        VALUES ($1, 'rust', $2, $3, $4, $5, $6, $7, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+                        ON CONFLICT (doc_type, source_name, doc_path) DO UPDATE SET
+                            content = EXCLUDED.content,
+                            metadata = EXCLUDED.metadata,
+                            token_count = EXCLUDED.token_count,
+                            updated_at = EXCLUDED.updated_at,
+                            embedding = COALESCE(EXCLUDED.embedding, CASE
+                                WHEN documents.content IS DISTINCT FROM EXCLUDED.content THEN NULL
+                                ELSE documents.embedding
+                            END)
+                        "
+                    )
+                    .bind(db::new_document_id(
+                        "rust",
+                        &self.crate_info.name,
+                        &doc_path,
+                        &chunk_metadata,
+                    ))
+                    .bind(&self.crate_info.name)
+                    .bind(&doc_path)
+                    .bind(&chunk)
+                    .bind(&chunk_metadata)
+                    .bind(token_count_i32)
+                    .bind(embedding)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+
+                // Drop chunks left over from a longer earlier version of the page
+                sqlx::query(
+                    r"
+                    DELETE FROM documents
+                    WHERE doc_type = 'rust' AND source_name = $1
+                      AND metadata->>'parent_doc_path' = $2
+                      AND (metadata->>'chunk_index')::int >= $3
+                    ",
+                )
+                .bind(&self.crate_info.name)
+                .bind(&doc_page.url)
+                .bind(i32::try_from(chunk_total).unwrap_or(i32::MAX))
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            if !unchanged_paths.is_empty() {
+                sqlx::query(
+                    r"
+                    UPDATE documents SET metadata = (metadata - 'alternate_urls') || $3
+                    WHERE doc_type = 'rust' AND source_name = $1 AND doc_path = ANY($2)
+                    ",
+                )
+                .bind(&self.crate_info.name)
+                .bind(&unchanged_paths)
+                .bind(json!({
+                    "crate_version": self.crate_version,
+                    "force_updated": self.force_update,
+                    "ingestion_job_id": self.job_id.to_string(),
+                }))
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            for (canonical, alternate) in alternates {
+                // Rows an earlier run stored for the duplicate page
+                sqlx::query(
+                    r"
+                    DELETE FROM documents
+                    WHERE doc_type = 'rust' AND source_name = $1
+                      AND (doc_path = $2 OR metadata->>'parent_doc_path' = $2)
+                    ",
+                )
+                .bind(&self.crate_info.name)
+                .bind(alternate)
+                .execute(&mut *tx)
+                .await?;
+
+                sqlx::query(
+                    r"
+                    UPDATE documents
+                    SET metadata = jsonb_set(
+                        metadata,
+                        '{alternate_urls}',
+                        COALESCE(metadata->'alternate_urls', '[]'::jsonb) || to_jsonb($3::text)
+                    )
+                    WHERE doc_type = 'rust' AND source_name = $1
+                      AND (doc_path = $2 OR metadata->>'parent_doc_path' = $2)
+                      AND NOT COALESCE(metadata->'alternate_urls', '[]'::jsonb) ? $3
+                    ",
+                )
+                .bind(&self.crate_info.name)
+                .bind(&canonical)
+                .bind(alternate)
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            tx.commit().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Embed the chunks of `pages` whose content changed, keyed by doc path
+    ///
+    /// Runs before the batch transaction opens so no API call holds it open.
+    /// A failed chunk gets its error in place of the embedding.
+    async fn embed_changed_chunks(
+        &mut self,
+        pages: &[(&DocPage, Vec<String>)],
+        stored_hashes: &HashMap<String, String>,
+    ) -> HashMap<String, Result<Vec<f32>>> {
+        if !self.vector_extension_available {
+            return HashMap::new();
+        }
+
+        let changed: Vec<(String, &str)> = pages
+            .iter()
+            .flat_map(|(doc_page, chunks)| {
+                chunks.iter().enumerate().map(|(index, chunk)| {
+                    (
+                        db::chunks::chunk_doc_path(&doc_page.url, index),
+                        chunk.as_str(),
+                    )
+                })
+            })
+            .filter(|(doc_path, chunk)| {
+                !chunk.is_empty() && stored_hashes.get(doc_path) != Some(&db::content_hash(chunk))
+            })
+            .collect();
+        if changed.is_empty() {
+            return HashMap::new();
+        }
+
+        let texts: Vec<&str> = changed.iter().map(|(_, chunk)| *chunk).collect();
+        let results = self.embedding_client.embed_many(&texts).await;
+
+        let mut failed = 0;
+        let mut first_error = None;
+        for result in &results {
+            self.embedding_stats.record(result);
+            if let Err(e) = result {
+                failed += 1;
+                first_error.get_or_insert_with(|| e.to_string());
+            }
+        }
+        if let Some(first_error) = first_error {
+            tracing::warn!(
+                "Failed to embed {} of {} documents for crate {}: {}",
+                failed,
+                results.len(),
+                self.crate_info.name,
+                first_error
+            );
+            self.job_processor
+                .record_job_event(
+                    self.job_id,
+                    "embedding_errors",
+                    json!({
+                        "failed": failed,
+                        "documents": results.len(),
+                        "first_error": first_error,
+                    }),
+                )
+                .await;
+        }
+
+        changed
+            .into_iter()
+            .map(|(doc_path, _)| doc_path)
+            .zip(results)
+            .collect()
+    }
+}
+
+/// Fetch cache for one crate version, backed by the `fetch_cache` table
+struct DbFetchCache {
+    pool: sqlx::PgPool,
+    source_name: String,
+    crate_version: String,
+}
+
+#[async_trait]
+impl FetchCache for DbFetchCache {
+    async fn lookup(&self, url: &str) -> Result<Option<CachedPage>> {
+        let entry =
+            FetchCacheQueries::lookup(&self.pool, &self.source_name, &self.crate_version, url)
+                .await?;
+        Ok(entry.map(|entry| CachedPage {
+            validators: CacheValidators {
+                etag: entry.etag,
+                last_modified: entry.last_modified,
+            },
+            links: entry.links,
+        }))
+    }
+
+    async fn store(&self, entries: &[(String, CachedPage)]) -> Result<()> {
+        let entries: Vec<FetchCacheEntry> = entries
+            .iter()
+            .map(|(url, page)| FetchCacheEntry {
+                url: url.clone(),
+                etag: page.validators.etag.clone(),
+                last_modified: page.validators.last_modified.clone(),
+                links: page.links.clone(),
+            })
+            .collect();
+        FetchCacheQueries::store(&self.pool, &self.crate_version, &entries).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CrawlSink for IngestionSink<'_> {
+    /// Stamp the stored documents of unchanged pages with this job, without re-embedding
+    async fn keep_unchanged(&mut self, urls: Vec<String>) -> Result<()> {
+        sqlx::query(
+            r"
+            UPDATE documents SET metadata = metadata || $3
+            WHERE doc_type = 'rust' AND source_name = $1 AND metadata->>'source_url' = ANY($2)
+            ",
+        )
+        .bind(&self.crate_info.name)
+        .bind(&urls)
+        .bind(json!({
+            "crate_version": self.crate_version,
+            "force_updated": self.force_update,
+            "ingestion_job_id": self.job_id.to_string(),
+        }))
+        .execute(self.db_pool.pool())
+        .await?;
+
+        self.unchanged_pages += urls.len();
+        CrateJobQueries::merge_job_details(
+            self.db_pool.pool(),
+            self.job_id,
+            &json!({"unchanged_pages": self.unchanged_pages}),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn checkpoint(&mut self, pages: Vec<DocPage>, state: &CrawlState) -> Result<()> {
+        self.store_pages(&pages).await?;
+
+        // Written after the document transactions so the checkpoint never covers unstored pages
+        CrateJobQueries::save_crawl_state(
+            self.db_pool.pool(),
+            self.job_id,
+            &serde_json::to_value(state)?,
+        )
+        .await?;
+
+        self.job_processor
+            .record_job_event(
+                self.job_id,
+                "batch_committed",
+                json!({
+                    "pages": pages.len(),
+                    "pages_processed": state.processed,
+                    "pages_queued": state.queue.len(),
+                }),
+            )
+            .await;
+
+        tracing::info!(
+            "Checkpointed crawl for crate {}: {} pages processed, {} queued",
+            self.crate_info.name,
+            state.processed,
+            state.queue.len()
+        );
+        Ok(())
+    }
+}
+
+/// Documents above which a hard delete runs as a background job (`CRATE_REMOVE_ASYNC_THRESHOLD`, default 5000)
+pub(crate) fn crate_remove_async_threshold() -> i32 {
+    std::env::var("CRATE_REMOVE_ASYNC_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse::<i32>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(5000)
+}
+
+/// Documents deleted per transaction by a removal job (`CRATE_REMOVE_BATCH_SIZE`, default 1000)
+pub(crate) fn crate_remove_batch_size() -> i64 {
+    std::env::var("CRATE_REMOVE_BATCH_SIZE")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(1000)
+}
+
+/// Remove Rust crate tool with cascade deletion
+///
+/// Hard deletes of crates above the async threshold are enqueued as
+/// `remove_crate` jobs and return 202 + job ID; smaller crates are removed
+/// within the request.
+pub struct RemoveRustCrateTool {
+    job_processor: CrateJobProcessor,
+    storage: CrateStorage,
+    async_threshold: i32,
+}
+
+impl RemoveRustCrateTool {
+    /// Create a new remove crate tool
+    pub fn new(storage: impl Into<CrateStorage>) -> Self {
+        let storage = storage.into();
+        Self {
+            job_processor: CrateJobProcessor::new(storage.clone()),
+            storage,
+            async_threshold: crate_remove_async_threshold(),
+        }
+    }
+
+    /// Run hard deletes of crates with more than `threshold` documents as background jobs
+    #[must_use]
+    pub fn with_async_threshold(mut self, threshold: i32) -> Self {
+        self.async_threshold = threshold;
+        self
+    }
+}
+
+#[async_trait]
+impl Tool for RemoveRustCrateTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "remove_rust_crate",
+            "description": "Remove a Rust crate from the documentation system with cascade deletion and cleanup verification. Supports both soft-delete and hard-delete operations with comprehensive cleanup verification. Hard deletes of large crates run as a background job and return a job ID for check_rust_status. Errors carry a code in error.data.code: not_found (-32004) when the crate or the requested version is not stored (details.stored_versions); dependency_error (-32024) when other ingested crates depend on it and force is not set (details.dependents); invalid_input (-32602) for an empty name.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "The name of the Rust crate to remove"
+                    },
+                    "crate_name": {
+                        "type": "string",
+                        "description": "Alias for 'name' (accepted for backward compatibility)"
+                    },
+                    "version": {
+                        "type": "string",
+                        "description": "Remove only this ingested version of the crate, leaving its other versions in place (optional, defaults to every version)"
+                    },
+                    "soft_delete": {
+                        "type": "boolean",
+                        "description": "If true, mark as inactive instead of hard delete (default: false)"
+                    },
+                    "verify_cleanup": {
+                        "type": "boolean",
+                        "description": "Perform comprehensive cleanup verification after deletion (default: true)"
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "Show what would be removed without actually deleting (default: false)"
+                    },
+                    "force": {
+                        "type": "boolean",
+                        "description": "Force removal even if other ingested crates record a dependency on this crate (default: false)"
+                    },
+                    "idempotency_key": {
+                        "type": "string",
+                        "description": "Client-chosen key for safe retries: a repeated call with the same key within 24 hours returns the job the first call created, with its current status, instead of queueing another. Applies to removals that run as a background job"
+                    }
+                },
+                "anyOf": [{"required": ["name"]}, {"required": ["crate_name"]}],
+                "additionalProperties": false
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        let crate_name = arguments
+            .get("name")
+            .and_then(|n| n.as_str())
+            .or_else(|| arguments.get("crate_name").and_then(|n| n.as_str()))
+            .ok_or_else(|| anyhow!("Missing required 'name' parameter"))?;
+        let version = arguments.get("version").and_then(Value::as_str);
+
+        let soft_delete = arguments
+            .get("soft_delete")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let verify_cleanup = arguments
+            .get("verify_cleanup")
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+        let dry_run = arguments
+            .get("dry_run")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let force = arguments
+            .get("force")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let idempotency_key = idempotency_key_argument(&arguments);
+
+        // Validate crate name
+        if crate_name.trim().is_empty() {
+            return Err(ToolError::invalid_input("Crate name cannot be empty").into());
+        }
+
+        // A retried request gets the job it already created, even once the crate is gone
+        if let Some(key) = idempotency_key.filter(|_| !dry_run) {
+            if let Some(job) = self
+                .storage
+                .store()
+                .find_job_by_idempotency_key(key)
+                .await?
+            {
+                return replay_job(&job, key, crate_name, "remove_crate");
+            }
+        }
+
+        // Check if crate exists and get preliminary info. Names match up to
+        // case and '-'/'_', and the removal uses the stored spelling.
+        let versions = self
+            .storage
+            .store()
+            .find_crate_versions(crate_name.trim())
+            .await?;
+        let Some(latest) = versions.first() else {
+            return Err(crate_not_found(crate_name).into());
+        };
+        let requested_name = crate_name;
+        let crate_name = latest.name.as_str();
+        let normalized_from = (crate_name != requested_name).then_some(requested_name);
+        let normalized_note = normalized_from
+            .map(|alias| format!("Crate name '{alias}' was normalized to '{crate_name}'."));
+        let total_docs = match version {
+            Some(version) => {
+                let Some(info) = versions.iter().find(|info| info.version == version) else {
+                    let stored: Vec<&str> = versions.iter().map(|v| v.version.as_str()).collect();
+                    return Err(ToolError::NotFound {
+                        message: format!(
+                            "Crate '{}' has no version '{}' in the system. Stored versions: {}.",
+                            crate_name,
+                            version,
+                            stored.join(", ")
+                        ),
+                        details: json!({
+                            "crate_name": crate_name,
+                            "version": version,
+                            "stored_versions": stored,
+                        }),
+                    }
+                    .into());
+                };
+                info.total_docs
+            }
+            None => versions.iter().map(|info| info.total_docs).sum(),
+        };
+        // Dependents are still served while another version of the crate stays
+        let removes_every_version = version.is_none() || versions.len() == 1;
+
+        // Perform dependency check if not forced
+        let mut warning = None;
+        if !force && removes_every_version {
+            match self.check_crate_dependencies(crate_name).await? {
+                DependencyCheck::Dependents(dependents) => {
+                    let mut message = format!(
+                        "Crate '{}' is a dependency of other ingested crates and cannot be safely removed. Use force=true to override.\nDependent crates:\n",
+                        crate_name
+                    );
+                    for dependent in &dependents {
+                        let _ = writeln!(
+                            &mut message,
+                            "  • {} {} requires {} {} ({})",
+                            dependent.crate_name,
+                            dependent
+                                .crate_version
+                                .as_deref()
+                                .unwrap_or("(version unknown)"),
+                            crate_name,
+                            dependent.req,
+                            dependent.kind
+                        );
+                    }
+                    return Err(ToolError::DependencyError {
+                        message: message.trim_end().to_string(),
+                        details: json!({
+                            "crate_name": crate_name,
+                            "dependents": dependents,
+                        }),
+                    }
+                    .into());
+                }
+                DependencyCheck::Unknown(crates) => {
+                    let shown: Vec<&str> = crates.iter().take(10).map(String::as_str).collect();
+                    let more = crates.len().saturating_sub(shown.len());
+                    warning = Some(format!(
+                        "⚠️ Dependency check: unknown for {} crate(s) ingested without dependency metadata ({}{}). Re-ingest them to record their dependencies.",
+                        crates.len(),
+                        shown.join(", "),
+                        if more > 0 { format!(", and {more} more") } else { String::new() }
+                    ));
+                }
+                DependencyCheck::Clear => {}
+            }
+        }
+
+        // If dry run, show what would be removed
+        if dry_run {
+            let report = self
+                .perform_dry_run(crate_name, version, soft_delete)
+                .await?;
+            let report = with_warning(normalized_note.as_deref(), report);
+            return Ok(with_warning(warning.as_deref(), report));
+        }
+
+        // Large hard deletes run in batches on a background job
+        if !soft_delete && total_docs > self.async_threshold {
+            return self
+                .enqueue_removal(
+                    crate_name,
+                    version,
+                    total_docs,
+                    verify_cleanup,
+                    warning.as_deref(),
+                    idempotency_key,
+                    normalized_from,
+                )
+                .await;
+        }
+
+        // Perform actual removal
+        let result = if soft_delete {
+            self.perform_soft_deletion(crate_name, version, verify_cleanup)
+                .await
+        } else {
+            self.perform_cascade_deletion(crate_name, version, verify_cleanup)
+                .await
+        };
+        query_cache().invalidate_doc_type("rust");
+
+        // Add enhanced reporting
+        match result {
+            Ok(message) => {
+                let message = with_warning(normalized_note.as_deref(), message);
+                let message = with_warning(warning.as_deref(), message);
+                if verify_cleanup {
+                    match Self::verify_complete_cleanup(self.storage.store(), crate_name, version)
+                        .await
+                    {
+                        Ok(verification_msg) => Ok(format!("{}\n\n{}", message, verification_msg)),
+                        Err(e) => Ok(format!(
+                            "{}\n\nWarning: Cleanup verification failed: {}",
+                            message, e
+                        )),
+                    }
+                } else {
+                    Ok(message)
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Outcome of the dependency check run before removing a crate
+enum DependencyCheck {
+    /// Ingested crates that record a dependency on the crate
+    Dependents(Vec<CrateDependent>),
+    /// No recorded dependency, but these crates have no dependency metadata
+    Unknown(Vec<String>),
+    /// No crate depends on it
+    Clear,
+}
+
+/// How removal messages name a crate, or one version of it (`tokio@0.2.25`)
+fn crate_label(crate_name: &str, version: Option<&str>) -> String {
+    match version {
+        Some(version) => format!("{crate_name}@{version}"),
+        None => crate_name.to_string(),
+    }
+}
+
+/// Error for a crate, or crate version label, with no stored documents
+fn crate_not_found(label: &str) -> ToolError {
+    ToolError::NotFound {
+        message: format!("Crate '{label}' not found in the system."),
+        details: json!({ "crate_name": label }),
+    }
+}
+
+/// Append a removal warning to a report
+/// Warnings a finished job recorded in its details
+fn job_warnings(details: &Value) -> Vec<&str> {
+    details
+        .get("warnings")
+        .and_then(Value::as_array)
+        .map(|warnings| warnings.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+fn with_warning(warning: Option<&str>, report: String) -> String {
+    match warning {
+        Some(warning) => format!("{report}\n\n{warning}"),
+        None => report,
+    }
+}
+
+impl RemoveRustCrateTool {
+    /// Enqueue a `remove_crate` job and return 202 + job ID
+    #[allow(clippy::too_many_arguments)]
+    async fn enqueue_removal(
+        &self,
+        crate_name: &str,
+        version: Option<&str>,
+        total_docs: i32,
+        verify_cleanup: bool,
+        warning: Option<&str>,
+        idempotency_key: Option<&str>,
+        normalized_from: Option<&str>,
+    ) -> Result<String> {
+        let options = RemoveJobOptions {
+            verify_cleanup,
+            version: version.map(String::from),
+        };
+        let enqueued = self
+            .job_processor
+            .enqueue_remove_crate_job(crate_name, &options, idempotency_key)
+            .await?;
+        if !enqueued.created {
+            return Ok(replayed_job_response(&enqueued.job));
+        }
+        let job_id = enqueued.job.id;
+
+        // The local dispatcher picks the job up from the notification
+        if crate::queue::use_redis_queue() {
+            let msg = crate::queue::RedisJobMessage::new(
+                job_id,
+                "crate_remove",
+                3,
+                json!({
+                    "crate_name": crate_name,
+                    "verify_cleanup": verify_cleanup,
+                    "version": version
+                }),
+            );
+            crate::queue::enqueue_job(&msg).await?;
+        }
+
+        Ok(json!({
+            "status": "accepted",
+            "job_id": job_id.to_string(),
+            "documents": total_docs,
+            "warning": warning,
+            "normalized_from": normalized_from,
+            "message": format!("Crate '{}' removal job queued ({} documents). Use check_rust_status with job_id to track progress.", crate_label(crate_name, version), total_docs)
+        })
+        .to_string())
+    }
+
+    /// Run a removal job on a background task
+    ///
+    /// Waits for a concurrency permit unless the caller already reserved one.
+    pub(crate) fn spawn_removal(
+        job_processor: CrateJobProcessor,
+        job_id: Uuid,
+        crate_name_owned: String,
+        permit: Option<OwnedSemaphorePermit>,
+        options: RemoveJobOptions,
+    ) {
+        tokio::spawn(async move {
+            let _permit = match permit {
+                Some(permit) => Some(permit),
+                None => get_crate_job_semaphore().acquire_owned().await.ok(),
+            };
+            let _running = RunningJobGuard::register(job_id);
+
+            let result = job_processor
+                .run_with_heartbeat(
+                    job_id,
+                    Self::process_removal(&job_processor, job_id, &crate_name_owned, &options),
+                )
+                .await;
+            if let Err(e) = result {
+                tracing::error!(
+                    "Background crate removal failed for {}: {}",
+                    crate_name_owned,
+                    e
+                );
+                if let Err(update_err) = job_processor.record_failure(job_id, &e).await {
+                    tracing::error!("Failed to record job failure: {}", update_err);
+                }
+            }
+        });
+    }
+
+    /// Delete a crate's documents in batches, recording progress on the job
+    ///
+    /// Each batch commits on its own, so an interrupted job resumes with the
+    /// documents that are left. Cleanup verification runs at the end and is
+    /// stored in the job's details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a batch or a job update fails.
+    pub async fn process_removal(
+        job_processor: &CrateJobProcessor,
+        job_id: Uuid,
+        crate_name: &str,
+        options: &RemoveJobOptions,
+    ) -> Result<()> {
+        let store = job_processor.storage().store();
+        let version = options.version.as_deref();
+        job_processor
+            .update_job_status(job_id, JobStatus::Running, Some(0), None)
+            .await?;
+
+        let total = store
+            .count_crate_documents(crate_name, version, CrateStatusFilter::All)
+            .await?;
+        let batch_size = crate_remove_batch_size();
+
+        let mut deleted: i64 = 0;
+        loop {
+            let removed = store
+                .delete_crate_documents_batch(crate_name, version, batch_size)
+                .await?;
+            if removed == 0 {
+                break;
+            }
+            query_cache().invalidate_doc_type("rust");
+            deleted += i64::try_from(removed).unwrap_or(i64::MAX);
+            let progress = (deleted * 100 / total.max(1)).min(99) as i32;
+            store
+                .merge_job_details(
+                    job_id,
+                    &json!({"documents_deleted": deleted, "documents_total": total}),
+                )
+                .await?;
+            job_processor
+                .update_job_status(job_id, JobStatus::Running, Some(progress), None)
+                .await?;
+        }
+
+        tracing::info!(
+            "Removal job {} deleted crate '{}': {} documents removed",
+            job_id,
+            crate_label(crate_name, version),
+            deleted
+        );
+
+        if options.verify_cleanup {
+            let verification = match Self::verify_complete_cleanup(store, crate_name, version).await
+            {
+                Ok(verification) => verification,
+                Err(e) => format!("Cleanup verification failed: {}", e),
+            };
+            store
+                .merge_job_details(job_id, &json!({"cleanup_verification": verification}))
+                .await?;
+        }
+
+        job_processor
+            .update_job_status(job_id, JobStatus::Completed, Some(100), None)
+            .await?;
+        Ok(())
+    }
+
+    /// Perform cascade deletion in a single statement, with cleanup verification
+    async fn perform_cascade_deletion(
+        &self,
+        crate_name: &str,
+        version: Option<&str>,
+        _verify_cleanup: bool,
+    ) -> Result<String> {
+        // Embeddings live on the document rows, so they go with them
+        let documents_deleted = self
+            .storage
+            .store()
+            .delete_crate_documents(crate_name, version)
+            .await?;
+        let label = crate_label(crate_name, version);
+
+        tracing::info!(
+            "Successfully deleted crate '{}': {} documents removed",
+            label,
+            documents_deleted
+        );
+
+        Ok(format!(
+            "Crate '{}' removed successfully. Deleted {} documents and all associated embeddings.",
+            label, documents_deleted
+        ))
+    }
+
+    /// Perform soft deletion by marking documents as inactive
+    async fn perform_soft_deletion(
+        &self,
+        crate_name: &str,
+        version: Option<&str>,
+        _verify_cleanup: bool,
+    ) -> Result<String> {
+        // Update metadata to mark as inactive
+        let updated = self
+            .storage
+            .store()
+            .mark_crate_inactive(crate_name, version)
+            .await?;
+        let label = crate_label(crate_name, version);
+
+        if updated == 0 {
+            return Err(crate_not_found(&label).into());
+        }
+
+        tracing::info!(
+            "Successfully marked crate '{}' as inactive: {} documents updated",
+            label,
+            updated
+        );
+
+        Ok(format!(
+            "Crate '{}' marked as inactive. {} documents remain in the system but are not searchable. Use restore_rust_crate to reactivate it.",
+            label, updated
+        ))
+    }
+
+    /// Check which ingested crates declare a dependency on this crate
+    ///
+    /// Only dependency metadata recorded at ingest time is consulted; crates
+    /// ingested without it make the result unknown rather than blocking.
+    async fn check_crate_dependencies(&self, crate_name: &str) -> Result<DependencyCheck> {
+        let store = self.storage.store();
+        let dependents = store.find_dependents(crate_name).await?;
+        if !dependents.is_empty() {
+            return Ok(DependencyCheck::Dependents(dependents));
+        }
+        let unknown = store.find_crates_without_dependencies(crate_name).await?;
+        if unknown.is_empty() {
+            Ok(DependencyCheck::Clear)
+        } else {
+            Ok(DependencyCheck::Unknown(unknown))
+        }
+    }
+
+    /// Perform dry run showing what would be removed
+    async fn perform_dry_run(
+        &self,
+        crate_name: &str,
+        version: Option<&str>,
+        soft_delete: bool,
+    ) -> Result<String> {
+        let store = self.storage.store();
+        let doc_count = store
+            .count_crate_documents(crate_name, version, CrateStatusFilter::All)
+            .await?;
+        let embedding_count = store.count_crate_embeddings(crate_name, version).await?;
+
+        let operation = if soft_delete {
+            "mark as inactive"
+        } else {
+            "permanently delete"
+        };
+
+        Ok(format!(
+            "🔍 **Dry Run for Crate '{}'**\n\n\
+            Operation: {}\n\
+            - {} documents would be affected\n\
+            - {} embeddings would be affected\n\
+            - Database storage would be impacted\n\n\
+            To execute this operation, run the command again with dry_run=false",
+            crate_label(crate_name, version),
+            operation,
+            doc_count,
+            embedding_count
+        ))
+    }
+
+    /// Verify complete cleanup after deletion of a crate, or of one version
+    async fn verify_complete_cleanup(
+        store: &dyn CrateStore,
+        crate_name: &str,
+        version: Option<&str>,
+    ) -> Result<String> {
+        // Check for any remaining documents
+        let remaining_docs = store
+            .count_crate_documents(crate_name, version, CrateStatusFilter::All)
+            .await?;
+
+        // Check for any remaining embeddings
+        let remaining_embeddings = store.count_crate_embeddings(crate_name, version).await?;
+
+        // Check database integrity
+        let total_rust_docs = store.count_rust_documents().await?;
+
+        if remaining_docs == 0 && remaining_embeddings == 0 {
+            Ok(format!(
+                "✅ **Cleanup Verification: PASSED**\n\
+                - ✅ No remaining documents found\n\
+                - ✅ No remaining embeddings found\n\
+                - ℹ️  Total Rust documents in system: {}\n\
+                - ✅ Database integrity maintained",
+                total_rust_docs
+            ))
+        } else {
+            Ok(format!(
+                "⚠️ **Cleanup Verification: INCOMPLETE**\n\
+                - ⚠️ {} documents still remain\n\
+                - ⚠️ {} embeddings still remain\n\
+                - ℹ️ Total Rust documents in system: {}\n\
+                - 🔧 Manual cleanup may be required",
+                remaining_docs, remaining_embeddings, total_rust_docs
+            ))
+        }
+    }
+}
+
+/// Restore Rust crate tool - reactivates a soft-deleted crate
+pub struct RestoreRustCrateTool {
+    storage: CrateStorage,
+}
+
+impl RestoreRustCrateTool {
+    /// Create a new restore crate tool
+    pub fn new(storage: impl Into<CrateStorage>) -> Self {
+        Self {
+            storage: storage.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for RestoreRustCrateTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "restore_rust_crate",
+            "description": "Restore a Rust crate previously removed with soft_delete=true, making its documents visible in listings and search again. Errors carry a code in error.data.code: not_found (-32004) when the crate has no soft-deleted documents; invalid_input (-32602) for an empty name.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "The name of the Rust crate to restore"
+                    },
+                    "crate_name": {
+                        "type": "string",
+                        "description": "Alias for 'name' (accepted for backward compatibility)"
+                    }
+                },
+                "anyOf": [{"required": ["name"]}, {"required": ["crate_name"]}],
+                "additionalProperties": false
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        let crate_name = arguments
+            .get("name")
+            .and_then(|n| n.as_str())
+            .or_else(|| arguments.get("crate_name").and_then(|n| n.as_str()))
+            .ok_or_else(|| anyhow!("Missing required 'name' parameter"))?;
+
+        if crate_name.is_empty() {
+            return Err(ToolError::invalid_input("Crate name cannot be empty").into());
+        }
+
+        let restored_count = self.storage.store().restore_crate(crate_name).await?;
+        query_cache().invalidate_doc_type("rust");
+        if restored_count == 0 {
+            return Err(ToolError::NotFound {
+                message: format!("Crate '{crate_name}' has no inactive documents to restore."),
+                details: json!({ "crate_name": crate_name }),
+            }
+            .into());
+        }
+
+        tracing::info!(
+            "Restored crate '{}': {} documents reactivated",
+            crate_name,
+            restored_count
+        );
+
+        Ok(format!(
+            "Crate '{}' restored. {} documents reactivated and searchable again.",
+            crate_name, restored_count
+        ))
+    }
+}
+
+/// Retry Rust job tool - requeues a failed or dead-lettered crate job
+pub struct RetryRustJobTool {
+    storage: CrateStorage,
+}
+
+impl RetryRustJobTool {
+    /// Create a new retry job tool
+    pub fn new(storage: impl Into<CrateStorage>) -> Self {
+        Self {
+            storage: storage.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for RetryRustJobTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "retry_rust_job",
+            "description": "Requeue a failed or dead-lettered crate ingestion job with its attempts reset. The job keeps its original options (version, features, force_update). Errors carry a code in error.data.code: not_found (-32004) for an unknown job; conflict (-32009) when the job is not failed or cancelled (details.status); invalid_input (-32602) for a malformed job_id.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "job_id": {
+                        "type": "string",
+                        "description": "ID of the failed job to retry"
+                    }
+                },
+                "required": ["job_id"],
+                "additionalProperties": false
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        let job_id = arguments
+            .get("job_id")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("Missing required 'job_id' parameter"))?;
+        let job_id = Uuid::parse_str(job_id)
+            .map_err(|_| ToolError::invalid_input("Invalid job ID format"))?;
+
+        let Some(job) = self.storage.store().requeue_job(job_id).await? else {
+            return Err(match self.storage.store().find_job_by_id(job_id).await? {
+                Some(job) => ToolError::Conflict {
+                    message: format!(
+                        "Job {job_id} was not requeued: it is {:?}, not failed or cancelled.",
+                        job.status
+                    ),
+                    details: json!({ "job_id": job_id, "status": job.status }),
+                },
+                None => ToolError::NotFound {
+                    message: format!("Job {job_id} not found."),
+                    details: json!({ "job_id": job_id }),
+                },
+            }
+            .into());
+        };
+
+        // The local dispatcher picks the job up from the notification; Redis
+        // mode needs the message pushed again
+        if crate::queue::use_redis_queue() && job.operation == "remove_crate" {
+            let options = RemoveJobOptions::from_job(&job);
+            let msg = crate::queue::RedisJobMessage::new(
+                job.id,
+                "crate_remove",
+                3,
+                json!({
+                    "crate_name": job.crate_name,
+                    "verify_cleanup": options.verify_cleanup,
+                    "version": options.version
+                }),
+            );
+            crate::queue::enqueue_job(&msg).await?;
+        } else if let Some(ecosystem) = PackageEcosystem::for_operation(&job.operation)
+            .filter(|_| crate::queue::use_redis_queue())
+        {
+            let msg = if job.operation == ecosystem.remove_operation {
+                let options = RemoveJobOptions::from_job(&job);
+                crate::queue::RedisJobMessage::new(
+                    job.id,
+                    ecosystem.remove_job_type,
+                    3,
+                    json!({
+                        "package_name": job.crate_name,
+                        "verify_cleanup": options.verify_cleanup,
+                        "version": options.version
+                    }),
+                )
+            } else {
+                let mut payload = serde_json::to_value(PackageJobOptions::from_job(&job))?;
+                payload["package_name"] = json!(job.crate_name);
+                crate::queue::RedisJobMessage::new(job.id, ecosystem.add_job_type, 3, payload)
+            };
+            crate::queue::enqueue_job(&msg).await?;
+        } else if crate::queue::use_redis_queue() {
+            let options = CrateJobOptions::from_job(&job);
+            let msg = crate::queue::RedisJobMessage::new(
+                job.id,
+                "crate_add",
+                3,
+                json!({
+                    "crate_name": job.crate_name,
+                    "version": options.version,
+                    "version_req": options.version_req,
+                    "features": options.features,
+                    "include_dev_deps": options.include_dev_deps,
+                    "force_update": options.force_update,
+                    "no_cache": options.no_cache,
+                    "atomic_rollback": options.atomic_rollback,
+                    "local": options.local
+                }),
+            );
+            crate::queue::enqueue_job(&msg).await?;
+        }
+
+        tracing::info!("Requeued crate job {} for {}", job.id, job.crate_name);
+        Ok(json!({
+            "status": "requeued",
+            "job_id": job.id.to_string(),
+            "message": format!("Job for crate '{}' requeued with attempts reset. Use check_rust_status with job_id to track progress.", job.crate_name)
+        })
+        .to_string())
+    }
+}
+
+/// Fails a backfill job whose call ends without finalizing it
+///
+/// A timed-out tool call drops its future mid-loop, which would otherwise
+/// leave the job `Running` until the next restart's stale sweep.
+struct BackfillJobGuard {
+    pool: sqlx::PgPool,
+    job_id: Uuid,
+    processed: i64,
+    finished: bool,
+}
 
-fn get_crate_job_semaphore() -> Arc<Semaphore> {
-    CRATE_JOB_SEMAPHORE
-        .get_or_init(|| Arc::new(Semaphore::new(crate_job_max_concurrency())))
-        .clone()
+impl Drop for BackfillJobGuard {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let (pool, job_id) = (self.pool.clone(), self.job_id);
+        let message = format!(
+            "stopped after {} documents: the call timed out or was dropped",
+            self.processed
+        );
+        runtime.spawn(async move {
+            if let Err(e) = CrateJobQueries::update_job_status(
+                &pool,
+                job_id,
+                JobStatus::Failed,
+                None,
+                Some(&message),
+            )
+            .await
+            {
+                tracing::warn!("Failed to finalize backfill job {}: {}", job_id, e);
+            }
+        });
+    }
 }
 
-/// Remove Rust crate tool with cascade deletion
-pub struct RemoveRustCrateTool {
+/// Backfill embeddings tool - generates embeddings for documents missing them
+pub struct BackfillEmbeddingsTool {
     db_pool: DatabasePool,
+    embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
 }
 
-impl RemoveRustCrateTool {
-    /// Create a new remove crate tool
-    pub fn new(db_pool: DatabasePool) -> Self {
-        Self { db_pool }
+impl BackfillEmbeddingsTool {
+    /// Create a new backfill embeddings tool
+    pub fn new(
+        db_pool: DatabasePool,
+        embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
+    ) -> Self {
+        Self {
+            db_pool,
+            embedding_client,
+        }
+    }
+
+    /// Embed a single document, backing off on rate limits and transient errors
+    async fn embed_with_backoff(&self, policy: &RetryPolicy, content: &str) -> Result<Vec<f32>> {
+        let mut attempt = 0;
+        loop {
+            match self.embedding_client.embed(content).await {
+                Ok(embedding) => return Ok(embedding),
+                Err(e) if attempt < policy.max_retries && RetryPolicy::is_retryable_error(&e) => {
+                    attempt += 1;
+                    let delay = policy.calculate_delay(attempt);
+                    tracing::warn!(
+                        "Embedding request failed (attempt {}), retrying after {:?}: {}",
+                        attempt,
+                        delay,
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
     }
 }
 
 #[async_trait]
-impl Tool for RemoveRustCrateTool {
+impl Tool for BackfillEmbeddingsTool {
     fn definition(&self) -> Value {
         json!({
-            "name": "remove_rust_crate",
-            "description": "Remove a Rust crate from the documentation system with cascade deletion and cleanup verification. Supports both soft-delete and hard-delete operations with comprehensive cleanup verification.",
+            "name": "backfill_embeddings",
+            "description": "Generate embeddings for documents that are missing them (e.g. ingested while the vector extension or embedding API was unavailable). Safe to re-run: documents that already have embeddings are skipped.",
             "inputSchema": {
                 "type": "object",
                 "properties": {
-                    "name": {
+                    "doc_type": {
                         "type": "string",
-                        "description": "The name of the Rust crate to remove"
+                        "description": "Only backfill documents of this type (optional)"
                     },
                     "crate_name": {
                         "type": "string",
-                        "description": "Alias for 'name' (accepted for backward compatibility)"
-                    },
-                    "soft_delete": {
-                        "type": "boolean",
-                        "description": "If true, mark as inactive instead of hard delete (default: false)"
+                        "description": "Only backfill documents of this Rust crate (optional)"
                     },
-                    "verify_cleanup": {
-                        "type": "boolean",
-                        "description": "Perform comprehensive cleanup verification after deletion (default: true)"
-                    },
-                    "dry_run": {
-                        "type": "boolean",
-                        "description": "Show what would be removed without actually deleting (default: false)"
+                    "batch_size": {
+                        "type": "integer",
+                        "description": "Documents embedded per transaction (default: 50, max: 500)",
+                        "minimum": 1,
+                        "maximum": 500
                     },
-                    "force": {
-                        "type": "boolean",
-                        "description": "Force removal even if crate has dependencies or references (default: false)"
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum documents to process in this run (default: 1000)",
+                        "minimum": 1
                     }
                 },
-                "required": ["name"]
+                "required": [],
+                "additionalProperties": false
             }
         })
     }
 
     async fn execute(&self, arguments: Value) -> Result<String> {
-        let crate_name = arguments
-            .get("name")
-            .and_then(|n| n.as_str())
-            .or_else(|| arguments.get("crate_name").and_then(|n| n.as_str()))
-            .ok_or_else(|| anyhow!("Missing required 'name' parameter"))?;
-
-        let soft_delete = arguments
-            .get("soft_delete")
-            .and_then(Value::as_bool)
-            .unwrap_or(false);
-        let verify_cleanup = arguments
-            .get("verify_cleanup")
-            .and_then(Value::as_bool)
-            .unwrap_or(true);
-        let dry_run = arguments
-            .get("dry_run")
-            .and_then(Value::as_bool)
-            .unwrap_or(false);
-        let force = arguments
-            .get("force")
-            .and_then(Value::as_bool)
-            .unwrap_or(false);
-
-        // Validate crate name
-        if crate_name.is_empty() {
-            return Err(anyhow!("Crate name cannot be empty"));
-        }
-
-        // Check if crate exists and get preliminary info
-        let crate_info = CrateQueries::find_crate_by_name(self.db_pool.pool(), crate_name).await?;
-        let Some(_existing_crate) = crate_info else {
-            return Ok(format!("Crate '{}' not found in the system.", crate_name));
-        };
-
-        // Perform dependency check if not forced
-        if !force {
-            if let Some(dependencies) = self.check_crate_dependencies(crate_name).await? {
-                return Ok(format!(
-                    "Crate '{}' has dependencies or references and cannot be safely removed. Use force=true to override.\nDependencies found: {}",
-                    crate_name,
-                    dependencies.join(", ")
-                ));
-            }
-        }
-
-        // If dry run, show what would be removed
-        if dry_run {
-            return self.perform_dry_run(crate_name, soft_delete).await;
-        }
-
-        // Perform actual removal
-        let result = if soft_delete {
-            self.perform_soft_deletion(crate_name, verify_cleanup).await
-        } else {
-            self.perform_cascade_deletion(crate_name, verify_cleanup)
-                .await
-        };
-
-        // Add enhanced reporting
-        match result {
-            Ok(message) => {
-                if verify_cleanup {
-                    match self.verify_complete_cleanup(crate_name).await {
-                        Ok(verification_msg) => Ok(format!("{}\n\n{}", message, verification_msg)),
-                        Err(e) => Ok(format!(
-                            "{}\n\nWarning: Cleanup verification failed: {}",
-                            message, e
-                        )),
-                    }
-                } else {
-                    Ok(message)
-                }
-            }
-            Err(e) => Err(e),
-        }
-    }
-}
-
-impl RemoveRustCrateTool {
-    /// Perform cascade deletion with full transaction support and cleanup verification
-    async fn perform_cascade_deletion(
-        &self,
-        crate_name: &str,
-        _verify_cleanup: bool,
-    ) -> Result<String> {
-        let mut tx = self.db_pool.pool().begin().await?;
-
-        // Find all documents for this crate first (for count reporting)
-        let doc_count = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM documents WHERE doc_type = 'rust' AND (metadata->>'crate_name' = $1 OR source_name = $1)"
-        )
-        .bind(crate_name)
-        .fetch_one(&mut *tx)
-        .await?;
-
-        // Delete documents first (this will cascade to embeddings if there are foreign key constraints)
-        let documents_deleted = sqlx::query(
-            "DELETE FROM documents WHERE doc_type = 'rust' AND (metadata->>'crate_name' = $1 OR source_name = $1)"
-        )
-        .bind(crate_name)
-        .execute(&mut *tx)
-        .await?;
-
-        // Commit transaction (no crates table to delete from)
-        tx.commit().await?;
-
-        tracing::info!(
-            "Successfully deleted crate '{}': {} documents removed",
-            crate_name,
-            documents_deleted.rows_affected()
-        );
-
-        Ok(format!(
-            "Crate '{}' removed successfully. Deleted {} documents and all associated embeddings.",
-            crate_name, doc_count
-        ))
+        self.execute_with_context(arguments, &ExecutionContext::default())
+            .await
     }
 
-    /// Perform soft deletion by marking documents as inactive
-    async fn perform_soft_deletion(
+    async fn execute_with_context(
         &self,
-        crate_name: &str,
-        _verify_cleanup: bool,
+        arguments: Value,
+        context: &ExecutionContext,
     ) -> Result<String> {
-        let mut tx = self.db_pool.pool().begin().await?;
+        let doc_type = arguments.get("doc_type").and_then(Value::as_str);
+        let crate_name = arguments.get("crate_name").and_then(Value::as_str);
+        let batch_size = arguments
+            .get("batch_size")
+            .and_then(Value::as_i64)
+            .unwrap_or(50)
+            .clamp(1, 500);
+        let limit = arguments
+            .get("limit")
+            .and_then(Value::as_i64)
+            .unwrap_or(1000)
+            .max(1);
 
-        // Count documents first
-        let doc_count = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM documents WHERE doc_type = 'rust' AND (metadata->>'crate_name' = $1 OR source_name = $1)"
-        )
-        .bind(crate_name)
-        .fetch_one(&mut *tx)
-        .await?;
+        let pool = self.db_pool.pool();
 
-        if doc_count == 0 {
-            tx.rollback().await?;
-            return Ok(format!("Crate '{}' not found in the system.", crate_name));
+        if let Err(e) = sqlx::query("SELECT '[1,2,3]'::vector(3)")
+            .execute(pool)
+            .await
+        {
+            return Err(anyhow!(
+                "Vector extension not available, cannot backfill embeddings: {}",
+                e
+            ));
         }
+        if !embed::vector_writes_enabled() {
+            return Err(anyhow!(
+                "Embedding writes are disabled: the configured embedding dimension does not match the database column"
+            ));
+        }
+        let embedding_metadata = embedding_metadata(self.embedding_client.as_ref());
+        let cache = CachedEmbeddingClient::new(self.embedding_client.clone(), pool.clone());
 
-        // Update metadata to mark as inactive
-        let updated = sqlx::query(
-            r#"
-            UPDATE documents 
-            SET metadata = jsonb_set(metadata, '{status}', '"inactive"', true),
-                updated_at = CURRENT_TIMESTAMP
-            WHERE doc_type = 'rust' 
-            AND (metadata->>'crate_name' = $1 OR source_name = $1)
-            "#,
-        )
-        .bind(crate_name)
-        .execute(&mut *tx)
-        .await?;
-
-        tx.commit().await?;
-
-        tracing::info!(
-            "Successfully marked crate '{}' as inactive: {} documents updated",
-            crate_name,
-            updated.rows_affected()
-        );
-
-        Ok(format!(
-            "Crate '{}' marked as inactive. {} documents remain in the system but are not searchable.",
-            crate_name, doc_count
-        ))
-    }
-
-    /// Check for dependencies or references to this crate
-    async fn check_crate_dependencies(&self, crate_name: &str) -> Result<Option<Vec<String>>> {
-        // Check if any other documents reference this crate in their content or metadata
-        let references = sqlx::query_scalar::<_, String>(
+        let pending = sqlx::query_scalar::<_, i64>(
             r"
-            SELECT DISTINCT source_name 
-            FROM documents 
-            WHERE doc_type = 'rust' 
-            AND source_name != $1 
-            AND (content ILIKE '%' || $1 || '%' OR metadata::text ILIKE '%' || $1 || '%')
-            LIMIT 10
+            SELECT COUNT(*) FROM documents
+            WHERE embedding IS NULL AND content <> ''
+            AND ($1::text IS NULL OR doc_type = $1)
+            AND ($2::text IS NULL OR metadata->>'crate_name' = $2)
             ",
         )
+        .bind(doc_type)
         .bind(crate_name)
-        .fetch_all(self.db_pool.pool())
+        .fetch_one(pool)
         .await?;
+        let target = pending.min(limit);
 
-        if references.is_empty() {
-            Ok(None)
-        } else {
-            Ok(Some(references))
+        if target == 0 {
+            return Ok("No documents are missing embeddings.".to_string());
         }
-    }
-
-    /// Perform dry run showing what would be removed
-    async fn perform_dry_run(&self, crate_name: &str, soft_delete: bool) -> Result<String> {
-        let doc_count = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM documents WHERE doc_type = 'rust' AND (metadata->>'crate_name' = $1 OR source_name = $1)"
-        )
-        .bind(crate_name)
-        .fetch_one(self.db_pool.pool())
-        .await?;
 
-        let embedding_count = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM documents WHERE doc_type = 'rust' AND (metadata->>'crate_name' = $1 OR source_name = $1) AND embedding IS NOT NULL"
-        )
-        .bind(crate_name)
-        .fetch_one(self.db_pool.pool())
-        .await?;
+        let job_scope = crate_name.or(doc_type).unwrap_or("*");
+        let job = CrateJobQueries::create_job(pool, job_scope, "backfill_embeddings").await?;
+        CrateJobQueries::update_job_status(pool, job.id, JobStatus::Running, Some(0), None).await?;
 
-        let operation = if soft_delete {
-            "mark as inactive"
-        } else {
-            "permanently delete"
+        // Marks the job failed if the call is timed out before finishing
+        let mut guard = BackfillJobGuard {
+            pool: pool.clone(),
+            job_id: job.id,
+            processed: 0,
+            finished: false,
         };
+        let policy = RetryPolicy::new();
+        let outcome: Result<usize> = async {
+            let mut failed_ids: Vec<Uuid> = Vec::new();
+            while guard.processed + (failed_ids.len() as i64) < target {
+                // Stop between batches if the client cancelled the call
+                if context.is_cancelled() {
+                    return Err(RequestCancelled.into());
+                }
 
-        Ok(format!(
-            "🔍 **Dry Run for Crate '{}'**\n\n\
-            Operation: {}\n\
-            - {} documents would be affected\n\
-            - {} embeddings would be affected\n\
-            - Database storage would be impacted\n\n\
-            To execute this operation, run the command again with dry_run=false",
-            crate_name, operation, doc_count, embedding_count
-        ))
-    }
+                let remaining = target - guard.processed - failed_ids.len() as i64;
+                let batch = sqlx::query_as::<_, (Uuid, String)>(
+                    r"
+                    SELECT id, content FROM documents
+                    WHERE embedding IS NULL AND content <> ''
+                    AND ($1::text IS NULL OR doc_type = $1)
+                    AND ($2::text IS NULL OR metadata->>'crate_name' = $2)
+                    AND NOT (id = ANY($3))
+                    ORDER BY created_at
+                    LIMIT $4
+                    ",
+                )
+                .bind(doc_type)
+                .bind(crate_name)
+                .bind(&failed_ids)
+                .bind(batch_size.min(remaining))
+                .fetch_all(pool)
+                .await?;
 
-    /// Verify complete cleanup after deletion
-    async fn verify_complete_cleanup(&self, crate_name: &str) -> Result<String> {
-        // Check for any remaining documents
-        let remaining_docs = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM documents WHERE doc_type = 'rust' AND (metadata->>'crate_name' = $1 OR source_name = $1)"
-        )
-        .bind(crate_name)
-        .fetch_one(self.db_pool.pool())
-        .await?;
+                if batch.is_empty() {
+                    break;
+                }
 
-        // Check for any remaining embeddings
-        let remaining_embeddings = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM documents WHERE doc_type = 'rust' AND (metadata->>'crate_name' = $1 OR source_name = $1) AND embedding IS NOT NULL"
-        )
-        .bind(crate_name)
-        .fetch_one(self.db_pool.pool())
-        .await?;
+                let contents: Vec<&str> =
+                    batch.iter().map(|(_, content)| content.as_str()).collect();
+                let mut cached = cache.lookup_many(&contents).await;
+                let mut fresh = Vec::new();
+
+                let mut embeddings = Vec::with_capacity(batch.len());
+                for (id, content) in &batch {
+                    let hash = content_hash(content);
+                    if let Some(embedding) = cached.get(&hash) {
+                        embeddings.push((*id, pgvector::Vector::from(embedding.clone())));
+                        continue;
+                    }
+                    match self.embed_with_backoff(&policy, content).await {
+                        Ok(embedding) => {
+                            cached.insert(hash.clone(), embedding.clone());
+                            fresh.push((hash, embedding.clone()));
+                            embeddings.push((*id, pgvector::Vector::from(embedding)));
+                        }
+                        Err(e) => {
+                            tracing::warn!(
+                                "Failed to generate embedding for document {}: {}",
+                                id,
+                                e
+                            );
+                            failed_ids.push(*id);
+                        }
+                    }
+                }
+                cache.store_many(&fresh).await;
 
-        // Check database integrity
-        let total_rust_docs =
-            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM documents WHERE doc_type = 'rust'")
-                .fetch_one(self.db_pool.pool())
+                let mut tx = pool.begin().await?;
+                for (id, vector) in &embeddings {
+                    // Clears the embedding_error left by a failed ingestion attempt
+                    sqlx::query(
+                        "UPDATE documents SET embedding = $1, metadata = (metadata - 'embedding_error') || $3 WHERE id = $2",
+                    )
+                    .bind(vector)
+                    .bind(id)
+                    .bind(&embedding_metadata)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                tx.commit().await?;
+                guard.processed += embeddings.len() as i64;
+
+                let progress = ((guard.processed + failed_ids.len() as i64) * 100 / target) as i32;
+                CrateJobQueries::update_job_status(
+                    pool,
+                    job.id,
+                    JobStatus::Running,
+                    Some(progress.min(99)),
+                    None,
+                )
                 .await?;
+            }
+            Ok(failed_ids.len())
+        }
+        .await;
+
+        let processed = guard.processed;
+        let failed = match outcome {
+            Ok(failed) => failed,
+            Err(e) => {
+                let (status, message) = if e.is::<RequestCancelled>() {
+                    (
+                        JobStatus::Cancelled,
+                        format!("cancelled after {processed} documents"),
+                    )
+                } else {
+                    (
+                        JobStatus::Failed,
+                        format!("failed after {processed} documents: {e}"),
+                    )
+                };
+                if let Err(update) =
+                    CrateJobQueries::update_job_status(pool, job.id, status, None, Some(&message))
+                        .await
+                {
+                    tracing::warn!("Failed to finalize backfill job {}: {}", job.id, update);
+                }
+                guard.finished = true;
+                return Err(e);
+            }
+        };
+        let error = (failed > 0).then(|| format!("{} documents failed to embed", failed));
+        CrateJobQueries::update_job_status(
+            pool,
+            job.id,
+            JobStatus::Completed,
+            Some(100),
+            error.as_deref(),
+        )
+        .await?;
+        guard.finished = true;
 
-        if remaining_docs == 0 && remaining_embeddings == 0 {
-            Ok(format!(
-                "✅ **Cleanup Verification: PASSED**\n\
-                - ✅ No remaining documents found\n\
-                - ✅ No remaining embeddings found\n\
-                - ℹ️  Total Rust documents in system: {}\n\
-                - ✅ Database integrity maintained",
-                total_rust_docs
-            ))
-        } else {
-            Ok(format!(
-                "⚠️ **Cleanup Verification: INCOMPLETE**\n\
-                - ⚠️ {} documents still remain\n\
-                - ⚠️ {} embeddings still remain\n\
-                - ℹ️ Total Rust documents in system: {}\n\
-                - 🔧 Manual cleanup may be required",
-                remaining_docs, remaining_embeddings, total_rust_docs
-            ))
+        let mut output = String::new();
+        let _ = writeln!(
+            &mut output,
+            "🧮 Embedding backfill finished (job {})",
+            job.id
+        );
+        let _ = writeln!(&mut output, "  • Processed: {}", processed);
+        let _ = writeln!(&mut output, "  • Failed: {}", failed);
+        let _ = writeln!(&mut output, "  • Still missing: {}", pending - processed);
+        if pending - processed > 0 {
+            output.push_str("Run again to continue; documents with embeddings are skipped.\n");
         }
+
+        Ok(output)
     }
 }
 
 /// List Rust crates tool with pagination
 pub struct ListRustCratesTool {
-    db_pool: DatabasePool,
+    storage: CrateStorage,
 }
 
 impl ListRustCratesTool {
     /// Create a new list crates tool
-    pub fn new(db_pool: DatabasePool) -> Self {
-        Self { db_pool }
+    pub fn new(storage: impl Into<CrateStorage>) -> Self {
+        Self {
+            storage: storage.into(),
+        }
     }
 }
 
@@ -1008,7 +3440,7 @@ impl Tool for ListRustCratesTool {
     fn definition(&self) -> Value {
         json!({
             "name": "list_rust_crates",
-            "description": "List all Rust crates in the documentation system with pagination, filtering, and statistics.",
+            "description": "List all Rust crates in the documentation system with pagination, sorting, filtering, and statistics. Use page for numbered pages or cursor (returned as next_cursor) to walk large listings.",
             "inputSchema": {
                 "type": "object",
                 "properties": {
@@ -1023,11 +3455,29 @@ impl Tool for ListRustCratesTool {
                         "minimum": 1,
                         "maximum": 100
                     },
+                    "cursor": {
+                        "type": "string",
+                        "description": "Opaque cursor from a previous page's next_cursor; continues after that page in the same sort and replaces page"
+                    },
+                    "sort_by": {
+                        "type": "string",
+                        "description": "Sort column (default: name)",
+                        "enum": ["name", "last_updated", "total_docs", "total_tokens"]
+                    },
+                    "sort_order": {
+                        "type": "string",
+                        "description": "Sort direction (default: asc)",
+                        "enum": ["asc", "desc"]
+                    },
                     "status_filter": {
                         "type": "string",
-                        "description": "Filter by crate status",
+                        "description": "Filter by crate status (default: active)",
                         "enum": ["active", "inactive", "updating", "failed"]
                     },
+                    "include_inactive": {
+                        "type": "boolean",
+                        "description": "Include soft-deleted crates alongside active ones (default: false)"
+                    },
                     "name_pattern": {
                         "type": "string",
                         "description": "Search pattern for crate names (case-insensitive)"
@@ -1037,11 +3487,16 @@ impl Tool for ListRustCratesTool {
                         "description": "Include comprehensive system statistics (default: false)"
                     }
                 },
-                "required": []
+                "required": [],
+                "additionalProperties": false
             }
         })
     }
 
+    fn response_soft_cap(&self) -> Option<usize> {
+        Some(response_soft_cap())
+    }
+
     async fn execute(&self, arguments: Value) -> Result<String> {
         let page = arguments
             .get("page")
@@ -1057,8 +3512,49 @@ impl Tool for ListRustCratesTool {
             .get("include_stats")
             .and_then(Value::as_bool)
             .unwrap_or(false);
+        let include_inactive = arguments
+            .get("include_inactive")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
 
-        let pagination = PaginationParams::new(page, limit);
+        let sort_by = match arguments.get("sort_by").and_then(Value::as_str) {
+            Some(value) => Some(CrateSortField::parse(value).ok_or_else(|| {
+                anyhow!(
+                    "Invalid sort_by: {}. Use name, last_updated, total_docs or total_tokens.",
+                    value
+                )
+            })?),
+            None => None,
+        };
+        let sort_order = match arguments.get("sort_order").and_then(Value::as_str) {
+            Some(value) => Some(
+                SortOrder::parse(value)
+                    .ok_or_else(|| anyhow!("Invalid sort_order: {}. Use asc or desc.", value))?,
+            ),
+            None => None,
+        };
+        let cursor = match arguments.get("cursor").and_then(Value::as_str) {
+            Some(value) => {
+                Some(CrateCursor::decode(value).ok_or_else(|| anyhow!("Invalid cursor"))?)
+            }
+            None => None,
+        };
+
+        let mut pagination = PaginationParams::new(page, limit)
+            .with_sort(sort_by.unwrap_or_default(), sort_order.unwrap_or_default());
+        if let Some(cursor) = cursor {
+            // A cursor only continues the ordering it was issued for
+            if sort_by.is_some_and(|s| s != cursor.sort_by)
+                || sort_order.is_some_and(|o| o != cursor.sort_order)
+            {
+                return Err(anyhow!(
+                    "Cursor was issued for sort_by={} sort_order={}; omit sort_by/sort_order or start again without cursor",
+                    cursor.sort_by.as_str(),
+                    cursor.sort_order.as_str()
+                ));
+            }
+            pagination = pagination.with_cursor(cursor);
+        }
 
         // Validate status filter (we only support active/inactive now)
         if let Some(status) = status_filter {
@@ -1070,30 +3566,53 @@ impl Tool for ListRustCratesTool {
             }
         }
 
+        let status = match status_filter {
+            Some("inactive") => CrateStatusFilter::Inactive,
+            Some(_) => CrateStatusFilter::Active,
+            None if include_inactive => CrateStatusFilter::All,
+            None => CrateStatusFilter::Active,
+        };
+
         // Get paginated results
-        let response =
-            CrateQueries::list_crates(self.db_pool.pool(), &pagination, name_pattern).await?;
+        let response = self
+            .storage
+            .store()
+            .list_crates_with_status(&pagination, name_pattern, status)
+            .await?;
 
         // Optionally get comprehensive statistics
         let stats = if include_stats {
-            Some(CrateQueries::get_crate_statistics(self.db_pool.pool()).await?)
+            Some(self.storage.store().get_crate_statistics().await?)
         } else {
             None
         };
 
         // Format response
-        let mut output = format!(
-            "Rust Crates (Page {} of {}, {} total items):\n\n",
-            response.page, response.total_pages, response.total_items
-        );
+        let mut output = if pagination.cursor.is_some() {
+            format!(
+                "Rust Crates (continued, {} total items, sorted by {} {}):\n\n",
+                response.total_items,
+                pagination.sort_by.as_str(),
+                pagination.sort_order.as_str()
+            )
+        } else {
+            format!(
+                "Rust Crates (Page {} of {}, {} total items, sorted by {} {}):\n\n",
+                response.page,
+                response.total_pages,
+                response.total_items,
+                pagination.sort_by.as_str(),
+                pagination.sort_order.as_str()
+            )
+        };
 
         // Add comprehensive statistics if requested
         if let Some(stats) = &stats {
             output.push_str("📊 **System Statistics:**\n");
             let _ = writeln!(
                 &mut output,
-                "   Total Crates: {} (Active: {})",
-                stats.total_crates, stats.active_crates
+                "   Total Crates: {} (Active: {}, Versions: {})",
+                stats.total_crates, stats.active_crates, stats.total_versions
             );
             let _ = writeln!(
                 &mut output,
@@ -1105,6 +3624,11 @@ impl Tool for ListRustCratesTool {
                 "   Total Tokens: {}",
                 stats.total_tokens_managed
             );
+            let _ = writeln!(
+                &mut output,
+                "   Embedded Documents: {} ({:.1}%)",
+                stats.docs_with_embeddings, stats.embedding_coverage_pct
+            );
             let _ = writeln!(
                 &mut output,
                 "   Average Docs per Crate: {:.1}",
@@ -1123,13 +3647,22 @@ impl Tool for ListRustCratesTool {
         for crate_info in &response.items {
             let _ = writeln!(
                 &mut output,
-                "📦 **{}** (v{})\n   Docs: {} | Tokens: {} | Updated: {}",
+                "📦 **{}** (v{})\n   Docs: {} | Tokens: {} | Embedded: {:.1}% | Updated: {}",
                 crate_info.name,
                 crate_info.version,
                 crate_info.total_docs,
                 crate_info.total_tokens,
+                crate_info.embedding_coverage_pct,
                 crate_info.last_updated.format("%Y-%m-%d %H:%M UTC")
             );
+            if crate_info.has_low_embedding_coverage() {
+                let _ = writeln!(
+                    &mut output,
+                    "   ⚠️ Low embedding coverage: semantic search misses {} documents; run backfill_embeddings with crate_name={}",
+                    i64::from(crate_info.total_docs) - crate_info.embedded_docs,
+                    crate_info.name
+                );
+            }
 
             if let Some(description) = &crate_info.description {
                 let _ = writeln!(&mut output, "   Description: {}", description);
@@ -1141,15 +3674,21 @@ impl Tool for ListRustCratesTool {
         // Add pagination info
         if response.has_previous || response.has_next {
             output.push_str("Navigation:\n");
-            if response.has_previous {
-                output.push_str("  ← Use page=");
-                output.push_str(&(response.page - 1).to_string());
-                output.push_str(" for previous\n");
+            // Cursor pages only go forward
+            if pagination.cursor.is_none() {
+                if response.has_previous {
+                    output.push_str("  ← Use page=");
+                    output.push_str(&(response.page - 1).to_string());
+                    output.push_str(" for previous\n");
+                }
+                if response.has_next {
+                    output.push_str("  → Use page=");
+                    output.push_str(&(response.page + 1).to_string());
+                    output.push_str(" for next\n");
+                }
             }
-            if response.has_next {
-                output.push_str("  → Use page=");
-                output.push_str(&(response.page + 1).to_string());
-                output.push_str(" for next\n");
+            if let Some(next_cursor) = &response.next_cursor {
+                let _ = writeln!(&mut output, "  → Use cursor={} for next", next_cursor);
             }
         }
 
@@ -1159,16 +3698,17 @@ impl Tool for ListRustCratesTool {
 
 /// Check Rust status tool for health monitoring
 pub struct CheckRustStatusTool {
-    db_pool: DatabasePool,
+    storage: CrateStorage,
     job_processor: CrateJobProcessor,
 }
 
 impl CheckRustStatusTool {
     /// Create a new check status tool
-    pub fn new(db_pool: DatabasePool) -> Self {
-        let job_processor = CrateJobProcessor::new(db_pool.clone());
+    pub fn new(storage: impl Into<CrateStorage>) -> Self {
+        let storage = storage.into();
+        let job_processor = CrateJobProcessor::new(storage.clone());
         Self {
-            db_pool,
+            storage,
             job_processor,
         }
     }
@@ -1179,14 +3719,22 @@ impl Tool for CheckRustStatusTool {
     fn definition(&self) -> Value {
         json!({
             "name": "check_rust_status",
-            "description": "Check system health and get comprehensive statistics about Rust crate management, including job status tracking and performance metrics. Supports detailed reporting and health monitoring.",
+            "description": "Check system health and get comprehensive statistics about Rust crate management, including job status tracking and performance metrics. Supports detailed reporting and health monitoring. Pass crate_name to get a per-crate report (versions, documents, tokens, embedding coverage, last ingestion job) instead of global statistics. job_id also tracks the jobs of the npm and Python package tools.",
             "inputSchema": {
                 "type": "object",
                 "properties": {
+                    "crate_name": {
+                        "type": "string",
+                        "description": "Narrow the report to a single crate (optional)"
+                    },
                     "job_id": {
                         "type": "string",
                         "description": "Specific job ID to check status (optional)"
                     },
+                    "include_events": {
+                        "type": "boolean",
+                        "description": "With job_id, list the job's last 20 events: status changes, progress, committed batches, retries and errors (default: false)"
+                    },
                     "include_active_jobs": {
                         "type": "boolean",
                         "description": "Include list of active/recent jobs (default: true)"
@@ -1203,18 +3751,36 @@ impl Tool for CheckRustStatusTool {
                         "type": "boolean",
                         "description": "Include comprehensive health checks and system diagnostics (default: true)"
                     },
+                    "include_cost_analysis": {
+                        "type": "boolean",
+                        "description": "Include embedding and LLM spend of crate jobs over the last 30 days, by model, crate and day (default: false)"
+                    },
                     "detailed_report": {
                         "type": "boolean",
                         "description": "Generate detailed report with all available metrics and analysis (default: false)"
                     }
                 },
-                "required": []
+                "required": [],
+                "additionalProperties": false
             }
         })
     }
 
+    fn response_soft_cap(&self) -> Option<usize> {
+        Some(response_soft_cap())
+    }
+
     async fn execute(&self, arguments: Value) -> Result<String> {
         let job_id = arguments.get("job_id").and_then(Value::as_str);
+        let include_events = arguments
+            .get("include_events")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let crate_name = arguments
+            .get("crate_name")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|name| !name.is_empty());
         let include_active_jobs = arguments
             .get("include_active_jobs")
             .and_then(Value::as_bool)
@@ -1231,6 +3797,10 @@ impl Tool for CheckRustStatusTool {
             .get("include_health_checks")
             .and_then(Value::as_bool)
             .unwrap_or(true);
+        let include_cost_analysis = arguments
+            .get("include_cost_analysis")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
         let detailed_report = arguments
             .get("detailed_report")
             .and_then(Value::as_bool)
@@ -1246,7 +3816,11 @@ impl Tool for CheckRustStatusTool {
             if let Some(job) = self.job_processor.get_job_status(job_id).await? {
                 let _ = writeln!(&mut output, "Job Status: {}", job_id);
                 output.push('\n');
-                let _ = writeln!(&mut output, "  Crate: {}", job.crate_name);
+                let subject = match PackageEcosystem::for_operation(&job.operation) {
+                    Some(_) => "Package",
+                    None => "Crate",
+                };
+                let _ = writeln!(&mut output, "  {subject}: {}", job.crate_name);
                 let _ = writeln!(&mut output, "  Operation: {}", job.operation);
                 let _ = writeln!(&mut output, "  Status: {:?}", job.status);
                 if let Some(progress) = job.progress {
@@ -1264,9 +3838,126 @@ impl Tool for CheckRustStatusTool {
                         finished.format("%Y-%m-%d %H:%M:%S UTC")
                     );
                 }
+                if job.attempts > 0 {
+                    let _ = writeln!(
+                        &mut output,
+                        "  Attempts: {}/{}",
+                        job.attempts, job.max_attempts
+                    );
+                }
+                if let Some(next_run_at) = job.next_run_at {
+                    let _ = writeln!(
+                        &mut output,
+                        "  Next Retry: {}",
+                        next_run_at.format("%Y-%m-%d %H:%M:%S UTC")
+                    );
+                }
+                if let Some(crawl) = job.details.as_ref().and_then(|d| d.get("crawl")) {
+                    let _ = writeln!(
+                        &mut output,
+                        "  Crawl: {} pages fetched, {} skipped in {}s",
+                        crawl
+                            .get("pages_fetched")
+                            .and_then(Value::as_u64)
+                            .unwrap_or(0),
+                        crawl
+                            .get("pages_skipped")
+                            .and_then(Value::as_u64)
+                            .unwrap_or(0),
+                        crawl
+                            .get("duration_secs")
+                            .and_then(Value::as_u64)
+                            .unwrap_or(0)
+                    );
+                }
+                if let Some(unchanged) = job
+                    .details
+                    .as_ref()
+                    .and_then(|d| d.get("unchanged_pages"))
+                    .and_then(Value::as_u64)
+                {
+                    let _ = writeln!(&mut output, "  Skipped (unchanged): {} pages", unchanged);
+                }
+                if let Some(duplicates) = job
+                    .details
+                    .as_ref()
+                    .and_then(|d| d.get("duplicates_skipped"))
+                    .and_then(Value::as_u64)
+                    .filter(|&n| n > 0)
+                {
+                    let _ = writeln!(&mut output, "  Skipped (duplicate): {} pages", duplicates);
+                }
+                if let Some(embeddings) = job
+                    .details
+                    .as_ref()
+                    .and_then(|d| d.get("embeddings"))
+                    .and_then(|e| serde_json::from_value::<embed::EmbeddingStats>(e.clone()).ok())
+                {
+                    let _ = writeln!(&mut output, "  Embeddings: {}", embeddings);
+                }
+                if let Some(details) = &job.details {
+                    if let Some(built) = details
+                        .get("built_features")
+                        .and_then(|b| serde_json::from_value::<BuiltFeatures>(b.clone()).ok())
+                    {
+                        let features = if built.all_features {
+                            "all features".to_string()
+                        } else if built.features.is_empty() {
+                            "no features".to_string()
+                        } else {
+                            built.features.join(", ")
+                        };
+                        let _ = writeln!(&mut output, "  Docs built with: {}", features);
+                    }
+                    for warning in job_warnings(details) {
+                        let _ = writeln!(&mut output, "  Warning: {}", warning);
+                    }
+                    if let (Some(deleted), Some(total)) = (
+                        details.get("documents_deleted").and_then(Value::as_i64),
+                        details.get("documents_total").and_then(Value::as_i64),
+                    ) {
+                        let _ = writeln!(&mut output, "  Deleted: {}/{} documents", deleted, total);
+                    }
+                    if let Some(verification) =
+                        details.get("cleanup_verification").and_then(Value::as_str)
+                    {
+                        let _ = writeln!(&mut output, "  Cleanup:\n{}", verification);
+                    }
+                }
+                if job.is_dead_lettered() {
+                    let _ = writeln!(
+                        &mut output,
+                        "  Dead-lettered: yes (use retry_rust_job to requeue)"
+                    );
+                }
                 if let Some(error) = &job.error {
                     let _ = writeln!(&mut output, "  Error: {}", error);
                 }
+                if include_events {
+                    let events = self.storage.store().find_job_events(job_id, 20).await?;
+                    let _ = writeln!(&mut output, "  Recent Events ({}):", events.len());
+                    for event in events.iter().rev() {
+                        let _ = write!(
+                            &mut output,
+                            "    {} {}",
+                            event.created_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                            event.event_type
+                        );
+                        match (&event.old_status, &event.new_status) {
+                            (Some(old), Some(new)) if old != new => {
+                                let _ = write!(&mut output, " {:?} -> {:?}", old, new);
+                            }
+                            _ => {}
+                        }
+                        if let Some(progress) = event.progress {
+                            let _ = write!(&mut output, " ({}%)", progress);
+                        }
+                        if let Some(detail) = &event.detail {
+                            let _ = write!(&mut output, " {}", detail);
+                        }
+                        output.push('\n');
+                    }
+                }
                 output.push('\n');
             } else {
                 let _ = writeln!(&mut output, "Job {} not found.", job_id);
@@ -1274,22 +3965,37 @@ impl Tool for CheckRustStatusTool {
             }
         }
 
-        // Detect stuck crate jobs (> 1 hour without updates)
-        let stuck_crate_jobs: i64 = sqlx::query_scalar(
-            "SELECT COUNT(*) FROM crate_jobs WHERE status = 'running' AND updated_at < NOW() - INTERVAL '1 hour'",
-        )
-        .fetch_one(self.db_pool.pool())
-        .await
-        .unwrap_or(0);
+        // Per-crate report replaces the global statistics
+        if let Some(crate_name) = crate_name {
+            output.push_str(&self.generate_crate_report(crate_name).await?);
+            return Ok(output);
+        }
+
+        // Detect stuck crate and ingest jobs (> 1 hour without updates)
+        let (stuck_crate_jobs, stuck_ingest_jobs): (i64, i64) = match self.storage.as_pool() {
+            Some(db_pool) => (
+                sqlx::query_scalar(
+                    "SELECT COUNT(*) FROM crate_jobs WHERE status = 'running' AND updated_at < NOW() - INTERVAL '1 hour'",
+                )
+                .fetch_one(db_pool.pool())
+                .await
+                .unwrap_or(0),
+                IngestJobQueries::count_stuck_jobs(db_pool.pool())
+                    .await
+                    .unwrap_or(0),
+            ),
+            None => (0, 0),
+        };
 
         // Get overall system statistics
-        let stats = CrateQueries::get_crate_statistics(self.db_pool.pool()).await?;
+        let stats = self.storage.store().get_crate_statistics().await?;
 
         output.push_str("🦀 Rust Crate Management System Status\n\n");
 
         let _ = writeln!(&mut output, "📊 **System Statistics:**");
         let _ = writeln!(&mut output, "  • Total Crates: {}", stats.total_crates);
         let _ = writeln!(&mut output, "  • Active Crates: {}", stats.active_crates);
+        let _ = writeln!(&mut output, "  • Crate Versions: {}", stats.total_versions);
         let _ = writeln!(
             &mut output,
             "  • Total Documents: {}",
@@ -1316,9 +4022,23 @@ impl Tool for CheckRustStatusTool {
 
         output.push('\n');
 
+        match self.generate_embedding_summary().await {
+            Ok(summary) => output.push_str(&summary),
+            Err(e) => {
+                let _ = writeln!(&mut output, "⚠️ **Embeddings:** Error - {}", e);
+            }
+        }
+        output.push('\n');
+
+        if let Some(run) = crate::auto_update::last_run() {
+            let _ = writeln!(&mut output, "🗓️ {}\n", run.summary(chrono::Utc::now()));
+        } else if crate::auto_update::AutoUpdateConfig::from_env().is_some() {
+            output.push_str("🗓️ auto-update: enabled, first run pending\n\n");
+        }
+
         // Show active/recent jobs if requested
         if include_active_jobs {
-            let active_jobs = CrateJobQueries::find_active_jobs(self.db_pool.pool()).await?;
+            let active_jobs = self.storage.store().find_active_jobs().await?;
 
             if !active_jobs.is_empty() {
                 output.push_str("🔄 **Active Jobs:**\n");
@@ -1331,34 +4051,63 @@ impl Tool for CheckRustStatusTool {
                     if let Some(progress) = job.progress {
                         let _ = write!(&mut output, " - {}%", progress);
                     }
+                    if let Some(next_run_at) = job.next_run_at {
+                        let _ = write!(
+                            &mut output,
+                            " - attempt {}/{} failed, retry at {}",
+                            job.attempts,
+                            job.max_attempts,
+                            next_run_at.format("%m-%d %H:%M")
+                        );
+                    }
                     output.push_str(")\n");
                 }
                 output.push('\n');
             }
 
+            // Dead-lettered jobs need an operator, so list them on their own
+            let dead_letter_jobs = self.storage.store().find_dead_letter_jobs(10).await?;
+            if !dead_letter_jobs.is_empty() {
+                output.push_str("☠️ **Dead-lettered Jobs:**\n");
+                for job in &dead_letter_jobs {
+                    let _ = writeln!(
+                        &mut output,
+                        "  • {} [{}] - {} attempts - {}",
+                        job.crate_name,
+                        job.id,
+                        job.attempts,
+                        job.error.as_deref().unwrap_or("unknown error")
+                    );
+                }
+                output.push_str("  Use retry_rust_job with a job_id to requeue.\n\n");
+            }
+
             // Show recent completed jobs
-            let all_jobs = sqlx::query_as::<_, CrateJob>(
-                "SELECT * FROM crate_jobs ORDER BY started_at DESC LIMIT 5",
-            )
-            .fetch_all(self.db_pool.pool())
-            .await?;
+            let all_jobs = self.storage.store().find_recent_jobs(5).await?;
 
             let recent_completed: Vec<_> = all_jobs
                 .into_iter()
                 .filter(|job| matches!(job.status, JobStatus::Completed | JobStatus::Failed))
+                .filter(|job| !job.is_dead_lettered())
                 .take(3)
                 .collect();
 
             if !recent_completed.is_empty() {
                 output.push_str("📋 **Recent Jobs:**\n");
                 for job in recent_completed {
+                    let warnings = job.details.as_ref().map_or(0, |d| job_warnings(d).len());
                     let _ = writeln!(
                         &mut output,
-                        "  • {} - {} ({:?}) - {}",
+                        "  • {} - {} ({:?}) - {}{}",
                         job.crate_name,
                         job.operation,
                         job.status,
-                        job.started_at.format("%m-%d %H:%M")
+                        job.started_at.format("%m-%d %H:%M"),
+                        if warnings > 0 {
+                            format!(" - ⚠️ {warnings} warning(s)")
+                        } else {
+                            String::new()
+                        }
                     );
                 }
                 output.push('\n');
@@ -1373,6 +4122,14 @@ impl Tool for CheckRustStatusTool {
                 );
                 output.push('\n');
             }
+            if stuck_ingest_jobs > 0 {
+                let _ = writeln!(
+                    &mut output,
+                    "⚠️  Stuck ingest jobs (no update > 1h): {} (see check_ingest_status)",
+                    stuck_ingest_jobs
+                );
+                output.push('\n');
+            }
         }
 
         // Add enhanced reporting sections based on parameters
@@ -1404,6 +4161,20 @@ impl Tool for CheckRustStatusTool {
             }
         }
 
+        if include_cost_analysis || detailed_report {
+            match self.generate_cost_analysis().await {
+                Ok(analysis) => {
+                    output.push_str("💰 **Cost Analysis (last 30 days):**\n");
+                    output.push_str(&analysis);
+                    output.push('\n');
+                }
+                Err(e) => {
+                    let _ = writeln!(&mut output, "⚠️ **Cost Analysis:** Error - {}", e);
+                    output.push('\n');
+                }
+            }
+        }
+
         if include_health_checks || detailed_report {
             match self.perform_comprehensive_health_checks().await {
                 Ok(health_report) => {
@@ -1419,13 +4190,17 @@ impl Tool for CheckRustStatusTool {
         }
 
         // Database connectivity and performance check
+        output.push_str("🔍 **System Health:**\n");
+        let Some(db_pool) = self.storage.as_pool() else {
+            output.push_str("  ℹ️ Database: in-memory store, no PostgreSQL connection\n");
+            return Ok(output);
+        };
         let start_time = std::time::Instant::now();
         let db_health = sqlx::query("SELECT 1 as health")
-            .fetch_one(self.db_pool.pool())
+            .fetch_one(db_pool.pool())
             .await;
         let db_response_time = start_time.elapsed();
 
-        output.push_str("🔍 **System Health:**\n");
         match db_health {
             Ok(_) => {
                 let _ = writeln!(
@@ -1462,15 +4237,252 @@ impl Tool for CheckRustStatusTool {
 }
 
 impl CheckRustStatusTool {
+    /// Generate a status report for a single crate
+    async fn generate_crate_report(&self, crate_name: &str) -> Result<String> {
+        let mut report = String::new();
+
+        let store = self.storage.store();
+        let Some(info) = store.find_crate_by_name(crate_name).await? else {
+            let _ = writeln!(
+                &mut report,
+                "Crate '{}' not found. Use list_rust_crates to see available crates.",
+                crate_name
+            );
+            return Ok(report);
+        };
+
+        let inactive_docs = store
+            .count_crate_documents(crate_name, None, CrateStatusFilter::Inactive)
+            .await?;
+        let versions = store.find_crate_versions(crate_name).await?;
+
+        let _ = writeln!(&mut report, "🦀 Rust Crate Status: {}", info.name);
+        report.push('\n');
+        let _ = writeln!(&mut report, "📦 **Crate Details:**");
+        let _ = writeln!(&mut report, "  • Version(s): {}", info.version);
+        if versions.len() > 1 {
+            for version in &versions {
+                let _ = writeln!(
+                    &mut report,
+                    "    - {}: {} documents, updated {}",
+                    version.version,
+                    version.total_docs,
+                    version.last_updated.format("%Y-%m-%d %H:%M UTC")
+                );
+            }
+        }
+        let _ = writeln!(&mut report, "  • Documents: {}", info.total_docs);
+        let _ = writeln!(&mut report, "  • Tokens: {}", info.total_tokens);
+        let _ = writeln!(
+            &mut report,
+            "  • Embedding Coverage: {:.1}% ({}/{})",
+            info.embedding_coverage_pct, info.embedded_docs, info.total_docs
+        );
+        let _ = writeln!(
+            &mut report,
+            "  • Last Updated: {}",
+            info.last_updated.format("%Y-%m-%d %H:%M UTC")
+        );
+        if inactive_docs > 0 {
+            let _ = writeln!(
+                &mut report,
+                "  • Status: inactive ({} documents soft-deleted)",
+                inactive_docs
+            );
+        } else {
+            let _ = writeln!(&mut report, "  • Status: active");
+        }
+        report.push('\n');
+
+        if let Some(job) = store.find_latest_job_for_crate(crate_name).await? {
+            let duration = job.finished_at.unwrap_or_else(chrono::Utc::now) - job.started_at;
+            let _ = writeln!(&mut report, "📋 **Last Ingestion Job:**");
+            let _ = writeln!(&mut report, "  • Job ID: {}", job.id);
+            let _ = writeln!(&mut report, "  • Operation: {}", job.operation);
+            let _ = writeln!(&mut report, "  • Status: {:?}", job.status);
+            let _ = writeln!(
+                &mut report,
+                "  • Duration: {}s{}",
+                duration.num_seconds().max(0),
+                if job.finished_at.is_none() {
+                    " (in progress)"
+                } else {
+                    ""
+                }
+            );
+            if let Some(error) = &job.error {
+                let _ = writeln!(&mut report, "  • Error: {}", error);
+            }
+        } else {
+            report.push_str("📋 **Last Ingestion Job:** none recorded\n");
+        }
+
+        Ok(report)
+    }
+
+    /// Summarize the active embedding model and documents embedded with other models
+    async fn generate_embedding_summary(&self) -> Result<String> {
+        let config = embed::EmbeddingConfig::from_env_or_default();
+        let by_model = self.storage.store().count_embeddings_by_model().await?;
+
+        let (mut current, mut other, mut unknown) = (0_i64, 0_i64, 0_i64);
+        for (model, count) in &by_model {
+            match model.as_deref() {
+                Some(m) if m == config.model => current += count,
+                Some(_) => other += count,
+                None => unknown += count,
+            }
+        }
+
+        let mut summary = String::new();
+        summary.push_str("🧠 **Embeddings:**\n");
+        let _ = writeln!(
+            &mut summary,
+            "  • Active Model: {} ({} dimensions)",
+            config.model, config.dimensions
+        );
+        if !embed::vector_writes_enabled() {
+            summary.push_str(
+                "  • ⚠️ Vector writes disabled: dimension mismatch with database column\n",
+            );
+        }
+        let _ = writeln!(&mut summary, "  • Embedded with active model: {}", current);
+        let _ = writeln!(&mut summary, "  • Embedded with other models: {}", other);
+        for (model, count) in &by_model {
+            if let Some(m) = model.as_deref().filter(|m| *m != config.model) {
+                let _ = writeln!(&mut summary, "    - {}: {}", m, count);
+            }
+        }
+        if unknown > 0 {
+            let _ = writeln!(
+                &mut summary,
+                "  • Embedded with unrecorded model: {}",
+                unknown
+            );
+        }
+        let snapshot = crate::metrics::metrics().snapshot();
+        let _ = writeln!(
+            &mut summary,
+            "  • Cache (since start): {} hits, {} misses",
+            snapshot.embedding_cache_hits, snapshot.embedding_cache_misses
+        );
+        let _ = writeln!(
+            &mut summary,
+            "  • Query cache (since start): {} hits, {} misses; query embeddings {} hits, {} misses",
+            snapshot.query_cache_hits,
+            snapshot.query_cache_misses,
+            snapshot.query_embedding_cache_hits,
+            snapshot.query_embedding_cache_misses
+        );
+        let _ = writeln!(
+            &mut summary,
+            "  • Reranking (since start): {} reranked, {} fell back to search order",
+            snapshot.reranks, snapshot.rerank_fallbacks
+        );
+        let retries = db::retry_counts();
+        let _ = writeln!(
+            &mut summary,
+            "  • Database retries (since start): {} connection, {} pool timeout, {} serialization, {} gave up",
+            retries.connection, retries.pool_timeout, retries.serialization, retries.exhausted
+        );
+
+        Ok(summary)
+    }
+
     /// Generate comprehensive performance metrics
+    /// Model API spend recorded by crate jobs of the last 30 days, by model, crate and day
+    async fn generate_cost_analysis(&self) -> Result<String> {
+        let since = chrono::Utc::now() - chrono::Duration::days(30);
+        let jobs = self.storage.store().find_jobs_with_costs(since).await?;
+        if jobs.is_empty() {
+            return Ok("  • No model API usage recorded\n".to_string());
+        }
+
+        let mut total = JobCosts::default();
+        let mut by_crate: HashMap<String, (JobCosts, usize)> = HashMap::new();
+        let mut by_day: std::collections::BTreeMap<chrono::NaiveDate, JobCosts> =
+            std::collections::BTreeMap::new();
+        for job in &jobs {
+            let Some(costs) = job
+                .details
+                .as_ref()
+                .and_then(|details| details.get("job_costs"))
+                .and_then(|costs| serde_json::from_value::<JobCosts>(costs.clone()).ok())
+            else {
+                continue;
+            };
+            total.merge(&costs);
+            let (crate_costs, crate_jobs) = by_crate.entry(job.crate_name.clone()).or_default();
+            crate_costs.merge(&costs);
+            *crate_jobs += 1;
+            by_day
+                .entry(job.started_at.date_naive())
+                .or_default()
+                .merge(&costs);
+        }
+
+        let tokens = |costs: &JobCosts| -> u64 {
+            costs
+                .models
+                .values()
+                .map(|usage| usage.input_tokens + usage.output_tokens)
+                .sum()
+        };
+        let mut analysis = String::new();
+        let _ = writeln!(
+            &mut analysis,
+            "  • Total: ${:.4} across {} jobs ({} tokens)",
+            total.total_cost_usd,
+            jobs.len(),
+            tokens(&total)
+        );
+        analysis.push_str("  • By model:\n");
+        for (model, usage) in &total.models {
+            let _ = writeln!(
+                &mut analysis,
+                "    - {}: ${:.4} ({} calls, {} input / {} output tokens)",
+                model, usage.cost_usd, usage.calls, usage.input_tokens, usage.output_tokens
+            );
+        }
+        analysis.push_str("  • By crate:\n");
+        let mut crates: Vec<_> = by_crate.into_iter().collect();
+        crates.sort_by(|(a_name, (a, _)), (b_name, (b, _))| {
+            b.total_cost_usd
+                .total_cmp(&a.total_cost_usd)
+                .then_with(|| a_name.cmp(b_name))
+        });
+        for (crate_name, (costs, job_count)) in &crates {
+            let _ = writeln!(
+                &mut analysis,
+                "    - {}: ${:.4} ({} jobs, {} tokens)",
+                crate_name,
+                costs.total_cost_usd,
+                job_count,
+                tokens(costs)
+            );
+        }
+        analysis.push_str("  • By day:\n");
+        for (day, costs) in &by_day {
+            let _ = writeln!(
+                &mut analysis,
+                "    - {}: ${:.4} ({} tokens)",
+                day,
+                costs.total_cost_usd,
+                tokens(costs)
+            );
+        }
+        Ok(analysis)
+    }
+
     async fn generate_performance_metrics(&self) -> Result<String> {
+        let db_pool = self.storage.pool()?;
         let mut metrics = String::new();
 
         // Query response times
         let start_time = std::time::Instant::now();
         let query_test =
             sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM documents WHERE doc_type = 'rust'")
-                .fetch_one(self.db_pool.pool())
+                .fetch_one(db_pool.pool())
                 .await?;
         let query_time = start_time.elapsed();
 
@@ -1478,14 +4490,14 @@ impl CheckRustStatusTool {
         let with_embeddings = sqlx::query_scalar::<_, i64>(
             "SELECT COUNT(*) FROM documents WHERE doc_type = 'rust' AND embedding IS NOT NULL",
         )
-        .fetch_one(self.db_pool.pool())
+        .fetch_one(db_pool.pool())
         .await?;
 
         // Average document sizes
         let avg_content_size = sqlx::query_scalar::<_, Option<f64>>(
             "SELECT AVG(LENGTH(content)) FROM documents WHERE doc_type = 'rust'",
         )
-        .fetch_one(self.db_pool.pool())
+        .fetch_one(db_pool.pool())
         .await?
         .unwrap_or(0.0);
 
@@ -1516,12 +4528,12 @@ impl CheckRustStatusTool {
 
         // Job processing metrics
         let total_jobs = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM crate_jobs")
-            .fetch_one(self.db_pool.pool())
+            .fetch_one(db_pool.pool())
             .await?;
         let successful_jobs = sqlx::query_scalar::<_, i64>(
             "SELECT COUNT(*) FROM crate_jobs WHERE status = 'completed'",
         )
-        .fetch_one(self.db_pool.pool())
+        .fetch_one(db_pool.pool())
         .await?;
 
         if total_jobs > 0 {
@@ -1539,6 +4551,7 @@ impl CheckRustStatusTool {
 
     /// Generate comprehensive storage analysis
     async fn generate_storage_analysis(&self) -> Result<String> {
+        let db_pool = self.storage.pool()?;
         let mut analysis = String::new();
 
         // Document distribution by crate
@@ -1552,14 +4565,14 @@ impl CheckRustStatusTool {
             LIMIT 5
             ",
         )
-        .fetch_all(self.db_pool.pool())
+        .fetch_all(db_pool.pool())
         .await?;
 
         // Storage estimates
         let total_content_size = sqlx::query_scalar::<_, Option<i64>>(
             "SELECT SUM(LENGTH(content)) FROM documents WHERE doc_type = 'rust'",
         )
-        .fetch_one(self.db_pool.pool())
+        .fetch_one(db_pool.pool())
         .await?
         .unwrap_or(0);
 
@@ -1599,11 +4612,12 @@ impl CheckRustStatusTool {
 
     /// Perform comprehensive health checks
     async fn perform_comprehensive_health_checks(&self) -> Result<String> {
+        let db_pool = self.storage.pool()?;
         let mut health = String::new();
 
         // Database connection health
         let db_start = std::time::Instant::now();
-        let db_health = sqlx::query("SELECT 1").fetch_one(self.db_pool.pool()).await;
+        let db_health = sqlx::query("SELECT 1").fetch_one(db_pool.pool()).await;
         let db_time = db_start.elapsed();
 
         match db_health {
@@ -1623,7 +4637,7 @@ impl CheckRustStatusTool {
         let orphaned_embeddings = sqlx::query_scalar::<_, i64>(
             "SELECT COUNT(*) FROM documents WHERE doc_type = 'rust' AND embedding IS NOT NULL AND content = ''"
         )
-        .fetch_one(self.db_pool.pool())
+        .fetch_one(db_pool.pool())
         .await?;
 
         if orphaned_embeddings == 0 {
@@ -1640,7 +4654,7 @@ impl CheckRustStatusTool {
         let stuck_jobs = sqlx::query_scalar::<_, i64>(
             "SELECT COUNT(*) FROM crate_jobs WHERE status = 'running' AND started_at < NOW() - INTERVAL '1 hour'"
         )
-        .fetch_one(self.db_pool.pool())
+        .fetch_one(db_pool.pool())
         .await?;
 
         if stuck_jobs == 0 {
@@ -1653,9 +4667,46 @@ impl CheckRustStatusTool {
             );
         }
 
+        // Provider reachability, from the probes shared with /health/ready
+        let providers = provider_checks().check().await;
+        let mut failing_providers = 0;
+        for (name, report) in &providers {
+            let label = if name == "llm" {
+                "LLM Provider"
+            } else {
+                "Embedding Provider"
+            };
+            let required = if report.required { ", required" } else { "" };
+            match report.status {
+                ProviderStatus::Ok => {
+                    let _ = writeln!(
+                        &mut health,
+                        "  ✅ {label}: ok ({}ms{required})",
+                        report.latency_ms.unwrap_or(0)
+                    );
+                }
+                ProviderStatus::Degraded => {
+                    failing_providers += 1;
+                    let _ = writeln!(
+                        &mut health,
+                        "  ⚠️ {label}: degraded{required} - {}",
+                        report.error.as_deref().unwrap_or("unknown error")
+                    );
+                }
+                ProviderStatus::Unconfigured => {
+                    failing_providers += usize::from(report.required);
+                    let _ = writeln!(
+                        &mut health,
+                        "  ℹ️ {label}: unconfigured{required} - {}",
+                        report.error.as_deref().unwrap_or("no client")
+                    );
+                }
+            }
+        }
+
         // System resource estimates
-        let pool_size = self.db_pool.pool().size();
-        let active_connections = self.db_pool.pool().num_idle();
+        let pool_size = db_pool.pool().size();
+        let active_connections = db_pool.pool().num_idle();
         let _ = writeln!(
             &mut health,
             "  ℹ️ Connection Pool: {} active, {} total capacity",
@@ -1663,7 +4714,9 @@ impl CheckRustStatusTool {
         );
 
         // Overall system health score
-        let issues = i32::from(orphaned_embeddings > 0) + i32::from(stuck_jobs > 0);
+        let issues = i32::from(orphaned_embeddings > 0)
+            + i32::from(stuck_jobs > 0)
+            + i32::from(failing_providers > 0);
         match issues {
             0 => health
                 .push_str("  🎯 **Overall Health: EXCELLENT** - All systems operating normally\n"),
@@ -0,0 +1,228 @@
+//! Document retrieval tools for MCP
+//!
+//! Search tools return snippets; `get_document` fetches a stored document in
+//! full, addressed either by ID or by its `(doc_type, source_name, doc_path)`
+//! key, and points at neighbouring chunks of chunked documents.
+
+use crate::resources::resource_uri;
+use crate::tools::Tool;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use db::chunks::{CHUNK_INDEX_KEY, CHUNK_TOTAL_KEY, PARENT_DOC_PATH_KEY};
+use db::{ChunkReference, DatabasePool, Document, DocumentQueries};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// How the caller addressed the document
+enum DocumentKey {
+    Id(Uuid),
+    Path {
+        doc_type: String,
+        source_name: String,
+        doc_path: String,
+    },
+}
+
+impl DocumentKey {
+    /// Read the key from tool arguments; `id` wins when both forms are given
+    fn from_arguments(arguments: &Value) -> Result<Self> {
+        let str_arg = |key: &str| {
+            arguments
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+
+        if let Some(id) = str_arg("id") {
+            let id = Uuid::parse_str(id).map_err(|_| anyhow!("Invalid document id: {id}"))?;
+            return Ok(Self::Id(id));
+        }
+
+        match (
+            str_arg("doc_type"),
+            str_arg("source_name"),
+            str_arg("doc_path"),
+        ) {
+            (Some(doc_type), Some(source_name), Some(doc_path)) => Ok(Self::Path {
+                doc_type: doc_type.to_string(),
+                source_name: source_name.to_string(),
+                doc_path: doc_path.to_string(),
+            }),
+            _ => Err(anyhow!(
+                "Provide either id or all of doc_type, source_name and doc_path"
+            )),
+        }
+    }
+
+    /// The key as echoed back in a not-found result
+    fn to_json(&self) -> Value {
+        match self {
+            Self::Id(id) => json!({ "id": id }),
+            Self::Path {
+                doc_type,
+                source_name,
+                doc_path,
+            } => json!({
+                "doc_type": doc_type,
+                "source_name": source_name,
+                "doc_path": doc_path,
+            }),
+        }
+    }
+}
+
+/// First `max_chars` characters of `content` and whether anything was cut
+fn truncate_chars(content: &str, max_chars: Option<usize>) -> (String, bool) {
+    match max_chars {
+        Some(max) => match content.char_indices().nth(max) {
+            Some((cut, _)) => (content[..cut].to_string(), true),
+            None => (content.to_string(), false),
+        },
+        None => (content.to_string(), false),
+    }
+}
+
+/// Position of a chunk row, when its metadata carries one
+fn chunk_position(document: &Document) -> Option<(&str, i64)> {
+    let parent = document.metadata.get(PARENT_DOC_PATH_KEY)?.as_str()?;
+    let index = document.metadata.get(CHUNK_INDEX_KEY)?.as_i64()?;
+    Some((parent, index))
+}
+
+/// Chunk navigation block for a chunk at `index` among `siblings`
+fn chunk_navigation(
+    document: &Document,
+    parent: &str,
+    index: i64,
+    siblings: &[ChunkReference],
+) -> Value {
+    let neighbour = |offset: i64| {
+        siblings
+            .iter()
+            .find(|chunk| i64::from(chunk.chunk_index) == index + offset)
+    };
+    let chunk_total = document
+        .metadata
+        .get(CHUNK_TOTAL_KEY)
+        .and_then(Value::as_u64)
+        .map_or(siblings.len(), |total| {
+            usize::try_from(total).unwrap_or(usize::MAX)
+        });
+
+    json!({
+        "parent_doc_path": parent,
+        "chunk_index": index,
+        "chunk_total": chunk_total,
+        "previous": neighbour(-1),
+        "next": neighbour(1),
+        "siblings": siblings,
+    })
+}
+
+/// Fetch a stored document in full by ID or by path
+pub struct GetDocumentTool {
+    db_pool: DatabasePool,
+}
+
+impl GetDocumentTool {
+    /// Create a new document retrieval tool
+    #[must_use]
+    pub const fn new(db_pool: DatabasePool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl Tool for GetDocumentTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "get_document",
+            "description": "Fetch the full content of a stored document by id, or by doc_type + source_name + doc_path, with its metadata, token count and timestamps. Chunked documents include references to their sibling chunks. Unknown documents return found: false.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "Document ID (UUID), as returned by search results"
+                    },
+                    "doc_type": {
+                        "type": "string",
+                        "description": "Doc type of the document (with source_name and doc_path)"
+                    },
+                    "source_name": {
+                        "type": "string",
+                        "description": "Source the document belongs to (with doc_type and doc_path)"
+                    },
+                    "doc_path": {
+                        "type": "string",
+                        "description": "Path of the document within its source (with doc_type and source_name)"
+                    },
+                    "max_chars": {
+                        "type": "integer",
+                        "description": "Return at most this many characters of content (optional)",
+                        "minimum": 1
+                    }
+                },
+                "required": []
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        let key = DocumentKey::from_arguments(&arguments)?;
+        let max_chars = arguments
+            .get("max_chars")
+            .and_then(Value::as_u64)
+            .map(|max| usize::try_from(max.max(1)).unwrap_or(usize::MAX));
+
+        let pool = self.db_pool.pool();
+        let document = match &key {
+            DocumentKey::Id(id) => DocumentQueries::find_by_id(pool, *id).await?,
+            DocumentKey::Path {
+                doc_type,
+                source_name,
+                doc_path,
+            } => DocumentQueries::find_by_path(pool, doc_type, source_name, doc_path).await?,
+        };
+
+        let Some(document) = document else {
+            let response = json!({
+                "found": false,
+                "query": key.to_json(),
+                "message": "No document matches; search again to get a current id or path",
+            });
+            return Ok(serde_json::to_string_pretty(&response)?);
+        };
+
+        let (content, truncated) = truncate_chars(&document.content, max_chars);
+        let mut response = json!({
+            "found": true,
+            "id": document.id,
+            "uri": resource_uri(&document.doc_type, &document.source_name, &document.doc_path),
+            "doc_type": document.doc_type,
+            "source_name": document.source_name,
+            "doc_path": document.doc_path,
+            "metadata": document.metadata,
+            "token_count": document.token_count,
+            "created_at": document.created_at,
+            "updated_at": document.updated_at,
+            "content_length": document.content.chars().count(),
+            "truncated": truncated,
+            "content": content,
+        });
+
+        if let Some((parent, index)) = chunk_position(&document) {
+            let siblings = DocumentQueries::find_chunks(
+                pool,
+                &document.doc_type,
+                &document.source_name,
+                parent,
+            )
+            .await?;
+            response["chunks"] = chunk_navigation(&document, parent, index, &siblings);
+        }
+
+        Ok(serde_json::to_string_pretty(&response)?)
+    }
+}
@@ -0,0 +1,249 @@
+//! Content-addressed embedding cache
+//!
+//! Embeddings are stored in the `embedding_cache` table keyed by the SHA-256
+//! of the embedded text, the model and the dimension. Re-ingesting a crate
+//! whose pages have not changed then costs database lookups instead of
+//! embedding API calls. Cache failures never fail an embedding request; they
+//! fall through to the wrapped client. Misses are embedded through an
+//! [`EmbeddingPipeline`], so they share its concurrency bound and retries.
+//! Embedded misses are the only requests that cost money; the client keeps
+//! their [`JobCosts`] for the job that owns it.
+
+use crate::metrics::metrics;
+use anyhow::Result;
+use async_trait::async_trait;
+use db::EmbeddingCacheQueries;
+use embed::client::EmbeddingClient;
+use embed::models::{
+    BatchResponse, EmbeddingRequest, EmbeddingResponse, FileUploadResponse, JsonlResponseLine,
+};
+use embed::{prices, EmbeddingConfig, EmbeddingPipeline, JobCosts};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// Hex-encoded SHA-256 of the embedded text, used as the cache key
+pub use db::content_hash;
+
+/// Days an entry may go unused before eviction (`EMBEDDING_CACHE_TTL_DAYS`, default 30)
+fn cache_ttl_days() -> i32 {
+    std::env::var("EMBEDDING_CACHE_TTL_DAYS")
+        .ok()
+        .and_then(|v| v.parse::<i32>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(30)
+}
+
+/// Count embedding requests to `model` in the process-wide cost metrics, returning their cost
+pub fn account_embeddings(model: &str, calls: u64, input_tokens: u64) -> f64 {
+    let cost = prices().cost(model, input_tokens, 0);
+    metrics().record_api_cost("embedding", model, calls, input_tokens, 0, cost);
+    cost
+}
+
+/// Embedding client wrapper that consults the embedding cache first
+#[derive(Clone)]
+pub struct CachedEmbeddingClient {
+    inner: Arc<dyn EmbeddingClient + Send + Sync>,
+    pipeline: EmbeddingPipeline,
+    pool: PgPool,
+    config: EmbeddingConfig,
+    /// Embedding requests made by this client and its clones
+    costs: Arc<Mutex<JobCosts>>,
+}
+
+impl CachedEmbeddingClient {
+    /// Wrap `inner`, storing cache entries in `pool`
+    #[must_use]
+    pub fn new(inner: Arc<dyn EmbeddingClient + Send + Sync>, pool: PgPool) -> Self {
+        let config = inner.embedding_config();
+        Self {
+            pipeline: EmbeddingPipeline::new(inner.clone()),
+            inner,
+            pool,
+            config,
+            costs: Arc::new(Mutex::new(JobCosts::default())),
+        }
+    }
+
+    /// Embedding requests made through this client and its clones, with their cost
+    #[must_use]
+    pub fn costs(&self) -> JobCosts {
+        self.costs
+            .lock()
+            .map(|costs| costs.clone())
+            .unwrap_or_default()
+    }
+
+    fn dimensions(&self) -> i32 {
+        i32::try_from(self.config.dimensions).unwrap_or(i32::MAX)
+    }
+
+    /// Look up cached embeddings for `texts` in one query
+    ///
+    /// Returns embeddings keyed by [`content_hash`]. Lookup errors are logged
+    /// and reported as misses.
+    pub async fn lookup_many(&self, texts: &[&str]) -> HashMap<String, Vec<f32>> {
+        let mut hashes: Vec<String> = texts.iter().map(|t| content_hash(t)).collect();
+        hashes.sort_unstable();
+        hashes.dedup();
+
+        let found = match EmbeddingCacheQueries::lookup(
+            &self.pool,
+            &hashes,
+            &self.config.model,
+            self.dimensions(),
+        )
+        .await
+        {
+            Ok(found) => found,
+            Err(e) => {
+                tracing::warn!("Embedding cache lookup failed: {}", e);
+                HashMap::new()
+            }
+        };
+
+        let hits = found.len() as u64;
+        metrics().record_embedding_cache(hits, hashes.len() as u64 - hits);
+        found
+    }
+
+    /// Store freshly generated embeddings keyed by [`content_hash`]
+    ///
+    /// Write errors are logged; the embeddings are still usable by the caller.
+    pub async fn store_many(&self, entries: &[(String, Vec<f32>)]) {
+        if let Err(e) =
+            EmbeddingCacheQueries::store(&self.pool, entries, &self.config.model, self.dimensions())
+                .await
+        {
+            tracing::warn!(
+                "Failed to write {} embedding cache entries: {}",
+                entries.len(),
+                e
+            );
+        }
+    }
+
+    /// Embed `texts`, calling the wrapped client only for cache misses
+    ///
+    /// Identical texts within the batch are embedded once. Results are in the
+    /// same order as `texts`.
+    pub async fn embed_many(&self, texts: &[&str]) -> Vec<Result<Vec<f32>>> {
+        let mut embeddings = self.lookup_many(texts).await;
+        let mut queued = HashSet::new();
+        let misses: Vec<&str> = texts
+            .iter()
+            .copied()
+            .filter(|text| {
+                let hash = content_hash(text);
+                !embeddings.contains_key(&hash) && queued.insert(hash)
+            })
+            .collect();
+
+        let mut fresh = Vec::new();
+        let mut failed: HashMap<String, String> = HashMap::new();
+        let mut input_tokens = 0;
+        for (text, result) in misses.iter().zip(self.pipeline.embed_all(&misses).await) {
+            let hash = content_hash(text);
+            match result {
+                Ok(embedding) => {
+                    input_tokens += embed::token_count(text) as u64;
+                    fresh.push((hash.clone(), embedding.clone()));
+                    embeddings.insert(hash, embedding);
+                }
+                Err(e) => {
+                    failed.insert(hash, e.to_string());
+                }
+            }
+        }
+
+        if !fresh.is_empty() {
+            let calls = fresh.len() as u64;
+            account_embeddings(&self.config.model, calls, input_tokens);
+            if let Ok(mut costs) = self.costs.lock() {
+                costs.record(&self.config.model, calls, input_tokens, 0, prices());
+            }
+        }
+        self.store_many(&fresh).await;
+
+        texts
+            .iter()
+            .map(|text| {
+                let hash = content_hash(text);
+                embeddings.get(&hash).cloned().ok_or_else(|| {
+                    anyhow::anyhow!(failed
+                        .get(&hash)
+                        .cloned()
+                        .unwrap_or_else(|| "embedding unavailable".to_string()))
+                })
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl EmbeddingClient for CachedEmbeddingClient {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.embed_many(&[text])
+            .await
+            .pop()
+            .unwrap_or_else(|| Err(anyhow::anyhow!("embedding unavailable")))
+    }
+
+    async fn generate_embedding(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse> {
+        self.inner.generate_embedding(request).await
+    }
+
+    async fn upload_batch_file(&self, content: &str, filename: &str) -> Result<FileUploadResponse> {
+        self.inner.upload_batch_file(content, filename).await
+    }
+
+    async fn create_batch(&self, input_file_id: &str) -> Result<BatchResponse> {
+        self.inner.create_batch(input_file_id).await
+    }
+
+    async fn get_batch(&self, batch_id: &str) -> Result<BatchResponse> {
+        self.inner.get_batch(batch_id).await
+    }
+
+    async fn download_batch_results(&self, file_id: &str) -> Result<Vec<JsonlResponseLine>> {
+        self.inner.download_batch_results(file_id).await
+    }
+
+    async fn cancel_batch(&self, batch_id: &str) -> Result<BatchResponse> {
+        self.inner.cancel_batch(batch_id).await
+    }
+
+    fn embedding_config(&self) -> EmbeddingConfig {
+        self.config.clone()
+    }
+}
+
+/// Evict cache entries unused for `EMBEDDING_CACHE_TTL_DAYS`
+///
+/// Run by the `embedding_cache_eviction` maintenance task.
+///
+/// # Errors
+///
+/// Returns an error if the entries cannot be deleted.
+pub async fn evict_unused_entries(pool: &PgPool) -> Result<String> {
+    let ttl_days = cache_ttl_days();
+    let evicted = EmbeddingCacheQueries::evict_unused(pool, ttl_days).await?;
+    Ok(format!(
+        "evicted {evicted} entries unused for {ttl_days} days"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(
+            content_hash("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_ne!(content_hash("abc"), content_hash("abd"));
+    }
+}
@@ -0,0 +1,327 @@
+//! Relevance feedback tools for MCP
+//!
+//! `record_feedback` lets an agent rate a search result it used; ratings are
+//! written in the background so the call returns at once. The admin-only
+//! `get_feedback_stats` aggregates them per doc type, crate and query term,
+//! and lists the terms that are queried often but rated badly.
+
+use crate::tools::{ExecutionContext, Tool};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use db::models::{FeedbackCounts, NewSearchFeedback};
+use db::{DatabasePool, FeedbackQueries};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Accepted ratings
+pub const RATINGS: [&str; 3] = ["useful", "partially", "not_useful"];
+
+/// Longest query stored with a rating, in characters
+const MAX_QUERY_CHARS: usize = 1000;
+
+/// Default and maximum rows returned per grouping by `get_feedback_stats`
+const DEFAULT_STATS_LIMIT: i64 = 20;
+const MAX_STATS_LIMIT: i64 = 200;
+
+/// Query terms considered when looking for gaps
+const GAP_CANDIDATE_TERMS: i64 = 1000;
+
+/// Terms scoring below this are reported as gaps
+pub const GAP_SCORE: f64 = 0.5;
+
+/// Default days of feedback aggregated by `get_feedback_stats`
+const DEFAULT_STATS_DAYS: i64 = 30;
+
+/// Default days feedback is kept (`SEARCH_FEEDBACK_RETENTION_DAYS`)
+const DEFAULT_RETENTION_DAYS: i32 = 180;
+
+/// Terms with at least `min_ratings` ratings and a score below [`GAP_SCORE`]
+///
+/// Ordered by number of ratings, most first, then by score, worst first.
+#[must_use]
+pub fn find_gaps(terms: &[FeedbackCounts], min_ratings: i64) -> Vec<&FeedbackCounts> {
+    let mut gaps: Vec<&FeedbackCounts> = terms
+        .iter()
+        .filter(|counts| counts.total() >= min_ratings && counts.score() < GAP_SCORE)
+        .collect();
+    gaps.sort_by(|a, b| {
+        b.total()
+            .cmp(&a.total())
+            .then(a.score().total_cmp(&b.score()))
+            .then_with(|| a.key.cmp(&b.key))
+    });
+    gaps
+}
+
+/// JSON of one grouping row, with its total and score
+fn counts_json(counts: &FeedbackCounts) -> Value {
+    json!({
+        "key": counts.key,
+        "total": counts.total(),
+        "useful": counts.useful,
+        "partially": counts.partially,
+        "not_useful": counts.not_useful,
+        "score": (counts.score() * 1000.0).round() / 1000.0,
+    })
+}
+
+/// Delete feedback older than `SEARCH_FEEDBACK_RETENTION_DAYS` (default 180)
+///
+/// Run by the `search_feedback_retention` maintenance task.
+///
+/// # Errors
+///
+/// Returns an error if the rows cannot be deleted.
+pub async fn prune_feedback(pool: &PgPool) -> Result<String> {
+    let retention_days = std::env::var("SEARCH_FEEDBACK_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.trim().parse::<i32>().ok())
+        .filter(|days| *days > 0)
+        .unwrap_or(DEFAULT_RETENTION_DAYS);
+    let removed = FeedbackQueries::prune(pool, retention_days).await?;
+    Ok(format!("removed {removed} search feedback entries"))
+}
+
+/// Rate a search result
+pub struct RecordFeedbackTool {
+    db_pool: DatabasePool,
+}
+
+impl RecordFeedbackTool {
+    /// Create a new feedback recording tool
+    #[must_use]
+    pub const fn new(db_pool: DatabasePool) -> Self {
+        Self { db_pool }
+    }
+
+    /// Validate the arguments of a call into the entry to record
+    fn parse(arguments: &Value, session_id: Option<&str>) -> Result<NewSearchFeedback> {
+        let text = |key: &str| {
+            arguments
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+        let query = text("query").ok_or_else(|| anyhow!("Missing required parameter: query"))?;
+        let rating = text("rating").ok_or_else(|| anyhow!("Missing required parameter: rating"))?;
+        if !RATINGS.contains(&rating) {
+            return Err(anyhow!(
+                "Invalid rating '{rating}': expected one of {}",
+                RATINGS.join(", ")
+            ));
+        }
+        let document_id = text("document_id")
+            .map(|id| Uuid::parse_str(id).map_err(|_| anyhow!("Invalid document_id: {id}")))
+            .transpose()?;
+        let doc_path = text("doc_path").map(ToString::to_string);
+        if document_id.is_none() && doc_path.is_none() {
+            return Err(anyhow!("Either document_id or doc_path is required"));
+        }
+
+        Ok(NewSearchFeedback {
+            session_id: session_id.map(ToString::to_string),
+            query: query.chars().take(MAX_QUERY_CHARS).collect(),
+            rating: rating.to_string(),
+            document_id,
+            doc_path,
+        })
+    }
+}
+
+#[async_trait]
+impl Tool for RecordFeedbackTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "record_feedback",
+            "description": "Rate a search result after using it, so documentation gaps can be found. Pass the query that returned the result and the document's id or path. Returns immediately; the rating is stored in the background.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Query that returned the result"
+                    },
+                    "document_id": {
+                        "type": "string",
+                        "description": "Id of the rated document"
+                    },
+                    "doc_path": {
+                        "type": "string",
+                        "description": "Path of the rated document, as shown in the results (used when document_id is not given)"
+                    },
+                    "rating": {
+                        "type": "string",
+                        "enum": RATINGS,
+                        "description": "How useful the result was for the query"
+                    }
+                },
+                "required": ["query", "rating"]
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        self.execute_with_context(arguments, &ExecutionContext::default())
+            .await
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ExecutionContext,
+    ) -> Result<String> {
+        let feedback = Self::parse(&arguments, context.session_id())?;
+        let response = json!({
+            "recorded": true,
+            "rating": feedback.rating,
+        });
+
+        let pool = self.db_pool.pool().clone();
+        tokio::spawn(async move {
+            if let Err(e) = FeedbackQueries::record(&pool, &feedback).await {
+                warn!("Failed to record search feedback: {}", e);
+            }
+        });
+
+        Ok(serde_json::to_string_pretty(&response)?)
+    }
+}
+
+/// Aggregate search feedback
+pub struct GetFeedbackStatsTool {
+    db_pool: DatabasePool,
+}
+
+impl GetFeedbackStatsTool {
+    /// Create a new feedback statistics tool
+    #[must_use]
+    pub const fn new(db_pool: DatabasePool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl Tool for GetFeedbackStatsTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "get_feedback_stats",
+            "description": "Search result ratings recorded with record_feedback, aggregated per doc type, crate and query term. Each row has useful, partially and not_useful counts and a score (share of useful ratings, partial ones counting half). Gaps are query terms rated at least min_ratings times with a score below 0.5. Requires the admin token.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "days": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "maximum": 3650,
+                        "description": "Only feedback from the last this many days (default 30)"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "maximum": MAX_STATS_LIMIT,
+                        "description": "Maximum rows per grouping, busiest first (default 20)"
+                    },
+                    "min_ratings": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Ratings a term needs before it can be reported as a gap (default 3)"
+                    }
+                },
+                "required": []
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        let days = arguments
+            .get("days")
+            .and_then(Value::as_i64)
+            .unwrap_or(DEFAULT_STATS_DAYS)
+            .clamp(1, 3650);
+        let limit = arguments
+            .get("limit")
+            .and_then(Value::as_i64)
+            .unwrap_or(DEFAULT_STATS_LIMIT)
+            .clamp(1, MAX_STATS_LIMIT);
+        let min_ratings = arguments
+            .get("min_ratings")
+            .and_then(Value::as_i64)
+            .unwrap_or(3)
+            .max(1);
+        let since = Utc::now() - Duration::days(days);
+        let pool = self.db_pool.pool();
+
+        let (total, unmatched) = FeedbackQueries::totals(pool, since).await?;
+        let by_doc_type = FeedbackQueries::counts_by_doc_type(pool, since, limit).await?;
+        let by_crate = FeedbackQueries::counts_by_crate(pool, since, limit).await?;
+        let terms = FeedbackQueries::counts_by_term(pool, since, GAP_CANDIDATE_TERMS).await?;
+        let gaps: Vec<Value> = find_gaps(&terms, min_ratings)
+            .into_iter()
+            .take(usize::try_from(limit).unwrap_or(usize::MAX))
+            .map(counts_json)
+            .collect();
+        let by_term: Vec<Value> = terms
+            .iter()
+            .take(usize::try_from(limit).unwrap_or(usize::MAX))
+            .map(counts_json)
+            .collect();
+
+        Ok(serde_json::to_string_pretty(&json!({
+            "since": since,
+            "total": total,
+            "unmatched_documents": unmatched,
+            "by_doc_type": by_doc_type.iter().map(counts_json).collect::<Vec<_>>(),
+            "by_crate": by_crate.iter().map(counts_json).collect::<Vec<_>>(),
+            "by_term": by_term,
+            "gaps": gaps,
+        }))?)
+    }
+
+    fn requires_admin(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(key: &str, useful: i64, partially: i64, not_useful: i64) -> FeedbackCounts {
+        FeedbackCounts {
+            key: key.to_string(),
+            useful,
+            partially,
+            not_useful,
+        }
+    }
+
+    #[test]
+    fn test_score_counts_partial_ratings_as_half() {
+        assert!((counts("a", 2, 2, 0).score() - 0.75).abs() < 1e-9);
+        assert!((counts("a", 0, 1, 3).score() - 0.125).abs() < 1e-9);
+        assert!(counts("a", 0, 0, 0).score().abs() < f64::EPSILON);
+        assert_eq!(counts("a", 1, 2, 3).total(), 6);
+    }
+
+    #[test]
+    fn test_gaps_are_frequent_badly_rated_terms() {
+        let terms = [
+            counts("spawn", 5, 0, 1),
+            counts("lifetim", 0, 1, 4),
+            counts("macro", 0, 0, 2),
+            counts("pin", 1, 0, 5),
+            counts("trait", 0, 2, 3),
+        ];
+        let gaps: Vec<&str> = find_gaps(&terms, 3)
+            .into_iter()
+            .map(|c| c.key.as_str())
+            .collect();
+        // Too few ratings for macro, spawn is rated well; pin (6) before the
+        // equally busy lifetim and trait, which order by score
+        assert_eq!(gaps, ["pin", "lifetim", "trait"]);
+    }
+}
@@ -1,144 +1,483 @@
 //! MCP request handlers
 
+use crate::answer_tools::AnswerQuestionTool;
+use crate::audit::{AuditLogger, ToolCaller};
 use crate::config::ConfigLoader;
+use crate::crate_diff_tools::DiffRustCrateVersionsTool;
 use crate::crate_tools::{
-    AddRustCrateTool, CheckRustStatusTool, ListRustCratesTool, RemoveRustCrateTool,
+    AddLocalCrateTool, AddRustCrateTool, BackfillEmbeddingsTool, CheckRustStatusTool,
+    ListRustCratesTool, RemoveRustCrateTool, ReprocessRustCrateTool, RestoreRustCrateTool,
+    RetryRustJobTool,
 };
+use crate::document_tools::GetDocumentTool;
+use crate::feedback_tools::{GetFeedbackStatsTool, RecordFeedbackTool};
+use crate::ingest_tools::{AnalyzeRepositoryTool, CheckIngestStatusTool, ExecuteIngestPlanTool};
+use crate::metrics::metrics;
+use crate::npm_tools::{AddNpmPackageTool, ListNpmPackagesTool, RemoveNpmPackageTool};
 use crate::protocol_version::ProtocolRegistry;
-use crate::tools::{DynamicQueryTool, RustQueryTool, Tool};
+use crate::python_tools::{AddPythonPackageTool, ListPythonPackagesTool, RemovePythonPackageTool};
+use crate::resources::{
+    parse_resource_uri, resource_contents, resource_descriptor, DEFAULT_RESOURCE_MAX_CHARS,
+    DEFAULT_RESOURCE_PAGE_SIZE,
+};
+use crate::source_tools::{DeleteDocSourceTool, ListDocSourcesTool, SetDocSourceEnabledTool};
+use crate::tool_concurrency::ToolConcurrency;
+use crate::tool_schema::ToolSchema;
+use crate::tool_switches::{SetToolEnabledTool, ToolSwitches, SET_TOOL_ENABLED};
+use crate::tool_timeouts::ToolTimeouts;
+use crate::tools::{
+    continuations, DynamicQueryTool, ExecutionContext, FetchContinuationTool, GetToolMetricsTool,
+    QueryAuditLogTool, RequestCancelled, RustQueryTool, StructuredToolError, Tool, ToolDisabled,
+    ToolError, ToolTimedOut,
+};
+use crate::webhook_tools::{
+    AddWebhookTool, DeleteWebhookTool, ListWebhookDeliveriesTool, ListWebhooksTool,
+};
 use anyhow::{anyhow, Result};
-use db::DatabasePool;
-use embed::OpenAIEmbeddingClient;
+use db::models::{ToolConfig, ToolsConfig};
+use db::{DatabasePool, DocumentQueries};
+use embed::embedding_client_from_env;
 use serde_json::{json, Value};
-use std::collections::HashMap;
-use std::sync::Arc;
-use tracing::{debug, error, info, warn};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn, Instrument};
+
+/// In-flight tool calls keyed by (session ID, JSON-RPC request ID)
+type InFlightCalls = Arc<Mutex<HashMap<(String, String), CancellationToken>>>;
+
+/// Removes an in-flight entry when the tool call finishes
+struct InFlightGuard {
+    calls: InFlightCalls,
+    key: (String, String),
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if let Ok(mut calls) = self.calls.lock() {
+            calls.remove(&self.key);
+        }
+    }
+}
 
 /// MCP request handler
 pub struct McpHandler {
     tools: HashMap<String, Box<dyn Tool + Send + Sync>>,
+    /// Doc types that already have a query tool registered
+    query_doc_types: HashSet<String>,
+    /// Cancellation tokens for tool calls that are still running
+    in_flight: InFlightCalls,
+    /// Pool backing `resources/list` and `resources/read`
+    db_pool: DatabasePool,
+    /// Descriptors returned per `resources/list` page
+    resource_page_size: i64,
+    /// Maximum characters returned by `resources/read`
+    resource_max_chars: usize,
+    /// Characters of a tool result returned at once, whatever the tool's soft cap
+    max_response_chars: Option<usize>,
+    /// Records every tool call when set
+    audit: Option<AuditLogger>,
+    /// Tools switched on or off at runtime
+    switches: ToolSwitches,
+    /// Compiled `inputSchema` of every tool that declares one
+    schemas: HashMap<String, ToolSchema>,
+    /// Time budget of each tool call
+    timeouts: ToolTimeouts,
+    /// Slots limiting concurrent tool calls per session and overall
+    concurrency: ToolConcurrency,
 }
 
 impl McpHandler {
-    /// Create a new MCP handler
+    /// Create a new MCP handler with the tools configuration from the environment
+    ///
+    /// Without a configuration only the built-in tools are registered.
     ///
     /// # Errors
     ///
-    /// Returns an error if any tool initialization fails.
+    /// Returns an error if the configuration is invalid or any tool
+    /// initialization fails.
     pub fn new(db_pool: &DatabasePool) -> Result<Self> {
+        let config = ConfigLoader::load_configured()?;
+        if config.is_none() {
+            warn!("No tools configuration found. Continuing with built-in tools only.");
+        }
+        Self::with_tools_config(db_pool, config.as_ref())
+    }
+
+    /// Create a new MCP handler registering the query and crate management
+    /// tools listed in `config` alongside the built-in tools
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the configuration entry if a configured tool
+    /// cannot be created, or if any built-in tool initialization fails.
+    pub fn with_tools_config(db_pool: &DatabasePool, config: Option<&ToolsConfig>) -> Result<Self> {
         let mut tools: HashMap<String, Box<dyn Tool + Send + Sync>> = HashMap::new();
+        let mut query_doc_types = HashSet::from(["rust".to_string()]);
 
         // Always register the rust_query tool as hardcoded (legacy)
         let rust_query_tool = RustQueryTool::new(db_pool.clone())?;
         tools.insert("rust_query".to_string(), Box::new(rust_query_tool));
         debug!("Registered hardcoded rust_query tool");
+        tools.insert("get_tool_metrics".to_string(), Box::new(GetToolMetricsTool));
+        tools.insert(
+            "fetch_continuation".to_string(),
+            Box::new(FetchContinuationTool),
+        );
+        tools.insert(
+            "query_audit_log".to_string(),
+            Box::new(QueryAuditLogTool::new(db_pool.clone())),
+        );
+        tools.insert(
+            "answer_question".to_string(),
+            Box::new(AnswerQuestionTool::new(db_pool.clone())?),
+        );
+        tools.insert(
+            "get_document".to_string(),
+            Box::new(GetDocumentTool::new(db_pool.clone())),
+        );
+        tools.insert(
+            "list_doc_sources".to_string(),
+            Box::new(ListDocSourcesTool::new(db_pool.clone())),
+        );
+        tools.insert(
+            "set_doc_source_enabled".to_string(),
+            Box::new(SetDocSourceEnabledTool::new(db_pool.clone())),
+        );
+        tools.insert(
+            "delete_doc_source".to_string(),
+            Box::new(DeleteDocSourceTool::new(db_pool.clone())),
+        );
+        tools.insert(
+            "add_webhook".to_string(),
+            Box::new(AddWebhookTool::new(db_pool.clone())),
+        );
+        tools.insert(
+            "list_webhooks".to_string(),
+            Box::new(ListWebhooksTool::new(db_pool.clone())),
+        );
+        tools.insert(
+            "delete_webhook".to_string(),
+            Box::new(DeleteWebhookTool::new(db_pool.clone())),
+        );
+        tools.insert(
+            "list_webhook_deliveries".to_string(),
+            Box::new(ListWebhookDeliveriesTool::new(db_pool.clone())),
+        );
+        tools.insert(
+            "record_feedback".to_string(),
+            Box::new(RecordFeedbackTool::new(db_pool.clone())),
+        );
+        tools.insert(
+            "get_feedback_stats".to_string(),
+            Box::new(GetFeedbackStatsTool::new(db_pool.clone())),
+        );
+        tools.insert(
+            "analyze_repository".to_string(),
+            Box::new(AnalyzeRepositoryTool::new()?),
+        );
+        tools.insert(
+            "execute_ingest_plan".to_string(),
+            Box::new(ExecuteIngestPlanTool::new(db_pool.clone())),
+        );
+        tools.insert(
+            "check_ingest_status".to_string(),
+            Box::new(CheckIngestStatusTool::new(db_pool.clone())),
+        );
 
         // Legacy ingest tool removed - use intelligent ingestion endpoint instead
 
-        // Load and register dynamic tools from configuration
-        match Self::register_dynamic_tools(&mut tools, db_pool) {
-            Ok(count) => {
-                info!(
-                    "Successfully registered {} dynamic tools from configuration",
-                    count
-                );
+        // Register dynamic tools from configuration
+        if let Some(config) = config {
+            ConfigLoader::validate_config(config)?;
+            let count =
+                Self::register_dynamic_tools(&mut tools, &mut query_doc_types, db_pool, config)?;
+            info!(
+                "Successfully registered {} dynamic tools from configuration",
+                count
+            );
+        }
+
+        let switches = ToolSwitches::new(db_pool.clone());
+        tools.insert(
+            SET_TOOL_ENABLED.to_string(),
+            Box::new(SetToolEnabledTool::new(switches.clone())),
+        );
+        let mut timeouts = ToolTimeouts::from_env()?;
+        for tool in config.iter().flat_map(|c| &c.tools) {
+            if let Some(secs) = tool.timeout_secs {
+                timeouts.set_default_for(&tool.name, Duration::from_secs(secs));
             }
-            Err(e) => {
-                warn!(
-                    "Failed to load dynamic tools: {}. Continuing with hardcoded tools only.",
-                    e
-                );
+        }
+
+        let mut schemas = HashMap::new();
+        for (name, tool) in &tools {
+            switches.register(name);
+            if let Some(schema) = ToolSchema::compile(name, &tool.definition())? {
+                schemas.insert(name.clone(), schema);
             }
         }
 
         info!("MCP handler initialized with {} total tools", tools.len());
-        Ok(Self { tools })
+        Ok(Self {
+            tools,
+            query_doc_types,
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            db_pool: db_pool.clone(),
+            resource_page_size: DEFAULT_RESOURCE_PAGE_SIZE,
+            resource_max_chars: std::env::var("MCP_RESOURCE_MAX_CHARS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&n| n > 0)
+                .unwrap_or(DEFAULT_RESOURCE_MAX_CHARS),
+            max_response_chars: None,
+            audit: None,
+            switches,
+            schemas,
+            timeouts,
+            concurrency: ToolConcurrency::from_env()?,
+        })
     }
 
-    /// Register dynamic tools from configuration
+    /// Register a query tool for each enabled document source doc type that has none yet
+    ///
+    /// Lets new doc types loaded into `document_sources` become searchable
+    /// without a tools configuration entry.
     ///
     /// # Errors
     ///
-    /// Returns an error if configuration loading or tool creation fails.
-    fn register_dynamic_tools(
-        tools: &mut HashMap<String, Box<dyn Tool + Send + Sync>>,
+    /// Returns an error if the document sources cannot be read or a tool
+    /// schema does not compile.
+    pub async fn register_document_source_tools(
+        &mut self,
         db_pool: &DatabasePool,
     ) -> Result<usize> {
-        // First try to load from config file, fall back to embedded config
-        let config = if let Ok(path) = std::env::var("TOOLS_CONFIG_PATH") {
-            info!("Loading tools configuration from: {path}");
-            ConfigLoader::load_from_file(path)?
-        } else {
-            debug!("No TOOLS_CONFIG_PATH specified, using embedded configuration");
-            ConfigLoader::load_default()?
-        };
-
-        let enabled_tools = ConfigLoader::filter_enabled_tools(&config);
+        let doc_types = DocumentQueries::list_enabled_source_doc_types(db_pool.pool()).await?;
         let mut registered_count = 0;
 
-        for tool_config in enabled_tools {
-            // Skip rust_query if it appears in config since we register it hardcoded
-            if tool_config.name == "rust_query" {
-                debug!("Skipping rust_query from config - already registered as hardcoded");
+        for doc_type in doc_types {
+            // Skip doc types already covered by rust_query or a configured query tool
+            let name = DynamicQueryTool::tool_name_for_doc_type(&doc_type);
+            if self.query_doc_types.contains(&doc_type) || self.tools.contains_key(&name) {
                 continue;
             }
 
-            // Check if tool name already exists
-            if tools.contains_key(&tool_config.name) {
-                warn!("Tool '{}' already registered, skipping", tool_config.name);
-                continue;
-            }
-
-            // Create and register the tool based on tool name
-            match Self::create_tool_from_config(&tool_config, db_pool) {
+            match DynamicQueryTool::for_doc_type(&doc_type, db_pool.clone()) {
                 Ok(tool) => {
                     debug!(
-                        "Created dynamic tool '{}' for doc_type '{}'",
-                        tool_config.name, tool_config.doc_type
+                        "Created query tool '{}' for document source doc_type '{}'",
+                        name, doc_type
                     );
-                    tools.insert(tool_config.name.clone(), tool);
+                    if let Some(schema) = ToolSchema::compile(&name, &tool.definition())? {
+                        self.schemas.insert(name.clone(), schema);
+                    }
+                    self.switches.register(&name);
+                    self.tools.insert(name, Box::new(tool));
+                    self.query_doc_types.insert(doc_type);
                     registered_count += 1;
                 }
                 Err(e) => {
-                    warn!(
-                        "Failed to create tool '{}': {}. Skipping.",
-                        tool_config.name, e
-                    );
+                    warn!("Failed to create query tool '{}': {}. Skipping.", name, e);
+                }
+            }
+        }
+
+        Ok(registered_count)
+    }
+
+    /// Register the enabled tools listed in `config`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the entry if tool creation fails.
+    fn register_dynamic_tools(
+        tools: &mut HashMap<String, Box<dyn Tool + Send + Sync>>,
+        query_doc_types: &mut HashSet<String>,
+        db_pool: &DatabasePool,
+        config: &ToolsConfig,
+    ) -> Result<usize> {
+        let mut registered_count = 0;
+
+        for (index, tool_config) in config.tools.iter().enumerate() {
+            if !tool_config.enabled {
+                continue;
+            }
+
+            // Skip rust_query if it appears in config since we register it hardcoded
+            if tool_config.name == "rust_query" {
+                debug!("Skipping rust_query from config - already registered as hardcoded");
+                continue;
+            }
+
+            // Management tools are known by name; any other tool queries its doc_type
+            let tool = match Self::create_management_tool(tool_config, db_pool) {
+                Ok(Some(tool)) => Ok(tool),
+                Ok(None) => {
+                    DynamicQueryTool::new(tool_config.clone(), db_pool.clone()).map(|tool| {
+                        query_doc_types.insert(tool.doc_type().to_string());
+                        Box::new(tool) as Box<dyn Tool + Send + Sync>
+                    })
                 }
+                Err(e) => Err(e),
             }
+            .map_err(|e| {
+                anyhow!(
+                    "tools[{index}] ('{}'): failed to create tool: {e}",
+                    tool_config.name
+                )
+            })?;
+            debug!(
+                "Created dynamic tool '{}' for doc_type '{}'",
+                tool_config.name, tool_config.doc_type
+            );
+            tools.insert(tool_config.name.clone(), tool);
+            registered_count += 1;
         }
 
         Ok(registered_count)
     }
 
-    /// Create a tool instance from configuration
+    /// Create the management tool `tool_config` names, or `None` when it
+    /// names a query tool
     ///
     /// # Errors
     ///
     /// Returns an error if tool creation fails.
-    fn create_tool_from_config(
-        tool_config: &db::models::ToolConfig,
+    fn create_management_tool(
+        tool_config: &ToolConfig,
         db_pool: &DatabasePool,
-    ) -> Result<Box<dyn Tool + Send + Sync>> {
-        match tool_config.name.as_str() {
+    ) -> Result<Option<Box<dyn Tool + Send + Sync>>> {
+        let tool: Box<dyn Tool + Send + Sync> = match tool_config.name.as_str() {
             // Crate management tools
             "add_rust_crate" => {
-                let embedding_client: Arc<dyn embed::client::EmbeddingClient + Send + Sync> =
-                    Arc::new(OpenAIEmbeddingClient::new()?);
-                Ok(Box::new(AddRustCrateTool::new(
+                let embedding_client = embedding_client_from_env()?;
+                Box::new(AddRustCrateTool::new(db_pool.clone(), embedding_client))
+            }
+            "add_local_crate" => {
+                let embedding_client = embedding_client_from_env()?;
+                Box::new(AddLocalCrateTool::new(db_pool.clone(), embedding_client))
+            }
+            "reprocess_rust_crate" => {
+                let embedding_client = embedding_client_from_env()?;
+                Box::new(ReprocessRustCrateTool::new(
                     db_pool.clone(),
                     embedding_client,
-                )))
+                ))
+            }
+            "remove_rust_crate" => Box::new(RemoveRustCrateTool::new(db_pool.clone())),
+            "restore_rust_crate" => Box::new(RestoreRustCrateTool::new(db_pool.clone())),
+            "retry_rust_job" => Box::new(RetryRustJobTool::new(db_pool.clone())),
+            "backfill_embeddings" => {
+                let embedding_client = embedding_client_from_env()?;
+                Box::new(BackfillEmbeddingsTool::new(
+                    db_pool.clone(),
+                    embedding_client,
+                ))
+            }
+            "list_rust_crates" => Box::new(ListRustCratesTool::new(db_pool.clone())),
+            "check_rust_status" => Box::new(CheckRustStatusTool::new(db_pool.clone())),
+            "diff_rust_crate_versions" => Box::new(DiffRustCrateVersionsTool::new(db_pool.clone())),
+            // npm package management tools
+            "add_npm_package" => {
+                let embedding_client = embedding_client_from_env()?;
+                Box::new(AddNpmPackageTool::new(db_pool.clone(), embedding_client))
             }
-            "remove_rust_crate" => Ok(Box::new(RemoveRustCrateTool::new(db_pool.clone()))),
-            "list_rust_crates" => Ok(Box::new(ListRustCratesTool::new(db_pool.clone()))),
-            "check_rust_status" => Ok(Box::new(CheckRustStatusTool::new(db_pool.clone()))),
-            // Query tools - use the existing dynamic pattern
-            _ => Ok(Box::new(DynamicQueryTool::new(
-                tool_config.clone(),
-                db_pool.clone(),
-            )?)),
+            "remove_npm_package" => Box::new(RemoveNpmPackageTool::new(db_pool.clone())),
+            "list_npm_packages" => Box::new(ListNpmPackagesTool::new(db_pool.clone())),
+            // Python package management tools
+            "add_python_package" => {
+                let embedding_client = embedding_client_from_env()?;
+                Box::new(AddPythonPackageTool::new(db_pool.clone(), embedding_client))
+            }
+            "remove_python_package" => Box::new(RemovePythonPackageTool::new(db_pool.clone())),
+            "list_python_packages" => Box::new(ListPythonPackagesTool::new(db_pool.clone())),
+            // Query tools - built by the caller
+            _ => return Ok(None),
+        };
+        Ok(Some(tool))
+    }
+
+    /// Override the number of descriptors returned per `resources/list` page
+    #[must_use]
+    pub fn with_resource_page_size(mut self, page_size: i64) -> Self {
+        self.resource_page_size = page_size.max(1);
+        self
+    }
+
+    /// Override the content cap applied by `resources/read`
+    #[must_use]
+    pub fn with_resource_max_chars(mut self, max_chars: usize) -> Self {
+        self.resource_max_chars = max_chars.max(1);
+        self
+    }
+
+    /// Cut every tool result to `max_chars`, keeping the rest behind a
+    /// continuation token; 0 leaves results uncut
+    #[must_use]
+    pub fn with_max_response_chars(mut self, max_chars: usize) -> Self {
+        self.max_response_chars = (max_chars > 0).then_some(max_chars);
+        self
+    }
+
+    /// Record every tool call in the audit log through `audit`
+    #[must_use]
+    pub fn with_audit_logger(mut self, audit: AuditLogger) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Number of tool calls currently executing
+    #[must_use]
+    pub fn in_flight_calls(&self) -> usize {
+        self.in_flight.lock().map_or(0, |calls| calls.len())
+    }
+
+    /// Register a tool under the given name, replacing any existing tool
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tool's `inputSchema` is not a valid JSON Schema.
+    pub fn register_tool(&mut self, name: impl Into<String>, tool: Box<dyn Tool + Send + Sync>) {
+        let name = name.into();
+        match ToolSchema::compile(&name, &tool.definition()) {
+            Ok(Some(schema)) => {
+                self.schemas.insert(name.clone(), schema);
+            }
+            Ok(None) => {
+                self.schemas.remove(&name);
+            }
+            Err(e) => panic!("{e}"),
         }
+        self.switches.register(&name);
+        self.tools.insert(name, tool);
+    }
+
+    /// Use `budget` for calls to `tool` instead of its configured budget
+    #[must_use]
+    pub fn with_tool_timeout(mut self, tool: &str, budget: Duration) -> Self {
+        self.timeouts.set(tool, budget);
+        self
+    }
+
+    /// Limit concurrent tool calls with `concurrency` instead of the
+    /// limits from the environment
+    #[must_use]
+    pub fn with_tool_concurrency(mut self, concurrency: ToolConcurrency) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Limit for a whole HTTP request, slightly above every tool budget
+    #[must_use]
+    pub fn http_timeout(&self) -> Duration {
+        self.timeouts.http_timeout()
+    }
+
+    /// Per-tool enabled flags, shared with `set_tool_enabled` and the admin routes
+    #[must_use]
+    pub const fn tool_switches(&self) -> &ToolSwitches {
+        &self.switches
     }
 
     /// Handle an MCP request
@@ -147,6 +486,42 @@ impl McpHandler {
     ///
     /// Returns an error when the request is malformed or tool execution fails.
     pub async fn handle_request(&self, request: Value) -> Result<Value> {
+        self.handle_session_request(request, None).await
+    }
+
+    /// Handle an MCP request on behalf of a session
+    ///
+    /// Tool calls are tracked per session so a later `notifications/cancelled`
+    /// carrying the same request ID can abort them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the request is malformed or tool execution fails,
+    /// and `RequestCancelled` when the client cancelled the call.
+    pub async fn handle_session_request(
+        &self,
+        request: Value,
+        session_id: Option<&str>,
+    ) -> Result<Value> {
+        self.handle_caller_request(request, &ToolCaller::from_session(session_id))
+            .await
+    }
+
+    /// Handle an MCP request on behalf of an identified caller
+    ///
+    /// Like [`Self::handle_session_request`], with the client label and user
+    /// agent recorded in the audit log for tool calls.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the request is malformed or tool execution fails,
+    /// and `RequestCancelled` when the client cancelled the call.
+    pub async fn handle_caller_request(
+        &self,
+        request: Value,
+        caller: &ToolCaller,
+    ) -> Result<Value> {
+        let session_id = caller.session_id.as_deref();
         debug!("Processing MCP request");
 
         // Extract method from request
@@ -157,7 +532,9 @@ impl McpHandler {
 
         match method {
             "tools/list" => Ok(self.handle_tools_list()),
-            "tools/call" => self.handle_tool_call(&request).await,
+            "tools/call" => self.handle_tool_call(&request, caller).await,
+            "resources/list" => self.handle_resources_list(&request).await,
+            "resources/read" => self.handle_resources_read(&request).await,
             "initialize" => Ok(Self::handle_initialize(&request)),
             "notifications/initialized" => {
                 // This notification should only be sent AFTER receiving initialize response
@@ -168,21 +545,134 @@ impl McpHandler {
                 // Return empty result for notification (no response body expected)
                 Ok(json!({}))
             }
+            "notifications/cancelled" => {
+                self.handle_cancelled(&request, session_id);
+                Ok(json!({}))
+            }
             _ => Err(anyhow!("Unsupported method: {}", method)),
         }
     }
 
-    /// Handle tools/list request
+    /// Handle tools/list request, leaving out tools switched off at runtime
     fn handle_tools_list(&self) -> Value {
-        let tools: Vec<Value> = self.tools.values().map(|tool| tool.definition()).collect();
+        let tools: Vec<Value> = self
+            .tools
+            .iter()
+            .filter(|(name, _)| self.switches.is_enabled(name))
+            .map(|(_, tool)| tool.definition())
+            .collect();
 
         json!({
             "tools": tools
         })
     }
 
+    /// Handle resources/list with cursor pagination
+    async fn handle_resources_list(&self, request: &Value) -> Result<Value> {
+        let cursor = request
+            .get("params")
+            .and_then(|p| p.get("cursor"))
+            .and_then(Value::as_str)
+            .map(|c| db::Uuid::parse_str(c).map_err(|_| anyhow!("Invalid cursor: {}", c)))
+            .transpose()?;
+
+        let (resources, next_cursor) =
+            DocumentQueries::list_resources(self.db_pool.pool(), cursor, self.resource_page_size)
+                .await?;
+
+        let mut result = json!({
+            "resources": resources.iter().map(resource_descriptor).collect::<Vec<_>>()
+        });
+        if let Some(next) = next_cursor {
+            result["nextCursor"] = json!(next.to_string());
+        }
+        Ok(result)
+    }
+
+    /// Handle resources/read for a single document URI
+    async fn handle_resources_read(&self, request: &Value) -> Result<Value> {
+        let uri = request
+            .get("params")
+            .and_then(|p| p.get("uri"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("Missing resource uri"))?;
+        let (doc_type, source_name, doc_path) = parse_resource_uri(uri)?;
+
+        let document =
+            DocumentQueries::find_by_path(self.db_pool.pool(), &doc_type, &source_name, &doc_path)
+                .await?
+                .ok_or_else(|| anyhow!("Resource not found: {}", uri))?;
+
+        Ok(json!({
+            "contents": [resource_contents(&document, self.resource_max_chars)]
+        }))
+    }
+
+    /// Handle notifications/cancelled by triggering the matching call's token
+    ///
+    /// Only calls of the same session can be cancelled; without a session
+    /// there is nothing to tie the notification to its caller.
+    fn handle_cancelled(&self, request: &Value, session_id: Option<&str>) {
+        let Some(request_id) = request.get("params").and_then(|p| p.get("requestId")) else {
+            warn!("notifications/cancelled without params.requestId");
+            return;
+        };
+        let Some(session_id) = session_id else {
+            debug!(
+                "Ignoring cancellation of request {} without a session",
+                request_id
+            );
+            return;
+        };
+        let key = (session_id.to_string(), request_id.to_string());
+        let token = self
+            .in_flight
+            .lock()
+            .ok()
+            .and_then(|calls| calls.get(&key).cloned());
+
+        if let Some(token) = token {
+            let reason = request
+                .get("params")
+                .and_then(|p| p.get("reason"))
+                .and_then(Value::as_str)
+                .unwrap_or("no reason given");
+            info!("Cancelling request {}: {}", request_id, reason);
+            token.cancel();
+        } else {
+            // Already finished or unknown; the spec says to ignore these
+            debug!("Ignoring cancellation for unknown request {}", request_id);
+        }
+    }
+
+    /// Register a tool call so it can be cancelled, returning its context
+    ///
+    /// Callers without a session would share one namespace of request IDs and
+    /// could cancel each other's calls, so their calls are registered under a
+    /// unique key no notification can name; shutdown still waits for them.
+    fn track_call(
+        &self,
+        request: &Value,
+        session_id: Option<&str>,
+    ) -> (ExecutionContext, Option<InFlightGuard>) {
+        let token = CancellationToken::new();
+        let guard = request.get("id").filter(|id| !id.is_null()).and_then(|id| {
+            let key = match session_id {
+                Some(session_id) => (session_id.to_string(), id.to_string()),
+                None => (String::new(), uuid::Uuid::new_v4().to_string()),
+            };
+            let mut calls = self.in_flight.lock().ok()?;
+            calls.insert(key.clone(), token.clone());
+            Some(InFlightGuard {
+                calls: Arc::clone(&self.in_flight),
+                key,
+            })
+        });
+        (ExecutionContext::new(token).with_session(session_id), guard)
+    }
+
     /// Handle tools/call request
-    async fn handle_tool_call(&self, request: &Value) -> Result<Value> {
+    async fn handle_tool_call(&self, request: &Value, caller: &ToolCaller) -> Result<Value> {
         let params = request
             .get("params")
             .ok_or_else(|| anyhow!("Missing params in tool call"))?;
@@ -201,23 +691,115 @@ impl McpHandler {
             .tools
             .get(tool_name)
             .ok_or_else(|| anyhow!("Unknown tool: {}", tool_name))?;
+        if !self.switches.is_enabled(tool_name) {
+            return Err(ToolDisabled(tool_name.to_string()).into());
+        }
+        if let Some(schema) = self.schemas.get(tool_name) {
+            schema.validate(tool_name, arguments)?;
+        }
 
-        match tool.execute(arguments.clone()).await {
-            Ok(result) => Ok(json!({
-                "content": [
-                    {
-                        "type": "text",
-                        "text": result
+        // Held until the call returns; refused calls never reach the tool
+        let _permit = self
+            .concurrency
+            .acquire(caller.session_id.as_deref())
+            .await
+            .inspect_err(|e| warn!("Tool call {} refused: {}", tool_name, e))?;
+        let (context, _guard) = self.track_call(request, caller.session_id.as_deref());
+        let span = tracing::info_span!(
+            "tool_call",
+            tool = %tool_name,
+            argument_bytes = tracing::field::Empty
+        );
+        // Serializing the arguments only pays off when the span is recorded
+        if !span.is_disabled() {
+            span.record("argument_bytes", arguments.to_string().len());
+        }
+        let started = std::time::Instant::now();
+        let outcome = if tool.requires_admin() && !caller.admin {
+            Err(StructuredToolError {
+                kind: "forbidden".to_string(),
+                message: format!("{tool_name} requires the admin token (MCP_ADMIN_TOKEN)"),
+            }
+            .into())
+        } else {
+            let budget = self.timeouts.budget(tool_name);
+            tokio::time::timeout(
+                budget,
+                tool.execute_with_context(arguments.clone(), &context)
+                    .instrument(span),
+            )
+            .await
+            .unwrap_or_else(|_| {
+                warn!("Tool call {} timed out after {:?}", tool_name, budget);
+                metrics().increment_tool_timeouts(tool_name);
+                Err(ToolTimedOut {
+                    tool: tool_name.to_string(),
+                    budget,
+                }
+                .into())
+            })
+        };
+        let elapsed = started.elapsed();
+        let error_message = outcome.as_ref().err().map(ToString::to_string);
+        metrics().record_tool_call(tool_name, elapsed, error_message.as_deref());
+
+        if let Some(audit) = &self.audit {
+            let error = if context.is_cancelled() {
+                Some("cancelled by client")
+            } else {
+                error_message.as_deref()
+            };
+            audit.record(caller, tool_name, arguments, elapsed, error);
+        }
+
+        // A cancelled call never produces a result, even if the tool finished anyway
+        if context.is_cancelled() {
+            metrics().increment_cancelled_requests();
+            info!("Tool call {} cancelled by client", tool_name);
+            return Err(RequestCancelled.into());
+        }
+
+        match outcome {
+            Ok(result) => {
+                let cap = match (tool.response_soft_cap(), self.max_response_chars) {
+                    (Some(soft), Some(max)) => Some(soft.min(max)),
+                    (soft, max) => soft.or(max),
+                };
+                let result = match cap {
+                    Some(cap) => {
+                        continuations().truncate_text(caller.session_id.as_deref(), result, cap)
                     }
-                ]
-            })),
+                    None => result,
+                };
+                Ok(json!({
+                    "content": [
+                        {
+                            "type": "text",
+                            "text": result
+                        }
+                    ]
+                }))
+            }
+            // Timeouts and coded tool errors become JSON-RPC errors rather than tool results
+            Err(e)
+                if e.downcast_ref::<ToolTimedOut>().is_some()
+                    || e.downcast_ref::<ToolError>().is_some() =>
+            {
+                Err(e)
+            }
             Err(e) => {
                 error!("Tool execution failed: {}", e);
+                let text = match e.downcast_ref::<StructuredToolError>() {
+                    Some(structured) => {
+                        serde_json::to_string_pretty(&json!({ "error": structured }))?
+                    }
+                    None => format!("Error: {e}"),
+                };
                 Ok(json!({
                     "content": [
                         {
                             "type": "text",
-                            "text": format!("Error: {}", e)
+                            "text": text
                         }
                     ],
                     "isError": true
@@ -243,6 +825,9 @@ impl McpHandler {
             "capabilities": {
                 "tools": {
                     "listChanged": true
+                },
+                "resources": {
+                    "listChanged": false
                 }
             },
             "serverInfo": {
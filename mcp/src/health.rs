@@ -4,13 +4,305 @@
 //! Kubernetes readiness and liveness probes, with detailed status reporting
 //! and connection pool monitoring.
 
-use axum::{extract::State, http::StatusCode, response::Json, routing::get, Router};
-use db::PoolStatus;
+use axum::{
+    extract::{FromRef, State},
+    http::{header, StatusCode},
+    response::Json,
+    routing::get,
+    Router,
+};
+use db::{DatabaseMigrationManager, DatabasePool, MigrateOnStart, PoolStatus};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use crate::maintenance::{MaintenanceScheduler, MaintenanceTaskStatus};
+use crate::provider_health::{provider_checks, ProviderChecks, ProviderReport};
 use crate::server::McpServerState;
 
+/// Longest wait for the database ping behind `/health`
+const DB_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest wait for Redis to answer `PING`
+const REDIS_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Thresholds past which `/health` reports a dependency as degraded
+#[derive(Debug, Clone)]
+pub struct HealthConfig {
+    /// Database round trip above which the database counts as slow
+    pub db_latency_degraded: Duration,
+    /// Share of `max_connections` in use above which the pool counts as saturated
+    pub pool_saturation_degraded_percent: f64,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            db_latency_degraded: Duration::from_millis(250),
+            pool_saturation_degraded_percent: 90.0,
+        }
+    }
+}
+
+impl HealthConfig {
+    /// Build health thresholds from environment variables with defaults
+    ///
+    /// Supported variables:
+    /// - `HEALTH_DB_LATENCY_DEGRADED_MS` (default 250)
+    /// - `HEALTH_POOL_SATURATION_DEGRADED_PERCENT` (default 90)
+    #[must_use]
+    pub fn from_env() -> Self {
+        let mut cfg = Self::default();
+        if let Some(ms) = std::env::var("HEALTH_DB_LATENCY_DEGRADED_MS")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+        {
+            cfg.db_latency_degraded = Duration::from_millis(ms);
+        }
+        if let Some(percent) = std::env::var("HEALTH_POOL_SATURATION_DEGRADED_PERCENT")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+        {
+            cfg.pool_saturation_degraded_percent = percent;
+        }
+        cfg
+    }
+}
+
+/// State shared by the health endpoints
+#[derive(Clone)]
+pub struct HealthState {
+    pub db_pool: DatabasePool,
+    pub config: HealthConfig,
+    /// Migrations this build expects, reported in `/health` and `/health/ready`
+    pub migrations: Option<Arc<DatabaseMigrationManager>>,
+    /// Whether readiness waits while any of `migrations` is pending
+    pub migrations_gate_readiness: bool,
+    /// Maintenance tasks reported in `/health`
+    pub maintenance: Option<MaintenanceScheduler>,
+    /// Provider checks for `/health/ready`; `None` uses [`provider_checks`]
+    pub providers: Option<Arc<ProviderChecks>>,
+}
+
+impl HealthState {
+    /// Health state with thresholds from the environment and no migration check
+    #[must_use]
+    pub fn new(db_pool: DatabasePool) -> Self {
+        Self {
+            db_pool,
+            config: HealthConfig::from_env(),
+            migrations: None,
+            migrations_gate_readiness: false,
+            maintenance: None,
+            providers: None,
+        }
+    }
+
+    /// Use the given degraded thresholds
+    #[must_use]
+    pub fn with_config(mut self, config: HealthConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Report pending migrations from `manager`, holding readiness until none
+    /// remain unless `mode` is [`MigrateOnStart::Off`]
+    #[must_use]
+    pub fn with_migrations(
+        mut self,
+        manager: DatabaseMigrationManager,
+        mode: MigrateOnStart,
+    ) -> Self {
+        self.migrations = Some(Arc::new(manager));
+        self.migrations_gate_readiness = mode.gates_readiness();
+        self
+    }
+
+    /// Report the last run of `scheduler`'s tasks
+    #[must_use]
+    pub fn with_maintenance(mut self, scheduler: MaintenanceScheduler) -> Self {
+        self.maintenance = Some(scheduler);
+        self
+    }
+
+    /// Probe `providers` instead of the clients built from the environment
+    #[must_use]
+    pub fn with_providers(mut self, providers: ProviderChecks) -> Self {
+        self.providers = Some(Arc::new(providers));
+        self
+    }
+
+    fn providers(&self) -> &ProviderChecks {
+        match &self.providers {
+            Some(providers) => providers,
+            None => provider_checks(),
+        }
+    }
+}
+
+impl FromRef<McpServerState> for HealthState {
+    fn from_ref(state: &McpServerState) -> Self {
+        state.health.clone()
+    }
+}
+
+/// Dependency report returned by `/health`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub status: HealthStatus,
+    pub service: String,
+    pub version: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub database: DatabaseReport,
+    pub pool: PoolReport,
+    /// Transient database errors retried since start
+    pub retries: db::RetryCounts,
+    /// `None` when the server was started without registered migrations
+    pub migrations: Option<MigrationReport>,
+    /// `None` unless the Redis queue is enabled
+    pub redis: Option<RedisReport>,
+    /// Jobs still running an hour after their last update; `None` if not counted
+    pub stuck_jobs: Option<i64>,
+    /// Last run of each maintenance task; `None` without a scheduler
+    pub maintenance: Option<Vec<MaintenanceTaskStatus>>,
+    /// Why the status is not `healthy`
+    pub reasons: Vec<String>,
+}
+
+/// Database connectivity as seen by a fresh ping
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseReport {
+    pub connected: bool,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Connection counts from the pool metrics snapshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolReport {
+    pub size: u32,
+    pub idle: u32,
+    pub in_use: u32,
+    pub max_connections: u32,
+    pub saturation_percent: f64,
+    /// Effective pool configuration
+    pub settings: PoolSettings,
+}
+
+/// Pool configuration the server runs with, without the connection URL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolSettings {
+    pub min_connections: u32,
+    pub max_connections: u32,
+    pub acquire_timeout_ms: u64,
+    pub idle_timeout_secs: Option<u64>,
+    pub max_lifetime_secs: Option<u64>,
+    /// `None` when the server's `statement_timeout` is kept
+    pub statement_timeout_ms: Option<u64>,
+    pub application_name: String,
+}
+
+impl From<&db::PoolConfig> for PoolSettings {
+    fn from(config: &db::PoolConfig) -> Self {
+        Self {
+            min_connections: config.min_connections,
+            max_connections: config.max_connections,
+            acquire_timeout_ms: config.acquire_timeout_ms,
+            idle_timeout_secs: config.idle_timeout_seconds,
+            max_lifetime_secs: config.max_lifetime_seconds,
+            statement_timeout_ms: config.statement_timeout_ms,
+            application_name: config.application_name.clone(),
+        }
+    }
+}
+
+/// Migration progress; counts are zero when `error` is set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationReport {
+    pub pending: usize,
+    pub failed: usize,
+    /// IDs of the pending migrations
+    pub pending_migrations: Vec<String>,
+    /// Newest applied migration ID
+    pub schema_version: Option<String>,
+    pub error: Option<String>,
+}
+
+impl MigrationReport {
+    fn unavailable(error: String) -> Self {
+        Self {
+            pending: 0,
+            failed: 0,
+            pending_migrations: Vec::new(),
+            schema_version: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// Redis queue reachability
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisReport {
+    pub reachable: bool,
+    pub error: Option<String>,
+}
+
+impl HealthReport {
+    /// Set the overall status and reasons from the collected checks
+    fn evaluate(&mut self, config: &HealthConfig) {
+        let mut status = HealthStatus::Healthy;
+        let mut reasons = Vec::new();
+
+        if self.database.connected {
+            let latency_limit = config.db_latency_degraded.as_millis();
+            if u128::from(self.database.latency_ms) > latency_limit {
+                status = HealthStatus::Degraded;
+                reasons.push(format!(
+                    "database latency {}ms exceeds {latency_limit}ms",
+                    self.database.latency_ms
+                ));
+            }
+            if self.pool.saturation_percent > config.pool_saturation_degraded_percent {
+                status = HealthStatus::Degraded;
+                reasons.push(format!(
+                    "connection pool {:.0}% saturated ({}/{} in use)",
+                    self.pool.saturation_percent, self.pool.in_use, self.pool.max_connections
+                ));
+            }
+        } else {
+            status = HealthStatus::Unhealthy;
+            reasons.push("database unreachable".to_string());
+        }
+
+        if let Some(migrations) = &self.migrations {
+            if let Some(error) = &migrations.error {
+                status = elevate_overall(status, HealthStatus::Degraded);
+                reasons.push(format!("migration status unavailable: {error}"));
+            } else if migrations.pending > 0 {
+                status = elevate_overall(status, HealthStatus::Degraded);
+                reasons.push(format!("{} migrations pending", migrations.pending));
+            }
+        }
+
+        if let Some(redis) = self.redis.as_ref().filter(|r| !r.reachable) {
+            status = elevate_overall(status, HealthStatus::Degraded);
+            reasons.push(format!(
+                "redis unreachable: {}",
+                redis.error.as_deref().unwrap_or("unknown error")
+            ));
+        }
+
+        if let Some(stuck) = self.stuck_jobs.filter(|&n| n > 0) {
+            status = elevate_overall(status, HealthStatus::Degraded);
+            reasons.push(format!("{stuck} stuck jobs"));
+        }
+
+        self.status = status;
+        self.reasons = reasons;
+    }
+}
+
 /// Overall service health status
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServiceHealthStatus {
@@ -46,6 +338,12 @@ pub struct ReadinessStatus {
     pub ready: bool,
     pub reason: Option<String>,
     pub checks: Vec<ReadinessCheck>,
+    /// Newest applied migration ID; `None` without registered migrations
+    pub schema_version: Option<String>,
+    /// Migrations registered by this build but not yet applied
+    pub pending_migrations: Vec<String>,
+    /// Embedding and LLM provider reachability, keyed by provider
+    pub providers: BTreeMap<String, ProviderReport>,
 }
 
 /// Individual readiness check
@@ -80,77 +378,177 @@ fn get_uptime_seconds() -> u64 {
 }
 
 /// Create health check router
-pub fn create_health_router() -> Router<McpServerState> {
+pub fn create_health_router<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    HealthState: FromRef<S>,
+{
     Router::new()
         .route("/health", get(health_check))
         .route("/health/ready", get(readiness_check))
         .route("/health/live", get(liveness_check))
         .route("/health/detailed", get(detailed_health_check))
+        .route("/metrics", get(prometheus_metrics))
 }
 
-/// Basic health check endpoint
+/// Prometheus scrape endpoint: tool call statistics, model API costs and
+/// concurrency limit counters
+async fn prometheus_metrics() -> ([(header::HeaderName, &'static str); 1], String) {
+    let metrics = crate::metrics::metrics();
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics.tool_metrics_prometheus()
+            + &metrics.api_cost_prometheus()
+            + &metrics.tool_concurrency_prometheus(),
+    )
+}
+
+/// Dependency health endpoint
 ///
-/// Returns simple JSON status suitable for load balancers and basic monitoring.
-/// This endpoint is lightweight and cached for high-frequency checks.
-async fn health_check(
-    State(state): State<McpServerState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // Quick database ping with timeout
-    match tokio::time::timeout(std::time::Duration::from_secs(5), state.db_pool.ping()).await {
-        Ok(Ok(())) => Ok(Json(serde_json::json!({
-            "status": "healthy",
-            "service": "mcp",
-            "version": env!("CARGO_PKG_VERSION"),
-            "timestamp": chrono::Utc::now()
-        }))),
-        _ => Err(StatusCode::SERVICE_UNAVAILABLE),
-    }
+/// Reports database latency, pool usage, pending migrations, Redis
+/// reachability (with the Redis queue) and stuck jobs. Slow or saturated
+/// dependencies make the status `degraded`; only an unreachable database
+/// answers 503.
+async fn health_check(State(state): State<HealthState>) -> (StatusCode, Json<HealthReport>) {
+    let report = collect_health_report(&state).await;
+    let status_code = if report.status == HealthStatus::Unhealthy {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+    (status_code, Json(report))
+}
+
+async fn collect_health_report(state: &HealthState) -> HealthReport {
+    let start = Instant::now();
+    let ping = tokio::time::timeout(DB_CHECK_TIMEOUT, state.db_pool.ping()).await;
+    let latency_ms = u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX);
+    let database = match ping {
+        Ok(Ok(())) => DatabaseReport {
+            connected: true,
+            latency_ms,
+            error: None,
+        },
+        Ok(Err(e)) => DatabaseReport {
+            connected: false,
+            latency_ms,
+            error: Some(e.to_string()),
+        },
+        Err(_) => DatabaseReport {
+            connected: false,
+            latency_ms,
+            error: Some("Database ping timeout".to_string()),
+        },
+    };
+
+    let metrics = state.db_pool.get_metrics_snapshot();
+    let pool_config = state.db_pool.config();
+    let max_connections = pool_config.max_connections;
+    let pool = PoolReport {
+        size: metrics.pool_size,
+        idle: metrics.idle_connections,
+        in_use: metrics.in_use_connections,
+        max_connections,
+        saturation_percent: if max_connections > 0 {
+            f64::from(metrics.in_use_connections) / f64::from(max_connections) * 100.0
+        } else {
+            0.0
+        },
+        settings: PoolSettings::from(pool_config),
+    };
+
+    // Skip the remaining queries once the ping has shown the database is down
+    let migrations = match &state.migrations {
+        None => None,
+        Some(_) if !database.connected => Some(MigrationReport::unavailable(
+            "database unreachable".to_string(),
+        )),
+        Some(manager) => Some(match manager.get_migration_status().await {
+            Ok(summary) => MigrationReport {
+                pending: summary.pending,
+                failed: summary.failed,
+                pending_migrations: summary.pending_migrations,
+                schema_version: summary.schema_version,
+                error: None,
+            },
+            Err(e) => MigrationReport::unavailable(e.to_string()),
+        }),
+    };
+
+    let stuck_jobs = if database.connected {
+        count_stuck_jobs(&state.db_pool)
+            .await
+            .ok()
+            .map(|(crate_stuck, ingest_stuck, _)| crate_stuck + ingest_stuck)
+    } else {
+        None
+    };
+
+    let redis = if crate::queue::use_redis_queue() {
+        Some(
+            match tokio::time::timeout(REDIS_CHECK_TIMEOUT, crate::queue::ping()).await {
+                Ok(Ok(())) => RedisReport {
+                    reachable: true,
+                    error: None,
+                },
+                Ok(Err(e)) => RedisReport {
+                    reachable: false,
+                    error: Some(e.to_string()),
+                },
+                Err(_) => RedisReport {
+                    reachable: false,
+                    error: Some("Redis ping timeout".to_string()),
+                },
+            },
+        )
+    } else {
+        None
+    };
+
+    let mut report = HealthReport {
+        status: HealthStatus::Healthy,
+        service: "mcp".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        timestamp: chrono::Utc::now(),
+        database,
+        pool,
+        retries: db::retry_counts(),
+        migrations,
+        redis,
+        stuck_jobs,
+        maintenance: state
+            .maintenance
+            .as_ref()
+            .map(MaintenanceScheduler::statuses),
+        reasons: Vec::new(),
+    };
+    report.evaluate(&state.config);
+    report
 }
 
 /// Kubernetes readiness probe endpoint
 ///
-/// Checks if the service is ready to receive traffic.
-/// This includes database connectivity and migration status.
-async fn readiness_check(
-    State(state): State<McpServerState>,
-) -> (StatusCode, Json<ReadinessStatus>) {
+/// Checks if the service is ready to receive traffic: the database answers,
+/// the pool has room, no registered migration is pending (unless
+/// `MIGRATE_ON_START=false`), with the Redis queue, Redis answers and
+/// every provider marked required answers its probe. Readiness drops while another pod migrates the schema and returns once it
+/// finishes, without restarting this pod. The response carries the schema
+/// version, the pending migration IDs and the status of each provider.
+async fn readiness_check(State(state): State<HealthState>) -> (StatusCode, Json<ReadinessStatus>) {
     let mut checks = Vec::new();
-    let mut overall_ready = true;
+    let report = collect_health_report(&state).await;
 
-    // Database connectivity check
-    let db_check = if let Ok(Ok(health)) = tokio::time::timeout(
-        std::time::Duration::from_secs(10),
-        state.db_pool.health_check(),
-    )
-    .await
-    {
-        let ready = health.is_healthy;
-        if !ready {
-            overall_ready = false;
-        }
-        ReadinessCheck {
-            name: "database".to_string(),
-            ready,
-            message: health.error_message,
-        }
-    } else {
-        overall_ready = false;
-        ReadinessCheck {
-            name: "database".to_string(),
-            ready: false,
-            message: Some("Database health check timeout".to_string()),
-        }
-    };
-    checks.push(db_check);
+    checks.push(ReadinessCheck {
+        name: "database".to_string(),
+        ready: report.database.connected,
+        message: report.database.error.clone(),
+    });
 
     // Connection pool check
     let pool_status = match state.db_pool.get_status().await {
         Ok(status) => {
             let pool_ready = status.pool_utilization_percent < 95.0
                 && status.metrics.success_rate_percent > 90.0;
-            if !pool_ready {
-                overall_ready = false;
-            }
             ReadinessCheck {
                 name: "connection_pool".to_string(),
                 ready: pool_ready,
@@ -164,17 +562,57 @@ async fn readiness_check(
                 },
             }
         }
-        Err(e) => {
-            overall_ready = false;
-            ReadinessCheck {
-                name: "connection_pool".to_string(),
-                ready: false,
-                message: Some(format!("Pool status check failed: {e}")),
-            }
-        }
+        Err(e) => ReadinessCheck {
+            name: "connection_pool".to_string(),
+            ready: false,
+            message: Some(format!("Pool status check failed: {e}")),
+        },
     };
     checks.push(pool_status);
 
+    if let Some(migrations) = report
+        .migrations
+        .as_ref()
+        .filter(|_| state.migrations_gate_readiness)
+    {
+        let ready = migrations.error.is_none() && migrations.pending == 0;
+        checks.push(ReadinessCheck {
+            name: "migrations".to_string(),
+            ready,
+            message: migrations.error.clone().or_else(|| {
+                (!ready).then(|| {
+                    format!(
+                        "{} migrations pending: {}",
+                        migrations.pending,
+                        migrations.pending_migrations.join(", ")
+                    )
+                })
+            }),
+        });
+    }
+
+    if let Some(redis) = &report.redis {
+        checks.push(ReadinessCheck {
+            name: "redis".to_string(),
+            ready: redis.reachable,
+            message: redis.error.clone(),
+        });
+    }
+
+    let providers = state.providers().check().await;
+    for (name, provider) in providers.iter().filter(|(_, p)| p.required) {
+        checks.push(ReadinessCheck {
+            name: name.clone(),
+            ready: provider.ready(),
+            message: provider.error.clone(),
+        });
+    }
+
+    let overall_ready = checks.iter().all(|check| check.ready);
+    let (schema_version, pending_migrations) = report
+        .migrations
+        .map(|m| (m.schema_version, m.pending_migrations))
+        .unwrap_or_default();
     let status = ReadinessStatus {
         ready: overall_ready,
         reason: if overall_ready {
@@ -183,6 +621,9 @@ async fn readiness_check(
             Some("One or more readiness checks failed".to_string())
         },
         checks,
+        schema_version,
+        pending_migrations,
+        providers,
     };
 
     let status_code = if overall_ready {
@@ -212,7 +653,7 @@ async fn liveness_check() -> Json<LivenessStatus> {
 /// and comprehensive monitoring. This endpoint is more expensive and should
 /// be used sparingly.
 async fn detailed_health_check(
-    State(state): State<McpServerState>,
+    State(state): State<HealthState>,
 ) -> (StatusCode, Json<ServiceHealthStatus>) {
     let mut checks = HashMap::new();
     let mut overall_status = HealthStatus::Healthy;
@@ -250,45 +691,49 @@ async fn detailed_health_check(
     (status_code, Json(health_status))
 }
 
-async fn build_jobs_health(state: &McpServerState) -> (String, ComponentHealth, HealthStatus) {
-    // Consider jobs stuck if 'running' and updated_at older than 1 hour
-    let stuck_threshold = "1 hour"; // SQL interval string
+/// Jobs still `running` an hour after their last update
+const STUCK_THRESHOLD: &str = "1 hour"; // SQL interval string
 
-    let res = async {
-        let crate_stuck: i64 = sqlx::query_scalar(
-            &format!(
-                "SELECT COUNT(*) FROM crate_jobs WHERE status = 'running' AND updated_at < NOW() - INTERVAL '{stuck_threshold}'"
-            ),
-        )
-        .fetch_one(state.db_pool.pool())
-        .await?;
+/// Count stuck crate and ingest jobs, with the oldest one's age in minutes
+async fn count_stuck_jobs(db_pool: &DatabasePool) -> anyhow::Result<(i64, i64, Option<i64>)> {
+    let stuck_threshold = STUCK_THRESHOLD;
+    let crate_stuck: i64 = sqlx::query_scalar(
+        &format!(
+            "SELECT COUNT(*) FROM crate_jobs WHERE status = 'running' AND updated_at < NOW() - INTERVAL '{stuck_threshold}'"
+        ),
+    )
+    .fetch_one(db_pool.pool())
+    .await?;
 
-        let ingest_stuck: i64 = sqlx::query_scalar(
-            &format!(
-                "SELECT COUNT(*) FROM ingest_jobs WHERE status = 'running' AND updated_at < NOW() - INTERVAL '{stuck_threshold}'"
-            ),
-        )
-        .fetch_one(state.db_pool.pool())
-        .await?;
-
-        // Oldest age of a stuck job in minutes (max staleness)
-        let oldest_minutes: Option<i64> = sqlx::query_scalar(
-            &format!(
-                "SELECT COALESCE(MAX(EXTRACT(EPOCH FROM (NOW() - updated_at))::bigint / 60), 0)
-                 FROM (
-                   SELECT updated_at FROM crate_jobs WHERE status='running' AND updated_at < NOW() - INTERVAL '{stuck_threshold}'
-                   UNION ALL
-                   SELECT updated_at FROM ingest_jobs WHERE status='running' AND updated_at < NOW() - INTERVAL '{stuck_threshold}'
-                 ) t"
-            ),
-        )
-        .fetch_optional(state.db_pool.pool())
-        .await?
-        .flatten();
+    let ingest_stuck: i64 = sqlx::query_scalar(
+        &format!(
+            "SELECT COUNT(*) FROM ingest_jobs WHERE status = 'running' AND updated_at < NOW() - INTERVAL '{stuck_threshold}'"
+        ),
+    )
+    .fetch_one(db_pool.pool())
+    .await?;
 
-        anyhow::Ok((crate_stuck, ingest_stuck, oldest_minutes))
-    }
-    .await;
+    // Oldest age of a stuck job in minutes (max staleness)
+    let oldest_minutes: Option<i64> = sqlx::query_scalar(
+        &format!(
+            "SELECT COALESCE(MAX(EXTRACT(EPOCH FROM (NOW() - updated_at))::bigint / 60), 0)
+             FROM (
+               SELECT updated_at FROM crate_jobs WHERE status='running' AND updated_at < NOW() - INTERVAL '{stuck_threshold}'
+               UNION ALL
+               SELECT updated_at FROM ingest_jobs WHERE status='running' AND updated_at < NOW() - INTERVAL '{stuck_threshold}'
+             ) t"
+        ),
+    )
+    .fetch_optional(db_pool.pool())
+    .await?
+    .flatten();
+
+    Ok((crate_stuck, ingest_stuck, oldest_minutes))
+}
+
+async fn build_jobs_health(state: &HealthState) -> (String, ComponentHealth, HealthStatus) {
+    let stuck_threshold = STUCK_THRESHOLD;
+    let res = count_stuck_jobs(&state.db_pool).await;
 
     match res {
         Ok((crate_stuck, ingest_stuck, oldest_minutes)) => {
@@ -330,7 +775,7 @@ async fn build_jobs_health(state: &McpServerState) -> (String, ComponentHealth,
     }
 }
 
-async fn build_database_health(state: &McpServerState) -> (String, ComponentHealth, HealthStatus) {
+async fn build_database_health(state: &HealthState) -> (String, ComponentHealth, HealthStatus) {
     let start = std::time::Instant::now();
     match state.db_pool.health_check().await {
         Ok(health) => {
@@ -368,7 +813,7 @@ async fn build_database_health(state: &McpServerState) -> (String, ComponentHeal
     }
 }
 
-async fn build_pool_health(state: &McpServerState) -> (String, ComponentHealth, HealthStatus) {
+async fn build_pool_health(state: &HealthState) -> (String, ComponentHealth, HealthStatus) {
     let start = std::time::Instant::now();
     match state.db_pool.get_status().await {
         Ok(pool_status) => {
@@ -476,6 +921,9 @@ mod tests {
                 query_failures: 0,
                 success_rate_percent: 100.0,
                 last_health_check_ago_seconds: 10,
+                pool_size: 10,
+                idle_connections: 5,
+                in_use_connections: 5,
             },
             health: db::HealthCheckResult {
                 is_healthy: true,
@@ -524,4 +972,76 @@ mod tests {
             HealthStatus::Unhealthy
         );
     }
+
+    #[test]
+    fn test_health_report_thresholds() {
+        let config = HealthConfig::default();
+        let mut report = HealthReport {
+            status: HealthStatus::Healthy,
+            service: "mcp".to_string(),
+            version: "test".to_string(),
+            timestamp: chrono::Utc::now(),
+            database: DatabaseReport {
+                connected: true,
+                latency_ms: 20,
+                error: None,
+            },
+            pool: PoolReport {
+                size: 10,
+                idle: 6,
+                in_use: 4,
+                max_connections: 20,
+                saturation_percent: 20.0,
+                settings: PoolSettings::from(&db::PoolConfig::default()),
+            },
+            retries: db::RetryCounts::default(),
+            migrations: Some(MigrationReport {
+                pending: 0,
+                failed: 0,
+                pending_migrations: Vec::new(),
+                schema_version: Some("023_tool_settings".to_string()),
+                error: None,
+            }),
+            redis: None,
+            stuck_jobs: Some(0),
+            maintenance: None,
+            reasons: Vec::new(),
+        };
+
+        report.evaluate(&config);
+        assert_eq!(report.status, HealthStatus::Healthy);
+        assert!(report.reasons.is_empty());
+
+        // Slow database and a nearly full pool degrade
+        report.database.latency_ms = 300;
+        report.pool.saturation_percent = 95.0;
+        report.evaluate(&config);
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert_eq!(report.reasons.len(), 2);
+
+        // Thresholds are configurable
+        let relaxed = HealthConfig {
+            db_latency_degraded: Duration::from_millis(500),
+            pool_saturation_degraded_percent: 99.0,
+        };
+        report.evaluate(&relaxed);
+        assert_eq!(report.status, HealthStatus::Healthy);
+
+        // Pending migrations degrade without failing the database
+        report.migrations = Some(MigrationReport {
+            pending: 2,
+            failed: 0,
+            pending_migrations: vec!["022_tool_audit_log".into(), "023_tool_settings".into()],
+            schema_version: Some("021_crate_job_events".to_string()),
+            error: None,
+        });
+        report.evaluate(&relaxed);
+        assert_eq!(report.status, HealthStatus::Degraded);
+        assert_eq!(report.reasons, vec!["2 migrations pending".to_string()]);
+
+        // An unreachable database is unhealthy
+        report.database.connected = false;
+        report.evaluate(&relaxed);
+        assert_eq!(report.status, HealthStatus::Unhealthy);
+    }
 }
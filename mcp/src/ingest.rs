@@ -5,11 +5,13 @@ use serde_json::json;
 use tokio::io::AsyncReadExt;
 use tokio::process::Command as TokioCommand;
 
+use crate::job_queue::IngestJobProcessor;
+use crate::query_cache::query_cache;
 use crate::server::McpServerState;
 use discovery::{IntelligentRepositoryAnalyzer, RepositoryAnalysis};
 use std::fmt::Write as _;
 use std::sync::{Arc, OnceLock};
-use tokio::sync::{oneshot, Semaphore};
+use tokio::sync::{mpsc, Semaphore};
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
@@ -121,108 +123,165 @@ impl IngestJobManager {
                 // Global concurrency cap for ingest jobs
                 let _permit = get_ingest_semaphore().acquire_owned().await.ok();
                 info!(%job_id, %url, %doc_type, "Starting intelligent ingest job");
-                let _ = db::queries::IngestJobQueries::update_job_status(
-                    db_pool.pool(),
-                    job_id,
-                    JobStatus::Running,
-                    None,
-                    None,
-                )
-                .await;
-
-                // Heartbeat task to keep updated_at fresh while job runs
-                let (hb_tx, mut hb_rx) = oneshot::channel::<()>();
-                let hb_pool = db_pool.clone();
-                let hb_job_id = job_id;
-                let hb_handle = tokio::spawn(async move {
-                    let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
-                    loop {
-                        tokio::select! {
-                            _ = interval.tick() => {
-                                let _ = db::queries::IngestJobQueries::update_job_status(
-                                    hb_pool.pool(),
-                                    hb_job_id,
-                                    JobStatus::Running,
-                                    None,
-                                    None,
-                                ).await;
+                let processor = IngestJobProcessor::new(db_pool.clone());
+                let _ = processor.transition(job_id, JobStatus::Running, None).await;
+
+                processor
+                    .run_with_heartbeat(job_id, async {
+                        // 1) Run discovery (Claude Code) to get a plan
+                        let analysis = match IntelligentRepositoryAnalyzer::from_env() {
+                            Ok(mut analyzer) => analyzer.analyze_repository(&url).await,
+                            Err(e) => Err(e),
+                        };
+                        match analysis {
+                            // 2) Execute plan with strict allowlist
+                            Ok(analysis) => {
+                                run_plan_for_job(&db_pool, job_id, &analysis, &doc_type, &url)
+                                    .await;
                             }
-                            _ = &mut hb_rx => {
-                                break;
+                            Err(e) => {
+                                warn!(%job_id, err = %e, "Discovery failed");
+                                let _ = processor
+                                    .transition(job_id, JobStatus::Failed, Some(&e.to_string()))
+                                    .await;
                             }
                         }
-                    }
-                });
-
-                // 1) Run discovery (Claude Code) to get a plan
-                let mut analyzer = IntelligentRepositoryAnalyzer::new();
-
-                let analysis = match analyzer.analyze_repository(&url).await {
-                    Ok(a) => a,
-                    Err(e) => {
-                        warn!(%job_id, err = %e, "Discovery failed");
-                        let _ = db::queries::IngestJobQueries::update_job_status(
-                            db_pool.pool(),
-                            job_id,
-                            JobStatus::Failed,
-                            None,
-                            Some(&e.to_string()),
-                        )
-                        .await;
-                        // Stop heartbeat
-                        let _ = hb_tx.send(());
-                        let _ = hb_handle.await;
-                        return;
-                    }
-                };
-
-                // 2) Execute plan with strict allowlist
-                let exec_res = execute_cli_plan(&analysis, &doc_type, &url).await;
-                match exec_res {
-                    Ok(output) => {
-                        debug!(%job_id, out_len = output.len(), "Ingest completed");
-                        let _ = db::queries::IngestJobQueries::update_job_status(
-                            db_pool.pool(),
-                            job_id,
-                            JobStatus::Completed,
-                            Some(&output),
-                            None,
-                        )
-                        .await;
-                    }
-                    Err(e) => {
-                        warn!(%job_id, err = %e, "Ingest plan execution failed");
-                        let _ = db::queries::IngestJobQueries::update_job_status(
-                            db_pool.pool(),
-                            job_id,
-                            JobStatus::Failed,
-                            None,
-                            Some(&e.to_string()),
-                        )
-                        .await;
-                    }
-                }
+                    })
+                    .await;
+            });
+        }
+
+        Ok(job_id)
+    }
+
+    /// Create an ingest job for an already analyzed plan and execute it in the background.
+    ///
+    /// Unlike [`Self::enqueue`], discovery is not run again; `repo_url` is the
+    /// repository URL or local checkout path the plan was made for.
+    ///
+    /// # Errors
+    /// Returns an error if the job record cannot be created or queued.
+    pub async fn enqueue_plan(
+        &self,
+        repo_url: String,
+        doc_type: String,
+        analysis: RepositoryAnalysis,
+    ) -> anyhow::Result<Uuid> {
+        let created =
+            db::queries::IngestJobQueries::create_job(self.db_pool.pool(), &repo_url, &doc_type)
+                .await?;
+        let job_id = created.id;
 
-                // Stop heartbeat
-                let _ = hb_tx.send(());
-                let _ = hb_handle.await;
+        if crate::queue::use_redis_queue() {
+            let msg = crate::queue::RedisJobMessage::new(
+                job_id,
+                "ingest",
+                3,
+                json!({ "url": repo_url, "doc_type": doc_type, "plan": analysis }),
+            );
+            crate::queue::enqueue_job(&msg).await?;
+        } else {
+            let db_pool = self.db_pool.clone();
+            tokio::spawn(async move {
+                let _permit = get_ingest_semaphore().acquire_owned().await.ok();
+                info!(%job_id, %repo_url, %doc_type, "Starting planned ingest job");
+                let processor = IngestJobProcessor::new(db_pool.clone());
+                let _ = processor.transition(job_id, JobStatus::Running, None).await;
+                processor
+                    .run_with_heartbeat(
+                        job_id,
+                        run_plan_for_job(&db_pool, job_id, &analysis, &doc_type, &repo_url),
+                    )
+                    .await;
             });
         }
 
         Ok(job_id)
     }
+}
 
-    /// Start a background cleanup task that prunes old jobs periodically
-    pub fn start_cleanup_task(&self) {
-        let db_pool = self.db_pool.clone();
-        // Run every 5 minutes
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(std::time::Duration::from_secs(300));
-            loop {
-                interval.tick().await;
-                let _ = db::queries::IngestJobQueries::cleanup_old_jobs(db_pool.pool()).await;
-            }
-        });
+/// Execute `analysis` for ingest job `job_id` and record the outcome on the job.
+///
+/// While the plan runs, the job's `output` shows the step in progress, e.g.
+/// `Step 2/3: loader cli …`; it is replaced by a document count summary and
+/// the combined step output once the plan completes.
+pub async fn run_plan_for_job(
+    db_pool: &DatabasePool,
+    job_id: Uuid,
+    analysis: &RepositoryAnalysis,
+    doc_type: &str,
+    repo_url: &str,
+) {
+    let (progress_tx, mut progress_rx) = mpsc::unbounded_channel::<String>();
+    let progress_pool = db_pool.clone();
+    let progress_writer = tokio::spawn(async move {
+        while let Some(line) = progress_rx.recv().await {
+            let _ = db::queries::IngestJobQueries::update_job_status(
+                progress_pool.pool(),
+                job_id,
+                JobStatus::Running,
+                Some(&line),
+                None,
+            )
+            .await;
+        }
+    });
+
+    let documents_before = db::DocumentQueries::count_by_type(db_pool.pool(), doc_type)
+        .await
+        .ok();
+    let on_step = move |step: usize, total: usize, command: &str| {
+        let _ = progress_tx.send(format!("Step {step}/{total}: {command}"));
+    };
+    let exec_res = execute_cli_plan_with_progress(analysis, doc_type, repo_url, &on_step).await;
+    // The loader may have stored documents even if a later step failed
+    query_cache().invalidate_doc_type(doc_type);
+    // Dropping the sender ends the writer; wait so progress never lands after the result
+    drop(on_step);
+    let _ = progress_writer.await;
+
+    match exec_res {
+        Ok(output) => {
+            debug!(%job_id, out_len = output.len(), "Ingest completed");
+            let documents_after = db::DocumentQueries::count_by_type(db_pool.pool(), doc_type)
+                .await
+                .ok();
+            let output = format!(
+                "{}\n\n{output}",
+                document_summary(doc_type, documents_before, documents_after)
+            );
+            let _ = db::queries::IngestJobQueries::update_job_status(
+                db_pool.pool(),
+                job_id,
+                JobStatus::Completed,
+                Some(&output),
+                None,
+            )
+            .await;
+        }
+        Err(e) => {
+            warn!(%job_id, err = %e, "Ingest plan execution failed");
+            let _ = db::queries::IngestJobQueries::update_job_status(
+                db_pool.pool(),
+                job_id,
+                JobStatus::Failed,
+                None,
+                Some(&e.to_string()),
+            )
+            .await;
+        }
+    }
+}
+
+/// First line of a completed job's output: documents stored for the doc type
+fn document_summary(doc_type: &str, before: Option<i64>, after: Option<i64>) -> String {
+    match (before, after) {
+        (Some(before), Some(after)) => format!(
+            "Documents: {after} stored for doc_type '{doc_type}' ({:+} from this job)",
+            after - before
+        ),
+        (_, Some(after)) => format!("Documents: {after} stored for doc_type '{doc_type}'"),
+        _ => format!("Documents: count unavailable for doc_type '{doc_type}'"),
     }
 }
 
@@ -243,7 +302,7 @@ fn get_ingest_semaphore() -> Arc<Semaphore> {
         .clone()
 }
 
-fn work_base() -> std::path::PathBuf {
+pub(crate) fn work_base() -> std::path::PathBuf {
     static WORK_BASE: std::sync::OnceLock<std::path::PathBuf> = std::sync::OnceLock::new();
     WORK_BASE
         .get_or_init(|| {
@@ -280,11 +339,25 @@ fn generate_unique_repo_dir(repo_url: &str) -> String {
 ///
 /// # Errors
 /// Returns an error if commands fail validation or execution fails.
-#[allow(clippy::too_many_lines)]
 pub async fn execute_cli_plan(
     analysis: &RepositoryAnalysis,
     doc_type: &str,
     repo_url: &str,
+) -> anyhow::Result<String> {
+    execute_cli_plan_with_progress(analysis, doc_type, repo_url, &|_, _, _| {}).await
+}
+
+/// Execute discovery CLI commands, calling `on_step(step, total, command)`
+/// before each planned step starts.
+///
+/// # Errors
+/// Returns an error if commands fail validation or execution fails.
+#[allow(clippy::too_many_lines)]
+pub async fn execute_cli_plan_with_progress(
+    analysis: &RepositoryAnalysis,
+    doc_type: &str,
+    repo_url: &str,
+    on_step: &(dyn Fn(usize, usize, &str) + Send + Sync),
 ) -> anyhow::Result<String> {
     // Ensure the work base exists so any nested paths can be created by tools
     if let Err(e) = std::fs::create_dir_all(work_base()) {
@@ -304,18 +377,10 @@ pub async fn execute_cli_plan(
         .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
         .unwrap_or(false);
 
+    let total_steps = analysis.cli_commands.len();
     for (i, original_cmd) in analysis.cli_commands.iter().enumerate() {
-        // Remap placeholders and /tmp to work_base
-        let cmd = original_cmd
-            .replace(
-                "UNIQUE_REPO_DIR",
-                &work_base().join(&unique_repo_dir).to_string_lossy(),
-            )
-            .replace(
-                "UNIQUE_DOCS_OUT",
-                &work_base().join(&unique_docs_dir).to_string_lossy(),
-            )
-            .replace("/tmp", work_base().to_string_lossy().as_ref());
+        on_step(i + 1, total_steps, original_cmd);
+        let cmd = remap_plan_paths(original_cmd, &unique_repo_dir, &unique_docs_dir);
 
         // Ensure output directories exist before running commands
         let unique_docs_out_path = work_base().join(&unique_docs_dir);
@@ -329,36 +394,9 @@ pub async fn execute_cli_plan(
 
         // Normalize cargo/loader invocations
         let (program, mut args) = normalize_command(&cmd, doc_type);
-        // If this is a git clone step, ensure a safe destination under work_base
-        if program == "git" && !args.is_empty() && args[0] == "clone" {
-            // Count non-option args excluding the subcommand itself to detect if a dest dir is provided
-            let non_opts: Vec<&String> = args
-                .iter()
-                .skip(1) // skip "clone"
-                .filter(|a| !a.starts_with('-'))
-                .collect();
-
-            // If only a single non-option exists, it's the repo URL and no dest was provided
-            if non_opts.len() < 2 {
-                args.push(
-                    work_base()
-                        .join(&unique_repo_dir)
-                        .to_string_lossy()
-                        .to_string(),
-                );
-            } else if let Some(dest) = args.last().cloned() {
-                // If a dest exists but isn't under the work base, force it into the safe work base
-                let dest_path = std::path::PathBuf::from(&dest);
-                if !dest_path.starts_with(work_base()) {
-                    // Replace the last argument with our safe destination
-                    if let Some(last) = args.last_mut() {
-                        *last = work_base()
-                            .join(&unique_repo_dir)
-                            .to_string_lossy()
-                            .to_string();
-                    }
-                }
-            }
+        // The clone command is rebuilt from the job's URL; the step only picks depth and branch
+        if program == "git" {
+            args = clone_args(repo_url, &args, &work_base().join(&unique_repo_dir))?;
         }
 
         // Allowlist check (after we may have patched args for safety)
@@ -479,7 +517,9 @@ pub async fn execute_cli_plan(
         command.args(&args);
         let out = run_cmd(command).await?;
         write!(&mut combined, "\n# Step {} output:\n{}\n", i + 1, out)?;
-        if program == loader_bin().to_string_lossy() && !args.is_empty() && args[0] == "cli" {
+        if program == loader_bin().to_string_lossy()
+            && matches!(args.first().map(String::as_str), Some("cli" | "web"))
+        {
             executed_cli_steps += 1;
         }
     }
@@ -535,6 +575,154 @@ pub async fn execute_cli_plan(
     Ok(combined)
 }
 
+/// Replace the plan placeholders `UNIQUE_REPO_DIR`/`UNIQUE_DOCS_OUT` and `/tmp`
+/// with directories under the work base
+pub(crate) fn remap_plan_paths(cmd: &str, repo_dir: &str, docs_dir: &str) -> String {
+    cmd.replace(
+        "UNIQUE_REPO_DIR",
+        &work_base().join(repo_dir).to_string_lossy(),
+    )
+    .replace(
+        "UNIQUE_DOCS_OUT",
+        &work_base().join(docs_dir).to_string_lossy(),
+    )
+    .replace("/tmp", work_base().to_string_lossy().as_ref())
+}
+
+/// Whether `name` is usable as a branch or tag name in a clone command
+fn is_safe_ref(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && !name.contains("..")
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'))
+}
+
+/// Build the `git clone` arguments for a plan step
+///
+/// The step's own arguments are not trusted: only `--depth` (default 1) and
+/// `--branch` are taken from it. The URL is `repo_url`, which must be
+/// `https://`, and follows `--` so it cannot be read as an option; the
+/// checkout goes to `dest`.
+///
+/// # Errors
+/// Returns an error if the step is not `git clone`, carries any other option,
+/// or `repo_url` is not an HTTPS URL.
+pub(crate) fn clone_args(
+    repo_url: &str,
+    step_args: &[String],
+    dest: &std::path::Path,
+) -> anyhow::Result<Vec<String>> {
+    if step_args.first().map(String::as_str) != Some("clone") {
+        return Err(anyhow::anyhow!("only 'git clone' is allowed"));
+    }
+    let url = url::Url::parse(repo_url)
+        .ok()
+        .filter(|u| u.scheme() == "https" && u.host().is_some())
+        .ok_or_else(|| anyhow::anyhow!("git clone needs an https:// URL, got '{repo_url}'"))?;
+
+    let mut depth = "1".to_string();
+    let mut branch = None;
+    let mut rest = step_args[1..].iter();
+    while let Some(arg) = rest.next() {
+        let (flag, inline) = arg
+            .split_once('=')
+            .map_or((arg.as_str(), None), |(f, v)| (f, Some(v.to_string())));
+        match flag {
+            "--" => break,
+            "--depth" => {
+                depth = inline
+                    .or_else(|| rest.next().cloned())
+                    .filter(|d| d.parse::<u32>().is_ok_and(|d| d > 0))
+                    .ok_or_else(|| anyhow::anyhow!("git clone --depth needs a positive number"))?;
+            }
+            "--branch" | "-b" => {
+                branch = Some(
+                    inline
+                        .or_else(|| rest.next().cloned())
+                        .filter(|b| is_safe_ref(b))
+                        .ok_or_else(|| anyhow::anyhow!("git clone --branch needs a branch name"))?,
+                );
+            }
+            _ if arg.starts_with('-') => {
+                return Err(anyhow::anyhow!("git clone option not allowed: {arg}"));
+            }
+            // The URL and destination are the server's own
+            _ => {}
+        }
+    }
+
+    let mut args = vec!["clone".to_string(), "--depth".to_string(), depth];
+    if let Some(branch) = branch {
+        args.extend(["--branch".to_string(), branch]);
+    }
+    args.extend([
+        "--".to_string(),
+        url.to_string(),
+        dest.to_string_lossy().to_string(),
+    ]);
+    Ok(args)
+}
+
+/// Whether `path` is a plain path inside the work base
+fn under_work_base(path: &str) -> bool {
+    let path = std::path::Path::new(path);
+    path.starts_with(work_base())
+        && !path
+            .components()
+            .any(|c| c == std::path::Component::ParentDir)
+}
+
+/// Directory local checkouts must live under, from `INGEST_LOCAL_ROOT`
+///
+/// Defaults to the ingest work directory so a client cannot point the
+/// loader at arbitrary host paths.
+pub(crate) fn local_root() -> std::path::PathBuf {
+    std::env::var("INGEST_LOCAL_ROOT").map_or_else(|_| work_base(), std::path::PathBuf::from)
+}
+
+/// Whether `path` is a plain path inside the work base or the local checkout root
+fn under_input_root(path: &str) -> bool {
+    if under_work_base(path) {
+        return true;
+    }
+    let root = local_root();
+    let root = root.canonicalize().unwrap_or(root);
+    let path = std::path::Path::new(path);
+    path.starts_with(root)
+        && !path
+            .components()
+            .any(|c| c == std::path::Component::ParentDir)
+}
+
+/// Output and input directories named by loader arguments (`-o`, `--output`,
+/// `-i`, `--input-dir`, in any of their spellings)
+fn loader_paths(args: &[String]) -> Vec<&str> {
+    let mut paths = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-o" | "--output" | "-i" | "--input-dir" => {
+                if let Some(value) = iter.next() {
+                    paths.push(value.as_str());
+                }
+            }
+            a => {
+                if let Some(value) = a
+                    .strip_prefix("--output=")
+                    .or_else(|| a.strip_prefix("--input-dir="))
+                    .or_else(|| a.strip_prefix("-o").filter(|_| !a.starts_with("--")))
+                    .or_else(|| a.strip_prefix("-i").filter(|_| !a.starts_with("--")))
+                {
+                    paths.push(value.trim_start_matches('='));
+                }
+            }
+        }
+    }
+    paths
+}
+
 pub(crate) fn normalize_command(cmd: &str, doc_type: &str) -> (String, Vec<String>) {
     let loader = loader_bin().to_string_lossy().to_string();
     let parts: Vec<&str> = cmd.split_whitespace().collect();
@@ -632,40 +820,66 @@ pub(crate) fn ensure_allowed(program: &str, args: &[String]) -> anyhow::Result<(
             if args.is_empty() {
                 return Err(anyhow::anyhow!("missing loader subcommand"));
             }
-            match args[0].as_str() {
-                "cli" | "database" => Ok(()),
-                other => Err(anyhow::anyhow!(format!(
+            if !matches!(args[0].as_str(), "cli" | "database" | "web") {
+                return Err(anyhow::anyhow!(format!(
                     "loader subcommand not allowed: {}",
-                    other
-                ))),
+                    args[0]
+                )));
             }
-        }
-        "git" => {
-            // allow: git clone --depth 1 <url> <dest>
-            if args.first().map(String::as_str) != Some("clone") {
-                return Err(anyhow::anyhow!("only 'git clone' is allowed"));
-            }
-            if !args
-                .iter()
-                .any(|a| a == "--depth" || a.starts_with("--depth"))
-            {
-                return Err(anyhow::anyhow!("git clone must include --depth"));
+            if let Some(path) = loader_paths(args).into_iter().find(|p| !under_work_base(p)) {
+                return Err(anyhow::anyhow!(format!(
+                    "loader path is outside work base: path={path:?}, base={:?}",
+                    work_base()
+                )));
             }
-            if let Some(dest) = args.last() {
-                let base = work_base();
-                let dest_path = std::path::Path::new(dest);
-                if ingest_debug_enabled() {
-                    tracing::debug!("Path validation: base={:?}, dest={:?}", base, dest_path);
-                }
-                if !dest_path.starts_with(&base) {
+            if args[0] == "cli" {
+                let input = args
+                    .get(1)
+                    .ok_or_else(|| anyhow::anyhow!("loader cli needs an input path"))?;
+                if !under_input_root(input) {
                     return Err(anyhow::anyhow!(format!(
-                        "git clone destination is outside work base: dest={:?}, base={:?}",
-                        dest_path, base
+                        "loader input is outside the repository: path={input:?}, base={:?}, local_root={:?}",
+                        work_base(),
+                        local_root()
                     )));
                 }
             }
             Ok(())
         }
+        "git" => {
+            // allow exactly what clone_args builds:
+            // git clone --depth N [--branch B] -- <https url> <dest>
+            let rest = match args {
+                [clone, depth, n, rest @ ..]
+                    if clone == "clone"
+                        && depth == "--depth"
+                        && n.parse::<u32>().is_ok_and(|n| n > 0) =>
+                {
+                    rest
+                }
+                _ => return Err(anyhow::anyhow!("only 'git clone --depth N' is allowed")),
+            };
+            let rest = match rest {
+                [flag, name, rest @ ..] if flag == "--branch" && is_safe_ref(name) => rest,
+                _ => rest,
+            };
+            let [separator, url, dest] = rest else {
+                return Err(anyhow::anyhow!("git clone arguments not allowed: {args:?}"));
+            };
+            if separator != "--" || !url.starts_with("https://") {
+                return Err(anyhow::anyhow!("git clone needs '-- <https url>'"));
+            }
+            let base = work_base();
+            if ingest_debug_enabled() {
+                tracing::debug!("Path validation: base={:?}, dest={:?}", base, dest);
+            }
+            if !under_work_base(dest) {
+                return Err(anyhow::anyhow!(format!(
+                    "git clone destination is outside work base: dest={dest:?}, base={base:?}"
+                )));
+            }
+            Ok(())
+        }
         _ => Err(anyhow::anyhow!(format!("program not allowed: {program}"))),
     }
 }
@@ -683,7 +897,10 @@ pub(crate) fn sanitize_loader_args(args: &mut Vec<String>) {
     let mut i = 0;
     while i < args.len() {
         let a = &args[i];
-        if a == "--exclude-patterns" || a == "--include-patterns" {
+        if a == "--exclude-patterns" || a == "--exclude-dirs" || a == "--exclude-paths" {
+            // translate to --exclude, which takes the same comma-separated globs
+            out.push("--exclude".to_string());
+        } else if a == "--include-patterns" {
             // drop this flag and consume its value if present and not another flag
             if i + 1 < args.len() && !args[i + 1].starts_with('-') {
                 i += 1; // skip value
@@ -0,0 +1,747 @@
+//! Repository analysis and planned ingestion tools for MCP
+//!
+//! `analyze_repository` asks the discovery analyzer for an ingest plan and
+//! keeps it server-side under a plan ID without running anything;
+//! `execute_ingest_plan` turns a plan into an `ingest_jobs` row and runs its
+//! loader steps in the background; `check_ingest_status` reports on those
+//! jobs.
+
+use crate::ingest::{
+    clone_args, ensure_allowed, local_root, normalize_command, remap_plan_paths, work_base,
+    IngestJobManager,
+};
+use crate::tools::{ExecutionContext, RequestCancelled, StructuredToolError, Tool};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use db::models::{IngestJob, JobStatus, STUCK_JOB_MINUTES};
+use db::{DatabasePool, IngestJobQueries};
+use discovery::{
+    prompt_runner_from_env, AnalysisError, IntelligentRepositoryAnalyzer, LlmUseCase, PromptRunner,
+    RepositoryAnalysis, RepositorySource,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// How long an analyzed plan can be executed by its ID
+pub const PLAN_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Plans kept at once; the oldest is dropped beyond this
+const MAX_PLANS: usize = 100;
+
+/// Characters of a job's output shown by `check_ingest_status`
+const OUTPUT_EXCERPT_CHARS: usize = 2000;
+
+/// Maximum finished jobs per `check_ingest_status` page
+const MAX_JOB_PAGE_SIZE: i64 = 50;
+
+/// Default limit on a single `analyze_repository` run, in seconds
+const DEFAULT_ANALYZE_TIMEOUT_SECS: u64 = 300;
+
+/// Limit on a single analyzer run, from `DISCOVERY_TIMEOUT_SECS`
+fn analyze_timeout() -> Duration {
+    Duration::from_secs(
+        std::env::var("DISCOVERY_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&secs| secs > 0)
+            .unwrap_or(DEFAULT_ANALYZE_TIMEOUT_SECS),
+    )
+}
+
+/// Hosts client plans may crawl besides the repository's, from
+/// `INGEST_WEB_ALLOWED_HOSTS` (comma-separated)
+fn web_allowed_hosts() -> Vec<String> {
+    std::env::var("INGEST_WEB_ALLOWED_HOSTS")
+        .map(|hosts| {
+            hosts
+                .split(',')
+                .map(|host| host.trim().to_ascii_lowercase())
+                .filter(|host| !host.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Repository a plan was made for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanSource {
+    /// Remote repository URL
+    RepoUrl(String),
+    /// Checkout on this host
+    LocalPath(PathBuf),
+}
+
+impl PlanSource {
+    /// URL or path recorded on the ingest job
+    #[must_use]
+    pub fn location(&self) -> String {
+        match self {
+            Self::RepoUrl(url) => url.clone(),
+            Self::LocalPath(path) => path.to_string_lossy().to_string(),
+        }
+    }
+}
+
+/// Analysis kept server-side until executed or expired
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestPlan {
+    /// Source the analysis describes
+    pub source: PlanSource,
+    /// Analyzer output, including the loader steps to run
+    pub analysis: RepositoryAnalysis,
+}
+
+/// In-memory store of analyzed plans keyed by plan ID
+pub struct IngestPlanStore {
+    ttl: Duration,
+    plans: Mutex<HashMap<Uuid, (Instant, IngestPlan)>>,
+}
+
+impl IngestPlanStore {
+    /// Create a store whose plans expire after `ttl`
+    #[must_use]
+    pub fn with_ttl(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            plans: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Store `plan` and return its ID
+    pub fn insert(&self, plan: IngestPlan) -> Uuid {
+        let plan_id = Uuid::new_v4();
+        if let Ok(mut plans) = self.plans.lock() {
+            let ttl = self.ttl;
+            plans.retain(|_, (created, _)| created.elapsed() < ttl);
+            if plans.len() >= MAX_PLANS {
+                if let Some(oldest) = plans
+                    .iter()
+                    .min_by_key(|(_, (created, _))| *created)
+                    .map(|(id, _)| *id)
+                {
+                    plans.remove(&oldest);
+                }
+            }
+            plans.insert(plan_id, (Instant::now(), plan));
+        }
+        plan_id
+    }
+
+    /// Look up an unexpired plan
+    #[must_use]
+    pub fn get(&self, plan_id: Uuid) -> Option<IngestPlan> {
+        let plans = self.plans.lock().ok()?;
+        plans
+            .get(&plan_id)
+            .filter(|(created, _)| created.elapsed() < self.ttl)
+            .map(|(_, plan)| plan.clone())
+    }
+}
+
+impl Default for IngestPlanStore {
+    fn default() -> Self {
+        Self::with_ttl(PLAN_TTL)
+    }
+}
+
+static INGEST_PLANS: LazyLock<IngestPlanStore> = LazyLock::new(IngestPlanStore::default);
+
+/// Process-wide plan store shared by the analysis and execution tools
+#[must_use]
+pub fn ingest_plans() -> &'static IngestPlanStore {
+    &INGEST_PLANS
+}
+
+/// Structured tool error for a failed analysis
+fn analysis_failure(error: anyhow::Error) -> anyhow::Error {
+    if error.is::<RequestCancelled>() {
+        return error;
+    }
+    let kind = error
+        .downcast_ref::<AnalysisError>()
+        .map_or("analysis_failed", AnalysisError::kind);
+    StructuredToolError {
+        kind: kind.to_string(),
+        message: error.to_string(),
+    }
+    .into()
+}
+
+/// Structured tool error for an unusable request
+fn invalid_source(message: impl Into<String>) -> anyhow::Error {
+    StructuredToolError {
+        kind: "invalid_source".to_string(),
+        message: message.into(),
+    }
+    .into()
+}
+
+/// Resolve a client-supplied checkout path, which must lie under [`local_root`]
+fn resolve_local_path(path: &str) -> Result<PathBuf> {
+    let root = local_root();
+    let root = root
+        .canonicalize()
+        .map_err(|e| invalid_source(format!("local root {} unavailable: {e}", root.display())))?;
+    let resolved = Path::new(path)
+        .canonicalize()
+        .map_err(|e| invalid_source(format!("local_path {path} unavailable: {e}")))?;
+    if !resolved.starts_with(&root) {
+        return Err(invalid_source(format!(
+            "local_path {} is outside the ingest root {}",
+            resolved.display(),
+            root.display()
+        )));
+    }
+    if !resolved.is_dir() {
+        return Err(invalid_source(format!(
+            "local_path {} is not a directory",
+            resolved.display()
+        )));
+    }
+    Ok(resolved)
+}
+
+/// Reject plans whose steps fall outside the allowlist or read outside the repository
+///
+/// Every `loader cli` path must stay inside the cloned repository
+/// (`UNIQUE_REPO_DIR`) or the local checkout, and loader outputs inside the
+/// work base. A `git clone` step is rebuilt from the plan's source, so only
+/// its depth and branch are checked here. Plans sent by the client
+/// (`from_client`) may not load documents themselves: their `database` step
+/// is added by the server. Their `web` crawls must target the repository's
+/// host or one listed in `INGEST_WEB_ALLOWED_HOSTS`.
+fn validate_plan(plan: &IngestPlan, doc_type: &str, from_client: bool) -> Result<()> {
+    if plan.analysis.cli_commands.is_empty() {
+        return Err(anyhow!("Plan has no steps to execute"));
+    }
+    for command in &plan.analysis.cli_commands {
+        let (program, args) = normalize_command(command, doc_type);
+        if program == "git" {
+            let PlanSource::RepoUrl(url) = &plan.source else {
+                return Err(anyhow!("Plan clones into a local checkout: {command}"));
+            };
+            clone_args(url, &args, &work_base())
+                .map_err(|e| anyhow!("Plan step not allowed: {e}"))?;
+            continue;
+        }
+        let remapped = remap_plan_paths(command, "plan", "plan_out");
+        let (_, remapped_args) = normalize_command(&remapped, doc_type);
+        ensure_allowed(&program, &remapped_args)
+            .map_err(|e| anyhow!("Plan step not allowed: {e}"))?;
+        if args[0] == "database" && from_client {
+            return Err(anyhow!(
+                "Plan step not allowed: database steps are added by the server"
+            ));
+        }
+        if args[0] == "web" {
+            // Crawls stay on the base URL's origin
+            let base = args
+                .get(1)
+                .and_then(|u| url::Url::parse(u).ok())
+                .filter(|u| matches!(u.scheme(), "http" | "https"));
+            let Some(base) = base else {
+                return Err(anyhow!("Plan step has no http(s) base URL: {command}"));
+            };
+            if from_client {
+                let host = base.host_str().unwrap_or_default().to_ascii_lowercase();
+                let repo_host = match &plan.source {
+                    PlanSource::RepoUrl(url) => url::Url::parse(url)
+                        .ok()
+                        .and_then(|u| u.host_str().map(str::to_ascii_lowercase)),
+                    PlanSource::LocalPath(_) => None,
+                };
+                if repo_host.as_deref() != Some(host.as_str())
+                    && !web_allowed_hosts().contains(&host)
+                {
+                    return Err(anyhow!(
+                        "Plan step not allowed: crawl host {host} is neither the repository host nor in INGEST_WEB_ALLOWED_HOSTS"
+                    ));
+                }
+            }
+            continue;
+        }
+        if args[0] != "cli" {
+            continue;
+        }
+        let target = args
+            .get(1)
+            .ok_or_else(|| anyhow!("Plan step has no input path: {command}"))?;
+        let target = Path::new(target);
+        let inside = match &plan.source {
+            PlanSource::RepoUrl(_) => target.starts_with("UNIQUE_REPO_DIR"),
+            PlanSource::LocalPath(root) => target.starts_with(root),
+        };
+        if !inside || target.components().any(|c| c == Component::ParentDir) {
+            return Err(anyhow!(
+                "Plan step reads outside the repository: {}",
+                target.display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Analyze a repository and propose an ingest plan without executing it
+pub struct AnalyzeRepositoryTool {
+    runner: Arc<dyn PromptRunner>,
+    timeout: Duration,
+}
+
+impl AnalyzeRepositoryTool {
+    /// Create an analysis tool using the runner selected by
+    /// `DISCOVERY_LLM_PROVIDER` or `LLM_PROVIDER`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configured runner cannot be built.
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_runner(prompt_runner_from_env(
+            LlmUseCase::Discovery,
+        )?))
+    }
+
+    /// Create an analysis tool that sends its prompts to `runner`
+    #[must_use]
+    pub fn with_runner(runner: Arc<dyn PromptRunner>) -> Self {
+        Self {
+            runner,
+            timeout: analyze_timeout(),
+        }
+    }
+
+    /// Override the limit on a single analysis
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[async_trait]
+impl Tool for AnalyzeRepositoryTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "analyze_repository",
+            "description": "Analyze a repository with Claude Code and propose an ingestion plan as JSON: detected documentation formats, directories worth ingesting, suggested doc_type and source_name, and estimated file counts. Nothing is executed; pass the returned plan_id to execute_ingest_plan to run it.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "repo_url": {
+                        "type": "string",
+                        "description": "HTTP(S) URL of the repository to analyze (provide this or local_path)"
+                    },
+                    "local_path": {
+                        "type": "string",
+                        "description": "Path of a checkout on the server, under INGEST_LOCAL_ROOT (provide this or repo_url)"
+                    },
+                    "hint": {
+                        "type": "string",
+                        "description": "Guidance for the analyzer, e.g. 'API reference lives in openapi/' (optional)"
+                    }
+                },
+                "required": []
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        self.execute_with_context(arguments, &ExecutionContext::default())
+            .await
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ExecutionContext,
+    ) -> Result<String> {
+        let arg = |key: &str| {
+            arguments
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+        };
+        let source = match (arg("repo_url"), arg("local_path")) {
+            (Some(url), None) => {
+                if !url.starts_with("https://") && !url.starts_with("http://") {
+                    return Err(invalid_source(format!(
+                        "repo_url must be an HTTP(S) URL, got '{url}'"
+                    )));
+                }
+                PlanSource::RepoUrl(url.to_string())
+            }
+            (None, Some(path)) => PlanSource::LocalPath(resolve_local_path(path)?),
+            _ => {
+                return Err(invalid_source(
+                    "Provide exactly one of repo_url or local_path",
+                ))
+            }
+        };
+        let hint = arg("hint");
+
+        let analyzer = IntelligentRepositoryAnalyzer::with_runner(Arc::clone(&self.runner));
+        let repository = match &source {
+            PlanSource::RepoUrl(url) => RepositorySource::Remote(url.clone()),
+            PlanSource::LocalPath(path) => RepositorySource::Local(path.clone()),
+        };
+        let analysis = tokio::time::timeout(
+            self.timeout,
+            context.run(analyzer.analyze(&repository, hint)),
+        )
+        .await
+        .map_err(|_| {
+            anyhow::Error::from(AnalysisError::Timeout {
+                secs: self.timeout.as_secs(),
+            })
+        })
+        .and_then(|result| result)
+        .map_err(analysis_failure)?;
+
+        let strategy = &analysis.strategy;
+        let assessment = &analysis.assessment;
+        let directories = if strategy.include_paths.is_empty() {
+            &assessment.key_directories
+        } else {
+            &strategy.include_paths
+        };
+        let summary = json!({
+            "doc_formats": strategy.extensions,
+            "primary_format": assessment.primary_format,
+            "directories": directories,
+            "doc_type": strategy.doc_type,
+            "source_name": strategy.source_name,
+            "estimated_file_count": assessment.estimated_file_count,
+            "file_counts": assessment.file_counts,
+        });
+
+        let mut response = json!({
+            "source": source,
+            "summary": summary,
+            "analysis": analysis,
+            "expires_in_secs": PLAN_TTL.as_secs(),
+        });
+        response["plan_id"] = json!(ingest_plans().insert(IngestPlan { source, analysis }));
+        Ok(serde_json::to_string_pretty(&response)?)
+    }
+}
+
+/// Whether `value` can be placed in a plan step as a single argument
+fn is_plain_token(value: &str) -> bool {
+    !value.is_empty()
+        && !value.starts_with('-')
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Run an analyzed plan as a background ingest job
+pub struct ExecuteIngestPlanTool {
+    jobs: IngestJobManager,
+}
+
+impl ExecuteIngestPlanTool {
+    /// Create a plan execution tool
+    #[must_use]
+    pub const fn new(db_pool: DatabasePool) -> Self {
+        Self {
+            jobs: IngestJobManager::new(db_pool),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ExecuteIngestPlanTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "execute_ingest_plan",
+            "description": "Execute an ingestion plan from analyze_repository in the background. Creates an ingest job and returns its ID; poll check_ingest_status with the job_id for step progress and the final result.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "plan_id": {
+                        "type": "string",
+                        "description": "Plan ID returned by analyze_repository (provide this or plan)"
+                    },
+                    "plan": {
+                        "type": "object",
+                        "description": "Full analyze_repository response, possibly edited (provide this or plan_id). Its steps may clone, parse and crawl into UNIQUE_DOCS_OUT; the server adds the database step that loads them"
+                    },
+                    "doc_type": {
+                        "type": "string",
+                        "description": "Document type to store the documents under (defaults to the plan's suggestion)"
+                    }
+                },
+                "required": []
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        let (mut plan, from_client) = match (
+            arguments.get("plan_id").and_then(Value::as_str),
+            arguments.get("plan").filter(|p| p.is_object()),
+        ) {
+            (Some(plan_id), None) => {
+                let plan_id = Uuid::parse_str(plan_id)
+                    .map_err(|e| anyhow!("Invalid plan_id '{plan_id}': {e}"))?;
+                let plan = ingest_plans()
+                    .get(plan_id)
+                    .ok_or_else(|| anyhow!("Unknown or expired plan_id {plan_id}"))?;
+                (plan, false)
+            }
+            (None, Some(plan)) => {
+                let mut plan = serde_json::from_value::<IngestPlan>(plan.clone())
+                    .map_err(|e| anyhow!("Invalid plan: {e}"))?;
+                // A client-sent checkout must pass the same check as in analyze_repository
+                if let PlanSource::LocalPath(path) = &plan.source {
+                    plan.source =
+                        PlanSource::LocalPath(resolve_local_path(&path.to_string_lossy())?);
+                }
+                (plan, true)
+            }
+            _ => return Err(anyhow!("Provide exactly one of plan_id or plan")),
+        };
+
+        let doc_type = arguments
+            .get("doc_type")
+            .and_then(Value::as_str)
+            .unwrap_or(&plan.analysis.strategy.doc_type)
+            .trim()
+            .to_string();
+        if doc_type.is_empty() || doc_type == "unknown" {
+            return Err(anyhow!(
+                "No doc_type suggested by the plan; pass doc_type explicitly"
+            ));
+        }
+        if !is_plain_token(&doc_type) {
+            return Err(anyhow!("Invalid doc_type '{doc_type}'"));
+        }
+        validate_plan(&plan, &doc_type, from_client)?;
+        if from_client {
+            let source_name = Some(plan.analysis.strategy.source_name.trim())
+                .filter(|name| is_plain_token(name))
+                .unwrap_or(&doc_type);
+            let load = format!(
+                "loader database --input-dir UNIQUE_DOCS_OUT --doc-type {doc_type} --source-name {source_name} --yes"
+            );
+            plan.analysis.cli_commands.push(load);
+        }
+
+        let steps = plan.analysis.cli_commands.len();
+        let location = plan.source.location();
+        let job_id = self
+            .jobs
+            .enqueue_plan(location.clone(), doc_type.clone(), plan.analysis)
+            .await?;
+
+        Ok(serde_json::to_string_pretty(&json!({
+            "job_id": job_id,
+            "status": "queued",
+            "source": location,
+            "doc_type": doc_type,
+            "steps": steps,
+            "status_url": format!("/ingest/jobs/{job_id}"),
+        }))?)
+    }
+}
+
+/// Last [`OUTPUT_EXCERPT_CHARS`] characters of a job's output
+fn output_excerpt(output: &str) -> String {
+    let output = output.trim_end();
+    let skip = output.chars().count().saturating_sub(OUTPUT_EXCERPT_CHARS);
+    if skip == 0 {
+        return output.to_string();
+    }
+    let tail: String = output.chars().skip(skip).collect();
+    format!("… ({skip} earlier characters omitted)\n{tail}")
+}
+
+/// Report ingest jobs started by `execute_ingest_plan` and `/ingest/intelligent`
+pub struct CheckIngestStatusTool {
+    db_pool: DatabasePool,
+}
+
+impl CheckIngestStatusTool {
+    /// Create a new ingest status tool
+    #[must_use]
+    pub const fn new(db_pool: DatabasePool) -> Self {
+        Self { db_pool }
+    }
+
+    fn job_report(job: &IngestJob, now: chrono::DateTime<chrono::Utc>) -> String {
+        let mut output = String::new();
+        let _ = writeln!(&mut output, "Ingest Job Status: {}", job.id);
+        output.push('\n');
+        let _ = writeln!(&mut output, "  Source: {}", job.url);
+        let _ = writeln!(&mut output, "  Doc Type: {}", job.doc_type);
+        let _ = writeln!(&mut output, "  Status: {:?}", job.status);
+        let _ = writeln!(
+            &mut output,
+            "  Started: {}",
+            job.started_at.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+        let _ = writeln!(
+            &mut output,
+            "  Updated: {}",
+            job.updated_at.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+        if let Some(finished) = job.finished_at {
+            let _ = writeln!(
+                &mut output,
+                "  Finished: {}",
+                finished.format("%Y-%m-%d %H:%M:%S UTC")
+            );
+        }
+        if job.is_stuck(now) {
+            let _ = writeln!(
+                &mut output,
+                "  ⚠️ Stuck: no update for {} minutes",
+                (now - job.updated_at).num_minutes()
+            );
+        }
+        if let Some(error) = &job.error {
+            let _ = writeln!(&mut output, "  Error: {}", error);
+        }
+        if let Some(job_output) = job.output.as_deref().filter(|o| !o.trim().is_empty()) {
+            let _ = writeln!(&mut output, "  Output:");
+            for line in output_excerpt(job_output).lines() {
+                let _ = writeln!(&mut output, "    {line}");
+            }
+        }
+        output
+    }
+
+    fn job_line(job: &IngestJob, now: chrono::DateTime<chrono::Utc>) -> String {
+        let mut line = format!(
+            "  • {} [{}] - {} ({:?}",
+            job.url, job.id, job.doc_type, job.status
+        );
+        if matches!(job.status, JobStatus::Running | JobStatus::Queued) {
+            if let Some(step) = job
+                .output
+                .as_deref()
+                .filter(|o| o.starts_with("Step "))
+                .and_then(|o| o.split(':').next())
+            {
+                let _ = write!(&mut line, " - {step}");
+            }
+            if job.is_stuck(now) {
+                line.push_str(" - stuck");
+            }
+        }
+        let _ = write!(
+            &mut line,
+            ") - {}",
+            job.finished_at
+                .unwrap_or(job.started_at)
+                .format("%m-%d %H:%M")
+        );
+        line
+    }
+}
+
+#[async_trait]
+impl Tool for CheckIngestStatusTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "check_ingest_status",
+            "description": "Check repository and URL ingestion jobs. With job_id, show the job's source, doc_type, status, timestamps, error and the tail of its output (the step in progress while running, a document summary once completed). Without job_id, list active jobs and a page of finished ones, and flag jobs with no update for over an hour.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "job_id": {
+                        "type": "string",
+                        "description": "Ingest job ID returned by execute_ingest_plan (optional)"
+                    },
+                    "page": {
+                        "type": "integer",
+                        "description": "Page of finished jobs, starting at 1 (default: 1)",
+                        "minimum": 1
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Finished jobs per page (default: 10, max: 50)",
+                        "minimum": 1,
+                        "maximum": MAX_JOB_PAGE_SIZE
+                    }
+                },
+                "required": [],
+                "additionalProperties": false
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        let pool = self.db_pool.pool();
+        let now = chrono::Utc::now();
+
+        if let Some(job_id) = arguments.get("job_id").and_then(Value::as_str) {
+            let job_id =
+                Uuid::parse_str(job_id.trim()).map_err(|_| anyhow!("Invalid job ID format"))?;
+            return Ok(
+                match IngestJobQueries::find_job_by_id(pool, job_id).await? {
+                    Some(job) => Self::job_report(&job, now),
+                    None => format!("Ingest job {job_id} not found.\n"),
+                },
+            );
+        }
+
+        let page = arguments
+            .get("page")
+            .and_then(Value::as_i64)
+            .unwrap_or(1)
+            .max(1);
+        let limit = arguments
+            .get("limit")
+            .and_then(Value::as_i64)
+            .unwrap_or(10)
+            .clamp(1, MAX_JOB_PAGE_SIZE);
+
+        let active = IngestJobQueries::find_active_jobs(pool).await?;
+        // Fetch one extra row to tell whether another page follows
+        let mut finished =
+            IngestJobQueries::list_finished_jobs(pool, limit + 1, (page - 1) * limit).await?;
+        let has_more = finished.len() > usize::try_from(limit).unwrap_or(usize::MAX);
+        finished.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+        let stuck = IngestJobQueries::count_stuck_jobs(pool).await?;
+
+        let mut output = String::from("📥 Ingest Jobs\n\n");
+        if active.is_empty() {
+            output.push_str("🔄 **Active Jobs:** none\n\n");
+        } else {
+            output.push_str("🔄 **Active Jobs:**\n");
+            for job in &active {
+                let _ = writeln!(&mut output, "{}", Self::job_line(job, now));
+            }
+            output.push('\n');
+        }
+
+        let _ = writeln!(&mut output, "📋 **Recent Jobs (page {page}):**");
+        if finished.is_empty() {
+            output.push_str("  none\n");
+        }
+        for job in &finished {
+            let _ = writeln!(&mut output, "{}", Self::job_line(job, now));
+        }
+        if has_more {
+            let _ = writeln!(&mut output, "  More on page {}", page + 1);
+        }
+        output.push('\n');
+
+        if stuck > 0 {
+            let _ = writeln!(
+                &mut output,
+                "⚠️  Stuck ingest jobs (no update > {}m): {}",
+                STUCK_JOB_MINUTES, stuck
+            );
+        }
+        Ok(output)
+    }
+}
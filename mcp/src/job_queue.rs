@@ -1,58 +1,539 @@
-//! Background job queue for crate ingestion
+//! Background job queue for crate and package ingestion and removal
+//!
+//! [`JobProcessor`] holds the lifecycle code shared by every job table behind
+//! a [`JobStore`]: status transitions, heartbeats while a job runs, stale-job
+//! recovery and retention. [`CrateJobProcessor`] and [`IngestJobProcessor`]
+//! are its two instances; the work a job does is supplied by the caller.
+//!
+//! Crate jobs are rows in `crate_jobs`. Creating one issues `NOTIFY
+//! crate_jobs_changed`; the dispatcher started by [`start_dispatcher`] listens
+//! on that channel, claims queued jobs with `FOR UPDATE SKIP LOCKED` and runs
+//! them, so a job runs exactly once no matter which process enqueued it.
+//! npm and Python package jobs share the table; their `operation`
+//! (`add_npm_package`, `remove_python_package`, ...) tells them apart from
+//! crate jobs.
+//!
+//! Failed jobs are classified with [`classify_job_error`]: retryable failures
+//! are rescheduled with exponential backoff until `max_attempts`, everything
+//! else is dead-lettered for an operator to inspect and retry.
+//!
+//! Finished, failed and dead-lettered crate jobs are reported to webhook
+//! subscribers through [`crate::webhooks::notify_job`].
 
+use crate::package_tools::PackageEcosystem;
+use crate::webhooks;
 use anyhow::Result;
 use db::{
-    models::{CrateJob, JobStatus},
-    queries::CrateJobQueries,
-    DatabasePool,
+    models::{CrateJob, JobStatus, STUCK_JOB_MINUTES},
+    queries::{CrateJobQueries, IdempotencyConflict, CRATE_JOBS_CHANNEL},
+    CrateStorage, DatabasePool, IngestJobStore, JobStore, NewCrateJob,
 };
-use tracing::info;
+use embed::client::EmbeddingClient;
+use embed::JobCosts;
+use npm_packages::NpmLoader;
+use python_packages::PyPiLoader;
+use rust_crates::{CrateMetadata, LocalDocsSource};
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgListener;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-/// Simplified job processor for crate ingestion
-/// For now, this just manages job creation and status tracking
-/// The actual processing happens synchronously in the tools
+/// Fallback poll interval, covering notifications missed while reconnecting
+const DISPATCH_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Interval between heartbeats of a running job
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Upper bound on the delay between attempts
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// Whether a failed job is worth running again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobErrorKind {
+    /// Network failures, 5xx/429 responses and lost database connections
+    Retryable,
+    /// Unknown crates, invalid versions and anything unrecognised
+    Permanent,
+}
+
+impl JobErrorKind {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Retryable => "retryable",
+            Self::Permanent => "permanent",
+        }
+    }
+}
+
+/// Classify an ingestion error as retryable or permanent
+///
+/// HTTP statuses reported as `HTTP status: NNN` decide first (408, 429 and
+/// 5xx retry, other 4xx are permanent); otherwise transient network and
+/// database connection failures retry and everything else is permanent.
+#[must_use]
+pub fn classify_job_error(error: &anyhow::Error) -> JobErrorKind {
+    if error.chain().any(|cause| {
+        cause.downcast_ref::<sqlx::Error>().is_some_and(|e| {
+            matches!(
+                e,
+                sqlx::Error::Io(_)
+                    | sqlx::Error::PoolTimedOut
+                    | sqlx::Error::PoolClosed
+                    | sqlx::Error::WorkerCrashed
+            )
+        })
+    }) {
+        return JobErrorKind::Retryable;
+    }
+
+    let message = format!("{error:#}").to_lowercase();
+    if let Some(status) = http_status(&message) {
+        return if status == 408 || status == 429 || status >= 500 {
+            JobErrorKind::Retryable
+        } else {
+            JobErrorKind::Permanent
+        };
+    }
+
+    const PERMANENT: &[&str] = &["not found", "invalid version", "invalid crates.io response"];
+    const RETRYABLE: &[&str] = &[
+        "http failed",
+        "timed out",
+        "timeout",
+        "connection refused",
+        "connection reset",
+        "connection closed",
+        "broken pipe",
+        "network",
+        "dns",
+        "temporarily unavailable",
+        "pool timed out",
+    ];
+    if PERMANENT.iter().any(|marker| message.contains(marker)) {
+        JobErrorKind::Permanent
+    } else if RETRYABLE.iter().any(|marker| message.contains(marker)) {
+        JobErrorKind::Retryable
+    } else {
+        JobErrorKind::Permanent
+    }
+}
+
+/// Status code from an `HTTP status: NNN ...` message
+fn http_status(message: &str) -> Option<u16> {
+    let (_, rest) = message.split_once("http status: ")?;
+    rest.get(..3)?.parse().ok()
+}
+
+/// Delay before attempt `attempt + 1` (`CRATE_JOB_RETRY_BASE_SECS`, default 60, doubling, capped at an hour)
+#[must_use]
+pub fn retry_delay(attempt: i32) -> Duration {
+    let base = std::env::var("CRATE_JOB_RETRY_BASE_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(60);
+    let exponent = u32::try_from(attempt.saturating_sub(1).clamp(0, 16)).unwrap_or(0);
+    Duration::from_secs(base.saturating_mul(1 << exponent)).min(MAX_RETRY_DELAY)
+}
+
+/// Request options stored with an `add_crate` job
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateJobOptions {
+    /// Resolved version to ingest
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Version or semver range the caller asked for
+    #[serde(default)]
+    pub version_req: Option<String>,
+    #[serde(default)]
+    pub features: Option<Vec<String>>,
+    #[serde(default)]
+    pub include_dev_deps: bool,
+    #[serde(default)]
+    pub force_update: bool,
+    /// Fetch every page in full instead of sending conditional requests
+    #[serde(default)]
+    pub no_cache: bool,
+    #[serde(default = "default_atomic_rollback")]
+    pub atomic_rollback: bool,
+    /// Local rustdoc output to read instead of crawling docs.rs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local: Option<LocalDocsSource>,
+    /// Name the caller asked for when it differs from the canonical crate name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// crates.io metadata fetched when the job was queued
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<CrateMetadata>,
+    /// crates.io could not confirm the crate exists when the job was queued
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub unverified: bool,
+    /// Parse the pages kept in the raw page archive instead of crawling docs.rs
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub from_archive: bool,
+}
+
+const fn default_atomic_rollback() -> bool {
+    true
+}
+
+impl Default for CrateJobOptions {
+    fn default() -> Self {
+        Self {
+            version: None,
+            version_req: None,
+            features: None,
+            include_dev_deps: false,
+            force_update: false,
+            no_cache: false,
+            atomic_rollback: true,
+            local: None,
+            alias: None,
+            metadata: None,
+            unverified: false,
+            from_archive: false,
+        }
+    }
+}
+
+impl CrateJobOptions {
+    /// Options recorded on a job, or the defaults for jobs enqueued without any
+    #[must_use]
+    pub fn from_job(job: &CrateJob) -> Self {
+        job.options
+            .clone()
+            .and_then(|options| serde_json::from_value(options).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Request options stored with a `remove_crate` job
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemoveJobOptions {
+    /// Verify no documents remain once the deletion finishes
+    #[serde(default = "default_verify_cleanup")]
+    pub verify_cleanup: bool,
+    /// Remove only this version of the crate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+const fn default_verify_cleanup() -> bool {
+    true
+}
+
+impl Default for RemoveJobOptions {
+    fn default() -> Self {
+        Self {
+            verify_cleanup: true,
+            version: None,
+        }
+    }
+}
+
+impl RemoveJobOptions {
+    /// Options recorded on a job, or the defaults for jobs enqueued without any
+    #[must_use]
+    pub fn from_job(job: &CrateJob) -> Self {
+        job.options
+            .clone()
+            .and_then(|options| serde_json::from_value(options).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Request options stored with an `add_npm_package` or `add_python_package` job
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageJobOptions {
+    /// Resolved version to ingest
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Version (or npm dist-tag) the caller asked for
+    #[serde(default)]
+    pub version_req: Option<String>,
+    #[serde(default)]
+    pub force_update: bool,
+    /// The package registry could not confirm the package exists when the job was queued
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub unverified: bool,
+}
+
+impl PackageJobOptions {
+    /// Options recorded on a job, or the defaults for jobs enqueued without any
+    #[must_use]
+    pub fn from_job(job: &CrateJob) -> Self {
+        job.options
+            .clone()
+            .and_then(|options| serde_json::from_value(options).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// A job returned by an enqueue call
+#[derive(Debug, Clone)]
+pub struct EnqueuedJob {
+    pub job: CrateJob,
+    /// `false` when an idempotency key matched a job created earlier
+    pub created: bool,
+}
+
+/// Job processor: creation, status tracking, heartbeats and recovery for
+/// the jobs in one [`JobStore`]
 #[derive(Clone)]
-pub struct CrateJobProcessor {
-    db_pool: DatabasePool,
+pub struct JobProcessor<S> {
+    storage: S,
 }
 
-impl CrateJobProcessor {
+/// Processor for `crate_jobs`, in PostgreSQL or in memory
+pub type CrateJobProcessor = JobProcessor<CrateStorage>;
+
+/// Processor for `ingest_jobs`
+pub type IngestJobProcessor = JobProcessor<IngestJobStore>;
+
+impl<S: JobStore + Clone + 'static> JobProcessor<S> {
     /// Create a new job processor
     #[must_use]
-    pub const fn new(db_pool: DatabasePool) -> Self {
-        Self { db_pool }
+    pub fn new(storage: impl Into<S>) -> Self {
+        Self {
+            storage: storage.into(),
+        }
+    }
+
+    /// Get a reference to the storage jobs are kept in
+    #[must_use]
+    pub const fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Get job status by ID
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub async fn get_job_status(&self, job_id: Uuid) -> Result<Option<S::Job>> {
+        self.storage.find(job_id).await
+    }
+
+    /// Move a job to `status`, keeping its other fields
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the job does not exist or cannot be updated.
+    pub async fn transition(
+        &self,
+        job_id: Uuid,
+        status: JobStatus,
+        error: Option<&str>,
+    ) -> Result<S::Job> {
+        self.storage.transition(job_id, status, error).await
     }
 
-    /// Get a reference to the database pool
+    /// Keep a job's `updated_at` fresh every 30 seconds until the heartbeat
+    /// is stopped or the job leaves `running`
     #[must_use]
-    pub const fn db_pool(&self) -> &DatabasePool {
-        &self.db_pool
+    pub fn start_heartbeat(&self, job_id: Uuid) -> JobHeartbeat {
+        let (stop, mut stopped) = oneshot::channel::<()>();
+        let storage = self.storage.clone();
+        let handle = tokio::spawn(async move {
+            // The first beat waits an interval, by which time the job is running
+            let mut interval = tokio::time::interval_at(
+                tokio::time::Instant::now() + HEARTBEAT_INTERVAL,
+                HEARTBEAT_INTERVAL,
+            );
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => match storage.heartbeat(job_id).await {
+                        Ok(true) => {}
+                        Ok(false) => break,
+                        Err(e) => debug!("Heartbeat for {} job {} failed: {}", storage.kind(), job_id, e),
+                    },
+                    _ = &mut stopped => break,
+                }
+            }
+        });
+        JobHeartbeat { stop, handle }
+    }
+
+    /// Run `work` for a job with a heartbeat going
+    pub async fn run_with_heartbeat<F: Future>(&self, job_id: Uuid, work: F) -> F::Output {
+        let heartbeat = self.start_heartbeat(job_id);
+        let output = work.await;
+        heartbeat.stop().await;
+        output
+    }
+
+    /// Fail running jobs with no update for `older_than`, noting `reason` in their error
+    ///
+    /// Returns how many jobs were failed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the jobs cannot be updated.
+    pub async fn recover_stale_jobs(&self, older_than: Duration, reason: &str) -> Result<u64> {
+        let cutoff = chrono::Utc::now()
+            - chrono::Duration::from_std(older_than).unwrap_or_else(|_| chrono::Duration::hours(1));
+        let recovered = self.storage.recover_stale(cutoff, reason).await?;
+        if recovered > 0 {
+            warn!(
+                "Failed {} stale {} jobs: {}",
+                recovered,
+                self.storage.kind(),
+                reason
+            );
+        }
+        Ok(recovered)
+    }
+
+    /// Clean up finished jobs past the 30-day retention window
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cleanup operation fails.
+    pub async fn cleanup_old_jobs(&self) -> Result<i32> {
+        self.storage.cleanup().await
+    }
+}
+
+/// Heartbeat task started by [`JobProcessor::start_heartbeat`]
+pub struct JobHeartbeat {
+    stop: oneshot::Sender<()>,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl JobHeartbeat {
+    /// Stop sending heartbeats and wait for the task to end
+    pub async fn stop(self) {
+        let _ = self.stop.send(());
+        let _ = self.handle.await;
     }
+}
 
+impl CrateJobProcessor {
     /// Enqueue a new crate ingestion job
     ///
+    /// With an `idempotency_key`, a job created with the same key in the last
+    /// 24 hours is returned instead of creating another.
+    ///
     /// # Errors
     ///
     /// Returns an error if the job cannot be created in the database.
-    pub async fn enqueue_add_crate_job(&self, crate_name: &str) -> Result<Uuid> {
-        let job = CrateJobQueries::create_job(self.db_pool.pool(), crate_name, "add_crate").await?;
+    pub async fn enqueue_add_crate_job(
+        &self,
+        crate_name: &str,
+        options: &CrateJobOptions,
+        idempotency_key: Option<&str>,
+    ) -> Result<EnqueuedJob> {
+        let options = serde_json::to_value(options)?;
+        self.enqueue(crate_name, "add_crate", &options, idempotency_key)
+            .await
+    }
 
-        info!("Enqueued add_crate job {} for {}", job.id, crate_name);
-        Ok(job.id)
+    /// Enqueue a background removal of a crate's documents
+    ///
+    /// `idempotency_key` behaves as for [`Self::enqueue_add_crate_job`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the job cannot be created in the database.
+    pub async fn enqueue_remove_crate_job(
+        &self,
+        crate_name: &str,
+        options: &RemoveJobOptions,
+        idempotency_key: Option<&str>,
+    ) -> Result<EnqueuedJob> {
+        let options = serde_json::to_value(options)?;
+        self.enqueue(crate_name, "remove_crate", &options, idempotency_key)
+            .await
     }
 
-    /// Get job status by ID
+    /// Enqueue a background ingestion of a package of `ecosystem`
+    ///
+    /// `idempotency_key` behaves as for [`Self::enqueue_add_crate_job`].
     ///
     /// # Errors
     ///
-    /// Returns an error if the database query fails.
-    pub async fn get_job_status(&self, job_id: Uuid) -> Result<Option<CrateJob>> {
-        CrateJobQueries::find_job_by_id(self.db_pool.pool(), job_id).await
+    /// Returns an error if the job cannot be created in the database.
+    pub async fn enqueue_add_package_job(
+        &self,
+        ecosystem: &PackageEcosystem,
+        package_name: &str,
+        options: &PackageJobOptions,
+        idempotency_key: Option<&str>,
+    ) -> Result<EnqueuedJob> {
+        let options = serde_json::to_value(options)?;
+        self.enqueue(
+            package_name,
+            ecosystem.add_operation,
+            &options,
+            idempotency_key,
+        )
+        .await
+    }
+
+    /// Enqueue a background removal of a package's documents
+    ///
+    /// `idempotency_key` behaves as for [`Self::enqueue_add_crate_job`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the job cannot be created in the database.
+    pub async fn enqueue_remove_package_job(
+        &self,
+        ecosystem: &PackageEcosystem,
+        package_name: &str,
+        options: &RemoveJobOptions,
+        idempotency_key: Option<&str>,
+    ) -> Result<EnqueuedJob> {
+        let options = serde_json::to_value(options)?;
+        self.enqueue(
+            package_name,
+            ecosystem.remove_operation,
+            &options,
+            idempotency_key,
+        )
+        .await
+    }
+
+    async fn enqueue(
+        &self,
+        crate_name: &str,
+        operation: &str,
+        options: &serde_json::Value,
+        idempotency_key: Option<&str>,
+    ) -> Result<EnqueuedJob> {
+        let (job, created) = self
+            .storage
+            .create(&NewCrateJob {
+                crate_name: crate_name.to_string(),
+                operation: operation.to_string(),
+                options: options.clone(),
+                idempotency_key: idempotency_key.map(String::from),
+            })
+            .await
+            .map_err(|e| match e.downcast::<IdempotencyConflict>() {
+                Ok(conflict) => crate::crate_tools::idempotency_conflict(&conflict).into(),
+                Err(e) => e,
+            })?;
+
+        if created {
+            info!("Enqueued {} job {} for {}", operation, job.id, crate_name);
+        } else {
+            info!(
+                "Idempotency key matched existing {} job {} for {}",
+                operation, job.id, crate_name
+            );
+        }
+        Ok(EnqueuedJob { job, created })
     }
 
     /// Update job status
     ///
+    /// A job reaching `completed` or `failed` is reported to webhook
+    /// subscribers in the background.
+    ///
     /// # Errors
     ///
     /// Returns an error if the database update fails.
@@ -63,16 +544,415 @@ impl CrateJobProcessor {
         progress: Option<i32>,
         error: Option<&str>,
     ) -> Result<CrateJob> {
-        CrateJobQueries::update_job_status(self.db_pool.pool(), job_id, status, progress, error)
+        let job = self
+            .storage
+            .store()
+            .update_job_status(job_id, status, progress, error)
+            .await?;
+        webhooks::notify_job(&self.storage, &job);
+        Ok(job)
+    }
+
+    /// Append an informational event to a job's audit trail
+    ///
+    /// Failures are logged rather than returned so a lost event never fails
+    /// the job itself.
+    pub async fn record_job_event(
+        &self,
+        job_id: Uuid,
+        event_type: &str,
+        detail: serde_json::Value,
+    ) {
+        if let Err(e) = self
+            .storage
+            .store()
+            .record_job_event(job_id, event_type, &detail)
             .await
+        {
+            debug!(
+                "Failed to record {} event for job {}: {}",
+                event_type, job_id, e
+            );
+        }
     }
 
-    /// Clean up old completed jobs
+    /// Add model API usage to the `job_costs` a job has recorded so far
+    ///
+    /// Every attempt of a job adds its own usage, so retried jobs report
+    /// what all their attempts spent.
     ///
     /// # Errors
     ///
-    /// Returns an error if the cleanup operation fails.
-    pub async fn cleanup_old_jobs(&self) -> Result<i32> {
-        CrateJobQueries::cleanup_old_jobs(self.db_pool.pool()).await
+    /// Returns an error if the job cannot be loaded or updated.
+    pub async fn add_job_costs(&self, job_id: Uuid, costs: &JobCosts) -> Result<JobCosts> {
+        let store = self.storage.store();
+        let job = store
+            .find_job_by_id(job_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Job {job_id} not found"))?;
+        let mut total: JobCosts = job
+            .details
+            .as_ref()
+            .and_then(|details| details.get("job_costs"))
+            .and_then(|costs| serde_json::from_value(costs.clone()).ok())
+            .unwrap_or_default();
+        total.merge(costs);
+        store
+            .merge_job_details(job_id, &serde_json::json!({ "job_costs": total }))
+            .await?;
+        Ok(total)
+    }
+
+    /// Reschedule a failed job with backoff, or dead-letter it
+    ///
+    /// Retryable failures run again after [`retry_delay`] until the job has
+    /// used `max_attempts`; permanent failures are dead-lettered immediately.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the job cannot be loaded or updated.
+    pub async fn record_failure(&self, job_id: Uuid, error: &anyhow::Error) -> Result<CrateJob> {
+        let job = self
+            .storage
+            .store()
+            .find_job_by_id(job_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Job {job_id} not found"))?;
+        let kind = classify_job_error(error);
+        let message = error.to_string();
+        let details = serde_json::json!({ "last_error_kind": kind.as_str() });
+
+        if kind == JobErrorKind::Retryable && job.attempts < job.max_attempts {
+            let delay = retry_delay(job.attempts);
+            let next_run_at = chrono::Utc::now()
+                + chrono::Duration::from_std(delay).unwrap_or_else(|_| chrono::Duration::hours(1));
+            warn!(
+                "Crate job {} failed (attempt {}/{}), retrying in {}s: {}",
+                job_id,
+                job.attempts,
+                job.max_attempts,
+                delay.as_secs(),
+                message
+            );
+            self.record_job_event(
+                job_id,
+                "retry_scheduled",
+                serde_json::json!({
+                    "error": message,
+                    "error_kind": kind.as_str(),
+                    "attempt": job.attempts,
+                    "next_run_at": next_run_at,
+                }),
+            )
+            .await;
+            return self
+                .storage
+                .store()
+                .schedule_retry(job_id, next_run_at, &message, &details)
+                .await;
+        }
+
+        warn!(
+            "Crate job {} dead-lettered after {} attempts ({} error): {}",
+            job_id,
+            job.attempts,
+            kind.as_str(),
+            message
+        );
+        self.record_job_event(
+            job_id,
+            "dead_lettered",
+            serde_json::json!({
+                "error": message,
+                "error_kind": kind.as_str(),
+                "attempt": job.attempts,
+            }),
+        )
+        .await;
+        let job = self
+            .storage
+            .store()
+            .dead_letter_job(job_id, &message, &details)
+            .await?;
+        webhooks::notify_job(&self.storage, &job);
+        Ok(job)
+    }
+}
+
+/// Error note on ingest jobs failed by [`recover_crashed_ingest_jobs`]
+pub const CRASHED_INGEST_JOB_NOTE: &str =
+    "Presumed crashed: no heartbeat for over an hour while running";
+
+/// Fail running ingest jobs without an update for [`STUCK_JOB_MINUTES`]
+///
+/// Running ingest jobs heartbeat every 30 seconds, so one this quiet lost
+/// the process running it. Ingest jobs keep no retry budget, so they are
+/// failed rather than requeued. Returns how many were failed.
+///
+/// # Errors
+///
+/// Returns an error if the jobs cannot be updated.
+pub async fn recover_crashed_ingest_jobs(db_pool: &DatabasePool) -> Result<u64> {
+    let older_than = Duration::from_secs(u64::try_from(STUCK_JOB_MINUTES).unwrap_or(60) * 60);
+    IngestJobProcessor::new(db_pool.clone())
+        .recover_stale_jobs(older_than, CRASHED_INGEST_JOB_NOTE)
+        .await
+}
+
+/// Delete finished crate jobs and crate job events past the 30-day
+/// retention window
+///
+/// Run by the `crate_job_retention` maintenance task.
+///
+/// # Errors
+///
+/// Returns an error if the rows cannot be deleted.
+pub async fn cleanup_crate_jobs(db_pool: &DatabasePool) -> Result<String> {
+    let removed = CrateJobProcessor::new(db_pool.clone())
+        .cleanup_old_jobs()
+        .await?;
+    let pruned = CrateJobQueries::prune_job_events(db_pool.pool()).await?;
+    Ok(format!("removed {removed} jobs and {pruned} job events"))
+}
+
+/// Delete finished ingest jobs past the 30-day retention window
+///
+/// Run by the `ingest_job_retention` maintenance task.
+///
+/// # Errors
+///
+/// Returns an error if the jobs cannot be deleted.
+pub async fn cleanup_ingest_jobs(db_pool: &DatabasePool) -> Result<String> {
+    let removed = IngestJobProcessor::new(db_pool.clone())
+        .cleanup_old_jobs()
+        .await?;
+    Ok(format!("removed {removed} jobs"))
+}
+
+/// Claim as many queued crate jobs as there are free concurrency permits and run them
+///
+/// Returns the number of jobs started.
+///
+/// # Errors
+///
+/// Returns an error if the queued jobs cannot be claimed.
+pub async fn dispatch_queued_jobs(
+    db_pool: &DatabasePool,
+    embedding_client: &Arc<dyn EmbeddingClient + Send + Sync>,
+) -> Result<usize> {
+    // Reserve permits before claiming so claimed jobs never wait in `running`
+    let semaphore = crate::crate_tools::get_crate_job_semaphore();
+    let mut permits = Vec::new();
+    while let Ok(permit) = semaphore.clone().try_acquire_owned() {
+        permits.push(permit);
+    }
+    if permits.is_empty() {
+        return Ok(0);
+    }
+
+    let limit = i64::try_from(permits.len()).unwrap_or(i64::MAX);
+    let jobs = CrateJobQueries::claim_queued_jobs(db_pool.pool(), limit).await?;
+    let started = jobs.len();
+    for (job, permit) in jobs.into_iter().zip(permits) {
+        info!(
+            "Dispatching {} job {} for {}",
+            job.operation, job.id, job.crate_name
+        );
+        match job.operation.as_str() {
+            "remove_crate" => crate::crate_tools::RemoveRustCrateTool::spawn_removal(
+                CrateJobProcessor::new(db_pool.clone()),
+                job.id,
+                job.crate_name.clone(),
+                Some(permit),
+                RemoveJobOptions::from_job(&job),
+            ),
+            "add_npm_package" => crate::npm_tools::AddNpmPackageTool::spawn_ingestion(
+                CrateJobProcessor::new(db_pool.clone()),
+                NpmLoader::new(),
+                embedding_client.clone(),
+                db_pool.clone(),
+                job.id,
+                job.crate_name.clone(),
+                Some(permit),
+                PackageJobOptions::from_job(&job),
+            ),
+            "add_python_package" => crate::python_tools::AddPythonPackageTool::spawn_ingestion(
+                CrateJobProcessor::new(db_pool.clone()),
+                PyPiLoader::new(),
+                embedding_client.clone(),
+                db_pool.clone(),
+                job.id,
+                job.crate_name.clone(),
+                Some(permit),
+                PackageJobOptions::from_job(&job),
+            ),
+            "remove_npm_package" => crate::package_tools::spawn_package_removal(
+                &crate::package_tools::NPM_PACKAGES,
+                CrateJobProcessor::new(db_pool.clone()),
+                db_pool.clone(),
+                job.id,
+                job.crate_name.clone(),
+                Some(permit),
+                RemoveJobOptions::from_job(&job),
+            ),
+            "remove_python_package" => crate::package_tools::spawn_package_removal(
+                &crate::package_tools::PYTHON_PACKAGES,
+                CrateJobProcessor::new(db_pool.clone()),
+                db_pool.clone(),
+                job.id,
+                job.crate_name.clone(),
+                Some(permit),
+                RemoveJobOptions::from_job(&job),
+            ),
+            _ => crate::crate_tools::AddRustCrateTool::spawn_ingestion(
+                CrateJobProcessor::new(db_pool.clone()),
+                embedding_client.clone(),
+                db_pool.clone(),
+                job.id,
+                job.crate_name.clone(),
+                Some(permit),
+                CrateJobOptions::from_job(&job),
+            ),
+        }
+    }
+    Ok(started)
+}
+
+/// Start the background task that runs queued crate jobs
+///
+/// The task wakes on `NOTIFY crate_jobs_changed` and on a 30 second fallback
+/// poll, and dispatches queued jobs whenever concurrency permits are free.
+pub fn start_dispatcher(
+    db_pool: DatabasePool,
+    embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
+) {
+    tokio::spawn(async move {
+        let mut listener: Option<PgListener> = None;
+        let mut interval = tokio::time::interval(DISPATCH_POLL_INTERVAL);
+
+        loop {
+            if listener.is_none() {
+                listener = listen(&db_pool).await;
+            }
+
+            let saturated = match dispatch_queued_jobs(&db_pool, &embedding_client).await {
+                Ok(_) => crate::crate_tools::get_crate_job_semaphore().available_permits() == 0,
+                Err(e) => {
+                    warn!("Crate job dispatch failed: {}", e);
+                    false
+                }
+            };
+
+            tokio::select! {
+                _ = interval.tick() => {}
+                received = next_notification(listener.as_mut()) => match received {
+                    Ok(Some(())) => {}
+                    Ok(None) => debug!("Job notification connection lost; reconnecting"),
+                    Err(e) => {
+                        warn!("Job notification listener failed: {}", e);
+                        listener = None;
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                },
+                // A finished job frees a permit for the next queued one
+                permit = crate::crate_tools::get_crate_job_semaphore().acquire_owned(), if saturated => {
+                    drop(permit);
+                }
+            }
+        }
+    });
+}
+
+/// Subscribe to [`CRATE_JOBS_CHANNEL`], or `None` to fall back to polling
+async fn listen(db_pool: &DatabasePool) -> Option<PgListener> {
+    let mut listener = match PgListener::connect_with(db_pool.pool()).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!("Failed to connect job notification listener: {}", e);
+            return None;
+        }
+    };
+    match listener.listen(CRATE_JOBS_CHANNEL).await {
+        Ok(()) => Some(listener),
+        Err(e) => {
+            warn!("Failed to LISTEN on {}: {}", CRATE_JOBS_CHANNEL, e);
+            None
+        }
+    }
+}
+
+/// Wait for the next notification; `Ok(None)` means the connection dropped
+async fn next_notification(listener: Option<&mut PgListener>) -> sqlx::Result<Option<()>> {
+    match listener {
+        Some(listener) => Ok(listener.try_recv().await?.map(|_| ())),
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_http_statuses() {
+        let classify = |msg: &str| classify_job_error(&anyhow!(msg.to_string()));
+        assert_eq!(
+            classify("HTTP status: 503 Service Unavailable"),
+            JobErrorKind::Retryable
+        );
+        assert_eq!(
+            classify("HTTP status: 429 Too Many Requests"),
+            JobErrorKind::Retryable
+        );
+        assert_eq!(
+            classify("Failed to load crate documentation: HTTP status: 404 Not Found"),
+            JobErrorKind::Permanent
+        );
+        assert_eq!(
+            classify("HTTP status: 403 Forbidden"),
+            JobErrorKind::Permanent
+        );
+    }
+
+    #[test]
+    fn test_network_and_database_errors_retry() {
+        let classify = |msg: &str| classify_job_error(&anyhow!(msg.to_string()));
+        assert_eq!(
+            classify("HTTP failed: error sending request for url (https://docs.rs/)"),
+            JobErrorKind::Retryable
+        );
+        assert_eq!(classify("operation timed out"), JobErrorKind::Retryable);
+
+        let error = anyhow::Error::from(sqlx::Error::PoolTimedOut).context("storing pages");
+        assert_eq!(classify_job_error(&error), JobErrorKind::Retryable);
+    }
+
+    #[test]
+    fn test_permanent_errors() {
+        let classify = |msg: &str| classify_job_error(&anyhow!(msg.to_string()));
+        assert_eq!(
+            classify("Failed to load crate documentation: Invalid crates.io response"),
+            JobErrorKind::Permanent
+        );
+        assert_eq!(
+            classify("invalid version '1.x' for crate serde"),
+            JobErrorKind::Permanent
+        );
+        // Unrecognised errors are not retried
+        assert_eq!(
+            classify("duplicate key value violates unique constraint"),
+            JobErrorKind::Permanent
+        );
+        let error = anyhow::Error::from(sqlx::Error::RowNotFound);
+        assert_eq!(classify_job_error(&error), JobErrorKind::Permanent);
+    }
+
+    #[test]
+    fn test_retry_delay_backoff() {
+        assert_eq!(retry_delay(1), Duration::from_secs(60));
+        assert_eq!(retry_delay(2), Duration::from_secs(120));
+        assert_eq!(retry_delay(3), Duration::from_secs(240));
+        assert_eq!(retry_delay(20), MAX_RETRY_DELAY);
     }
 }
@@ -5,21 +5,51 @@
 //!
 //! Test deployment with namespace fix applied.
 
+pub mod admin;
+pub mod answer_tools;
+pub mod audit;
+pub mod auto_update;
 pub mod config;
+pub mod crate_diff_tools;
 pub mod crate_tools;
+pub mod document_tools;
+pub mod embedding_cache;
+pub mod feedback_tools;
 pub mod handlers;
 pub mod headers;
 pub mod health;
 pub mod ingest;
+pub mod ingest_tools;
 pub mod job_queue;
+pub mod maintenance;
 pub mod metrics;
+pub mod npm_tools;
+pub mod package_tools;
 pub mod protocol_version;
+pub mod provider_health;
+pub mod python_tools;
+pub mod query_cache;
+pub mod query_expansion;
 pub mod queue;
+pub mod rate_limit;
+pub mod rerank;
+pub mod resources;
 pub mod security;
 pub mod server;
 pub mod session;
+pub mod session_admin;
+pub mod snippets;
+pub mod source_tools;
+pub mod sse;
+pub mod telemetry;
+pub mod tool_concurrency;
+pub mod tool_schema;
+pub mod tool_switches;
+pub mod tool_timeouts;
 pub mod tools;
 pub mod transport;
+pub mod webhook_tools;
+pub mod webhooks;
 
 pub use server::McpServer;
 
@@ -0,0 +1,432 @@
+//! Periodic maintenance tasks
+//!
+//! Retention, cache eviction, session cleanup and similar chores register
+//! with a [`MaintenanceScheduler`] under a name and a default interval. Each
+//! task runs on its own loop with ±10% jitter, so tasks sharing an interval
+//! across replicas do not hit the database together. A run still in flight
+//! when the next one is due is skipped rather than stacked.
+//!
+//! Every run is spawned separately: an error or a panic is recorded against
+//! the task and the loop carries on. The last run of each task is reported
+//! by `/health` and the `get_maintenance_status` admin tool.
+//!
+//! Intervals can be overridden with `MAINTENANCE_INTERVALS`, as `name=secs`
+//! pairs separated by commas, e.g. `session_cleanup=60,audit_log_retention=3600`.
+//! An interval of 0 disables the task.
+
+use crate::tools::Tool;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// Name of the admin tool reporting task status
+pub const GET_MAINTENANCE_STATUS: &str = "get_maintenance_status";
+
+/// Longest wait before a task's first run; the wait is random so tasks
+/// started together do not run together
+const MAX_INITIAL_DELAY: Duration = Duration::from_secs(60);
+
+/// Fraction of the interval added or removed at random before each run
+const JITTER: f64 = 0.1;
+
+type TaskFn = Arc<dyn Fn() -> BoxFuture<'static, Result<String>> + Send + Sync>;
+
+/// Last known state of one maintenance task
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MaintenanceTaskStatus {
+    pub name: String,
+    /// Interval between runs before jitter
+    pub interval_ms: u64,
+    /// Whether a run is in flight
+    pub running: bool,
+    /// Completed runs, failed ones included
+    pub runs: u64,
+    /// Runs that returned an error or panicked
+    pub failures: u64,
+    /// Runs skipped because the previous run was still in flight
+    pub skipped: u64,
+    pub last_started_at: Option<DateTime<Utc>>,
+    pub last_finished_at: Option<DateTime<Utc>>,
+    pub last_duration_ms: Option<u64>,
+    /// Summary returned by the last successful run
+    pub last_result: Option<String>,
+    /// Error of the last run, cleared when a run succeeds
+    pub last_error: Option<String>,
+}
+
+struct Task {
+    interval: Duration,
+    run: TaskFn,
+    status: Mutex<MaintenanceTaskStatus>,
+}
+
+impl Task {
+    fn update(&self, f: impl FnOnce(&mut MaintenanceTaskStatus)) {
+        f(&mut self.status.lock().unwrap_or_else(PoisonError::into_inner));
+    }
+
+    fn status(&self) -> MaintenanceTaskStatus {
+        self.status
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+#[derive(Default)]
+struct Inner {
+    intervals: HashMap<String, Duration>,
+    tasks: Mutex<Vec<Arc<Task>>>,
+    loops: Mutex<Vec<JoinHandle<()>>>,
+    cancel: CancellationToken,
+}
+
+/// Runs named maintenance tasks on jittered intervals
+///
+/// Cloning shares the registry; [`MaintenanceScheduler::shutdown`] stops
+/// every task.
+#[derive(Clone, Default)]
+pub struct MaintenanceScheduler {
+    inner: Arc<Inner>,
+}
+
+impl MaintenanceScheduler {
+    /// Scheduler using each task's default interval
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scheduler with interval overrides from `MAINTENANCE_INTERVALS`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an entry is not `name=secs` with a whole number
+    /// of seconds.
+    pub fn from_env() -> Result<Self> {
+        let mut intervals = HashMap::new();
+        if let Ok(list) = std::env::var("MAINTENANCE_INTERVALS") {
+            for entry in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                let (name, secs) = entry.split_once('=').ok_or_else(|| {
+                    anyhow!("MAINTENANCE_INTERVALS: expected name=secs, got '{entry}'")
+                })?;
+                let secs = secs.trim().parse::<u64>().map_err(|_| {
+                    anyhow!("MAINTENANCE_INTERVALS: '{entry}': expected a number of seconds")
+                })?;
+                intervals.insert(name.trim().to_string(), Duration::from_secs(secs));
+            }
+        }
+        Ok(Self {
+            inner: Arc::new(Inner {
+                intervals,
+                ..Inner::default()
+            }),
+        })
+    }
+
+    /// Register `task` under `name` and start running it
+    ///
+    /// The task runs every `default_interval` unless `MAINTENANCE_INTERVALS`
+    /// names it; a zero interval leaves it disabled. On success the task
+    /// returns a short summary of what it did, recorded as its last result.
+    pub fn register<F, Fut>(&self, name: &str, default_interval: Duration, task: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        let interval = self
+            .inner
+            .intervals
+            .get(name)
+            .copied()
+            .unwrap_or(default_interval);
+        if interval.is_zero() {
+            info!("Maintenance task {} is disabled", name);
+            return;
+        }
+        let task = Arc::new(Task {
+            interval,
+            run: Arc::new(move || Box::pin(task())),
+            status: Mutex::new(MaintenanceTaskStatus {
+                name: name.to_string(),
+                interval_ms: u64::try_from(interval.as_millis()).unwrap_or(u64::MAX),
+                ..MaintenanceTaskStatus::default()
+            }),
+        });
+        self.inner
+            .tasks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(task.clone());
+        let handle = tokio::spawn(run_loop(task, self.inner.cancel.clone()));
+        self.inner
+            .loops
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(handle);
+        debug!("Registered maintenance task {} every {:?}", name, interval);
+    }
+
+    /// Status of every registered task, in registration order
+    #[must_use]
+    pub fn statuses(&self) -> Vec<MaintenanceTaskStatus> {
+        self.inner
+            .tasks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|task| task.status())
+            .collect()
+    }
+
+    /// Stop every task, aborting runs in flight, and wait for the loops to exit
+    pub async fn shutdown(&self) {
+        self.inner.cancel.cancel();
+        let loops = std::mem::take(
+            &mut *self
+                .inner
+                .loops
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+        for handle in loops {
+            let _ = handle.await;
+        }
+    }
+}
+
+/// Delay before the next run: the interval with ±[`JITTER`] applied
+fn jittered(interval: Duration) -> Duration {
+    interval.mul_f64(rand::rng().random_range((1.0 - JITTER)..=(1.0 + JITTER)))
+}
+
+async fn run_loop(task: Arc<Task>, cancel: CancellationToken) {
+    let first = task.interval.min(MAX_INITIAL_DELAY);
+    let mut delay = first.mul_f64(rand::rng().random_range(0.0..=1.0));
+    let mut in_flight: Option<JoinHandle<()>> = None;
+    loop {
+        tokio::select! {
+            () = cancel.cancelled() => break,
+            () = tokio::time::sleep(delay) => {}
+        }
+        delay = jittered(task.interval);
+
+        if in_flight.as_ref().is_some_and(|run| !run.is_finished()) {
+            task.update(|status| status.skipped += 1);
+            debug!(
+                "Skipping maintenance task {}: previous run still in flight",
+                task.status().name
+            );
+            continue;
+        }
+        in_flight = Some(tokio::spawn(run_once(task.clone())));
+    }
+    if let Some(run) = in_flight {
+        run.abort();
+        let _ = run.await;
+    }
+}
+
+/// Run the task once and record the outcome; the run is spawned so that a
+/// panic is caught as a join error
+async fn run_once(task: Arc<Task>) {
+    task.update(|status| {
+        status.running = true;
+        status.last_started_at = Some(Utc::now());
+    });
+    let started = Instant::now();
+    let outcome = tokio::spawn((task.run)()).await;
+    let elapsed = started.elapsed();
+
+    let name = task.status().name;
+    let outcome = match outcome {
+        Ok(Ok(summary)) => {
+            debug!(
+                "Maintenance task {} finished in {:?}: {}",
+                name, elapsed, summary
+            );
+            Ok(summary)
+        }
+        Ok(Err(e)) => {
+            warn!("Maintenance task {} failed: {:#}", name, e);
+            Err(format!("{e:#}"))
+        }
+        Err(e) if e.is_panic() => {
+            warn!("Maintenance task {} panicked", name);
+            Err(format!(
+                "panicked: {}",
+                panic_message(e.into_panic().as_ref())
+            ))
+        }
+        Err(_) => Err("cancelled".to_string()),
+    };
+    task.update(|status| {
+        status.running = false;
+        status.runs += 1;
+        status.last_finished_at = Some(Utc::now());
+        status.last_duration_ms = Some(u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX));
+        match outcome {
+            Ok(summary) => {
+                status.last_result = Some(summary);
+                status.last_error = None;
+            }
+            Err(error) => {
+                status.failures += 1;
+                status.last_error = Some(error);
+            }
+        }
+    });
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+/// Admin tool reporting the last run of every maintenance task
+pub struct GetMaintenanceStatusTool {
+    scheduler: MaintenanceScheduler,
+}
+
+impl GetMaintenanceStatusTool {
+    #[must_use]
+    pub fn new(scheduler: MaintenanceScheduler) -> Self {
+        Self { scheduler }
+    }
+}
+
+#[async_trait]
+impl Tool for GetMaintenanceStatusTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": GET_MAINTENANCE_STATUS,
+            "description": "Status of the server's periodic maintenance tasks (job retention, cache eviction, session cleanup): interval, run and failure counts, and the last run's timing, result and error, as JSON. Requires the admin token.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        })
+    }
+
+    async fn execute(&self, _arguments: Value) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&json!({
+            "tasks": self.scheduler.statuses(),
+        }))?)
+    }
+
+    fn requires_admin(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(scheduler: &MaintenanceScheduler, name: &str) -> MaintenanceTaskStatus {
+        scheduler
+            .statuses()
+            .into_iter()
+            .find(|s| s.name == name)
+            .unwrap()
+    }
+
+    /// Poll until `done` holds for the task, failing after five seconds
+    async fn wait_for(
+        scheduler: &MaintenanceScheduler,
+        name: &str,
+        done: impl Fn(&MaintenanceTaskStatus) -> bool,
+    ) -> MaintenanceTaskStatus {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let current = status(scheduler, name);
+            if done(&current) {
+                return current;
+            }
+            assert!(Instant::now() < deadline, "timed out: {current:?}");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_task_runs_repeatedly_until_shutdown() {
+        let scheduler = MaintenanceScheduler::new();
+        scheduler.register("tick", Duration::from_millis(10), || async {
+            Ok("ticked".to_string())
+        });
+        let tick = wait_for(&scheduler, "tick", |s| s.runs >= 3).await;
+        assert_eq!(tick.failures, 0);
+        assert_eq!(tick.last_result.as_deref(), Some("ticked"));
+        assert!(tick.last_duration_ms.is_some());
+
+        // Nothing runs after shutdown
+        scheduler.shutdown().await;
+        let runs = status(&scheduler, "tick").runs;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(status(&scheduler, "tick").runs, runs);
+    }
+
+    #[tokio::test]
+    async fn test_panicking_task_is_isolated() {
+        let scheduler = MaintenanceScheduler::new();
+        scheduler.register("panics", Duration::from_millis(10), || async {
+            panic!("boom");
+        });
+        scheduler.register("fails", Duration::from_millis(10), || async {
+            Err(anyhow!("database unavailable"))
+        });
+        scheduler.register("healthy", Duration::from_millis(10), || async {
+            Ok("fine".to_string())
+        });
+
+        // The failing tasks keep being retried on their schedule
+        let panics = wait_for(&scheduler, "panics", |s| s.failures >= 2).await;
+        assert_eq!(panics.last_error.as_deref(), Some("panicked: boom"));
+        let fails = wait_for(&scheduler, "fails", |s| s.failures >= 2).await;
+        assert_eq!(fails.last_error.as_deref(), Some("database unavailable"));
+
+        let healthy = wait_for(&scheduler, "healthy", |s| s.runs >= 3).await;
+        assert_eq!(healthy.failures, 0);
+        scheduler.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_run_in_flight_is_not_stacked() {
+        let scheduler = MaintenanceScheduler::new();
+        scheduler.register("slow", Duration::from_millis(10), || async {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok("done".to_string())
+        });
+        let slow = wait_for(&scheduler, "slow", |s| s.skipped >= 3).await;
+        scheduler.shutdown().await;
+
+        // Ticks during the first run were skipped rather than queued, and
+        // shutdown aborted the run instead of waiting it out
+        assert_eq!(slow.runs, 0, "{slow:?}");
+        assert!(slow.running);
+    }
+
+    #[tokio::test]
+    async fn test_zero_interval_disables_task() {
+        let scheduler = MaintenanceScheduler::new();
+        scheduler.register("off", Duration::ZERO, || async { Ok(String::new()) });
+        assert!(scheduler.statuses().is_empty());
+        scheduler.shutdown().await;
+    }
+}
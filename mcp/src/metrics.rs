@@ -3,9 +3,158 @@
 //! This module provides simple counters for tracking requests and errors.
 //! For MVP, we use atomic counters. In production, these could be extended
 //! to integrate with Prometheus or other metrics systems.
+//!
+//! Tool calls additionally get per-tool call/error counters and a fixed-bucket
+//! latency histogram built from atomics, so recording a call never blocks on
+//! other tools. Values live for the lifetime of the process.
 
+use crate::rate_limit::RequestClass;
+use crate::rerank::RerankOutcome;
+use chrono::{DateTime, Utc};
+use discovery::{result_text, result_usage, PromptRunner, Usage};
+use embed::{prices, ModelUsage};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::LazyLock;
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
+use std::time::Duration;
+
+/// Upper bounds (milliseconds) of the tool latency histogram buckets; slower calls land in an overflow bucket
+pub const LATENCY_BUCKETS_MS: [u64; 13] = [
+    5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000, 60_000,
+];
+
+/// Counters and latency histogram for one tool
+#[derive(Default)]
+struct ToolStats {
+    calls: AtomicU64,
+    errors: AtomicU64,
+    timeouts: AtomicU64,
+    total_micros: AtomicU64,
+    max_micros: AtomicU64,
+    /// One counter per [`LATENCY_BUCKETS_MS`] entry plus the overflow bucket
+    buckets: [AtomicU64; LATENCY_BUCKETS_MS.len() + 1],
+    last_error: Mutex<Option<(String, DateTime<Utc>)>>,
+}
+
+impl ToolStats {
+    fn record(&self, elapsed: Duration, error: Option<&str>) {
+        let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+        let millis = micros / 1_000;
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|&le| millis < le)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.total_micros.fetch_add(micros, Ordering::Relaxed);
+        self.max_micros.fetch_max(micros, Ordering::Relaxed);
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+
+        if let Some(message) = error {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+            if let Ok(mut last) = self.last_error.lock() {
+                *last = Some((message.to_string(), Utc::now()));
+            }
+        }
+    }
+
+    fn snapshot(&self, tool: &str) -> ToolMetricsSnapshot {
+        let counts: Vec<u64> = self
+            .buckets
+            .iter()
+            .map(|b| b.load(Ordering::Relaxed))
+            .collect();
+        let calls: u64 = counts.iter().sum();
+        let max_ms = self.max_micros.load(Ordering::Relaxed) / 1_000;
+        let percentile = |q: f64| percentile_ms(&counts, calls, q, max_ms);
+        let (last_error, last_error_at) = self
+            .last_error
+            .lock()
+            .ok()
+            .and_then(|last| last.clone())
+            .map_or((None, None), |(message, at)| (Some(message), Some(at)));
+
+        #[allow(clippy::cast_precision_loss)]
+        let mean_ms = if calls == 0 {
+            0.0
+        } else {
+            self.total_micros.load(Ordering::Relaxed) as f64 / calls as f64 / 1_000.0
+        };
+
+        ToolMetricsSnapshot {
+            tool: tool.to_string(),
+            calls,
+            errors: self.errors.load(Ordering::Relaxed),
+            timeouts: self.timeouts.load(Ordering::Relaxed),
+            p50_ms: percentile(0.50),
+            p95_ms: percentile(0.95),
+            p99_ms: percentile(0.99),
+            mean_ms,
+            max_ms,
+            latency_buckets: counts
+                .iter()
+                .enumerate()
+                .map(|(i, &count)| LatencyBucket {
+                    le_ms: LATENCY_BUCKETS_MS.get(i).copied(),
+                    count,
+                })
+                .collect(),
+            last_error,
+            last_error_at,
+        }
+    }
+}
+
+/// Upper bound of the bucket holding quantile `q`, or the observed maximum for the overflow bucket
+fn percentile_ms(counts: &[u64], total: u64, q: f64, max_ms: u64) -> u64 {
+    if total == 0 {
+        return 0;
+    }
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    let rank = ((q * total as f64).ceil() as u64).max(1);
+    let mut seen = 0;
+    for (i, count) in counts.iter().enumerate() {
+        seen += count;
+        if seen >= rank {
+            return LATENCY_BUCKETS_MS
+                .get(i)
+                .map_or(max_ms, |&le| le.min(max_ms.max(1)));
+        }
+    }
+    max_ms
+}
+
+/// Latency histogram bucket in a [`ToolMetricsSnapshot`]
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyBucket {
+    /// Upper bound in milliseconds; `None` for the overflow bucket
+    pub le_ms: Option<u64>,
+    pub count: u64,
+}
+
+/// Per-tool call statistics
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolMetricsSnapshot {
+    pub tool: String,
+    pub calls: u64,
+    pub errors: u64,
+    /// Calls abandoned after running past their time budget
+    pub timeouts: u64,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub p99_ms: u64,
+    pub mean_ms: f64,
+    pub max_ms: u64,
+    pub latency_buckets: Vec<LatencyBucket>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+}
 
 /// Global metrics collection
 pub struct McpMetrics {
@@ -27,6 +176,75 @@ pub struct McpMetrics {
     pub sessions_created: AtomicU64,
     /// Total number of sessions deleted
     pub sessions_deleted: AtomicU64,
+    /// Sessions currently stored (gauge)
+    pub sessions_open: AtomicU64,
+    /// Sessions with an SSE stream attached to this replica (gauge)
+    pub sessions_sse_attached: AtomicU64,
+    /// Total number of requests rejected for missing or invalid bearer tokens
+    pub auth_failures: AtomicU64,
+    /// Total number of POST/DELETE requests rejected by the rate limiter
+    pub rate_limited_requests: AtomicU64,
+    /// Total number of SSE GET requests rejected by the rate limiter
+    pub rate_limited_sse_requests: AtomicU64,
+    /// Total number of tool calls cancelled by clients
+    pub requests_cancelled: AtomicU64,
+    /// Total number of tool calls abandoned after running past their time budget
+    pub tool_timeouts: AtomicU64,
+    /// Total number of embeddings served from the embedding cache
+    pub embedding_cache_hits: AtomicU64,
+    /// Total number of embeddings not found in the embedding cache
+    pub embedding_cache_misses: AtomicU64,
+    /// Total number of query tool calls answered from the query result cache
+    pub query_cache_hits: AtomicU64,
+    /// Total number of query tool calls that ran a search
+    pub query_cache_misses: AtomicU64,
+    /// Total number of query embeddings served from the in-process cache
+    pub query_embedding_cache_hits: AtomicU64,
+    /// Total number of query embeddings requested from the embedding client
+    pub query_embedding_cache_misses: AtomicU64,
+    /// Total number of query results reordered by the reranking stage
+    pub reranks: AtomicU64,
+    /// Total number of reranking requests that timed out or failed and kept search order
+    pub rerank_fallbacks: AtomicU64,
+    /// Total number of audit log entries dropped because the writer queue was full
+    pub audit_entries_dropped: AtomicU64,
+    /// Authenticated requests per client label
+    requests_by_client: Mutex<BTreeMap<String, u64>>,
+    /// Call statistics per tool name
+    tools: RwLock<BTreeMap<String, Arc<ToolStats>>>,
+    /// Model API usage and its cost per kind (`embedding`, `llm`) and model
+    api_costs: Mutex<BTreeMap<(String, String), ModelUsage>>,
+    /// Tool calls that waited for or were refused a concurrency slot
+    tool_concurrency: Mutex<ToolConcurrencyMetrics>,
+}
+
+/// Concurrency limit effects across all sessions, and per open session
+#[derive(Debug, Default)]
+struct ToolConcurrencyMetrics {
+    totals: SessionConcurrencyStats,
+    /// Dropped when the session ends, so only open sessions are kept
+    sessions: BTreeMap<String, SessionConcurrencyStats>,
+}
+
+impl ToolConcurrencyMetrics {
+    /// Apply `update` to the totals and to the stats of `session`
+    fn record(&mut self, session: &str, update: impl Fn(&mut SessionConcurrencyStats)) {
+        update(&mut self.totals);
+        update(self.sessions.entry(session.to_string()).or_default());
+    }
+}
+
+/// Concurrency limit effects on the tool calls of one session, or of all
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionConcurrencyStats {
+    /// Calls that waited for a slot and got one
+    pub queued_calls: u64,
+    /// Total time those calls waited
+    pub queue_wait: Duration,
+    /// Longest wait of one of those calls
+    pub max_queue_wait: Duration,
+    /// Calls refused because no slot freed up in time
+    pub rejections: u64,
 }
 
 impl McpMetrics {
@@ -43,6 +261,34 @@ impl McpMetrics {
             internal_errors: AtomicU64::new(0),
             sessions_created: AtomicU64::new(0),
             sessions_deleted: AtomicU64::new(0),
+            sessions_open: AtomicU64::new(0),
+            sessions_sse_attached: AtomicU64::new(0),
+            auth_failures: AtomicU64::new(0),
+            rate_limited_requests: AtomicU64::new(0),
+            rate_limited_sse_requests: AtomicU64::new(0),
+            requests_cancelled: AtomicU64::new(0),
+            tool_timeouts: AtomicU64::new(0),
+            embedding_cache_hits: AtomicU64::new(0),
+            embedding_cache_misses: AtomicU64::new(0),
+            query_cache_hits: AtomicU64::new(0),
+            query_cache_misses: AtomicU64::new(0),
+            query_embedding_cache_hits: AtomicU64::new(0),
+            query_embedding_cache_misses: AtomicU64::new(0),
+            reranks: AtomicU64::new(0),
+            rerank_fallbacks: AtomicU64::new(0),
+            audit_entries_dropped: AtomicU64::new(0),
+            requests_by_client: Mutex::new(BTreeMap::new()),
+            tools: RwLock::new(BTreeMap::new()),
+            api_costs: Mutex::new(BTreeMap::new()),
+            tool_concurrency: Mutex::new(ToolConcurrencyMetrics {
+                totals: SessionConcurrencyStats {
+                    queued_calls: 0,
+                    queue_wait: Duration::ZERO,
+                    max_queue_wait: Duration::ZERO,
+                    rejections: 0,
+                },
+                sessions: BTreeMap::new(),
+            }),
         }
     }
 
@@ -93,6 +339,336 @@ impl McpMetrics {
         self.sessions_deleted.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Set the open and SSE-attached session gauges
+    pub fn set_session_gauges(&self, open: usize, sse_attached: usize) {
+        self.sessions_open
+            .store(open.try_into().unwrap_or(u64::MAX), Ordering::Relaxed);
+        self.sessions_sse_attached.store(
+            sse_attached.try_into().unwrap_or(u64::MAX),
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Increment authentication failures counter
+    pub fn increment_auth_failures(&self) {
+        self.auth_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Increment throttled requests counter for the given request class
+    pub fn increment_rate_limited(&self, class: RequestClass) {
+        let counter = match class {
+            RequestClass::Post => &self.rate_limited_requests,
+            RequestClass::Sse => &self.rate_limited_sse_requests,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Increment cancelled requests counter
+    pub fn increment_cancelled_requests(&self) {
+        self.requests_cancelled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record embedding cache hits and misses from one lookup
+    pub fn record_embedding_cache(&self, hits: u64, misses: u64) {
+        self.embedding_cache_hits.fetch_add(hits, Ordering::Relaxed);
+        self.embedding_cache_misses
+            .fetch_add(misses, Ordering::Relaxed);
+    }
+
+    /// Record a query result cache lookup
+    pub fn record_query_cache(&self, hit: bool) {
+        let counter = if hit {
+            &self.query_cache_hits
+        } else {
+            &self.query_cache_misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a query embedding cache lookup
+    pub fn record_query_embedding_cache(&self, hit: bool) {
+        let counter = if hit {
+            &self.query_embedding_cache_hits
+        } else {
+            &self.query_embedding_cache_misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record the outcome of one reranking request
+    pub fn record_rerank(&self, outcome: RerankOutcome) {
+        let counter = match outcome {
+            RerankOutcome::Reranked => &self.reranks,
+            RerankOutcome::TimedOut | RerankOutcome::Failed => &self.rerank_fallbacks,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record model API requests of one kind (`embedding`, `llm`) and their cost
+    pub fn record_api_cost(
+        &self,
+        kind: &str,
+        model: &str,
+        calls: u64,
+        input_tokens: u64,
+        output_tokens: u64,
+        cost_usd: f64,
+    ) {
+        if let Ok(mut costs) = self.api_costs.lock() {
+            let usage = costs
+                .entry((kind.to_string(), model.to_string()))
+                .or_default();
+            usage.calls += calls;
+            usage.input_tokens += input_tokens;
+            usage.output_tokens += output_tokens;
+            usage.cost_usd += cost_usd;
+        }
+    }
+
+    /// Model API usage keyed by kind and model
+    #[must_use]
+    pub fn api_costs(&self) -> BTreeMap<(String, String), ModelUsage> {
+        self.api_costs
+            .lock()
+            .map(|costs| costs.clone())
+            .unwrap_or_default()
+    }
+
+    /// Model API usage counters in the Prometheus text exposition format
+    #[must_use]
+    pub fn api_cost_prometheus(&self) -> String {
+        let costs = self.api_costs();
+        let mut out = String::new();
+        out.push_str("# TYPE mcp_api_cost_usd_total counter\n");
+        for ((kind, model), usage) in &costs {
+            let _ = writeln!(
+                out,
+                "mcp_api_cost_usd_total{{kind=\"{kind}\",model=\"{model}\"}} {}",
+                usage.cost_usd
+            );
+        }
+        out.push_str("# TYPE mcp_api_calls_total counter\n");
+        for ((kind, model), usage) in &costs {
+            let _ = writeln!(
+                out,
+                "mcp_api_calls_total{{kind=\"{kind}\",model=\"{model}\"}} {}",
+                usage.calls
+            );
+        }
+        out.push_str("# TYPE mcp_api_tokens_total counter\n");
+        for ((kind, model), usage) in &costs {
+            for (direction, tokens) in [
+                ("input", usage.input_tokens),
+                ("output", usage.output_tokens),
+            ] {
+                let _ = writeln!(
+                    out,
+                    "mcp_api_tokens_total{{kind=\"{kind}\",model=\"{model}\",direction=\"{direction}\"}} {tokens}"
+                );
+            }
+        }
+        out
+    }
+
+    /// Record a tool call of `session` that waited `waited` for a concurrency slot
+    pub fn record_tool_queue_wait(&self, session: &str, waited: Duration) {
+        if let Ok(mut concurrency) = self.tool_concurrency.lock() {
+            concurrency.record(session, |stats| {
+                stats.queued_calls += 1;
+                stats.queue_wait += waited;
+                stats.max_queue_wait = stats.max_queue_wait.max(waited);
+            });
+        }
+    }
+
+    /// Increment refused tool calls of `session`
+    pub fn increment_tool_concurrency_rejections(&self, session: &str) {
+        if let Ok(mut concurrency) = self.tool_concurrency.lock() {
+            concurrency.record(session, |stats| stats.rejections += 1);
+        }
+    }
+
+    /// Drop the concurrency limit stats of a session that ended
+    ///
+    /// Its calls stay counted in [`Self::tool_concurrency_totals`].
+    pub fn forget_tool_concurrency_session(&self, session: &str) {
+        if let Ok(mut concurrency) = self.tool_concurrency.lock() {
+            concurrency.sessions.remove(session);
+        }
+    }
+
+    /// Concurrency limit effects of open sessions keyed by session ID (`none`
+    /// without a session)
+    #[must_use]
+    pub fn tool_concurrency(&self) -> BTreeMap<String, SessionConcurrencyStats> {
+        self.tool_concurrency
+            .lock()
+            .map(|concurrency| concurrency.sessions.clone())
+            .unwrap_or_default()
+    }
+
+    /// Concurrency limit effects across all sessions since startup
+    #[must_use]
+    pub fn tool_concurrency_totals(&self) -> SessionConcurrencyStats {
+        self.tool_concurrency
+            .lock()
+            .map(|concurrency| concurrency.totals)
+            .unwrap_or_default()
+    }
+
+    /// Concurrency limit counters across all sessions in the Prometheus text
+    /// exposition format
+    ///
+    /// Session IDs are unbounded, so they are not used as labels.
+    #[must_use]
+    pub fn tool_concurrency_prometheus(&self) -> String {
+        let totals = self.tool_concurrency_totals();
+        format!(
+            "# TYPE mcp_tool_queued_calls_total counter\n\
+             mcp_tool_queued_calls_total {}\n\
+             # TYPE mcp_tool_queue_wait_seconds_total counter\n\
+             mcp_tool_queue_wait_seconds_total {}\n\
+             # TYPE mcp_tool_queue_wait_seconds_max gauge\n\
+             mcp_tool_queue_wait_seconds_max {}\n\
+             # TYPE mcp_tool_concurrency_rejections_total counter\n\
+             mcp_tool_concurrency_rejections_total {}\n",
+            totals.queued_calls,
+            totals.queue_wait.as_secs_f64(),
+            totals.max_queue_wait.as_secs_f64(),
+            totals.rejections,
+        )
+    }
+
+    /// Increment dropped audit log entries counter
+    pub fn increment_audit_entries_dropped(&self) {
+        self.audit_entries_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record an authenticated request for the given client label
+    pub fn record_client_request(&self, label: &str) {
+        if let Ok(mut counts) = self.requests_by_client.lock() {
+            *counts.entry(label.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    /// Authenticated request counts keyed by client label
+    #[must_use]
+    pub fn requests_by_client(&self) -> BTreeMap<String, u64> {
+        self.requests_by_client
+            .lock()
+            .map(|counts| counts.clone())
+            .unwrap_or_default()
+    }
+
+    /// Record one tool call, with the error message if it failed
+    pub fn record_tool_call(&self, tool: &str, elapsed: Duration, error: Option<&str>) {
+        if let Some(stats) = self.tool_stats(tool) {
+            stats.record(elapsed, error);
+        }
+    }
+
+    /// Increment timed out tool calls, overall and for `tool`
+    ///
+    /// The call itself is still recorded by [`Self::record_tool_call`].
+    pub fn increment_tool_timeouts(&self, tool: &str) {
+        self.tool_timeouts.fetch_add(1, Ordering::Relaxed);
+        if let Some(stats) = self.tool_stats(tool) {
+            stats.timeouts.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn tool_stats(&self, tool: &str) -> Option<Arc<ToolStats>> {
+        // Only the first call of a tool takes the write lock
+        let existing = self
+            .tools
+            .read()
+            .ok()
+            .and_then(|tools| tools.get(tool).cloned());
+        match existing {
+            Some(stats) => Some(stats),
+            None => self
+                .tools
+                .write()
+                .ok()
+                .map(|mut tools| tools.entry(tool.to_string()).or_default().clone()),
+        }
+    }
+
+    /// Call statistics for every tool called so far, sorted by name
+    #[must_use]
+    pub fn tool_metrics(&self) -> Vec<ToolMetricsSnapshot> {
+        self.tools
+            .read()
+            .map(|tools| {
+                tools
+                    .iter()
+                    .map(|(name, stats)| stats.snapshot(name))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Tool metrics in the Prometheus text exposition format
+    #[must_use]
+    pub fn tool_metrics_prometheus(&self) -> String {
+        let tools = self.tool_metrics();
+        let mut out = String::new();
+        out.push_str("# TYPE mcp_tool_calls_total counter\n");
+        for t in &tools {
+            let _ = writeln!(
+                out,
+                "mcp_tool_calls_total{{tool=\"{}\"}} {}",
+                t.tool, t.calls
+            );
+        }
+        out.push_str("# TYPE mcp_tool_errors_total counter\n");
+        for t in &tools {
+            let _ = writeln!(
+                out,
+                "mcp_tool_errors_total{{tool=\"{}\"}} {}",
+                t.tool, t.errors
+            );
+        }
+        out.push_str("# TYPE mcp_tool_timeouts_total counter\n");
+        for t in &tools {
+            let _ = writeln!(
+                out,
+                "mcp_tool_timeouts_total{{tool=\"{}\"}} {}",
+                t.tool, t.timeouts
+            );
+        }
+        out.push_str("# TYPE mcp_tool_latency_seconds histogram\n");
+        for t in &tools {
+            let mut cumulative = 0;
+            for bucket in &t.latency_buckets {
+                cumulative += bucket.count;
+                #[allow(clippy::cast_precision_loss)]
+                let le = bucket.le_ms.map_or_else(
+                    || "+Inf".to_string(),
+                    |ms| (ms as f64 / 1_000.0).to_string(),
+                );
+                let _ = writeln!(
+                    out,
+                    "mcp_tool_latency_seconds_bucket{{tool=\"{}\",le=\"{}\"}} {}",
+                    t.tool, le, cumulative
+                );
+            }
+            #[allow(clippy::cast_precision_loss)]
+            let sum = t.mean_ms * t.calls as f64 / 1_000.0;
+            let _ = writeln!(
+                out,
+                "mcp_tool_latency_seconds_sum{{tool=\"{}\"}} {}",
+                t.tool, sum
+            );
+            let _ = writeln!(
+                out,
+                "mcp_tool_latency_seconds_count{{tool=\"{}\"}} {}",
+                t.tool, t.calls
+            );
+        }
+        out
+    }
+
     /// Get current metrics as a snapshot
     #[must_use]
     pub fn snapshot(&self) -> MetricsSnapshot {
@@ -106,6 +682,22 @@ impl McpMetrics {
             internal_errors: self.internal_errors.load(Ordering::Relaxed),
             sessions_created: self.sessions_created.load(Ordering::Relaxed),
             sessions_deleted: self.sessions_deleted.load(Ordering::Relaxed),
+            sessions_open: self.sessions_open.load(Ordering::Relaxed),
+            sessions_sse_attached: self.sessions_sse_attached.load(Ordering::Relaxed),
+            auth_failures: self.auth_failures.load(Ordering::Relaxed),
+            rate_limited_requests: self.rate_limited_requests.load(Ordering::Relaxed),
+            rate_limited_sse_requests: self.rate_limited_sse_requests.load(Ordering::Relaxed),
+            requests_cancelled: self.requests_cancelled.load(Ordering::Relaxed),
+            tool_timeouts: self.tool_timeouts.load(Ordering::Relaxed),
+            embedding_cache_hits: self.embedding_cache_hits.load(Ordering::Relaxed),
+            embedding_cache_misses: self.embedding_cache_misses.load(Ordering::Relaxed),
+            query_cache_hits: self.query_cache_hits.load(Ordering::Relaxed),
+            query_cache_misses: self.query_cache_misses.load(Ordering::Relaxed),
+            query_embedding_cache_hits: self.query_embedding_cache_hits.load(Ordering::Relaxed),
+            query_embedding_cache_misses: self.query_embedding_cache_misses.load(Ordering::Relaxed),
+            reranks: self.reranks.load(Ordering::Relaxed),
+            rerank_fallbacks: self.rerank_fallbacks.load(Ordering::Relaxed),
+            audit_entries_dropped: self.audit_entries_dropped.load(Ordering::Relaxed),
         }
     }
 }
@@ -128,6 +720,22 @@ pub struct MetricsSnapshot {
     pub internal_errors: u64,
     pub sessions_created: u64,
     pub sessions_deleted: u64,
+    pub sessions_open: u64,
+    pub sessions_sse_attached: u64,
+    pub auth_failures: u64,
+    pub rate_limited_requests: u64,
+    pub rate_limited_sse_requests: u64,
+    pub requests_cancelled: u64,
+    pub tool_timeouts: u64,
+    pub embedding_cache_hits: u64,
+    pub embedding_cache_misses: u64,
+    pub query_cache_hits: u64,
+    pub query_cache_misses: u64,
+    pub query_embedding_cache_hits: u64,
+    pub query_embedding_cache_misses: u64,
+    pub reranks: u64,
+    pub rerank_fallbacks: u64,
+    pub audit_entries_dropped: u64,
 }
 
 /// Global metrics instance
@@ -139,6 +747,28 @@ pub fn metrics() -> &'static McpMetrics {
     &METRICS
 }
 
+/// Count one prompt run in the process-wide cost metrics, returning its usage and cost
+///
+/// Token counts come from the run's `result` event; output without one is
+/// counted with the tokenizer over the prompt and the answer text.
+pub fn account_llm_run(runner: &dyn PromptRunner, prompt: &str, output: &str) -> (Usage, f64) {
+    let model = runner.model().unwrap_or("unknown");
+    let usage = result_usage(output).unwrap_or_else(|| Usage {
+        input_tokens: embed::token_count(prompt) as u64,
+        output_tokens: embed::token_count(&result_text(output)) as u64,
+    });
+    let cost = prices().cost(model, usage.input_tokens, usage.output_tokens);
+    metrics().record_api_cost(
+        "llm",
+        model,
+        1,
+        usage.input_tokens,
+        usage.output_tokens,
+        cost,
+    );
+    (usage, cost)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -174,6 +804,92 @@ mod tests {
         assert_eq!(snapshot.method_not_allowed_total, 1);
     }
 
+    #[test]
+    fn test_auth_metrics() {
+        let metrics = McpMetrics::new();
+
+        metrics.increment_auth_failures();
+        metrics.record_client_request("ci");
+        metrics.record_client_request("ci");
+        metrics.record_client_request("laptop");
+
+        assert_eq!(metrics.snapshot().auth_failures, 1);
+        let by_client = metrics.requests_by_client();
+        assert_eq!(by_client.get("ci"), Some(&2));
+        assert_eq!(by_client.get("laptop"), Some(&1));
+    }
+
+    #[test]
+    fn test_embedding_cache_metrics() {
+        let metrics = McpMetrics::new();
+
+        metrics.record_embedding_cache(3, 1);
+        metrics.record_embedding_cache(0, 2);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.embedding_cache_hits, 3);
+        assert_eq!(snapshot.embedding_cache_misses, 3);
+    }
+
+    #[test]
+    fn test_api_cost_metrics() {
+        let metrics = McpMetrics::new();
+
+        metrics.record_api_cost("embedding", "text-embedding-3-large", 2, 1_000, 0, 0.00013);
+        metrics.record_api_cost("embedding", "text-embedding-3-large", 1, 500, 0, 0.000_065);
+        metrics.record_api_cost("llm", "claude-3-5-sonnet", 1, 100, 50, 0.001);
+
+        let costs = metrics.api_costs();
+        let embedding = &costs[&(
+            "embedding".to_string(),
+            "text-embedding-3-large".to_string(),
+        )];
+        assert_eq!(embedding.calls, 3);
+        assert_eq!(embedding.input_tokens, 1_500);
+        assert!((embedding.cost_usd - 0.000_195).abs() < 1e-12);
+
+        let text = metrics.api_cost_prometheus();
+        assert!(text.contains(
+            "mcp_api_calls_total{kind=\"embedding\",model=\"text-embedding-3-large\"} 3"
+        ));
+        assert!(text.contains(
+            "mcp_api_tokens_total{kind=\"llm\",model=\"claude-3-5-sonnet\",direction=\"output\"} 50"
+        ));
+        assert!(
+            text.contains("mcp_api_cost_usd_total{kind=\"llm\",model=\"claude-3-5-sonnet\"} 0.001")
+        );
+    }
+
+    #[test]
+    fn test_tool_latency_percentiles() {
+        let metrics = McpMetrics::new();
+        for _ in 0..98 {
+            metrics.record_tool_call("search_query", Duration::from_millis(3), None);
+        }
+        metrics.record_tool_call("search_query", Duration::from_millis(400), None);
+        metrics.record_tool_call("search_query", Duration::from_secs(90), Some("timeout"));
+
+        let tools = metrics.tool_metrics();
+        assert_eq!(tools.len(), 1);
+        let stats = &tools[0];
+        assert_eq!(stats.calls, 100);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(stats.p50_ms, 5);
+        assert_eq!(stats.p95_ms, 5);
+        assert_eq!(stats.p99_ms, 500);
+        assert_eq!(stats.max_ms, 90_000);
+        // The overflow bucket has no upper bound
+        assert_eq!(stats.latency_buckets.last().unwrap().le_ms, None);
+        assert_eq!(stats.latency_buckets.last().unwrap().count, 1);
+        assert_eq!(stats.last_error.as_deref(), Some("timeout"));
+
+        let text = metrics.tool_metrics_prometheus();
+        assert!(text.contains("mcp_tool_errors_total{tool=\"search_query\"} 1"));
+        assert!(
+            text.contains("mcp_tool_latency_seconds_bucket{tool=\"search_query\",le=\"+Inf\"} 100")
+        );
+    }
+
     #[test]
     fn test_global_metrics() {
         let metrics1 = metrics();
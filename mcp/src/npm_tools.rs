@@ -0,0 +1,93 @@
+//! npm package management tools for MCP
+//!
+//! `add_npm_package`, `remove_npm_package` and `list_npm_packages` are the
+//! shared tools of [`crate::package_tools`] on top of the npm registry.
+//! Documents are stored under doc type `npm`: a package's README and one
+//! page per declaration in its bundled TypeScript typings.
+
+use crate::package_tools::{
+    AddPackageTool, ListPackagesTool, PackageEcosystem, PackageRegistry, PackageRelease,
+    PackageToolDocs, RegistryLookup, RemovePackageTool, NPM_PACKAGES,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use npm_packages::{is_valid_package_name, NpmLoader, NpmLookup, NpmPackage};
+use rust_crates::{DocPage, RateLimiter};
+use serde_json::{json, Map, Value};
+use std::time::Duration;
+
+/// Add npm package tool - enqueues background job and returns 202 + job ID
+pub type AddNpmPackageTool = AddPackageTool<NpmLoader>;
+
+/// Remove npm package tool
+pub type RemoveNpmPackageTool = RemovePackageTool<NpmLoader>;
+
+/// List npm packages tool
+pub type ListNpmPackagesTool = ListPackagesTool<NpmLoader>;
+
+#[async_trait]
+impl PackageRegistry for NpmLoader {
+    type Package = NpmPackage;
+
+    const ECOSYSTEM: &'static PackageEcosystem = &NPM_PACKAGES;
+    const DOCS: PackageToolDocs = PackageToolDocs {
+        add: "Add an npm package to the documentation system: its README and the declarations of its bundled TypeScript typings are ingested from the npm registry. Returns immediately with a job ID; pass it as job_id to check_rust_status, which tracks package jobs as well as crate jobs. Errors carry a code in error.data.code: already_exists (-32005) when the package, or the pinned version, is already stored and force_update is not set (details.current_version); invalid_input (-32602) for an invalid name, a package the registry does not know or a version it does not have. If the registry cannot be reached within 5 seconds the job is queued anyway and the response says verified: false.",
+        add_name: "The npm package to add (e.g., 'zod', '@tanstack/query-core')",
+        add_version: "Exact version or dist-tag to fetch, e.g. '3.22.4' or 'next' (optional, defaults to latest). A requested version is kept next to the package's other ingested versions; force_update then replaces only that version.",
+        remove: "Remove an npm package, or one ingested version of it, from the documentation system with cleanup verification. Large packages are removed by a background job whose job ID check_rust_status tracks like a crate job. Errors carry a code in error.data.code: not_found (-32004) when the package or the requested version is not stored (details.stored_versions); invalid_input (-32602) for an empty name.",
+    };
+
+    fn for_tools() -> Self {
+        Self::with_fetcher(Box::new(RateLimiter::with_limits(
+            Duration::from_secs(1),
+            2,
+        )))
+    }
+
+    fn is_valid_name(name: &str) -> bool {
+        is_valid_package_name(name)
+    }
+
+    async fn lookup(&self, name: &str, version: Option<&str>, within: Duration) -> RegistryLookup {
+        match self.lookup_package(name, version, within).await {
+            NpmLookup::Found(package) => RegistryLookup::Found(package.version),
+            NpmLookup::NotFound => RegistryLookup::NotFound,
+            NpmLookup::InvalidVersion(reason) => RegistryLookup::InvalidVersion(reason),
+            NpmLookup::Unavailable(reason) => RegistryLookup::Unavailable(reason),
+        }
+    }
+
+    async fn load(&self, name: &str, version: Option<&str>) -> Result<NpmPackage> {
+        self.load_package(name, version).await
+    }
+
+    async fn pages(&self, package: &NpmPackage) -> Vec<DocPage> {
+        self.load_package_docs(package).await
+    }
+
+    fn release(package: &NpmPackage) -> PackageRelease<'_> {
+        PackageRelease {
+            name: &package.name,
+            version: &package.version,
+            info: json!(package),
+            metadata: package_metadata(package),
+            readme_format: "markdown",
+            page_format: "markdown",
+            details: json!({"typings": package.types}),
+        }
+    }
+}
+
+/// Registry metadata every page of a package version carries
+fn package_metadata(package: &NpmPackage) -> Map<String, Value> {
+    let mut metadata = Map::new();
+    metadata.insert(
+        "package_description".to_string(),
+        json!(package.description),
+    );
+    metadata.insert("homepage".to_string(), json!(package.homepage));
+    metadata.insert("repository".to_string(), json!(package.repository));
+    metadata.insert("license".to_string(), json!(package.license));
+    metadata.insert("keywords".to_string(), json!(package.keywords));
+    metadata
+}
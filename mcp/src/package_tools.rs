@@ -0,0 +1,1572 @@
+//! Shared implementation of the package ecosystem tools
+//!
+//! npm and Python packages are managed the same way. `add_*_package` checks
+//! the package on its registry and queues an `add_*_package` job in
+//! `crate_jobs`, so package jobs share the crate job dispatcher, concurrency
+//! cap and `check_rust_status` tracking. The job stores the package's pages
+//! under the ecosystem's doc type, one source per package, with
+//! `package_name` and `package_version` metadata; `remove_*_package` and
+//! `list_*_packages` work on those documents. A [`PackageEcosystem`] names
+//! what differs between ecosystems, and each ecosystem's loader implements
+//! [`PackageRegistry`] for [`AddPackageTool`], [`RemovePackageTool`] and
+//! [`ListPackagesTool`].
+
+#![allow(clippy::uninlined_format_args)]
+#![allow(clippy::must_use_candidate)]
+#![allow(clippy::doc_markdown)]
+#![allow(clippy::too_many_lines)]
+
+use crate::crate_tools::{
+    crate_remove_async_threshold, crate_remove_batch_size, get_crate_job_semaphore,
+    idempotency_key_argument, replay_job, replayed_job_response, vector_writes_available,
+    RunningJobGuard,
+};
+use crate::embedding_cache::CachedEmbeddingClient;
+use crate::job_queue::{CrateJobProcessor, PackageJobOptions, RemoveJobOptions};
+use crate::query_cache::query_cache;
+use crate::tools::{Tool, ToolError};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use db::{
+    models::{CrateInfo, JobStatus, PaginationParams},
+    queries::{CrateJobQueries, DocumentQueries, PackageQueries},
+    DatabasePool,
+};
+use embed::client::EmbeddingClient;
+use npm_packages::NPM_DOC_TYPE;
+use python_packages::{normalize_package_name, PYTHON_DOC_TYPE};
+use rust_crates::DocPage;
+use serde_json::{json, Map, Value};
+use std::{
+    collections::HashMap, fmt::Write as _, future::Future, marker::PhantomData, sync::Arc,
+    time::Duration,
+};
+use tokio::sync::OwnedSemaphorePermit;
+use uuid::Uuid;
+
+/// What tells one package ecosystem's tools and jobs apart from another's
+#[derive(Debug)]
+pub struct PackageEcosystem {
+    /// `doc_type` of the ecosystem's documents
+    pub doc_type: &'static str,
+    /// How messages name the ecosystem, e.g. `npm` or `Python`
+    pub label: &'static str,
+    /// `crate_jobs` operation of ingestion jobs
+    pub add_operation: &'static str,
+    /// `crate_jobs` operation of removal jobs
+    pub remove_operation: &'static str,
+    /// Redis job type of ingestion jobs
+    pub add_job_type: &'static str,
+    /// Redis job type of removal jobs
+    pub remove_job_type: &'static str,
+    /// Name under which a package is stored, given the name a caller used
+    pub normalize_name: fn(&str) -> String,
+    /// How messages name the registry, e.g. `the npm registry`
+    pub registry: &'static str,
+    /// What a package without pages lacks, e.g. `a README nor typings`
+    pub page_kinds: &'static str,
+}
+
+/// npm packages, stored under their exact registry name
+pub const NPM_PACKAGES: PackageEcosystem = PackageEcosystem {
+    doc_type: NPM_DOC_TYPE,
+    label: "npm",
+    add_operation: "add_npm_package",
+    remove_operation: "remove_npm_package",
+    add_job_type: "npm_add",
+    remove_job_type: "npm_remove",
+    normalize_name: str::to_string,
+    registry: "the npm registry",
+    page_kinds: "a README nor typings",
+};
+
+/// Python packages, stored under their normalized (PEP 503) name
+pub const PYTHON_PACKAGES: PackageEcosystem = PackageEcosystem {
+    doc_type: PYTHON_DOC_TYPE,
+    label: "Python",
+    add_operation: "add_python_package",
+    remove_operation: "remove_python_package",
+    add_job_type: "python_add",
+    remove_job_type: "python_remove",
+    normalize_name: normalize_package_name,
+    registry: "PyPI",
+    page_kinds: "a README nor readthedocs pages",
+};
+
+impl PackageEcosystem {
+    /// Ecosystem whose jobs have the `crate_jobs` operation `operation`
+    pub fn for_operation(operation: &str) -> Option<&'static Self> {
+        [&NPM_PACKAGES, &PYTHON_PACKAGES]
+            .into_iter()
+            .find(|ecosystem| {
+                ecosystem.add_operation == operation || ecosystem.remove_operation == operation
+            })
+    }
+}
+
+/// Response to a request retried with an idempotency key that already created a job
+///
+/// # Errors
+///
+/// Returns an error if the job lookup fails, or a conflict if the key names
+/// a job for another package or operation.
+async fn replayed_job(
+    db_pool: &DatabasePool,
+    idempotency_key: Option<&str>,
+    package_name: &str,
+    operation: &str,
+) -> Result<Option<String>> {
+    let Some(key) = idempotency_key else {
+        return Ok(None);
+    };
+    CrateJobQueries::find_job_by_idempotency_key(db_pool.pool(), key)
+        .await?
+        .map(|job| replay_job(&job, key, package_name, operation))
+        .transpose()
+}
+
+/// Refuse to add a package that is already stored, unless `force_update` is set
+///
+/// A pinned version is added next to the package's other versions, so only
+/// the same version counts as existing.
+///
+/// # Errors
+///
+/// Returns [`ToolError::AlreadyExists`] for a stored package, or an error if
+/// the lookup fails.
+async fn check_not_stored(
+    ecosystem: &PackageEcosystem,
+    db_pool: &DatabasePool,
+    package_name: &str,
+    pinned_version: Option<&str>,
+    force_update: bool,
+) -> Result<()> {
+    let versions =
+        PackageQueries::find_package_versions(db_pool.pool(), ecosystem.doc_type, package_name)
+            .await?;
+    let existing = match pinned_version {
+        Some(pinned) => versions.iter().find(|info| info.version == pinned),
+        None => versions.first(),
+    };
+    if let Some(existing) = existing {
+        if !force_update {
+            return Err(already_exists(ecosystem, package_name, &existing.version).into());
+        }
+        tracing::info!(
+            "Force updating existing {} package '{}' (current version: {})",
+            ecosystem.label,
+            package_name,
+            existing.version
+        );
+    }
+    Ok(())
+}
+
+/// Queue an ingestion job and hand it to Redis or the local dispatcher
+///
+/// Returns the `accepted` response, or the earlier job's response when a
+/// concurrent call with the same idempotency key won.
+///
+/// # Errors
+///
+/// Returns an error if the job cannot be created or published.
+async fn queue_add_job(
+    ecosystem: &PackageEcosystem,
+    job_processor: &CrateJobProcessor,
+    db_pool: &DatabasePool,
+    embedding_client: &Arc<dyn EmbeddingClient + Send + Sync>,
+    package_name: &str,
+    options: &PackageJobOptions,
+    idempotency_key: Option<&str>,
+) -> Result<String> {
+    let enqueued = job_processor
+        .enqueue_add_package_job(ecosystem, package_name, options, idempotency_key)
+        .await?;
+    if !enqueued.created {
+        return Ok(replayed_job_response(&enqueued.job));
+    }
+    let job_id = enqueued.job.id;
+
+    if crate::queue::use_redis_queue() {
+        let mut payload = serde_json::to_value(options)?;
+        payload["package_name"] = json!(package_name);
+        let msg = crate::queue::RedisJobMessage::new(job_id, ecosystem.add_job_type, 3, payload);
+        crate::queue::enqueue_job(&msg).await?;
+    } else if let Err(e) = crate::job_queue::dispatch_queued_jobs(db_pool, embedding_client).await {
+        // The job stays queued for the dispatcher's next pass
+        tracing::warn!(
+            "Failed to dispatch {} package job {}: {}",
+            ecosystem.label,
+            job_id,
+            e
+        );
+    }
+
+    Ok(json!({
+        "status": "accepted",
+        "job_id": job_id.to_string(),
+        "package_name": package_name,
+        "verified": !options.unverified,
+        "version": options.version,
+        "version_req": options.version_req,
+        "message": format!("{} package '{package_name}' ingestion job queued successfully. Use check_rust_status with job_id, which also tracks package jobs, to follow progress.", ecosystem.label)
+    })
+    .to_string())
+}
+
+/// Error for adding a package that is already stored without `force_update`
+fn already_exists(
+    ecosystem: &PackageEcosystem,
+    package_name: &str,
+    current_version: &str,
+) -> ToolError {
+    ToolError::AlreadyExists {
+        message: format!(
+            "{} package '{package_name}' already exists in the system (version: {current_version}). Use force_update=true to update it, or remove_{}_package first if you want to completely replace it.",
+            ecosystem.label, ecosystem.doc_type
+        ),
+        details: json!({
+            "package_name": package_name,
+            "current_version": current_version,
+        }),
+    }
+}
+
+/// Run a package job on a background task, recording a failure on the job
+///
+/// Waits for a concurrency permit unless the caller already reserved one;
+/// package and crate jobs share one concurrency cap.
+fn spawn_package_job<F>(
+    job_processor: CrateJobProcessor,
+    job_id: Uuid,
+    description: String,
+    permit: Option<OwnedSemaphorePermit>,
+    work: F,
+) where
+    F: Future<Output = Result<()>> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let _permit = match permit {
+            Some(permit) => Some(permit),
+            None => get_crate_job_semaphore().acquire_owned().await.ok(),
+        };
+        let _running = RunningJobGuard::register(job_id);
+        tracing::info!("Background task started for {}", description);
+
+        if let Err(e) = job_processor.run_with_heartbeat(job_id, work).await {
+            tracing::error!("Background {} failed: {}", description, e);
+            // Reschedule with backoff, or dead-letter once attempts run out
+            if let Err(update_err) = job_processor.record_failure(job_id, &e).await {
+                tracing::error!("Failed to record job failure: {}", update_err);
+            }
+        }
+    });
+}
+
+/// One loaded package release, as an ingestion job stores it
+pub struct PackageRelease<'a> {
+    pub name: &'a str,
+    pub version: &'a str,
+    /// Registry metadata, recorded on the package's document source
+    pub info: Value,
+    /// Metadata every page of the release carries, e.g. description and license
+    pub metadata: Map<String, Value>,
+    /// `format` of the README page
+    pub readme_format: &'a str,
+    /// `format` of every other page
+    pub page_format: &'a str,
+    /// Recorded in the job's details next to the embedding statistics
+    pub details: Value,
+}
+
+/// Store the pages of a loaded release for an ingestion job and complete it
+///
+/// Pages already stored with the same content keep their embeddings. A
+/// force update then drops the package's documents this job did not write;
+/// a pinned version only replaces its own documents.
+///
+/// # Errors
+///
+/// Returns an error if a database write or a job update fails.
+#[allow(clippy::too_many_arguments)]
+async fn store_package(
+    ecosystem: &PackageEcosystem,
+    job_processor: &CrateJobProcessor,
+    embedding_client: &Arc<dyn EmbeddingClient + Send + Sync>,
+    db_pool: &DatabasePool,
+    job_id: Uuid,
+    release: &PackageRelease<'_>,
+    pages: &[DocPage],
+    options: &PackageJobOptions,
+) -> Result<()> {
+    let vector_extension_available = vector_writes_available(db_pool, release.name).await?;
+
+    sqlx::query(
+        r"
+        INSERT INTO document_sources (doc_type, source_name, config, enabled)
+        VALUES ($1, $2, $3, true)
+        ON CONFLICT (doc_type, source_name) DO UPDATE
+        SET config = document_sources.config || $4, updated_at = CURRENT_TIMESTAMP
+        ",
+    )
+    .bind(ecosystem.doc_type)
+    .bind(release.name)
+    .bind(json!({"auto_ingested": true, "package_info": release.info}))
+    .bind(json!({"package_info": release.info}))
+    .execute(db_pool.pool())
+    .await?;
+    job_processor
+        .update_job_status(job_id, JobStatus::Running, Some(25), None)
+        .await?;
+
+    let cached_client =
+        CachedEmbeddingClient::new(embedding_client.clone(), db_pool.pool().clone());
+    let mut sink = PackageSink {
+        ecosystem,
+        job_processor,
+        embedding_client: &cached_client,
+        db_pool,
+        release,
+        version_req: options.version_req.as_deref(),
+        job_id,
+        force_update: options.force_update,
+        vector_extension_available,
+        chunk_config: embed::ChunkConfig::from_env(),
+        total_docs: 0,
+        total_tokens: 0,
+        embedding_stats: embed::EmbeddingStats::default(),
+    };
+    let stored = sink.store_pages(pages).await;
+
+    let costs = cached_client.costs();
+    if !costs.is_empty() {
+        if let Err(e) = job_processor.add_job_costs(job_id, &costs).await {
+            tracing::warn!("Failed to record costs of job {}: {}", job_id, e);
+        }
+    }
+    stored?;
+
+    if options.force_update {
+        let removed = sqlx::query(
+            r"
+            DELETE FROM documents
+            WHERE doc_type = $1 AND source_name = $2
+            AND metadata->>'ingestion_job_id' IS DISTINCT FROM $3
+            AND ($4::text IS NULL OR metadata->>'package_version' = $4)
+            AND NOT (metadata ? 'version_req' AND metadata->>'package_version' IS DISTINCT FROM $5)
+            ",
+        )
+        .bind(ecosystem.doc_type)
+        .bind(release.name)
+        .bind(job_id.to_string())
+        .bind(options.version_req.as_ref().map(|_| release.version))
+        .bind(release.version)
+        .execute(db_pool.pool())
+        .await?;
+        tracing::info!(
+            "Removed {} stale documents for force update of {} package: {}",
+            removed.rows_affected(),
+            ecosystem.label,
+            release.name
+        );
+    }
+
+    // Shown by check_rust_status next to the final status
+    let mut details = json!({
+        "embeddings": sink.embedding_stats,
+        "pages": pages.len(),
+        "package_version": release.version,
+    });
+    if let (Some(details), Some(extra)) = (details.as_object_mut(), release.details.as_object()) {
+        details.extend(extra.clone());
+    }
+    CrateJobQueries::merge_job_details(db_pool.pool(), job_id, &details).await?;
+    job_processor
+        .update_job_status(job_id, JobStatus::Completed, Some(100), None)
+        .await?;
+
+    tracing::info!(
+        "Completed ingestion of {} package {} {}: {} documents, {} tokens, embeddings: {}",
+        ecosystem.label,
+        release.name,
+        release.version,
+        sink.total_docs,
+        sink.total_tokens,
+        sink.embedding_stats
+    );
+    Ok(())
+}
+
+/// Stores the pages of one package release for an ingestion job
+struct PackageSink<'a> {
+    ecosystem: &'a PackageEcosystem,
+    job_processor: &'a CrateJobProcessor,
+    embedding_client: &'a CachedEmbeddingClient,
+    db_pool: &'a DatabasePool,
+    release: &'a PackageRelease<'a>,
+    version_req: Option<&'a str>,
+    job_id: Uuid,
+    force_update: bool,
+    vector_extension_available: bool,
+    chunk_config: embed::ChunkConfig,
+    total_docs: usize,
+    total_tokens: i64,
+    embedding_stats: embed::EmbeddingStats,
+}
+
+impl PackageSink<'_> {
+    /// Upsert pages, split into chunks, in transactions of ten pages
+    ///
+    /// Chunks whose content hash matches the stored row are not rewritten;
+    /// only their job metadata is refreshed so force-update cleanup keeps them.
+    async fn store_pages(&mut self, doc_pages: &[DocPage]) -> Result<()> {
+        let doc_type = self.ecosystem.doc_type;
+        let total_batches = doc_pages.len().div_ceil(10).max(1);
+
+        for (batch_index, batch) in doc_pages.chunks(10).enumerate() {
+            let pages: Vec<(&DocPage, Vec<String>)> = batch
+                .iter()
+                .map(|doc_page| {
+                    let mut chunks = embed::chunk_text(&doc_page.content, &self.chunk_config);
+                    if chunks.is_empty() {
+                        chunks.push(doc_page.content.clone());
+                    }
+                    (doc_page, chunks)
+                })
+                .collect();
+
+            let chunk_paths: Vec<String> = pages
+                .iter()
+                .flat_map(|(doc_page, chunks)| {
+                    (0..chunks.len()).map(|i| db::chunks::chunk_doc_path(&doc_page.url, i))
+                })
+                .collect();
+            let stored_hashes = DocumentQueries::stored_content_hashes(
+                self.db_pool.pool(),
+                doc_type,
+                self.release.name,
+                &chunk_paths,
+            )
+            .await?;
+
+            let mut embeddings = self.embed_changed_chunks(&pages, &stored_hashes).await;
+            let embedding_config = self.embedding_client.embedding_config();
+
+            let mut tx = self.db_pool.pool().begin().await?;
+            let mut unchanged_paths: Vec<String> = Vec::new();
+
+            for (doc_page, chunks) in pages {
+                let metadata = self.page_metadata(doc_page);
+                let chunk_total = chunks.len();
+
+                for (chunk_index, chunk) in chunks.into_iter().enumerate() {
+                    let doc_path = db::chunks::chunk_doc_path(&doc_page.url, chunk_index);
+                    let token_count = i32::try_from(embed::token_count(&chunk)).unwrap_or(i32::MAX);
+                    self.total_docs += 1;
+                    self.total_tokens += i64::from(token_count);
+
+                    if stored_hashes.get(&doc_path) == Some(&db::content_hash(&chunk)) {
+                        self.embedding_stats.skipped += 1;
+                        unchanged_paths.push(doc_path);
+                        continue;
+                    }
+
+                    let mut chunk_metadata = metadata.clone();
+                    db::chunks::annotate_chunk_metadata(
+                        &mut chunk_metadata,
+                        &doc_page.url,
+                        chunk_index,
+                        chunk_total,
+                    );
+                    db::annotate_content_hash(&mut chunk_metadata, &chunk);
+
+                    let embedding = match embeddings.remove(&doc_path) {
+                        Some(Ok(embedding)) => {
+                            embedding_config.annotate_metadata(&mut chunk_metadata);
+                            Some(pgvector::Vector::from(embedding))
+                        }
+                        Some(Err(e)) => {
+                            // Left for the backfill tool to retry
+                            chunk_metadata[embed::pipeline::METADATA_EMBEDDING_ERROR_KEY] =
+                                json!(e.to_string());
+                            None
+                        }
+                        None => {
+                            self.embedding_stats.skipped += 1;
+                            None
+                        }
+                    };
+
+                    // Upsert document; an existing embedding is kept only if the content is unchanged
+                    sqlx::query(
+                        r"
+                        INSERT INTO documents (id, doc_type, source_name, doc_path, content, metadata, token_count, embedding, created_at, updated_at)
+                        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, CURRENT_TIMESTAMP, CURRENT_TIMESTAMP)
+                        ON CONFLICT (doc_type, source_name, doc_path) DO UPDATE SET
+                            content = EXCLUDED.content,
+                            metadata = EXCLUDED.metadata,
+                            token_count = EXCLUDED.token_count,
+                            updated_at = EXCLUDED.updated_at,
+                            embedding = COALESCE(EXCLUDED.embedding, CASE
+                                WHEN documents.content IS DISTINCT FROM EXCLUDED.content THEN NULL
+                                ELSE documents.embedding
+                            END)
+                        ",
+                    )
+                    .bind(db::new_document_id(
+                        doc_type,
+                        self.release.name,
+                        &doc_path,
+                        &chunk_metadata,
+                    ))
+                    .bind(doc_type)
+                    .bind(self.release.name)
+                    .bind(&doc_path)
+                    .bind(&chunk)
+                    .bind(&chunk_metadata)
+                    .bind(token_count)
+                    .bind(embedding)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+
+                // Drop chunks left over from a longer earlier version of the page
+                sqlx::query(
+                    r"
+                    DELETE FROM documents
+                    WHERE doc_type = $1 AND source_name = $2
+                      AND metadata->>'parent_doc_path' = $3
+                      AND (metadata->>'chunk_index')::int >= $4
+                    ",
+                )
+                .bind(doc_type)
+                .bind(self.release.name)
+                .bind(&doc_page.url)
+                .bind(i32::try_from(chunk_total).unwrap_or(i32::MAX))
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            if !unchanged_paths.is_empty() {
+                sqlx::query(
+                    r"
+                    UPDATE documents SET metadata = metadata || $4
+                    WHERE doc_type = $1 AND source_name = $2 AND doc_path = ANY($3)
+                    ",
+                )
+                .bind(doc_type)
+                .bind(self.release.name)
+                .bind(&unchanged_paths)
+                .bind(json!({
+                    "package_version": self.release.version,
+                    "force_updated": self.force_update,
+                    "ingestion_job_id": self.job_id.to_string(),
+                }))
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            tx.commit().await?;
+
+            let progress = 25 + i32::try_from((batch_index + 1) * 70 / total_batches).unwrap_or(70);
+            self.job_processor
+                .update_job_status(self.job_id, JobStatus::Running, Some(progress), None)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Metadata shared by every chunk of a page
+    fn page_metadata(&self, doc_page: &DocPage) -> Value {
+        let release = self.release;
+        let mut metadata = db::create_enhanced_metadata(
+            self.ecosystem.doc_type,
+            release.name,
+            &doc_page.content,
+            &doc_page.module_path,
+        );
+        if let Some(metadata_obj) = metadata.as_object_mut() {
+            metadata_obj.insert("package_name".to_string(), json!(release.name));
+            metadata_obj.insert("package_version".to_string(), json!(release.version));
+            if let Some(version_req) = self.version_req {
+                metadata_obj.insert("version_req".to_string(), json!(version_req));
+            }
+            metadata_obj.insert("item_type".to_string(), json!(doc_page.item_type));
+            let format = if doc_page.item_type == "readme" {
+                release.readme_format
+            } else {
+                release.page_format
+            };
+            metadata_obj.insert("format".to_string(), json!(format));
+            metadata_obj.insert("module_path".to_string(), json!(doc_page.module_path));
+            metadata_obj.extend(release.metadata.clone());
+            metadata_obj.insert("extracted_at".to_string(), json!(doc_page.extracted_at));
+            metadata_obj.insert("source_url".to_string(), json!(doc_page.url));
+            metadata_obj.insert("force_updated".to_string(), json!(self.force_update));
+            metadata_obj.insert(
+                "ingestion_job_id".to_string(),
+                json!(self.job_id.to_string()),
+            );
+        }
+        metadata
+    }
+
+    /// Embed the chunks of `pages` whose content changed, keyed by doc path
+    ///
+    /// Runs before the batch transaction opens so no API call holds it open.
+    async fn embed_changed_chunks(
+        &mut self,
+        pages: &[(&DocPage, Vec<String>)],
+        stored_hashes: &HashMap<String, String>,
+    ) -> HashMap<String, Result<Vec<f32>>> {
+        if !self.vector_extension_available {
+            return HashMap::new();
+        }
+
+        let changed: Vec<(String, &str)> = pages
+            .iter()
+            .flat_map(|(doc_page, chunks)| {
+                chunks.iter().enumerate().map(|(index, chunk)| {
+                    (
+                        db::chunks::chunk_doc_path(&doc_page.url, index),
+                        chunk.as_str(),
+                    )
+                })
+            })
+            .filter(|(doc_path, chunk)| {
+                !chunk.is_empty() && stored_hashes.get(doc_path) != Some(&db::content_hash(chunk))
+            })
+            .collect();
+        if changed.is_empty() {
+            return HashMap::new();
+        }
+
+        let texts: Vec<&str> = changed.iter().map(|(_, chunk)| *chunk).collect();
+        let results = self.embedding_client.embed_many(&texts).await;
+
+        let mut failed = 0;
+        let mut first_error = None;
+        for result in &results {
+            self.embedding_stats.record(result);
+            if let Err(e) = result {
+                failed += 1;
+                first_error.get_or_insert_with(|| e.to_string());
+            }
+        }
+        if let Some(first_error) = first_error {
+            tracing::warn!(
+                "Failed to embed {} of {} documents for {} package {}: {}",
+                failed,
+                results.len(),
+                self.ecosystem.label,
+                self.release.name,
+                first_error
+            );
+            self.job_processor
+                .record_job_event(
+                    self.job_id,
+                    "embedding_errors",
+                    json!({
+                        "failed": failed,
+                        "documents": results.len(),
+                        "first_error": first_error,
+                    }),
+                )
+                .await;
+        }
+
+        changed
+            .into_iter()
+            .map(|(doc_path, _)| doc_path)
+            .zip(results)
+            .collect()
+    }
+}
+
+/// `remove_*_package`: delete a package, or one version of it
+///
+/// Packages above `async_threshold` documents are removed by a background
+/// job and return 202 + job ID; smaller packages are removed within the
+/// request.
+///
+/// # Errors
+///
+/// Returns [`ToolError::NotFound`] for a package or version that is not
+/// stored, or an error if a query fails.
+async fn remove_package(
+    ecosystem: &PackageEcosystem,
+    job_processor: &CrateJobProcessor,
+    db_pool: &DatabasePool,
+    async_threshold: i32,
+    arguments: &Value,
+) -> Result<String> {
+    let package_name = arguments
+        .get("name")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .ok_or_else(|| anyhow!("Missing required 'name' parameter"))?;
+    let version = arguments.get("version").and_then(Value::as_str);
+    let verify_cleanup = arguments
+        .get("verify_cleanup")
+        .and_then(Value::as_bool)
+        .unwrap_or(true);
+    let dry_run = arguments
+        .get("dry_run")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    let idempotency_key = idempotency_key_argument(arguments);
+
+    if package_name.is_empty() {
+        return Err(ToolError::invalid_input("Package name cannot be empty").into());
+    }
+    let package_name = (ecosystem.normalize_name)(package_name);
+    let package_name = package_name.as_str();
+
+    // A retried request gets the job it already created, even once the package is gone
+    if let Some(response) = replayed_job(
+        db_pool,
+        idempotency_key.filter(|_| !dry_run),
+        package_name,
+        ecosystem.remove_operation,
+    )
+    .await?
+    {
+        return Ok(response);
+    }
+
+    let doc_type = ecosystem.doc_type;
+    let versions =
+        PackageQueries::find_package_versions(db_pool.pool(), doc_type, package_name).await?;
+    if versions.is_empty() {
+        return Err(package_not_found(ecosystem, package_name).into());
+    }
+    let total_docs = match version {
+        Some(version) => {
+            let Some(info) = versions.iter().find(|info| info.version == version) else {
+                let stored: Vec<&str> = versions.iter().map(|v| v.version.as_str()).collect();
+                return Err(ToolError::NotFound {
+                    message: format!(
+                        "{} package '{}' has no version '{}' in the system. Stored versions: {}.",
+                        ecosystem.label,
+                        package_name,
+                        version,
+                        stored.join(", ")
+                    ),
+                    details: json!({
+                        "package_name": package_name,
+                        "version": version,
+                        "stored_versions": stored,
+                    }),
+                }
+                .into());
+            };
+            info.total_docs
+        }
+        None => versions.iter().map(|info| info.total_docs).sum(),
+    };
+    let label = package_label(package_name, version);
+
+    if dry_run {
+        let embeddings = PackageQueries::count_package_embeddings(
+            db_pool.pool(),
+            doc_type,
+            package_name,
+            version,
+        )
+        .await?;
+        return Ok(format!(
+            "🔍 **Dry Run for {} Package '{}'**\n\n\
+            Operation: permanently delete\n\
+            - {} documents would be affected\n\
+            - {} embeddings would be affected\n\n\
+            To execute this operation, run the command again with dry_run=false",
+            ecosystem.label, label, total_docs, embeddings
+        ));
+    }
+
+    // Large packages are deleted in batches on a background job
+    if total_docs > async_threshold {
+        let options = RemoveJobOptions {
+            verify_cleanup,
+            version: version.map(String::from),
+        };
+        let enqueued = job_processor
+            .enqueue_remove_package_job(ecosystem, package_name, &options, idempotency_key)
+            .await?;
+        if !enqueued.created {
+            return Ok(replayed_job_response(&enqueued.job));
+        }
+        let job_id = enqueued.job.id;
+        // The local dispatcher picks the job up from the notification
+        if crate::queue::use_redis_queue() {
+            let msg = crate::queue::RedisJobMessage::new(
+                job_id,
+                ecosystem.remove_job_type,
+                3,
+                json!({
+                    "package_name": package_name,
+                    "verify_cleanup": verify_cleanup,
+                    "version": version
+                }),
+            );
+            crate::queue::enqueue_job(&msg).await?;
+        }
+        return Ok(json!({
+            "status": "accepted",
+            "job_id": job_id.to_string(),
+            "documents": total_docs,
+            "message": format!("{} package '{}' removal job queued ({} documents). Use check_rust_status with job_id, which also tracks package jobs, to follow progress.", ecosystem.label, label, total_docs)
+        })
+        .to_string());
+    }
+
+    let deleted =
+        PackageQueries::delete_package_documents(db_pool.pool(), doc_type, package_name, version)
+            .await?;
+    query_cache().invalidate_doc_type(doc_type);
+    tracing::info!(
+        "Deleted {} package '{}': {} documents removed",
+        ecosystem.label,
+        label,
+        deleted
+    );
+
+    let message = format!(
+        "{} package '{}' removed successfully. Deleted {} documents and all associated embeddings.",
+        ecosystem.label, label, deleted
+    );
+    if !verify_cleanup {
+        return Ok(message);
+    }
+    match verify_complete_cleanup(ecosystem, db_pool, package_name, version).await {
+        Ok(verification) => Ok(format!("{message}\n\n{verification}")),
+        Err(e) => Ok(format!(
+            "{message}\n\nWarning: Cleanup verification failed: {e}"
+        )),
+    }
+}
+
+/// Run a `remove_*_package` job on a background task
+///
+/// Waits for a concurrency permit unless the caller already reserved one.
+pub(crate) fn spawn_package_removal(
+    ecosystem: &'static PackageEcosystem,
+    job_processor: CrateJobProcessor,
+    db_pool: DatabasePool,
+    job_id: Uuid,
+    package_name: String,
+    permit: Option<OwnedSemaphorePermit>,
+    options: RemoveJobOptions,
+) {
+    let description = format!("{} package removal of {}", ecosystem.label, package_name);
+    let processor = job_processor.clone();
+    spawn_package_job(job_processor, job_id, description, permit, async move {
+        process_package_removal(
+            ecosystem,
+            &processor,
+            &db_pool,
+            job_id,
+            &package_name,
+            &options,
+        )
+        .await
+    });
+}
+
+/// Delete a package's documents in batches, recording progress on the job
+///
+/// Each batch commits on its own, so an interrupted job resumes with the
+/// documents that are left. Cleanup verification runs at the end and is
+/// stored in the job's details.
+///
+/// # Errors
+///
+/// Returns an error if a batch or a job update fails.
+pub async fn process_package_removal(
+    ecosystem: &PackageEcosystem,
+    job_processor: &CrateJobProcessor,
+    db_pool: &DatabasePool,
+    job_id: Uuid,
+    package_name: &str,
+    options: &RemoveJobOptions,
+) -> Result<()> {
+    let doc_type = ecosystem.doc_type;
+    let version = options.version.as_deref();
+    job_processor
+        .update_job_status(job_id, JobStatus::Running, Some(0), None)
+        .await?;
+
+    let total =
+        PackageQueries::count_package_documents(db_pool.pool(), doc_type, package_name, version)
+            .await?;
+    let batch_size = crate_remove_batch_size();
+
+    let mut deleted: i64 = 0;
+    loop {
+        let removed = PackageQueries::delete_package_documents_batch(
+            db_pool.pool(),
+            doc_type,
+            package_name,
+            version,
+            batch_size,
+        )
+        .await?;
+        if removed == 0 {
+            break;
+        }
+        query_cache().invalidate_doc_type(doc_type);
+        deleted += i64::try_from(removed).unwrap_or(i64::MAX);
+        let progress = i32::try_from((deleted * 100 / total.max(1)).min(99)).unwrap_or(99);
+        CrateJobQueries::merge_job_details(
+            db_pool.pool(),
+            job_id,
+            &json!({"documents_deleted": deleted, "documents_total": total}),
+        )
+        .await?;
+        job_processor
+            .update_job_status(job_id, JobStatus::Running, Some(progress), None)
+            .await?;
+    }
+
+    tracing::info!(
+        "Removal job {} deleted {} package '{}': {} documents removed",
+        job_id,
+        ecosystem.label,
+        package_label(package_name, version),
+        deleted
+    );
+
+    if options.verify_cleanup {
+        let verification =
+            match verify_complete_cleanup(ecosystem, db_pool, package_name, version).await {
+                Ok(verification) => verification,
+                Err(e) => format!("Cleanup verification failed: {}", e),
+            };
+        CrateJobQueries::merge_job_details(
+            db_pool.pool(),
+            job_id,
+            &json!({"cleanup_verification": verification}),
+        )
+        .await?;
+    }
+
+    job_processor
+        .update_job_status(job_id, JobStatus::Completed, Some(100), None)
+        .await?;
+    Ok(())
+}
+
+/// How removal messages name a package, or one version of it (`zod@3.22.4`)
+fn package_label(package_name: &str, version: Option<&str>) -> String {
+    match version {
+        Some(version) => format!("{package_name}@{version}"),
+        None => package_name.to_string(),
+    }
+}
+
+/// Error for a package with no stored documents
+fn package_not_found(ecosystem: &PackageEcosystem, package_name: &str) -> ToolError {
+    ToolError::NotFound {
+        message: format!(
+            "{} package '{package_name}' not found in the system.",
+            ecosystem.label
+        ),
+        details: json!({ "package_name": package_name }),
+    }
+}
+
+/// Report whether any documents or embeddings of a package, or of one version, remain
+async fn verify_complete_cleanup(
+    ecosystem: &PackageEcosystem,
+    db_pool: &DatabasePool,
+    package_name: &str,
+    version: Option<&str>,
+) -> Result<String> {
+    let doc_type = ecosystem.doc_type;
+    let remaining_docs =
+        PackageQueries::count_package_documents(db_pool.pool(), doc_type, package_name, version)
+            .await?;
+    let remaining_embeddings =
+        PackageQueries::count_package_embeddings(db_pool.pool(), doc_type, package_name, version)
+            .await?;
+
+    if remaining_docs == 0 && remaining_embeddings == 0 {
+        Ok("✅ **Cleanup Verification: PASSED**\n\
+            - ✅ No remaining documents found\n\
+            - ✅ No remaining embeddings found"
+            .to_string())
+    } else {
+        Ok(format!(
+            "⚠️ **Cleanup Verification: INCOMPLETE**\n\
+            - ⚠️ {} documents still remain\n\
+            - ⚠️ {} embeddings still remain\n\
+            - 🔧 Manual cleanup may be required",
+            remaining_docs, remaining_embeddings
+        ))
+    }
+}
+
+/// `list_*_packages`: a page of the ecosystem's packages, one entry per ingested version
+///
+/// # Errors
+///
+/// Returns an error if the listing query fails.
+async fn list_packages(
+    ecosystem: &PackageEcosystem,
+    db_pool: &DatabasePool,
+    arguments: &Value,
+) -> Result<String> {
+    let page = arguments
+        .get("page")
+        .and_then(Value::as_i64)
+        .map(|p| i32::try_from(p).unwrap_or(i32::MAX));
+    let limit = arguments
+        .get("limit")
+        .and_then(Value::as_i64)
+        .map(|l| i32::try_from(l).unwrap_or(i32::MAX));
+    let name_pattern = arguments.get("name_pattern").and_then(Value::as_str);
+
+    let pagination = PaginationParams::new(page, limit);
+    let response = PackageQueries::list_packages(
+        db_pool.pool(),
+        ecosystem.doc_type,
+        &pagination,
+        name_pattern,
+    )
+    .await?;
+
+    let mut output = format!(
+        "{} Packages (Page {} of {}, {} total items):\n\n",
+        ecosystem.label, response.page, response.total_pages, response.total_items
+    );
+    for package in &response.items {
+        write_package(&mut output, package);
+    }
+
+    if response.has_previous || response.has_next {
+        output.push_str("Navigation:\n");
+        if response.has_previous {
+            let _ = writeln!(
+                &mut output,
+                "  ← Use page={} for previous",
+                response.page - 1
+            );
+        }
+        if response.has_next {
+            let _ = writeln!(&mut output, "  → Use page={} for next", response.page + 1);
+        }
+    }
+
+    Ok(output)
+}
+
+/// One package version of a package listing
+fn write_package(output: &mut String, package: &CrateInfo) {
+    let _ = writeln!(
+        output,
+        "📦 **{}** (v{})\n   Docs: {} | Tokens: {} | Embedded: {:.1}% | Updated: {}",
+        package.name,
+        package.version,
+        package.total_docs,
+        package.total_tokens,
+        package.embedding_coverage_pct,
+        package.last_updated.format("%Y-%m-%d %H:%M UTC")
+    );
+    if let Some(description) = &package.description {
+        let _ = writeln!(output, "   Description: {}", description);
+    }
+    output.push('\n');
+}
+
+/// How long `add_*_package` waits for the registry to confirm a package exists
+pub const REGISTRY_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Outcome of asking a registry about a package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryLookup {
+    /// The package has the requested version, resolved to this exact version
+    Found(String),
+    /// The registry has no such package
+    NotFound,
+    /// The package exists without the requested version; the reason says which it has
+    InvalidVersion(String),
+    /// The registry failed or did not answer in time, for the given reason
+    Unavailable(String),
+}
+
+/// Ecosystem-specific text of the package tool definitions
+#[derive(Debug)]
+pub struct PackageToolDocs {
+    /// Description of `add_*_package`
+    pub add: &'static str,
+    /// Description of the `name` argument of `add_*_package`
+    pub add_name: &'static str,
+    /// Description of the `version` argument of `add_*_package`
+    pub add_version: &'static str,
+    /// Description of `remove_*_package`
+    pub remove: &'static str,
+}
+
+/// Registry an ecosystem's packages are checked on and loaded from
+///
+/// Implemented by each ecosystem's loader; everything else the package tools
+/// do is shared.
+#[async_trait]
+pub trait PackageRegistry: Clone + Send + Sync + 'static {
+    /// One package version as the registry describes it
+    type Package: Send + Sync;
+
+    /// Ecosystem of the registry's packages
+    const ECOSYSTEM: &'static PackageEcosystem;
+    /// Descriptions of the ecosystem's tools
+    const DOCS: PackageToolDocs;
+
+    /// Loader `add_*_package` checks packages with
+    fn for_tools() -> Self;
+
+    /// Whether `name` is a valid package name on the registry
+    fn is_valid_name(name: &str) -> bool;
+
+    /// Ask the registry about `version` of a package, waiting at most `within`
+    async fn lookup(&self, name: &str, version: Option<&str>, within: Duration) -> RegistryLookup;
+
+    /// Load `version` of a package (latest by default)
+    async fn load(&self, name: &str, version: Option<&str>) -> Result<Self::Package>;
+
+    /// Documentation pages of a loaded package
+    async fn pages(&self, package: &Self::Package) -> Vec<DocPage>;
+
+    /// How an ingestion job stores `package`
+    fn release(package: &Self::Package) -> PackageRelease<'_>;
+}
+
+/// Add package tool - enqueues background job and returns 202 + job ID
+pub struct AddPackageTool<R: PackageRegistry> {
+    job_processor: CrateJobProcessor,
+    /// Checks requested packages on the registry before a job is queued
+    registry: R,
+    registry_timeout: Duration,
+    embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
+    db_pool: DatabasePool,
+}
+
+impl<R: PackageRegistry> AddPackageTool<R> {
+    /// Create a new add package tool
+    pub fn new(
+        db_pool: DatabasePool,
+        embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
+    ) -> Self {
+        Self {
+            job_processor: CrateJobProcessor::new(db_pool.clone()),
+            registry: R::for_tools(),
+            registry_timeout: REGISTRY_CHECK_TIMEOUT,
+            embedding_client,
+            db_pool,
+        }
+    }
+
+    /// Check packages through `loader`, waiting at most `timeout` for an answer
+    #[must_use]
+    pub fn with_registry(mut self, loader: R, timeout: Duration) -> Self {
+        self.registry = loader;
+        self.registry_timeout = timeout;
+        self
+    }
+}
+
+#[async_trait]
+impl<R: PackageRegistry> Tool for AddPackageTool<R> {
+    fn definition(&self) -> Value {
+        json!({
+            "name": R::ECOSYSTEM.add_operation,
+            "description": R::DOCS.add,
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": R::DOCS.add_name
+                    },
+                    "version": {
+                        "type": "string",
+                        "description": R::DOCS.add_version
+                    },
+                    "force_update": {
+                        "type": "boolean",
+                        "description": "Force update if the package already exists (optional, defaults to false)"
+                    },
+                    "idempotency_key": {
+                        "type": "string",
+                        "description": "Client-chosen key for safe retries: a repeated call with the same key within 24 hours returns the job the first call created, with its current status, instead of queueing another (optional)"
+                    }
+                },
+                "required": ["name"],
+                "additionalProperties": false
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        let ecosystem = R::ECOSYSTEM;
+        let requested_name = arguments
+            .get("name")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .ok_or_else(|| anyhow!("Missing required 'name' parameter"))?;
+        let version = arguments
+            .get("version")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|v| !v.is_empty());
+        let force_update = arguments
+            .get("force_update")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let idempotency_key = idempotency_key_argument(&arguments);
+
+        if requested_name.is_empty() {
+            return Err(ToolError::invalid_input("Package name cannot be empty").into());
+        }
+        if !R::is_valid_name(requested_name) {
+            return Err(ToolError::invalid_input(format!(
+                "'{requested_name}' is not a valid {} package name",
+                ecosystem.label
+            ))
+            .into());
+        }
+        let package_name = (ecosystem.normalize_name)(requested_name);
+        let package_name = package_name.as_str();
+
+        // A retried request gets the job it already created
+        if let Some(response) = replayed_job(
+            &self.db_pool,
+            idempotency_key,
+            package_name,
+            ecosystem.add_operation,
+        )
+        .await?
+        {
+            return Ok(response);
+        }
+
+        let (resolved_version, unverified) = match self
+            .registry
+            .lookup(package_name, version, self.registry_timeout)
+            .await
+        {
+            RegistryLookup::Found(resolved) => (Some(resolved), false),
+            RegistryLookup::NotFound => {
+                return Err(ToolError::invalid_input(format!(
+                    "{} package '{requested_name}' not found on {}",
+                    ecosystem.label, ecosystem.registry
+                ))
+                .into());
+            }
+            RegistryLookup::InvalidVersion(reason) => {
+                return Err(ToolError::invalid_input(reason).into());
+            }
+            RegistryLookup::Unavailable(reason) => {
+                tracing::warn!(
+                    "Could not check {} package '{}' on {}, queueing it unverified: {}",
+                    ecosystem.label,
+                    package_name,
+                    ecosystem.registry,
+                    reason
+                );
+                (version.map(String::from), true)
+            }
+        };
+
+        check_not_stored(
+            ecosystem,
+            &self.db_pool,
+            package_name,
+            version.and(resolved_version.as_deref()),
+            force_update,
+        )
+        .await?;
+
+        let options = PackageJobOptions {
+            version: resolved_version,
+            version_req: version.map(String::from),
+            force_update,
+            unverified,
+        };
+        queue_add_job(
+            ecosystem,
+            &self.job_processor,
+            &self.db_pool,
+            &self.embedding_client,
+            package_name,
+            &options,
+            idempotency_key,
+        )
+        .await
+    }
+}
+
+impl<R: PackageRegistry> AddPackageTool<R> {
+    /// Run package ingestion for an existing job on a background task
+    ///
+    /// Waits for a concurrency permit unless the caller already reserved one.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn spawn_ingestion(
+        job_processor: CrateJobProcessor,
+        loader: R,
+        embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
+        db_pool: DatabasePool,
+        job_id: Uuid,
+        package_name: String,
+        permit: Option<OwnedSemaphorePermit>,
+        options: PackageJobOptions,
+    ) {
+        let description = format!("{} package ingestion of {package_name}", R::ECOSYSTEM.label);
+        let processor = job_processor.clone();
+        spawn_package_job(job_processor, job_id, description, permit, async move {
+            Self::process_ingestion(
+                &processor,
+                &loader,
+                &embedding_client,
+                &db_pool,
+                job_id,
+                &package_name,
+                &options,
+            )
+            .await
+        });
+    }
+
+    /// Ingest a package's pages for an `add_*_package` job
+    ///
+    /// Pages already stored with the same content keep their embeddings. A
+    /// force update then drops the package's documents this job did not
+    /// write; a pinned version only replaces its own documents.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the registry cannot be read, the package has
+    /// nothing to ingest, or a database write fails.
+    pub async fn process_ingestion(
+        job_processor: &CrateJobProcessor,
+        loader: &R,
+        embedding_client: &Arc<dyn EmbeddingClient + Send + Sync>,
+        db_pool: &DatabasePool,
+        job_id: Uuid,
+        package_name: &str,
+        options: &PackageJobOptions,
+    ) -> Result<()> {
+        let result = Self::ingest(
+            job_processor,
+            loader,
+            embedding_client,
+            db_pool,
+            job_id,
+            package_name,
+            options,
+        )
+        .await;
+        // Even a failed run may have stored some pages
+        query_cache().invalidate_doc_type(R::ECOSYSTEM.doc_type);
+        result
+    }
+
+    async fn ingest(
+        job_processor: &CrateJobProcessor,
+        loader: &R,
+        embedding_client: &Arc<dyn EmbeddingClient + Send + Sync>,
+        db_pool: &DatabasePool,
+        job_id: Uuid,
+        package_name: &str,
+        options: &PackageJobOptions,
+    ) -> Result<()> {
+        let ecosystem = R::ECOSYSTEM;
+        job_processor
+            .update_job_status(job_id, JobStatus::Running, Some(0), None)
+            .await?;
+
+        let package = loader
+            .load(package_name, options.version.as_deref())
+            .await
+            .map_err(|e| anyhow!("Failed to load {} package: {}", ecosystem.label, e))?;
+        let pages = loader.pages(&package).await;
+        let release = R::release(&package);
+        if pages.is_empty() {
+            return Err(anyhow!(
+                "{} package '{}' {} publishes neither {} to ingest",
+                ecosystem.label,
+                release.name,
+                release.version,
+                ecosystem.page_kinds
+            ));
+        }
+
+        store_package(
+            ecosystem,
+            job_processor,
+            embedding_client,
+            db_pool,
+            job_id,
+            &release,
+            &pages,
+            options,
+        )
+        .await
+    }
+}
+
+/// Remove package tool
+///
+/// Packages above the async threshold are removed by a `remove_*_package`
+/// job and return 202 + job ID; smaller packages are removed within the
+/// request.
+pub struct RemovePackageTool<R: PackageRegistry> {
+    job_processor: CrateJobProcessor,
+    db_pool: DatabasePool,
+    async_threshold: i32,
+    registry: PhantomData<fn() -> R>,
+}
+
+impl<R: PackageRegistry> RemovePackageTool<R> {
+    /// Create a new remove package tool
+    pub fn new(db_pool: DatabasePool) -> Self {
+        Self {
+            job_processor: CrateJobProcessor::new(db_pool.clone()),
+            db_pool,
+            async_threshold: crate_remove_async_threshold(),
+            registry: PhantomData,
+        }
+    }
+
+    /// Remove packages with more than `threshold` documents as background jobs
+    #[must_use]
+    pub fn with_async_threshold(mut self, threshold: i32) -> Self {
+        self.async_threshold = threshold;
+        self
+    }
+
+    /// Delete a package's documents in batches for a `remove_*_package` job
+    ///
+    /// See [`process_package_removal`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a batch or a job update fails.
+    pub async fn process_removal(
+        job_processor: &CrateJobProcessor,
+        db_pool: &DatabasePool,
+        job_id: Uuid,
+        package_name: &str,
+        options: &RemoveJobOptions,
+    ) -> Result<()> {
+        process_package_removal(
+            R::ECOSYSTEM,
+            job_processor,
+            db_pool,
+            job_id,
+            package_name,
+            options,
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl<R: PackageRegistry> Tool for RemovePackageTool<R> {
+    fn definition(&self) -> Value {
+        json!({
+            "name": R::ECOSYSTEM.remove_operation,
+            "description": R::DOCS.remove,
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": format!("The {} package to remove", R::ECOSYSTEM.label)
+                    },
+                    "version": {
+                        "type": "string",
+                        "description": "Remove only this ingested version of the package, leaving its other versions in place (optional, defaults to every version)"
+                    },
+                    "verify_cleanup": {
+                        "type": "boolean",
+                        "description": "Verify no documents remain after deletion (default: true)"
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "Show what would be removed without actually deleting (default: false)"
+                    },
+                    "idempotency_key": {
+                        "type": "string",
+                        "description": "Client-chosen key for safe retries: a repeated call with the same key within 24 hours returns the job the first call created, with its current status, instead of queueing another. Applies to removals that run as a background job"
+                    }
+                },
+                "required": ["name"],
+                "additionalProperties": false
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        remove_package(
+            R::ECOSYSTEM,
+            &self.job_processor,
+            &self.db_pool,
+            self.async_threshold,
+            &arguments,
+        )
+        .await
+    }
+}
+
+/// List packages tool
+pub struct ListPackagesTool<R: PackageRegistry> {
+    db_pool: DatabasePool,
+    registry: PhantomData<fn() -> R>,
+}
+
+impl<R: PackageRegistry> ListPackagesTool<R> {
+    /// Create a new list packages tool
+    pub fn new(db_pool: DatabasePool) -> Self {
+        Self {
+            db_pool,
+            registry: PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<R: PackageRegistry> Tool for ListPackagesTool<R> {
+    fn definition(&self) -> Value {
+        json!({
+            "name": format!("list_{}_packages", R::ECOSYSTEM.doc_type),
+            "description": format!("List the {} packages in the documentation system with pagination and statistics, one entry per ingested version.", R::ECOSYSTEM.label),
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "page": {
+                        "type": "integer",
+                        "description": "Page number (default: 1)",
+                        "minimum": 1
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Items per page (default: 20, max: 100)",
+                        "minimum": 1,
+                        "maximum": 100
+                    },
+                    "name_pattern": {
+                        "type": "string",
+                        "description": "Search pattern for package names (case-insensitive)"
+                    }
+                },
+                "required": [],
+                "additionalProperties": false
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        list_packages(R::ECOSYSTEM, &self.db_pool, &arguments).await
+    }
+}
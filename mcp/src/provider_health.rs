@@ -0,0 +1,235 @@
+//! Reachability of the embedding and LLM providers
+//!
+//! Each provider is probed with the smallest real request it accepts: one
+//! embedded word, or a one-word prompt. A probe costs a little, so its result
+//! is kept for `PROVIDER_HEALTH_TTL_SECS` (default 300) and concurrent checks
+//! share one probe. `/health/ready` and `check_rust_status` report the
+//! results; readiness only depends on a provider marked required with
+//! `REQUIRE_EMBEDDINGS=true` or `REQUIRE_LLM=true`.
+
+use chrono::{DateTime, Utc};
+use discovery::{prompt_runner_from_env, ClaudeRunner, LlmUseCase, PromptRunner};
+use embed::EmbeddingClient;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// How long a probe result is reused
+const DEFAULT_TTL: Duration = Duration::from_secs(300);
+
+/// Longest wait for a probe to answer
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Outcome of a provider probe
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderStatus {
+    /// The probe succeeded
+    Ok,
+    /// The probe failed or timed out
+    Degraded,
+    /// No client is configured for the provider
+    Unconfigured,
+}
+
+/// Latest probe of one provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderReport {
+    pub status: ProviderStatus,
+    /// Whether readiness fails unless `status` is `ok`
+    pub required: bool,
+    /// `None` when no probe ran
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl ProviderReport {
+    /// Whether this provider lets the service count as ready
+    #[must_use]
+    pub fn ready(&self) -> bool {
+        !self.required || self.status == ProviderStatus::Ok
+    }
+}
+
+/// Client a [`ProviderHealth`] probes
+enum Target {
+    Embedding(Arc<dyn EmbeddingClient + Send + Sync>),
+    Llm(Arc<dyn PromptRunner>),
+    /// Why no client is available
+    Unconfigured(String),
+}
+
+/// Cached reachability check of one provider
+pub struct ProviderHealth {
+    target: Target,
+    required: bool,
+    ttl: Duration,
+    timeout: Duration,
+    last: Mutex<Option<(Instant, ProviderReport)>>,
+}
+
+impl ProviderHealth {
+    fn new(target: Target) -> Self {
+        let ttl = std::env::var("PROVIDER_HEALTH_TTL_SECS")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .map_or(DEFAULT_TTL, Duration::from_secs);
+        Self {
+            target,
+            required: false,
+            ttl,
+            timeout: DEFAULT_TIMEOUT,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Probe `client` by embedding one word
+    #[must_use]
+    pub fn embedding(client: Arc<dyn EmbeddingClient + Send + Sync>) -> Self {
+        Self::new(Target::Embedding(client))
+    }
+
+    /// Probe `runner` with a one-word prompt
+    #[must_use]
+    pub fn llm(runner: Arc<dyn PromptRunner>) -> Self {
+        Self::new(Target::Llm(runner))
+    }
+
+    /// A provider without a client, reported with `reason`
+    #[must_use]
+    pub fn unconfigured(reason: impl Into<String>) -> Self {
+        Self::new(Target::Unconfigured(reason.into()))
+    }
+
+    /// Fail readiness unless the provider answers
+    #[must_use]
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    /// Reuse a probe result for `ttl`
+    #[must_use]
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Give up on a probe after `timeout`
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// The cached report, probing the provider if it is older than the TTL
+    pub async fn check(&self) -> ProviderReport {
+        let mut last = self.last.lock().await;
+        if let Some((at, report)) = last.as_ref() {
+            if at.elapsed() < self.ttl {
+                return report.clone();
+            }
+        }
+        let report = self.probe().await;
+        *last = Some((Instant::now(), report.clone()));
+        report
+    }
+
+    async fn probe(&self) -> ProviderReport {
+        let start = Instant::now();
+        let result = match &self.target {
+            Target::Unconfigured(reason) => {
+                return ProviderReport {
+                    status: ProviderStatus::Unconfigured,
+                    required: self.required,
+                    latency_ms: None,
+                    error: Some(reason.clone()),
+                    checked_at: Utc::now(),
+                };
+            }
+            Target::Embedding(client) => {
+                tokio::time::timeout(self.timeout, client.health_check()).await
+            }
+            Target::Llm(runner) => tokio::time::timeout(self.timeout, runner.health_check()).await,
+        };
+        let latency_ms = u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX);
+        let error = match result {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(e.to_string()),
+            Err(_) => Some(format!(
+                "probe timed out after {}ms",
+                self.timeout.as_millis()
+            )),
+        };
+        ProviderReport {
+            status: if error.is_none() {
+                ProviderStatus::Ok
+            } else {
+                ProviderStatus::Degraded
+            },
+            required: self.required,
+            latency_ms: Some(latency_ms),
+            error,
+            checked_at: Utc::now(),
+        }
+    }
+}
+
+/// Health checks of the embedding and LLM providers
+pub struct ProviderChecks {
+    pub embedding: ProviderHealth,
+    pub llm: ProviderHealth,
+}
+
+impl ProviderChecks {
+    /// Checks of the clients the tools would build from the environment
+    ///
+    /// The embedding provider is unconfigured when its client cannot be
+    /// built, the LLM when the `answer_question` runner cannot be built or,
+    /// when that runner is the Claude CLI, the CLI is not installed.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let embedding = match embed::embedding_client_from_env() {
+            Ok(client) => ProviderHealth::embedding(client),
+            Err(e) => ProviderHealth::unconfigured(e.to_string()),
+        };
+        let cli = ClaudeRunner::new();
+        let uses_cli = LlmUseCase::Answer.uses_cli();
+        let llm = match prompt_runner_from_env(LlmUseCase::Answer) {
+            Err(e) => ProviderHealth::unconfigured(e.to_string()),
+            Ok(_) if uses_cli && !cli.is_installed() => ProviderHealth::unconfigured(format!(
+                "Claude CLI not found at '{}'",
+                cli.binary_path
+            )),
+            Ok(runner) => ProviderHealth::llm(runner),
+        };
+        Self {
+            embedding: embedding.required(env_flag("REQUIRE_EMBEDDINGS")),
+            llm: llm.required(env_flag("REQUIRE_LLM")),
+        }
+    }
+
+    /// Reports keyed by provider (`embeddings`, `llm`), probing both concurrently
+    pub async fn check(&self) -> BTreeMap<String, ProviderReport> {
+        let (embedding, llm) = tokio::join!(self.embedding.check(), self.llm.check());
+        BTreeMap::from([
+            ("embeddings".to_string(), embedding),
+            ("llm".to_string(), llm),
+        ])
+    }
+}
+
+fn env_flag(name: &str) -> bool {
+    std::env::var(name).is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
+
+static PROVIDER_CHECKS: LazyLock<ProviderChecks> = LazyLock::new(ProviderChecks::from_env);
+
+/// Process-wide provider checks, built from the environment on first use
+#[must_use]
+pub fn provider_checks() -> &'static ProviderChecks {
+    &PROVIDER_CHECKS
+}
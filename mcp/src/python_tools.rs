@@ -0,0 +1,102 @@
+//! Python package management tools for MCP
+//!
+//! `add_python_package`, `remove_python_package` and `list_python_packages`
+//! are the shared tools of [`crate::package_tools`] on top of PyPI.
+//! Documents are stored under doc type `python`: a package's README from
+//! PyPI and the pages of its readthedocs site, under the package's
+//! normalized (PEP 503) name.
+
+use crate::package_tools::{
+    AddPackageTool, ListPackagesTool, PackageEcosystem, PackageRegistry, PackageRelease,
+    PackageToolDocs, RegistryLookup, RemovePackageTool, PYTHON_PACKAGES,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use python_packages::{is_valid_package_name, PyPiLoader, PyPiLookup, PythonPackage};
+use rust_crates::{DocPage, RateLimiter};
+use serde_json::{json, Map, Value};
+use std::time::Duration;
+
+/// Add Python package tool - enqueues background job and returns 202 + job ID
+pub type AddPythonPackageTool = AddPackageTool<PyPiLoader>;
+
+/// Remove Python package tool
+pub type RemovePythonPackageTool = RemovePackageTool<PyPiLoader>;
+
+/// List Python packages tool
+pub type ListPythonPackagesTool = ListPackagesTool<PyPiLoader>;
+
+#[async_trait]
+impl PackageRegistry for PyPiLoader {
+    type Package = PythonPackage;
+
+    const ECOSYSTEM: &'static PackageEcosystem = &PYTHON_PACKAGES;
+    const DOCS: PackageToolDocs = PackageToolDocs {
+        add: "Add a Python package to the documentation system: its README is ingested from PyPI, along with the pages of its readthedocs site when the project links one. Returns immediately with a job ID; pass it as job_id to check_rust_status, which tracks package jobs as well as crate jobs. Packages are stored under their normalized name (PEP 503), so 'Flask_Login' and 'flask-login' are the same package. Errors carry a code in error.data.code: already_exists (-32005) when the package, or the pinned version, is already stored and force_update is not set (details.current_version); invalid_input (-32602) for an invalid name, a package PyPI does not know or a version it does not have. If PyPI cannot be reached within 5 seconds the job is queued anyway and the response says verified: false.",
+        add_name: "The Python package to add (e.g., 'requests', 'Django')",
+        add_version: "Exact release to fetch, e.g. '2.31.0' (optional, defaults to latest). A requested version is kept next to the package's other ingested versions; force_update then replaces only that version.",
+        remove: "Remove a Python package, or one ingested version of it, from the documentation system with cleanup verification. The name is matched after normalization (PEP 503). Large packages are removed by a background job whose job ID check_rust_status tracks like a crate job. Errors carry a code in error.data.code: not_found (-32004) when the package or the requested version is not stored (details.stored_versions); invalid_input (-32602) for an empty name.",
+    };
+
+    fn for_tools() -> Self {
+        Self::with_fetcher(Box::new(RateLimiter::with_limits(
+            Duration::from_secs(1),
+            2,
+        )))
+    }
+
+    fn is_valid_name(name: &str) -> bool {
+        is_valid_package_name(name)
+    }
+
+    async fn lookup(&self, name: &str, version: Option<&str>, within: Duration) -> RegistryLookup {
+        match self.lookup_package(name, version, within).await {
+            PyPiLookup::Found(package) => RegistryLookup::Found(package.version),
+            PyPiLookup::NotFound => RegistryLookup::NotFound,
+            PyPiLookup::InvalidVersion(reason) => RegistryLookup::InvalidVersion(reason),
+            PyPiLookup::Unavailable(reason) => RegistryLookup::Unavailable(reason),
+        }
+    }
+
+    async fn load(&self, name: &str, version: Option<&str>) -> Result<PythonPackage> {
+        self.load_package(name, version).await
+    }
+
+    async fn pages(&self, package: &PythonPackage) -> Vec<DocPage> {
+        self.load_package_docs(package).await
+    }
+
+    fn release(package: &PythonPackage) -> PackageRelease<'_> {
+        PackageRelease {
+            name: &package.name,
+            version: &package.version,
+            info: json!(package),
+            metadata: package_metadata(package),
+            readme_format: &package.readme_format,
+            page_format: "text",
+            details: json!({"documentation_url": package.readthedocs_url}),
+        }
+    }
+}
+
+/// PyPI metadata every page of a package version carries
+fn package_metadata(package: &PythonPackage) -> Map<String, Value> {
+    let mut metadata = Map::new();
+    metadata.insert(
+        "package_description".to_string(),
+        json!(package.description),
+    );
+    metadata.insert("homepage".to_string(), json!(package.homepage));
+    metadata.insert(
+        "documentation_url".to_string(),
+        json!(package.documentation_url),
+    );
+    metadata.insert("repository".to_string(), json!(package.repository));
+    metadata.insert("license".to_string(), json!(package.license));
+    metadata.insert("keywords".to_string(), json!(package.keywords));
+    metadata.insert(
+        "requires_python".to_string(),
+        json!(package.requires_python),
+    );
+    metadata
+}
@@ -0,0 +1,594 @@
+//! In-process cache of query tool results and query embeddings
+//!
+//! Agents often repeat a query within a session, and each repeat costs a
+//! search and an embedding request. Formatted results are kept for a short
+//! time, keyed by doc type, normalized query text and the remaining
+//! arguments (filters, limit, snippet options). Query embeddings are
+//! deterministic, so they are kept longer, keyed by model and exact text.
+//!
+//! Both caches are bounded by entry count and total bytes and drop the least
+//! recently used entry first. Code that writes a doc type's documents calls
+//! [`QueryCache::invalidate_doc_type`], so results never outlive an ingestion
+//! or removal. Writes from other processes (the job worker, the loader) reach
+//! the server through database triggers notifying [`QUERY_CACHE_CHANNEL`],
+//! which [`start_invalidation_listener`] subscribes to.
+
+use crate::embedding_cache::account_embeddings;
+use crate::metrics::metrics;
+use anyhow::Result;
+use db::queries::QUERY_CACHE_CHANNEL;
+use db::DatabasePool;
+use embed::EmbeddingClient;
+use serde_json::Value;
+use sqlx::postgres::PgListener;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Default lifetime of a cached query result
+pub const DEFAULT_RESULT_TTL: Duration = Duration::from_secs(60);
+
+/// Default lifetime of a cached query embedding
+pub const DEFAULT_EMBEDDING_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Arguments that change how a call runs but not what it returns
+const NON_KEY_ARGUMENTS: [&str; 2] = ["query", "cache"];
+
+/// Lifetime and size bounds of one cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheLimits {
+    /// How long an entry is served; zero disables the cache
+    pub ttl: Duration,
+    pub max_entries: usize,
+    pub max_bytes: usize,
+}
+
+impl CacheLimits {
+    /// Limits from `{prefix}_TTL_SECS`, `{prefix}_MAX_ENTRIES` and
+    /// `{prefix}_MAX_BYTES`, falling back to `defaults`
+    #[must_use]
+    pub fn from_env(prefix: &str, defaults: Self) -> Self {
+        let var = |name: &str| {
+            std::env::var(format!("{prefix}_{name}"))
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+        let size = |name: &str, default: usize| {
+            var(name)
+                .and_then(|n| usize::try_from(n).ok())
+                .unwrap_or(default)
+        };
+        Self {
+            ttl: var("TTL_SECS").map_or(defaults.ttl, Duration::from_secs),
+            max_entries: size("MAX_ENTRIES", defaults.max_entries),
+            max_bytes: size("MAX_BYTES", defaults.max_bytes),
+        }
+    }
+
+    const fn enabled(&self) -> bool {
+        !self.ttl.is_zero() && self.max_entries > 0 && self.max_bytes > 0
+    }
+}
+
+/// Key of a cached query result
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResultKey {
+    doc_type: String,
+    query: String,
+    arguments: String,
+}
+
+impl ResultKey {
+    /// Key for a query tool call on `doc_type`
+    ///
+    /// The query is lowercased with runs of whitespace collapsed; every other
+    /// argument except `cache` is part of the key.
+    #[must_use]
+    pub fn new(doc_type: &str, query: &str, arguments: &Value) -> Self {
+        let query = query
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+        // Sorted, so argument order does not matter
+        let rest: BTreeMap<&str, &Value> = arguments
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(key, _)| !NON_KEY_ARGUMENTS.contains(&key.as_str()))
+            .map(|(key, value)| (key.as_str(), value))
+            .collect();
+        Self {
+            doc_type: doc_type.to_string(),
+            query,
+            arguments: serde_json::to_string(&rest).unwrap_or_default(),
+        }
+    }
+
+    fn bytes(&self) -> usize {
+        self.doc_type.len() + self.query.len() + self.arguments.len()
+    }
+}
+
+/// Whether a query tool call may use the caches (`cache: false` bypasses them)
+#[must_use]
+pub fn cache_enabled(arguments: &Value) -> bool {
+    arguments
+        .get("cache")
+        .and_then(Value::as_bool)
+        .unwrap_or(true)
+}
+
+struct Entry<V> {
+    value: V,
+    bytes: usize,
+    expires_at: Instant,
+    last_used: u64,
+}
+
+/// Map evicting the least recently used entry once over its bounds
+struct Lru<K, V> {
+    limits: CacheLimits,
+    entries: HashMap<K, Entry<V>>,
+    bytes: usize,
+    clock: u64,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> Lru<K, V> {
+    fn new(limits: CacheLimits) -> Self {
+        Self {
+            limits,
+            entries: HashMap::new(),
+            bytes: 0,
+            clock: 0,
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<V> {
+        let now = Instant::now();
+        if self.entries.get(key)?.expires_at <= now {
+            self.remove(key);
+            return None;
+        }
+        self.clock += 1;
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = self.clock;
+        Some(entry.value.clone())
+    }
+
+    fn insert(&mut self, key: K, value: V, bytes: usize) {
+        if !self.limits.enabled() || bytes > self.limits.max_bytes {
+            return;
+        }
+        self.remove(&key);
+        let now = Instant::now();
+        self.retain(|_, entry| entry.expires_at > now);
+        while !self.entries.is_empty()
+            && (self.entries.len() >= self.limits.max_entries
+                || self.bytes + bytes > self.limits.max_bytes)
+        {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.remove(&oldest);
+        }
+        self.clock += 1;
+        self.bytes += bytes;
+        self.entries.insert(
+            key,
+            Entry {
+                value,
+                bytes,
+                expires_at: now + self.limits.ttl,
+                last_used: self.clock,
+            },
+        );
+    }
+
+    fn remove(&mut self, key: &K) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.bytes -= entry.bytes;
+        }
+    }
+
+    fn retain(&mut self, mut keep: impl FnMut(&K, &Entry<V>) -> bool) {
+        let mut freed = 0;
+        self.entries.retain(|key, entry| {
+            let kept = keep(key, entry);
+            if !kept {
+                freed += entry.bytes;
+            }
+            kept
+        });
+        self.bytes -= freed;
+    }
+}
+
+struct State {
+    results: Lru<ResultKey, String>,
+    /// Model, dimensions and text of the embedded query
+    embeddings: Lru<(String, u32, String), Vec<f32>>,
+    /// Bumped by every invalidation of a doc type
+    generations: HashMap<String, u64>,
+}
+
+/// Entry counts and sizes of the caches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryCacheStats {
+    pub results: usize,
+    pub result_bytes: usize,
+    pub embeddings: usize,
+    pub embedding_bytes: usize,
+}
+
+/// Query result and query embedding caches
+pub struct QueryCache {
+    state: Mutex<State>,
+}
+
+impl QueryCache {
+    /// Create caches with the given bounds
+    #[must_use]
+    pub fn new(results: CacheLimits, embeddings: CacheLimits) -> Self {
+        Self {
+            state: Mutex::new(State {
+                results: Lru::new(results),
+                embeddings: Lru::new(embeddings),
+                generations: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Caches configured from `QUERY_CACHE_*` and `QUERY_EMBEDDING_CACHE_*`
+    ///
+    /// Results default to 60 seconds, 1000 entries and 16 MiB; embeddings to
+    /// one hour, 5000 entries and 64 MiB.
+    #[must_use]
+    pub fn from_env() -> Self {
+        Self::new(
+            CacheLimits::from_env(
+                "QUERY_CACHE",
+                CacheLimits {
+                    ttl: DEFAULT_RESULT_TTL,
+                    max_entries: 1_000,
+                    max_bytes: 16 * 1024 * 1024,
+                },
+            ),
+            CacheLimits::from_env(
+                "QUERY_EMBEDDING_CACHE",
+                CacheLimits {
+                    ttl: DEFAULT_EMBEDDING_TTL,
+                    max_entries: 5_000,
+                    max_bytes: 64 * 1024 * 1024,
+                },
+            ),
+        )
+    }
+
+    fn generation(&self, doc_type: &str) -> u64 {
+        self.state
+            .lock()
+            .map(|state| state.generations.get(doc_type).copied().unwrap_or(0))
+            .unwrap_or(0)
+    }
+
+    /// Cached result for `key`, or the output of `search`, which is cached
+    ///
+    /// Errors are not cached. A result computed while the doc type was
+    /// invalidated is returned but not stored, since it may predate the write.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `search`.
+    pub async fn result<F>(&self, key: ResultKey, search: F) -> Result<String>
+    where
+        F: Future<Output = Result<String>>,
+    {
+        let cached = self
+            .state
+            .lock()
+            .ok()
+            .and_then(|mut state| state.results.get(&key));
+        metrics().record_query_cache(cached.is_some());
+        if let Some(result) = cached {
+            return Ok(result);
+        }
+
+        let generation = self.generation(&key.doc_type);
+        let result = search.await?;
+        if let Ok(mut state) = self.state.lock() {
+            let current = state.generations.get(&key.doc_type).copied().unwrap_or(0);
+            if current == generation {
+                let bytes = key.bytes() + result.len();
+                state.results.insert(key, result.clone(), bytes);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Embedding of `text` from `client`, served from the cache when present
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the embedding request fails.
+    pub async fn embedding(
+        &self,
+        client: &(dyn EmbeddingClient + Send + Sync),
+        text: &str,
+    ) -> Result<Vec<f32>> {
+        let config = client.embedding_config();
+        let key = (config.model, config.dimensions, text.to_string());
+        let cached = self
+            .state
+            .lock()
+            .ok()
+            .and_then(|mut state| state.embeddings.get(&key));
+        metrics().record_query_embedding_cache(cached.is_some());
+        if let Some(embedding) = cached {
+            return Ok(embedding);
+        }
+
+        let embedding = client.embed(text).await?;
+        account_embeddings(&key.0, 1, embed::token_count(text) as u64);
+        if let Ok(mut state) = self.state.lock() {
+            let bytes = key.0.len() + key.2.len() + std::mem::size_of_val(embedding.as_slice());
+            state.embeddings.insert(key, embedding.clone(), bytes);
+        }
+        Ok(embedding)
+    }
+
+    /// Drop every cached result for `doc_type`
+    ///
+    /// Called after documents of the doc type are inserted, removed or
+    /// restored. Query embeddings do not depend on stored documents and stay.
+    pub fn invalidate_doc_type(&self, doc_type: &str) {
+        if let Ok(mut state) = self.state.lock() {
+            *state.generations.entry(doc_type.to_string()).or_insert(0) += 1;
+            state.results.retain(|key, _| key.doc_type != doc_type);
+        }
+    }
+
+    /// Drop every cached result, for when invalidations may have been missed
+    pub fn invalidate_all(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            let doc_types: Vec<String> = state
+                .results
+                .entries
+                .keys()
+                .map(|key| key.doc_type.clone())
+                .collect();
+            for doc_type in doc_types {
+                state.generations.entry(doc_type).or_insert(0);
+            }
+            for generation in state.generations.values_mut() {
+                *generation += 1;
+            }
+            state.results.retain(|_, _| false);
+        }
+    }
+
+    /// Current entry counts and sizes
+    #[must_use]
+    pub fn stats(&self) -> QueryCacheStats {
+        self.state
+            .lock()
+            .map(|state| QueryCacheStats {
+                results: state.results.entries.len(),
+                result_bytes: state.results.bytes,
+                embeddings: state.embeddings.entries.len(),
+                embedding_bytes: state.embeddings.bytes,
+            })
+            .unwrap_or(QueryCacheStats {
+                results: 0,
+                result_bytes: 0,
+                embeddings: 0,
+                embedding_bytes: 0,
+            })
+    }
+}
+
+static QUERY_CACHE: LazyLock<QueryCache> = LazyLock::new(QueryCache::from_env);
+
+/// Process-wide query cache used by the query tools
+#[must_use]
+pub fn query_cache() -> &'static QueryCache {
+    &QUERY_CACHE
+}
+
+/// Start the task invalidating [`query_cache`] on [`QUERY_CACHE_CHANNEL`]
+/// notifications, whose payload is the changed doc type
+///
+/// Notifications sent while the listener is disconnected are lost, so the
+/// whole cache is dropped whenever it (re)connects.
+pub fn start_invalidation_listener(db_pool: DatabasePool) {
+    tokio::spawn(async move {
+        loop {
+            let mut listener = match PgListener::connect_with(db_pool.pool()).await {
+                Ok(listener) => listener,
+                Err(e) => {
+                    warn!("Failed to connect query cache listener: {}", e);
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    continue;
+                }
+            };
+            if let Err(e) = listener.listen(QUERY_CACHE_CHANNEL).await {
+                warn!("Failed to LISTEN on {}: {}", QUERY_CACHE_CHANNEL, e);
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+            query_cache().invalidate_all();
+
+            loop {
+                match listener.try_recv().await {
+                    Ok(Some(notification)) => {
+                        query_cache().invalidate_doc_type(notification.payload());
+                    }
+                    // The next call reconnects and listens again
+                    Ok(None) => {
+                        debug!("Query cache listener connection lost; reconnecting");
+                        query_cache().invalidate_all();
+                    }
+                    Err(e) => {
+                        warn!("Query cache listener failed: {}", e);
+                        break;
+                    }
+                }
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn limits(max_entries: usize, max_bytes: usize) -> CacheLimits {
+        CacheLimits {
+            ttl: Duration::from_secs(60),
+            max_entries,
+            max_bytes,
+        }
+    }
+
+    #[test]
+    fn test_result_key_normalizes_query_and_argument_order() {
+        let a = ResultKey::new(
+            "rust",
+            "  Spawn   Blocking ",
+            &json!({"query": "x", "limit": 5, "crate_name": "tokio", "cache": true}),
+        );
+        let b = ResultKey::new(
+            "rust",
+            "spawn blocking",
+            &json!({"crate_name": "tokio", "limit": 5}),
+        );
+        assert_eq!(a, b);
+        assert_ne!(
+            a,
+            ResultKey::new("rust", "spawn blocking", &json!({"limit": 5}))
+        );
+        assert_ne!(
+            a,
+            ResultKey::new(
+                "solana",
+                "spawn blocking",
+                &json!({"crate_name": "tokio", "limit": 5})
+            )
+        );
+    }
+
+    #[test]
+    fn test_lru_bounds_entries_and_bytes() {
+        let mut lru = Lru::new(limits(2, 100));
+        lru.insert("a", 1, 10);
+        lru.insert("b", 2, 10);
+        // Touch `a`, so `b` is the least recently used
+        assert_eq!(lru.get(&"a"), Some(1));
+        lru.insert("c", 3, 10);
+        assert_eq!(lru.get(&"b"), None);
+        assert_eq!(lru.entries.len(), 2);
+
+        // Byte bound: a 95-byte entry pushes out both 10-byte ones
+        lru.insert("d", 4, 95);
+        assert_eq!(lru.entries.len(), 1);
+        assert_eq!(lru.bytes, 95);
+        // Entries larger than the whole cache are not stored
+        lru.insert("e", 5, 101);
+        assert_eq!(lru.get(&"e"), None);
+        assert_eq!(lru.get(&"d"), Some(4));
+    }
+
+    #[test]
+    fn test_expired_entries_are_not_served() {
+        let mut lru = Lru::new(CacheLimits {
+            ttl: Duration::from_millis(1),
+            ..limits(10, 100)
+        });
+        lru.insert("a", 1, 10);
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(lru.get(&"a"), None);
+        assert_eq!(lru.bytes, 0);
+    }
+
+    #[tokio::test]
+    async fn test_invalidation_drops_doc_type_results() {
+        let cache = QueryCache::new(limits(10, 10_000), limits(10, 10_000));
+        let rust = ResultKey::new("rust", "spawn", &json!({}));
+        let solana = ResultKey::new("solana", "spawn", &json!({}));
+        cache
+            .result(rust.clone(), async { Ok("rust v1".to_string()) })
+            .await
+            .unwrap();
+        cache
+            .result(solana.clone(), async { Ok("solana v1".to_string()) })
+            .await
+            .unwrap();
+
+        cache.invalidate_doc_type("rust");
+        let rust_again = cache
+            .result(rust, async { Ok("rust v2".to_string()) })
+            .await
+            .unwrap();
+        assert_eq!(rust_again, "rust v2");
+        let solana_again = cache
+            .result(solana, async { Ok("solana v2".to_string()) })
+            .await
+            .unwrap();
+        assert_eq!(solana_again, "solana v1");
+    }
+
+    #[tokio::test]
+    async fn test_result_computed_across_invalidation_is_not_stored() {
+        let cache = QueryCache::new(limits(10, 10_000), limits(10, 10_000));
+        let key = ResultKey::new("rust", "spawn", &json!({}));
+        let stale = cache
+            .result(key.clone(), async {
+                cache.invalidate_doc_type("rust");
+                Ok("before ingestion".to_string())
+            })
+            .await
+            .unwrap();
+        assert_eq!(stale, "before ingestion");
+        assert_eq!(cache.stats().results, 0);
+
+        // Errors are not cached either
+        assert!(cache
+            .result(key.clone(), async { Err(anyhow::anyhow!("db down")) })
+            .await
+            .is_err());
+        assert_eq!(cache.stats().results, 0);
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_all_drops_every_doc_type() {
+        let cache = QueryCache::new(limits(10, 10_000), limits(10, 10_000));
+        let rust = ResultKey::new("rust", "spawn", &json!({}));
+        let solana = ResultKey::new("solana", "spawn", &json!({}));
+        for key in [&rust, &solana] {
+            cache
+                .result(key.clone(), async { Ok("v1".to_string()) })
+                .await
+                .unwrap();
+        }
+        let generation = cache.generation("rust");
+
+        cache.invalidate_all();
+        assert_eq!(cache.stats().results, 0);
+        assert!(cache.generation("rust") > generation);
+        let solana_again = cache
+            .result(solana, async { Ok("v2".to_string()) })
+            .await
+            .unwrap();
+        assert_eq!(solana_again, "v2");
+    }
+}
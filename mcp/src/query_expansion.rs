@@ -0,0 +1,311 @@
+//! Query expansion with synonyms and Rust identifier aliasing
+//!
+//! Questions are often phrased differently from the documentation that
+//! answers them: "dictionary" for `HashMap`, "read to string" for
+//! `read_to_string` or `ReadToString`. When a query tool is called with
+//! `expand`, the query is rewritten into alternative phrasings that full-text
+//! search ORs with the original; the vector search keeps the original text.
+//!
+//! Phrasings come from, in order:
+//!
+//! - the synonym table: a built-in set of cross-language aliases, extended or
+//!   overridden by the JSON object in `QUERY_SYNONYMS_PATH`
+//!   (`{"term": ["expansion", ...]}`)
+//! - identifiers in the query: `snake_case` and `CamelCase` words are split
+//!   into their parts and joined into one lowercase word
+//! - runs of two to four query words joined into one identifier-like word
+//!
+//! At most `QUERY_EXPANSION_MAX` (default 8) phrasings are added.
+
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::LazyLock;
+use tracing::warn;
+
+/// Default cap on the phrasings added to a query
+pub const DEFAULT_MAX_EXPANSIONS: usize = 8;
+
+/// Longest run of query words joined into one word
+const MAX_JOINED_WORDS: usize = 4;
+
+/// Built-in synonyms: terms from other languages and their Rust names
+const BUILTIN_SYNONYMS: &[(&str, &[&str])] = &[
+    ("dictionary", &["hashmap", "btreemap"]),
+    ("dict", &["hashmap", "btreemap"]),
+    ("list", &["vec"]),
+    ("array", &["vec", "slice"]),
+    ("vector", &["vec"]),
+    ("interface", &["trait"]),
+    ("class", &["struct"]),
+    ("lambda", &["closure"]),
+    ("exception", &["error", "panic"]),
+    ("null", &["option", "none"]),
+    ("nullable", &["option"]),
+    ("optional", &["option"]),
+    ("promise", &["future"]),
+    ("coroutine", &["async"]),
+    ("lock", &["mutex", "rwlock"]),
+    ("refcount", &["rc", "arc"]),
+    ("destructor", &["drop"]),
+    ("constructor", &["new", "default"]),
+    ("generics", &["generic"]),
+    ("serialization", &["serde"]),
+];
+
+/// A query rewritten into alternative phrasings
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryExpansion {
+    /// Whole-query phrasings to OR with the original query
+    pub phrasings: Vec<String>,
+    /// Terms substituted into the phrasings, in order of first use
+    pub terms: Vec<String>,
+}
+
+/// Rewrites queries using a synonym table and identifier aliasing
+#[derive(Debug, Clone)]
+pub struct QueryExpander {
+    synonyms: HashMap<String, Vec<String>>,
+    max_expansions: usize,
+}
+
+impl Default for QueryExpander {
+    fn default() -> Self {
+        Self::new(builtin_synonyms(), DEFAULT_MAX_EXPANSIONS)
+    }
+}
+
+impl QueryExpander {
+    /// Create an expander from a synonym table, adding at most `max_expansions` phrasings
+    ///
+    /// Terms are matched case-insensitively.
+    #[must_use]
+    pub fn new(synonyms: HashMap<String, Vec<String>>, max_expansions: usize) -> Self {
+        let synonyms = synonyms
+            .into_iter()
+            .map(|(term, expansions)| {
+                let expansions = expansions
+                    .into_iter()
+                    .map(|e| e.trim().to_string())
+                    .filter(|e| !e.is_empty())
+                    .collect();
+                (term.trim().to_lowercase(), expansions)
+            })
+            .collect();
+        Self {
+            synonyms,
+            max_expansions,
+        }
+    }
+
+    /// Expander configured by `QUERY_SYNONYMS_PATH` and `QUERY_EXPANSION_MAX`
+    ///
+    /// A synonym file that cannot be read is logged and the built-in table
+    /// is used alone.
+    #[must_use]
+    pub fn from_env() -> Self {
+        let mut synonyms = builtin_synonyms();
+        if let Some(path) = std::env::var("QUERY_SYNONYMS_PATH")
+            .ok()
+            .filter(|p| !p.trim().is_empty())
+        {
+            match load_synonyms(Path::new(path.trim())) {
+                Ok(table) => synonyms.extend(
+                    table
+                        .into_iter()
+                        .map(|(term, expansions)| (term.trim().to_lowercase(), expansions)),
+                ),
+                Err(e) => warn!("Ignoring query synonyms: {e:#}"),
+            }
+        }
+        let max_expansions = std::env::var("QUERY_EXPANSION_MAX")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_MAX_EXPANSIONS);
+        Self::new(synonyms, max_expansions)
+    }
+
+    /// Alternative phrasings of `query`, at most `max_expansions` of them
+    #[must_use]
+    pub fn expand(&self, query: &str) -> QueryExpansion {
+        let words: Vec<&str> = query.split_whitespace().collect();
+        let mut expansion = QueryExpansion::default();
+        let mut add = |start: usize, end: usize, term: &str| {
+            let phrasing = words[..start]
+                .iter()
+                .copied()
+                .chain(std::iter::once(term))
+                .chain(words[end..].iter().copied())
+                .collect::<Vec<_>>()
+                .join(" ");
+            if phrasing.eq_ignore_ascii_case(query.trim())
+                || expansion.phrasings.len() >= self.max_expansions
+                || expansion.phrasings.contains(&phrasing)
+            {
+                return;
+            }
+            expansion.phrasings.push(phrasing);
+            if !expansion.terms.iter().any(|t| t == term) {
+                expansion.terms.push(term.to_string());
+            }
+        };
+
+        for (i, word) in words.iter().enumerate() {
+            let bare = bare_word(word);
+            if let Some(synonyms) = self.synonyms.get(&bare.to_lowercase()) {
+                for synonym in synonyms {
+                    add(i, i + 1, synonym);
+                }
+            }
+            // Paths are split at their last segment: `fs::read_to_string`
+            let parts = identifier_parts(bare.rsplit("::").next().unwrap_or(bare));
+            if parts.len() > 1 {
+                add(i, i + 1, &parts.join(" "));
+                add(i, i + 1, &parts.concat());
+            }
+        }
+        // Longest runs first, so a whole identifier beats its pieces
+        for len in (2..=MAX_JOINED_WORDS.min(words.len())).rev() {
+            for start in 0..=words.len() - len {
+                let run = &words[start..start + len];
+                if run
+                    .iter()
+                    .all(|w| !w.is_empty() && w.chars().all(char::is_alphanumeric))
+                {
+                    add(start, start + len, &run.concat().to_lowercase());
+                }
+            }
+        }
+        expansion
+    }
+}
+
+/// Read a synonym table: a JSON object of terms to lists of expansions
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or is not such an object.
+pub fn load_synonyms(path: &Path) -> Result<HashMap<String, Vec<String>>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read synonyms from {}", path.display()))?;
+    serde_json::from_str(&text).map_err(|e| {
+        anyhow!(
+            "{} must map terms to lists of expansions: {e}",
+            path.display()
+        )
+    })
+}
+
+fn builtin_synonyms() -> HashMap<String, Vec<String>> {
+    BUILTIN_SYNONYMS
+        .iter()
+        .map(|(term, expansions)| {
+            (
+                (*term).to_string(),
+                expansions.iter().map(ToString::to_string).collect(),
+            )
+        })
+        .collect()
+}
+
+/// `word` without surrounding punctuation other than underscores
+fn bare_word(word: &str) -> &str {
+    word.trim_matches(|c: char| !c.is_alphanumeric() && c != '_')
+}
+
+/// Lowercase parts of a `snake_case` or `CamelCase` identifier
+///
+/// Returns a single part for plain words; acronyms stay together
+/// (`HTTPClient` -> `http`, `client`).
+fn identifier_parts(word: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    for segment in word.split('_').filter(|s| !s.is_empty()) {
+        let chars: Vec<char> = segment.chars().collect();
+        let mut current = String::new();
+        for (i, &c) in chars.iter().enumerate() {
+            let boundary = i > 0
+                && c.is_uppercase()
+                && (chars[i - 1].is_lowercase()
+                    || chars[i - 1].is_numeric()
+                    || chars.get(i + 1).is_some_and(|next| next.is_lowercase()));
+            if boundary && !current.is_empty() {
+                parts.push(std::mem::take(&mut current).to_lowercase());
+            }
+            current.push(c);
+        }
+        if !current.is_empty() {
+            parts.push(current.to_lowercase());
+        }
+    }
+    parts
+}
+
+static QUERY_EXPANDER: LazyLock<QueryExpander> = LazyLock::new(QueryExpander::from_env);
+
+/// Process-wide query expander used by the query tools
+#[must_use]
+pub fn query_expander() -> &'static QueryExpander {
+    &QUERY_EXPANDER
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expander(synonyms: &[(&str, &[&str])], max: usize) -> QueryExpander {
+        QueryExpander::new(
+            synonyms
+                .iter()
+                .map(|(t, e)| {
+                    (
+                        (*t).to_string(),
+                        e.iter().map(ToString::to_string).collect(),
+                    )
+                })
+                .collect(),
+            max,
+        )
+    }
+
+    #[test]
+    fn test_identifier_parts() {
+        assert_eq!(identifier_parts("read_to_string"), ["read", "to", "string"]);
+        assert_eq!(identifier_parts("ReadToString"), ["read", "to", "string"]);
+        assert_eq!(identifier_parts("HTTPClient"), ["http", "client"]);
+        assert_eq!(identifier_parts("spawn"), ["spawn"]);
+    }
+
+    #[test]
+    fn test_synonyms_replace_their_term() {
+        let expansion =
+            expander(&[("Dictionary", &["hashmap", "btreemap"])], 8).expand("ordered dictionary");
+        assert_eq!(
+            expansion.phrasings,
+            ["ordered hashmap", "ordered btreemap", "ordereddictionary"]
+        );
+        assert_eq!(
+            expansion.terms,
+            ["hashmap", "btreemap", "ordereddictionary"]
+        );
+    }
+
+    #[test]
+    fn test_identifiers_are_split_and_joined() {
+        let expansion = expander(&[], 8).expand("fs::read_to_string");
+        assert_eq!(expansion.phrasings, ["read to string", "readtostring"]);
+
+        let expansion = expander(&[], 8).expand("read to string");
+        assert_eq!(
+            expansion.phrasings,
+            ["readtostring", "readto string", "read tostring"]
+        );
+    }
+
+    #[test]
+    fn test_expansions_are_capped() {
+        let expansion =
+            expander(&[("map", &["hashmap", "btreemap", "indexmap"])], 2).expand("map keys");
+        assert_eq!(expansion.phrasings, ["hashmap keys", "btreemap keys"]);
+        assert!(expander(&[], 0).expand("ReadToString").phrasings.is_empty());
+    }
+}
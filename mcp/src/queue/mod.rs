@@ -65,3 +65,14 @@ pub async fn enqueue_job(msg: &RedisJobMessage) -> anyhow::Result<()> {
     let _: i64 = con.lpush(key, val).await?;
     Ok(())
 }
+
+/// Check that the Redis queue answers `PING`
+///
+/// # Errors
+/// Returns an error if Redis cannot be reached or does not answer.
+pub async fn ping() -> anyhow::Result<()> {
+    let client = redis::Client::open(redis_url_from_env())?;
+    let mut con = client.get_multiplexed_async_connection().await?;
+    let _: String = redis::cmd("PING").query_async(&mut con).await?;
+    Ok(())
+}
@@ -0,0 +1,242 @@
+//! Per-client rate limiting for the MCP transport
+//!
+//! A token bucket is kept per client key and request class in a sharded
+//! in-memory map. Idle buckets are dropped by the transport's periodic
+//! session cleanup task, so no external store is needed.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Number of independently locked shards
+const SHARD_COUNT: usize = 16;
+
+/// Token-bucket limits for one class of requests
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Sustained requests allowed per minute (0 disables limiting)
+    pub requests_per_minute: u32,
+    /// Maximum number of requests allowed in a burst
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Create a limit from a per-minute rate and burst size
+    #[must_use]
+    pub const fn new(requests_per_minute: u32, burst: u32) -> Self {
+        Self {
+            requests_per_minute,
+            burst,
+        }
+    }
+
+    /// Whether this limit is enforced
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.requests_per_minute > 0 && self.burst > 0
+    }
+
+    fn tokens_per_second(self) -> f64 {
+        f64::from(self.requests_per_minute) / 60.0
+    }
+}
+
+/// Request classes that are limited independently
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RequestClass {
+    /// JSON-RPC POST (and session DELETE) requests
+    Post,
+    /// SSE stream GET requests
+    Sse,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+type Shard = Mutex<HashMap<(RequestClass, String), Bucket>>;
+
+/// Sharded token-bucket rate limiter keyed by client
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    post: RateLimit,
+    sse: RateLimit,
+    shards: Arc<Vec<Shard>>,
+}
+
+impl RateLimiter {
+    /// Create a limiter with separate limits for POST and SSE requests
+    #[must_use]
+    pub fn new(post: RateLimit, sse: RateLimit) -> Self {
+        let shards = (0..SHARD_COUNT)
+            .map(|_| Mutex::new(HashMap::new()))
+            .collect();
+        Self {
+            post,
+            sse,
+            shards: Arc::new(shards),
+        }
+    }
+
+    const fn limit_for(&self, class: RequestClass) -> RateLimit {
+        match class {
+            RequestClass::Post => self.post,
+            RequestClass::Sse => self.sse,
+        }
+    }
+
+    fn shard_for(&self, key: &(RequestClass, String)) -> &Shard {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        // Truncation is fine: only the low bits select a shard
+        #[allow(clippy::cast_possible_truncation)]
+        let index = hasher.finish() as usize % self.shards.len();
+        &self.shards[index]
+    }
+
+    /// Take one token for `client_key`
+    ///
+    /// # Errors
+    ///
+    /// Returns the time until a token becomes available when the bucket is empty
+    pub fn check(&self, class: RequestClass, client_key: &str) -> Result<(), Duration> {
+        self.check_at(class, client_key, Instant::now())
+    }
+
+    fn check_at(
+        &self,
+        class: RequestClass,
+        client_key: &str,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let limit = self.limit_for(class);
+        if !limit.is_enabled() {
+            return Ok(());
+        }
+
+        let key = (class, client_key.to_string());
+        let Ok(mut shard) = self.shard_for(&key).lock() else {
+            // A poisoned shard should not take the endpoint down
+            return Ok(());
+        };
+
+        let capacity = f64::from(limit.burst);
+        let rate = limit.tokens_per_second();
+        let bucket = shard.entry(key).or_insert(Bucket {
+            tokens: capacity,
+            last_refill: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.last_refill);
+        bucket.tokens = elapsed
+            .as_secs_f64()
+            .mul_add(rate, bucket.tokens)
+            .min(capacity);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+
+    /// Drop buckets that have refilled completely and carry no state
+    ///
+    /// Returns the number of buckets removed.
+    pub fn cleanup_idle(&self) -> usize {
+        self.cleanup_idle_at(Instant::now())
+    }
+
+    fn cleanup_idle_at(&self, now: Instant) -> usize {
+        let mut removed = 0;
+        for shard in self.shards.iter() {
+            let Ok(mut buckets) = shard.lock() else {
+                continue;
+            };
+            let before = buckets.len();
+            buckets.retain(|(class, _), bucket| {
+                let limit = self.limit_for(*class);
+                let refill = Duration::from_secs_f64(
+                    f64::from(limit.burst) / limit.tokens_per_second().max(f64::EPSILON),
+                );
+                now.saturating_duration_since(bucket.last_refill) < refill
+            });
+            removed += before - buckets.len();
+        }
+        removed
+    }
+
+    /// Number of tracked buckets
+    #[must_use]
+    pub fn tracked_clients(&self) -> usize {
+        self.shards
+            .iter()
+            .filter_map(|shard| shard.lock().ok().map(|buckets| buckets.len()))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_then_throttle() {
+        let limiter = RateLimiter::new(RateLimit::new(60, 3), RateLimit::new(60, 1));
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check_at(RequestClass::Post, "a", now).is_ok());
+        }
+        let retry = limiter.check_at(RequestClass::Post, "a", now).unwrap_err();
+        assert!(retry <= Duration::from_secs(1));
+
+        // Other clients are unaffected
+        assert!(limiter.check_at(RequestClass::Post, "b", now).is_ok());
+
+        // One token refills per second at 60 rpm
+        let later = now + Duration::from_secs(1);
+        assert!(limiter.check_at(RequestClass::Post, "a", later).is_ok());
+        assert!(limiter.check_at(RequestClass::Post, "a", later).is_err());
+    }
+
+    #[test]
+    fn test_sse_counted_separately() {
+        let limiter = RateLimiter::new(RateLimit::new(60, 1), RateLimit::new(60, 1));
+        let now = Instant::now();
+
+        assert!(limiter.check_at(RequestClass::Post, "a", now).is_ok());
+        assert!(limiter.check_at(RequestClass::Post, "a", now).is_err());
+        assert!(limiter.check_at(RequestClass::Sse, "a", now).is_ok());
+        assert!(limiter.check_at(RequestClass::Sse, "a", now).is_err());
+    }
+
+    #[test]
+    fn test_disabled_limit() {
+        let limiter = RateLimiter::new(RateLimit::new(0, 0), RateLimit::new(0, 0));
+        for _ in 0..1000 {
+            assert!(limiter.check(RequestClass::Post, "a").is_ok());
+        }
+        assert_eq!(limiter.tracked_clients(), 0);
+    }
+
+    #[test]
+    fn test_cleanup_idle() {
+        let limiter = RateLimiter::new(RateLimit::new(60, 2), RateLimit::new(60, 2));
+        let now = Instant::now();
+
+        assert!(limiter.check_at(RequestClass::Post, "a", now).is_ok());
+        assert!(limiter.check_at(RequestClass::Sse, "b", now).is_ok());
+        assert_eq!(limiter.tracked_clients(), 2);
+
+        assert_eq!(limiter.cleanup_idle_at(now + Duration::from_secs(1)), 0);
+        assert_eq!(limiter.cleanup_idle_at(now + Duration::from_secs(3)), 2);
+        assert_eq!(limiter.tracked_clients(), 0);
+    }
+}
@@ -0,0 +1,319 @@
+//! Optional reranking stage for query tools
+//!
+//! FTS and vector ranking often leave the best page at rank 5–10. When a
+//! query tool is called with `rerank: true` it fetches a wider candidate set,
+//! scores every (query, passage) pair through a [`Reranker`] and reorders the
+//! candidates before the caller's limit is applied. A reranker that errors or
+//! exceeds the timeout leaves the search order untouched; either event is
+//! counted in the server metrics.
+
+use crate::metrics::{account_llm_run, metrics};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use db::chunks::ChunkedResult;
+use discovery::{result_text, PromptRunner};
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info_span, warn, Instrument};
+
+/// Candidates scored when `RERANK_CANDIDATES` is not set
+pub const DEFAULT_RERANK_CANDIDATES: usize = 30;
+
+/// Reranking time limit when `RERANK_TIMEOUT_MS` is not set
+pub const DEFAULT_RERANK_TIMEOUT_MS: u64 = 5_000;
+
+/// Characters of each passage shown to a prompt-based reranker
+const PASSAGE_CHARS: usize = 1_000;
+
+/// Scores how well passages answer a query
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    /// One score per passage, in passage order; higher is more relevant
+    async fn score(&self, query: &str, passages: &[String]) -> Result<Vec<f64>>;
+}
+
+/// Reranking settings
+#[derive(Debug, Clone)]
+pub struct RerankConfig {
+    /// Search results fetched and scored before the limit is applied
+    pub candidates: usize,
+    /// Limit on one reranking call
+    pub timeout: Duration,
+}
+
+impl RerankConfig {
+    /// Read `RERANK_CANDIDATES` and `RERANK_TIMEOUT_MS`, falling back to the defaults
+    #[must_use]
+    pub fn from_env() -> Self {
+        let env_u64 = |key: &str| {
+            std::env::var(key)
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .filter(|&n| n > 0)
+        };
+        Self {
+            candidates: env_u64("RERANK_CANDIDATES")
+                .and_then(|n| usize::try_from(n).ok())
+                .unwrap_or(DEFAULT_RERANK_CANDIDATES),
+            timeout: Duration::from_millis(
+                env_u64("RERANK_TIMEOUT_MS").unwrap_or(DEFAULT_RERANK_TIMEOUT_MS),
+            ),
+        }
+    }
+}
+
+impl Default for RerankConfig {
+    fn default() -> Self {
+        Self {
+            candidates: DEFAULT_RERANK_CANDIDATES,
+            timeout: Duration::from_millis(DEFAULT_RERANK_TIMEOUT_MS),
+        }
+    }
+}
+
+/// What happened to one reranking request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RerankOutcome {
+    /// Candidates were reordered by reranker score
+    Reranked,
+    /// The reranker did not answer in time; search order kept
+    TimedOut,
+    /// The reranker failed; search order kept
+    Failed,
+}
+
+impl RerankOutcome {
+    /// Note for the tool response when the search order was kept
+    #[must_use]
+    pub const fn fallback_note(self) -> Option<&'static str> {
+        match self {
+            Self::Reranked => None,
+            Self::TimedOut => Some("Reranking timed out; results are in search order."),
+            Self::Failed => Some("Reranking failed; results are in search order."),
+        }
+    }
+}
+
+/// Reorder `results` by reranker score, keeping the search order on timeout or error
+///
+/// Ties keep their search order. The outcome is recorded in the server metrics.
+pub async fn rerank<T: ChunkedResult>(
+    reranker: &dyn Reranker,
+    config: &RerankConfig,
+    query: &str,
+    results: Vec<T>,
+) -> (Vec<T>, RerankOutcome) {
+    if results.len() < 2 {
+        return (results, RerankOutcome::Reranked);
+    }
+
+    let passages: Vec<String> = results
+        .iter()
+        .map(|result| result.document().content.clone())
+        .collect();
+    let scores = match tokio::time::timeout(config.timeout, reranker.score(query, &passages)).await
+    {
+        Ok(Ok(scores)) if scores.len() == results.len() => Ok(scores),
+        Ok(Ok(scores)) => Err(anyhow!(
+            "reranker returned {} scores for {} passages",
+            scores.len(),
+            results.len()
+        )),
+        Ok(Err(e)) => Err(e),
+        Err(_) => {
+            warn!(
+                "Reranking timed out after {:?}; keeping search order",
+                config.timeout
+            );
+            metrics().record_rerank(RerankOutcome::TimedOut);
+            return (results, RerankOutcome::TimedOut);
+        }
+    };
+
+    let scores = match scores {
+        Ok(scores) => scores,
+        Err(e) => {
+            warn!("Reranking failed ({}); keeping search order", e);
+            metrics().record_rerank(RerankOutcome::Failed);
+            return (results, RerankOutcome::Failed);
+        }
+    };
+
+    let mut scored: Vec<(f64, T)> = scores.into_iter().zip(results).collect();
+    // Stable sort, so ties keep their search order
+    scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    metrics().record_rerank(RerankOutcome::Reranked);
+    (
+        scored.into_iter().map(|(_, result)| result).collect(),
+        RerankOutcome::Reranked,
+    )
+}
+
+/// Reranker that asks Claude to rate every passage in one prompt
+pub struct PromptReranker {
+    runner: Arc<dyn PromptRunner>,
+}
+
+impl PromptReranker {
+    /// Create a reranker backed by the given prompt runner
+    #[must_use]
+    pub fn new(runner: Arc<dyn PromptRunner>) -> Self {
+        Self { runner }
+    }
+}
+
+/// Reranker standing in for one whose runner could not be built
+///
+/// Every call fails with `reason`, so queries fall back to search order.
+pub struct UnavailableReranker {
+    reason: String,
+}
+
+impl UnavailableReranker {
+    #[must_use]
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Reranker for UnavailableReranker {
+    async fn score(&self, _query: &str, _passages: &[String]) -> Result<Vec<f64>> {
+        Err(anyhow!("reranker unavailable: {}", self.reason))
+    }
+}
+
+/// Prompt asking for a 0–10 relevance score per numbered passage
+fn scoring_prompt(query: &str, passages: &[String]) -> String {
+    let mut prompt = format!(
+        "Rate how well each numbered documentation passage answers the search query, \
+         from 0 (irrelevant) to 10 (answers it directly). Reply with only a JSON array \
+         of {} numbers, one per passage, in passage order.\n\nQuery: {query}\n",
+        passages.len()
+    );
+    for (i, passage) in passages.iter().enumerate() {
+        let excerpt: String = passage.chars().take(PASSAGE_CHARS).collect();
+        let _ = write!(&mut prompt, "\n[{}]\n{excerpt}\n", i + 1);
+    }
+    prompt
+}
+
+/// The first JSON array of numbers in `answer`
+fn parse_scores(answer: &str) -> Result<Vec<f64>> {
+    let start = answer
+        .find('[')
+        .ok_or_else(|| anyhow!("no score array in reranker answer"))?;
+    let end = answer[start..]
+        .find(']')
+        .ok_or_else(|| anyhow!("unterminated score array in reranker answer"))?;
+    Ok(serde_json::from_str(&answer[start..=start + end])?)
+}
+
+#[async_trait]
+impl Reranker for PromptReranker {
+    async fn score(&self, query: &str, passages: &[String]) -> Result<Vec<f64>> {
+        let span = info_span!("llm", purpose = "rerank", passages = passages.len());
+        let prompt = scoring_prompt(query, passages);
+        let output = self.runner.run(&prompt).instrument(span).await?;
+        account_llm_run(self.runner.as_ref(), &prompt, &output);
+        parse_scores(&result_text(&output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use db::models::Document;
+    use uuid::Uuid;
+
+    fn doc(path: &str, content: &str) -> Document {
+        Document {
+            id: Uuid::new_v4(),
+            doc_type: "rust".to_string(),
+            source_name: "tokio".to_string(),
+            doc_path: path.to_string(),
+            content: content.to_string(),
+            metadata: serde_json::json!({}),
+            embedding: None,
+            token_count: None,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    /// Scores passages by how often they contain the query, after an optional delay
+    struct CountingReranker {
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl Reranker for CountingReranker {
+        async fn score(&self, query: &str, passages: &[String]) -> Result<Vec<f64>> {
+            tokio::time::sleep(self.delay).await;
+            #[allow(clippy::cast_precision_loss)]
+            Ok(passages
+                .iter()
+                .map(|p| p.matches(query).count() as f64)
+                .collect())
+        }
+    }
+
+    fn candidates() -> Vec<Document> {
+        vec![
+            doc("a.html", "intro"),
+            doc("b.html", "spawn once"),
+            doc("c.html", "spawn spawn spawn"),
+            doc("d.html", "no match"),
+        ]
+    }
+
+    fn paths(results: &[Document]) -> Vec<&str> {
+        results.iter().map(|d| d.doc_path.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_rerank_reorders_by_score() {
+        let reranker = CountingReranker {
+            delay: Duration::ZERO,
+        };
+        let (results, outcome) =
+            rerank(&reranker, &RerankConfig::default(), "spawn", candidates()).await;
+
+        assert_eq!(outcome, RerankOutcome::Reranked);
+        assert_eq!(
+            paths(&results),
+            vec!["c.html", "b.html", "a.html", "d.html"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rerank_timeout_keeps_search_order() {
+        let reranker = CountingReranker {
+            delay: Duration::from_millis(200),
+        };
+        let config = RerankConfig {
+            candidates: 30,
+            timeout: Duration::from_millis(10),
+        };
+        let before = metrics().snapshot().rerank_fallbacks;
+        let (results, outcome) = rerank(&reranker, &config, "spawn", candidates()).await;
+
+        assert_eq!(outcome, RerankOutcome::TimedOut);
+        assert!(outcome.fallback_note().is_some());
+        assert_eq!(
+            paths(&results),
+            vec!["a.html", "b.html", "c.html", "d.html"]
+        );
+        assert!(metrics().snapshot().rerank_fallbacks > before);
+    }
+
+    #[test]
+    fn test_parse_scores() {
+        let scores = parse_scores("Scores:\n[7, 2.5, 0]\n").unwrap();
+        assert_eq!(scores, vec![7.0, 2.5, 0.0]);
+        assert!(parse_scores("no idea").is_err());
+    }
+}
@@ -0,0 +1,168 @@
+//! MCP resources backed by the `documents` table
+//!
+//! Each document is exposed as `doc://{doc_type}/{source_name}/{doc_path}`.
+//! The doc type and source name are percent-encoded as single segments; the
+//! doc path keeps its slashes so URIs stay readable.
+
+use anyhow::{anyhow, Result};
+use db::models::{Document, DocumentResource};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use serde_json::{json, Value};
+
+/// URI scheme for document resources
+pub const RESOURCE_SCHEME: &str = "doc://";
+
+/// Default number of resources per `resources/list` page
+pub const DEFAULT_RESOURCE_PAGE_SIZE: i64 = 50;
+
+/// Default cap on the content returned by `resources/read`, in characters
+pub const DEFAULT_RESOURCE_MAX_CHARS: usize = 200_000;
+
+/// Characters escaped inside a single URI segment
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Characters escaped inside the doc path (slashes are kept)
+const PATH: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Build the resource URI for a document
+#[must_use]
+pub fn resource_uri(doc_type: &str, source_name: &str, doc_path: &str) -> String {
+    format!(
+        "{RESOURCE_SCHEME}{}/{}/{}",
+        utf8_percent_encode(doc_type, SEGMENT),
+        utf8_percent_encode(source_name, SEGMENT),
+        utf8_percent_encode(doc_path, PATH)
+    )
+}
+
+/// Split a resource URI into `(doc_type, source_name, doc_path)`
+///
+/// # Errors
+///
+/// Returns an error if the URI does not use the `doc://` scheme or lacks a segment.
+pub fn parse_resource_uri(uri: &str) -> Result<(String, String, String)> {
+    let rest = uri
+        .strip_prefix(RESOURCE_SCHEME)
+        .ok_or_else(|| anyhow!("Unsupported resource URI: {uri}"))?;
+
+    let mut parts = rest.splitn(3, '/');
+    let (Some(doc_type), Some(source_name), Some(doc_path)) =
+        (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(anyhow!(
+            "Resource URI must look like doc://{{doc_type}}/{{source_name}}/{{doc_path}}: {uri}"
+        ));
+    };
+    if doc_type.is_empty() || source_name.is_empty() || doc_path.is_empty() {
+        return Err(anyhow!("Resource URI has an empty segment: {uri}"));
+    }
+
+    let decode = |segment: &str| {
+        percent_decode_str(segment)
+            .decode_utf8()
+            .map(|s| s.into_owned())
+            .map_err(|e| anyhow!("Invalid percent-encoding in resource URI: {e}"))
+    };
+    Ok((decode(doc_type)?, decode(source_name)?, decode(doc_path)?))
+}
+
+/// Guess a MIME type from the document path
+#[must_use]
+pub fn mime_type_for(doc_path: &str) -> &'static str {
+    let extension = doc_path
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("json") => "application/json",
+        Some("rs") => "text/x-rust",
+        Some("txt") => "text/plain",
+        Some("yaml" | "yml") => "application/yaml",
+        _ => "text/markdown",
+    }
+}
+
+/// Resource descriptor for `resources/list`
+#[must_use]
+pub fn resource_descriptor(resource: &DocumentResource) -> Value {
+    let mut descriptor = json!({
+        "uri": resource_uri(&resource.doc_type, &resource.source_name, &resource.doc_path),
+        "name": format!("{}/{}", resource.source_name, resource.doc_path),
+        "description": format!("{} documentation from {}", resource.doc_type, resource.source_name),
+        "mimeType": mime_type_for(&resource.doc_path),
+        "size": resource.content_length,
+    });
+    if let Some(tokens) = resource.token_count {
+        descriptor["annotations"] = json!({ "tokenCount": tokens });
+    }
+    descriptor
+}
+
+/// Resource contents for `resources/read`, truncated to `max_chars`
+#[must_use]
+pub fn resource_contents(document: &Document, max_chars: usize) -> Value {
+    let total = document.content.chars().count();
+    let text = if total > max_chars {
+        let kept: String = document.content.chars().take(max_chars).collect();
+        format!("{kept}\n\n[... truncated: showing {max_chars} of {total} characters ...]")
+    } else {
+        document.content.clone()
+    };
+
+    json!({
+        "uri": resource_uri(&document.doc_type, &document.source_name, &document.doc_path),
+        "mimeType": mime_type_for(&document.doc_path),
+        "text": text,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uri_round_trip() {
+        let uri = resource_uri("rust", "tokio/extra", "tokio/task/fn.spawn.html");
+        assert_eq!(uri, "doc://rust/tokio%2Fextra/tokio/task/fn.spawn.html");
+
+        let (doc_type, source, path) = parse_resource_uri(&uri).unwrap();
+        assert_eq!(doc_type, "rust");
+        assert_eq!(source, "tokio/extra");
+        assert_eq!(path, "tokio/task/fn.spawn.html");
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_uris() {
+        assert!(parse_resource_uri("file:///etc/passwd").is_err());
+        assert!(parse_resource_uri("doc://rust/tokio").is_err());
+        assert!(parse_resource_uri("doc://rust//path").is_err());
+    }
+
+    #[test]
+    fn test_mime_types() {
+        assert_eq!(mime_type_for("api/openapi.json"), "application/json");
+        assert_eq!(mime_type_for("src/lib.rs"), "text/x-rust");
+        assert_eq!(mime_type_for("tokio/fn.spawn.html"), "text/markdown");
+        assert_eq!(mime_type_for("README"), "text/markdown");
+    }
+}
@@ -1,11 +1,12 @@
 //! Security validation module for MCP server
 //!
 //! This module provides comprehensive security features including Origin header validation,
-//! DNS rebinding protection, and localhost binding enforcement for secure local deployments.
+//! DNS rebinding protection, optional bearer-token authentication, and localhost binding
+//! enforcement for secure local deployments.
 
 use axum::{
     extract::Request,
-    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
     Json,
@@ -13,7 +14,9 @@ use axum::{
 use serde_json::json;
 use std::collections::HashSet;
 use std::net::IpAddr;
+use std::time::Duration;
 use thiserror::Error;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tracing::{debug, error, warn};
 
 /// Security configuration for the MCP server
@@ -21,12 +24,203 @@ use tracing::{debug, error, warn};
 pub struct SecurityConfig {
     /// Set of allowed origins for CORS and DNS rebinding protection
     pub allowed_origins: HashSet<String>,
+    /// Wildcard origins such as `https://*.example.com`
+    pub allowed_origin_patterns: Vec<OriginPattern>,
+    /// Host headers accepted by the DNS rebinding check (any host when empty)
+    pub allowed_hosts: Vec<HostPattern>,
+    /// Skip origin and DNS rebinding checks entirely (localhost development only)
+    pub origin_check_disabled: bool,
     /// Enable strict origin validation (reject requests without valid origins)
     pub strict_origin_validation: bool,
     /// Restrict server binding to localhost only for security
     pub localhost_only: bool,
     /// Require Origin header on all requests (recommended for web security)
     pub require_origin_header: bool,
+    /// Bearer tokens accepted on the MCP endpoint (authentication is off when empty)
+    pub auth_tokens: Vec<AuthToken>,
+    /// Bearer token granting access to admin tools and routes (admin is off when unset)
+    pub admin_token: Option<AuthToken>,
+}
+
+/// Client label of requests made with the admin token
+pub const ADMIN_CLIENT_LABEL: &str = "admin";
+
+/// A bearer token accepted by the server and the client label it is attributed to
+#[derive(Clone)]
+pub struct AuthToken {
+    /// Client label used in metrics and audit logs
+    pub label: String,
+    token: String,
+}
+
+impl AuthToken {
+    /// Create a token with the given client label
+    #[must_use]
+    pub fn new(label: impl Into<String>, token: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            token: token.into(),
+        }
+    }
+
+    /// Parse a `label=token` entry; entries without a label are named `client-{index}`
+    ///
+    /// Bearer tokens only carry `=` as trailing padding, so an entry is
+    /// labelled exactly when something other than `=` follows its first `=`.
+    /// Any other entry, including one containing `:`, is a bare token.
+    fn parse(entry: &str, index: usize) -> Option<Self> {
+        let entry = entry.trim();
+        if entry.is_empty() || entry.starts_with('#') {
+            return None;
+        }
+        match entry.split_once('=') {
+            Some((label, token)) if !token.trim_start_matches('=').is_empty() => {
+                let (label, token) = (label.trim(), token.trim());
+                (!label.is_empty() && !token.is_empty()).then(|| Self::new(label, token))
+            }
+            _ => Some(Self::new(format!("client-{index}"), entry)),
+        }
+    }
+}
+
+/// A host, optionally with a `*.` wildcard for subdomains and a port
+///
+/// Without a port the pattern matches the host on any port.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostPattern {
+    domain: String,
+    wildcard: bool,
+    port: Option<u16>,
+}
+
+impl HostPattern {
+    /// Parse `example.com`, `example.com:8080` or `*.example.com`
+    ///
+    /// # Errors
+    ///
+    /// Returns `SecurityError::InvalidConfig` if `entry` is not a host with an optional port.
+    pub fn parse(entry: &str) -> Result<Self, SecurityError> {
+        let invalid = || SecurityError::InvalidConfig(format!("invalid host pattern '{entry}'"));
+        let trimmed = entry.trim();
+        let (wildcard, rest) = trimmed
+            .strip_prefix("*.")
+            .map_or((false, trimmed), |rest| (true, rest));
+        let url = url::Url::parse(&format!("http://{rest}")).map_err(|_| invalid())?;
+        let domain = url.host_str().ok_or_else(invalid)?;
+        if rest.contains(['/', '?', '#', '@', '*'])
+            || (wildcard && !matches!(url.host(), Some(url::Host::Domain(_))))
+        {
+            return Err(invalid());
+        }
+        Ok(Self {
+            domain: domain.to_string(),
+            wildcard,
+            port: url.port(),
+        })
+    }
+
+    /// Whether `host` (and `port`, when the pattern names one) match
+    #[must_use]
+    pub fn matches(&self, host: &str, port: Option<u16>) -> bool {
+        if self.port.is_some() && self.port != port {
+            return false;
+        }
+        let host = host.to_ascii_lowercase();
+        if self.wildcard {
+            host.strip_suffix(self.domain.as_str())
+                .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.'))
+        } else {
+            host == self.domain
+        }
+    }
+
+    /// Whether a `Host` header value such as `api.example.com:3001` matches
+    #[must_use]
+    pub fn matches_header(&self, host_header: &str) -> bool {
+        url::Url::parse(&format!("http://{host_header}"))
+            .ok()
+            .and_then(|url| url.host_str().map(|h| self.matches(h, url.port())))
+            .unwrap_or(false)
+    }
+}
+
+/// An allowed origin with a wildcard host, e.g. `https://*.example.com`
+///
+/// Without a scheme the pattern accepts both `http` and `https`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OriginPattern {
+    scheme: Option<String>,
+    host: HostPattern,
+}
+
+impl OriginPattern {
+    /// Whether `origin` (e.g. `https://app.example.com`) matches
+    #[must_use]
+    pub fn matches(&self, origin: &str) -> bool {
+        let Ok(url) = url::Url::parse(origin) else {
+            return false;
+        };
+        self.scheme.as_deref().is_none_or(|s| s == url.scheme())
+            && url
+                .host_str()
+                .is_some_and(|host| self.host.matches(host, url.port()))
+    }
+}
+
+/// An `MCP_ALLOWED_ORIGINS` entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AllowedOrigin {
+    Exact(String),
+    Wildcard(OriginPattern),
+}
+
+impl AllowedOrigin {
+    /// Parse `scheme://host[:port]` or a wildcard such as `*.example.com`
+    fn parse(entry: &str) -> Result<Self, SecurityError> {
+        let invalid = |reason: &str| {
+            SecurityError::InvalidConfig(format!("invalid origin '{entry}': {reason}"))
+        };
+        let trimmed = entry.trim().trim_end_matches('/');
+        let (scheme, rest) = match trimmed.split_once("://") {
+            Some((scheme, rest)) => (Some(scheme.to_ascii_lowercase()), rest),
+            None => (None, trimmed),
+        };
+        if scheme
+            .as_deref()
+            .is_some_and(|s| s != "http" && s != "https")
+        {
+            return Err(invalid("scheme must be http or https"));
+        }
+
+        if rest.starts_with("*.") {
+            let host = HostPattern::parse(rest).map_err(|_| invalid("expected *.domain[:port]"))?;
+            return Ok(Self::Wildcard(OriginPattern { scheme, host }));
+        }
+
+        let Some(scheme) = scheme else {
+            return Err(invalid("expected scheme://host[:port]"));
+        };
+        let url =
+            url::Url::parse(&format!("{scheme}://{rest}")).map_err(|e| invalid(&e.to_string()))?;
+        if url.host_str().is_none()
+            || url.path() != "/"
+            || url.query().is_some()
+            || !url.username().is_empty()
+            || rest.contains('*')
+        {
+            return Err(invalid("expected scheme://host[:port]"));
+        }
+        Ok(Self::Exact(url.origin().ascii_serialization()))
+    }
+}
+
+impl std::fmt::Debug for AuthToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthToken")
+            .field("label", &self.label)
+            .field("token", &"[REDACTED]")
+            .finish()
+    }
 }
 
 impl Default for SecurityConfig {
@@ -41,9 +235,14 @@ impl Default for SecurityConfig {
 
         Self {
             allowed_origins,
+            allowed_origin_patterns: Vec::new(),
+            allowed_hosts: Vec::new(),
+            origin_check_disabled: false,
             strict_origin_validation: true,
             localhost_only: true,
             require_origin_header: false, // Keep flexible for MVP
+            auth_tokens: Vec::new(),
+            admin_token: None,
         }
     }
 }
@@ -58,28 +257,53 @@ impl SecurityConfig {
     /// Construct from environment variables with sensible defaults.
     ///
     /// Supported env vars:
-    /// - `MCP_ALLOWED_ORIGINS` (comma-separated)
+    /// - `MCP_ALLOWED_ORIGINS` (comma-separated, `*.example.com` allows subdomains)
+    /// - `MCP_ALLOWED_HOSTS` (comma-separated `Host` values for DNS rebinding protection)
+    /// - `MCP_DISABLE_ORIGIN_CHECK` (true/false, localhost development only)
     /// - `MCP_STRICT_ORIGIN_VALIDATION` (true/false)
     /// - `MCP_REQUIRE_ORIGIN_HEADER` (true/false)
     /// - `MCP_LOCALHOST_ONLY` (true/false)
-    #[must_use]
-    pub fn from_env() -> Self {
+    /// - `MCP_AUTH_TOKENS` (comma-separated `label=token` or bare tokens)
+    /// - `MCP_AUTH_TOKENS_FILE` (one `label=token` or bare token per line, `#` comments)
+    /// - `MCP_ADMIN_TOKEN` (bearer token for admin tools and `/admin` routes)
+    ///
+    /// # Errors
+    ///
+    /// Returns `SecurityError::InvalidConfig` if an allowed origin or host is
+    /// malformed, or `MCP_AUTH_TOKENS_FILE` is set but cannot be read.
+    pub fn from_env() -> Result<Self, SecurityError> {
         let mut cfg = Self::default();
 
         // Allowed origins
         if let Ok(list) = std::env::var("MCP_ALLOWED_ORIGINS") {
             let mut set: HashSet<String> = HashSet::new();
-            for item in list.split(',') {
-                let trimmed = item.trim();
-                if !trimmed.is_empty() {
-                    set.insert(trimmed.to_string());
+            let mut patterns = Vec::new();
+            for item in list.split(',').filter(|item| !item.trim().is_empty()) {
+                match AllowedOrigin::parse(item).map_err(|e| {
+                    SecurityError::InvalidConfig(format!("MCP_ALLOWED_ORIGINS: {e}"))
+                })? {
+                    AllowedOrigin::Exact(origin) => {
+                        set.insert(origin);
+                    }
+                    AllowedOrigin::Wildcard(pattern) => patterns.push(pattern),
                 }
             }
-            if !set.is_empty() {
+            if !set.is_empty() || !patterns.is_empty() {
                 cfg.allowed_origins = set;
+                cfg.allowed_origin_patterns = patterns;
             }
         }
 
+        // Allowed Host headers
+        if let Ok(list) = std::env::var("MCP_ALLOWED_HOSTS") {
+            cfg.allowed_hosts = list
+                .split(',')
+                .filter(|item| !item.trim().is_empty())
+                .map(HostPattern::parse)
+                .collect::<Result<_, _>>()
+                .map_err(|e| SecurityError::InvalidConfig(format!("MCP_ALLOWED_HOSTS: {e}")))?;
+        }
+
         // Booleans
         if let Ok(v) = std::env::var("MCP_STRICT_ORIGIN_VALIDATION") {
             cfg.strict_origin_validation = matches!(v.as_str(), "1" | "true" | "TRUE" | "True");
@@ -90,13 +314,69 @@ impl SecurityConfig {
         if let Ok(v) = std::env::var("MCP_LOCALHOST_ONLY") {
             cfg.localhost_only = matches!(v.as_str(), "1" | "true" | "TRUE" | "True");
         }
+        if let Ok(v) = std::env::var("MCP_DISABLE_ORIGIN_CHECK") {
+            cfg.origin_check_disabled = matches!(v.as_str(), "1" | "true" | "TRUE" | "True");
+        }
+        if cfg.origin_check_disabled {
+            warn!("Origin and DNS rebinding checks are disabled (MCP_DISABLE_ORIGIN_CHECK); use only for localhost development");
+        }
 
-        cfg
+        // Bearer tokens
+        let mut entries: Vec<String> = Vec::new();
+        if let Ok(list) = std::env::var("MCP_AUTH_TOKENS") {
+            entries.extend(list.split(',').map(String::from));
+        }
+        if let Ok(path) = std::env::var("MCP_AUTH_TOKENS_FILE") {
+            // Carrying on without the file would silently turn authentication off
+            let contents = std::fs::read_to_string(&path).map_err(|e| {
+                SecurityError::InvalidConfig(format!("MCP_AUTH_TOKENS_FILE {path}: {e}"))
+            })?;
+            entries.extend(contents.lines().map(String::from));
+        }
+        if entries.iter().any(|e| e.contains(':') && !e.contains('=')) {
+            warn!("MCP_AUTH_TOKENS entries containing ':' are bare tokens; write client labels as label=token");
+        }
+        cfg.auth_tokens = entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| AuthToken::parse(entry, i + 1))
+            .collect();
+        cfg.admin_token = std::env::var("MCP_ADMIN_TOKEN")
+            .ok()
+            .map(|token| token.trim().to_string())
+            .filter(|token| !token.is_empty())
+            .map(|token| AuthToken::new(ADMIN_CLIENT_LABEL, token));
+
+        Ok(cfg)
     }
 
     /// Add an allowed origin to the configuration
+    ///
+    /// Wildcard entries such as `https://*.example.com` allow every subdomain.
     pub fn add_allowed_origin(&mut self, origin: &str) -> &mut Self {
-        self.allowed_origins.insert(origin.to_string());
+        match AllowedOrigin::parse(origin) {
+            Ok(AllowedOrigin::Wildcard(pattern)) => self.allowed_origin_patterns.push(pattern),
+            _ => {
+                self.allowed_origins.insert(origin.to_string());
+            }
+        }
+        self
+    }
+
+    /// Accept `Host` headers matching `pattern` (see [`HostPattern::parse`])
+    ///
+    /// # Errors
+    ///
+    /// Returns `SecurityError::InvalidConfig` if the pattern is malformed.
+    pub fn add_allowed_host(&mut self, pattern: &str) -> Result<&mut Self, SecurityError> {
+        self.allowed_hosts.push(HostPattern::parse(pattern)?);
+        Ok(self)
+    }
+
+    /// Turn origin and DNS rebinding checks off for localhost development
+    #[must_use]
+    pub const fn with_origin_check_disabled(mut self, disabled: bool) -> Self {
+        self.origin_check_disabled = disabled;
         self
     }
 
@@ -121,10 +401,78 @@ impl SecurityConfig {
         self
     }
 
+    /// Add an accepted bearer token for the given client label
+    #[must_use]
+    pub fn with_auth_token(mut self, label: &str, token: &str) -> Self {
+        self.auth_tokens.push(AuthToken::new(label, token));
+        self
+    }
+
+    /// Accept `token` as the admin token
+    #[must_use]
+    pub fn with_admin_token(mut self, token: &str) -> Self {
+        self.admin_token = Some(AuthToken::new(ADMIN_CLIENT_LABEL, token));
+        self
+    }
+
+    /// Whether bearer-token authentication is enabled
+    #[must_use]
+    pub fn auth_enabled(&self) -> bool {
+        !self.auth_tokens.is_empty()
+    }
+
     /// Validate a given origin against the allowed origins list
     #[must_use]
     pub fn is_origin_allowed(&self, origin: &str) -> bool {
         self.allowed_origins.contains(origin)
+            || self
+                .allowed_origin_patterns
+                .iter()
+                .any(|pattern| pattern.matches(origin))
+    }
+
+    /// Whether the DNS rebinding check accepts `host` as the `Host` header
+    #[must_use]
+    pub fn is_host_allowed(&self, host: &str) -> bool {
+        self.allowed_hosts.is_empty()
+            || self
+                .allowed_hosts
+                .iter()
+                .any(|pattern| pattern.matches_header(host))
+    }
+
+    /// CORS layer answering preflight requests for the configured origins
+    ///
+    /// Browsers may send the MCP headers and read `Mcp-Session-Id` and
+    /// `MCP-Protocol-Version` from responses. Every origin is allowed while
+    /// origin checks are disabled.
+    pub fn cors_layer(&self) -> CorsLayer {
+        let allow_origin = if self.origin_check_disabled {
+            AllowOrigin::mirror_request()
+        } else {
+            let config = self.clone();
+            AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+                origin
+                    .to_str()
+                    .is_ok_and(|origin| config.is_origin_allowed(origin))
+            })
+        };
+        let mcp_protocol_version = HeaderName::from_static("mcp-protocol-version");
+        let mcp_session_id = HeaderName::from_static("mcp-session-id");
+
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::POST, Method::GET, Method::DELETE])
+            .allow_headers([
+                header::CONTENT_TYPE,
+                header::ACCEPT,
+                header::AUTHORIZATION,
+                mcp_protocol_version.clone(),
+                mcp_session_id.clone(),
+                HeaderName::from_static("last-event-id"),
+            ])
+            .expose_headers([mcp_session_id, mcp_protocol_version])
+            .max_age(Duration::from_secs(600))
     }
 
     /// Check if an origin represents a localhost variant
@@ -154,6 +502,15 @@ pub enum SecurityError {
 
     #[error("Invalid host header: {0}")]
     InvalidHostHeader(String),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Host not allowed: {0}")]
+    HostNotAllowed(String),
+
+    #[error("Invalid security configuration: {0}")]
+    InvalidConfig(String),
 }
 
 impl IntoResponse for SecurityError {
@@ -167,6 +524,12 @@ impl IntoResponse for SecurityError {
             Self::InvalidOriginFormat(_) => (StatusCode::BAD_REQUEST, "Invalid origin format"),
             Self::LocalhostBindingRequired => (StatusCode::FORBIDDEN, "Localhost binding required"),
             Self::InvalidHostHeader(_) => (StatusCode::BAD_REQUEST, "Invalid Host header"),
+            Self::Unauthorized(_) => (StatusCode::UNAUTHORIZED, "Unauthorized"),
+            Self::HostNotAllowed(_) => (StatusCode::FORBIDDEN, "Host not allowed"),
+            Self::InvalidConfig(_) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Invalid security configuration",
+            ),
         };
 
         error!("Security validation error: {}", self);
@@ -205,6 +568,10 @@ fn extract_host(headers: &HeaderMap) -> Option<String> {
 ///
 /// Returns `SecurityError` if origin validation fails
 pub fn validate_origin(headers: &HeaderMap, config: &SecurityConfig) -> Result<(), SecurityError> {
+    if config.origin_check_disabled {
+        return Ok(());
+    }
+
     let origin = extract_origin(headers);
 
     // Check if Origin header is required
@@ -229,6 +596,76 @@ pub fn validate_origin(headers: &HeaderMap, config: &SecurityConfig) -> Result<(
     Ok(())
 }
 
+/// Compare two byte strings in time independent of where they differ
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Token from a `Bearer` `Authorization` header
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(axum::http::header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .split_once(' ')
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+        .map(|(_, token)| token.trim())
+        .filter(|token| !token.is_empty())
+}
+
+/// Whether the request carries the configured admin token
+#[must_use]
+pub fn is_admin_request(headers: &HeaderMap, config: &SecurityConfig) -> bool {
+    match (&config.admin_token, bearer_token(headers)) {
+        (Some(admin), Some(presented)) => {
+            constant_time_eq(admin.token.as_bytes(), presented.as_bytes())
+        }
+        _ => false,
+    }
+}
+
+/// Authenticate a request against the configured bearer tokens
+///
+/// Returns the matching client label, or `None` when authentication is disabled.
+/// The admin token is accepted as well, labelled [`ADMIN_CLIENT_LABEL`].
+///
+/// # Errors
+///
+/// Returns `SecurityError::Unauthorized` if the `Authorization` header is missing,
+/// malformed, or carries an unknown token
+pub fn authenticate_bearer(
+    headers: &HeaderMap,
+    config: &SecurityConfig,
+) -> Result<Option<String>, SecurityError> {
+    if !config.auth_enabled() {
+        return Ok(None);
+    }
+
+    let presented = bearer_token(headers).ok_or_else(|| {
+        let problem = if headers.contains_key(axum::http::header::AUTHORIZATION) {
+            "malformed Authorization header"
+        } else {
+            "missing bearer token"
+        };
+        SecurityError::Unauthorized(problem.to_string())
+    })?;
+
+    // Check every token so timing does not reveal which one matched
+    let mut matched: Option<&AuthToken> = None;
+    for candidate in config.auth_tokens.iter().chain(&config.admin_token) {
+        if constant_time_eq(candidate.token.as_bytes(), presented.as_bytes()) && matched.is_none() {
+            matched = Some(candidate);
+        }
+    }
+
+    matched
+        .map(|t| Some(t.label.clone()))
+        .ok_or_else(|| SecurityError::Unauthorized("invalid bearer token".to_string()))
+}
+
 /// Validate Host header against DNS rebinding attacks
 ///
 /// # Errors
@@ -238,10 +675,23 @@ pub fn validate_dns_rebinding(
     headers: &HeaderMap,
     config: &SecurityConfig,
 ) -> Result<(), SecurityError> {
+    if config.origin_check_disabled {
+        return Ok(());
+    }
+
     let host = extract_host(headers);
     let origin = extract_origin(headers);
 
-    // If both headers are present, validate they match for security
+    // Only configured hosts may be addressed when an allow-list is set
+    if let Some(host_value) = host.as_deref() {
+        if !config.is_host_allowed(host_value) {
+            warn!("Host not allowed: {}", host_value);
+            return Err(SecurityError::HostNotAllowed(host_value.to_string()));
+        }
+    }
+
+    // If both headers are present, validate they match for security;
+    // explicitly allowed origins may front a different host
     if let (Some(host_value), Some(origin_value)) = (host, origin) {
         // Parse origin to extract host part
         let origin_host = url::Url::parse(&origin_value).map_or(None, |url| {
@@ -257,7 +707,10 @@ pub fn validate_dns_rebinding(
             let is_safe = config.is_localhost_origin(&host_value)
                 && config.is_localhost_origin(&origin_host_value);
 
-            if !is_safe && host_value != origin_host_value {
+            if !is_safe
+                && host_value != origin_host_value
+                && !config.is_origin_allowed(&origin_value)
+            {
                 error!(
                     "DNS rebinding attack detected - Host: {}, Origin: {}",
                     host_value, origin_value
@@ -428,6 +881,101 @@ mod tests {
         assert!(config.is_origin_allowed("https://127.0.0.1:3001"));
     }
 
+    #[test]
+    fn test_auth_token_parsing() {
+        assert!(AuthToken::parse("", 1).is_none());
+        assert!(AuthToken::parse("# comment", 1).is_none());
+        assert!(AuthToken::parse("=token", 1).is_none());
+
+        let labelled = AuthToken::parse(" ci = abc123== ", 1).unwrap();
+        assert_eq!(labelled.label, "ci");
+        assert_eq!(labelled.token, "abc123==");
+
+        let bare = AuthToken::parse("abc123", 3).unwrap();
+        assert_eq!(bare.label, "client-3");
+        assert!(!format!("{bare:?}").contains("abc123"));
+
+        // A token with a colon or padding is kept whole, not split into a label
+        let colon = AuthToken::parse("user:s3cr3t", 4).unwrap();
+        assert_eq!(colon.label, "client-4");
+        assert_eq!(colon.token, "user:s3cr3t");
+        let padded = AuthToken::parse("YWJjZA==", 5).unwrap();
+        assert_eq!(padded.label, "client-5");
+        assert_eq!(padded.token, "YWJjZA==");
+    }
+
+    #[test]
+    fn test_bearer_authentication() {
+        let mut headers = HeaderMap::new();
+
+        // Disabled when no tokens are configured
+        let open = SecurityConfig::new();
+        assert_eq!(authenticate_bearer(&headers, &open).unwrap(), None);
+
+        let config = SecurityConfig::new()
+            .with_auth_token("ci", "abc123")
+            .with_auth_token("laptop", "def456");
+
+        // Missing token
+        assert!(matches!(
+            authenticate_bearer(&headers, &config),
+            Err(SecurityError::Unauthorized(_))
+        ));
+
+        // Wrong token
+        headers.insert("authorization", HeaderValue::from_static("Bearer abc124"));
+        assert!(matches!(
+            authenticate_bearer(&headers, &config),
+            Err(SecurityError::Unauthorized(_))
+        ));
+
+        // Wrong scheme
+        headers.insert("authorization", HeaderValue::from_static("Basic def456"));
+        assert!(authenticate_bearer(&headers, &config).is_err());
+
+        // Valid token resolves to its label
+        headers.insert("authorization", HeaderValue::from_static("bearer def456"));
+        assert_eq!(
+            authenticate_bearer(&headers, &config).unwrap().as_deref(),
+            Some("laptop")
+        );
+
+        // A configured token containing `:` must be presented whole
+        let config = config.with_auth_token("client-3", "user:s3cr3t");
+        headers.insert("authorization", HeaderValue::from_static("Bearer s3cr3t"));
+        assert!(authenticate_bearer(&headers, &config).is_err());
+        headers.insert(
+            "authorization",
+            HeaderValue::from_static("Bearer user:s3cr3t"),
+        );
+        assert_eq!(
+            authenticate_bearer(&headers, &config).unwrap().as_deref(),
+            Some("client-3")
+        );
+    }
+
+    #[test]
+    fn test_unreadable_auth_tokens_file_is_an_error() {
+        let missing = std::env::temp_dir().join(format!("missing-tokens-{}", uuid::Uuid::new_v4()));
+        std::env::set_var("MCP_AUTH_TOKENS_FILE", &missing);
+        let result = SecurityConfig::from_env();
+        std::env::remove_var("MCP_AUTH_TOKENS_FILE");
+
+        match result {
+            Err(SecurityError::InvalidConfig(message)) => {
+                assert!(message.contains("MCP_AUTH_TOKENS_FILE"), "{message}");
+            }
+            other => panic!("expected InvalidConfig, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"token", b"token"));
+        assert!(!constant_time_eq(b"token", b"tokem"));
+        assert!(!constant_time_eq(b"token", b"token2"));
+    }
+
     #[test]
     fn test_security_config_builder() {
         let mut config = SecurityConfig::new()
@@ -443,6 +991,110 @@ mod tests {
         assert!(config.is_origin_allowed("https://example.com"));
     }
 
+    #[test]
+    fn test_wildcard_origin_matching() {
+        let mut config = SecurityConfig::new();
+        config
+            .add_allowed_origin("https://*.example.com")
+            .add_allowed_origin("*.dev.test:8080");
+
+        for (origin, allowed) in [
+            ("https://app.example.com", true),
+            ("https://a.b.example.com", true),
+            ("https://APP.example.com", true),
+            ("https://app.example.com:8443", true),
+            ("https://example.com", false),
+            ("https://badexample.com", false),
+            ("https://example.com.evil.io", false),
+            ("http://app.example.com", false),
+            ("http://ui.dev.test:8080", true),
+            ("https://ui.dev.test:8080", true),
+            ("http://ui.dev.test:9090", false),
+            ("http://ui.dev.test", false),
+            ("null", false),
+        ] {
+            assert_eq!(config.is_origin_allowed(origin), allowed, "{origin}");
+        }
+        // Exact defaults still apply
+        assert!(config.is_origin_allowed("http://localhost:3001"));
+    }
+
+    #[test]
+    fn test_allowed_origin_parsing() {
+        assert_eq!(
+            AllowedOrigin::parse(" https://Example.com/ ").unwrap(),
+            AllowedOrigin::Exact("https://example.com".to_string())
+        );
+        assert_eq!(
+            AllowedOrigin::parse("http://localhost:3000").unwrap(),
+            AllowedOrigin::Exact("http://localhost:3000".to_string())
+        );
+        assert!(matches!(
+            AllowedOrigin::parse("*.example.com").unwrap(),
+            AllowedOrigin::Wildcard(_)
+        ));
+
+        for malformed in [
+            "example.com",
+            "ftp://example.com",
+            "https://example.com/path",
+            "https://user@example.com",
+            "https://app.*.example.com",
+            "https://*.",
+            "https://*.127.0.0.1",
+            "https://",
+        ] {
+            assert!(
+                matches!(
+                    AllowedOrigin::parse(malformed),
+                    Err(SecurityError::InvalidConfig(_))
+                ),
+                "{malformed}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_allowed_hosts() {
+        let mut config = SecurityConfig::new();
+        assert!(config.is_host_allowed("anything.example"));
+
+        config
+            .add_allowed_host("api.example.com")
+            .unwrap()
+            .add_allowed_host("*.internal:3001")
+            .unwrap();
+        assert!(config.is_host_allowed("api.example.com"));
+        assert!(config.is_host_allowed("api.example.com:3001"));
+        assert!(config.is_host_allowed("mcp.internal:3001"));
+        assert!(!config.is_host_allowed("mcp.internal:3002"));
+        assert!(!config.is_host_allowed("evil.com"));
+        assert!(config.add_allowed_host("api.example.com/path").is_err());
+
+        // Unlisted hosts are rejected even when Origin matches Host
+        let headers = create_test_headers(Some("http://evil.com:3001"), Some("evil.com:3001"));
+        assert!(matches!(
+            validate_dns_rebinding(&headers, &config),
+            Err(SecurityError::HostNotAllowed(_))
+        ));
+    }
+
+    #[test]
+    fn test_allowed_origin_may_front_other_host() {
+        let mut config = SecurityConfig::new();
+        config.add_allowed_origin("https://*.example.com");
+        let headers = create_test_headers(Some("https://app.example.com"), Some("mcp.example.net"));
+        assert!(validate_dns_rebinding(&headers, &config).is_ok());
+    }
+
+    #[test]
+    fn test_origin_check_disabled() {
+        let config = SecurityConfig::new().with_origin_check_disabled(true);
+        let headers = create_test_headers(Some("https://malicious.com"), Some("localhost:3001"));
+        assert!(validate_origin(&headers, &config).is_ok());
+        assert!(validate_dns_rebinding(&headers, &config).is_ok());
+    }
+
     #[test]
     fn test_localhost_origin_detection() {
         let config = SecurityConfig::default();
@@ -1,18 +1,28 @@
 //! MCP server implementation
 
+use crate::audit::{AuditConfig, AuditLogger};
 use crate::handlers::McpHandler;
-use crate::health::{create_health_router, init_service_start_time};
+use crate::health::{create_health_router, init_service_start_time, HealthState};
 use crate::ingest::IngestJobManager;
+use crate::job_queue::{CrateJobProcessor, IngestJobProcessor};
+use crate::maintenance::{GetMaintenanceStatusTool, MaintenanceScheduler, GET_MAINTENANCE_STATUS};
+use crate::rate_limit::RateLimiter;
 use crate::security::{validate_server_binding, SecurityConfig};
 use crate::session::{SessionConfig, SessionManager as ComprehensiveSessionManager};
+use crate::sse::{hub_from_env, SseHub};
 use crate::transport::{
-    initialize_transport, unified_mcp_handler, SessionManager, TransportConfig,
+    broadcast_shutdown, initialize_transport, unified_mcp_handler, SessionManager, TransportConfig,
 };
 use anyhow::Result;
-use axum::{http::Method, routing::any, routing::post, Router};
-use db::DatabasePool;
+use axum::{routing::any, routing::post, Router};
+use db::{CrateJobQueries, DatabasePool, DocumentQueries};
+use std::future::{Future, IntoFuture};
 use std::sync::Arc;
-use tower_http::cors::{Any, CorsLayer};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::signal;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
 use tracing::{error, info, warn};
 
 /// MCP server state
@@ -25,6 +35,12 @@ pub struct McpServerState {
     pub transport_config: TransportConfig,
     pub security_config: SecurityConfig,
     pub ingest_jobs: IngestJobManager,
+    pub rate_limiter: RateLimiter,
+    pub health: HealthState,
+    /// Periodic retention and cleanup tasks
+    pub maintenance: MaintenanceScheduler,
+    /// Per-session SSE buffers, in memory or in Redis
+    pub sse_hub: Arc<dyn SseHub>,
 }
 
 /// MCP server
@@ -37,32 +53,65 @@ impl McpServer {
     ///
     /// # Errors
     ///
-    /// Returns an error if handler initialization fails.
+    /// Returns an error if the security configuration is invalid, handler
+    /// initialization fails, or the configured embedding dimension does not
+    /// match the `documents.embedding` column and
+    /// `EMBEDDING_DIMENSION_MISMATCH` is not set to `warn`.
     pub async fn new(db_pool: DatabasePool) -> Result<Self> {
         // Initialize service start time for uptime tracking
         init_service_start_time();
-        let handler = Arc::new(McpHandler::new(&db_pool)?);
+        // Initialize security configuration (allow env overrides for production)
+        let security_config = SecurityConfig::from_env()?;
+        verify_embedding_schema(&db_pool).await?;
 
+        let mut handler = McpHandler::new(&db_pool)?;
+        match handler.register_document_source_tools(&db_pool).await {
+            Ok(count) if count > 0 => {
+                info!("Registered {} query tools from document sources", count);
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to register document source query tools: {}", e),
+        }
+        match handler.tool_switches().load().await {
+            Ok(count) if count > 0 => info!("Applied {} stored tool settings", count),
+            Ok(_) => {}
+            Err(e) => warn!("Failed to load stored tool settings: {}", e),
+        }
+        let maintenance = MaintenanceScheduler::from_env()?;
+        handler.register_tool(
+            GET_MAINTENANCE_STATUS,
+            Box::new(GetMaintenanceStatusTool::new(maintenance.clone())),
+        );
         // Initialize transport configuration
-        let transport_config = TransportConfig::default();
+        let transport_config = TransportConfig::from_env();
+        let audit_config = AuditConfig::from_env();
+        let handler = Arc::new(
+            handler
+                .with_max_response_chars(transport_config.max_response_chars)
+                .with_audit_logger(AuditLogger::start(db_pool.pool().clone(), &audit_config)),
+        );
+        let rate_limiter = RateLimiter::new(
+            transport_config.post_rate_limit,
+            transport_config.sse_rate_limit,
+        );
         let session_manager = SessionManager::new(transport_config.clone());
 
         // Initialize comprehensive session manager
         let session_config = SessionConfig::default();
         let comprehensive_session_manager = ComprehensiveSessionManager::new(session_config);
 
-        // Start background cleanup task for comprehensive session manager
-        comprehensive_session_manager.start_cleanup_task();
-
-        // Initialize security configuration (allow env overrides for production)
-        let security_config = SecurityConfig::from_env();
-
         // Initialize the transport with legacy session cleanup (for backward compatibility)
-        initialize_transport(session_manager.clone()).await;
+        initialize_transport(session_manager.clone(), rate_limiter.clone()).await;
 
         let ingest_jobs = IngestJobManager::new(db_pool.clone());
-        // Start background cleanup for ingest jobs
-        ingest_jobs.start_cleanup_task();
+        register_maintenance_tasks(
+            &maintenance,
+            &db_pool,
+            &comprehensive_session_manager,
+            audit_config.retention_days,
+        );
+
+        let sse_hub = hub_from_env()?;
 
         let state = McpServerState {
             db_pool: db_pool.clone(),
@@ -72,19 +121,74 @@ impl McpServer {
             transport_config,
             security_config,
             ingest_jobs,
+            rate_limiter,
+            health: HealthState::new(db_pool.clone()).with_maintenance(maintenance.clone()),
+            maintenance,
+            sse_hub,
         };
 
         // Start background monitoring for the database pool
         db_pool.start_monitoring();
+        // Keep the session gauges current
+        crate::session_admin::start_gauge_task(state.clone());
+        // Drop cached query results when any process changes documents
+        crate::query_cache::start_invalidation_listener(db_pool.clone());
 
         // Attempt recovery of any stale running jobs from previous restarts
-        if let Err(e) = recover_stale_jobs(db_pool.pool()).await {
+        if let Err(e) = recover_stale_jobs(&db_pool).await {
             warn!("Job recovery on startup encountered an error: {}", e);
         }
 
+        // Resume crate jobs that a previous shutdown requeued, then dispatch
+        // queued jobs from any process (Redis mode uses the worker)
+        if !crate::queue::use_redis_queue() {
+            match embed::embedding_client_from_env() {
+                Ok(client) => {
+                    match crate::crate_tools::resume_interrupted_crate_jobs(
+                        &db_pool,
+                        client.clone(),
+                    )
+                    .await
+                    {
+                        Ok(count) if count > 0 => {
+                            info!("Resumed {} interrupted crate ingestion jobs", count);
+                        }
+                        Ok(_) => {}
+                        Err(e) => warn!("Failed to resume interrupted crate jobs: {}", e),
+                    }
+                    crate::job_queue::start_dispatcher(db_pool.clone(), client);
+                    crate::auto_update::start_scheduler(db_pool.clone());
+                }
+                Err(e) => warn!("Skipping crate job dispatch, no embedding client: {}", e),
+            }
+        }
+
         Ok(Self { state })
     }
 
+    /// Report `manager`'s pending migrations in `/health` and, unless `mode`
+    /// is [`db::MigrateOnStart::Off`], hold readiness until they are applied
+    #[must_use]
+    pub fn with_migration_manager(
+        mut self,
+        manager: db::DatabaseMigrationManager,
+        mode: db::MigrateOnStart,
+    ) -> Self {
+        self.state.health = self.state.health.with_migrations(manager, mode);
+        self
+    }
+
+    /// Probe `providers` in `/health/ready` instead of the clients built
+    /// from the environment
+    #[must_use]
+    pub fn with_provider_checks(
+        mut self,
+        providers: crate::provider_health::ProviderChecks,
+    ) -> Self {
+        self.state.health = self.state.health.with_providers(providers);
+        self
+    }
+
     /// Start serving on the given address
     ///
     /// # Errors
@@ -97,16 +201,93 @@ impl McpServer {
             return Err(anyhow::anyhow!("Security validation failed: {}", e));
         }
 
+        let listener = TcpListener::bind(addr).await?;
+        info!("MCP server listening on {} (security validated)", addr);
+
+        self.serve_with_shutdown(listener, shutdown_signal()).await
+    }
+
+    /// Serve on `listener` until `signal` resolves, then shut down gracefully
+    ///
+    /// On shutdown the server stops accepting connections, sends a final
+    /// `shutdown` event to every SSE stream, and waits up to the grace period
+    /// (`MCP_SHUTDOWN_GRACE_SECS`, default 25) for in-flight tool calls and
+    /// crate jobs. Jobs still running afterwards are requeued for resume.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP server fails.
+    pub async fn serve_with_shutdown<F>(&self, listener: TcpListener, signal: F) -> Result<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
         let app = self.create_router();
+        let stop_accepting = CancellationToken::new();
+        let mut server = tokio::spawn(
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+            )
+            .with_graceful_shutdown(stop_accepting.clone().cancelled_owned())
+            .into_future(),
+        );
 
-        let listener = tokio::net::TcpListener::bind(addr).await?;
-        info!("MCP server listening on {} (security validated)", addr);
+        tokio::select! {
+            result = &mut server => return Ok(result??),
+            () = signal => {}
+        }
+
+        let grace = shutdown_grace_period();
+        let deadline = Instant::now() + grace;
+        info!("Starting graceful shutdown (grace period: {:?})", grace);
+
+        let notified = broadcast_shutdown();
+        info!("Sent shutdown event to {} SSE sessions", notified);
+        stop_accepting.cancel();
 
-        axum::serve(listener, app).await?;
+        self.drain(deadline).await;
+        self.state.maintenance.shutdown().await;
+
+        match tokio::time::timeout_at(deadline, &mut server).await {
+            Ok(result) => result??,
+            Err(_) => {
+                warn!("Connections still open after grace period; closing them");
+                server.abort();
+            }
+        }
 
+        info!("Graceful shutdown complete");
         Ok(())
     }
 
+    /// Wait for in-flight tool calls and crate jobs, requeueing jobs that outlive `deadline`
+    async fn drain(&self, deadline: Instant) {
+        loop {
+            let calls = self.state.handler.in_flight_calls();
+            let jobs = crate::crate_tools::active_crate_jobs();
+            if calls == 0 && jobs == 0 {
+                return;
+            }
+            if Instant::now() >= deadline {
+                warn!(
+                    "Grace period elapsed with {} tool calls and {} crate jobs still running",
+                    calls, jobs
+                );
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        let unfinished = crate::crate_tools::running_crate_job_ids();
+        match CrateJobQueries::requeue_interrupted_jobs(self.state.db_pool.pool(), &unfinished)
+            .await
+        {
+            Ok(count) if count > 0 => info!("Requeued {} unfinished crate jobs", count),
+            Ok(_) => {}
+            Err(e) => error!("Failed to requeue unfinished crate jobs: {}", e),
+        }
+    }
+
     /// Create the router with all endpoints
     pub fn create_router(&self) -> Router {
         Router::new()
@@ -121,59 +302,210 @@ impl McpServer {
                 "/ingest/jobs/{job_id}",
                 axum::routing::get(crate::ingest::get_ingest_status_handler),
             )
+            // Runtime tool enable/disable (admin token required)
+            .route(
+                "/admin/tools",
+                axum::routing::get(crate::tool_switches::list_tool_switches_handler),
+            )
+            .route(
+                "/admin/tools/{name}",
+                post(crate::tool_switches::set_tool_switch_handler),
+            )
+            // Session listing and termination (admin token required)
+            .route(
+                "/admin/sessions",
+                axum::routing::get(crate::session_admin::list_sessions_handler)
+                    .delete(crate::session_admin::terminate_all_sessions_handler),
+            )
+            .route(
+                "/admin/sessions/{id}",
+                axum::routing::delete(crate::session_admin::terminate_session_handler),
+            )
             // Unified MCP endpoint using new Streamable HTTP transport
             // Supports POST (JSON-RPC) and GET (SSE) - MVP: POST only with 405 for GET
             .route("/mcp", any(unified_mcp_handler))
-            // Add CORS for Toolman compatibility
-            .layer(
-                CorsLayer::new()
-                    .allow_origin(Any)
-                    .allow_methods([Method::GET, Method::POST, Method::DELETE, Method::OPTIONS])
-                    .allow_headers(Any),
-            )
+            // Answer CORS preflights for the configured origins
+            .layer(self.state.security_config.cors_layer())
             .with_state(self.state.clone())
     }
 }
 
+/// Register the periodic retention and cleanup tasks
+fn register_maintenance_tasks(
+    scheduler: &MaintenanceScheduler,
+    db_pool: &DatabasePool,
+    sessions: &ComprehensiveSessionManager,
+    audit_retention_days: i32,
+) {
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    let manager = sessions.clone();
+    let session_interval = manager
+        .config()
+        .cleanup_interval
+        .to_std()
+        .unwrap_or(Duration::from_secs(5 * 60));
+    scheduler.register("session_cleanup", session_interval, move || {
+        let manager = manager.clone();
+        async move {
+            let removed = manager.cleanup_expired_sessions()?;
+            Ok(format!("removed {removed} expired sessions"))
+        }
+    });
+
+    let pool = db_pool.clone();
+    scheduler.register("crate_job_retention", HOUR, move || {
+        let pool = pool.clone();
+        async move { crate::job_queue::cleanup_crate_jobs(&pool).await }
+    });
+
+    let pool = db_pool.clone();
+    scheduler.register("ingest_job_retention", HOUR, move || {
+        let pool = pool.clone();
+        async move { crate::job_queue::cleanup_ingest_jobs(&pool).await }
+    });
+
+    let pool = db_pool.clone();
+    scheduler.register("ingest_job_recovery", HOUR, move || {
+        let pool = pool.clone();
+        async move {
+            let failed = crate::job_queue::recover_crashed_ingest_jobs(&pool).await?;
+            Ok(format!("failed {failed} crashed jobs"))
+        }
+    });
+
+    let pool = db_pool.pool().clone();
+    scheduler.register("embedding_cache_eviction", 6 * HOUR, move || {
+        let pool = pool.clone();
+        async move { crate::embedding_cache::evict_unused_entries(&pool).await }
+    });
+
+    let pool = db_pool.pool().clone();
+    scheduler.register("audit_log_retention", 6 * HOUR, move || {
+        let pool = pool.clone();
+        async move { crate::audit::prune_audit_log(&pool, audit_retention_days).await }
+    });
+
+    let pool = db_pool.pool().clone();
+    scheduler.register("webhook_delivery_retention", 6 * HOUR, move || {
+        let pool = pool.clone();
+        async move { crate::webhooks::prune_deliveries(&pool).await }
+    });
+
+    let pool = db_pool.pool().clone();
+    scheduler.register("search_feedback_retention", 6 * HOUR, move || {
+        let pool = pool.clone();
+        async move { crate::feedback_tools::prune_feedback(&pool).await }
+    });
+}
+
+/// Grace period for draining work on shutdown (`MCP_SHUTDOWN_GRACE_SECS`, default 25)
+fn shutdown_grace_period() -> Duration {
+    std::env::var("MCP_SHUTDOWN_GRACE_SECS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .map_or(Duration::from_secs(25), Duration::from_secs)
+}
+
+/// Wait for shutdown signal (SIGTERM or SIGINT)
+///
+/// # Panics
+///
+/// Panics if the signal handlers cannot be installed.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("Failed to install signal handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {
+            warn!("Received SIGINT (Ctrl+C), initiating graceful shutdown...");
+        },
+        () = terminate => {
+            warn!("Received SIGTERM, initiating graceful shutdown...");
+        }
+    }
+}
+
+/// Compare the configured embedding dimension with the `vector(N)` column
+///
+/// A mismatch would otherwise surface as an opaque SQL error on the first
+/// insert. With `EMBEDDING_DIMENSION_MISMATCH=warn` the server keeps running
+/// with vector writes disabled instead of refusing to start.
+async fn verify_embedding_schema(db_pool: &DatabasePool) -> Result<()> {
+    let config = embed::EmbeddingConfig::from_env()?;
+
+    let column_dimensions = match DocumentQueries::embedding_column_dimensions(db_pool.pool()).await
+    {
+        Ok(Some(dimensions)) => dimensions,
+        Ok(None) => {
+            warn!(
+                "documents.embedding is not a dimensioned vector column, skipping dimension check"
+            );
+            return Ok(());
+        }
+        Err(e) => {
+            warn!("Could not read embedding column definition: {}", e);
+            return Ok(());
+        }
+    };
+
+    if i64::from(column_dimensions) == i64::from(config.dimensions) {
+        info!(
+            "Embedding model {} matches vector({}) column",
+            config.model, column_dimensions
+        );
+        return Ok(());
+    }
+
+    let message = format!(
+        "Embedding model {} produces {} dimensions but documents.embedding is vector({}); \
+         run http_server --recreate-embedding-column to switch the column",
+        config.model, config.dimensions, column_dimensions
+    );
+    let warn_only =
+        std::env::var("EMBEDDING_DIMENSION_MISMATCH").is_ok_and(|v| v.eq_ignore_ascii_case("warn"));
+    if warn_only {
+        error!("{}; vector writes are DISABLED", message);
+        embed::config::disable_vector_writes();
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(message))
+    }
+}
+
+/// Running jobs without an update for this long are failed on startup
+const STALE_JOB_AGE: Duration = Duration::from_secs(30 * 60);
+
+/// Error note on jobs failed by [`recover_stale_jobs`]
+const STALE_JOB_NOTE: &str =
+    "Recovery: marked failed on startup due to stale running (updated_at older than 30 minutes).";
+
 /// Recover stale running jobs that may have been abandoned due to a restart
 ///
-/// This marks jobs in 'running' state whose `updated_at` is older than a threshold
-/// as failed and sets `finished_at`, preserving a clear error reason.
-async fn recover_stale_jobs(pool: &sqlx::PgPool) -> Result<()> {
-    // Thresholds can be tuned; keeping conservative defaults
-    // Crate jobs: 30 minutes
-    let crate_recovery = sqlx::query(
-        r"
-        UPDATE crate_jobs
-        SET status = 'failed',
-            finished_at = CURRENT_TIMESTAMP,
-            error = COALESCE(error, '') || CASE WHEN error IS NULL OR error = '' THEN '' ELSE E'\n' END ||
-                   'Recovery: marked failed on startup due to stale running (updated_at older than 30 minutes).'
-        WHERE status = 'running'
-          AND updated_at < CURRENT_TIMESTAMP - INTERVAL '30 minutes'
-        ",
-    )
-    .execute(pool)
-    .await?;
-
-    let crate_count = crate_recovery.rows_affected();
-
-    // Ingest jobs: 30 minutes
-    let ingest_recovery = sqlx::query(
-        r"
-        UPDATE ingest_jobs
-        SET status = 'failed',
-            finished_at = CURRENT_TIMESTAMP,
-            error = COALESCE(error, '') || CASE WHEN error IS NULL OR error = '' THEN '' ELSE E'\n' END ||
-                   'Recovery: marked failed on startup due to stale running (updated_at older than 30 minutes).'
-        WHERE status = 'running'
-          AND updated_at < CURRENT_TIMESTAMP - INTERVAL '30 minutes'
-        ",
-    )
-    .execute(pool)
-    .await?;
-
-    let ingest_count = ingest_recovery.rows_affected();
+/// This marks crate and ingest jobs in 'running' state whose `updated_at` is
+/// older than 30 minutes as failed and sets `finished_at`, preserving a clear
+/// error reason.
+async fn recover_stale_jobs(db_pool: &DatabasePool) -> Result<()> {
+    let crate_count = CrateJobProcessor::new(db_pool.clone())
+        .recover_stale_jobs(STALE_JOB_AGE, STALE_JOB_NOTE)
+        .await?;
+    let ingest_count = IngestJobProcessor::new(db_pool.clone())
+        .recover_stale_jobs(STALE_JOB_AGE, STALE_JOB_NOTE)
+        .await?;
 
     if crate_count > 0 || ingest_count > 0 {
         info!(
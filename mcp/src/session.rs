@@ -8,9 +8,10 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 use thiserror::Error;
-use tracing::{debug, error, warn};
+use tracing::{debug, warn};
 use uuid::Uuid;
 
+use crate::metrics::metrics;
 use crate::protocol_version::{ProtocolRegistry, SUPPORTED_PROTOCOL_VERSION};
 
 /// Client information extracted from request headers for security and audit purposes
@@ -377,6 +378,7 @@ impl SessionManager {
         let mut sessions = self.sessions.write().map_err(|_| SessionError::LockError)?;
 
         if sessions.remove(&session_id).is_some() {
+            metrics().forget_tool_concurrency_session(&session_id.to_string());
             debug!(
                 "Deleted session: {} (total: {})",
                 session_id,
@@ -401,6 +403,7 @@ impl SessionManager {
         sessions.retain(|id, session| {
             if session.is_expired() {
                 debug!("Cleaning up expired session: {}", id);
+                metrics().forget_tool_concurrency_session(&id.to_string());
                 false
             } else {
                 true
@@ -429,6 +432,19 @@ impl SessionManager {
         Ok(sessions.len())
     }
 
+    /// Snapshot of every stored session, oldest first
+    ///
+    /// # Errors
+    ///
+    /// Returns `SessionError::LockError` if the session storage cannot be accessed.
+    pub fn list_sessions(&self) -> Result<Vec<Session>, SessionError> {
+        let sessions = self.sessions.read().map_err(|_| SessionError::LockError)?;
+        let mut list: Vec<Session> = sessions.values().cloned().collect();
+        drop(sessions);
+        list.sort_by_key(|s| s.created_at);
+        Ok(list)
+    }
+
     /// Validate protocol version for an existing session
     ///
     /// # Errors
@@ -502,44 +518,6 @@ impl SessionManager {
         })
     }
 
-    /// Start background cleanup task
-    ///
-    /// This function spawns a tokio task that periodically cleans up expired sessions.
-    /// It should be called during server initialization.
-    pub fn start_cleanup_task(&self) {
-        let manager = self.clone();
-        let cleanup_interval = self.config.cleanup_interval;
-
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(std::time::Duration::from_secs(
-                cleanup_interval.num_seconds().max(1).unsigned_abs(),
-            ));
-
-            loop {
-                interval.tick().await;
-
-                match manager.cleanup_expired_sessions() {
-                    Ok(cleaned) => {
-                        if cleaned > 0 {
-                            debug!(
-                                "Background session cleanup: removed {} expired sessions",
-                                cleaned
-                            );
-                        }
-                    }
-                    Err(e) => {
-                        error!("Background session cleanup failed: {}", e);
-                    }
-                }
-            }
-        });
-
-        debug!(
-            "Started background session cleanup task (interval: {:?})",
-            cleanup_interval
-        );
-    }
-
     /// Get session configuration
     #[must_use]
     pub const fn config(&self) -> &SessionConfig {
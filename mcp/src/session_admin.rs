@@ -0,0 +1,147 @@
+//! Session listing and termination for operators
+//!
+//! `GET /admin/sessions` lists every session with its client and whether an
+//! SSE stream is attached to this replica. `DELETE /admin/sessions/{id}` and
+//! `DELETE /admin/sessions` terminate one or all sessions; attached streams
+//! receive a final `session_closed` event and are closed. All routes need
+//! the admin token, like `/admin/tools`.
+
+use crate::metrics::metrics;
+use crate::server::McpServerState;
+use crate::tool_switches::require_admin;
+use crate::transport::terminate_session;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+/// How often the session gauges are refreshed in the background
+const GAUGE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Update the open and SSE-attached session gauges
+///
+/// Returns the number of open sessions and of those with a stream attached.
+pub fn refresh_session_gauges(state: &McpServerState) -> (usize, usize) {
+    let sessions = match state.comprehensive_session_manager.list_sessions() {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            error!("Failed to read sessions for gauges: {}", e);
+            return (0, 0);
+        }
+    };
+    let streams = state.sse_hub.active_streams();
+    let attached = sessions
+        .iter()
+        .filter(|s| streams.contains(&s.session_id))
+        .count();
+    metrics().set_session_gauges(sessions.len(), attached);
+    (sessions.len(), attached)
+}
+
+/// Refresh the session gauges every [`GAUGE_INTERVAL`]
+pub fn start_gauge_task(state: McpServerState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(GAUGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            let (open, attached) = refresh_session_gauges(&state);
+            debug!("Sessions: {} open, {} with SSE attached", open, attached);
+        }
+    });
+}
+
+/// `GET /admin/sessions`: every session, oldest first
+///
+/// # Errors
+///
+/// Returns 401 without the admin token, 403 when no admin token is
+/// configured and 500 if the session store cannot be read.
+pub async fn list_sessions_handler(
+    State(state): State<McpServerState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let sessions = state
+        .comprehensive_session_manager
+        .list_sessions()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let streams = state.sse_hub.active_streams();
+
+    let entries: Vec<Value> = sessions
+        .iter()
+        .map(|s| {
+            json!({
+                "session_id": s.session_id,
+                "created_at": s.created_at,
+                "last_accessed": s.last_accessed,
+                "user_agent": s.client_info.user_agent,
+                "origin": s.client_info.origin,
+                "protocol_version": s.protocol_version,
+                "sse_attached": streams.contains(&s.session_id),
+            })
+        })
+        .collect();
+    let attached = entries
+        .iter()
+        .filter(|e| e["sse_attached"] == json!(true))
+        .count();
+    metrics().set_session_gauges(entries.len(), attached);
+
+    Ok(Json(json!({
+        "total": entries.len(),
+        "sse_attached": attached,
+        "sessions": entries,
+    })))
+}
+
+/// `DELETE /admin/sessions/{id}`: terminate one session
+///
+/// # Errors
+///
+/// Returns 401 without the admin token, 403 when no admin token is
+/// configured and 404 for unknown sessions.
+pub async fn terminate_session_handler(
+    State(state): State<McpServerState>,
+    Path(session_id): Path<Uuid>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    if !terminate_session(&state, session_id).await {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("Unknown session: {session_id}"),
+        ));
+    }
+    refresh_session_gauges(&state);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// `DELETE /admin/sessions`: terminate every session
+///
+/// # Errors
+///
+/// Returns 401 without the admin token, 403 when no admin token is
+/// configured and 500 if the session store cannot be read.
+pub async fn terminate_all_sessions_handler(
+    State(state): State<McpServerState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let sessions = state
+        .comprehensive_session_manager
+        .list_sessions()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut terminated = 0;
+    for session in sessions {
+        if terminate_session(&state, session.session_id).await {
+            terminated += 1;
+        }
+    }
+    info!("Terminated {} sessions through the admin API", terminated);
+    refresh_session_gauges(&state);
+    Ok(Json(json!({ "terminated": terminated })))
+}
@@ -0,0 +1,388 @@
+//! Result snippets for the query tools
+//!
+//! A snippet is the window of a document's content holding the most distinct
+//! matched terms, with those terms marked. PostgreSQL full-text search
+//! supplies the matched words when it can (`ts_headline` knows stemmed forms
+//! such as `spawned` for `spawn`); otherwise the query's own terms are looked
+//! for in the content. Documents without any match, like pure vector hits,
+//! show their first characters.
+
+use anyhow::{anyhow, Result};
+use db::{DocumentQueries, DocumentSourceQueries, FtsSettings};
+use serde_json::{json, Map, Value};
+use sqlx::PgPool;
+use tracing::debug;
+
+/// Smallest `snippet_chars` a call may ask for
+pub const MIN_SNIPPET_CHARS: usize = 100;
+
+/// Largest `snippet_chars` a call may ask for
+pub const MAX_SNIPPET_CHARS: usize = 4_000;
+
+/// Occurrences of one term considered when choosing the window
+const MAX_MATCHES_PER_TERM: usize = 50;
+
+/// How matched terms are marked in a snippet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Highlight {
+    /// `**term**`
+    #[default]
+    Markdown,
+    /// `<em>term</em>`
+    Html,
+    /// No markers
+    None,
+}
+
+impl Highlight {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "markdown" => Some(Self::Markdown),
+            "html" => Some(Self::Html),
+            "none" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    const fn markers(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Markdown => ("**", "**"),
+            Self::Html => ("<em>", "</em>"),
+            Self::None => ("", ""),
+        }
+    }
+}
+
+/// Length and highlighting of the snippets in one response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnippetOptions {
+    /// Snippet length in characters, not counting markers and ellipses
+    pub max_chars: usize,
+    pub highlight: Highlight,
+}
+
+impl SnippetOptions {
+    /// Read `snippet_chars` and `highlight` from tool arguments
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `snippet_chars` is outside
+    /// [`MIN_SNIPPET_CHARS`]..=[`MAX_SNIPPET_CHARS`] or `highlight` is not
+    /// `markdown`, `html` or `none`.
+    pub fn from_arguments(arguments: &Value, default_chars: usize) -> Result<Self> {
+        let max_chars = match arguments.get("snippet_chars").and_then(Value::as_u64) {
+            Some(chars) => usize::try_from(chars)
+                .ok()
+                .filter(|c| (MIN_SNIPPET_CHARS..=MAX_SNIPPET_CHARS).contains(c))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "snippet_chars must be between {MIN_SNIPPET_CHARS} and {MAX_SNIPPET_CHARS}"
+                    )
+                })?,
+            None => default_chars,
+        };
+        let highlight = match arguments.get("highlight").and_then(Value::as_str) {
+            Some(style) => Highlight::parse(style).ok_or_else(|| {
+                anyhow!("highlight must be one of 'markdown', 'html' or 'none', got '{style}'")
+            })?,
+            None => Highlight::default(),
+        };
+        Ok(Self {
+            max_chars,
+            highlight,
+        })
+    }
+}
+
+/// Input schema properties for `snippet_chars` and `highlight`
+#[must_use]
+pub fn snippet_properties(default_chars: usize) -> Map<String, Value> {
+    let properties = json!({
+        "snippet_chars": {
+            "type": "integer",
+            "description": format!("Length of each result snippet in characters (default: {default_chars})"),
+            "minimum": MIN_SNIPPET_CHARS,
+            "maximum": MAX_SNIPPET_CHARS
+        },
+        "highlight": {
+            "type": "string",
+            "description": "How matched terms are marked in snippets: 'markdown' (**term**, default), 'html' (<em>term</em>) or 'none'",
+            "enum": ["markdown", "html", "none"]
+        }
+    });
+    match properties {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    }
+}
+
+/// Split a query into lowercase search terms, ignoring very short words
+#[must_use]
+pub fn query_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for term in query
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|t| t.len() >= 3)
+        .map(str::to_ascii_lowercase)
+    {
+        if !terms.contains(&term) {
+            terms.push(term);
+        }
+    }
+
+    if terms.is_empty() {
+        let trimmed = query.trim().to_ascii_lowercase();
+        if !trimmed.is_empty() {
+            terms.push(trimmed);
+        }
+    }
+
+    terms
+}
+
+/// Terms to mark in each of `contents` of `doc_type`: the words full-text
+/// search matched in the doc type's language, or the query terms where it
+/// matched nothing or is unavailable
+pub async fn match_terms(
+    pool: &PgPool,
+    doc_type: &str,
+    query: &str,
+    contents: &[&str],
+) -> Vec<Vec<String>> {
+    let fallback = query_terms(query);
+    let fts = DocumentSourceQueries::fts_settings(pool, doc_type)
+        .await
+        .unwrap_or_else(|e| {
+            debug!(
+                "Search settings unavailable, highlighting in the default language: {}",
+                e
+            );
+            FtsSettings::default()
+        });
+    match DocumentQueries::headline_terms(pool, &fts, query, contents).await {
+        Ok(per_content) => per_content
+            .into_iter()
+            .map(|words| {
+                if words.is_empty() {
+                    fallback.clone()
+                } else {
+                    words
+                }
+            })
+            .collect(),
+        Err(e) => {
+            debug!("ts_headline unavailable, scanning for query terms: {}", e);
+            vec![fallback; contents.len()]
+        }
+    }
+}
+
+/// Byte offset `n` characters after `from`, or the end of `text`
+fn advance_chars(text: &str, from: usize, n: usize) -> usize {
+    text[from..]
+        .char_indices()
+        .nth(n)
+        .map_or(text.len(), |(i, _)| from + i)
+}
+
+/// Byte offset up to `n` characters before `from`
+fn retreat_chars(text: &str, from: usize, n: usize) -> usize {
+    if n == 0 {
+        return from;
+    }
+    text[..from]
+        .char_indices()
+        .rev()
+        .take(n)
+        .last()
+        .map_or(from, |(i, _)| i)
+}
+
+/// Non-overlapping `(start, end, term)` byte ranges of `terms` in `content`,
+/// in order, preferring the longest term where two start together
+fn term_matches(content: &str, terms: &[String]) -> Vec<(usize, usize, usize)> {
+    // ASCII lowercasing keeps byte offsets aligned with `content`
+    let lower = content.to_ascii_lowercase();
+    let mut found: Vec<(usize, usize, usize)> = Vec::new();
+    for (index, term) in terms.iter().enumerate() {
+        let term = term.to_ascii_lowercase();
+        if term.is_empty() {
+            continue;
+        }
+        found.extend(
+            lower
+                .match_indices(term.as_str())
+                .take(MAX_MATCHES_PER_TERM)
+                .map(|(pos, _)| (pos, pos + term.len(), index)),
+        );
+    }
+    found.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+
+    let mut matches = Vec::with_capacity(found.len());
+    let mut covered = 0;
+    for m in found {
+        if m.0 >= covered {
+            covered = m.1;
+            matches.push(m);
+        }
+    }
+    matches
+}
+
+/// Up to `options.max_chars` characters of `content` around the densest
+/// cluster of `terms`, with the terms marked
+///
+/// The window starts a quarter of its length before the first match of the
+/// cluster; without any match it is the start of the content. Ellipses show
+/// where content was cut.
+#[must_use]
+pub fn build_snippet(content: &str, terms: &[String], options: SnippetOptions) -> String {
+    let max_chars = options.max_chars.max(1);
+    let matches = term_matches(content, terms);
+
+    // (distinct terms in the window, byte offset of its first match)
+    let mut best: Option<(usize, usize)> = None;
+    for (i, &(pos, _, _)) in matches.iter().enumerate() {
+        let window_end = advance_chars(content, pos, max_chars);
+        let mut distinct: Vec<usize> = matches[i..]
+            .iter()
+            .take_while(|m| m.1 <= window_end)
+            .map(|m| m.2)
+            .collect();
+        distinct.sort_unstable();
+        distinct.dedup();
+        if best.is_none_or(|(hits, _)| distinct.len() > hits) {
+            best = Some((distinct.len(), pos));
+        }
+    }
+
+    let mut start = best.map_or(0, |(_, pos)| retreat_chars(content, pos, max_chars / 4));
+    let mut end = advance_chars(content, start, max_chars);
+    let window = &content[start..end];
+    start += window.len() - window.trim_start().len();
+    end -= window.len() - window.trim_end().len();
+    if start > end {
+        start = end;
+    }
+
+    let (open, close) = options.highlight.markers();
+    let mut out = String::with_capacity(end - start + 6);
+    if !content[..start].trim().is_empty() {
+        out.push_str("...");
+    }
+    let mut cursor = start;
+    for &(m_start, m_end, _) in &matches {
+        if m_start < start || m_end > end {
+            continue;
+        }
+        out.push_str(&content[cursor..m_start]);
+        out.push_str(open);
+        out.push_str(&content[m_start..m_end]);
+        out.push_str(close);
+        cursor = m_end;
+    }
+    out.push_str(&content[cursor..end]);
+    if !content[end..].trim().is_empty() {
+        out.push_str("...");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_snippet, query_terms, Highlight, SnippetOptions};
+    use serde_json::json;
+
+    fn options(max_chars: usize, highlight: Highlight) -> SnippetOptions {
+        SnippetOptions {
+            max_chars,
+            highlight,
+        }
+    }
+
+    fn terms(words: &[&str]) -> Vec<String> {
+        words.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_window_covers_the_densest_cluster() {
+        let content = format!(
+            "{} Spawn once here. {} Spawn a blocking task on the pool.",
+            "filler ".repeat(20),
+            "padding ".repeat(20)
+        );
+        let snippet = build_snippet(
+            &content,
+            &terms(&["spawn", "task"]),
+            options(40, Highlight::Markdown),
+        );
+        assert!(snippet.starts_with("..."), "{snippet}");
+        assert!(
+            snippet.contains("**Spawn** a blocking **task** on"),
+            "{snippet}"
+        );
+        assert!(!snippet.contains("once"), "{snippet}");
+    }
+
+    #[test]
+    fn test_markers_follow_the_highlight_style() {
+        let content = "Spawn a task. Tasks are spawned lazily.";
+        let words = terms(&["spawn", "spawned", "task"]);
+        assert_eq!(
+            build_snippet(content, &words, options(200, Highlight::Html)),
+            "<em>Spawn</em> a <em>task</em>. <em>Task</em>s are <em>spawned</em> lazily."
+        );
+        assert_eq!(
+            build_snippet(content, &words, options(200, Highlight::None)),
+            content
+        );
+    }
+
+    #[test]
+    fn test_no_match_falls_back_to_the_start() {
+        let content = "The runtime drives futures to completion. ".repeat(10);
+        let snippet = build_snippet(
+            &content,
+            &terms(&["channel"]),
+            options(30, Highlight::Markdown),
+        );
+        assert_eq!(snippet, "The runtime drives futures to...");
+    }
+
+    #[test]
+    fn test_multibyte_content_is_sliced_on_char_boundaries() {
+        let content = format!(
+            "{} émoji 🦀 crab — Ünïcode spawn 🦀🦀 ✓ {}",
+            "🎉".repeat(50),
+            "ß".repeat(50)
+        );
+        for max_chars in 1..60 {
+            let snippet = build_snippet(
+                &content,
+                &terms(&["spawn", "crab"]),
+                options(max_chars, Highlight::Markdown),
+            );
+            let visible = snippet.trim_matches('.').replace("**", "");
+            assert!(visible.chars().count() <= max_chars, "{snippet}");
+        }
+        let snippet = build_snippet(&content, &terms(&["spawn"]), options(20, Highlight::Html));
+        assert!(snippet.contains("<em>spawn</em>"), "{snippet}");
+    }
+
+    #[test]
+    fn test_options_are_read_from_arguments() {
+        let parsed = SnippetOptions::from_arguments(
+            &json!({"snippet_chars": 200, "highlight": "html"}),
+            600,
+        )
+        .unwrap();
+        assert_eq!(parsed, options(200, Highlight::Html));
+        assert_eq!(
+            SnippetOptions::from_arguments(&json!({}), 600).unwrap(),
+            options(600, Highlight::Markdown)
+        );
+        assert!(SnippetOptions::from_arguments(&json!({"snippet_chars": 5}), 600).is_err());
+        assert!(SnippetOptions::from_arguments(&json!({"highlight": "bold"}), 600).is_err());
+        assert_eq!(query_terms("Spawn a task"), ["spawn", "task"]);
+    }
+}
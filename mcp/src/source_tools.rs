@@ -0,0 +1,283 @@
+//! Document source management tools for MCP
+//!
+//! `document_sources` rows are created implicitly by ingestion; these tools
+//! list them, switch them on and off for search, and delete them together
+//! with their documents.
+
+use crate::query_cache::query_cache;
+use crate::tools::{continuations, response_soft_cap, ExecutionContext, Tool};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use db::{DatabasePool, DocumentSourceQueries, DocumentSourceSummary};
+use serde_json::{json, Map, Value};
+
+/// Maximum sources returned per `list_doc_sources` page
+const MAX_SOURCE_PAGE_SIZE: i64 = 200;
+
+/// Read a required, non-empty string argument
+fn required_str<'a>(arguments: &'a Value, key: &str) -> Result<&'a str> {
+    arguments
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| anyhow!("Missing required parameter: {key}"))
+}
+
+/// Compact view of a source config: scalars as-is, nested values by size
+fn summarize_config(config: &Value) -> Value {
+    let Some(fields) = config.as_object() else {
+        return config.clone();
+    };
+    fields
+        .iter()
+        .map(|(key, value)| {
+            let summary = match value {
+                Value::Object(map) => json!(format!("{{{} keys}}", map.len())),
+                Value::Array(items) => json!(format!("[{} items]", items.len())),
+                scalar => scalar.clone(),
+            };
+            (key.clone(), summary)
+        })
+        .collect::<Map<_, _>>()
+        .into()
+}
+
+/// JSON form of a source as returned by the tools
+fn source_json(source: &DocumentSourceSummary) -> Value {
+    json!({
+        "doc_type": source.doc_type,
+        "source_name": source.source_name,
+        "enabled": source.enabled,
+        "document_count": source.document_count,
+        "config": summarize_config(&source.config),
+        "updated_at": source.updated_at,
+    })
+}
+
+/// List document sources with their document counts
+pub struct ListDocSourcesTool {
+    db_pool: DatabasePool,
+}
+
+impl ListDocSourcesTool {
+    /// Create a new source listing tool
+    #[must_use]
+    pub const fn new(db_pool: DatabasePool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl Tool for ListDocSourcesTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "list_doc_sources",
+            "description": "List document sources with doc_type, source_name, enabled flag, a config summary and the number of stored documents, as JSON.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "doc_type": {
+                        "type": "string",
+                        "description": "Only sources of this doc type (optional)"
+                    },
+                    "page": {
+                        "type": "integer",
+                        "description": "Page number, starting at 1 (default: 1)",
+                        "minimum": 1
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Sources per page (default: 50, max: 200)",
+                        "minimum": 1,
+                        "maximum": MAX_SOURCE_PAGE_SIZE
+                    }
+                },
+                "required": []
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        self.execute_with_context(arguments, &ExecutionContext::default())
+            .await
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ExecutionContext,
+    ) -> Result<String> {
+        let doc_type = arguments.get("doc_type").and_then(Value::as_str);
+        let page = arguments
+            .get("page")
+            .and_then(Value::as_i64)
+            .unwrap_or(1)
+            .max(1);
+        let limit = arguments
+            .get("limit")
+            .and_then(Value::as_i64)
+            .unwrap_or(50)
+            .clamp(1, MAX_SOURCE_PAGE_SIZE);
+
+        // Fetch one extra row to tell whether another page follows
+        let mut sources = DocumentSourceQueries::list_sources(
+            self.db_pool.pool(),
+            doc_type,
+            limit + 1,
+            (page - 1) * limit,
+        )
+        .await?;
+        let has_more = sources.len() > usize::try_from(limit).unwrap_or(usize::MAX);
+        sources.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+
+        let mut envelope = Map::new();
+        envelope.insert("page".to_string(), json!(page));
+        envelope.insert("limit".to_string(), json!(limit));
+        envelope.insert("has_more".to_string(), json!(has_more));
+        let response = continuations().paginate_items(
+            context.session_id(),
+            envelope,
+            "sources",
+            sources.iter().map(source_json).collect(),
+            response_soft_cap(),
+        );
+        Ok(serde_json::to_string_pretty(&response)?)
+    }
+}
+
+/// Enable or disable a document source for search
+pub struct SetDocSourceEnabledTool {
+    db_pool: DatabasePool,
+}
+
+impl SetDocSourceEnabledTool {
+    /// Create a new source toggle tool
+    #[must_use]
+    pub const fn new(db_pool: DatabasePool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl Tool for SetDocSourceEnabledTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "set_doc_source_enabled",
+            "description": "Enable or disable a document source. Documents of a disabled source stay stored but are excluded from search results.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "doc_type": {
+                        "type": "string",
+                        "description": "Doc type of the source"
+                    },
+                    "source_name": {
+                        "type": "string",
+                        "description": "Name of the source"
+                    },
+                    "enabled": {
+                        "type": "boolean",
+                        "description": "Whether the source's documents are searchable"
+                    }
+                },
+                "required": ["doc_type", "source_name", "enabled"]
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        let doc_type = required_str(&arguments, "doc_type")?;
+        let source_name = required_str(&arguments, "source_name")?;
+        let enabled = arguments
+            .get("enabled")
+            .and_then(Value::as_bool)
+            .ok_or_else(|| anyhow!("Missing required parameter: enabled"))?;
+
+        let pool = self.db_pool.pool();
+        if !DocumentSourceQueries::set_enabled(pool, doc_type, source_name, enabled).await? {
+            return Err(anyhow!(
+                "Document source not found: {doc_type}/{source_name}"
+            ));
+        }
+        query_cache().invalidate_doc_type(doc_type);
+        let source = DocumentSourceQueries::find_source(pool, doc_type, source_name)
+            .await?
+            .ok_or_else(|| anyhow!("Document source not found: {doc_type}/{source_name}"))?;
+
+        Ok(serde_json::to_string_pretty(&source_json(&source))?)
+    }
+}
+
+/// Delete a document source together with its documents
+pub struct DeleteDocSourceTool {
+    db_pool: DatabasePool,
+}
+
+impl DeleteDocSourceTool {
+    /// Create a new source deletion tool
+    #[must_use]
+    pub const fn new(db_pool: DatabasePool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl Tool for DeleteDocSourceTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "delete_doc_source",
+            "description": "Delete a document source and all of its documents in one transaction. Refused while ingest or crate jobs for the source are queued or running. Use dry_run to see what would be removed.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "doc_type": {
+                        "type": "string",
+                        "description": "Doc type of the source"
+                    },
+                    "source_name": {
+                        "type": "string",
+                        "description": "Name of the source"
+                    },
+                    "dry_run": {
+                        "type": "boolean",
+                        "description": "Report the documents that would be deleted without deleting anything (default: false)"
+                    }
+                },
+                "required": ["doc_type", "source_name"]
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        let doc_type = required_str(&arguments, "doc_type")?;
+        let source_name = required_str(&arguments, "source_name")?;
+        let dry_run = arguments
+            .get("dry_run")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        let deletion = DocumentSourceQueries::delete_source(
+            self.db_pool.pool(),
+            doc_type,
+            source_name,
+            dry_run,
+        )
+        .await?
+        .ok_or_else(|| anyhow!("Document source not found: {doc_type}/{source_name}"))?;
+
+        if !dry_run && !deletion.deleted {
+            return Err(anyhow!(
+                "Document source {doc_type}/{source_name} has {} active job(s); wait for them to finish before deleting it",
+                deletion.active_jobs
+            ));
+        }
+        if deletion.deleted {
+            query_cache().invalidate_doc_type(doc_type);
+        }
+
+        let mut response = serde_json::to_value(&deletion)?;
+        response["dry_run"] = json!(dry_run);
+        Ok(serde_json::to_string_pretty(&response)?)
+    }
+}
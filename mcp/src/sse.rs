@@ -0,0 +1,451 @@
+//! Per-session SSE event hubs with replay
+//!
+//! Messages sent to a session's SSE stream get a per-session event ID and are
+//! kept in a capped buffer, so a client reconnecting with `Last-Event-ID`
+//! receives what it missed. [`InMemorySseHub`] keeps the buffer in the
+//! process and is the default. [`RedisSseHub`] keeps it in a Redis Stream per
+//! session, so replay works across pod restarts and replicas; it is selected
+//! with `USE_REDIS_QUEUE`/`REDIS_URL` like the Redis job queue.
+
+use crate::queue::{redis_url_from_env, use_redis_queue};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, RwLock};
+use tokio::sync::{broadcast, mpsc, OnceCell};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Messages kept per session for replay
+pub const MAX_BUFFER: usize = 256;
+
+/// SSE message structure for future streaming support
+#[derive(Debug, Clone)]
+pub struct SseMessage {
+    pub id: Option<String>,
+    pub event: Option<String>,
+    pub data: String,
+}
+
+/// Next item on a live subscription
+#[derive(Debug)]
+pub enum SubscriptionEvent {
+    Message(SseMessage),
+    /// Messages were skipped because the subscriber fell behind
+    Lagged,
+    /// The hub stopped delivering messages
+    Closed,
+}
+
+/// Live messages published to a session after subscribing
+pub struct SseSubscription {
+    inner: SubscriptionInner,
+}
+
+enum SubscriptionInner {
+    Local(broadcast::Receiver<SseMessage>),
+    Forwarded(mpsc::Receiver<SseMessage>),
+}
+
+impl SseSubscription {
+    /// Wait for the next live message
+    pub async fn recv(&mut self) -> SubscriptionEvent {
+        match &mut self.inner {
+            SubscriptionInner::Local(rx) => match rx.recv().await {
+                Ok(msg) => SubscriptionEvent::Message(msg),
+                Err(broadcast::error::RecvError::Lagged(_)) => SubscriptionEvent::Lagged,
+                Err(broadcast::error::RecvError::Closed) => SubscriptionEvent::Closed,
+            },
+            SubscriptionInner::Forwarded(rx) => rx
+                .recv()
+                .await
+                .map_or(SubscriptionEvent::Closed, SubscriptionEvent::Message),
+        }
+    }
+}
+
+/// Per-session message hub behind the SSE transport
+///
+/// Event IDs increase monotonically per session. Subscribing before taking
+/// the replay snapshot may deliver a message both ways; callers skip live
+/// messages whose ID was already replayed.
+#[async_trait]
+pub trait SseHub: Send + Sync {
+    /// Buffer `msg` for `session_id` and deliver it to live subscribers, returning its event ID
+    async fn publish(&self, session_id: Uuid, msg: SseMessage) -> Result<u64>;
+
+    /// Buffered messages with an event ID greater than `last_id`, oldest first
+    async fn snapshot_from(
+        &self,
+        session_id: Uuid,
+        last_id: Option<u64>,
+    ) -> Result<Vec<(u64, SseMessage)>>;
+
+    /// Subscribe to messages published to `session_id` from now on
+    async fn subscribe(&self, session_id: Uuid) -> Result<SseSubscription>;
+
+    /// Sessions with a live subscription in this process
+    fn active_streams(&self) -> HashSet<Uuid>;
+}
+
+/// Select the hub from the environment: Redis when the Redis queue is enabled
+///
+/// # Errors
+///
+/// Returns an error if the Redis hub is selected and `REDIS_URL` is invalid.
+pub fn hub_from_env() -> Result<Arc<dyn SseHub>> {
+    if use_redis_queue() {
+        info!("Using Redis-backed SSE replay buffer");
+        Ok(Arc::new(RedisSseHub::new(&redis_url_from_env())?))
+    } else {
+        Ok(Arc::new(InMemorySseHub::new()))
+    }
+}
+
+fn event_id(id: &str) -> Option<u64> {
+    id.split('-').next()?.parse().ok()
+}
+
+/// Buffer and live channel of one session
+#[derive(Debug)]
+struct SessionStream {
+    sender: broadcast::Sender<SseMessage>,
+    buffer: VecDeque<(u64, SseMessage)>,
+    next_id: u64,
+}
+
+impl SessionStream {
+    fn new() -> Self {
+        let (sender, _) = broadcast::channel(MAX_BUFFER);
+        Self {
+            sender,
+            buffer: VecDeque::with_capacity(MAX_BUFFER),
+            next_id: 1,
+        }
+    }
+}
+
+/// In-memory SSE hub for per-session message broadcasting with replay buffer
+#[derive(Debug, Default)]
+pub struct InMemorySseHub {
+    sessions: RwLock<HashMap<Uuid, Arc<RwLock<SessionStream>>>>,
+}
+
+impl InMemorySseHub {
+    /// Create an empty hub
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn get_or_create(&self, session_id: Uuid) -> Arc<RwLock<SessionStream>> {
+        if let Ok(map) = self.sessions.read() {
+            if let Some(s) = map.get(&session_id) {
+                return Arc::clone(s);
+            }
+        }
+        let new_stream = Arc::new(RwLock::new(SessionStream::new()));
+        if let Ok(mut map) = self.sessions.write() {
+            let entry = map
+                .entry(session_id)
+                .or_insert_with(|| Arc::clone(&new_stream));
+            Arc::clone(entry)
+        } else {
+            new_stream
+        }
+    }
+}
+
+#[async_trait]
+impl SseHub for InMemorySseHub {
+    async fn publish(&self, session_id: Uuid, mut msg: SseMessage) -> Result<u64> {
+        let s = self.get_or_create(session_id);
+        let mut ss = s
+            .write()
+            .map_err(|_| anyhow!("SSE session buffer poisoned"))?;
+        let id = ss.next_id;
+        ss.next_id = ss.next_id.saturating_add(1);
+        msg.id = Some(id.to_string());
+        // buffer and trim
+        ss.buffer.push_back((id, msg.clone()));
+        while ss.buffer.len() > MAX_BUFFER {
+            ss.buffer.pop_front();
+        }
+        // best-effort broadcast
+        let _ = ss.sender.send(msg);
+        Ok(id)
+    }
+
+    async fn snapshot_from(
+        &self,
+        session_id: Uuid,
+        last_id: Option<u64>,
+    ) -> Result<Vec<(u64, SseMessage)>> {
+        let s = self.get_or_create(session_id);
+        let ss = s
+            .read()
+            .map_err(|_| anyhow!("SSE session buffer poisoned"))?;
+        let start_after = last_id.unwrap_or(0);
+        Ok(ss
+            .buffer
+            .iter()
+            .filter(|(id, _)| *id > start_after)
+            .cloned()
+            .collect())
+    }
+
+    async fn subscribe(&self, session_id: Uuid) -> Result<SseSubscription> {
+        let s = self.get_or_create(session_id);
+        let ss = s
+            .read()
+            .map_err(|_| anyhow!("SSE session buffer poisoned"))?;
+        Ok(SseSubscription {
+            inner: SubscriptionInner::Local(ss.sender.subscribe()),
+        })
+    }
+
+    fn active_streams(&self) -> HashSet<Uuid> {
+        let Ok(map) = self.sessions.read() else {
+            return HashSet::new();
+        };
+        map.iter()
+            .filter(|(_, s)| s.read().is_ok_and(|ss| ss.sender.receiver_count() > 0))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+}
+
+/// Seconds a session's stream and counter are kept after the last publish
+const REDIS_STREAM_TTL_SECS: u64 = 24 * 60 * 60;
+
+/// Milliseconds each `XREAD` blocks before checking whether the subscriber left
+const REDIS_BLOCK_MS: u64 = 5_000;
+
+/// Assign the next event ID and append to the capped stream in one step
+///
+/// Entry IDs are `<event id>-0`, so stream order matches event ID order even
+/// with several replicas publishing to the same session.
+const PUBLISH_SCRIPT: &str = r"
+local id = redis.call('INCR', KEYS[2])
+redis.call('XADD', KEYS[1], 'MAXLEN', ARGV[1], id .. '-0', 'event', ARGV[2], 'data', ARGV[3])
+redis.call('EXPIRE', KEYS[1], ARGV[4])
+redis.call('EXPIRE', KEYS[2], ARGV[4])
+return id
+";
+
+/// Stream entries as returned by `XRANGE`: ID and flat field/value list
+type StreamEntries = Vec<(String, Vec<String>)>;
+
+/// SSE hub keeping each session's buffer in a Redis Stream
+///
+/// Keys are `sse:{<session>}` for the stream and `sse:{<session>}:seq` for
+/// the event ID counter; the hash tag keeps both in one cluster slot.
+pub struct RedisSseHub {
+    client: redis::Client,
+    connection: OnceCell<redis::aio::ConnectionManager>,
+    publish_script: redis::Script,
+    /// Forwarding channels of subscriptions made here, closed once dropped
+    subscribers: RwLock<Vec<(Uuid, mpsc::Sender<SseMessage>)>>,
+}
+
+impl RedisSseHub {
+    /// Create a hub for the Redis server at `url`; connects on first use
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `url` is not a valid Redis URL.
+    pub fn new(url: &str) -> Result<Self> {
+        Ok(Self {
+            client: redis::Client::open(url)?,
+            connection: OnceCell::new(),
+            publish_script: redis::Script::new(PUBLISH_SCRIPT),
+            subscribers: RwLock::new(Vec::new()),
+        })
+    }
+
+    fn stream_key(session_id: Uuid) -> String {
+        format!("sse:{{{session_id}}}")
+    }
+
+    fn counter_key(session_id: Uuid) -> String {
+        format!("sse:{{{session_id}}}:seq")
+    }
+
+    async fn connection(&self) -> Result<redis::aio::ConnectionManager> {
+        let connection = self
+            .connection
+            .get_or_try_init(|| redis::aio::ConnectionManager::new(self.client.clone()))
+            .await?;
+        Ok(connection.clone())
+    }
+
+    fn parse_entry((id, fields): (String, Vec<String>)) -> Option<(u64, SseMessage)> {
+        let id = event_id(&id)?;
+        let mut event = None;
+        let mut data = String::new();
+        let mut fields = fields.into_iter();
+        while let (Some(name), Some(value)) = (fields.next(), fields.next()) {
+            match name.as_str() {
+                "event" if !value.is_empty() => event = Some(value),
+                "data" => data = value,
+                _ => {}
+            }
+        }
+        Some((
+            id,
+            SseMessage {
+                id: Some(id.to_string()),
+                event,
+                data,
+            },
+        ))
+    }
+}
+
+#[async_trait]
+impl SseHub for RedisSseHub {
+    async fn publish(&self, session_id: Uuid, msg: SseMessage) -> Result<u64> {
+        let mut con = self.connection().await?;
+        let id: u64 = self
+            .publish_script
+            .key(Self::stream_key(session_id))
+            .key(Self::counter_key(session_id))
+            .arg(MAX_BUFFER)
+            .arg(msg.event.as_deref().unwrap_or_default())
+            .arg(&msg.data)
+            .arg(REDIS_STREAM_TTL_SECS)
+            .invoke_async(&mut con)
+            .await?;
+        Ok(id)
+    }
+
+    async fn snapshot_from(
+        &self,
+        session_id: Uuid,
+        last_id: Option<u64>,
+    ) -> Result<Vec<(u64, SseMessage)>> {
+        let mut con = self.connection().await?;
+        let start = last_id.map_or_else(|| "-".to_string(), |id| format!("({id}-0"));
+        let entries: StreamEntries = redis::cmd("XRANGE")
+            .arg(Self::stream_key(session_id))
+            .arg(start)
+            .arg("+")
+            .query_async(&mut con)
+            .await?;
+        Ok(entries.into_iter().filter_map(Self::parse_entry).collect())
+    }
+
+    async fn subscribe(&self, session_id: Uuid) -> Result<SseSubscription> {
+        // Read from the current last event so nothing published after this call is missed
+        let mut con = self.connection().await?;
+        let current: Option<u64> = redis::cmd("GET")
+            .arg(Self::counter_key(session_id))
+            .query_async(&mut con)
+            .await?;
+
+        // Blocking reads get their own connection so they never stall publishers
+        let mut reader = self.client.get_multiplexed_async_connection().await?;
+        let key = Self::stream_key(session_id);
+        let (tx, rx) = mpsc::channel(MAX_BUFFER);
+        if let Ok(mut subscribers) = self.subscribers.write() {
+            subscribers.retain(|(_, tx)| !tx.is_closed());
+            subscribers.push((session_id, tx.clone()));
+        }
+        tokio::spawn(async move {
+            let mut last = current.unwrap_or(0);
+            while !tx.is_closed() {
+                let reply: redis::RedisResult<Option<Vec<(String, StreamEntries)>>> =
+                    redis::cmd("XREAD")
+                        .arg("BLOCK")
+                        .arg(REDIS_BLOCK_MS)
+                        .arg("STREAMS")
+                        .arg(&key)
+                        .arg(format!("{last}-0"))
+                        .query_async(&mut reader)
+                        .await;
+                let streams = match reply {
+                    Ok(streams) => streams.unwrap_or_default(),
+                    Err(e) => {
+                        warn!("SSE stream read for session {} failed: {}", session_id, e);
+                        break;
+                    }
+                };
+                for (id, msg) in streams
+                    .into_iter()
+                    .flat_map(|(_, entries)| entries)
+                    .filter_map(Self::parse_entry)
+                {
+                    last = id;
+                    if tx.send(msg).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            debug!("Stopped SSE stream reader for session {}", session_id);
+        });
+
+        Ok(SseSubscription {
+            inner: SubscriptionInner::Forwarded(rx),
+        })
+    }
+
+    fn active_streams(&self) -> HashSet<Uuid> {
+        let Ok(mut subscribers) = self.subscribers.write() else {
+            return HashSet::new();
+        };
+        subscribers.retain(|(_, tx)| !tx.is_closed());
+        subscribers.iter().map(|(id, _)| *id).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(data: &str) -> SseMessage {
+        SseMessage {
+            id: None,
+            event: Some("message".to_string()),
+            data: data.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_replay_after_last_event_id() {
+        let hub = InMemorySseHub::new();
+        let session_id = Uuid::new_v4();
+        for data in ["a", "b", "c"] {
+            hub.publish(session_id, message(data)).await.unwrap();
+        }
+
+        let replay = hub.snapshot_from(session_id, Some(1)).await.unwrap();
+        let ids: Vec<u64> = replay.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![2, 3]);
+        assert_eq!(replay[0].1.data, "b");
+
+        let mut live = hub.subscribe(session_id).await.unwrap();
+        assert_eq!(hub.publish(session_id, message("d")).await.unwrap(), 4);
+        match live.recv().await {
+            SubscriptionEvent::Message(msg) => assert_eq!(msg.id.as_deref(), Some("4")),
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_stream_entry() {
+        let entry = (
+            "7-0".to_string(),
+            vec![
+                "event".to_string(),
+                String::new(),
+                "data".to_string(),
+                "{}".to_string(),
+            ],
+        );
+        let (id, msg) = RedisSseHub::parse_entry(entry).unwrap();
+        assert_eq!(id, 7);
+        assert_eq!(msg.id.as_deref(), Some("7"));
+        assert!(msg.event.is_none());
+        assert_eq!(msg.data, "{}");
+    }
+}
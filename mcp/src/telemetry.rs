@@ -0,0 +1,158 @@
+//! Log setup and optional OpenTelemetry trace export
+//!
+//! Logs always go through `tracing_subscriber::fmt`. When the crate is built
+//! with the `otel` feature and `OTEL_EXPORTER_OTLP_ENDPOINT` is set, spans
+//! are also exported over OTLP/gRPC: each `mcp_request` span is a trace
+//! root, with `tool_call`, `db.query`, `embedding` and `llm` spans below it.
+//! `OTEL_TRACES_SAMPLER_ARG` is the fraction of traces sampled (default
+//! 1.0). Requests carrying a W3C `traceparent` header join the caller's
+//! trace and follow its sampling decision. Without the feature or the
+//! endpoint no exporter is installed and header propagation is skipped.
+
+use axum::http::HeaderMap;
+use tracing::Span;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// Flushes exported spans when dropped; keep it alive for the process lifetime
+#[must_use = "dropping the guard stops trace export"]
+pub struct TelemetryGuard {
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to flush trace exporter: {e}");
+            }
+        }
+    }
+}
+
+/// Install the global subscriber: `RUST_LOG` (or `default_filter`) logs,
+/// plus OTLP export when configured
+///
+/// Must be called from within a Tokio runtime when export is enabled.
+///
+/// # Panics
+///
+/// Panics if a global subscriber is already installed.
+pub fn init_tracing(default_filter: &str) -> TelemetryGuard {
+    let filter =
+        EnvFilter::new(std::env::var("RUST_LOG").unwrap_or_else(|_| default_filter.to_string()));
+    let registry = tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer());
+
+    #[cfg(feature = "otel")]
+    {
+        let provider = otel::provider_from_env();
+        let layer = provider.as_ref().map(otel::layer);
+        registry.with(layer).init();
+        if provider.is_some() {
+            tracing::info!("Exporting traces over OTLP");
+        }
+        TelemetryGuard { provider }
+    }
+    #[cfg(not(feature = "otel"))]
+    {
+        registry.init();
+        TelemetryGuard {}
+    }
+}
+
+/// Make `span` a child of the trace named by the request's `traceparent`
+/// header, when traces are exported
+pub fn set_remote_parent(span: &Span, headers: &HeaderMap) {
+    #[cfg(feature = "otel")]
+    otel::set_remote_parent(span, headers);
+    #[cfg(not(feature = "otel"))]
+    let _ = (span, headers);
+}
+
+#[cfg(feature = "otel")]
+pub use otel::layer;
+
+#[cfg(feature = "otel")]
+mod otel {
+    use axum::http::HeaderMap;
+    use opentelemetry::propagation::{Extractor, TextMapPropagator};
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+    use opentelemetry_sdk::Resource;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tracing::Span;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    /// Set once a tracer provider is built, so idle propagation costs nothing
+    static EXPORTING: AtomicBool = AtomicBool::new(false);
+
+    /// Tracer provider exporting to `OTEL_EXPORTER_OTLP_ENDPOINT`, if set
+    pub(super) fn provider_from_env() -> Option<SdkTracerProvider> {
+        std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+            .ok()
+            .filter(|endpoint| !endpoint.trim().is_empty())?;
+        let exporter = match opentelemetry_otlp::SpanExporter::builder()
+            .with_tonic()
+            .build()
+        {
+            Ok(exporter) => exporter,
+            Err(e) => {
+                eprintln!("OTLP trace export disabled: {e}");
+                return None;
+            }
+        };
+        let ratio = std::env::var("OTEL_TRACES_SAMPLER_ARG")
+            .ok()
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .map_or(1.0, |r| r.clamp(0.0, 1.0));
+        let service = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| "doc-server".into());
+        Some(
+            SdkTracerProvider::builder()
+                .with_batch_exporter(exporter)
+                .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                    ratio,
+                ))))
+                .with_resource(Resource::builder().with_service_name(service).build())
+                .build(),
+        )
+    }
+
+    /// Layer sending spans to `provider`
+    ///
+    /// Also turns on `traceparent` propagation for [`super::set_remote_parent`].
+    pub fn layer<S>(
+        provider: &SdkTracerProvider,
+    ) -> tracing_opentelemetry::OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>
+    where
+        S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+    {
+        EXPORTING.store(true, Ordering::Relaxed);
+        tracing_opentelemetry::layer().with_tracer(provider.tracer("mcp"))
+    }
+
+    struct HeaderExtractor<'a>(&'a HeaderMap);
+
+    impl Extractor for HeaderExtractor<'_> {
+        fn get(&self, key: &str) -> Option<&str> {
+            self.0.get(key).and_then(|v| v.to_str().ok())
+        }
+
+        fn keys(&self) -> Vec<&str> {
+            self.0.keys().map(axum::http::HeaderName::as_str).collect()
+        }
+    }
+
+    pub(super) fn set_remote_parent(span: &Span, headers: &HeaderMap) {
+        if !EXPORTING.load(Ordering::Relaxed) || !headers.contains_key("traceparent") {
+            return;
+        }
+        let context = TraceContextPropagator::new().extract(&HeaderExtractor(headers));
+        let _ = span.set_parent(context);
+    }
+}
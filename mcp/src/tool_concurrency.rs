@@ -0,0 +1,228 @@
+//! Concurrency limits for tool calls
+//!
+//! One agent firing dozens of parallel searches can take every pooled
+//! database connection from everyone else. Each session may run a few tool
+//! calls at once, and the server as a whole a bounded number. A call over
+//! either limit waits briefly for a slot and is then refused with
+//! [`ToolConcurrencyExhausted`]. Only `tools/call` takes a slot, so
+//! `initialize`, `ping` and `tools/list` answer however busy a session is.
+//! Crate ingestion jobs run detached under their own limit.
+
+use crate::metrics::metrics;
+use crate::tools::ToolConcurrencyExhausted;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Tool calls one session may run at once
+pub const DEFAULT_PER_SESSION: usize = 4;
+
+/// Tool calls the server runs at once across all sessions
+pub const DEFAULT_GLOBAL: usize = 32;
+
+/// Longest wait for a free slot before the call is refused
+pub const DEFAULT_MAX_WAIT: Duration = Duration::from_secs(2);
+
+/// Session label in metrics for calls made without a session
+const NO_SESSION: &str = "none";
+
+type SessionSemaphores = Arc<Mutex<HashMap<String, Arc<Semaphore>>>>;
+
+/// Per-session and global tool call slots
+#[derive(Debug)]
+pub struct ToolConcurrency {
+    per_session: usize,
+    global_limit: usize,
+    max_wait: Duration,
+    global: Arc<Semaphore>,
+    /// Semaphores of sessions with calls running or waiting
+    sessions: SessionSemaphores,
+}
+
+impl Default for ToolConcurrency {
+    fn default() -> Self {
+        Self::new(DEFAULT_PER_SESSION, DEFAULT_GLOBAL, DEFAULT_MAX_WAIT)
+    }
+}
+
+impl ToolConcurrency {
+    /// Limits of `per_session` and `global` concurrent calls, waiting up to `max_wait`
+    #[must_use]
+    pub fn new(per_session: usize, global: usize, max_wait: Duration) -> Self {
+        let per_session = per_session.max(1);
+        let global_limit = global.max(1);
+        Self {
+            per_session,
+            global_limit,
+            max_wait,
+            global: Arc::new(Semaphore::new(global_limit)),
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Read limits from the environment, falling back to the defaults
+    ///
+    /// - `TOOL_CONCURRENCY_PER_SESSION`: calls per session (default: 4)
+    /// - `TOOL_CONCURRENCY_GLOBAL`: calls across all sessions (default: 32)
+    /// - `TOOL_CONCURRENCY_WAIT_MS`: wait for a slot before refusing the
+    ///   call (default: 2000; 0 refuses at once)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the variable if a value is not a number, or a
+    /// limit is zero.
+    pub fn from_env() -> Result<Self> {
+        let number = |key: &str, default: u64| -> Result<u64> {
+            match std::env::var(key) {
+                Ok(value) if !value.trim().is_empty() => value
+                    .trim()
+                    .parse()
+                    .map_err(|_| anyhow!("{key}: expected a number, got '{}'", value.trim())),
+                _ => Ok(default),
+            }
+        };
+        let limit = |key: &str, default: usize| -> Result<usize> {
+            match usize::try_from(number(key, default as u64)?) {
+                Ok(limit) if limit > 0 => Ok(limit),
+                _ => Err(anyhow!("{key}: expected a positive limit")),
+            }
+        };
+        let default_wait = u64::try_from(DEFAULT_MAX_WAIT.as_millis()).unwrap_or(u64::MAX);
+        Ok(Self::new(
+            limit("TOOL_CONCURRENCY_PER_SESSION", DEFAULT_PER_SESSION)?,
+            limit("TOOL_CONCURRENCY_GLOBAL", DEFAULT_GLOBAL)?,
+            Duration::from_millis(number("TOOL_CONCURRENCY_WAIT_MS", default_wait)?),
+        ))
+    }
+
+    /// Take a slot for one call of `session_id`, waiting up to the configured time
+    ///
+    /// Calls without a session only count against the global limit. Waits
+    /// and refusals are recorded in the metrics, per open session and in total.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ToolConcurrencyExhausted`] if no slot frees up in time.
+    pub async fn acquire(
+        &self,
+        session_id: Option<&str>,
+    ) -> Result<ToolPermit, ToolConcurrencyExhausted> {
+        let started = Instant::now();
+        let label = session_id.unwrap_or(NO_SESSION);
+        let mut queued = false;
+
+        let session = match session_id {
+            Some(id) => {
+                let semaphore = self.session_semaphore(id);
+                let Some(permit) = self.wait(semaphore, started, &mut queued).await else {
+                    forget_if_idle(&self.sessions, id, self.per_session);
+                    return Err(self.refuse(label, "session", self.per_session, started));
+                };
+                Some((id.to_string(), permit))
+            }
+            None => None,
+        };
+        let Some(global) = self
+            .wait(Arc::clone(&self.global), started, &mut queued)
+            .await
+        else {
+            if let Some((id, permit)) = session {
+                drop(permit);
+                forget_if_idle(&self.sessions, &id, self.per_session);
+            }
+            return Err(self.refuse(label, "global", self.global_limit, started));
+        };
+
+        if queued {
+            metrics().record_tool_queue_wait(label, started.elapsed());
+        }
+        Ok(ToolPermit {
+            session,
+            _global: global,
+            sessions: Arc::clone(&self.sessions),
+            per_session: self.per_session,
+        })
+    }
+
+    /// Number of sessions with calls running or waiting
+    #[must_use]
+    pub fn active_sessions(&self) -> usize {
+        self.sessions.lock().map_or(0, |sessions| sessions.len())
+    }
+
+    fn session_semaphore(&self, session_id: &str) -> Arc<Semaphore> {
+        let mut sessions = self
+            .sessions
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        Arc::clone(
+            sessions
+                .entry(session_id.to_string())
+                .or_insert_with(|| Arc::new(Semaphore::new(self.per_session))),
+        )
+    }
+
+    /// A permit of `semaphore`, or `None` once the wait that began at `started` is over
+    async fn wait(
+        &self,
+        semaphore: Arc<Semaphore>,
+        started: Instant,
+        queued: &mut bool,
+    ) -> Option<OwnedSemaphorePermit> {
+        if let Ok(permit) = Arc::clone(&semaphore).try_acquire_owned() {
+            return Some(permit);
+        }
+        *queued = true;
+        let remaining = self.max_wait.saturating_sub(started.elapsed());
+        tokio::time::timeout(remaining, semaphore.acquire_owned())
+            .await
+            .ok()?
+            .ok()
+    }
+
+    fn refuse(
+        &self,
+        label: &str,
+        scope: &'static str,
+        limit: usize,
+        started: Instant,
+    ) -> ToolConcurrencyExhausted {
+        metrics().increment_tool_concurrency_rejections(label);
+        ToolConcurrencyExhausted {
+            scope,
+            limit,
+            waited: started.elapsed(),
+        }
+    }
+}
+
+/// Slot held for the duration of one tool call
+pub struct ToolPermit {
+    session: Option<(String, OwnedSemaphorePermit)>,
+    _global: OwnedSemaphorePermit,
+    sessions: SessionSemaphores,
+    per_session: usize,
+}
+
+impl Drop for ToolPermit {
+    fn drop(&mut self) {
+        if let Some((session_id, permit)) = self.session.take() {
+            drop(permit);
+            forget_if_idle(&self.sessions, &session_id, self.per_session);
+        }
+    }
+}
+
+/// Drop the semaphore of a session with nothing running or waiting
+fn forget_if_idle(sessions: &SessionSemaphores, session_id: &str, per_session: usize) {
+    if let Ok(mut sessions) = sessions.lock() {
+        let idle = sessions.get(session_id).is_some_and(|semaphore| {
+            Arc::strong_count(semaphore) == 1 && semaphore.available_permits() == per_session
+        });
+        if idle {
+            sessions.remove(session_id);
+        }
+    }
+}
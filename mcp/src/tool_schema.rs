@@ -0,0 +1,139 @@
+//! Tool argument validation against each tool's `inputSchema`
+//!
+//! Schemas are compiled once when a tool is registered. Arguments that do not
+//! match are rejected before `execute` runs, with every violation listed.
+//! String `format`s such as `date-time` are enforced as well.
+
+use anyhow::{anyhow, Result};
+use jsonschema::error::{TypeKind, ValidationErrorKind};
+use jsonschema::{ValidationError, Validator};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+/// One way in which tool arguments fail their schema
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ArgumentViolation {
+    /// JSON pointer to the offending value (`/status_filter`)
+    pub path: String,
+    /// What the schema expects there
+    pub expected: String,
+    /// The value that was sent (`null` when missing)
+    pub actual: Value,
+    /// Human-readable description
+    pub message: String,
+}
+
+/// Arguments rejected by a tool's schema, mapped to JSON-RPC invalid params
+#[derive(Debug, thiserror::Error)]
+pub struct InvalidToolArguments {
+    pub tool: String,
+    pub violations: Vec<ArgumentViolation>,
+}
+
+impl fmt::Display for InvalidToolArguments {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid arguments for tool '{}': ", self.tool)?;
+        let messages: Vec<&str> = self.violations.iter().map(|v| v.message.as_str()).collect();
+        write!(f, "{}", messages.join("; "))
+    }
+}
+
+/// Compiled `inputSchema` of a single tool
+pub struct ToolSchema {
+    validator: Validator,
+}
+
+impl ToolSchema {
+    /// Compile the `inputSchema` of a tool definition, if it has one
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the tool if the schema is not a valid JSON Schema.
+    pub fn compile(tool_name: &str, definition: &Value) -> Result<Option<Self>> {
+        let Some(schema) = definition.get("inputSchema") else {
+            return Ok(None);
+        };
+        let validator = jsonschema::options()
+            .should_validate_formats(true)
+            .build(schema)
+            .map_err(|e| anyhow!("Invalid inputSchema for tool '{tool_name}': {e}"))?;
+        Ok(Some(Self { validator }))
+    }
+
+    /// Check `arguments`, listing every violation
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidToolArguments`] if any part of `arguments` fails the schema.
+    pub fn validate(&self, tool_name: &str, arguments: &Value) -> Result<(), InvalidToolArguments> {
+        let violations: Vec<ArgumentViolation> = self
+            .validator
+            .iter_errors(arguments)
+            .flat_map(|error| violations_for(&error))
+            .collect();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(InvalidToolArguments {
+                tool: tool_name.to_string(),
+                violations,
+            })
+        }
+    }
+}
+
+/// Describe a validation error as one or more violations
+fn violations_for(error: &ValidationError<'_>) -> Vec<ArgumentViolation> {
+    let path = error.instance_path.to_string();
+    match &error.kind {
+        ValidationErrorKind::Required { property } => {
+            let name = property.as_str().unwrap_or_default();
+            vec![ArgumentViolation {
+                path: format!("{path}/{name}"),
+                expected: "required property".to_string(),
+                actual: Value::Null,
+                message: format!("Missing required property '{name}'"),
+            }]
+        }
+        ValidationErrorKind::AdditionalProperties { unexpected } => unexpected
+            .iter()
+            .map(|name| ArgumentViolation {
+                path: format!("{path}/{name}"),
+                expected: "no such property".to_string(),
+                actual: error.instance.get(name).cloned().unwrap_or(Value::Null),
+                message: format!("Unknown property '{name}'"),
+            })
+            .collect(),
+        kind => {
+            let expected = match kind {
+                ValidationErrorKind::Type {
+                    kind: TypeKind::Single(ty),
+                } => ty.to_string(),
+                ValidationErrorKind::Type {
+                    kind: TypeKind::Multiple(types),
+                } => types
+                    .iter()
+                    .map(|ty| ty.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" or "),
+                ValidationErrorKind::Enum { options } => format!("one of {options}"),
+                ValidationErrorKind::Format { format } => format.clone(),
+                _ => error
+                    .schema_path
+                    .as_str()
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+            };
+            let location = if path.is_empty() { "arguments" } else { &path };
+            vec![ArgumentViolation {
+                path: path.clone(),
+                expected,
+                actual: error.instance.clone().into_owned(),
+                message: format!("{location}: {error}"),
+            }]
+        }
+    }
+}
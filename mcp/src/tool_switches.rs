@@ -0,0 +1,314 @@
+//! Runtime tool enable/disable
+//!
+//! Operators can switch a misbehaving tool off without a redeploy, through
+//! the admin-only `set_tool_enabled` tool or `POST /admin/tools/{name}`.
+//! Disabled tools drop out of `tools/list` and calls to them are rejected.
+//! Changes are stored in `tool_settings` unless `persist` is false, and are
+//! announced to open SSE streams with `notifications/tools/list_changed`.
+//! Other replicas pick up stored changes on their next start.
+
+use crate::security::is_admin_request;
+use crate::server::McpServerState;
+use crate::tools::{ExecutionContext, Tool};
+use crate::transport::broadcast_tools_list_changed;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use db::{DatabasePool, ToolSettingQueries};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use tracing::{debug, info, warn};
+
+/// Name of the admin tool, which cannot switch itself off
+pub const SET_TOOL_ENABLED: &str = "set_tool_enabled";
+
+/// Per-tool enabled flags shared by the handler and the admin tool
+///
+/// Every registered tool has an entry; tools are enabled unless switched off.
+#[derive(Clone)]
+pub struct ToolSwitches {
+    flags: Arc<RwLock<HashMap<String, bool>>>,
+    db_pool: DatabasePool,
+}
+
+impl ToolSwitches {
+    /// Create switches persisting to `db_pool`
+    #[must_use]
+    pub fn new(db_pool: DatabasePool) -> Self {
+        Self {
+            flags: Arc::new(RwLock::new(HashMap::new())),
+            db_pool,
+        }
+    }
+
+    /// Track a newly registered tool, enabled unless already switched off
+    pub fn register(&self, name: &str) {
+        if let Ok(mut flags) = self.flags.write() {
+            flags.entry(name.to_string()).or_insert(true);
+        }
+    }
+
+    /// Whether `name` is enabled (unknown tools count as enabled)
+    #[must_use]
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.flags
+            .read()
+            .map_or(true, |flags| flags.get(name).copied().unwrap_or(true))
+    }
+
+    /// Every tracked tool and whether it is enabled, sorted by name
+    #[must_use]
+    pub fn snapshot(&self) -> BTreeMap<String, bool> {
+        self.flags
+            .read()
+            .map(|flags| flags.iter().map(|(k, v)| (k.clone(), *v)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Apply the overrides stored in `tool_settings`, returning how many matched a tool
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stored overrides cannot be read.
+    pub async fn load(&self) -> Result<usize> {
+        let stored = ToolSettingQueries::list(self.db_pool.pool()).await?;
+        let mut flags = self
+            .flags
+            .write()
+            .map_err(|_| anyhow!("tool switches lock poisoned"))?;
+
+        let mut applied = 0;
+        for (name, enabled) in stored {
+            match flags.get_mut(&name) {
+                Some(flag) if name != SET_TOOL_ENABLED => {
+                    *flag = enabled;
+                    applied += 1;
+                }
+                _ => debug!("Ignoring stored setting for unregistered tool '{}'", name),
+            }
+        }
+        Ok(applied)
+    }
+
+    /// Switch `name` on or off, returning whether the flag changed
+    ///
+    /// With `persist` the change is stored so it survives restarts. Open SSE
+    /// streams are notified when the flag changes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for unknown tools, for `set_tool_enabled` itself, or
+    /// if a persisted change cannot be stored.
+    pub async fn set(
+        &self,
+        name: &str,
+        enabled: bool,
+        persist: bool,
+        updated_by: &str,
+    ) -> Result<bool> {
+        if name == SET_TOOL_ENABLED {
+            return Err(anyhow!("{SET_TOOL_ENABLED} cannot be switched off"));
+        }
+        if !self.snapshot().contains_key(name) {
+            return Err(anyhow!("Unknown tool: {name}"));
+        }
+
+        if persist {
+            ToolSettingQueries::set_enabled(self.db_pool.pool(), name, enabled, Some(updated_by))
+                .await?;
+        }
+
+        let previous = self
+            .flags
+            .write()
+            .map_err(|_| anyhow!("tool switches lock poisoned"))?
+            .insert(name.to_string(), enabled);
+        let changed = previous != Some(enabled);
+
+        if changed {
+            info!(
+                "Tool '{}' {} by {} (persisted: {})",
+                name,
+                if enabled { "enabled" } else { "disabled" },
+                updated_by,
+                persist
+            );
+            broadcast_tools_list_changed();
+        }
+        Ok(changed)
+    }
+}
+
+/// Admin tool switching other tools on or off at runtime
+pub struct SetToolEnabledTool {
+    switches: ToolSwitches,
+}
+
+impl SetToolEnabledTool {
+    /// Create a new tool toggle over `switches`
+    #[must_use]
+    pub const fn new(switches: ToolSwitches) -> Self {
+        Self { switches }
+    }
+}
+
+#[async_trait]
+impl Tool for SetToolEnabledTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": SET_TOOL_ENABLED,
+            "description": "Enable or disable a tool without restarting the server (admin token required). Disabled tools are hidden from tools/list and calls to them are rejected.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Name of the tool to switch"
+                    },
+                    "enabled": {
+                        "type": "boolean",
+                        "description": "Whether the tool can be listed and called"
+                    },
+                    "persist": {
+                        "type": "boolean",
+                        "description": "Keep the change across restarts (default: true)"
+                    }
+                },
+                "required": ["name", "enabled"]
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        self.execute_with_context(arguments, &ExecutionContext::default())
+            .await
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ExecutionContext,
+    ) -> Result<String> {
+        let name = arguments
+            .get("name")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .ok_or_else(|| anyhow!("Missing required parameter: name"))?;
+        let enabled = arguments
+            .get("enabled")
+            .and_then(Value::as_bool)
+            .ok_or_else(|| anyhow!("Missing required parameter: enabled"))?;
+        let persist = arguments
+            .get("persist")
+            .and_then(Value::as_bool)
+            .unwrap_or(true);
+
+        let updated_by = format!(
+            "{SET_TOOL_ENABLED} (session {})",
+            context.session_id().unwrap_or("none")
+        );
+        let changed = self
+            .switches
+            .set(name, enabled, persist, &updated_by)
+            .await?;
+
+        Ok(serde_json::to_string_pretty(&json!({
+            "name": name,
+            "enabled": enabled,
+            "changed": changed,
+            "persisted": persist,
+        }))?)
+    }
+
+    fn requires_admin(&self) -> bool {
+        true
+    }
+}
+
+/// Body of `POST /admin/tools/{name}`
+#[derive(Debug, Deserialize)]
+pub struct SetToolEnabledRequest {
+    pub enabled: bool,
+    #[serde(default = "default_persist")]
+    pub persist: bool,
+}
+
+const fn default_persist() -> bool {
+    true
+}
+
+/// Reject requests without the admin token
+pub(crate) fn require_admin(
+    state: &McpServerState,
+    headers: &HeaderMap,
+) -> Result<(), (StatusCode, String)> {
+    if state.security_config.admin_token.is_none() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            "admin API disabled: MCP_ADMIN_TOKEN is not set".to_string(),
+        ));
+    }
+    if !is_admin_request(headers, &state.security_config) {
+        warn!("Rejected admin request without a valid admin token");
+        return Err((StatusCode::UNAUTHORIZED, "admin token required".to_string()));
+    }
+    Ok(())
+}
+
+/// `GET /admin/tools`: every tool and whether it is enabled
+///
+/// # Errors
+///
+/// Returns 401 without the admin token, 403 when no admin token is configured.
+pub async fn list_tool_switches_handler(
+    State(state): State<McpServerState>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    Ok(Json(
+        json!({ "tools": state.handler.tool_switches().snapshot() }),
+    ))
+}
+
+/// `POST /admin/tools/{name}`: switch a tool on or off
+///
+/// # Errors
+///
+/// Returns 401 without the admin token, 403 when no admin token is
+/// configured, 404 for unknown tools and 500 if the change cannot be stored.
+pub async fn set_tool_switch_handler(
+    State(state): State<McpServerState>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    Json(body): Json<SetToolEnabledRequest>,
+) -> Result<Json<Value>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let switches = state.handler.tool_switches();
+    if name != SET_TOOL_ENABLED && !switches.snapshot().contains_key(&name) {
+        return Err((StatusCode::NOT_FOUND, format!("Unknown tool: {name}")));
+    }
+
+    let changed = switches
+        .set(&name, body.enabled, body.persist, "admin API")
+        .await
+        .map_err(|e| {
+            let status = if name == SET_TOOL_ENABLED {
+                StatusCode::BAD_REQUEST
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+            (status, e.to_string())
+        })?;
+
+    Ok(Json(json!({
+        "name": name,
+        "enabled": body.enabled,
+        "changed": changed,
+        "persisted": body.persist,
+    })))
+}
@@ -0,0 +1,136 @@
+//! Time budgets for tool calls
+//!
+//! Every tool call runs under a budget so a hung embedding API or a slow
+//! query cannot hold the request open forever. Budgets default by kind of
+//! tool and can be overridden per tool in the tools configuration
+//! (`timeoutSecs`) or with `TOOL_TIMEOUTS`.
+
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Budget of `*_query` tools
+pub const DEFAULT_QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Budget of status reports, which aggregate over every crate
+pub const DEFAULT_STATUS_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Budget of every other tool; job tools queue work and return immediately
+pub const DEFAULT_TOOL_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Extra time the HTTP layer allows beyond the largest budget, so the
+/// timeout error still reaches the client
+pub const HTTP_TIMEOUT_MARGIN: Duration = Duration::from_secs(5);
+
+/// Tools budgeted as status reports
+const STATUS_TOOLS: [&str; 1] = ["check_rust_status"];
+
+/// Per-tool time budgets
+#[derive(Debug, Clone)]
+pub struct ToolTimeouts {
+    query: Duration,
+    status: Duration,
+    other: Duration,
+    overrides: HashMap<String, Duration>,
+}
+
+impl Default for ToolTimeouts {
+    fn default() -> Self {
+        Self {
+            query: DEFAULT_QUERY_TIMEOUT,
+            status: DEFAULT_STATUS_TIMEOUT,
+            other: DEFAULT_TOOL_TIMEOUT,
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+impl ToolTimeouts {
+    /// Read budgets from the environment, falling back to the defaults
+    ///
+    /// - `TOOL_TIMEOUT_QUERY_SECS`: budget of `*_query` tools (default: 30)
+    /// - `TOOL_TIMEOUT_STATUS_SECS`: budget of `check_rust_status` (default: 120)
+    /// - `TOOL_TIMEOUT_DEFAULT_SECS`: budget of every other tool (default: 600)
+    /// - `TOOL_TIMEOUTS`: per-tool budgets as `name=secs` pairs separated by
+    ///   commas, e.g. `rust_query=45,analyze_repository=900`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the variable if a value is not a positive
+    /// number of seconds.
+    pub fn from_env() -> Result<Self> {
+        let defaults = Self::default();
+        let secs = |key: &str, default: Duration| -> Result<Duration> {
+            match std::env::var(key) {
+                Ok(value) if !value.trim().is_empty() => {
+                    parse_secs(&value).map_err(|e| anyhow!("{key}: {e}"))
+                }
+                _ => Ok(default),
+            }
+        };
+
+        let mut timeouts = Self {
+            query: secs("TOOL_TIMEOUT_QUERY_SECS", defaults.query)?,
+            status: secs("TOOL_TIMEOUT_STATUS_SECS", defaults.status)?,
+            other: secs("TOOL_TIMEOUT_DEFAULT_SECS", defaults.other)?,
+            overrides: HashMap::new(),
+        };
+        if let Ok(list) = std::env::var("TOOL_TIMEOUTS") {
+            for entry in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                let (name, value) = entry
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("TOOL_TIMEOUTS: expected name=secs, got '{entry}'"))?;
+                let budget =
+                    parse_secs(value).map_err(|e| anyhow!("TOOL_TIMEOUTS: '{entry}': {e}"))?;
+                timeouts.overrides.insert(name.trim().to_string(), budget);
+            }
+        }
+        Ok(timeouts)
+    }
+
+    /// Set the budget of one tool unless `TOOL_TIMEOUTS` already names it
+    pub fn set_default_for(&mut self, tool: &str, budget: Duration) {
+        self.overrides.entry(tool.to_string()).or_insert(budget);
+    }
+
+    /// Set the budget of one tool, replacing any earlier override
+    pub fn set(&mut self, tool: &str, budget: Duration) {
+        self.overrides.insert(tool.to_string(), budget);
+    }
+
+    /// Budget of a call to `tool`
+    #[must_use]
+    pub fn budget(&self, tool: &str) -> Duration {
+        if let Some(&budget) = self.overrides.get(tool) {
+            budget
+        } else if tool.ends_with("_query") {
+            self.query
+        } else if STATUS_TOOLS.contains(&tool) {
+            self.status
+        } else {
+            self.other
+        }
+    }
+
+    /// Limit for a whole HTTP request: the largest budget plus [`HTTP_TIMEOUT_MARGIN`]
+    #[must_use]
+    pub fn http_timeout(&self) -> Duration {
+        self.overrides
+            .values()
+            .copied()
+            .chain([self.query, self.status, self.other])
+            .max()
+            .unwrap_or(DEFAULT_TOOL_TIMEOUT)
+            + HTTP_TIMEOUT_MARGIN
+    }
+}
+
+fn parse_secs(value: &str) -> Result<Duration> {
+    match value.trim().parse::<u64>() {
+        Ok(secs) if secs > 0 => Ok(Duration::from_secs(secs)),
+        _ => Err(anyhow!(
+            "expected a positive number of seconds, got '{}'",
+            value.trim()
+        )),
+    }
+}
@@ -1,17 +1,35 @@
 //! MCP tool definitions
 
+use crate::config::{FILTER_KEYS, MAX_QUERY_LIMIT};
+use crate::query_cache::{cache_enabled, query_cache, ResultKey};
+use crate::query_expansion::{query_expander, QueryExpansion};
+use crate::rerank::{
+    rerank, PromptReranker, RerankConfig, RerankOutcome, Reranker, UnavailableReranker,
+};
+use crate::snippets::{build_snippet, match_terms, snippet_properties, SnippetOptions};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use db::{
+    chunks::group_adjacent_chunks,
     models::ToolConfig,
     queries::{DocumentQueries, MetadataFilters},
-    DatabasePool,
+    DatabasePool, ToolAuditFilter, ToolAuditQueries,
 };
-use embed::{EmbeddingClient, OpenAIEmbeddingClient};
-use serde_json::{json, Value};
+use discovery::{prompt_runner_from_env, LlmUseCase};
+use embed::{embedding_client_from_env, EmbeddingClient};
+use npm_packages::NPM_DOC_TYPE;
+use python_packages::{normalize_package_name, PYTHON_DOC_TYPE};
+use serde_json::{json, Map, Value};
 use sqlx::Row;
+use std::collections::HashMap;
 use std::fmt::Write as _;
+use std::future::Future;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, warn};
+use uuid::Uuid;
 
 // Legacy IngestTool removed - use intelligent ingestion endpoint at /ingest/intelligent instead
 
@@ -23,73 +41,889 @@ pub trait Tool {
 
     /// Execute the tool with given arguments
     async fn execute(&self, arguments: Value) -> Result<String>;
+
+    /// Execute the tool with a per-call context
+    ///
+    /// Tools that can stop early override this and check `context` between
+    /// await points; the default ignores the context.
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ExecutionContext,
+    ) -> Result<String> {
+        let _ = context;
+        self.execute(arguments).await
+    }
+
+    /// Soft cap on the size of a text result, in characters
+    ///
+    /// Longer results are cut at a line boundary and the rest is handed out
+    /// through `fetch_continuation`. Tools returning JSON keep this `None`
+    /// and paginate their items with [`ContinuationStore::paginate_items`].
+    fn response_soft_cap(&self) -> Option<usize> {
+        None
+    }
+
+    /// Whether only callers presenting the admin token may call the tool
+    fn requires_admin(&self) -> bool {
+        false
+    }
+}
+
+/// Error returned when a tool call was cancelled by the client
+#[derive(Debug, thiserror::Error)]
+#[error("Request cancelled by client")]
+pub struct RequestCancelled;
+
+/// Error returned when a called tool has been switched off by an operator
+///
+/// The transport answers it with JSON-RPC error `-32601`, as for an unknown
+/// method, so clients drop the tool from their cache.
+#[derive(Debug, thiserror::Error)]
+#[error("Tool '{0}' is administratively disabled")]
+pub struct ToolDisabled(pub String);
+
+/// Error returned when a tool call ran past its time budget
+///
+/// The transport answers it with JSON-RPC error `-32001`. The call's future is
+/// dropped, so the tool stops at its next `.await`.
+#[derive(Debug, thiserror::Error)]
+#[error("Tool '{tool}' timed out after {}s", budget.as_secs_f64())]
+pub struct ToolTimedOut {
+    pub tool: String,
+    pub budget: std::time::Duration,
+}
+
+/// Error returned when a tool call found no free concurrency slot in time
+///
+/// The transport answers it with JSON-RPC error `-32029`, echoing HTTP 429.
+/// `scope` is `session` or `global`, the limit that was exhausted.
+#[derive(Debug, thiserror::Error)]
+#[error("Too many concurrent tool calls: {scope} limit of {limit} reached after waiting {}ms", waited.as_millis())]
+pub struct ToolConcurrencyExhausted {
+    pub scope: &'static str,
+    pub limit: usize,
+    pub waited: std::time::Duration,
+}
+
+/// Tool failure reported to the client as a JSON error object
+///
+/// The handler renders it as `{"error": {"kind": ..., "message": ...}}` so
+/// clients can branch on `kind` instead of parsing the message.
+#[derive(Debug, thiserror::Error, serde::Serialize)]
+#[error("{message}")]
+pub struct StructuredToolError {
+    /// Stable machine-readable failure name, e.g. `timeout`
+    pub kind: String,
+    /// Human-readable description
+    pub message: String,
+}
+
+/// Failure of a management tool, with a stable code clients can branch on
+///
+/// The transport answers it with a JSON-RPC error: `error.code` depends on
+/// the variant (see [`ToolError::jsonrpc_code`]), `error.message` is the
+/// prose message and `error.data` is `{code, message, details}`.
+#[derive(Debug, thiserror::Error)]
+pub enum ToolError {
+    /// The crate, version or job named in the call is not stored
+    #[error("{message}")]
+    NotFound { message: String, details: Value },
+    /// The call would create something that already exists
+    #[error("{message}")]
+    AlreadyExists { message: String, details: Value },
+    /// The arguments are well-formed but not acceptable
+    #[error("{message}")]
+    InvalidInput { message: String, details: Value },
+    /// The target is in a state that does not allow the operation
+    #[error("{message}")]
+    Conflict { message: String, details: Value },
+    /// Other stored data depends on the target
+    #[error("{message}")]
+    DependencyError { message: String, details: Value },
+    /// The operation failed on the server's side
+    #[error("{message}")]
+    Internal { message: String, details: Value },
+}
+
+impl ToolError {
+    /// Invalid input without further details
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::InvalidInput {
+            message: message.into(),
+            details: json!({}),
+        }
+    }
+
+    /// Stable machine-readable name of the variant, e.g. `not_found`
+    #[must_use]
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound { .. } => "not_found",
+            Self::AlreadyExists { .. } => "already_exists",
+            Self::InvalidInput { .. } => "invalid_input",
+            Self::Conflict { .. } => "conflict",
+            Self::DependencyError { .. } => "dependency_error",
+            Self::Internal { .. } => "internal",
+        }
+    }
+
+    /// JSON-RPC error code the transport answers with
+    ///
+    /// Invalid input and internal errors use the standard invalid params and
+    /// internal error codes; the others use the server error range, echoing
+    /// the matching HTTP status where there is one (404, 409, 424).
+    #[must_use]
+    pub const fn jsonrpc_code(&self) -> i64 {
+        match self {
+            Self::NotFound { .. } => -32004,
+            Self::AlreadyExists { .. } => -32005,
+            Self::InvalidInput { .. } => -32602,
+            Self::Conflict { .. } => -32009,
+            Self::DependencyError { .. } => -32024,
+            Self::Internal { .. } => -32603,
+        }
+    }
+
+    /// Structured details, e.g. the stored version of an existing crate
+    #[must_use]
+    pub const fn details(&self) -> &Value {
+        match self {
+            Self::NotFound { details, .. }
+            | Self::AlreadyExists { details, .. }
+            | Self::InvalidInput { details, .. }
+            | Self::Conflict { details, .. }
+            | Self::DependencyError { details, .. }
+            | Self::Internal { details, .. } => details,
+        }
+    }
+
+    /// `{code, message, details}`, sent as the JSON-RPC error's `data`
+    #[must_use]
+    pub fn to_json(&self) -> Value {
+        json!({
+            "code": self.code(),
+            "message": self.to_string(),
+            "details": self.details(),
+        })
+    }
+}
+
+/// Per-call state passed to [`Tool::execute_with_context`]
+#[derive(Clone, Debug, Default)]
+pub struct ExecutionContext {
+    cancellation: CancellationToken,
+    session_id: Option<String>,
+}
+
+impl ExecutionContext {
+    /// Create a context driven by the given cancellation token
+    #[must_use]
+    pub const fn new(cancellation: CancellationToken) -> Self {
+        Self {
+            cancellation,
+            session_id: None,
+        }
+    }
+
+    /// Attach the MCP session the call belongs to
+    #[must_use]
+    pub fn with_session(mut self, session_id: Option<&str>) -> Self {
+        self.session_id = session_id.map(ToString::to_string);
+        self
+    }
+
+    /// MCP session the call belongs to, if any
+    #[must_use]
+    pub fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+
+    /// Whether the client has cancelled this call
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// Fail with [`RequestCancelled`] if the call has been cancelled
+    ///
+    /// # Errors
+    ///
+    /// Returns `RequestCancelled` once the client cancels the call.
+    pub fn check_cancelled(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(RequestCancelled.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Run `future`, abandoning it if the call is cancelled first
+    ///
+    /// # Errors
+    ///
+    /// Returns `RequestCancelled` on cancellation, otherwise the future's own error.
+    pub async fn run<T>(&self, future: impl Future<Output = Result<T>> + Send) -> Result<T> {
+        tokio::select! {
+            biased;
+            () = self.cancellation.cancelled() => Err(RequestCancelled.into()),
+            result = future => result,
+        }
+    }
+}
+
+/// Default soft cap on tool results, in characters
+pub const DEFAULT_RESPONSE_SOFT_CAP: usize = 25_000;
+
+/// How long a continuation token stays valid
+pub const CONTINUATION_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Continuations kept at once; the ones closest to expiry go first
+const MAX_CONTINUATIONS: usize = 1_000;
+
+/// Soft cap on tool results (`MCP_RESPONSE_SOFT_CAP_CHARS`, default [`DEFAULT_RESPONSE_SOFT_CAP`])
+#[must_use]
+pub fn response_soft_cap() -> usize {
+    std::env::var("MCP_RESPONSE_SOFT_CAP_CHARS")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_RESPONSE_SOFT_CAP)
+}
+
+/// Rest of an oversized result waiting to be fetched
+enum PendingOutput {
+    Text(String),
+    /// Remaining items of the array stored under `key`
+    Items {
+        key: String,
+        items: Vec<Value>,
+    },
+}
+
+struct Continuation {
+    session_id: Option<String>,
+    cap: usize,
+    pending: PendingOutput,
+    expires_at: Instant,
+}
+
+/// Short-lived store for the rest of results that exceeded their budget
+///
+/// Each token is bound to the session that received it and can be used once;
+/// fetching a slice that still leaves output behind returns a new token.
+pub struct ContinuationStore {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, Continuation>>,
+}
+
+impl ContinuationStore {
+    /// Create a store whose tokens expire after `ttl`
+    #[must_use]
+    pub fn with_ttl(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn insert(&self, session_id: Option<&str>, cap: usize, pending: PendingOutput) -> String {
+        let token = Uuid::new_v4().simple().to_string();
+        let Ok(mut entries) = self.entries.lock() else {
+            return token;
+        };
+        let now = Instant::now();
+        entries.retain(|_, c| c.expires_at > now);
+        while entries.len() >= MAX_CONTINUATIONS {
+            let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, c)| c.expires_at)
+                .map(|(token, _)| token.clone())
+            else {
+                break;
+            };
+            entries.remove(&oldest);
+        }
+        entries.insert(
+            token.clone(),
+            Continuation {
+                session_id: session_id.map(ToString::to_string),
+                cap,
+                pending,
+                expires_at: now + self.ttl,
+            },
+        );
+        token
+    }
+
+    /// Cut `text` to about `cap` characters, keeping the rest behind a continuation token
+    ///
+    /// Text within the cap is returned unchanged. Otherwise the cut falls on
+    /// the last line break (or space) in the second half of the allowance and
+    /// a marker naming the token is appended.
+    pub fn truncate_text(&self, session_id: Option<&str>, text: String, cap: usize) -> String {
+        let cap = cap.max(1);
+        let Some(cut) = text_boundary(&text, cap) else {
+            return text;
+        };
+        let (head, rest) = text.split_at(cut);
+        let remaining = rest.chars().count();
+        let mut output = head.to_string();
+        let token = self.insert(session_id, cap, PendingOutput::Text(rest.to_string()));
+        let _ = write!(
+            output,
+            "\n\n[Output truncated: {remaining} more characters. Call fetch_continuation with token \"{token}\" for the next part.]"
+        );
+        output
+    }
+
+    /// Fit as many `items` into `envelope[key]` as stay within `cap` characters
+    ///
+    /// At least one item is always included. When items are left over, the
+    /// envelope gets a `continuation_token` for fetching them.
+    pub fn paginate_items(
+        &self,
+        session_id: Option<&str>,
+        mut envelope: Map<String, Value>,
+        key: &str,
+        mut items: Vec<Value>,
+        cap: usize,
+    ) -> Value {
+        let mut budget = cap.saturating_sub(Value::Object(envelope.clone()).to_string().len());
+        let mut fitting = 0;
+        for item in &items {
+            // Pretty-printed size inside a nested array
+            let pretty = serde_json::to_string_pretty(item).unwrap_or_default();
+            let size = pretty.len() + 4 * (pretty.lines().count() + 1);
+            if fitting > 0 && size > budget {
+                break;
+            }
+            budget = budget.saturating_sub(size);
+            fitting += 1;
+        }
+        let rest = items.split_off(fitting.min(items.len()));
+        envelope.insert(key.to_string(), Value::Array(items));
+        if !rest.is_empty() {
+            let token = self.insert(
+                session_id,
+                cap,
+                PendingOutput::Items {
+                    key: key.to_string(),
+                    items: rest,
+                },
+            );
+            envelope.insert("continuation_token".to_string(), Value::String(token));
+        }
+        Value::Object(envelope)
+    }
+
+    /// Next part of the result behind `token`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token is unknown, expired, already used, or
+    /// was issued to another session.
+    pub fn fetch(&self, session_id: Option<&str>, token: &str) -> Result<String> {
+        let continuation = self
+            .entries
+            .lock()
+            .ok()
+            .and_then(|mut entries| entries.remove(token))
+            .filter(|c| c.expires_at > Instant::now())
+            .filter(|c| c.session_id.is_none() || c.session_id.as_deref() == session_id)
+            .ok_or_else(|| anyhow!("Unknown or expired continuation token"))?;
+
+        match continuation.pending {
+            PendingOutput::Text(text) => Ok(self.truncate_text(session_id, text, continuation.cap)),
+            PendingOutput::Items { key, items } => {
+                let page =
+                    self.paginate_items(session_id, Map::new(), &key, items, continuation.cap);
+                Ok(serde_json::to_string_pretty(&page)?)
+            }
+        }
+    }
+}
+
+impl Default for ContinuationStore {
+    fn default() -> Self {
+        Self::with_ttl(CONTINUATION_TTL)
+    }
+}
+
+/// Byte offset to cut `text` at so at most `cap` characters remain, or `None` if it fits
+fn text_boundary(text: &str, cap: usize) -> Option<usize> {
+    let (limit, _) = text.char_indices().nth(cap)?;
+    let window = &text[..limit];
+    let half = limit / 2;
+    window
+        .rfind('\n')
+        .map(|i| i + 1)
+        .or_else(|| window.rfind(' ').map(|i| i + 1))
+        .filter(|&i| i > half)
+        .or(Some(limit))
+}
+
+static CONTINUATIONS: LazyLock<ContinuationStore> = LazyLock::new(ContinuationStore::default);
+
+/// Process-wide continuation store used by the tool handler
+#[must_use]
+pub fn continuations() -> &'static ContinuationStore {
+    &CONTINUATIONS
+}
+
+/// Fetch the next part of a result that exceeded its size budget
+pub struct FetchContinuationTool;
+
+#[async_trait]
+impl Tool for FetchContinuationTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "fetch_continuation",
+            "description": "Fetch the next part of a tool result that was cut for size. Pass the continuation token from the truncation marker or the continuation_token field; each token works once, and expires after 10 minutes.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "token": {
+                        "type": "string",
+                        "description": "Continuation token from the previous part"
+                    }
+                },
+                "required": ["token"]
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        self.execute_with_context(arguments, &ExecutionContext::default())
+            .await
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ExecutionContext,
+    ) -> Result<String> {
+        let token = arguments
+            .get("token")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("Missing required parameter: token"))?;
+        continuations().fetch(context.session_id(), token)
+    }
+}
+
+/// Default cap on the total size of a `rust_query` response, in characters
+pub const DEFAULT_MAX_RESPONSE_CHARS: usize = 20_000;
+
+/// Default length of the content snippet shown for each `rust_query` result, in characters
+const SNIPPET_CHARS: usize = 600;
+
+/// Default length of the content snippet shown for each result of other query tools
+const DYNAMIC_SNIPPET_CHARS: usize = 1_000;
+
+/// Space kept free in the response budget for the truncation note
+const TRUNCATION_NOTE_RESERVE: usize = 200;
+
+/// Reranker built by the query tools' `new`: Claude via the runner selected
+/// by `RERANK_LLM_PROVIDER` or `LLM_PROVIDER`
+fn default_reranker() -> Result<Arc<dyn Reranker>> {
+    Ok(Arc::new(PromptReranker::new(prompt_runner_from_env(
+        LlmUseCase::Rerank,
+    )?)))
+}
+
+/// Reranker of tools built without one; rerank requests keep search order
+fn missing_reranker() -> Arc<dyn Reranker> {
+    Arc::new(UnavailableReranker::new("no reranker configured"))
+}
+
+/// Input schema for the `rerank` argument of query tools
+fn rerank_property() -> Value {
+    json!({
+        "type": "boolean",
+        "description": "Rerank a wider candidate set by relevance to the query before applying limit (slower; default: false)"
+    })
+}
+
+/// Input schema for the `cache` argument of query tools
+fn cache_property() -> Value {
+    json!({
+        "type": "boolean",
+        "description": "Serve repeated queries from the short-lived result cache; false always runs a fresh search (default: true)"
+    })
+}
+
+/// Input schema for the `expand` argument of query tools
+fn expand_property() -> Value {
+    json!({
+        "type": "boolean",
+        "description": "Also match synonyms and identifier forms of the query terms (e.g. 'read to string' finds read_to_string and ReadToString); the terms added are listed in the response (default: false)"
+    })
+}
+
+/// Expansion of `query` when the call asks for `expand`
+fn expansion_for(arguments: &Value, query: &str) -> QueryExpansion {
+    if arguments
+        .get("expand")
+        .and_then(Value::as_bool)
+        .unwrap_or(false)
+    {
+        query_expander().expand(query)
+    } else {
+        QueryExpansion::default()
+    }
+}
+
+/// Text whose matches are highlighted in snippets: the query and its expanded terms
+fn highlight_text(query: &str, expanded_terms: &[String]) -> String {
+    std::iter::once(query)
+        .chain(expanded_terms.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Input schema for the date filters and recency boost of query tools
+fn recency_properties() -> Map<String, Value> {
+    let date = |description: &str| {
+        json!({
+            "type": "string",
+            "format": "date-time",
+            "description": description
+        })
+    };
+    let mut properties = Map::new();
+    properties.insert(
+        "created_after".to_string(),
+        date(
+            "Only documents ingested at or after this RFC 3339 time (e.g. '2024-05-01T00:00:00Z')",
+        ),
+    );
+    properties.insert(
+        "created_before".to_string(),
+        date("Only documents ingested before this RFC 3339 time"),
+    );
+    properties.insert(
+        "updated_after".to_string(),
+        date("Only documents updated at or after this RFC 3339 time"),
+    );
+    properties.insert(
+        "recency_boost".to_string(),
+        json!({
+            "type": "number",
+            "description": "Favour newer documents: ranks are multiplied by 1 + recency_boost for brand-new documents, halving every 30 days of age (default: 0)",
+            "minimum": 0,
+            "maximum": 10
+        }),
+    );
+    properties
+}
+
+/// Input schema for the package filters of npm and Python query tools
+fn package_properties(doc_type: &str) -> Map<String, Value> {
+    let text = |description: &str| json!({"type": "string", "description": description});
+    let mut properties = Map::new();
+    if doc_type == PYTHON_DOC_TYPE {
+        properties.insert(
+            "package_name".to_string(),
+            text("Only documents of this Python package (e.g. 'requests'); matched after normalization (PEP 503)"),
+        );
+        properties.insert(
+            "package_version".to_string(),
+            text("Only documents of this package version"),
+        );
+        properties.insert(
+            "item_type".to_string(),
+            text("Only items of this kind: readme or web_page (a readthedocs page)"),
+        );
+        return properties;
+    }
+    properties.insert(
+        "package_name".to_string(),
+        text("Only documents of this npm package (e.g. 'zod' or '@tanstack/query-core')"),
+    );
+    properties.insert(
+        "package_version".to_string(),
+        text("Only documents of this package version"),
+    );
+    properties.insert(
+        "item_type".to_string(),
+        text("Only items of this kind: readme, function, class, interface, type, enum, constant or namespace"),
+    );
+    properties
+}
+
+/// Read the date filters and recency boost of a query tool call into `filters`
+///
+/// Returns whether any of them was given.
+fn parse_recency_arguments(arguments: &Value, filters: &mut MetadataFilters) -> Result<bool> {
+    let date = |key: &str| -> Result<Option<DateTime<Utc>>> {
+        arguments
+            .get(key)
+            .and_then(Value::as_str)
+            .map(|v| {
+                DateTime::parse_from_rfc3339(v.trim())
+                    .map(|d| d.with_timezone(&Utc))
+                    .map_err(|e| {
+                        anyhow!("Invalid '{key}' {v:?}: expected an RFC 3339 date-time ({e})")
+                    })
+            })
+            .transpose()
+    };
+    filters.created_after = date("created_after")?;
+    filters.created_before = date("created_before")?;
+    filters.updated_after = date("updated_after")?;
+    filters.recency_boost = arguments
+        .get("recency_boost")
+        .and_then(Value::as_f64)
+        .filter(|boost| *boost > 0.0);
+
+    Ok(filters.created_after.is_some()
+        || filters.created_before.is_some()
+        || filters.updated_after.is_some()
+        || filters.recency_boost.is_some())
+}
+
+/// Number of search results to fetch: the limit, or the rerank candidate set when reranking
+/// Embedding of a search query, from the query embedding cache unless `use_cache` is off
+async fn embed_query(
+    client: &(dyn EmbeddingClient + Send + Sync),
+    query: &str,
+    use_cache: bool,
+) -> Result<Vec<f32>> {
+    if use_cache {
+        query_cache().embedding(client, query).await
+    } else {
+        client.embed(query).await
+    }
+}
+
+fn candidate_limit(limit: i64, rerank: Option<&RerankConfig>) -> i64 {
+    rerank.map_or(limit, |config| {
+        limit.max(i64::try_from(config.candidates).unwrap_or(i64::MAX))
+    })
 }
 
 /// Rust documentation query tool
 pub struct RustQueryTool {
     db_pool: DatabasePool,
-    #[allow(dead_code)]
-    embedding_client: OpenAIEmbeddingClient,
+    embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
+    max_response_chars: usize,
+    reranker: Arc<dyn Reranker>,
+    rerank_config: RerankConfig,
 }
 
 impl RustQueryTool {
-    /// Create a new Rust query tool
     /// Create a new Rust query tool.
     ///
     /// # Errors
     ///
-    /// Returns an error if the embedding client fails to initialize.
+    /// Returns an error if the embedding client or the reranker fails to
+    /// initialize.
     pub fn new(db_pool: DatabasePool) -> Result<Self> {
-        let embedding_client = OpenAIEmbeddingClient::new()?;
+        Ok(
+            Self::with_embedding_client(db_pool, embedding_client_from_env()?)
+                .with_reranker(default_reranker()?, RerankConfig::from_env()),
+        )
+    }
 
-        Ok(Self {
+    /// Create a new Rust query tool using the given embedding client.
+    ///
+    /// The response size cap is read from `RUST_QUERY_MAX_RESPONSE_CHARS`
+    /// (default [`DEFAULT_MAX_RESPONSE_CHARS`]).
+    #[must_use]
+    pub fn with_embedding_client(
+        db_pool: DatabasePool,
+        embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
+    ) -> Self {
+        let max_response_chars = std::env::var("RUST_QUERY_MAX_RESPONSE_CHARS")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|&n| n > 0)
+            .unwrap_or(DEFAULT_MAX_RESPONSE_CHARS);
+
+        Self {
             db_pool,
             embedding_client,
-        })
+            max_response_chars,
+            reranker: missing_reranker(),
+            rerank_config: RerankConfig::from_env(),
+        }
+    }
+
+    /// Override the response size cap, in characters
+    #[must_use]
+    pub const fn with_max_response_chars(mut self, max_response_chars: usize) -> Self {
+        self.max_response_chars = max_response_chars;
+        self
+    }
+
+    /// Use `reranker` with `config` for calls that ask for `rerank`
+    #[must_use]
+    pub fn with_reranker(mut self, reranker: Arc<dyn Reranker>, config: RerankConfig) -> Self {
+        self.reranker = reranker;
+        self.rerank_config = config;
+        self
     }
 
     /// Perform semantic search for Rust documentation
-    async fn semantic_search(&self, query: &str, limit: Option<i64>) -> Result<String> {
+    #[allow(clippy::too_many_arguments)]
+    async fn semantic_search(
+        &self,
+        query: &str,
+        limit: i64,
+        filters: &MetadataFilters,
+        expanded_terms: &[String],
+        rerank_results: bool,
+        snippet: SnippetOptions,
+        use_cache: bool,
+        context: &ExecutionContext,
+    ) -> Result<String> {
         debug!("Performing Rust documentation search for: {}", query);
+        let rerank_config = rerank_results.then_some(&self.rerank_config);
 
         // Generate embeddings via OpenAI embedding client (Claude is not used here)
-        let query_embedding = self.embedding_client.embed(query).await?;
+        let query_embedding = context
+            .run(embed_query(
+                self.embedding_client.as_ref(),
+                query,
+                use_cache,
+            ))
+            .await?;
 
-        // Perform vector similarity search
-        let results = DocumentQueries::rust_vector_search(
-            self.db_pool.pool(),
-            query,
-            &query_embedding,
-            limit.unwrap_or(5),
-        )
-        .await?;
+        let results = context
+            .run(DocumentQueries::doc_type_search_scored(
+                self.db_pool.pool(),
+                "rust",
+                query,
+                &query_embedding,
+                candidate_limit(limit, rerank_config),
+                filters,
+            ))
+            .await?;
+        let mut results = group_adjacent_chunks(results);
+
+        let mut rerank_outcome = None;
+        if let Some(config) = rerank_config {
+            let (reranked, outcome) = context
+                .run(async { Ok(rerank(self.reranker.as_ref(), config, query, results).await) })
+                .await?;
+            results = reranked;
+            rerank_outcome = Some(outcome);
+        }
+        results.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
 
         if results.is_empty() {
             return Ok("No relevant Rust documentation found for your query.".to_string());
         }
 
-        // Format results
+        let contents: Vec<&str> = results
+            .iter()
+            .map(|r| r.document.content.as_str())
+            .collect();
+        let terms = match_terms(
+            self.db_pool.pool(),
+            "rust",
+            &highlight_text(query, expanded_terms),
+            &contents,
+        )
+        .await;
+        // Search ranks no longer describe the order once results are reranked
+        let top_score = if rerank_outcome == Some(RerankOutcome::Reranked) {
+            0.0
+        } else {
+            results.iter().map(|r| r.score).fold(0.0_f64, f64::max)
+        };
+        let budget = self
+            .max_response_chars
+            .saturating_sub(TRUNCATION_NOTE_RESERVE)
+            .max(1);
+
         let mut response = format!(
             "Found {} relevant Rust documentation results:\n\n",
             results.len()
         );
+        if !expanded_terms.is_empty() {
+            let _ = write!(
+                &mut response,
+                "Expanded terms: {}\n\n",
+                expanded_terms.join(", ")
+            );
+        }
+        let mut used = response.chars().count();
+        let mut shown = 0;
 
-        for (i, doc) in results.iter().enumerate() {
-            let metadata = doc
+        for (i, result) in results.iter().enumerate() {
+            let doc = &result.document;
+            let metadata_str = |key: &str| {
+                doc.metadata
+                    .get(key)
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown")
+                    .to_string()
+            };
+            // Shown with its version, since a crate may be ingested in several
+            let crate_name = match doc.metadata.get("crate_version").and_then(Value::as_str) {
+                Some(version) => format!("{} {version}", metadata_str("crate_name")),
+                None => metadata_str("crate_name"),
+            };
+            let module_path = metadata_str("module_path");
+            let item_type = metadata_str("item_type");
+
+            // Ranks are relative to the best hit; the ILIKE fallback has no rank
+            let relevance = if top_score > 0.0 {
+                result.score / top_score
+            } else {
+                DynamicQueryTool::calculate_relevance_score(i, results.len())
+            };
+
+            // Duplicate pages were stored once, under the first URL crawled
+            let alternates = db::alternate_urls(&doc.metadata);
+            let also_at = if alternates.is_empty() {
+                String::new()
+            } else {
+                format!("Also at: {}\n", alternates.join(", "))
+            };
+            // Crawled pages record the docs.rs URL they came from
+            let cite = doc
                 .metadata
-                .as_object()
-                .and_then(|m| m.get("crate_name"))
-                .and_then(|c| c.as_str())
-                .unwrap_or("unknown");
+                .get("source_url")
+                .and_then(Value::as_str)
+                .map_or_else(String::new, |url| format!("Source: {url}\n"));
 
-            let _ = write!(
-                &mut response,
-                "{}. **{}** (from `{metadata}`)\n{}...\n\n",
+            let entry = format!(
+                "{}. **{}** (from `{crate_name}`)\n*{item_type}* `{module_path}` | Relevance: {:.1}%\n{cite}{also_at}\n{}\n\n",
                 i + 1,
                 doc.doc_path,
-                doc.content.chars().take(1200).collect::<String>()
+                relevance * 100.0,
+                build_snippet(&doc.content, &terms[i], snippet)
+            );
+
+            let entry_len = entry.chars().count();
+            if used + entry_len > budget {
+                if shown == 0 {
+                    // Always return something, even if the cap is smaller than one result
+                    response.extend(entry.chars().take(budget.saturating_sub(used)));
+                    response.push_str("...\n\n");
+                    shown = 1;
+                }
+                break;
+            }
+
+            response.push_str(&entry);
+            used += entry_len;
+            shown += 1;
+        }
+
+        if shown < results.len() {
+            let _ = writeln!(
+                &mut response,
+                "⚠️ Response truncated: showing {shown} of {} results to stay within {} characters. Narrow the query or lower `limit` to see more.",
+                results.len(),
+                self.max_response_chars
             );
         }
+        if let Some(note) = rerank_outcome.and_then(RerankOutcome::fallback_note) {
+            let _ = writeln!(&mut response, "ℹ️ {note}");
+        }
 
         Ok(response)
     }
@@ -98,7 +932,7 @@ impl RustQueryTool {
 #[async_trait]
 impl Tool for RustQueryTool {
     fn definition(&self) -> Value {
-        json!({
+        let mut definition = json!({
             "name": "rust_query",
             "description": "Search and retrieve information from Rust crate documentation. Query across 40+ popular Rust crates including tokio, serde, clap, sqlx, axum, and more.",
             "inputSchema": {
@@ -108,34 +942,117 @@ impl Tool for RustQueryTool {
                         "type": "string",
                         "description": "The search query. Can be a specific function name, concept, or natural language question about Rust code."
                     },
+                    "crate_name": {
+                        "type": "string",
+                        "description": "Only search documentation of this crate (e.g., 'tokio')"
+                    },
+                    "crate_version": {
+                        "type": "string",
+                        "description": "Only search documentation of this crate version (e.g., '1.38.0'), for crates ingested in several versions"
+                    },
                     "limit": {
                         "type": "integer",
-                        "description": "Maximum number of results to return (default: 5, max: 20)",
+                        "description": "Maximum number of results to return (default: 10, max: 20)",
                         "minimum": 1,
                         "maximum": 20
-                    }
+                    },
+                    "format": {
+                        "type": "string",
+                        "description": "Filter by content format (metadata 'format')"
+                    },
+                    "complexity": {
+                        "type": "string",
+                        "description": "Filter by complexity level (metadata 'complexity')"
+                    },
+                    "topic": {
+                        "type": "string",
+                        "description": "Filter by topic (metadata 'topic')"
+                    },
+                    "item_type": {
+                        "type": "string",
+                        "description": "Only return items of this kind, e.g. 'struct', 'trait', 'macro', or 'example' for runnable code snippets"
+                    },
+                    "rerank": rerank_property(),
+                    "cache": cache_property(),
+                    "expand": expand_property()
                 },
                 "required": ["query"]
             }
-        })
+        });
+        if let Some(properties) = definition["inputSchema"]["properties"].as_object_mut() {
+            properties.extend(recency_properties());
+            properties.extend(snippet_properties(SNIPPET_CHARS));
+        }
+        definition
     }
 
     async fn execute(&self, arguments: Value) -> Result<String> {
+        self.execute_with_context(arguments, &ExecutionContext::default())
+            .await
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ExecutionContext,
+    ) -> Result<String> {
         let query = arguments
             .get("query")
             .and_then(|q| q.as_str())
             .ok_or_else(|| anyhow!("Missing required 'query' parameter"))?;
 
-        let limit = arguments.get("limit").and_then(Value::as_i64);
+        let limit = arguments.get("limit").and_then(Value::as_i64).unwrap_or(10);
 
         // Validate limit
-        if let Some(l) = limit {
-            if !(1..=20).contains(&l) {
-                return Err(anyhow!("Limit must be between 1 and 20"));
-            }
+        if !(1..=20).contains(&limit) {
+            return Err(anyhow!("Limit must be between 1 and 20"));
         }
 
-        self.semantic_search(query, limit).await
+        let string_arg = |key: &str| {
+            arguments
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(ToString::to_string)
+        };
+        let mut filters = MetadataFilters {
+            format: string_arg("format"),
+            complexity: string_arg("complexity"),
+            topic: string_arg("topic"),
+            crate_name: string_arg("crate_name"),
+            crate_version: string_arg("crate_version"),
+            item_type: string_arg("item_type"),
+            ..MetadataFilters::default()
+        };
+        parse_recency_arguments(&arguments, &mut filters)?;
+        let expansion = expansion_for(&arguments, query);
+        filters.expansions = expansion.phrasings;
+
+        let rerank_results = arguments
+            .get("rerank")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let snippet = SnippetOptions::from_arguments(&arguments, SNIPPET_CHARS)?;
+        let use_cache = cache_enabled(&arguments);
+
+        let search = self.semantic_search(
+            query,
+            limit,
+            &filters,
+            &expansion.terms,
+            rerank_results,
+            snippet,
+            use_cache,
+            context,
+        );
+        if use_cache {
+            query_cache()
+                .result(ResultKey::new("rust", query, &arguments), search)
+                .await
+        } else {
+            search.await
+        }
     }
 }
 
@@ -143,8 +1060,9 @@ impl Tool for RustQueryTool {
 pub struct DynamicQueryTool {
     config: ToolConfig,
     db_pool: DatabasePool,
-    #[allow(dead_code)]
-    embedding_client: OpenAIEmbeddingClient,
+    embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
+    reranker: Arc<dyn Reranker>,
+    rerank_config: RerankConfig,
 }
 
 impl DynamicQueryTool {
@@ -152,28 +1070,135 @@ impl DynamicQueryTool {
     ///
     /// # Errors
     ///
-    /// Returns an error if the embedding client fails to initialize.
+    /// Returns an error if the embedding client or the reranker fails to
+    /// initialize.
     pub fn new(config: ToolConfig, db_pool: DatabasePool) -> Result<Self> {
-        let embedding_client = OpenAIEmbeddingClient::new()?;
+        Ok(
+            Self::with_embedding_client(config, db_pool, embedding_client_from_env()?)
+                .with_reranker(default_reranker()?, RerankConfig::from_env()),
+        )
+    }
 
-        Ok(Self {
+    /// Create a new dynamic query tool using the given embedding client
+    #[must_use]
+    pub fn with_embedding_client(
+        config: ToolConfig,
+        db_pool: DatabasePool,
+        embedding_client: Arc<dyn EmbeddingClient + Send + Sync>,
+    ) -> Self {
+        Self {
             config,
             db_pool,
             embedding_client,
-        })
+            reranker: missing_reranker(),
+            rerank_config: RerankConfig::from_env(),
+        }
+    }
+
+    /// Use `reranker` with `rerank_config` for calls that ask for `rerank`
+    #[must_use]
+    pub fn with_reranker(
+        mut self,
+        reranker: Arc<dyn Reranker>,
+        rerank_config: RerankConfig,
+    ) -> Self {
+        self.reranker = reranker;
+        self.rerank_config = rerank_config;
+        self
+    }
+
+    /// Create a query tool for a doc type that has no entry in the tools configuration
+    ///
+    /// The tool name is derived from the doc type (e.g. `solana` -> `solana_query`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the embedding client or the reranker fails to
+    /// initialize.
+    pub fn for_doc_type(doc_type: &str, db_pool: DatabasePool) -> Result<Self> {
+        let mut title_chars = doc_type.chars();
+        let title = title_chars.next().map_or_else(String::new, |first| {
+            first.to_uppercase().chain(title_chars).collect::<String>()
+        });
+
+        let config = ToolConfig {
+            name: Self::tool_name_for_doc_type(doc_type),
+            doc_type: doc_type.to_string(),
+            title: format!("{title} Documentation Query"),
+            description: format!(
+                "Search {doc_type} documentation ingested from configured document sources."
+            ),
+            enabled: true,
+            metadata_hints: None,
+            filters: None,
+            default_limit: None,
+            timeout_secs: None,
+        };
+
+        Self::new(config, db_pool)
+    }
+
+    /// Doc type the tool searches
+    #[must_use]
+    pub fn doc_type(&self) -> &str {
+        &self.config.doc_type
+    }
+
+    /// Tool name used for a doc type's query tool
+    #[must_use]
+    pub fn tool_name_for_doc_type(doc_type: &str) -> String {
+        let slug: String = doc_type
+            .trim()
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("{slug}_query")
+    }
+
+    /// Tool name as registered (from configuration or derived from the doc type)
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    /// Results returned when a call gives no `limit`
+    fn default_limit(&self) -> i64 {
+        self.config.default_limit.unwrap_or(5)
+    }
+
+    /// Whether the configuration enables the metadata filter `key`
+    fn filter_enabled(&self, key: &str) -> bool {
+        self.config
+            .filters
+            .as_ref()
+            .is_none_or(|filters| filters.iter().any(|f| f == key))
     }
 
     /// Perform semantic search for documents of the configured type
+    #[allow(clippy::too_many_arguments)]
     async fn semantic_search(
         &self,
         query: &str,
         limit: Option<i64>,
         filters: Option<MetadataFilters>,
+        expanded_terms: &[String],
+        rerank_results: bool,
+        snippet: SnippetOptions,
+        use_cache: bool,
     ) -> Result<String> {
         debug!(
             "Performing {} documentation search for: {}",
             self.config.doc_type, query
         );
+        let rerank_config = rerank_results.then_some(&self.rerank_config);
+        let result_limit = limit.unwrap_or_else(|| self.default_limit());
+        let search_limit = candidate_limit(result_limit, rerank_config);
 
         // Use config doc_type directly (already in correct format)
         let db_doc_type = self.config.doc_type.as_str();
@@ -190,7 +1215,13 @@ impl DynamicQueryTool {
 
         // Try vector search first, fallback to text search if vector extension not available
         let results = match self
-            .try_vector_search(query, db_doc_type, limit, filters.as_ref())
+            .try_vector_search(
+                query,
+                db_doc_type,
+                Some(search_limit),
+                filters.as_ref(),
+                use_cache,
+            )
             .await
         {
             Ok(results) => {
@@ -203,9 +1234,19 @@ impl DynamicQueryTool {
             }
             Err(e) => {
                 warn!("Vector search failed ({}), falling back to text search", e);
-                self.text_search(query, db_doc_type, limit).await?
+                self.text_search(query, db_doc_type, Some(search_limit))
+                    .await?
             }
         };
+        let mut results = group_adjacent_chunks(results);
+
+        let mut rerank_outcome = None;
+        if let Some(config) = rerank_config {
+            let (reranked, outcome) = rerank(self.reranker.as_ref(), config, query, results).await;
+            results = reranked;
+            rerank_outcome = Some(outcome);
+        }
+        results.truncate(usize::try_from(result_limit).unwrap_or(5));
 
         if results.is_empty() {
             return Ok(format!(
@@ -220,6 +1261,22 @@ impl DynamicQueryTool {
             results.len(),
             self.config.title
         );
+        if !expanded_terms.is_empty() {
+            let _ = write!(
+                &mut response,
+                "Expanded terms: {}\n\n",
+                expanded_terms.join(", ")
+            );
+        }
+
+        let contents: Vec<&str> = results.iter().map(|doc| doc.content.as_str()).collect();
+        let terms = match_terms(
+            self.db_pool.pool(),
+            db_doc_type,
+            &highlight_text(query, expanded_terms),
+            &contents,
+        )
+        .await;
 
         for (i, doc) in results.iter().enumerate() {
             // Extract source information from metadata
@@ -227,16 +1284,20 @@ impl DynamicQueryTool {
             let relevance_score = Self::calculate_relevance_score(i, results.len());
 
             // Apply adaptive formatting based on content type
-            let formatted_content = Self::format_content_adaptively(doc);
+            let formatted_content = Self::format_content_adaptively(doc, &terms[i], snippet);
+            let sources = Self::extract_sources(doc);
 
             let _ = write!(
                 &mut response,
-                "{}. **{}** ({source_info})\n*Relevance: {:.1}%*\n\n{formatted_content}\n\n",
+                "{}. **{}** ({source_info})\n*Relevance: {:.1}%*\n\n{formatted_content}\n\n📎 Sources: {sources}\n\n",
                 i + 1,
                 doc.doc_path,
                 relevance_score * 100.0,
             );
         }
+        if let Some(note) = rerank_outcome.and_then(RerankOutcome::fallback_note) {
+            let _ = writeln!(&mut response, "ℹ️ {note}");
+        }
 
         Ok(response)
     }
@@ -455,7 +1516,14 @@ impl DynamicQueryTool {
     }
 
     /// Format content adaptively based on metadata hints
-    fn format_content_adaptively(doc: &db::models::Document) -> String {
+    ///
+    /// Text formats show a snippet around `terms`; diagrams and PDFs keep
+    /// their own previews.
+    fn format_content_adaptively(
+        doc: &db::models::Document,
+        terms: &[String],
+        snippet: SnippetOptions,
+    ) -> String {
         // Check content format from metadata
         let format = doc
             .metadata
@@ -495,24 +1563,7 @@ impl DynamicQueryTool {
                     doc.content.chars().take(1000).collect::<String>()
                 )
             }
-            Some("markdown") | None => {
-                // Format as markdown with proper structure
-                let content_preview = doc.content.chars().take(1500).collect::<String>();
-                if content_preview.starts_with('#') {
-                    // Already has markdown headers
-                    format!("{content_preview}...")
-                } else {
-                    // Add context
-                    format!("```markdown\n{content_preview}...\n```")
-                }
-            }
-            _ => {
-                // Default formatting
-                format!(
-                    "{content_preview}...",
-                    content_preview = doc.content.chars().take(1000).collect::<String>()
-                )
-            }
+            _ => build_snippet(&doc.content, terms, snippet),
         }
     }
 
@@ -575,6 +1626,29 @@ impl DynamicQueryTool {
         }
     }
 
+    /// Citable locations for a result: its origin URL if known, the document path, and the source name
+    fn extract_sources(doc: &db::models::Document) -> String {
+        let mut sources = Vec::new();
+        for key in ["source_url", "url"] {
+            if let Some(url) = doc.metadata.get(key).and_then(Value::as_str) {
+                if !sources.iter().any(|s: &String| s == url) {
+                    sources.push(url.to_string());
+                }
+            }
+        }
+        sources.push(format!("`{}`", doc.doc_path));
+        if let Some(module_path) = doc.metadata.get("module_path").and_then(Value::as_str) {
+            sources.push(format!("module `{module_path}`"));
+        }
+        sources.extend(
+            db::alternate_urls(&doc.metadata)
+                .into_iter()
+                .map(|url| format!("also at {url}")),
+        );
+        sources.push(format!("source `{}`", doc.source_name));
+        sources.join(" | ")
+    }
+
     /// Try vector search with real embeddings
     async fn try_vector_search(
         &self,
@@ -582,9 +1656,10 @@ impl DynamicQueryTool {
         db_doc_type: &str,
         limit: Option<i64>,
         filters: Option<&MetadataFilters>,
+        use_cache: bool,
     ) -> Result<Vec<db::models::Document>> {
         // Generate embeddings via OpenAI embedding client (Claude is not used here)
-        let query_embedding = self.embedding_client.embed(query).await?;
+        let query_embedding = embed_query(self.embedding_client.as_ref(), query, use_cache).await?;
 
         // Perform vector similarity search filtered by doc_type and metadata
         let results = if let Some(metadata_filters) = filters {
@@ -671,58 +1746,73 @@ impl Tool for DynamicQueryTool {
             },
             "limit": {
                 "type": "integer",
-                "description": "Maximum number of results to return (default: 5, max: 20)",
+                "description": format!(
+                    "Maximum number of results to return (default: {}, max: {MAX_QUERY_LIMIT})",
+                    self.default_limit()
+                ),
                 "minimum": 1,
-                "maximum": 20
-            }
+                "maximum": MAX_QUERY_LIMIT
+            },
+            "rerank": rerank_property(),
+            "cache": cache_property(),
+            "expand": expand_property()
         });
 
-        // Add optional filter properties based on metadata hints
-        if let Some(hints) = &self.config.metadata_hints {
-            let properties_obj = properties.as_object_mut().unwrap();
-
-            if !hints.supported_formats.is_empty() {
-                properties_obj.insert("format".to_string(), json!({
-                    "type": "string",
-                    "description": format!("Filter by content format. Supported: {}", hints.supported_formats.join(", ")),
-                    "enum": hints.supported_formats
-                }));
-            }
-
-            if !hints.supported_complexity_levels.is_empty() {
-                properties_obj.insert("complexity".to_string(), json!({
-                    "type": "string", 
-                    "description": format!("Filter by complexity level. Supported: {}", hints.supported_complexity_levels.join(", ")),
-                    "enum": hints.supported_complexity_levels
-                }));
-            }
-
-            if !hints.supported_categories.is_empty() {
-                properties_obj.insert("category".to_string(), json!({
-                    "type": "string",
-                    "description": format!("Filter by category. Supported: {}", hints.supported_categories.join(", ")),
-                    "enum": hints.supported_categories
-                }));
-            }
+        // Metadata filters are available unless the configuration narrows
+        // them; hints restrict them to known values
+        let hints = self.config.metadata_hints.as_ref();
+        let properties_obj = properties.as_object_mut().unwrap();
 
-            if !hints.supported_topics.is_empty() {
-                properties_obj.insert("topic".to_string(), json!({
+        if let Some(formats) = hints
+            .map(|h| &h.supported_formats)
+            .filter(|f| !f.is_empty() && self.filter_enabled("format"))
+        {
+            properties_obj.insert(
+                "format".to_string(),
+                filter_property("Filter by content format", formats),
+            );
+        }
+        if self.filter_enabled("complexity") {
+            properties_obj.insert(
+                "complexity".to_string(),
+                filter_property(
+                    "Filter by complexity level",
+                    hints.map_or(&[][..], |h| &h.supported_complexity_levels),
+                ),
+            );
+        }
+        if self.filter_enabled("category") {
+            properties_obj.insert(
+                "category".to_string(),
+                filter_property(
+                    "Filter by category",
+                    hints.map_or(&[][..], |h| &h.supported_categories),
+                ),
+            );
+        }
+        if self.filter_enabled("topic") {
+            properties_obj.insert(
+                "topic".to_string(),
+                filter_property(
+                    "Filter by topic",
+                    hints.map_or(&[][..], |h| &h.supported_topics),
+                ),
+            );
+        }
+        if self.filter_enabled("api_version") && hints.is_none_or(|h| h.supports_api_version) {
+            properties_obj.insert(
+                "api_version".to_string(),
+                json!({
                     "type": "string",
-                    "description": format!("Filter by topic. Supported: {}", hints.supported_topics.join(", ")),
-                    "enum": hints.supported_topics
-                }));
-            }
-
-            if hints.supports_api_version {
-                properties_obj.insert(
-                    "api_version".to_string(),
-                    json!({
-                        "type": "string",
-                        "description": "Filter by API version (e.g., 'v1', 'v2')"
-                    }),
-                );
-            }
+                    "description": "Filter by API version (e.g., 'v1', 'v2')"
+                }),
+            );
+        }
+        if [NPM_DOC_TYPE, PYTHON_DOC_TYPE].contains(&self.config.doc_type.as_str()) {
+            properties_obj.extend(package_properties(&self.config.doc_type));
         }
+        properties_obj.extend(recency_properties());
+        properties_obj.extend(snippet_properties(DYNAMIC_SNIPPET_CHARS));
 
         json!({
             "name": self.config.name,
@@ -736,6 +1826,15 @@ impl Tool for DynamicQueryTool {
     }
 
     async fn execute(&self, arguments: Value) -> Result<String> {
+        self.execute_with_context(arguments, &ExecutionContext::default())
+            .await
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ExecutionContext,
+    ) -> Result<String> {
         let query = arguments
             .get("query")
             .and_then(|q| q.as_str())
@@ -745,112 +1844,340 @@ impl Tool for DynamicQueryTool {
 
         // Validate limit
         if let Some(l) = limit {
-            if !(1..=20).contains(&l) {
-                return Err(anyhow!("Limit must be between 1 and 20"));
+            if !(1..=MAX_QUERY_LIMIT).contains(&l) {
+                return Err(anyhow!("Limit must be between 1 and {MAX_QUERY_LIMIT}"));
             }
         }
 
         // Parse optional metadata filters
-        let filters = self.parse_metadata_filters(&arguments)?;
-
-        self.semantic_search(query, limit, filters).await
+        let mut filters = self.parse_metadata_filters(&arguments)?;
+        // Expansions reach full-text search through the filtered search path
+        let expansion = expansion_for(&arguments, query);
+        if !expansion.phrasings.is_empty() {
+            filters
+                .get_or_insert_with(MetadataFilters::default)
+                .expansions = expansion.phrasings;
+        }
+        let rerank_results = arguments
+            .get("rerank")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let snippet = SnippetOptions::from_arguments(&arguments, DYNAMIC_SNIPPET_CHARS)?;
+        let use_cache = cache_enabled(&arguments);
+
+        let search = context.run(self.semantic_search(
+            query,
+            limit,
+            filters,
+            &expansion.terms,
+            rerank_results,
+            snippet,
+            use_cache,
+        ));
+        if use_cache {
+            query_cache()
+                .result(
+                    ResultKey::new(&self.config.doc_type, query, &arguments),
+                    search,
+                )
+                .await
+        } else {
+            search.await
+        }
     }
 }
 
 // Legacy IngestTool implementation removed - use intelligent ingestion endpoint instead
 
+/// Admin tool returning per-tool call counts, error counts and latency percentiles
+pub struct GetToolMetricsTool;
+
+#[async_trait]
+impl Tool for GetToolMetricsTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "get_tool_metrics",
+            "description": "Per-tool call counts, error counts, p50/p95/p99 latency and the last error since the server started, as JSON.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "tool": {
+                        "type": "string",
+                        "description": "Only return metrics for this tool (optional)"
+                    }
+                },
+                "required": []
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        self.execute_with_context(arguments, &ExecutionContext::default())
+            .await
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ExecutionContext,
+    ) -> Result<String> {
+        let filter = arguments.get("tool").and_then(Value::as_str);
+        let tools = crate::metrics::metrics()
+            .tool_metrics()
+            .into_iter()
+            .filter(|t| filter.is_none_or(|name| t.tool == name))
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        let page = continuations().paginate_items(
+            context.session_id(),
+            Map::new(),
+            "tools",
+            tools,
+            response_soft_cap(),
+        );
+        Ok(serde_json::to_string_pretty(&page)?)
+    }
+}
+
+/// Maximum audit entries returned per `query_audit_log` page
+const MAX_AUDIT_PAGE_SIZE: i64 = 200;
+
+/// Admin tool listing recorded tool invocations from the audit log
+pub struct QueryAuditLogTool {
+    db_pool: DatabasePool,
+}
+
+impl QueryAuditLogTool {
+    /// Create a new audit log query tool
+    #[must_use]
+    pub fn new(db_pool: DatabasePool) -> Self {
+        Self { db_pool }
+    }
+}
+
+/// Parse an optional RFC 3339 timestamp argument
+fn timestamp_arg(arguments: &Value, key: &str) -> Result<Option<DateTime<Utc>>> {
+    arguments
+        .get(key)
+        .and_then(Value::as_str)
+        .map(|v| {
+            DateTime::parse_from_rfc3339(v)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| anyhow!("Invalid '{key}' timestamp '{v}': {e}"))
+        })
+        .transpose()
+}
+
+#[async_trait]
+impl Tool for QueryAuditLogTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "query_audit_log",
+            "description": "List recorded tool invocations, newest first, with caller, redacted arguments, outcome and duration, as JSON.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "since": {
+                        "type": "string",
+                        "description": "Only entries at or after this RFC 3339 timestamp (optional)"
+                    },
+                    "until": {
+                        "type": "string",
+                        "description": "Only entries before this RFC 3339 timestamp (optional)"
+                    },
+                    "tool": {
+                        "type": "string",
+                        "description": "Only entries for this tool (optional)"
+                    },
+                    "session_id": {
+                        "type": "string",
+                        "description": "Only entries from this MCP session (optional)"
+                    },
+                    "page": {
+                        "type": "integer",
+                        "description": "Page number, starting at 1 (default: 1)",
+                        "minimum": 1
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Entries per page (default: 50, max: 200)",
+                        "minimum": 1,
+                        "maximum": MAX_AUDIT_PAGE_SIZE
+                    }
+                },
+                "required": []
+            }
+        })
+    }
+
+    fn requires_admin(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        self.execute_with_context(arguments, &ExecutionContext::default())
+            .await
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ExecutionContext,
+    ) -> Result<String> {
+        let arg = |key: &str| arguments.get(key).and_then(Value::as_str).map(String::from);
+        let filter = ToolAuditFilter {
+            since: timestamp_arg(&arguments, "since")?,
+            until: timestamp_arg(&arguments, "until")?,
+            tool_name: arg("tool"),
+            session_id: arg("session_id"),
+        };
+        let page = arguments
+            .get("page")
+            .and_then(Value::as_i64)
+            .unwrap_or(1)
+            .max(1);
+        let limit = arguments
+            .get("limit")
+            .and_then(Value::as_i64)
+            .unwrap_or(50)
+            .clamp(1, MAX_AUDIT_PAGE_SIZE);
+
+        // Fetch one extra row to tell whether another page follows
+        let mut entries = ToolAuditQueries::find_entries(
+            self.db_pool.pool(),
+            &filter,
+            limit + 1,
+            (page - 1) * limit,
+        )
+        .await?;
+        let has_more = entries.len() > usize::try_from(limit).unwrap_or(usize::MAX);
+        entries.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+
+        // Entries that do not fit the response budget come back through fetch_continuation
+        let mut envelope = Map::new();
+        envelope.insert("page".to_string(), json!(page));
+        envelope.insert("limit".to_string(), json!(limit));
+        envelope.insert("has_more".to_string(), json!(has_more));
+        let entries = entries
+            .into_iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        let response = continuations().paginate_items(
+            context.session_id(),
+            envelope,
+            "entries",
+            entries,
+            response_soft_cap(),
+        );
+        Ok(serde_json::to_string_pretty(&response)?)
+    }
+}
+
 impl DynamicQueryTool {
     /// Parse metadata filters from arguments
     fn parse_metadata_filters(&self, arguments: &Value) -> Result<Option<MetadataFilters>> {
-        let mut filters = MetadataFilters::default();
-        let mut has_filters = false;
-
-        // Extract format filter
-        if let Some(format_val) = arguments.get("format").and_then(|f| f.as_str()) {
-            if let Some(hints) = &self.config.metadata_hints {
-                if hints.supported_formats.contains(&format_val.to_string()) {
-                    filters.format = Some(format_val.to_string());
-                    has_filters = true;
-                } else {
-                    return Err(anyhow!(
-                        "Unsupported format '{}'. Supported: {}",
-                        format_val,
-                        hints.supported_formats.join(", ")
-                    ));
-                }
-            }
-        }
+        let hints = self.config.metadata_hints.as_ref();
+        let arg = |key: &str| arguments.get(key).and_then(Value::as_str);
 
-        // Extract complexity filter
-        if let Some(complexity_val) = arguments.get("complexity").and_then(|c| c.as_str()) {
-            if let Some(hints) = &self.config.metadata_hints {
-                if hints
-                    .supported_complexity_levels
-                    .contains(&complexity_val.to_string())
-                {
-                    filters.complexity = Some(complexity_val.to_string());
-                    has_filters = true;
-                } else {
-                    return Err(anyhow!(
-                        "Unsupported complexity '{}'. Supported: {}",
-                        complexity_val,
-                        hints.supported_complexity_levels.join(", ")
-                    ));
-                }
-            }
+        if let Some(key) = FILTER_KEYS
+            .iter()
+            .find(|key| arg(key).is_some() && !self.filter_enabled(key))
+        {
+            return Err(anyhow!("Filter '{key}' is not enabled for this tool"));
         }
 
-        // Extract category filter
-        if let Some(category_val) = arguments.get("category").and_then(|c| c.as_str()) {
-            if let Some(hints) = &self.config.metadata_hints {
-                if hints
-                    .supported_categories
-                    .contains(&category_val.to_string())
-                {
-                    filters.category = Some(category_val.to_string());
-                    has_filters = true;
-                } else {
-                    return Err(anyhow!(
-                        "Unsupported category '{}'. Supported: {}",
-                        category_val,
-                        hints.supported_categories.join(", ")
-                    ));
+        let mut filters = MetadataFilters {
+            format: arg("format")
+                .map(|v| {
+                    check_allowed("format", v, hints.map_or(&[][..], |h| &h.supported_formats))
+                })
+                .transpose()?,
+            complexity: arg("complexity")
+                .map(|v| {
+                    check_allowed(
+                        "complexity",
+                        v,
+                        hints.map_or(&[][..], |h| &h.supported_complexity_levels),
+                    )
+                })
+                .transpose()?,
+            category: arg("category")
+                .map(|v| {
+                    check_allowed(
+                        "category",
+                        v,
+                        hints.map_or(&[][..], |h| &h.supported_categories),
+                    )
+                })
+                .transpose()?,
+            topic: arg("topic")
+                .map(|v| check_allowed("topic", v, hints.map_or(&[][..], |h| &h.supported_topics)))
+                .transpose()?,
+            api_version: match arg("api_version") {
+                Some(_) if hints.is_some_and(|h| !h.supports_api_version) => {
+                    return Err(anyhow!("API version filtering not supported for this tool"));
                 }
-            }
-        }
-
-        // Extract topic filter
-        if let Some(topic_val) = arguments.get("topic").and_then(|t| t.as_str()) {
-            if let Some(hints) = &self.config.metadata_hints {
-                if hints.supported_topics.contains(&topic_val.to_string()) {
-                    filters.topic = Some(topic_val.to_string());
-                    has_filters = true;
+                v => v.map(ToString::to_string),
+            },
+            ..MetadataFilters::default()
+        };
+        if [NPM_DOC_TYPE, PYTHON_DOC_TYPE].contains(&self.config.doc_type.as_str()) {
+            let text = |key: &str| {
+                arg(key)
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(ToString::to_string)
+            };
+            filters.package_name = text("package_name").map(|name| {
+                if self.config.doc_type == PYTHON_DOC_TYPE {
+                    normalize_package_name(&name)
                 } else {
-                    return Err(anyhow!(
-                        "Unsupported topic '{}'. Supported: {}",
-                        topic_val,
-                        hints.supported_topics.join(", ")
-                    ));
+                    name
                 }
-            }
+            });
+            filters.package_version = text("package_version");
+            filters.item_type = text("item_type");
         }
+        let has_dates = parse_recency_arguments(arguments, &mut filters)?;
+
+        let has_filters = has_dates
+            || filters.format.is_some()
+            || filters.complexity.is_some()
+            || filters.category.is_some()
+            || filters.topic.is_some()
+            || filters.api_version.is_some()
+            || filters.package_name.is_some()
+            || filters.package_version.is_some()
+            || filters.item_type.is_some();
+
+        Ok(has_filters.then_some(filters))
+    }
+}
 
-        // Extract API version filter
-        if let Some(api_version_val) = arguments.get("api_version").and_then(|v| v.as_str()) {
-            if let Some(hints) = &self.config.metadata_hints {
-                if hints.supports_api_version {
-                    filters.api_version = Some(api_version_val.to_string());
-                    has_filters = true;
-                } else {
-                    return Err(anyhow!("API version filtering not supported for this tool"));
-                }
-            }
-        }
+/// Input schema for a metadata filter, restricted to `allowed` when it is non-empty
+fn filter_property(description: &str, allowed: &[String]) -> Value {
+    if allowed.is_empty() {
+        json!({
+            "type": "string",
+            "description": description
+        })
+    } else {
+        json!({
+            "type": "string",
+            "description": format!("{description}. Supported: {}", allowed.join(", ")),
+            "enum": allowed
+        })
+    }
+}
 
-        if has_filters {
-            Ok(Some(filters))
-        } else {
-            Ok(None)
-        }
+/// Validate a filter value against the configured values (an empty list allows any value)
+fn check_allowed(kind: &str, value: &str, allowed: &[String]) -> Result<String> {
+    if allowed.is_empty() || allowed.iter().any(|a| a == value) {
+        Ok(value.to_string())
+    } else {
+        Err(anyhow!(
+            "Unsupported {kind} '{value}'. Supported: {}",
+            allowed.join(", ")
+        ))
     }
 }
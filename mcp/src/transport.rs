@@ -6,20 +6,21 @@
 use axum::{
     body::Body,
     extract::{Request, State},
-    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    http::{HeaderMap, HeaderValue, Method, StatusCode},
     response::{
         sse::{Event, KeepAlive, Sse},
         IntoResponse, Response,
     },
     Json,
 };
+use http_body_util::BodyExt;
 use serde_json::{json, Value};
-use std::collections::{HashMap, VecDeque};
+use std::collections::HashMap;
 use std::convert::Infallible;
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 use thiserror::Error;
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, watch};
 use tracing::{debug, error, info, warn, Instrument};
 use uuid::Uuid;
 
@@ -33,14 +34,23 @@ const SENSITIVE_HEADERS: &[&str] = &[
     "x-api-key-id",
 ];
 
+use crate::audit::ToolCaller;
 use crate::headers::{
     set_json_response_headers, set_standard_headers, validate_protocol_version, MCP_SESSION_ID,
     SUPPORTED_PROTOCOL_VERSION,
 };
 use crate::metrics::metrics;
-use crate::security::{add_security_headers, validate_dns_rebinding, validate_origin};
+use crate::rate_limit::{RateLimit, RateLimiter, RequestClass};
+use crate::security::{
+    add_security_headers, authenticate_bearer, is_admin_request, log_security_event,
+    validate_dns_rebinding, validate_origin, SecurityError, SecurityEventSeverity,
+};
 use crate::server::McpServerState;
 use crate::session::ClientInfo;
+use crate::tool_schema::InvalidToolArguments;
+use crate::tools::{
+    RequestCancelled, ToolConcurrencyExhausted, ToolDisabled, ToolError, ToolTimedOut,
+};
 
 /// Transport configuration
 #[derive(Clone, Debug)]
@@ -48,7 +58,18 @@ pub struct TransportConfig {
     pub protocol_version: String,
     pub session_timeout: Duration,
     pub heartbeat_interval: Duration,
+    /// Largest JSON-RPC request body accepted, checked while the body is read
     pub max_json_body_bytes: usize,
+    /// Characters of a tool result returned at once; the rest waits behind a
+    /// continuation token (0 disables the limit)
+    pub max_response_chars: usize,
+    /// Per-client limit for JSON-RPC POST and session DELETE requests
+    pub post_rate_limit: RateLimit,
+    /// Per-client limit for SSE GET requests, tracked separately from POSTs
+    pub sse_rate_limit: RateLimit,
+    /// Take the client address from `X-Forwarded-For`/`X-Real-IP` instead of
+    /// the peer address; only safe behind a proxy that sets them
+    pub trust_proxy_headers: bool,
 }
 
 impl Default for TransportConfig {
@@ -58,117 +79,167 @@ impl Default for TransportConfig {
             session_timeout: Duration::from_secs(1800), // 30 minutes for SSE connections
             heartbeat_interval: Duration::from_secs(30), // 30 seconds
             max_json_body_bytes: 2 * 1024 * 1024, // 2 MiB default, matching Axum's default body limit
+            max_response_chars: 1_000_000,
+            post_rate_limit: RateLimit::new(600, 120),
+            sse_rate_limit: RateLimit::new(60, 10),
+            trust_proxy_headers: false,
+        }
+    }
+}
+
+impl TransportConfig {
+    /// Build transport configuration from environment variables with defaults
+    ///
+    /// Supported variables (a rate of 0 disables the limit):
+    /// - `MCP_RATE_LIMIT_RPM`, `MCP_RATE_LIMIT_BURST` for POST requests
+    /// - `MCP_SSE_RATE_LIMIT_RPM`, `MCP_SSE_RATE_LIMIT_BURST` for SSE GET requests
+    /// - `MCP_TRUST_PROXY_HEADERS=true` to key clients by forwarded addresses
+    /// - `MCP_MAX_BODY_BYTES` for the request body limit (0 is ignored)
+    /// - `MCP_MAX_RESPONSE_CHARS` for the tool result limit (0 disables it)
+    #[must_use]
+    pub fn from_env() -> Self {
+        fn env_u32(name: &str, default: u32) -> u32 {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(default)
+        }
+        fn env_usize(name: &str, default: usize) -> usize {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse().ok())
+                .unwrap_or(default)
         }
+
+        let mut cfg = Self::default();
+        cfg.post_rate_limit = RateLimit::new(
+            env_u32(
+                "MCP_RATE_LIMIT_RPM",
+                cfg.post_rate_limit.requests_per_minute,
+            ),
+            env_u32("MCP_RATE_LIMIT_BURST", cfg.post_rate_limit.burst),
+        );
+        cfg.sse_rate_limit = RateLimit::new(
+            env_u32(
+                "MCP_SSE_RATE_LIMIT_RPM",
+                cfg.sse_rate_limit.requests_per_minute,
+            ),
+            env_u32("MCP_SSE_RATE_LIMIT_BURST", cfg.sse_rate_limit.burst),
+        );
+        cfg.max_json_body_bytes = match env_usize("MCP_MAX_BODY_BYTES", 0) {
+            0 => cfg.max_json_body_bytes,
+            limit => limit,
+        };
+        cfg.max_response_chars = env_usize("MCP_MAX_RESPONSE_CHARS", cfg.max_response_chars);
+        cfg.trust_proxy_headers = std::env::var("MCP_TRUST_PROXY_HEADERS")
+            .is_ok_and(|v| v.trim() == "1" || v.trim().eq_ignore_ascii_case("true"));
+        cfg
     }
 }
 
 /// Session identifier type
 pub type SessionId = Uuid;
 
-/// SSE message structure for future streaming support
-#[derive(Debug, Clone)]
-pub struct SseMessage {
-    pub id: Option<String>,
-    pub event: Option<String>,
-    pub data: String,
-}
+pub use crate::sse::SseMessage;
+use crate::sse::SubscriptionEvent;
 
-/// In-memory SSE hub for per-session message broadcasting with replay buffer.
-#[derive(Debug)]
-struct SessionStream {
-    sender: broadcast::Sender<SseMessage>,
-    buffer: VecDeque<(u64, SseMessage)>,
-    next_id: u64,
-}
+/// SSE event name sent to every open stream when the server shuts down
+pub const SHUTDOWN_EVENT: &str = "shutdown";
 
-impl SessionStream {
-    fn new() -> Self {
-        let (sender, _) = broadcast::channel(256);
-        Self {
-            sender,
-            buffer: VecDeque::with_capacity(256),
-            next_id: 1,
-        }
+/// Set once shutdown starts; every open SSE stream holds a receiver
+///
+/// Shutdown is process-local, so it never goes through the hub where a
+/// shared replay buffer would hand it to streams on other replicas.
+static SHUTDOWN: std::sync::LazyLock<watch::Sender<bool>> =
+    std::sync::LazyLock::new(|| watch::channel(false).0);
+
+fn shutdown_message() -> SseMessage {
+    SseMessage {
+        id: None,
+        event: Some(SHUTDOWN_EVENT.to_string()),
+        data: json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/shutdown",
+            "params": { "reason": "server shutting down", "reconnect": true }
+        })
+        .to_string(),
     }
 }
 
-#[derive(Debug, Default)]
-struct SseHub {
-    sessions: RwLock<HashMap<Uuid, Arc<RwLock<SessionStream>>>>,
+/// Send a final `shutdown` event to every SSE session and close their streams
+///
+/// Returns the number of open streams notified.
+pub fn broadcast_shutdown() -> usize {
+    SHUTDOWN.send_replace(true);
+    SHUTDOWN.receiver_count()
 }
 
-impl SseHub {
-    const MAX_BUFFER: usize = 256;
+/// Bumped whenever the set of listed tools changes; every open SSE stream holds a receiver
+///
+/// Like shutdown this is process-local: the change was made to this
+/// process's tool registry.
+static TOOLS_CHANGED: std::sync::LazyLock<watch::Sender<u64>> =
+    std::sync::LazyLock::new(|| watch::channel(0).0);
+
+fn tools_list_changed_message() -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/tools/list_changed"
+    })
+    .to_string()
+}
 
-    fn new() -> Self {
-        Self {
-            sessions: RwLock::new(HashMap::new()),
-        }
-    }
+/// Send `notifications/tools/list_changed` to every open SSE stream
+///
+/// Returns the number of open streams notified.
+pub fn broadcast_tools_list_changed() -> usize {
+    TOOLS_CHANGED.send_modify(|version| *version += 1);
+    TOOLS_CHANGED.receiver_count()
+}
 
-    fn get_or_create(&self, session_id: Uuid) -> Arc<RwLock<SessionStream>> {
-        if let Ok(map) = self.sessions.read() {
-            if let Some(s) = map.get(&session_id) {
-                return Arc::clone(s);
-            }
-        }
-        let new_stream = Arc::new(RwLock::new(SessionStream::new()));
-        if let Ok(mut map) = self.sessions.write() {
-            let entry = map
-                .entry(session_id)
-                .or_insert_with(|| Arc::clone(&new_stream));
-            Arc::clone(entry)
-        } else {
-            new_stream
-        }
-    }
+/// SSE event name sent to a session's stream when the session is terminated
+pub const SESSION_CLOSED_EVENT: &str = "session_closed";
 
-    #[allow(dead_code)]
-    fn subscribe(&self, session_id: Uuid) -> broadcast::Receiver<SseMessage> {
-        let s = self.get_or_create(session_id);
-        s.read().map_or_else(
-            |_| {
-                let (tx, _rx) = broadcast::channel(1);
-                tx.subscribe()
-            },
-            |ss| ss.sender.subscribe(),
-        )
+fn session_closed_message() -> SseMessage {
+    SseMessage {
+        id: None,
+        event: Some(SESSION_CLOSED_EVENT.to_string()),
+        data: json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/session_closed",
+            "params": { "reason": "session terminated by administrator", "reconnect": false }
+        })
+        .to_string(),
     }
+}
 
-    #[allow(dead_code)]
-    fn snapshot_from(&self, session_id: Uuid, last_id: Option<u64>) -> Vec<(u64, SseMessage)> {
-        let s = self.get_or_create(session_id);
-        let Ok(ss) = s.read() else { return vec![] };
-        let start_after = last_id.unwrap_or(0);
-        ss.buffer
-            .iter()
-            .filter(|(id, _)| *id > start_after)
-            .cloned()
-            .collect()
+/// Delete a session and send a final `session_closed` event to its SSE stream
+///
+/// The event goes through the hub, so a stream held by another replica is
+/// closed as well. Returns whether the session existed.
+pub async fn terminate_session(state: &McpServerState, session_id: Uuid) -> bool {
+    if state
+        .comprehensive_session_manager
+        .delete_session(session_id)
+        .is_err()
+    {
+        return false;
     }
-
-    fn publish(&self, session_id: Uuid, mut msg: SseMessage) -> u64 {
-        let s = self.get_or_create(session_id);
-        let mut id_assigned = 0;
-        if let Ok(mut ss) = s.write() {
-            let id = ss.next_id;
-            ss.next_id = ss.next_id.saturating_add(1);
-            msg.id = Some(id.to_string());
-            // buffer and trim
-            ss.buffer.push_back((id, msg.clone()));
-            while ss.buffer.len() > Self::MAX_BUFFER {
-                ss.buffer.pop_front();
-            }
-            // best-effort broadcast
-            let _ = ss.sender.send(msg);
-            id_assigned = id;
-        }
-        id_assigned
+    metrics().increment_sessions_deleted();
+    if let Err(e) = state
+        .sse_hub
+        .publish(session_id, session_closed_message())
+        .await
+    {
+        warn!(
+            "Failed to notify SSE stream of closed session {}: {}",
+            session_id, e
+        );
     }
+    info!("Terminated session {}", session_id);
+    true
 }
 
-static SSE_HUB: std::sync::LazyLock<SseHub> = std::sync::LazyLock::new(SseHub::new);
-
 /// MCP session state
 #[derive(Debug, Clone)]
 pub struct McpSession {
@@ -417,8 +488,8 @@ pub enum TransportError {
     #[error("JSON parsing error: {0}")]
     JsonParseError(String),
 
-    #[error("Payload too large")]
-    PayloadTooLarge,
+    #[error("Payload too large, limit is {0} bytes")]
+    PayloadTooLarge(usize),
 
     #[error("Internal server error: {0}")]
     InternalError(String),
@@ -431,6 +502,12 @@ pub enum TransportError {
 
     #[error("Unacceptable Accept header: {0}")]
     UnacceptableAcceptHeader(String),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Rate limit exceeded, retry after {0}s")]
+    RateLimited(u64),
 }
 
 impl IntoResponse for TransportError {
@@ -448,32 +525,102 @@ impl IntoResponse for TransportError {
                 (StatusCode::UNSUPPORTED_MEDIA_TYPE, "Unsupported Media Type")
             }
             Self::JsonParseError(_) => (StatusCode::BAD_REQUEST, "Invalid JSON"),
-            Self::PayloadTooLarge => (StatusCode::PAYLOAD_TOO_LARGE, "Payload Too Large"),
+            Self::PayloadTooLarge(_) => (StatusCode::PAYLOAD_TOO_LARGE, "Payload Too Large"),
             Self::InternalError(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error"),
             Self::SecurityValidationFailed(_) => {
                 (StatusCode::FORBIDDEN, "Security Validation Failed")
             }
             Self::InvalidAcceptHeader(_) => (StatusCode::BAD_REQUEST, "Invalid Accept Header"),
             Self::UnacceptableAcceptHeader(_) => (StatusCode::NOT_ACCEPTABLE, "Not Acceptable"),
+            Self::Unauthorized(_) => (StatusCode::UNAUTHORIZED, "Unauthorized"),
+            Self::RateLimited(_) => (StatusCode::TOO_MANY_REQUESTS, "Too Many Requests"),
         };
 
         error!("Transport error: {}", self);
 
-        let error_response = json!({
-            "error": {
-                "code": -32600,
-                "message": error_message,
-                "data": self.to_string()
-            }
-        });
+        let jsonrpc_code = match self {
+            Self::Unauthorized(_) => Some(-32001),
+            Self::RateLimited(_) => Some(-32029),
+            _ => None,
+        };
+        let error_response = if let Some(code) = jsonrpc_code {
+            json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": {
+                    "code": code,
+                    "message": error_message,
+                    "data": self.to_string()
+                }
+            })
+        } else if let Self::PayloadTooLarge(limit) = self {
+            json!({
+                "error": {
+                    "code": -32600,
+                    "message": error_message,
+                    "data": { "detail": self.to_string(), "maxBodyBytes": limit }
+                }
+            })
+        } else {
+            json!({
+                "error": {
+                    "code": -32600,
+                    "message": error_message,
+                    "data": self.to_string()
+                }
+            })
+        };
 
         let mut headers = HeaderMap::new();
         set_json_response_headers(&mut headers, None);
+        if matches!(self, Self::Unauthorized(_)) {
+            headers.insert(
+                axum::http::header::WWW_AUTHENTICATE,
+                HeaderValue::from_static("Bearer"),
+            );
+        }
+        if let Self::RateLimited(retry_after) = self {
+            headers.insert(
+                axum::http::header::RETRY_AFTER,
+                HeaderValue::from(retry_after),
+            );
+        }
 
         (status, headers, Json(error_response)).into_response()
     }
 }
 
+/// Read a request body of at most `limit` bytes
+///
+/// A declared `Content-Length` over the limit is rejected before reading.
+/// The body itself is counted as it streams in, so chunked bodies and
+/// bodies longer than their `Content-Length` fail as soon as they pass the
+/// limit instead of being buffered whole.
+async fn read_limited_body(
+    request: Request<Body>,
+    limit: usize,
+) -> Result<axum::body::Bytes, TransportError> {
+    let declared = request
+        .headers()
+        .get(axum::http::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok());
+    if declared.is_some_and(|len| len > limit as u64) {
+        return Err(TransportError::PayloadTooLarge(limit));
+    }
+
+    let body = http_body_util::Limited::new(request.into_body(), limit);
+    match body.collect().await {
+        Ok(collected) => Ok(collected.to_bytes()),
+        Err(e) if e.is::<http_body_util::LengthLimitError>() => {
+            Err(TransportError::PayloadTooLarge(limit))
+        }
+        Err(e) => Err(TransportError::InternalError(format!(
+            "Failed to read body: {e}"
+        ))),
+    }
+}
+
 /// Unified MCP endpoint handler supporting both POST (JSON) and GET (SSE)
 ///
 /// This handler processes all MCP requests according to the 2025-06-18 specification:
@@ -511,6 +658,8 @@ pub async fn unified_mcp_handler(
         uri = %uri,
         protocol_version = %protocol_version
     );
+    // Join the caller's trace when a gateway forwarded one
+    crate::telemetry::set_remote_parent(&span, &headers);
 
     async move {
         // Increment total request counter (count every incoming request)
@@ -542,7 +691,9 @@ pub async fn unified_mcp_handler(
             // Avoid duplicate header dumps for SSE GET; detailed SSE headers are logged in handle_sse_request
             if !is_sse_get {
                 for (name, value) in &headers {
-                    if let Ok(v) = value.to_str() {
+                    if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                        info!("  Header: {}: <redacted>", name);
+                    } else if let Ok(v) = value.to_str() {
                         info!("  Header: {}: {}", name, v);
                     }
                 }
@@ -560,6 +711,102 @@ pub async fn unified_mcp_handler(
     .await
 }
 
+/// Check the bearer token when authentication is configured and attribute the request
+fn authenticate_request(
+    state: &McpServerState,
+    headers: &HeaderMap,
+    request_id: Uuid,
+) -> Result<Option<String>, TransportError> {
+    match authenticate_bearer(headers, &state.security_config) {
+        Ok(Some(label)) => {
+            metrics().record_client_request(&label);
+            log_security_event(
+                "auth_success",
+                &format!("request {request_id} authenticated as client '{label}'"),
+                SecurityEventSeverity::Info,
+            );
+            Ok(Some(label))
+        }
+        Ok(None) => Ok(None),
+        Err(SecurityError::Unauthorized(reason)) => {
+            metrics().increment_auth_failures();
+            log_security_event(
+                "auth_failure",
+                &format!("request {request_id} rejected: {reason}"),
+                SecurityEventSeverity::Warning,
+            );
+            Err(TransportError::Unauthorized(reason))
+        }
+        Err(e) => Err(TransportError::SecurityValidationFailed(e.to_string())),
+    }
+}
+
+/// Identify the client for rate limiting
+///
+/// A request naming a live session is keyed by that session, anything else
+/// by the client address. Headers the client controls never pick the key on
+/// their own: an unknown session ID counts against the address, and the
+/// forwarded address headers are read only with `trust_proxy_headers`.
+fn rate_limit_key(state: &McpServerState, headers: &HeaderMap, request: &Request<Body>) -> String {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+    };
+
+    if let Some(session_id) = header(MCP_SESSION_ID)
+        .and_then(|v| Uuid::parse_str(v).ok())
+        .filter(|id| state.comprehensive_session_manager.get_session(*id).is_ok())
+    {
+        return format!("session:{session_id}");
+    }
+    if state.transport_config.trust_proxy_headers {
+        // The last entry is the address the nearest proxy saw; earlier ones
+        // came from the client
+        let forwarded = header("X-Forwarded-For")
+            .and_then(|v| v.rsplit(',').next())
+            .map(str::trim)
+            .or_else(|| header("X-Real-IP"))
+            .and_then(|ip| ip.parse::<std::net::IpAddr>().ok());
+        if let Some(ip) = forwarded {
+            return format!("ip:{ip}");
+        }
+    }
+    request
+        .extensions()
+        .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
+        .map_or_else(
+            || "ip:unknown".to_string(),
+            |info| format!("ip:{}", info.0.ip()),
+        )
+}
+
+/// Take a token from the client's bucket or fail with `RateLimited`
+fn enforce_rate_limit(
+    state: &McpServerState,
+    headers: &HeaderMap,
+    request: &Request<Body>,
+    class: RequestClass,
+    request_id: Uuid,
+) -> Result<(), TransportError> {
+    let key = rate_limit_key(state, headers, request);
+    state
+        .rate_limiter
+        .check(class, &key)
+        .map_err(|retry_after| {
+            metrics().increment_rate_limited(class);
+            warn!(
+                request_id = %request_id,
+                client = %key,
+                class = ?class,
+                "Rate limit exceeded"
+            );
+            TransportError::RateLimited(retry_after.as_secs().max(1))
+        })
+}
+
 /// Internal implementation of the MCP handler with request ID context
 async fn unified_mcp_handler_impl(
     state: McpServerState,
@@ -584,6 +831,27 @@ async fn unified_mcp_handler_impl(
         };
     }
 
+    // Throttle per client before any handler work, and before authentication
+    // so rejected tokens count against the budget too
+    let class = match *request.method() {
+        Method::POST | Method::DELETE => Some(RequestClass::Post),
+        Method::GET => Some(RequestClass::Sse),
+        _ => None,
+    };
+    if let Some(class) = class {
+        enforce_rate_limit(&state, &headers, &request, class, request_id)?;
+    }
+
+    // Authenticate before any session is created or looked up
+    let client_label = if matches!(
+        *request.method(),
+        Method::POST | Method::GET | Method::DELETE
+    ) {
+        authenticate_request(&state, &headers, request_id)?
+    } else {
+        None
+    };
+
     // Validate Accept header for method compatibility
     // POST: application/json, GET: text/event-stream, HEAD: skip (no body expected)
     if request.method() != Method::HEAD {
@@ -591,32 +859,16 @@ async fn unified_mcp_handler_impl(
     }
 
     match *request.method() {
-        Method::POST => handle_json_rpc_request(state, headers, request, request_id).await,
+        Method::POST => {
+            handle_json_rpc_request(state, headers, request, request_id, client_label).await
+        }
         Method::DELETE => handle_delete_session_request(&state, &headers, request_id),
-        Method::GET => handle_sse_request(&state, &headers, request_id),
+        Method::GET => handle_sse_request(&state, &headers, request_id).await,
         Method::OPTIONS => {
-            // CORS preflight response (204 No Content) with permissive headers for internal usage
+            // CORS headers come from the CORS layer alone
             let mut response_headers = HeaderMap::new();
             set_standard_headers(&mut response_headers, None);
             add_security_headers(&mut response_headers);
-            response_headers.insert(
-                HeaderName::from_static("access-control-allow-methods"),
-                HeaderValue::from_static("GET, POST, DELETE, OPTIONS, HEAD"),
-            );
-            response_headers.insert(
-                HeaderName::from_static("access-control-allow-headers"),
-                HeaderValue::from_static(
-                    "Accept, Accept-Language, Content-Type, Cache-Control, MCP-Protocol-Version, Mcp-Session-Id, X-Client-Id",
-                ),
-            );
-            response_headers.insert(
-                HeaderName::from_static("access-control-allow-origin"),
-                HeaderValue::from_static("*"),
-            );
-            response_headers.insert(
-                HeaderName::from_static("access-control-max-age"),
-                HeaderValue::from_static("600"),
-            );
             Ok((StatusCode::NO_CONTENT, response_headers, "").into_response())
         }
         Method::HEAD => {
@@ -976,6 +1228,7 @@ async fn handle_json_rpc_request(
     headers: HeaderMap,
     request: Request<Body>,
     request_id: Uuid,
+    client_label: Option<String>,
 ) -> Result<Response, TransportError> {
     debug!(request_id = %request_id, "Processing JSON-RPC request");
 
@@ -1012,32 +1265,7 @@ async fn handle_json_rpc_request(
 
     debug!(request_id = %request_id, session_id = %session_id, "Session associated with request");
 
-    // Enforce a maximum body size similar to Axum's Json extractor default
-    let max_body_bytes = state.transport_config.max_json_body_bytes;
-
-    // If Content-Length is present and exceeds the limit, reject early
-    if let Some(len_header) = headers.get("content-length") {
-        if let Ok(len_str) = len_header.to_str() {
-            if let Ok(len) = len_str.parse::<u64>() {
-                if len > max_body_bytes as u64 {
-                    return Err(TransportError::PayloadTooLarge);
-                }
-            }
-        }
-    }
-
-    // Extract request body with an explicit limit
-    let body_bytes = axum::body::to_bytes(request.into_body(), max_body_bytes)
-        .await
-        .map_err(|e| {
-            let msg = e.to_string();
-            let lower = msg.to_ascii_lowercase();
-            if lower.contains("length") && lower.contains("limit") || lower.contains("too large") {
-                TransportError::PayloadTooLarge
-            } else {
-                TransportError::InternalError(format!("Failed to read body: {msg}"))
-            }
-        })?;
+    let body_bytes = read_limited_body(request, state.transport_config.max_json_body_bytes).await?;
 
     // Log raw body for Cursor debugging
     let user_agent = headers
@@ -1097,7 +1325,29 @@ async fn handle_json_rpc_request(
         .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
         .unwrap_or(false);
 
-    match state.handler.handle_request(json_request).await {
+    let caller = ToolCaller {
+        session_id: Some(session_id.to_string()),
+        client_label,
+        user_agent: headers
+            .get("user-agent")
+            .and_then(|v| v.to_str().ok())
+            .map(ToString::to_string),
+        admin: is_admin_request(&headers, &state.security_config),
+    };
+    // Backstop above every tool budget, so a tool timeout error is still delivered
+    let http_timeout = state.handler.http_timeout();
+    let outcome = tokio::time::timeout(
+        http_timeout,
+        state.handler.handle_caller_request(json_request, &caller),
+    )
+    .await
+    .unwrap_or_else(|_| {
+        Err(anyhow::anyhow!(
+            "Request timed out after {}s",
+            http_timeout.as_secs()
+        ))
+    });
+    match outcome {
         Ok(result_value) => {
             metrics().increment_post_success();
             // Enhanced logging for Cursor responses
@@ -1138,14 +1388,20 @@ async fn handle_json_rpc_request(
             if mirror_to_sse {
                 let payload =
                     serde_json::to_string(&envelope).unwrap_or_else(|_| envelope.to_string());
-                let _ = SSE_HUB.publish(
-                    session_id,
-                    SseMessage {
-                        id: None,
-                        event: Some("message".to_string()),
-                        data: payload,
-                    },
-                );
+                if let Err(e) = state
+                    .sse_hub
+                    .publish(
+                        session_id,
+                        SseMessage {
+                            id: None,
+                            event: Some("message".to_string()),
+                            data: payload,
+                        },
+                    )
+                    .await
+                {
+                    warn!(request_id = %request_id, session_id = %session_id, "Failed to mirror response to SSE: {}", e);
+                }
             }
 
             // Return a standard JSON-RPC response for POST
@@ -1154,6 +1410,111 @@ async fn handle_json_rpc_request(
             add_security_headers(&mut response_headers);
             Ok((StatusCode::OK, response_headers, Json(envelope)).into_response())
         }
+        Err(e) if e.downcast_ref::<ToolDisabled>().is_some() && !is_notification => {
+            info!(request_id = %request_id, session_id = %session_id, "{}", e);
+            let error_envelope = json!({
+                "jsonrpc": "2.0",
+                "id": jsonrpc_id_value,
+                "error": {
+                    "code": -32601,
+                    "message": e.to_string()
+                }
+            });
+            let mut response_headers = HeaderMap::new();
+            set_json_response_headers(&mut response_headers, Some(session_id));
+            add_security_headers(&mut response_headers);
+            Ok((StatusCode::OK, response_headers, Json(error_envelope)).into_response())
+        }
+        Err(e) if e.downcast_ref::<ToolTimedOut>().is_some() && !is_notification => {
+            warn!(request_id = %request_id, session_id = %session_id, "{}", e);
+            let data = e.downcast_ref::<ToolTimedOut>().map(|timed_out| {
+                json!({"tool": timed_out.tool, "budget_secs": timed_out.budget.as_secs_f64()})
+            });
+            let error_envelope = json!({
+                "jsonrpc": "2.0",
+                "id": jsonrpc_id_value,
+                "error": {
+                    "code": -32001,
+                    "message": e.to_string(),
+                    "data": data
+                }
+            });
+            let mut response_headers = HeaderMap::new();
+            set_json_response_headers(&mut response_headers, Some(session_id));
+            add_security_headers(&mut response_headers);
+            Ok((StatusCode::OK, response_headers, Json(error_envelope)).into_response())
+        }
+        Err(e) if e.downcast_ref::<ToolConcurrencyExhausted>().is_some() && !is_notification => {
+            let data = e
+                .downcast_ref::<ToolConcurrencyExhausted>()
+                .map(|exhausted| {
+                    json!({
+                        "scope": exhausted.scope,
+                        "limit": exhausted.limit,
+                        "waited_ms": u64::try_from(exhausted.waited.as_millis()).unwrap_or(u64::MAX)
+                    })
+                });
+            let error_envelope = json!({
+                "jsonrpc": "2.0",
+                "id": jsonrpc_id_value,
+                "error": {
+                    "code": -32029,
+                    "message": e.to_string(),
+                    "data": data
+                }
+            });
+            let mut response_headers = HeaderMap::new();
+            set_json_response_headers(&mut response_headers, Some(session_id));
+            add_security_headers(&mut response_headers);
+            Ok((StatusCode::OK, response_headers, Json(error_envelope)).into_response())
+        }
+        Err(e) if e.downcast_ref::<ToolError>().is_some() && !is_notification => {
+            info!(request_id = %request_id, session_id = %session_id, "{}", e);
+            let (code, data) = e
+                .downcast_ref::<ToolError>()
+                .map(|tool_error| (tool_error.jsonrpc_code(), tool_error.to_json()))
+                .unwrap_or_default();
+            let error_envelope = json!({
+                "jsonrpc": "2.0",
+                "id": jsonrpc_id_value,
+                "error": {
+                    "code": code,
+                    "message": e.to_string(),
+                    "data": data
+                }
+            });
+            let mut response_headers = HeaderMap::new();
+            set_json_response_headers(&mut response_headers, Some(session_id));
+            add_security_headers(&mut response_headers);
+            Ok((StatusCode::OK, response_headers, Json(error_envelope)).into_response())
+        }
+        Err(e) if e.downcast_ref::<InvalidToolArguments>().is_some() && !is_notification => {
+            info!(request_id = %request_id, session_id = %session_id, "{}", e);
+            let data = e
+                .downcast_ref::<InvalidToolArguments>()
+                .map(|invalid| json!({"tool": invalid.tool, "violations": invalid.violations}));
+            let error_envelope = json!({
+                "jsonrpc": "2.0",
+                "id": jsonrpc_id_value,
+                "error": {
+                    "code": -32602,
+                    "message": e.to_string(),
+                    "data": data
+                }
+            });
+            let mut response_headers = HeaderMap::new();
+            set_json_response_headers(&mut response_headers, Some(session_id));
+            add_security_headers(&mut response_headers);
+            Ok((StatusCode::OK, response_headers, Json(error_envelope)).into_response())
+        }
+        Err(e) if e.downcast_ref::<RequestCancelled>().is_some() => {
+            // Cancelled calls must not send a result or error envelope
+            info!(request_id = %request_id, session_id = %session_id, jsonrpc_id = %jsonrpc_id_str, "Request cancelled by client");
+            let mut response_headers = HeaderMap::new();
+            set_json_response_headers(&mut response_headers, Some(session_id));
+            add_security_headers(&mut response_headers);
+            Ok((StatusCode::NO_CONTENT, response_headers, Body::empty()).into_response())
+        }
         Err(e) => {
             metrics().increment_internal_errors();
 
@@ -1197,14 +1558,20 @@ async fn handle_json_rpc_request(
             if mirror_to_sse {
                 let payload = serde_json::to_string(&error_envelope)
                     .unwrap_or_else(|_| error_envelope.to_string());
-                let _ = SSE_HUB.publish(
-                    session_id,
-                    SseMessage {
-                        id: None,
-                        event: Some("error".to_string()),
-                        data: payload,
-                    },
-                );
+                if let Err(e) = state
+                    .sse_hub
+                    .publish(
+                        session_id,
+                        SseMessage {
+                            id: None,
+                            event: Some("error".to_string()),
+                            data: payload,
+                        },
+                    )
+                    .await
+                {
+                    warn!(request_id = %request_id, session_id = %session_id, "Failed to mirror error to SSE: {}", e);
+                }
             }
 
             // Always return JSON-RPC error envelope with HTTP 200 per JSON-RPC semantics
@@ -1220,7 +1587,7 @@ async fn handle_json_rpc_request(
 ///
 /// # Errors
 /// Returns a `TransportError` if session creation or SSE setup fails.
-fn handle_sse_request(
+async fn handle_sse_request(
     state: &McpServerState,
     headers: &HeaderMap,
     request_id: Uuid,
@@ -1234,7 +1601,9 @@ fn handle_sse_request(
         info!(request_id = %request_id, "🔍 CURSOR SSE REQUEST - Establishing SSE connection");
         // Log ALL headers at INFO level for Cursor SSE
         for (name, value) in headers {
-            if let Ok(v) = value.to_str() {
+            if SENSITIVE_HEADERS.contains(&name.as_str()) {
+                info!("  SSE Header: {}: <redacted>", name);
+            } else if let Ok(v) = value.to_str() {
                 info!("  SSE Header: {}: {}", name, v);
             }
         }
@@ -1259,11 +1628,23 @@ fn handle_sse_request(
         .and_then(|v| v.to_str().ok())
         .and_then(|s| s.parse::<u64>().ok());
 
-    // Receiver for live messages
-    let mut rx = SSE_HUB.subscribe(session_id);
+    // Receiver for live messages, taken before the snapshot so nothing falls in between
+    let mut rx = state.sse_hub.subscribe(session_id).await.map_err(|e| {
+        error!(request_id = %request_id, "SSE subscribe failed: {}", e);
+        TransportError::InternalError(format!("SSE stream unavailable: {e}"))
+    })?;
 
     // Snapshot for replay, if any
-    let snapshot: Vec<(u64, SseMessage)> = SSE_HUB.snapshot_from(session_id, last_event_id);
+    let snapshot: Vec<(u64, SseMessage)> = state
+        .sse_hub
+        .snapshot_from(session_id, last_event_id)
+        .await
+        .map_err(|e| {
+            error!(request_id = %request_id, "SSE replay failed: {}", e);
+            TransportError::InternalError(format!("SSE replay unavailable: {e}"))
+        })?;
+    let mut shutdown = SHUTDOWN.subscribe();
+    let mut tools_changed = TOOLS_CHANGED.subscribe();
 
     let stream = async_stream::stream! {
         info!(request_id = %request_id, "SSE stream established for session {}; replay_from={:?}", session_id, last_event_id);
@@ -1276,14 +1657,26 @@ fn handle_sse_request(
             "method": "notifications/initialized",
             "params": {
                 "protocolVersion": SUPPORTED_PROTOCOL_VERSION,
-                "capabilities": { "tools": { "listChanged": true } }
+                "capabilities": {
+                    "tools": { "listChanged": true },
+                    "resources": { "listChanged": false }
+                }
             }
         }).to_string();
         let init_event = Event::default().event("message").data(init_payload);
         yield Ok::<Event, Infallible>(init_event);
 
+        // Streams opened during shutdown only get the shutdown event
+        if *shutdown.borrow_and_update() {
+            let msg = shutdown_message();
+            yield Ok::<Event, Infallible>(Event::default().event(SHUTDOWN_EVENT).data(msg.data));
+            return;
+        }
+
         // First, deliver any buffered messages newer than Last-Event-ID
+        let mut last_sent = last_event_id.unwrap_or(0);
         for (id, msg) in snapshot {
+            last_sent = last_sent.max(id);
             let mut ev = Event::default();
             if let Some(name) = msg.event.clone() { ev = ev.event(name); }
             ev = ev.id(id.to_string());
@@ -1299,21 +1692,39 @@ fn handle_sse_request(
                     let keep_alive = Event::default().comment("keep-alive");
                     yield Ok::<Event, Infallible>(keep_alive);
                 }
+                _ = shutdown.changed() => {
+                    let msg = shutdown_message();
+                    yield Ok::<Event, Infallible>(Event::default().event(SHUTDOWN_EVENT).data(msg.data));
+                    // Close the stream so graceful shutdown can complete
+                    break;
+                }
+                Ok(()) = tools_changed.changed() => {
+                    yield Ok::<Event, Infallible>(Event::default().event("message").data(tools_list_changed_message()));
+                }
                 recv = rx.recv() => {
                     match recv {
-                        Ok(msg) => {
+                        SubscriptionEvent::Message(msg) => {
+                            // Already delivered by the replay snapshot
+                            let id = msg.id.as_deref().and_then(|id| id.parse::<u64>().ok());
+                            if id.is_some_and(|id| id <= last_sent) {
+                                continue;
+                            }
                             let mut ev = Event::default();
                             if let Some(name) = msg.event.clone() { ev = ev.event(name); }
                             if let Some(id) = msg.id.clone() { ev = ev.id(id); }
                             ev = ev.data(msg.data.clone());
                             yield Ok::<Event, Infallible>(ev);
+                            // The session is gone; nothing more will arrive
+                            if msg.event.as_deref() == Some(SESSION_CLOSED_EVENT) {
+                                break;
+                            }
                         }
-                        Err(broadcast::error::RecvError::Lagged(_)) => {
+                        SubscriptionEvent::Lagged => {
                             // On lag, send a comment to hint client it may want to reconnect
                             let hint = Event::default().comment("lagged");
                             yield Ok::<Event, Infallible>(hint);
                         }
-                        Err(broadcast::error::RecvError::Closed) => {
+                        SubscriptionEvent::Closed => {
                             // Hub closed; end stream
                             break;
                         }
@@ -1348,9 +1759,9 @@ fn handle_sse_request(
 
 /// Initialize transport with session cleanup task
 ///
-/// This function starts a background task that periodically cleans up expired sessions.
-/// It should be called during server startup.
-pub async fn initialize_transport(session_manager: SessionManager) {
+/// This function starts a background task that periodically cleans up expired sessions
+/// and idle rate-limit buckets. It should be called during server startup.
+pub async fn initialize_transport(session_manager: SessionManager, rate_limiter: RateLimiter) {
     let cleanup_interval = Duration::from_secs(60); // Cleanup every minute
     let manager = session_manager;
 
@@ -1370,6 +1781,11 @@ pub async fn initialize_transport(session_manager: SessionManager) {
                     error!("Session cleanup failed: {}", e);
                 }
             }
+
+            let idle = rate_limiter.cleanup_idle();
+            if idle > 0 {
+                debug!("Rate limiter cleanup: removed {} idle buckets", idle);
+            }
         }
     });
 
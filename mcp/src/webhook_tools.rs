@@ -0,0 +1,274 @@
+//! Webhook subscription management tools for MCP
+//!
+//! Admin-only tools to subscribe URLs to job events, list and delete the
+//! subscriptions, and inspect the delivery log. Secrets are write-only:
+//! they are never returned by these tools.
+
+use crate::tools::Tool;
+use crate::webhooks::WebhookEvent;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use db::{DatabasePool, WebhookQueries};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// Default and maximum deliveries returned by `list_webhook_deliveries`
+const DEFAULT_DELIVERY_LIMIT: i64 = 50;
+const MAX_DELIVERY_LIMIT: i64 = 500;
+
+/// Read a required, non-empty string argument
+fn required_str<'a>(arguments: &'a Value, key: &str) -> Result<&'a str> {
+    arguments
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| anyhow!("Missing required parameter: {key}"))
+}
+
+/// Read an optional webhook id argument
+fn optional_id(arguments: &Value, key: &str) -> Result<Option<Uuid>> {
+    arguments
+        .get(key)
+        .and_then(Value::as_str)
+        .map(|id| Uuid::parse_str(id.trim()).map_err(|_| anyhow!("Invalid {key}: {id}")))
+        .transpose()
+}
+
+/// Event names for the input schemas
+fn event_names() -> Vec<&'static str> {
+    WebhookEvent::ALL
+        .iter()
+        .map(|event| event.as_str())
+        .collect()
+}
+
+/// Subscribe a URL to job events
+pub struct AddWebhookTool {
+    db_pool: DatabasePool,
+}
+
+impl AddWebhookTool {
+    /// Create a new webhook subscription tool
+    #[must_use]
+    pub const fn new(db_pool: DatabasePool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl Tool for AddWebhookTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "add_webhook",
+            "description": "Subscribe a URL to crate and package job events. Each event is POSTed as JSON with an X-Signature header holding the HMAC-SHA256 of the body keyed with the secret (sha256=<hex>). Requires the admin token.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "url": {
+                        "type": "string",
+                        "description": "http(s) URL receiving the events"
+                    },
+                    "secret": {
+                        "type": "string",
+                        "description": "Key the payloads are signed with"
+                    },
+                    "events": {
+                        "type": "array",
+                        "items": {"type": "string", "enum": event_names()},
+                        "description": "Events to deliver; all when omitted or empty"
+                    }
+                },
+                "required": ["url", "secret"]
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        let url = required_str(&arguments, "url")?;
+        let parsed = url::Url::parse(url).map_err(|e| anyhow!("Invalid url '{url}': {e}"))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(anyhow!("Webhook url must use http or https: {url}"));
+        }
+        let secret = required_str(&arguments, "secret")?;
+        let mut events = Vec::new();
+        for name in arguments
+            .get("events")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let event = name
+                .as_str()
+                .and_then(WebhookEvent::parse)
+                .ok_or_else(|| anyhow!("Unknown webhook event: {name}"))?;
+            let event = event.as_str().to_string();
+            if !events.contains(&event) {
+                events.push(event);
+            }
+        }
+
+        let webhook = WebhookQueries::create(self.db_pool.pool(), url, secret, &events).await?;
+        Ok(serde_json::to_string_pretty(&webhook)?)
+    }
+
+    fn requires_admin(&self) -> bool {
+        true
+    }
+}
+
+/// List webhook subscriptions
+pub struct ListWebhooksTool {
+    db_pool: DatabasePool,
+}
+
+impl ListWebhooksTool {
+    /// Create a new webhook listing tool
+    #[must_use]
+    pub const fn new(db_pool: DatabasePool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl Tool for ListWebhooksTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "list_webhooks",
+            "description": "List webhook subscriptions with their URLs and events, oldest first. Secrets are not shown. Requires the admin token.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        })
+    }
+
+    async fn execute(&self, _arguments: Value) -> Result<String> {
+        let webhooks = WebhookQueries::list(self.db_pool.pool()).await?;
+        Ok(serde_json::to_string_pretty(&json!({
+            "webhooks": webhooks,
+            "from_env": crate::webhooks::WebhookTarget::from_env().map(|target| json!({
+                "url": target.url,
+                "events": target.events,
+            })),
+        }))?)
+    }
+
+    fn requires_admin(&self) -> bool {
+        true
+    }
+}
+
+/// Delete a webhook subscription
+pub struct DeleteWebhookTool {
+    db_pool: DatabasePool,
+}
+
+impl DeleteWebhookTool {
+    /// Create a new webhook deletion tool
+    #[must_use]
+    pub const fn new(db_pool: DatabasePool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl Tool for DeleteWebhookTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "delete_webhook",
+            "description": "Delete a webhook subscription. Its past deliveries stay in the delivery log. Requires the admin token.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "Id of the subscription, as listed by list_webhooks"
+                    }
+                },
+                "required": ["id"]
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        let id = optional_id(&arguments, "id")?
+            .ok_or_else(|| anyhow!("Missing required parameter: id"))?;
+        if !WebhookQueries::delete(self.db_pool.pool(), id).await? {
+            return Err(anyhow!("Webhook not found: {id}"));
+        }
+        Ok(serde_json::to_string_pretty(&json!({
+            "id": id,
+            "deleted": true,
+        }))?)
+    }
+
+    fn requires_admin(&self) -> bool {
+        true
+    }
+}
+
+/// Inspect the webhook delivery log
+pub struct ListWebhookDeliveriesTool {
+    db_pool: DatabasePool,
+}
+
+impl ListWebhookDeliveriesTool {
+    /// Create a new delivery log tool
+    #[must_use]
+    pub const fn new(db_pool: DatabasePool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl Tool for ListWebhookDeliveriesTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "list_webhook_deliveries",
+            "description": "Recent webhook deliveries, newest first: event, payload, status (pending, delivered, failed), attempts, and the last response code or error. Requires the admin token.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "webhook_id": {
+                        "type": "string",
+                        "description": "Only deliveries to this subscription"
+                    },
+                    "status": {
+                        "type": "string",
+                        "enum": ["pending", "delivered", "failed"],
+                        "description": "Only deliveries with this status"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "maximum": MAX_DELIVERY_LIMIT,
+                        "description": "Maximum deliveries returned (default 50)"
+                    }
+                },
+                "required": []
+            }
+        })
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        let webhook_id = optional_id(&arguments, "webhook_id")?;
+        let status = arguments.get("status").and_then(Value::as_str);
+        let limit = arguments
+            .get("limit")
+            .and_then(Value::as_i64)
+            .unwrap_or(DEFAULT_DELIVERY_LIMIT)
+            .clamp(1, MAX_DELIVERY_LIMIT);
+
+        let deliveries =
+            WebhookQueries::list_deliveries(self.db_pool.pool(), webhook_id, status, limit).await?;
+        Ok(serde_json::to_string_pretty(&json!({
+            "deliveries": deliveries,
+        }))?)
+    }
+
+    fn requires_admin(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,467 @@
+//! Webhook notifications for crate job events
+//!
+//! Subscriptions live in the `webhooks` table (managed by the webhook admin
+//! tools) and, optionally, in the environment: `WEBHOOK_URL`,
+//! `WEBHOOK_SECRET` and a comma-separated `WEBHOOK_EVENTS`. When a crate or
+//! package job finishes, [`notify_job`] hands the event to a background task
+//! that POSTs a JSON payload to every subscribed URL, signed with
+//! HMAC-SHA256 in an `X-Signature: sha256=<hex>` header. Timeouts, connection
+//! errors and 5xx/408/429 responses are retried with exponential backoff up
+//! to `WEBHOOK_MAX_ATTEMPTS`; the outcome of each delivery is logged in
+//! `webhook_deliveries`. Delivery never delays or fails the job itself.
+
+use chrono::Utc;
+use db::{models::CrateJob, CrateStorage, WebhookQueries};
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use sqlx::PgPool;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Header carrying the payload signature
+pub const SIGNATURE_HEADER: &str = "X-Signature";
+
+/// Header naming the event of a delivery
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+
+/// Upper bound on the delay between delivery attempts
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Characters of error text kept per delivery
+const MAX_ERROR_CHARS: usize = 500;
+
+/// Days logged deliveries are kept
+const DELIVERY_RETENTION_DAYS: i32 = 30;
+
+/// Event a webhook can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookEvent {
+    /// A job finished successfully
+    JobCompleted,
+    /// A job was marked failed
+    JobFailed,
+    /// A job failed permanently or ran out of attempts
+    JobDeadLettered,
+    /// A crate or package removal job finished
+    CrateRemoved,
+}
+
+impl WebhookEvent {
+    /// Every event, in documentation order
+    pub const ALL: [Self; 4] = [
+        Self::JobCompleted,
+        Self::JobFailed,
+        Self::JobDeadLettered,
+        Self::CrateRemoved,
+    ];
+
+    /// Name used in subscriptions and payloads
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::JobCompleted => "job_completed",
+            Self::JobFailed => "job_failed",
+            Self::JobDeadLettered => "job_dead_lettered",
+            Self::CrateRemoved => "crate_removed",
+        }
+    }
+
+    /// Parse an event name
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|event| event.as_str() == name.trim())
+    }
+}
+
+/// Where and how to deliver events
+#[derive(Debug, Clone)]
+pub struct WebhookTarget {
+    /// Row in `webhooks`; `None` for the webhook configured by `WEBHOOK_URL`
+    pub webhook_id: Option<Uuid>,
+    pub url: String,
+    pub secret: String,
+    /// Events delivered; empty means every event
+    pub events: Vec<String>,
+}
+
+impl WebhookTarget {
+    /// Webhook configured by `WEBHOOK_URL` and `WEBHOOK_SECRET`, if both are set
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        let url = std::env::var("WEBHOOK_URL")
+            .ok()
+            .filter(|url| !url.trim().is_empty())?;
+        let Some(secret) = std::env::var("WEBHOOK_SECRET")
+            .ok()
+            .filter(|secret| !secret.is_empty())
+        else {
+            warn!("WEBHOOK_URL is set without WEBHOOK_SECRET; not sending webhooks to it");
+            return None;
+        };
+        let events = std::env::var("WEBHOOK_EVENTS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|event| !event.is_empty())
+            .map(String::from)
+            .collect();
+        Some(Self {
+            webhook_id: None,
+            url: url.trim().to_string(),
+            secret,
+            events,
+        })
+    }
+
+    /// Whether the webhook subscribes to `event`
+    #[must_use]
+    pub fn wants(&self, event: WebhookEvent) -> bool {
+        self.events.is_empty() || self.events.iter().any(|e| e == event.as_str())
+    }
+}
+
+impl From<db::Webhook> for WebhookTarget {
+    fn from(webhook: db::Webhook) -> Self {
+        Self {
+            webhook_id: Some(webhook.id),
+            url: webhook.url,
+            secret: webhook.secret,
+            events: webhook.events,
+        }
+    }
+}
+
+/// Attempts and timeouts of a delivery
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Requests sent before a delivery is given up
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for each further one
+    pub base_delay: Duration,
+    /// Timeout of each request
+    pub timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_secs(1),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy from `WEBHOOK_MAX_ATTEMPTS`, `WEBHOOK_RETRY_BASE_MS` and
+    /// `WEBHOOK_TIMEOUT_SECS`, defaulting to 5 attempts, 1s and 10s
+    #[must_use]
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            max_attempts: env_number("WEBHOOK_MAX_ATTEMPTS")
+                .unwrap_or(defaults.max_attempts)
+                .max(1),
+            base_delay: env_number("WEBHOOK_RETRY_BASE_MS")
+                .map_or(defaults.base_delay, Duration::from_millis),
+            timeout: env_number("WEBHOOK_TIMEOUT_SECS")
+                .map_or(defaults.timeout, Duration::from_secs),
+        }
+    }
+
+    /// Delay before attempt `attempt + 1`
+    fn delay_after(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(MAX_RETRY_DELAY)
+    }
+}
+
+/// How a delivery ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeliveryOutcome {
+    pub delivered: bool,
+    /// Requests sent
+    pub attempts: u32,
+    /// Status of the last response, if any arrived
+    pub status_code: Option<u16>,
+    /// Why the last attempt failed
+    pub error: Option<String>,
+}
+
+/// `sha256=<hex>` HMAC-SHA256 of `body` keyed with `secret`
+#[must_use]
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    let digest = mac.finalize().into_bytes();
+    let hex: String = digest.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("sha256={hex}")
+}
+
+/// Payload sent for `event` about `job`
+#[must_use]
+pub fn job_payload(event: WebhookEvent, job: &CrateJob) -> Value {
+    json!({
+        "event": event.as_str(),
+        "sent_at": Utc::now(),
+        "job": {
+            "id": job.id,
+            "crate_name": job.crate_name,
+            "operation": job.operation,
+            "status": job.status,
+            "progress": job.progress,
+            "error": job.error,
+            "attempts": job.attempts,
+            "max_attempts": job.max_attempts,
+            "started_at": job.started_at,
+            "finished_at": job.finished_at,
+        },
+    })
+}
+
+/// Deliver the events a finished job raises to every subscribed webhook
+///
+/// Returns at once: subscriptions are looked up and deliveries made by a
+/// background task. Completed jobs raise `job_completed` and, for removals,
+/// `crate_removed`; failed jobs raise `job_failed`, or `job_dead_lettered`
+/// when they will not run again.
+pub fn notify_job(storage: &CrateStorage, job: &CrateJob) {
+    let events: Vec<WebhookEvent> = match job.status {
+        db::models::JobStatus::Completed if job.operation.starts_with("remove_") => {
+            vec![WebhookEvent::JobCompleted, WebhookEvent::CrateRemoved]
+        }
+        db::models::JobStatus::Completed => vec![WebhookEvent::JobCompleted],
+        db::models::JobStatus::Failed if job.is_dead_lettered() => {
+            vec![WebhookEvent::JobDeadLettered]
+        }
+        db::models::JobStatus::Failed => vec![WebhookEvent::JobFailed],
+        _ => return,
+    };
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    let pool = storage.as_pool().map(|pool| pool.pool().clone());
+    let payloads: Vec<(WebhookEvent, Value)> = events
+        .into_iter()
+        .map(|event| (event, job_payload(event, job)))
+        .collect();
+
+    runtime.spawn(async move {
+        let targets = subscribed_targets(pool.as_ref()).await;
+        if targets.is_empty() {
+            return;
+        }
+        let policy = RetryPolicy::from_env();
+        for (event, payload) in payloads {
+            for target in targets.iter().filter(|target| target.wants(event)) {
+                tokio::spawn(deliver_and_record(
+                    pool.clone(),
+                    target.clone(),
+                    event,
+                    payload.clone(),
+                    policy,
+                ));
+            }
+        }
+    });
+}
+
+/// The environment webhook and, with a database, every stored subscription
+async fn subscribed_targets(pool: Option<&PgPool>) -> Vec<WebhookTarget> {
+    let mut targets: Vec<WebhookTarget> = WebhookTarget::from_env().into_iter().collect();
+    if let Some(pool) = pool {
+        match WebhookQueries::list(pool).await {
+            Ok(webhooks) => targets.extend(webhooks.into_iter().map(WebhookTarget::from)),
+            Err(e) => debug!("Failed to load webhook subscriptions: {}", e),
+        }
+    }
+    targets
+}
+
+/// Deliver `payload` to `target`, logging the delivery when a pool is given
+async fn deliver_and_record(
+    pool: Option<PgPool>,
+    target: WebhookTarget,
+    event: WebhookEvent,
+    payload: Value,
+    policy: RetryPolicy,
+) {
+    let delivery_id = match &pool {
+        Some(pool) => WebhookQueries::create_delivery(
+            pool,
+            target.webhook_id,
+            &target.url,
+            event.as_str(),
+            &payload,
+        )
+        .await
+        .map_err(|e| debug!("Failed to log webhook delivery to {}: {}", target.url, e))
+        .ok()
+        .map(|delivery| delivery.id),
+        None => None,
+    };
+
+    let outcome = deliver(&target, event, &payload, &policy).await;
+    if outcome.delivered {
+        info!(
+            "Delivered {} webhook to {} after {} attempt(s)",
+            event.as_str(),
+            target.url,
+            outcome.attempts
+        );
+    } else {
+        warn!(
+            "Giving up on {} webhook to {} after {} attempt(s): {}",
+            event.as_str(),
+            target.url,
+            outcome.attempts,
+            outcome.error.as_deref().unwrap_or("unknown error")
+        );
+    }
+
+    if let (Some(pool), Some(id)) = (&pool, delivery_id) {
+        if let Err(e) = WebhookQueries::finish_delivery(
+            pool,
+            id,
+            outcome.delivered,
+            i32::try_from(outcome.attempts).unwrap_or(i32::MAX),
+            outcome.status_code.map(i32::from),
+            outcome.error.as_deref(),
+        )
+        .await
+        {
+            debug!("Failed to record webhook delivery {}: {}", id, e);
+        }
+    }
+}
+
+/// POST `payload` to `target`, retrying as `policy` allows
+///
+/// 2xx responses count as delivered. Timeouts, connection errors and
+/// 5xx/408/429 responses are retried; other responses end the delivery.
+pub async fn deliver(
+    target: &WebhookTarget,
+    event: WebhookEvent,
+    payload: &Value,
+    policy: &RetryPolicy,
+) -> DeliveryOutcome {
+    let body = payload.to_string();
+    let signature = sign(&target.secret, body.as_bytes());
+    let mut outcome = DeliveryOutcome {
+        delivered: false,
+        attempts: 0,
+        status_code: None,
+        error: None,
+    };
+
+    while outcome.attempts < policy.max_attempts {
+        if outcome.attempts > 0 {
+            tokio::time::sleep(policy.delay_after(outcome.attempts)).await;
+        }
+        outcome.attempts += 1;
+
+        let response = http_client()
+            .post(&target.url)
+            .timeout(policy.timeout)
+            .header("Content-Type", "application/json")
+            .header(SIGNATURE_HEADER, &signature)
+            .header(EVENT_HEADER, event.as_str())
+            .body(body.clone())
+            .send()
+            .await;
+        let retryable = match response {
+            Ok(response) => {
+                let status = response.status();
+                outcome.status_code = Some(status.as_u16());
+                if status.is_success() {
+                    outcome.delivered = true;
+                    outcome.error = None;
+                    return outcome;
+                }
+                outcome.error = Some(format!("HTTP {status}"));
+                status.is_server_error()
+                    || status == reqwest::StatusCode::REQUEST_TIMEOUT
+                    || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            Err(e) => {
+                outcome.status_code = None;
+                outcome.error = Some(e.to_string().chars().take(MAX_ERROR_CHARS).collect());
+                true
+            }
+        };
+        if !retryable {
+            break;
+        }
+    }
+    outcome
+}
+
+/// Delete logged deliveries past the 30-day retention window
+///
+/// Run by the `webhook_delivery_retention` maintenance task.
+///
+/// # Errors
+///
+/// Returns an error if the rows cannot be deleted.
+pub async fn prune_deliveries(pool: &PgPool) -> anyhow::Result<String> {
+    let removed = WebhookQueries::prune_deliveries(pool, DELIVERY_RETENTION_DAYS).await?;
+    Ok(format!("removed {removed} webhook deliveries"))
+}
+
+/// Client shared by every delivery
+fn http_client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .user_agent(concat!("doc-server-webhooks/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .unwrap_or_default()
+    })
+}
+
+/// Number in the environment variable `key`, if set and valid
+fn env_number<T: std::str::FromStr>(key: &str) -> Option<T> {
+    std::env::var(key).ok().and_then(|v| v.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sign, RetryPolicy, WebhookEvent};
+    use std::time::Duration;
+
+    #[test]
+    fn test_signature_matches_known_hmac() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_retry_delay_doubles_up_to_the_cap() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            base_delay: Duration::from_secs(1),
+            timeout: Duration::from_secs(1),
+        };
+        assert_eq!(policy.delay_after(1), Duration::from_secs(1));
+        assert_eq!(policy.delay_after(3), Duration::from_secs(4));
+        assert_eq!(policy.delay_after(9), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_events_parse_by_name() {
+        for event in WebhookEvent::ALL {
+            assert_eq!(WebhookEvent::parse(event.as_str()), Some(event));
+        }
+        assert_eq!(WebhookEvent::parse("job_started"), None);
+    }
+}
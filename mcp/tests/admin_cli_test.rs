@@ -0,0 +1,181 @@
+//! `doc-admin` argument parsing and the job list/retry/cancel path
+//!
+//! Database tests skip when no database is configured.
+
+mod common;
+
+use clap::Parser;
+use common::create_test_pool;
+use db::models::JobStatus;
+use db::CrateJobQueries;
+use mcp::admin::{
+    run, AdminCli, AdminCommand, CrateStatusArg, CratesCommand, JobStatusArg, JobsCommand,
+};
+use serde_json::Value;
+use uuid::Uuid;
+
+fn parse(args: &[&str]) -> AdminCli {
+    AdminCli::try_parse_from(std::iter::once("doc-admin").chain(args.iter().copied()))
+        .unwrap_or_else(|e| panic!("{args:?} should parse: {e}"))
+}
+
+#[test]
+fn test_jobs_list_filters_parse() {
+    let cli = parse(&[
+        "jobs", "list", "--status", "failed", "--crate", "serde", "--json",
+    ]);
+    assert!(cli.json);
+    match cli.command {
+        AdminCommand::Jobs {
+            command:
+                JobsCommand::List {
+                    status,
+                    crate_name,
+                    limit,
+                },
+        } => {
+            assert_eq!(status, Some(JobStatusArg::Failed));
+            assert_eq!(crate_name.as_deref(), Some("serde"));
+            assert_eq!(limit, 50);
+        }
+        other => panic!("unexpected command: {other:?}"),
+    }
+
+    // --json is accepted before the subcommand too
+    assert!(parse(&["--json", "stats"]).json);
+    assert!(!parse(&["stats"]).json);
+}
+
+#[test]
+fn test_job_ids_must_be_uuids() {
+    let job_id = Uuid::new_v4();
+    match parse(&["jobs", "retry", &job_id.to_string()]).command {
+        AdminCommand::Jobs {
+            command: JobsCommand::Retry { job_id: parsed },
+        } => assert_eq!(parsed, job_id),
+        other => panic!("unexpected command: {other:?}"),
+    }
+
+    assert!(AdminCli::try_parse_from(["doc-admin", "jobs", "cancel", "not-a-uuid"]).is_err());
+    assert!(AdminCli::try_parse_from(["doc-admin", "jobs", "retry"]).is_err());
+    assert!(AdminCli::try_parse_from(["doc-admin", "jobs", "list", "--status", "stuck"]).is_err());
+}
+
+#[test]
+fn test_crates_commands_parse() {
+    match parse(&["crates", "remove", "tokio", "--soft"]).command {
+        AdminCommand::Crates {
+            command: CratesCommand::Remove { name, soft, force },
+        } => {
+            assert_eq!(name, "tokio");
+            assert!(soft);
+            assert!(!force);
+        }
+        other => panic!("unexpected command: {other:?}"),
+    }
+
+    match parse(&["crates", "list", "--status", "inactive", "--name", "to"]).command {
+        AdminCommand::Crates {
+            command: CratesCommand::List {
+                name, status, page, ..
+            },
+        } => {
+            assert_eq!(name.as_deref(), Some("to"));
+            assert_eq!(status, CrateStatusArg::Inactive);
+            assert_eq!(page, 1);
+        }
+        other => panic!("unexpected command: {other:?}"),
+    }
+
+    assert!(AdminCli::try_parse_from(["doc-admin", "crates", "remove"]).is_err());
+    assert!(AdminCli::try_parse_from(["doc-admin"]).is_err());
+}
+
+#[tokio::test]
+async fn test_jobs_list_retry_and_cancel() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping doc-admin database test: no database configured");
+        return;
+    };
+    let crate_name = format!("admin-cli-{}", Uuid::new_v4());
+    let job = CrateJobQueries::create_job(pool.pool(), &crate_name, "add_crate")
+        .await
+        .expect("create job");
+    CrateJobQueries::update_job_status(
+        pool.pool(),
+        job.id,
+        JobStatus::Failed,
+        Some(40),
+        Some("docs.rs timed out"),
+    )
+    .await
+    .expect("fail job");
+
+    // The failed job is listed, in JSON and as a table
+    let listed = run(
+        &parse(&[
+            "jobs",
+            "list",
+            "--status",
+            "failed",
+            "--crate",
+            &crate_name,
+            "--json",
+        ]),
+        &pool,
+    )
+    .await
+    .expect("jobs list");
+    let listed: Value = serde_json::from_str(&listed).expect("JSON job list");
+    let listed = listed.as_array().expect("job array");
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0]["id"], job.id.to_string());
+    assert_eq!(listed[0]["error"], "docs.rs timed out");
+
+    let table = run(&parse(&["jobs", "list", "--crate", &crate_name]), &pool)
+        .await
+        .expect("jobs table");
+    assert!(table.starts_with("ID"), "{table}");
+    assert!(table.contains(&job.id.to_string()), "{table}");
+    assert!(table.contains("failed"), "{table}");
+
+    // The status filter excludes it
+    let queued = run(
+        &parse(&["jobs", "list", "--status", "queued", "--crate", &crate_name]),
+        &pool,
+    )
+    .await
+    .expect("queued jobs");
+    assert_eq!(queued, "(none)");
+
+    // Retry requeues it with attempts reset, like retry_rust_job
+    let retried = run(&parse(&["jobs", "retry", &job.id.to_string()]), &pool)
+        .await
+        .expect("jobs retry");
+    assert!(retried.contains("requeued"), "{retried}");
+    let requeued = CrateJobQueries::find_job_by_id(pool.pool(), job.id)
+        .await
+        .expect("find job")
+        .expect("job exists");
+    assert_eq!(requeued.status, JobStatus::Queued);
+    assert_eq!(requeued.attempts, 0);
+
+    // Cancel stops the queued job; a finished job cannot be cancelled again
+    let cancelled = run(&parse(&["jobs", "cancel", &job.id.to_string()]), &pool)
+        .await
+        .expect("jobs cancel");
+    assert!(cancelled.contains("Cancelled job"), "{cancelled}");
+    let job_after = CrateJobQueries::find_job_by_id(pool.pool(), job.id)
+        .await
+        .expect("find job")
+        .expect("job exists");
+    assert_eq!(job_after.status, JobStatus::Cancelled);
+    assert!(run(&parse(&["jobs", "cancel", &job.id.to_string()]), &pool)
+        .await
+        .is_err());
+
+    let _ = sqlx::query("DELETE FROM crate_jobs WHERE crate_name = $1")
+        .bind(&crate_name)
+        .execute(pool.pool())
+        .await;
+}
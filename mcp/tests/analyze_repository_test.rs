@@ -0,0 +1,420 @@
+//! `analyze_repository` and `execute_ingest_plan` with a stubbed Claude layer
+//!
+//! The job test skips when no database is configured.
+
+mod common;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use common::create_test_pool;
+use db::{DatabasePool, IngestJobQueries};
+use discovery::{AnalysisError, PromptRunner};
+use mcp::handlers::McpHandler;
+use mcp::ingest_tools::AnalyzeRepositoryTool;
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Canned stream-json output of a Claude analysis run
+fn canned_output(loader_path: &str) -> String {
+    let analysis = json!({
+        "repository_type": "api-docs",
+        "documentation_assessment": {
+            "primary_format": "markdown",
+            "key_directories": ["docs/"],
+            "estimated_file_count": 40
+        },
+        "ingestion_strategy": {
+            "docs_only": true,
+            "extensions": ["md"],
+            "recursive": true,
+            "chunk_size": 2000,
+            "use_ai_chunking": false,
+            "doc_type": "widgets",
+            "source_name": "widgets-docs"
+        },
+        "cli_commands": [
+            format!("loader cli {loader_path} --extensions md --recursive -o UNIQUE_DOCS_OUT"),
+            "loader database --input-dir UNIQUE_DOCS_OUT --doc-type widgets --source-name widgets-docs --yes"
+        ],
+        "reasoning": "Markdown guides live under docs/."
+    });
+    let event = json!({ "type": "result", "result": analysis.to_string() });
+    format!("{event}\n")
+}
+
+/// Runner returning a fixed response and remembering the prompt it got
+struct StubRunner {
+    response: Result<String, fn() -> AnalysisError>,
+    delay: Duration,
+    prompts: Mutex<Vec<String>>,
+}
+
+impl StubRunner {
+    fn answering(output: String) -> Arc<Self> {
+        Arc::new(Self {
+            response: Ok(output),
+            delay: Duration::ZERO,
+            prompts: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn failing(error: fn() -> AnalysisError) -> Arc<Self> {
+        Arc::new(Self {
+            response: Err(error),
+            delay: Duration::ZERO,
+            prompts: Mutex::new(Vec::new()),
+        })
+    }
+}
+
+#[async_trait]
+impl PromptRunner for StubRunner {
+    async fn run(&self, prompt: &str) -> Result<String> {
+        self.prompts.lock().unwrap().push(prompt.to_string());
+        tokio::time::sleep(self.delay).await;
+        match &self.response {
+            Ok(output) => Ok(output.clone()),
+            Err(error) => Err(error().into()),
+        }
+    }
+}
+
+/// Handler without a reachable database
+fn offline_handler() -> McpHandler {
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+        .expect("lazy pool");
+    McpHandler::new(&DatabasePool::from_pool(pool)).expect("handler should build")
+}
+
+/// Checkout with a few documentation files under the ingest work directory
+fn local_checkout() -> PathBuf {
+    let base = std::env::var("INGEST_WORK_DIR").map_or_else(
+        |_| std::env::temp_dir().join("agent-docs-ingest"),
+        PathBuf::from,
+    );
+    let root = base.join(format!("analyze-test-{}", Uuid::new_v4()));
+    std::fs::create_dir_all(root.join("docs/guides")).unwrap();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::create_dir_all(root.join(".git")).unwrap();
+    std::fs::write(root.join("README.md"), "# Widgets").unwrap();
+    std::fs::write(root.join("docs/intro.md"), "# Intro").unwrap();
+    std::fs::write(root.join("docs/guides/setup.md"), "# Setup").unwrap();
+    std::fs::write(root.join("src/lib.rs"), "pub fn widget() {}").unwrap();
+    std::fs::write(root.join(".git/description.txt"), "ignored").unwrap();
+    root.canonicalize().unwrap()
+}
+
+async fn call(handler: &McpHandler, name: &str, arguments: Value) -> Value {
+    handler
+        .handle_request(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": name, "arguments": arguments }
+        }))
+        .await
+        .expect("tool call should produce a result")
+}
+
+fn result_json(response: &Value) -> Value {
+    let text = response["content"][0]["text"].as_str().unwrap();
+    serde_json::from_str(text).unwrap_or_else(|_| panic!("not JSON: {text}"))
+}
+
+#[tokio::test]
+async fn test_local_analysis_returns_plan_without_executing() {
+    let checkout = local_checkout();
+    let docs = checkout.join("docs");
+    let runner = StubRunner::answering(canned_output(&docs.to_string_lossy()));
+    let mut handler = offline_handler();
+    handler.register_tool(
+        "analyze_repository",
+        Box::new(AnalyzeRepositoryTool::with_runner(runner.clone())),
+    );
+
+    let response = call(
+        &handler,
+        "analyze_repository",
+        json!({
+            "local_path": checkout.to_string_lossy(),
+            "hint": "only the docs folder matters"
+        }),
+    )
+    .await;
+    assert!(response.get("isError").is_none(), "{response}");
+    let plan = result_json(&response);
+
+    assert!(Uuid::parse_str(plan["plan_id"].as_str().unwrap()).is_ok());
+    assert_eq!(
+        plan["source"]["local_path"],
+        checkout.to_string_lossy().as_ref()
+    );
+    let summary = &plan["summary"];
+    assert_eq!(summary["doc_type"], "widgets");
+    assert_eq!(summary["source_name"], "widgets-docs");
+    assert_eq!(summary["doc_formats"], json!(["md"]));
+    assert_eq!(summary["primary_format"], "markdown");
+    assert_eq!(summary["directories"], json!(["docs/"]));
+    // Counted on disk, ignoring src/*.rs and hidden directories
+    assert_eq!(summary["estimated_file_count"], 3);
+    assert_eq!(summary["file_counts"], json!({ "md": 3 }));
+    assert_eq!(
+        plan["analysis"]["cli_commands"].as_array().unwrap().len(),
+        2
+    );
+
+    let prompts = runner.prompts.lock().unwrap();
+    assert_eq!(prompts.len(), 1);
+    assert!(prompts[0].contains("only the docs folder matters"));
+    assert!(prompts[0].contains("Do NOT include a git clone step"));
+    assert!(prompts[0].contains("- docs/ (2 files)"));
+
+    let _ = std::fs::remove_dir_all(&checkout);
+}
+
+#[tokio::test]
+async fn test_cli_failure_is_a_structured_error() {
+    let runner = StubRunner::failing(|| AnalysisError::CliFailed {
+        status: "exit status: 2".to_string(),
+        stderr: "authentication required".to_string(),
+    });
+    let mut handler = offline_handler();
+    handler.register_tool(
+        "analyze_repository",
+        Box::new(AnalyzeRepositoryTool::with_runner(runner)),
+    );
+
+    let response = call(
+        &handler,
+        "analyze_repository",
+        json!({ "repo_url": "https://github.com/example/widgets" }),
+    )
+    .await;
+    assert_eq!(response["isError"], true);
+    let error = &result_json(&response)["error"];
+    assert_eq!(error["kind"], "cli_failed");
+    assert!(error["message"]
+        .as_str()
+        .unwrap()
+        .contains("authentication required"));
+}
+
+#[tokio::test]
+async fn test_slow_analysis_times_out() {
+    let runner = Arc::new(StubRunner {
+        response: Ok(canned_output("UNIQUE_REPO_DIR/docs")),
+        delay: Duration::from_secs(30),
+        prompts: Mutex::new(Vec::new()),
+    });
+    let mut handler = offline_handler();
+    handler.register_tool(
+        "analyze_repository",
+        Box::new(
+            AnalyzeRepositoryTool::with_runner(runner).with_timeout(Duration::from_millis(50)),
+        ),
+    );
+
+    let response = call(
+        &handler,
+        "analyze_repository",
+        json!({ "repo_url": "https://github.com/example/widgets" }),
+    )
+    .await;
+    assert_eq!(response["isError"], true);
+    assert_eq!(result_json(&response)["error"]["kind"], "timeout");
+}
+
+#[tokio::test]
+async fn test_local_path_outside_ingest_root_is_rejected() {
+    let runner = StubRunner::answering(canned_output("/etc"));
+    let mut handler = offline_handler();
+    handler.register_tool(
+        "analyze_repository",
+        Box::new(AnalyzeRepositoryTool::with_runner(runner.clone())),
+    );
+
+    let response = call(
+        &handler,
+        "analyze_repository",
+        json!({ "local_path": "/etc" }),
+    )
+    .await;
+    assert_eq!(response["isError"], true);
+    assert_eq!(result_json(&response)["error"]["kind"], "invalid_source");
+    assert!(runner.prompts.lock().unwrap().is_empty());
+}
+
+/// Plan for the widgets repository as a client might send it
+fn client_plan(cli_commands: &[&str]) -> Value {
+    json!({
+        "source": { "repo_url": "https://github.com/example/widgets" },
+        "analysis": {
+            "repo_info": {
+                "url": "https://github.com/example/widgets",
+                "name": "widgets",
+                "primary_language": null,
+                "documentation_type": "Unknown",
+                "estimated_size": ""
+            },
+            "strategy": {
+                "docs_only": true,
+                "include_paths": [],
+                "exclude_paths": [],
+                "extensions": ["md"],
+                "recursive": true,
+                "chunk_size": null,
+                "use_ai_chunking": false,
+                "doc_type": "widgets",
+                "source_name": "widgets-docs"
+            },
+            "cli_commands": cli_commands,
+            "reasoning": "edited by the client"
+        }
+    })
+}
+
+/// Error text of an `execute_ingest_plan` call with a client plan
+async fn rejected_plan(handler: &McpHandler, cli_commands: &[&str]) -> String {
+    let plan = client_plan(cli_commands);
+    let response = call(handler, "execute_ingest_plan", json!({ "plan": plan })).await;
+    assert_eq!(response["isError"], true, "{response}");
+    response["content"][0]["text"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_plan_reading_outside_repository_is_rejected() {
+    let handler = offline_handler();
+    let text = rejected_plan(
+        &handler,
+        &["loader cli /etc --extensions conf -o UNIQUE_DOCS_OUT"],
+    )
+    .await;
+    assert!(text.contains("outside the repository"), "{text}");
+}
+
+#[tokio::test]
+async fn test_client_plan_cannot_forge_local_path() {
+    let handler = offline_handler();
+    let mut plan = client_plan(&["loader cli /etc -o UNIQUE_DOCS_OUT"]);
+    plan["source"] = json!({ "local_path": "/" });
+    let response = call(&handler, "execute_ingest_plan", json!({ "plan": plan })).await;
+    assert_eq!(response["isError"], true, "{response}");
+    assert_eq!(result_json(&response)["error"]["kind"], "invalid_source");
+}
+
+#[tokio::test]
+async fn test_client_plan_cannot_inject_git_options() {
+    let handler = offline_handler();
+    for step in [
+        "git clone --depth 1 --upload-pack=touch${IFS}pwned REPO_URL UNIQUE_REPO_DIR",
+        "git -c core.sshCommand=touch${IFS}pwned clone --depth 1 REPO_URL UNIQUE_REPO_DIR",
+        "git clone --depth 1 --config=core.sshCommand=sh REPO_URL UNIQUE_REPO_DIR",
+    ] {
+        let text = rejected_plan(&handler, &[step]).await;
+        assert!(text.contains("not allowed"), "{step}: {text}");
+    }
+}
+
+#[tokio::test]
+async fn test_client_plan_outputs_stay_in_work_base_and_cannot_load() {
+    let handler = offline_handler();
+    let text = rejected_plan(
+        &handler,
+        &["loader cli UNIQUE_REPO_DIR/docs --extensions md -o /var/lib/docs"],
+    )
+    .await;
+    assert!(text.contains("outside work base"), "{text}");
+
+    let text = rejected_plan(
+        &handler,
+        &["loader database --input-dir /etc --doc-type widgets --yes"],
+    )
+    .await;
+    assert!(text.contains("outside work base"), "{text}");
+
+    let text = rejected_plan(
+        &handler,
+        &["loader database --input-dir UNIQUE_DOCS_OUT --doc-type widgets --yes"],
+    )
+    .await;
+    assert!(text.contains("added by the server"), "{text}");
+}
+
+#[tokio::test]
+async fn test_client_plan_cannot_crawl_other_hosts() {
+    let handler = offline_handler();
+    for step in [
+        "loader web http://169.254.169.254/latest/meta-data -o UNIQUE_DOCS_OUT",
+        "loader web http://postgres.default.svc:5432/ -o UNIQUE_DOCS_OUT",
+    ] {
+        let text = rejected_plan(&handler, &[step]).await;
+        assert!(text.contains("crawl host"), "{step}: {text}");
+    }
+}
+
+#[tokio::test]
+async fn test_execute_stored_plan_creates_ingest_job() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping ingest plan test: no test database configured");
+        return;
+    };
+
+    let runner = StubRunner::answering(canned_output("UNIQUE_REPO_DIR/docs"));
+    let mut handler = McpHandler::new(&pool).expect("handler should build");
+    handler.register_tool(
+        "analyze_repository",
+        Box::new(AnalyzeRepositoryTool::with_runner(runner)),
+    );
+
+    let analysis = call(
+        &handler,
+        "analyze_repository",
+        json!({ "repo_url": "https://github.com/example/widgets" }),
+    )
+    .await;
+    assert!(analysis.get("isError").is_none(), "{analysis}");
+    let plan_id = result_json(&analysis)["plan_id"].clone();
+
+    let response = call(
+        &handler,
+        "execute_ingest_plan",
+        json!({ "plan_id": plan_id }),
+    )
+    .await;
+    assert!(response.get("isError").is_none(), "{response}");
+    let queued = result_json(&response);
+    assert_eq!(queued["doc_type"], "widgets");
+    assert_eq!(queued["steps"], 2);
+
+    let job_id = Uuid::parse_str(queued["job_id"].as_str().unwrap()).unwrap();
+    let job = IngestJobQueries::find_job_by_id(pool.pool(), job_id)
+        .await
+        .expect("job lookup should succeed")
+        .expect("job row should exist");
+    assert_eq!(job.url, "https://github.com/example/widgets");
+    assert_eq!(job.doc_type, "widgets");
+
+    // Client plans get their database step from the server
+    let plan = client_plan(&[
+        "git clone --depth 1 --branch main REPO_URL UNIQUE_REPO_DIR",
+        "loader cli UNIQUE_REPO_DIR/docs --extensions md --recursive -o UNIQUE_DOCS_OUT",
+    ]);
+    let response = call(&handler, "execute_ingest_plan", json!({ "plan": plan })).await;
+    assert!(response.get("isError").is_none(), "{response}");
+    assert_eq!(result_json(&response)["steps"], 3);
+
+    // Stored plans stay executable by ID until they expire
+    let unknown = call(
+        &handler,
+        "execute_ingest_plan",
+        json!({ "plan_id": Uuid::new_v4().to_string() }),
+    )
+    .await;
+    assert_eq!(unknown["isError"], true);
+}
@@ -0,0 +1,64 @@
+//! Rate limiting of requests that fail authentication
+//!
+//! Kept apart from `auth_test.rs` and `rate_limit_test.rs` because both
+//! settings are read from the environment, which would leak into the tests
+//! there.
+
+mod common;
+
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use common::lazy_pool;
+use mcp::{headers::SUPPORTED_PROTOCOL_VERSION, McpServer};
+use serde_json::json;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+
+async fn create_router() -> Router {
+    std::env::set_var("MCP_AUTH_TOKENS", "ci=s3cret-ci-token");
+    std::env::set_var("MCP_RATE_LIMIT_RPM", "1");
+    std::env::set_var("MCP_RATE_LIMIT_BURST", "2");
+    McpServer::new(lazy_pool())
+        .await
+        .expect("server should start without a database")
+        .create_router()
+}
+
+/// `initialize` from 10.2.0.1 with a guessed bearer token
+fn guessing_request(token: &str) -> Request<Body> {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {"protocolVersion": SUPPORTED_PROTOCOL_VERSION, "capabilities": {}}
+    });
+    Request::builder()
+        .method(Method::POST)
+        .uri("/mcp")
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .header("MCP-Protocol-Version", SUPPORTED_PROTOCOL_VERSION)
+        .header(header::AUTHORIZATION, format!("Bearer {token}"))
+        .extension(ConnectInfo(SocketAddr::from(([10, 2, 0, 1], 40_000))))
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_token_guessing_is_throttled() {
+    let app = create_router().await;
+    for i in 0..2 {
+        let response = app
+            .clone()
+            .oneshot(guessing_request(&format!("guess-{i}")))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+    let response = app.oneshot(guessing_request("guess-2")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
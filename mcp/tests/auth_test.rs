@@ -0,0 +1,128 @@
+//! Integration tests for optional bearer-token authentication on `/mcp`
+//!
+//! The server is built over a lazy pool pointing at an unreachable database,
+//! so requests rejected by authentication never touch storage.
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use db::DatabasePool;
+use mcp::{headers::SUPPORTED_PROTOCOL_VERSION, metrics::metrics, McpServer};
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use std::time::Duration;
+use tower::ServiceExt;
+
+const VALID_TOKEN: &str = "s3cret-ci-token";
+
+async fn create_auth_router() -> Router {
+    std::env::set_var("MCP_AUTH_TOKENS", format!("ci={VALID_TOKEN},other-token"));
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+        .expect("lazy pool");
+    McpServer::new(DatabasePool::from_pool(pool))
+        .await
+        .expect("server should start without a database")
+        .create_router()
+}
+
+fn initialize_request(authorization: Option<&str>) -> Request<Body> {
+    let mut builder = Request::builder()
+        .method(Method::POST)
+        .uri("/mcp")
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .header("MCP-Protocol-Version", SUPPORTED_PROTOCOL_VERSION);
+    if let Some(value) = authorization {
+        builder = builder.header(header::AUTHORIZATION, value);
+    }
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {"protocolVersion": SUPPORTED_PROTOCOL_VERSION, "capabilities": {}}
+    });
+    builder.body(Body::from(body.to_string())).unwrap()
+}
+
+async fn assert_unauthorized(app: Router, request: Request<Body>) {
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        response.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+        "Bearer"
+    );
+    assert!(response.headers().get("Mcp-Session-Id").is_none());
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["jsonrpc"], "2.0");
+    assert!(json["id"].is_null());
+    assert_eq!(json["error"]["message"], "Unauthorized");
+}
+
+#[tokio::test]
+async fn test_bearer_auth() {
+    let app = create_auth_router().await;
+    let failures_before = metrics().snapshot().auth_failures;
+
+    // Missing token
+    assert_unauthorized(app.clone(), initialize_request(None)).await;
+
+    // Wrong token and wrong scheme
+    assert_unauthorized(app.clone(), initialize_request(Some("Bearer nope"))).await;
+    assert_unauthorized(
+        app.clone(),
+        initialize_request(Some(&format!("Basic {VALID_TOKEN}"))),
+    )
+    .await;
+
+    // DELETE and GET are rejected before any session lookup
+    let delete = Request::builder()
+        .method(Method::DELETE)
+        .uri("/mcp")
+        .header("MCP-Protocol-Version", SUPPORTED_PROTOCOL_VERSION)
+        .body(Body::empty())
+        .unwrap();
+    assert_unauthorized(app.clone(), delete).await;
+    let get = Request::builder()
+        .method(Method::GET)
+        .uri("/mcp")
+        .header("Accept", "text/event-stream")
+        .header("MCP-Protocol-Version", SUPPORTED_PROTOCOL_VERSION)
+        .body(Body::empty())
+        .unwrap();
+    assert_unauthorized(app.clone(), get).await;
+
+    assert!(metrics().snapshot().auth_failures >= failures_before + 5);
+
+    // Valid token is accepted and attributed to its label
+    let ci_before = metrics()
+        .requests_by_client()
+        .get("ci")
+        .copied()
+        .unwrap_or(0);
+    let response = app
+        .clone()
+        .oneshot(initialize_request(Some(&format!("Bearer {VALID_TOKEN}"))))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get("Mcp-Session-Id").is_some());
+    assert_eq!(
+        metrics().requests_by_client().get("ci"),
+        Some(&(ci_before + 1))
+    );
+
+    // Health endpoints stay unauthenticated
+    let health = Request::builder()
+        .method(Method::GET)
+        .uri("/health/live")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(health).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
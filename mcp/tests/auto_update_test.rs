@@ -0,0 +1,195 @@
+//! Auto-update scheduler tests
+//!
+//! Runs use a fixed clock and canned crates.io versions. Tests skip when no
+//! database is configured.
+
+mod common;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use common::create_test_pool;
+use db::{CrateJobQueries, DatabasePool};
+use mcp::auto_update::{
+    last_run, run_once, AutoUpdateConfig, Clock, UpdateDecision, VersionSource,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use std::time::Duration;
+use uuid::Uuid;
+
+struct FixedClock(DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// Newest versions for the test's crates; every other crate fails to resolve
+struct CannedVersions(HashMap<String, String>);
+
+#[async_trait]
+impl VersionSource for CannedVersions {
+    async fn newest_version(&self, crate_name: &str) -> Result<String> {
+        self.0
+            .get(crate_name)
+            .cloned()
+            .ok_or_else(|| anyhow!("HTTP status: 404 for {crate_name}"))
+    }
+}
+
+const fn config(max_backlog: usize) -> AutoUpdateConfig {
+    AutoUpdateConfig {
+        interval: Duration::from_secs(3600),
+        max_start_jitter: Duration::ZERO,
+        max_backlog,
+    }
+}
+
+async fn store_crate(pool: &DatabasePool, crate_name: &str, version: &str) {
+    sqlx::query(
+        "INSERT INTO document_sources (doc_type, source_name, config, enabled)
+         VALUES ('rust', $1, '{}', true)
+         ON CONFLICT (doc_type, source_name) DO NOTHING",
+    )
+    .bind(crate_name)
+    .execute(pool.pool())
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO documents (id, doc_type, source_name, doc_path, content, metadata)
+         VALUES ($1, 'rust', $2, $3, 'content', $4)",
+    )
+    .bind(Uuid::new_v4())
+    .bind(crate_name)
+    .bind(format!("{crate_name}/{version}/index.html"))
+    .bind(json!({"crate_name": crate_name, "crate_version": version}))
+    .execute(pool.pool())
+    .await
+    .unwrap();
+}
+
+async fn cleanup(pool: &DatabasePool, prefix: &str) {
+    let pattern = format!("{prefix}%");
+    for table in ["documents", "document_sources", "crate_jobs"] {
+        let column = if table == "crate_jobs" {
+            "crate_name"
+        } else {
+            "source_name"
+        };
+        let _ = sqlx::query(&format!("DELETE FROM {table} WHERE {column} LIKE $1"))
+            .bind(&pattern)
+            .execute(pool.pool())
+            .await;
+    }
+}
+
+#[tokio::test]
+async fn test_queues_outdated_crates_only() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database configured");
+        return;
+    };
+
+    let prefix = format!("autoupdate-{}", Uuid::new_v4().simple());
+    let outdated = format!("{prefix}-outdated");
+    let current = format!("{prefix}-current");
+    let opted_out = format!("{prefix}-opted-out");
+    let busy = format!("{prefix}-busy");
+
+    store_crate(&pool, &outdated, "1.0.0").await;
+    store_crate(&pool, &outdated, "1.2.0").await;
+    store_crate(&pool, &current, "2.0.0").await;
+    store_crate(&pool, &opted_out, "1.0.0").await;
+    store_crate(&pool, &busy, "1.0.0").await;
+    sqlx::query(
+        "UPDATE document_sources SET config = $2
+         WHERE doc_type = 'rust' AND source_name = $1",
+    )
+    .bind(&opted_out)
+    .bind(json!({"auto_update": false}))
+    .execute(pool.pool())
+    .await
+    .unwrap();
+    CrateJobQueries::create_job(pool.pool(), &busy, "add_crate")
+        .await
+        .unwrap();
+
+    let versions = CannedVersions(
+        [&outdated, &current, &opted_out, &busy]
+            .into_iter()
+            .map(|name| (name.clone(), "2.0.0".to_string()))
+            .collect(),
+    );
+    let clock = FixedClock(Utc.with_ymd_and_hms(2025, 6, 1, 3, 0, 0).unwrap());
+    let run = run_once(&pool, &versions, &clock, &config(usize::MAX))
+        .await
+        .unwrap();
+    assert_eq!(run.started_at, clock.0);
+    assert!(run.skipped.is_none());
+
+    let decision = |name: &str| {
+        run.decisions
+            .iter()
+            .find(|d| d.crate_name == name)
+            .unwrap_or_else(|| panic!("no decision for {name}"))
+    };
+    // The highest stored version is the one compared
+    assert_eq!(decision(&outdated).current_version, "1.2.0");
+    let UpdateDecision::Queued { version, job_id } = &decision(&outdated).decision else {
+        panic!("expected a queued update: {:?}", decision(&outdated));
+    };
+    assert_eq!(version, "2.0.0");
+    assert_eq!(decision(&current).decision, UpdateDecision::UpToDate);
+    assert_eq!(decision(&opted_out).decision, UpdateDecision::OptedOut);
+    assert_eq!(decision(&busy).decision, UpdateDecision::JobActive);
+
+    let job = CrateJobQueries::find_job_by_id(pool.pool(), *job_id)
+        .await
+        .unwrap()
+        .expect("queued job exists");
+    assert_eq!(job.crate_name, outdated);
+    let options = job.options.unwrap();
+    assert_eq!(options["version"], "2.0.0");
+    assert_eq!(options["force_update"], true);
+
+    cleanup(&pool, &prefix).await;
+}
+
+#[tokio::test]
+async fn test_skips_run_while_backlog_exceeds_threshold() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database configured");
+        return;
+    };
+
+    let prefix = format!("autoupdate-{}", Uuid::new_v4().simple());
+    let outdated = format!("{prefix}-outdated");
+    store_crate(&pool, &outdated, "1.0.0").await;
+    CrateJobQueries::create_job(pool.pool(), &format!("{prefix}-busy"), "add_crate")
+        .await
+        .unwrap();
+
+    let versions = CannedVersions(HashMap::from([(outdated.clone(), "2.0.0".to_string())]));
+    let clock = FixedClock(Utc.with_ymd_and_hms(2025, 6, 1, 3, 0, 0).unwrap());
+    let run = run_once(&pool, &versions, &clock, &config(0))
+        .await
+        .unwrap();
+    assert!(run.decisions.is_empty());
+    assert!(run
+        .skipped
+        .as_deref()
+        .is_some_and(|reason| reason.contains("exceeds 0")));
+    assert_eq!(last_run().map(|run| run.started_at), Some(clock.0));
+
+    let queued_for_crate: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM crate_jobs WHERE crate_name = $1")
+            .bind(&outdated)
+            .fetch_one(pool.pool())
+            .await
+            .unwrap();
+    assert_eq!(queued_for_crate, 0);
+
+    cleanup(&pool, &prefix).await;
+}
@@ -0,0 +1,117 @@
+//! Integration tests for the `POST /mcp` request body limit
+//!
+//! The server is built over a lazy pool pointing at an unreachable database;
+//! `initialize` and rejected requests never touch storage.
+
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use db::DatabasePool;
+use mcp::{headers::SUPPORTED_PROTOCOL_VERSION, McpServer};
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use std::time::Duration;
+use tower::ServiceExt;
+
+const LIMIT: usize = 4096;
+
+async fn create_router() -> Router {
+    std::env::set_var("MCP_MAX_BODY_BYTES", LIMIT.to_string());
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+        .expect("lazy pool");
+    McpServer::new(DatabasePool::from_pool(pool))
+        .await
+        .expect("server should start without a database")
+        .create_router()
+}
+
+/// `initialize` request padded with trailing whitespace to exactly `size` bytes
+fn initialize_body(size: usize) -> String {
+    let mut body = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {"protocolVersion": SUPPORTED_PROTOCOL_VERSION, "capabilities": {}}
+    })
+    .to_string();
+    assert!(body.len() <= size);
+    body.push_str(&" ".repeat(size - body.len()));
+    body
+}
+
+fn post(body: Body) -> Request<Body> {
+    Request::builder()
+        .method(Method::POST)
+        .uri("/mcp")
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .header("MCP-Protocol-Version", SUPPORTED_PROTOCOL_VERSION)
+        .body(body)
+        .unwrap()
+}
+
+async fn assert_payload_too_large(app: &Router, request: Request<Body>) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["error"]["data"]["maxBodyBytes"], LIMIT);
+}
+
+#[tokio::test]
+async fn test_bodies_up_to_the_limit_are_accepted() {
+    let app = create_router().await;
+    for size in [LIMIT - 1, LIMIT] {
+        let response = app
+            .clone()
+            .oneshot(post(Body::from(initialize_body(size))))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "body of {size} bytes");
+    }
+}
+
+#[tokio::test]
+async fn test_body_over_the_limit_is_rejected() {
+    let app = create_router().await;
+    assert_payload_too_large(&app, post(Body::from(initialize_body(LIMIT + 1)))).await;
+}
+
+#[tokio::test]
+async fn test_declared_length_over_the_limit_is_rejected() {
+    let app = create_router().await;
+    let mut request = post(Body::from("{}"));
+    request
+        .headers_mut()
+        .insert(header::CONTENT_LENGTH, (LIMIT + 1).into());
+    assert_payload_too_large(&app, request).await;
+}
+
+#[tokio::test]
+async fn test_body_longer_than_its_content_length_is_rejected() {
+    let app = create_router().await;
+    let mut request = post(Body::from(initialize_body(LIMIT * 2)));
+    request
+        .headers_mut()
+        .insert(header::CONTENT_LENGTH, 100.into());
+    assert_payload_too_large(&app, request).await;
+}
+
+#[tokio::test]
+async fn test_endless_chunked_body_is_cut_off() {
+    let app = create_router().await;
+    // Never ends, so the request only finishes if reading stops at the limit
+    let chunks =
+        futures::stream::repeat_with(|| Ok::<_, std::io::Error>(Bytes::from(vec![b' '; 512])));
+    let request = post(Body::from_stream(chunks));
+    tokio::time::timeout(
+        Duration::from_secs(10),
+        assert_payload_too_large(&app, request),
+    )
+    .await
+    .expect("oversized stream should be rejected without reading it all");
+}
@@ -0,0 +1,154 @@
+//! Tests for `notifications/cancelled` handling of in-flight tool calls
+
+use anyhow::Result;
+use async_trait::async_trait;
+use db::DatabasePool;
+use mcp::handlers::McpHandler;
+use mcp::metrics::metrics;
+use mcp::tools::{ExecutionContext, RequestCancelled, Tool};
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Tool that works in small steps and stops as soon as it is cancelled
+struct SlowTool;
+
+#[async_trait]
+impl Tool for SlowTool {
+    fn definition(&self) -> Value {
+        json!({"name": "slow_query", "description": "Deliberately slow", "inputSchema": {}})
+    }
+
+    async fn execute(&self, arguments: Value) -> Result<String> {
+        self.execute_with_context(arguments, &ExecutionContext::default())
+            .await
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: Value,
+        context: &ExecutionContext,
+    ) -> Result<String> {
+        let steps = arguments.get("steps").and_then(Value::as_u64).unwrap_or(1);
+        for _ in 0..steps {
+            context.check_cancelled()?;
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        Ok("done".to_string())
+    }
+}
+
+fn create_handler() -> McpHandler {
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+        .expect("lazy pool");
+    let mut handler =
+        McpHandler::new(&DatabasePool::from_pool(pool)).expect("handler should build");
+    handler.register_tool("slow_query", Box::new(SlowTool));
+    handler
+}
+
+fn tool_call(id: i64, steps: u64) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "tools/call",
+        "params": {"name": "slow_query", "arguments": {"steps": steps}}
+    })
+}
+
+#[tokio::test]
+async fn test_cancelled_tool_call_stops_early() {
+    let handler = Arc::new(create_handler());
+    let cancelled_before = metrics().snapshot().requests_cancelled;
+
+    // 30 seconds of work if never cancelled
+    let started = Instant::now();
+    let call = tokio::spawn({
+        let handler = Arc::clone(&handler);
+        async move {
+            handler
+                .handle_session_request(tool_call(7, 1500), Some("session-a"))
+                .await
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // A cancellation from another session does not match
+    handler
+        .handle_session_request(
+            json!({"jsonrpc": "2.0", "method": "notifications/cancelled", "params": {"requestId": 7}}),
+            Some("session-b"),
+        )
+        .await
+        .unwrap();
+    assert!(!call.is_finished());
+
+    handler
+        .handle_session_request(
+            json!({
+                "jsonrpc": "2.0",
+                "method": "notifications/cancelled",
+                "params": {"requestId": 7, "reason": "user aborted"}
+            }),
+            Some("session-a"),
+        )
+        .await
+        .unwrap();
+
+    let result = tokio::time::timeout(Duration::from_secs(2), call)
+        .await
+        .expect("cancelled call should stop within the bound")
+        .unwrap();
+    assert!(started.elapsed() < Duration::from_secs(3));
+    let err = result.expect_err("cancelled call must not produce a result");
+    assert!(err.downcast_ref::<RequestCancelled>().is_some());
+    assert!(metrics().snapshot().requests_cancelled > cancelled_before);
+}
+
+#[tokio::test]
+async fn test_uncancelled_tool_call_completes() {
+    let handler = create_handler();
+
+    let response = handler
+        .handle_session_request(tool_call(1, 2), Some("session-a"))
+        .await
+        .unwrap();
+    assert_eq!(response["content"][0]["text"], "done");
+
+    // Cancelling a finished request is ignored
+    handler
+        .handle_session_request(
+            json!({"jsonrpc": "2.0", "method": "notifications/cancelled", "params": {"requestId": 1}}),
+            Some("session-a"),
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_sessionless_calls_cannot_be_cancelled() {
+    let handler = Arc::new(create_handler());
+
+    let call = tokio::spawn({
+        let handler = Arc::clone(&handler);
+        async move { handler.handle_session_request(tool_call(3, 10), None).await }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // Another sessionless client guessing the request ID has no effect
+    handler
+        .handle_session_request(
+            json!({"jsonrpc": "2.0", "method": "notifications/cancelled", "params": {"requestId": 3}}),
+            None,
+        )
+        .await
+        .unwrap();
+    assert!(!call.is_finished());
+    assert_eq!(handler.in_flight_calls(), 1);
+
+    let response = call.await.unwrap().unwrap();
+    assert_eq!(response["content"][0]["text"], "done");
+}
@@ -0,0 +1,89 @@
+//! Helpers shared by the integration tests
+
+use anyhow::{anyhow, Result};
+use db::DatabasePool;
+use embed::client::EmbeddingClient;
+use embed::models::{
+    BatchResponse, EmbeddingRequest, EmbeddingResponse, FileUploadResponse, JsonlResponseLine,
+};
+use sqlx::postgres::PgPoolOptions;
+use std::time::Duration;
+
+/// Connect to the test database, or `None` when tests should be skipped
+///
+/// Uses `TEST_DATABASE_URL`, then `DATABASE_URL`; an empty URL or `mock`
+/// means no database.
+#[allow(dead_code)]
+pub async fn create_test_pool() -> Option<DatabasePool> {
+    let database_url = std::env::var("TEST_DATABASE_URL")
+        .or_else(|_| std::env::var("DATABASE_URL"))
+        .ok()?;
+
+    if database_url.trim().is_empty() || database_url.trim().eq_ignore_ascii_case("mock") {
+        return None;
+    }
+
+    DatabasePool::new(&database_url).await.ok()
+}
+
+/// Pool pointing at an unreachable database, for servers and handlers whose
+/// tests never touch storage
+#[allow(dead_code)]
+pub fn lazy_pool() -> DatabasePool {
+    lazy_pool_at(
+        "postgres://nobody@127.0.0.1:1/none",
+        Duration::from_millis(200),
+    )
+}
+
+/// Pool that connects to `url` only once a query runs
+#[allow(dead_code)]
+pub fn lazy_pool_at(url: &str, acquire_timeout: Duration) -> DatabasePool {
+    DatabasePool::from_pool(
+        PgPoolOptions::new()
+            .acquire_timeout(acquire_timeout)
+            .connect_lazy(url)
+            .expect("lazy pool"),
+    )
+}
+
+/// Embedding client that returns a fixed vector and supports nothing else
+#[allow(dead_code)]
+pub struct FixedEmbeddingClient;
+
+#[async_trait::async_trait]
+impl EmbeddingClient for FixedEmbeddingClient {
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+        Ok(vec![0.1; 3072])
+    }
+
+    async fn generate_embedding(&self, _request: EmbeddingRequest) -> Result<EmbeddingResponse> {
+        Ok(EmbeddingResponse {
+            embedding: vec![0.1; 3072],
+        })
+    }
+
+    async fn upload_batch_file(
+        &self,
+        _content: &str,
+        _filename: &str,
+    ) -> Result<FileUploadResponse> {
+        Err(anyhow!("batch API not supported in tests"))
+    }
+
+    async fn create_batch(&self, _input_file_id: &str) -> Result<BatchResponse> {
+        Err(anyhow!("batch API not supported in tests"))
+    }
+
+    async fn get_batch(&self, _batch_id: &str) -> Result<BatchResponse> {
+        Err(anyhow!("batch API not supported in tests"))
+    }
+
+    async fn download_batch_results(&self, _file_id: &str) -> Result<Vec<JsonlResponseLine>> {
+        Err(anyhow!("batch API not supported in tests"))
+    }
+
+    async fn cancel_batch(&self, _batch_id: &str) -> Result<BatchResponse> {
+        Err(anyhow!("batch API not supported in tests"))
+    }
+}
@@ -52,7 +52,11 @@ async fn test_configuration_validation() {
         let is_valid_name = tool.name.ends_with("_query")
             || matches!(
                 tool.name.as_str(),
-                "add_rust_crate" | "remove_rust_crate" | "list_rust_crates" | "check_rust_status"
+                "add_rust_crate"
+                    | "remove_rust_crate"
+                    | "restore_rust_crate"
+                    | "list_rust_crates"
+                    | "check_rust_status"
             );
         assert!(
             is_valid_name,
@@ -74,6 +78,7 @@ async fn test_configuration_validation() {
         "rust_best_practices_query",
         "add_rust_crate",
         "remove_rust_crate",
+        "restore_rust_crate",
         "list_rust_crates",
         "check_rust_status",
     ];
@@ -152,6 +157,9 @@ fn test_configuration_validation_failures() {
             description: "Invalid tool name".to_string(),
             enabled: true,
             metadata_hints: None,
+            filters: None,
+            default_limit: None,
+            timeout_secs: None,
         }],
     };
 
@@ -170,6 +178,9 @@ fn test_configuration_validation_failures() {
             description: "Valid tool with empty doc type".to_string(),
             enabled: true,
             metadata_hints: None,
+            filters: None,
+            default_limit: None,
+            timeout_secs: None,
         }],
     };
 
@@ -186,6 +197,9 @@ fn test_configuration_validation_failures() {
                 description: "First tool".to_string(),
                 enabled: true,
                 metadata_hints: None,
+                filters: None,
+                default_limit: None,
+                timeout_secs: None,
             },
             ToolConfig {
                 name: "duplicate_query".to_string(),
@@ -194,6 +208,9 @@ fn test_configuration_validation_failures() {
                 description: "Second tool with same name".to_string(),
                 enabled: true,
                 metadata_hints: None,
+                filters: None,
+                default_limit: None,
+                timeout_secs: None,
             },
         ],
     };
@@ -205,6 +222,48 @@ fn test_configuration_validation_failures() {
     );
 }
 
+#[test]
+fn test_taxonomy_validation() {
+    use db::models::ToolsConfig;
+
+    let config_with = |taxonomy: serde_json::Value| {
+        json!({"tools": [{
+            "name": "talos_query",
+            "docType": "talos",
+            "title": "Talos",
+            "description": "Talos docs",
+            "enabled": true,
+            "metadataHints": {"taxonomy": taxonomy}
+        }]})
+    };
+
+    let valid: ToolsConfig = serde_json::from_value(config_with(json!({
+        "topics": [{"name": "networking", "keywords": ["cni"], "path_patterns": ["*/network/*"]}],
+        "categories": [{"name": "installation", "keywords": ["install"], "priority": 2}]
+    })))
+    .expect("valid taxonomy parses");
+    ConfigLoader::validate_config(&valid).expect("valid taxonomy validates");
+
+    let empty: ToolsConfig = serde_json::from_value(config_with(json!({
+        "topics": [{"name": "networking", "keywords": []}]
+    })))
+    .unwrap();
+    let error = ConfigLoader::validate_config(&empty)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("tools[0] ('talos_query'): taxonomy.topics[0] ('networking'): needs at least one keyword or path pattern"),
+        "{error}"
+    );
+
+    let unknown = serde_json::from_value::<ToolsConfig>(config_with(json!({
+        "topics": [{"name": "networking", "keywords": ["cni"], "weight": 3}]
+    })))
+    .unwrap_err()
+    .to_string();
+    assert!(unknown.contains("unknown field `weight`"), "{unknown}");
+}
+
 #[test]
 fn test_doctype_to_tool_name_mapping() {
     setup_test_config();
@@ -221,6 +280,7 @@ fn test_doctype_to_tool_name_mapping() {
                     tool.name.as_str(),
                     "add_rust_crate"
                         | "remove_rust_crate"
+                        | "restore_rust_crate"
                         | "list_rust_crates"
                         | "check_rust_status"
                 ),
@@ -0,0 +1,185 @@
+//! Response budgeting: oversized results are cut and continued through `fetch_continuation`
+
+use anyhow::Result;
+use async_trait::async_trait;
+use db::DatabasePool;
+use mcp::handlers::McpHandler;
+use mcp::tools::{ContinuationStore, Tool};
+use serde_json::{json, Map, Value};
+use sqlx::postgres::PgPoolOptions;
+use std::time::Duration;
+
+/// Extract the continuation token from a truncation marker
+fn marker_token(text: &str) -> &str {
+    let start = text.find("token \"").expect("truncation marker") + "token \"".len();
+    let end = start + text[start..].find('"').unwrap();
+    &text[start..end]
+}
+
+#[test]
+fn test_truncation_at_exact_boundary() {
+    let store = ContinuationStore::default();
+
+    // Exactly at the cap: returned untouched
+    let text = "x".repeat(100);
+    assert_eq!(store.truncate_text(None, text.clone(), 100), text);
+
+    // One character over without a line break: cut at exactly the cap
+    let text = "é".repeat(101);
+    let first = store.truncate_text(None, text, 100);
+    assert!(first.starts_with(&"é".repeat(100)));
+    assert!(!first.starts_with(&"é".repeat(101)));
+    assert!(first.contains("1 more characters"));
+    let rest = store.fetch(None, marker_token(&first)).unwrap();
+    assert_eq!(rest, "é");
+
+    // Lines are kept whole when a line break falls in the second half
+    let text = format!("{}\n{}\n{}", "a".repeat(40), "b".repeat(40), "c".repeat(40));
+    let first = store.truncate_text(None, text, 100);
+    assert!(first.starts_with(&format!(
+        "{}\n{}\n\n\n[Output truncated",
+        "a".repeat(40),
+        "b".repeat(40)
+    )));
+    let rest = store.fetch(None, marker_token(&first)).unwrap();
+    assert_eq!(rest, "c".repeat(40));
+}
+
+#[test]
+fn test_tokens_are_single_use_and_session_bound() {
+    let store = ContinuationStore::default();
+    let first = store.truncate_text(Some("session-a"), "word ".repeat(50), 100);
+    let token = marker_token(&first).to_string();
+
+    assert!(store.fetch(Some("session-b"), &token).is_err());
+
+    let store = ContinuationStore::default();
+    let first = store.truncate_text(Some("session-a"), "word ".repeat(50), 100);
+    let token = marker_token(&first).to_string();
+    let second = store.fetch(Some("session-a"), &token).unwrap();
+    assert!(second.contains("[Output truncated"));
+    assert!(store.fetch(Some("session-a"), &token).is_err());
+}
+
+#[test]
+fn test_expired_token_is_rejected() {
+    let store = ContinuationStore::with_ttl(Duration::ZERO);
+    let first = store.truncate_text(None, "line\n".repeat(100), 50);
+    let error = store.fetch(None, marker_token(&first)).unwrap_err();
+    assert!(error.to_string().contains("expired"), "{error}");
+}
+
+#[test]
+fn test_items_paginate_without_cutting_json() {
+    let store = ContinuationStore::default();
+    let items: Vec<Value> = (0..20)
+        .map(|i| json!({ "id": i, "name": format!("item-{i}"), "note": "x".repeat(50) }))
+        .collect();
+    let mut envelope = Map::new();
+    envelope.insert("page".to_string(), json!(1));
+
+    let mut seen = Vec::new();
+    let mut page = store.paginate_items(None, envelope, "entries", items, 500);
+    assert_eq!(page["page"], 1);
+    loop {
+        let entries = page["entries"].as_array().unwrap();
+        assert!(!entries.is_empty());
+        assert!(serde_json::to_string_pretty(&page).unwrap().len() <= 600);
+        seen.extend(entries.iter().map(|e| e["id"].as_i64().unwrap()));
+        let Some(token) = page["continuation_token"].as_str() else {
+            break;
+        };
+        let next = store.fetch(None, token).unwrap();
+        page = serde_json::from_str(&next).expect("continuation is valid JSON");
+    }
+    assert_eq!(seen, (0..20).collect::<Vec<_>>());
+}
+
+/// Tool returning a long text report
+struct LongReportTool;
+
+#[async_trait]
+impl Tool for LongReportTool {
+    fn definition(&self) -> Value {
+        json!({"name": "long_report", "description": "Long report", "inputSchema": {}})
+    }
+
+    async fn execute(&self, _arguments: Value) -> Result<String> {
+        Ok((0..50).map(|i| format!("line {i:02}\n")).collect())
+    }
+
+    fn response_soft_cap(&self) -> Option<usize> {
+        Some(200)
+    }
+}
+
+async fn call(handler: &McpHandler, name: &str, arguments: Value) -> String {
+    let response = handler
+        .handle_session_request(
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "tools/call",
+                "params": { "name": name, "arguments": arguments }
+            }),
+            Some("continuation-session"),
+        )
+        .await
+        .unwrap();
+    assert!(response.get("isError").is_none(), "{response}");
+    response["content"][0]["text"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_fetch_continuation_tool_returns_rest() {
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+        .expect("lazy pool");
+    let mut handler =
+        McpHandler::new(&DatabasePool::from_pool(pool)).expect("handler should build");
+    handler.register_tool("long_report", Box::new(LongReportTool));
+
+    let mut text = call(&handler, "long_report", json!({})).await;
+    let mut report = String::new();
+    while let Some(at) = text.find("\n\n[Output truncated") {
+        report.push_str(&text[..at]);
+        let token = marker_token(&text).to_string();
+        text = call(&handler, "fetch_continuation", json!({ "token": token })).await;
+    }
+    report.push_str(&text);
+
+    let expected: String = (0..50).map(|i| format!("line {i:02}\n")).collect();
+    assert_eq!(report, expected);
+}
+
+/// Tool returning a long text report without a soft cap of its own
+struct UncappedReportTool;
+
+#[async_trait]
+impl Tool for UncappedReportTool {
+    fn definition(&self) -> Value {
+        json!({"name": "uncapped_report", "description": "Uncapped report", "inputSchema": {}})
+    }
+
+    async fn execute(&self, _arguments: Value) -> Result<String> {
+        Ok((0..50).map(|i| format!("line {i:02}\n")).collect())
+    }
+}
+
+#[tokio::test]
+async fn test_max_response_chars_cuts_uncapped_tools() {
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+        .expect("lazy pool");
+    let mut handler = McpHandler::new(&DatabasePool::from_pool(pool))
+        .expect("handler should build")
+        .with_max_response_chars(120);
+    handler.register_tool("uncapped_report", Box::new(UncappedReportTool));
+
+    let text = call(&handler, "uncapped_report", json!({})).await;
+    let (head, _) = text.split_once("\n\n[Output truncated").expect("marker");
+    assert!(head.len() <= 120);
+    assert!(head.starts_with("line 00\n"));
+}
@@ -0,0 +1,80 @@
+//! CORS preflight on `/mcp` for origins configured through `MCP_ALLOWED_ORIGINS`
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, Method, Request, StatusCode},
+    Router,
+};
+use common::lazy_pool;
+use mcp::McpServer;
+use tower::ServiceExt;
+
+async fn preflight(app: &Router, origin: &str) -> (StatusCode, HeaderMap) {
+    let request = Request::builder()
+        .method(Method::OPTIONS)
+        .uri("/mcp")
+        .header(header::ORIGIN, origin)
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+        .header(
+            header::ACCESS_CONTROL_REQUEST_HEADERS,
+            "content-type,mcp-protocol-version,mcp-session-id",
+        )
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    (response.status(), response.headers().clone())
+}
+
+fn header_list(headers: &HeaderMap, name: header::HeaderName) -> String {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+#[tokio::test]
+async fn test_preflight_uses_configured_origins() {
+    // A malformed origin fails startup with the offending entry named
+    std::env::set_var("MCP_ALLOWED_ORIGINS", "https://app.example.com,example.com");
+    let error = McpServer::new(lazy_pool())
+        .await
+        .err()
+        .expect("malformed origin should fail startup");
+    assert!(error.to_string().contains("'example.com'"), "{error}");
+
+    std::env::set_var(
+        "MCP_ALLOWED_ORIGINS",
+        "https://app.example.com,https://*.preview.example.com",
+    );
+    let app = McpServer::new(lazy_pool())
+        .await
+        .expect("server should start without a database")
+        .create_router();
+
+    for origin in [
+        "https://app.example.com",
+        "https://pr-42.preview.example.com",
+    ] {
+        let (status, headers) = preflight(&app, origin).await;
+        assert_eq!(status, StatusCode::OK, "{origin}");
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            origin
+        );
+        let methods = header_list(&headers, header::ACCESS_CONTROL_ALLOW_METHODS);
+        for method in ["post", "get", "delete"] {
+            assert!(methods.contains(method), "{methods}");
+        }
+        let allowed = header_list(&headers, header::ACCESS_CONTROL_ALLOW_HEADERS);
+        for name in ["mcp-protocol-version", "mcp-session-id", "last-event-id"] {
+            assert!(allowed.contains(name), "{allowed}");
+        }
+    }
+
+    // Origins outside the allow-list get no CORS grant
+    let (_, headers) = preflight(&app, "https://evil.example.org").await;
+    assert!(headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+}
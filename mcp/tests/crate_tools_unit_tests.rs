@@ -10,7 +10,10 @@
 #![allow(clippy::match_same_arms)]
 #![allow(clippy::doc_markdown)]
 
+mod common;
+
 use anyhow::Result;
+use common::create_test_pool;
 use db::DatabasePool;
 use embed::client::EmbeddingClient;
 use embed::models::{
@@ -18,19 +21,24 @@ use embed::models::{
     FileUploadResponse, JsonlResponse, JsonlResponseBody, JsonlResponseLine,
 };
 use mcp::crate_tools::{
-    AddRustCrateTool, CheckRustStatusTool, ListRustCratesTool, RemoveRustCrateTool,
+    AddRustCrateTool, BackfillEmbeddingsTool, CheckRustStatusTool, ListRustCratesTool,
+    RemoveRustCrateTool,
 };
-use mcp::tools::Tool;
+use mcp::tools::{Tool, ToolError};
 use serde_json::json;
-use std::{env, sync::Arc};
+use sqlx::postgres::PgPoolOptions;
+use std::sync::Arc;
 use tokio::time::{timeout, Duration};
 
-/// Mock embedding client for testing
-struct MockEmbeddingClient;
+/// Mock embedding client for testing, answering after `delay`
+struct MockEmbeddingClient {
+    delay: Duration,
+}
 
 #[async_trait::async_trait]
 impl EmbeddingClient for MockEmbeddingClient {
     async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+        tokio::time::sleep(self.delay).await;
         Ok(vec![0.1; 3072]) // Mock OpenAI embedding dimensions
     }
 
@@ -157,20 +165,9 @@ impl EmbeddingClient for MockEmbeddingClient {
 
 /// Helper to create mock embedding client
 fn create_mock_embedding_client() -> Arc<dyn EmbeddingClient + Send + Sync> {
-    Arc::new(MockEmbeddingClient)
-}
-
-/// Helper to create test database pool (mock if not available)
-async fn create_test_pool() -> Option<DatabasePool> {
-    let database_url = env::var("TEST_DATABASE_URL")
-        .or_else(|_| env::var("DATABASE_URL"))
-        .ok()?;
-
-    if database_url.trim().is_empty() || database_url.trim().eq_ignore_ascii_case("mock") {
-        return None;
-    }
-
-    DatabasePool::new(&database_url).await.ok()
+    Arc::new(MockEmbeddingClient {
+        delay: Duration::ZERO,
+    })
 }
 
 #[tokio::test]
@@ -238,6 +235,37 @@ async fn test_add_rust_crate_invalid_input_validation() {
     }
 }
 
+#[tokio::test]
+async fn test_add_rust_crate_channel_applies_only_to_std_crates() {
+    // Rejected before any database or network access
+    let pool = DatabasePool::from_pool(
+        PgPoolOptions::new()
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .expect("lazy pool"),
+    );
+    let tool = AddRustCrateTool::new(pool, create_mock_embedding_client());
+
+    let error = tool
+        .execute(json!({"name": "std", "version": "1.79.0"}))
+        .await
+        .expect_err("std takes a channel, not a version");
+    assert!(error.to_string().contains("use 'channel'"));
+
+    let error = tool
+        .execute(json!({"name": "core", "channel": "weekly"}))
+        .await
+        .expect_err("unknown channel");
+    assert!(error.to_string().contains("Unknown channel 'weekly'"));
+
+    let error = tool
+        .execute(json!({"name": "tokio", "channel": "nightly"}))
+        .await
+        .expect_err("channel is only for std crates");
+    assert!(error
+        .to_string()
+        .contains("only applies to standard library crates"));
+}
+
 #[tokio::test]
 async fn test_add_rust_crate_valid_input() {
     let client = create_mock_embedding_client();
@@ -435,14 +463,9 @@ async fn test_remove_rust_crate_nonexistent_crate() {
             .execute(json!({"name": "non-existent-test-crate-12345"}))
             .await;
 
-        match result {
-            Ok(response) => {
-                assert!(response.contains("not found") || response.contains("does not exist"));
-            }
-            Err(_) => {
-                // Errors are acceptable in test environment
-            }
-        }
+        let error = result.expect_err("missing crate");
+        let error = error.downcast_ref::<ToolError>().expect("ToolError");
+        assert_eq!(error.code(), "not_found");
     } else {
         println!("⚠️ Skipping database-dependent test - no database available");
     }
@@ -585,6 +608,108 @@ async fn test_list_rust_crates_pagination_parameters() {
     }
 }
 
+#[tokio::test]
+async fn test_backfill_embeddings_scoped_to_unknown_crate() {
+    if let Some(pool) = create_test_pool().await {
+        let tool = BackfillEmbeddingsTool::new(pool, create_mock_embedding_client());
+
+        let definition = tool.definition();
+        assert_eq!(definition["name"], "backfill_embeddings");
+        assert!(definition["inputSchema"]["properties"]["batch_size"].is_object());
+
+        // Nothing to backfill for a crate that doesn't exist
+        let result = tool
+            .execute(json!({"crate_name": "nonexistent-backfill-crate-12345", "limit": 10}))
+            .await;
+
+        match result {
+            Ok(response) => assert!(response.contains("No documents are missing embeddings")),
+            Err(e) => {
+                // Vector extension may be unavailable in test databases
+                assert!(e.to_string().contains("Vector extension"));
+            }
+        }
+    } else {
+        println!("⚠️ Skipping database-dependent test - no database available");
+    }
+}
+
+#[tokio::test]
+async fn test_timed_out_backfill_fails_its_job() {
+    let Some(pool) = create_test_pool().await else {
+        println!("⚠️ Skipping database-dependent test - no database available");
+        return;
+    };
+    let crate_name = format!("backfill-timeout-{}", uuid::Uuid::new_v4().simple());
+    sqlx::query(
+        "INSERT INTO document_sources (doc_type, source_name, config, enabled) VALUES ('rust', $1, '{}', true)",
+    )
+    .bind(&crate_name)
+    .execute(pool.pool())
+    .await
+    .unwrap();
+    sqlx::query(
+        "INSERT INTO documents (id, doc_type, source_name, doc_path, content, metadata)
+         VALUES ($1, 'rust', $2, 'lib.rs', 'pub fn slow() {}', jsonb_build_object('crate_name', $2::text))",
+    )
+    .bind(uuid::Uuid::new_v4())
+    .bind(&crate_name)
+    .execute(pool.pool())
+    .await
+    .unwrap();
+
+    // The embedding outlasts the call, as under a tool timeout
+    let tool = BackfillEmbeddingsTool::new(
+        pool.clone(),
+        Arc::new(MockEmbeddingClient {
+            delay: Duration::from_secs(30),
+        }),
+    );
+    let call = timeout(
+        Duration::from_millis(500),
+        tool.execute(json!({"crate_name": crate_name})),
+    )
+    .await;
+
+    let mut status = None;
+    match call {
+        Ok(Err(e)) => {
+            // Vector extension may be unavailable in test databases
+            assert!(
+                e.to_string().contains("Vector extension") || e.to_string().contains("disabled")
+            );
+        }
+        Ok(Ok(output)) => panic!("backfill should not finish: {output}"),
+        Err(_) => {
+            for _ in 0..50 {
+                status = sqlx::query_scalar::<_, String>(
+                    "SELECT status::text FROM crate_jobs WHERE crate_name = $1 AND status::text <> 'running'",
+                )
+                .bind(&crate_name)
+                .fetch_optional(pool.pool())
+                .await
+                .unwrap();
+                if status.is_some() {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            assert_eq!(status.as_deref(), Some("failed"));
+        }
+    }
+
+    for sql in [
+        "DELETE FROM crate_jobs WHERE crate_name = $1",
+        "DELETE FROM documents WHERE doc_type = 'rust' AND source_name = $1",
+        "DELETE FROM document_sources WHERE doc_type = 'rust' AND source_name = $1",
+    ] {
+        let _ = sqlx::query(sql)
+            .bind(&crate_name)
+            .execute(pool.pool())
+            .await;
+    }
+}
+
 #[tokio::test]
 async fn test_all_tools_handle_malformed_json() {
     if let Some(pool) = create_test_pool().await {
@@ -0,0 +1,225 @@
+//! Document source management: listing, disabling and deleting sources
+//!
+//! Tests skip when no database is configured.
+
+mod common;
+
+use common::create_test_pool;
+use db::{DatabasePool, DocumentQueries, IngestJobQueries};
+use mcp::handlers::McpHandler;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// Create a source holding `count` documents that mention "quokka"
+async fn seed_source(pool: &DatabasePool, doc_type: &str, source_name: &str, count: usize) {
+    sqlx::query(
+        r#"INSERT INTO document_sources (doc_type, source_name, config, enabled)
+           VALUES ($1, $2, '{"repo": "example/widgets", "paths": ["docs", "guides"]}', true)"#,
+    )
+    .bind(doc_type)
+    .bind(source_name)
+    .execute(pool.pool())
+    .await
+    .expect("seed source");
+
+    for i in 0..count {
+        sqlx::query(
+            "INSERT INTO documents (id, doc_type, source_name, doc_path, content, metadata)
+             VALUES ($1, $2, $3, $4, $5, '{}')",
+        )
+        .bind(Uuid::new_v4())
+        .bind(doc_type)
+        .bind(source_name)
+        .bind(format!("{source_name}/page-{i}.md"))
+        .bind(format!("The quokka guide, part {i}, from {source_name}."))
+        .execute(pool.pool())
+        .await
+        .expect("seed document");
+    }
+}
+
+async fn cleanup(pool: &DatabasePool, doc_type: &str) {
+    for table in ["documents", "document_sources", "ingest_jobs"] {
+        let _ = sqlx::query(&format!("DELETE FROM {table} WHERE doc_type = $1"))
+            .bind(doc_type)
+            .execute(pool.pool())
+            .await;
+    }
+}
+
+async fn call(handler: &McpHandler, name: &str, arguments: Value) -> Value {
+    handler
+        .handle_request(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": name, "arguments": arguments }
+        }))
+        .await
+        .expect("tool call should produce a result")
+}
+
+fn result_json(response: &Value) -> Value {
+    assert!(response.get("isError").is_none(), "{response}");
+    serde_json::from_str(response["content"][0]["text"].as_str().unwrap()).unwrap()
+}
+
+/// Source names of the documents a search returns
+async fn searched_sources(pool: &DatabasePool, doc_type: &str) -> Vec<String> {
+    let mut sources: Vec<String> =
+        DocumentQueries::doc_type_vector_search(pool.pool(), doc_type, "quokka", &[], 20)
+            .await
+            .expect("search should succeed")
+            .into_iter()
+            .map(|doc| doc.source_name)
+            .collect();
+    sources.sort();
+    sources.dedup();
+    sources
+}
+
+#[tokio::test]
+async fn test_disabled_source_is_hidden_from_search() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping doc source test: no test database configured");
+        return;
+    };
+    let doc_type = format!("srctest_{}", &Uuid::new_v4().simple().to_string()[..8]);
+    seed_source(&pool, &doc_type, "alpha", 2).await;
+    seed_source(&pool, &doc_type, "beta", 3).await;
+    let handler = McpHandler::new(&pool).expect("handler should build");
+
+    let listing = result_json(
+        &call(
+            &handler,
+            "list_doc_sources",
+            json!({ "doc_type": doc_type }),
+        )
+        .await,
+    );
+    let sources = listing["sources"].as_array().unwrap();
+    assert_eq!(sources.len(), 2);
+    assert_eq!(sources[0]["source_name"], "alpha");
+    assert_eq!(sources[0]["document_count"], 2);
+    assert_eq!(sources[1]["document_count"], 3);
+    assert_eq!(sources[0]["enabled"], true);
+    assert_eq!(
+        sources[0]["config"],
+        json!({ "repo": "example/widgets", "paths": "[2 items]" })
+    );
+
+    // Pagination keeps the doc_type filter
+    let first = result_json(
+        &call(
+            &handler,
+            "list_doc_sources",
+            json!({ "doc_type": doc_type, "limit": 1 }),
+        )
+        .await,
+    );
+    assert_eq!(first["has_more"], true);
+    let second = result_json(
+        &call(
+            &handler,
+            "list_doc_sources",
+            json!({ "doc_type": doc_type, "limit": 1, "page": 2 }),
+        )
+        .await,
+    );
+    assert_eq!(second["sources"][0]["source_name"], "beta");
+    assert_eq!(second["has_more"], false);
+
+    assert_eq!(
+        searched_sources(&pool, &doc_type).await,
+        vec!["alpha", "beta"]
+    );
+
+    let toggled = result_json(
+        &call(
+            &handler,
+            "set_doc_source_enabled",
+            json!({ "doc_type": doc_type, "source_name": "beta", "enabled": false }),
+        )
+        .await,
+    );
+    assert_eq!(toggled["enabled"], false);
+    assert_eq!(toggled["document_count"], 3);
+    assert_eq!(searched_sources(&pool, &doc_type).await, vec!["alpha"]);
+
+    call(
+        &handler,
+        "set_doc_source_enabled",
+        json!({ "doc_type": doc_type, "source_name": "beta", "enabled": true }),
+    )
+    .await;
+    assert_eq!(
+        searched_sources(&pool, &doc_type).await,
+        vec!["alpha", "beta"]
+    );
+
+    cleanup(&pool, &doc_type).await;
+}
+
+#[tokio::test]
+async fn test_delete_source_dry_run_guard_and_cascade() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping doc source test: no test database configured");
+        return;
+    };
+    let doc_type = format!("srctest_{}", &Uuid::new_v4().simple().to_string()[..8]);
+    seed_source(&pool, &doc_type, "alpha", 2).await;
+    seed_source(&pool, &doc_type, "beta", 3).await;
+    let handler = McpHandler::new(&pool).expect("handler should build");
+    let target = json!({ "doc_type": doc_type, "source_name": "beta" });
+
+    // Dry run reports without deleting
+    let mut args = target.clone();
+    args["dry_run"] = json!(true);
+    let preview = result_json(&call(&handler, "delete_doc_source", args).await);
+    assert_eq!(preview["document_count"], 3);
+    assert_eq!(preview["deleted"], false);
+    assert_eq!(
+        searched_sources(&pool, &doc_type).await,
+        vec!["alpha", "beta"]
+    );
+
+    // An active ingest job for the doc type blocks deletion
+    let job = IngestJobQueries::create_job(pool.pool(), "https://example.com/widgets", &doc_type)
+        .await
+        .expect("create job");
+    let refused = call(&handler, "delete_doc_source", target.clone()).await;
+    assert_eq!(refused["isError"], true);
+    assert!(refused["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("1 active job"));
+    IngestJobQueries::update_job_status(
+        pool.pool(),
+        job.id,
+        db::models::JobStatus::Completed,
+        None,
+        None,
+    )
+    .await
+    .expect("finish job");
+
+    let deleted = result_json(&call(&handler, "delete_doc_source", target.clone()).await);
+    assert_eq!(deleted["deleted"], true);
+    assert_eq!(deleted["document_count"], 3);
+    assert_eq!(searched_sources(&pool, &doc_type).await, vec!["alpha"]);
+
+    let listing = result_json(
+        &call(
+            &handler,
+            "list_doc_sources",
+            json!({ "doc_type": doc_type }),
+        )
+        .await,
+    );
+    assert_eq!(listing["sources"].as_array().unwrap().len(), 1);
+
+    let missing = call(&handler, "delete_doc_source", target).await;
+    assert_eq!(missing["isError"], true);
+
+    cleanup(&pool, &doc_type).await;
+}
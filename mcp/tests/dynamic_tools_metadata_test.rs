@@ -145,6 +145,7 @@ fn test_tool_metadata_hints_creation() {
         supported_categories: vec!["docs".to_string(), "guides".to_string()],
         supported_topics: vec!["installation".to_string(), "configuration".to_string()],
         supports_api_version: false,
+        taxonomy: None,
     };
 
     assert_eq!(hints.supported_formats.len(), 2);
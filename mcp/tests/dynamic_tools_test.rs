@@ -1,5 +1,7 @@
 //! Integration tests for dynamic tool registration and usage
 
+#![allow(clippy::unnecessary_unwrap)]
+
 use db::{DatabasePool, PoolConfig};
 use mcp::{config::ConfigLoader, handlers::McpHandler};
 use serde_json::json;
@@ -58,8 +60,8 @@ async fn test_dynamic_tools_registration() {
             );
             assert!(tool.enabled, "All filtered tools should be enabled");
             assert!(
-                tool.name.ends_with("_query") || tool.name.starts_with("add_") || tool.name.starts_with("remove_") || tool.name.starts_with("list_") || tool.name.starts_with("check_"),
-                "Tool names should end with '_query' or be action tools (add_*, remove_*, list_*, check_*)"
+                tool.name.ends_with("_query") || tool.name.starts_with("add_") || tool.name.starts_with("remove_") || tool.name.starts_with("restore_") || tool.name.starts_with("list_") || tool.name.starts_with("check_"),
+                "Tool names should end with '_query' or be action tools (add_*, remove_*, restore_*, list_*, check_*)"
             );
         }
     } else if let Ok(Ok(db_pool)) = timeout(
@@ -345,3 +347,91 @@ async fn test_parameter_validation_dynamic_tools() {
 
     cleanup_test_config();
 }
+
+#[test]
+fn test_tool_name_for_doc_type() {
+    use mcp::tools::DynamicQueryTool;
+
+    assert_eq!(
+        DynamicQueryTool::tool_name_for_doc_type("solana"),
+        "solana_query"
+    );
+    assert_eq!(
+        DynamicQueryTool::tool_name_for_doc_type("Rust-Best Practices"),
+        "rust_best_practices_query"
+    );
+}
+
+#[tokio::test]
+async fn test_document_source_tools_registration() {
+    let _lock = TEST_MUTEX.lock().await;
+    setup_test_config();
+
+    let database_url = std::env::var("TEST_DATABASE_URL").unwrap_or_else(|_| "mock".to_string());
+
+    if database_url == "mock" {
+        eprintln!("Skipping document source tools test - no test database available");
+    } else if let Ok(Ok(db_pool)) = timeout(
+        Duration::from_secs(10),
+        DatabasePool::with_config(
+            PoolConfig::builder()
+                .database_url(database_url)
+                .min_connections(1)
+                .max_connections(5)
+                .acquire_timeout(Duration::from_secs(30))
+                .build()
+                .unwrap(),
+        ),
+    )
+    .await
+    {
+        let doc_type = format!("dsrc{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+        db::DocumentQueries::ensure_document_source(db_pool.pool(), &doc_type, "test-source")
+            .await
+            .expect("Failed to create document source");
+        // A configured doc type, under a source name of its own
+        let solana_source = format!("test-source-{doc_type}");
+        db::DocumentQueries::ensure_document_source(db_pool.pool(), "solana", &solana_source)
+            .await
+            .expect("Failed to create document source");
+
+        let mut handler = McpHandler::new(&db_pool).expect("Failed to create handler");
+        let registered = handler.register_document_source_tools(&db_pool).await;
+
+        let _ = sqlx::query(
+            "DELETE FROM document_sources
+             WHERE doc_type = $1 OR (doc_type = 'solana' AND source_name = $2)",
+        )
+        .bind(&doc_type)
+        .bind(&solana_source)
+        .execute(db_pool.pool())
+        .await;
+        assert!(registered.expect("registration should succeed") >= 1);
+
+        let response = handler
+            .handle_request(json!({"method": "tools/list", "params": {}}))
+            .await
+            .expect("tools/list should succeed");
+        let tools = response["tools"]
+            .as_array()
+            .expect("tools should be an array");
+
+        let name = format!("{doc_type}_query");
+        let tool = tools
+            .iter()
+            .find(|t| t["name"] == name.as_str())
+            .expect("document source should get a derived query tool");
+        let properties = &tool["inputSchema"]["properties"];
+        for filter in ["category", "topic", "complexity", "api_version"] {
+            assert!(properties[filter].is_object(), "missing {filter} filter");
+        }
+
+        // Configured tools are not duplicated
+        let solana_tools = tools.iter().filter(|t| t["name"] == "solana_query").count();
+        assert_eq!(solana_tools, 1);
+    } else {
+        eprintln!("Skipping document source tools test - DB not reachable");
+    }
+
+    cleanup_test_config();
+}
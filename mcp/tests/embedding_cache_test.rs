@@ -0,0 +1,251 @@
+//! Embedding cache tests
+//!
+//! A counting mock client verifies that identical content is only sent to the
+//! embedding API once, and that only those requests are charged to the job.
+//! Tests skip when no database with the `embedding_cache` table is configured.
+
+mod common;
+
+use anyhow::{anyhow, Result};
+use db::models::JobStatus;
+use db::{CrateStorage, DatabasePool, EmbeddingCacheQueries};
+use embed::client::EmbeddingClient;
+use embed::models::{
+    BatchResponse, EmbeddingRequest, EmbeddingResponse, FileUploadResponse, JsonlResponseLine,
+};
+use embed::{prices, JobCosts};
+use mcp::crate_tools::CheckRustStatusTool;
+use mcp::embedding_cache::{content_hash, CachedEmbeddingClient};
+use mcp::job_queue::CrateJobProcessor;
+use mcp::metrics::metrics;
+use mcp::tools::Tool;
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Mock client that counts embedding API calls
+#[derive(Default)]
+struct CountingEmbeddingClient {
+    calls: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl EmbeddingClient for CountingEmbeddingClient {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        #[allow(clippy::cast_precision_loss)]
+        Ok(vec![text.len() as f32; 4])
+    }
+
+    async fn generate_embedding(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse> {
+        Ok(EmbeddingResponse {
+            embedding: self.embed(&request.input).await?,
+        })
+    }
+
+    async fn upload_batch_file(
+        &self,
+        _content: &str,
+        _filename: &str,
+    ) -> Result<FileUploadResponse> {
+        Err(anyhow!("not supported"))
+    }
+
+    async fn create_batch(&self, _input_file_id: &str) -> Result<BatchResponse> {
+        Err(anyhow!("not supported"))
+    }
+
+    async fn get_batch(&self, _batch_id: &str) -> Result<BatchResponse> {
+        Err(anyhow!("not supported"))
+    }
+
+    async fn download_batch_results(&self, _file_id: &str) -> Result<Vec<JsonlResponseLine>> {
+        Err(anyhow!("not supported"))
+    }
+
+    async fn cancel_batch(&self, _batch_id: &str) -> Result<BatchResponse> {
+        Err(anyhow!("not supported"))
+    }
+}
+
+/// Test database pool, or `None` without one or without the `embedding_cache` table
+async fn create_test_pool() -> Option<DatabasePool> {
+    let pool = common::create_test_pool().await?;
+    let has_table: bool = sqlx::query_scalar("SELECT to_regclass('embedding_cache') IS NOT NULL")
+        .fetch_one(pool.pool())
+        .await
+        .ok()?;
+    has_table.then_some(pool)
+}
+
+async fn cleanup(pool: &DatabasePool, contents: &[&str]) {
+    let hashes: Vec<String> = contents.iter().map(|c| content_hash(c)).collect();
+    let _ = sqlx::query("DELETE FROM embedding_cache WHERE content_sha256 = ANY($1)")
+        .bind(&hashes)
+        .execute(pool.pool())
+        .await;
+}
+
+#[tokio::test]
+async fn test_identical_content_embedded_once() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database with embedding_cache configured");
+        return;
+    };
+
+    let content = format!("pub fn cached() {{}} // {}", Uuid::new_v4());
+    let mock = Arc::new(CountingEmbeddingClient::default());
+
+    // Two separate ingestions of the same page
+    for _ in 0..2 {
+        let client = CachedEmbeddingClient::new(mock.clone(), pool.pool().clone());
+        let embedding = client.embed(&content).await.unwrap();
+        assert_eq!(embedding.len(), 4);
+    }
+
+    assert_eq!(mock.calls.load(Ordering::SeqCst), 1);
+    cleanup(&pool, &[&content]).await;
+}
+
+#[tokio::test]
+async fn test_embed_many_batches_and_dedups() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database with embedding_cache configured");
+        return;
+    };
+
+    let suffix = Uuid::new_v4();
+    let a = format!("alpha {suffix}");
+    let b = format!("beta {suffix}");
+    let c = format!("gamma {suffix}");
+    let mock = Arc::new(CountingEmbeddingClient::default());
+    let client = CachedEmbeddingClient::new(mock.clone(), pool.pool().clone());
+
+    let results = client.embed_many(&[&a, &b, &a]).await;
+    assert_eq!(results.len(), 3);
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(mock.calls.load(Ordering::SeqCst), 2);
+
+    // Only the new text misses the cache
+    let results = client.embed_many(&[&a, &b, &c]).await;
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(mock.calls.load(Ordering::SeqCst), 3);
+
+    cleanup(&pool, &[&a, &b, &c]).await;
+}
+
+#[tokio::test]
+async fn test_job_costs_count_embedded_misses() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database with embedding_cache configured");
+        return;
+    };
+
+    let suffix = Uuid::new_v4();
+    let a = format!("alpha costs {suffix}");
+    let b = format!("beta costs with a few more words {suffix}");
+    let mock = Arc::new(CountingEmbeddingClient::default());
+    let client = CachedEmbeddingClient::new(mock.clone(), pool.pool().clone());
+    let model = client.embedding_config().model;
+    let calls_before = metrics()
+        .api_costs()
+        .get(&("embedding".to_string(), model.clone()))
+        .map_or(0, |usage| usage.calls);
+
+    // The repeated text and the later cache hit are free
+    client.embed_many(&[&a, &b, &a]).await;
+    client.embed_many(&[&a]).await;
+    let tokens = (embed::token_count(&a) + embed::token_count(&b)) as u64;
+    let costs = client.costs();
+    let usage = &costs.models[&model];
+    assert_eq!(usage.calls, 2);
+    assert_eq!(usage.input_tokens, tokens);
+    assert!((costs.total_cost_usd - prices().cost(&model, tokens, 0)).abs() < 1e-12);
+    assert_eq!(client.clone().costs(), costs);
+    let calls_after = metrics().api_costs()[&("embedding".to_string(), model.clone())].calls;
+    assert!(calls_after >= calls_before + 2);
+
+    // Each attempt of a job adds to its recorded costs
+    let storage = CrateStorage::in_memory();
+    let processor = CrateJobProcessor::new(storage.clone());
+    let job = processor
+        .enqueue_add_crate_job("costed", &Default::default(), None)
+        .await
+        .unwrap()
+        .job;
+    processor.add_job_costs(job.id, &costs).await.unwrap();
+    let total = processor.add_job_costs(job.id, &costs).await.unwrap();
+    assert_eq!(total.models[&model].calls, 4);
+    assert_eq!(total.models[&model].input_tokens, 2 * tokens);
+    processor
+        .update_job_status(job.id, JobStatus::Completed, Some(100), None)
+        .await
+        .unwrap();
+    let recorded: JobCosts = serde_json::from_value(
+        storage
+            .store()
+            .find_job_by_id(job.id)
+            .await
+            .unwrap()
+            .unwrap()
+            .details
+            .unwrap()["job_costs"]
+            .clone(),
+    )
+    .unwrap();
+    assert_eq!(recorded, total);
+
+    let report = CheckRustStatusTool::new(storage)
+        .execute(json!({"include_cost_analysis": true}))
+        .await
+        .unwrap();
+    assert!(report.contains("Cost Analysis (last 30 days)"), "{report}");
+    assert!(
+        report.contains(&format!(
+            "    - costed: ${:.4} (1 jobs, {} tokens)",
+            total.total_cost_usd,
+            2 * tokens
+        )),
+        "{report}"
+    );
+    assert!(report.contains(&format!("    - {model}: ")), "{report}");
+
+    cleanup(&pool, &[&a, &b]).await;
+}
+
+#[tokio::test]
+async fn test_evict_unused_entries() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database with embedding_cache configured");
+        return;
+    };
+
+    let content = format!("stale {}", Uuid::new_v4());
+    let hash = content_hash(&content);
+    EmbeddingCacheQueries::store(
+        pool.pool(),
+        &[(hash.clone(), vec![1.0; 4])],
+        "test-model",
+        4,
+    )
+    .await
+    .unwrap();
+    sqlx::query(
+        "UPDATE embedding_cache SET last_used_at = NOW() - INTERVAL '40 days' WHERE content_sha256 = $1",
+    )
+    .bind(&hash)
+    .execute(pool.pool())
+    .await
+    .unwrap();
+
+    let evicted = EmbeddingCacheQueries::evict_unused(pool.pool(), 30)
+        .await
+        .unwrap();
+    assert!(evicted >= 1);
+
+    let found = EmbeddingCacheQueries::lookup(pool.pool(), &[hash], "test-model", 4)
+        .await
+        .unwrap();
+    assert!(found.is_empty());
+}
@@ -0,0 +1,249 @@
+//! Relevance feedback capture and aggregation against a real database
+//!
+//! Tests skip when no database with the `search_feedback` table is
+//! configured. Each test rates queries made of a unique term, so counts
+//! from other runs never mix in.
+
+mod common;
+
+use chrono::{Duration, Utc};
+use db::models::{Document, NewSearchFeedback};
+use db::{DatabasePool, DocumentQueries, FeedbackQueries};
+use mcp::feedback_tools::{GetFeedbackStatsTool, RecordFeedbackTool};
+use mcp::tools::Tool;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// Test database pool, or `None` without one or without the feedback table
+async fn create_test_pool() -> Option<DatabasePool> {
+    let pool = common::create_test_pool().await?;
+    FeedbackQueries::totals(pool.pool(), Utc::now())
+        .await
+        .ok()?;
+    Some(pool)
+}
+
+/// A unique lowercase word to query with, ending in `x` so stemming keeps it whole
+fn unique_term() -> String {
+    let suffix = Uuid::new_v4().simple().to_string();
+    format!(
+        "fbterm{}x",
+        suffix
+            .chars()
+            .filter(char::is_ascii_lowercase)
+            .collect::<String>()
+    )
+}
+
+async fn seed_document(pool: &DatabasePool, crate_name: &str) -> Document {
+    let doc = Document {
+        id: Uuid::new_v4(),
+        doc_type: "rust".to_string(),
+        source_name: crate_name.to_string(),
+        doc_path: format!("{crate_name}/fn.spawn.html"),
+        content: "Spawn a new task.".to_string(),
+        metadata: json!({"crate_name": crate_name}),
+        embedding: None,
+        token_count: None,
+        created_at: Some(Utc::now()),
+        updated_at: Some(Utc::now()),
+    };
+    DocumentQueries::batch_insert_documents(pool.pool(), std::slice::from_ref(&doc))
+        .await
+        .unwrap();
+    doc
+}
+
+async fn cleanup(pool: &DatabasePool, term: &str, crate_name: &str) {
+    let _ = sqlx::query("DELETE FROM search_feedback WHERE query LIKE '%' || $1 || '%'")
+        .bind(term)
+        .execute(pool.pool())
+        .await;
+    let _ = sqlx::query("DELETE FROM documents WHERE source_name = $1")
+        .bind(crate_name)
+        .execute(pool.pool())
+        .await;
+}
+
+fn feedback(query: &str, rating: &str, doc: &Document) -> NewSearchFeedback {
+    NewSearchFeedback {
+        session_id: Some("feedback-test".to_string()),
+        query: query.to_string(),
+        rating: rating.to_string(),
+        document_id: Some(doc.id),
+        doc_path: None,
+    }
+}
+
+#[tokio::test]
+async fn test_record_resolves_document_by_id_and_path() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping feedback test: no database with the search_feedback table");
+        return;
+    };
+    let term = unique_term();
+    let crate_name = format!("fb-{term}");
+    let doc = seed_document(&pool, &crate_name).await;
+
+    let by_id = FeedbackQueries::record(pool.pool(), &feedback(&term, "useful", &doc)).await;
+    let by_path = FeedbackQueries::record(
+        pool.pool(),
+        &NewSearchFeedback {
+            document_id: None,
+            doc_path: Some(doc.doc_path.clone()),
+            ..feedback(&term, "partially", &doc)
+        },
+    )
+    .await;
+    cleanup(&pool, &term, &crate_name).await;
+
+    let by_id = by_id.unwrap();
+    assert!(by_id.document_found);
+    assert_eq!(by_id.document_id, Some(doc.id));
+    assert_eq!(by_id.doc_path.as_deref(), Some(doc.doc_path.as_str()));
+    assert_eq!(by_id.doc_type.as_deref(), Some("rust"));
+    assert_eq!(by_id.crate_name.as_deref(), Some(crate_name.as_str()));
+    assert_eq!(by_id.session_id.as_deref(), Some("feedback-test"));
+
+    let by_path = by_path.unwrap();
+    assert!(by_path.document_found);
+    assert_eq!(by_path.document_id, Some(doc.id));
+    assert_eq!(by_path.rating, "partially");
+}
+
+#[tokio::test]
+async fn test_unknown_documents_are_stored_flagged() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping feedback test: no database with the search_feedback table");
+        return;
+    };
+    let term = unique_term();
+    let missing_id = Uuid::new_v4();
+
+    let entry = FeedbackQueries::record(
+        pool.pool(),
+        &NewSearchFeedback {
+            session_id: None,
+            query: term.clone(),
+            rating: "not_useful".to_string(),
+            document_id: Some(missing_id),
+            doc_path: Some("gone/fn.missing.html".to_string()),
+        },
+    )
+    .await;
+    cleanup(&pool, &term, "none").await;
+
+    let entry = entry.unwrap();
+    assert!(!entry.document_found);
+    assert_eq!(entry.document_id, Some(missing_id));
+    assert_eq!(entry.doc_path.as_deref(), Some("gone/fn.missing.html"));
+    assert_eq!(entry.doc_type, None);
+}
+
+#[tokio::test]
+async fn test_ratings_aggregate_per_crate_and_term() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping feedback test: no database with the search_feedback table");
+        return;
+    };
+    let term = unique_term();
+    let crate_name = format!("fb-{term}");
+    let doc = seed_document(&pool, &crate_name).await;
+
+    let query = format!("how to use {term}");
+    for rating in ["useful", "partially", "not_useful", "not_useful"] {
+        FeedbackQueries::record(pool.pool(), &feedback(&query, rating, &doc))
+            .await
+            .unwrap();
+    }
+    let since = Utc::now() - Duration::minutes(5);
+    let by_crate = FeedbackQueries::counts_by_crate(pool.pool(), since, 10_000).await;
+    let by_term = FeedbackQueries::counts_by_term(pool.pool(), since, 10_000).await;
+    cleanup(&pool, &term, &crate_name).await;
+
+    let by_crate = by_crate.unwrap();
+    let counts = by_crate
+        .iter()
+        .find(|c| c.key == crate_name)
+        .expect("crate counted");
+    assert_eq!(
+        (counts.useful, counts.partially, counts.not_useful),
+        (1, 1, 2)
+    );
+    assert_eq!(counts.total(), 4);
+    assert!((counts.score() - 0.375).abs() < 1e-9);
+
+    // Stop words are not terms; the unique word is counted once per entry
+    let by_term = by_term.unwrap();
+    let counts = by_term
+        .iter()
+        .find(|c| c.key == term)
+        .expect("term counted");
+    assert_eq!(counts.total(), 4);
+    assert!(!by_term.iter().any(|c| c.key == "to" || c.key == "how"));
+}
+
+#[tokio::test]
+async fn test_feedback_tools_record_in_background_and_report_gaps() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping feedback test: no database with the search_feedback table");
+        return;
+    };
+    let term = unique_term();
+    let record = RecordFeedbackTool::new(pool.clone());
+
+    // Invalid calls are rejected before anything is written
+    for arguments in [
+        json!({"query": term, "doc_path": "x", "rating": "great"}),
+        json!({"query": term, "rating": "useful"}),
+        json!({"query": term, "document_id": "not-a-uuid", "rating": "useful"}),
+    ] {
+        assert!(record.execute(arguments).await.is_err());
+    }
+
+    for _ in 0..3 {
+        let response = record
+            .execute(json!({
+                "query": format!("{term} lifetimes"),
+                "doc_path": "unknown/page.html",
+                "rating": "not_useful",
+            }))
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&response).unwrap()["recorded"],
+            true
+        );
+    }
+
+    // Writes are fire-and-forget; wait for them to land
+    let mut stats = Value::Null;
+    for _ in 0..50 {
+        let response = GetFeedbackStatsTool::new(pool.clone())
+            .execute(json!({"days": 1, "limit": 200, "min_ratings": 3}))
+            .await
+            .unwrap();
+        stats = serde_json::from_str(&response).unwrap();
+        let found = stats["gaps"]
+            .as_array()
+            .is_some_and(|gaps| gaps.iter().any(|g| g["key"] == term.as_str()));
+        // Totals are read first, so they may lag the gaps of the same call
+        if found && stats["unmatched_documents"].as_i64() >= Some(3) {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    cleanup(&pool, &term, "none").await;
+
+    let gap = stats["gaps"]
+        .as_array()
+        .and_then(|gaps| gaps.iter().find(|g| g["key"] == term.as_str()))
+        .unwrap_or_else(|| panic!("term reported as a gap: {stats}"));
+    assert_eq!(gap["total"], 3);
+    assert_eq!(gap["not_useful"], 3);
+    assert_eq!(gap["score"], 0.0);
+    assert!(
+        stats["unmatched_documents"].as_i64().unwrap() >= 3,
+        "{stats}"
+    );
+}
@@ -0,0 +1,229 @@
+//! `get_document`: lookup by ID or path, truncation, chunk navigation and
+//! hiding of inactive documents
+//!
+//! Database tests skip when no database is configured.
+
+mod common;
+
+use common::create_test_pool;
+use db::chunks::{annotate_chunk_metadata, chunk_doc_path};
+use db::DatabasePool;
+use mcp::handlers::McpHandler;
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Insert a document row and return its ID
+async fn insert_document(
+    pool: &DatabasePool,
+    doc_type: &str,
+    doc_path: &str,
+    content: &str,
+    metadata: Value,
+) -> Uuid {
+    sqlx::query(
+        "INSERT INTO document_sources (doc_type, source_name, config, enabled)
+         VALUES ($1, 'guides', '{}', true)
+         ON CONFLICT (doc_type, source_name) DO NOTHING",
+    )
+    .bind(doc_type)
+    .execute(pool.pool())
+    .await
+    .expect("seed source");
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO documents (id, doc_type, source_name, doc_path, content, metadata, token_count)
+         VALUES ($1, $2, 'guides', $3, $4, $5, 12)",
+    )
+    .bind(id)
+    .bind(doc_type)
+    .bind(doc_path)
+    .bind(content)
+    .bind(metadata)
+    .execute(pool.pool())
+    .await
+    .expect("seed document");
+    id
+}
+
+async fn cleanup(pool: &DatabasePool, doc_type: &str) {
+    for table in ["documents", "document_sources"] {
+        let _ = sqlx::query(&format!("DELETE FROM {table} WHERE doc_type = $1"))
+            .bind(doc_type)
+            .execute(pool.pool())
+            .await;
+    }
+}
+
+async fn call(handler: &McpHandler, arguments: Value) -> Value {
+    handler
+        .handle_request(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": "get_document", "arguments": arguments }
+        }))
+        .await
+        .expect("tool call should produce a result")
+}
+
+fn result_json(response: &Value) -> Value {
+    assert!(response.get("isError").is_none(), "{response}");
+    serde_json::from_str(response["content"][0]["text"].as_str().unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn test_arguments_are_validated_before_lookup() {
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+        .expect("lazy pool");
+    let handler = McpHandler::new(&DatabasePool::from_pool(pool)).expect("handler should build");
+
+    let bad_id = call(&handler, json!({ "id": "not-a-uuid" })).await;
+    assert_eq!(bad_id["isError"], true);
+    assert!(bad_id["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("Invalid document id"));
+
+    let partial = call(&handler, json!({ "doc_type": "rust", "doc_path": "a.md" })).await;
+    assert_eq!(partial["isError"], true);
+}
+
+#[tokio::test]
+async fn test_get_document_by_id_and_path() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping get_document test: no test database configured");
+        return;
+    };
+    let doc_type = format!("gettest_{}", &Uuid::new_v4().simple().to_string()[..8]);
+    let id = insert_document(
+        &pool,
+        &doc_type,
+        "setup.md",
+        "Überblick: install the widget, then configure it.",
+        json!({ "title": "Setup" }),
+    )
+    .await;
+    let handler = McpHandler::new(&pool).expect("handler should build");
+
+    let by_id = result_json(&call(&handler, json!({ "id": id.to_string() })).await);
+    assert_eq!(by_id["found"], true);
+    assert_eq!(by_id["doc_path"], "setup.md");
+    assert_eq!(by_id["metadata"]["title"], "Setup");
+    assert_eq!(by_id["token_count"], 12);
+    assert_eq!(by_id["truncated"], false);
+    assert!(by_id["created_at"].is_string());
+    assert!(by_id.get("chunks").is_none());
+
+    let by_path = result_json(
+        &call(
+            &handler,
+            json!({
+                "doc_type": doc_type,
+                "source_name": "guides",
+                "doc_path": "setup.md",
+                "max_chars": 9
+            }),
+        )
+        .await,
+    );
+    assert_eq!(by_path["id"], id.to_string());
+    assert_eq!(by_path["content"], "Überblick");
+    assert_eq!(by_path["truncated"], true);
+    assert_eq!(by_path["content_length"], 49);
+
+    // Unknown documents are a result, not an error
+    let missing = result_json(&call(&handler, json!({ "id": Uuid::new_v4().to_string() })).await);
+    assert_eq!(missing["found"], false);
+    assert!(missing["query"]["id"].is_string());
+
+    cleanup(&pool, &doc_type).await;
+}
+
+#[tokio::test]
+async fn test_chunked_document_lists_siblings() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping get_document test: no test database configured");
+        return;
+    };
+    let doc_type = format!("gettest_{}", &Uuid::new_v4().simple().to_string()[..8]);
+    let mut ids = Vec::new();
+    for index in 0..3 {
+        let mut metadata = json!({});
+        annotate_chunk_metadata(&mut metadata, "manual.md", index, 3);
+        ids.push(
+            insert_document(
+                &pool,
+                &doc_type,
+                &chunk_doc_path("manual.md", index),
+                &format!("Manual part {index}"),
+                metadata,
+            )
+            .await,
+        );
+    }
+    let handler = McpHandler::new(&pool).expect("handler should build");
+
+    let middle = result_json(&call(&handler, json!({ "id": ids[1].to_string() })).await);
+    let chunks = &middle["chunks"];
+    assert_eq!(chunks["parent_doc_path"], "manual.md");
+    assert_eq!(chunks["chunk_index"], 1);
+    assert_eq!(chunks["chunk_total"], 3);
+    assert_eq!(chunks["previous"]["id"], ids[0].to_string());
+    assert_eq!(chunks["previous"]["doc_path"], "manual.md");
+    assert_eq!(chunks["next"]["doc_path"], "manual.md#chunk-2");
+    let siblings: Vec<&str> = chunks["siblings"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|chunk| chunk["doc_path"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        siblings,
+        vec!["manual.md", "manual.md#chunk-1", "manual.md#chunk-2"]
+    );
+
+    let last = result_json(&call(&handler, json!({ "id": ids[2].to_string() })).await);
+    assert!(last["chunks"]["next"].is_null());
+
+    cleanup(&pool, &doc_type).await;
+}
+
+#[tokio::test]
+async fn test_inactive_documents_are_hidden_by_id() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping get_document test: no test database configured");
+        return;
+    };
+    let doc_type = format!("gettest_{}", &Uuid::new_v4().simple().to_string()[..8]);
+    // A soft-deleted crate keeps its rows with `status: inactive`
+    let deleted = insert_document(
+        &pool,
+        &doc_type,
+        "removed.md",
+        "Documentation of a soft-deleted crate",
+        json!({ "status": "inactive" }),
+    )
+    .await;
+    let handler = McpHandler::new(&pool).expect("handler should build");
+
+    let by_id = result_json(&call(&handler, json!({ "id": deleted.to_string() })).await);
+    assert_eq!(by_id["found"], false);
+    assert!(by_id.get("content").is_none());
+
+    // Documents of a disabled source are hidden the same way
+    let active = insert_document(&pool, &doc_type, "kept.md", "Still here", json!({})).await;
+    sqlx::query("UPDATE document_sources SET enabled = false WHERE doc_type = $1")
+        .bind(&doc_type)
+        .execute(pool.pool())
+        .await
+        .expect("disable source");
+    let by_id = result_json(&call(&handler, json!({ "id": active.to_string() })).await);
+    assert_eq!(by_id["found"], false);
+
+    cleanup(&pool, &doc_type).await;
+}
@@ -0,0 +1,230 @@
+//! Health endpoints with the database down: the process stays live while
+//! readiness and `/health` report the outage. Failing provider clients show
+//! up as degraded without probing on every request.
+
+use anyhow::{anyhow, Result};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use db::DatabasePool;
+use discovery::PromptRunner;
+use embed::client::EmbeddingClient;
+use embed::models::{
+    BatchResponse, EmbeddingRequest, EmbeddingResponse, FileUploadResponse, JsonlResponseLine,
+};
+use mcp::health::{create_health_router, HealthState};
+use mcp::provider_health::{ProviderChecks, ProviderHealth, ProviderStatus};
+use mcp::McpServer;
+use serde_json::Value;
+use sqlx::postgres::PgPoolOptions;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tower::ServiceExt;
+
+/// Embedding client whose provider is down, counting the attempts
+#[derive(Default)]
+struct FailingEmbeddingClient {
+    calls: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl EmbeddingClient for FailingEmbeddingClient {
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Err(anyhow!("connection refused"))
+    }
+
+    async fn generate_embedding(&self, request: EmbeddingRequest) -> Result<EmbeddingResponse> {
+        Ok(EmbeddingResponse {
+            embedding: self.embed(&request.input).await?,
+        })
+    }
+
+    async fn upload_batch_file(
+        &self,
+        _content: &str,
+        _filename: &str,
+    ) -> Result<FileUploadResponse> {
+        Err(anyhow!("not supported"))
+    }
+
+    async fn create_batch(&self, _input_file_id: &str) -> Result<BatchResponse> {
+        Err(anyhow!("not supported"))
+    }
+
+    async fn get_batch(&self, _batch_id: &str) -> Result<BatchResponse> {
+        Err(anyhow!("not supported"))
+    }
+
+    async fn download_batch_results(&self, _file_id: &str) -> Result<Vec<JsonlResponseLine>> {
+        Err(anyhow!("not supported"))
+    }
+
+    async fn cancel_batch(&self, _batch_id: &str) -> Result<BatchResponse> {
+        Err(anyhow!("not supported"))
+    }
+}
+
+/// Prompt runner that always answers
+struct OkRunner;
+
+#[async_trait::async_trait]
+impl PromptRunner for OkRunner {
+    async fn run(&self, _prompt: &str) -> Result<String> {
+        Ok("ok".to_string())
+    }
+}
+
+/// Pool pointing at a port nothing listens on
+async fn closed_database() -> DatabasePool {
+    let port = {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().port()
+    };
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy(&format!("postgres://nobody@127.0.0.1:{port}/none"))
+        .expect("lazy pool");
+    DatabasePool::from_pool(pool)
+}
+
+/// Router whose pool points at a port nothing listens on
+async fn router_with_closed_database() -> Router {
+    McpServer::new(closed_database().await)
+        .await
+        .expect("server should start without a database")
+        .with_provider_checks(ProviderChecks {
+            embedding: ProviderHealth::unconfigured("no embedding client"),
+            llm: ProviderHealth::unconfigured("no LLM client"),
+        })
+        .create_router()
+}
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, Value) {
+    let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint_serves_prometheus_text() {
+    let app = router_with_closed_database().await;
+    mcp::metrics::metrics().record_api_cost("embedding", "health-test-model", 1, 10, 0, 0.5);
+
+    let request = Request::builder()
+        .uri("/metrics")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let text = String::from_utf8(body.to_vec()).unwrap();
+    assert!(
+        text.contains("# TYPE mcp_tool_calls_total counter"),
+        "{text}"
+    );
+    assert!(
+        text.contains("mcp_api_cost_usd_total{kind=\"embedding\",model=\"health-test-model\"} 0.5"),
+        "{text}"
+    );
+}
+
+#[tokio::test]
+async fn test_failing_database_is_live_but_not_ready() {
+    let app = router_with_closed_database().await;
+
+    let (status, live) = get(&app, "/health/live").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(live["alive"], true);
+
+    let (status, ready) = get(&app, "/health/ready").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(ready["ready"], false);
+    let database = ready["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|check| check["name"] == "database")
+        .expect("database readiness check");
+    assert_eq!(database["ready"], false);
+
+    let (status, health) = get(&app, "/health").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(health["status"], "unhealthy");
+    assert_eq!(health["database"]["connected"], false);
+    assert!(health["database"]["error"].is_string());
+    assert!(health["pool"]["max_connections"].as_u64().unwrap() > 0);
+    assert!(
+        health["pool"]["settings"]["acquire_timeout_ms"]
+            .as_u64()
+            .unwrap()
+            > 0
+    );
+    assert!(health["pool"]["settings"].get("database_url").is_none());
+    // Queries behind the database are skipped rather than timing out
+    assert!(health["stuck_jobs"].is_null());
+    assert_eq!(health["reasons"][0], "database unreachable");
+}
+
+#[tokio::test]
+async fn test_failing_provider_is_degraded_and_cached() {
+    let client = Arc::new(FailingEmbeddingClient::default());
+    let health = ProviderHealth::embedding(client.clone()).with_ttl(Duration::from_secs(60));
+
+    let report = health.check().await;
+    assert_eq!(report.status, ProviderStatus::Degraded);
+    assert_eq!(report.error.as_deref(), Some("connection refused"));
+    // Not required, so readiness does not depend on it
+    assert!(report.ready());
+
+    // Within the TTL the cached result is returned without probing again
+    let again = health.check().await;
+    assert_eq!(again.status, ProviderStatus::Degraded);
+    assert_eq!(again.checked_at, report.checked_at);
+    assert_eq!(client.calls.load(Ordering::SeqCst), 1);
+
+    // An expired result is probed again
+    let expiring = ProviderHealth::embedding(client.clone()).with_ttl(Duration::ZERO);
+    expiring.check().await;
+    expiring.check().await;
+    assert_eq!(client.calls.load(Ordering::SeqCst), 3);
+
+    let unconfigured = ProviderHealth::unconfigured("OPENAI_API_KEY not set").required(true);
+    let report = unconfigured.check().await;
+    assert_eq!(report.status, ProviderStatus::Unconfigured);
+    assert!(!report.ready());
+}
+
+#[tokio::test]
+async fn test_readiness_reports_provider_components() {
+    let client = Arc::new(FailingEmbeddingClient::default());
+    let state = HealthState::new(closed_database().await).with_providers(ProviderChecks {
+        embedding: ProviderHealth::embedding(client.clone()).required(true),
+        llm: ProviderHealth::llm(Arc::new(OkRunner)),
+    });
+    let app = create_health_router().with_state(state);
+
+    for _ in 0..2 {
+        let (status, ready) = get(&app, "/health/ready").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(ready["providers"]["embeddings"]["status"], "degraded");
+        assert_eq!(ready["providers"]["embeddings"]["required"], true);
+        assert_eq!(ready["providers"]["llm"]["status"], "ok");
+        let checks = ready["checks"].as_array().unwrap();
+        let embeddings = checks
+            .iter()
+            .find(|check| check["name"] == "embeddings")
+            .expect("required provider readiness check");
+        assert_eq!(embeddings["ready"], false);
+        assert_eq!(embeddings["message"], "connection refused");
+        // Optional providers are reported but not checked
+        assert!(checks.iter().all(|check| check["name"] != "llm"));
+    }
+    assert_eq!(client.calls.load(Ordering::SeqCst), 1);
+}
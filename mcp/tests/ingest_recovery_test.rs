@@ -0,0 +1,76 @@
+//! Detection and recovery of ingest jobs whose process crashed
+//!
+//! A running job is made stale by moving its `updated_at` back two hours.
+//! Tests skip when no database is configured.
+
+mod common;
+
+use common::create_test_pool;
+use db::models::JobStatus;
+use db::{DatabasePool, IngestJobQueries};
+use mcp::crate_tools::CheckRustStatusTool;
+use mcp::job_queue::{recover_crashed_ingest_jobs, CRASHED_INGEST_JOB_NOTE};
+use mcp::tools::Tool;
+use serde_json::json;
+use uuid::Uuid;
+
+/// Create a running ingest job, last updated `hours` ago
+async fn running_job(pool: &DatabasePool, doc_type: &str, hours: i32) -> Uuid {
+    let job = IngestJobQueries::create_job(pool.pool(), "https://example.com/repo", doc_type)
+        .await
+        .unwrap();
+    IngestJobQueries::update_job_status(pool.pool(), job.id, JobStatus::Running, None, None)
+        .await
+        .unwrap();
+    sqlx::query(
+        "UPDATE ingest_jobs SET updated_at = NOW() - make_interval(hours => $2) WHERE id = $1",
+    )
+    .bind(job.id)
+    .bind(hours)
+    .execute(pool.pool())
+    .await
+    .unwrap();
+    job.id
+}
+
+#[tokio::test]
+async fn test_crashed_ingest_jobs_are_reported_and_failed() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database configured");
+        return;
+    };
+    let doc_type = format!("recovery-{}", Uuid::new_v4().simple());
+    let crashed = running_job(&pool, &doc_type, 2).await;
+    let alive = running_job(&pool, &doc_type, 0).await;
+
+    // check_rust_status points at the stuck ingest job
+    let status = CheckRustStatusTool::new(pool.clone())
+        .execute(json!({}))
+        .await
+        .unwrap();
+    assert!(
+        status.contains("Stuck ingest jobs (no update > 1h)"),
+        "{status}"
+    );
+
+    assert!(recover_crashed_ingest_jobs(&pool).await.unwrap() >= 1);
+    let failed = IngestJobQueries::find_job_by_id(pool.pool(), crashed)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(failed.status, JobStatus::Failed);
+    assert_eq!(failed.error.as_deref(), Some(CRASHED_INGEST_JOB_NOTE));
+    assert!(failed.finished_at.is_some());
+
+    // A job with a recent heartbeat keeps running
+    let running = IngestJobQueries::find_job_by_id(pool.pool(), alive)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(running.status, JobStatus::Running);
+
+    let _ = sqlx::query("DELETE FROM ingest_jobs WHERE doc_type = $1")
+        .bind(&doc_type)
+        .execute(pool.pool())
+        .await;
+}
@@ -0,0 +1,152 @@
+//! `check_ingest_status`: single-job reports, job listing and stuck jobs
+//!
+//! Jobs are created and moved through their states with `IngestJobQueries`,
+//! then rendered through `tools/call`. Tests skip when no database is
+//! configured.
+
+mod common;
+
+use common::create_test_pool;
+use db::models::JobStatus;
+use db::{DatabasePool, IngestJobQueries};
+use mcp::handlers::McpHandler;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+async fn cleanup(pool: &DatabasePool, doc_type: &str) {
+    let _ = sqlx::query("DELETE FROM ingest_jobs WHERE doc_type = $1")
+        .bind(doc_type)
+        .execute(pool.pool())
+        .await;
+}
+
+/// Text of a successful `check_ingest_status` call
+async fn check(handler: &McpHandler, arguments: Value) -> String {
+    let response = handler
+        .handle_request(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": { "name": "check_ingest_status", "arguments": arguments }
+        }))
+        .await
+        .expect("tool call should produce a result");
+    assert!(response.get("isError").is_none(), "{response}");
+    response["content"][0]["text"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_job_report_follows_status_transitions() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database configured");
+        return;
+    };
+    let doc_type = format!("ingest-{}", Uuid::new_v4().simple());
+    let handler = McpHandler::new(&pool).expect("handler should build");
+    let url = "https://github.com/example/widgets";
+
+    let job = IngestJobQueries::create_job(pool.pool(), url, &doc_type)
+        .await
+        .unwrap();
+    let id = json!({ "job_id": job.id.to_string() });
+    let queued = check(&handler, id.clone()).await;
+    assert!(queued.contains(&format!("Ingest Job Status: {}", job.id)));
+    assert!(queued.contains(&format!("Source: {url}")));
+    assert!(queued.contains(&format!("Doc Type: {doc_type}")));
+    assert!(queued.contains("Status: Queued"));
+
+    IngestJobQueries::update_job_status(
+        pool.pool(),
+        job.id,
+        JobStatus::Running,
+        Some("Step 2/3: loader cli"),
+        None,
+    )
+    .await
+    .unwrap();
+    let running = check(&handler, id.clone()).await;
+    assert!(running.contains("Status: Running"));
+    assert!(running.contains("Step 2/3: loader cli"));
+    assert!(!running.contains("Finished:"));
+    assert!(!running.contains("Stuck"));
+
+    IngestJobQueries::update_job_status(
+        pool.pool(),
+        job.id,
+        JobStatus::Completed,
+        Some("Documents: 12 stored for doc_type (+12 from this job)"),
+        None,
+    )
+    .await
+    .unwrap();
+    let completed = check(&handler, id).await;
+    assert!(completed.contains("Status: Completed"));
+    assert!(completed.contains("Finished:"));
+    assert!(completed.contains("Documents: 12 stored"));
+
+    let failed = IngestJobQueries::create_job(pool.pool(), url, &doc_type)
+        .await
+        .unwrap();
+    IngestJobQueries::update_job_status(
+        pool.pool(),
+        failed.id,
+        JobStatus::Failed,
+        None,
+        Some("git clone exited with status 128"),
+    )
+    .await
+    .unwrap();
+    let report = check(&handler, json!({ "job_id": failed.id.to_string() })).await;
+    assert!(report.contains("Status: Failed"));
+    assert!(report.contains("Error: git clone exited with status 128"));
+
+    let missing = check(&handler, json!({ "job_id": Uuid::new_v4().to_string() })).await;
+    assert!(missing.contains("not found"));
+
+    cleanup(&pool, &doc_type).await;
+}
+
+#[tokio::test]
+async fn test_listing_flags_stuck_jobs() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database configured");
+        return;
+    };
+    let doc_type = format!("ingest-{}", Uuid::new_v4().simple());
+    let handler = McpHandler::new(&pool).expect("handler should build");
+
+    let stuck = IngestJobQueries::create_job(pool.pool(), "https://example.com/stuck", &doc_type)
+        .await
+        .unwrap();
+    IngestJobQueries::update_job_status(pool.pool(), stuck.id, JobStatus::Running, None, None)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE ingest_jobs SET updated_at = NOW() - INTERVAL '2 hours' WHERE id = $1")
+        .bind(stuck.id)
+        .execute(pool.pool())
+        .await
+        .unwrap();
+    let done = IngestJobQueries::create_job(pool.pool(), "https://example.com/done", &doc_type)
+        .await
+        .unwrap();
+    IngestJobQueries::update_job_status(pool.pool(), done.id, JobStatus::Completed, None, None)
+        .await
+        .unwrap();
+
+    let listing = check(&handler, json!({ "limit": 50 })).await;
+    let stuck_line = listing
+        .lines()
+        .find(|line| line.contains(&stuck.id.to_string()))
+        .expect("active job listed");
+    assert!(stuck_line.contains("Running"));
+    assert!(stuck_line.contains("stuck"));
+    assert!(listing.contains("Stuck ingest jobs (no update > 60m)"));
+    // The newest finished job leads the first page
+    let recent = listing.split("Recent Jobs").nth(1).expect("recent section");
+    assert!(recent.contains(&done.id.to_string()));
+
+    let report = check(&handler, json!({ "job_id": stuck.id.to_string() })).await;
+    assert!(report.contains("Stuck: no update for 120 minutes"));
+
+    cleanup(&pool, &doc_type).await;
+}
@@ -0,0 +1,224 @@
+//! `MIGRATE_ON_START` against a fresh database
+//!
+//! Each test creates its own database next to `TEST_DATABASE_URL`, starts
+//! the `http_server` binary on it and drops the database afterwards. Tests
+//! skip when no database is configured.
+
+use db::{DatabaseMigrationManager, DatabasePool};
+use serde_json::Value;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+use std::env;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use url::Url;
+
+/// How long a server may take to migrate and start listening
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// A throwaway database, dropped with [`FreshDatabase::drop_database`]
+struct FreshDatabase {
+    admin: PgPool,
+    name: String,
+    url: String,
+}
+
+impl FreshDatabase {
+    /// Create an empty database, or `None` when tests should be skipped
+    async fn create() -> Option<Self> {
+        let admin_url = env::var("TEST_DATABASE_URL").ok()?;
+        if admin_url.trim().is_empty() || admin_url.trim().eq_ignore_ascii_case("mock") {
+            return None;
+        }
+        let admin = PgPoolOptions::new()
+            .max_connections(2)
+            .connect(&admin_url)
+            .await
+            .ok()?;
+
+        let name = format!("migrate_on_start_{}", uuid::Uuid::new_v4().simple());
+        sqlx::query(&format!("CREATE DATABASE {name}"))
+            .execute(&admin)
+            .await
+            .expect("create test database");
+        let mut url = Url::parse(&admin_url).expect("TEST_DATABASE_URL is a URL");
+        url.set_path(&name);
+        Some(Self {
+            admin,
+            name,
+            url: url.to_string(),
+        })
+    }
+
+    async fn drop_database(self) {
+        let _ = sqlx::query(&format!(
+            "DROP DATABASE IF EXISTS {} WITH (FORCE)",
+            self.name
+        ))
+        .execute(&self.admin)
+        .await;
+    }
+}
+
+/// `http_server` child process, killed on drop
+struct Server {
+    child: Child,
+    port: u16,
+}
+
+impl Server {
+    async fn start(database_url: &str, migrate_on_start: &str) -> Self {
+        let port = {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let child = Command::new(env!("CARGO_BIN_EXE_http_server"))
+            .env("DATABASE_URL", database_url)
+            .env("MIGRATE_ON_START", migrate_on_start)
+            .env("MCP_HOST", "127.0.0.1")
+            .env("PORT", port.to_string())
+            .env("RUST_LOG", "warn")
+            .env_remove("MCP_PORT")
+            .env_remove("USE_REDIS_QUEUE")
+            .stdout(Stdio::null())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .expect("start http_server");
+        Self { child, port }
+    }
+
+    /// Status code and JSON body of `GET path`, `None` while nothing listens
+    async fn get(&self, path: &str) -> Option<(u16, Value)> {
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port)).await.ok()?;
+        let request =
+            format!("GET {path} HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n");
+        stream.write_all(request.as_bytes()).await.ok()?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.ok()?;
+        let status = response.split_whitespace().nth(1)?.parse().ok()?;
+        let body = response.split_once("\r\n\r\n").map_or("", |(_, body)| body);
+        Some((status, serde_json::from_str(body).unwrap_or(Value::Null)))
+    }
+
+    /// Wait until `/health/live` answers, failing if the process exits
+    async fn wait_until_listening(&mut self) {
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        while self.get("/health/live").await.is_none() {
+            if let Some(status) = self.child.try_wait().unwrap() {
+                panic!("http_server exited during startup: {status}");
+            }
+            assert!(Instant::now() < deadline, "http_server did not start");
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[tokio::test]
+async fn test_migrate_on_start_leaves_a_clean_schema() {
+    let Some(database) = FreshDatabase::create().await else {
+        eprintln!("Skipping: TEST_DATABASE_URL not set");
+        return;
+    };
+
+    // Two replicas racing on one database; the advisory lock serializes them
+    let mut first = Server::start(&database.url, "true").await;
+    let mut second = Server::start(&database.url, "true").await;
+    first.wait_until_listening().await;
+    second.wait_until_listening().await;
+
+    for server in [&first, &second] {
+        let (status, ready) = server.get("/health/ready").await.expect("readiness");
+        assert_eq!(status, 200, "{ready}");
+        assert_eq!(ready["pending_migrations"], Value::Array(Vec::new()));
+        assert!(ready["schema_version"].is_string(), "{ready}");
+    }
+    drop((first, second));
+
+    let pool = DatabasePool::new(&database.url).await.expect("connect");
+    let manager = DatabaseMigrationManager::new(pool.pool().clone())
+        .await
+        .expect("migration manager");
+    let report = manager.validate_schema().await.expect("validate schema");
+    let vector_available: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM pg_available_extensions WHERE name = 'vector')",
+    )
+    .fetch_one(pool.pool())
+    .await
+    .unwrap();
+    if vector_available {
+        assert!(report.is_valid, "{:?}", report.issues);
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+    } else {
+        // Migration 001 skips pgvector where the server lacks it
+        assert!(
+            report.issues.iter().all(|issue| issue.contains("vector")),
+            "{:?}",
+            report.issues
+        );
+    }
+
+    // Each migration ran once
+    let duplicates: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM (SELECT migration_id FROM migration_history \
+         WHERE status = 'completed' GROUP BY migration_id HAVING COUNT(*) > 1) d",
+    )
+    .fetch_one(pool.pool())
+    .await
+    .unwrap();
+    assert_eq!(duplicates, 0);
+
+    pool.pool().close().await;
+    database.drop_database().await;
+}
+
+#[tokio::test]
+async fn test_check_mode_is_not_ready_while_migrations_are_pending() {
+    let Some(database) = FreshDatabase::create().await else {
+        eprintln!("Skipping: TEST_DATABASE_URL not set");
+        return;
+    };
+
+    let mut server = Server::start(&database.url, "check").await;
+    server.wait_until_listening().await;
+
+    let (status, ready) = server.get("/health/ready").await.expect("readiness");
+    assert_eq!(status, 503, "{ready}");
+    let pending = ready["pending_migrations"]
+        .as_array()
+        .expect("pending list");
+    assert!(
+        pending.iter().any(|id| id == "001_core_extensions"),
+        "{ready}"
+    );
+    let migrations = ready["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|check| check["name"] == "migrations")
+        .expect("migrations readiness check");
+    assert_eq!(migrations["ready"], false);
+    assert!(migrations["message"]
+        .as_str()
+        .unwrap()
+        .contains("001_core_extensions"));
+
+    // Nothing was applied
+    let applied: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM migration_history WHERE status = 'completed'")
+            .fetch_one(&PgPool::connect(&database.url).await.unwrap())
+            .await
+            .unwrap();
+    assert_eq!(applied, 0);
+
+    drop(server);
+    database.drop_database().await;
+}
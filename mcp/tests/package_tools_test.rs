@@ -0,0 +1,581 @@
+//! Package management tools of every ecosystem: add, ingest, list and remove
+//!
+//! Each test body runs once per ecosystem through [`MockedRegistry`]; only the
+//! canned registry responses are ecosystem-specific. Registries are mocked;
+//! tests skip when no database is configured.
+
+mod common;
+
+use anyhow::{anyhow, Result};
+use db::models::JobStatus;
+use db::queries::CrateJobQueries;
+use db::DatabasePool;
+use embed::OpenAIEmbeddingClient;
+use mcp::job_queue::{CrateJobProcessor, PackageJobOptions, RemoveJobOptions};
+use mcp::package_tools::{
+    AddPackageTool, ListPackagesTool, PackageEcosystem, PackageRegistry, RemovePackageTool,
+};
+use mcp::tools::{Tool, ToolError};
+use npm_packages::NpmLoader;
+use python_packages::PyPiLoader;
+use rust_crates::PageFetcher;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Registry stand-in serving canned responses; other URLs fail with a 404
+struct MockFetcher {
+    pages: HashMap<String, String>,
+}
+
+#[async_trait::async_trait]
+impl PageFetcher for MockFetcher {
+    async fn fetch_text(&self, url: &str) -> Result<String> {
+        self.pages
+            .get(url)
+            .cloned()
+            .ok_or_else(|| anyhow!("HTTP status: 404 Not Found"))
+    }
+}
+
+/// A loader the tests can point at canned registry responses
+trait MockedRegistry: PackageRegistry {
+    /// A name the registry rejects outright
+    const INVALID_NAME: &'static str;
+
+    /// Registry serving `package` at version 1.0.0, with a README and two
+    /// more pages
+    fn mocked(package: &str) -> Self;
+
+    /// Check the documents ingested from [`MockedRegistry::mocked`], ordered
+    /// by module path
+    fn check_ingested(rows: &[(String, Value)]);
+
+    /// How a caller might spell `package`; the stored name is the same after
+    /// normalization
+    fn spelling(package: &str) -> String {
+        package.to_string()
+    }
+}
+
+impl MockedRegistry for NpmLoader {
+    const INVALID_NAME: &'static str = "Not-Valid";
+
+    /// Registry with a README and typings
+    fn mocked(package: &str) -> Self {
+        let document = json!({
+            "name": package,
+            "dist-tags": {"latest": "1.0.0"},
+            "readme": "# Quokka\n\nHops around.",
+            "versions": {"1.0.0": {"description": "Quokka helpers", "types": "index.d.ts"}}
+        });
+        let typings = "/** Make a quokka hop */\nexport declare function hop(height: number): void;\nexport interface Quokka { name: string; }\n";
+        let pages = HashMap::from([
+            (
+                format!("https://registry.npmjs.org/{package}"),
+                document.to_string(),
+            ),
+            (
+                format!("https://unpkg.com/{package}@1.0.0/index.d.ts"),
+                typings.to_string(),
+            ),
+        ]);
+        Self::with_fetcher(Box::new(MockFetcher { pages }))
+    }
+
+    /// README plus two declarations
+    fn check_ingested(rows: &[(String, Value)]) {
+        let item_types: Vec<&str> = rows
+            .iter()
+            .map(|(_, metadata)| metadata["item_type"].as_str().unwrap())
+            .collect();
+        assert_eq!(item_types, vec!["interface", "readme", "function"]);
+        assert!(rows[2].0.contains("Make a quokka hop"));
+    }
+}
+
+impl MockedRegistry for PyPiLoader {
+    const INVALID_NAME: &'static str = "-quokka";
+
+    /// PyPI with a README and a two-page readthedocs site without a sitemap
+    fn mocked(package: &str) -> Self {
+        let docs = format!("https://{package}.readthedocs.io/en/latest/");
+        let document = json!({
+            "info": {
+                "name": package,
+                "version": "1.0.0",
+                "summary": "Quokka helpers",
+                "description": "# Quokka\n\nHops around.",
+                "description_content_type": "text/markdown",
+                "project_urls": {"Documentation": docs},
+                "requires_python": ">=3.9"
+            },
+            "releases": {"1.0.0": []}
+        });
+        let page = |body: &str| {
+            format!(
+                "<html><body><div class=\"document\"><div role=\"main\">{body}</div></div></body></html>"
+            )
+        };
+        let pages = HashMap::from([
+            (
+                format!("https://pypi.org/pypi/{package}/json"),
+                document.to_string(),
+            ),
+            (
+                docs.clone(),
+                page("<h1>Quokka</h1><p>Start here.</p><a href=\"api.html\">API</a>"),
+            ),
+            (
+                format!("{docs}api.html"),
+                page("<h1>API</h1><p>hop(height) makes a quokka hop.</p>"),
+            ),
+        ]);
+        Self::with_fetcher(Box::new(MockFetcher { pages }))
+    }
+
+    /// README plus the two readthedocs pages
+    fn check_ingested(rows: &[(String, Value)]) {
+        for (_, metadata) in rows {
+            assert_eq!(metadata["requires_python"], ">=3.9");
+        }
+        let formats: Vec<(&str, &str)> = rows
+            .iter()
+            .map(|(_, metadata)| {
+                (
+                    metadata["item_type"].as_str().unwrap(),
+                    metadata["format"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            formats,
+            vec![
+                ("readme", "markdown"),
+                ("web_page", "text"),
+                ("web_page", "text")
+            ]
+        );
+    }
+
+    /// `test-python-1a2b` as `TEST_PYTHON_1A2B`
+    fn spelling(package: &str) -> String {
+        package.to_uppercase().replace('-', "_")
+    }
+}
+
+/// Test database pool, or `None` when tests should be skipped
+///
+/// Also skips databases whose job table predates `ecosystem`'s operations.
+async fn create_test_pool(ecosystem: &PackageEcosystem) -> Option<DatabasePool> {
+    let pool = common::create_test_pool().await?;
+    let supported: Option<bool> = sqlx::query_scalar(
+        "SELECT pg_get_constraintdef(oid) LIKE '%' || $1 || '%'
+         FROM pg_constraint WHERE conname = 'crate_jobs_operation_check'",
+    )
+    .bind(ecosystem.add_operation)
+    .fetch_optional(pool.pool())
+    .await
+    .ok()?;
+    if supported == Some(false) {
+        println!(
+            "Skipping test: crate_jobs does not accept {} package operations",
+            ecosystem.label
+        );
+        return None;
+    }
+    Some(pool)
+}
+
+/// Store `count` documents of `version` of a package, bypassing ingestion
+async fn seed_package(
+    pool: &DatabasePool,
+    ecosystem: &PackageEcosystem,
+    package: &str,
+    version: &str,
+    count: usize,
+) {
+    sqlx::query(
+        "INSERT INTO document_sources (doc_type, source_name, config, enabled)
+         VALUES ($1, $2, '{}', true) ON CONFLICT (doc_type, source_name) DO NOTHING",
+    )
+    .bind(ecosystem.doc_type)
+    .bind(package)
+    .execute(pool.pool())
+    .await
+    .expect("seed source");
+
+    for i in 0..count {
+        sqlx::query(
+            "INSERT INTO documents (id, doc_type, source_name, doc_path, content, metadata, token_count)
+             VALUES ($1, $2, $3, $4, $5, $6, 10)",
+        )
+        .bind(Uuid::new_v4())
+        .bind(ecosystem.doc_type)
+        .bind(package)
+        .bind(format!("{package}@{version}/page{i}"))
+        .bind(format!("Page {i} of {package}"))
+        .bind(json!({
+            "package_name": package,
+            "package_version": version,
+            "package_description": "Quokka helpers",
+            "item_type": "web_page",
+        }))
+        .execute(pool.pool())
+        .await
+        .expect("seed document");
+    }
+}
+
+async fn cleanup(pool: &DatabasePool, ecosystem: &PackageEcosystem, package: &str) {
+    for sql in [
+        "DELETE FROM documents WHERE doc_type = $2 AND source_name = $1",
+        "DELETE FROM document_sources WHERE doc_type = $2 AND source_name = $1",
+    ] {
+        let _ = sqlx::query(sql)
+            .bind(package)
+            .bind(ecosystem.doc_type)
+            .execute(pool.pool())
+            .await;
+    }
+    let _ = sqlx::query("DELETE FROM crate_jobs WHERE crate_name = $1")
+        .bind(package)
+        .execute(pool.pool())
+        .await;
+}
+
+async fn document_count(pool: &DatabasePool, ecosystem: &PackageEcosystem, package: &str) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM documents WHERE doc_type = $1 AND source_name = $2")
+        .bind(ecosystem.doc_type)
+        .bind(package)
+        .fetch_one(pool.pool())
+        .await
+        .expect("count documents")
+}
+
+/// A normalized package name unique to the test
+fn test_package(ecosystem: &PackageEcosystem) -> String {
+    format!("test-{}-{}", ecosystem.doc_type, Uuid::new_v4())
+}
+
+fn add_tool<R: MockedRegistry>(pool: &DatabasePool, package: &str) -> Result<AddPackageTool<R>> {
+    Ok(
+        AddPackageTool::<R>::new(pool.clone(), Arc::new(OpenAIEmbeddingClient::new()?))
+            .with_registry(R::mocked(package), Duration::from_secs(5)),
+    )
+}
+
+async fn add_package_checks_registry<R: MockedRegistry>() -> Result<()> {
+    let ecosystem = R::ECOSYSTEM;
+    let Some(pool) = create_test_pool(ecosystem).await else {
+        println!("Skipping test: no database available");
+        return Ok(());
+    };
+    let package = test_package(ecosystem);
+    let tool = add_tool::<R>(&pool, &package)?;
+
+    for (arguments, message) in [
+        (
+            json!({"name": R::INVALID_NAME}),
+            format!("not a valid {} package name", ecosystem.label),
+        ),
+        (
+            json!({"name": "missing-quokka"}),
+            format!("not found on {}", ecosystem.registry),
+        ),
+        (
+            json!({"name": package, "version": "9.9.9"}),
+            "Invalid version '9.9.9'".to_string(),
+        ),
+    ] {
+        let error = tool.execute(arguments).await.expect_err("rejected");
+        let error = error.downcast_ref::<ToolError>().expect("ToolError");
+        assert_eq!(error.code(), "invalid_input");
+        assert!(error.to_string().contains(&message), "{error}");
+    }
+
+    // The job is queued under the normalized name
+    let arguments = json!({"name": R::spelling(&package), "idempotency_key": package});
+    let accepted: Value = serde_json::from_str(&tool.execute(arguments.clone()).await?)?;
+    assert_eq!(accepted["status"], "accepted");
+    assert_eq!(accepted["verified"], true);
+    assert_eq!(accepted["version"], "1.0.0");
+    assert_eq!(accepted["package_name"], package.as_str());
+    let job_id = Uuid::parse_str(accepted["job_id"].as_str().unwrap())?;
+    let job = CrateJobQueries::find_job_by_id(pool.pool(), job_id)
+        .await?
+        .expect("job exists");
+    assert_eq!(job.operation, ecosystem.add_operation);
+    assert_eq!(job.crate_name, package);
+    assert_eq!(
+        PackageJobOptions::from_job(&job).version.as_deref(),
+        Some("1.0.0")
+    );
+
+    // A retry with the same key gets the same job
+    let replayed: Value = serde_json::from_str(&tool.execute(arguments).await?)?;
+    assert_eq!(replayed["job_id"], accepted["job_id"]);
+
+    // A stored package needs force_update, whatever the spelling
+    seed_package(&pool, ecosystem, &package, "1.0.0", 1).await;
+    let error = tool
+        .execute(json!({"name": R::spelling(&package)}))
+        .await
+        .expect_err("already stored");
+    let error = error.downcast_ref::<ToolError>().expect("ToolError");
+    assert_eq!(error.code(), "already_exists");
+    assert_eq!(error.details()["current_version"], "1.0.0");
+
+    cleanup(&pool, ecosystem, &package).await;
+    Ok(())
+}
+
+async fn package_ingestion<R: MockedRegistry>() -> Result<()> {
+    let ecosystem = R::ECOSYSTEM;
+    let Some(pool) = create_test_pool(ecosystem).await else {
+        println!("Skipping test: no database available");
+        return Ok(());
+    };
+    // Storing documents needs pgvector
+    if sqlx::query("SELECT '[1]'::vector(1)")
+        .execute(pool.pool())
+        .await
+        .is_err()
+    {
+        println!("Skipping test: pgvector is not installed");
+        return Ok(());
+    }
+    let package = test_package(ecosystem);
+
+    let processor = CrateJobProcessor::new(pool.clone());
+    let options = PackageJobOptions {
+        version: Some("1.0.0".to_string()),
+        ..PackageJobOptions::default()
+    };
+    let job_id = processor
+        .enqueue_add_package_job(ecosystem, &package, &options, None)
+        .await?
+        .job
+        .id;
+    let client: Arc<dyn embed::client::EmbeddingClient + Send + Sync> =
+        Arc::new(OpenAIEmbeddingClient::new()?);
+    AddPackageTool::<R>::process_ingestion(
+        &processor,
+        &R::mocked(&package),
+        &client,
+        &pool,
+        job_id,
+        &package,
+        &options,
+    )
+    .await?;
+
+    let job = CrateJobQueries::find_job_by_id(pool.pool(), job_id)
+        .await?
+        .expect("job exists");
+    assert_eq!(job.status, JobStatus::Completed);
+
+    let rows: Vec<(String, Value)> = sqlx::query_as(
+        "SELECT content, metadata FROM documents WHERE doc_type = $1 AND source_name = $2
+         ORDER BY metadata->>'module_path'",
+    )
+    .bind(ecosystem.doc_type)
+    .bind(&package)
+    .fetch_all(pool.pool())
+    .await?;
+    assert_eq!(rows.len(), 3);
+    for (_, metadata) in &rows {
+        assert_eq!(metadata["package_name"], package.as_str());
+        assert_eq!(metadata["package_version"], "1.0.0");
+    }
+    R::check_ingested(&rows);
+
+    cleanup(&pool, ecosystem, &package).await;
+    Ok(())
+}
+
+async fn list_packages<R: MockedRegistry>() -> Result<()> {
+    let ecosystem = R::ECOSYSTEM;
+    let Some(pool) = create_test_pool(ecosystem).await else {
+        println!("Skipping test: no database available");
+        return Ok(());
+    };
+    let package = test_package(ecosystem);
+    seed_package(&pool, ecosystem, &package, "1.0.0", 2).await;
+    seed_package(&pool, ecosystem, &package, "2.0.0", 3).await;
+
+    let tool = ListPackagesTool::<R>::new(pool.clone());
+    let output = tool.execute(json!({"name_pattern": package})).await?;
+    assert!(
+        output.starts_with(&format!(
+            "{} Packages (Page 1 of 1, 2 total items)",
+            ecosystem.label
+        )),
+        "{output}"
+    );
+    assert!(
+        output.contains(&format!("📦 **{package}** (v1.0.0)")),
+        "{output}"
+    );
+    assert!(output.contains("Docs: 3 |"), "{output}");
+    assert!(output.contains("Description: Quokka helpers"), "{output}");
+
+    cleanup(&pool, ecosystem, &package).await;
+    Ok(())
+}
+
+async fn remove_package<R: MockedRegistry>() -> Result<()> {
+    let ecosystem = R::ECOSYSTEM;
+    let Some(pool) = create_test_pool(ecosystem).await else {
+        println!("Skipping test: no database available");
+        return Ok(());
+    };
+    let package = test_package(ecosystem);
+    seed_package(&pool, ecosystem, &package, "1.0.0", 2).await;
+    seed_package(&pool, ecosystem, &package, "2.0.0", 3).await;
+    let tool = RemovePackageTool::<R>::new(pool.clone());
+
+    let error = tool
+        .execute(json!({"name": package, "version": "3.0.0"}))
+        .await
+        .expect_err("no such version");
+    let error = error.downcast_ref::<ToolError>().expect("ToolError");
+    assert_eq!(error.code(), "not_found");
+    assert!(
+        error
+            .to_string()
+            .starts_with(&format!("{} package", ecosystem.label)),
+        "{error}"
+    );
+    assert_eq!(
+        error.details()["stored_versions"].as_array().unwrap().len(),
+        2
+    );
+
+    let dry_run = tool
+        .execute(json!({"name": package, "version": "1.0.0", "dry_run": true}))
+        .await?;
+    assert!(
+        dry_run.contains("2 documents would be affected"),
+        "{dry_run}"
+    );
+    assert_eq!(document_count(&pool, ecosystem, &package).await, 5);
+
+    // One version goes, the other stays; the name matches after normalization
+    let removed = tool
+        .execute(json!({"name": R::spelling(&package), "version": "1.0.0"}))
+        .await?;
+    assert!(removed.contains("Deleted 2 documents"), "{removed}");
+    assert!(removed.contains("PASSED"), "{removed}");
+    assert_eq!(document_count(&pool, ecosystem, &package).await, 3);
+
+    cleanup(&pool, ecosystem, &package).await;
+    let error = tool
+        .execute(json!({"name": package}))
+        .await
+        .expect_err("package is gone");
+    assert_eq!(
+        error.downcast_ref::<ToolError>().expect("ToolError").code(),
+        "not_found"
+    );
+    Ok(())
+}
+
+async fn remove_package_background_job<R: MockedRegistry>() -> Result<()> {
+    let ecosystem = R::ECOSYSTEM;
+    let Some(pool) = create_test_pool(ecosystem).await else {
+        println!("Skipping test: no database available");
+        return Ok(());
+    };
+    let package = test_package(ecosystem);
+    seed_package(&pool, ecosystem, &package, "1.0.0", 5).await;
+
+    // Anything above two documents is removed by a job
+    let tool = RemovePackageTool::<R>::new(pool.clone()).with_async_threshold(2);
+    let result: Value = serde_json::from_str(&tool.execute(json!({"name": package})).await?)?;
+    assert_eq!(result["status"], "accepted");
+    assert_eq!(result["documents"], 5);
+    let job_id = Uuid::parse_str(result["job_id"].as_str().unwrap())?;
+    let job = CrateJobQueries::find_job_by_id(pool.pool(), job_id)
+        .await?
+        .expect("removal job");
+    assert_eq!(job.operation, ecosystem.remove_operation);
+
+    // Run the job here instead of waiting for a dispatcher
+    let processor = CrateJobProcessor::new(pool.clone());
+    RemovePackageTool::<R>::process_removal(
+        &processor,
+        &pool,
+        job_id,
+        &package,
+        &RemoveJobOptions::default(),
+    )
+    .await?;
+
+    assert_eq!(document_count(&pool, ecosystem, &package).await, 0);
+    let job = CrateJobQueries::find_job_by_id(pool.pool(), job_id)
+        .await?
+        .expect("removal job");
+    assert_eq!(job.status, JobStatus::Completed);
+    let details = job.details.expect("job details");
+    assert_eq!(details["documents_deleted"], 5);
+    assert!(details["cleanup_verification"]
+        .as_str()
+        .unwrap()
+        .contains("PASSED"));
+
+    cleanup(&pool, ecosystem, &package).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_add_npm_package_checks_registry() -> Result<()> {
+    add_package_checks_registry::<NpmLoader>().await
+}
+
+#[tokio::test]
+async fn test_add_python_package_checks_registry() -> Result<()> {
+    add_package_checks_registry::<PyPiLoader>().await
+}
+
+#[tokio::test]
+async fn test_npm_package_ingestion() -> Result<()> {
+    package_ingestion::<NpmLoader>().await
+}
+
+#[tokio::test]
+async fn test_python_package_ingestion() -> Result<()> {
+    package_ingestion::<PyPiLoader>().await
+}
+
+#[tokio::test]
+async fn test_list_npm_packages() -> Result<()> {
+    list_packages::<NpmLoader>().await
+}
+
+#[tokio::test]
+async fn test_list_python_packages() -> Result<()> {
+    list_packages::<PyPiLoader>().await
+}
+
+#[tokio::test]
+async fn test_remove_npm_package() -> Result<()> {
+    remove_package::<NpmLoader>().await
+}
+
+#[tokio::test]
+async fn test_remove_python_package() -> Result<()> {
+    remove_package::<PyPiLoader>().await
+}
+
+#[tokio::test]
+async fn test_remove_npm_package_background_job() -> Result<()> {
+    remove_package_background_job::<NpmLoader>().await
+}
+
+#[tokio::test]
+async fn test_remove_python_package_background_job() -> Result<()> {
+    remove_package_background_job::<PyPiLoader>().await
+}
@@ -0,0 +1,261 @@
+//! Query result caching and its invalidation when documents change
+//!
+//! Each test seeds documents under a unique doc type or crate, so cached
+//! results of other tests never match. Tests skip when no database is
+//! configured.
+
+mod common;
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use common::create_test_pool;
+use db::models::Document;
+use db::{CrateStorage, DocumentQueries, IngestJobQueries};
+use discovery::RepositoryAnalysis;
+use embed::client::EmbeddingClient;
+use embed::models::{
+    BatchResponse, EmbeddingRequest, EmbeddingResponse, FileUploadResponse, JsonlResponseLine,
+};
+use mcp::config::ConfigLoader;
+use mcp::crate_tools::RemoveRustCrateTool;
+use mcp::ingest::run_plan_for_job;
+use mcp::metrics::metrics;
+use mcp::tools::{DynamicQueryTool, RustQueryTool, Tool};
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Embedding client returning a fixed vector and counting requests
+#[derive(Default)]
+struct CountingEmbeddingClient {
+    calls: AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl EmbeddingClient for CountingEmbeddingClient {
+    async fn embed(&self, _text: &str) -> Result<Vec<f32>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(vec![0.1; 3072])
+    }
+
+    async fn generate_embedding(&self, _request: EmbeddingRequest) -> Result<EmbeddingResponse> {
+        Ok(EmbeddingResponse {
+            embedding: vec![0.1; 3072],
+        })
+    }
+
+    async fn upload_batch_file(
+        &self,
+        _content: &str,
+        _filename: &str,
+    ) -> Result<FileUploadResponse> {
+        Err(anyhow!("batch API not supported in tests"))
+    }
+
+    async fn create_batch(&self, _input_file_id: &str) -> Result<BatchResponse> {
+        Err(anyhow!("batch API not supported in tests"))
+    }
+
+    async fn get_batch(&self, _batch_id: &str) -> Result<BatchResponse> {
+        Err(anyhow!("batch API not supported in tests"))
+    }
+
+    async fn download_batch_results(&self, _file_id: &str) -> Result<Vec<JsonlResponseLine>> {
+        Err(anyhow!("batch API not supported in tests"))
+    }
+
+    async fn cancel_batch(&self, _batch_id: &str) -> Result<BatchResponse> {
+        Err(anyhow!("batch API not supported in tests"))
+    }
+}
+
+fn document(doc_type: &str, source_name: &str, path: &str, content: &str) -> Document {
+    Document {
+        id: Uuid::new_v4(),
+        doc_type: doc_type.to_string(),
+        source_name: source_name.to_string(),
+        doc_path: path.to_string(),
+        content: content.to_string(),
+        metadata: json!({"crate_name": source_name, "item_type": "function"}),
+        embedding: None,
+        token_count: None,
+        created_at: Some(Utc::now()),
+        updated_at: Some(Utc::now()),
+    }
+}
+
+#[tokio::test]
+async fn test_repeated_query_is_cached_until_ingestion() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database configured");
+        return;
+    };
+    let doc_type = format!("qcache{}", &Uuid::new_v4().simple().to_string()[..8]);
+    let config = ConfigLoader::parse(
+        &json!({"tools": [{
+            "name": format!("{doc_type}_query"),
+            "docType": doc_type,
+            "title": "Cache Test",
+            "description": "Cache test documents.",
+            "enabled": true
+        }]})
+        .to_string(),
+        "tools.json",
+        false,
+    )
+    .expect("valid config");
+    let embeddings = Arc::new(CountingEmbeddingClient::default());
+    let tool = DynamicQueryTool::with_embedding_client(
+        config.tools[0].clone(),
+        pool.clone(),
+        embeddings.clone(),
+    );
+    DocumentQueries::batch_insert_documents(
+        pool.pool(),
+        &[document(
+            &doc_type,
+            "widgets",
+            "guide/setup.md",
+            "Widget setup: install the widget runtime first.",
+        )],
+    )
+    .await
+    .unwrap();
+
+    let hits_before = metrics().snapshot().query_cache_hits;
+    let first = tool
+        .execute(json!({"query": "widget setup"}))
+        .await
+        .unwrap();
+    assert!(first.contains("guide/setup.md"), "{first}");
+    assert_eq!(embeddings.calls.load(Ordering::SeqCst), 1);
+
+    // Same query up to case and spacing: served from the cache
+    let second = tool
+        .execute(json!({"query": "  Widget   SETUP "}))
+        .await
+        .unwrap();
+    assert_eq!(second, first);
+    assert_eq!(embeddings.calls.load(Ordering::SeqCst), 1);
+    assert!(metrics().snapshot().query_cache_hits > hits_before);
+
+    // A document written behind the cache's back stays invisible...
+    DocumentQueries::batch_insert_documents(
+        pool.pool(),
+        &[document(
+            &doc_type,
+            "widgets",
+            "guide/upgrade.md",
+            "Widget setup for upgrades: remove the old widget runtime.",
+        )],
+    )
+    .await
+    .unwrap();
+    let cached = tool
+        .execute(json!({"query": "widget setup"}))
+        .await
+        .unwrap();
+    assert!(!cached.contains("guide/upgrade.md"));
+
+    // ...unless the call bypasses the cache
+    let fresh = tool
+        .execute(json!({"query": "widget setup", "cache": false}))
+        .await
+        .unwrap();
+    assert!(fresh.contains("guide/upgrade.md"), "{fresh}");
+    assert_eq!(embeddings.calls.load(Ordering::SeqCst), 2);
+
+    // An ingest job for the doc type drops its cached results
+    let job = IngestJobQueries::create_job(pool.pool(), "https://example.com/widgets", &doc_type)
+        .await
+        .unwrap();
+    let analysis: RepositoryAnalysis = serde_json::from_value(json!({
+        "repo_info": {
+            "url": "https://example.com/widgets",
+            "name": "widgets",
+            "primary_language": null,
+            "documentation_type": "Software",
+            "estimated_size": "small"
+        },
+        "strategy": {
+            "docs_only": true,
+            "include_paths": [],
+            "exclude_paths": [],
+            "extensions": ["md"],
+            "recursive": true,
+            "chunk_size": null,
+            "use_ai_chunking": false,
+            "doc_type": doc_type,
+            "source_name": "widgets"
+        },
+        "cli_commands": [],
+        "reasoning": "nothing to run"
+    }))
+    .unwrap();
+    run_plan_for_job(
+        &pool,
+        job.id,
+        &analysis,
+        &doc_type,
+        "https://example.com/widgets",
+    )
+    .await;
+    let after_ingest = tool
+        .execute(json!({"query": "widget setup"}))
+        .await
+        .unwrap();
+    assert!(after_ingest.contains("guide/upgrade.md"), "{after_ingest}");
+
+    let _ = sqlx::query("DELETE FROM documents WHERE doc_type = $1")
+        .bind(&doc_type)
+        .execute(pool.pool())
+        .await;
+    let _ = sqlx::query("DELETE FROM ingest_jobs WHERE doc_type = $1")
+        .bind(&doc_type)
+        .execute(pool.pool())
+        .await;
+}
+
+#[tokio::test]
+async fn test_crate_removal_invalidates_rust_results() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping test - no database configured");
+        return;
+    };
+    let crate_name = format!("qcache-{}", &Uuid::new_v4().simple().to_string()[..8]);
+    DocumentQueries::batch_insert_documents(
+        pool.pool(),
+        &[document(
+            "rust",
+            &crate_name,
+            &format!("{crate_name}/fn.spawn_widget.html"),
+            "Spawn a widget task on the runtime.",
+        )],
+    )
+    .await
+    .unwrap();
+    let query = RustQueryTool::with_embedding_client(
+        pool.clone(),
+        Arc::new(CountingEmbeddingClient::default()),
+    );
+    let arguments = json!({"query": "spawn widget", "crate_name": crate_name});
+
+    let found = query.execute(arguments.clone()).await.unwrap();
+    assert!(found.contains("fn.spawn_widget.html"), "{found}");
+
+    RemoveRustCrateTool::new(CrateStorage::from(pool.clone()))
+        .execute(json!({"name": crate_name, "soft_delete": true}))
+        .await
+        .unwrap();
+    let after_removal = query.execute(arguments).await.unwrap();
+    assert!(
+        after_removal.contains("No relevant Rust documentation"),
+        "{after_removal}"
+    );
+
+    let _ = sqlx::query("DELETE FROM documents WHERE source_name = $1")
+        .bind(&crate_name)
+        .execute(pool.pool())
+        .await;
+}
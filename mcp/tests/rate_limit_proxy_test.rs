@@ -0,0 +1,77 @@
+//! Rate limiting behind a trusted proxy (`MCP_TRUST_PROXY_HEADERS=true`)
+//!
+//! Kept apart from `rate_limit_test.rs` because the setting is read from the
+//! environment, which would leak into the untrusted tests there.
+
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{Method, Request, StatusCode},
+    Router,
+};
+use db::DatabasePool;
+use mcp::{headers::SUPPORTED_PROTOCOL_VERSION, McpServer};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tower::ServiceExt;
+
+async fn create_proxied_router() -> Router {
+    std::env::set_var("MCP_RATE_LIMIT_RPM", "1");
+    std::env::set_var("MCP_RATE_LIMIT_BURST", "1");
+    std::env::set_var("MCP_TRUST_PROXY_HEADERS", "true");
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+        .expect("lazy pool");
+    McpServer::new(DatabasePool::from_pool(pool))
+        .await
+        .expect("server should start without a database")
+        .create_router()
+}
+
+/// `initialize` relayed by the proxy at 10.1.0.1 with `forwarded_for`
+fn proxied_request(forwarded_for: &str) -> Request<Body> {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {"protocolVersion": SUPPORTED_PROTOCOL_VERSION, "capabilities": {}}
+    });
+    Request::builder()
+        .method(Method::POST)
+        .uri("/mcp")
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .header("MCP-Protocol-Version", SUPPORTED_PROTOCOL_VERSION)
+        .header("X-Forwarded-For", forwarded_for)
+        .extension(ConnectInfo(SocketAddr::from(([10, 1, 0, 1], 40_000))))
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_forwarded_address_keys_clients_behind_the_proxy() {
+    let app = create_proxied_router().await;
+    let status = |forwarded_for: &'static str| {
+        let app = app.clone();
+        async move {
+            app.oneshot(proxied_request(forwarded_for))
+                .await
+                .unwrap()
+                .status()
+        }
+    };
+
+    // Clients behind the same proxy get their own budgets
+    assert_eq!(status("192.0.2.10").await, StatusCode::OK);
+    assert_eq!(status("192.0.2.11").await, StatusCode::OK);
+    assert_eq!(status("192.0.2.10").await, StatusCode::TOO_MANY_REQUESTS);
+
+    // Only the entry the proxy appended counts; a client-supplied prefix does not
+    assert_eq!(
+        status("198.51.100.1, 192.0.2.11").await,
+        StatusCode::TOO_MANY_REQUESTS
+    );
+}
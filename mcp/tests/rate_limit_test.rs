@@ -0,0 +1,160 @@
+//! Integration tests for per-client rate limiting on `/mcp`
+//!
+//! The server is built over a lazy pool pointing at an unreachable database;
+//! `initialize` and throttled requests never touch storage.
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::ConnectInfo,
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use db::DatabasePool;
+use mcp::{headers::SUPPORTED_PROTOCOL_VERSION, metrics::metrics, McpServer};
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+async fn create_limited_router() -> Router {
+    std::env::set_var("MCP_RATE_LIMIT_RPM", "1");
+    std::env::set_var("MCP_RATE_LIMIT_BURST", "2");
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+        .expect("lazy pool");
+    McpServer::new(DatabasePool::from_pool(pool))
+        .await
+        .expect("server should start without a database")
+        .create_router()
+}
+
+/// `initialize` from `peer`, with extra client-chosen headers
+fn initialize_request(peer: [u8; 4], headers: &[(&str, String)]) -> Request<Body> {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {"protocolVersion": SUPPORTED_PROTOCOL_VERSION, "capabilities": {}}
+    });
+    let mut request = Request::builder()
+        .method(Method::POST)
+        .uri("/mcp")
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .header("MCP-Protocol-Version", SUPPORTED_PROTOCOL_VERSION)
+        .extension(ConnectInfo(SocketAddr::from((peer, 40_000))));
+    for (name, value) in headers {
+        request = request.header(*name, value);
+    }
+    request.body(Body::from(body.to_string())).unwrap()
+}
+
+/// Send three requests built by `request`; the burst of two passes, the third is throttled
+async fn assert_third_request_throttled(app: &Router, request: impl Fn(usize) -> Request<Body>) {
+    for i in 0..2 {
+        let response = app.clone().oneshot(request(i)).await.unwrap();
+        assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+    let response = app.clone().oneshot(request(2)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn test_rate_limit_returns_429_with_retry_after() {
+    let app = create_limited_router().await;
+    let throttled_before = metrics().snapshot().rate_limited_requests;
+    let peer = [10, 0, 0, 1];
+
+    // Burst of two is allowed
+    for _ in 0..2 {
+        let response = app
+            .clone()
+            .oneshot(initialize_request(peer, &[]))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    // Third request is throttled
+    let response = app
+        .clone()
+        .oneshot(initialize_request(peer, &[]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = response
+        .headers()
+        .get(header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .expect("Retry-After header");
+    assert!((1..=60).contains(&retry_after));
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["jsonrpc"], "2.0");
+    assert_eq!(json["error"]["message"], "Too Many Requests");
+    assert!(metrics().snapshot().rate_limited_requests > throttled_before);
+
+    // Other clients keep their own budget
+    let response = app
+        .oneshot(initialize_request([10, 0, 0, 2], &[]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_rotating_session_ids_share_the_address_budget() {
+    let app = create_limited_router().await;
+    assert_third_request_throttled(&app, |_| {
+        initialize_request(
+            [10, 0, 1, 1],
+            &[("Mcp-Session-Id", Uuid::new_v4().to_string())],
+        )
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_spoofed_client_headers_share_the_address_budget() {
+    let app = create_limited_router().await;
+    assert_third_request_throttled(&app, |i| {
+        initialize_request(
+            [10, 0, 2, 1],
+            &[
+                ("X-Client-Id", format!("client-{i}")),
+                ("X-Forwarded-For", format!("203.0.113.{i}")),
+                ("X-Real-IP", format!("198.51.100.{i}")),
+            ],
+        )
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_live_session_has_its_own_budget() {
+    let app = create_limited_router().await;
+    let peer = [10, 0, 3, 1];
+    let response = app
+        .clone()
+        .oneshot(initialize_request(peer, &[]))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let session_id = response.headers()["mcp-session-id"]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    // The address has one request left; the session starts with a full burst
+    assert_third_request_throttled(&app, |_| {
+        initialize_request(peer, &[("Mcp-Session-Id", session_id.clone())])
+    })
+    .await;
+    let response = app.oneshot(initialize_request(peer, &[])).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -0,0 +1,192 @@
+//! Handler tests for `resources/list` and `resources/read`
+//!
+//! Seeds documents under a unique source name; tests skip when no database
+//! is configured.
+
+mod common;
+
+use chrono::Utc;
+use common::create_test_pool;
+use db::models::Document;
+use db::{CrateQueries, DatabasePool, DocumentQueries};
+use mcp::handlers::McpHandler;
+use mcp::resources::resource_uri;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+fn document(source_name: &str, doc_path: &str, content: &str) -> Document {
+    Document {
+        id: Uuid::new_v4(),
+        doc_type: "rust".to_string(),
+        source_name: source_name.to_string(),
+        doc_path: doc_path.to_string(),
+        content: content.to_string(),
+        metadata: json!({"crate_name": source_name}),
+        embedding: None,
+        token_count: Some(3),
+        created_at: Some(Utc::now()),
+        updated_at: Some(Utc::now()),
+    }
+}
+
+/// Seed three documents and return the source name
+async fn seed(pool: &DatabasePool) -> Option<String> {
+    let source = format!("res-{}", &Uuid::new_v4().simple().to_string()[..8]);
+    let docs = vec![
+        document(&source, "a/fn.one.html", "First document"),
+        document(&source, "b/fn.two.html", "Second document"),
+        document(&source, "c/struct.Three.html", &"x".repeat(500)),
+    ];
+    DocumentQueries::batch_insert_documents(pool.pool(), &docs)
+        .await
+        .ok()?;
+    Some(source)
+}
+
+async fn cleanup(pool: &DatabasePool, source: &str) {
+    let _ = sqlx::query("DELETE FROM documents WHERE source_name = $1")
+        .bind(source)
+        .execute(pool.pool())
+        .await;
+}
+
+#[tokio::test]
+async fn test_resources_list_paginates_with_cursor() {
+    let Some(pool) = create_test_pool().await else {
+        println!("⚠️ Skipping database-dependent test - no database available");
+        return;
+    };
+    let Some(source) = seed(&pool).await else {
+        println!("⚠️ Skipping resources test - unable to seed documents");
+        return;
+    };
+    let handler = McpHandler::new(&pool)
+        .expect("handler should build")
+        .with_resource_page_size(2);
+
+    let mut seen = HashSet::new();
+    let mut cursor: Option<String> = None;
+    let mut pages = 0;
+    loop {
+        let mut request =
+            json!({"jsonrpc": "2.0", "id": 1, "method": "resources/list", "params": {}});
+        if let Some(c) = &cursor {
+            request["params"]["cursor"] = json!(c);
+        }
+        let result = handler.handle_request(request).await.unwrap();
+        let resources = result["resources"].as_array().unwrap();
+        assert!(resources.len() <= 2);
+        for resource in resources {
+            let uri = resource["uri"].as_str().unwrap().to_string();
+            assert!(seen.insert(uri), "resource listed twice");
+        }
+        pages += 1;
+        match result.get("nextCursor").and_then(Value::as_str) {
+            Some(next) => cursor = Some(next.to_string()),
+            None => break,
+        }
+    }
+    cleanup(&pool, &source).await;
+
+    assert!(pages >= 2, "three seeded documents need at least two pages");
+    for path in ["a/fn.one.html", "b/fn.two.html", "c/struct.Three.html"] {
+        assert!(seen.contains(&resource_uri("rust", &source, path)));
+    }
+
+    // Malformed cursors are rejected
+    let err = handler
+        .handle_request(json!({"method": "resources/list", "params": {"cursor": "nope"}}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Invalid cursor"));
+}
+
+#[tokio::test]
+async fn test_resources_read_returns_content() {
+    let Some(pool) = create_test_pool().await else {
+        println!("⚠️ Skipping database-dependent test - no database available");
+        return;
+    };
+    let Some(source) = seed(&pool).await else {
+        println!("⚠️ Skipping resources test - unable to seed documents");
+        return;
+    };
+    let handler = McpHandler::new(&pool)
+        .expect("handler should build")
+        .with_resource_max_chars(100);
+
+    let read = |path: &str| {
+        json!({
+            "method": "resources/read",
+            "params": {"uri": resource_uri("rust", &source, path)}
+        })
+    };
+
+    let small = handler.handle_request(read("a/fn.one.html")).await;
+    let large = handler.handle_request(read("c/struct.Three.html")).await;
+    let missing = handler.handle_request(read("z/missing.html")).await;
+    cleanup(&pool, &source).await;
+
+    let small = small.unwrap();
+    let contents = &small["contents"][0];
+    assert_eq!(contents["text"], "First document");
+    assert_eq!(contents["mimeType"], "text/markdown");
+    assert_eq!(
+        contents["uri"].as_str().unwrap(),
+        resource_uri("rust", &source, "a/fn.one.html")
+    );
+
+    let large = large.unwrap();
+    let text = large["contents"][0]["text"].as_str().unwrap();
+    assert!(text.starts_with(&"x".repeat(100)));
+    assert!(text.contains("truncated: showing 100 of 500 characters"));
+
+    assert!(missing
+        .unwrap_err()
+        .to_string()
+        .contains("Resource not found"));
+}
+
+#[tokio::test]
+async fn test_resources_hide_deactivated_crates() {
+    let Some(pool) = create_test_pool().await else {
+        println!("⚠️ Skipping database-dependent test - no database available");
+        return;
+    };
+    let Some(source) = seed(&pool).await else {
+        println!("⚠️ Skipping resources test - unable to seed documents");
+        return;
+    };
+    let handler = McpHandler::new(&pool).expect("handler should build");
+    let marked = CrateQueries::mark_crate_inactive(pool.pool(), &source, None).await;
+
+    let mut listed = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut request = json!({"method": "resources/list", "params": {}});
+        if let Some(c) = &cursor {
+            request["params"]["cursor"] = json!(c);
+        }
+        let result = handler.handle_request(request).await.unwrap();
+        for resource in result["resources"].as_array().unwrap() {
+            listed.push(resource["uri"].as_str().unwrap().to_string());
+        }
+        match result.get("nextCursor").and_then(Value::as_str) {
+            Some(next) => cursor = Some(next.to_string()),
+            None => break,
+        }
+    }
+    let read = handler
+        .handle_request(json!({
+            "method": "resources/read",
+            "params": {"uri": resource_uri("rust", &source, "a/fn.one.html")}
+        }))
+        .await;
+    cleanup(&pool, &source).await;
+
+    assert_eq!(marked.unwrap(), 3);
+    let prefix = resource_uri("rust", &source, "");
+    assert!(!listed.iter().any(|uri| uri.starts_with(&prefix)));
+    assert!(read.unwrap_err().to_string().contains("Resource not found"));
+}
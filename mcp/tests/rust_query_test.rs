@@ -0,0 +1,415 @@
+//! Integration tests for the `rust_query` tool
+//!
+//! Seeds a handful of Rust documents under a unique crate name and checks
+//! ranking, metadata filtering, snippets, response truncation and the optional
+//! reranking stage. Tests skip when no database is configured.
+
+#![allow(clippy::uninlined_format_args)]
+
+mod common;
+
+use anyhow::Result;
+use chrono::Utc;
+use common::{create_test_pool, FixedEmbeddingClient};
+use db::models::Document;
+use db::{DatabasePool, DocumentQueries};
+use mcp::rerank::{RerankConfig, Reranker};
+use mcp::tools::{RustQueryTool, Tool};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Reranker that prefers passages mentioning `favourite`, after an optional delay
+struct FakeReranker {
+    favourite: &'static str,
+    delay: Duration,
+}
+
+#[async_trait::async_trait]
+impl Reranker for FakeReranker {
+    async fn score(&self, _query: &str, passages: &[String]) -> Result<Vec<f64>> {
+        tokio::time::sleep(self.delay).await;
+        Ok(passages
+            .iter()
+            .map(|p| if p.contains(self.favourite) { 1.0 } else { 0.0 })
+            .collect())
+    }
+}
+
+fn rerank_config(timeout_ms: u64) -> RerankConfig {
+    RerankConfig {
+        candidates: 30,
+        timeout: Duration::from_millis(timeout_ms),
+    }
+}
+
+fn rust_doc(crate_name: &str, path: &str, content: &str, extra: &serde_json::Value) -> Document {
+    let mut metadata = json!({
+        "crate_name": crate_name,
+        "item_type": "function",
+        "module_path": format!("{}::runtime", crate_name.replace('-', "_")),
+    });
+    if let (Some(obj), Some(extra)) = (metadata.as_object_mut(), extra.as_object()) {
+        obj.extend(extra.clone());
+    }
+
+    Document {
+        id: Uuid::new_v4(),
+        doc_type: "rust".to_string(),
+        source_name: crate_name.to_string(),
+        doc_path: format!("{crate_name}/{path}"),
+        content: content.to_string(),
+        metadata,
+        embedding: None,
+        token_count: None,
+        created_at: Some(Utc::now()),
+        updated_at: Some(Utc::now()),
+    }
+}
+
+/// Seed two crates and return their names
+async fn seed(pool: &DatabasePool) -> Result<(String, String)> {
+    let suffix = Uuid::new_v4().simple().to_string();
+    let primary = format!("rq-primary-{}", &suffix[..8]);
+    let other = format!("rq-other-{}", &suffix[..8]);
+    let filler = "Unrelated introductory text about configuration. ".repeat(30);
+
+    let docs = vec![
+        rust_doc(
+            &primary,
+            "fn.spawn_blocking.html",
+            &format!(
+                "{filler} Spawn a blocking task on the blocking thread pool. The spawned task \
+                 runs to completion; spawn many tasks to spawn parallel work."
+            ),
+            &json!({"topic": "runtime"}),
+        ),
+        rust_doc(
+            &primary,
+            "fn.yield_now.html",
+            "Yields execution back to the runtime so another task can spawn.",
+            &json!({"topic": "scheduling"}),
+        ),
+        rust_doc(
+            &other,
+            "fn.spawn.html",
+            "Spawn a new task onto the executor and spawn again.",
+            &json!({"topic": "runtime"}),
+        ),
+    ];
+
+    DocumentQueries::batch_insert_documents(pool.pool(), &docs).await?;
+    Ok((primary, other))
+}
+
+async fn cleanup(pool: &DatabasePool, crates: &[&str]) {
+    for name in crates {
+        let _ = sqlx::query("DELETE FROM documents WHERE source_name = $1")
+            .bind(name)
+            .execute(pool.pool())
+            .await;
+    }
+}
+
+fn tool(pool: DatabasePool) -> RustQueryTool {
+    RustQueryTool::with_embedding_client(pool, Arc::new(FixedEmbeddingClient))
+}
+
+#[tokio::test]
+async fn test_rust_query_ranks_and_filters_by_crate() {
+    let Some(pool) = create_test_pool().await else {
+        println!("⚠️ Skipping database-dependent test - no database available");
+        return;
+    };
+    let Ok((primary, other)) = seed(&pool).await else {
+        println!("⚠️ Skipping rust_query test - unable to seed documents");
+        return;
+    };
+
+    let response = tool(pool.clone())
+        .execute(json!({"query": "spawn task", "crate_name": primary}))
+        .await;
+    cleanup(&pool, &[&primary, &other]).await;
+    let response = response.expect("rust_query should succeed");
+
+    // Only the requested crate is searched
+    assert!(response.contains(&format!("from `{primary}`")));
+    assert!(!response.contains(&other));
+
+    // The document mentioning the terms most often ranks first
+    let first = response.find("spawn_blocking").expect("spawn_blocking hit");
+    let second = response.find("yield_now").expect("yield_now hit");
+    assert!(first < second, "unexpected ranking:\n{response}");
+
+    // Metadata and relevance are reported, and the snippet skips the filler
+    // text and marks the matched terms
+    assert!(response.contains("*function*"));
+    assert!(response.contains("Relevance: 100.0%"));
+    assert!(
+        response.contains("**Spawn** a blocking **task**"),
+        "{response}"
+    );
+    assert!(!response.contains("truncated"));
+}
+
+#[tokio::test]
+async fn test_rust_query_snippet_options_and_citation() {
+    let Some(pool) = create_test_pool().await else {
+        println!("⚠️ Skipping database-dependent test - no database available");
+        return;
+    };
+    let Ok((primary, other)) = seed(&pool).await else {
+        println!("⚠️ Skipping rust_query test - unable to seed documents");
+        return;
+    };
+    let url = format!("https://docs.rs/{primary}/1.0.0/{primary}/fn.sleep.html");
+    let cited = rust_doc(
+        &primary,
+        "fn.sleep.html",
+        "Waits until duration has elapsed. Sleeping tasks are woken by the timer 🦀 driver.",
+        &json!({"topic": "timers", "source_url": url}),
+    );
+    let seeded = DocumentQueries::batch_insert_documents(pool.pool(), &[cited]).await;
+
+    let html = tool(pool.clone())
+        .execute(json!({
+            "query": "sleeping tasks",
+            "crate_name": primary,
+            "topic": "timers",
+            "highlight": "html",
+            "snippet_chars": 100
+        }))
+        .await;
+    let plain = tool(pool.clone())
+        .execute(json!({
+            "query": "sleeping tasks",
+            "crate_name": primary,
+            "topic": "timers",
+            "highlight": "none"
+        }))
+        .await;
+    let invalid = tool(pool.clone())
+        .execute(json!({"query": "sleep", "snippet_chars": 10}))
+        .await;
+    cleanup(&pool, &[&primary, &other]).await;
+    seeded.expect("seed cited document");
+    let html = html.expect("rust_query should succeed");
+    let plain = plain.expect("rust_query should succeed");
+
+    // Stemmed matches are marked, and the docs.rs page is cited with its module path
+    assert!(
+        html.contains("<em>Sleeping</em> <em>tasks</em> are woken"),
+        "{html}"
+    );
+    assert!(html.contains(&format!("Source: {url}")), "{html}");
+    assert!(html.contains(&format!("`{}::runtime`", primary.replace('-', "_"))));
+    assert!(plain.contains("Sleeping tasks are woken by the timer 🦀 driver."));
+    assert!(!plain.contains("<em>") && !plain.contains("**Sleeping"));
+    assert!(invalid
+        .unwrap_err()
+        .to_string()
+        .contains("snippet_chars must be between"));
+}
+
+#[tokio::test]
+async fn test_rust_query_topic_filter() {
+    let Some(pool) = create_test_pool().await else {
+        println!("⚠️ Skipping database-dependent test - no database available");
+        return;
+    };
+    let Ok((primary, other)) = seed(&pool).await else {
+        println!("⚠️ Skipping rust_query test - unable to seed documents");
+        return;
+    };
+
+    let response = tool(pool.clone())
+        .execute(json!({"query": "spawn", "crate_name": primary, "topic": "scheduling"}))
+        .await;
+    cleanup(&pool, &[&primary, &other]).await;
+    let response = response.expect("rust_query should succeed");
+
+    assert!(response.contains("yield_now"));
+    assert!(!response.contains("spawn_blocking"));
+}
+
+#[tokio::test]
+async fn test_rust_query_truncates_large_responses() {
+    let Some(pool) = create_test_pool().await else {
+        println!("⚠️ Skipping database-dependent test - no database available");
+        return;
+    };
+    let Ok((primary, other)) = seed(&pool).await else {
+        println!("⚠️ Skipping rust_query test - unable to seed documents");
+        return;
+    };
+
+    let response = tool(pool.clone())
+        .with_max_response_chars(500)
+        .execute(json!({"query": "spawn", "crate_name": primary}))
+        .await;
+    cleanup(&pool, &[&primary, &other]).await;
+    let response = response.expect("rust_query should succeed");
+
+    assert!(
+        response.chars().count() <= 500,
+        "response exceeded cap:\n{response}"
+    );
+    assert!(
+        response.contains("Response truncated: showing 1 of 2 results"),
+        "missing truncation note:\n{response}"
+    );
+}
+
+#[tokio::test]
+async fn test_rust_query_rejects_invalid_limit() {
+    let Some(pool) = create_test_pool().await else {
+        println!("⚠️ Skipping database-dependent test - no database available");
+        return;
+    };
+
+    let err = tool(pool)
+        .execute(json!({"query": "spawn", "limit": 0}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Limit must be between 1 and 20"));
+}
+
+#[tokio::test]
+async fn test_rust_query_rerank_reorders_before_limit() {
+    let Some(pool) = create_test_pool().await else {
+        println!("⚠️ Skipping database-dependent test - no database available");
+        return;
+    };
+    let Ok((primary, other)) = seed(&pool).await else {
+        println!("⚠️ Skipping rust_query test - unable to seed documents");
+        return;
+    };
+    let reranker = Arc::new(FakeReranker {
+        favourite: "Yields execution",
+        delay: Duration::ZERO,
+    });
+    let tool = tool(pool.clone()).with_reranker(reranker, rerank_config(1_000));
+
+    // Search order puts spawn_blocking first; the reranker promotes yield_now
+    // from the candidate set even though only one result is requested
+    let plain = tool
+        .execute(json!({"query": "spawn", "crate_name": primary, "limit": 1}))
+        .await;
+    let reranked = tool
+        .execute(json!({"query": "spawn", "crate_name": primary, "limit": 1, "rerank": true}))
+        .await;
+    cleanup(&pool, &[&primary, &other]).await;
+    let plain = plain.expect("rust_query should succeed");
+    let reranked = reranked.expect("rust_query should succeed");
+
+    assert!(plain.contains("spawn_blocking"), "{plain}");
+    assert!(reranked.contains("yield_now"), "{reranked}");
+    assert!(!reranked.contains("spawn_blocking"), "{reranked}");
+    assert!(!reranked.contains("search order"));
+}
+
+#[tokio::test]
+async fn test_rust_query_rerank_timeout_keeps_search_order() {
+    let Some(pool) = create_test_pool().await else {
+        println!("⚠️ Skipping database-dependent test - no database available");
+        return;
+    };
+    let Ok((primary, other)) = seed(&pool).await else {
+        println!("⚠️ Skipping rust_query test - unable to seed documents");
+        return;
+    };
+    let reranker = Arc::new(FakeReranker {
+        favourite: "Yields execution",
+        delay: Duration::from_millis(500),
+    });
+
+    let response = tool(pool.clone())
+        .with_reranker(reranker, rerank_config(20))
+        .execute(json!({"query": "spawn", "crate_name": primary, "limit": 1, "rerank": true}))
+        .await;
+    cleanup(&pool, &[&primary, &other]).await;
+    let response = response.expect("rust_query should succeed");
+
+    assert!(response.contains("spawn_blocking"), "{response}");
+    assert!(response.contains("Reranking timed out; results are in search order."));
+}
+
+/// Seed documents that only match expanded queries and return their crate name
+async fn seed_expansion_docs(pool: &DatabasePool) -> Result<String> {
+    let suffix = Uuid::new_v4().simple().to_string();
+    let name = format!("rq-expand-{}", &suffix[..8]);
+    let docs = vec![
+        rust_doc(
+            &name,
+            "struct.HashMap.html",
+            "A HashMap stores values by key with constant-time lookups.",
+            &json!({"item_type": "struct"}),
+        ),
+        rust_doc(
+            &name,
+            "trait.ReadToString.html",
+            "Implementors of ReadToString load a whole file at once.",
+            &json!({"item_type": "trait"}),
+        ),
+        rust_doc(
+            &name,
+            "fn.load.html",
+            "Call fs::read_to_string to load a file into memory.",
+            &json!({}),
+        ),
+    ];
+    DocumentQueries::batch_insert_documents(pool.pool(), &docs).await?;
+    Ok(name)
+}
+
+#[tokio::test]
+async fn test_rust_query_expansion_matches_synonyms_and_identifiers() {
+    let Some(pool) = create_test_pool().await else {
+        println!("⚠️ Skipping database-dependent test - no database available");
+        return;
+    };
+    let Ok(name) = seed_expansion_docs(&pool).await else {
+        println!("⚠️ Skipping rust_query test - unable to seed documents");
+        return;
+    };
+    let tool = tool(pool.clone());
+    let query = |query: &str, expand: bool| {
+        tool.execute(json!({
+            "query": query,
+            "crate_name": name,
+            "expand": expand,
+            "cache": false,
+        }))
+    };
+
+    let synonym_plain = query("dictionary", false).await;
+    let synonym = query("dictionary", true).await;
+    let identifier_plain = query("read to string", false).await;
+    let identifier = query("read to string", true).await;
+    cleanup(&pool, &[&name]).await;
+    let synonym_plain = synonym_plain.expect("rust_query should succeed");
+    let synonym = synonym.expect("rust_query should succeed");
+    let identifier_plain = identifier_plain.expect("rust_query should succeed");
+    let identifier = identifier.expect("rust_query should succeed");
+
+    // The document only says HashMap, a built-in synonym of dictionary
+    assert!(!synonym_plain.contains("struct.HashMap"), "{synonym_plain}");
+    assert!(synonym.contains("struct.HashMap"), "{synonym}");
+    assert!(
+        synonym.contains("Expanded terms: hashmap, btreemap"),
+        "{synonym}"
+    );
+
+    // The snake_case identifier matches either way; the CamelCase one only
+    // through the joined form
+    assert!(identifier_plain.contains("fn.load"), "{identifier_plain}");
+    assert!(
+        !identifier_plain.contains("trait.ReadToString"),
+        "{identifier_plain}"
+    );
+    assert!(identifier.contains("fn.load"), "{identifier}");
+    assert!(identifier.contains("trait.ReadToString"), "{identifier}");
+    assert!(identifier.contains("readtostring"), "{identifier}");
+    assert!(!identifier_plain.contains("Expanded terms"));
+}
@@ -0,0 +1,191 @@
+//! Session listing and termination through `/admin/sessions`
+//!
+//! The server runs over a lazy pool pointing at an unreachable database;
+//! `initialize`, SSE streams and the admin routes never touch storage.
+
+mod common;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use common::lazy_pool;
+use mcp::headers::{MCP_SESSION_ID, SUPPORTED_PROTOCOL_VERSION};
+use mcp::McpServer;
+use serde_json::{json, Value};
+use std::env;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use tower::ServiceExt;
+
+const ADMIN_TOKEN: &str = "session-admin-token";
+
+/// Read from `stream` until `needle` appears; false once the stream closes
+async fn read_until(stream: &mut TcpStream, received: &mut String, needle: &str) -> bool {
+    let mut buf = [0u8; 4096];
+    while !received.contains(needle) {
+        match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return false,
+            Ok(n) => received.push_str(&String::from_utf8_lossy(&buf[..n])),
+        }
+    }
+    true
+}
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+/// Initialize a new session and return its ID
+async fn initialize(app: &Router, user_agent: &str) -> String {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {"protocolVersion": SUPPORTED_PROTOCOL_VERSION, "capabilities": {}}
+    });
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/mcp")
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .header("MCP-Protocol-Version", SUPPORTED_PROTOCOL_VERSION)
+        .header(header::USER_AGENT, user_agent)
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    response
+        .headers()
+        .get(MCP_SESSION_ID)
+        .and_then(|v| v.to_str().ok())
+        .expect("session ID header")
+        .to_string()
+}
+
+fn admin(method: Method, uri: &str, token: Option<&str>) -> Request<Body> {
+    let mut builder = Request::builder().method(method).uri(uri);
+    if let Some(token) = token {
+        builder = builder.header(header::AUTHORIZATION, format!("Bearer {token}"));
+    }
+    builder.body(Body::empty()).unwrap()
+}
+
+async fn list(app: &Router) -> Value {
+    let (status, body) = send(
+        app,
+        admin(Method::GET, "/admin/sessions", Some(ADMIN_TOKEN)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    body
+}
+
+fn entry<'a>(listing: &'a Value, session_id: &str) -> Option<&'a Value> {
+    listing["sessions"]
+        .as_array()
+        .expect("sessions array")
+        .iter()
+        .find(|s| s["session_id"] == session_id)
+}
+
+#[tokio::test]
+async fn test_list_and_terminate_sessions() {
+    env::set_var("MCP_ADMIN_TOKEN", ADMIN_TOKEN);
+    let server = McpServer::new(lazy_pool())
+        .await
+        .expect("server should start without a database");
+    let app = server.create_router();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (trigger, signal) = oneshot::channel::<()>();
+    let serving = tokio::spawn(async move {
+        server
+            .serve_with_shutdown(listener, async {
+                let _ = signal.await;
+            })
+            .await
+    });
+
+    let first = initialize(&app, "first-client/1.0").await;
+    let second = initialize(&app, "second-client/2.0").await;
+    assert_ne!(first, second);
+
+    // Attach an SSE stream to the first session
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let request = format!(
+        "GET /mcp HTTP/1.1\r\nHost: {addr}\r\nAccept: text/event-stream\r\n\
+         MCP-Protocol-Version: {SUPPORTED_PROTOCOL_VERSION}\r\n{MCP_SESSION_ID}: {first}\r\n\r\n"
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut received = String::new();
+    let opened = tokio::time::timeout(
+        Duration::from_secs(5),
+        read_until(&mut stream, &mut received, "notifications/initialized"),
+    )
+    .await
+    .expect("SSE stream should open");
+    assert!(opened, "unexpected response: {received}");
+
+    // The routes need the admin token
+    let (status, _) = send(&app, admin(Method::GET, "/admin/sessions", None)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(
+        &app,
+        admin(Method::DELETE, "/admin/sessions", Some("wrong")),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let listing = list(&app).await;
+    assert_eq!(listing["total"], 2, "{listing}");
+    assert_eq!(listing["sse_attached"], 1, "{listing}");
+    let attached = entry(&listing, &first).expect("first session listed");
+    assert_eq!(attached["sse_attached"], true);
+    assert_eq!(attached["user_agent"], "first-client/1.0");
+    assert_eq!(attached["protocol_version"], SUPPORTED_PROTOCOL_VERSION);
+    assert!(attached["created_at"].is_string());
+    assert!(attached["last_accessed"].is_string());
+    let detached = entry(&listing, &second).expect("second session listed");
+    assert_eq!(detached["sse_attached"], false);
+
+    // Terminating the first session closes its stream with a final event
+    let uri = format!("/admin/sessions/{first}");
+    let (status, _) = send(&app, admin(Method::DELETE, &uri, Some(ADMIN_TOKEN))).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let closed = tokio::time::timeout(
+        Duration::from_secs(5),
+        read_until(&mut stream, &mut received, "event: session_closed"),
+    )
+    .await
+    .expect("session_closed event should arrive");
+    assert!(closed, "missing close event: {received}");
+
+    // The other session survives
+    let listing = list(&app).await;
+    assert_eq!(listing["total"], 1, "{listing}");
+    assert!(entry(&listing, &first).is_none());
+    assert!(entry(&listing, &second).is_some());
+
+    let (status, _) = send(&app, admin(Method::DELETE, &uri, Some(ADMIN_TOKEN))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = send(
+        &app,
+        admin(Method::DELETE, "/admin/sessions", Some(ADMIN_TOKEN)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["terminated"], 1);
+    assert_eq!(list(&app).await["total"], 0);
+
+    trigger.send(()).unwrap();
+    let _ = tokio::time::timeout(Duration::from_secs(5), serving).await;
+}
@@ -0,0 +1,79 @@
+//! Graceful shutdown test: open an SSE stream, trigger shutdown, and expect
+//! a final `shutdown` event followed by a clean exit within the grace period.
+
+use db::DatabasePool;
+use mcp::{headers::SUPPORTED_PROTOCOL_VERSION, McpServer};
+use sqlx::postgres::PgPoolOptions;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+/// Read from `stream` until `needle` appears or the stream closes
+async fn read_until(stream: &mut TcpStream, received: &mut String, needle: &str) -> bool {
+    let mut buf = [0u8; 4096];
+    while !received.contains(needle) {
+        match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return false,
+            Ok(n) => received.push_str(&String::from_utf8_lossy(&buf[..n])),
+        }
+    }
+    true
+}
+
+#[tokio::test]
+async fn test_shutdown_notifies_sse_and_exits_within_grace_period() {
+    std::env::set_var("MCP_SHUTDOWN_GRACE_SECS", "2");
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+        .expect("lazy pool");
+    let server = McpServer::new(DatabasePool::from_pool(pool))
+        .await
+        .expect("server should start without a database");
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (trigger, signal) = oneshot::channel::<()>();
+    let serving = tokio::spawn(async move {
+        server
+            .serve_with_shutdown(listener, async {
+                let _ = signal.await;
+            })
+            .await
+    });
+
+    // Open an SSE stream and wait for the initial event
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let request = format!(
+        "GET /mcp HTTP/1.1\r\nHost: {addr}\r\nAccept: text/event-stream\r\n\
+         MCP-Protocol-Version: {SUPPORTED_PROTOCOL_VERSION}\r\n\r\n"
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut received = String::new();
+    let opened = tokio::time::timeout(
+        Duration::from_secs(5),
+        read_until(&mut stream, &mut received, "notifications/initialized"),
+    )
+    .await
+    .expect("SSE stream should open");
+    assert!(opened, "unexpected response: {received}");
+
+    // Trigger shutdown: the stream gets a final shutdown event, then closes
+    trigger.send(()).unwrap();
+    let notified = tokio::time::timeout(
+        Duration::from_secs(5),
+        read_until(&mut stream, &mut received, "notifications/shutdown"),
+    )
+    .await
+    .expect("shutdown event should arrive");
+    assert!(notified, "missing shutdown event: {received}");
+    assert!(received.contains("event: shutdown"));
+
+    // The server exits cleanly within the grace period
+    let result = tokio::time::timeout(Duration::from_secs(3), serving)
+        .await
+        .expect("server should exit within the grace period")
+        .unwrap();
+    assert!(result.is_ok(), "shutdown failed: {result:?}");
+}
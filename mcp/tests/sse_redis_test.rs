@@ -0,0 +1,62 @@
+//! Redis-backed SSE replay across hub instances
+//!
+//! Each hub stands in for a replica or a restarted pod. Tests skip unless
+//! `TEST_REDIS_URL` points at a Redis server, e.g. a `redis:7` test container.
+
+use mcp::sse::{RedisSseHub, SseHub, SseMessage, SubscriptionEvent};
+use std::time::Duration;
+use uuid::Uuid;
+
+fn test_hub() -> Option<RedisSseHub> {
+    let url = std::env::var("TEST_REDIS_URL").ok()?;
+    Some(RedisSseHub::new(&url).expect("valid Redis URL"))
+}
+
+fn message(data: &str) -> SseMessage {
+    SseMessage {
+        id: None,
+        event: Some("message".to_string()),
+        data: data.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_replay_after_reconnect_to_another_hub() {
+    let (Some(first), Some(second)) = (test_hub(), test_hub()) else {
+        println!("Skipping Redis SSE test: TEST_REDIS_URL not set");
+        return;
+    };
+    let session_id = Uuid::new_v4();
+
+    for (expected, data) in (1..).zip(["one", "two", "three"]) {
+        assert_eq!(
+            first.publish(session_id, message(data)).await.unwrap(),
+            expected
+        );
+    }
+
+    // Reconnect with Last-Event-ID: 1 on a hub that never saw the messages
+    let mut live = second.subscribe(session_id).await.unwrap();
+    let replay = second.snapshot_from(session_id, Some(1)).await.unwrap();
+    let replayed: Vec<(u64, &str)> = replay
+        .iter()
+        .map(|(id, msg)| (*id, msg.data.as_str()))
+        .collect();
+    assert_eq!(replayed, vec![(2, "two"), (3, "three")]);
+    assert_eq!(replay[0].1.event.as_deref(), Some("message"));
+    assert_eq!(replay[0].1.id.as_deref(), Some("2"));
+
+    // Live delivery crosses hubs, and IDs keep increasing for a fresh hub
+    let third = test_hub().unwrap();
+    assert_eq!(third.publish(session_id, message("four")).await.unwrap(), 4);
+    let event = tokio::time::timeout(Duration::from_secs(5), live.recv())
+        .await
+        .expect("live message should arrive");
+    match event {
+        SubscriptionEvent::Message(msg) => {
+            assert_eq!(msg.id.as_deref(), Some("4"));
+            assert_eq!(msg.data, "four");
+        }
+        other => panic!("unexpected subscription event: {other:?}"),
+    }
+}
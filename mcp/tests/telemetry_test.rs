@@ -0,0 +1,124 @@
+//! Span tree exported for a tool call (`otel` feature)
+//!
+//! Spans go to an in-memory exporter through the same layer the server
+//! installs when `OTEL_EXPORTER_OTLP_ENDPOINT` is set. The server runs over a
+//! lazy pool pointing at an unreachable database; `get_tool_metrics` never
+//! touches storage.
+
+#![cfg(feature = "otel")]
+
+mod common;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Method, Request, StatusCode},
+};
+use common::lazy_pool;
+use db::QueryPerformanceMonitor;
+use mcp::{headers::SUPPORTED_PROTOCOL_VERSION, McpServer};
+use opentelemetry::trace::{SpanId, TraceId};
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider, SpanData};
+use serde_json::json;
+use tower::ServiceExt;
+use tracing::Instrument;
+use tracing_subscriber::layer::SubscriberExt;
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const PARENT_SPAN_ID: &str = "00f067aa0ba902b7";
+
+/// Exporter and provider recording spans synchronously
+fn recording_provider() -> (InMemorySpanExporter, SdkTracerProvider) {
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    (exporter, provider)
+}
+
+fn attribute(span: &SpanData, key: &str) -> Option<String> {
+    span.attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == key)
+        .map(|kv| kv.value.to_string())
+}
+
+#[tokio::test]
+async fn test_tool_call_span_joins_incoming_trace() {
+    let (exporter, provider) = recording_provider();
+    let subscriber = tracing_subscriber::registry().with(mcp::telemetry::layer(&provider));
+    let _default = tracing::subscriber::set_default(subscriber);
+
+    let app = McpServer::new(lazy_pool())
+        .await
+        .expect("server should start without a database")
+        .create_router();
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": {"name": "get_tool_metrics", "arguments": {}}
+    });
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/mcp")
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .header("MCP-Protocol-Version", SUPPORTED_PROTOCOL_VERSION)
+        .header("traceparent", format!("00-{TRACE_ID}-{PARENT_SPAN_ID}-01"))
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let _ = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+    provider.force_flush().unwrap();
+    let spans = exporter.get_finished_spans().unwrap();
+    let tool = spans
+        .iter()
+        .find(|s| s.name == "tool_call")
+        .expect("tool_call span");
+    assert_eq!(attribute(tool, "tool").as_deref(), Some("get_tool_metrics"));
+    assert_eq!(attribute(tool, "argument_bytes").as_deref(), Some("2"));
+
+    // The request span is the parent and continues the caller's trace
+    let request = spans
+        .iter()
+        .find(|s| s.span_context.span_id() == tool.parent_span_id)
+        .expect("parent of the tool span");
+    assert_eq!(request.name, "mcp_request");
+    let trace_id = TraceId::from_hex(TRACE_ID).unwrap();
+    assert_eq!(request.span_context.trace_id(), trace_id);
+    assert_eq!(tool.span_context.trace_id(), trace_id);
+    assert_eq!(
+        request.parent_span_id,
+        SpanId::from_hex(PARENT_SPAN_ID).unwrap()
+    );
+    assert!(request.parent_span_is_remote);
+}
+
+#[tokio::test]
+async fn test_monitored_query_is_a_child_span() {
+    let (exporter, provider) = recording_provider();
+    let subscriber = tracing_subscriber::registry().with(mcp::telemetry::layer(&provider));
+    let _default = tracing::subscriber::set_default(subscriber);
+
+    let parent = tracing::info_span!("tool_call", tool = "probe");
+    QueryPerformanceMonitor::execute_with_monitoring("probe_query", async { Ok(vec![1, 2, 3]) })
+        .instrument(parent)
+        .await
+        .unwrap();
+
+    provider.force_flush().unwrap();
+    let spans = exporter.get_finished_spans().unwrap();
+    let query = spans
+        .iter()
+        .find(|s| s.name == "db.query")
+        .expect("db.query span");
+    assert_eq!(attribute(query, "query").as_deref(), Some("probe_query"));
+    assert_eq!(attribute(query, "rows").as_deref(), Some("3"));
+    let parent = spans
+        .iter()
+        .find(|s| s.name == "tool_call")
+        .expect("tool_call span");
+    assert_eq!(query.parent_span_id, parent.span_context.span_id());
+}
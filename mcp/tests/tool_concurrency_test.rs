@@ -0,0 +1,210 @@
+//! Concurrent tool calls beyond the per-session or global limit wait briefly
+//! for a slot and are then refused, while protocol methods stay unaffected
+
+use anyhow::Result;
+use async_trait::async_trait;
+use db::DatabasePool;
+use mcp::handlers::McpHandler;
+use mcp::metrics::metrics;
+use mcp::session::{SessionConfig, SessionManager};
+use mcp::tool_concurrency::ToolConcurrency;
+use mcp::tools::{Tool, ToolConcurrencyExhausted};
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Tool that holds its slot for a while, tracking how many calls overlap
+struct SlowTool {
+    delay: Duration,
+    running: Arc<AtomicUsize>,
+    peak: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl Tool for SlowTool {
+    fn definition(&self) -> Value {
+        json!({"name": "slow_query", "description": "Takes its time", "inputSchema": {}})
+    }
+
+    async fn execute(&self, _arguments: Value) -> Result<String> {
+        let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(now, Ordering::SeqCst);
+        tokio::time::sleep(self.delay).await;
+        self.running.fetch_sub(1, Ordering::SeqCst);
+        Ok("done".to_string())
+    }
+}
+
+/// Handler with `slow_query` registered, returning the peak overlap counter
+fn handler(delay: Duration, concurrency: ToolConcurrency) -> (Arc<McpHandler>, Arc<AtomicUsize>) {
+    let pool = DatabasePool::from_pool(
+        PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(200))
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .expect("lazy pool"),
+    );
+    let peak = Arc::new(AtomicUsize::new(0));
+    let mut handler = McpHandler::new(&pool)
+        .expect("handler")
+        .with_tool_concurrency(concurrency);
+    handler.register_tool(
+        "slow_query",
+        Box::new(SlowTool {
+            delay,
+            running: Arc::new(AtomicUsize::new(0)),
+            peak: Arc::clone(&peak),
+        }),
+    );
+    (Arc::new(handler), peak)
+}
+
+/// Start `count` concurrent `slow_query` calls, one per entry of `sessions` in turn
+fn spawn_calls(
+    handler: &Arc<McpHandler>,
+    sessions: &[String],
+    count: usize,
+) -> Vec<tokio::task::JoinHandle<Result<Value>>> {
+    (0..count)
+        .map(|i| {
+            let handler = Arc::clone(handler);
+            let session = sessions[i % sessions.len()].clone();
+            tokio::spawn(async move {
+                handler
+                    .handle_session_request(
+                        json!({
+                            "jsonrpc": "2.0",
+                            "id": i,
+                            "method": "tools/call",
+                            "params": {"name": "slow_query", "arguments": {}}
+                        }),
+                        Some(&session),
+                    )
+                    .await
+            })
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_calls_over_the_session_limit_queue() {
+    let (handler, peak) = handler(
+        Duration::from_millis(150),
+        ToolConcurrency::new(2, 32, Duration::from_secs(5)),
+    );
+    let session = Uuid::new_v4().to_string();
+
+    for call in spawn_calls(&handler, std::slice::from_ref(&session), 6) {
+        call.await
+            .unwrap()
+            .expect("queued calls run once a slot frees");
+    }
+
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+    let stats = metrics().tool_concurrency()[&session];
+    assert_eq!(stats.queued_calls, 4);
+    assert_eq!(stats.rejections, 0);
+    assert!(stats.max_queue_wait >= Duration::from_millis(100));
+    assert!(stats.queue_wait >= stats.max_queue_wait);
+}
+
+#[tokio::test]
+async fn test_calls_over_the_session_limit_are_refused_after_the_wait() {
+    let (handler, peak) = handler(
+        Duration::from_millis(600),
+        ToolConcurrency::new(2, 32, Duration::from_millis(50)),
+    );
+    let session = Uuid::new_v4().to_string();
+    let calls = spawn_calls(&handler, std::slice::from_ref(&session), 5);
+
+    // Protocol methods of the saturated session answer at once
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let started = Instant::now();
+    for method in ["initialize", "tools/list"] {
+        handler
+            .handle_session_request(
+                json!({"jsonrpc": "2.0", "id": method, "method": method, "params": {}}),
+                Some(&session),
+            )
+            .await
+            .expect("protocol methods take no slot");
+    }
+    assert!(started.elapsed() < Duration::from_millis(100));
+
+    let mut refused = 0;
+    for call in calls {
+        match call.await.unwrap() {
+            Ok(_) => {}
+            Err(e) => {
+                let exhausted = e
+                    .downcast_ref::<ToolConcurrencyExhausted>()
+                    .expect("ToolConcurrencyExhausted");
+                assert_eq!(exhausted.scope, "session");
+                assert_eq!(exhausted.limit, 2);
+                assert!(exhausted.waited >= Duration::from_millis(50));
+                refused += 1;
+            }
+        }
+    }
+    assert_eq!(refused, 3);
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+    assert_eq!(metrics().tool_concurrency()[&session].rejections, 3);
+    assert!(metrics().tool_concurrency_totals().rejections >= 3);
+}
+
+#[tokio::test]
+async fn test_session_stats_are_dropped_when_the_session_ends() {
+    let sessions = SessionManager::new(SessionConfig::default());
+    let session_id = sessions.create_session(None).unwrap();
+    let session = session_id.to_string();
+    let concurrency = ToolConcurrency::new(1, 4, Duration::ZERO);
+    let _permit = concurrency.acquire(Some(&session)).await.unwrap();
+    assert!(concurrency.acquire(Some(&session)).await.is_err());
+    assert_eq!(metrics().tool_concurrency()[&session].rejections, 1);
+
+    sessions.delete_session(session_id).unwrap();
+    assert!(!metrics().tool_concurrency().contains_key(&session));
+    assert!(metrics().tool_concurrency_totals().rejections >= 1);
+    // Session IDs never become metric labels
+    let exported = metrics().tool_concurrency_prometheus();
+    assert!(!exported.contains("session="));
+    assert!(exported.contains("mcp_tool_concurrency_rejections_total "));
+}
+
+#[tokio::test]
+async fn test_global_limit_caps_calls_across_sessions() {
+    let (handler, peak) = handler(
+        Duration::from_millis(600),
+        ToolConcurrency::new(4, 2, Duration::from_millis(50)),
+    );
+    let sessions: Vec<String> = (0..4).map(|_| Uuid::new_v4().to_string()).collect();
+
+    let mut scopes = Vec::new();
+    for call in spawn_calls(&handler, &sessions, 4) {
+        if let Err(e) = call.await.unwrap() {
+            let exhausted = e
+                .downcast_ref::<ToolConcurrencyExhausted>()
+                .expect("ToolConcurrencyExhausted");
+            scopes.push(exhausted.scope);
+        }
+    }
+    assert_eq!(scopes, vec!["global", "global"]);
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_idle_sessions_release_their_slots() {
+    let concurrency = ToolConcurrency::new(1, 4, Duration::ZERO);
+    let permit = concurrency.acquire(Some("a")).await.unwrap();
+    assert_eq!(concurrency.active_sessions(), 1);
+    assert!(concurrency.acquire(Some("a")).await.is_err());
+    // Other sessions and sessionless calls have their own slots
+    let other = concurrency.acquire(Some("b")).await.unwrap();
+    let anonymous = concurrency.acquire(None).await.unwrap();
+
+    drop((permit, other, anonymous));
+    assert_eq!(concurrency.active_sessions(), 0);
+    assert!(concurrency.acquire(Some("a")).await.is_ok());
+}
@@ -0,0 +1,95 @@
+//! Coded tool errors reach clients as JSON-RPC errors
+//!
+//! The server runs over a lazy pool pointing at an unreachable database;
+//! the calls fail on their arguments before any storage access.
+
+mod common;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Method, Request, StatusCode},
+};
+use common::lazy_pool;
+use mcp::config::ConfigLoader;
+use mcp::handlers::McpHandler;
+use mcp::tools::ToolError;
+use mcp::{headers::SUPPORTED_PROTOCOL_VERSION, McpServer};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+/// Crate management tools whose argument checks raise coded errors
+const TOOLS_CONFIG: &str = r#"{"tools": [
+    {"name": "remove_rust_crate", "docType": "rust", "title": "Remove Rust Crate",
+     "description": "Remove a crate.", "enabled": true},
+    {"name": "retry_rust_job", "docType": "rust", "title": "Retry Rust Job",
+     "description": "Retry a failed job.", "enabled": true}
+]}"#;
+
+#[test]
+fn test_codes_and_payload() {
+    let error = ToolError::Conflict {
+        message: "Job is Running".to_string(),
+        details: json!({"status": "Running"}),
+    };
+    assert_eq!(error.code(), "conflict");
+    assert_eq!(error.jsonrpc_code(), -32009);
+    assert_eq!(
+        error.to_json(),
+        json!({"code": "conflict", "message": "Job is Running", "details": {"status": "Running"}})
+    );
+    assert_eq!(ToolError::invalid_input("bad").jsonrpc_code(), -32602);
+}
+
+#[tokio::test]
+async fn test_tool_error_is_returned_from_handler() {
+    let config = ConfigLoader::parse(TOOLS_CONFIG, "tools.json", false).expect("valid config");
+    let handler = McpHandler::with_tools_config(&lazy_pool(), Some(&config)).expect("handler");
+    let error = handler
+        .handle_request(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": "retry_rust_job", "arguments": {"job_id": "not-a-uuid"}}
+        }))
+        .await
+        .expect_err("a malformed job id is rejected");
+    let error = error.downcast_ref::<ToolError>().expect("ToolError");
+    assert_eq!(error.code(), "invalid_input");
+}
+
+#[tokio::test]
+async fn test_tool_error_over_http() {
+    std::env::set_var("TOOLS_CONFIG", TOOLS_CONFIG);
+    let app = McpServer::new(lazy_pool())
+        .await
+        .expect("server should start without a database")
+        .create_router();
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 4,
+        "method": "tools/call",
+        "params": {"name": "remove_rust_crate", "arguments": {"name": ""}}
+    });
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/mcp")
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .header("MCP-Protocol-Version", SUPPORTED_PROTOCOL_VERSION)
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(json["id"], 4);
+    assert_eq!(json["error"]["code"], -32602);
+    assert_eq!(json["error"]["message"], "Crate name cannot be empty");
+    assert_eq!(json["error"]["data"]["code"], "invalid_input");
+    assert_eq!(
+        json["error"]["data"]["message"],
+        "Crate name cannot be empty"
+    );
+    assert!(json.get("result").is_none());
+}
@@ -0,0 +1,119 @@
+//! Tests for per-tool call metrics and the `get_tool_metrics` tool
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use db::DatabasePool;
+use mcp::handlers::McpHandler;
+use mcp::metrics::{metrics, LATENCY_BUCKETS_MS};
+use mcp::tools::Tool;
+use serde_json::{json, Value};
+use sqlx::postgres::PgPoolOptions;
+use std::time::Duration;
+
+/// Tool that always fails
+struct FailingTool;
+
+#[async_trait]
+impl Tool for FailingTool {
+    fn definition(&self) -> Value {
+        json!({"name": "failing_metrics_query", "description": "Always fails", "inputSchema": {}})
+    }
+
+    async fn execute(&self, _arguments: Value) -> Result<String> {
+        Err(anyhow!("upstream unavailable"))
+    }
+}
+
+/// Tool that takes a fixed time to answer
+struct SlowTool(Duration);
+
+#[async_trait]
+impl Tool for SlowTool {
+    fn definition(&self) -> Value {
+        json!({"name": "slow_metrics_query", "description": "Sleeps", "inputSchema": {}})
+    }
+
+    async fn execute(&self, _arguments: Value) -> Result<String> {
+        tokio::time::sleep(self.0).await;
+        Ok("done".to_string())
+    }
+}
+
+fn create_handler() -> McpHandler {
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_millis(200))
+        .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+        .expect("lazy pool");
+    let mut handler =
+        McpHandler::new(&DatabasePool::from_pool(pool)).expect("handler should build");
+    handler.register_tool("failing_metrics_query", Box::new(FailingTool));
+    handler.register_tool(
+        "slow_metrics_query",
+        Box::new(SlowTool(Duration::from_millis(60))),
+    );
+    handler
+}
+
+async fn call(handler: &McpHandler, name: &str, arguments: Value) -> Value {
+    handler
+        .handle_request(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": name, "arguments": arguments}
+        }))
+        .await
+        .expect("tool call should produce a result")
+}
+
+fn tool_stats(name: &str) -> mcp::metrics::ToolMetricsSnapshot {
+    metrics()
+        .tool_metrics()
+        .into_iter()
+        .find(|t| t.tool == name)
+        .expect("tool should have metrics")
+}
+
+#[tokio::test]
+async fn test_failing_tool_increments_error_counter() {
+    let handler = create_handler();
+
+    let result = call(&handler, "failing_metrics_query", json!({})).await;
+    assert_eq!(result["isError"], true);
+    call(&handler, "failing_metrics_query", json!({})).await;
+
+    let stats = tool_stats("failing_metrics_query");
+    assert_eq!(stats.calls, 2);
+    assert_eq!(stats.errors, 2);
+    assert_eq!(stats.last_error.as_deref(), Some("upstream unavailable"));
+    assert!(stats.last_error_at.is_some());
+}
+
+#[tokio::test]
+async fn test_slow_tool_lands_in_latency_bucket() {
+    let handler = create_handler();
+
+    call(&handler, "slow_metrics_query", json!({})).await;
+
+    let stats = tool_stats("slow_metrics_query");
+    assert_eq!(stats.calls, 1);
+    assert_eq!(stats.errors, 0);
+    // 60ms falls in the 50-100ms bucket
+    let index = LATENCY_BUCKETS_MS.iter().position(|&le| le == 100).unwrap();
+    assert_eq!(stats.latency_buckets[index].count, 1);
+    // Percentiles report the bucket bound, capped at the slowest observed call
+    assert!((60..=100).contains(&stats.p50_ms));
+
+    // The same numbers are available in-band
+    let result = call(
+        &handler,
+        "get_tool_metrics",
+        json!({"tool": "slow_metrics_query"}),
+    )
+    .await;
+    let text = result["content"][0]["text"].as_str().unwrap();
+    let report: Value = serde_json::from_str(text).unwrap();
+    assert_eq!(report["tools"].as_array().unwrap().len(), 1);
+    assert_eq!(report["tools"][0]["calls"], 1);
+    assert_eq!(report["tools"][0]["p50_ms"], stats.p50_ms);
+}
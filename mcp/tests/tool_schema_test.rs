@@ -0,0 +1,240 @@
+//! Tool arguments are checked against each tool's `inputSchema` before execution
+//!
+//! The handler runs over a lazy pool pointing at an unreachable database, so
+//! rejected calls prove validation happened before any storage access.
+
+mod common;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::{
+    body::{to_bytes, Body},
+    http::{Method, Request, StatusCode},
+};
+use common::lazy_pool;
+use mcp::config::ConfigLoader;
+use mcp::handlers::McpHandler;
+use mcp::tool_schema::{InvalidToolArguments, ToolSchema};
+use mcp::tools::Tool;
+use mcp::{headers::SUPPORTED_PROTOCOL_VERSION, McpServer};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+/// Crate management tools, which reject unknown properties
+const TOOLS_CONFIG: &str = r#"{"tools": [
+    {"name": "add_rust_crate", "docType": "rust", "title": "Add Rust Crate",
+     "description": "Add a crate.", "enabled": true},
+    {"name": "remove_rust_crate", "docType": "rust", "title": "Remove Rust Crate",
+     "description": "Remove a crate.", "enabled": true},
+    {"name": "list_rust_crates", "docType": "rust", "title": "List Rust Crates",
+     "description": "List crates.", "enabled": true},
+    {"name": "solana_query", "docType": "solana", "title": "Solana Query",
+     "description": "Search Solana docs.", "enabled": true}
+]}"#;
+
+/// Tool whose schema is not a valid JSON Schema
+struct BrokenSchemaTool;
+
+#[async_trait]
+impl Tool for BrokenSchemaTool {
+    fn definition(&self) -> Value {
+        json!({
+            "name": "broken_schema_query",
+            "description": "Declares an impossible type",
+            "inputSchema": {"type": "object", "properties": {"x": {"type": "widget"}}}
+        })
+    }
+
+    async fn execute(&self, _arguments: Value) -> Result<String> {
+        Ok("unreachable".to_string())
+    }
+}
+
+fn create_handler() -> McpHandler {
+    let config = ConfigLoader::parse(TOOLS_CONFIG, "tools.json", false).expect("valid config");
+    McpHandler::with_tools_config(&lazy_pool(), Some(&config)).expect("handler")
+}
+
+/// Call `name` and return the validation failure, if any
+async fn invalid_arguments(
+    handler: &McpHandler,
+    name: &str,
+    arguments: Value,
+) -> Option<InvalidToolArguments> {
+    let error = handler
+        .handle_request(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": name, "arguments": arguments}
+        }))
+        .await
+        .err()?;
+    let invalid = error.downcast_ref::<InvalidToolArguments>()?;
+    Some(InvalidToolArguments {
+        tool: invalid.tool.clone(),
+        violations: invalid.violations.clone(),
+    })
+}
+
+#[tokio::test]
+async fn test_missing_required_field() {
+    let handler = create_handler();
+    let invalid = invalid_arguments(&handler, "add_rust_crate", json!({"version": "1.0.0"}))
+        .await
+        .expect("missing name must be rejected");
+    assert_eq!(invalid.tool, "add_rust_crate");
+    assert_eq!(invalid.violations.len(), 1);
+    assert_eq!(invalid.violations[0].path, "/name");
+    assert_eq!(invalid.violations[0].expected, "required property");
+}
+
+#[tokio::test]
+async fn test_wrong_types_are_all_listed() {
+    let handler = create_handler();
+    let invalid = invalid_arguments(
+        &handler,
+        "list_rust_crates",
+        json!({"page": "two", "include_stats": "yes", "limit": 500}),
+    )
+    .await
+    .expect("wrong types must be rejected");
+
+    let mut paths: Vec<&str> = invalid.violations.iter().map(|v| v.path.as_str()).collect();
+    paths.sort_unstable();
+    assert_eq!(paths, ["/include_stats", "/limit", "/page"]);
+    let page = invalid
+        .violations
+        .iter()
+        .find(|v| v.path == "/page")
+        .unwrap();
+    assert_eq!(page.expected, "integer");
+    assert_eq!(page.actual, json!("two"));
+    let stats = invalid
+        .violations
+        .iter()
+        .find(|v| v.path == "/include_stats")
+        .unwrap();
+    assert_eq!(stats.expected, "boolean");
+    let limit = invalid
+        .violations
+        .iter()
+        .find(|v| v.path == "/limit")
+        .unwrap();
+    assert_eq!(limit.expected, "maximum");
+    assert_eq!(limit.actual, json!(500));
+}
+
+#[tokio::test]
+async fn test_status_filter_enum_violation() {
+    let handler = create_handler();
+    let invalid = invalid_arguments(
+        &handler,
+        "list_rust_crates",
+        json!({"status_filter": "deleted"}),
+    )
+    .await
+    .expect("unknown status must be rejected");
+    assert_eq!(invalid.violations.len(), 1);
+    let violation = &invalid.violations[0];
+    assert_eq!(violation.path, "/status_filter");
+    assert_eq!(violation.actual, json!("deleted"));
+    assert!(violation.expected.starts_with("one of"), "{violation:?}");
+    assert!(violation.expected.contains("\"failed\""), "{violation:?}");
+}
+
+#[tokio::test]
+async fn test_invalid_date_filter_is_rejected() {
+    let handler = create_handler();
+    let invalid = invalid_arguments(
+        &handler,
+        "solana_query",
+        json!({"query": "accounts", "created_after": "last week"}),
+    )
+    .await
+    .expect("unparseable dates must be rejected");
+    assert_eq!(invalid.violations.len(), 1);
+    assert_eq!(invalid.violations[0].path, "/created_after");
+    assert_eq!(invalid.violations[0].expected, "date-time");
+    assert_eq!(invalid.violations[0].actual, json!("last week"));
+}
+
+#[tokio::test]
+async fn test_unknown_properties_on_management_tools() {
+    let handler = create_handler();
+    let invalid = invalid_arguments(
+        &handler,
+        "remove_rust_crate",
+        json!({"name": "serde", "crate_nmae": "serde"}),
+    )
+    .await
+    .expect("typo must be rejected");
+    assert_eq!(invalid.violations.len(), 1);
+    assert_eq!(invalid.violations[0].path, "/crate_nmae");
+
+    // The crate_name alias stays accepted on its own
+    assert!(invalid_arguments(
+        &handler,
+        "remove_rust_crate",
+        json!({"crate_name": "serde"})
+    )
+    .await
+    .is_none());
+    assert!(
+        invalid_arguments(&handler, "remove_rust_crate", json!({"dry_run": true}))
+            .await
+            .is_some()
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_params_error_over_http() {
+    std::env::set_var("TOOLS_CONFIG", TOOLS_CONFIG);
+    let app = McpServer::new(lazy_pool())
+        .await
+        .expect("server should start without a database")
+        .create_router();
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 9,
+        "method": "tools/call",
+        "params": {"name": "list_rust_crates", "arguments": {"status_filter": 3}}
+    });
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/mcp")
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .header("MCP-Protocol-Version", SUPPORTED_PROTOCOL_VERSION)
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(json["id"], 9);
+    assert_eq!(json["error"]["code"], -32602);
+    assert_eq!(json["error"]["data"]["tool"], "list_rust_crates");
+    let violations = json["error"]["data"]["violations"].as_array().unwrap();
+    assert!(violations
+        .iter()
+        .any(|v| v["path"] == "/status_filter" && v["expected"] == "string" && v["actual"] == 3));
+}
+
+#[tokio::test]
+async fn test_schema_compile_failure_names_the_tool() {
+    let error = ToolSchema::compile("broken_schema_query", &BrokenSchemaTool.definition())
+        .err()
+        .expect("invalid schema must not compile");
+    assert!(
+        error.to_string().contains("'broken_schema_query'"),
+        "{error}"
+    );
+
+    let mut handler = McpHandler::new(&lazy_pool()).expect("handler");
+    let registered = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        handler.register_tool("broken_schema_query", Box::new(BrokenSchemaTool));
+    }));
+    assert!(registered.is_err());
+}
@@ -0,0 +1,226 @@
+//! Runtime tool enable/disable through the admin route and `set_tool_enabled`
+//!
+//! The server runs over a lazy pool pointing at an unreachable database and
+//! every change uses `persist: false`, so no storage is touched; the
+//! persistence test skips when no database is configured.
+
+mod common;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use common::{create_test_pool, lazy_pool};
+use db::ToolSettingQueries;
+use mcp::handlers::McpHandler;
+use mcp::{headers::SUPPORTED_PROTOCOL_VERSION, McpServer};
+use serde_json::{json, Value};
+use std::env;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use tower::ServiceExt;
+
+const ADMIN_TOKEN: &str = "switch-admin-token";
+
+/// Read from `stream` until `needle` appears or the stream closes
+async fn read_until(stream: &mut TcpStream, received: &mut String, needle: &str) -> bool {
+    let mut buf = [0u8; 4096];
+    while !received.contains(needle) {
+        match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return false,
+            Ok(n) => received.push_str(&String::from_utf8_lossy(&buf[..n])),
+        }
+    }
+    true
+}
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn rpc(method: &str, params: Value, token: Option<&str>) -> Request<Body> {
+    let mut builder = Request::builder()
+        .method(Method::POST)
+        .uri("/mcp")
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .header("MCP-Protocol-Version", SUPPORTED_PROTOCOL_VERSION);
+    if let Some(token) = token {
+        builder = builder.header(header::AUTHORIZATION, format!("Bearer {token}"));
+    }
+    let body = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+    builder.body(Body::from(body.to_string())).unwrap()
+}
+
+fn switch(name: &str, enabled: bool, token: Option<&str>) -> Request<Body> {
+    let mut builder = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/admin/tools/{name}"))
+        .header("Content-Type", "application/json");
+    if let Some(token) = token {
+        builder = builder.header(header::AUTHORIZATION, format!("Bearer {token}"));
+    }
+    let body = json!({"enabled": enabled, "persist": false});
+    builder.body(Body::from(body.to_string())).unwrap()
+}
+
+async fn listed(app: &Router, name: &str) -> bool {
+    let (status, body) = send(app, rpc("tools/list", json!({}), None)).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    body["result"]["tools"]
+        .as_array()
+        .expect("tools array")
+        .iter()
+        .any(|t| t["name"] == name)
+}
+
+#[tokio::test]
+async fn test_disable_and_reenable_tool_at_runtime() {
+    env::set_var("MCP_ADMIN_TOKEN", ADMIN_TOKEN);
+    let server = McpServer::new(lazy_pool())
+        .await
+        .expect("server should start without a database");
+    let app = server.create_router();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (trigger, signal) = oneshot::channel::<()>();
+    let serving = tokio::spawn(async move {
+        server
+            .serve_with_shutdown(listener, async {
+                let _ = signal.await;
+            })
+            .await
+    });
+
+    // Open an SSE stream to watch for list change notifications
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let request = format!(
+        "GET /mcp HTTP/1.1\r\nHost: {addr}\r\nAccept: text/event-stream\r\n\
+         MCP-Protocol-Version: {SUPPORTED_PROTOCOL_VERSION}\r\n\r\n"
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut received = String::new();
+    let opened = tokio::time::timeout(
+        Duration::from_secs(5),
+        read_until(&mut stream, &mut received, "notifications/initialized"),
+    )
+    .await
+    .expect("SSE stream should open");
+    assert!(opened, "unexpected response: {received}");
+
+    // The admin route needs the admin token
+    let (status, _) = send(&app, switch("get_document", false, None)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(&app, switch("get_document", false, Some("wrong"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(&app, switch("no_such_tool", false, Some(ADMIN_TOKEN))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(listed(&app, "get_document").await);
+
+    // Disable: omitted from tools/list, calls rejected, SSE notified
+    let (status, body) = send(&app, switch("get_document", false, Some(ADMIN_TOKEN))).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    assert_eq!(body["changed"], true);
+    assert!(!listed(&app, "get_document").await);
+
+    let (status, body) = send(
+        &app,
+        rpc(
+            "tools/call",
+            json!({"name": "get_document", "arguments": {"doc_path": "a.md"}}),
+            None,
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["error"]["code"], -32601, "{body}");
+    assert!(body["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("administratively disabled"));
+
+    let notified = tokio::time::timeout(
+        Duration::from_secs(5),
+        read_until(
+            &mut stream,
+            &mut received,
+            "notifications/tools/list_changed",
+        ),
+    )
+    .await
+    .expect("list change notification should arrive");
+    assert!(notified, "missing notification: {received}");
+
+    // The admin tool rejects callers without the admin token
+    let arguments = json!({"name": "get_document", "enabled": true, "persist": false});
+    let (_, body) = send(
+        &app,
+        rpc(
+            "tools/call",
+            json!({"name": "set_tool_enabled", "arguments": arguments}),
+            None,
+        ),
+    )
+    .await;
+    assert_eq!(body["result"]["isError"], true, "{body}");
+    assert!(!listed(&app, "get_document").await);
+
+    // Re-enable through the tool with the admin token
+    let (_, body) = send(
+        &app,
+        rpc(
+            "tools/call",
+            json!({"name": "set_tool_enabled", "arguments": arguments}),
+            Some(ADMIN_TOKEN),
+        ),
+    )
+    .await;
+    assert!(body["result"]["isError"] != true, "{body}");
+    assert!(listed(&app, "get_document").await);
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/admin/tools")
+        .header(header::AUTHORIZATION, format!("Bearer {ADMIN_TOKEN}"))
+        .body(Body::empty())
+        .unwrap();
+    let (status, body) = send(&app, request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["tools"]["get_document"], true);
+    assert_eq!(body["tools"]["set_tool_enabled"], true);
+
+    trigger.send(()).unwrap();
+    let _ = tokio::time::timeout(Duration::from_secs(5), serving).await;
+}
+
+#[tokio::test]
+async fn test_stored_settings_apply_on_load() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping tool settings persistence test: no database configured");
+        return;
+    };
+
+    let handler = McpHandler::with_tools_config(&pool, None).expect("handler");
+    let switches = handler.tool_switches();
+    switches
+        .set("execute_ingest_plan", false, true, "test")
+        .await
+        .expect("persist setting");
+
+    // A fresh handler picks the stored setting up
+    let restarted = McpHandler::with_tools_config(&pool, None).expect("handler");
+    assert!(restarted.tool_switches().is_enabled("execute_ingest_plan"));
+    assert!(restarted.tool_switches().load().await.expect("load") >= 1);
+    assert!(!restarted.tool_switches().is_enabled("execute_ingest_plan"));
+
+    ToolSettingQueries::set_enabled(pool.pool(), "execute_ingest_plan", true, None)
+        .await
+        .expect("reset setting");
+}
@@ -0,0 +1,168 @@
+//! Tool calls that run past their time budget are abandoned with a JSON-RPC error
+
+mod common;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::{
+    body::{to_bytes, Body},
+    http::{Method, Request, StatusCode},
+};
+use common::{lazy_pool, lazy_pool_at};
+use mcp::handlers::McpHandler;
+use mcp::metrics::metrics;
+use mcp::tool_timeouts::{ToolTimeouts, DEFAULT_QUERY_TIMEOUT, DEFAULT_STATUS_TIMEOUT};
+use mcp::tools::{Tool, ToolTimedOut};
+use mcp::{headers::SUPPORTED_PROTOCOL_VERSION, McpServer};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tower::ServiceExt;
+
+/// Tool that sleeps far past any test budget, noting when its future is dropped
+struct SleepyTool {
+    dropped: Arc<AtomicBool>,
+}
+
+/// Sets the flag when the tool future is dropped before finishing
+struct DropFlag(Arc<AtomicBool>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+#[async_trait]
+impl Tool for SleepyTool {
+    fn definition(&self) -> Value {
+        json!({"name": "sleepy_query", "description": "Never answers in time", "inputSchema": {}})
+    }
+
+    async fn execute(&self, _arguments: Value) -> Result<String> {
+        let flag = DropFlag(Arc::clone(&self.dropped));
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        std::mem::forget(flag);
+        Ok("too late".to_string())
+    }
+}
+
+#[test]
+fn test_default_budgets_by_kind_of_tool() {
+    let timeouts = ToolTimeouts::default();
+    assert_eq!(timeouts.budget("rust_query"), DEFAULT_QUERY_TIMEOUT);
+    assert_eq!(timeouts.budget("check_rust_status"), DEFAULT_STATUS_TIMEOUT);
+    assert!(timeouts.budget("add_rust_crate") > DEFAULT_STATUS_TIMEOUT);
+    assert!(timeouts.http_timeout() > timeouts.budget("add_rust_crate"));
+}
+
+#[tokio::test]
+async fn test_tool_past_its_budget_times_out() {
+    let dropped = Arc::new(AtomicBool::new(false));
+    let mut handler = McpHandler::new(&lazy_pool())
+        .expect("handler")
+        .with_tool_timeout("sleepy_query", Duration::from_millis(100));
+    handler.register_tool(
+        "sleepy_query",
+        Box::new(SleepyTool {
+            dropped: Arc::clone(&dropped),
+        }),
+    );
+    let timeouts_before = metrics().snapshot().tool_timeouts;
+
+    let started = Instant::now();
+    let error = handler
+        .handle_request(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {"name": "sleepy_query", "arguments": {}}
+        }))
+        .await
+        .expect_err("the call must time out");
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    let timed_out = error.downcast_ref::<ToolTimedOut>().expect("ToolTimedOut");
+    assert_eq!(timed_out.tool, "sleepy_query");
+    assert_eq!(timed_out.budget, Duration::from_millis(100));
+    assert_eq!(
+        error.to_string(),
+        "Tool 'sleepy_query' timed out after 0.1s"
+    );
+
+    // The tool future was dropped rather than left running
+    assert!(dropped.load(Ordering::SeqCst));
+    assert!(metrics().snapshot().tool_timeouts > timeouts_before);
+    let stats = metrics()
+        .tool_metrics()
+        .into_iter()
+        .find(|t| t.tool == "sleepy_query")
+        .expect("tool metrics");
+    assert_eq!(stats.timeouts, 1);
+    assert_eq!(stats.errors, 1);
+}
+
+#[tokio::test]
+async fn test_hung_database_call_returns_timeout_error_over_http() {
+    // A "database" that refuses connections during startup, then accepts
+    // them and never answers
+    let blackhole = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = blackhole.local_addr().unwrap();
+    let hang = Arc::new(AtomicBool::new(false));
+    let _accepting = tokio::spawn({
+        let hang = Arc::clone(&hang);
+        async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = blackhole.accept().await {
+                if hang.load(Ordering::SeqCst) {
+                    held.push(socket);
+                }
+            }
+        }
+    });
+
+    std::env::set_var("TOOL_TIMEOUTS", "list_doc_sources=1");
+    let app = McpServer::new(lazy_pool_at(
+        &format!("postgres://nobody@{addr}/none"),
+        Duration::from_secs(60),
+    ))
+    .await
+    .expect("server should start without a database")
+    .create_router();
+    hang.store(true, Ordering::SeqCst);
+
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 4,
+        "method": "tools/call",
+        "params": {"name": "list_doc_sources", "arguments": {}}
+    });
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/mcp")
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .header("MCP-Protocol-Version", SUPPORTED_PROTOCOL_VERSION)
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let started = Instant::now();
+    let response = tokio::time::timeout(Duration::from_secs(10), app.oneshot(request))
+        .await
+        .expect("the budget must end the request")
+        .unwrap();
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["id"], 4);
+    assert_eq!(json["error"]["code"], -32001);
+    assert_eq!(
+        json["error"]["message"],
+        "Tool 'list_doc_sources' timed out after 1s"
+    );
+    assert_eq!(json["error"]["data"]["tool"], "list_doc_sources");
+    assert_eq!(json["error"]["data"]["budget_secs"], 1.0);
+}
@@ -0,0 +1,250 @@
+//! Config-driven query tool registration
+//!
+//! Registration tests use a lazily connected pool and need no database;
+//! the empty doc type test skips when no database is configured.
+
+mod common;
+
+use common::{create_test_pool, lazy_pool, FixedEmbeddingClient};
+use mcp::config::ConfigLoader;
+use mcp::handlers::McpHandler;
+use mcp::tools::{DynamicQueryTool, Tool};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::env;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const VALID_CONFIG: &str = r#"{
+  "tools": [
+    {
+      "name": "widgets_query",
+      "docType": "widgets",
+      "title": "Widgets Documentation",
+      "description": "Search widget guides.",
+      "enabled": true,
+      "filters": ["category", "topic"],
+      "defaultLimit": 8
+    },
+    {
+      "name": "gadgets_query",
+      "docType": "gadgets",
+      "title": "Gadgets Documentation",
+      "description": "Search gadget references.",
+      "enabled": true
+    },
+    {
+      "name": "retired_query",
+      "docType": "retired",
+      "title": "Retired Documentation",
+      "description": "No longer served.",
+      "enabled": false
+    }
+  ]
+}"#;
+
+async fn list_tools(handler: &McpHandler) -> Vec<Value> {
+    let response = handler
+        .handle_request(json!({"method": "tools/list", "params": {}}))
+        .await
+        .expect("tools/list should succeed");
+    response["tools"].as_array().expect("tools array").clone()
+}
+
+#[tokio::test]
+async fn test_valid_config_registers_exactly_the_configured_query_tools() {
+    let config = ConfigLoader::parse(VALID_CONFIG, "tools.json", false).expect("valid config");
+    let handler = McpHandler::with_tools_config(&lazy_pool(), Some(&config)).expect("handler");
+    let tools = list_tools(&handler).await;
+
+    let query_tools: BTreeSet<&str> = tools
+        .iter()
+        .filter_map(|t| t["name"].as_str())
+        .filter(|name| name.ends_with("_query"))
+        .collect();
+    assert_eq!(
+        query_tools,
+        BTreeSet::from(["gadgets_query", "rust_query", "widgets_query"])
+    );
+    // Built-in tools are still registered
+    assert!(tools.iter().any(|t| t["name"] == "get_document"));
+
+    // Configured filters and default limit shape the schema
+    let widgets = tools.iter().find(|t| t["name"] == "widgets_query").unwrap();
+    assert_eq!(widgets["description"], "Search widget guides.");
+    let properties = widgets["inputSchema"]["properties"].as_object().unwrap();
+    assert!(properties.contains_key("category"));
+    assert!(properties.contains_key("topic"));
+    assert!(!properties.contains_key("complexity"));
+    assert!(!properties.contains_key("api_version"));
+    assert!(properties["limit"]["description"]
+        .as_str()
+        .unwrap()
+        .contains("default: 8"));
+
+    // Without `filters` every filter is offered
+    let gadgets = tools.iter().find(|t| t["name"] == "gadgets_query").unwrap();
+    for filter in ["category", "topic", "complexity", "api_version"] {
+        assert!(
+            gadgets["inputSchema"]["properties"][filter].is_object(),
+            "missing {filter} filter"
+        );
+    }
+}
+
+#[test]
+fn test_toml_config_matches_json() {
+    let toml = r#"
+        [[tools]]
+        name = "widgets_query"
+        docType = "widgets"
+        title = "Widgets Documentation"
+        description = "Search widget guides."
+        enabled = true
+        filters = ["category", "topic"]
+        defaultLimit = 8
+    "#;
+    let config = ConfigLoader::parse(toml, "tools.toml", true).expect("valid TOML config");
+    assert_eq!(config.tools.len(), 1);
+    assert_eq!(
+        config.tools[0].filters.as_deref(),
+        Some(&["category".to_string(), "topic".to_string()][..])
+    );
+    assert_eq!(config.tools[0].default_limit, Some(8));
+
+    // Files are parsed by extension
+    let path = env::temp_dir().join(format!("tools-{}.toml", Uuid::new_v4()));
+    std::fs::write(&path, toml).unwrap();
+    let loaded = ConfigLoader::load_from_file(&path);
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(loaded.expect("TOML file").tools[0].name, "widgets_query");
+}
+
+#[test]
+fn test_duplicate_names_fail_with_path_and_index() {
+    let config = json!({
+        "tools": [
+            {"name": "widgets_query", "docType": "widgets", "title": "Widgets",
+             "description": "Widget guides.", "enabled": true},
+            {"name": "gadgets_query", "docType": "gadgets", "title": "Gadgets",
+             "description": "Gadget guides.", "enabled": true},
+            {"name": "widgets_query", "docType": "widgets-v2", "title": "Widgets v2",
+             "description": "Newer widget guides.", "enabled": false}
+        ]
+    });
+    let path = env::temp_dir().join(format!("tools-{}.json", Uuid::new_v4()));
+    std::fs::write(&path, config.to_string()).unwrap();
+    let error = ConfigLoader::load_from_file(&path)
+        .expect_err("duplicate names must be rejected")
+        .to_string();
+    let _ = std::fs::remove_file(&path);
+
+    assert!(error.starts_with(&path.display().to_string()), "{error}");
+    assert!(error.contains("tools[2]"), "{error}");
+    assert!(
+        error.contains("Duplicate tool name 'widgets_query' (first defined at tools[0])"),
+        "{error}"
+    );
+}
+
+#[tokio::test]
+async fn test_invalid_entries_name_the_entry() {
+    let unknown_filter = r#"{"tools": [
+        {"name": "widgets_query", "docType": "widgets", "title": "Widgets",
+         "description": "Widget guides.", "enabled": true, "filters": ["topic", "colour"]}
+    ]}"#;
+    let error = ConfigLoader::parse(unknown_filter, "tools.json", false)
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.starts_with("tools.json: tools[0] ('widgets_query'): Unknown filter key 'colour'"),
+        "{error}"
+    );
+
+    let bad_limit = r#"{"tools": [
+        {"name": "widgets_query", "docType": "widgets", "title": "Widgets",
+         "description": "Widget guides.", "enabled": true},
+        {"name": "gadgets_query", "docType": "gadgets", "title": "Gadgets",
+         "description": "Gadget guides.", "enabled": true, "defaultLimit": 50}
+    ]}"#;
+    let error = ConfigLoader::parse(bad_limit, "tools.json", false)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("tools[1] ('gadgets_query')"), "{error}");
+    assert!(
+        error.contains("defaultLimit must be between 1 and 20"),
+        "{error}"
+    );
+
+    let zero_timeout = r#"{"tools": [
+        {"name": "widgets_query", "docType": "widgets", "title": "Widgets",
+         "description": "Widget guides.", "enabled": true, "timeoutSecs": 0}
+    ]}"#;
+    assert!(ConfigLoader::parse(zero_timeout, "tools.json", false)
+        .unwrap_err()
+        .to_string()
+        .contains("timeoutSecs must be between 1 and 3600"));
+
+    let management_filters = r#"{"tools": [
+        {"name": "add_rust_crate", "docType": "rust", "title": "Add",
+         "description": "Add a crate.", "enabled": true, "filters": ["topic"]}
+    ]}"#;
+    assert!(ConfigLoader::parse(management_filters, "tools.json", false)
+        .unwrap_err()
+        .to_string()
+        .contains("only supported on query tools"));
+
+    // Handlers refuse an invalid configuration instead of skipping entries
+    let config: db::models::ToolsConfig = serde_json::from_str(unknown_filter).unwrap();
+    assert!(McpHandler::with_tools_config(&lazy_pool(), Some(&config)).is_err());
+}
+
+#[tokio::test]
+async fn test_configured_tool_for_doc_type_without_documents() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping empty doc type test: no database configured");
+        return;
+    };
+    let doc_type = format!("empty{}", &Uuid::new_v4().simple().to_string()[..8]);
+    let config = ConfigLoader::parse(
+        &json!({"tools": [{
+            "name": format!("{doc_type}_query"),
+            "docType": doc_type,
+            "title": "Empty Documentation",
+            "description": "Nothing ingested yet.",
+            "enabled": true,
+            "filters": ["topic"],
+            "defaultLimit": 3
+        }]})
+        .to_string(),
+        "tools.json",
+        false,
+    )
+    .expect("valid config");
+    let tool = DynamicQueryTool::with_embedding_client(
+        config.tools[0].clone(),
+        pool,
+        Arc::new(FixedEmbeddingClient),
+    );
+
+    let response = tool
+        .execute(json!({"query": "how do I get started?"}))
+        .await
+        .expect("an empty doc type is not an error");
+    assert!(
+        response.contains("No relevant Empty Documentation documentation found"),
+        "{response}"
+    );
+
+    let filtered = tool
+        .execute(json!({"query": "networking", "topic": "setup"}))
+        .await
+        .expect("enabled filter");
+    assert!(filtered.contains("No relevant"), "{filtered}");
+
+    let error = tool
+        .execute(json!({"query": "networking", "category": "guides"}))
+        .await
+        .expect_err("category is not enabled");
+    assert!(error.to_string().contains("'category' is not enabled"));
+}
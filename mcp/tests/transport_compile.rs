@@ -14,6 +14,7 @@ fn test_transport_config_compiles() {
         session_timeout: Duration::from_secs(300),
         heartbeat_interval: Duration::from_secs(30),
         max_json_body_bytes: 2 * 1024 * 1024,
+        ..Default::default()
     };
 
     assert_eq!(config.protocol_version, "2025-06-18");
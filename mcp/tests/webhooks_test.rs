@@ -0,0 +1,335 @@
+//! Webhook delivery against a local receiver: signatures, retries, and
+//! notifications of finished jobs
+//!
+//! The notification test needs the `webhooks` tables and skips when no
+//! database is configured.
+
+mod common;
+
+use db::{CrateStorage, DatabasePool, WebhookQueries};
+use mcp::job_queue::{CrateJobProcessor, RemoveJobOptions};
+use mcp::webhooks::{deliver, sign, RetryPolicy, WebhookEvent, WebhookTarget};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const SECRET: &str = "webhook-test-secret";
+
+/// Request seen by the receiver
+#[derive(Debug, Clone)]
+struct Received {
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl Received {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Local HTTP receiver answering with scripted status codes
+///
+/// Once the script runs out every request gets 200. A status of 0 never
+/// answers, so the request times out.
+struct Receiver {
+    url: String,
+    received: Arc<Mutex<Vec<Received>>>,
+}
+
+impl Receiver {
+    async fn start(script: &[u16]) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let script = Arc::new(Mutex::new(script.iter().copied().collect::<VecDeque<_>>()));
+
+        let log = received.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let log = log.clone();
+                let script = script.clone();
+                tokio::spawn(async move { serve(stream, &log, &script).await });
+            }
+        });
+        Self { url, received }
+    }
+
+    fn requests(&self) -> Vec<Received> {
+        self.received.lock().unwrap().clone()
+    }
+
+    fn target(&self, events: &[&str]) -> WebhookTarget {
+        WebhookTarget {
+            webhook_id: None,
+            url: self.url.clone(),
+            secret: SECRET.to_string(),
+            events: events.iter().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+async fn serve(mut stream: TcpStream, log: &Mutex<Vec<Received>>, script: &Mutex<VecDeque<u16>>) {
+    let mut raw = Vec::new();
+    let mut buf = [0u8; 4096];
+    let header_end = loop {
+        match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => raw.extend_from_slice(&buf[..n]),
+        }
+        if let Some(pos) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+    let head = String::from_utf8_lossy(&raw[..header_end]).into_owned();
+    let headers: Vec<(String, String)> = head
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+    let length: usize = headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(0);
+    while raw.len() < header_end + length {
+        match stream.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => raw.extend_from_slice(&buf[..n]),
+        }
+    }
+    let body = String::from_utf8_lossy(&raw[header_end..header_end + length]).into_owned();
+    log.lock().unwrap().push(Received { headers, body });
+
+    let status = script.lock().unwrap().pop_front().unwrap_or(200);
+    if status == 0 {
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        return;
+    }
+    let response =
+        format!("HTTP/1.1 {status} Scripted\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+fn fast_policy(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        base_delay: Duration::from_millis(10),
+        timeout: Duration::from_millis(300),
+    }
+}
+
+fn assert_signed(request: &Received) {
+    assert_eq!(
+        request.header("X-Signature"),
+        Some(sign(SECRET, request.body.as_bytes()).as_str()),
+        "signature of {}",
+        request.body
+    );
+}
+
+#[tokio::test]
+async fn test_delivery_is_signed_and_retried_on_server_errors() {
+    let receiver = Receiver::start(&[500, 503]).await;
+    let payload = json!({"event": "job_completed", "job": {"id": "abc"}});
+
+    let outcome = deliver(
+        &receiver.target(&[]),
+        WebhookEvent::JobCompleted,
+        &payload,
+        &fast_policy(5),
+    )
+    .await;
+
+    assert!(outcome.delivered, "{outcome:?}");
+    assert_eq!(outcome.attempts, 3);
+    assert_eq!(outcome.status_code, Some(200));
+    let requests = receiver.requests();
+    assert_eq!(requests.len(), 3);
+    for request in &requests {
+        assert_signed(request);
+        assert_eq!(request.header("X-Webhook-Event"), Some("job_completed"));
+        assert_eq!(
+            serde_json::from_str::<Value>(&request.body).unwrap(),
+            payload
+        );
+    }
+    // A wrong secret does not produce the same signature
+    assert_ne!(
+        requests[0].header("X-Signature"),
+        Some(sign("other-secret", requests[0].body.as_bytes()).as_str())
+    );
+}
+
+#[tokio::test]
+async fn test_delivery_gives_up_after_max_attempts() {
+    let receiver = Receiver::start(&[500, 500, 500, 500]).await;
+
+    let outcome = deliver(
+        &receiver.target(&[]),
+        WebhookEvent::JobFailed,
+        &json!({"event": "job_failed"}),
+        &fast_policy(3),
+    )
+    .await;
+
+    assert!(!outcome.delivered);
+    assert_eq!(outcome.attempts, 3);
+    assert_eq!(outcome.status_code, Some(500));
+    assert_eq!(receiver.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn test_client_errors_are_not_retried() {
+    let receiver = Receiver::start(&[404]).await;
+
+    let outcome = deliver(
+        &receiver.target(&[]),
+        WebhookEvent::JobCompleted,
+        &json!({"event": "job_completed"}),
+        &fast_policy(5),
+    )
+    .await;
+
+    assert!(!outcome.delivered);
+    assert_eq!(outcome.attempts, 1);
+    assert_eq!(outcome.status_code, Some(404));
+}
+
+#[tokio::test]
+async fn test_timeouts_are_retried() {
+    let receiver = Receiver::start(&[0]).await;
+
+    let outcome = deliver(
+        &receiver.target(&[]),
+        WebhookEvent::JobDeadLettered,
+        &json!({"event": "job_dead_lettered"}),
+        &fast_policy(3),
+    )
+    .await;
+
+    assert!(outcome.delivered, "{outcome:?}");
+    assert_eq!(outcome.attempts, 2);
+    assert_eq!(receiver.requests().len(), 2);
+}
+
+#[test]
+fn test_event_filters() {
+    let target = WebhookTarget {
+        webhook_id: None,
+        url: "http://127.0.0.1/hook".to_string(),
+        secret: SECRET.to_string(),
+        events: vec!["job_failed".to_string(), "job_dead_lettered".to_string()],
+    };
+    assert!(target.wants(WebhookEvent::JobFailed));
+    assert!(target.wants(WebhookEvent::JobDeadLettered));
+    assert!(!target.wants(WebhookEvent::JobCompleted));
+
+    let everything = WebhookTarget {
+        events: Vec::new(),
+        ..target
+    };
+    assert!(WebhookEvent::ALL
+        .into_iter()
+        .all(|event| everything.wants(event)));
+}
+
+#[tokio::test]
+async fn test_in_memory_job_transition_is_not_delayed_by_webhooks() {
+    // Without a database only the environment webhook could be notified, and
+    // none is configured here; the transition must succeed regardless
+    let processor = CrateJobProcessor::new(CrateStorage::in_memory());
+    let options = RemoveJobOptions {
+        verify_cleanup: true,
+        version: None,
+    };
+    let job = processor
+        .enqueue_remove_crate_job("webhook-memory", &options, None)
+        .await
+        .unwrap()
+        .job;
+    let finished = processor
+        .update_job_status(job.id, db::models::JobStatus::Completed, Some(100), None)
+        .await
+        .unwrap();
+    assert_eq!(finished.status, db::models::JobStatus::Completed);
+}
+
+/// Test database pool, or `None` without one or without the webhook tables
+async fn create_test_pool() -> Option<DatabasePool> {
+    let pool = common::create_test_pool().await?;
+    WebhookQueries::list(pool.pool()).await.ok()?;
+    Some(pool)
+}
+
+#[tokio::test]
+async fn test_finished_jobs_notify_subscribers() {
+    let Some(pool) = create_test_pool().await else {
+        println!("Skipping webhook notification test: no database with webhook tables");
+        return;
+    };
+    // The first delivery fails, so the logged delivery records a retry
+    let receiver = Receiver::start(&[502]).await;
+    let webhook = WebhookQueries::create(
+        pool.pool(),
+        &receiver.url,
+        SECRET,
+        &["crate_removed".to_string()],
+    )
+    .await
+    .unwrap();
+
+    let processor = CrateJobProcessor::new(pool.clone());
+    let options = RemoveJobOptions {
+        verify_cleanup: true,
+        version: None,
+    };
+    let job = processor
+        .enqueue_remove_crate_job("webhook-notify-test", &options, None)
+        .await
+        .unwrap()
+        .job;
+    processor
+        .update_job_status(job.id, db::models::JobStatus::Completed, Some(100), None)
+        .await
+        .unwrap();
+
+    let mut delivery = None;
+    for _ in 0..100 {
+        let deliveries = WebhookQueries::list_deliveries(pool.pool(), Some(webhook.id), None, 10)
+            .await
+            .unwrap();
+        delivery = deliveries.into_iter().find(|d| d.status != "pending");
+        if delivery.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    WebhookQueries::delete(pool.pool(), webhook.id)
+        .await
+        .unwrap();
+
+    let delivery = delivery.expect("delivery recorded");
+    assert_eq!(delivery.status, "delivered");
+    assert_eq!(delivery.event, "crate_removed");
+    assert_eq!(delivery.attempts, 2);
+    assert_eq!(delivery.last_status_code, Some(200));
+    assert_eq!(delivery.payload["job"]["id"], json!(job.id));
+
+    // Only the subscribed event was sent, signed, twice
+    let requests = receiver.requests();
+    assert_eq!(requests.len(), 2);
+    for request in &requests {
+        assert_signed(request);
+        let body: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["event"], "crate_removed");
+        assert_eq!(body["job"]["crate_name"], "webhook-notify-test");
+    }
+}
@@ -0,0 +1,318 @@
+//! npm package ingestion: registry metadata, README and TypeScript declarations.
+//!
+//! Pages are emitted as the same [`DocPage`]s the Rust crate loader produces
+//! and fetched through the same rate-limited [`PageFetcher`], so the
+//! ingestion pipeline stores them like crate pages under doc type
+//! [`NPM_DOC_TYPE`].
+
+use anyhow::Result;
+use chrono::Utc;
+use rust_crates::{DocPage, PageFetcher, RateLimiter};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::timeout;
+use tracing::{debug, info, warn};
+
+mod registry;
+mod typings;
+
+pub use registry::{parse_package_document, NpmPackage};
+pub use typings::{parse_declarations, Declaration};
+
+/// `doc_type` of npm package documents
+pub const NPM_DOC_TYPE: &str = "npm";
+
+/// Base URL of the npm registry API
+pub const REGISTRY_URL: &str = "https://registry.npmjs.org";
+
+/// CDN serving files of published package tarballs
+pub const UNPKG_URL: &str = "https://unpkg.com";
+
+/// Longest package name the registry accepts
+const MAX_NAME_LEN: usize = 214;
+
+/// Whether `name` is a valid npm package name, scoped (`@scope/name`) or not
+///
+/// Names are lowercase, at most 214 characters, and made of URL-safe
+/// characters; neither a name nor its scope may start with `.` or `_`.
+#[must_use]
+pub fn is_valid_package_name(name: &str) -> bool {
+    let valid_part = |part: &str| {
+        !part.is_empty()
+            && !part.starts_with(['.', '_'])
+            && part.chars().all(|c| {
+                c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '.' | '_' | '~')
+            })
+    };
+    if name.len() > MAX_NAME_LEN {
+        return false;
+    }
+    match name.strip_prefix('@') {
+        Some(scoped) => scoped
+            .split_once('/')
+            .is_some_and(|(scope, package)| valid_part(scope) && valid_part(package)),
+        None => valid_part(name),
+    }
+}
+
+/// Registry URL of a package document; the `/` of a scoped name is encoded
+#[must_use]
+pub fn registry_url(name: &str) -> String {
+    format!("{REGISTRY_URL}/{}", name.replace('/', "%2F"))
+}
+
+/// npm website page of one package version, recorded as the README's URL
+#[must_use]
+pub fn package_page_url(name: &str, version: &str) -> String {
+    format!("https://www.npmjs.com/package/{name}/v/{version}")
+}
+
+/// Outcome of asking the registry about a package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NpmLookup {
+    /// The package has the requested version
+    Found(Box<NpmPackage>),
+    /// The registry has no such package
+    NotFound,
+    /// The package exists without the requested version; the reason says which it has
+    InvalidVersion(String),
+    /// The registry failed or did not answer in time, for the given reason
+    Unavailable(String),
+}
+
+/// Loads npm packages from the registry; clones share the fetcher, and with it any rate limit
+#[derive(Clone)]
+pub struct NpmLoader {
+    fetcher: Arc<dyn PageFetcher>,
+}
+
+impl Default for NpmLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NpmLoader {
+    /// Loader fetching through a [`RateLimiter`] with the configured crawl limits
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_fetcher(Box::new(RateLimiter::new()))
+    }
+
+    /// Loader fetching every page through `fetcher`
+    #[must_use]
+    pub fn with_fetcher(fetcher: Box<dyn PageFetcher>) -> Self {
+        Self {
+            fetcher: Arc::from(fetcher),
+        }
+    }
+
+    /// Load `version` of a package (exact or a dist-tag; latest by default)
+    ///
+    /// # Errors
+    /// Returns an error if the registry cannot be fetched, the response
+    /// cannot be parsed or the package has no such version.
+    pub async fn load_package(&self, name: &str, version: Option<&str>) -> Result<NpmPackage> {
+        info!("Loading npm package: {} (version: {:?})", name, version);
+        let body = self.fetcher.fetch_text(&registry_url(name)).await?;
+        parse_package_document(&body, version)
+    }
+
+    /// Ask the registry about `version` of a package, waiting at most `within`
+    ///
+    /// A 404 gives [`NpmLookup::NotFound`]. Any other failure, including the
+    /// timeout, gives [`NpmLookup::Unavailable`], so callers can go ahead
+    /// unchecked.
+    pub async fn lookup_package(
+        &self,
+        name: &str,
+        version: Option<&str>,
+        within: Duration,
+    ) -> NpmLookup {
+        match timeout(within, self.fetcher.fetch_text(&registry_url(name))).await {
+            Ok(Ok(body)) => match parse_package_document(&body, version) {
+                Ok(package) => NpmLookup::Found(Box::new(package)),
+                Err(e) if e.to_string().starts_with("Invalid version") => {
+                    NpmLookup::InvalidVersion(e.to_string())
+                }
+                Err(e) => NpmLookup::Unavailable(format!("{e:#}")),
+            },
+            Ok(Err(e)) if format!("{e:#}").contains("HTTP status: 404") => NpmLookup::NotFound,
+            Ok(Err(e)) => NpmLookup::Unavailable(format!("{e:#}")),
+            Err(_) => NpmLookup::Unavailable(format!(
+                "the npm registry did not answer within {}s",
+                within.as_secs_f64()
+            )),
+        }
+    }
+
+    /// Documentation pages of a package: its README and one page per
+    /// declaration in its bundled typings
+    ///
+    /// Typings that cannot be fetched are skipped with a warning; the README
+    /// alone is still returned.
+    pub async fn load_package_docs(&self, package: &NpmPackage) -> Vec<DocPage> {
+        let mut pages = Vec::new();
+        if let Some(readme) = &package.readme {
+            pages.push(DocPage {
+                url: package_page_url(&package.name, &package.version),
+                content: readme.clone(),
+                item_type: "readme".to_string(),
+                module_path: format!("{}::README", package.name),
+                extracted_at: Utc::now(),
+                parent_url: None,
+            });
+        }
+
+        let Some(types) = &package.types else {
+            debug!("npm package {} publishes no typings", package.name);
+            return pages;
+        };
+        let url = format!("{UNPKG_URL}/{}@{}/{types}", package.name, package.version);
+        let source = match self.fetcher.fetch_text(&url).await {
+            Ok(source) => source,
+            Err(e) => {
+                warn!(
+                    "Failed to fetch typings of {} {}: {}",
+                    package.name, package.version, e
+                );
+                return pages;
+            }
+        };
+        pages.extend(
+            parse_declarations(&source)
+                .into_iter()
+                .map(|declaration| DocPage {
+                    url: format!("{url}#{}", declaration.name),
+                    content: declaration.markdown(),
+                    item_type: declaration.kind.to_string(),
+                    module_path: format!("{}::{}", package.name, declaration.name),
+                    extracted_at: Utc::now(),
+                    parent_url: None,
+                }),
+        );
+        pages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    /// Serves canned registry responses; other URLs fail with a 404
+    struct MockRegistry {
+        pages: HashMap<String, String>,
+    }
+
+    #[async_trait]
+    impl PageFetcher for MockRegistry {
+        async fn fetch_text(&self, url: &str) -> Result<String> {
+            self.pages
+                .get(url)
+                .cloned()
+                .ok_or_else(|| anyhow!("HTTP status: 404 Not Found"))
+        }
+    }
+
+    fn loader(pages: &[(&str, String)]) -> NpmLoader {
+        NpmLoader::with_fetcher(Box::new(MockRegistry {
+            pages: pages
+                .iter()
+                .map(|(url, body)| ((*url).to_string(), body.clone()))
+                .collect(),
+        }))
+    }
+
+    fn scoped_document() -> String {
+        json!({
+            "name": "@acme/pad",
+            "dist-tags": {"latest": "1.0.0"},
+            "readme": "# pad",
+            "versions": {"1.0.0": {"types": "dist/index.d.ts"}}
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_package_names() {
+        assert!(is_valid_package_name("left-pad"));
+        assert!(is_valid_package_name("@acme/pad.js"));
+        assert!(!is_valid_package_name("Left-Pad"));
+        assert!(!is_valid_package_name("_private"));
+        assert!(!is_valid_package_name("@acme"));
+        assert!(!is_valid_package_name("@acme/"));
+        assert!(!is_valid_package_name("a b"));
+        assert!(!is_valid_package_name(&"a".repeat(215)));
+        assert_eq!(
+            registry_url("@acme/pad"),
+            "https://registry.npmjs.org/@acme%2Fpad"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_readme_and_declaration_pages() {
+        let loader = loader(&[
+            ("https://registry.npmjs.org/@acme%2Fpad", scoped_document()),
+            (
+                "https://unpkg.com/@acme/pad@1.0.0/dist/index.d.ts",
+                "/** Pads */\nexport declare function pad(s: string): string;\nexport type Side = 'l' | 'r';\n"
+                    .to_string(),
+            ),
+        ]);
+        let package = loader.load_package("@acme/pad", None).await.unwrap();
+        let pages = loader.load_package_docs(&package).await;
+
+        let paths: Vec<(&str, &str)> = pages
+            .iter()
+            .map(|p| (p.item_type.as_str(), p.module_path.as_str()))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("readme", "@acme/pad::README"),
+                ("function", "@acme/pad::pad"),
+                ("type", "@acme/pad::Side"),
+            ]
+        );
+        assert_eq!(
+            pages[0].url,
+            "https://www.npmjs.com/package/@acme/pad/v/1.0.0"
+        );
+        assert_eq!(
+            pages[1].url,
+            "https://unpkg.com/@acme/pad@1.0.0/dist/index.d.ts#pad"
+        );
+        assert!(pages[1].content.starts_with("Pads\n\n```ts\n"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_typings_keep_the_readme() {
+        let loader = loader(&[("https://registry.npmjs.org/@acme%2Fpad", scoped_document())]);
+        let package = loader.load_package("@acme/pad", None).await.unwrap();
+        let pages = loader.load_package_docs(&package).await;
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].item_type, "readme");
+    }
+
+    #[tokio::test]
+    async fn test_lookup() {
+        let loader = loader(&[("https://registry.npmjs.org/@acme%2Fpad", scoped_document())]);
+        let within = Duration::from_secs(1);
+        assert!(matches!(
+            loader.lookup_package("@acme/pad", None, within).await,
+            NpmLookup::Found(package) if package.version == "1.0.0"
+        ));
+        assert!(matches!(
+            loader.lookup_package("@acme/pad", Some("2.0.0"), within).await,
+            NpmLookup::InvalidVersion(reason) if reason.contains("latest: 1.0.0")
+        ));
+        assert_eq!(
+            loader.lookup_package("missing", None, within).await,
+            NpmLookup::NotFound
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Package documents served by the npm registry.
+//!
+//! `GET https://registry.npmjs.org/{name}` returns every published version
+//! with its manifest, the dist-tags and the README of the latest release.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Placeholder the registry stores for packages published without a README
+const MISSING_README: &str = "ERROR: No README data found!";
+
+/// One version of an npm package, as the registry describes it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NpmPackage {
+    pub name: String,
+    /// The resolved version
+    pub version: String,
+    /// Version the `latest` dist-tag points at
+    pub latest_version: String,
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    pub repository: Option<String>,
+    pub license: Option<String>,
+    pub keywords: Vec<String>,
+    /// Path of the bundled declaration file (`types` or `typings`), when published
+    pub types: Option<String>,
+    /// Markdown README; left out of serialized metadata
+    #[serde(skip)]
+    pub readme: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PackageDocument {
+    name: String,
+    #[serde(rename = "dist-tags", default)]
+    dist_tags: HashMap<String, String>,
+    #[serde(default)]
+    versions: HashMap<String, VersionManifest>,
+    #[serde(default)]
+    readme: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct VersionManifest {
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    homepage: Option<String>,
+    #[serde(default)]
+    repository: Option<Value>,
+    #[serde(default)]
+    license: Option<Value>,
+    #[serde(default)]
+    keywords: Option<Value>,
+    #[serde(default)]
+    types: Option<String>,
+    #[serde(default)]
+    typings: Option<String>,
+    #[serde(default)]
+    readme: Option<String>,
+}
+
+/// Read `version` of the package in a registry document body
+///
+/// `version` may be an exact version or a dist-tag such as `next`; without
+/// one the `latest` dist-tag is used. The README of the version's manifest
+/// wins over the document's, which belongs to the latest release.
+///
+/// # Errors
+/// Returns an error if the body is not a registry package document, or it
+/// has no such version.
+pub fn parse_package_document(body: &str, version: Option<&str>) -> Result<NpmPackage> {
+    let document: PackageDocument =
+        serde_json::from_str(body).map_err(|e| anyhow!("Invalid npm registry response: {}", e))?;
+    let latest_version = document
+        .dist_tags
+        .get("latest")
+        .cloned()
+        .ok_or_else(|| anyhow!("npm package '{}' has no latest version", document.name))?;
+    let requested = version.map_or("latest", str::trim);
+    let resolved = document
+        .dist_tags
+        .get(requested)
+        .map_or(requested, String::as_str);
+    let Some(manifest) = document.versions.get(resolved) else {
+        return Err(anyhow!(
+            "Invalid version '{}' for npm package '{}' (latest: {})",
+            requested,
+            document.name,
+            latest_version
+        ));
+    };
+
+    let readme = [manifest.readme.as_deref(), document.readme.as_deref()]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|readme| !readme.is_empty() && *readme != MISSING_README)
+        .map(String::from);
+
+    Ok(NpmPackage {
+        name: document.name.clone(),
+        version: resolved.to_string(),
+        latest_version,
+        description: manifest.description.clone().filter(|d| !d.is_empty()),
+        homepage: manifest.homepage.clone(),
+        repository: manifest
+            .repository
+            .as_ref()
+            .and_then(|r| field_text(r, "url")),
+        license: manifest
+            .license
+            .as_ref()
+            .and_then(|l| field_text(l, "type")),
+        keywords: match &manifest.keywords {
+            Some(Value::Array(keywords)) => keywords
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect(),
+            Some(Value::String(keywords)) => keywords
+                .split([',', ' '])
+                .filter(|k| !k.is_empty())
+                .map(String::from)
+                .collect(),
+            _ => Vec::new(),
+        },
+        types: manifest
+            .types
+            .clone()
+            .or_else(|| manifest.typings.clone())
+            .filter(|path| {
+                let path = path.trim_start_matches("./");
+                !path.is_empty() && path.ends_with(".d.ts")
+            })
+            .map(|path| path.trim_start_matches("./").to_string()),
+        readme,
+    })
+}
+
+/// A manifest field given either as a string or as an object holding it under `key`
+fn field_text(value: &Value, key: &str) -> Option<String> {
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Object(object) => object.get(key).and_then(Value::as_str).map(String::from),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn document() -> String {
+        json!({
+            "name": "left-pad",
+            "dist-tags": {"latest": "1.3.0", "next": "2.0.0-rc.1"},
+            "readme": "# left-pad\n\nPads strings.",
+            "versions": {
+                "1.3.0": {
+                    "description": "String left pad",
+                    "repository": {"type": "git", "url": "git+https://github.com/left-pad/left-pad.git"},
+                    "license": "WTFPL",
+                    "keywords": ["pad", "left"],
+                    "types": "./index.d.ts"
+                },
+                "1.2.0": {
+                    "license": {"type": "MIT"},
+                    "keywords": "pad, string",
+                    "readme": "ERROR: No README data found!"
+                },
+                "2.0.0-rc.1": {"typings": "lib/main.js"}
+            }
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_latest_version_by_default() {
+        let package = parse_package_document(&document(), None).unwrap();
+        assert_eq!(package.version, "1.3.0");
+        assert_eq!(package.latest_version, "1.3.0");
+        assert_eq!(package.description.as_deref(), Some("String left pad"));
+        assert_eq!(
+            package.repository.as_deref(),
+            Some("git+https://github.com/left-pad/left-pad.git")
+        );
+        assert_eq!(package.license.as_deref(), Some("WTFPL"));
+        assert_eq!(package.keywords, vec!["pad", "left"]);
+        assert_eq!(package.types.as_deref(), Some("index.d.ts"));
+        assert_eq!(
+            package.readme.as_deref(),
+            Some("# left-pad\n\nPads strings.")
+        );
+    }
+
+    #[test]
+    fn test_exact_versions_and_dist_tags() {
+        let old = parse_package_document(&document(), Some("1.2.0")).unwrap();
+        assert_eq!(old.license.as_deref(), Some("MIT"));
+        assert_eq!(old.keywords, vec!["pad", "string"]);
+        assert_eq!(old.types, None);
+        // The placeholder is skipped for the document's README
+        assert_eq!(old.readme.as_deref(), Some("# left-pad\n\nPads strings."));
+
+        let next = parse_package_document(&document(), Some("next")).unwrap();
+        assert_eq!(next.version, "2.0.0-rc.1");
+        // Only declaration files count as typings
+        assert_eq!(next.types, None);
+    }
+
+    #[test]
+    fn test_unknown_version_and_bad_body() {
+        let error = parse_package_document(&document(), Some("9.9.9")).unwrap_err();
+        assert!(error.to_string().contains("Invalid version '9.9.9'"));
+        assert!(error.to_string().contains("latest: 1.3.0"));
+
+        let error = parse_package_document("<html>", None).unwrap_err();
+        assert!(error.to_string().contains("Invalid npm registry response"));
+    }
+}
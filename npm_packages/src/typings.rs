@@ -0,0 +1,353 @@
+//! Top-level declarations of a TypeScript declaration (`.d.ts`) file.
+//!
+//! Declaration files are read line by line, tracking brace depth: every
+//! statement starting at the top level with a declaration keyword becomes a
+//! [`Declaration`] carrying the JSDoc comment right above it. The bodies of
+//! `declare module "name" { ... }` blocks count as top level, since older
+//! typings wrap a whole package in one. Overloads of a name are merged.
+
+/// One declared item of a package's public API
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+    /// `function`, `class`, `interface`, `type`, `enum`, `constant` or `namespace`
+    pub kind: &'static str,
+    pub name: String,
+    /// JSDoc text without comment markers; empty when undocumented
+    pub docs: String,
+    /// Declaration source, one statement per overload
+    pub signature: String,
+}
+
+impl Declaration {
+    /// Markdown for the declaration: its docs followed by the source in a `ts` block
+    #[must_use]
+    pub fn markdown(&self) -> String {
+        let code = format!("```ts\n{}\n```", self.signature);
+        if self.docs.is_empty() {
+            code
+        } else {
+            format!("{}\n\n{code}", self.docs)
+        }
+    }
+}
+
+/// Declaration keywords and the `kind` recorded for them
+const KINDS: &[(&str, &str)] = &[
+    ("function", "function"),
+    ("class", "class"),
+    ("interface", "interface"),
+    ("type", "type"),
+    ("enum", "enum"),
+    ("const", "constant"),
+    ("let", "constant"),
+    ("var", "constant"),
+    ("namespace", "namespace"),
+    ("module", "namespace"),
+];
+
+/// Modifiers that may precede a declaration keyword
+const MODIFIERS: &[&str] = &["export", "default", "declare", "abstract", "async"];
+
+/// Top-level declarations of a `.d.ts` source, in order of first appearance
+#[must_use]
+pub fn parse_declarations(source: &str) -> Vec<Declaration> {
+    let mut declarations: Vec<Declaration> = Vec::new();
+    let mut depth = 0_i32;
+    // Depth of the innermost `declare module "name"` body
+    let mut module_depths: Vec<i32> = Vec::new();
+    let mut doc: Option<Vec<String>> = None;
+    let mut in_doc = false;
+    let mut current: Option<(&'static str, String, Vec<&str>)> = None;
+
+    for line in source.lines() {
+        let trimmed = line.trim();
+
+        if let Some((kind, name, mut lines)) = current.take() {
+            lines.push(line);
+            depth += brace_delta(line);
+            if depth <= top(&module_depths) && statement_ends(trimmed) {
+                depth = top(&module_depths);
+                push(&mut declarations, kind, name, doc.take(), &lines);
+            } else {
+                current = Some((kind, name, lines));
+            }
+            continue;
+        }
+
+        if in_doc {
+            if let Some(comment) = doc.as_mut() {
+                comment.push(trimmed.to_string());
+            }
+            in_doc = !trimmed.contains("*/");
+            continue;
+        }
+
+        if depth > top(&module_depths) {
+            depth += brace_delta(line);
+            continue;
+        }
+
+        if trimmed.starts_with("/**") {
+            doc = Some(vec![trimmed.to_string()]);
+            in_doc = !trimmed.contains("*/");
+            continue;
+        }
+        if trimmed == "}" && !module_depths.is_empty() {
+            module_depths.pop();
+            depth -= 1;
+            continue;
+        }
+        if is_module_block(trimmed) {
+            depth += brace_delta(line);
+            module_depths.push(depth);
+            doc = None;
+            continue;
+        }
+
+        match declaration_head(trimmed) {
+            Some((kind, name)) => {
+                depth += brace_delta(line);
+                if depth <= top(&module_depths) && statement_ends(trimmed) {
+                    depth = top(&module_depths);
+                    push(&mut declarations, kind, name, doc.take(), &[line]);
+                } else {
+                    current = Some((kind, name, vec![line]));
+                }
+            }
+            None => {
+                depth += brace_delta(line);
+                if !trimmed.is_empty() && !trimmed.starts_with("//") {
+                    doc = None;
+                }
+            }
+        }
+    }
+
+    declarations
+}
+
+fn top(module_depths: &[i32]) -> i32 {
+    module_depths.last().copied().unwrap_or(0)
+}
+
+/// Add a declaration, merging it into an earlier one of the same kind and name
+fn push(
+    declarations: &mut Vec<Declaration>,
+    kind: &'static str,
+    name: String,
+    doc: Option<Vec<String>>,
+    lines: &[&str],
+) {
+    let docs = doc.map(|lines| doc_text(&lines)).unwrap_or_default();
+    let signature = dedent(lines);
+    if let Some(existing) = declarations
+        .iter_mut()
+        .find(|d| d.kind == kind && d.name == name)
+    {
+        existing.signature.push('\n');
+        existing.signature.push_str(&signature);
+        if !docs.is_empty() && !existing.docs.contains(&docs) {
+            if !existing.docs.is_empty() {
+                existing.docs.push_str("\n\n");
+            }
+            existing.docs.push_str(&docs);
+        }
+        return;
+    }
+    declarations.push(Declaration {
+        kind,
+        name,
+        docs,
+        signature,
+    });
+}
+
+/// Kind and name of a declaration starting on `line`
+fn declaration_head(line: &str) -> Option<(&'static str, String)> {
+    let mut words = line.split_whitespace().peekable();
+    while words.peek().is_some_and(|word| MODIFIERS.contains(word)) {
+        words.next();
+    }
+    let mut keyword = words.next()?;
+    if keyword == "const" && words.peek() == Some(&"enum") {
+        keyword = words.next()?;
+    }
+    let (_, kind) = KINDS.iter().find(|(k, _)| *k == keyword)?;
+    let name: String = words
+        .next()?
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '$' || *c == '.')
+        .collect();
+    (!name.is_empty()).then_some((kind, name))
+}
+
+/// Whether `line` opens a `declare module "name" {` block
+fn is_module_block(line: &str) -> bool {
+    let rest = line
+        .strip_prefix("declare module ")
+        .or_else(|| line.strip_prefix("export declare module "));
+    rest.is_some_and(|rest| rest.starts_with(['"', '\'']) && rest.ends_with('{'))
+}
+
+fn statement_ends(line: &str) -> bool {
+    line.ends_with(';') || line.ends_with('}')
+}
+
+/// Opening minus closing braces on `line`, outside strings and line comments
+fn brace_delta(line: &str) -> i32 {
+    let mut delta = 0;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match quote {
+            Some(_) if c == '\\' => {
+                chars.next();
+            }
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' | '`' => quote = Some(c),
+                '/' if chars.peek() == Some(&'/') => break,
+                '{' => delta += 1,
+                '}' => delta -= 1,
+                _ => {}
+            },
+        }
+    }
+    delta
+}
+
+/// Text of a JSDoc comment without `/**`, `*/` and leading `*`
+fn doc_text(lines: &[String]) -> String {
+    let text: Vec<&str> = lines
+        .iter()
+        .map(|line| {
+            let line = line.trim();
+            let line = line.strip_prefix("/**").unwrap_or(line);
+            let line = line.strip_suffix("*/").unwrap_or(line);
+            let line = line.trim();
+            line.strip_prefix('*')
+                .map_or(line, str::trim_start)
+                .trim_end()
+        })
+        .collect();
+    text.join("\n").trim().to_string()
+}
+
+/// `lines` without the indentation they all share
+fn dedent(lines: &[&str]) -> String {
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or(line.trim_start()).trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TYPINGS: &str = r#"import { Readable } from "stream";
+
+/**
+ * Pad the start of a string.
+ *
+ * @param str - String to pad
+ */
+export declare function leftPad(str: string, len: number): string;
+export declare function leftPad(str: number, len: number): string;
+
+/** Options accepted by {@link pad} */
+export interface PadOptions {
+    /** Character to pad with, "{" included */
+    ch?: string;
+    nested: { depth: number };
+}
+
+export type Side = "left" | "right";
+
+export declare const enum Mode { Fast, Slow }
+
+export declare class Padder {
+    constructor(options?: PadOptions);
+    pad(value: string): string;
+}
+
+export default leftPad;
+"#;
+
+    #[test]
+    fn test_top_level_declarations() {
+        let declarations = parse_declarations(TYPINGS);
+        let names: Vec<(&str, &str)> = declarations
+            .iter()
+            .map(|d| (d.kind, d.name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("function", "leftPad"),
+                ("interface", "PadOptions"),
+                ("type", "Side"),
+                ("enum", "Mode"),
+                ("class", "Padder"),
+            ]
+        );
+
+        let left_pad = &declarations[0];
+        assert_eq!(
+            left_pad.docs,
+            "Pad the start of a string.\n\n@param str - String to pad"
+        );
+        // Overloads share one declaration
+        assert_eq!(left_pad.signature.lines().count(), 2);
+
+        let options = &declarations[1];
+        assert_eq!(options.docs, "Options accepted by {@link pad}");
+        assert!(options
+            .signature
+            .starts_with("export interface PadOptions {"));
+        assert!(options.signature.ends_with("}"));
+        assert_eq!(declarations[2].docs, "");
+        assert!(declarations[4]
+            .signature
+            .contains("pad(value: string): string;"));
+    }
+
+    #[test]
+    fn test_declare_module_bodies_are_top_level() {
+        let source = r"declare module 'legacy' {
+    /** Does it */
+    export function run(): void;
+    namespace internal {
+        function hidden(): void;
+    }
+}
+";
+        let declarations = parse_declarations(source);
+        assert_eq!(declarations.len(), 2);
+        assert_eq!(declarations[0].name, "run");
+        assert_eq!(declarations[0].docs, "Does it");
+        assert_eq!(declarations[0].signature, "export function run(): void;");
+        assert_eq!(declarations[1].kind, "namespace");
+        assert_eq!(declarations[1].name, "internal");
+        assert!(declarations[1]
+            .signature
+            .contains("function hidden(): void;"));
+    }
+
+    #[test]
+    fn test_markdown() {
+        let declaration = &parse_declarations(TYPINGS)[2];
+        assert_eq!(
+            declaration.markdown(),
+            "```ts\nexport type Side = \"left\" | \"right\";\n```"
+        );
+    }
+}
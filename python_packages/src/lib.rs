@@ -0,0 +1,503 @@
+//! Python package ingestion: PyPI metadata, README and readthedocs pages.
+//!
+//! Pages are emitted as the same [`DocPage`]s the Rust crate loader produces
+//! and fetched through the same rate-limited [`PageFetcher`], so the
+//! ingestion pipeline stores them like crate pages under doc type
+//! [`PYTHON_DOC_TYPE`]. A readthedocs site linked from the project is
+//! crawled with the web crawler of the `loader` crate, reading Sphinx's
+//! content elements.
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use loader::web::{crawl_site, WebOptions, SPHINX_CONTENT_SELECTORS, WEB_PAGE_ITEM_TYPE};
+use rust_crates::{DocPage, PageFetcher, RateLimiter};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::timeout;
+use tracing::{info, warn};
+use url::Url;
+
+mod pypi;
+
+pub use pypi::{
+    is_readthedocs_url, normalize_package_name, parse_project_document, PyPiProject, PythonPackage,
+};
+
+/// `doc_type` of Python package documents
+pub const PYTHON_DOC_TYPE: &str = "python";
+
+/// Base URL of PyPI
+pub const PYPI_URL: &str = "https://pypi.org";
+
+/// Path readthedocs serves a project's default version under
+const READTHEDOCS_DEFAULT_PATH: &str = "en/latest/";
+
+/// Whether `name` is a valid Python project name (PEP 508): ASCII letters,
+/// digits, `-`, `_` and `.`, starting and ending with a letter or digit
+#[must_use]
+pub fn is_valid_package_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name.ends_with(|c: char| c.is_ascii_alphanumeric())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// PyPI JSON API URL of a project, or of one release of it
+#[must_use]
+pub fn pypi_json_url(name: &str, version: Option<&str>) -> String {
+    match version {
+        Some(version) => format!("{PYPI_URL}/pypi/{name}/{version}/json"),
+        None => format!("{PYPI_URL}/pypi/{name}/json"),
+    }
+}
+
+/// PyPI page of one release, recorded as the README's URL
+#[must_use]
+pub fn project_page_url(name: &str, version: &str) -> String {
+    format!("{PYPI_URL}/project/{name}/{version}/")
+}
+
+/// Outcome of asking PyPI about a package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PyPiLookup {
+    /// The package has the requested version
+    Found(Box<PythonPackage>),
+    /// PyPI has no such package
+    NotFound,
+    /// The package exists without the requested version; the reason says which is latest
+    InvalidVersion(String),
+    /// PyPI failed or did not answer in time, for the given reason
+    Unavailable(String),
+}
+
+/// Loads Python packages from PyPI and readthedocs; clones share the
+/// fetcher, and with it any rate limit
+#[derive(Clone)]
+pub struct PyPiLoader {
+    fetcher: Arc<dyn PageFetcher>,
+    max_pages: usize,
+}
+
+impl Default for PyPiLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PyPiLoader {
+    /// Loader fetching through a [`RateLimiter`] with the configured crawl limits
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_fetcher(Box::new(RateLimiter::new()))
+    }
+
+    /// Loader fetching every page through `fetcher`
+    #[must_use]
+    pub fn with_fetcher(fetcher: Box<dyn PageFetcher>) -> Self {
+        Self {
+            fetcher: Arc::from(fetcher),
+            max_pages: Self::max_pages(),
+        }
+    }
+
+    /// Crawl at most `max_pages` documentation pages per package
+    #[must_use]
+    pub fn with_max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = max_pages;
+        self
+    }
+
+    /// Page limit for a readthedocs crawl (`PYTHON_DOCS_MAX_PAGES`, default 200)
+    #[must_use]
+    pub fn max_pages() -> usize {
+        std::env::var("PYTHON_DOCS_MAX_PAGES")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(200)
+    }
+
+    /// Load `version` of a package (latest by default)
+    ///
+    /// The project document is read first, so an unknown package fails with
+    /// a 404 and an unknown version with an "Invalid version" error.
+    ///
+    /// # Errors
+    /// Returns an error if PyPI cannot be fetched, the response cannot be
+    /// parsed or the package has no such version.
+    pub async fn load_package(&self, name: &str, version: Option<&str>) -> Result<PythonPackage> {
+        info!("Loading Python package: {} (version: {:?})", name, version);
+        let name = normalize_package_name(name);
+        let latest =
+            parse_project_document(&self.fetcher.fetch_text(&pypi_json_url(&name, None)).await?)?;
+        let version = version.map(str::trim).filter(|v| !v.is_empty());
+        let Some(version) = version.filter(|v| *v != latest.package.version) else {
+            return Ok(latest.package);
+        };
+        if !latest.releases.iter().any(|release| release == version) {
+            return Err(anyhow!(
+                "Invalid version '{}' for Python package '{}' (latest: {})",
+                version,
+                latest.package.name,
+                latest.package.version
+            ));
+        }
+
+        let body = self
+            .fetcher
+            .fetch_text(&pypi_json_url(&name, Some(version)))
+            .await?;
+        let mut package = parse_project_document(&body)?.package;
+        package.latest_version = latest.package.version;
+        Ok(package)
+    }
+
+    /// Ask PyPI about `version` of a package, waiting at most `within`
+    ///
+    /// A 404 gives [`PyPiLookup::NotFound`]. Any other failure, including the
+    /// timeout, gives [`PyPiLookup::Unavailable`], so callers can go ahead
+    /// unchecked.
+    pub async fn lookup_package(
+        &self,
+        name: &str,
+        version: Option<&str>,
+        within: Duration,
+    ) -> PyPiLookup {
+        match timeout(within, self.load_package(name, version)).await {
+            Ok(Ok(package)) => PyPiLookup::Found(Box::new(package)),
+            Ok(Err(e)) if e.to_string().starts_with("Invalid version") => {
+                PyPiLookup::InvalidVersion(e.to_string())
+            }
+            Ok(Err(e)) if format!("{e:#}").contains("HTTP status: 404") => PyPiLookup::NotFound,
+            Ok(Err(e)) => PyPiLookup::Unavailable(format!("{e:#}")),
+            Err(_) => PyPiLookup::Unavailable(format!(
+                "PyPI did not answer within {}s",
+                within.as_secs_f64()
+            )),
+        }
+    }
+
+    /// Documentation pages of a package: its README and the pages of its
+    /// readthedocs site
+    ///
+    /// The site's `sitemap.xml` is read first; without one, or when it lists
+    /// none of the docs' pages, links are followed from the docs root. The
+    /// crawl stays on the site's origin under the docs root. A failed crawl
+    /// is skipped with a warning; the README alone is still returned.
+    pub async fn load_package_docs(&self, package: &PythonPackage) -> Vec<DocPage> {
+        let mut pages = Vec::new();
+        if let Some(readme) = &package.readme {
+            pages.push(DocPage {
+                url: project_page_url(&package.name, &package.version),
+                content: readme.clone(),
+                item_type: "readme".to_string(),
+                module_path: format!("{}::README", package.name),
+                extracted_at: Utc::now(),
+                parent_url: None,
+            });
+        }
+
+        let Some(base) = package.readthedocs_url.as_deref().and_then(docs_root) else {
+            info!("Python package {} links no readthedocs site", package.name);
+            return pages;
+        };
+        let mut options = WebOptions {
+            sitemap: Some("sitemap.xml".to_string()),
+            max_pages: self.max_pages,
+            content_selectors: SPHINX_CONTENT_SELECTORS
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            ..WebOptions::new(base.clone())
+        };
+        let crawled = match crawl_site(self.fetcher.as_ref(), &options).await {
+            Ok((documents, summary)) if !documents.is_empty() => Ok((documents, summary)),
+            Ok(_) | Err(_) => {
+                options.sitemap = None;
+                crawl_site(self.fetcher.as_ref(), &options).await
+            }
+        };
+        let documents = match crawled {
+            Ok((documents, summary)) => {
+                info!(
+                    "Crawled readthedocs site of {} at {}: {}",
+                    package.name, base, summary
+                );
+                documents
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to crawl readthedocs site of {} at {}: {}",
+                    package.name, base, e
+                );
+                return pages;
+            }
+        };
+
+        // The docs root and its index.html are one page
+        let mut seen = HashSet::new();
+        pages.extend(documents.into_iter().filter_map(|document| {
+            let page = Url::parse(&document.url)
+                .ok()
+                .map(|url| page_name(&base, &url))
+                .unwrap_or_default();
+            if !seen.insert(page.clone()) {
+                return None;
+            }
+            Some(DocPage {
+                // Sphinx heading permalinks
+                content: document.content.replace('¶', ""),
+                url: document.url,
+                item_type: WEB_PAGE_ITEM_TYPE.to_string(),
+                module_path: format!("{}::{page}", package.name),
+                extracted_at: document.extracted_at,
+                parent_url: None,
+            })
+        }));
+        pages
+    }
+}
+
+/// Root of a readthedocs site's docs, as a directory URL
+///
+/// A bare project host (`https://project.readthedocs.io/`) gets the default
+/// version's path, since its own sitemap lists only version roots.
+fn docs_root(url: &str) -> Option<Url> {
+    let mut url = Url::parse(url).ok()?;
+    url.set_query(None);
+    url.set_fragment(None);
+    if url.path() == "/" {
+        return url.join(READTHEDOCS_DEFAULT_PATH).ok();
+    }
+    let last = url.path().rsplit('/').next().unwrap_or_default();
+    if last.contains('.') {
+        // A page; its directory is the root
+        return url.join("./").ok();
+    }
+    if !url.path().ends_with('/') {
+        let path = format!("{}/", url.path());
+        url.set_path(&path);
+    }
+    Some(url)
+}
+
+/// Page path under the docs root without `.html`, e.g. `user/quickstart`
+fn page_name(base: &Url, url: &Url) -> String {
+    let path = url
+        .path()
+        .strip_prefix(base.path())
+        .unwrap_or_else(|| url.path());
+    let path = path.trim_end_matches('/');
+    let path = path.strip_suffix(".html").unwrap_or(path);
+    if path.is_empty() {
+        "index".to_string()
+    } else {
+        path.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    const DOCS: &str = "https://quokka-tools.readthedocs.io/en/latest/";
+
+    /// Serves canned PyPI and readthedocs responses, recording requested
+    /// URLs; other URLs fail with a 404
+    struct MockPyPi {
+        pages: HashMap<String, String>,
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl PageFetcher for MockPyPi {
+        async fn fetch_text(&self, url: &str) -> Result<String> {
+            self.requests.lock().unwrap().push(url.to_string());
+            self.pages
+                .get(url)
+                .cloned()
+                .ok_or_else(|| anyhow!("HTTP status: 404 Not Found"))
+        }
+    }
+
+    fn loader(pages: Vec<(String, String)>) -> (PyPiLoader, Arc<Mutex<Vec<String>>>) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let fetcher = MockPyPi {
+            pages: pages.into_iter().collect(),
+            requests: Arc::clone(&requests),
+        };
+        (PyPiLoader::with_fetcher(Box::new(fetcher)), requests)
+    }
+
+    fn project(version: &str, docs: &str) -> String {
+        json!({
+            "info": {
+                "name": "Quokka-Tools",
+                "version": version,
+                "summary": "Helpers for quokkas",
+                "description": format!("# Quokka Tools {version}"),
+                "description_content_type": "text/markdown",
+                "project_urls": {"Documentation": docs}
+            },
+            "releases": {"1.0.0": [], "2.1.0": []}
+        })
+        .to_string()
+    }
+
+    /// The fixture readthedocs site, with or without its sitemap
+    fn site(with_sitemap: bool) -> Vec<(String, String)> {
+        let mut pages = vec![
+            (
+                DOCS.to_string(),
+                include_str!("../tests/fixtures/readthedocs/index.html").to_string(),
+            ),
+            (
+                format!("{DOCS}index.html"),
+                include_str!("../tests/fixtures/readthedocs/index.html").to_string(),
+            ),
+            (
+                format!("{DOCS}api.html"),
+                include_str!("../tests/fixtures/readthedocs/api.html").to_string(),
+            ),
+        ];
+        if with_sitemap {
+            pages.push((
+                format!("{DOCS}sitemap.xml"),
+                include_str!("../tests/fixtures/readthedocs/sitemap.xml").to_string(),
+            ));
+        }
+        pages
+    }
+
+    #[test]
+    fn test_package_names_and_urls() {
+        assert!(is_valid_package_name("Quokka_Tools.2"));
+        assert!(!is_valid_package_name("-quokka"));
+        assert!(!is_valid_package_name("quokka tools"));
+        assert!(!is_valid_package_name(""));
+        assert_eq!(
+            pypi_json_url("quokka-tools", Some("1.0.0")),
+            "https://pypi.org/pypi/quokka-tools/1.0.0/json"
+        );
+        assert_eq!(
+            docs_root("https://x.readthedocs.io").unwrap().as_str(),
+            "https://x.readthedocs.io/en/latest/"
+        );
+        assert_eq!(
+            docs_root("https://x.readthedocs.io/en/stable")
+                .unwrap()
+                .as_str(),
+            "https://x.readthedocs.io/en/stable/"
+        );
+        assert_eq!(
+            docs_root("https://x.readthedocs.io/en/stable/index.html#top")
+                .unwrap()
+                .as_str(),
+            "https://x.readthedocs.io/en/stable/"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_versions_and_lookup() {
+        let (loader, _) = loader(vec![
+            (
+                "https://pypi.org/pypi/quokka-tools/json".to_string(),
+                project("2.1.0", DOCS),
+            ),
+            (
+                "https://pypi.org/pypi/quokka-tools/1.0.0/json".to_string(),
+                project("1.0.0", DOCS),
+            ),
+        ]);
+        // Names are normalized before the request
+        let latest = loader.load_package("Quokka_Tools", None).await.unwrap();
+        assert_eq!(latest.version, "2.1.0");
+        let old = loader
+            .load_package("quokka-tools", Some("1.0.0"))
+            .await
+            .unwrap();
+        assert_eq!(old.version, "1.0.0");
+        assert_eq!(old.latest_version, "2.1.0");
+        assert_eq!(old.readme.as_deref(), Some("# Quokka Tools 1.0.0"));
+
+        let within = Duration::from_secs(1);
+        assert!(matches!(
+            loader.lookup_package("quokka-tools", Some("3.0.0"), within).await,
+            PyPiLookup::InvalidVersion(reason) if reason.contains("latest: 2.1.0")
+        ));
+        assert_eq!(
+            loader.lookup_package("missing", None, within).await,
+            PyPiLookup::NotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn test_readthedocs_sitemap_first() {
+        let (loader, requests) = loader(site(true));
+        let package = parse_project_document(&project("2.1.0", DOCS))
+            .unwrap()
+            .package;
+        let pages = loader.load_package_docs(&package).await;
+
+        let paths: Vec<(&str, &str)> = pages
+            .iter()
+            .map(|p| (p.item_type.as_str(), p.module_path.as_str()))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("readme", "quokka-tools::README"),
+                ("web_page", "quokka-tools::index"),
+                ("web_page", "quokka-tools::api"),
+            ]
+        );
+        assert_eq!(pages[0].url, "https://pypi.org/project/quokka-tools/2.1.0/");
+        // Sphinx main content only: no sidebar, related links or permalinks
+        assert!(pages[1]
+            .content
+            .starts_with("Quokka Tools\nQuokka Tools makes quokkas hop."));
+        assert!(!pages[1].content.contains("Navigation"));
+        assert!(!pages[2].content.contains('¶'));
+        assert!(pages[2]
+            .content
+            .contains("Make a quokka hop height centimetres."));
+        // Listed pages of other versions are out of scope
+        let requests = requests.lock().unwrap();
+        assert!(!requests.iter().any(|url| url.contains("/en/stable/")));
+    }
+
+    #[tokio::test]
+    async fn test_readthedocs_links_without_sitemap() {
+        let (loader, _) = loader(site(false));
+        let package = parse_project_document(&project("2.1.0", DOCS))
+            .unwrap()
+            .package;
+        let pages = loader.load_package_docs(&package).await;
+        let mut paths: Vec<&str> = pages.iter().map(|p| p.module_path.as_str()).collect();
+        paths.sort_unstable();
+        assert_eq!(
+            paths,
+            vec![
+                "quokka-tools::README",
+                "quokka-tools::api",
+                "quokka-tools::index"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_site_keeps_the_readme() {
+        let (loader, _) = loader(Vec::new());
+        let package = parse_project_document(&project("2.1.0", "https://example.com/docs"))
+            .unwrap()
+            .package;
+        let pages = loader.load_package_docs(&package).await;
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].item_type, "readme");
+    }
+}
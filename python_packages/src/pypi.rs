@@ -0,0 +1,290 @@
+//! Project documents served by the PyPI JSON API.
+//!
+//! `GET https://pypi.org/pypi/{name}/json` describes the latest release and
+//! lists every released version; `GET https://pypi.org/pypi/{name}/{version}/json`
+//! describes one release. Both carry the release's long description, which
+//! is the project README.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use url::Url;
+
+/// Long description setuptools stores when a project has none
+const MISSING_DESCRIPTION: &str = "UNKNOWN";
+
+/// `project_urls` keys naming the source repository, lowercased
+const REPOSITORY_KEYS: &[&str] = &["source", "source code", "repository", "code", "github"];
+
+/// One release of a Python package, as PyPI describes it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PythonPackage {
+    /// Normalized name (PEP 503), under which the package is stored
+    pub name: String,
+    /// Name as the project spells it, e.g. `Django`
+    pub display_name: String,
+    pub version: String,
+    /// Latest release on PyPI
+    pub latest_version: String,
+    /// One-line summary
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    /// Documentation link, wherever it is hosted
+    pub documentation_url: Option<String>,
+    /// Documentation link when it is a readthedocs site, the one crawled
+    pub readthedocs_url: Option<String>,
+    pub repository: Option<String>,
+    pub license: Option<String>,
+    pub keywords: Vec<String>,
+    pub requires_python: Option<String>,
+    /// Markup of the README: `markdown`, `rst` or `text`
+    pub readme_format: String,
+    /// README (the long description); left out of serialized metadata
+    #[serde(skip)]
+    pub readme: Option<String>,
+}
+
+/// A parsed project document: the release it describes and every released version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyPiProject {
+    pub package: PythonPackage,
+    /// Released versions; empty in documents of a single release
+    pub releases: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ProjectDocument {
+    info: ProjectInfo,
+    #[serde(default)]
+    releases: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct ProjectInfo {
+    name: String,
+    version: String,
+    #[serde(default)]
+    summary: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    description_content_type: Option<String>,
+    #[serde(default)]
+    home_page: Option<String>,
+    #[serde(default)]
+    docs_url: Option<String>,
+    #[serde(default)]
+    project_urls: Option<HashMap<String, String>>,
+    #[serde(default)]
+    license: Option<String>,
+    #[serde(default)]
+    license_expression: Option<String>,
+    #[serde(default)]
+    keywords: Option<String>,
+    #[serde(default)]
+    requires_python: Option<String>,
+}
+
+/// Normalize a project name the way PyPI compares them (PEP 503): lowercase,
+/// with runs of `-`, `_` and `.` replaced by one `-`
+#[must_use]
+pub fn normalize_package_name(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    for c in name.trim().chars() {
+        if matches!(c, '-' | '_' | '.') {
+            if !normalized.ends_with('-') {
+                normalized.push('-');
+            }
+        } else {
+            normalized.push(c.to_ascii_lowercase());
+        }
+    }
+    normalized
+}
+
+/// Read a project document body
+///
+/// # Errors
+/// Returns an error if the body is not a PyPI project document.
+pub fn parse_project_document(body: &str) -> Result<PyPiProject> {
+    let document: ProjectDocument =
+        serde_json::from_str(body).map_err(|e| anyhow!("Invalid PyPI response: {}", e))?;
+    let info = document.info;
+    let project_urls = info.project_urls.unwrap_or_default();
+    let non_empty = |value: Option<String>| value.filter(|v| !v.trim().is_empty());
+
+    let documentation_url = non_empty(info.docs_url.clone()).or_else(|| {
+        project_urls
+            .iter()
+            .filter(|(key, _)| key.to_ascii_lowercase().contains("doc"))
+            .map(|(_, url)| url.clone())
+            .min()
+    });
+    // The documentation link wins, then the homepage, then any other link
+    let mut links: Vec<&String> = documentation_url.iter().collect();
+    links.extend(info.home_page.iter());
+    let mut other_links: Vec<&String> = project_urls.values().collect();
+    other_links.sort();
+    links.extend(other_links);
+    let readthedocs_url = links
+        .into_iter()
+        .find(|url| is_readthedocs_url(url))
+        .cloned();
+
+    let repository = project_urls
+        .iter()
+        .filter(|(key, _)| REPOSITORY_KEYS.contains(&key.to_ascii_lowercase().as_str()))
+        .map(|(_, url)| url.clone())
+        .min();
+
+    let readme_format = match info.description_content_type.as_deref() {
+        Some(content_type) if content_type.starts_with("text/markdown") => "markdown",
+        Some(content_type) if content_type.starts_with("text/plain") => "text",
+        // PyPI renders descriptions without a content type as reStructuredText
+        _ => "rst",
+    };
+    let readme = info
+        .description
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty() && d != MISSING_DESCRIPTION);
+
+    let mut releases: Vec<String> = document.releases.into_keys().collect();
+    releases.sort();
+
+    Ok(PyPiProject {
+        package: PythonPackage {
+            name: normalize_package_name(&info.name),
+            display_name: info.name,
+            version: info.version.clone(),
+            latest_version: info.version,
+            description: non_empty(info.summary),
+            homepage: non_empty(info.home_page),
+            documentation_url,
+            readthedocs_url,
+            repository,
+            license: non_empty(info.license_expression)
+                .or_else(|| non_empty(info.license))
+                .and_then(|l| l.lines().next().map(|line| line.trim().to_string())),
+            keywords: info
+                .keywords
+                .unwrap_or_default()
+                .split([',', ' '])
+                .filter(|k| !k.is_empty())
+                .map(String::from)
+                .collect(),
+            requires_python: non_empty(info.requires_python),
+            readme_format: readme_format.to_string(),
+            readme,
+        },
+        releases,
+    })
+}
+
+/// Whether `url` points at a site hosted on readthedocs
+#[must_use]
+pub fn is_readthedocs_url(url: &str) -> bool {
+    Url::parse(url).ok().is_some_and(|url| {
+        url.host_str().is_some_and(|host| {
+            host.ends_with(".readthedocs.io") || host.ends_with(".readthedocs.org")
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn document() -> String {
+        json!({
+            "info": {
+                "name": "Quokka_Tools",
+                "version": "2.1.0",
+                "summary": "Helpers for quokkas",
+                "description": "# Quokka Tools\n\nHop helpers.",
+                "description_content_type": "text/markdown; charset=UTF-8",
+                "home_page": "https://example.com/quokka",
+                "docs_url": null,
+                "project_urls": {
+                    "Documentation": "https://quokka-tools.readthedocs.io/en/latest/",
+                    "Source": "https://github.com/acme/quokka-tools",
+                    "Homepage": "https://example.com/quokka"
+                },
+                "license": "MIT License\n\nPermission is hereby granted...",
+                "keywords": "quokka, hop",
+                "requires_python": ">=3.9"
+            },
+            "releases": {"1.0.0": [], "2.0.0rc1": [], "2.1.0": []}
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_project_document() {
+        let project = parse_project_document(&document()).unwrap();
+        let package = project.package;
+        assert_eq!(package.name, "quokka-tools");
+        assert_eq!(package.display_name, "Quokka_Tools");
+        assert_eq!(package.version, "2.1.0");
+        assert_eq!(package.description.as_deref(), Some("Helpers for quokkas"));
+        assert_eq!(
+            package.readthedocs_url.as_deref(),
+            Some("https://quokka-tools.readthedocs.io/en/latest/")
+        );
+        assert_eq!(
+            package.repository.as_deref(),
+            Some("https://github.com/acme/quokka-tools")
+        );
+        // Only the first line of a license text
+        assert_eq!(package.license.as_deref(), Some("MIT License"));
+        assert_eq!(package.keywords, vec!["quokka", "hop"]);
+        assert_eq!(package.readme_format, "markdown");
+        assert_eq!(
+            package.readme.as_deref(),
+            Some("# Quokka Tools\n\nHop helpers.")
+        );
+        assert_eq!(project.releases, vec!["1.0.0", "2.0.0rc1", "2.1.0"]);
+    }
+
+    #[test]
+    fn test_sparse_release_document() {
+        let body = json!({
+            "info": {
+                "name": "bare",
+                "version": "0.1",
+                "summary": "",
+                "description": "UNKNOWN",
+                "home_page": "https://bare.readthedocs.org/",
+                "project_urls": null,
+                "keywords": null
+            }
+        })
+        .to_string();
+        let project = parse_project_document(&body).unwrap();
+        assert!(project.releases.is_empty());
+        let package = project.package;
+        assert_eq!(package.description, None);
+        assert_eq!(package.readme, None);
+        assert_eq!(package.readme_format, "rst");
+        assert_eq!(
+            package.readthedocs_url.as_deref(),
+            Some("https://bare.readthedocs.org/")
+        );
+        assert!(package.keywords.is_empty());
+
+        let error = parse_project_document("<html>").unwrap_err();
+        assert!(error.to_string().contains("Invalid PyPI response"));
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(
+            normalize_package_name("Friendly.Bard__Two"),
+            "friendly-bard-two"
+        );
+        assert_eq!(normalize_package_name("requests"), "requests");
+        assert!(is_readthedocs_url("https://docs.readthedocs.io/en/stable/"));
+        assert!(!is_readthedocs_url("https://readthedocs.example.com/"));
+        assert!(!is_readthedocs_url("not a url"));
+    }
+}
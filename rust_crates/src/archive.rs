@@ -0,0 +1,471 @@
+//! Raw HTML of crawled pages, kept in object storage for reprocessing.
+//!
+//! Every change to content extraction would otherwise need a fresh crawl
+//! at docs.rs's rate limit. When an archive is configured, each fetched page
+//! is stored gzip-compressed under `{prefix}/{crate}/{version}/{hash}.html.gz`,
+//! where `hash` is the SHA-256 of the page URL and the URL itself travels
+//! in the gzip header, so a crate version can be parsed again from the
+//! archive alone. Writes are best-effort: the crawl logs failures and moves
+//! on.
+
+use crate::{CrawlProgress, CrawlSink, CrawlState, RustLoader, PROGRESS_EVERY_PAGES};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::{Compression, GzBuilder};
+use futures::TryStreamExt;
+use object_store::path::Path;
+use object_store::{ObjectStore, PutPayload};
+use scraper::Html;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
+use tracing::{debug, info, warn};
+use url::Url;
+
+/// Extension of archived pages
+const PAGE_EXTENSION: &str = ".html.gz";
+
+/// Object store holding the raw HTML of crawled pages
+pub struct RawArchive {
+    store: Arc<dyn ObjectStore>,
+    prefix: Path,
+    /// Compressed bytes a crawl may write per crate version
+    max_version_bytes: Option<u64>,
+    /// Versions of a crate kept after pruning
+    keep_versions: Option<usize>,
+    /// Compressed bytes written so far per `crate/version`
+    written: Mutex<HashMap<String, u64>>,
+}
+
+impl RawArchive {
+    /// Open the archive at `url`: `file:///path` for a local directory or
+    /// `s3://bucket/prefix` for S3, configured by the usual `AWS_*`
+    /// variables.
+    ///
+    /// # Errors
+    /// Returns an error if the URL is invalid or names an unsupported store.
+    pub fn from_url(url: &str) -> Result<Self> {
+        let parsed =
+            Url::parse(url).map_err(|e| anyhow!("Invalid archive URL '{}': {}", url, e))?;
+        let options = std::env::vars()
+            .filter(|(key, _)| key.starts_with("AWS_"))
+            .map(|(key, value)| (key.to_ascii_lowercase(), value));
+        let (store, prefix) = object_store::parse_url_opts(&parsed, options)
+            .map_err(|e| anyhow!("Cannot open archive '{}': {}", url, e))?;
+        Ok(Self::with_store(Arc::from(store), prefix))
+    }
+
+    /// Archive in `store` under `prefix`
+    #[must_use]
+    pub fn with_store(store: Arc<dyn ObjectStore>, prefix: Path) -> Self {
+        Self {
+            store,
+            prefix,
+            max_version_bytes: None,
+            keep_versions: None,
+            written: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Archive configured by `RAW_ARCHIVE_URL`, if any
+    ///
+    /// `RAW_ARCHIVE_MAX_BYTES` caps the compressed bytes a crawl writes per
+    /// crate version and `RAW_ARCHIVE_KEEP_VERSIONS` the versions kept per
+    /// crate. An invalid URL is logged and leaves archiving off.
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        let url = std::env::var("RAW_ARCHIVE_URL")
+            .ok()
+            .filter(|url| !url.trim().is_empty())?;
+        let mut archive = match Self::from_url(url.trim()) {
+            Ok(archive) => archive,
+            Err(e) => {
+                warn!("Raw page archive disabled: {}", e);
+                return None;
+            }
+        };
+        if let Some(bytes) = env_number::<u64>("RAW_ARCHIVE_MAX_BYTES") {
+            archive = archive.with_max_version_bytes(bytes);
+        }
+        if let Some(versions) = env_number::<usize>("RAW_ARCHIVE_KEEP_VERSIONS") {
+            archive = archive.with_keep_versions(versions);
+        }
+        Some(archive)
+    }
+
+    /// Stop archiving a crate version once a crawl wrote `bytes` for it
+    #[must_use]
+    pub const fn with_max_version_bytes(mut self, bytes: u64) -> Self {
+        self.max_version_bytes = Some(bytes);
+        self
+    }
+
+    /// Keep only the `versions` most recently archived versions of a crate
+    #[must_use]
+    pub fn with_keep_versions(mut self, versions: usize) -> Self {
+        self.keep_versions = Some(versions.max(1));
+        self
+    }
+
+    /// Store the HTML of the page at `url` for `crate_name` `version`
+    ///
+    /// Pages beyond the crate version's size limit are skipped, with a
+    /// warning for the first.
+    ///
+    /// # Errors
+    /// Returns an error if the page cannot be compressed or written.
+    pub async fn store_page(
+        &self,
+        crate_name: &str,
+        version: &str,
+        url: &str,
+        html: &str,
+    ) -> Result<()> {
+        let mut encoder = GzBuilder::new()
+            .filename(url)
+            .write(Vec::new(), Compression::default());
+        encoder.write_all(html.as_bytes())?;
+        let compressed = encoder.finish()?;
+
+        let label = format!("{crate_name}/{version}");
+        if let Some(limit) = self.max_version_bytes {
+            let mut written = self.written.lock().unwrap_or_else(PoisonError::into_inner);
+            let total = written.entry(label.clone()).or_insert(0);
+            let before = *total;
+            *total += compressed.len() as u64;
+            if *total > limit {
+                if before <= limit {
+                    warn!(
+                        "Raw archive of {} is over {} bytes; not archiving further pages",
+                        label, limit
+                    );
+                }
+                return Ok(());
+            }
+        }
+
+        let path = self.page_path(crate_name, version, url);
+        self.store
+            .put(&path, PutPayload::from(compressed))
+            .await
+            .map_err(|e| anyhow!("Failed to archive {}: {}", url, e))?;
+        debug!("Archived {} as {}", url, path);
+        Ok(())
+    }
+
+    /// Archived pages of `crate_name` `version` as `(url, html)`, in URL order
+    ///
+    /// Objects that cannot be read or decoded are logged and skipped.
+    ///
+    /// # Errors
+    /// Returns an error if the archive cannot be listed.
+    pub async fn pages(&self, crate_name: &str, version: &str) -> Result<Vec<(String, String)>> {
+        let mut pages = BTreeMap::new();
+        for meta in self.list(&self.version_prefix(crate_name, version)).await? {
+            if !meta.location.as_ref().ends_with(PAGE_EXTENSION) {
+                continue;
+            }
+            match self.read_page(&meta.location).await {
+                Ok((url, html)) => {
+                    pages.insert(url, html);
+                }
+                Err(e) => debug!("Skipping archived page {}: {}", meta.location, e),
+            }
+        }
+        Ok(pages.into_iter().collect())
+    }
+
+    /// Whether any page of `crate_name` `version` is archived
+    ///
+    /// # Errors
+    /// Returns an error if the archive cannot be listed.
+    pub async fn has_pages(&self, crate_name: &str, version: &str) -> Result<bool> {
+        let prefix = self.version_prefix(crate_name, version);
+        let first = self
+            .store
+            .list(Some(&prefix))
+            .try_next()
+            .await
+            .map_err(|e| anyhow!("Failed to list archive {}: {}", prefix, e))?;
+        Ok(first.is_some())
+    }
+
+    /// Delete archived versions of `crate_name` beyond the configured number
+    /// to keep, oldest archive writes first; returns the versions deleted.
+    ///
+    /// # Errors
+    /// Returns an error if the archive cannot be listed or an object cannot
+    /// be deleted.
+    pub async fn prune(&self, crate_name: &str) -> Result<Vec<String>> {
+        let Some(keep) = self.keep_versions else {
+            return Ok(Vec::new());
+        };
+        let crate_prefix = self.prefix.child(crate_name);
+        let mut versions: HashMap<String, ArchivedVersion> = HashMap::new();
+        for meta in self.list(&crate_prefix).await? {
+            let Some(version) = meta
+                .location
+                .prefix_match(&crate_prefix)
+                .and_then(|mut parts| parts.next())
+            else {
+                continue;
+            };
+            let archived = versions
+                .entry(version.as_ref().to_string())
+                .or_insert_with(|| ArchivedVersion {
+                    last_written: meta.last_modified,
+                    objects: Vec::new(),
+                });
+            archived.last_written = archived.last_written.max(meta.last_modified);
+            archived.objects.push(meta.location);
+        }
+        let mut by_age: Vec<(String, ArchivedVersion)> = versions.into_iter().collect();
+        by_age.sort_by(|a, b| {
+            b.1.last_written
+                .cmp(&a.1.last_written)
+                .then_with(|| b.0.cmp(&a.0))
+        });
+
+        let mut deleted = Vec::new();
+        for (version, archived) in by_age.into_iter().skip(keep) {
+            for path in archived.objects {
+                self.store
+                    .delete(&path)
+                    .await
+                    .map_err(|e| anyhow!("Failed to delete {}: {}", path, e))?;
+            }
+            deleted.push(version);
+        }
+        if !deleted.is_empty() {
+            info!(
+                "Pruned archived versions of {}: {}",
+                crate_name,
+                deleted.join(", ")
+            );
+        }
+        Ok(deleted)
+    }
+
+    /// Parse the archived pages of `crate_name` `version` like a crawl,
+    /// handing them to `sink` every `checkpoint_every` pages and a
+    /// [`CrawlProgress`] to `progress` every [`PROGRESS_EVERY_PAGES`] pages.
+    ///
+    /// Pages keep the URLs they were fetched from, and an archived crates.io
+    /// README becomes the crate's README page as in a crawl. Returns the final
+    /// state, whose `processed` counts the pages read.
+    ///
+    /// # Errors
+    /// Returns an error if the archive holds no page of the crate version,
+    /// cannot be listed, or the sink fails.
+    pub async fn crawl(
+        &self,
+        crate_name: &str,
+        version: &str,
+        max_pages: usize,
+        checkpoint_every: usize,
+        sink: &mut dyn CrawlSink,
+        progress: &mut (dyn FnMut(CrawlProgress) + Send),
+    ) -> Result<CrawlState> {
+        let started = Instant::now();
+        let mut archived = self.pages(crate_name, version).await?;
+        if archived.is_empty() {
+            return Err(anyhow!(
+                "No archived pages for {} {}; add the crate again to crawl it",
+                crate_name,
+                version
+            ));
+        }
+        if archived.len() > max_pages {
+            info!(
+                "Reached page limit ({}), reading {} of {} archived pages",
+                max_pages,
+                max_pages,
+                archived.len()
+            );
+            archived.truncate(max_pages);
+        }
+
+        let root_url = RustLoader::start_url(crate_name, version);
+        let readme_url = RustLoader::readme_url(crate_name, version);
+        let checkpoint_every = checkpoint_every.max(1);
+        let total = archived.len();
+        let snapshot = |state: &CrawlState, handled: usize| CrawlProgress {
+            pages_fetched: state.processed,
+            pages_queued: total - handled,
+            pages_skipped: 0,
+            elapsed: started.elapsed(),
+        };
+        let mut state = CrawlState::default();
+        let mut pages = Vec::new();
+        for (handled, (url, html)) in (1..).zip(archived) {
+            if handled % PROGRESS_EVERY_PAGES == 0 {
+                RustLoader::report_progress(progress, snapshot(&state, handled - 1));
+            }
+            if url == readme_url {
+                pages.extend(RustLoader::readme_page(crate_name, url, &html));
+            } else {
+                let document = Html::parse_document(&html);
+                pages.extend(RustLoader::parse_doc_page(
+                    &document,
+                    &url,
+                    &url,
+                    crate_name,
+                    url == root_url,
+                ));
+            }
+
+            state.processed += 1;
+            if state.processed % checkpoint_every == 0 {
+                sink.checkpoint(std::mem::take(&mut pages), &state).await?;
+            }
+        }
+        sink.checkpoint(pages, &state).await?;
+        RustLoader::report_progress(progress, snapshot(&state, total));
+        info!(
+            "Read {} archived pages of {} {}",
+            state.processed, crate_name, version
+        );
+        Ok(state)
+    }
+
+    /// Prefix of a crate version's pages
+    fn version_prefix(&self, crate_name: &str, version: &str) -> Path {
+        self.prefix.child(crate_name).child(version)
+    }
+
+    /// Object holding the page at `url`
+    fn page_path(&self, crate_name: &str, version: &str, url: &str) -> Path {
+        let hash = hex(&Sha256::digest(url.as_bytes()));
+        self.version_prefix(crate_name, version)
+            .child(format!("{hash}{PAGE_EXTENSION}"))
+    }
+
+    /// Every object under `prefix`; a prefix that was never written is empty
+    async fn list(&self, prefix: &Path) -> Result<Vec<object_store::ObjectMeta>> {
+        self.store
+            .list(Some(prefix))
+            .try_collect()
+            .await
+            .map_err(|e| anyhow!("Failed to list archive {}: {}", prefix, e))
+    }
+
+    /// URL and HTML of an archived page
+    async fn read_page(&self, path: &Path) -> Result<(String, String)> {
+        let bytes = self.store.get(path).await?.bytes().await?;
+        let mut decoder = GzDecoder::new(&bytes[..]);
+        let mut html = String::new();
+        decoder.read_to_string(&mut html)?;
+        let url = decoder
+            .header()
+            .and_then(|header| header.filename())
+            .ok_or_else(|| anyhow!("no URL in the gzip header"))?;
+        Ok((String::from_utf8_lossy(url).into_owned(), html))
+    }
+}
+
+/// Objects of one archived crate version
+struct ArchivedVersion {
+    /// Most recent write of any of its pages
+    last_written: DateTime<Utc>,
+    objects: Vec<Path>,
+}
+
+/// Lowercase hex of `bytes`
+fn hex(bytes: &[u8]) -> String {
+    use std::fmt::Write as _;
+    bytes.iter().fold(String::new(), |mut out, byte| {
+        let _ = write!(out, "{byte:02x}");
+        out
+    })
+}
+
+/// Number in the environment variable `key`, if set and valid
+fn env_number<T: std::str::FromStr>(key: &str) -> Option<T> {
+    std::env::var(key).ok().and_then(|v| v.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RawArchive;
+    use object_store::local::LocalFileSystem;
+    use object_store::path::Path;
+    use std::sync::Arc;
+
+    fn temp_archive() -> (RawArchive, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("raw-archive-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let store = LocalFileSystem::new_with_prefix(&dir).unwrap();
+        (
+            RawArchive::with_store(Arc::new(store), Path::from("raw")),
+            dir,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_pages_round_trip_with_their_urls() {
+        let (archive, dir) = temp_archive();
+        let url = "https://docs.rs/demo/1.0.0/demo/fn.run.html";
+        archive
+            .store_page("demo", "1.0.0", url, "<p>run</p>")
+            .await
+            .unwrap();
+        // Storing a page again replaces it
+        archive
+            .store_page("demo", "1.0.0", url, "<p>run, again</p>")
+            .await
+            .unwrap();
+        archive
+            .store_page("demo", "2.0.0", url, "<p>other version</p>")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            archive.pages("demo", "1.0.0").await.unwrap(),
+            vec![(url.to_string(), "<p>run, again</p>".to_string())]
+        );
+        assert!(archive.has_pages("demo", "2.0.0").await.unwrap());
+        assert!(!archive.has_pages("demo", "3.0.0").await.unwrap());
+        assert!(archive.pages("other", "1.0.0").await.unwrap().is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_size_limit_stops_archiving_a_version() {
+        let (archive, dir) = temp_archive();
+        let archive = archive.with_max_version_bytes(200);
+        for i in 0..20 {
+            let url = format!("https://docs.rs/demo/1.0.0/demo/fn.f{i}.html");
+            archive
+                .store_page("demo", "1.0.0", &url, &format!("<p>{i}</p>"))
+                .await
+                .unwrap();
+        }
+        let stored = archive.pages("demo", "1.0.0").await.unwrap().len();
+        assert!(stored > 0 && stored < 20, "{stored} pages archived");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_prune_keeps_latest_versions() {
+        let (archive, dir) = temp_archive();
+        let archive = archive.with_keep_versions(2);
+        for version in ["1.0.0", "1.1.0", "1.2.0"] {
+            let url = format!("https://docs.rs/demo/{version}/demo/");
+            archive
+                .store_page("demo", version, &url, "<p>demo</p>")
+                .await
+                .unwrap();
+            // File modification times need to differ between versions
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+
+        assert_eq!(archive.prune("demo").await.unwrap(), vec!["1.0.0"]);
+        assert!(!archive.has_pages("demo", "1.0.0").await.unwrap());
+        assert!(archive.has_pages("demo", "1.2.0").await.unwrap());
+        assert!(archive.prune("demo").await.unwrap().is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
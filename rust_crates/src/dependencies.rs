@@ -0,0 +1,87 @@
+//! Dependencies a crate version declares, as listed by crates.io.
+
+use crate::{is_std_crate, RustLoader};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// A dependency declared by a crate version
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateDependency {
+    /// Name of the depended-on crate
+    #[serde(alias = "crate_id")]
+    pub name: String,
+    /// Version requirement, e.g. `^1.0`
+    pub req: String,
+    /// `normal`, `dev` or `build`
+    #[serde(default = "default_kind")]
+    pub kind: String,
+    #[serde(default)]
+    pub optional: bool,
+}
+
+fn default_kind() -> String {
+    "normal".to_string()
+}
+
+/// Parse a crates.io `/dependencies` response body
+///
+/// # Errors
+/// Returns an error if the body is not a crates.io dependencies payload.
+pub fn parse_dependencies(body: &str) -> Result<Vec<CrateDependency>> {
+    #[derive(Deserialize)]
+    struct Payload {
+        dependencies: Vec<CrateDependency>,
+    }
+    let payload: Payload =
+        serde_json::from_str(body).map_err(|e| anyhow!("Parse crates.io dependencies: {}", e))?;
+    Ok(payload.dependencies)
+}
+
+impl RustLoader {
+    /// Fetch the dependencies crates.io lists for a crate version. Standard
+    /// library crates have none to record.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response cannot be parsed.
+    pub async fn fetch_dependencies(
+        &mut self,
+        crate_name: &str,
+        version: &str,
+    ) -> Result<Vec<CrateDependency>> {
+        if is_std_crate(crate_name) {
+            return Ok(Vec::new());
+        }
+        let url = format!("https://crates.io/api/v1/crates/{crate_name}/{version}/dependencies");
+        let text = self.get_text(&url).await?;
+        parse_dependencies(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_dependencies;
+
+    #[test]
+    fn test_parses_crates_io_dependencies() {
+        let body = r#"{
+            "dependencies": [
+                {"id": 1, "version_id": 7, "crate_id": "serde", "req": "^1.0.100",
+                 "optional": false, "default_features": true, "features": ["derive"],
+                 "target": null, "kind": "normal", "downloads": 0},
+                {"id": 2, "version_id": 7, "crate_id": "tokio", "req": "^1", "optional": true,
+                 "default_features": true, "features": [], "target": null, "kind": "dev",
+                 "downloads": 0}
+            ]
+        }"#;
+        let deps = parse_dependencies(body).unwrap();
+        assert_eq!(deps.len(), 2);
+        assert_eq!(deps[0].name, "serde");
+        assert_eq!(deps[0].req, "^1.0.100");
+        assert_eq!(deps[0].kind, "normal");
+        assert!(!deps[0].optional);
+        assert_eq!(deps[1].kind, "dev");
+        assert!(deps[1].optional);
+
+        assert!(parse_dependencies("{}").is_err());
+    }
+}
@@ -0,0 +1,181 @@
+//! Estimate the size of a docs.rs crawl from the crate's index pages, without crawling it.
+
+use crate::{RateLimiter, RustLoader};
+use scraper::Html;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+use tracing::debug;
+
+/// Rough token count of a docs.rs item page once its doc blocks are extracted
+pub const ESTIMATED_TOKENS_PER_PAGE: usize = 700;
+
+/// Projected scope of ingesting one crate version
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrawlEstimate {
+    pub crate_name: String,
+    pub version: String,
+    /// Distinct crawlable item links on the index pages
+    pub discovered_links: usize,
+    /// Whether `all.html` (the full item list) was available
+    pub all_items_page: bool,
+    /// Pages the crawl would fetch, capped at `page_limit`
+    pub estimated_pages: usize,
+    pub page_limit: usize,
+    /// Time the rate limit alone imposes on fetching `estimated_pages`
+    pub estimated_crawl_secs: u64,
+    pub estimated_tokens: usize,
+}
+
+/// Estimate a crawl from the crate root page and, when available, `all.html`.
+///
+/// Every distinct crawlable link counts as one page plus the root, capped
+/// at `max_pages`. The crawl duration assumes the token bucket is the
+/// bottleneck: `burst` pages go out immediately and each further page waits
+/// one `interval`, however many workers run.
+#[must_use]
+pub fn estimate_crawl(
+    crate_name: &str,
+    version: &str,
+    root_html: &str,
+    all_items_html: Option<&str>,
+    max_pages: usize,
+    interval: Duration,
+    burst: u32,
+) -> CrawlEstimate {
+    let root_url = RustLoader::root_url(crate_name, version);
+    let mut links: HashSet<String> = HashSet::new();
+    for (page_url, html) in [
+        (format!("{root_url}/index.html"), Some(root_html)),
+        (format!("{root_url}/all.html"), all_items_html),
+    ] {
+        if let Some(html) = html {
+            let document = Html::parse_document(html);
+            links.extend(RustLoader::crate_links(
+                &document, &page_url, crate_name, version,
+            ));
+        }
+    }
+    // Anchors and index aliases of the root are not separate pages
+    links.retain(|link| {
+        let link = link.split('#').next().unwrap_or(link);
+        let link = link.trim_end_matches("index.html").trim_end_matches('/');
+        link != root_url && !link.ends_with("/all.html")
+    });
+
+    let estimated_pages = (links.len() + 1).min(max_pages);
+    let waits = u32::try_from(estimated_pages.saturating_sub(burst as usize)).unwrap_or(u32::MAX);
+    CrawlEstimate {
+        crate_name: crate_name.to_string(),
+        version: version.to_string(),
+        discovered_links: links.len(),
+        all_items_page: all_items_html.is_some(),
+        estimated_pages,
+        page_limit: max_pages,
+        estimated_crawl_secs: interval.saturating_mul(waits).as_secs(),
+        estimated_tokens: estimated_pages * ESTIMATED_TOKENS_PER_PAGE,
+    }
+}
+
+impl RustLoader {
+    /// Fetch the crate's docs.rs index pages and estimate a crawl of them.
+    ///
+    /// A missing `all.html` is tolerated; the estimate then relies on the
+    /// links of the root page alone.
+    ///
+    /// # Errors
+    /// Returns an error if the root page cannot be fetched.
+    pub async fn estimate_crawl(
+        &mut self,
+        crate_name: &str,
+        version: &str,
+    ) -> anyhow::Result<CrawlEstimate> {
+        let root_url = Self::root_url(crate_name, version);
+        let root_html = self.get_text(&format!("{root_url}/index.html")).await?;
+        let all_items_html = match self.get_text(&format!("{root_url}/all.html")).await {
+            Ok(html) => Some(html),
+            Err(e) => {
+                debug!("No all.html for {} {}: {}", crate_name, version, e);
+                None
+            }
+        };
+        let (interval, burst) = RateLimiter::configured_limits();
+        Ok(estimate_crawl(
+            crate_name,
+            version,
+            &root_html,
+            all_items_html.as_deref(),
+            Self::max_pages(),
+            interval,
+            burst,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::estimate_crawl;
+    use std::time::Duration;
+
+    const ROOT: &str = r#"<html><body>
+        <a href="index.html">demo</a>
+        <a href="all.html">All Items</a>
+        <a href="struct.Client.html">Client</a>
+        <a href="fn.connect.html">connect</a>
+        <a href="io/index.html">io</a>
+        <a href="struct.Client.html#method.new">new</a>
+        <a href="../src/demo/lib.rs.html">source</a>
+        <a href="https://docs.rs/other/1.0.0/other/index.html">other crate</a>
+    </body></html>"#;
+
+    const ALL_ITEMS: &str = r#"<html><body>
+        <a href="struct.Client.html">Client</a>
+        <a href="fn.connect.html">connect</a>
+        <a href="io/struct.Reader.html">io::Reader</a>
+        <a href="io/trait.Read.html">io::Read</a>
+        <a href="macro.demo.html">demo!</a>
+    </body></html>"#;
+
+    #[test]
+    fn test_counts_distinct_item_links() {
+        let estimate = estimate_crawl(
+            "demo",
+            "1.0.0",
+            ROOT,
+            Some(ALL_ITEMS),
+            2000,
+            Duration::from_secs(6),
+            1,
+        );
+        // Client, connect, io, io::Reader, io::Read, demo! plus the root
+        assert_eq!(estimate.discovered_links, 6);
+        assert!(estimate.all_items_page);
+        assert_eq!(estimate.estimated_pages, 7);
+        assert_eq!(estimate.estimated_crawl_secs, 36);
+        assert_eq!(
+            estimate.estimated_tokens,
+            7 * super::ESTIMATED_TOKENS_PER_PAGE
+        );
+    }
+
+    #[test]
+    fn test_root_page_alone_and_page_limit() {
+        let estimate = estimate_crawl("demo", "1.0.0", ROOT, None, 2000, Duration::from_secs(6), 2);
+        assert_eq!(estimate.discovered_links, 3);
+        assert!(!estimate.all_items_page);
+        assert_eq!(estimate.estimated_pages, 4);
+        assert_eq!(estimate.estimated_crawl_secs, 12);
+
+        let capped = estimate_crawl(
+            "demo",
+            "1.0.0",
+            ROOT,
+            Some(ALL_ITEMS),
+            3,
+            Duration::from_secs(6),
+            1,
+        );
+        assert_eq!(capped.estimated_pages, 3);
+        assert_eq!(capped.estimated_crawl_secs, 12);
+    }
+}
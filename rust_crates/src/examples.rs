@@ -0,0 +1,214 @@
+//! Code examples pulled out of rustdoc pages as documents of their own.
+//!
+//! Rustdoc renders doc-comment examples as `<pre class="rust">` blocks inside
+//! the item's doc block. Each runnable example becomes a [`DocPage`] with
+//! `item_type` [`EXAMPLE_ITEM_TYPE`], so a search for "an example of
+//! `tokio::select!`" can return the snippet rather than the whole page.
+
+use crate::{DocPage, RustLoader, ITEM_PREFIXES};
+use scraper::{ElementRef, Html, Selector};
+use url::Url;
+
+/// `item_type` of pages holding a single code example
+pub const EXAMPLE_ITEM_TYPE: &str = "example";
+
+/// Examples with fewer non-blank lines than this are skipped
+pub const MIN_EXAMPLE_LINES: usize = 3;
+
+/// Line starts of declarations, attributes and comments; an example made only
+/// of these (and where-clause bounds) shows a signature, not usage
+const DECLARATION_STARTS: &[&str] = &[
+    "pub ",
+    "pub(",
+    "fn ",
+    "async fn ",
+    "const fn ",
+    "unsafe fn ",
+    "struct ",
+    "enum ",
+    "trait ",
+    "type ",
+    "const ",
+    "static ",
+    "impl",
+    "where",
+    "#[",
+    "//",
+];
+
+/// Extract the runnable examples of the page at `parent`.
+///
+/// Only `rust` blocks inside doc blocks count; item declarations, examples
+/// marked `compile_fail`, examples shorter than [`MIN_EXAMPLE_LINES`] and
+/// signature-only blocks are skipped. Examples are numbered from 1 in page
+/// order and link back to the parent page through [`DocPage::parent_url`].
+#[must_use]
+pub fn extract_examples(document: &Html, parent: &DocPage, crate_name: &str) -> Vec<DocPage> {
+    let Ok(code_selector) = Selector::parse("pre.rust") else {
+        return Vec::new();
+    };
+    let item_path = item_path(&parent.url, &parent.module_path, crate_name);
+
+    document
+        .select(&code_selector)
+        .filter(|pre| in_doc_block(pre) && !has_class(pre, "item-decl"))
+        .filter(|pre| !has_class(pre, "compile_fail"))
+        .map(|pre| pre.text().collect::<String>())
+        .map(|code| code.trim_end().trim_start_matches('\n').to_string())
+        .filter(|code| is_runnable_example(code))
+        .enumerate()
+        .map(|(i, code)| {
+            let index = i + 1;
+            DocPage {
+                url: format!("{}#example-{index}", parent.url),
+                content: format!("Example {index} of `{item_path}`\n\n```rust\n{code}\n```"),
+                item_type: EXAMPLE_ITEM_TYPE.to_string(),
+                module_path: format!("{item_path}#example-{index}"),
+                extracted_at: parent.extracted_at,
+                parent_url: Some(parent.url.clone()),
+            }
+        })
+        .collect()
+}
+
+/// Whether `code` is long enough to be worth its own document and shows more
+/// than a signature
+#[must_use]
+pub fn is_runnable_example(code: &str) -> bool {
+    let lines: Vec<&str> = code
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    if lines.len() < MIN_EXAMPLE_LINES {
+        return false;
+    }
+    !lines.iter().all(|line| {
+        DECLARATION_STARTS
+            .iter()
+            .any(|start| line.starts_with(start))
+            || line.ends_with(',')
+            || line.chars().all(|c| matches!(c, '{' | '}' | ';' | ')'))
+    })
+}
+
+/// Path of the item a page documents: its module path plus the item name
+/// from the file name (`tokio::select` for `tokio/macro.select.html`)
+fn item_path(url: &str, module_path: &str, crate_name: &str) -> String {
+    let file_name = Url::parse(url)
+        .ok()
+        .and_then(|parsed| {
+            parsed
+                .path_segments()
+                .and_then(|mut segments| segments.next_back().map(ToString::to_string))
+        })
+        .unwrap_or_default();
+    let item_name = file_name
+        .strip_suffix(".html")
+        .and_then(|stem| stem.split_once('.'))
+        .filter(|(prefix, _)| ITEM_PREFIXES.iter().any(|(p, _)| p == prefix))
+        .map(|(_, name)| name.to_string());
+
+    let module_path = if module_path.is_empty() {
+        RustLoader::extract_module_path(url, crate_name)
+    } else {
+        module_path.to_string()
+    };
+    match item_name {
+        Some(name) => format!("{module_path}::{name}"),
+        None => module_path,
+    }
+}
+
+fn in_doc_block(element: &ElementRef<'_>) -> bool {
+    element
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .any(|ancestor| has_class(&ancestor, "docblock"))
+}
+
+fn has_class(element: &ElementRef<'_>, class: &str) -> bool {
+    element.value().classes().any(|c| c == class)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract_examples, is_runnable_example, EXAMPLE_ITEM_TYPE};
+    use crate::DocPage;
+    use chrono::Utc;
+    use scraper::Html;
+
+    const SELECT_URL: &str = "https://docs.rs/tokio/1.38.0/tokio/macro.select.html";
+
+    /// docs.rs page of `tokio::select!`, trimmed to its main content
+    const SELECT_PAGE: &str = include_str!("../tests/fixtures/tokio_macro_select.html");
+
+    fn parent(url: &str, module_path: &str) -> DocPage {
+        DocPage {
+            url: url.to_string(),
+            content: "Waits on multiple concurrent branches".to_string(),
+            item_type: "macro".to_string(),
+            module_path: module_path.to_string(),
+            extracted_at: Utc::now(),
+            parent_url: None,
+        }
+    }
+
+    #[test]
+    fn test_extracts_runnable_examples_from_docs_rs_page() {
+        let document = Html::parse_document(SELECT_PAGE);
+        let examples = extract_examples(&document, &parent(SELECT_URL, "tokio"), "tokio");
+
+        // Six rust blocks in the doc block: the two-line snippet, the
+        // signature and the compile_fail example are skipped
+        assert_eq!(examples.len(), 3);
+        for (i, example) in examples.iter().enumerate() {
+            let index = i + 1;
+            assert_eq!(example.item_type, EXAMPLE_ITEM_TYPE);
+            assert_eq!(example.url, format!("{SELECT_URL}#example-{index}"));
+            assert_eq!(
+                example.module_path,
+                format!("tokio::select#example-{index}")
+            );
+            assert_eq!(example.parent_url.as_deref(), Some(SELECT_URL));
+            assert!(example
+                .content
+                .starts_with(&format!("Example {index} of `tokio::select`\n\n```rust\n")));
+        }
+
+        assert!(examples[0].content.contains("async fn do_stuff_async() {"));
+        assert!(examples[1]
+            .content
+            .contains("stream1.next() => v.unwrap(),"));
+        assert!(examples[2].content.contains("tokio::pin!(sleep);"));
+        // The macro declaration above the doc block is not an example
+        assert!(examples.iter().all(|e| !e.content.contains("macro_rules!")));
+        assert!(examples.iter().all(|e| !e.content.contains("&mut data")));
+    }
+
+    #[test]
+    fn test_module_pages_name_examples_after_the_module() {
+        let url = "https://docs.rs/tokio/1.38.0/tokio/sync/index.html";
+        let html = "<div class=\"docblock\"><pre class=\"rust rust-example-rendered\"><code>\
+                    use tokio::sync::oneshot;\n\nlet (tx, rx) = oneshot::channel();\n\
+                    tx.send(3).unwrap();\nassert_eq!(rx.await, Ok(3));</code></pre></div>";
+        let document = Html::parse_document(html);
+        let examples = extract_examples(&document, &parent(url, "tokio::sync"), "tokio");
+        assert_eq!(examples.len(), 1);
+        assert_eq!(examples[0].module_path, "tokio::sync#example-1");
+    }
+
+    #[test]
+    fn test_short_and_signature_only_blocks_are_not_examples() {
+        assert!(!is_runnable_example("let x = 1;\nprintln!(\"{x}\");"));
+        assert!(!is_runnable_example(
+            "pub fn spawn<F>(future: F) -> JoinHandle<F::Output>\nwhere\n    F: Future + Send + 'static,\n    F::Output: Send + 'static,"
+        ));
+        assert!(!is_runnable_example(
+            "pub struct Config {\n    pub retries: u32,\n    pub verbose: bool,\n}"
+        ));
+        assert!(is_runnable_example(
+            "let handle = tokio::spawn(async {\n    42\n});\nassert_eq!(handle.await.unwrap(), 42);"
+        ));
+    }
+}
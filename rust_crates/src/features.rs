@@ -0,0 +1,281 @@
+//! Which crate features the docs.rs build of a release enabled.
+//!
+//! docs.rs builds a release with its default features unless the crate's
+//! `[package.metadata.docs.rs]` table asks for `all-features`,
+//! `no-default-features` or extra `features`. Both the table and the feature
+//! definitions are read from the `Cargo.toml` docs.rs shows in its source
+//! view; `builds.json` says whether the latest build succeeded.
+
+use crate::RustLoader;
+use anyhow::{anyhow, Result};
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Features enabled by the docs.rs build of one release
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuiltFeatures {
+    /// Enabled features, sorted; `default` is listed when default features were on
+    pub features: Vec<String>,
+    /// Whether the build enabled every feature
+    pub all_features: bool,
+    /// Whether the latest docs.rs build succeeded; `None` when unknown
+    #[serde(default)]
+    pub build_succeeded: Option<bool>,
+}
+
+impl BuiltFeatures {
+    /// Requested features the build did not enable
+    #[must_use]
+    pub fn missing<'a>(&self, requested: &'a [String]) -> Vec<&'a str> {
+        requested
+            .iter()
+            .map(String::as_str)
+            .filter(|feature| !self.features.iter().any(|built| built == feature))
+            .collect()
+    }
+}
+
+/// Whether the newest build in a docs.rs `builds.json` body succeeded
+///
+/// docs.rs reports `build_status` as a boolean or, in newer responses, as
+/// `"success"`/`"failure"`/`"in_progress"`. `None` when no build is listed.
+///
+/// # Errors
+/// Returns an error if the body is not a docs.rs builds payload.
+pub fn latest_build_succeeded(body: &str) -> Result<Option<bool>> {
+    #[derive(Deserialize)]
+    struct Build {
+        build_status: serde_json::Value,
+    }
+    let builds: Vec<Build> =
+        serde_json::from_str(body).map_err(|e| anyhow!("Parse docs.rs builds: {}", e))?;
+    Ok(builds.first().map(|build| match &build.build_status {
+        serde_json::Value::Bool(succeeded) => *succeeded,
+        serde_json::Value::String(status) => status == "success",
+        _ => false,
+    }))
+}
+
+/// Features a docs.rs build of the crate described by `manifest` enables
+///
+/// Features turned on by enabled features are followed; entries naming a
+/// dependency's feature (`serde/derive`) or a `dep:` dependency are not
+/// features of this crate and are skipped. With `all-features`, optional
+/// dependencies count as the implicit features Cargo gives them.
+///
+/// # Errors
+/// Returns an error if `manifest` is not valid TOML.
+pub fn built_features(manifest: &str) -> Result<BuiltFeatures> {
+    let manifest: toml::Value =
+        toml::from_str(manifest).map_err(|e| anyhow!("Parse Cargo.toml: {}", e))?;
+    let definitions: BTreeMap<String, Vec<String>> = manifest
+        .get("features")
+        .and_then(toml::Value::as_table)
+        .map(|table| {
+            table
+                .iter()
+                .map(|(name, enables)| (name.clone(), string_list(Some(enables))))
+                .collect()
+        })
+        .unwrap_or_default();
+    let config = manifest
+        .get("package")
+        .and_then(|package| package.get("metadata"))
+        .and_then(|metadata| metadata.get("docs"))
+        .and_then(|docs| docs.get("rs"));
+    let flag = |key: &str| {
+        config
+            .and_then(|config| config.get(key))
+            .and_then(toml::Value::as_bool)
+            .unwrap_or(false)
+    };
+    let all_features = flag("all-features");
+
+    let mut enabled = BTreeSet::new();
+    if all_features {
+        enabled.extend(definitions.keys().cloned());
+        enabled.extend(optional_dependencies(&manifest));
+    } else {
+        let mut pending = string_list(config.and_then(|config| config.get("features")));
+        if !flag("no-default-features") {
+            pending.push("default".to_string());
+        }
+        while let Some(feature) = pending.pop() {
+            if feature.contains('/') || feature.starts_with("dep:") {
+                continue;
+            }
+            if let Some(enables) = definitions.get(&feature) {
+                pending.extend(enables.iter().cloned());
+            }
+            enabled.insert(feature);
+        }
+    }
+    // A crate without a `default` feature has nothing to turn on by default
+    if !definitions.contains_key("default") {
+        enabled.remove("default");
+    }
+
+    Ok(BuiltFeatures {
+        features: enabled.into_iter().collect(),
+        all_features,
+        build_succeeded: None,
+    })
+}
+
+fn string_list(value: Option<&toml::Value>) -> Vec<String> {
+    value
+        .and_then(toml::Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str().map(ToString::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Names of the optional dependencies a manifest declares
+fn optional_dependencies(manifest: &toml::Value) -> Vec<String> {
+    manifest
+        .get("dependencies")
+        .and_then(toml::Value::as_table)
+        .map(|dependencies| {
+            dependencies
+                .iter()
+                .filter(|(_, spec)| {
+                    spec.get("optional")
+                        .and_then(toml::Value::as_bool)
+                        .unwrap_or(false)
+                })
+                .map(|(name, _)| name.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The file text of a docs.rs source view page, or the body itself when it is plain text
+fn source_text(body: &str) -> String {
+    if toml::from_str::<toml::Value>(body).is_ok() {
+        return body.to_string();
+    }
+    let document = Html::parse_document(body);
+    Selector::parse("#source-code pre, pre")
+        .ok()
+        .and_then(|selector| {
+            document
+                .select(&selector)
+                .next()
+                .map(|pre| pre.text().collect())
+        })
+        .unwrap_or_default()
+}
+
+impl RustLoader {
+    /// Features the docs.rs build of `crate_name` `version` enabled
+    ///
+    /// The build outcome is left unknown if `builds.json` cannot be read.
+    ///
+    /// # Errors
+    /// Returns an error if the crate's `Cargo.toml` cannot be fetched or parsed.
+    pub async fn fetch_built_features(
+        &mut self,
+        crate_name: &str,
+        version: &str,
+    ) -> Result<BuiltFeatures> {
+        let source = self
+            .get_text(&format!(
+                "https://docs.rs/crate/{crate_name}/{version}/source/Cargo.toml"
+            ))
+            .await?;
+        let mut built = built_features(&source_text(&source))?;
+        built.build_succeeded = match self
+            .get_text(&format!(
+                "https://docs.rs/crate/{crate_name}/{version}/builds.json"
+            ))
+            .await
+        {
+            Ok(body) => latest_build_succeeded(&body).ok().flatten(),
+            Err(e) => {
+                tracing::warn!(
+                    "Could not read docs.rs builds of {} {}: {}",
+                    crate_name,
+                    version,
+                    e
+                );
+                None
+            }
+        };
+        Ok(built)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+[package]
+name = "demo"
+version = "1.0.0"
+
+[package.metadata.docs.rs]
+features = ["tls"]
+
+[dependencies]
+serde = { version = "1", optional = true }
+rustls = { version = "0.23", optional = true }
+
+[features]
+default = ["std"]
+std = []
+tls = ["dep:rustls", "net"]
+net = []
+derive = ["serde/derive"]
+"#;
+
+    #[test]
+    fn test_built_features_follow_docs_rs_metadata() {
+        let built = built_features(MANIFEST).unwrap();
+        assert_eq!(built.features, vec!["default", "net", "std", "tls"]);
+        assert!(!built.all_features);
+        assert_eq!(
+            built.missing(&["tls".to_string(), "derive".to_string()]),
+            vec!["derive"]
+        );
+
+        let all = MANIFEST.replace(r#"features = ["tls"]"#, "all-features = true");
+        let built = built_features(&all).unwrap();
+        assert!(built.all_features);
+        assert_eq!(
+            built.features,
+            vec!["default", "derive", "net", "rustls", "serde", "std", "tls"]
+        );
+
+        let bare = MANIFEST.replace(r#"features = ["tls"]"#, "no-default-features = true");
+        assert!(built_features(&bare).unwrap().features.is_empty());
+    }
+
+    #[test]
+    fn test_source_view_and_builds() {
+        let html = format!(
+            "<html><body><div id=\"source-code\"><pre><code>{}</code></pre></div></body></html>",
+            MANIFEST.replace('"', "&quot;")
+        );
+        assert_eq!(source_text(&html).trim(), MANIFEST.trim());
+        assert_eq!(source_text(MANIFEST), MANIFEST);
+
+        assert_eq!(
+            latest_build_succeeded(
+                r#"[{"id": 2, "build_status": true}, {"id": 1, "build_status": false}]"#
+            )
+            .unwrap(),
+            Some(true)
+        );
+        assert_eq!(
+            latest_build_succeeded(r#"[{"build_status": "failure"}]"#).unwrap(),
+            Some(false)
+        );
+        assert_eq!(latest_build_succeeded("[]").unwrap(), None);
+    }
+}
@@ -1,21 +1,79 @@
 //! Rust crate ingestion: fetch crate metadata and docs (stub docs.rs scraping).
 
 use anyhow::{anyhow, Result};
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use reqwest::Client;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
 // (no serde_json::Value import)
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::Duration;
-use tokio::time;
-use tracing::{debug, info};
+use tokio::task::JoinSet;
+use tokio::time::{self, Instant};
+use tracing::{debug, info, warn};
 use url::Url;
 
+mod archive;
+mod dependencies;
+mod estimate;
+mod examples;
+mod features;
+mod local;
+mod lookup;
+mod markdown;
+mod std_docs;
+mod versions;
+
+pub use archive::RawArchive;
+pub use dependencies::{parse_dependencies, CrateDependency};
+pub use estimate::{estimate_crawl, CrawlEstimate, ESTIMATED_TOKENS_PER_PAGE};
+pub use examples::{extract_examples, is_runnable_example, EXAMPLE_ITEM_TYPE, MIN_EXAMPLE_LINES};
+pub use features::{built_features, latest_build_succeeded, BuiltFeatures};
+pub use local::{LocalCrate, LocalDocsSource, LOCAL_ORIGIN};
+pub use lookup::{closest_names, parse_search_results, CrateLookup, MAX_SUGGESTIONS};
+pub use markdown::{docblock_markdown, page_markdown, plain_text};
+pub use std_docs::{
+    is_rust_channel, is_std_crate, parse_rust_release, DEFAULT_RUST_CHANNEL, RUST_CHANNELS,
+    STD_CRATES,
+};
+pub use versions::{is_newer_version, parse_versions, resolve_version, CrateVersion};
+
+/// docs.rs file name prefixes and the `item_type` recorded for them
+const ITEM_PREFIXES: &[(&str, &str)] = &[
+    ("struct", "struct"),
+    ("fn", "function"),
+    ("trait", "trait"),
+    ("enum", "enum"),
+    ("macro", "macro"),
+    ("constant", "constant"),
+    ("type", "type"),
+    ("union", "union"),
+    ("attr", "attribute"),
+    ("derive", "derive"),
+];
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+/// HTTP client with a token bucket shared by every crawl worker.
+///
+/// One token is earned per `CRATE_CRAWL_INTERVAL_MS` (default 6000, i.e. 10
+/// requests per minute) up to `CRATE_CRAWL_BURST` tokens (default 1), so the
+/// request budget holds no matter how many workers fetch through it.
 #[derive(Debug)]
 pub struct RateLimiter {
     client: Client,
-    last_request: Option<std::time::Instant>,
-    min_interval: Duration,
+    bucket: Mutex<Bucket>,
+    interval: Duration,
+    burst: u32,
+    /// Largest response body read, if limited
+    max_page_bytes: Option<u64>,
 }
 
 impl RateLimiter {
@@ -25,19 +83,85 @@ impl RateLimiter {
     /// Panics if the HTTP client cannot be created.
     #[must_use]
     pub fn new() -> Self {
-        let min_interval = std::env::var("CRATE_CRAWL_INTERVAL_MS")
+        let (interval, burst) = Self::configured_limits();
+        Self::with_limits(interval, burst)
+    }
+
+    /// Token interval and burst from `CRATE_CRAWL_INTERVAL_MS` / `CRATE_CRAWL_BURST`
+    #[must_use]
+    pub fn configured_limits() -> (Duration, u32) {
+        let interval = std::env::var("CRATE_CRAWL_INTERVAL_MS")
             .ok()
             .and_then(|v| v.parse::<u64>().ok())
             .map_or_else(|| Duration::from_secs(6), Duration::from_millis);
+        let burst = std::env::var("CRATE_CRAWL_BURST")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(1);
+        (interval, burst.max(1))
+    }
 
+    /// Create a rate limiter earning one token per `interval`, holding at most `burst`.
+    ///
+    /// A zero `interval` disables limiting.
+    ///
+    /// # Panics
+    /// Panics if the HTTP client cannot be created.
+    #[must_use]
+    pub fn with_limits(interval: Duration, burst: u32) -> Self {
+        let burst = burst.max(1);
         Self {
             client: Client::builder()
                 .timeout(Duration::from_secs(30))
                 .user_agent("doc-server-rust-loader/1.0")
                 .build()
                 .expect("Failed to create HTTP client"),
-            last_request: None,
-            min_interval,
+            bucket: Mutex::new(Bucket {
+                tokens: f64::from(burst),
+                last_refill: Instant::now(),
+            }),
+            interval,
+            burst,
+            max_page_bytes: None,
+        }
+    }
+
+    /// Refuse response bodies larger than `bytes`
+    ///
+    /// A page is rejected from its `Content-Length` before the body is read;
+    /// without one, reading stops as soon as the body passes the limit.
+    #[must_use]
+    pub const fn with_max_page_bytes(mut self, bytes: u64) -> Self {
+        self.max_page_bytes = Some(bytes);
+        self
+    }
+
+    /// Take a token, or return how long until one is earned
+    fn try_acquire_at(&self, now: Instant) -> Result<(), Duration> {
+        if self.interval.is_zero() {
+            return Ok(());
+        }
+        let mut bucket = self.bucket.lock().unwrap_or_else(PoisonError::into_inner);
+        let earned = now
+            .saturating_duration_since(bucket.last_refill)
+            .as_secs_f64()
+            / self.interval.as_secs_f64();
+        bucket.tokens = (bucket.tokens + earned).min(f64::from(self.burst));
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(self.interval.mul_f64(1.0 - bucket.tokens))
+        }
+    }
+
+    /// Wait until the shared budget allows another request
+    async fn acquire(&self) {
+        while let Err(wait_time) = self.try_acquire_at(Instant::now()) {
+            debug!("Rate limiting: waiting {:.2}s", wait_time.as_secs_f64());
+            time::sleep(wait_time).await;
         }
     }
 
@@ -45,42 +169,303 @@ impl RateLimiter {
     ///
     /// # Errors
     /// Returns an error if the request fails or the response status is not successful.
-    pub async fn get(&mut self, url: &str) -> Result<reqwest::Response> {
-        if let Some(last) = self.last_request {
-            let elapsed = last.elapsed();
-            if elapsed < self.min_interval {
-                let wait_time = self.min_interval - elapsed;
-                debug!("Rate limiting: waiting {:.2}s", wait_time.as_secs_f64());
-                time::sleep(wait_time).await;
-            }
+    pub async fn get(&self, url: &str) -> Result<reqwest::Response> {
+        let resp = self.send(url, &CacheValidators::default()).await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("HTTP status: {}", resp.status()));
         }
+        Ok(resp)
+    }
+
+    /// Rate-limited GET sending `If-None-Match`/`If-Modified-Since` for the given validators
+    async fn send(&self, url: &str, validators: &CacheValidators) -> Result<reqwest::Response> {
+        self.acquire().await;
         info!("HTTP GET: {}", url);
-        let resp = self
-            .client
-            .get(url)
+        let mut request = self.client.get(url);
+        if let Some(etag) = &validators.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+        }
+        request
             .send()
             .await
-            .map_err(|e| anyhow!("HTTP failed: {}", e))?;
-        self.last_request = Some(std::time::Instant::now());
-        if !resp.status().is_success() {
-            return Err(anyhow!("HTTP status: {}", resp.status()));
+            .map_err(|e| anyhow!("HTTP failed: {}", e))
+    }
+
+    /// Body of `resp` as text, within the page size limit
+    async fn read_body(&self, mut resp: reqwest::Response) -> Result<String> {
+        let Some(limit) = self.max_page_bytes else {
+            return Ok(resp.text().await?);
+        };
+        if let Some(length) = resp.content_length().filter(|length| *length > limit) {
+            return Err(anyhow!(
+                "{}: {} bytes (limit: {})",
+                PAGE_TOO_LARGE,
+                length,
+                limit
+            ));
         }
-        Ok(resp)
+        let mut body = Vec::new();
+        while let Some(chunk) = resp.chunk().await? {
+            body.extend_from_slice(&chunk);
+            if body.len() as u64 > limit {
+                return Err(anyhow!(
+                    "{}: over {} bytes (limit: {})",
+                    PAGE_TOO_LARGE,
+                    body.len(),
+                    limit
+                ));
+            }
+        }
+        Ok(String::from_utf8_lossy(&body).into_owned())
     }
 }
 
+/// Start of the error returned for a response over the page size limit
+pub const PAGE_TOO_LARGE: &str = "Page too large";
+
 impl Default for RateLimiter {
     fn default() -> Self {
         Self::new()
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Source of page bodies for the crawler; abstracted so tests can serve pages without HTTP
+#[async_trait]
+pub trait PageFetcher: Send + Sync {
+    /// Fetch the body of `url` as text.
+    ///
+    /// Called concurrently by crawl workers.
+    ///
+    /// # Errors
+    /// Returns an error if the page cannot be fetched.
+    async fn fetch_text(&self, url: &str) -> Result<String>;
+
+    /// Fetch `url` unless it still matches `validators`.
+    ///
+    /// Fetchers without conditional request support always fetch the page.
+    ///
+    /// # Errors
+    /// Returns an error if the page cannot be fetched.
+    async fn fetch_conditional(
+        &self,
+        url: &str,
+        _validators: &CacheValidators,
+    ) -> Result<FetchOutcome> {
+        Ok(FetchOutcome::Modified {
+            body: self.fetch_text(url).await?,
+            validators: CacheValidators::default(),
+        })
+    }
+}
+
+#[async_trait]
+impl PageFetcher for RateLimiter {
+    async fn fetch_text(&self, url: &str) -> Result<String> {
+        let resp = self.get(url).await?;
+        self.read_body(resp).await
+    }
+
+    async fn fetch_conditional(
+        &self,
+        url: &str,
+        validators: &CacheValidators,
+    ) -> Result<FetchOutcome> {
+        let resp = self.send(url, validators).await?;
+        if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(FetchOutcome::NotModified);
+        }
+        if !resp.status().is_success() {
+            return Err(anyhow!("HTTP status: {}", resp.status()));
+        }
+        let header = |name: reqwest::header::HeaderName| {
+            resp.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(ToString::to_string)
+        };
+        let validators = CacheValidators {
+            etag: header(reqwest::header::ETAG),
+            last_modified: header(reqwest::header::LAST_MODIFIED),
+        };
+        Ok(FetchOutcome::Modified {
+            body: self.read_body(resp).await?,
+            validators,
+        })
+    }
+}
+
+/// `ETag`/`Last-Modified` values a server returned for a page
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheValidators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl CacheValidators {
+    /// Whether there is anything to send in a conditional request
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+/// Result of a conditional fetch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FetchOutcome {
+    /// The page changed (or was never cached)
+    Modified {
+        body: String,
+        validators: CacheValidators,
+    },
+    /// The server answered `304 Not Modified`
+    NotModified,
+}
+
+/// What the crawl remembers about a fetched page between runs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedPage {
+    pub validators: CacheValidators,
+    /// docs.rs links found on the page, so an unchanged page still extends the crawl
+    pub links: Vec<String>,
+}
+
+/// Store of [`CachedPage`]s for one crate version.
+///
+/// Implementations should only return entries for pages whose document is
+/// still stored, since an unchanged page is not fetched again.
+#[async_trait]
+pub trait FetchCache: Send + Sync {
+    /// Cached entry for `url`, if any.
+    ///
+    /// # Errors
+    /// Returns an error if the cache cannot be read.
+    async fn lookup(&self, url: &str) -> Result<Option<CachedPage>>;
+
+    /// Remember `entries` after their pages were stored.
+    ///
+    /// # Errors
+    /// Returns an error if the cache cannot be written.
+    async fn store(&self, entries: &[(String, CachedPage)]) -> Result<()>;
+}
+
+/// Resumable position of a docs.rs crawl, persisted between checkpoints
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrawlState {
+    /// URLs already taken off the queue
+    pub visited: Vec<String>,
+    /// URLs discovered but not yet visited, in BFS order
+    pub queue: Vec<String>,
+    /// Pages fetched so far (counts towards the page limit)
+    pub processed: usize,
+    /// Link depth from the crate root of queued URLs; URLs without one count as depth 0
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub depths: BTreeMap<String, usize>,
+}
+
+/// Pages handled between two progress reports of a crawl
+pub const PROGRESS_EVERY_PAGES: usize = 10;
+
+/// Snapshot of a running crawl, reported every [`PROGRESS_EVERY_PAGES`] pages
+/// and once more when the crawl ends
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CrawlProgress {
+    /// Pages fetched in full, including those fetched before a resume
+    pub pages_fetched: usize,
+    /// Pages discovered but not yet handled, including those being fetched
+    pub pages_queued: usize,
+    /// Pages kept without a full fetch: unchanged since the cached fetch or
+    /// already stored by an earlier attempt
+    pub pages_skipped: usize,
+    /// Time since the crawl started
+    pub elapsed: Duration,
+}
+
+impl CrawlProgress {
+    /// Share of the crawl done, from 0.0 to 1.0: pages handled out of those
+    /// handled plus still queued, with at most `max_pages` expected
+    #[must_use]
+    pub fn fraction(&self, max_pages: usize) -> f64 {
+        let done = self.pages_fetched + self.pages_skipped;
+        let expected = (done + self.pages_queued).min(max_pages).max(done).max(1);
+        #[allow(clippy::cast_precision_loss)]
+        let fraction = done as f64 / expected as f64;
+        fraction
+    }
+}
+
+/// Receives crawled pages in batches together with a checkpoint of the crawl.
+///
+/// The state passed alongside a batch already accounts for its pages, so a
+/// crawl resumed from it will not fetch them again.
+#[async_trait]
+pub trait CrawlSink: Send {
+    /// Persist `pages` and then the crawl `state`.
+    ///
+    /// # Errors
+    /// Returning an error aborts the crawl.
+    async fn checkpoint(&mut self, pages: Vec<DocPage>, state: &CrawlState) -> Result<()>;
+
+    /// Keep the stored documents of pages the server reported unchanged.
+    ///
+    /// Called with the pages answered `304 Not Modified` since the last
+    /// checkpoint, right before that checkpoint.
+    ///
+    /// # Errors
+    /// Returning an error aborts the crawl.
+    async fn keep_unchanged(&mut self, _urls: Vec<String>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A page as seen by a crawl worker
+enum Fetched {
+    Page {
+        body: String,
+        validators: CacheValidators,
+    },
+    /// Unchanged since the cached fetch; carries the links recorded then
+    Unchanged { links: Vec<String> },
+}
+
+impl Fetched {
+    fn from_outcome(outcome: FetchOutcome) -> Self {
+        match outcome {
+            FetchOutcome::Modified { body, validators } => Self::Page { body, validators },
+            // Without cached links the page cannot be replayed; treat it as empty
+            FetchOutcome::NotModified => Self::Unchanged { links: Vec::new() },
+        }
+    }
+}
+
+/// Sink that keeps every page in memory and ignores checkpoints
+#[derive(Debug, Default)]
+struct CollectSink {
+    pages: Vec<DocPage>,
+}
+
+#[async_trait]
+impl CrawlSink for CollectSink {
+    async fn checkpoint(&mut self, pages: Vec<DocPage>, _state: &CrawlState) -> Result<()> {
+        self.pages.extend(pages);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CrateMetadata {
     pub name: String,
     pub newest_version: String,
     pub description: Option<String>,
     pub documentation: Option<String>,
+    #[serde(default)]
+    pub repository: Option<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    #[serde(default)]
+    pub categories: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -90,10 +475,21 @@ pub struct DocPage {
     pub item_type: String,
     pub module_path: String,
     pub extracted_at: DateTime<Utc>,
+    /// Page an example was extracted from; `None` for crawled pages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_url: Option<String>,
 }
 
+/// Clones share the fetcher, and with it any rate limit
+#[derive(Clone)]
 pub struct RustLoader {
-    rate_limiter: RateLimiter,
+    fetcher: Arc<dyn PageFetcher>,
+    workers: usize,
+    max_depth: usize,
+    max_frontier: usize,
+    cache: Option<Arc<dyn FetchCache>>,
+    conditional: bool,
+    archive: Option<Arc<RawArchive>>,
 }
 impl Default for RustLoader {
     fn default() -> Self {
@@ -104,12 +500,122 @@ impl Default for RustLoader {
 impl RustLoader {
     #[must_use]
     pub fn new() -> Self {
+        Self::with_fetcher(Box::new(
+            RateLimiter::new().with_max_page_bytes(Self::max_page_bytes()),
+        ))
+    }
+
+    /// Create a loader that fetches pages through `fetcher`
+    #[must_use]
+    pub fn with_fetcher(fetcher: Box<dyn PageFetcher>) -> Self {
         Self {
-            rate_limiter: RateLimiter::new(),
+            fetcher: Arc::from(fetcher),
+            workers: Self::crawl_workers(),
+            max_depth: Self::crawl_max_depth(),
+            max_frontier: Self::crawl_max_frontier(),
+            cache: None,
+            conditional: false,
+            archive: None,
         }
     }
 
-    /// Load crate metadata and documentation pages.
+    /// Record page validators in `cache` while crawling.
+    ///
+    /// With `conditional`, cached pages are requested with
+    /// `If-None-Match`/`If-Modified-Since` and a `304` keeps the stored
+    /// document; without it every page is fetched in full and the cache is
+    /// only refreshed.
+    pub fn set_fetch_cache(&mut self, cache: Arc<dyn FetchCache>, conditional: bool) {
+        self.cache = Some(cache);
+        self.conditional = conditional;
+    }
+
+    /// Store the raw HTML of every fetched page in `archive`
+    pub fn set_raw_archive(&mut self, archive: Arc<RawArchive>) {
+        self.archive = Some(archive);
+    }
+
+    /// Use `workers` concurrent fetches while crawling
+    #[must_use]
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Follow links at most `max_depth` links away from the crate root
+    #[must_use]
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Keep at most `max_frontier` URLs queued, dropping the deepest beyond that
+    #[must_use]
+    pub fn with_max_frontier(mut self, max_frontier: usize) -> Self {
+        self.max_frontier = max_frontier.max(1);
+        self
+    }
+
+    /// Concurrent fetches per crawl (`CRATE_CRAWL_WORKERS`, default 2)
+    #[must_use]
+    pub fn crawl_workers() -> usize {
+        std::env::var("CRATE_CRAWL_WORKERS")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(2)
+            .max(1)
+    }
+
+    /// Link depth limit for a crawl (`CRATE_CRAWL_MAX_DEPTH`, default 8)
+    #[must_use]
+    pub fn crawl_max_depth() -> usize {
+        std::env::var("CRATE_CRAWL_MAX_DEPTH")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(8)
+    }
+
+    /// Queued URL limit for a crawl (`CRATE_CRAWL_MAX_FRONTIER`, default 10000)
+    #[must_use]
+    pub fn crawl_max_frontier() -> usize {
+        std::env::var("CRATE_CRAWL_MAX_FRONTIER")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(10_000)
+            .max(1)
+    }
+
+    /// Largest page a crawl reads (`CRATE_CRAWL_MAX_PAGE_BYTES`, default 5 MiB)
+    #[must_use]
+    pub fn max_page_bytes() -> u64 {
+        std::env::var("CRATE_CRAWL_MAX_PAGE_BYTES")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(5 * 1024 * 1024)
+    }
+
+    /// Page limit for a crawl (`CRATE_CRAWL_MAX_PAGES`, default 2000)
+    #[must_use]
+    pub fn max_pages() -> usize {
+        std::env::var("CRATE_CRAWL_MAX_PAGES")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(2000)
+    }
+
+    /// Fetch crate metadata from crates.io. Standard library crates, which
+    /// crates.io does not list, get fixed metadata for the stable channel.
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response cannot be parsed.
+    pub async fn load_crate_metadata(&mut self, crate_name: &str) -> Result<CrateMetadata> {
+        if is_std_crate(crate_name) {
+            return Ok(Self::std_crate_metadata(crate_name));
+        }
+        self.fetch_crate_metadata(crate_name).await
+    }
+
+    /// Load crate metadata and documentation pages, including the README when published.
     ///
     /// # Errors
     /// Returns an error if fetching metadata or pages fails.
@@ -122,64 +628,247 @@ impl RustLoader {
             "Loading crate docs: {} (version: {:?})",
             crate_name, version
         );
-        let meta = self.fetch_crate_metadata(crate_name).await?;
+        let meta = self.load_crate_metadata(crate_name).await?;
         let target = version.unwrap_or(&meta.newest_version);
-        let max_pages = std::env::var("CRATE_CRAWL_MAX_PAGES")
-            .ok()
-            .and_then(|v| v.parse::<usize>().ok())
-            .unwrap_or(2000);
-        let pages = self
-            .crawl_docs_rs(crate_name, target, Some(max_pages))
-            .await?;
+        let mut sink = CollectSink::default();
+        self.crawl_docs_rs(
+            crate_name,
+            target,
+            Self::max_pages(),
+            None,
+            &HashSet::new(),
+            usize::MAX,
+            &mut sink,
+            &mut |_| {},
+        )
+        .await?;
+        let mut pages = sink.pages;
+        pages.extend(self.fetch_readme(crate_name, target).await);
         Ok((meta, pages))
     }
 
-    #[allow(clippy::too_many_lines)]
-    async fn crawl_docs_rs(
+    /// Fetch the README crates.io renders for a crate version as a `readme` page.
+    ///
+    /// Crates without a README (or a failed request) yield `None`, as do
+    /// standard library crates.
+    pub async fn fetch_readme(&mut self, crate_name: &str, version: &str) -> Option<DocPage> {
+        if is_std_crate(crate_name) {
+            return None;
+        }
+        let url = Self::readme_url(crate_name, version);
+        let html = match self.get_text(&url).await {
+            Ok(t) => t,
+            Err(e) => {
+                debug!("No README for {} {}: {}", crate_name, version, e);
+                return None;
+            }
+        };
+        if let Some(archive) = &self.archive {
+            if let Err(e) = archive.store_page(crate_name, version, &url, &html).await {
+                warn!("{}", e);
+            }
+        }
+        Self::readme_page(crate_name, url, &html)
+    }
+
+    /// crates.io URL of a crate version's rendered README
+    fn readme_url(crate_name: &str, version: &str) -> String {
+        format!("https://crates.io/api/v1/crates/{crate_name}/{version}/readme")
+    }
+
+    /// README page from the HTML crates.io rendered for it, unless it is empty
+    fn readme_page(crate_name: &str, url: String, html: &str) -> Option<DocPage> {
+        let content = plain_text(Html::parse_fragment(html).root_element());
+        if content.is_empty() {
+            return None;
+        }
+        Some(DocPage {
+            url,
+            content,
+            item_type: "readme".to_string(),
+            module_path: format!("{crate_name}::README"),
+            extracted_at: Utc::now(),
+            parent_url: None,
+        })
+    }
+
+    /// Crawl docs.rs for a crate, handing pages to `sink` every `checkpoint_every` pages
+    /// and a [`CrawlProgress`] to `progress` every [`PROGRESS_EVERY_PAGES`] pages.
+    /// Standard library crates are crawled on doc.rust-lang.org, with
+    /// `version` naming the release channel.
+    ///
+    /// Up to [`Self::crawl_workers`] pages are fetched at once while this task
+    /// owns the visited set and link discovery, so pages may arrive out of
+    /// order. Starts from `resume` when given instead of the crate root. URLs in
+    /// `skip_urls` (pages already stored) are marked visited without being
+    /// fetched. Fetched pages are stored in the raw archive, if one is set,
+    /// without ever failing the crawl. Returns the final crawl state.
+    ///
+    /// Links are followed at most [`Self::crawl_max_depth`] links away from
+    /// the crate root. When more than [`Self::crawl_max_frontier`] URLs are
+    /// queued, the deepest are dropped so a page with endless links cannot
+    /// blow up the crawl.
+    ///
+    /// A panicking `progress` callback is logged and ignored; it never fails
+    /// the crawl.
+    ///
+    /// # Errors
+    /// Returns an error if the sink fails to persist a checkpoint.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn crawl_docs_rs(
         &mut self,
         crate_name: &str,
         version: &str,
-        max_pages: Option<usize>,
-    ) -> Result<Vec<DocPage>> {
-        use std::collections::{HashSet, VecDeque};
+        max_pages: usize,
+        resume: Option<CrawlState>,
+        skip_urls: &HashSet<String>,
+        checkpoint_every: usize,
+        sink: &mut dyn CrawlSink,
+        progress: &mut (dyn FnMut(CrawlProgress) + Send),
+    ) -> Result<CrawlState> {
+        let started = Instant::now();
+        let base_url = Self::start_url(crate_name, version);
 
-        let base_url = format!("https://docs.rs/{crate_name}/{version}/{crate_name}");
-
-        let max_pages = max_pages.unwrap_or(10_000);
+        let checkpoint_every = checkpoint_every.max(1);
         let mut pages = Vec::new();
-        let mut visited: HashSet<String> = HashSet::new();
-        let mut queue: VecDeque<String> = VecDeque::new();
-        queue.push_back(base_url.clone());
-
-        let mut processed = 0usize;
-        let should_process_url = |url: &str| -> bool {
-            if url.contains("/src/") {
-                return false;
+        let mut resume = resume;
+        let resume_depths = resume
+            .as_mut()
+            .map(|state| std::mem::take(&mut state.depths))
+            .unwrap_or_default();
+        let (mut visited, mut queue, mut processed) = match resume {
+            Some(state) => {
+                info!(
+                    "Resuming crawl of {} at {} pages ({} queued)",
+                    crate_name,
+                    state.processed,
+                    state.queue.len()
+                );
+                (
+                    state.visited.into_iter().collect::<HashSet<String>>(),
+                    state.queue.into_iter().collect::<VecDeque<String>>(),
+                    state.processed,
+                )
             }
-            if url.contains("#method.")
-                || url.contains("#impl-")
-                || url.contains("#associatedtype.")
-                || url.contains("#associatedconstant.")
-            {
-                return false;
-            }
-            true
+            None => (HashSet::new(), VecDeque::from([base_url.clone()]), 0usize),
         };
+        // Depth of every queued or in-flight URL, which also keeps the queue free of duplicates
+        let mut depths: HashMap<String, usize> = HashMap::new();
+        for url in &queue {
+            let depth = resume_depths.get(url).copied().unwrap_or(0);
+            depths.entry(url.clone()).or_insert(depth);
+        }
+        let (mut too_deep, mut evicted) = (0usize, 0usize);
+        let mut since_checkpoint = 0usize;
+        // Pages handled when progress was last reported
+        let (mut skipped, mut unchanged_count, mut reported) = (0usize, 0usize, processed);
+        let mut in_flight: JoinSet<(String, Result<Fetched>)> = JoinSet::new();
+        let mut in_flight_urls: HashSet<String> = HashSet::new();
+        // Pages answered 304 and cache entries, both handed over at the next checkpoint
+        let mut unchanged: Vec<String> = Vec::new();
+        let mut cache_entries: Vec<(String, CachedPage)> = Vec::new();
 
-        while let Some(url) = queue.pop_front() {
-            if processed >= max_pages {
-                info!("Reached page limit ({}), stopping crawl", max_pages);
-                break;
-            }
-            if !visited.insert(url.clone()) {
-                continue;
+        loop {
+            // Hand frontier URLs to idle workers; pages in flight count
+            // towards the limit so it is never overshot
+            while in_flight.len() < self.workers && processed + in_flight.len() < max_pages {
+                let Some(url) = queue.pop_front() else {
+                    break;
+                };
+                if !visited.insert(url.clone()) {
+                    continue;
+                }
+                if !Self::should_process_url(&url) {
+                    depths.remove(&url);
+                    continue;
+                }
+                if skip_urls.contains(&url) {
+                    debug!("Skipping already stored page {}", url);
+                    depths.remove(&url);
+                    skipped += 1;
+                    continue;
+                }
+                in_flight_urls.insert(url.clone());
+                let fetcher = Arc::clone(&self.fetcher);
+                let cache = self.cache.clone().filter(|_| self.conditional);
+                let archive = self.archive.clone();
+                let (archived_crate, archived_version) =
+                    (crate_name.to_string(), version.to_string());
+                in_flight.spawn(async move {
+                    let result = Self::fetch_page(&*fetcher, cache.as_deref(), &url).await;
+                    if let (Some(archive), Ok(Fetched::Page { body, .. })) = (archive, &result) {
+                        if let Err(e) = archive
+                            .store_page(&archived_crate, &archived_version, &url, body)
+                            .await
+                        {
+                            warn!("{}", e);
+                        }
+                    }
+                    (url, result)
+                });
             }
-            if !should_process_url(&url) {
-                continue;
+
+            if processed + skipped >= reported + PROGRESS_EVERY_PAGES {
+                reported = processed + skipped;
+                Self::report_progress(
+                    progress,
+                    CrawlProgress {
+                        pages_fetched: processed - unchanged_count,
+                        pages_queued: queue.len() + in_flight.len(),
+                        pages_skipped: unchanged_count + skipped,
+                        elapsed: started.elapsed(),
+                    },
+                );
             }
 
-            let html = match self.get_text(&url).await {
-                Ok(t) => t,
+            let Some(joined) = in_flight.join_next().await else {
+                break;
+            };
+            let (url, result) = joined.map_err(|e| anyhow!("Crawl worker failed: {}", e))?;
+            in_flight_urls.remove(&url);
+            let depth = depths.remove(&url).unwrap_or(0);
+
+            let (html, validators) = match result {
+                Ok(Fetched::Page { body, validators }) => (body, validators),
+                Ok(Fetched::Unchanged { links }) => {
+                    debug!("Not modified: {}", url);
+                    if processed < (max_pages * 3 / 4) {
+                        too_deep += self.enqueue_links(
+                            &mut queue,
+                            &mut depths,
+                            &visited,
+                            &links,
+                            depth + 1,
+                        );
+                        evicted += self.trim_frontier(&mut queue, &mut depths, &mut visited);
+                    }
+                    unchanged.push(url);
+                    unchanged_count += 1;
+                    processed += 1;
+                    since_checkpoint += 1;
+                    if since_checkpoint >= checkpoint_every {
+                        self.checkpoint(
+                            sink,
+                            &mut pages,
+                            &mut unchanged,
+                            &mut cache_entries,
+                            Self::crawl_state(
+                                &visited,
+                                &queue,
+                                &in_flight_urls,
+                                &depths,
+                                processed,
+                            ),
+                        )
+                        .await?;
+                        since_checkpoint = 0;
+                    }
+                    continue;
+                }
+                Err(e) if e.to_string().starts_with(PAGE_TOO_LARGE) => {
+                    warn!("Skipping {}: {}", url, e);
+                    continue;
+                }
                 Err(e) => {
                     debug!("Failed to fetch {}: {}", url, e);
                     continue;
@@ -187,80 +876,354 @@ impl RustLoader {
             };
 
             // Limit non-Send scraper types to this inner scope so they are dropped before awaits
-            let mut discovered_links: Vec<String> = Vec::new();
+            let page_links: Vec<String>;
             {
                 let document = Html::parse_document(&html);
-                let content_selector =
-                    Selector::parse("div.docblock, section.docblock, .rustdoc .docblock")
-                        .unwrap_or_else(|_| Selector::parse("body").expect("body selector"));
-
-                // Extract content blocks
-                let mut blocks: Vec<String> = Vec::new();
-                for element in document.select(&content_selector) {
-                    let text_content: String = element
-                        .text()
-                        .map(str::trim)
-                        .filter(|s| !s.is_empty())
-                        .collect::<Vec<&str>>()
-                        .join("\n");
-                    if !text_content.is_empty() {
-                        blocks.push(text_content);
-                    }
-                }
 
-                if !blocks.is_empty() {
-                    let item_type = if url.contains("/struct.") {
-                        "struct"
-                    } else if url.contains("/fn.") {
-                        "function"
-                    } else if url.ends_with("/index.html") || url == base_url {
-                        "crate"
-                    } else {
-                        "module"
-                    };
-
-                    pages.push(DocPage {
-                        url: url.clone(),
-                        content: blocks.join("\n\n"),
-                        item_type: item_type.to_string(),
-                        module_path: Self::extract_module_path(&url, crate_name),
-                        extracted_at: Utc::now(),
-                    });
-                }
+                pages.extend(Self::parse_doc_page(
+                    &document,
+                    &url,
+                    &url,
+                    crate_name,
+                    url == base_url,
+                ));
 
-                // Link discovery for first ~75% of crawl
-                if processed < (max_pages * 3 / 4) {
-                    if let Ok(link_sel) = Selector::parse("a") {
-                        for link in document.select(&link_sel) {
-                            if let Some(href) = link.value().attr("href") {
-                                if let Ok(base) = Url::parse(&url) {
-                                    if let Ok(abs) = base.join(href) {
-                                        let link_url = abs.to_string();
-                                        if link_url.contains("docs.rs")
-                                            && link_url.contains(crate_name)
-                                            && should_process_url(&link_url)
-                                            && !visited.contains(&link_url)
-                                        {
-                                            discovered_links.push(link_url);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+                // Links are always collected so the cache can replay them for an unchanged page
+                page_links = Self::crate_links(&document, &url, crate_name, version);
             }
 
-            for link_url in discovered_links {
-                queue.push_back(link_url);
+            // Link discovery for the first ~75% of pages processed
+            if processed < (max_pages * 3 / 4) {
+                too_deep +=
+                    self.enqueue_links(&mut queue, &mut depths, &visited, &page_links, depth + 1);
+                evicted += self.trim_frontier(&mut queue, &mut depths, &mut visited);
+            }
+            if self.cache.is_some() && !validators.is_empty() {
+                cache_entries.push((
+                    url,
+                    CachedPage {
+                        validators,
+                        links: page_links,
+                    },
+                ));
             }
 
             processed += 1;
-            // Extra small delay to be respectful
-            time::sleep(Duration::from_millis(500)).await;
+            since_checkpoint += 1;
+
+            if since_checkpoint >= checkpoint_every {
+                self.checkpoint(
+                    sink,
+                    &mut pages,
+                    &mut unchanged,
+                    &mut cache_entries,
+                    Self::crawl_state(&visited, &queue, &in_flight_urls, &depths, processed),
+                )
+                .await?;
+                since_checkpoint = 0;
+            }
         }
 
-        Ok(pages)
+        if processed >= max_pages && !queue.is_empty() {
+            info!("Reached page limit ({}), stopping crawl", max_pages);
+        }
+        if too_deep + evicted > 0 {
+            info!(
+                "Crawl of {} left out {} links deeper than {} and dropped {} queued URLs",
+                crate_name, too_deep, self.max_depth, evicted
+            );
+        }
+        let state = Self::crawl_state(&visited, &queue, &in_flight_urls, &depths, processed);
+        self.checkpoint(
+            sink,
+            &mut pages,
+            &mut unchanged,
+            &mut cache_entries,
+            state.clone(),
+        )
+        .await?;
+        Self::report_progress(
+            progress,
+            CrawlProgress {
+                pages_fetched: processed - unchanged_count,
+                pages_queued: queue.len(),
+                pages_skipped: unchanged_count + skipped,
+                elapsed: started.elapsed(),
+            },
+        );
+        Ok(state)
+    }
+
+    /// Hand `snapshot` to `progress`, containing any panic so it cannot fail the crawl
+    pub(crate) fn report_progress(
+        progress: &mut (dyn FnMut(CrawlProgress) + Send),
+        snapshot: CrawlProgress,
+    ) {
+        if catch_unwind(AssertUnwindSafe(|| progress(snapshot))).is_err() {
+            warn!("Crawl progress callback panicked; continuing the crawl");
+        }
+    }
+
+    /// Documents of one rustdoc page: its doc blocks as Markdown, so headings,
+    /// lists and code survive, followed by its code examples. Pages without
+    /// a doc block (redirects, `all.html`) yield nothing.
+    ///
+    /// `location` places the page in rustdoc's layout and decides its item
+    /// type and module path; for docs.rs it is `url` itself, while a local
+    /// build passes a docs.rs-shaped location for its `file://` URL.
+    #[must_use]
+    pub fn parse_doc_page(
+        document: &Html,
+        url: &str,
+        location: &str,
+        crate_name: &str,
+        is_root: bool,
+    ) -> Vec<DocPage> {
+        let Some(content) = page_markdown(document, url) else {
+            return Vec::new();
+        };
+        let item_type = if is_root {
+            "crate"
+        } else {
+            Self::classify_item_type(location, crate_name)
+        };
+        let page = DocPage {
+            url: url.to_string(),
+            content,
+            item_type: item_type.to_string(),
+            module_path: Self::extract_module_path(location, crate_name),
+            extracted_at: Utc::now(),
+            parent_url: None,
+        };
+        let examples = extract_examples(document, &page, crate_name);
+        let mut pages = vec![page];
+        pages.extend(examples);
+        pages
+    }
+
+    /// First page of a crawl: the crate root, as an `index.html` on
+    /// doc.rust-lang.org, which serves the root without redirecting to it,
+    /// so relative links resolve under the crate
+    fn start_url(crate_name: &str, version: &str) -> String {
+        if is_std_crate(crate_name) {
+            format!("{}/index.html", Self::root_url(crate_name, version))
+        } else {
+            Self::root_url(crate_name, version)
+        }
+    }
+
+    /// docs.rs URL of a crate version's root module, or the doc.rust-lang.org
+    /// URL on channel `version` for standard library crates
+    fn root_url(crate_name: &str, version: &str) -> String {
+        if is_std_crate(crate_name) {
+            return Self::std_root_url(crate_name, version);
+        }
+        format!("https://docs.rs/{crate_name}/{version}/{crate_name}")
+    }
+
+    /// Whether a docs.rs URL is worth crawling (no source views or item anchors)
+    fn should_process_url(url: &str) -> bool {
+        if url.contains("/src/") {
+            return false;
+        }
+        if url.contains("#method.")
+            || url.contains("#impl-")
+            || url.contains("#associatedtype.")
+            || url.contains("#associatedconstant.")
+        {
+            return false;
+        }
+        true
+    }
+
+    /// Crawlable docs.rs links of `crate_name` on the page at `url`, limited
+    /// to the docs of `version`; for standard library crates, only links
+    /// under the crate root on the page's channel
+    fn crate_links(document: &Html, url: &str, crate_name: &str, version: &str) -> Vec<String> {
+        let (Ok(link_sel), Ok(base)) = (Selector::parse("a"), Url::parse(url)) else {
+            return Vec::new();
+        };
+        if is_std_crate(crate_name) {
+            let Some(scope) = Self::std_link_scope(url, crate_name) else {
+                return Vec::new();
+            };
+            return document
+                .select(&link_sel)
+                .filter_map(|link| link.value().attr("href"))
+                .filter_map(|href| base.join(href).ok())
+                .map(|abs| abs.to_string())
+                .filter(|link_url| {
+                    link_url.starts_with(&scope) && Self::should_process_url(link_url)
+                })
+                .collect();
+        }
+        document
+            .select(&link_sel)
+            .filter_map(|link| link.value().attr("href"))
+            .filter_map(|href| base.join(href).ok())
+            .map(|abs| abs.to_string())
+            .filter(|link_url| {
+                Self::is_crate_docs_url(link_url, crate_name, version)
+                    && Self::should_process_url(link_url)
+            })
+            .collect()
+    }
+
+    /// Whether `url` is a page of `crate_name` `version` on docs.rs, i.e. its
+    /// path starts with `/{crate_name}/{version}/`. Crate names match with
+    /// `-` and `_` interchangeable, as crates.io treats them.
+    fn is_crate_docs_url(url: &str, crate_name: &str, version: &str) -> bool {
+        let Ok(parsed) = Url::parse(url) else {
+            return false;
+        };
+        if parsed.scheme() != "https" || parsed.host_str() != Some("docs.rs") {
+            return false;
+        }
+        let mut segments = parsed.path().trim_start_matches('/').splitn(3, '/');
+        let (Some(name), Some(release), Some(_)) =
+            (segments.next(), segments.next(), segments.next())
+        else {
+            return false;
+        };
+        name.replace('-', "_") == crate_name.replace('-', "_") && release == version
+    }
+
+    /// Queue `links` not seen yet at `depth`, returning how many were left
+    /// out for being deeper than the depth limit
+    fn enqueue_links(
+        &self,
+        queue: &mut VecDeque<String>,
+        depths: &mut HashMap<String, usize>,
+        visited: &HashSet<String>,
+        links: &[String],
+        depth: usize,
+    ) -> usize {
+        let mut too_deep = 0;
+        for link in links {
+            if visited.contains(link) || depths.contains_key(link) {
+                continue;
+            }
+            if depth > self.max_depth {
+                too_deep += 1;
+                continue;
+            }
+            depths.insert(link.clone(), depth);
+            queue.push_back(link.clone());
+        }
+        too_deep
+    }
+
+    /// Drop the deepest queued URLs beyond the frontier limit, marking them
+    /// visited so they are not queued again; returns how many were dropped
+    fn trim_frontier(
+        &self,
+        queue: &mut VecDeque<String>,
+        depths: &mut HashMap<String, usize>,
+        visited: &mut HashSet<String>,
+    ) -> usize {
+        let dropped = evict_deepest(queue, depths, self.max_frontier);
+        if !dropped.is_empty() {
+            warn!(
+                "Crawl frontier over {} URLs, dropped {} deepest",
+                self.max_frontier,
+                dropped.len()
+            );
+        }
+        for url in &dropped {
+            depths.remove(url);
+        }
+        let count = dropped.len();
+        visited.extend(dropped);
+        count
+    }
+
+    /// Fetch a page, conditionally when `cache` knows its validators
+    async fn fetch_page(
+        fetcher: &dyn PageFetcher,
+        cache: Option<&dyn FetchCache>,
+        url: &str,
+    ) -> Result<Fetched> {
+        let cached = match cache {
+            Some(cache) => cache.lookup(url).await.unwrap_or_else(|e| {
+                debug!("Fetch cache lookup failed for {}: {}", url, e);
+                None
+            }),
+            None => None,
+        };
+        let Some(cached) = cached else {
+            return fetcher
+                .fetch_conditional(url, &CacheValidators::default())
+                .await
+                .map(Fetched::from_outcome);
+        };
+        match fetcher.fetch_conditional(url, &cached.validators).await? {
+            FetchOutcome::NotModified => Ok(Fetched::Unchanged {
+                links: cached.links,
+            }),
+            outcome => Ok(Fetched::from_outcome(outcome)),
+        }
+    }
+
+    /// Hand buffered pages and unchanged URLs to `sink`, then record cache entries
+    ///
+    /// Cache entries are written only once their pages are stored, so a crash
+    /// in between refetches the pages instead of trusting a 304 for a page
+    /// that was never stored.
+    async fn checkpoint(
+        &self,
+        sink: &mut dyn CrawlSink,
+        pages: &mut Vec<DocPage>,
+        unchanged: &mut Vec<String>,
+        cache_entries: &mut Vec<(String, CachedPage)>,
+        state: CrawlState,
+    ) -> Result<()> {
+        if !unchanged.is_empty() {
+            sink.keep_unchanged(std::mem::take(unchanged)).await?;
+        }
+        sink.checkpoint(std::mem::take(pages), &state).await?;
+        if let Some(cache) = &self.cache {
+            if !cache_entries.is_empty() {
+                if let Err(e) = cache.store(cache_entries).await {
+                    warn!("Failed to update fetch cache: {}", e);
+                }
+                cache_entries.clear();
+            }
+        }
+        Ok(())
+    }
+
+    /// Checkpoint of the crawl; pages still being fetched go back on the queue
+    fn crawl_state(
+        visited: &HashSet<String>,
+        queue: &VecDeque<String>,
+        in_flight: &HashSet<String>,
+        depths: &HashMap<String, usize>,
+        processed: usize,
+    ) -> CrawlState {
+        let mut visited: Vec<String> = visited
+            .iter()
+            .filter(|url| !in_flight.contains(*url))
+            .cloned()
+            .collect();
+        visited.sort();
+        let mut pending: Vec<String> = in_flight.iter().cloned().collect();
+        pending.sort();
+        pending.extend(queue.iter().cloned());
+        let depths = pending
+            .iter()
+            .filter_map(|url| {
+                depths
+                    .get(url)
+                    .filter(|depth| **depth > 0)
+                    .map(|depth| (url.clone(), *depth))
+            })
+            .collect();
+        CrawlState {
+            visited,
+            queue: pending,
+            processed,
+            depths,
+        }
     }
 
     async fn fetch_crate_metadata(&mut self, crate_name: &str) -> Result<CrateMetadata> {
@@ -290,12 +1253,47 @@ impl RustLoader {
                 .get("documentation")
                 .and_then(|v| v.as_str())
                 .map(ToString::to_string),
+            repository: c
+                .get("repository")
+                .and_then(|v| v.as_str())
+                .map(ToString::to_string),
+            keywords: Self::string_ids(c.get("keywords"), json.get("keywords")),
+            categories: Self::string_ids(c.get("categories"), json.get("categories")),
         })
     }
 
+    /// Read a list of ids from the crate object (`["serde"]`), falling back to
+    /// the top-level objects crates.io includes alongside it (`[{"id": "serde"}]`)
+    fn string_ids(
+        crate_field: Option<&serde_json::Value>,
+        included: Option<&serde_json::Value>,
+    ) -> Vec<String> {
+        let ids: Vec<String> = crate_field
+            .and_then(|v| v.as_array())
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|v| v.as_str().map(ToString::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        if !ids.is_empty() {
+            return ids;
+        }
+        included
+            .and_then(|v| v.as_array())
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|v| v.get("id").and_then(|id| id.as_str()))
+                    .map(ToString::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     async fn get_text(&mut self, url: &str) -> Result<String> {
-        let resp = self.rate_limiter.get(url).await?;
-        Ok(resp.text().await?)
+        self.fetcher.fetch_text(url).await
     }
 
     #[allow(dead_code)]
@@ -327,21 +1325,66 @@ impl RustLoader {
             item_type: item_type.into(),
             module_path,
             extracted_at: Utc::now(),
+            parent_url: None,
         })
     }
 
+    /// Classify a docs.rs page by the item prefix of its file name
+    /// (e.g. `trait.Serialize.html` is a trait). Index pages are the crate
+    /// root when they sit directly under the crate, otherwise a module.
+    fn classify_item_type(url: &str, crate_name: &str) -> &'static str {
+        let file_name = Url::parse(url)
+            .ok()
+            .and_then(|parsed| {
+                parsed
+                    .path_segments()
+                    .and_then(|mut segments| segments.next_back().map(ToString::to_string))
+            })
+            .unwrap_or_default();
+
+        if let Some((prefix, _)) = file_name.split_once('.') {
+            if let Some((_, item_type)) = ITEM_PREFIXES.iter().find(|(p, _)| *p == prefix) {
+                return item_type;
+            }
+        }
+
+        let crate_ident = crate_name.replace('-', "_");
+        if Self::extract_module_path(url, crate_name) == crate_ident {
+            "crate"
+        } else {
+            "module"
+        }
+    }
+
     fn extract_module_path(url: &str, crate_name: &str) -> String {
+        let crate_ident = crate_name.replace('-', "_");
         if let Ok(parsed) = Url::parse(url) {
             let parts: Vec<&str> = parsed
                 .path_segments()
                 .map(std::iter::Iterator::collect)
                 .unwrap_or_default();
-            if let Some(idx) = parts.iter().position(|&s| s == crate_name) {
+            // docs.rs paths look like /{crate}/{version}/{crate_ident}/..., so
+            // skip the crate/version prefix before locating the crate ident
+            let start = if parts.first() == Some(&crate_name) {
+                2
+            } else {
+                0
+            };
+            if let Some(idx) = parts
+                .iter()
+                .skip(start)
+                .position(|&s| s == crate_ident)
+                .map(|i| i + start)
+            {
                 let module: Vec<String> = parts
                     .iter()
                     .skip(idx)
                     .filter(|&&s| !s.is_empty() && s != "index.html")
-                    .filter(|&s| !s.starts_with("struct.") && !s.starts_with("fn."))
+                    .filter(|&s| {
+                        !ITEM_PREFIXES
+                            .iter()
+                            .any(|(prefix, _)| s.starts_with(&format!("{prefix}.")))
+                    })
                     .map(|s| s.replace(".html", ""))
                     .collect();
                 if !module.is_empty() {
@@ -349,6 +1392,1023 @@ impl RustLoader {
                 }
             }
         }
-        crate_name.to_string()
+        crate_ident
+    }
+}
+
+/// Remove queued URLs beyond `max_frontier`, deepest first and, among equally
+/// deep ones, the most recently discovered first; returns the removed URLs
+/// in queue order
+fn evict_deepest(
+    queue: &mut VecDeque<String>,
+    depths: &HashMap<String, usize>,
+    max_frontier: usize,
+) -> Vec<String> {
+    let excess = queue.len().saturating_sub(max_frontier);
+    if excess == 0 {
+        return Vec::new();
+    }
+    let mut order: Vec<(usize, usize)> = queue
+        .iter()
+        .enumerate()
+        .map(|(idx, url)| (depths.get(url).copied().unwrap_or(0), idx))
+        .collect();
+    order.sort_unstable_by(|a, b| b.cmp(a));
+    let doomed: HashSet<usize> = order.into_iter().take(excess).map(|(_, idx)| idx).collect();
+    let mut dropped = Vec::with_capacity(excess);
+    let mut kept = VecDeque::with_capacity(max_frontier);
+    for (idx, url) in queue.drain(..).enumerate() {
+        if doomed.contains(&idx) {
+            dropped.push(url);
+        } else {
+            kept.push_back(url);
+        }
+    }
+    *queue = kept;
+    dropped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        evict_deepest, CacheValidators, CachedPage, CrawlProgress, CrawlSink, CrawlState, DocPage,
+        FetchCache, FetchOutcome, PageFetcher, RateLimiter, RawArchive, RustLoader, PAGE_TOO_LARGE,
+    };
+    use anyhow::{anyhow, Result};
+    use async_trait::async_trait;
+    use std::collections::{HashMap, HashSet, VecDeque};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::time::Instant;
+
+    const ROOT: &str = "https://docs.rs/demo/1.0.0/demo";
+
+    /// Serves canned docs.rs pages and records every URL requested
+    struct MockFetcher {
+        pages: HashMap<String, String>,
+        requested: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl PageFetcher for MockFetcher {
+        async fn fetch_text(&self, url: &str) -> Result<String> {
+            self.requested.lock().unwrap().push(url.to_string());
+            self.pages
+                .get(url)
+                .cloned()
+                .ok_or_else(|| anyhow!("HTTP status: 404 Not Found"))
+        }
+    }
+
+    /// Crate root linking to six item pages
+    fn mock_site() -> HashMap<String, String> {
+        let items: Vec<String> = (0..6).map(|i| format!("{ROOT}/fn.item{i}.html")).collect();
+        let links: String = items
+            .iter()
+            .map(|url| format!("<a href=\"{url}\">item</a>"))
+            .collect();
+
+        let mut site = HashMap::new();
+        site.insert(
+            ROOT.to_string(),
+            format!("<div class=\"docblock\">Demo crate</div>{links}"),
+        );
+        for (i, url) in items.into_iter().enumerate() {
+            site.insert(url, format!("<div class=\"docblock\">Item {i}</div>"));
+        }
+        site
+    }
+
+    /// Stores pages and checkpoints like the ingestion job, optionally dying on a checkpoint
+    #[derive(Default)]
+    struct RecordingSink {
+        stored: Vec<DocPage>,
+        kept: Vec<String>,
+        saved: Option<CrawlState>,
+        fail_on_checkpoint: Option<usize>,
+        checkpoints: usize,
+    }
+
+    #[async_trait]
+    impl CrawlSink for RecordingSink {
+        async fn checkpoint(&mut self, pages: Vec<DocPage>, state: &CrawlState) -> Result<()> {
+            self.checkpoints += 1;
+            if self.fail_on_checkpoint == Some(self.checkpoints) {
+                return Err(anyhow!("server died"));
+            }
+            self.stored.extend(pages);
+            self.saved = Some(state.clone());
+            Ok(())
+        }
+
+        async fn keep_unchanged(&mut self, urls: Vec<String>) -> Result<()> {
+            self.kept.extend(urls);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resumed_crawl_fetches_only_remaining_pages() {
+        let first_requests = Arc::new(Mutex::new(Vec::new()));
+        let mut loader = RustLoader::with_fetcher(Box::new(MockFetcher {
+            pages: mock_site(),
+            requested: first_requests.clone(),
+        }));
+
+        // Abort on the second checkpoint, after two pages were stored
+        let mut sink = RecordingSink {
+            fail_on_checkpoint: Some(2),
+            ..RecordingSink::default()
+        };
+        let aborted = loader
+            .crawl_docs_rs(
+                "demo",
+                "1.0.0",
+                100,
+                None,
+                &HashSet::new(),
+                2,
+                &mut sink,
+                &mut |_| {},
+            )
+            .await;
+        assert!(aborted.is_err());
+        assert_eq!(sink.stored.len(), 2);
+        let checkpoint = sink.saved.clone().expect("first checkpoint saved");
+        assert_eq!(checkpoint.processed, 2);
+
+        let stored_urls: HashSet<String> = sink.stored.iter().map(|p| p.url.clone()).collect();
+        let second_requests = Arc::new(Mutex::new(Vec::new()));
+        let mut loader = RustLoader::with_fetcher(Box::new(MockFetcher {
+            pages: mock_site(),
+            requested: second_requests.clone(),
+        }));
+        let mut resumed = RecordingSink::default();
+        let state = loader
+            .crawl_docs_rs(
+                "demo",
+                "1.0.0",
+                100,
+                Some(checkpoint),
+                &stored_urls,
+                2,
+                &mut resumed,
+                &mut |_| {},
+            )
+            .await
+            .expect("resumed crawl succeeds");
+
+        // Only pages that were not checkpointed are fetched again
+        let second: Vec<String> = second_requests.lock().unwrap().clone();
+        assert_eq!(second.len(), 5);
+        assert!(second.iter().all(|url| !stored_urls.contains(url)));
+
+        // Together the two runs cover the whole site exactly once
+        let mut all_urls: Vec<String> = sink
+            .stored
+            .iter()
+            .chain(resumed.stored.iter())
+            .map(|p| p.url.clone())
+            .collect();
+        all_urls.sort();
+        let mut expected: Vec<String> = mock_site().into_keys().collect();
+        expected.sort();
+        assert_eq!(all_urls, expected);
+        assert_eq!(state.processed, 7);
+        assert!(state.queue.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_crawl_stores_examples_after_their_page() {
+        let mut site = HashMap::new();
+        site.insert(
+            ROOT.to_string(),
+            "<div class=\"docblock\">Demo crate\
+             <pre class=\"rust rust-example-rendered\"><code>use demo::run;\n\n\
+             let answer = run();\nassert_eq!(answer, 42);</code></pre></div>"
+                .to_string(),
+        );
+        let mut loader = RustLoader::with_fetcher(Box::new(MockFetcher {
+            pages: site,
+            requested: Arc::new(Mutex::new(Vec::new())),
+        }));
+        let mut sink = RecordingSink::default();
+        loader
+            .crawl_docs_rs(
+                "demo",
+                "1.0.0",
+                10,
+                None,
+                &HashSet::new(),
+                5,
+                &mut sink,
+                &mut |_| {},
+            )
+            .await
+            .expect("crawl succeeds");
+
+        assert_eq!(sink.stored.len(), 2);
+        assert_eq!(sink.stored[0].url, ROOT);
+        assert_eq!(sink.stored[1].url, format!("{ROOT}#example-1"));
+        assert_eq!(sink.stored[1].item_type, "example");
+        assert_eq!(sink.stored[1].parent_url.as_deref(), Some(ROOT));
+    }
+
+    #[tokio::test]
+    async fn test_crawl_reports_monotonic_progress() {
+        // Root linking to 24 items: enough pages for a couple of reports
+        let items: Vec<String> = (0..24).map(|i| format!("{ROOT}/fn.item{i}.html")).collect();
+        let mut site: HashMap<String, String> = items
+            .iter()
+            .map(|url| {
+                (
+                    url.clone(),
+                    "<div class=\"docblock\">Item</div>".to_string(),
+                )
+            })
+            .collect();
+        site.insert(
+            ROOT.to_string(),
+            items
+                .iter()
+                .map(|url| format!("<a href=\"{url}\">item</a>"))
+                .collect(),
+        );
+        let mut loader = RustLoader::with_fetcher(Box::new(MockFetcher {
+            pages: site,
+            requested: Arc::new(Mutex::new(Vec::new())),
+        }));
+        let mut sink = RecordingSink::default();
+        let mut reports: Vec<CrawlProgress> = Vec::new();
+        loader
+            .crawl_docs_rs(
+                "demo",
+                "1.0.0",
+                100,
+                None,
+                &HashSet::new(),
+                5,
+                &mut sink,
+                &mut |p| reports.push(p),
+            )
+            .await
+            .expect("crawl succeeds");
+
+        assert!(reports.len() >= 3, "{reports:?}");
+        for pair in reports.windows(2) {
+            assert!(pair[1].pages_fetched > pair[0].pages_fetched, "{reports:?}");
+            assert!(
+                pair[1].fraction(100) >= pair[0].fraction(100),
+                "{reports:?}"
+            );
+            assert!(pair[1].elapsed >= pair[0].elapsed);
+        }
+        let last = reports.last().unwrap();
+        assert_eq!(last.pages_fetched, 25);
+        assert_eq!(last.pages_queued, 0);
+        assert_eq!(last.pages_skipped, 0);
+        assert!((last.fraction(100) - 1.0).abs() < f64::EPSILON);
+
+        // A panicking callback is contained and the crawl still completes
+        let mut loader = RustLoader::with_fetcher(Box::new(MockFetcher {
+            pages: mock_site(),
+            requested: Arc::new(Mutex::new(Vec::new())),
+        }));
+        let mut sink = RecordingSink::default();
+        loader
+            .crawl_docs_rs(
+                "demo",
+                "1.0.0",
+                100,
+                None,
+                &HashSet::new(),
+                5,
+                &mut sink,
+                &mut |_| panic!("progress sink broke"),
+            )
+            .await
+            .expect("crawl survives a panicking callback");
+        assert_eq!(sink.stored.len(), 7);
+    }
+
+    /// Serves `mock_site` slowly, tracking how many fetches overlap
+    #[derive(Default)]
+    struct SlowFetcher {
+        active: AtomicUsize,
+        peak: AtomicUsize,
+        fetched: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl PageFetcher for SlowFetcher {
+        async fn fetch_text(&self, url: &str) -> Result<String> {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(active, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            self.fetched.fetch_add(1, Ordering::SeqCst);
+            mock_site()
+                .remove(url)
+                .ok_or_else(|| anyhow!("HTTP status: 404 Not Found"))
+        }
+    }
+
+    /// Crawl `mock_site` through a shared `SlowFetcher`
+    async fn slow_crawl(workers: usize, max_pages: usize) -> (Arc<SlowFetcher>, Vec<DocPage>) {
+        struct Shared(Arc<SlowFetcher>);
+
+        #[async_trait]
+        impl PageFetcher for Shared {
+            async fn fetch_text(&self, url: &str) -> Result<String> {
+                self.0.fetch_text(url).await
+            }
+        }
+
+        let fetcher = Arc::new(SlowFetcher::default());
+        let mut loader =
+            RustLoader::with_fetcher(Box::new(Shared(fetcher.clone()))).with_workers(workers);
+        let mut sink = RecordingSink::default();
+        loader
+            .crawl_docs_rs(
+                "demo",
+                "1.0.0",
+                max_pages,
+                None,
+                &HashSet::new(),
+                2,
+                &mut sink,
+                &mut |_| {},
+            )
+            .await
+            .expect("crawl succeeds");
+        (fetcher, sink.stored)
+    }
+
+    #[tokio::test]
+    async fn test_workers_fetch_in_parallel() {
+        let started = Instant::now();
+        let (sequential, pages) = slow_crawl(1, 100).await;
+        let sequential_time = started.elapsed();
+        assert_eq!(sequential.peak.load(Ordering::SeqCst), 1);
+        assert_eq!(pages.len(), 7);
+
+        let started = Instant::now();
+        let (parallel, pages) = slow_crawl(3, 100).await;
+        let parallel_time = started.elapsed();
+        assert_eq!(parallel.peak.load(Ordering::SeqCst), 3);
+        assert_eq!(pages.len(), 7);
+        // Root, then the six items three at a time: 3 rounds instead of 7
+        assert!(
+            parallel_time * 2 < sequential_time,
+            "{parallel_time:?} vs {sequential_time:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_page_limit_holds_with_parallel_workers() {
+        let (fetcher, pages) = slow_crawl(4, 3).await;
+        assert_eq!(fetcher.fetched.load(Ordering::SeqCst), 3);
+        assert_eq!(pages.len(), 3);
+    }
+
+    #[test]
+    fn test_rate_limiter_enforces_shared_budget() {
+        let limiter = RateLimiter::with_limits(Duration::from_secs(6), 2);
+        let start = Instant::now();
+        assert!(limiter.try_acquire_at(start).is_ok());
+        assert!(limiter.try_acquire_at(start).is_ok());
+        let wait = limiter.try_acquire_at(start).unwrap_err();
+        assert_eq!(wait, Duration::from_secs(6));
+
+        // One token is earned per interval, whichever worker asks
+        let later = start + Duration::from_secs(3);
+        assert!(limiter.try_acquire_at(later).is_err());
+        assert!(limiter
+            .try_acquire_at(start + Duration::from_secs(6))
+            .is_ok());
+        assert!(limiter
+            .try_acquire_at(start + Duration::from_secs(6))
+            .is_err());
+
+        let unlimited = RateLimiter::with_limits(Duration::ZERO, 1);
+        assert!((0..10).all(|_| unlimited.try_acquire_at(start).is_ok()));
+    }
+
+    /// Serves `mock_site` with an `ETag` per page and honours `If-None-Match`
+    #[derive(Default)]
+    struct ConditionalFetcher {
+        full_fetches: AtomicUsize,
+        not_modified: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl PageFetcher for ConditionalFetcher {
+        async fn fetch_text(&self, url: &str) -> Result<String> {
+            mock_site()
+                .remove(url)
+                .ok_or_else(|| anyhow!("HTTP status: 404 Not Found"))
+        }
+
+        async fn fetch_conditional(
+            &self,
+            url: &str,
+            validators: &CacheValidators,
+        ) -> Result<FetchOutcome> {
+            let etag = format!("\"{}\"", url.len());
+            if validators.etag.as_deref() == Some(etag.as_str()) {
+                self.not_modified.fetch_add(1, Ordering::SeqCst);
+                return Ok(FetchOutcome::NotModified);
+            }
+            self.full_fetches.fetch_add(1, Ordering::SeqCst);
+            Ok(FetchOutcome::Modified {
+                body: self.fetch_text(url).await?,
+                validators: CacheValidators {
+                    etag: Some(etag),
+                    last_modified: None,
+                },
+            })
+        }
+    }
+
+    #[derive(Default)]
+    struct MemoryCache(Mutex<HashMap<String, CachedPage>>);
+
+    #[async_trait]
+    impl FetchCache for MemoryCache {
+        async fn lookup(&self, url: &str) -> Result<Option<CachedPage>> {
+            Ok(self.0.lock().unwrap().get(url).cloned())
+        }
+
+        async fn store(&self, entries: &[(String, CachedPage)]) -> Result<()> {
+            self.0.lock().unwrap().extend(entries.iter().cloned());
+            Ok(())
+        }
+    }
+
+    /// Crawl `mock_site` through a shared fetcher and cache
+    async fn cached_crawl(
+        fetcher: &Arc<ConditionalFetcher>,
+        cache: &Arc<MemoryCache>,
+        conditional: bool,
+    ) -> RecordingSink {
+        struct Shared(Arc<ConditionalFetcher>);
+
+        #[async_trait]
+        impl PageFetcher for Shared {
+            async fn fetch_text(&self, url: &str) -> Result<String> {
+                self.0.fetch_text(url).await
+            }
+
+            async fn fetch_conditional(
+                &self,
+                url: &str,
+                validators: &CacheValidators,
+            ) -> Result<FetchOutcome> {
+                self.0.fetch_conditional(url, validators).await
+            }
+        }
+
+        let mut loader = RustLoader::with_fetcher(Box::new(Shared(fetcher.clone())));
+        loader.set_fetch_cache(cache.clone(), conditional);
+        let mut sink = RecordingSink::default();
+        loader
+            .crawl_docs_rs(
+                "demo",
+                "1.0.0",
+                100,
+                None,
+                &HashSet::new(),
+                3,
+                &mut sink,
+                &mut |_| {},
+            )
+            .await
+            .expect("crawl succeeds");
+        sink
+    }
+
+    #[tokio::test]
+    async fn test_unchanged_pages_are_kept_without_refetching() {
+        let fetcher = Arc::new(ConditionalFetcher::default());
+        let cache = Arc::new(MemoryCache::default());
+
+        let first = cached_crawl(&fetcher, &cache, true).await;
+        assert_eq!(first.stored.len(), 7);
+        assert_eq!(cache.0.lock().unwrap().len(), 7);
+
+        // Every page answers 304; cached links still reach the whole site
+        let second = cached_crawl(&fetcher, &cache, true).await;
+        assert!(second.stored.is_empty());
+        assert_eq!(second.kept.len(), 7);
+        assert_eq!(second.saved.unwrap().processed, 7);
+        assert_eq!(fetcher.not_modified.load(Ordering::SeqCst), 7);
+        assert_eq!(fetcher.full_fetches.load(Ordering::SeqCst), 7);
+
+        // Bypassing conditional requests fetches everything again
+        let forced = cached_crawl(&fetcher, &cache, false).await;
+        assert_eq!(forced.stored.len(), 7);
+        assert!(forced.kept.is_empty());
+        assert_eq!(fetcher.full_fetches.load(Ordering::SeqCst), 14);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_sends_conditional_headers() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        // Answers 304 once the client presents the ETag it was given
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let request = String::from_utf8_lossy(&request).to_lowercase();
+                let response = if request.contains("if-none-match: \"v1\"") {
+                    "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nContent-Length: 0\r\n\r\n"
+                        .to_string()
+                } else {
+                    let body = "<div class=\"docblock\">Demo</div>";
+                    format!(
+                        "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nLast-Modified: Wed, 01 Jan 2025 00:00:00 GMT\r\nContent-Length: {}\r\n\r\n{body}",
+                        body.len()
+                    )
+                };
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let limiter = RateLimiter::with_limits(Duration::ZERO, 1);
+        let url = format!("http://{addr}/demo/1.0.0/demo/index.html");
+        let FetchOutcome::Modified { body, validators } = limiter
+            .fetch_conditional(&url, &CacheValidators::default())
+            .await
+            .unwrap()
+        else {
+            panic!("first fetch should return the page");
+        };
+        assert!(body.contains("Demo"));
+        assert_eq!(validators.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(
+            validators.last_modified.as_deref(),
+            Some("Wed, 01 Jan 2025 00:00:00 GMT")
+        );
+
+        let outcome = limiter.fetch_conditional(&url, &validators).await.unwrap();
+        assert_eq!(outcome, FetchOutcome::NotModified);
+    }
+
+    /// `mock_site` plus the crates.io metadata for `demo` 1.0.0
+    fn mock_registry(readme: Option<&str>) -> HashMap<String, String> {
+        let mut site = mock_site();
+        site.insert(
+            "https://crates.io/api/v1/crates/demo".to_string(),
+            r#"{
+                "crate": {
+                    "id": "demo",
+                    "newest_version": "1.0.0",
+                    "repository": "https://github.com/example/demo",
+                    "keywords": ["demo", "example"],
+                    "categories": ["development-tools"]
+                }
+            }"#
+            .to_string(),
+        );
+        if let Some(readme) = readme {
+            site.insert(
+                "https://crates.io/api/v1/crates/demo/1.0.0/readme".to_string(),
+                readme.to_string(),
+            );
+        }
+        site
+    }
+
+    #[tokio::test]
+    async fn test_loads_readme_and_registry_metadata() {
+        let mut loader = RustLoader::with_fetcher(Box::new(MockFetcher {
+            pages: mock_registry(Some("<h1>Demo</h1><p>Getting started</p>")),
+            requested: Arc::new(Mutex::new(Vec::new())),
+        }));
+        let (meta, pages) = loader.load_crate_docs("demo", None).await.unwrap();
+
+        assert_eq!(
+            meta.repository.as_deref(),
+            Some("https://github.com/example/demo")
+        );
+        assert_eq!(meta.keywords, ["demo", "example"]);
+        assert_eq!(meta.categories, ["development-tools"]);
+
+        let readmes: Vec<&DocPage> = pages.iter().filter(|p| p.item_type == "readme").collect();
+        assert_eq!(readmes.len(), 1);
+        assert_eq!(readmes[0].module_path, "demo::README");
+        assert_eq!(readmes[0].content, "Demo\nGetting started");
+        assert_eq!(pages.len(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_missing_readme_is_skipped() {
+        let mut loader = RustLoader::with_fetcher(Box::new(MockFetcher {
+            pages: mock_registry(None),
+            requested: Arc::new(Mutex::new(Vec::new())),
+        }));
+        let (_, pages) = loader.load_crate_docs("demo", Some("1.0.0")).await.unwrap();
+
+        assert!(pages.iter().all(|p| p.item_type != "readme"));
+        assert_eq!(pages.len(), 7);
+    }
+
+    #[test]
+    fn test_classifies_docs_rs_urls() {
+        let cases = [
+            (
+                "https://docs.rs/serde/1.0.0/serde/index.html",
+                "crate",
+                "serde",
+            ),
+            (
+                "https://docs.rs/serde/1.0.0/serde/de/index.html",
+                "module",
+                "serde::de",
+            ),
+            (
+                "https://docs.rs/serde/1.0.0/serde/trait.Serialize.html",
+                "trait",
+                "serde",
+            ),
+            (
+                "https://docs.rs/serde/1.0.0/serde/de/enum.Unexpected.html",
+                "enum",
+                "serde::de",
+            ),
+            (
+                "https://docs.rs/serde/1.0.0/serde/macro.forward_to_deserialize_any.html",
+                "macro",
+                "serde",
+            ),
+            (
+                "https://docs.rs/serde/1.0.0/serde/de/value/struct.Error.html",
+                "struct",
+                "serde::de::value",
+            ),
+            (
+                "https://docs.rs/tokio/1.0.0/tokio/fn.spawn.html",
+                "function",
+                "tokio",
+            ),
+            (
+                "https://docs.rs/tokio/1.0.0/tokio/io/type.Result.html",
+                "type",
+                "tokio::io",
+            ),
+            (
+                "https://docs.rs/libc/0.2.0/libc/constant.EINTR.html",
+                "constant",
+                "libc",
+            ),
+            (
+                "https://docs.rs/libc/0.2.0/libc/union.sigval.html",
+                "union",
+                "libc",
+            ),
+            (
+                "https://docs.rs/tokio/1.0.0/tokio/attr.main.html",
+                "attribute",
+                "tokio",
+            ),
+            (
+                "https://docs.rs/serde/1.0.0/serde/derive.Serialize.html",
+                "derive",
+                "serde",
+            ),
+            (
+                "https://docs.rs/tokio-util/0.7.0/tokio_util/codec/index.html",
+                "module",
+                "tokio_util::codec",
+            ),
+            (
+                "https://docs.rs/tokio-util/0.7.0/tokio_util/codec/trait.Decoder.html",
+                "trait",
+                "tokio_util::codec",
+            ),
+        ];
+
+        for (url, item_type, module_path) in cases {
+            let crate_name = url.split('/').nth(3).unwrap();
+            assert_eq!(
+                RustLoader::classify_item_type(url, crate_name),
+                item_type,
+                "item_type for {url}"
+            );
+            assert_eq!(
+                RustLoader::extract_module_path(url, crate_name),
+                module_path,
+                "module_path for {url}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_crate_docs_urls_stay_within_crate_version() {
+        let cases = [
+            ("https://docs.rs/log/0.4.0/log/macro.info.html", true),
+            ("https://docs.rs/log/0.4.0/log/index.html", true),
+            ("https://docs.rs/log/0.4.0/", true),
+            // Other crates whose names contain the crate name
+            ("https://docs.rs/env_logger/0.11.0/env_logger/", false),
+            ("https://docs.rs/log4rs/1.0.0/log/index.html", false),
+            // Other versions of the crate
+            ("https://docs.rs/log/0.3.0/log/index.html", false),
+            ("https://docs.rs/log/latest/log/index.html", false),
+            // External crates re-exported by the docs
+            ("https://docs.rs/serde/1.0.0/serde/log/index.html", false),
+            // Hosts that merely mention docs.rs
+            ("https://docs.rs.evil.com/log/0.4.0/log/", false),
+            ("https://notdocs.rs/log/0.4.0/log/", false),
+            ("https://evil.com/docs.rs/log/0.4.0/log/", false),
+            ("http://docs.rs/log/0.4.0/log/", false),
+            // Crate pages outside the versioned docs
+            ("https://docs.rs/log/0.4.0", false),
+            ("https://docs.rs/crate/log/0.4.0", false),
+            ("https://docs.rs/releases/search?query=log", false),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(
+                RustLoader::is_crate_docs_url(url, "log", "0.4.0"),
+                expected,
+                "{url}"
+            );
+        }
+        // Hyphens and underscores name the same crate
+        assert!(RustLoader::is_crate_docs_url(
+            "https://docs.rs/tokio_util/0.7.0/tokio_util/",
+            "tokio-util",
+            "0.7.0"
+        ));
+        assert!(RustLoader::is_crate_docs_url(
+            "https://docs.rs/tokio-util/0.7.0/tokio_util/",
+            "tokio_util",
+            "0.7.0"
+        ));
+    }
+
+    #[test]
+    fn test_crate_links_skip_other_crates_and_source_views() {
+        let html = format!(
+            "<a href=\"fn.run.html\">run</a>\
+             <a href=\"../src/demo/lib.rs.html\">source</a>\
+             <a href=\"https://docs.rs/demo/0.9.0/demo/fn.run.html\">old</a>\
+             <a href=\"https://docs.rs/demo-extra/1.0.0/demo_extra/\">extra</a>\
+             <a href=\"https://docs.rs.example.com/demo/1.0.0/demo/\">fake</a>\
+             <a href=\"{ROOT}/struct.Config.html\">config</a>"
+        );
+        let document = scraper::Html::parse_document(&html);
+        let links =
+            RustLoader::crate_links(&document, &format!("{ROOT}/index.html"), "demo", "1.0.0");
+        assert_eq!(
+            links,
+            vec![
+                format!("{ROOT}/fn.run.html"),
+                format!("{ROOT}/struct.Config.html"),
+            ]
+        );
+    }
+
+    /// Crate root at the top of a chain of modules, each linking to the next
+    fn nested_site(levels: usize) -> HashMap<String, String> {
+        let mut site = HashMap::new();
+        let mut url = ROOT.to_string();
+        for level in 0..levels {
+            let next = format!("{url}/m{level}/index.html");
+            let next = next.replace("/index.html/", "/");
+            site.insert(
+                url.clone(),
+                format!("<div class=\"docblock\">Level {level}</div><a href=\"{next}\">next</a>"),
+            );
+            url = next;
+        }
+        site.insert(url, "<div class=\"docblock\">Bottom</div>".to_string());
+        site
+    }
+
+    #[tokio::test]
+    async fn test_crawl_stops_at_max_depth() {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let mut loader = RustLoader::with_fetcher(Box::new(MockFetcher {
+            pages: nested_site(6),
+            requested: requested.clone(),
+        }))
+        .with_max_depth(2);
+        let mut sink = RecordingSink::default();
+        let state = loader
+            .crawl_docs_rs(
+                "demo",
+                "1.0.0",
+                100,
+                None,
+                &HashSet::new(),
+                10,
+                &mut sink,
+                &mut |_| {},
+            )
+            .await
+            .expect("crawl succeeds");
+
+        // The root plus two levels below it
+        assert_eq!(requested.lock().unwrap().len(), 3);
+        assert_eq!(sink.stored.len(), 3);
+        assert!(state.queue.is_empty());
+        assert!(state.depths.is_empty());
+    }
+
+    #[test]
+    fn test_evicts_deepest_and_latest_urls_first() {
+        let mut queue: VecDeque<String> = ["a", "b", "c", "d", "e"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let depths: HashMap<String, usize> = [("a", 1), ("b", 3), ("c", 2), ("d", 3), ("e", 1)]
+            .into_iter()
+            .map(|(url, depth)| (url.to_string(), depth))
+            .collect();
+
+        assert!(evict_deepest(&mut queue, &depths, 5).is_empty());
+        let dropped = evict_deepest(&mut queue, &depths, 2);
+        assert_eq!(dropped, vec!["b", "c", "d"]);
+        assert_eq!(queue, VecDeque::from(["a".to_string(), "e".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn test_crawl_caps_frontier() {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let mut loader = RustLoader::with_fetcher(Box::new(MockFetcher {
+            pages: mock_site(),
+            requested: requested.clone(),
+        }))
+        .with_max_frontier(2);
+        let mut sink = RecordingSink::default();
+        loader
+            .crawl_docs_rs(
+                "demo",
+                "1.0.0",
+                100,
+                None,
+                &HashSet::new(),
+                10,
+                &mut sink,
+                &mut |_| {},
+            )
+            .await
+            .expect("crawl succeeds");
+
+        // Of the six items found on the root, the first two discovered are kept
+        let mut requested = requested.lock().unwrap().clone();
+        requested.sort();
+        assert_eq!(
+            requested,
+            vec![
+                ROOT.to_string(),
+                format!("{ROOT}/fn.item0.html"),
+                format!("{ROOT}/fn.item1.html"),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_rejects_oversized_pages() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        // `/declared` announces its size, `/streamed` only ends when the connection closes
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let request = String::from_utf8_lossy(&request).to_string();
+                let body = if request.starts_with("GET /small") {
+                    "x".repeat(50)
+                } else {
+                    "x".repeat(4096)
+                };
+                let head = if request.starts_with("GET /streamed") {
+                    "HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n".to_string()
+                } else {
+                    format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len())
+                };
+                let _ = socket.write_all(head.as_bytes()).await;
+                let _ = socket.write_all(body.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+
+        let limiter = RateLimiter::with_limits(Duration::ZERO, 1).with_max_page_bytes(1024);
+        let small = limiter
+            .fetch_text(&format!("http://{addr}/small"))
+            .await
+            .unwrap();
+        assert_eq!(small.len(), 50);
+        for path in ["declared", "streamed"] {
+            let err = limiter
+                .fetch_text(&format!("http://{addr}/{path}"))
+                .await
+                .expect_err("oversized page is rejected");
+            assert!(err.to_string().starts_with(PAGE_TOO_LARGE), "{path}: {err}");
+        }
+
+        let unlimited = RateLimiter::with_limits(Duration::ZERO, 1);
+        let page = unlimited
+            .fetch_text(&format!("http://{addr}/streamed"))
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 4096);
+    }
+
+    #[tokio::test]
+    async fn test_archived_pages_are_reprocessed_without_fetching() {
+        let dir = std::env::temp_dir().join(format!("raw-archive-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let archive = Arc::new(RawArchive::from_url(&format!("file://{}", dir.display())).unwrap());
+
+        let mut loader = RustLoader::with_fetcher(Box::new(MockFetcher {
+            pages: mock_site(),
+            requested: Arc::new(Mutex::new(Vec::new())),
+        }));
+        loader.set_raw_archive(archive.clone());
+        let mut crawled = RecordingSink::default();
+        loader
+            .crawl_docs_rs(
+                "demo",
+                "1.0.0",
+                100,
+                None,
+                &HashSet::new(),
+                10,
+                &mut crawled,
+                &mut |_| {},
+            )
+            .await
+            .expect("crawl succeeds");
+        // The site is gone; only the archive is left
+        drop(loader);
+
+        let mut reprocessed = RecordingSink::default();
+        let state = archive
+            .crawl("demo", "1.0.0", 100, 4, &mut reprocessed, &mut |_| {})
+            .await
+            .expect("archive holds the crawl");
+        assert_eq!(state.processed, 7);
+        assert_eq!(reprocessed.checkpoints, 2);
+
+        let summary = |sink: &RecordingSink| {
+            let mut pages: Vec<(String, String, String)> = sink
+                .stored
+                .iter()
+                .map(|page| {
+                    (
+                        page.url.clone(),
+                        page.item_type.clone(),
+                        page.content.clone(),
+                    )
+                })
+                .collect();
+            pages.sort();
+            pages
+        };
+        assert_eq!(summary(&reprocessed), summary(&crawled));
+        assert!(reprocessed
+            .stored
+            .iter()
+            .any(|page| page.url == ROOT && page.item_type == "crate"));
+
+        let missing = archive
+            .crawl(
+                "demo",
+                "2.0.0",
+                100,
+                4,
+                &mut RecordingSink::default(),
+                &mut |_| {},
+            )
+            .await
+            .unwrap_err();
+        assert!(missing.to_string().contains("No archived pages"));
+        std::fs::remove_dir_all(dir).unwrap();
     }
 }
@@ -0,0 +1,444 @@
+//! Crates documented by a local `cargo doc` build instead of docs.rs.
+//!
+//! Private crates never reach docs.rs, but their rustdoc output in
+//! `target/doc` has the same layout, so every HTML file under the crate's
+//! directory is parsed exactly like a crawled page. A directory walk replaces
+//! the crawl frontier and rate limit. Name and version come from the crate's
+//! `Cargo.toml`.
+
+use crate::{
+    CollectSink, CrateMetadata, CrawlProgress, CrawlSink, CrawlState, DocPage, RustLoader,
+    PROGRESS_EVERY_PAGES,
+};
+use anyhow::{anyhow, Result};
+use scraper::Html;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use std::time::Instant;
+use tracing::{debug, info};
+use url::Url;
+
+/// `origin` metadata of documents ingested from a local build
+pub const LOCAL_ORIGIN: &str = "local";
+
+/// Where the documentation of a local crate comes from, as recorded on its job
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalDocsSource {
+    /// Crate directory holding `Cargo.toml`
+    pub path: PathBuf,
+    /// Rustdoc output directory; looked up under `target/doc` when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc_dir: Option<PathBuf>,
+    /// Run `cargo doc --no-deps` before reading the output
+    #[serde(default)]
+    pub build: bool,
+}
+
+/// A crate described by its local `Cargo.toml`
+#[derive(Debug, Clone)]
+pub struct LocalCrate {
+    /// Directory holding the manifest
+    pub dir: PathBuf,
+    /// Name, version and descriptive fields from the manifest
+    pub metadata: CrateMetadata,
+}
+
+impl LocalCrate {
+    /// Read the crate whose `Cargo.toml` is in `dir`.
+    ///
+    /// Fields inherited from a workspace (`version.workspace = true`) are
+    /// read from the nearest enclosing `[workspace.package]`. A crate without
+    /// a version gets `0.0.0`, as Cargo does.
+    ///
+    /// # Errors
+    /// Returns an error if the manifest cannot be read or has no `[package]`
+    /// name.
+    pub fn from_manifest(dir: &Path) -> Result<Self> {
+        let manifest_path = dir.join("Cargo.toml");
+        let manifest = read_manifest(&manifest_path)?;
+        let package = manifest
+            .get("package")
+            .and_then(toml::Value::as_table)
+            .ok_or_else(|| {
+                anyhow!(
+                    "{} has no [package]; pass the directory of a single crate",
+                    manifest_path.display()
+                )
+            })?;
+
+        let string = |key: &str| -> Result<Option<String>> {
+            Ok(package_field(package, key, dir)?
+                .and_then(|value| value.as_str().map(ToString::to_string)))
+        };
+        let strings = |key: &str| -> Result<Vec<String>> {
+            Ok(package_field(package, key, dir)?
+                .and_then(|value| value.as_array().cloned())
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|v| v.as_str().map(ToString::to_string))
+                .collect())
+        };
+
+        let name = string("name")?
+            .ok_or_else(|| anyhow!("{} has no package name", manifest_path.display()))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            metadata: CrateMetadata {
+                newest_version: string("version")?.unwrap_or_else(|| "0.0.0".to_string()),
+                description: string("description")?,
+                documentation: string("documentation")?,
+                repository: string("repository")?,
+                keywords: strings("keywords")?,
+                categories: strings("categories")?,
+                name,
+            },
+        })
+    }
+
+    /// Crate name from the manifest
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.metadata.name
+    }
+
+    /// Crate version from the manifest
+    #[must_use]
+    pub fn version(&self) -> &str {
+        &self.metadata.newest_version
+    }
+
+    /// Rustdoc output directory holding this crate's pages: `doc_dir` when
+    /// given, otherwise `target/doc` of the crate or of an enclosing workspace.
+    ///
+    /// # Errors
+    /// Returns an error if the directory has no `{crate}/index.html`.
+    pub fn find_doc_dir(&self, doc_dir: Option<&Path>) -> Result<PathBuf> {
+        let index = |dir: &Path| dir.join(self.crate_ident()).join("index.html");
+        if let Some(doc_dir) = doc_dir {
+            if index(doc_dir).is_file() {
+                return Ok(doc_dir.to_path_buf());
+            }
+            return Err(anyhow!(
+                "{} has no rustdoc output for {} (expected {})",
+                doc_dir.display(),
+                self.name(),
+                index(doc_dir).display()
+            ));
+        }
+        self.dir
+            .ancestors()
+            .map(|dir| dir.join("target").join("doc"))
+            .find(|doc_dir| index(doc_dir).is_file())
+            .ok_or_else(|| {
+                anyhow!(
+                    "No rustdoc output for {} under {}; run `cargo doc --no-deps` or ask for a build",
+                    self.name(),
+                    self.dir.join("target").join("doc").display()
+                )
+            })
+    }
+
+    /// Run `cargo doc --no-deps` for the crate and return its output directory.
+    ///
+    /// Output goes to `target/doc` in the crate directory even inside a
+    /// workspace, so the pages are found without asking Cargo where they went.
+    ///
+    /// # Errors
+    /// Returns an error if Cargo cannot be started or the build fails.
+    pub async fn build_docs(&self) -> Result<PathBuf> {
+        let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+        let target_dir = self.dir.join("target");
+        info!(
+            "Building docs for {} in {}",
+            self.name(),
+            self.dir.display()
+        );
+        let output = tokio::process::Command::new(cargo)
+            .arg("doc")
+            .arg("--no-deps")
+            .arg("--manifest-path")
+            .arg(self.dir.join("Cargo.toml"))
+            .arg("--target-dir")
+            .arg(&target_dir)
+            .output()
+            .await
+            .map_err(|e| anyhow!("Failed to run cargo doc: {}", e))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let tail: Vec<&str> = stderr.lines().rev().take(10).collect();
+            return Err(anyhow!(
+                "cargo doc failed for {} ({}):\n{}",
+                self.name(),
+                output.status,
+                tail.into_iter().rev().collect::<Vec<_>>().join("\n")
+            ));
+        }
+        self.find_doc_dir(Some(&target_dir.join("doc")))
+    }
+
+    /// Parse the pages of this crate in `doc_dir`, handing them to `sink`
+    /// every `checkpoint_every` pages and a [`CrawlProgress`] to `progress`
+    /// every [`PROGRESS_EVERY_PAGES`] files.
+    ///
+    /// Files are read in path order and stored under their `file://` URLs;
+    /// sources (`doc/src`) and other crates' pages sit outside the crate's
+    /// directory and are never read. Returns the final state, whose
+    /// `processed` counts the files read.
+    ///
+    /// # Errors
+    /// Returns an error if the directory cannot be listed or the sink fails.
+    pub async fn crawl(
+        &self,
+        doc_dir: &Path,
+        max_pages: usize,
+        checkpoint_every: usize,
+        sink: &mut dyn CrawlSink,
+        progress: &mut (dyn FnMut(CrawlProgress) + Send),
+    ) -> Result<CrawlState> {
+        let started = Instant::now();
+        let doc_dir = doc_dir
+            .canonicalize()
+            .map_err(|e| anyhow!("Cannot read {}: {}", doc_dir.display(), e))?;
+        let crate_dir = doc_dir.join(self.crate_ident());
+        let root_index = crate_dir.join("index.html");
+        let mut files = Vec::new();
+        html_files(&crate_dir, &mut files)?;
+        files.sort();
+        if files.len() > max_pages {
+            info!(
+                "Reached page limit ({}), reading {} of {} files",
+                max_pages,
+                max_pages,
+                files.len()
+            );
+            files.truncate(max_pages);
+        }
+
+        let checkpoint_every = checkpoint_every.max(1);
+        let total = files.len();
+        let snapshot = |state: &CrawlState, handled: usize| CrawlProgress {
+            pages_fetched: state.processed,
+            pages_queued: total - handled,
+            pages_skipped: 0,
+            elapsed: started.elapsed(),
+        };
+        let mut state = CrawlState::default();
+        let mut pages = Vec::new();
+        for (handled, path) in (1..).zip(files) {
+            if handled % PROGRESS_EVERY_PAGES == 0 {
+                RustLoader::report_progress(progress, snapshot(&state, handled - 1));
+            }
+            let html = match tokio::fs::read_to_string(&path).await {
+                Ok(html) => html,
+                Err(e) => {
+                    debug!("Failed to read {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            let url = Url::from_file_path(&path)
+                .map_err(|()| anyhow!("{} is not an absolute path", path.display()))?
+                .to_string();
+            let location = self.location(path.strip_prefix(&doc_dir).unwrap_or(&path));
+            {
+                let document = Html::parse_document(&html);
+                pages.extend(RustLoader::parse_doc_page(
+                    &document,
+                    &url,
+                    &location,
+                    self.name(),
+                    path == root_index,
+                ));
+            }
+
+            state.processed += 1;
+            if state.processed % checkpoint_every == 0 {
+                sink.checkpoint(std::mem::take(&mut pages), &state).await?;
+            }
+        }
+        sink.checkpoint(pages, &state).await?;
+        RustLoader::report_progress(progress, snapshot(&state, total));
+        Ok(state)
+    }
+
+    /// Every page of this crate in `doc_dir`, in path order
+    ///
+    /// # Errors
+    /// Returns an error if the directory cannot be listed.
+    pub async fn load_docs(&self, doc_dir: &Path) -> Result<Vec<DocPage>> {
+        let mut sink = CollectSink::default();
+        self.crawl(doc_dir, usize::MAX, usize::MAX, &mut sink, &mut |_| {})
+            .await?;
+        Ok(sink.pages)
+    }
+
+    /// Directory rustdoc names after the crate
+    fn crate_ident(&self) -> String {
+        self.name().replace('-', "_")
+    }
+
+    /// docs.rs-shaped location of a file at `relative` in the doc directory,
+    /// from which the shared parser derives item type and module path
+    fn location(&self, relative: &Path) -> String {
+        let segments: Vec<String> = relative
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(segment) => Some(segment.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect();
+        format!(
+            "file:///{}/{}/{}",
+            self.name(),
+            self.version(),
+            segments.join("/")
+        )
+    }
+}
+
+fn read_manifest(path: &Path) -> Result<toml::Table> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Cannot read {}: {}", path.display(), e))?;
+    text.parse::<toml::Table>()
+        .map_err(|e| anyhow!("Invalid {}: {}", path.display(), e))
+}
+
+/// `[package]` field `key`, following `key.workspace = true` to the nearest
+/// enclosing workspace manifest
+fn package_field(package: &toml::Table, key: &str, dir: &Path) -> Result<Option<toml::Value>> {
+    let Some(value) = package.get(key) else {
+        return Ok(None);
+    };
+    let inherited = value
+        .as_table()
+        .and_then(|table| table.get("workspace"))
+        .and_then(toml::Value::as_bool)
+        .unwrap_or(false);
+    if !inherited {
+        return Ok(Some(value.clone()));
+    }
+    for ancestor in dir.ancestors() {
+        let manifest_path = ancestor.join("Cargo.toml");
+        if !manifest_path.is_file() {
+            continue;
+        }
+        let manifest = read_manifest(&manifest_path)?;
+        if let Some(workspace) = manifest.get("workspace") {
+            return Ok(workspace
+                .get("package")
+                .and_then(|package| package.get(key))
+                .cloned());
+        }
+    }
+    Err(anyhow!(
+        "{} inherits '{}' from a workspace, but no workspace manifest encloses it",
+        dir.join("Cargo.toml").display(),
+        key
+    ))
+}
+
+/// HTML files under `dir`, without following directory symlinks
+fn html_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| anyhow!("Cannot read {}: {}", dir.display(), e))?;
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            html_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LocalCrate;
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    fn fixture() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/local_crate")
+    }
+
+    #[test]
+    fn test_reads_manifest_with_workspace_inheritance() {
+        let local = LocalCrate::from_manifest(&fixture().join("widgets")).unwrap();
+        assert_eq!(local.name(), "widgets");
+        // version and repository come from the workspace's [workspace.package]
+        assert_eq!(local.version(), "0.3.1");
+        assert_eq!(
+            local.metadata.repository.as_deref(),
+            Some("https://git.example.com/acme/widgets")
+        );
+        assert_eq!(local.metadata.keywords, ["shapes", "internal"]);
+
+        assert!(LocalCrate::from_manifest(&fixture())
+            .unwrap_err()
+            .to_string()
+            .contains("has no [package]"));
+    }
+
+    #[test]
+    fn test_doc_dir_must_hold_the_crate() {
+        let local = LocalCrate::from_manifest(&fixture().join("widgets")).unwrap();
+        let doc_dir = fixture().join("doc");
+        assert_eq!(local.find_doc_dir(Some(&doc_dir)).unwrap(), doc_dir);
+        // The fixture has no target/doc, so nothing is found without doc_dir
+        assert!(local
+            .find_doc_dir(None)
+            .unwrap_err()
+            .to_string()
+            .contains("No rustdoc output for widgets"));
+        assert!(local.find_doc_dir(Some(&fixture())).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_parses_local_doc_tree_like_docs_rs() {
+        let local = LocalCrate::from_manifest(&fixture().join("widgets")).unwrap();
+        let doc_dir = fixture().join("doc").canonicalize().unwrap();
+        let pages = local.load_docs(&doc_dir).await.unwrap();
+
+        let by_url: BTreeMap<String, (String, String)> = pages
+            .iter()
+            .map(|page| {
+                let relative = page
+                    .url
+                    .strip_prefix(&format!("file://{}/", doc_dir.display()))
+                    .expect("file URL under the doc dir")
+                    .to_string();
+                (relative, (page.item_type.clone(), page.module_path.clone()))
+            })
+            .collect();
+        let expected: BTreeMap<String, (String, String)> = [
+            ("widgets/index.html", "crate", "widgets"),
+            (
+                "widgets/index.html#example-1",
+                "example",
+                "widgets#example-1",
+            ),
+            ("widgets/shapes/fn.area.html", "function", "widgets::shapes"),
+            ("widgets/shapes/index.html", "module", "widgets::shapes"),
+            ("widgets/struct.Widget.html", "struct", "widgets"),
+        ]
+        .into_iter()
+        .map(|(url, item_type, module_path)| {
+            (
+                url.to_string(),
+                (item_type.to_string(), module_path.to_string()),
+            )
+        })
+        .collect();
+        // all.html and the redirect stub have no doc block; doc/src is outside the crate
+        assert_eq!(by_url, expected);
+
+        let root = pages
+            .iter()
+            .find(|page| page.url.ends_with("widgets/index.html"))
+            .unwrap();
+        assert!(root.content.contains("Widgets for **internal** tools."));
+        assert!(root.content.contains("[`Widget`]("));
+    }
+}
@@ -0,0 +1,238 @@
+//! Check a crate name against crates.io before any work is queued for it.
+
+use crate::{CrateMetadata, RustLoader};
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::time::{timeout_at, Instant};
+use tracing::warn;
+
+/// Most names suggested for a crate crates.io does not know
+pub const MAX_SUGGESTIONS: usize = 5;
+
+/// Search results ranked by their distance to the requested name
+const SEARCH_RESULTS: usize = 20;
+
+/// What crates.io says about a requested crate name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CrateLookup {
+    /// The crate exists; its metadata carries the crates.io id as `name`
+    Found(CrateMetadata),
+    /// crates.io has no such crate; the existing names closest to it
+    NotFound { suggestions: Vec<String> },
+    /// crates.io failed or did not answer in time, for the given reason
+    Unavailable(String),
+}
+
+/// Names of the crates in a crates.io search response body
+///
+/// # Errors
+/// Returns an error if the body is not a crates.io search payload.
+pub fn parse_search_results(body: &str) -> Result<Vec<String>> {
+    #[derive(Deserialize)]
+    struct Hit {
+        name: String,
+    }
+    #[derive(Deserialize)]
+    struct Payload {
+        crates: Vec<Hit>,
+    }
+    let payload: Payload =
+        serde_json::from_str(body).map_err(|e| anyhow!("Parse crates.io search: {}", e))?;
+    Ok(payload.crates.into_iter().map(|hit| hit.name).collect())
+}
+
+/// Up to `limit` of `candidates`, closest to `name` first
+///
+/// Names are compared by edit distance, ignoring case and treating `-` and
+/// `_` alike; candidates at the same distance keep their order.
+#[must_use]
+pub fn closest_names(name: &str, candidates: &[String], limit: usize) -> Vec<String> {
+    let name = fold(name);
+    let mut ranked: Vec<(usize, &String)> = candidates
+        .iter()
+        .map(|candidate| (edit_distance(&name, &fold(candidate)), candidate))
+        .collect();
+    ranked.sort_by_key(|(distance, _)| *distance);
+    ranked
+        .into_iter()
+        .take(limit)
+        .map(|(_, candidate)| candidate.clone())
+        .collect()
+}
+
+fn fold(name: &str) -> Vec<char> {
+    name.trim()
+        .chars()
+        .map(|c| {
+            if c == '-' {
+                '_'
+            } else {
+                c.to_ascii_lowercase()
+            }
+        })
+        .collect()
+}
+
+/// Levenshtein distance between two names
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Whether a fetch failed because the page does not exist
+fn is_not_found(error: &anyhow::Error) -> bool {
+    format!("{error:#}").contains("HTTP status: 404")
+}
+
+impl RustLoader {
+    /// Search crates.io for up to `per_page` crates matching `query`, most relevant first
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the response cannot be parsed.
+    pub async fn search_crates(&mut self, query: &str, per_page: usize) -> Result<Vec<String>> {
+        let url = url::Url::parse_with_params(
+            "https://crates.io/api/v1/crates",
+            &[("q", query), ("per_page", &per_page.to_string())],
+        )?;
+        let text = self.get_text(url.as_str()).await?;
+        parse_search_results(&text)
+    }
+
+    /// Ask crates.io about `crate_name`, waiting at most `within`
+    ///
+    /// A 404 gives [`CrateLookup::NotFound`] with up to [`MAX_SUGGESTIONS`]
+    /// names from a crates.io search, or none if the search fails or time
+    /// runs out. Any other failure, including the timeout, gives
+    /// [`CrateLookup::Unavailable`], so callers can go ahead unchecked.
+    pub async fn lookup_crate(&mut self, crate_name: &str, within: Duration) -> CrateLookup {
+        let deadline = Instant::now() + within;
+        match timeout_at(deadline, self.load_crate_metadata(crate_name)).await {
+            Ok(Ok(metadata)) => CrateLookup::Found(metadata),
+            Ok(Err(e)) if is_not_found(&e) => {
+                let search = timeout_at(deadline, self.search_crates(crate_name, SEARCH_RESULTS));
+                let suggestions = match search.await {
+                    Ok(Ok(names)) => closest_names(crate_name, &names, MAX_SUGGESTIONS),
+                    Ok(Err(e)) => {
+                        warn!("crates.io search for '{}' failed: {}", crate_name, e);
+                        Vec::new()
+                    }
+                    Err(_) => Vec::new(),
+                };
+                CrateLookup::NotFound { suggestions }
+            }
+            Ok(Err(e)) => CrateLookup::Unavailable(format!("{e:#}")),
+            Err(_) => CrateLookup::Unavailable(format!(
+                "crates.io did not answer within {}s",
+                within.as_secs_f64()
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PageFetcher;
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+
+    /// Serves canned crates.io responses; other URLs fail with `status`
+    struct MockCratesIo {
+        pages: HashMap<String, String>,
+        status: &'static str,
+    }
+
+    #[async_trait]
+    impl PageFetcher for MockCratesIo {
+        async fn fetch_text(&self, url: &str) -> Result<String> {
+            self.pages
+                .get(url)
+                .cloned()
+                .ok_or_else(|| anyhow!("HTTP status: {}", self.status))
+        }
+    }
+
+    fn loader(pages: &[(&str, &str)], status: &'static str) -> RustLoader {
+        RustLoader::with_fetcher(Box::new(MockCratesIo {
+            pages: pages
+                .iter()
+                .map(|(url, body)| ((*url).to_string(), (*body).to_string()))
+                .collect(),
+            status,
+        }))
+    }
+
+    #[test]
+    fn test_closest_names() {
+        let candidates: Vec<String> = ["tokio-util", "tokio", "toml", "Tokio_Core"]
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            closest_names("tokoi", &candidates, 3),
+            vec!["tokio", "toml", "tokio-util"]
+        );
+        assert_eq!(
+            closest_names("tokio-core", &candidates, 1),
+            vec!["Tokio_Core"]
+        );
+        assert_eq!(edit_distance(&fold("kitten"), &fold("sitting")), 3);
+    }
+
+    #[test]
+    fn test_parse_search_results() {
+        let body = r#"{"crates": [{"id": "tokio", "name": "tokio"}, {"id": "toml", "name": "toml"}], "meta": {"total": 2}}"#;
+        assert_eq!(parse_search_results(body).unwrap(), vec!["tokio", "toml"]);
+        assert!(parse_search_results("<html>").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_lookup_crate() {
+        let mut found = loader(
+            &[(
+                "https://crates.io/api/v1/crates/serde-json",
+                r#"{"crate": {"id": "serde_json", "newest_version": "1.0.120"}}"#,
+            )],
+            "404 Not Found",
+        );
+        match found
+            .lookup_crate("serde-json", Duration::from_secs(5))
+            .await
+        {
+            CrateLookup::Found(metadata) => {
+                assert_eq!(metadata.name, "serde_json");
+                assert_eq!(metadata.newest_version, "1.0.120");
+            }
+            other => panic!("unexpected lookup: {other:?}"),
+        }
+
+        let mut missing = loader(
+            &[(
+                "https://crates.io/api/v1/crates?q=tokoi&per_page=20",
+                r#"{"crates": [{"name": "tokio-tokoi-bridge"}, {"name": "tokio"}]}"#,
+            )],
+            "404 Not Found",
+        );
+        assert_eq!(
+            missing.lookup_crate("tokoi", Duration::from_secs(5)).await,
+            CrateLookup::NotFound {
+                suggestions: vec!["tokio".to_string(), "tokio-tokoi-bridge".to_string()]
+            }
+        );
+
+        let mut down = loader(&[], "503 Service Unavailable");
+        assert!(matches!(
+            down.lookup_crate("tokio", Duration::from_secs(5)).await,
+            CrateLookup::Unavailable(reason) if reason.contains("503")
+        ));
+    }
+}
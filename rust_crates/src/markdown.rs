@@ -0,0 +1,460 @@
+//! HTML to Markdown conversion of docs.rs doc blocks.
+//!
+//! Headings, lists, tables, inline code and code blocks survive as Markdown,
+//! and intra-doc links are rewritten to absolute docs.rs URLs. A doc block
+//! that cannot be converted (nesting deeper than [`MAX_DEPTH`], or a bug in
+//! the walk) falls back to its plain text instead of failing the crawl.
+
+use scraper::{ElementRef, Html, Node, Selector};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use tracing::debug;
+use url::Url;
+
+/// Doc blocks of a rustdoc page: the item docs and those of its members
+pub const DOCBLOCK_SELECTOR: &str = "div.docblock, section.docblock, .rustdoc .docblock";
+
+/// Deepest element nesting converted before falling back to plain text
+pub const MAX_DEPTH: usize = 64;
+
+/// Elements whose content is laid out as blocks rather than inline text
+const BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "figure",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "ul",
+];
+
+/// Elements that carry no documentation
+const SKIPPED_TAGS: &[&str] = &["button", "script", "style", "noscript", "img", "svg"];
+
+/// Nesting exceeded [`MAX_DEPTH`]
+#[derive(Debug)]
+struct TooDeep;
+
+type Converted<T> = Result<T, TooDeep>;
+
+/// Markdown of every doc block on a rustdoc page, `None` when it has none
+#[must_use]
+pub fn page_markdown(document: &Html, page_url: &str) -> Option<String> {
+    let selector = Selector::parse(DOCBLOCK_SELECTOR).ok()?;
+    let base = Url::parse(page_url).ok();
+    let blocks: Vec<String> = document
+        .select(&selector)
+        .map(|element| docblock_markdown(element, base.as_ref()))
+        .filter(|markdown| !markdown.is_empty())
+        .collect();
+    (!blocks.is_empty()).then(|| blocks.join("\n\n"))
+}
+
+/// Markdown of one element, or its plain text if conversion fails
+#[must_use]
+pub fn docblock_markdown(element: ElementRef<'_>, base: Option<&Url>) -> String {
+    let converter = Converter { base };
+    match catch_unwind(AssertUnwindSafe(|| converter.blocks(element, 0))) {
+        Ok(Ok(blocks)) => blocks.join("\n\n"),
+        Ok(Err(TooDeep)) => {
+            debug!("Doc block nested deeper than {MAX_DEPTH}; using plain text");
+            plain_text(element)
+        }
+        Err(_) => {
+            debug!("Markdown conversion of doc block failed; using plain text");
+            plain_text(element)
+        }
+    }
+}
+
+/// Text nodes of `element`, trimmed and joined line by line
+#[must_use]
+pub fn plain_text(element: ElementRef<'_>) -> String {
+    element
+        .text()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+struct Converter<'a> {
+    base: Option<&'a Url>,
+}
+
+impl Converter<'_> {
+    /// Markdown blocks of the children of `parent`; runs of inline content
+    /// between block elements become paragraphs
+    fn blocks(&self, parent: ElementRef<'_>, depth: usize) -> Converted<Vec<String>> {
+        if depth > MAX_DEPTH {
+            return Err(TooDeep);
+        }
+        let mut blocks = Vec::new();
+        let mut inline = String::new();
+        for child in parent.children() {
+            match child.value() {
+                Node::Text(text) => inline.push_str(text),
+                Node::Element(_) => {
+                    let Some(element) = ElementRef::wrap(child) else {
+                        continue;
+                    };
+                    if is_skipped(element) {
+                        continue;
+                    }
+                    if BLOCK_TAGS.contains(&element.value().name()) {
+                        push_paragraph(&mut blocks, &std::mem::take(&mut inline));
+                        blocks.extend(self.block(element, depth + 1)?);
+                    } else {
+                        inline.push_str(&self.inline(element, depth + 1)?);
+                    }
+                }
+                _ => {}
+            }
+        }
+        push_paragraph(&mut blocks, &inline);
+        Ok(blocks)
+    }
+
+    /// Markdown blocks of a block-level element
+    fn block(&self, element: ElementRef<'_>, depth: usize) -> Converted<Vec<String>> {
+        let name = element.value().name();
+        let block = match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = usize::from(name.as_bytes()[1] - b'0');
+                let text = collapse_whitespace(&self.inline_children(element, depth)?);
+                if text.is_empty() {
+                    return Ok(Vec::new());
+                }
+                format!("{} {text}", "#".repeat(level))
+            }
+            "p" | "dt" | "summary" => {
+                let text = collapse_whitespace(&self.inline_children(element, depth)?);
+                if text.is_empty() {
+                    return Ok(Vec::new());
+                }
+                text
+            }
+            "pre" => code_block(element),
+            "ul" | "ol" => self.list(element, name == "ol", depth)?,
+            "blockquote" => self
+                .blocks(element, depth)?
+                .join("\n\n")
+                .lines()
+                .map(|line| {
+                    if line.is_empty() {
+                        ">".to_string()
+                    } else {
+                        format!("> {line}")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            "table" => self.table(element, depth)?,
+            "hr" => "---".to_string(),
+            _ => return self.blocks(element, depth),
+        };
+        Ok(if block.is_empty() {
+            Vec::new()
+        } else {
+            vec![block]
+        })
+    }
+
+    /// A list with one item per `li`, nested lists indented under their item
+    fn list(&self, element: ElementRef<'_>, ordered: bool, depth: usize) -> Converted<String> {
+        let mut items = Vec::new();
+        for (index, item) in element
+            .children()
+            .filter_map(ElementRef::wrap)
+            .filter(|child| child.value().name() == "li")
+            .enumerate()
+        {
+            let marker = if ordered {
+                format!("{}. ", index + 1)
+            } else {
+                "- ".to_string()
+            };
+            let indent = " ".repeat(marker.len());
+            let body = self.blocks(item, depth + 1)?.join("\n");
+            let mut lines = body.lines();
+            let first = lines.next().unwrap_or_default();
+            let mut rendered = format!("{marker}{first}");
+            for line in lines {
+                rendered.push('\n');
+                if !line.is_empty() {
+                    rendered.push_str(&indent);
+                    rendered.push_str(line);
+                }
+            }
+            items.push(rendered);
+        }
+        Ok(items.join("\n"))
+    }
+
+    /// A pipe table; the first row is the header
+    fn table(&self, element: ElementRef<'_>, depth: usize) -> Converted<String> {
+        let Ok(row_selector) = Selector::parse("tr") else {
+            return Ok(String::new());
+        };
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        for row in element.select(&row_selector) {
+            let mut cells = Vec::new();
+            for cell in row
+                .children()
+                .filter_map(ElementRef::wrap)
+                .filter(|cell| matches!(cell.value().name(), "td" | "th"))
+            {
+                let text = collapse_whitespace(&self.inline_children(cell, depth + 1)?);
+                cells.push(text.replace('|', "\\|"));
+            }
+            if !cells.is_empty() {
+                rows.push(cells);
+            }
+        }
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return Ok(String::new());
+        }
+
+        let render = |cells: &[String]| {
+            let mut padded: Vec<&str> = cells.iter().map(String::as_str).collect();
+            padded.resize(columns, "");
+            format!("| {} |", padded.join(" | "))
+        };
+        let mut lines = vec![render(&rows[0]), format!("|{}", " --- |".repeat(columns))];
+        lines.extend(rows[1..].iter().map(|row| render(row)));
+        Ok(lines.join("\n"))
+    }
+
+    /// Inline Markdown of an element inside a paragraph
+    fn inline(&self, element: ElementRef<'_>, depth: usize) -> Converted<String> {
+        if depth > MAX_DEPTH {
+            return Err(TooDeep);
+        }
+        if is_skipped(element) {
+            return Ok(String::new());
+        }
+        let text = match element.value().name() {
+            "code" | "kbd" | "samp" => inline_code(&element.text().collect::<String>()),
+            "strong" | "b" => wrap_inline(&self.inline_children(element, depth)?, "**"),
+            "em" | "i" => wrap_inline(&self.inline_children(element, depth)?, "*"),
+            "br" => "\n".to_string(),
+            "a" => {
+                let text = self.inline_children(element, depth)?;
+                match element
+                    .value()
+                    .attr("href")
+                    .and_then(|href| self.link(href))
+                {
+                    Some(url) if !text.trim().is_empty() => {
+                        format!("[{}]({url})", collapse_whitespace(&text))
+                    }
+                    _ => text,
+                }
+            }
+            _ => self.inline_children(element, depth)?,
+        };
+        Ok(text)
+    }
+
+    fn inline_children(&self, element: ElementRef<'_>, depth: usize) -> Converted<String> {
+        let mut text = String::new();
+        for child in element.children() {
+            match child.value() {
+                Node::Text(t) => text.push_str(t),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        text.push_str(&self.inline(child, depth + 1)?);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(text)
+    }
+
+    /// Absolute URL of a link, resolved against the page
+    fn link(&self, href: &str) -> Option<String> {
+        match self.base {
+            Some(base) => base.join(href).ok().map(String::from),
+            None => Url::parse(href).ok().map(String::from),
+        }
+    }
+}
+
+/// Rustdoc decoration that is not part of the docs: `§` heading anchors,
+/// the `ⓘ` tooltip of ignored examples, buttons and images
+fn is_skipped(element: ElementRef<'_>) -> bool {
+    let value = element.value();
+    SKIPPED_TAGS.contains(&value.name())
+        || value
+            .classes()
+            .any(|class| matches!(class, "doc-anchor" | "tooltip" | "anchor"))
+}
+
+fn push_paragraph(blocks: &mut Vec<String>, inline: &str) {
+    let paragraph = inline
+        .split('\n')
+        .map(collapse_whitespace)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    if !paragraph.is_empty() {
+        blocks.push(paragraph);
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Fenced code block, tagged with the language rustdoc or the Markdown
+/// renderer recorded in its classes
+fn code_block(element: ElementRef<'_>) -> String {
+    let code = element.text().collect::<String>();
+    let code = code.trim_end().trim_start_matches('\n');
+    let classes = element.value().classes().chain(
+        element
+            .children()
+            .filter_map(ElementRef::wrap)
+            .flat_map(|child| child.value().classes()),
+    );
+    let mut language = "";
+    for class in classes {
+        if let Some(lang) = class.strip_prefix("language-") {
+            language = lang;
+            break;
+        }
+        if class == "rust" {
+            language = "rust";
+        }
+    }
+    let fence = if code.contains("```") { "````" } else { "```" };
+    format!("{fence}{language}\n{code}\n{fence}")
+}
+
+fn inline_code(code: &str) -> String {
+    let code = code.trim();
+    if code.is_empty() {
+        String::new()
+    } else if code.contains('`') {
+        format!("`` {code} ``")
+    } else {
+        format!("`{code}`")
+    }
+}
+
+/// Wrap inline text in emphasis markers, keeping surrounding spaces outside
+fn wrap_inline(text: &str, marker: &str) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return text.to_string();
+    }
+    let leading = if text.starts_with(char::is_whitespace) {
+        " "
+    } else {
+        ""
+    };
+    let trailing = if text.ends_with(char::is_whitespace) {
+        " "
+    } else {
+        ""
+    };
+    format!("{leading}{marker}{trimmed}{marker}{trailing}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{docblock_markdown, page_markdown, MAX_DEPTH};
+    use scraper::{Html, Selector};
+    use std::path::PathBuf;
+
+    const SEMAPHORE_URL: &str = "https://docs.rs/tokio/1.38.0/tokio/sync/struct.Semaphore.html";
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name)
+    }
+
+    /// Compare with the golden file; `UPDATE_GOLDEN=1` rewrites it instead
+    fn assert_golden(name: &str, actual: &str) {
+        let path = fixture(name);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(&path, actual).expect("write golden file");
+            return;
+        }
+        let expected = std::fs::read_to_string(&path).expect("golden file");
+        assert_eq!(actual, expected, "markdown differs from {}", path.display());
+    }
+
+    #[test]
+    fn test_docs_rs_page_matches_golden_markdown() {
+        let html = std::fs::read_to_string(fixture("tokio_struct_semaphore.html")).unwrap();
+        let markdown = page_markdown(&Html::parse_document(&html), SEMAPHORE_URL).unwrap();
+        assert_golden("tokio_struct_semaphore.md", &format!("{markdown}\n"));
+    }
+
+    #[test]
+    fn test_select_page_keeps_code_languages() {
+        let html = std::fs::read_to_string(fixture("tokio_macro_select.html")).unwrap();
+        let markdown = page_markdown(
+            &Html::parse_document(&html),
+            "https://docs.rs/tokio/1.38.0/tokio/macro.select.html",
+        )
+        .unwrap();
+        assert!(markdown.starts_with("Waits on multiple concurrent branches"));
+        assert!(markdown.contains("## Examples"));
+        assert!(markdown.contains("```text\n<pattern> = <async expression>"));
+        assert!(markdown.contains("```rust\nasync fn do_stuff_async() {"));
+        assert!(!markdown.contains('§'));
+        assert!(!markdown.contains('ⓘ'));
+    }
+
+    #[test]
+    fn test_deep_nesting_falls_back_to_plain_text() {
+        let depth = MAX_DEPTH + 10;
+        let html = format!(
+            "<div class=\"docblock\">{}<p>Deep <code>item</code></p>{}</div>",
+            "<ul><li>".repeat(depth),
+            "</li></ul>".repeat(depth)
+        );
+        let document = Html::parse_document(&html);
+        let selector = Selector::parse("div.docblock").unwrap();
+        let element = document.select(&selector).next().unwrap();
+        assert_eq!(docblock_markdown(element, None), "Deep\nitem");
+    }
+
+    #[test]
+    fn test_malformed_html_still_converts() {
+        let html = "<div class=\"docblock\"><p>Unclosed <strong>bold<p>Next</em> para\
+                    <ul><li>one<li>two</ul></div></span>";
+        let markdown = page_markdown(&Html::parse_document(html), SEMAPHORE_URL).unwrap();
+        // The parser reopens the unclosed <strong> in each following block
+        assert_eq!(
+            markdown,
+            "Unclosed **bold**\n\n**Next para**\n\n- **one**\n- **two**"
+        );
+    }
+}
@@ -0,0 +1,286 @@
+//! Standard library crates, documented on doc.rust-lang.org per release channel.
+//!
+//! `std`, `core` and friends are not published to crates.io or docs.rs. Their
+//! rustdoc output lives at `https://doc.rust-lang.org/{channel}/{crate}/` with
+//! the same layout as docs.rs, so the crawl, item-type detection and
+//! module-path extraction are shared; only the root URL, the link scope and
+//! the version (the Rust release instead of a crates.io version) differ.
+
+use crate::{CrateMetadata, RustLoader};
+use scraper::{Html, Selector};
+use tracing::debug;
+
+/// Crates documented on doc.rust-lang.org instead of docs.rs
+pub const STD_CRATES: &[&str] = &["std", "core", "alloc", "proc_macro", "test"];
+
+/// Release channels doc.rust-lang.org publishes documentation for
+pub const RUST_CHANNELS: &[&str] = &["stable", "beta", "nightly"];
+
+/// Channel crawled when none is requested
+pub const DEFAULT_RUST_CHANNEL: &str = "stable";
+
+const STD_DOCS_HOST: &str = "https://doc.rust-lang.org";
+
+/// Whether `crate_name` is a standard library crate
+#[must_use]
+pub fn is_std_crate(crate_name: &str) -> bool {
+    STD_CRATES.contains(&crate_name)
+}
+
+/// Whether `channel` is a release channel doc.rust-lang.org serves
+#[must_use]
+pub fn is_rust_channel(channel: &str) -> bool {
+    RUST_CHANNELS.contains(&channel)
+}
+
+/// Rust release a rustdoc page was built from, as shown next to the crate
+/// name in the sidebar (`1.79.0`, `1.81.0-nightly`, `1.80.0-beta.3`)
+#[must_use]
+pub fn parse_rust_release(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse(".sidebar-crate .version, .version").ok()?;
+    document
+        .select(&selector)
+        .flat_map(|element| {
+            element
+                .text()
+                .flat_map(str::split_whitespace)
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        })
+        .find_map(|token| release_token(&token))
+}
+
+/// `token` as a release number if it looks like one (`1.79.0`)
+fn release_token(token: &str) -> Option<String> {
+    let token = token.trim_matches(|c: char| matches!(c, '(' | ')' | ',' | 'v'));
+    let (numbers, _) = token.split_once('-').unwrap_or((token, ""));
+    let parts: Vec<&str> = numbers.split('.').collect();
+    let numeric = parts.len() == 3
+        && parts
+            .iter()
+            .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()));
+    numeric.then(|| token.to_string())
+}
+
+impl RustLoader {
+    /// doc.rust-lang.org directory of a standard library crate on `channel`
+    pub(crate) fn std_root_url(crate_name: &str, channel: &str) -> String {
+        format!("{STD_DOCS_HOST}/{channel}/{crate_name}")
+    }
+
+    /// Links a crawl of a standard library crate may follow: pages of the
+    /// same crate on the same channel, never the book or the reference
+    pub(crate) fn std_link_scope(url: &str, crate_name: &str) -> Option<String> {
+        let rest = url.strip_prefix(STD_DOCS_HOST)?.strip_prefix('/')?;
+        let channel = rest.split('/').next().filter(|c| !c.is_empty())?;
+        Some(format!("{STD_DOCS_HOST}/{channel}/{crate_name}/"))
+    }
+
+    /// Metadata of a standard library crate, which crates.io does not list
+    pub(crate) fn std_crate_metadata(crate_name: &str) -> CrateMetadata {
+        CrateMetadata {
+            name: crate_name.to_string(),
+            newest_version: DEFAULT_RUST_CHANNEL.to_string(),
+            description: Some(format!("The Rust standard library crate `{crate_name}`")),
+            documentation: Some(format!(
+                "{}/",
+                Self::std_root_url(crate_name, DEFAULT_RUST_CHANNEL)
+            )),
+            repository: Some("https://github.com/rust-lang/rust".to_string()),
+            keywords: Vec::new(),
+            categories: Vec::new(),
+        }
+    }
+
+    /// Rust release the `channel` documentation of a standard library crate
+    /// was built from, read from its root page; `None` if it cannot be found
+    pub async fn fetch_rust_release(&mut self, crate_name: &str, channel: &str) -> Option<String> {
+        let url = format!("{}/index.html", Self::std_root_url(crate_name, channel));
+        match self.get_text(&url).await {
+            Ok(html) => parse_rust_release(&html),
+            Err(e) => {
+                debug!("No release for {} on {}: {}", crate_name, channel, e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_std_crate, parse_rust_release};
+    use crate::{CrawlSink, CrawlState, DocPage, PageFetcher, RustLoader};
+    use anyhow::{anyhow, Result};
+    use async_trait::async_trait;
+    use std::collections::{HashMap, HashSet};
+    use std::sync::{Arc, Mutex};
+
+    const ROOT: &str = "https://doc.rust-lang.org/stable/std";
+
+    struct MockFetcher {
+        pages: HashMap<String, String>,
+        requested: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl PageFetcher for MockFetcher {
+        async fn fetch_text(&self, url: &str) -> Result<String> {
+            self.requested.lock().unwrap().push(url.to_string());
+            self.pages
+                .get(url)
+                .cloned()
+                .ok_or_else(|| anyhow!("HTTP status: 404 Not Found"))
+        }
+    }
+
+    #[derive(Default)]
+    struct Collect(Vec<DocPage>);
+
+    #[async_trait]
+    impl CrawlSink for Collect {
+        async fn checkpoint(&mut self, pages: Vec<DocPage>, _state: &CrawlState) -> Result<()> {
+            self.0.extend(pages);
+            Ok(())
+        }
+    }
+
+    /// Root of `std` linking to its own pages, to `core`, the book and the reference
+    fn mock_std() -> HashMap<String, String> {
+        let mut site = HashMap::new();
+        site.insert(
+            format!("{ROOT}/index.html"),
+            r#"<nav class="sidebar"><div class="sidebar-crate"><h2><a href="../std/index.html">std</a>
+               <span class="version">1.79.0</span></h2></div></nav>
+               <div class="docblock"><p>The Rust Standard Library</p></div>
+               <a href="collections/index.html">collections</a>
+               <a href="option/enum.Option.html">Option</a>
+               <a href="../core/index.html">core</a>
+               <a href="../book/index.html">The Book</a>
+               <a href="https://doc.rust-lang.org/stable/reference/">Reference</a>
+               <a href="../src/std/lib.rs.html">source</a>"#
+                .to_string(),
+        );
+        site.insert(
+            format!("{ROOT}/collections/index.html"),
+            r#"<div class="docblock"><p>Collection types.</p></div>
+               <a href="struct.HashMap.html">HashMap</a>
+               <a href="../../nightly/std/collections/index.html">nightly</a>"#
+                .to_string(),
+        );
+        site.insert(
+            format!("{ROOT}/collections/struct.HashMap.html"),
+            r#"<div class="docblock"><p>A hash map.</p></div>"#.to_string(),
+        );
+        site.insert(
+            format!("{ROOT}/option/enum.Option.html"),
+            r#"<div class="docblock"><p>The <code>Option</code> type.</p></div>"#.to_string(),
+        );
+        site
+    }
+
+    #[tokio::test]
+    async fn test_crawls_std_within_its_crate_root() {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let mut loader = RustLoader::with_fetcher(Box::new(MockFetcher {
+            pages: mock_std(),
+            requested: requested.clone(),
+        }));
+        let mut sink = Collect::default();
+        loader
+            .crawl_docs_rs(
+                "std",
+                "stable",
+                50,
+                None,
+                &HashSet::new(),
+                10,
+                &mut sink,
+                &mut |_| {},
+            )
+            .await
+            .expect("crawl succeeds");
+
+        let mut pages: Vec<(String, String, String)> = sink
+            .0
+            .iter()
+            .map(|p| (p.url.clone(), p.item_type.clone(), p.module_path.clone()))
+            .collect();
+        pages.sort();
+        assert_eq!(
+            pages,
+            [
+                (
+                    format!("{ROOT}/collections/index.html"),
+                    "module".to_string(),
+                    "std::collections".to_string()
+                ),
+                (
+                    format!("{ROOT}/collections/struct.HashMap.html"),
+                    "struct".to_string(),
+                    "std::collections".to_string()
+                ),
+                (
+                    format!("{ROOT}/index.html"),
+                    "crate".to_string(),
+                    "std".to_string()
+                ),
+                (
+                    format!("{ROOT}/option/enum.Option.html"),
+                    "enum".to_string(),
+                    "std::option".to_string()
+                ),
+            ]
+        );
+        // Nothing outside stable/std was requested: no core, book, reference or nightly
+        let requested = requested.lock().unwrap().clone();
+        assert_eq!(requested.len(), 4);
+        assert!(requested
+            .iter()
+            .all(|url| url.starts_with(&format!("{ROOT}/"))));
+    }
+
+    #[tokio::test]
+    async fn test_std_metadata_and_release_come_from_doc_rust_lang_org() {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let mut loader = RustLoader::with_fetcher(Box::new(MockFetcher {
+            pages: mock_std(),
+            requested: requested.clone(),
+        }));
+        let metadata = loader.load_crate_metadata("std").await.unwrap();
+        assert_eq!(metadata.newest_version, "stable");
+        assert_eq!(
+            loader.fetch_rust_release("std", "stable").await.as_deref(),
+            Some("1.79.0")
+        );
+        assert!(loader.fetch_readme("std", "stable").await.is_none());
+        assert!(loader
+            .fetch_dependencies("std", "stable")
+            .await
+            .unwrap()
+            .is_empty());
+        // Only the root page was fetched; crates.io was never asked
+        assert_eq!(
+            requested.lock().unwrap().clone(),
+            [format!("{ROOT}/index.html")]
+        );
+    }
+
+    #[test]
+    fn test_parses_release_from_sidebar() {
+        assert_eq!(
+            parse_rust_release(
+                r#"<div class="version">Version 1.81.0-nightly (3cf924b93 2024-06-13)</div>"#
+            )
+            .as_deref(),
+            Some("1.81.0-nightly")
+        );
+        assert_eq!(
+            parse_rust_release(r#"<span class="version">1.80.0-beta.3</span>"#).as_deref(),
+            Some("1.80.0-beta.3")
+        );
+        assert_eq!(parse_rust_release("<p>no version here</p>"), None);
+        assert!(is_std_crate("core"));
+        assert!(!is_std_crate("tokio"));
+    }
+}
@@ -0,0 +1,222 @@
+//! Resolve a requested version or semver range against the versions published on crates.io.
+
+use crate::RustLoader;
+use anyhow::{anyhow, Result};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+
+/// Most versions listed when a request matches nothing
+const NEARBY_VERSIONS: usize = 10;
+
+/// Pages of the crates.io versions list followed before giving up
+const MAX_VERSION_PAGES: usize = 20;
+
+/// A release listed by crates.io
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrateVersion {
+    pub num: String,
+    #[serde(default)]
+    pub yanked: bool,
+}
+
+/// Parse a crates.io `/versions` response body
+///
+/// # Errors
+/// Returns an error if the body is not a crates.io versions payload.
+pub fn parse_versions(body: &str) -> Result<Vec<CrateVersion>> {
+    #[derive(Deserialize)]
+    struct Payload {
+        versions: Vec<CrateVersion>,
+    }
+    let payload: Payload =
+        serde_json::from_str(body).map_err(|e| anyhow!("Parse crates.io versions: {}", e))?;
+    Ok(payload.versions)
+}
+
+/// Resolve `requested` to the highest published, non-yanked version it matches.
+///
+/// A full version (`1.0.3`) pins exactly; anything else is a semver range
+/// (`^1.0`, `1.x`, `>=0.4, <0.6`), with `latest` meaning `*`. Pre-releases
+/// only match ranges that name a pre-release themselves.
+///
+/// # Errors
+/// Returns an error if `requested` is not a valid version requirement, or if
+/// nothing matches; the latter lists the nearest available versions.
+pub fn resolve_version(
+    crate_name: &str,
+    requested: &str,
+    available: &[CrateVersion],
+) -> Result<String> {
+    let requested = requested.trim();
+    let mut published: Vec<Version> = available
+        .iter()
+        .filter(|v| !v.yanked)
+        .filter_map(|v| Version::parse(&v.num).ok())
+        .collect();
+    published.sort_unstable_by(|a, b| b.cmp(a));
+
+    let req = if let Ok(exact) = Version::parse(requested) {
+        if published.contains(&exact) {
+            return Ok(exact.to_string());
+        }
+        VersionReq::parse(&format!("={exact}"))?
+    } else {
+        let spec = if requested.eq_ignore_ascii_case("latest") {
+            "*"
+        } else {
+            requested
+        };
+        VersionReq::parse(spec).map_err(|e| {
+            anyhow!("Invalid version requirement '{requested}' for crate {crate_name}: {e}")
+        })?
+    };
+
+    if let Some(found) = published.iter().find(|v| req.matches(v)) {
+        return Ok(found.to_string());
+    }
+
+    // Prefer releases sharing the requested major version, newest first
+    let major = req.comparators.first().map(|c| c.major);
+    let mut nearby: Vec<String> = published
+        .iter()
+        .filter(|v| Some(v.major) == major)
+        .take(NEARBY_VERSIONS)
+        .map(ToString::to_string)
+        .collect();
+    if nearby.is_empty() {
+        nearby = published
+            .iter()
+            .take(NEARBY_VERSIONS)
+            .map(ToString::to_string)
+            .collect();
+    }
+    if nearby.is_empty() {
+        return Err(anyhow!(
+            "No version of crate {crate_name} matches '{requested}': no published versions"
+        ));
+    }
+    Err(anyhow!(
+        "No version of crate {crate_name} matches '{requested}'. Available versions: {}",
+        nearby.join(", ")
+    ))
+}
+
+/// Whether `candidate` is a later release than `current`
+///
+/// Versions that do not parse as semver are compared for equality only.
+#[must_use]
+pub fn is_newer_version(current: &str, candidate: &str) -> bool {
+    match (
+        Version::parse(current.trim()),
+        Version::parse(candidate.trim()),
+    ) {
+        (Ok(current), Ok(candidate)) => candidate > current,
+        _ => current.trim() != candidate.trim(),
+    }
+}
+
+impl RustLoader {
+    /// Fetch every version of a crate listed on crates.io, following pagination.
+    ///
+    /// # Errors
+    /// Returns an error if a request fails or a response cannot be parsed.
+    pub async fn fetch_versions(&mut self, crate_name: &str) -> Result<Vec<CrateVersion>> {
+        let base = format!("https://crates.io/api/v1/crates/{crate_name}/versions");
+        let mut url = base.clone();
+        let mut versions = Vec::new();
+        for _ in 0..MAX_VERSION_PAGES {
+            let text = self.get_text(&url).await?;
+            versions.extend(parse_versions(&text)?);
+            let next_page = serde_json::from_str::<serde_json::Value>(&text)
+                .ok()
+                .and_then(|json| {
+                    json.pointer("/meta/next_page")
+                        .and_then(|v| v.as_str())
+                        .map(ToString::to_string)
+                });
+            match next_page {
+                Some(query) => url = format!("{base}{query}"),
+                None => break,
+            }
+        }
+        Ok(versions)
+    }
+
+    /// Resolve a requested version or range against crates.io
+    ///
+    /// # Errors
+    /// Returns an error if the version list cannot be fetched or nothing matches.
+    pub async fn resolve_version(&mut self, crate_name: &str, requested: &str) -> Result<String> {
+        let versions = self.fetch_versions(crate_name).await?;
+        resolve_version(crate_name, requested, &versions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_newer_version, parse_versions, resolve_version};
+
+    const VERSIONS: &str = r#"{
+        "versions": [
+            {"num": "2.0.0-beta.2", "yanked": false},
+            {"num": "1.4.0", "yanked": true},
+            {"num": "1.3.1", "yanked": false},
+            {"num": "1.3.0", "yanked": false},
+            {"num": "1.2.0-rc.1", "yanked": false},
+            {"num": "1.0.0", "yanked": false},
+            {"num": "0.9.5", "yanked": false}
+        ],
+        "meta": {"total": 7, "next_page": null}
+    }"#;
+
+    fn resolve(requested: &str) -> anyhow::Result<String> {
+        resolve_version("demo", requested, &parse_versions(VERSIONS).unwrap())
+    }
+
+    #[test]
+    fn test_ranges_resolve_to_highest_non_yanked_release() {
+        assert_eq!(resolve("^1.0").unwrap(), "1.3.1");
+        assert_eq!(resolve("1.x").unwrap(), "1.3.1");
+        assert_eq!(resolve("~1.3.0").unwrap(), "1.3.1");
+        assert_eq!(resolve(">=0.9, <1.0").unwrap(), "0.9.5");
+        assert_eq!(resolve("latest").unwrap(), "1.3.1");
+    }
+
+    #[test]
+    fn test_exact_versions_pin() {
+        assert_eq!(resolve("1.3.0").unwrap(), "1.3.0");
+        assert_eq!(resolve("1.2.0-rc.1").unwrap(), "1.2.0-rc.1");
+        // Yanked releases cannot be pinned
+        let err = resolve("1.4.0").unwrap_err().to_string();
+        assert!(
+            err.contains("Available versions: 1.3.1, 1.3.0, 1.2.0-rc.1, 1.0.0"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_pre_releases_need_an_explicit_pre_release_range() {
+        assert!(resolve("^2").is_err());
+        assert_eq!(resolve("^2.0.0-beta.1").unwrap(), "2.0.0-beta.2");
+        assert_eq!(resolve(">=1.2.0-rc.0, <1.2.0").unwrap(), "1.2.0-rc.1");
+    }
+
+    #[test]
+    fn test_unmatched_request_lists_nearby_versions() {
+        let err = resolve("^3").unwrap_err().to_string();
+        assert!(
+            err.contains("Available versions: 2.0.0-beta.2, 1.3.1, 1.3.0, 1.2.0-rc.1"),
+            "{err}"
+        );
+        assert!(resolve("not a version").is_err());
+    }
+
+    #[test]
+    fn test_newer_versions_compare_by_semver() {
+        assert!(is_newer_version("1.9.0", "1.10.0"));
+        assert!(!is_newer_version("1.10.0", "1.9.0"));
+        assert!(!is_newer_version("1.0.0", "1.0.0"));
+        assert!(is_newer_version("1.0.0-rc.1", "1.0.0"));
+        assert!(is_newer_version("unknown", "1.0.0"));
+    }
+}
@@ -0,0 +1,17 @@
+//! Widgets for **internal** tools.
+//!
+//! Build one as a [`Widget`]:
+//!
+//! ```
+//! use widgets::Widget;
+//!
+//! let widget = Widget { size: 3 };
+//! assert_eq!(widget.size, 3);
+//! ```
+
+pub mod shapes;
+
+/// A widget with a size
+pub struct Widget {
+    pub size: u32,
+}
@@ -0,0 +1,8 @@
+//! Geometry helpers for widgets.
+
+use crate::Widget;
+
+/// Area covered by a widget.
+pub fn area(widget: &Widget) -> u32 {
+    widget.size * widget.size
+}
@@ -200,6 +200,23 @@ BEGIN
     END IF;
 END $$;
 
+-- Create ingest_jobs table for intelligent ingestion tracking
+CREATE TABLE IF NOT EXISTS ingest_jobs (
+    id UUID PRIMARY KEY,
+    url TEXT NOT NULL,
+    doc_type TEXT NOT NULL,
+    status job_status DEFAULT 'queued',
+    started_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finished_at TIMESTAMPTZ NULL,
+    output TEXT NULL,
+    error TEXT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_ingest_jobs_status ON ingest_jobs(status);
+CREATE INDEX IF NOT EXISTS idx_ingest_jobs_started_at ON ingest_jobs(started_at DESC);
+
 -- Create crate_jobs table for background job tracking
 CREATE TABLE IF NOT EXISTS crate_jobs (
     id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
@@ -210,6 +227,13 @@ CREATE TABLE IF NOT EXISTS crate_jobs (
     error TEXT,
     started_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
     finished_at TIMESTAMPTZ,
+    crate_job_state JSONB,
+    options JSONB,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL DEFAULT 3,
+    next_run_at TIMESTAMPTZ,
+    details JSONB,
+    idempotency_key TEXT,
     created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
     updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
 );
@@ -219,6 +243,72 @@ CREATE INDEX IF NOT EXISTS idx_crate_jobs_crate_name ON crate_jobs(crate_name);
 CREATE INDEX IF NOT EXISTS idx_crate_jobs_status ON crate_jobs(status);
 CREATE INDEX IF NOT EXISTS idx_crate_jobs_operation ON crate_jobs(operation);
 CREATE INDEX IF NOT EXISTS idx_crate_jobs_started_at ON crate_jobs(started_at DESC);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_crate_jobs_idempotency_key ON crate_jobs(idempotency_key);
+
+-- Create crate_job_events table for the job audit trail
+CREATE TABLE IF NOT EXISTS crate_job_events (
+    id BIGSERIAL PRIMARY KEY,
+    job_id UUID NOT NULL REFERENCES crate_jobs(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    event_type TEXT NOT NULL,
+    old_status job_status,
+    new_status job_status,
+    progress INTEGER,
+    detail JSONB
+);
+
+CREATE INDEX IF NOT EXISTS idx_crate_job_events_job ON crate_job_events(job_id, id DESC);
+CREATE INDEX IF NOT EXISTS idx_crate_job_events_created_at ON crate_job_events(created_at);
+
+-- Create tool_audit_log table for recorded tool invocations
+CREATE TABLE IF NOT EXISTS tool_audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    session_id TEXT,
+    client_label TEXT,
+    user_agent TEXT,
+    tool_name TEXT NOT NULL,
+    arguments JSONB NOT NULL DEFAULT '{}',
+    success BOOLEAN NOT NULL,
+    duration_ms BIGINT NOT NULL,
+    error TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_tool_audit_log_created_at ON tool_audit_log(created_at);
+CREATE INDEX IF NOT EXISTS idx_tool_audit_log_tool ON tool_audit_log(tool_name, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_tool_audit_log_session ON tool_audit_log(session_id, created_at DESC);
+
+-- Create embedding_cache table for reusing embeddings of identical content
+CREATE TABLE IF NOT EXISTS embedding_cache (
+    content_sha256 TEXT NOT NULL,
+    model TEXT NOT NULL,
+    dimensions INTEGER NOT NULL,
+    embedding REAL[] NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_used_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (content_sha256, model, dimensions)
+);
+
+CREATE INDEX IF NOT EXISTS idx_embedding_cache_last_used ON embedding_cache(last_used_at);
+
+-- Create fetch_cache table for conditional docs.rs requests
+CREATE TABLE IF NOT EXISTS fetch_cache (
+    url TEXT NOT NULL,
+    crate_version TEXT NOT NULL,
+    etag TEXT,
+    last_modified TEXT,
+    links TEXT[] NOT NULL DEFAULT '{}',
+    fetched_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (url, crate_version)
+);
+
+-- Create tool_settings table for tools switched on or off at runtime
+CREATE TABLE IF NOT EXISTS tool_settings (
+    tool_name TEXT PRIMARY KEY,
+    enabled BOOLEAN NOT NULL,
+    updated_by TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
 
 -- Create function to update updated_at if it doesn't exist
 CREATE OR REPLACE FUNCTION update_updated_at_column()